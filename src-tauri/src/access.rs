@@ -0,0 +1,550 @@
+/// Local operator accounts, role capabilities and the audit trail.
+///
+/// Until the first user is created the station runs open, as a single
+/// operator, and every action is attributed to `local`. Once an account
+/// exists, every command that changes station state checks the signed-in
+/// operator's role, and changes to encoders, rotation rules, the queue and
+/// settings also write who did what to `audit_log`.
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+use argon2::{
+    password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
+    Argon2,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::{Row, SqlitePool};
+
+use crate::{error::AppError, state::AppState};
+
+const DEFAULT_AUDIT_LIMIT: i64 = 200;
+const MAX_AUDIT_LIMIT: i64 = 5000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    Admin,
+    Dj,
+    Producer,
+    Viewer,
+}
+
+impl Role {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Role::Admin => "admin",
+            Role::Dj => "dj",
+            Role::Producer => "producer",
+            Role::Viewer => "viewer",
+        }
+    }
+
+    pub fn allows(self, capability: Capability) -> bool {
+        use Capability::*;
+        match self {
+            Role::Admin => true,
+            Role::Dj => matches!(
+                capability,
+                ControlPlayout | ControlEncoders | EditLibrary | EditQueue | ViewAuditLog
+            ),
+            Role::Producer => matches!(
+                capability,
+                EditSchedule | EditRotation | EditLibrary | EditQueue | ViewAuditLog
+            ),
+            Role::Viewer => false,
+        }
+    }
+}
+
+impl std::str::FromStr for Role {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "admin" => Ok(Role::Admin),
+            "dj" => Ok(Role::Dj),
+            "producer" => Ok(Role::Producer),
+            "viewer" => Ok(Role::Viewer),
+            _ => Err(format!("Unknown role: {s}")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Capability {
+    /// Create, edit and remove operator accounts
+    ManageUsers,
+    /// Add, change or delete encoder definitions
+    EditEncoders,
+    /// Start and stop encoders and recordings
+    ControlEncoders,
+    /// Decks, mixer, carts, mic and DJ mode
+    ControlPlayout,
+    /// Song metadata, cue points and library analysis
+    EditLibrary,
+    /// Playlists, clockwheels, shows, events and traffic
+    EditSchedule,
+    /// Rotation rules
+    EditRotation,
+    /// Add, remove and reorder queue entries
+    EditQueue,
+    ViewAuditLog,
+    /// Devices, integrations and the station settings archive
+    ManageSettings,
+}
+
+impl Capability {
+    pub fn label(self) -> &'static str {
+        match self {
+            Capability::ManageUsers => "manage users",
+            Capability::EditEncoders => "edit encoders",
+            Capability::ControlEncoders => "start or stop encoders",
+            Capability::ControlPlayout => "control playout",
+            Capability::EditLibrary => "edit the library",
+            Capability::EditSchedule => "edit the schedule",
+            Capability::EditRotation => "edit rotation rules",
+            Capability::EditQueue => "edit the queue",
+            Capability::ViewAuditLog => "view the audit log",
+            Capability::ManageSettings => "change station settings",
+        }
+    }
+}
+
+/// Who a command runs as.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Actor {
+    /// `None` while no accounts exist
+    pub user_id: Option<i64>,
+    pub username: String,
+    pub role: Role,
+}
+
+impl Actor {
+    /// The implicit operator of a station with no accounts.
+    pub fn local() -> Self {
+        Self {
+            user_id: None,
+            username: "local".to_string(),
+            role: Role::Admin,
+        }
+    }
+}
+
+/// Signed-in operator and whether accounts are in force.
+#[derive(Debug, Default)]
+pub struct AccessControl {
+    enforced: AtomicBool,
+    current: Mutex<Option<Actor>>,
+}
+
+impl AccessControl {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set_enforced(&self, enforced: bool) {
+        self.enforced.store(enforced, Ordering::SeqCst);
+    }
+
+    pub fn is_enforced(&self) -> bool {
+        self.enforced.load(Ordering::SeqCst)
+    }
+
+    pub fn current(&self) -> Option<Actor> {
+        if !self.is_enforced() {
+            return Some(Actor::local());
+        }
+        self.current.lock().unwrap().clone()
+    }
+
+    pub fn sign_in(&self, actor: Actor) {
+        *self.current.lock().unwrap() = Some(actor);
+    }
+
+    pub fn sign_out(&self) -> Option<Actor> {
+        self.current.lock().unwrap().take()
+    }
+
+    /// The current operator, if their role grants `capability`.
+    pub fn require(&self, capability: Capability) -> Result<Actor, AppError> {
+        let actor = self
+            .current()
+            .ok_or_else(|| AppError::permission_denied("Sign in required"))?;
+        if !actor.role.allows(capability) {
+            return Err(AppError::permission_denied(format!(
+                "{} ({}) is not allowed to {}",
+                actor.username,
+                actor.role.as_str(),
+                capability.label()
+            )));
+        }
+        Ok(actor)
+    }
+}
+
+// ── Users ─────────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct User {
+    pub id: i64,
+    pub username: String,
+    pub display_name: Option<String>,
+    pub role: Role,
+    pub enabled: bool,
+    pub created_at: i64,
+    pub last_login_at: Option<i64>,
+}
+
+impl User {
+    pub fn actor(&self) -> Actor {
+        Actor {
+            user_id: Some(self.id),
+            username: self.username.clone(),
+            role: self.role,
+        }
+    }
+}
+
+pub fn hash_password(password: &str) -> Result<String, String> {
+    let salt = SaltString::generate(&mut OsRng);
+    Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .map(|h| h.to_string())
+        .map_err(|e| format!("Password hashing failed: {e}"))
+}
+
+pub fn verify_password(password: &str, hash: &str) -> bool {
+    PasswordHash::new(hash)
+        .map(|parsed| {
+            Argon2::default()
+                .verify_password(password.as_bytes(), &parsed)
+                .is_ok()
+        })
+        .unwrap_or(false)
+}
+
+fn user_from_row(row: &sqlx::sqlite::SqliteRow) -> User {
+    User {
+        id: row.get("id"),
+        username: row.get("username"),
+        display_name: row.get("display_name"),
+        role: row.get::<String, _>("role").parse().unwrap_or(Role::Viewer),
+        enabled: row.get::<i64, _>("enabled") != 0,
+        created_at: row.get("created_at"),
+        last_login_at: row.get("last_login_at"),
+    }
+}
+
+pub async fn count_users(pool: &SqlitePool) -> Result<i64, sqlx::Error> {
+    sqlx::query_scalar("SELECT COUNT(*) FROM users")
+        .fetch_one(pool)
+        .await
+}
+
+pub async fn count_enabled_admins(pool: &SqlitePool) -> Result<i64, sqlx::Error> {
+    sqlx::query_scalar("SELECT COUNT(*) FROM users WHERE role = 'admin' AND enabled = 1")
+        .fetch_one(pool)
+        .await
+}
+
+pub async fn list_users(pool: &SqlitePool) -> Result<Vec<User>, sqlx::Error> {
+    let rows = sqlx::query(
+        "SELECT id, username, display_name, role, enabled, created_at, last_login_at \
+         FROM users ORDER BY username COLLATE NOCASE",
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(rows.iter().map(user_from_row).collect())
+}
+
+pub async fn get_user(pool: &SqlitePool, id: i64) -> Result<Option<User>, sqlx::Error> {
+    let row = sqlx::query(
+        "SELECT id, username, display_name, role, enabled, created_at, last_login_at \
+         FROM users WHERE id = ?",
+    )
+    .bind(id)
+    .fetch_optional(pool)
+    .await?;
+    Ok(row.as_ref().map(user_from_row))
+}
+
+/// The user and their password hash, for sign-in.
+pub async fn find_login(
+    pool: &SqlitePool,
+    username: &str,
+) -> Result<Option<(User, String)>, sqlx::Error> {
+    let row = sqlx::query(
+        "SELECT id, username, display_name, role, enabled, created_at, last_login_at, password_hash \
+         FROM users WHERE username = ? COLLATE NOCASE",
+    )
+    .bind(username)
+    .fetch_optional(pool)
+    .await?;
+    Ok(row.map(|r| (user_from_row(&r), r.get("password_hash"))))
+}
+
+pub async fn insert_user(
+    pool: &SqlitePool,
+    username: &str,
+    display_name: Option<&str>,
+    role: Role,
+    password_hash: &str,
+) -> Result<i64, sqlx::Error> {
+    let id = sqlx::query(
+        "INSERT INTO users (username, display_name, role, password_hash, enabled, created_at) \
+         VALUES (?, ?, ?, ?, 1, ?)",
+    )
+    .bind(username)
+    .bind(display_name)
+    .bind(role.as_str())
+    .bind(password_hash)
+    .bind(chrono::Utc::now().timestamp_millis())
+    .execute(pool)
+    .await?
+    .last_insert_rowid();
+    Ok(id)
+}
+
+pub async fn update_user(pool: &SqlitePool, user: &User) -> Result<(), sqlx::Error> {
+    sqlx::query("UPDATE users SET display_name = ?, role = ?, enabled = ? WHERE id = ?")
+        .bind(&user.display_name)
+        .bind(user.role.as_str())
+        .bind(user.enabled as i64)
+        .bind(user.id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+pub async fn set_password_hash(
+    pool: &SqlitePool,
+    id: i64,
+    password_hash: &str,
+) -> Result<(), sqlx::Error> {
+    sqlx::query("UPDATE users SET password_hash = ? WHERE id = ?")
+        .bind(password_hash)
+        .bind(id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+pub async fn touch_last_login(pool: &SqlitePool, id: i64) -> Result<(), sqlx::Error> {
+    sqlx::query("UPDATE users SET last_login_at = ? WHERE id = ?")
+        .bind(chrono::Utc::now().timestamp_millis())
+        .bind(id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+pub async fn delete_user(pool: &SqlitePool, id: i64) -> Result<(), sqlx::Error> {
+    sqlx::query("DELETE FROM users WHERE id = ?")
+        .bind(id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+// ── Audit trail ───────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub id: i64,
+    /// Unix ms
+    pub timestamp: i64,
+    pub user_id: Option<i64>,
+    pub username: String,
+    pub role: String,
+    /// Dotted action name, e.g. `encoder.save`, `queue.reorder`
+    pub action: String,
+    /// What was changed, e.g. `encoder:3`
+    pub target: Option<String>,
+    pub details: serde_json::Value,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AuditFilter {
+    pub username: Option<String>,
+    /// Matches the action exactly or as a dotted prefix (`encoder` → `encoder.*`)
+    pub action: Option<String>,
+    pub since_ms: Option<i64>,
+    pub until_ms: Option<i64>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+pub async fn insert_audit(
+    pool: &SqlitePool,
+    actor: &Actor,
+    action: &str,
+    target: Option<&str>,
+    details: &serde_json::Value,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        INSERT INTO audit_log (timestamp, user_id, username, role, action, target, details_json)
+        VALUES (?, ?, ?, ?, ?, ?, ?)
+        "#,
+    )
+    .bind(chrono::Utc::now().timestamp_millis())
+    .bind(actor.user_id)
+    .bind(&actor.username)
+    .bind(actor.role.as_str())
+    .bind(action)
+    .bind(target)
+    .bind(details.to_string())
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Top-level fields that differ between two serialized configs, as
+/// `{ field: { "from": .., "to": .. } }`. Fields whose name contains
+/// `password` are reported as changed without their values.
+pub fn changed_fields(before: &serde_json::Value, after: &serde_json::Value) -> serde_json::Value {
+    let empty = serde_json::Map::new();
+    let before = before.as_object().unwrap_or(&empty);
+    let after = after.as_object().unwrap_or(&empty);
+    let mut out = serde_json::Map::new();
+    for (key, new) in after {
+        let old = before.get(key).unwrap_or(&serde_json::Value::Null);
+        if old == new {
+            continue;
+        }
+        let change = if key.contains("password") {
+            serde_json::json!({ "changed": true })
+        } else {
+            serde_json::json!({ "from": old, "to": new })
+        };
+        out.insert(key.clone(), change);
+    }
+    serde_json::Value::Object(out)
+}
+
+/// Record an action. Failures are logged; the action itself has already
+/// happened and is not rolled back.
+pub async fn audit(
+    state: &AppState,
+    actor: &Actor,
+    action: &str,
+    target: Option<String>,
+    details: serde_json::Value,
+) {
+    let Some(pool) = state.local_db.as_ref() else {
+        return;
+    };
+    if let Err(e) = insert_audit(pool, actor, action, target.as_deref(), &details).await {
+        log::warn!("Audit entry '{action}' not written: {e}");
+    }
+}
+
+pub async fn get_audit_log(
+    pool: &SqlitePool,
+    filter: &AuditFilter,
+) -> Result<Vec<AuditEntry>, sqlx::Error> {
+    let mut qb = sqlx::QueryBuilder::<sqlx::Sqlite>::new(
+        "SELECT id, timestamp, user_id, username, role, action, target, details_json \
+         FROM audit_log WHERE 1 = 1",
+    );
+    if let Some(username) = filter.username.as_deref().filter(|s| !s.is_empty()) {
+        qb.push(" AND username = ")
+            .push_bind(username.to_string())
+            .push(" COLLATE NOCASE");
+    }
+    if let Some(action) = filter.action.as_deref().filter(|s| !s.is_empty()) {
+        qb.push(" AND (action = ")
+            .push_bind(action.to_string())
+            .push(" OR action LIKE ")
+            .push_bind(format!("{action}.%"))
+            .push(")");
+    }
+    if let Some(since) = filter.since_ms {
+        qb.push(" AND timestamp >= ").push_bind(since);
+    }
+    if let Some(until) = filter.until_ms {
+        qb.push(" AND timestamp <= ").push_bind(until);
+    }
+    let limit = filter
+        .limit
+        .unwrap_or(DEFAULT_AUDIT_LIMIT)
+        .clamp(1, MAX_AUDIT_LIMIT);
+    qb.push(" ORDER BY timestamp DESC, id DESC LIMIT ")
+        .push_bind(limit)
+        .push(" OFFSET ")
+        .push_bind(filter.offset.unwrap_or(0).max(0));
+
+    let rows = qb.build().fetch_all(pool).await?;
+    Ok(rows
+        .iter()
+        .map(|r| AuditEntry {
+            id: r.get("id"),
+            timestamp: r.get("timestamp"),
+            user_id: r.get("user_id"),
+            username: r.get("username"),
+            role: r.get("role"),
+            action: r.get("action"),
+            target: r.get("target"),
+            details: serde_json::from_str(&r.get::<String, _>("details_json"))
+                .unwrap_or(serde_json::Value::Null),
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn roles_grant_expected_capabilities() {
+        assert!(Role::Admin.allows(Capability::ManageUsers));
+        assert!(Role::Dj.allows(Capability::ControlEncoders));
+        assert!(!Role::Dj.allows(Capability::EditEncoders));
+        assert!(Role::Producer.allows(Capability::EditRotation));
+        assert!(!Role::Producer.allows(Capability::ControlEncoders));
+        assert!(Role::Dj.allows(Capability::ControlPlayout));
+        assert!(!Role::Dj.allows(Capability::EditSchedule));
+        assert!(Role::Producer.allows(Capability::EditSchedule));
+        assert!(!Role::Producer.allows(Capability::ControlPlayout));
+        assert!(!Role::Viewer.allows(Capability::ControlPlayout));
+        assert!(!Role::Viewer.allows(Capability::EditQueue));
+    }
+
+    #[test]
+    fn changed_fields_reports_diffs_and_redacts_passwords() {
+        let before =
+            serde_json::json!({ "name": "Main", "bitrate_kbps": 128, "server_password": "a" });
+        let after =
+            serde_json::json!({ "name": "Main", "bitrate_kbps": 192, "server_password": "b" });
+        assert_eq!(
+            changed_fields(&before, &after),
+            serde_json::json!({
+                "bitrate_kbps": { "from": 128, "to": 192 },
+                "server_password": { "changed": true },
+            })
+        );
+    }
+
+    #[test]
+    fn open_station_acts_as_local_until_accounts_exist() {
+        let access = AccessControl::new();
+        assert_eq!(
+            access.require(Capability::ManageUsers).unwrap(),
+            Actor::local()
+        );
+
+        access.set_enforced(true);
+        assert!(access.require(Capability::EditQueue).is_err());
+
+        access.sign_in(Actor {
+            user_id: Some(2),
+            username: "sam".to_string(),
+            role: Role::Dj,
+        });
+        assert!(access.require(Capability::EditQueue).is_ok());
+        let err = access.require(Capability::EditEncoders).unwrap_err();
+        assert_eq!(err.code, crate::error::ErrorCode::PermissionDenied);
+    }
+}
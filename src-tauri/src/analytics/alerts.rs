@@ -0,0 +1,352 @@
+/// Health alert configuration and delivery
+///
+/// The health watchdog raises a `HealthAlert` when a condition (encoder down,
+/// sustained underrun, SAM DB lost, dead air) has held past its threshold, and
+/// again when it clears. Each alert goes to every enabled target whose kind
+/// filter matches: an SMTP mailbox, a Discord/Slack/plain JSON webhook, or a
+/// user script run with the alert as its `event` table.
+use lettre::{
+    message::Mailbox, transport::smtp::authentication::Credentials, AsyncSmtpTransport,
+    AsyncTransport, Message, Tokio1Executor,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use std::time::Duration;
+
+use super::health_monitor::{AlertKind, HealthAlert};
+use crate::scripting::{engine::ScriptEngine, trigger::ScriptEvent};
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+
+// ── Config ────────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AlertConfig {
+    /// Master switch for the watchdog
+    pub enabled: bool,
+    /// An encoder retrying or failed this long is reported
+    pub encoder_down_secs: u32,
+    /// A playing deck starved of decoded audio this long is reported
+    pub underrun_secs: u32,
+    /// A configured SAM connection unreachable this long is reported
+    pub sam_down_secs: u32,
+    /// Master output below `dead_air_threshold_db` this long while on air
+    pub dead_air_secs: u32,
+    pub dead_air_threshold_db: f32,
+    /// The same condition does not alert again within this window
+    pub repeat_after_mins: u32,
+    /// Also notify when a condition clears
+    pub notify_recovery: bool,
+    pub targets: Vec<AlertTarget>,
+}
+
+impl Default for AlertConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            encoder_down_secs: 15,
+            underrun_secs: 10,
+            sam_down_secs: 30,
+            dead_air_secs: 20,
+            dead_air_threshold_db: -50.0,
+            repeat_after_mins: 15,
+            notify_recovery: true,
+            targets: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AlertTarget {
+    #[serde(default)]
+    pub name: String,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// Alert kinds delivered to this target; empty = all
+    #[serde(default)]
+    pub kinds: Vec<AlertKind>,
+    #[serde(flatten)]
+    pub action: AlertAction,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SmtpSecurity {
+    /// Implicit TLS (usually port 465)
+    Tls,
+    /// STARTTLS upgrade (usually port 587)
+    #[default]
+    StartTls,
+    /// Plain text; local relays only
+    None,
+}
+
+/// Outgoing mail server, as used by email targets and the report digest.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SmtpSettings {
+    pub smtp_host: String,
+    pub smtp_port: u16,
+    pub security: SmtpSecurity,
+    pub username: String,
+    pub password: String,
+    pub from: String,
+}
+
+impl Default for SmtpSettings {
+    fn default() -> Self {
+        Self {
+            smtp_host: String::new(),
+            smtp_port: 587,
+            security: SmtpSecurity::StartTls,
+            username: String::new(),
+            password: String::new(),
+            from: String::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookFormat {
+    Discord,
+    Slack,
+    /// The alert itself as the JSON body
+    #[default]
+    Json,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum AlertAction {
+    Email {
+        smtp_host: String,
+        smtp_port: u16,
+        #[serde(default)]
+        security: SmtpSecurity,
+        #[serde(default)]
+        username: String,
+        #[serde(default)]
+        password: String,
+        from: String,
+        to: Vec<String>,
+    },
+    Webhook {
+        url: String,
+        #[serde(default)]
+        format: WebhookFormat,
+    },
+    Script {
+        script_id: i64,
+    },
+}
+
+impl AlertTarget {
+    pub fn accepts(&self, kind: AlertKind) -> bool {
+        self.enabled && (self.kinds.is_empty() || self.kinds.contains(&kind))
+    }
+}
+
+// ── Delivery ──────────────────────────────────────────────────────────────────
+
+/// Send `alert` to every matching target. Failures are logged per target and
+/// do not stop delivery to the rest.
+pub async fn dispatch(config: &AlertConfig, alert: &HealthAlert, scripts: &ScriptEngine) {
+    for target in config.targets.iter().filter(|t| t.accepts(alert.kind)) {
+        if let Err(e) = send(target, alert, scripts).await {
+            log::warn!("Alert target '{}' failed: {e}", target.name);
+        }
+    }
+}
+
+pub async fn send(
+    target: &AlertTarget,
+    alert: &HealthAlert,
+    scripts: &ScriptEngine,
+) -> Result<(), String> {
+    match &target.action {
+        AlertAction::Email {
+            smtp_host,
+            smtp_port,
+            security,
+            username,
+            password,
+            from,
+            to,
+        } => {
+            let smtp = SmtpSettings {
+                smtp_host: smtp_host.clone(),
+                smtp_port: *smtp_port,
+                security: *security,
+                username: username.clone(),
+                password: password.clone(),
+                from: from.clone(),
+            };
+            send_email(&smtp, to, subject(alert), body(alert)).await
+        }
+        AlertAction::Webhook { url, format } => {
+            let payload = match format {
+                WebhookFormat::Discord => serde_json::json!({ "content": body(alert) }),
+                WebhookFormat::Slack => serde_json::json!({ "text": body(alert) }),
+                WebhookFormat::Json => serde_json::to_value(alert).map_err(|e| e.to_string())?,
+            };
+            let response = reqwest::Client::builder()
+                .timeout(REQUEST_TIMEOUT)
+                .build()
+                .map_err(|e| e.to_string())?
+                .post(url)
+                .json(&payload)
+                .send()
+                .await
+                .map_err(|e| e.to_string())?;
+            if !response.status().is_success() {
+                return Err(format!("Webhook returned HTTP {}", response.status()));
+            }
+            Ok(())
+        }
+        AlertAction::Script { script_id } => {
+            let result = scripts
+                .run_script_for_event(
+                    *script_id,
+                    ScriptEvent::HealthAlert {
+                        kind: alert.kind.as_str().to_string(),
+                        message: alert.message.clone(),
+                        resolved: alert.resolved,
+                        encoder_id: alert.encoder_id,
+                        deck: alert.deck.clone(),
+                    },
+                )
+                .await;
+            match result.error {
+                Some(e) => Err(e),
+                None => Ok(()),
+            }
+        }
+    }
+}
+
+/// Send a plain-text message through `smtp` to every non-blank address in `to`.
+pub async fn send_email(
+    smtp: &SmtpSettings,
+    to: &[String],
+    subject: String,
+    body: String,
+) -> Result<(), String> {
+    let builder = match smtp.security {
+        SmtpSecurity::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(&smtp.smtp_host)
+            .map_err(|e| e.to_string())?,
+        SmtpSecurity::StartTls => {
+            AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&smtp.smtp_host)
+                .map_err(|e| e.to_string())?
+        }
+        SmtpSecurity::None => {
+            AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&smtp.smtp_host)
+        }
+    };
+    let mut builder = builder.port(smtp.smtp_port).timeout(Some(REQUEST_TIMEOUT));
+    if !smtp.username.is_empty() {
+        builder = builder.credentials(Credentials::new(
+            smtp.username.clone(),
+            smtp.password.clone(),
+        ));
+    }
+
+    let from: Mailbox = smtp
+        .from
+        .parse()
+        .map_err(|e| format!("Invalid sender: {e}"))?;
+    let mut message = Message::builder().from(from).subject(subject);
+    for addr in to.iter().map(|a| a.trim()).filter(|a| !a.is_empty()) {
+        let mailbox: Mailbox = addr
+            .parse()
+            .map_err(|e| format!("Invalid recipient {addr}: {e}"))?;
+        message = message.to(mailbox);
+    }
+    let message = message.body(body).map_err(|e| e.to_string())?;
+    builder
+        .build()
+        .send(message)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+fn subject(alert: &HealthAlert) -> String {
+    let state = if alert.resolved { "RESOLVED" } else { "ALERT" };
+    format!("[DesiZone {state}] {}", alert.kind.label())
+}
+
+fn body(alert: &HealthAlert) -> String {
+    let at = chrono::DateTime::from_timestamp_millis(alert.timestamp)
+        .map(|t| t.format("%Y-%m-%d %H:%M:%S UTC").to_string())
+        .unwrap_or_default();
+    format!("{}: {} ({at})", subject(alert), alert.message)
+}
+
+// ── DB helpers ────────────────────────────────────────────────────────────────
+
+pub async fn get_config(pool: &SqlitePool) -> Result<AlertConfig, sqlx::Error> {
+    let row: Option<String> =
+        sqlx::query_scalar("SELECT config_json FROM alert_config WHERE id = 1")
+            .fetch_optional(pool)
+            .await?;
+    Ok(row
+        .and_then(|j| serde_json::from_str(&j).ok())
+        .unwrap_or_default())
+}
+
+pub async fn save_config(pool: &SqlitePool, config: &AlertConfig) -> Result<(), sqlx::Error> {
+    let json = serde_json::to_string(config).unwrap_or_else(|_| "{}".to_string());
+    sqlx::query(
+        "INSERT INTO alert_config (id, config_json, updated_at) VALUES (1, ?, strftime('%s','now')) \
+         ON CONFLICT(id) DO UPDATE SET config_json = excluded.config_json, updated_at = excluded.updated_at",
+    )
+    .bind(json)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn targets_round_trip_and_filter_by_kind() {
+        let json = r#"{
+            "targets": [
+                { "name": "ops", "type": "webhook", "url": "https://discord.test/hook", "format": "discord", "kinds": ["dead_air"] },
+                { "name": "mail", "type": "email", "smtp_host": "smtp.test", "smtp_port": 587, "from": "radio@test", "to": ["ops@test"] }
+            ]
+        }"#;
+        let config: AlertConfig = serde_json::from_str(json).expect("valid config");
+        assert!(config.enabled);
+        assert_eq!(config.dead_air_secs, 20);
+
+        let hook = &config.targets[0];
+        assert!(matches!(
+            hook.action,
+            AlertAction::Webhook {
+                format: WebhookFormat::Discord,
+                ..
+            }
+        ));
+        assert!(hook.accepts(AlertKind::DeadAir));
+        assert!(!hook.accepts(AlertKind::SamDbLost));
+
+        let mail = &config.targets[1];
+        assert!(mail.enabled && mail.accepts(AlertKind::EncoderDisconnect));
+        assert!(matches!(
+            mail.action,
+            AlertAction::Email {
+                security: SmtpSecurity::StartTls,
+                ..
+            }
+        ));
+    }
+}
@@ -0,0 +1,354 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+/// Time the UI loop may spend emitting per tick; leftovers wait (coalesced).
+const TICK_EMIT_BUDGET: Duration = Duration::from_millis(10);
+/// Average per-emit cost above which the webview is treated as backed up.
+const SLOW_EMIT_US: f64 = 2_000.0;
+/// Highest sampling stride for high-rate streams (8 × 80 ms ≈ 640 ms).
+const MAX_STRIDE: u32 = 8;
+/// Event carrying every high-rate stream sample of a tick.
+pub const BATCH_EVENT: &str = "engine_tick";
+const RATES_FILE: &str = "emit_rates.json";
+
+/// Emitter loop diagnostics.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EmitterMetrics {
+    pub ticks: u64,
+    pub emitted: u64,
+    /// Payloads replaced by a newer one before they went out
+    pub coalesced: u64,
+    /// Ticks on which high-rate streams (VU, deck position) were skipped
+    pub throttled_ticks: u64,
+    /// Ticks that started more than one interval late
+    pub late_ticks: u64,
+    /// Payloads still queued after the last flush
+    pub pending: usize,
+    /// Current high-rate sampling stride (1 = every tick)
+    pub stride: u32,
+    /// Stream samples dropped because they matched what was last sent
+    pub unchanged: u64,
+    /// Whether the main window had focus on the last tick
+    pub focused: bool,
+    pub last_tick_ms: f64,
+    pub max_tick_ms: f64,
+    pub avg_emit_us: f64,
+    pub max_emit_us: u64,
+}
+
+static METRICS: OnceLock<Mutex<EmitterMetrics>> = OnceLock::new();
+
+fn metrics_cell() -> &'static Mutex<EmitterMetrics> {
+    METRICS.get_or_init(|| Mutex::new(EmitterMetrics::default()))
+}
+
+pub fn get_emitter_metrics() -> EmitterMetrics {
+    metrics_cell().lock().unwrap().clone()
+}
+
+// ── Rates ─────────────────────────────────────────────────────────────────────
+
+/// How often high-rate streams (deck position, VU, crossfade) reach the UI.
+/// Rates are rounded to whole polling ticks.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EmitRateConfig {
+    pub focused_interval_ms: u64,
+    /// While the main window is in the background
+    pub unfocused_interval_ms: u64,
+    /// Unchanged values are re-sent this often so new views catch up
+    pub resync_ms: u64,
+}
+
+impl Default for EmitRateConfig {
+    fn default() -> Self {
+        Self {
+            focused_interval_ms: 80,
+            unfocused_interval_ms: 400,
+            resync_ms: 2_000,
+        }
+    }
+}
+
+impl EmitRateConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(20..=2_000).contains(&self.focused_interval_ms) {
+            return Err("Focused interval must be 20-2000 ms".to_string());
+        }
+        if !(self.focused_interval_ms..=10_000).contains(&self.unfocused_interval_ms) {
+            return Err(
+                "Background interval must be between the focused interval and 10000 ms".to_string(),
+            );
+        }
+        if !(500..=60_000).contains(&self.resync_ms) {
+            return Err("Resync interval must be 500-60000 ms".to_string());
+        }
+        Ok(())
+    }
+}
+
+static RATES: OnceLock<Mutex<EmitRateConfig>> = OnceLock::new();
+static WINDOW_FOCUSED: AtomicBool = AtomicBool::new(true);
+
+fn rates_path() -> PathBuf {
+    PathBuf::from(crate::compute_app_data_dir()).join(RATES_FILE)
+}
+
+fn rates_cell() -> &'static Mutex<EmitRateConfig> {
+    RATES.get_or_init(|| {
+        let config = std::fs::read(rates_path())
+            .ok()
+            .and_then(|bytes| serde_json::from_slice::<EmitRateConfig>(&bytes).ok())
+            .filter(|c| c.validate().is_ok())
+            .unwrap_or_default();
+        Mutex::new(config)
+    })
+}
+
+pub fn get_emit_rates() -> EmitRateConfig {
+    rates_cell().lock().unwrap().clone()
+}
+
+/// Validate, save and apply from the next tick.
+pub fn set_emit_rates(config: EmitRateConfig) -> Result<(), String> {
+    config.validate()?;
+    let json = serde_json::to_vec_pretty(&config).map_err(|e| e.to_string())?;
+    std::fs::write(rates_path(), json).map_err(|e| format!("Cannot save emit rates: {e}"))?;
+    *rates_cell().lock().unwrap() = config;
+    Ok(())
+}
+
+/// Track main window focus; background windows get the slower rate.
+pub fn set_window_focused(focused: bool) {
+    WINDOW_FOCUSED.store(focused, Ordering::Relaxed);
+}
+
+/// Coalescing emit queue for the UI polling loop.
+///
+/// Each payload is keyed (event name + source); a newer payload replaces an
+/// unsent one, so the queue never holds more than one value per key. State
+/// events flush first, one emit each. High-rate stream samples that differ
+/// from what was last sent go out together as a single `engine_tick` event
+/// (`{ event name: [payloads] }`); they are sampled at the configured rate
+/// for the window's focus, and less often still while emits are slow.
+pub struct EmitQueue {
+    state: BTreeMap<String, (&'static str, serde_json::Value)>,
+    stream: BTreeMap<String, (&'static str, serde_json::Value)>,
+    /// Last stream value sent per key
+    sent: HashMap<String, serde_json::Value>,
+    last_resync: Option<Instant>,
+    resync: bool,
+    /// Ticks per stream sample for the current focus and rate
+    rate_stride: u32,
+    tick: u64,
+    metrics: EmitterMetrics,
+}
+
+impl Default for EmitQueue {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EmitQueue {
+    pub fn new() -> Self {
+        Self {
+            state: BTreeMap::new(),
+            stream: BTreeMap::new(),
+            sent: HashMap::new(),
+            last_resync: None,
+            resync: true,
+            rate_stride: 1,
+            tick: 0,
+            metrics: EmitterMetrics {
+                stride: 1,
+                focused: true,
+                ..Default::default()
+            },
+        }
+    }
+
+    /// Start a tick; `late_by` is how far past its deadline the tick started.
+    pub fn begin_tick(&mut self, late_by: Duration, interval: Duration) {
+        self.tick += 1;
+        self.metrics.ticks += 1;
+        if late_by > interval {
+            self.metrics.late_ticks += 1;
+        }
+        let rates = get_emit_rates();
+        self.metrics.focused = WINDOW_FOCUSED.load(Ordering::Relaxed);
+        let rate_ms = if self.metrics.focused {
+            rates.focused_interval_ms
+        } else {
+            rates.unfocused_interval_ms
+        };
+        let tick_ms = (interval.as_millis() as u64).max(1);
+        self.rate_stride = ((rate_ms + tick_ms / 2) / tick_ms).max(1) as u32;
+        self.resync = self
+            .last_resync
+            .is_none_or(|t| t.elapsed() >= Duration::from_millis(rates.resync_ms));
+        if self.resync {
+            self.last_resync = Some(Instant::now());
+        }
+    }
+
+    /// Whether high-rate streams should be sampled on this tick.
+    pub fn stream_due(&mut self) -> bool {
+        let stride = self.metrics.stride.max(1) * self.rate_stride;
+        let due = self.resync || self.tick.is_multiple_of(stride as u64);
+        if !due {
+            self.metrics.throttled_ticks += 1;
+        }
+        due
+    }
+
+    /// Queue a change-driven event (never throttled, only coalesced).
+    pub fn offer_state<T: Serialize>(&mut self, event: &'static str, key: &str, payload: &T) {
+        Self::offer_into(&mut self.state, &mut self.metrics, event, key, payload);
+    }
+
+    /// Queue a high-rate stream sample, unless it matches what was last sent
+    /// (resync ticks send everything).
+    pub fn offer_stream<T: Serialize>(&mut self, event: &'static str, key: &str, payload: &T) {
+        if !self.resync {
+            let sent = self.sent.get(&format!("{event}:{key}"));
+            let unchanged = serde_json::to_value(payload).is_ok_and(|v| sent == Some(&v));
+            if unchanged {
+                // A stale unsent value must not go out after it.
+                self.stream.remove(&format!("{event}:{key}"));
+                self.metrics.unchanged += 1;
+                return;
+            }
+        }
+        Self::offer_into(&mut self.stream, &mut self.metrics, event, key, payload);
+    }
+
+    fn offer_into<T: Serialize>(
+        queue: &mut BTreeMap<String, (&'static str, serde_json::Value)>,
+        metrics: &mut EmitterMetrics,
+        event: &'static str,
+        key: &str,
+        payload: &T,
+    ) {
+        let Ok(value) = serde_json::to_value(payload) else {
+            return;
+        };
+        if queue
+            .insert(format!("{event}:{key}"), (event, value))
+            .is_some()
+        {
+            metrics.coalesced += 1;
+        }
+    }
+
+    /// Emit queued state events until the tick budget is spent, then the
+    /// stream batch if there is time left; then adapt the stream stride and
+    /// publish metrics.
+    pub fn flush(&mut self, tick_started: Instant, mut emit: impl FnMut(&str, &serde_json::Value)) {
+        let mut over_budget = false;
+        while !over_budget {
+            let Some((_, (event, value))) = self.state.pop_first() else {
+                break;
+            };
+            let started = Instant::now();
+            emit(event, &value);
+            self.record_emit(started);
+            over_budget = tick_started.elapsed() >= TICK_EMIT_BUDGET;
+        }
+        if !over_budget && !self.stream.is_empty() {
+            let mut batch = serde_json::Map::new();
+            for (key, (event, value)) in std::mem::take(&mut self.stream) {
+                if let serde_json::Value::Array(samples) = batch
+                    .entry(event)
+                    .or_insert_with(|| serde_json::Value::Array(Vec::new()))
+                {
+                    samples.push(value.clone());
+                }
+                self.sent.insert(key, value);
+            }
+            let started = Instant::now();
+            emit(BATCH_EVENT, &serde_json::Value::Object(batch));
+            self.record_emit(started);
+            over_budget = tick_started.elapsed() >= TICK_EMIT_BUDGET;
+        }
+
+        let backlog = !self.state.is_empty() || !self.stream.is_empty();
+        if over_budget || backlog || self.metrics.avg_emit_us > SLOW_EMIT_US {
+            self.metrics.stride = (self.metrics.stride * 2).min(MAX_STRIDE);
+        } else if self.metrics.avg_emit_us < SLOW_EMIT_US / 4.0 {
+            self.metrics.stride = (self.metrics.stride / 2).max(1);
+        }
+
+        let tick_ms = tick_started.elapsed().as_secs_f64() * 1000.0;
+        self.metrics.pending = self.state.len() + self.stream.len();
+        self.metrics.last_tick_ms = tick_ms;
+        self.metrics.max_tick_ms = self.metrics.max_tick_ms.max(tick_ms);
+        *metrics_cell().lock().unwrap() = self.metrics.clone();
+    }
+
+    fn record_emit(&mut self, started: Instant) {
+        let us = started.elapsed().as_micros() as u64;
+        self.metrics.emitted += 1;
+        self.metrics.max_emit_us = self.metrics.max_emit_us.max(us);
+        self.metrics.avg_emit_us = if self.metrics.emitted == 1 {
+            us as f64
+        } else {
+            self.metrics.avg_emit_us * 0.9 + us as f64 * 0.1
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn coalesces_and_batches_only_changed_samples() {
+        let mut queue = EmitQueue::new();
+        queue.begin_tick(Duration::ZERO, Duration::from_millis(80));
+        queue.offer_stream("vu_meter", "deck_a", &1);
+        queue.offer_stream("vu_meter", "deck_a", &2);
+        queue.offer_state("master_volume_changed", "", &0.5);
+
+        let mut sent = Vec::new();
+        queue.flush(Instant::now(), |event, value| {
+            sent.push((event.to_string(), value.clone()))
+        });
+
+        assert_eq!(
+            sent,
+            vec![
+                ("master_volume_changed".to_string(), serde_json::json!(0.5)),
+                (
+                    BATCH_EVENT.to_string(),
+                    serde_json::json!({ "vu_meter": [2] })
+                ),
+            ]
+        );
+        let metrics = get_emitter_metrics();
+        assert_eq!(metrics.coalesced, 1);
+        assert_eq!(metrics.pending, 0);
+
+        // Between resyncs only changed samples go out, batched.
+        sent.clear();
+        queue.begin_tick(Duration::ZERO, Duration::from_millis(80));
+        queue.offer_stream("vu_meter", "deck_a", &2);
+        queue.offer_stream("deck_state_changed", "deck_a", &"playing");
+        queue.offer_stream("deck_state_changed", "deck_b", &"idle");
+        queue.flush(Instant::now(), |event, value| {
+            sent.push((event.to_string(), value.clone()))
+        });
+        assert_eq!(
+            sent,
+            vec![(
+                BATCH_EVENT.to_string(),
+                serde_json::json!({ "deck_state_changed": ["playing", "idle"] })
+            )]
+        );
+        assert_eq!(get_emitter_metrics().unchanged, 1);
+    }
+}
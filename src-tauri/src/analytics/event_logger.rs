@@ -1,6 +1,11 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
 use serde::{Deserialize, Serialize};
 use sqlx::{QueryBuilder, Sqlite, SqlitePool};
 
+/// Set once `event_log_fts` exists; until then search falls back to `LIKE`.
+static SEARCH_INDEX_READY: AtomicBool = AtomicBool::new(false);
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum LogLevel {
@@ -61,6 +66,67 @@ pub struct EventLogEntry {
     pub encoder_id: Option<i64>,
 }
 
+/// `event_log` columns in `EventLogEntry` order.
+pub(crate) const EVENT_LOG_COLUMNS: &str =
+    "id, timestamp, level, category, event, message, metadata_json, deck, song_id, encoder_id";
+
+pub(crate) type EventLogRow = (
+    i64,
+    i64,
+    String,
+    String,
+    String,
+    String,
+    Option<String>,
+    Option<String>,
+    Option<i64>,
+    Option<i64>,
+);
+
+impl From<EventLogRow> for EventLogEntry {
+    fn from(row: EventLogRow) -> Self {
+        let (
+            id,
+            timestamp,
+            level,
+            category,
+            event,
+            message,
+            metadata_json,
+            deck,
+            song_id,
+            encoder_id,
+        ) = row;
+        Self {
+            id,
+            timestamp,
+            level,
+            category,
+            event,
+            message,
+            metadata_json,
+            deck,
+            song_id,
+            encoder_id,
+        }
+    }
+}
+
+/// Filters for `get_event_log`; empty strings are ignored.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EventLogFilter {
+    pub level: Option<String>,
+    pub category: Option<String>,
+    pub start_time: Option<i64>,
+    pub end_time: Option<i64>,
+    /// Words matched against event, message and metadata (prefix match)
+    pub search: Option<String>,
+    pub deck: Option<String>,
+    /// Keyset cursor: only entries older than this id. Cheaper than `offset`
+    /// deep into a large log.
+    pub before_id: Option<i64>,
+}
+
 /// Log an event to the SQLite event_log table
 pub async fn log_event(
     pool: &SqlitePool,
@@ -110,95 +176,40 @@ pub async fn log_event(
     Ok(())
 }
 
-/// Get event log entries with filtering and pagination
+/// Get event log entries with filtering and pagination, newest first.
 pub async fn get_event_log(
     pool: &SqlitePool,
     limit: i64,
     offset: i64,
-    level: Option<&str>,
-    category: Option<&str>,
-    start_time: Option<i64>,
-    end_time: Option<i64>,
-    search: Option<&str>,
-    deck: Option<&str>,
+    filter: &EventLogFilter,
 ) -> Result<(Vec<EventLogEntry>, i64), sqlx::Error> {
-    let mut query_builder = QueryBuilder::<Sqlite>::new(
-        "SELECT id, timestamp, level, category, event, message, metadata_json, deck, song_id, encoder_id FROM event_log WHERE 1=1",
-    );
-
-    append_filters(
-        &mut query_builder,
-        level,
-        category,
-        start_time,
-        end_time,
-        search,
-        deck,
-    );
-
-    query_builder.push(" ORDER BY timestamp DESC LIMIT ");
+    let mut query_builder = QueryBuilder::<Sqlite>::new(format!(
+        "SELECT {EVENT_LOG_COLUMNS} FROM event_log WHERE 1=1"
+    ));
+
+    append_filters(&mut query_builder, filter);
+    if let Some(before_id) = filter.before_id {
+        query_builder.push(" AND id < ");
+        query_builder.push_bind(before_id);
+    }
+
+    // Ids follow insertion order, which is timestamp order.
+    query_builder.push(" ORDER BY id DESC LIMIT ");
     query_builder.push_bind(limit.max(1));
     query_builder.push(" OFFSET ");
     query_builder.push_bind(offset.max(0));
 
-    let rows = query_builder
-        .build_query_as::<(
-            i64,
-            i64,
-            String,
-            String,
-            String,
-            String,
-            Option<String>,
-            Option<String>,
-            Option<i64>,
-            Option<i64>,
-        )>()
+    let entries: Vec<EventLogEntry> = query_builder
+        .build_query_as::<EventLogRow>()
         .fetch_all(pool)
-        .await?;
-
-    let entries: Vec<EventLogEntry> = rows
+        .await?
         .into_iter()
-        .map(
-            |(
-                id,
-                timestamp,
-                level,
-                category,
-                event,
-                message,
-                metadata_json,
-                deck,
-                song_id,
-                encoder_id,
-            )| {
-                EventLogEntry {
-                    id,
-                    timestamp,
-                    level,
-                    category,
-                    event,
-                    message,
-                    metadata_json,
-                    deck,
-                    song_id,
-                    encoder_id,
-                }
-            },
-        )
+        .map(EventLogEntry::from)
         .collect();
 
     let mut count_query_builder =
         QueryBuilder::<Sqlite>::new("SELECT COUNT(*) FROM event_log WHERE 1=1");
-    append_filters(
-        &mut count_query_builder,
-        level,
-        category,
-        start_time,
-        end_time,
-        search,
-        deck,
-    );
+    append_filters(&mut count_query_builder, filter);
     let total: i64 = count_query_builder
         .build_query_scalar()
         .fetch_one(pool)
@@ -207,52 +218,103 @@ pub async fn get_event_log(
     Ok((entries, total))
 }
 
-fn append_filters(
-    query_builder: &mut QueryBuilder<'_, Sqlite>,
-    level: Option<&str>,
-    category: Option<&str>,
-    start_time: Option<i64>,
-    end_time: Option<i64>,
-    search: Option<&str>,
-    deck: Option<&str>,
-) {
-    if let Some(level) = level.filter(|value| !value.trim().is_empty()) {
+fn append_filters(query_builder: &mut QueryBuilder<'_, Sqlite>, filter: &EventLogFilter) {
+    let text = |value: &Option<String>| {
+        value
+            .as_deref()
+            .map(str::trim)
+            .filter(|v| !v.is_empty())
+            .map(str::to_string)
+    };
+
+    if let Some(level) = text(&filter.level) {
         query_builder.push(" AND level = ");
-        query_builder.push_bind(level.trim().to_string());
+        query_builder.push_bind(level);
     }
 
-    if let Some(category) = category.filter(|value| !value.trim().is_empty()) {
+    if let Some(category) = text(&filter.category) {
         query_builder.push(" AND category = ");
-        query_builder.push_bind(category.trim().to_string());
+        query_builder.push_bind(category);
     }
 
-    if let Some(start_time) = start_time {
+    if let Some(start_time) = filter.start_time {
         query_builder.push(" AND timestamp >= ");
         query_builder.push_bind(start_time);
     }
 
-    if let Some(end_time) = end_time {
+    if let Some(end_time) = filter.end_time {
         query_builder.push(" AND timestamp <= ");
         query_builder.push_bind(end_time);
     }
 
-    if let Some(deck) = deck.filter(|value| !value.trim().is_empty()) {
+    if let Some(deck) = text(&filter.deck) {
         query_builder.push(" AND deck = ");
-        query_builder.push_bind(deck.trim().to_string());
+        query_builder.push_bind(deck);
     }
 
-    if let Some(search) = search.filter(|value| !value.trim().is_empty()) {
-        let pattern = format!("%{}%", search.trim().to_lowercase());
-        query_builder.push(" AND (LOWER(event) LIKE ");
-        query_builder.push_bind(pattern.clone());
-        query_builder.push(" OR LOWER(message) LIKE ");
-        query_builder.push_bind(pattern.clone());
-        query_builder.push(" OR LOWER(COALESCE(metadata_json, '')) LIKE ");
-        query_builder.push_bind(pattern);
-        query_builder.push(")");
+    if let Some(search) = text(&filter.search) {
+        if SEARCH_INDEX_READY.load(Ordering::Relaxed) {
+            query_builder
+                .push(" AND id IN (SELECT rowid FROM event_log_fts WHERE event_log_fts MATCH ");
+            query_builder.push_bind(fts_query(&search));
+            query_builder.push(")");
+        } else {
+            let pattern = format!("%{}%", search.to_lowercase());
+            query_builder.push(" AND (LOWER(event) LIKE ");
+            query_builder.push_bind(pattern.clone());
+            query_builder.push(" OR LOWER(message) LIKE ");
+            query_builder.push_bind(pattern.clone());
+            query_builder.push(" OR LOWER(COALESCE(metadata_json, '')) LIKE ");
+            query_builder.push_bind(pattern);
+            query_builder.push(")");
+        }
     }
 }
 
+/// Every word must appear, as a word prefix; quoting keeps FTS5 operators
+/// and punctuation in the search box literal.
+fn fts_query(search: &str) -> String {
+    search
+        .split_whitespace()
+        .map(|word| format!("\"{}\"*", word.replace('"', "\"\"")))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Create the full-text index over `event_log` (kept in step by triggers)
+/// and fill it from existing rows the first time.
+pub async fn ensure_search_index(pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    let exists: Option<String> = sqlx::query_scalar(
+        "SELECT name FROM sqlite_master WHERE type = 'table' AND name = 'event_log_fts'",
+    )
+    .fetch_optional(pool)
+    .await?;
+    sqlx::query(
+        r#"
+        CREATE VIRTUAL TABLE IF NOT EXISTS event_log_fts USING fts5(
+            event, message, metadata_json, content = 'event_log', content_rowid = 'id'
+        );
+        CREATE TRIGGER IF NOT EXISTS event_log_fts_insert AFTER INSERT ON event_log BEGIN
+            INSERT INTO event_log_fts (rowid, event, message, metadata_json)
+            VALUES (new.id, new.event, new.message, new.metadata_json);
+        END;
+        CREATE TRIGGER IF NOT EXISTS event_log_fts_delete AFTER DELETE ON event_log BEGIN
+            INSERT INTO event_log_fts (event_log_fts, rowid, event, message, metadata_json)
+            VALUES ('delete', old.id, old.event, old.message, old.metadata_json);
+        END;
+        "#,
+    )
+    .execute(pool)
+    .await?;
+    if exists.is_none() {
+        sqlx::query("INSERT INTO event_log_fts (event_log_fts) VALUES ('rebuild')")
+            .execute(pool)
+            .await?;
+    }
+    SEARCH_INDEX_READY.store(true, Ordering::Relaxed);
+    Ok(())
+}
+
 /// Clear old event log entries
 pub async fn clear_event_log(pool: &SqlitePool, older_than_days: i64) -> Result<u64, sqlx::Error> {
     let cutoff_ms = std::time::SystemTime::now()
@@ -303,6 +365,14 @@ mod tests {
         pool
     }
 
+    #[test]
+    fn search_words_become_quoted_prefixes() {
+        assert_eq!(
+            fts_query(" buffer_underrun  deck\"b "),
+            "\"buffer_underrun\"* \"deck\"\"b\"*"
+        );
+    }
+
     #[tokio::test]
     async fn get_event_log_applies_filters_and_count() {
         let pool = setup_pool().await;
@@ -331,19 +401,18 @@ mod tests {
             .await
             .expect("insert row 2");
 
-        let (rows, total) = get_event_log(
-            &pool,
-            20,
-            0,
-            Some("info"),
-            Some("stream"),
-            Some(1_699_999_999_000),
-            Some(1_700_000_050_000),
-            Some("icecast"),
-            Some("deck_a"),
-        )
-        .await
-        .expect("filtered event log");
+        let filter = EventLogFilter {
+            level: Some("info".to_string()),
+            category: Some("stream".to_string()),
+            start_time: Some(1_699_999_999_000),
+            end_time: Some(1_700_000_050_000),
+            search: Some("icecast".to_string()),
+            deck: Some("deck_a".to_string()),
+            before_id: None,
+        };
+        let (rows, total) = get_event_log(&pool, 20, 0, &filter)
+            .await
+            .expect("filtered event log");
 
         assert_eq!(total, 1);
         assert_eq!(rows.len(), 1);
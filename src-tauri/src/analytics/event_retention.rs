@@ -0,0 +1,347 @@
+/// Event log retention and archival
+///
+/// `event_log` is capped by age, row count and approximate size. Once an
+/// hour the oldest entries past any limit are removed, after being written
+/// (when archiving is on) to a gzip'd NDJSON file, one `EventLogEntry` per
+/// line, which zcat / jq read directly. If the archive cannot be written
+/// nothing is deleted. The policy is a JSON file in the app data folder.
+use std::{
+    io::{BufWriter, Write},
+    path::PathBuf,
+    time::Duration,
+};
+
+use chrono::Local;
+use flate2::{write::GzEncoder, Compression};
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use tauri::{AppHandle, Manager};
+
+use super::event_logger::{self, EventLogEntry, EventLogRow, EVENT_LOG_COLUMNS};
+use crate::state::AppState;
+
+const CONFIG_FILE: &str = "event_log_retention.json";
+const TICK: Duration = Duration::from_secs(3600);
+/// Rows read or deleted per statement, so pruning never holds a long write lock.
+const BATCH: i64 = 5_000;
+/// Estimated bytes of one row: its text plus ids, timestamp, level and category.
+const ROW_BYTES: &str =
+    "LENGTH(event) + LENGTH(message) + COALESCE(LENGTH(metadata_json), 0) + COALESCE(LENGTH(deck), 0) + 64";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EventLogRetention {
+    /// Prune automatically every hour
+    pub enabled: bool,
+    /// Entries older than this are removed (0 = no age limit)
+    pub max_age_days: u32,
+    /// Newest entries kept (0 = no row limit)
+    pub max_rows: u32,
+    /// Estimated table size kept (0 = no size limit)
+    pub max_size_mb: u32,
+    /// Write pruned entries to `.ndjson.gz` before deleting them
+    pub archive: bool,
+    /// Default: `event_log_archive` in the app data folder
+    pub archive_dir: Option<String>,
+}
+
+impl Default for EventLogRetention {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_age_days: 90,
+            max_rows: 250_000,
+            max_size_mb: 0,
+            archive: false,
+            archive_dir: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PruneReport {
+    pub deleted: u64,
+    /// Archive written for the deleted entries
+    pub archive_path: Option<String>,
+    pub remaining: i64,
+}
+
+fn app_data_dir() -> PathBuf {
+    PathBuf::from(crate::compute_app_data_dir())
+}
+
+pub fn load_config() -> EventLogRetention {
+    std::fs::read(app_data_dir().join(CONFIG_FILE))
+        .ok()
+        .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+        .unwrap_or_default()
+}
+
+pub fn save_config(config: &EventLogRetention) -> Result<(), String> {
+    let json = serde_json::to_vec_pretty(config).map_err(|e| e.to_string())?;
+    std::fs::write(app_data_dir().join(CONFIG_FILE), json)
+        .map_err(|e| format!("Cannot save event log retention: {e}"))
+}
+
+pub fn archive_dir(config: &EventLogRetention) -> PathBuf {
+    config
+        .archive_dir
+        .as_deref()
+        .filter(|d| !d.trim().is_empty())
+        .map(PathBuf::from)
+        .unwrap_or_else(|| app_data_dir().join("event_log_archive"))
+}
+
+/// Newest id past any limit; it and everything older goes.
+async fn cutoff_id(
+    pool: &SqlitePool,
+    config: &EventLogRetention,
+    now_ms: i64,
+) -> Result<Option<i64>, sqlx::Error> {
+    let mut cutoff: Option<i64> = None;
+
+    if config.max_age_days > 0 {
+        let before = now_ms - i64::from(config.max_age_days) * 86_400_000;
+        let id: Option<i64> =
+            sqlx::query_scalar("SELECT MAX(id) FROM event_log WHERE timestamp < ?")
+                .bind(before)
+                .fetch_one(pool)
+                .await?;
+        cutoff = cutoff.max(id);
+    }
+
+    if config.max_rows > 0 {
+        let id: Option<i64> =
+            sqlx::query_scalar("SELECT id FROM event_log ORDER BY id DESC LIMIT 1 OFFSET ?")
+                .bind(i64::from(config.max_rows))
+                .fetch_optional(pool)
+                .await?;
+        cutoff = cutoff.max(id);
+    }
+
+    if config.max_size_mb > 0 {
+        let max_bytes = i64::from(config.max_size_mb) * 1024 * 1024;
+        let total: i64 = sqlx::query_scalar(&format!(
+            "SELECT COALESCE(SUM({ROW_BYTES}), 0) FROM event_log"
+        ))
+        .fetch_one(pool)
+        .await?;
+        if total > max_bytes {
+            // Oldest rows whose running size covers the excess.
+            let id: Option<i64> = sqlx::query_scalar(&format!(
+                "SELECT id FROM (SELECT id, SUM({ROW_BYTES}) OVER (ORDER BY id) AS running \
+                 FROM event_log) WHERE running >= ? ORDER BY id LIMIT 1"
+            ))
+            .bind(total - max_bytes)
+            .fetch_optional(pool)
+            .await?;
+            cutoff = cutoff.max(id);
+        }
+    }
+
+    Ok(cutoff)
+}
+
+/// Write entries up to `cutoff` to a new archive, oldest first.
+async fn write_archive(
+    pool: &SqlitePool,
+    config: &EventLogRetention,
+    cutoff: i64,
+) -> Result<PathBuf, String> {
+    let dir = archive_dir(config);
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Cannot create {}: {e}", dir.display()))?;
+    let name = format!(
+        "event_log-{}.ndjson.gz",
+        Local::now().format("%Y%m%d-%H%M%S")
+    );
+    let path = dir.join(&name);
+    // Renamed into place once complete, like database backups.
+    let partial = dir.join(format!("{name}.partial"));
+    let write_err = |e: std::io::Error| format!("Cannot write {}: {e}", partial.display());
+
+    let file = std::fs::File::create(&partial).map_err(write_err)?;
+    let mut encoder = GzEncoder::new(BufWriter::new(file), Compression::default());
+    let mut after = 0_i64;
+    let result: Result<(), String> = async {
+        loop {
+            let rows = sqlx::query_as::<_, EventLogRow>(&format!(
+                "SELECT {EVENT_LOG_COLUMNS} FROM event_log WHERE id > ? AND id <= ? ORDER BY id LIMIT ?"
+            ))
+            .bind(after)
+            .bind(cutoff)
+            .bind(BATCH)
+            .fetch_all(pool)
+            .await
+            .map_err(|e| format!("Cannot read event log: {e}"))?;
+            let Some(last) = rows.last() else {
+                return Ok(());
+            };
+            after = last.0;
+            for row in rows {
+                serde_json::to_writer(&mut encoder, &EventLogEntry::from(row))
+                    .map_err(|e| e.to_string())?;
+                encoder.write_all(b"\n").map_err(write_err)?;
+            }
+        }
+    }
+    .await;
+    if let Err(e) = result.and_then(|()| {
+        encoder
+            .finish()
+            .and_then(|mut out| out.flush())
+            .map_err(write_err)?;
+        std::fs::rename(&partial, &path).map_err(write_err)
+    }) {
+        let _ = std::fs::remove_file(&partial);
+        return Err(e);
+    }
+    Ok(path)
+}
+
+/// Apply `config` now: archive (if enabled) and delete entries past the limits.
+pub async fn prune(pool: &SqlitePool, config: &EventLogRetention) -> Result<PruneReport, String> {
+    let now_ms = chrono::Utc::now().timestamp_millis();
+    let cutoff = cutoff_id(pool, config, now_ms)
+        .await
+        .map_err(|e| format!("Cannot size event log: {e}"))?;
+
+    let mut report = PruneReport {
+        deleted: 0,
+        archive_path: None,
+        remaining: 0,
+    };
+    if let Some(cutoff) = cutoff {
+        if config.archive {
+            let path = write_archive(pool, config, cutoff).await?;
+            report.archive_path = Some(path.to_string_lossy().to_string());
+        }
+        loop {
+            let deleted = sqlx::query(
+                "DELETE FROM event_log WHERE id IN (SELECT id FROM event_log WHERE id <= ? ORDER BY id LIMIT ?)",
+            )
+            .bind(cutoff)
+            .bind(BATCH)
+            .execute(pool)
+            .await
+            .map_err(|e| format!("Cannot prune event log: {e}"))?
+            .rows_affected();
+            if deleted == 0 {
+                break;
+            }
+            report.deleted += deleted;
+        }
+    }
+    report.remaining = sqlx::query_scalar("SELECT COUNT(*) FROM event_log")
+        .fetch_one(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(report)
+}
+
+/// Build the search index, then prune every hour while enabled.
+pub fn start(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let state = app.state::<AppState>();
+        let Some(pool) = state.local_db.clone() else {
+            return;
+        };
+        if let Err(e) = event_logger::ensure_search_index(&pool).await {
+            log::warn!("Event log search index unavailable, using plain matching: {e}");
+        }
+        let mut interval = tokio::time::interval(TICK);
+        loop {
+            interval.tick().await;
+            let config = load_config();
+            if !config.enabled {
+                continue;
+            }
+            match prune(&pool, &config).await {
+                Ok(report) if report.deleted > 0 => log::info!(
+                    "Event log pruned: {} removed, {} kept{}",
+                    report.deleted,
+                    report.remaining,
+                    report
+                        .archive_path
+                        .map(|p| format!(", archived to {p}"))
+                        .unwrap_or_default()
+                ),
+                Ok(_) => {}
+                Err(e) => log::warn!("Event log pruning failed: {e}"),
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn pool_with_rows(count: i64) -> SqlitePool {
+        let pool = sqlx::sqlite::SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .expect("in-memory sqlite pool");
+        sqlx::query(
+            r#"
+            CREATE TABLE event_log (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                timestamp INTEGER NOT NULL,
+                level TEXT NOT NULL,
+                category TEXT NOT NULL,
+                event TEXT NOT NULL,
+                message TEXT NOT NULL,
+                metadata_json TEXT,
+                deck TEXT,
+                song_id INTEGER,
+                encoder_id INTEGER
+            )
+            "#,
+        )
+        .execute(&pool)
+        .await
+        .expect("create event_log table");
+        for i in 0..count {
+            // One row a day, oldest first; 100-byte messages.
+            sqlx::query(
+                "INSERT INTO event_log (timestamp, level, category, event, message) \
+                 VALUES (?, 'info', 'system', 'tick', ?)",
+            )
+            .bind(i * 86_400_000)
+            .bind("x".repeat(96))
+            .execute(&pool)
+            .await
+            .expect("insert row");
+        }
+        pool
+    }
+
+    #[tokio::test]
+    async fn cutoff_takes_the_strictest_limit() {
+        let pool = pool_with_rows(20).await;
+        let now_ms = 20 * 86_400_000;
+        let none = EventLogRetention {
+            max_age_days: 0,
+            max_rows: 0,
+            ..EventLogRetention::default()
+        };
+        assert_eq!(cutoff_id(&pool, &none, now_ms).await.unwrap(), None);
+
+        // Rows 1..=5 are more than 15 days old.
+        let by_age = EventLogRetention {
+            max_age_days: 15,
+            ..none.clone()
+        };
+        assert_eq!(cutoff_id(&pool, &by_age, now_ms).await.unwrap(), Some(5));
+
+        let by_rows = EventLogRetention {
+            max_rows: 12,
+            ..by_age.clone()
+        };
+        assert_eq!(cutoff_id(&pool, &by_rows, now_ms).await.unwrap(), Some(8));
+
+        let report = prune(&pool, &by_rows).await.unwrap();
+        assert_eq!((report.deleted, report.remaining), (8, 12));
+        assert!(report.archive_path.is_none());
+    }
+}
@@ -1,10 +1,29 @@
 use serde::{Deserialize, Serialize};
 use sqlx::SqlitePool;
+use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
 use tokio::sync::Mutex;
 use tokio::time::interval;
 
+use super::alerts::{self, AlertConfig};
+use super::event_logger::{log_event, EventCategory, LogLevel};
+use crate::audio::crossfade::DeckId;
+use crate::audio::loudness_meter::{self, LoudnessHealth};
+use crate::scripting::trigger::ScriptEvent;
+use crate::state::AppState;
+use crate::stream::broadcaster::EncoderStatus;
+
+/// How often health is sampled and the watchdog evaluated.
+const SAMPLE_INTERVAL_SECS: u64 = 5;
+/// The decoder ring holds about this much audio when full.
+const DECODER_RING_MS: f32 = 12_000.0;
+/// A playing deck with less than this buffered is starving.
+const UNDERRUN_BUFFER_MS: u64 = 100;
+/// Buffers drain naturally at the end of a track; ignore the last few seconds.
+const TRACK_TAIL_MS: u64 = 5_000;
+const SAM_PING_TIMEOUT: Duration = Duration::from_secs(3);
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SystemHealthSnapshot {
     pub timestamp: i64,
@@ -16,6 +35,13 @@ pub struct SystemHealthSnapshot {
     pub stream_connected: bool,
     pub mysql_connected: bool,
     pub active_encoders: i32,
+    /// Master bus loudness at sample time; `None` before any audio
+    pub short_term_lufs: Option<f32>,
+    pub integrated_lufs: Option<f32>,
+    /// Highest master true peak since the previous snapshot
+    pub true_peak_dbtp: Option<f32>,
+    /// True-peak overs since the previous snapshot
+    pub true_peak_overs: i64,
 }
 
 impl Default for SystemHealthSnapshot {
@@ -30,12 +56,351 @@ impl Default for SystemHealthSnapshot {
             stream_connected: false,
             mysql_connected: false,
             active_encoders: 0,
+            short_term_lufs: None,
+            integrated_lufs: None,
+            true_peak_dbtp: None,
+            true_peak_overs: 0,
+        }
+    }
+}
+
+// ── Watchdog ──────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertKind {
+    EncoderDisconnect,
+    BufferUnderrun,
+    SamDbLost,
+    DeadAir,
+}
+
+impl AlertKind {
+    pub fn as_str(&self) -> &str {
+        match self {
+            AlertKind::EncoderDisconnect => "encoder_disconnect",
+            AlertKind::BufferUnderrun => "buffer_underrun",
+            AlertKind::SamDbLost => "sam_db_lost",
+            AlertKind::DeadAir => "dead_air",
+        }
+    }
+
+    pub fn label(&self) -> &str {
+        match self {
+            AlertKind::EncoderDisconnect => "Encoder disconnected",
+            AlertKind::BufferUnderrun => "Buffer underrun",
+            AlertKind::SamDbLost => "SAM database unreachable",
+            AlertKind::DeadAir => "Dead air",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HealthAlert {
+    pub kind: AlertKind,
+    /// `true` when the condition has cleared
+    pub resolved: bool,
+    pub message: String,
+    pub encoder_id: Option<i64>,
+    pub deck: Option<String>,
+    /// Unix ms when the condition started
+    pub since: i64,
+    /// Unix ms when the alert was raised
+    pub timestamp: i64,
+}
+
+#[derive(Debug, Clone)]
+pub struct DeckProbe {
+    pub deck: String,
+    pub playing: bool,
+    pub buffer_ms: u64,
+    pub position_ms: u64,
+    pub duration_ms: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct EncoderProbe {
+    pub id: i64,
+    pub status: EncoderStatus,
+    pub error: Option<String>,
+}
+
+/// Raw inputs for one health sample.
+#[derive(Debug, Clone, Default)]
+pub struct HealthProbe {
+    pub timestamp: i64,
+    pub decks: Vec<DeckProbe>,
+    pub encoders: Vec<EncoderProbe>,
+    /// Master output peak from the last render block
+    pub master_peak_db: f32,
+    /// Master loudness since the previous probe
+    pub loudness: LoudnessHealth,
+    /// `None` when no SAM connection is configured
+    pub sam_connected: Option<bool>,
+}
+
+impl HealthProbe {
+    pub async fn collect(state: &AppState) -> Self {
+        let (decks, master_peak_db) = {
+            let engine = state.engine.lock().unwrap();
+            let decks = DeckId::PLAYBACK
+                .iter()
+                .filter_map(|id| engine.get_deck_state(*id))
+                .map(|ev| DeckProbe {
+                    playing: matches!(ev.state.as_str(), "playing" | "crossfading"),
+                    deck: ev.deck,
+                    buffer_ms: ev.decoder_buffer_ms,
+                    position_ms: ev.position_ms,
+                    duration_ms: ev.duration_ms,
+                })
+                .collect();
+            let master = engine
+                .get_vu_readings()
+                .into_iter()
+                .find(|v| v.channel == "master")
+                .map_or(-96.0, |v| v.left_db.max(v.right_db));
+            (decks, master)
+        };
+        let encoders = state
+            .encoder_manager
+            .get_all_runtime()
+            .into_iter()
+            .map(|r| EncoderProbe {
+                id: r.id,
+                status: r.status,
+                error: r.error,
+            })
+            .collect();
+        let sam_pool = state.sam_db.read().await.as_ref().cloned();
+        let sam_connected = match sam_pool {
+            Some(pool) => Some(matches!(
+                tokio::time::timeout(SAM_PING_TIMEOUT, sqlx::query("SELECT 1").execute(&pool))
+                    .await,
+                Ok(Ok(_))
+            )),
+            None => None,
+        };
+
+        Self {
+            timestamp: chrono::Utc::now().timestamp_millis(),
+            decks,
+            encoders,
+            master_peak_db,
+            loudness: loudness_meter::take_health(),
+            sam_connected,
+        }
+    }
+
+    fn on_air(&self) -> bool {
+        self.decks.iter().any(|d| d.playing)
+            || self.encoders.iter().any(|e| {
+                matches!(
+                    e.status,
+                    EncoderStatus::Streaming | EncoderStatus::Recording
+                )
+            })
+    }
+
+    fn snapshot(&self) -> SystemHealthSnapshot {
+        let fill = |deck: DeckId| {
+            let name = deck.to_string();
+            self.decks
+                .iter()
+                .find(|d| d.deck == name)
+                .map_or(0.0, |d| (d.buffer_ms as f32 / DECODER_RING_MS).min(1.0))
+        };
+        let active_encoders = self
+            .encoders
+            .iter()
+            .filter(|e| {
+                matches!(
+                    e.status,
+                    EncoderStatus::Streaming | EncoderStatus::Recording
+                )
+            })
+            .count();
+        SystemHealthSnapshot {
+            timestamp: self.timestamp,
+            ring_buffer_fill_deck_a: fill(DeckId::DeckA),
+            ring_buffer_fill_deck_b: fill(DeckId::DeckB),
+            stream_connected: active_encoders > 0,
+            mysql_connected: self.sam_connected.unwrap_or(false),
+            active_encoders: active_encoders as i32,
+            short_term_lufs: self.loudness.short_term_lufs,
+            integrated_lufs: self.loudness.integrated_lufs,
+            true_peak_dbtp: self.loudness.true_peak_dbtp,
+            true_peak_overs: self.loudness.overs as i64,
+            ..Default::default()
+        }
+    }
+}
+
+/// A condition that currently holds, before its threshold is applied.
+struct Condition {
+    key: String,
+    kind: AlertKind,
+    threshold_ms: i64,
+    message: String,
+    encoder_id: Option<i64>,
+    deck: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+struct Tracked {
+    kind: AlertKind,
+    since: i64,
+    alerted: bool,
+    message: String,
+    encoder_id: Option<i64>,
+    deck: Option<String>,
+}
+
+/// Edge-triggered alerting: a condition alerts once after holding for its
+/// threshold and once more when it clears.
+#[derive(Debug, Default)]
+pub struct Watchdog {
+    active: HashMap<String, Tracked>,
+    /// Condition key → unix ms of its last raised alert
+    last_alert: HashMap<String, i64>,
+}
+
+impl Watchdog {
+    pub fn evaluate(&mut self, probe: &HealthProbe, config: &AlertConfig) -> Vec<HealthAlert> {
+        let now = probe.timestamp;
+        let conditions = Self::conditions(probe, config);
+        let mut alerts = Vec::new();
+
+        let present: Vec<&str> = conditions.iter().map(|c| c.key.as_str()).collect();
+        let cleared: Vec<String> = self
+            .active
+            .keys()
+            .filter(|k| !present.contains(&k.as_str()))
+            .cloned()
+            .collect();
+        for key in cleared {
+            let tracked = self.active.remove(&key).expect("key taken from map");
+            if tracked.alerted && config.notify_recovery {
+                alerts.push(HealthAlert {
+                    kind: tracked.kind,
+                    resolved: true,
+                    message: format!("Recovered: {}", tracked.message),
+                    encoder_id: tracked.encoder_id,
+                    deck: tracked.deck,
+                    since: tracked.since,
+                    timestamp: now,
+                });
+            }
+        }
+
+        let repeat_ms = i64::from(config.repeat_after_mins) * 60_000;
+        for condition in conditions {
+            let tracked = self
+                .active
+                .entry(condition.key.clone())
+                .or_insert_with(|| Tracked {
+                    kind: condition.kind,
+                    since: now,
+                    alerted: false,
+                    message: condition.message.clone(),
+                    encoder_id: condition.encoder_id,
+                    deck: condition.deck.clone(),
+                });
+            tracked.message = condition.message;
+            if tracked.alerted || now - tracked.since < condition.threshold_ms {
+                continue;
+            }
+            let recently = self
+                .last_alert
+                .get(&condition.key)
+                .is_some_and(|at| now - at < repeat_ms);
+            if recently {
+                continue;
+            }
+            tracked.alerted = true;
+            self.last_alert.insert(condition.key, now);
+            alerts.push(HealthAlert {
+                kind: tracked.kind,
+                resolved: false,
+                message: tracked.message.clone(),
+                encoder_id: tracked.encoder_id,
+                deck: tracked.deck.clone(),
+                since: tracked.since,
+                timestamp: now,
+            });
         }
+        alerts
+    }
+
+    fn conditions(probe: &HealthProbe, config: &AlertConfig) -> Vec<Condition> {
+        let secs = |s: u32| i64::from(s) * 1000;
+        let mut out = Vec::new();
+
+        for enc in &probe.encoders {
+            if !matches!(
+                enc.status,
+                EncoderStatus::Retrying { .. } | EncoderStatus::Failed
+            ) {
+                continue;
+            }
+            let reason = enc.error.as_deref().unwrap_or("connection lost");
+            out.push(Condition {
+                key: format!("encoder:{}", enc.id),
+                kind: AlertKind::EncoderDisconnect,
+                threshold_ms: secs(config.encoder_down_secs),
+                message: format!("Encoder {} is disconnected ({reason})", enc.id),
+                encoder_id: Some(enc.id),
+                deck: None,
+            });
+        }
+
+        for deck in &probe.decks {
+            let in_tail = deck.duration_ms > 0
+                && deck.position_ms.saturating_add(TRACK_TAIL_MS) >= deck.duration_ms;
+            if deck.playing && deck.buffer_ms < UNDERRUN_BUFFER_MS && !in_tail {
+                out.push(Condition {
+                    key: format!("underrun:{}", deck.deck),
+                    kind: AlertKind::BufferUnderrun,
+                    threshold_ms: secs(config.underrun_secs),
+                    message: format!("{} is starved of decoded audio", deck.deck),
+                    encoder_id: None,
+                    deck: Some(deck.deck.clone()),
+                });
+            }
+        }
+
+        if probe.sam_connected == Some(false) {
+            out.push(Condition {
+                key: "sam_db".to_string(),
+                kind: AlertKind::SamDbLost,
+                threshold_ms: secs(config.sam_down_secs),
+                message: "SAM database is not responding".to_string(),
+                encoder_id: None,
+                deck: None,
+            });
+        }
+
+        if probe.on_air() && probe.master_peak_db < config.dead_air_threshold_db {
+            out.push(Condition {
+                key: "dead_air".to_string(),
+                kind: AlertKind::DeadAir,
+                threshold_ms: secs(config.dead_air_secs),
+                message: format!(
+                    "Master output below {:.0} dBFS while on air",
+                    config.dead_air_threshold_db
+                ),
+                encoder_id: None,
+                deck: None,
+            });
+        }
+        out
     }
 }
 
+// ── Monitor ───────────────────────────────────────────────────────────────────
+
 pub struct HealthMonitor {
     current: Arc<Mutex<SystemHealthSnapshot>>,
+    watchdog: Mutex<Watchdog>,
     pool: Option<SqlitePool>,
 }
 
@@ -43,6 +408,7 @@ impl HealthMonitor {
     pub fn new() -> Self {
         Self {
             current: Arc::new(Mutex::new(SystemHealthSnapshot::default())),
+            watchdog: Mutex::new(Watchdog::default()),
             pool: None,
         }
     }
@@ -52,53 +418,80 @@ impl HealthMonitor {
         self
     }
 
-    /// Start background monitoring task
-    pub fn start_monitoring(self: Arc<Self>) {
-        tokio::spawn(async move {
-            let mut ticker = interval(Duration::from_secs(5));
+    /// Start the background sampler: records health snapshots and raises
+    /// watchdog alerts (UI event, event log, configured targets).
+    pub fn start_monitoring(self: Arc<Self>, app: AppHandle) {
+        tauri::async_runtime::spawn(async move {
+            let mut ticker = interval(Duration::from_secs(SAMPLE_INTERVAL_SECS));
 
             loop {
                 ticker.tick().await;
+                let state = app.state::<AppState>();
+                let pool = self.pool.clone().or_else(|| state.local_db.clone());
+                let config = match &pool {
+                    Some(pool) => alerts::get_config(pool).await.unwrap_or_default(),
+                    None => AlertConfig::default(),
+                };
 
-                // Collect metrics
-                let snapshot = self.collect_snapshot().await;
-
-                // Update current snapshot
-                {
-                    let mut current = self.current.lock().await;
-                    *current = snapshot.clone();
+                let probe = HealthProbe::collect(&state).await;
+                let snapshot = probe.snapshot();
+                *self.current.lock().await = snapshot.clone();
+                if let Some(pool) = &pool {
+                    let _ = self.save_snapshot(pool, &snapshot).await;
                 }
 
-                // Save to database if available
-                if let Some(pool) = &self.pool {
-                    let _ = self.save_snapshot(pool, &snapshot).await;
+                if !config.enabled {
+                    continue;
+                }
+                let raised = self.watchdog.lock().await.evaluate(&probe, &config);
+                for alert in raised {
+                    let _ = app.emit("health_alert", &alert);
+                    if let Some(pool) = &pool {
+                        let level = if alert.resolved {
+                            LogLevel::Info
+                        } else {
+                            LogLevel::Error
+                        };
+                        let _ = log_event(
+                            pool,
+                            level,
+                            EventCategory::System,
+                            "health_alert",
+                            &alert.message,
+                            serde_json::to_value(&alert).ok(),
+                            alert.deck.as_deref(),
+                            None,
+                            alert.encoder_id,
+                        )
+                        .await;
+                    }
+                    match (alert.kind, alert.encoder_id) {
+                        (AlertKind::DeadAir, _) => state.script_engine.fire(ScriptEvent::Silence {
+                            resolved: alert.resolved,
+                            message: alert.message.clone(),
+                        }),
+                        (AlertKind::EncoderDisconnect, Some(encoder_id)) if alert.resolved => state
+                            .script_engine
+                            .fire(ScriptEvent::EncoderConnect { encoder_id }),
+                        (AlertKind::EncoderDisconnect, Some(encoder_id)) => {
+                            state.script_engine.fire(ScriptEvent::EncoderDisconnect {
+                                encoder_id,
+                                reason: alert.message.clone(),
+                            })
+                        }
+                        _ => {}
+                    }
+                    let config = config.clone();
+                    let scripts = state.script_engine.clone();
+                    tauri::async_runtime::spawn(async move {
+                        alerts::dispatch(&config, &alert, &scripts).await;
+                    });
                 }
             }
         });
     }
 
-    async fn collect_snapshot(&self) -> SystemHealthSnapshot {
-        let now_ms = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_millis() as i64;
-
-        // TODO: Collect real metrics from audio engine
-        // For now, return mock data
-        SystemHealthSnapshot {
-            timestamp: now_ms,
-            cpu_pct: 5.0,
-            memory_mb: 250.0,
-            ring_buffer_fill_deck_a: 0.85,
-            ring_buffer_fill_deck_b: 0.90,
-            decoder_latency_ms: 2.5,
-            stream_connected: true,
-            mysql_connected: true,
-            active_encoders: 2,
-        }
-    }
-
-    async fn save_snapshot(
+    pub async fn save_snapshot(
         &self,
         pool: &SqlitePool,
         snapshot: &SystemHealthSnapshot,
@@ -108,8 +501,9 @@ impl HealthMonitor {
             INSERT INTO system_health_snapshots (
                 timestamp, cpu_pct, memory_mb,
                 ring_buffer_fill_deck_a, ring_buffer_fill_deck_b,
-                decoder_latency_ms, stream_connected, mysql_connected, active_encoders
-            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+                decoder_latency_ms, stream_connected, mysql_connected, active_encoders,
+                short_term_lufs, integrated_lufs, true_peak_dbtp, true_peak_overs
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
             "#,
         )
         .bind(snapshot.timestamp)
@@ -121,6 +515,10 @@ impl HealthMonitor {
         .bind(snapshot.stream_connected as i64)
         .bind(snapshot.mysql_connected as i64)
         .bind(snapshot.active_encoders)
+        .bind(snapshot.short_term_lufs)
+        .bind(snapshot.integrated_lufs)
+        .bind(snapshot.true_peak_dbtp)
+        .bind(snapshot.true_peak_overs)
         .execute(pool)
         .await?;
 
@@ -141,11 +539,27 @@ impl HealthMonitor {
             .as_millis() as i64
             - (period_minutes * 60 * 1000);
 
-        let rows = sqlx::query_as::<_, (i64, f32, f32, f32, f32, f32, i64, i64, i32)>(
+        type HealthRow = (
+            i64,
+            f32,
+            f32,
+            f32,
+            f32,
+            f32,
+            i64,
+            i64,
+            i32,
+            Option<f32>,
+            Option<f32>,
+            Option<f32>,
+            i64,
+        );
+        let rows = sqlx::query_as::<_, HealthRow>(
             r#"
             SELECT timestamp, cpu_pct, memory_mb,
                    ring_buffer_fill_deck_a, ring_buffer_fill_deck_b,
-                   decoder_latency_ms, stream_connected, mysql_connected, active_encoders
+                   decoder_latency_ms, stream_connected, mysql_connected, active_encoders,
+                   short_term_lufs, integrated_lufs, true_peak_dbtp, true_peak_overs
             FROM system_health_snapshots
             WHERE timestamp >= ?
             ORDER BY timestamp ASC
@@ -168,6 +582,10 @@ impl HealthMonitor {
                     stream_connected,
                     mysql_connected,
                     active_encoders,
+                    short_term_lufs,
+                    integrated_lufs,
+                    true_peak_dbtp,
+                    true_peak_overs,
                 )| {
                     SystemHealthSnapshot {
                         timestamp,
@@ -179,9 +597,83 @@ impl HealthMonitor {
                         stream_connected: stream_connected != 0,
                         mysql_connected: mysql_connected != 0,
                         active_encoders,
+                        short_term_lufs,
+                        integrated_lufs,
+                        true_peak_dbtp,
+                        true_peak_overs,
                     }
                 },
             )
             .collect())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn probe(timestamp: i64, encoder: EncoderStatus, master_peak_db: f32) -> HealthProbe {
+        HealthProbe {
+            timestamp,
+            decks: vec![DeckProbe {
+                deck: "deck_a".to_string(),
+                playing: true,
+                buffer_ms: 4_000,
+                position_ms: 60_000,
+                duration_ms: 200_000,
+            }],
+            encoders: vec![EncoderProbe {
+                id: 1,
+                status: encoder,
+                error: None,
+            }],
+            master_peak_db,
+            loudness: LoudnessHealth::default(),
+            sam_connected: None,
+        }
+    }
+
+    #[test]
+    fn alerts_once_after_threshold_and_on_recovery() {
+        let config = AlertConfig::default();
+        let mut dog = Watchdog::default();
+        let retrying = EncoderStatus::Retrying { attempt: 1, max: 0 };
+
+        assert!(dog
+            .evaluate(&probe(0, retrying.clone(), -12.0), &config)
+            .is_empty());
+        assert!(dog
+            .evaluate(&probe(10_000, retrying.clone(), -12.0), &config)
+            .is_empty());
+
+        let raised = dog.evaluate(&probe(15_000, retrying.clone(), -12.0), &config);
+        assert_eq!(raised.len(), 1);
+        assert_eq!(raised[0].kind, AlertKind::EncoderDisconnect);
+        assert_eq!(raised[0].since, 0);
+        assert!(dog
+            .evaluate(&probe(20_000, retrying, -12.0), &config)
+            .is_empty());
+
+        let cleared = dog.evaluate(&probe(25_000, EncoderStatus::Streaming, -12.0), &config);
+        assert_eq!(cleared.len(), 1);
+        assert!(cleared[0].resolved);
+    }
+
+    #[test]
+    fn dead_air_only_counts_while_on_air() {
+        let config = AlertConfig::default();
+        let mut dog = Watchdog::default();
+        let mut idle = probe(0, EncoderStatus::Disabled, -96.0);
+        idle.decks[0].playing = false;
+        assert!(dog.evaluate(&idle, &config).is_empty());
+        idle.timestamp = 60_000;
+        assert!(dog.evaluate(&idle, &config).is_empty());
+
+        assert!(dog
+            .evaluate(&probe(60_000, EncoderStatus::Streaming, -96.0), &config)
+            .is_empty());
+        let raised = dog.evaluate(&probe(80_000, EncoderStatus::Streaming, -96.0), &config);
+        assert_eq!(raised.len(), 1);
+        assert_eq!(raised[0].kind, AlertKind::DeadAir);
+    }
+}
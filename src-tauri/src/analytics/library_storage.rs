@@ -0,0 +1,400 @@
+/// Library storage analyzer
+///
+/// Stats every song file SAM knows about and breaks disk usage down by
+/// category, format and (estimated) bitrate. Songs that exist more than once
+/// are grouped so low-bitrate copies of tracks already held in high quality
+/// can be cleaned up; the report ends with concrete cleanup suggestions.
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+/// Bitrate (kbps) from which a lossy copy counts as high quality.
+const HIGH_QUALITY_KBPS: u32 = 256;
+/// Lossy files below this are flagged for replacement.
+const LOW_BITRATE_KBPS: u32 = 96;
+/// Rough FLAC saving over PCM WAV.
+const FLAC_SAVING: f64 = 0.4;
+
+// ── Data model ────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StorageFile {
+    pub song_id: i64,
+    pub artist: String,
+    pub title: String,
+    pub category: String,
+    pub path: String,
+    /// Lower-case file extension ("mp3", "flac", …)
+    pub format: String,
+    pub size_bytes: u64,
+    pub duration_secs: u32,
+    /// Average bitrate derived from size and duration
+    pub bitrate_kbps: Option<u32>,
+}
+
+impl StorageFile {
+    pub fn is_lossless(&self) -> bool {
+        matches!(
+            self.format.as_str(),
+            "flac" | "wav" | "aif" | "aiff" | "alac" | "ape"
+        )
+    }
+
+    pub fn is_high_quality(&self) -> bool {
+        self.is_lossless() || self.bitrate_kbps.is_some_and(|k| k >= HIGH_QUALITY_KBPS)
+    }
+
+    /// Ordering key: lossless beats lossy, then higher bitrate.
+    fn quality(&self) -> (bool, u32) {
+        (self.is_lossless(), self.bitrate_kbps.unwrap_or(0))
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StorageBucket {
+    pub key: String,
+    pub files: u64,
+    pub bytes: u64,
+    pub duration_secs: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DuplicateGroup {
+    pub artist: String,
+    pub title: String,
+    pub keep: StorageFile,
+    /// Lower-quality copies of `keep`
+    pub redundant: Vec<StorageFile>,
+    pub reclaimable_bytes: u64,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CleanupAction {
+    RemoveLowBitrateDuplicates,
+    RelinkMissingFiles,
+    ReplaceLowBitrate,
+    ConvertWavToFlac,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CleanupSuggestion {
+    pub action: CleanupAction,
+    pub message: String,
+    pub song_ids: Vec<i64>,
+    pub reclaimable_bytes: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LibraryStorageReport {
+    pub generated_at: i64,
+    pub total_files: u64,
+    pub total_bytes: u64,
+    /// Song IDs whose file could not be found on disk
+    pub missing_song_ids: Vec<i64>,
+    pub by_category: Vec<StorageBucket>,
+    pub by_format: Vec<StorageBucket>,
+    pub by_bitrate: Vec<StorageBucket>,
+    pub duplicates: Vec<DuplicateGroup>,
+    pub suggestions: Vec<CleanupSuggestion>,
+    pub reclaimable_bytes: u64,
+}
+
+// ── Scanning ──────────────────────────────────────────────────────────────────
+
+/// A library entry before its file has been looked at.
+#[derive(Debug, Clone)]
+pub struct LibraryEntry {
+    pub song_id: i64,
+    pub artist: String,
+    pub title: String,
+    pub category: String,
+    pub path: String,
+    pub duration_secs: u32,
+}
+
+/// Stat each entry's file (blocking). Returns the files found and the IDs of
+/// entries whose file is missing.
+pub fn scan_files(entries: Vec<LibraryEntry>) -> (Vec<StorageFile>, Vec<i64>) {
+    let mut files = Vec::with_capacity(entries.len());
+    let mut missing = Vec::new();
+    for entry in entries {
+        let size_bytes = match std::fs::metadata(&entry.path) {
+            Ok(meta) if meta.is_file() => meta.len(),
+            _ => {
+                missing.push(entry.song_id);
+                continue;
+            }
+        };
+        let format = Path::new(&entry.path)
+            .extension()
+            .map(|e| e.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        let bitrate_kbps = (entry.duration_secs > 0)
+            .then(|| (size_bytes * 8 / 1000 / u64::from(entry.duration_secs)) as u32);
+        files.push(StorageFile {
+            song_id: entry.song_id,
+            artist: entry.artist,
+            title: entry.title,
+            category: entry.category,
+            path: entry.path,
+            format,
+            size_bytes,
+            duration_secs: entry.duration_secs,
+            bitrate_kbps,
+        });
+    }
+    (files, missing)
+}
+
+// ── Analysis ──────────────────────────────────────────────────────────────────
+
+fn bitrate_band(file: &StorageFile) -> &'static str {
+    if file.is_lossless() {
+        return "lossless";
+    }
+    match file.bitrate_kbps {
+        None => "unknown",
+        Some(k) if k < 96 => "<96 kbps",
+        Some(k) if k < 128 => "96-127 kbps",
+        Some(k) if k < 192 => "128-191 kbps",
+        Some(k) if k < 256 => "192-255 kbps",
+        Some(k) if k < 320 => "256-319 kbps",
+        Some(_) => "320+ kbps",
+    }
+}
+
+/// Artist/title key that ignores case, spacing and punctuation.
+fn duplicate_key(file: &StorageFile) -> Option<String> {
+    let norm = |s: &str| {
+        s.chars()
+            .filter(|c| c.is_alphanumeric())
+            .flat_map(char::to_lowercase)
+            .collect::<String>()
+    };
+    let (artist, title) = (norm(&file.artist), norm(&file.title));
+    (!artist.is_empty() && !title.is_empty()).then(|| format!("{artist}\u{1f}{title}"))
+}
+
+fn buckets(files: &[StorageFile], key: impl Fn(&StorageFile) -> String) -> Vec<StorageBucket> {
+    let mut map: BTreeMap<String, StorageBucket> = BTreeMap::new();
+    for file in files {
+        let k = key(file);
+        let bucket = map.entry(k.clone()).or_insert_with(|| StorageBucket {
+            key: k,
+            ..Default::default()
+        });
+        bucket.files += 1;
+        bucket.bytes += file.size_bytes;
+        bucket.duration_secs += u64::from(file.duration_secs);
+    }
+    let mut out: Vec<_> = map.into_values().collect();
+    out.sort_by(|a, b| b.bytes.cmp(&a.bytes).then(a.key.cmp(&b.key)));
+    out
+}
+
+fn fmt_bytes(bytes: u64) -> String {
+    const GB: f64 = 1024.0 * 1024.0 * 1024.0;
+    const MB: f64 = 1024.0 * 1024.0;
+    let b = bytes as f64;
+    if b >= GB {
+        format!("{:.1} GB", b / GB)
+    } else {
+        format!("{:.1} MB", b / MB)
+    }
+}
+
+/// Build the report from scanned files.
+pub fn analyze(
+    files: Vec<StorageFile>,
+    missing_song_ids: Vec<i64>,
+    generated_at: i64,
+) -> LibraryStorageReport {
+    let by_category = buckets(&files, |f| {
+        if f.category.is_empty() {
+            "Uncategorized".to_string()
+        } else {
+            f.category.clone()
+        }
+    });
+    let by_format = buckets(&files, |f| {
+        if f.format.is_empty() {
+            "unknown".to_string()
+        } else {
+            f.format.clone()
+        }
+    });
+    let by_bitrate = buckets(&files, |f| bitrate_band(f).to_string());
+
+    let mut groups: HashMap<String, Vec<&StorageFile>> = HashMap::new();
+    for file in &files {
+        if let Some(key) = duplicate_key(file) {
+            groups.entry(key).or_default().push(file);
+        }
+    }
+    let mut duplicates: Vec<DuplicateGroup> = groups
+        .into_values()
+        .filter(|g| g.len() > 1)
+        .filter_map(|mut group| {
+            group.sort_by(|a, b| {
+                b.quality()
+                    .cmp(&a.quality())
+                    .then(a.song_id.cmp(&b.song_id))
+            });
+            let keep = group[0];
+            if !keep.is_high_quality() {
+                return None;
+            }
+            let redundant: Vec<StorageFile> = group[1..]
+                .iter()
+                .filter(|f| f.quality() < keep.quality() && !f.is_lossless())
+                .map(|f| (*f).clone())
+                .collect();
+            if redundant.is_empty() {
+                return None;
+            }
+            Some(DuplicateGroup {
+                artist: keep.artist.clone(),
+                title: keep.title.clone(),
+                keep: keep.clone(),
+                reclaimable_bytes: redundant.iter().map(|f| f.size_bytes).sum(),
+                redundant,
+            })
+        })
+        .collect();
+    duplicates.sort_by(|a, b| {
+        b.reclaimable_bytes
+            .cmp(&a.reclaimable_bytes)
+            .then(a.artist.cmp(&b.artist))
+            .then(a.title.cmp(&b.title))
+    });
+
+    let mut suggestions = Vec::new();
+    let duplicate_bytes: u64 = duplicates.iter().map(|d| d.reclaimable_bytes).sum();
+    if !duplicates.is_empty() {
+        let song_ids: Vec<i64> = duplicates
+            .iter()
+            .flat_map(|d| d.redundant.iter().map(|f| f.song_id))
+            .collect();
+        suggestions.push(CleanupSuggestion {
+            action: CleanupAction::RemoveLowBitrateDuplicates,
+            message: format!(
+                "Remove {} low-bitrate copies of songs also held in high quality ({})",
+                song_ids.len(),
+                fmt_bytes(duplicate_bytes)
+            ),
+            song_ids,
+            reclaimable_bytes: duplicate_bytes,
+        });
+    }
+    if !missing_song_ids.is_empty() {
+        suggestions.push(CleanupSuggestion {
+            action: CleanupAction::RelinkMissingFiles,
+            message: format!(
+                "{} library entries point to missing files; relink or remove them",
+                missing_song_ids.len()
+            ),
+            song_ids: missing_song_ids.clone(),
+            reclaimable_bytes: 0,
+        });
+    }
+    let redundant_ids: std::collections::HashSet<i64> = duplicates
+        .iter()
+        .flat_map(|d| d.redundant.iter().map(|f| f.song_id))
+        .collect();
+    let low: Vec<i64> = files
+        .iter()
+        .filter(|f| {
+            !f.is_lossless()
+                && f.bitrate_kbps.is_some_and(|k| k < LOW_BITRATE_KBPS)
+                && !redundant_ids.contains(&f.song_id)
+        })
+        .map(|f| f.song_id)
+        .collect();
+    if !low.is_empty() {
+        suggestions.push(CleanupSuggestion {
+            action: CleanupAction::ReplaceLowBitrate,
+            message: format!(
+                "{} songs are below {LOW_BITRATE_KBPS} kbps with no better copy; replace them",
+                low.len()
+            ),
+            song_ids: low,
+            reclaimable_bytes: 0,
+        });
+    }
+    let wav: Vec<&StorageFile> = files.iter().filter(|f| f.format == "wav").collect();
+    if !wav.is_empty() {
+        let saving = (wav.iter().map(|f| f.size_bytes).sum::<u64>() as f64 * FLAC_SAVING) as u64;
+        suggestions.push(CleanupSuggestion {
+            action: CleanupAction::ConvertWavToFlac,
+            message: format!(
+                "Convert {} WAV files to FLAC to save about {}",
+                wav.len(),
+                fmt_bytes(saving)
+            ),
+            song_ids: wav.iter().map(|f| f.song_id).collect(),
+            reclaimable_bytes: saving,
+        });
+    }
+
+    LibraryStorageReport {
+        generated_at,
+        total_files: files.len() as u64,
+        total_bytes: files.iter().map(|f| f.size_bytes).sum(),
+        reclaimable_bytes: suggestions.iter().map(|s| s.reclaimable_bytes).sum(),
+        missing_song_ids,
+        by_category,
+        by_format,
+        by_bitrate,
+        duplicates,
+        suggestions,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(song_id: i64, title: &str, format: &str, kbps: u32) -> StorageFile {
+        let duration_secs = 200;
+        StorageFile {
+            song_id,
+            artist: "Arijit Singh".to_string(),
+            title: title.to_string(),
+            category: "Bollywood".to_string(),
+            path: format!("/music/{song_id}.{format}"),
+            format: format.to_string(),
+            size_bytes: u64::from(kbps) * 1000 / 8 * u64::from(duration_secs),
+            duration_secs,
+            bitrate_kbps: Some(kbps),
+        }
+    }
+
+    #[test]
+    fn flags_low_bitrate_copies_of_high_quality_songs() {
+        let files = vec![
+            file(1, "Tum Hi Ho", "mp3", 320),
+            file(2, "tum hi ho!", "mp3", 128),
+            file(3, "Channa Mereya", "mp3", 128),
+            file(4, "Channa Mereya", "mp3", 96),
+            file(5, "Kesariya", "mp3", 64),
+        ];
+        let report = analyze(files, vec![9], 0);
+
+        assert_eq!(report.duplicates.len(), 1);
+        assert_eq!(report.duplicates[0].keep.song_id, 1);
+        assert_eq!(report.duplicates[0].redundant[0].song_id, 2);
+
+        let actions: Vec<_> = report.suggestions.iter().map(|s| s.action).collect();
+        assert_eq!(
+            actions,
+            vec![
+                CleanupAction::RemoveLowBitrateDuplicates,
+                CleanupAction::RelinkMissingFiles,
+                CleanupAction::ReplaceLowBitrate,
+            ]
+        );
+        assert_eq!(report.suggestions[2].song_ids, vec![5]);
+        assert_eq!(report.by_category[0].files, 5);
+    }
+}
@@ -1,59 +1,102 @@
+/// Listener analytics over the per-mount history written by
+/// `stats::icecast_stats` (`listener_snapshots`, `listener_sessions`).
+/// Snapshot times are stored in seconds; everything returned here is in ms.
+///
+/// Session KPIs follow the usual streaming-audience conventions: sessions
+/// shorter than a minute are bounces and don't count, a listener is
+/// identified by IP + user agent (an estimate — NAT and dynamic IPs blur
+/// it), and listening time is clipped to the reporting window.
+use std::collections::{HashMap, HashSet};
+
 use serde::{Deserialize, Serialize};
 use sqlx::SqlitePool;
 
+use super::play_stats::local_date_range_utc;
+
+/// Sessions shorter than this are treated as bounces.
+const MIN_SESSION_SECS: i64 = 60;
+/// How far before the window a listener must have been seen to count as returning.
+const RETURNING_LOOKBACK_SECS: i64 = 30 * 24 * 3600;
+const HOUR_SECS: i64 = 3600;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ListenerSnapshot {
     pub timestamp: i64,
     pub listener_count: i32,
     pub peak_listeners: Option<i32>,
+    pub unique_listeners: i32,
+    pub mount: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ListenerPeak {
     pub peak: i32,
     pub average: f32,
+    /// When the peak was reached
     pub timestamp: i64,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BreakdownEntry {
+    pub label: String,
+    pub listeners: i64,
+}
+
+/// Who listened over a period, from the admin client lists.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ListenerBreakdown {
+    pub unique_listeners: i64,
+    pub sessions: i64,
+    pub user_agents: Vec<BreakdownEntry>,
+    pub countries: Vec<BreakdownEntry>,
+}
+
+fn period_secs(period: &str) -> i64 {
+    match period {
+        "1h" => 3600,
+        "6h" => 6 * 3600,
+        "24h" => 24 * 3600,
+        "7d" => 7 * 24 * 3600,
+        "30d" => 30 * 24 * 3600,
+        _ => 3600,
+    }
+}
+
+fn cutoff_secs(period: &str) -> i64 {
+    chrono::Utc::now().timestamp() - period_secs(period)
+}
+
 /// Get listener graph data for an encoder
 pub async fn get_listener_graph(
     pool: &SqlitePool,
     encoder_id: i64,
     period: &str,
 ) -> Result<Vec<ListenerSnapshot>, sqlx::Error> {
-    let minutes = match period {
-        "1h" => 60,
-        "24h" => 24 * 60,
-        "7d" => 7 * 24 * 60,
-        _ => 60,
-    };
-
-    let cutoff_ms = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .unwrap()
-        .as_millis() as i64
-        - (minutes * 60 * 1000);
-
-    let rows = sqlx::query_as::<_, (i64, i32, Option<i32>)>(
+    let rows = sqlx::query_as::<_, (i64, i32, Option<i32>, i32, Option<String>)>(
         r#"
-        SELECT timestamp, listener_count, peak_listeners
+        SELECT snapshot_at * 1000, current_listeners, peak_listeners,
+               unique_listeners, mount
         FROM listener_snapshots
-        WHERE encoder_id = ? AND timestamp >= ?
-        ORDER BY timestamp ASC
+        WHERE encoder_id = ? AND snapshot_at >= ?
+        ORDER BY snapshot_at ASC
         "#,
     )
     .bind(encoder_id)
-    .bind(cutoff_ms)
+    .bind(cutoff_secs(period))
     .fetch_all(pool)
     .await?;
 
     Ok(rows
         .into_iter()
         .map(
-            |(timestamp, listener_count, peak_listeners)| ListenerSnapshot {
-                timestamp,
-                listener_count,
-                peak_listeners,
+            |(timestamp, listener_count, peak_listeners, unique_listeners, mount)| {
+                ListenerSnapshot {
+                    timestamp,
+                    listener_count,
+                    peak_listeners,
+                    unique_listeners,
+                    mount,
+                }
             },
         )
         .collect())
@@ -65,71 +108,459 @@ pub async fn get_listener_peak(
     encoder_id: i64,
     period: &str,
 ) -> Result<ListenerPeak, sqlx::Error> {
-    let minutes = match period {
-        "1h" => 60,
-        "24h" => 24 * 60,
-        "7d" => 7 * 24 * 60,
-        _ => 60,
-    };
+    let cutoff = cutoff_secs(period);
 
-    let cutoff_ms = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .unwrap()
-        .as_millis() as i64
-        - (minutes * 60 * 1000);
+    let average: f64 = sqlx::query_scalar(
+        r#"
+        SELECT COALESCE(AVG(current_listeners), 0.0)
+        FROM listener_snapshots
+        WHERE encoder_id = ? AND snapshot_at >= ?
+        "#,
+    )
+    .bind(encoder_id)
+    .bind(cutoff)
+    .fetch_one(pool)
+    .await?;
 
-    let row = sqlx::query_as::<_, (i32, f64, i64)>(
+    let peak = sqlx::query_as::<_, (i32, i64)>(
         r#"
-        SELECT
-            MAX(listener_count) as peak,
-            AVG(listener_count) as average,
-            MAX(timestamp) as timestamp
+        SELECT current_listeners, snapshot_at * 1000
         FROM listener_snapshots
-        WHERE encoder_id = ? AND timestamp >= ?
+        WHERE encoder_id = ? AND snapshot_at >= ?
+        ORDER BY current_listeners DESC, snapshot_at DESC
+        LIMIT 1
         "#,
     )
     .bind(encoder_id)
-    .bind(cutoff_ms)
+    .bind(cutoff)
     .fetch_optional(pool)
     .await?;
 
-    match row {
-        Some((peak, average, timestamp)) => Ok(ListenerPeak {
-            peak,
-            average: average as f32,
-            timestamp,
-        }),
-        None => Ok(ListenerPeak {
-            peak: 0,
-            average: 0.0,
-            timestamp: 0,
-        }),
-    }
+    let (peak, timestamp) = peak.unwrap_or((0, 0));
+    Ok(ListenerPeak {
+        peak,
+        average: average as f32,
+        timestamp,
+    })
 }
 
-/// Record a listener snapshot (called from encoder polling task)
-pub async fn record_listener_snapshot(
+/// Unique listeners, top user agents and countries for a period.
+pub async fn get_listener_breakdown(
     pool: &SqlitePool,
     encoder_id: i64,
-    listener_count: i32,
-) -> Result<(), sqlx::Error> {
-    let now_ms = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .unwrap()
-        .as_millis() as i64;
-
-    sqlx::query(
+    period: &str,
+    limit: i64,
+) -> Result<ListenerBreakdown, sqlx::Error> {
+    let cutoff = cutoff_secs(period);
+
+    let (unique_listeners, sessions) = sqlx::query_as::<_, (i64, i64)>(
         r#"
-        INSERT INTO listener_snapshots (encoder_id, timestamp, listener_count, peak_listeners)
-        VALUES (?, ?, ?, ?)
+        SELECT COUNT(DISTINCT ip), COUNT(*)
+        FROM listener_sessions
+        WHERE encoder_id = ? AND last_seen_at >= ?
         "#,
     )
     .bind(encoder_id)
-    .bind(now_ms)
-    .bind(listener_count)
-    .bind(listener_count) // Use same value for peak initially
-    .execute(pool)
+    .bind(cutoff)
+    .fetch_one(pool)
     .await?;
 
-    Ok(())
+    let grouped = |column: &'static str| {
+        format!(
+            "SELECT COALESCE(NULLIF({column}, ''), 'Unknown') AS label, COUNT(DISTINCT ip) AS listeners \
+             FROM listener_sessions WHERE encoder_id = ? AND last_seen_at >= ? \
+             GROUP BY label ORDER BY listeners DESC, label ASC LIMIT ?"
+        )
+    };
+    let mut lists = Vec::with_capacity(2);
+    for column in ["user_agent", "country"] {
+        let rows = sqlx::query_as::<_, (String, i64)>(&grouped(column))
+            .bind(encoder_id)
+            .bind(cutoff)
+            .bind(limit.max(1))
+            .fetch_all(pool)
+            .await?;
+        lists.push(
+            rows.into_iter()
+                .map(|(label, listeners)| BreakdownEntry { label, listeners })
+                .collect::<Vec<_>>(),
+        );
+    }
+    let countries = lists.pop().unwrap_or_default();
+    let user_agents = lists.pop().unwrap_or_default();
+
+    Ok(ListenerBreakdown {
+        unique_listeners,
+        sessions,
+        user_agents,
+        countries,
+    })
+}
+
+// ── Geography ─────────────────────────────────────────────────────────────────
+
+/// Listeners from one country, or one city when `city` is set.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GeoEntry {
+    /// ISO 3166-1 alpha-2; `None` when only the server's country name is known
+    pub country_code: Option<String>,
+    /// "Unknown" for listeners that could not be located
+    pub country: String,
+    pub city: Option<String>,
+    pub listeners: i64,
+    pub sessions: i64,
+    /// Clipped to the period
+    pub listening_hours: f64,
+}
+
+/// Where listeners were over a period, from GeoIP-located sessions.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ListenerGeoBreakdown {
+    pub unique_listeners: i64,
+    /// Of `unique_listeners`, those with a known country
+    pub located_listeners: i64,
+    pub countries: Vec<GeoEntry>,
+    pub cities: Vec<GeoEntry>,
+}
+
+/// Country and city aggregates for one encoder (`None` = all encoders).
+/// Sessions the GeoIP database has not seen yet are located first.
+pub async fn get_listener_geo_breakdown(
+    pool: &SqlitePool,
+    encoder_id: Option<i64>,
+    period: &str,
+    limit: i64,
+) -> Result<ListenerGeoBreakdown, sqlx::Error> {
+    let cutoff = cutoff_secs(period);
+    crate::stats::geoip::backfill(pool, cutoff).await?;
+
+    let (unique_listeners, located_listeners) = sqlx::query_as::<_, (i64, i64)>(
+        r#"
+        SELECT COUNT(DISTINCT ip),
+               COUNT(DISTINCT CASE
+                   WHEN COALESCE(NULLIF(country_code, ''), NULLIF(country, '')) IS NOT NULL
+                   THEN ip END)
+        FROM listener_sessions
+        WHERE (? IS NULL OR encoder_id = ?) AND last_seen_at >= ?
+        "#,
+    )
+    .bind(encoder_id)
+    .bind(encoder_id)
+    .bind(cutoff)
+    .fetch_one(pool)
+    .await?;
+
+    let grouped = |by_city: bool| {
+        let (city, filter, group) = if by_city {
+            ("city", "AND NULLIF(city, '') IS NOT NULL", ", city")
+        } else {
+            ("NULL", "", "")
+        };
+        format!(
+            "SELECT NULLIF(country_code, ''), COALESCE(MAX(NULLIF(country, '')), 'Unknown'), {city}, \
+                    COUNT(DISTINCT ip) AS listeners, COUNT(*), \
+                    SUM(MAX(0, last_seen_at - MAX(connected_at, ?))) / 3600.0 \
+             FROM listener_sessions \
+             WHERE (? IS NULL OR encoder_id = ?) AND last_seen_at >= ? {filter} \
+             GROUP BY COALESCE(NULLIF(country_code, ''), NULLIF(country, ''), ''){group} \
+             ORDER BY listeners DESC, 2 ASC LIMIT ?"
+        )
+    };
+    let mut lists = Vec::with_capacity(2);
+    for by_city in [false, true] {
+        let rows = sqlx::query_as::<_, (Option<String>, String, Option<String>, i64, i64, f64)>(
+            &grouped(by_city),
+        )
+        .bind(cutoff)
+        .bind(encoder_id)
+        .bind(encoder_id)
+        .bind(cutoff)
+        .bind(limit.max(1))
+        .fetch_all(pool)
+        .await?;
+        lists.push(
+            rows.into_iter()
+                .map(
+                    |(country_code, country, city, listeners, sessions, hours)| GeoEntry {
+                        country_code,
+                        country,
+                        city,
+                        listeners,
+                        sessions,
+                        listening_hours: hours,
+                    },
+                )
+                .collect::<Vec<_>>(),
+        );
+    }
+    let cities = lists.pop().unwrap_or_default();
+    let countries = lists.pop().unwrap_or_default();
+
+    Ok(ListenerGeoBreakdown {
+        unique_listeners,
+        located_listeners,
+        countries,
+        cities,
+    })
+}
+
+// ── Session KPIs ──────────────────────────────────────────────────────────────
+
+/// One listener connection, in UTC seconds.
+#[derive(Debug, Clone)]
+pub struct ListenerSession {
+    pub ip: String,
+    pub user_agent: Option<String>,
+    pub connected_at: i64,
+    pub last_seen_at: i64,
+}
+
+impl ListenerSession {
+    fn listener_key(&self) -> String {
+        format!("{}|{}", self.ip, self.user_agent.as_deref().unwrap_or(""))
+    }
+
+    fn duration_secs(&self) -> i64 {
+        (self.last_seen_at - self.connected_at).max(0)
+    }
+
+    /// Seconds of this session inside `[from, to)`.
+    fn overlap_secs(&self, from: i64, to: i64) -> i64 {
+        (self.last_seen_at.min(to) - self.connected_at.max(from)).max(0)
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HourlyRetention {
+    /// Hour start, UTC ms
+    pub hour_start: i64,
+    /// Listeners connected as the hour began
+    pub audience_at_start: i64,
+    /// …of whom were still connected when it ended
+    pub retained: i64,
+    pub retention_pct: Option<f64>,
+    pub sessions_started: i64,
+    pub listening_hours: f64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ListenerKpis {
+    pub start_ms: i64,
+    pub end_ms: i64,
+    /// Total listening hours (TLH)
+    pub total_listening_hours: f64,
+    pub sessions: i64,
+    /// Sessions dropped as bounces (under a minute)
+    pub bounced_sessions: i64,
+    pub unique_listeners: i64,
+    pub average_session_secs: f64,
+    pub median_session_secs: i64,
+    /// Listeners also heard in the 30 days before the window, or on more
+    /// than one day within it
+    pub returning_listeners: i64,
+    pub returning_pct: f64,
+    pub hourly: Vec<HourlyRetention>,
+}
+
+/// KPIs for sessions overlapping `[from, to)` (UTC seconds). `prior` holds
+/// the listener keys seen before the window; `day_of` maps a timestamp to
+/// its local day.
+pub fn compute_kpis(
+    sessions: &[ListenerSession],
+    from: i64,
+    to: i64,
+    prior: &HashSet<String>,
+    day_of: impl Fn(i64) -> i64,
+) -> ListenerKpis {
+    let mut kpis = ListenerKpis {
+        start_ms: from * 1000,
+        end_ms: to * 1000,
+        ..ListenerKpis::default()
+    };
+    let counted: Vec<&ListenerSession> = sessions
+        .iter()
+        .filter(|s| s.overlap_secs(from, to) > 0)
+        .filter(|s| {
+            let keep = s.duration_secs() >= MIN_SESSION_SECS;
+            if !keep {
+                kpis.bounced_sessions += 1;
+            }
+            keep
+        })
+        .collect();
+
+    kpis.sessions = counted.len() as i64;
+    let listening_secs: i64 = counted.iter().map(|s| s.overlap_secs(from, to)).sum();
+    kpis.total_listening_hours = listening_secs as f64 / 3600.0;
+
+    let mut durations: Vec<i64> = counted.iter().map(|s| s.duration_secs()).collect();
+    durations.sort_unstable();
+    if !durations.is_empty() {
+        kpis.average_session_secs = durations.iter().sum::<i64>() as f64 / durations.len() as f64;
+        kpis.median_session_secs = durations[durations.len() / 2];
+    }
+
+    let mut days_by_listener: HashMap<String, HashSet<i64>> = HashMap::new();
+    for s in &counted {
+        days_by_listener
+            .entry(s.listener_key())
+            .or_default()
+            .insert(day_of(s.connected_at.max(from)));
+    }
+    kpis.unique_listeners = days_by_listener.len() as i64;
+    kpis.returning_listeners = days_by_listener
+        .iter()
+        .filter(|(key, days)| prior.contains(*key) || days.len() > 1)
+        .count() as i64;
+    if kpis.unique_listeners > 0 {
+        kpis.returning_pct = kpis.returning_listeners as f64 * 100.0 / kpis.unique_listeners as f64;
+    }
+
+    let mut hour = from;
+    while hour < to {
+        let end = (hour + HOUR_SECS).min(to);
+        let at_start: Vec<&&ListenerSession> = counted
+            .iter()
+            .filter(|s| s.connected_at <= hour && s.last_seen_at > hour)
+            .collect();
+        let retained = at_start.iter().filter(|s| s.last_seen_at >= end).count() as i64;
+        let audience_at_start = at_start.len() as i64;
+        kpis.hourly.push(HourlyRetention {
+            hour_start: hour * 1000,
+            audience_at_start,
+            retained,
+            retention_pct: (audience_at_start > 0)
+                .then(|| retained as f64 * 100.0 / audience_at_start as f64),
+            sessions_started: counted
+                .iter()
+                .filter(|s| s.connected_at >= hour && s.connected_at < end)
+                .count() as i64,
+            listening_hours: counted
+                .iter()
+                .map(|s| s.overlap_secs(hour, end))
+                .sum::<i64>() as f64
+                / 3600.0,
+        });
+        hour = end;
+    }
+
+    kpis
+}
+
+/// Session KPIs for the local dates `start_date..=end_date`, for one encoder
+/// or (with `None`) all of them.
+pub async fn get_listener_kpis(
+    pool: &SqlitePool,
+    start_date: &str,
+    end_date: &str,
+    encoder_id: Option<i64>,
+) -> Result<ListenerKpis, sqlx::Error> {
+    let tz = chrono::Local;
+    // Unparseable dates fall back to the last 24 hours.
+    let (from, to) = local_date_range_utc(&tz, start_date, end_date).unwrap_or_else(|| {
+        let now = chrono::Utc::now().timestamp();
+        (now - 24 * HOUR_SECS, now)
+    });
+
+    let sessions = sqlx::query_as::<_, (String, Option<String>, i64, i64)>(
+        r#"
+        SELECT ip, user_agent, connected_at, last_seen_at
+        FROM listener_sessions
+        WHERE last_seen_at >= ? AND connected_at < ?
+          AND (? IS NULL OR encoder_id = ?)
+        "#,
+    )
+    .bind(from)
+    .bind(to)
+    .bind(encoder_id)
+    .bind(encoder_id)
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(
+        |(ip, user_agent, connected_at, last_seen_at)| ListenerSession {
+            ip,
+            user_agent,
+            connected_at,
+            last_seen_at,
+        },
+    )
+    .collect::<Vec<_>>();
+
+    let prior: HashSet<String> = sqlx::query_as::<_, (String, Option<String>)>(
+        r#"
+        SELECT DISTINCT ip, user_agent
+        FROM listener_sessions
+        WHERE last_seen_at >= ? AND last_seen_at < ?
+          AND last_seen_at - connected_at >= ?
+          AND (? IS NULL OR encoder_id = ?)
+        "#,
+    )
+    .bind(from - RETURNING_LOOKBACK_SECS)
+    .bind(from)
+    .bind(MIN_SESSION_SECS)
+    .bind(encoder_id)
+    .bind(encoder_id)
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .map(|(ip, ua)| format!("{ip}|{}", ua.as_deref().unwrap_or("")))
+    .collect();
+
+    Ok(compute_kpis(&sessions, from, to, &prior, |ts| {
+        use chrono::{Datelike, TimeZone};
+        tz.timestamp_opt(ts, 0)
+            .single()
+            .map(|dt| dt.date_naive().num_days_from_ce() as i64)
+            .unwrap_or(ts / 86_400)
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session(ip: &str, connected_at: i64, last_seen_at: i64) -> ListenerSession {
+        ListenerSession {
+            ip: ip.to_string(),
+            user_agent: Some("VLC".to_string()),
+            connected_at,
+            last_seen_at,
+        }
+    }
+
+    #[test]
+    fn computes_tlh_sessions_and_retention() {
+        let day = |ts: i64| ts / 86_400;
+        let sessions = vec![
+            // Whole first hour, half the second
+            session("10.0.0.1", 0, 5_400),
+            // Starts before the window: only the in-window part counts
+            session("10.0.0.2", -1_800, 1_800),
+            // Bounce
+            session("10.0.0.3", 100, 130),
+            // Same listener again the next day
+            session("10.0.0.2", 86_400, 90_000),
+        ];
+        let prior = HashSet::new();
+        let kpis = compute_kpis(&sessions, 0, 7_200, &prior, day);
+
+        assert_eq!(kpis.sessions, 2);
+        assert_eq!(kpis.bounced_sessions, 1);
+        assert!((kpis.total_listening_hours - 2.0).abs() < 1e-9);
+        assert_eq!(kpis.unique_listeners, 2);
+        assert_eq!(kpis.hourly.len(), 2);
+        assert_eq!(kpis.hourly[0].audience_at_start, 2);
+        assert_eq!(kpis.hourly[0].retained, 1);
+        assert_eq!(kpis.hourly[1].audience_at_start, 1);
+        assert_eq!(kpis.hourly[1].retained, 0);
+
+        let two_days = compute_kpis(&sessions, 0, 2 * 86_400, &prior, day);
+        assert_eq!(two_days.returning_listeners, 1);
+
+        let prior: HashSet<String> = ["10.0.0.1|VLC".to_string()].into_iter().collect();
+        let with_prior = compute_kpis(&sessions, 0, 7_200, &prior, day);
+        assert_eq!(with_prior.returning_listeners, 1);
+        assert!((with_prior.returning_pct - 50.0).abs() < 1e-9);
+    }
 }
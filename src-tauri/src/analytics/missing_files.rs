@@ -0,0 +1,352 @@
+/// Missing-file audit and relink
+///
+/// Walks the SAM songlist, translates each filename with the path rules and
+/// flags songs whose file is not on disk. For each missing song the
+/// configured music roots are searched for a file with the same name; a
+/// single candidate whose duration matches the song is relinked
+/// automatically, anything less certain is left for the operator.
+///
+/// Flagged songs are kept in `missing_files` and skipped by AutoDJ (queue
+/// and rotation) until they are relinked or a later scan finds them again.
+use serde::{Deserialize, Serialize};
+use sqlx::{Row, SqlitePool};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use symphonia::core::{
+    codecs::CODEC_TYPE_NULL, formats::FormatOptions, io::MediaSourceStream, meta::MetadataOptions,
+    probe::Hint,
+};
+
+use super::library_storage::LibraryEntry;
+
+const AUDIO_EXTENSIONS: &[&str] = &[
+    "mp3", "m4a", "aac", "mp4", "flac", "ogg", "oga", "opus", "wav", "aif", "aiff", "wma",
+];
+/// Candidates listed per missing song.
+const MAX_CANDIDATES: usize = 10;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MissingFileConfig {
+    /// Directories searched for relink candidates
+    pub music_roots: Vec<String>,
+    /// Allowed difference between the song and a candidate file
+    pub duration_tolerance_secs: u32,
+    /// Relink songs with exactly one matching candidate during a scan
+    pub auto_relink: bool,
+}
+
+impl Default for MissingFileConfig {
+    fn default() -> Self {
+        Self {
+            music_roots: Vec::new(),
+            duration_tolerance_secs: 2,
+            auto_relink: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RelinkCandidate {
+    pub path: String,
+    pub duration_secs: Option<u32>,
+    /// `None` when either duration is unknown
+    pub duration_match: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MissingFile {
+    pub song_id: i64,
+    pub artist: String,
+    pub title: String,
+    /// Path as translated from SAM
+    pub path: String,
+    pub duration_secs: u32,
+    pub detected_at: i64,
+    pub candidates: Vec<RelinkCandidate>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Relinked {
+    pub song_id: i64,
+    pub old_path: String,
+    pub new_path: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct IntegrityScanReport {
+    pub scanned: usize,
+    /// Missing after automatic relinking
+    pub missing: usize,
+    pub relinked: Vec<Relinked>,
+    pub started_at: i64,
+    pub finished_at: i64,
+}
+
+// ── Scanning ──────────────────────────────────────────────────────────────────
+
+fn is_audio(path: &Path) -> bool {
+    path.extension()
+        .map(|e| e.to_string_lossy().to_ascii_lowercase())
+        .is_some_and(|e| AUDIO_EXTENSIONS.contains(&e.as_str()))
+}
+
+/// Lower-case file name, treating `\` as a separator so SAM paths work too.
+fn file_name_key(path: &str) -> String {
+    path.rsplit(['/', '\\'])
+        .next()
+        .unwrap_or(path)
+        .to_lowercase()
+}
+
+/// Audio files under `roots`, by lower-case file name (blocking).
+pub fn index_roots(roots: &[String]) -> HashMap<String, Vec<PathBuf>> {
+    let mut index: HashMap<String, Vec<PathBuf>> = HashMap::new();
+    let mut stack: Vec<PathBuf> = roots
+        .iter()
+        .filter(|r| !r.trim().is_empty())
+        .map(PathBuf::from)
+        .collect();
+    while let Some(dir) = stack.pop() {
+        let Ok(read) = std::fs::read_dir(&dir) else {
+            log::debug!("Skipping unreadable directory {}", dir.display());
+            continue;
+        };
+        for entry in read.flatten() {
+            let path = entry.path();
+            let hidden = entry.file_name().to_string_lossy().starts_with('.');
+            match entry.file_type() {
+                Ok(t) if t.is_dir() && !hidden => stack.push(path),
+                Ok(t) if t.is_file() && is_audio(&path) => {
+                    let key = entry.file_name().to_string_lossy().to_lowercase();
+                    index.entry(key).or_default().push(path);
+                }
+                _ => {}
+            }
+        }
+    }
+    index
+}
+
+/// Container-reported duration in whole seconds, without decoding.
+pub fn probe_duration_secs(path: &Path) -> Option<u32> {
+    let file = std::fs::File::open(path).ok()?;
+    let mss = MediaSourceStream::new(Box::new(file), Default::default());
+    let mut hint = Hint::new();
+    if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
+        hint.with_extension(ext);
+    }
+    let probed = symphonia::default::get_probe()
+        .format(
+            &hint,
+            mss,
+            &FormatOptions::default(),
+            &MetadataOptions::default(),
+        )
+        .ok()?;
+    let params = &probed
+        .format
+        .tracks()
+        .iter()
+        .find(|t| t.codec_params.codec != CODEC_TYPE_NULL)?
+        .codec_params;
+    let frames = params.n_frames?;
+    let rate = params.sample_rate.filter(|r| *r > 0)?;
+    Some((frames / u64::from(rate)) as u32)
+}
+
+/// Same-named files for `entry`, duration matches first (blocking).
+pub fn candidates_for(
+    entry: &LibraryEntry,
+    index: &HashMap<String, Vec<PathBuf>>,
+    tolerance_secs: u32,
+) -> Vec<RelinkCandidate> {
+    let Some(paths) = index.get(&file_name_key(&entry.path)) else {
+        return Vec::new();
+    };
+    let mut candidates: Vec<RelinkCandidate> = paths
+        .iter()
+        .take(MAX_CANDIDATES)
+        .map(|p| {
+            let duration_secs = probe_duration_secs(p);
+            let duration_match = match (duration_secs, entry.duration_secs) {
+                (Some(found), expected) if expected > 0 => {
+                    Some(found.abs_diff(expected) <= tolerance_secs)
+                }
+                _ => None,
+            };
+            RelinkCandidate {
+                path: p.to_string_lossy().to_string(),
+                duration_secs,
+                duration_match,
+            }
+        })
+        .collect();
+    candidates.sort_by_key(|c| match c.duration_match {
+        Some(true) => 0,
+        None => 1,
+        Some(false) => 2,
+    });
+    candidates
+}
+
+/// The candidate to relink to without asking: the only one whose duration
+/// matches, or the only one at all when durations can't be compared.
+pub fn auto_pick(candidates: &[RelinkCandidate]) -> Option<&RelinkCandidate> {
+    let matching: Vec<_> = candidates
+        .iter()
+        .filter(|c| c.duration_match == Some(true))
+        .collect();
+    match (matching.as_slice(), candidates) {
+        ([only], _) => Some(*only),
+        ([], [only]) if only.duration_match.is_none() => Some(only),
+        _ => None,
+    }
+}
+
+/// Entries whose file is not on disk (blocking).
+pub fn find_missing(entries: Vec<LibraryEntry>) -> Vec<LibraryEntry> {
+    entries
+        .into_iter()
+        .filter(|e| !Path::new(&e.path).is_file())
+        .collect()
+}
+
+// ── DB helpers ────────────────────────────────────────────────────────────────
+
+pub async fn get_config(pool: &SqlitePool) -> Result<MissingFileConfig, sqlx::Error> {
+    let row: Option<String> =
+        sqlx::query_scalar("SELECT config_json FROM missing_file_config WHERE id = 1")
+            .fetch_optional(pool)
+            .await?;
+    Ok(row
+        .and_then(|j| serde_json::from_str(&j).ok())
+        .unwrap_or_default())
+}
+
+pub async fn save_config(pool: &SqlitePool, config: &MissingFileConfig) -> Result<(), sqlx::Error> {
+    let json = serde_json::to_string(config).unwrap_or_else(|_| "{}".to_string());
+    sqlx::query(
+        "INSERT INTO missing_file_config (id, config_json, updated_at) \
+         VALUES (1, ?, strftime('%s','now')) \
+         ON CONFLICT(id) DO UPDATE SET config_json = excluded.config_json, \
+         updated_at = excluded.updated_at",
+    )
+    .bind(json)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Replace the stored scan result.
+pub async fn replace_missing(
+    pool: &SqlitePool,
+    missing: &[MissingFile],
+) -> Result<(), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    sqlx::query("DELETE FROM missing_files")
+        .execute(&mut *tx)
+        .await?;
+    for m in missing {
+        sqlx::query(
+            "INSERT INTO missing_files \
+             (song_id, artist, title, path, duration_secs, detected_at, candidates_json) \
+             VALUES (?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(m.song_id)
+        .bind(&m.artist)
+        .bind(&m.title)
+        .bind(&m.path)
+        .bind(m.duration_secs as i64)
+        .bind(m.detected_at)
+        .bind(serde_json::to_string(&m.candidates).unwrap_or_else(|_| "[]".to_string()))
+        .execute(&mut *tx)
+        .await?;
+    }
+    tx.commit().await?;
+    Ok(())
+}
+
+pub async fn get_missing(pool: &SqlitePool) -> Result<Vec<MissingFile>, sqlx::Error> {
+    let rows = sqlx::query(
+        "SELECT song_id, artist, title, path, duration_secs, detected_at, candidates_json \
+         FROM missing_files ORDER BY artist, title",
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(rows
+        .into_iter()
+        .map(|r| MissingFile {
+            song_id: r.get("song_id"),
+            artist: r.get("artist"),
+            title: r.get("title"),
+            path: r.get("path"),
+            duration_secs: r.get::<i64, _>("duration_secs").max(0) as u32,
+            detected_at: r.get("detected_at"),
+            candidates: serde_json::from_str(&r.get::<String, _>("candidates_json"))
+                .unwrap_or_default(),
+        })
+        .collect())
+}
+
+pub async fn get_missing_song(
+    pool: &SqlitePool,
+    song_id: i64,
+) -> Result<Option<MissingFile>, sqlx::Error> {
+    Ok(get_missing(pool)
+        .await?
+        .into_iter()
+        .find(|m| m.song_id == song_id))
+}
+
+pub async fn clear_missing(pool: &SqlitePool, song_id: i64) -> Result<(), sqlx::Error> {
+    sqlx::query("DELETE FROM missing_files WHERE song_id = ?")
+        .bind(song_id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// Song ids AutoDJ must not pick.
+pub async fn missing_song_ids(pool: &SqlitePool) -> Result<HashSet<i64>, sqlx::Error> {
+    let ids: Vec<i64> = sqlx::query_scalar("SELECT song_id FROM missing_files")
+        .fetch_all(pool)
+        .await?;
+    Ok(ids.into_iter().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candidate(path: &str, duration_match: Option<bool>) -> RelinkCandidate {
+        RelinkCandidate {
+            path: path.to_string(),
+            duration_secs: None,
+            duration_match,
+        }
+    }
+
+    #[test]
+    fn auto_pick_requires_a_single_confident_match() {
+        let one_match = [candidate("/a", Some(false)), candidate("/b", Some(true))];
+        assert_eq!(auto_pick(&one_match).map(|c| c.path.as_str()), Some("/b"));
+
+        let two_matches = [candidate("/a", Some(true)), candidate("/b", Some(true))];
+        assert!(auto_pick(&two_matches).is_none());
+
+        assert_eq!(
+            auto_pick(&[candidate("/a", None)]).map(|c| c.path.as_str()),
+            Some("/a")
+        );
+        assert!(auto_pick(&[candidate("/a", Some(false))]).is_none());
+        assert!(auto_pick(&[candidate("/a", None), candidate("/b", None)]).is_none());
+    }
+
+    #[test]
+    fn file_name_key_handles_sam_paths() {
+        assert_eq!(file_name_key("C:\\Music\\Song.MP3"), "song.mp3");
+        assert_eq!(file_name_key("/Volumes/Music/song.mp3"), "song.mp3");
+    }
+}
@@ -1,8 +1,18 @@
+pub mod alerts;
+pub mod emit_metrics;
 pub mod event_logger;
+pub mod event_retention;
 pub mod health_monitor;
+pub mod library_storage;
 pub mod listener_stats;
+pub mod missing_files;
+pub mod play_log;
 pub mod play_stats;
+pub mod report_digest;
 pub mod reports;
+pub mod royalty;
+pub mod scrobbler;
+pub mod show_audience;
 
 pub use event_logger::{log_event, EventCategory, LogLevel};
 pub use health_monitor::HealthMonitor;
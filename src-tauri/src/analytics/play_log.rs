@@ -0,0 +1,372 @@
+use serde::{Deserialize, Serialize};
+use sqlx::{QueryBuilder, Sqlite, SqlitePool};
+
+use crate::audio::{deck::StopReason, engine::TrackCompletionEvent};
+
+/// Share of the track that must have aired for an early stop to still count as a full play.
+const FULL_PLAY_PCT: u64 = 90;
+/// Stops within this much of the end (the outro/crossfade zone) count as full plays.
+const OUTRO_GRACE_MS: u64 = 30_000;
+/// Anything shorter than this is a false start rather than a partial play.
+const FALSE_START_MS: u64 = 30_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PlayOutcome {
+    Full,
+    Partial,
+    Skip,
+}
+
+impl PlayOutcome {
+    pub fn as_str(&self) -> &str {
+        match self {
+            PlayOutcome::Full => "full",
+            PlayOutcome::Partial => "partial",
+            PlayOutcome::Skip => "skip",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PlayLogReason {
+    Completed,
+    ManualSkip,
+    Crossfade,
+    CrossfadeEarly,
+    Error,
+}
+
+impl PlayLogReason {
+    pub fn as_str(&self) -> &str {
+        match self {
+            PlayLogReason::Completed => "completed",
+            PlayLogReason::ManualSkip => "manual_skip",
+            PlayLogReason::Crossfade => "crossfade",
+            PlayLogReason::CrossfadeEarly => "crossfade_early",
+            PlayLogReason::Error => "error",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlayLogEntry {
+    pub id: i64,
+    /// Unix ms when the deck stopped
+    pub ended_at: i64,
+    pub song_id: i64,
+    pub queue_id: Option<i64>,
+    pub deck: String,
+    pub artist: Option<String>,
+    pub title: Option<String>,
+    pub played_ms: i64,
+    pub duration_ms: i64,
+    pub outcome: String,
+    pub reason: String,
+    pub from_rotation: bool,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct PlayLogFilter {
+    pub start_time: Option<i64>,
+    pub end_time: Option<i64>,
+    pub song_id: Option<i64>,
+    pub deck: Option<String>,
+    pub outcome: Option<String>,
+    pub reason: Option<String>,
+}
+
+/// Classify how much of a track aired and why it stopped.
+pub fn classify(
+    reason: StopReason,
+    played_ms: u64,
+    duration_ms: u64,
+) -> (PlayOutcome, PlayLogReason) {
+    let reached_end = duration_ms == 0
+        || played_ms.saturating_add(OUTRO_GRACE_MS) >= duration_ms
+        || played_ms.saturating_mul(100) >= duration_ms.saturating_mul(FULL_PLAY_PCT);
+    let reason = match reason {
+        StopReason::Ended => PlayLogReason::Completed,
+        StopReason::Skipped => PlayLogReason::ManualSkip,
+        StopReason::Crossfade if reached_end => PlayLogReason::Crossfade,
+        StopReason::Crossfade => PlayLogReason::CrossfadeEarly,
+        StopReason::Error => PlayLogReason::Error,
+    };
+    let outcome = if reached_end && reason != PlayLogReason::Error {
+        PlayOutcome::Full
+    } else if played_ms < FALSE_START_MS {
+        PlayOutcome::Skip
+    } else {
+        PlayOutcome::Partial
+    };
+    (outcome, reason)
+}
+
+/// Record one deck completion in the play log.
+pub async fn record_completion(
+    pool: &SqlitePool,
+    ev: &TrackCompletionEvent,
+    artist: Option<&str>,
+    title: Option<&str>,
+) -> Result<PlayOutcome, sqlx::Error> {
+    let (outcome, reason) = classify(ev.reason, ev.played_ms, ev.duration_ms);
+    let now_ms = chrono::Utc::now().timestamp_millis();
+
+    sqlx::query(
+        r#"
+        INSERT INTO play_log (
+            ended_at, song_id, queue_id, deck, artist, title,
+            played_ms, duration_ms, outcome, reason, from_rotation
+        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        "#,
+    )
+    .bind(now_ms)
+    .bind(ev.song_id)
+    .bind(ev.queue_id)
+    .bind(&ev.deck)
+    .bind(artist)
+    .bind(title)
+    .bind(ev.played_ms.min(i64::MAX as u64) as i64)
+    .bind(ev.duration_ms.min(i64::MAX as u64) as i64)
+    .bind(outcome.as_str())
+    .bind(reason.as_str())
+    .bind(ev.from_rotation)
+    .execute(pool)
+    .await?;
+
+    Ok(outcome)
+}
+
+/// Get play log entries (newest first) with filtering and pagination
+pub async fn get_play_log(
+    pool: &SqlitePool,
+    filter: &PlayLogFilter,
+    limit: i64,
+    offset: i64,
+) -> Result<(Vec<PlayLogEntry>, i64), sqlx::Error> {
+    let mut query_builder = QueryBuilder::<Sqlite>::new(
+        "SELECT id, ended_at, song_id, queue_id, deck, artist, title, played_ms, duration_ms, outcome, reason, from_rotation FROM play_log WHERE 1=1",
+    );
+    append_filters(&mut query_builder, filter);
+    query_builder.push(" ORDER BY ended_at DESC, id DESC LIMIT ");
+    query_builder.push_bind(limit.max(1));
+    query_builder.push(" OFFSET ");
+    query_builder.push_bind(offset.max(0));
+
+    let rows = query_builder
+        .build_query_as::<(
+            i64,
+            i64,
+            i64,
+            Option<i64>,
+            String,
+            Option<String>,
+            Option<String>,
+            i64,
+            i64,
+            String,
+            String,
+            bool,
+        )>()
+        .fetch_all(pool)
+        .await?;
+
+    let entries = rows
+        .into_iter()
+        .map(
+            |(
+                id,
+                ended_at,
+                song_id,
+                queue_id,
+                deck,
+                artist,
+                title,
+                played_ms,
+                duration_ms,
+                outcome,
+                reason,
+                from_rotation,
+            )| PlayLogEntry {
+                id,
+                ended_at,
+                song_id,
+                queue_id,
+                deck,
+                artist,
+                title,
+                played_ms,
+                duration_ms,
+                outcome,
+                reason,
+                from_rotation,
+            },
+        )
+        .collect();
+
+    let mut count_query_builder =
+        QueryBuilder::<Sqlite>::new("SELECT COUNT(*) FROM play_log WHERE 1=1");
+    append_filters(&mut count_query_builder, filter);
+    let total: i64 = count_query_builder
+        .build_query_scalar()
+        .fetch_one(pool)
+        .await?;
+
+    Ok((entries, total))
+}
+
+/// Songs that were on air at some point between `from_ms` and `to_ms`, oldest first.
+pub async fn get_plays_between(
+    pool: &SqlitePool,
+    from_ms: i64,
+    to_ms: i64,
+) -> Result<Vec<crate::stream::show_export::PlayedSpan>, sqlx::Error> {
+    let rows = sqlx::query_as::<_, (i64, i64, Option<String>, Option<String>)>(
+        r#"
+        SELECT ended_at - played_ms AS started_at, ended_at, artist, title
+        FROM play_log
+        WHERE ended_at > ? AND ended_at - played_ms < ?
+        ORDER BY started_at
+        "#,
+    )
+    .bind(from_ms)
+    .bind(to_ms)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows
+        .into_iter()
+        .map(
+            |(started_at, ended_at, artist, title)| crate::stream::show_export::PlayedSpan {
+                started_at,
+                ended_at,
+                artist,
+                title,
+            },
+        )
+        .collect())
+}
+
+fn append_filters(query_builder: &mut QueryBuilder<'_, Sqlite>, filter: &PlayLogFilter) {
+    if let Some(start_time) = filter.start_time {
+        query_builder.push(" AND ended_at >= ");
+        query_builder.push_bind(start_time);
+    }
+
+    if let Some(end_time) = filter.end_time {
+        query_builder.push(" AND ended_at <= ");
+        query_builder.push_bind(end_time);
+    }
+
+    if let Some(song_id) = filter.song_id {
+        query_builder.push(" AND song_id = ");
+        query_builder.push_bind(song_id);
+    }
+
+    for (column, value) in [
+        ("deck", &filter.deck),
+        ("outcome", &filter.outcome),
+        ("reason", &filter.reason),
+    ] {
+        if let Some(value) = value.as_deref().filter(|v| !v.trim().is_empty()) {
+            query_builder.push(format!(" AND {column} = "));
+            query_builder.push_bind(value.trim().to_string());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classifies_full_partial_and_false_starts() {
+        let track = 240_000;
+        assert_eq!(
+            classify(StopReason::Ended, track, track),
+            (PlayOutcome::Full, PlayLogReason::Completed)
+        );
+        assert_eq!(
+            classify(StopReason::Crossfade, track - 8_000, track),
+            (PlayOutcome::Full, PlayLogReason::Crossfade)
+        );
+        assert_eq!(
+            classify(StopReason::Crossfade, 120_000, track),
+            (PlayOutcome::Partial, PlayLogReason::CrossfadeEarly)
+        );
+        assert_eq!(
+            classify(StopReason::Skipped, 10_000, track),
+            (PlayOutcome::Skip, PlayLogReason::ManualSkip)
+        );
+        assert_eq!(
+            classify(StopReason::Error, 45_000, track),
+            (PlayOutcome::Partial, PlayLogReason::Error)
+        );
+        assert_eq!(
+            classify(StopReason::Error, track, track),
+            (PlayOutcome::Partial, PlayLogReason::Error)
+        );
+    }
+
+    #[tokio::test]
+    async fn get_play_log_filters_by_outcome() {
+        let pool = sqlx::sqlite::SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .expect("in-memory sqlite pool");
+        sqlx::query(
+            r#"
+            CREATE TABLE play_log (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                ended_at INTEGER NOT NULL,
+                song_id INTEGER NOT NULL,
+                queue_id INTEGER,
+                deck TEXT NOT NULL,
+                artist TEXT,
+                title TEXT,
+                played_ms INTEGER NOT NULL,
+                duration_ms INTEGER NOT NULL,
+                outcome TEXT NOT NULL,
+                reason TEXT NOT NULL,
+                from_rotation INTEGER NOT NULL DEFAULT 0
+            )
+            "#,
+        )
+        .execute(&pool)
+        .await
+        .expect("create play_log table");
+
+        for (song_id, played_ms, reason) in [
+            (1, 200_000, StopReason::Ended),
+            (2, 9_000, StopReason::Skipped),
+            (3, 12_000, StopReason::Skipped),
+        ] {
+            let ev = TrackCompletionEvent {
+                deck: "deck_a".to_string(),
+                song_id,
+                queue_id: None,
+                from_rotation: true,
+                played_ms,
+                duration_ms: 200_000,
+                reason,
+            };
+            record_completion(&pool, &ev, Some("Artist"), Some("Title"))
+                .await
+                .expect("record completion");
+        }
+
+        let filter = PlayLogFilter {
+            outcome: Some("skip".to_string()),
+            ..Default::default()
+        };
+        let (rows, total) = get_play_log(&pool, &filter, 1, 0)
+            .await
+            .expect("filtered play log");
+
+        assert_eq!(total, 2);
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].reason, "manual_skip");
+        assert!(rows[0].from_rotation);
+    }
+}
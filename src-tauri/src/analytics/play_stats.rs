@@ -1,4 +1,4 @@
-use chrono::Timelike;
+use chrono::{DateTime, Duration, NaiveDate, Offset, TimeZone, Timelike, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::SqlitePool;
 
@@ -17,6 +17,67 @@ pub struct HeatmapData {
     pub hour: i32,
     pub play_count: i64,
     pub unique_songs: i64,
+    /// Unix seconds at the start of this local hour; distinguishes a repeated DST hour
+    pub hour_start_utc: i64,
+    pub utc_offset_min: i32,
+}
+
+/// One station-local hour, anchored on its UTC start.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StationHour {
+    pub hour_start_utc: i64,
+    pub date: String,
+    pub hour: i32,
+    pub utc_offset_min: i32,
+}
+
+/// The station-local hour containing `at`, as seen in `tz`.
+pub fn station_hour<Tz: TimeZone>(tz: &Tz, at: DateTime<Utc>) -> StationHour {
+    // Offsets can be half/quarter hours, so the bucket starts at the local hour.
+    let local = at.with_timezone(tz);
+    let hour_start = at.timestamp() - i64::from(local.minute() * 60 + local.second());
+    StationHour {
+        hour_start_utc: hour_start,
+        date: local.date_naive().format("%Y-%m-%d").to_string(),
+        hour: local.hour() as i32,
+        utc_offset_min: local.offset().fix().local_minus_utc() / 60,
+    }
+}
+
+/// Resolve a naive local `date`/`hour` in `tz`. A repeated (fall-back) hour
+/// resolves to its first occurrence; a skipped (spring-forward) hour resolves
+/// to the hour that replaced it.
+pub fn station_hour_from_local<Tz: TimeZone>(
+    tz: &Tz,
+    date: &str,
+    hour: i32,
+) -> Option<StationHour> {
+    let naive = NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .ok()?
+        .and_hms_opt(u32::try_from(hour).ok()?, 0, 0)?;
+    let utc = match tz.from_local_datetime(&naive).earliest() {
+        Some(dt) => dt.with_timezone(&Utc),
+        None => {
+            tz.from_local_datetime(&(naive - Duration::hours(1)))
+                .earliest()?
+                .with_timezone(&Utc)
+                + Duration::hours(1)
+        }
+    };
+    Some(station_hour(tz, utc))
+}
+
+/// UTC bounds `[start, end)` covering the local dates `start_date..=end_date`.
+pub(crate) fn local_date_range_utc<Tz: TimeZone>(
+    tz: &Tz,
+    start_date: &str,
+    end_date: &str,
+) -> Option<(i64, i64)> {
+    let start = station_hour_from_local(tz, start_date, 0)?.hour_start_utc;
+    let end_day = NaiveDate::parse_from_str(end_date, "%Y-%m-%d").ok()? + Duration::days(1);
+    let end =
+        station_hour_from_local(tz, &end_day.format("%Y-%m-%d").to_string(), 0)?.hour_start_utc;
+    Some((start, end))
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -65,31 +126,48 @@ pub async fn get_top_songs(
 }
 
 /// Get hourly play heatmap data
+///
+/// Rows are selected and labelled in the station's current timezone from their
+/// UTC hour, so a DST change yields a 23- or 25-hour day (the repeated hour
+/// appears twice with different offsets) and a timezone change re-projects
+/// history instead of shifting it.
 pub async fn get_hourly_heatmap(
     pool: &SqlitePool,
     start_date: &str,
     end_date: &str,
 ) -> Result<Vec<HeatmapData>, sqlx::Error> {
-    let rows = sqlx::query_as::<_, (String, i32, i64, i64)>(
+    let tz = chrono::Local;
+    let Some((start_utc, end_utc)) = local_date_range_utc(&tz, start_date, end_date) else {
+        return Err(sqlx::Error::Protocol(format!(
+            "Invalid heatmap date range: {start_date}..{end_date}"
+        )));
+    };
+    let rows = sqlx::query_as::<_, (i64, i64, i64)>(
         r#"
-        SELECT date, hour, play_count, unique_songs
+        SELECT hour_start_utc, play_count, unique_songs
         FROM hourly_play_counts
-        WHERE date >= ? AND date <= ?
-        ORDER BY date ASC, hour ASC
+        WHERE hour_start_utc >= ? AND hour_start_utc < ?
+        ORDER BY hour_start_utc ASC
         "#,
     )
-    .bind(start_date)
-    .bind(end_date)
+    .bind(start_utc)
+    .bind(end_utc)
     .fetch_all(pool)
     .await?;
 
     Ok(rows
         .into_iter()
-        .map(|(date, hour, play_count, unique_songs)| HeatmapData {
-            date,
-            hour,
-            play_count,
-            unique_songs,
+        .filter_map(|(hour_start_utc, play_count, unique_songs)| {
+            let at = Utc.timestamp_opt(hour_start_utc, 0).single()?;
+            let slot = station_hour(&tz, at);
+            Some(HeatmapData {
+                date: slot.date,
+                hour: slot.hour,
+                play_count,
+                unique_songs,
+                hour_start_utc,
+                utc_offset_min: slot.utc_offset_min,
+            })
         })
         .collect())
 }
@@ -117,22 +195,60 @@ pub async fn refresh_play_stats_cache(
 
 /// Update hourly play counts (called on each track play)
 pub async fn update_hourly_play_count(pool: &SqlitePool, song_id: i64) -> Result<(), sqlx::Error> {
-    let now = chrono::Local::now();
-    let date = now.format("%Y-%m-%d").to_string();
-    let hour = now.hour() as i32;
+    let slot = station_hour(&chrono::Local, Utc::now());
 
     sqlx::query(
         r#"
-        INSERT INTO hourly_play_counts (date, hour, play_count, unique_songs)
-        VALUES (?, ?, 1, 1)
-        ON CONFLICT(date, hour) DO UPDATE SET
+        INSERT INTO hourly_play_counts
+            (hour_start_utc, date, hour, utc_offset_min, play_count, unique_songs)
+        VALUES (?, ?, ?, ?, 1, 1)
+        ON CONFLICT(hour_start_utc) DO UPDATE SET
             play_count = play_count + 1
         "#,
     )
-    .bind(date)
-    .bind(hour)
+    .bind(slot.hour_start_utc)
+    .bind(slot.date)
+    .bind(slot.hour)
+    .bind(slot.utc_offset_min)
     .execute(pool)
     .await?;
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::FixedOffset;
+
+    #[test]
+    fn repeated_dst_hour_keeps_distinct_utc_keys() {
+        // 01:00 local occurs twice at a fall-back: once at UTC-4, once at UTC-5.
+        let edt = FixedOffset::west_opt(4 * 3600).unwrap();
+        let est = FixedOffset::west_opt(5 * 3600).unwrap();
+        let first = station_hour(&edt, Utc.with_ymd_and_hms(2025, 11, 2, 5, 30, 0).unwrap());
+        let second = station_hour(&est, Utc.with_ymd_and_hms(2025, 11, 2, 6, 10, 0).unwrap());
+
+        assert_eq!((first.date.as_str(), first.hour), ("2025-11-02", 1));
+        assert_eq!((second.date.as_str(), second.hour), ("2025-11-02", 1));
+        assert_ne!(first.hour_start_utc, second.hour_start_utc);
+        assert_eq!(first.utc_offset_min, -240);
+        assert_eq!(second.utc_offset_min, -300);
+    }
+
+    #[test]
+    fn local_hour_round_trips_through_utc() {
+        let ist = FixedOffset::east_opt(5 * 3600 + 1800).unwrap();
+        let slot = station_hour_from_local(&ist, "2025-03-09", 2).unwrap();
+        assert_eq!(slot.hour, 2);
+        assert_eq!(slot.date, "2025-03-09");
+        assert_eq!(slot.utc_offset_min, 330);
+        assert_eq!(
+            slot.hour_start_utc,
+            Utc.with_ymd_and_hms(2025, 3, 8, 20, 30, 0)
+                .unwrap()
+                .timestamp()
+        );
+        assert!(station_hour_from_local(&ist, "not-a-date", 2).is_none());
+    }
+}
@@ -0,0 +1,436 @@
+/// Scheduled report digest by email
+///
+/// Once a day or once a week, at a local hour the operator picks, the
+/// station's numbers for the period just ended — top songs, listener peak
+/// and average, stream uptime and request totals — are assembled from the
+/// report generator and mailed as plain text. The config (SMTP server
+/// included) is a JSON file in the app data folder; it also remembers which
+/// scheduled digest went out last, so a restart neither repeats one nor
+/// skips one that fell due while the app was closed.
+use std::time::Duration;
+
+use chrono::{Datelike, Local, NaiveDate, NaiveDateTime, Timelike};
+use serde::{Deserialize, Serialize};
+use sqlx::{MySqlPool, SqlitePool};
+use tauri::{AppHandle, Manager};
+
+use super::alerts::{self, SmtpSettings};
+use super::event_logger::{log_event, EventCategory, LogLevel};
+use super::play_stats::local_date_range_utc;
+use super::reports::{self, ReportData, ReportType};
+use crate::state::AppState;
+
+const CONFIG_FILE: &str = "report_digest.json";
+const TICK: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DigestFrequency {
+    /// Covers the previous day
+    #[default]
+    Daily,
+    /// Covers the seven days before the send day
+    Weekly,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DigestConfig {
+    pub enabled: bool,
+    pub frequency: DigestFrequency,
+    /// Local hour the digest goes out (0–23)
+    pub send_hour: u32,
+    /// Weekly send day, 0 = Monday … 6 = Sunday
+    pub weekday: u32,
+    /// Songs listed in the top-songs section
+    pub top_songs: u32,
+    pub smtp: SmtpSettings,
+    pub to: Vec<String>,
+    /// Send day (`YYYY-MM-DD`) of the last scheduled digest; kept by the scheduler
+    pub last_sent: Option<String>,
+}
+
+impl Default for DigestConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            frequency: DigestFrequency::Daily,
+            send_hour: 7,
+            weekday: 0,
+            top_songs: 10,
+            smtp: SmtpSettings::default(),
+            to: Vec::new(),
+            last_sent: None,
+        }
+    }
+}
+
+fn config_path() -> std::path::PathBuf {
+    std::path::PathBuf::from(crate::compute_app_data_dir()).join(CONFIG_FILE)
+}
+
+pub fn load_config() -> DigestConfig {
+    std::fs::read(config_path())
+        .ok()
+        .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+        .unwrap_or_default()
+}
+
+fn write_config(config: &DigestConfig) -> Result<(), String> {
+    let json = serde_json::to_vec_pretty(config).map_err(|e| e.to_string())?;
+    std::fs::write(config_path(), json).map_err(|e| format!("Cannot save digest config: {e}"))
+}
+
+fn validate(config: &DigestConfig) -> Result<(), String> {
+    if config.send_hour > 23 {
+        return Err("Send hour must be 0–23".to_string());
+    }
+    if config.weekday > 6 {
+        return Err("Weekday must be 0 (Monday) to 6 (Sunday)".to_string());
+    }
+    if config.enabled {
+        if config.smtp.smtp_host.trim().is_empty() || config.smtp.from.trim().is_empty() {
+            return Err("The digest needs an SMTP server and a sender address".to_string());
+        }
+        if config.to.iter().all(|a| a.trim().is_empty()) {
+            return Err("The digest needs at least one recipient".to_string());
+        }
+    }
+    Ok(())
+}
+
+/// The saved config as shown to the operator, without the SMTP password.
+pub fn load_config_redacted() -> DigestConfig {
+    let mut config = load_config();
+    config.smtp.password.clear();
+    config
+}
+
+/// `config` with a blank SMTP password filled from the saved config, as
+/// long as it still names the same server and login.
+fn with_saved_password(mut config: DigestConfig, saved: &DigestConfig) -> DigestConfig {
+    if config.smtp.password.is_empty()
+        && config.smtp.smtp_host == saved.smtp.smtp_host
+        && config.smtp.username == saved.smtp.username
+    {
+        config.smtp.password = saved.smtp.password.clone();
+    }
+    config
+}
+
+/// Save the operator's settings; the scheduler's `last_sent` is kept, and so
+/// is the password when it is left blank.
+pub fn save_config(config: DigestConfig) -> Result<(), String> {
+    validate(&config)?;
+    let saved = load_config();
+    write_config(&DigestConfig {
+        last_sent: saved.last_sent.clone(),
+        ..with_saved_password(config, &saved)
+    })
+}
+
+// ── Schedule ──────────────────────────────────────────────────────────────────
+
+/// The most recent send day at or before `now`.
+fn scheduled_day(config: &DigestConfig, now: NaiveDateTime) -> NaiveDate {
+    let today = now.date();
+    let mut day = if now.hour() >= config.send_hour {
+        today
+    } else {
+        today.pred_opt().unwrap_or(today)
+    };
+    if config.frequency == DigestFrequency::Weekly {
+        while day.weekday().num_days_from_monday() != config.weekday {
+            day = day.pred_opt().unwrap_or(day);
+        }
+    }
+    day
+}
+
+/// Local dates (inclusive) reported on by the digest sent on `day`.
+fn covered_dates(frequency: DigestFrequency, day: NaiveDate) -> (NaiveDate, NaiveDate) {
+    let end = day.pred_opt().unwrap_or(day);
+    let start = match frequency {
+        DigestFrequency::Daily => end,
+        DigestFrequency::Weekly => day - chrono::Duration::days(7),
+    };
+    (start, end)
+}
+
+// ── Content ───────────────────────────────────────────────────────────────────
+
+/// Most-aired songs between two local dates, from the play log.
+async fn top_songs(
+    pool: &SqlitePool,
+    start: &str,
+    end: &str,
+    limit: u32,
+) -> Result<Vec<(String, i64)>, sqlx::Error> {
+    let Some((start_utc, end_utc)) = local_date_range_utc(&Local, start, end) else {
+        return Ok(Vec::new());
+    };
+    let rows = sqlx::query_as::<_, (Option<String>, Option<String>, i64)>(
+        r#"
+        SELECT MAX(artist), MAX(title), COUNT(*) AS plays
+        FROM play_log
+        WHERE ended_at >= ? AND ended_at < ? AND outcome != 'skip'
+        GROUP BY song_id
+        ORDER BY plays DESC, MAX(ended_at) DESC
+        LIMIT ?
+        "#,
+    )
+    .bind(start_utc * 1000)
+    .bind(end_utc * 1000)
+    .bind(i64::from(limit.max(1)))
+    .fetch_all(pool)
+    .await?;
+    Ok(rows
+        .into_iter()
+        .map(|(artist, title, plays)| {
+            let title = title.unwrap_or_else(|| "Unknown Title".to_string());
+            let label = match artist.filter(|a| !a.is_empty()) {
+                Some(artist) => format!("{artist} - {title}"),
+                None => title,
+            };
+            (label, plays)
+        })
+        .collect())
+}
+
+fn section_value<'a>(report: &'a ReportData, key: &str) -> Option<&'a serde_json::Value> {
+    report.sections.first().and_then(|s| s.data.get(key))
+}
+
+/// Subject and plain-text body for the period `start..=end`.
+async fn compose(
+    pool: &SqlitePool,
+    sam_pool: Option<&MySqlPool>,
+    config: &DigestConfig,
+    start: NaiveDate,
+    end: NaiveDate,
+) -> Result<(String, String), sqlx::Error> {
+    let start_date = start.format("%Y-%m-%d").to_string();
+    let end_date = end.format("%Y-%m-%d").to_string();
+    // Listener and uptime reports count back from now, which is the period
+    // just ended as long as the digest goes out on its send day.
+    let period_days = (end - start).num_days() as i32 + 1;
+
+    let songs = top_songs(pool, &start_date, &end_date, config.top_songs).await?;
+    let listeners =
+        reports::generate_report(pool, sam_pool, ReportType::ListenerTrend { period_days }).await?;
+    let uptime =
+        reports::generate_report(pool, sam_pool, ReportType::StreamUptime { period_days }).await?;
+    let requests = reports::generate_report(
+        pool,
+        sam_pool,
+        ReportType::RequestLog {
+            start_date: start_date.clone(),
+            end_date: end_date.clone(),
+        },
+    )
+    .await?;
+
+    let (kind, period) = match config.frequency {
+        DigestFrequency::Daily => ("Daily", start_date.clone()),
+        DigestFrequency::Weekly => ("Weekly", format!("{start_date} to {end_date}")),
+    };
+    let subject = format!("[DesiZone] {kind} digest — {period}");
+
+    let mut body = format!("DesiZone {} digest for {period}\n", kind.to_lowercase());
+
+    body.push_str("\nTOP SONGS\n");
+    if songs.is_empty() {
+        body.push_str("  No plays logged\n");
+    }
+    for (i, (label, plays)) in songs.iter().enumerate() {
+        let unit = if *plays == 1 { "play" } else { "plays" };
+        body.push_str(&format!("  {:>2}. {label} ({plays} {unit})\n", i + 1));
+    }
+
+    let average = section_value(&listeners, "average_listeners")
+        .and_then(|v| v.as_f64())
+        .unwrap_or(0.0);
+    body.push_str(&format!(
+        "\nLISTENERS\n  Peak: {}\n  Average: {average:.1}\n",
+        listeners.summary.total_listeners.unwrap_or(0),
+    ));
+
+    let samples = section_value(&uptime, "sample_count")
+        .and_then(|v| v.as_i64())
+        .unwrap_or(0);
+    let uptime_pct = section_value(&uptime, "uptime_pct")
+        .and_then(|v| v.as_f64())
+        .unwrap_or(0.0);
+    body.push_str("\nENCODER UPTIME\n");
+    if samples == 0 {
+        body.push_str("  No health samples recorded\n");
+    } else {
+        body.push_str(&format!(
+            "  Streaming {uptime_pct:.1}% of the time ({samples} health samples)\n"
+        ));
+    }
+
+    body.push_str(&format!(
+        "\nREQUESTS\n  Received: {}\n  Played: {}\n",
+        requests.summary.total_plays.unwrap_or(0),
+        requests.summary.total_listeners.unwrap_or(0),
+    ));
+    if let Some(top) = &requests.summary.top_song {
+        body.push_str(&format!("  Most requested: {top}\n"));
+    }
+
+    Ok((subject, body))
+}
+
+/// Build the digest due on `day` and mail it.
+async fn send_for_day(
+    state: &AppState,
+    config: &DigestConfig,
+    day: NaiveDate,
+) -> Result<(), String> {
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or("Local database unavailable")?;
+    let sam_pool = state.sam_db.read().await.clone();
+    let (start, end) = covered_dates(config.frequency, day);
+    let (subject, body) = compose(pool, sam_pool.as_ref(), config, start, end)
+        .await
+        .map_err(|e| format!("Digest report failed: {e}"))?;
+    alerts::send_email(&config.smtp, &config.to, subject, body).await
+}
+
+/// Send the most recent digest now with `config` (saved or not), so the
+/// operator can check the SMTP settings and the content. A blank password
+/// means the saved one, as in [`save_config`].
+pub async fn send_test(state: &AppState, config: &DigestConfig) -> Result<(), String> {
+    let config = with_saved_password(config.clone(), &load_config());
+    validate(&DigestConfig {
+        enabled: true,
+        ..config.clone()
+    })?;
+    let day = scheduled_day(&config, Local::now().naive_local());
+    send_for_day(state, &config, day).await
+}
+
+/// Mail each digest as it falls due.
+pub fn start(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(TICK).await;
+            let mut config = load_config();
+            if !config.enabled {
+                continue;
+            }
+            let day = scheduled_day(&config, Local::now().naive_local());
+            let key = day.format("%Y-%m-%d").to_string();
+            if config.last_sent.as_deref() == Some(key.as_str()) {
+                continue;
+            }
+
+            let state = app.state::<AppState>();
+            let result = send_for_day(&state, &config, day).await;
+            // Failures are not retried every minute; the next send day tries again.
+            config.last_sent = Some(key.clone());
+            if let Err(e) = write_config(&config) {
+                log::warn!("{e}");
+            }
+            let Some(pool) = state.local_db.clone() else {
+                continue;
+            };
+            let (level, event, message) = match result {
+                Ok(()) => (
+                    LogLevel::Info,
+                    "report_digest_sent",
+                    format!("Report digest for {key} sent"),
+                ),
+                Err(e) => (
+                    LogLevel::Error,
+                    "report_digest_failed",
+                    format!("Report digest for {key} failed: {e}"),
+                ),
+            };
+            let _ = log_event(
+                &pool,
+                level,
+                EventCategory::System,
+                event,
+                &message,
+                Some(serde_json::json!({ "send_day": key })),
+                None,
+                None,
+                None,
+            )
+            .await;
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(date: &str, hour: u32) -> NaiveDateTime {
+        NaiveDate::parse_from_str(date, "%Y-%m-%d")
+            .unwrap()
+            .and_hms_opt(hour, 30, 0)
+            .unwrap()
+    }
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn schedules_daily_and_weekly_digests() {
+        let daily = DigestConfig::default();
+        assert_eq!(
+            scheduled_day(&daily, at("2026-10-14", 6)),
+            date("2026-10-13")
+        );
+        assert_eq!(
+            scheduled_day(&daily, at("2026-10-14", 7)),
+            date("2026-10-14")
+        );
+        assert_eq!(
+            covered_dates(DigestFrequency::Daily, date("2026-10-14")),
+            (date("2026-10-13"), date("2026-10-13"))
+        );
+
+        // Mondays at 07:00; Wednesday 2026-10-14 falls back to Monday the 12th.
+        let weekly = DigestConfig {
+            frequency: DigestFrequency::Weekly,
+            ..DigestConfig::default()
+        };
+        assert_eq!(
+            scheduled_day(&weekly, at("2026-10-14", 12)),
+            date("2026-10-12")
+        );
+        assert_eq!(
+            scheduled_day(&weekly, at("2026-10-12", 6)),
+            date("2026-10-05")
+        );
+        assert_eq!(
+            covered_dates(DigestFrequency::Weekly, date("2026-10-12")),
+            (date("2026-10-05"), date("2026-10-11"))
+        );
+    }
+
+    #[test]
+    fn blank_password_only_reuses_the_saved_one_for_the_same_login() {
+        let mut saved = DigestConfig::default();
+        saved.smtp.smtp_host = "mail.example.com".to_string();
+        saved.smtp.username = "radio".to_string();
+        saved.smtp.password = "secret".to_string();
+
+        let mut edited = saved.clone();
+        edited.smtp.password.clear();
+        assert_eq!(
+            with_saved_password(edited.clone(), &saved).smtp.password,
+            "secret"
+        );
+
+        edited.smtp.smtp_host = "mail.elsewhere.net".to_string();
+        assert_eq!(with_saved_password(edited, &saved).smtp.password, "");
+    }
+}
@@ -1,10 +1,17 @@
-use chrono::{NaiveDate, Utc};
+use chrono::{Local, NaiveDate, TimeZone, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::{MySqlPool, Row, SqlitePool};
+use std::collections::BTreeMap;
 use std::fs::OpenOptions;
 use std::io::Write;
 use std::path::PathBuf;
 
+use super::listener_stats;
+use super::play_stats::local_date_range_utc;
+use super::royalty::{self, Delimiter, RoyaltyPeriod, RoyaltyReportRequest};
+use super::show_audience;
+use crate::scheduler::traffic::{self, SpotLogEntry, SpotStatus};
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type")]
 pub enum ReportType {
@@ -25,6 +32,44 @@ pub enum ReportType {
     StreamUptime {
         period_days: i32,
     },
+    TrafficAffidavit {
+        start_date: String,
+        end_date: String,
+        campaign_id: Option<i64>,
+    },
+    ShowAudience {
+        start_date: String,
+        end_date: String,
+        show_id: Option<i64>,
+        /// Weeks before the range used as the growth baseline
+        #[serde(default = "default_compare_weeks")]
+        compare_weeks: u32,
+    },
+    /// TLH, session length and returning listeners
+    ListeningHours {
+        start_date: String,
+        end_date: String,
+        /// All encoders when omitted
+        #[serde(default)]
+        encoder_id: Option<i64>,
+    },
+    /// Hour-by-hour audience retention
+    AudienceRetention {
+        start_date: String,
+        end_date: String,
+        #[serde(default)]
+        encoder_id: Option<i64>,
+    },
+    /// Per-recording spins and listener performances for royalty filing
+    RoyaltySpins {
+        period: RoyaltyPeriod,
+        #[serde(default)]
+        song_types: Vec<String>,
+    },
+}
+
+fn default_compare_weeks() -> u32 {
+    4
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -75,6 +120,48 @@ pub async fn generate_report(
         ReportType::StreamUptime { period_days } => {
             generate_stream_uptime_report(pool, now_ms, period_days).await
         }
+        ReportType::TrafficAffidavit {
+            start_date,
+            end_date,
+            campaign_id,
+        } => {
+            generate_traffic_affidavit_report(pool, now_ms, &start_date, &end_date, campaign_id)
+                .await
+        }
+        ReportType::ShowAudience {
+            start_date,
+            end_date,
+            show_id,
+            compare_weeks,
+        } => {
+            generate_show_audience_report(
+                pool,
+                now_ms,
+                &start_date,
+                &end_date,
+                show_id,
+                compare_weeks,
+            )
+            .await
+        }
+        ReportType::ListeningHours {
+            start_date,
+            end_date,
+            encoder_id,
+        } => {
+            generate_listening_hours_report(pool, now_ms, &start_date, &end_date, encoder_id).await
+        }
+        ReportType::AudienceRetention {
+            start_date,
+            end_date,
+            encoder_id,
+        } => {
+            generate_audience_retention_report(pool, now_ms, &start_date, &end_date, encoder_id)
+                .await
+        }
+        ReportType::RoyaltySpins { period, song_types } => {
+            generate_royalty_spins_report(pool, sam_pool, now_ms, &period, &song_types).await
+        }
     }
 }
 
@@ -115,7 +202,7 @@ async fn generate_daily_broadcast_report(
     };
 
     let hourly_rows = sqlx::query_as::<_, (i32, i64, i64)>(
-        "SELECT hour, play_count, unique_songs FROM hourly_play_counts WHERE date = ? ORDER BY hour ASC",
+        "SELECT hour, play_count, unique_songs FROM hourly_play_counts WHERE date = ? ORDER BY hour_start_utc ASC",
     )
     .bind(date)
     .fetch_all(pool)
@@ -205,9 +292,9 @@ async fn generate_listener_trend_report(
     let cutoff_ms = now_ms - (period_days.max(1) as i64 * 24 * 60 * 60 * 1000);
 
     let summary_row = sqlx::query(
-        "SELECT COALESCE(MAX(listener_count), 0) AS peak, COALESCE(AVG(listener_count), 0.0) AS avg_count FROM listener_snapshots WHERE timestamp >= ?",
+        "SELECT COALESCE(MAX(current_listeners), 0) AS peak, COALESCE(AVG(current_listeners), 0.0) AS avg_count FROM listener_snapshots WHERE snapshot_at >= ?",
     )
-    .bind(cutoff_ms)
+    .bind(cutoff_ms / 1000)
     .fetch_one(pool)
     .await?;
 
@@ -215,9 +302,9 @@ async fn generate_listener_trend_report(
     let average: f64 = summary_row.get("avg_count");
 
     let trend_rows = sqlx::query_as::<_, (i64, i64)>(
-        "SELECT timestamp, listener_count FROM listener_snapshots WHERE timestamp >= ? ORDER BY timestamp ASC LIMIT 500",
+        "SELECT snapshot_at * 1000, current_listeners FROM listener_snapshots WHERE snapshot_at >= ? ORDER BY snapshot_at ASC LIMIT 500",
     )
-    .bind(cutoff_ms)
+    .bind(cutoff_ms / 1000)
     .fetch_all(pool)
     .await?;
 
@@ -389,6 +476,436 @@ async fn resolve_song_label(sam_pool: Option<&MySqlPool>, song_id: i64, play_cou
     format!("Song #{} ({} plays)", song_id, play_count)
 }
 
+async fn traffic_spot_log(
+    pool: &SqlitePool,
+    start_date: &str,
+    end_date: &str,
+    campaign_id: Option<i64>,
+) -> Result<Vec<SpotLogEntry>, sqlx::Error> {
+    let Some((start_utc, end_utc)) = local_date_range_utc(&Local, start_date, end_date) else {
+        return Err(sqlx::Error::Protocol(format!(
+            "Invalid affidavit date range: {start_date}..{end_date}"
+        )));
+    };
+    traffic::get_spot_log(pool, start_utc, end_utc, campaign_id).await
+}
+
+fn local_time_label(ts: Option<i64>) -> String {
+    ts.and_then(|t| Local.timestamp_opt(t, 0).single())
+        .map(|dt| dt.format("%Y-%m-%d %H:%M:%S").to_string())
+        .unwrap_or_default()
+}
+
+async fn generate_traffic_affidavit_report(
+    pool: &SqlitePool,
+    now_ms: i64,
+    start_date: &str,
+    end_date: &str,
+    campaign_id: Option<i64>,
+) -> Result<ReportData, sqlx::Error> {
+    let spots = traffic_spot_log(pool, start_date, end_date, campaign_id).await?;
+
+    let mut totals: BTreeMap<(String, String), (i64, i64, i64)> = BTreeMap::new();
+    for spot in &spots {
+        let entry = totals
+            .entry((spot.advertiser.clone(), spot.title.clone()))
+            .or_default();
+        match spot.status {
+            SpotStatus::Aired => {
+                entry.0 += 1;
+                if spot.make_good_for.is_some() {
+                    entry.2 += 1;
+                }
+            }
+            SpotStatus::Missed => entry.1 += 1,
+            SpotStatus::Scheduled => {}
+        }
+    }
+    let aired = totals.values().map(|t| t.0).sum::<i64>();
+    let top_advertiser = totals
+        .iter()
+        .max_by_key(|(_, t)| t.0)
+        .filter(|(_, t)| t.0 > 0)
+        .map(|((advertiser, title), t)| format!("{advertiser} - {title} ({} spots)", t.0));
+
+    Ok(ReportData {
+        report_type: "traffic_affidavit".to_string(),
+        generated_at: now_ms,
+        title: format!("Traffic Affidavit - {start_date} to {end_date}"),
+        summary: ReportSummary {
+            total_plays: Some(aired),
+            total_listeners: None,
+            top_song: top_advertiser,
+            peak_hour: None,
+        },
+        sections: vec![
+            ReportSection {
+                title: "Campaign Totals".to_string(),
+                data: serde_json::json!(totals
+                    .iter()
+                    .map(
+                        |((advertiser, title), (aired, missed, make_goods))| serde_json::json!({
+                            "advertiser": advertiser,
+                            "title": title,
+                            "aired": aired,
+                            "missed": missed,
+                            "make_goods": make_goods,
+                        })
+                    )
+                    .collect::<Vec<_>>()),
+            },
+            ReportSection {
+                title: "Proof of Play".to_string(),
+                data: serde_json::to_value(&spots).unwrap_or_default(),
+            },
+        ],
+    })
+}
+
+/// Export the traffic spot log as a tabular proof-of-play CSV.
+pub async fn export_proof_of_play_csv(
+    pool: &SqlitePool,
+    start_date: &str,
+    end_date: &str,
+    campaign_id: Option<i64>,
+) -> Result<String, String> {
+    let spots = traffic_spot_log(pool, start_date, end_date, campaign_id)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let mut csv_content = String::from(
+        "spot_id,campaign_id,advertiser,title,sponsor_category,scheduled_at,aired_at,played_secs,status,make_good_for\n",
+    );
+    for spot in &spots {
+        csv_content.push_str(&format!(
+            "{},{},{},{},{},{},{},{},{},{}\n",
+            spot.id,
+            spot.campaign_id,
+            csv_escape(&spot.advertiser),
+            csv_escape(&spot.title),
+            csv_escape(&spot.sponsor_category),
+            csv_escape(&local_time_label(Some(spot.scheduled_at))),
+            csv_escape(&local_time_label(spot.aired_at)),
+            spot.played_ms
+                .map(|ms| (ms / 1000).to_string())
+                .unwrap_or_default(),
+            spot.status.as_str(),
+            spot.make_good_for
+                .map(|id| id.to_string())
+                .unwrap_or_default(),
+        ));
+    }
+
+    let file_name = format!(
+        "desizone_proof_of_play_{}_{}_{}.csv",
+        start_date.replace('-', ""),
+        end_date.replace('-', ""),
+        Utc::now().timestamp_millis()
+    );
+    write_temp_csv(&file_name, &csv_content)
+}
+
+async fn generate_show_audience_report(
+    pool: &SqlitePool,
+    now_ms: i64,
+    start_date: &str,
+    end_date: &str,
+    show_id: Option<i64>,
+    compare_weeks: u32,
+) -> Result<ReportData, sqlx::Error> {
+    let report =
+        show_audience::build_report(pool, start_date, end_date, show_id, compare_weeks).await?;
+
+    let total_tlh = report.shows.iter().map(|s| s.stats.tlh).sum::<f64>();
+    let peak = report
+        .airings
+        .iter()
+        .max_by_key(|a| a.stats.peak_listeners)
+        .filter(|a| a.stats.peak_listeners > 0);
+
+    Ok(ReportData {
+        report_type: "show_audience".to_string(),
+        generated_at: now_ms,
+        title: format!("Show Audience - {start_date} to {end_date}"),
+        summary: ReportSummary {
+            total_plays: Some(report.airings.len() as i64),
+            total_listeners: peak.map(|a| a.stats.peak_listeners),
+            top_song: report
+                .shows
+                .first()
+                .filter(|s| s.stats.tlh > 0.0)
+                .map(|s| format!("{} ({:.1} TLH)", s.show_name, s.stats.tlh)),
+            peak_hour: peak.and_then(|a| {
+                a.stats
+                    .peak_at
+                    .map(|ms| format!("{} {}", a.show_name, local_time_label(Some(ms / 1000))))
+            }),
+        },
+        sections: vec![
+            ReportSection {
+                title: "Shows".to_string(),
+                data: serde_json::json!({
+                    "total_tlh": total_tlh,
+                    "compare_weeks": compare_weeks,
+                    "shows": report.shows,
+                }),
+            },
+            ReportSection {
+                title: "Airings".to_string(),
+                data: serde_json::to_value(&report.airings).unwrap_or_default(),
+            },
+        ],
+    })
+}
+
+/// Export per-show audience figures as a tabular CSV.
+pub async fn export_show_audience_csv(
+    pool: &SqlitePool,
+    start_date: &str,
+    end_date: &str,
+    show_id: Option<i64>,
+    compare_weeks: u32,
+) -> Result<String, String> {
+    let report = show_audience::build_report(pool, start_date, end_date, show_id, compare_weeks)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let mut csv_content = String::from(
+        "show_id,show_name,airings,average_listeners,peak_listeners,peak_at,tlh,baseline_average_listeners,growth_pct\n",
+    );
+    for show in &report.shows {
+        csv_content.push_str(&format!(
+            "{},{},{},{:.2},{},{},{:.2},{},{}\n",
+            show.show_id,
+            csv_escape(&show.show_name),
+            show.airings,
+            show.stats.average_listeners,
+            show.stats.peak_listeners,
+            csv_escape(&local_time_label(show.stats.peak_at.map(|ms| ms / 1000))),
+            show.stats.tlh,
+            show.baseline_average_listeners
+                .map(|v| format!("{v:.2}"))
+                .unwrap_or_default(),
+            show.growth_pct
+                .map(|v| format!("{v:.1}"))
+                .unwrap_or_default(),
+        ));
+    }
+
+    let file_name = format!(
+        "desizone_show_audience_{}_{}_{}.csv",
+        start_date.replace('-', ""),
+        end_date.replace('-', ""),
+        Utc::now().timestamp_millis()
+    );
+    write_temp_csv(&file_name, &csv_content)
+}
+
+async fn generate_listening_hours_report(
+    pool: &SqlitePool,
+    now_ms: i64,
+    start_date: &str,
+    end_date: &str,
+    encoder_id: Option<i64>,
+) -> Result<ReportData, sqlx::Error> {
+    let kpis = listener_stats::get_listener_kpis(pool, start_date, end_date, encoder_id).await?;
+    let busiest = kpis
+        .hourly
+        .iter()
+        .filter(|h| h.listening_hours > 0.0)
+        .max_by(|a, b| a.listening_hours.total_cmp(&b.listening_hours));
+
+    Ok(ReportData {
+        report_type: "listening_hours".to_string(),
+        generated_at: now_ms,
+        title: format!("Listening Hours - {start_date} to {end_date}"),
+        summary: ReportSummary {
+            total_plays: Some(kpis.sessions),
+            total_listeners: Some(kpis.unique_listeners),
+            top_song: None,
+            peak_hour: busiest.map(|h| local_time_label(Some(h.hour_start / 1000))),
+        },
+        sections: vec![ReportSection {
+            title: "KPIs".to_string(),
+            data: serde_json::json!({
+                "encoder_id": encoder_id,
+                "total_listening_hours": kpis.total_listening_hours,
+                "sessions": kpis.sessions,
+                "bounced_sessions": kpis.bounced_sessions,
+                "unique_listeners": kpis.unique_listeners,
+                "average_session_secs": kpis.average_session_secs,
+                "median_session_secs": kpis.median_session_secs,
+                "returning_listeners": kpis.returning_listeners,
+                "returning_pct": kpis.returning_pct,
+            }),
+        }],
+    })
+}
+
+async fn generate_audience_retention_report(
+    pool: &SqlitePool,
+    now_ms: i64,
+    start_date: &str,
+    end_date: &str,
+    encoder_id: Option<i64>,
+) -> Result<ReportData, sqlx::Error> {
+    let kpis = listener_stats::get_listener_kpis(pool, start_date, end_date, encoder_id).await?;
+    let measured: Vec<f64> = kpis.hourly.iter().filter_map(|h| h.retention_pct).collect();
+    let average_retention = if measured.is_empty() {
+        None
+    } else {
+        Some(measured.iter().sum::<f64>() / measured.len() as f64)
+    };
+
+    Ok(ReportData {
+        report_type: "audience_retention".to_string(),
+        generated_at: now_ms,
+        title: format!("Audience Retention - {start_date} to {end_date}"),
+        summary: ReportSummary {
+            total_plays: Some(kpis.sessions),
+            total_listeners: Some(kpis.unique_listeners),
+            top_song: None,
+            peak_hour: average_retention.map(|pct| format!("avg_retention:{pct:.1}%")),
+        },
+        sections: vec![ReportSection {
+            title: "Hourly retention".to_string(),
+            data: serde_json::json!({
+                "encoder_id": encoder_id,
+                "average_retention_pct": average_retention,
+                "hours": kpis.hourly,
+            }),
+        }],
+    })
+}
+
+/// Export hourly listening hours and retention as a tabular CSV, with a
+/// closing totals row.
+pub async fn export_listener_kpis_csv(
+    pool: &SqlitePool,
+    start_date: &str,
+    end_date: &str,
+    encoder_id: Option<i64>,
+) -> Result<String, String> {
+    let kpis = listener_stats::get_listener_kpis(pool, start_date, end_date, encoder_id)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let mut csv_content = String::from(
+        "hour,audience_at_start,retained,retention_pct,sessions_started,listening_hours\n",
+    );
+    for hour in &kpis.hourly {
+        csv_content.push_str(&format!(
+            "{},{},{},{},{},{:.2}\n",
+            csv_escape(&local_time_label(Some(hour.hour_start / 1000))),
+            hour.audience_at_start,
+            hour.retained,
+            hour.retention_pct
+                .map(|v| format!("{v:.1}"))
+                .unwrap_or_default(),
+            hour.sessions_started,
+            hour.listening_hours,
+        ));
+    }
+    csv_content.push_str(&format!(
+        "{},,,,{},{:.2}\n",
+        csv_escape("TOTAL"),
+        kpis.sessions,
+        kpis.total_listening_hours,
+    ));
+    csv_content.push_str(&format!(
+        "\nunique_listeners,{}\naverage_session_secs,{:.0}\nmedian_session_secs,{}\nreturning_listeners,{}\nreturning_pct,{:.1}\n",
+        kpis.unique_listeners,
+        kpis.average_session_secs,
+        kpis.median_session_secs,
+        kpis.returning_listeners,
+        kpis.returning_pct,
+    ));
+
+    let file_name = format!(
+        "desizone_listener_kpis_{}_{}_{}.csv",
+        start_date.replace('-', ""),
+        end_date.replace('-', ""),
+        Utc::now().timestamp_millis()
+    );
+    write_temp_csv(&file_name, &csv_content)
+}
+
+async fn generate_royalty_spins_report(
+    pool: &SqlitePool,
+    sam_pool: Option<&MySqlPool>,
+    now_ms: i64,
+    period: &RoyaltyPeriod,
+    song_types: &[String],
+) -> Result<ReportData, sqlx::Error> {
+    let (first, last) = period.dates().map_err(sqlx::Error::Protocol)?;
+    let sam_pool =
+        sam_pool.ok_or_else(|| sqlx::Error::Protocol("SAM database not connected".to_string()))?;
+    let song_types = if song_types.is_empty() {
+        vec!["S".to_string()]
+    } else {
+        song_types.to_vec()
+    };
+    let spins = royalty::load_spins(sam_pool, Some(pool), first, last, &song_types).await?;
+    let summary = royalty::summarize(&spins);
+    let total_performances: i64 = spins.iter().map(|s| s.listeners).sum();
+    let missing_isrc = summary.iter().filter(|s| s.isrc.is_empty()).count();
+
+    Ok(ReportData {
+        report_type: "royalty_spins".to_string(),
+        generated_at: now_ms,
+        title: format!("Royalty Spins - {first} to {last}"),
+        summary: ReportSummary {
+            total_plays: Some(spins.len() as i64),
+            total_listeners: Some(total_performances),
+            top_song: summary
+                .first()
+                .map(|s| format!("{} - {} ({} plays)", s.artist, s.title, s.play_count)),
+            peak_hour: None,
+        },
+        sections: vec![
+            ReportSection {
+                title: "Totals".to_string(),
+                data: serde_json::json!({
+                    "start_date": first.to_string(),
+                    "end_date": last.to_string(),
+                    "spins": spins.len(),
+                    "recordings": summary.len(),
+                    "total_performances": total_performances,
+                    "recordings_missing_isrc": missing_isrc,
+                }),
+            },
+            ReportSection {
+                title: "Recordings".to_string(),
+                data: serde_json::to_value(&summary).unwrap_or_default(),
+            },
+        ],
+    })
+}
+
+/// Write a SoundExchange / PPL / BMI spin report and return its path.
+pub async fn export_royalty_report(
+    pool: Option<&SqlitePool>,
+    sam_pool: &MySqlPool,
+    request: &RoyaltyReportRequest,
+) -> Result<String, String> {
+    let (first, last) = request.period.dates()?;
+    let spins = royalty::load_spins(sam_pool, pool, first, last, &request.song_types)
+        .await
+        .map_err(|e| e.to_string())?;
+    let content = royalty::render(request, &spins);
+
+    let extension = match request.delimiter {
+        Delimiter::Csv => "csv",
+        Delimiter::Tsv => "tsv",
+    };
+    let file_name = format!(
+        "desizone_{}_{}.{extension}",
+        royalty::file_stem(request.format, first, last),
+        Utc::now().timestamp_millis()
+    );
+    write_temp_csv(&file_name, &content)
+}
+
 /// Export report data to CSV format
 pub fn export_report_csv(report_data: &ReportData) -> Result<String, String> {
     let sanitized_type = report_data
@@ -401,8 +918,6 @@ pub fn export_report_csv(report_data: &ReportData) -> Result<String, String> {
         "desizone_report_{}_{}.csv",
         sanitized_type, report_data.generated_at
     );
-    let path: PathBuf = std::env::temp_dir().join(file_name);
-
     let mut csv_content = String::from("field,value\n");
     csv_content.push_str(&format!(
         "report_type,{}\n",
@@ -448,6 +963,11 @@ pub fn export_report_csv(report_data: &ReportData) -> Result<String, String> {
         ));
     }
 
+    write_temp_csv(&file_name, &csv_content)
+}
+
+fn write_temp_csv(file_name: &str, csv_content: &str) -> Result<String, String> {
+    let path: PathBuf = std::env::temp_dir().join(file_name);
     let mut file = OpenOptions::new()
         .write(true)
         .create_new(true)
@@ -0,0 +1,563 @@
+/// Spin / royalty reporting
+///
+/// Builds the reports stations file with collecting societies from SAM's
+/// `historylist` (a metadata snapshot per play, including ISRC and the
+/// listener count when the track aired):
+///
+///   SoundExchange — Report of Use: one row per sound recording with Actual
+///                   Total Performances (sum of listeners across its plays)
+///   PPL           — one row per play, with date, time and duration
+///   BMI / ASCAP   — one row per recording with its play count
+///
+/// Plays logged with no listener count fall back to the local listener
+/// snapshots nearest before the play (summed across encoders).
+use std::collections::{BTreeMap, HashMap};
+
+use chrono::{Datelike, Duration, Local, NaiveDate, NaiveDateTime, TimeZone};
+use serde::{Deserialize, Serialize};
+use sqlx::{MySqlPool, SqlitePool};
+
+use crate::db::sam::{self, HistoryEntry};
+
+/// A listener snapshot older than this at play time is not used.
+const SNAPSHOT_MAX_AGE_SECS: i64 = 10 * 60;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RoyaltyFormat {
+    SoundExchange,
+    Ppl,
+    /// BMI, ASCAP and SESAC take the same per-recording spin counts
+    Bmi,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Delimiter {
+    #[default]
+    Csv,
+    Tsv,
+}
+
+/// Reporting period, resolved to inclusive local dates.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum RoyaltyPeriod {
+    Custom {
+        start_date: String,
+        end_date: String,
+    },
+    Month {
+        year: i32,
+        month: u32,
+    },
+    Quarter {
+        year: i32,
+        quarter: u32,
+    },
+}
+
+impl RoyaltyPeriod {
+    pub fn dates(&self) -> Result<(NaiveDate, NaiveDate), String> {
+        let (first, last) = match self {
+            RoyaltyPeriod::Custom {
+                start_date,
+                end_date,
+            } => {
+                let parse = |d: &str| {
+                    NaiveDate::parse_from_str(d, "%Y-%m-%d")
+                        .map_err(|_| format!("Invalid date: {d} (expected YYYY-MM-DD)"))
+                };
+                (parse(start_date)?, parse(end_date)?)
+            }
+            RoyaltyPeriod::Month { year, month } => month_bounds(*year, *month)
+                .ok_or_else(|| format!("Invalid month: {year}-{month}"))?,
+            RoyaltyPeriod::Quarter { year, quarter } => {
+                if !(1..=4).contains(quarter) {
+                    return Err(format!("Quarter must be 1-4, got {quarter}"));
+                }
+                let (first, _) = month_bounds(*year, quarter * 3 - 2)
+                    .ok_or_else(|| format!("Invalid year: {year}"))?;
+                let (_, last) = month_bounds(*year, quarter * 3)
+                    .ok_or_else(|| format!("Invalid year: {year}"))?;
+                (first, last)
+            }
+        };
+        if last < first {
+            return Err("Reporting period ends before it starts".to_string());
+        }
+        Ok((first, last))
+    }
+}
+
+fn month_bounds(year: i32, month: u32) -> Option<(NaiveDate, NaiveDate)> {
+    let first = NaiveDate::from_ymd_opt(year, month, 1)?;
+    let next = if month == 12 {
+        NaiveDate::from_ymd_opt(year + 1, 1, 1)?
+    } else {
+        NaiveDate::from_ymd_opt(year, month + 1, 1)?
+    };
+    Some((first, next - Duration::days(1)))
+}
+
+fn default_song_types() -> Vec<String> {
+    vec!["S".to_string()]
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoyaltyReportRequest {
+    pub format: RoyaltyFormat,
+    pub period: RoyaltyPeriod,
+    #[serde(default)]
+    pub delimiter: Delimiter,
+    /// SAM song types to report (music only by default)
+    #[serde(default = "default_song_types")]
+    pub song_types: Vec<String>,
+    /// `NAME_OF_SERVICE` on SoundExchange reports
+    #[serde(default)]
+    pub service_name: String,
+    /// SoundExchange transmission category code
+    #[serde(default)]
+    pub transmission_category: String,
+}
+
+/// One aired track with the listener count used for royalties.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Spin {
+    pub played_at: String,
+    pub duration_secs: i32,
+    pub artist: String,
+    pub title: String,
+    pub album: String,
+    pub label: String,
+    pub isrc: String,
+    pub listeners: i64,
+}
+
+/// Per-recording totals.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpinSummary {
+    pub artist: String,
+    pub title: String,
+    pub album: String,
+    pub label: String,
+    pub isrc: String,
+    pub play_count: i64,
+    /// Sum of listeners across plays (SoundExchange "ATP")
+    pub total_performances: i64,
+    pub first_played: String,
+    pub last_played: String,
+}
+
+/// Spins for the local dates `first..=last`.
+pub async fn load_spins(
+    sam_pool: &MySqlPool,
+    local_pool: Option<&SqlitePool>,
+    first: NaiveDate,
+    last: NaiveDate,
+    song_types: &[String],
+) -> Result<Vec<Spin>, sqlx::Error> {
+    let history = sam::get_history_between(
+        sam_pool,
+        &format!("{first} 00:00:00"),
+        &format!("{last} 23:59:59"),
+        song_types,
+    )
+    .await?;
+
+    let snapshots = match local_pool {
+        Some(pool) if history.iter().any(|h| h.listeners <= 0) => {
+            load_listener_snapshots(pool, first, last).await?
+        }
+        _ => BTreeMap::new(),
+    };
+
+    Ok(history
+        .into_iter()
+        .map(|h| {
+            let listeners = if h.listeners > 0 {
+                i64::from(h.listeners)
+            } else {
+                played_at_utc(&h.date_played)
+                    .map(|ts| listeners_at(&snapshots, ts))
+                    .unwrap_or(0)
+            };
+            spin_from_history(h, listeners)
+        })
+        .collect())
+}
+
+fn spin_from_history(h: HistoryEntry, listeners: i64) -> Spin {
+    Spin {
+        played_at: h.date_played.replace('T', " "),
+        duration_secs: h.duration,
+        artist: h.artist.trim().to_string(),
+        title: h.title.trim().to_string(),
+        album: h.album.trim().to_string(),
+        label: h.label.trim().to_string(),
+        isrc: normalize_isrc(&h.isrc),
+        listeners,
+    }
+}
+
+/// ISRCs are 12 characters; SAM often stores them with dashes or spaces.
+fn normalize_isrc(isrc: &str) -> String {
+    let compact: String = isrc
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .collect::<String>()
+        .to_ascii_uppercase();
+    if compact.len() == 12 {
+        compact
+    } else {
+        String::new()
+    }
+}
+
+fn played_at_utc(date_played: &str) -> Option<i64> {
+    let naive = NaiveDateTime::parse_from_str(date_played, "%Y-%m-%dT%H:%M:%S")
+        .or_else(|_| NaiveDateTime::parse_from_str(date_played, "%Y-%m-%d %H:%M:%S"))
+        .ok()?;
+    Local
+        .from_local_datetime(&naive)
+        .earliest()
+        .map(|dt| dt.timestamp())
+}
+
+/// Snapshot times (UTC seconds) and counts per encoder.
+type Snapshots = BTreeMap<i64, Vec<(i64, i64)>>;
+
+async fn load_listener_snapshots(
+    pool: &SqlitePool,
+    first: NaiveDate,
+    last: NaiveDate,
+) -> Result<Snapshots, sqlx::Error> {
+    let bound = |date: NaiveDate| {
+        date.and_hms_opt(0, 0, 0)
+            .and_then(|dt| Local.from_local_datetime(&dt).earliest())
+            .map(|dt| dt.timestamp())
+            .unwrap_or(0)
+    };
+    let from = bound(first) - SNAPSHOT_MAX_AGE_SECS;
+    let to = bound(last + Duration::days(1));
+    let rows = sqlx::query_as::<_, (i64, i64, i64)>(
+        "SELECT encoder_id, snapshot_at, current_listeners FROM listener_snapshots \
+         WHERE snapshot_at >= ? AND snapshot_at < ? ORDER BY snapshot_at ASC",
+    )
+    .bind(from)
+    .bind(to)
+    .fetch_all(pool)
+    .await?;
+    let mut out = Snapshots::new();
+    for (encoder_id, ts, count) in rows {
+        out.entry(encoder_id).or_default().push((ts, count));
+    }
+    Ok(out)
+}
+
+/// Listeners across encoders from each one's latest snapshot at or before `ts`.
+fn listeners_at(snapshots: &Snapshots, ts: i64) -> i64 {
+    snapshots
+        .values()
+        .filter_map(|samples| {
+            let idx = samples.partition_point(|(t, _)| *t <= ts);
+            let (t, count) = *samples.get(idx.checked_sub(1)?)?;
+            (ts - t <= SNAPSHOT_MAX_AGE_SECS).then_some(count)
+        })
+        .sum()
+}
+
+/// Roll spins up per recording (ISRC when known, else artist + title),
+/// most-played first.
+pub fn summarize(spins: &[Spin]) -> Vec<SpinSummary> {
+    let mut by_key: HashMap<String, SpinSummary> = HashMap::new();
+    for spin in spins {
+        let key = if spin.isrc.is_empty() {
+            format!(
+                "{}\u{1f}{}",
+                spin.artist.to_lowercase(),
+                spin.title.to_lowercase()
+            )
+        } else {
+            spin.isrc.clone()
+        };
+        let entry = by_key.entry(key).or_insert_with(|| SpinSummary {
+            artist: spin.artist.clone(),
+            title: spin.title.clone(),
+            album: spin.album.clone(),
+            label: spin.label.clone(),
+            isrc: spin.isrc.clone(),
+            play_count: 0,
+            total_performances: 0,
+            first_played: spin.played_at.clone(),
+            last_played: spin.played_at.clone(),
+        });
+        entry.play_count += 1;
+        entry.total_performances += spin.listeners;
+        if spin.played_at < entry.first_played {
+            entry.first_played = spin.played_at.clone();
+        }
+        if spin.played_at > entry.last_played {
+            entry.last_played = spin.played_at.clone();
+        }
+    }
+    let mut out: Vec<SpinSummary> = by_key.into_values().collect();
+    out.sort_by(|a, b| {
+        b.play_count
+            .cmp(&a.play_count)
+            .then_with(|| a.artist.cmp(&b.artist))
+            .then_with(|| a.title.cmp(&b.title))
+    });
+    out
+}
+
+/// Render a report in the society's column layout.
+pub fn render(request: &RoyaltyReportRequest, spins: &[Spin]) -> String {
+    let mut table = Table::new(request.delimiter);
+    match request.format {
+        RoyaltyFormat::SoundExchange => {
+            table.row(&[
+                "NAME_OF_SERVICE",
+                "TRANSMISSION_CATEGORY",
+                "FEATURED_ARTIST",
+                "SOUND_RECORDING_TITLE",
+                "ISRC",
+                "ALBUM_TITLE",
+                "MARKETING_LABEL",
+                "ACTUAL_TOTAL_PERFORMANCES",
+            ]);
+            for s in summarize(spins) {
+                table.row(&[
+                    request.service_name.as_str(),
+                    request.transmission_category.as_str(),
+                    &s.artist,
+                    &s.title,
+                    &s.isrc,
+                    &s.album,
+                    &s.label,
+                    &s.total_performances.to_string(),
+                ]);
+            }
+        }
+        RoyaltyFormat::Ppl => {
+            table.row(&[
+                "DATE",
+                "TIME",
+                "DURATION",
+                "TITLE",
+                "ARTIST",
+                "ALBUM",
+                "LABEL",
+                "ISRC",
+                "LISTENERS",
+            ]);
+            for s in spins {
+                let (date, time) = s.played_at.split_once(' ').unwrap_or((&s.played_at, ""));
+                let duration = format!(
+                    "{:02}:{:02}:{:02}",
+                    s.duration_secs / 3600,
+                    s.duration_secs / 60 % 60,
+                    s.duration_secs % 60
+                );
+                table.row(&[
+                    date,
+                    time,
+                    &duration,
+                    &s.title,
+                    &s.artist,
+                    &s.album,
+                    &s.label,
+                    &s.isrc,
+                    &s.listeners.to_string(),
+                ]);
+            }
+        }
+        RoyaltyFormat::Bmi => {
+            table.row(&[
+                "TITLE",
+                "ARTIST",
+                "ALBUM",
+                "LABEL",
+                "ISRC",
+                "PLAY_COUNT",
+                "TOTAL_LISTENERS",
+                "FIRST_PLAYED",
+                "LAST_PLAYED",
+            ]);
+            for s in summarize(spins) {
+                table.row(&[
+                    &s.title,
+                    &s.artist,
+                    &s.album,
+                    &s.label,
+                    &s.isrc,
+                    &s.play_count.to_string(),
+                    &s.total_performances.to_string(),
+                    &s.first_played,
+                    &s.last_played,
+                ]);
+            }
+        }
+    }
+    table.out
+}
+
+struct Table {
+    delimiter: Delimiter,
+    out: String,
+}
+
+impl Table {
+    fn new(delimiter: Delimiter) -> Self {
+        Self {
+            delimiter,
+            out: String::new(),
+        }
+    }
+
+    fn row(&mut self, cells: &[&str]) {
+        let sep = match self.delimiter {
+            Delimiter::Csv => ",",
+            Delimiter::Tsv => "\t",
+        };
+        let line = cells
+            .iter()
+            .map(|cell| match self.delimiter {
+                Delimiter::Csv => {
+                    if cell.contains([',', '"', '\n', '\r']) {
+                        format!("\"{}\"", cell.replace('"', "\"\""))
+                    } else {
+                        cell.to_string()
+                    }
+                }
+                // TSV has no quoting; tabs and newlines inside a field are flattened.
+                Delimiter::Tsv => cell.replace(['\t', '\n', '\r'], " "),
+            })
+            .collect::<Vec<_>>()
+            .join(sep);
+        self.out.push_str(&line);
+        self.out.push_str("\r\n");
+    }
+}
+
+/// File name stem such as `soundexchange_2026-07-01_2026-09-30`.
+pub fn file_stem(format: RoyaltyFormat, first: NaiveDate, last: NaiveDate) -> String {
+    let society = match format {
+        RoyaltyFormat::SoundExchange => "soundexchange",
+        RoyaltyFormat::Ppl => "ppl",
+        RoyaltyFormat::Bmi => "bmi",
+    };
+    if first.day() == 1 && last.month() == first.month() && last.year() == first.year() {
+        format!("{society}_{}", first.format("%Y-%m"))
+    } else {
+        format!("{society}_{first}_{last}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spin(title: &str, isrc: &str, played_at: &str, listeners: i64) -> Spin {
+        Spin {
+            played_at: played_at.to_string(),
+            duration_secs: 215,
+            artist: "Artist, The".to_string(),
+            title: title.to_string(),
+            album: "Album".to_string(),
+            label: "Label".to_string(),
+            isrc: normalize_isrc(isrc),
+            listeners,
+        }
+    }
+
+    #[test]
+    fn resolves_periods() {
+        let q3 = RoyaltyPeriod::Quarter {
+            year: 2026,
+            quarter: 3,
+        };
+        assert_eq!(
+            q3.dates().unwrap(),
+            (
+                NaiveDate::from_ymd_opt(2026, 7, 1).unwrap(),
+                NaiveDate::from_ymd_opt(2026, 9, 30).unwrap()
+            )
+        );
+        let feb = RoyaltyPeriod::Month {
+            year: 2028,
+            month: 2,
+        };
+        assert_eq!(feb.dates().unwrap().1.day(), 29);
+        assert!(RoyaltyPeriod::Quarter {
+            year: 2026,
+            quarter: 5
+        }
+        .dates()
+        .is_err());
+    }
+
+    #[test]
+    fn summarizes_by_isrc_and_renders_soundexchange() {
+        let spins = vec![
+            spin("Song", "US-AB1-26-00001", "2026-07-01 10:00:00", 12),
+            spin(
+                "Song (Radio Edit)",
+                "USAB12600001",
+                "2026-07-02 11:00:00",
+                30,
+            ),
+            spin("Other", "", "2026-07-01 12:00:00", 5),
+        ];
+        let summary = summarize(&spins);
+        assert_eq!(summary.len(), 2);
+        assert_eq!(summary[0].isrc, "USAB12600001");
+        assert_eq!(summary[0].play_count, 2);
+        assert_eq!(summary[0].total_performances, 42);
+        assert_eq!(summary[0].last_played, "2026-07-02 11:00:00");
+
+        let request = RoyaltyReportRequest {
+            format: RoyaltyFormat::SoundExchange,
+            period: RoyaltyPeriod::Month {
+                year: 2026,
+                month: 7,
+            },
+            delimiter: Delimiter::Csv,
+            song_types: default_song_types(),
+            service_name: "DesiZone Radio".to_string(),
+            transmission_category: "A".to_string(),
+        };
+        let csv = render(&request, &spins);
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[1].starts_with("DesiZone Radio,A,\"Artist, The\",Song,USAB12600001"));
+        assert!(lines[1].ends_with(",42"));
+
+        let tsv = render(
+            &RoyaltyReportRequest {
+                format: RoyaltyFormat::Ppl,
+                delimiter: Delimiter::Tsv,
+                ..request
+            },
+            &spins,
+        );
+        assert!(tsv
+            .lines()
+            .nth(1)
+            .unwrap()
+            .starts_with("2026-07-01\t10:00:00\t00:03:35\tSong\tArtist, The"));
+    }
+
+    #[test]
+    fn falls_back_to_recent_snapshots() {
+        let mut snapshots = Snapshots::new();
+        snapshots.insert(1, vec![(1_000, 10), (1_300, 14)]);
+        snapshots.insert(2, vec![(1_290, 3)]);
+        assert_eq!(listeners_at(&snapshots, 1_310), 17);
+        assert_eq!(listeners_at(&snapshots, 1_100), 10);
+        assert_eq!(
+            listeners_at(&snapshots, 1_300 + SNAPSHOT_MAX_AGE_SECS + 1),
+            0
+        );
+    }
+}
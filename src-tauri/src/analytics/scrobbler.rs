@@ -0,0 +1,645 @@
+/// Last.fm / ListenBrainz scrobbler
+///
+/// Completed plays that pass the play-length rule are written to a local
+/// `scrobble_queue` (one row per service) and submitted in batches. Failed
+/// submissions stay queued, so scrobbles made while offline go out once
+/// connectivity returns; rows a service rejects outright are dropped after
+/// a few attempts. Last.fm uses the desktop auth flow (token → browser →
+/// session key); ListenBrainz uses the user token from the profile page.
+use md5::{Digest, Md5};
+use serde::{Deserialize, Serialize};
+use sqlx::{Row, SqlitePool};
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+const LASTFM_API: &str = "https://ws.audioscrobbler.com/2.0/";
+const LASTFM_AUTH_URL: &str = "https://www.last.fm/api/auth/";
+/// Last.fm ignores scrobbles older than two weeks.
+const LASTFM_MAX_AGE_SECS: i64 = 14 * 24 * 3600;
+const BATCH_SIZE: i64 = 50;
+/// Rejected rows are dropped after this many submissions.
+const MAX_ATTEMPTS: i64 = 5;
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+
+// ── Config ────────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LastFmConfig {
+    pub enabled: bool,
+    pub api_key: String,
+    pub api_secret: String,
+    /// Set by the auth flow
+    pub session_key: Option<String>,
+    pub username: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ListenBrainzConfig {
+    pub enabled: bool,
+    pub api_url: String,
+    pub user_token: String,
+    /// Filled in when the token is validated
+    pub username: Option<String>,
+}
+
+impl Default for ListenBrainzConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            api_url: "https://api.listenbrainz.org".to_string(),
+            user_token: String::new(),
+            username: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScrobblerConfig {
+    pub lastfm: LastFmConfig,
+    pub listenbrainz: ListenBrainzConfig,
+    /// Share of the track that must have played
+    pub min_play_percent: u8,
+    /// A play of this many seconds always counts (Last.fm uses 4 minutes)
+    pub always_scrobble_after_secs: u32,
+    /// Tracks shorter than this are never scrobbled
+    pub min_track_secs: u32,
+    /// SAM song types to scrobble
+    pub song_types: Vec<String>,
+}
+
+impl Default for ScrobblerConfig {
+    fn default() -> Self {
+        Self {
+            lastfm: LastFmConfig::default(),
+            listenbrainz: ListenBrainzConfig::default(),
+            min_play_percent: 50,
+            always_scrobble_after_secs: 240,
+            min_track_secs: 30,
+            song_types: vec!["S".to_string()],
+        }
+    }
+}
+
+impl ScrobblerConfig {
+    /// Whether a play of `played_ms` out of `duration_ms` counts.
+    pub fn play_qualifies(&self, played_ms: u64, duration_ms: u64) -> bool {
+        if duration_ms < u64::from(self.min_track_secs) * 1000 {
+            return false;
+        }
+        let needed = (duration_ms * u64::from(self.min_play_percent.min(100)) / 100)
+            .min(u64::from(self.always_scrobble_after_secs) * 1000);
+        played_ms >= needed
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum ScrobbleService {
+    LastFm,
+    ListenBrainz,
+}
+
+impl ScrobbleService {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ScrobbleService::LastFm => "last_fm",
+            ScrobbleService::ListenBrainz => "listen_brainz",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Scrobble {
+    pub artist: String,
+    pub title: String,
+    pub album: String,
+    pub duration_secs: u32,
+    /// Unix time the track started
+    pub started_at: i64,
+    pub isrc: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ServiceStatus {
+    pub enabled: bool,
+    pub authenticated: bool,
+    pub username: Option<String>,
+    pub queued: i64,
+    pub last_success_at: Option<i64>,
+    pub last_error: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ScrobblerStatus {
+    pub lastfm: ServiceStatus,
+    pub listenbrainz: ServiceStatus,
+}
+
+// ── Runtime state ─────────────────────────────────────────────────────────────
+
+/// (last success, last error) per service.
+type Outcomes = BTreeMap<ScrobbleService, (Option<i64>, Option<String>)>;
+
+static OUTCOMES: OnceLock<Mutex<Outcomes>> = OnceLock::new();
+static DRAINING: AtomicBool = AtomicBool::new(false);
+
+fn outcomes() -> &'static Mutex<Outcomes> {
+    OUTCOMES.get_or_init(|| Mutex::new(BTreeMap::new()))
+}
+
+fn record_outcome(service: ScrobbleService, result: &Result<(), String>) {
+    let mut map = outcomes().lock().unwrap();
+    let entry = map.entry(service).or_default();
+    match result {
+        Ok(()) => {
+            entry.0 = Some(now_secs());
+            entry.1 = None;
+        }
+        Err(e) => entry.1 = Some(e.clone()),
+    }
+}
+
+fn now_secs() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs() as i64
+}
+
+fn client() -> Result<reqwest::Client, String> {
+    reqwest::Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .map_err(|e| e.to_string())
+}
+
+// ── Queue ─────────────────────────────────────────────────────────────────────
+
+/// Queue a completed play for every enabled, authenticated service.
+pub async fn record_play(
+    pool: &SqlitePool,
+    song: &crate::db::sam::SamSong,
+    played_ms: u64,
+    duration_ms: u64,
+) -> Result<bool, sqlx::Error> {
+    let config = get_config(pool).await?;
+    let duration_ms = if duration_ms > 0 {
+        duration_ms
+    } else {
+        u64::try_from(song.duration).unwrap_or(0) * 1000
+    };
+    let type_ok = config
+        .song_types
+        .iter()
+        .any(|t| t.eq_ignore_ascii_case(&song.songtype));
+    if !type_ok || !config.play_qualifies(played_ms, duration_ms) {
+        return Ok(false);
+    }
+    if song.artist.trim().is_empty() || song.title.trim().is_empty() {
+        return Ok(false);
+    }
+
+    let scrobble = Scrobble {
+        artist: song.artist.clone(),
+        title: song.title.clone(),
+        album: song.album.clone(),
+        duration_secs: (duration_ms / 1000) as u32,
+        started_at: now_secs() - (played_ms / 1000) as i64,
+        isrc: song.isrc.clone(),
+    };
+    let payload = serde_json::to_string(&scrobble).unwrap_or_default();
+    let mut queued = false;
+    for (service, active) in [
+        (ScrobbleService::LastFm, lastfm_ready(&config.lastfm)),
+        (
+            ScrobbleService::ListenBrainz,
+            listenbrainz_ready(&config.listenbrainz),
+        ),
+    ] {
+        if !active {
+            continue;
+        }
+        sqlx::query(
+            "INSERT INTO scrobble_queue (service, payload_json, created_at) VALUES (?, ?, ?)",
+        )
+        .bind(service.as_str())
+        .bind(&payload)
+        .bind(now_secs())
+        .execute(pool)
+        .await?;
+        queued = true;
+    }
+    Ok(queued)
+}
+
+fn lastfm_ready(cfg: &LastFmConfig) -> bool {
+    cfg.enabled && !cfg.api_key.is_empty() && cfg.session_key.is_some()
+}
+
+fn listenbrainz_ready(cfg: &ListenBrainzConfig) -> bool {
+    cfg.enabled && !cfg.user_token.trim().is_empty()
+}
+
+/// Submit everything queued. Concurrent calls return immediately.
+pub async fn drain_queue(pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    if DRAINING.swap(true, Ordering::AcqRel) {
+        return Ok(());
+    }
+    let result = drain_all(pool).await;
+    DRAINING.store(false, Ordering::Release);
+    result
+}
+
+async fn drain_all(pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    let config = get_config(pool).await?;
+    if lastfm_ready(&config.lastfm) {
+        drain_service(pool, &config, ScrobbleService::LastFm).await?;
+    }
+    if listenbrainz_ready(&config.listenbrainz) {
+        drain_service(pool, &config, ScrobbleService::ListenBrainz).await?;
+    }
+    Ok(())
+}
+
+/// Outcome of one batch submission.
+enum Submit {
+    Accepted,
+    /// Service refused these scrobbles; retrying will not help
+    Rejected(String),
+    /// Network/auth/server trouble; keep everything queued
+    Retry(String),
+}
+
+async fn drain_service(
+    pool: &SqlitePool,
+    config: &ScrobblerConfig,
+    service: ScrobbleService,
+) -> Result<(), sqlx::Error> {
+    loop {
+        let rows = sqlx::query(
+            "SELECT id, payload_json FROM scrobble_queue WHERE service = ? ORDER BY id LIMIT ?",
+        )
+        .bind(service.as_str())
+        .bind(BATCH_SIZE)
+        .fetch_all(pool)
+        .await?;
+        if rows.is_empty() {
+            return Ok(());
+        }
+        let ids: Vec<i64> = rows.iter().map(|r| r.get("id")).collect();
+        let mut batch: Vec<Scrobble> = rows
+            .iter()
+            .filter_map(|r| serde_json::from_str(&r.get::<String, _>("payload_json")).ok())
+            .collect();
+        if service == ScrobbleService::LastFm {
+            let cutoff = now_secs() - LASTFM_MAX_AGE_SECS;
+            batch.retain(|s| s.started_at >= cutoff);
+        }
+
+        let outcome = if batch.is_empty() {
+            Submit::Accepted
+        } else {
+            match service {
+                ScrobbleService::LastFm => submit_lastfm(&config.lastfm, &batch).await,
+                ScrobbleService::ListenBrainz => {
+                    submit_listenbrainz(&config.listenbrainz, &batch).await
+                }
+            }
+        };
+
+        let id_list = ids
+            .iter()
+            .map(|id| id.to_string())
+            .collect::<Vec<_>>()
+            .join(",");
+        match outcome {
+            Submit::Accepted => {
+                record_outcome(service, &Ok(()));
+                sqlx::query(&format!(
+                    "DELETE FROM scrobble_queue WHERE id IN ({id_list})"
+                ))
+                .execute(pool)
+                .await?;
+            }
+            Submit::Rejected(e) => {
+                log::warn!("{} rejected scrobbles: {e}", service.as_str());
+                record_outcome(service, &Err(e.clone()));
+                sqlx::query(&format!(
+                    "UPDATE scrobble_queue SET attempts = attempts + 1, last_error = ? WHERE id IN ({id_list})"
+                ))
+                .bind(&e)
+                .execute(pool)
+                .await?;
+                sqlx::query("DELETE FROM scrobble_queue WHERE service = ? AND attempts >= ?")
+                    .bind(service.as_str())
+                    .bind(MAX_ATTEMPTS)
+                    .execute(pool)
+                    .await?;
+                return Ok(());
+            }
+            Submit::Retry(e) => {
+                log::debug!(
+                    "{} unavailable, scrobbles stay queued: {e}",
+                    service.as_str()
+                );
+                record_outcome(service, &Err(e));
+                return Ok(());
+            }
+        }
+    }
+}
+
+// ── Last.fm ───────────────────────────────────────────────────────────────────
+
+/// `api_sig`: md5 of the sorted name/value pairs followed by the secret.
+fn lastfm_signature(params: &BTreeMap<String, String>, secret: &str) -> String {
+    let mut hasher = Md5::new();
+    for (k, v) in params {
+        hasher.update(k.as_bytes());
+        hasher.update(v.as_bytes());
+    }
+    hasher.update(secret.as_bytes());
+    format!("{:x}", hasher.finalize())
+}
+
+async fn lastfm_call(
+    cfg: &LastFmConfig,
+    mut params: BTreeMap<String, String>,
+) -> Result<serde_json::Value, (bool, String)> {
+    params.insert("api_key".to_string(), cfg.api_key.clone());
+    let sig = lastfm_signature(&params, &cfg.api_secret);
+    params.insert("api_sig".to_string(), sig);
+    params.insert("format".to_string(), "json".to_string());
+
+    let resp = client()
+        .map_err(|e| (true, e))?
+        .post(LASTFM_API)
+        .form(&params)
+        .send()
+        .await
+        .map_err(|e| (true, format!("Last.fm request failed: {e}")))?;
+    let status = resp.status();
+    let body: serde_json::Value = resp.json().await.unwrap_or_default();
+    if let Some(code) = body.get("error").and_then(|c| c.as_i64()) {
+        let message = body
+            .get("message")
+            .and_then(|m| m.as_str())
+            .unwrap_or("unknown error");
+        // 9 invalid session, 11/16 service offline, 29 rate limit: keep data.
+        let transient = matches!(code, 9 | 11 | 16 | 29);
+        return Err((transient, format!("Last.fm error {code}: {message}")));
+    }
+    if !status.is_success() {
+        return Err((
+            status.is_server_error() || status.as_u16() == 429,
+            format!("Last.fm: HTTP {status}"),
+        ));
+    }
+    Ok(body)
+}
+
+async fn submit_lastfm(cfg: &LastFmConfig, batch: &[Scrobble]) -> Submit {
+    let mut params = BTreeMap::new();
+    params.insert("method".to_string(), "track.scrobble".to_string());
+    params.insert(
+        "sk".to_string(),
+        cfg.session_key.clone().unwrap_or_default(),
+    );
+    for (i, s) in batch.iter().enumerate() {
+        params.insert(format!("artist[{i}]"), s.artist.clone());
+        params.insert(format!("track[{i}]"), s.title.clone());
+        params.insert(format!("timestamp[{i}]"), s.started_at.to_string());
+        params.insert(format!("duration[{i}]"), s.duration_secs.to_string());
+        if !s.album.is_empty() {
+            params.insert(format!("album[{i}]"), s.album.clone());
+        }
+    }
+    match lastfm_call(cfg, params).await {
+        Ok(_) => Submit::Accepted,
+        Err((true, e)) => Submit::Retry(e),
+        Err((false, e)) => Submit::Rejected(e),
+    }
+}
+
+/// Start desktop auth: returns the token and the URL the user must open.
+pub async fn lastfm_begin_auth(cfg: &LastFmConfig) -> Result<(String, String), String> {
+    let mut params = BTreeMap::new();
+    params.insert("method".to_string(), "auth.getToken".to_string());
+    let body = lastfm_call(cfg, params).await.map_err(|(_, e)| e)?;
+    let token = body
+        .get("token")
+        .and_then(|t| t.as_str())
+        .ok_or("Last.fm did not return a token")?
+        .to_string();
+    let url = format!(
+        "{LASTFM_AUTH_URL}?api_key={}&token={}",
+        urlencoding::encode(&cfg.api_key),
+        urlencoding::encode(&token)
+    );
+    Ok((token, url))
+}
+
+/// Finish desktop auth once the user approved `token`: returns (user, session key).
+pub async fn lastfm_complete_auth(
+    cfg: &LastFmConfig,
+    token: &str,
+) -> Result<(String, String), String> {
+    let mut params = BTreeMap::new();
+    params.insert("method".to_string(), "auth.getSession".to_string());
+    params.insert("token".to_string(), token.to_string());
+    let body = lastfm_call(cfg, params).await.map_err(|(_, e)| e)?;
+    let session = body
+        .get("session")
+        .ok_or("Last.fm did not return a session")?;
+    let name = session
+        .get("name")
+        .and_then(|v| v.as_str())
+        .unwrap_or_default()
+        .to_string();
+    let key = session
+        .get("key")
+        .and_then(|v| v.as_str())
+        .ok_or("Last.fm session has no key")?
+        .to_string();
+    Ok((name, key))
+}
+
+// ── ListenBrainz ──────────────────────────────────────────────────────────────
+
+fn listenbrainz_url(cfg: &ListenBrainzConfig, path: &str) -> String {
+    format!("{}{path}", cfg.api_url.trim_end_matches('/'))
+}
+
+async fn submit_listenbrainz(cfg: &ListenBrainzConfig, batch: &[Scrobble]) -> Submit {
+    let payload: Vec<serde_json::Value> = batch
+        .iter()
+        .map(|s| {
+            let mut info = serde_json::json!({
+                "duration_ms": u64::from(s.duration_secs) * 1000,
+                "submission_client": "DesiZone Broadcaster",
+                "submission_client_version": env!("CARGO_PKG_VERSION"),
+            });
+            if !s.isrc.is_empty() {
+                info["isrc"] = serde_json::Value::String(s.isrc.clone());
+            }
+            let mut meta = serde_json::json!({
+                "artist_name": s.artist,
+                "track_name": s.title,
+                "additional_info": info,
+            });
+            if !s.album.is_empty() {
+                meta["release_name"] = serde_json::Value::String(s.album.clone());
+            }
+            serde_json::json!({ "listened_at": s.started_at, "track_metadata": meta })
+        })
+        .collect();
+    let body = serde_json::json!({
+        "listen_type": if payload.len() == 1 { "single" } else { "import" },
+        "payload": payload,
+    });
+
+    let client = match client() {
+        Ok(c) => c,
+        Err(e) => return Submit::Retry(e),
+    };
+    let resp = match client
+        .post(listenbrainz_url(cfg, "/1/submit-listens"))
+        .header("Authorization", format!("Token {}", cfg.user_token.trim()))
+        .json(&body)
+        .send()
+        .await
+    {
+        Ok(r) => r,
+        Err(e) => return Submit::Retry(format!("ListenBrainz request failed: {e}")),
+    };
+    let status = resp.status();
+    if status.is_success() {
+        return Submit::Accepted;
+    }
+    let text = resp.text().await.unwrap_or_default();
+    let message = format!(
+        "ListenBrainz: HTTP {status} {}",
+        text.chars().take(200).collect::<String>()
+    );
+    if status.is_server_error() || matches!(status.as_u16(), 401 | 429) {
+        Submit::Retry(message)
+    } else {
+        Submit::Rejected(message)
+    }
+}
+
+/// Check a user token; returns the ListenBrainz user name.
+pub async fn listenbrainz_validate(cfg: &ListenBrainzConfig) -> Result<String, String> {
+    let body: serde_json::Value = client()?
+        .get(listenbrainz_url(cfg, "/1/validate-token"))
+        .header("Authorization", format!("Token {}", cfg.user_token.trim()))
+        .send()
+        .await
+        .map_err(|e| format!("ListenBrainz request failed: {e}"))?
+        .json()
+        .await
+        .map_err(|e| format!("ListenBrainz: invalid response: {e}"))?;
+    if body.get("valid").and_then(|v| v.as_bool()) != Some(true) {
+        return Err("ListenBrainz rejected the token".to_string());
+    }
+    Ok(body
+        .get("user_name")
+        .and_then(|v| v.as_str())
+        .unwrap_or_default()
+        .to_string())
+}
+
+// ── Status ────────────────────────────────────────────────────────────────────
+
+pub async fn get_status(pool: &SqlitePool) -> Result<ScrobblerStatus, sqlx::Error> {
+    let config = get_config(pool).await?;
+    let counts: Vec<(String, i64)> =
+        sqlx::query_as("SELECT service, COUNT(*) FROM scrobble_queue GROUP BY service")
+            .fetch_all(pool)
+            .await?;
+    let queued = |service: ScrobbleService| {
+        counts
+            .iter()
+            .find(|(s, _)| s == service.as_str())
+            .map(|(_, n)| *n)
+            .unwrap_or(0)
+    };
+    let map = outcomes().lock().unwrap();
+    let outcome = |service| map.get(&service).cloned().unwrap_or_default();
+
+    let (lastfm_ok, lastfm_err) = outcome(ScrobbleService::LastFm);
+    let (lb_ok, lb_err) = outcome(ScrobbleService::ListenBrainz);
+    Ok(ScrobblerStatus {
+        lastfm: ServiceStatus {
+            enabled: config.lastfm.enabled,
+            authenticated: config.lastfm.session_key.is_some(),
+            username: config.lastfm.username.clone(),
+            queued: queued(ScrobbleService::LastFm),
+            last_success_at: lastfm_ok,
+            last_error: lastfm_err,
+        },
+        listenbrainz: ServiceStatus {
+            enabled: config.listenbrainz.enabled,
+            authenticated: config.listenbrainz.username.is_some(),
+            username: config.listenbrainz.username.clone(),
+            queued: queued(ScrobbleService::ListenBrainz),
+            last_success_at: lb_ok,
+            last_error: lb_err,
+        },
+    })
+}
+
+// ── DB helpers ────────────────────────────────────────────────────────────────
+
+pub async fn get_config(pool: &SqlitePool) -> Result<ScrobblerConfig, sqlx::Error> {
+    let row: Option<String> =
+        sqlx::query_scalar("SELECT config_json FROM scrobbler_config WHERE id = 1")
+            .fetch_optional(pool)
+            .await?;
+    Ok(row
+        .and_then(|j| serde_json::from_str(&j).ok())
+        .unwrap_or_default())
+}
+
+pub async fn save_config(pool: &SqlitePool, config: &ScrobblerConfig) -> Result<(), sqlx::Error> {
+    let json = serde_json::to_string(config).unwrap_or_else(|_| "{}".to_string());
+    sqlx::query(
+        "INSERT INTO scrobbler_config (id, config_json, updated_at) VALUES (1, ?, strftime('%s','now')) \
+         ON CONFLICT(id) DO UPDATE SET config_json = excluded.config_json, updated_at = excluded.updated_at",
+    )
+    .bind(json)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn play_rule_and_signature() {
+        let cfg = ScrobblerConfig::default();
+        // 3:20 track: half (100 s) must play.
+        assert!(!cfg.play_qualifies(99_000, 200_000));
+        assert!(cfg.play_qualifies(100_000, 200_000));
+        // 10 min track: 4 minutes is enough.
+        assert!(cfg.play_qualifies(240_000, 600_000));
+        // Short jingles never count.
+        assert!(!cfg.play_qualifies(20_000, 20_000));
+
+        let mut params = BTreeMap::new();
+        params.insert("method".to_string(), "auth.getToken".to_string());
+        params.insert("api_key".to_string(), "key".to_string());
+        // md5("api_keykeymethodauth.getTokensecret")
+        assert_eq!(
+            lastfm_signature(&params, "secret"),
+            format!("{:x}", Md5::digest(b"api_keykeymethodauth.getTokensecret"))
+        );
+    }
+}
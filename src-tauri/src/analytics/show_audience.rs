@@ -0,0 +1,403 @@
+/// Per-show audience attribution
+///
+/// Lays the show schedule over the listener-count time series. Every airing
+/// of a recurring show becomes a time window; listener snapshots from all
+/// encoders are summed minute by minute inside it, and the airings roll up
+/// into average/peak listeners, total listening hours (TLH) and growth
+/// against the weeks before the report range. One-time shows carry no date
+/// in the schedule and are not attributed.
+use chrono::{Datelike, Duration, Local, NaiveDate, TimeZone};
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use std::collections::{BTreeMap, HashMap};
+
+use crate::scheduler::show_scheduler::{self, Show};
+
+/// A snapshot older than this no longer says anything about the audience.
+const STALE_MS: i64 = 10 * 60 * 1000;
+/// Cap for shows scheduled to "run until the next show".
+const OPEN_ENDED_MAX_MINUTES: i64 = 6 * 60;
+const MINUTE_MS: i64 = 60 * 1000;
+
+// ── Data model ────────────────────────────────────────────────────────────────
+
+/// One scheduled airing of a show (UTC milliseconds).
+#[derive(Debug, Clone)]
+pub struct Airing {
+    pub show_id: i64,
+    pub show_name: String,
+    pub date: NaiveDate,
+    pub start_ms: i64,
+    pub end_ms: i64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AudienceStats {
+    pub average_listeners: f64,
+    pub peak_listeners: i64,
+    /// Unix ms of the peak minute
+    pub peak_at: Option<i64>,
+    /// Total listening hours
+    pub tlh: f64,
+    /// Minutes with listener data
+    pub covered_minutes: u32,
+}
+
+impl AudienceStats {
+    fn merge(&mut self, other: &AudienceStats) {
+        let listener_minutes = self.tlh * 60.0 + other.tlh * 60.0;
+        self.covered_minutes += other.covered_minutes;
+        self.tlh = listener_minutes / 60.0;
+        self.average_listeners = if self.covered_minutes > 0 {
+            listener_minutes / f64::from(self.covered_minutes)
+        } else {
+            0.0
+        };
+        if other.peak_at.is_some()
+            && (self.peak_at.is_none() || other.peak_listeners > self.peak_listeners)
+        {
+            self.peak_listeners = other.peak_listeners;
+            self.peak_at = other.peak_at;
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AiringAudience {
+    pub show_id: i64,
+    pub show_name: String,
+    pub starts_at: i64,
+    pub ends_at: i64,
+    #[serde(flatten)]
+    pub stats: AudienceStats,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WeeklyAudience {
+    /// Monday of the week (local date, YYYY-MM-DD)
+    pub week_start: String,
+    pub airings: u32,
+    #[serde(flatten)]
+    pub stats: AudienceStats,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShowAudienceSummary {
+    pub show_id: i64,
+    pub show_name: String,
+    pub airings: u32,
+    #[serde(flatten)]
+    pub stats: AudienceStats,
+    /// Average listeners over the comparison weeks before the range
+    pub baseline_average_listeners: Option<f64>,
+    pub growth_pct: Option<f64>,
+    /// Comparison weeks followed by the weeks of the range
+    pub weekly: Vec<WeeklyAudience>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShowAudienceReport {
+    pub shows: Vec<ShowAudienceSummary>,
+    pub airings: Vec<AiringAudience>,
+}
+
+// ── Schedule expansion ────────────────────────────────────────────────────────
+
+fn parse_start_time(start_time: &str) -> Option<(u32, u32)> {
+    let (h, m) = start_time.split_once(':')?;
+    let (h, m) = (h.trim().parse().ok()?, m.trim().parse().ok()?);
+    (h < 24 && m < 60).then_some((h, m))
+}
+
+/// Expand recurring shows into airings for every local date in
+/// `first..=last`. Open-ended shows run until the next airing of any show.
+pub fn airings(shows: &[Show], first: NaiveDate, last: NaiveDate) -> Vec<Airing> {
+    let mut out = Vec::new();
+    // One extra day so open-ended shows late on `last` can find their end.
+    let mut date = first;
+    while date <= last + Duration::days(1) {
+        for show in shows.iter().filter(|s| s.enabled && !s.days.is_empty()) {
+            let (Some(show_id), Some((h, m))) = (show.id, parse_start_time(&show.start_time))
+            else {
+                continue;
+            };
+            if !show.days.iter().any(|d| d.matches(date.weekday())) {
+                continue;
+            }
+            let Some(start) = date
+                .and_hms_opt(h, m, 0)
+                .and_then(|dt| Local.from_local_datetime(&dt).earliest())
+            else {
+                continue;
+            };
+            let start_ms = start.timestamp_millis();
+            out.push(Airing {
+                show_id,
+                show_name: show.name.clone(),
+                date,
+                start_ms,
+                end_ms: start_ms + i64::from(show.duration_minutes) * MINUTE_MS,
+            });
+        }
+        date += Duration::days(1);
+    }
+
+    out.sort_by_key(|a| (a.start_ms, a.show_id));
+    let starts: Vec<i64> = out.iter().map(|a| a.start_ms).collect();
+    for airing in out.iter_mut().filter(|a| a.end_ms == a.start_ms) {
+        let cap = airing.start_ms + OPEN_ENDED_MAX_MINUTES * MINUTE_MS;
+        let next = starts.iter().copied().find(|&s| s > airing.start_ms);
+        airing.end_ms = next.unwrap_or(cap).min(cap);
+    }
+    out.retain(|a| a.date <= last);
+    out
+}
+
+// ── Measurement ───────────────────────────────────────────────────────────────
+
+/// Listener samples per encoder, each sorted by timestamp (ms).
+pub type EncoderSamples = BTreeMap<i64, Vec<(i64, i64)>>;
+
+/// Sum the latest fresh sample of every encoder for each minute of the window.
+pub fn measure(samples: &EncoderSamples, start_ms: i64, end_ms: i64) -> AudienceStats {
+    let mut stats = AudienceStats::default();
+    let mut listener_minutes = 0i64;
+    let mut minute_end = start_ms + MINUTE_MS;
+    while minute_end <= end_ms {
+        let mut covered = false;
+        let mut total = 0i64;
+        for series in samples.values() {
+            let idx = series.partition_point(|(ts, _)| *ts <= minute_end);
+            if idx == 0 {
+                continue;
+            }
+            let (ts, count) = series[idx - 1];
+            if minute_end - ts <= STALE_MS {
+                covered = true;
+                total += count.max(0);
+            }
+        }
+        if covered {
+            stats.covered_minutes += 1;
+            listener_minutes += total;
+            if total > stats.peak_listeners || stats.peak_at.is_none() {
+                stats.peak_listeners = total;
+                stats.peak_at = Some(minute_end - MINUTE_MS);
+            }
+        }
+        minute_end += MINUTE_MS;
+    }
+    stats.tlh = listener_minutes as f64 / 60.0;
+    if stats.covered_minutes > 0 {
+        stats.average_listeners = listener_minutes as f64 / f64::from(stats.covered_minutes);
+    }
+    stats
+}
+
+fn week_start(date: NaiveDate) -> NaiveDate {
+    date - Duration::days(i64::from(date.weekday().num_days_from_monday()))
+}
+
+// ── Report ────────────────────────────────────────────────────────────────────
+
+async fn load_samples(
+    pool: &SqlitePool,
+    from_ms: i64,
+    to_ms: i64,
+) -> Result<EncoderSamples, sqlx::Error> {
+    let rows = sqlx::query_as::<_, (i64, i64, i64)>(
+        "SELECT encoder_id, snapshot_at * 1000, current_listeners FROM listener_snapshots \
+         WHERE snapshot_at >= ? AND snapshot_at <= ? ORDER BY snapshot_at ASC",
+    )
+    .bind(from_ms / 1000)
+    .bind(to_ms / 1000)
+    .fetch_all(pool)
+    .await?;
+    let mut samples = EncoderSamples::new();
+    for (encoder_id, ts, count) in rows {
+        samples.entry(encoder_id).or_default().push((ts, count));
+    }
+    Ok(samples)
+}
+
+/// Audience per show for the local dates `start_date..=end_date`, compared
+/// with the `compare_weeks` weeks before.
+pub async fn build_report(
+    pool: &SqlitePool,
+    start_date: &str,
+    end_date: &str,
+    show_id: Option<i64>,
+    compare_weeks: u32,
+) -> Result<ShowAudienceReport, sqlx::Error> {
+    let today = Local::now().date_naive();
+    let first = NaiveDate::parse_from_str(start_date, "%Y-%m-%d").unwrap_or(today);
+    let last = NaiveDate::parse_from_str(end_date, "%Y-%m-%d")
+        .unwrap_or(first)
+        .max(first);
+    let baseline_first = first - Duration::weeks(i64::from(compare_weeks));
+
+    let shows: Vec<Show> = show_scheduler::get_shows(pool)
+        .await?
+        .into_iter()
+        .filter(|s| show_id.is_none() || s.id == show_id)
+        .collect();
+    let all_airings = airings(&shows, baseline_first, last);
+    let (Some(from_ms), Some(to_ms)) = (
+        all_airings.iter().map(|a| a.start_ms).min(),
+        all_airings.iter().map(|a| a.end_ms).max(),
+    ) else {
+        return Ok(ShowAudienceReport {
+            shows: Vec::new(),
+            airings: Vec::new(),
+        });
+    };
+    let samples = load_samples(pool, from_ms - STALE_MS, to_ms).await?;
+
+    let mut summaries: HashMap<i64, ShowAudienceSummary> = HashMap::new();
+    let mut baselines: HashMap<i64, AudienceStats> = HashMap::new();
+    let mut weeks: HashMap<i64, BTreeMap<NaiveDate, WeeklyAudience>> = HashMap::new();
+    let mut in_range = Vec::new();
+
+    for airing in &all_airings {
+        let stats = measure(&samples, airing.start_ms, airing.end_ms);
+        let week = week_start(airing.date);
+        let weekly = weeks
+            .entry(airing.show_id)
+            .or_default()
+            .entry(week)
+            .or_insert_with(|| WeeklyAudience {
+                week_start: week.format("%Y-%m-%d").to_string(),
+                airings: 0,
+                stats: AudienceStats::default(),
+            });
+        weekly.airings += 1;
+        weekly.stats.merge(&stats);
+
+        if airing.date < first {
+            baselines.entry(airing.show_id).or_default().merge(&stats);
+            continue;
+        }
+        let summary = summaries
+            .entry(airing.show_id)
+            .or_insert_with(|| ShowAudienceSummary {
+                show_id: airing.show_id,
+                show_name: airing.show_name.clone(),
+                airings: 0,
+                stats: AudienceStats::default(),
+                baseline_average_listeners: None,
+                growth_pct: None,
+                weekly: Vec::new(),
+            });
+        summary.airings += 1;
+        summary.stats.merge(&stats);
+        in_range.push(AiringAudience {
+            show_id: airing.show_id,
+            show_name: airing.show_name.clone(),
+            starts_at: airing.start_ms,
+            ends_at: airing.end_ms,
+            stats,
+        });
+    }
+
+    let mut shows: Vec<ShowAudienceSummary> = summaries
+        .into_values()
+        .map(|mut summary| {
+            if let Some(base) = baselines
+                .get(&summary.show_id)
+                .filter(|b| b.covered_minutes > 0)
+            {
+                summary.baseline_average_listeners = Some(base.average_listeners);
+                if base.average_listeners > 0.0 {
+                    summary.growth_pct = Some(
+                        (summary.stats.average_listeners - base.average_listeners)
+                            / base.average_listeners
+                            * 100.0,
+                    );
+                }
+            }
+            summary.weekly = weeks
+                .remove(&summary.show_id)
+                .map(|w| w.into_values().collect())
+                .unwrap_or_default();
+            summary
+        })
+        .collect();
+    shows.sort_by(|a, b| {
+        b.stats
+            .tlh
+            .partial_cmp(&a.stats.tlh)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| a.show_name.cmp(&b.show_name))
+    });
+
+    Ok(ShowAudienceReport {
+        shows,
+        airings: in_range,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sums_encoders_and_ignores_stale_samples() {
+        let start = 1_000 * MINUTE_MS;
+        let mut samples = EncoderSamples::new();
+        // Encoder 1: 10 listeners throughout, encoder 2: 4 then 6 from minute 2.
+        samples.insert(1, vec![(start - MINUTE_MS, 10)]);
+        samples.insert(2, vec![(start, 4), (start + 2 * MINUTE_MS, 6)]);
+        let stats = measure(&samples, start, start + 4 * MINUTE_MS);
+        assert_eq!(stats.covered_minutes, 4);
+        assert_eq!(stats.peak_listeners, 16);
+        assert_eq!(stats.peak_at, Some(start + MINUTE_MS));
+        assert!((stats.tlh - (14.0 + 16.0 + 16.0 + 16.0) / 60.0).abs() < 1e-9);
+
+        // Nothing fresh once the last sample is older than the stale cut-off.
+        let late = start + 2 * MINUTE_MS + STALE_MS;
+        let stats = measure(&samples, late, late + 2 * MINUTE_MS);
+        assert_eq!(stats.covered_minutes, 0);
+        assert_eq!(stats.average_listeners, 0.0);
+    }
+
+    #[tokio::test]
+    async fn load_samples_reads_listener_snapshots_in_ms() {
+        let pool = sqlx::sqlite::SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .expect("in-memory sqlite pool");
+        sqlx::query(
+            r#"
+            CREATE TABLE listener_snapshots (
+                id INTEGER PRIMARY KEY,
+                encoder_id INTEGER NOT NULL,
+                snapshot_at INTEGER DEFAULT (strftime('%s','now')),
+                current_listeners INTEGER DEFAULT 0,
+                peak_listeners INTEGER DEFAULT 0,
+                unique_listeners INTEGER DEFAULT 0,
+                stream_bitrate INTEGER
+            )
+            "#,
+        )
+        .execute(&pool)
+        .await
+        .expect("create listener_snapshots table");
+        for (encoder_id, snapshot_at, listeners) in [(1, 990, 3), (1, 1_060, 5), (2, 1_000, 7)] {
+            sqlx::query(
+                "INSERT INTO listener_snapshots (encoder_id, snapshot_at, current_listeners, peak_listeners) \
+                 VALUES (?, ?, ?, 99)",
+            )
+            .bind(encoder_id)
+            .bind(snapshot_at)
+            .bind(listeners)
+            .execute(&pool)
+            .await
+            .expect("insert snapshot");
+        }
+
+        let samples = load_samples(&pool, 1_000_000, 1_060_000).await.unwrap();
+        assert_eq!(samples.get(&1), Some(&vec![(1_060_000, 5)]));
+        assert_eq!(samples.get(&2), Some(&vec![(1_000_000, 7)]));
+    }
+}
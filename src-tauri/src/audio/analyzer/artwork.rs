@@ -0,0 +1,561 @@
+/// Album artwork extraction and cache
+///
+/// Cover art is taken from, in order: the picture embedded in the audio
+/// file, SAM's `picture` column, then — when online lookup is enabled —
+/// MusicBrainz / Cover Art Archive and the iTunes Search API. The original
+/// image and resized JPEG variants are cached on disk under
+/// `<app data>/artwork/song_<id>_<mtime>/`, so a retagged or replaced file
+/// gets fresh art. A miss is remembered for a week before looking again.
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use symphonia::core::{
+    formats::FormatOptions,
+    io::MediaSourceStream,
+    meta::{MetadataOptions, StandardVisualKey, Visual},
+    probe::Hint,
+};
+
+/// Square bounding boxes of the cached JPEG variants.
+pub const VARIANT_SIZES: [u32; 3] = [96, 300, 600];
+const JPEG_QUALITY: u8 = 85;
+const MISS_RETRY_SECS: i64 = 7 * 24 * 3600;
+const MAX_IMAGE_BYTES: usize = 8 * 1024 * 1024;
+/// MusicBrainz allows one request per second per client.
+const MUSICBRAINZ_INTERVAL: Duration = Duration::from_millis(1100);
+pub(crate) const USER_AGENT: &str = concat!(
+    "DesiZoneBroadcaster/",
+    env!("CARGO_PKG_VERSION"),
+    " ( https://github.com/DesiZone-Network/desizone-broadcaster )"
+);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ArtworkConfig {
+    /// Fall back to MusicBrainz / iTunes when a file has no picture
+    pub online_lookup: bool,
+    /// Externally reachable base URL of the request API (e.g.
+    /// `https://radio.example.com:8095`), used to link art that only
+    /// exists locally. Empty = such art is not linked in pushes.
+    pub public_base_url: String,
+}
+
+impl Default for ArtworkConfig {
+    fn default() -> Self {
+        Self {
+            online_lookup: true,
+            public_base_url: String::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ArtworkSource {
+    Embedded,
+    SamPicture,
+    MusicBrainz,
+    Itunes,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SongArtwork {
+    pub song_id: i64,
+    pub source: ArtworkSource,
+    /// Cached image on disk
+    pub path: String,
+    pub mime_type: String,
+    /// Variant edge in pixels (None = original)
+    pub size: Option<u32>,
+    /// Where the image was downloaded from, for online sources
+    pub remote_url: Option<String>,
+}
+
+/// What is needed to find art for one song.
+#[derive(Debug, Clone, Default)]
+pub struct ArtworkLookup {
+    pub song_id: i64,
+    /// Local (already translated) audio file path
+    pub file_path: Option<String>,
+    pub artist: String,
+    pub title: String,
+    pub album: String,
+    pub picture: Option<String>,
+}
+
+impl ArtworkLookup {
+    /// Lookup for a SAM song, translating its path with the path translation rules.
+    pub async fn for_song(local: &SqlitePool, song: &crate::db::sam::SamSong) -> Self {
+        let translator = crate::db::path_rules::load_translator(local).await;
+        Self {
+            song_id: song.id,
+            file_path: Some(translator.translate(&song.filename)).filter(|p| !p.is_empty()),
+            artist: song.artist.clone(),
+            title: song.title.clone(),
+            album: song.album.clone(),
+            picture: song
+                .picture
+                .as_deref()
+                .filter(|p| !p.trim().is_empty())
+                .map(|p| translator.translate(p)),
+        }
+    }
+}
+
+/// Contents of `meta.json` in a song's cache directory.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct CacheMeta {
+    source: Option<ArtworkSource>,
+    /// File name of the original image inside the cache directory
+    original: Option<String>,
+    mime_type: String,
+    remote_url: Option<String>,
+    checked_at: i64,
+}
+
+// ── Cache ─────────────────────────────────────────────────────────────────────
+
+fn artwork_root() -> PathBuf {
+    PathBuf::from(crate::compute_app_data_dir()).join("artwork")
+}
+
+fn file_mtime_ms(path: Option<&str>) -> i64 {
+    path.and_then(|p| Path::new(p).metadata().ok())
+        .and_then(|m| m.modified().ok())
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or(0)
+}
+
+fn now_secs() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs() as i64
+}
+
+/// Smallest cached variant that covers `requested` pixels.
+pub fn variant_for(requested: u32) -> u32 {
+    VARIANT_SIZES
+        .iter()
+        .copied()
+        .find(|&s| s >= requested)
+        .unwrap_or(VARIANT_SIZES[VARIANT_SIZES.len() - 1])
+}
+
+/// Art for a song, extracting or fetching it on first use. `size` picks a
+/// JPEG variant (see `VARIANT_SIZES`); None returns the original image.
+pub async fn resolve(
+    lookup: &ArtworkLookup,
+    size: Option<u32>,
+    config: &ArtworkConfig,
+) -> Result<Option<SongArtwork>, String> {
+    let mtime = file_mtime_ms(lookup.file_path.as_deref());
+    let dir = artwork_root().join(format!("song_{}_{mtime}", lookup.song_id));
+    let meta_path = dir.join("meta.json");
+
+    let cached: Option<CacheMeta> = tokio::fs::read(&meta_path)
+        .await
+        .ok()
+        .and_then(|b| serde_json::from_slice(&b).ok());
+    let meta = match cached {
+        Some(m) if m.source.is_some() && m.original.is_some() => m,
+        Some(m) if m.source.is_none() && now_secs() - m.checked_at < MISS_RETRY_SECS => {
+            return Ok(None)
+        }
+        _ => {
+            let meta = populate(lookup, &dir, config).await?;
+            let json = serde_json::to_vec(&meta).map_err(|e| e.to_string())?;
+            tokio::fs::write(&meta_path, json)
+                .await
+                .map_err(|e| format!("Cannot write artwork cache: {e}"))?;
+            meta
+        }
+    };
+    let (Some(source), Some(original)) = (meta.source, meta.original.as_deref()) else {
+        return Ok(None);
+    };
+    let original = dir.join(original);
+
+    let (path, mime_type, size) = match size {
+        None => (original, meta.mime_type.clone(), None),
+        Some(requested) => {
+            let px = variant_for(requested);
+            let variant = dir.join(format!("{px}.jpg"));
+            if !variant.exists() {
+                let out = variant.clone();
+                tauri::async_runtime::spawn_blocking(move || render_variant(&original, px, &out))
+                    .await
+                    .map_err(|e| format!("Artwork worker join failed: {e}"))??;
+            }
+            (variant, "image/jpeg".to_string(), Some(px))
+        }
+    };
+    Ok(Some(SongArtwork {
+        song_id: lookup.song_id,
+        source,
+        path: path.to_string_lossy().to_string(),
+        mime_type,
+        size,
+        remote_url: meta.remote_url,
+    }))
+}
+
+/// Find art for a song and store the original in a fresh cache directory.
+async fn populate(
+    lookup: &ArtworkLookup,
+    dir: &Path,
+    config: &ArtworkConfig,
+) -> Result<CacheMeta, String> {
+    // Earlier versions of this file's cache are stale now.
+    let prefix = format!("song_{}_", lookup.song_id);
+    if let Ok(mut entries) = tokio::fs::read_dir(artwork_root()).await {
+        while let Ok(Some(entry)) = entries.next_entry().await {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if name.starts_with(&prefix) && entry.path() != dir {
+                let _ = tokio::fs::remove_dir_all(entry.path()).await;
+            }
+        }
+    }
+    tokio::fs::create_dir_all(dir)
+        .await
+        .map_err(|e| format!("Cannot create artwork cache: {e}"))?;
+
+    let mut found: Option<(Vec<u8>, ArtworkSource, Option<String>)> = None;
+    if let Some(path) = lookup.file_path.clone() {
+        found = tauri::async_runtime::spawn_blocking(move || extract_embedded(Path::new(&path)))
+            .await
+            .ok()
+            .flatten()
+            .map(|data| (data, ArtworkSource::Embedded, None));
+    }
+    if found.is_none() {
+        if let Some(picture) = lookup.picture.as_deref() {
+            found = tokio::fs::read(picture)
+                .await
+                .ok()
+                .filter(|d| sniff(d).is_some())
+                .map(|data| (data, ArtworkSource::SamPicture, None));
+        }
+    }
+    if found.is_none() && config.online_lookup && !lookup.artist.trim().is_empty() {
+        found = fetch_online(lookup).await;
+    }
+
+    let mut meta = CacheMeta {
+        checked_at: now_secs(),
+        ..Default::default()
+    };
+    if let Some((data, source, remote_url)) = found {
+        let Some((ext, mime)) = sniff(&data) else {
+            return Ok(meta);
+        };
+        let name = format!("original.{ext}");
+        tokio::fs::write(dir.join(&name), &data)
+            .await
+            .map_err(|e| format!("Cannot write artwork: {e}"))?;
+        meta.source = Some(source);
+        meta.original = Some(name);
+        meta.mime_type = mime.to_string();
+        meta.remote_url = remote_url;
+    }
+    Ok(meta)
+}
+
+/// Drop cached art for one song, or all of it.
+pub async fn clear_cache(song_id: Option<i64>) -> Result<(), String> {
+    let root = artwork_root();
+    let Some(song_id) = song_id else {
+        return match tokio::fs::remove_dir_all(&root).await {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.to_string()),
+            _ => Ok(()),
+        };
+    };
+    let prefix = format!("song_{song_id}_");
+    if let Ok(mut entries) = tokio::fs::read_dir(&root).await {
+        while let Ok(Some(entry)) = entries.next_entry().await {
+            if entry.file_name().to_string_lossy().starts_with(&prefix) {
+                tokio::fs::remove_dir_all(entry.path())
+                    .await
+                    .map_err(|e| e.to_string())?;
+            }
+        }
+    }
+    Ok(())
+}
+
+// ── Extraction / resizing ─────────────────────────────────────────────────────
+
+/// Image type from magic bytes: (extension, MIME type).
+fn sniff(data: &[u8]) -> Option<(&'static str, &'static str)> {
+    if data.starts_with(&[0xFF, 0xD8, 0xFF]) {
+        Some(("jpg", "image/jpeg"))
+    } else if data.starts_with(b"\x89PNG\r\n\x1a\n") {
+        Some(("png", "image/png"))
+    } else if data.len() > 12 && &data[..4] == b"RIFF" && &data[8..12] == b"WEBP" {
+        Some(("webp", "image/webp"))
+    } else {
+        None
+    }
+}
+
+fn best_visual(visuals: &[Visual]) -> Option<Vec<u8>> {
+    visuals
+        .iter()
+        .find(|v| v.usage == Some(StandardVisualKey::FrontCover))
+        .or_else(|| visuals.first())
+        .map(|v| v.data.to_vec())
+        .filter(|d| sniff(d).is_some())
+}
+
+/// Front cover (or first picture) embedded in an audio file's tags.
+fn extract_embedded(path: &Path) -> Option<Vec<u8>> {
+    let file = File::open(path).ok()?;
+    let mss = MediaSourceStream::new(Box::new(file), Default::default());
+    let mut hint = Hint::new();
+    if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
+        hint.with_extension(ext);
+    }
+    let mut probed = symphonia::default::get_probe()
+        .format(
+            &hint,
+            mss,
+            &FormatOptions::default(),
+            &MetadataOptions::default(),
+        )
+        .ok()?;
+    // ID3v2 ahead of the container, then the container's own tags.
+    if let Some(found) = probed
+        .metadata
+        .get()
+        .and_then(|m| m.current().and_then(|r| best_visual(r.visuals())))
+    {
+        return Some(found);
+    }
+    let metadata = probed.format.metadata();
+    metadata.current().and_then(|r| best_visual(r.visuals()))
+}
+
+fn render_variant(original: &Path, px: u32, out: &Path) -> Result<(), String> {
+    let bytes = std::fs::read(original).map_err(|e| format!("Cannot read artwork: {e}"))?;
+    let img = image::load_from_memory(&bytes).map_err(|e| format!("Cannot decode artwork: {e}"))?;
+    let img = if img.width() > px || img.height() > px {
+        img.thumbnail(px, px)
+    } else {
+        img
+    };
+    let mut buf = Vec::new();
+    image::codecs::jpeg::JpegEncoder::new_with_quality(&mut buf, JPEG_QUALITY)
+        .encode_image(&img.to_rgb8())
+        .map_err(|e| format!("Cannot encode artwork: {e}"))?;
+    let tmp = out.with_extension("jpg.tmp");
+    std::fs::write(&tmp, buf).map_err(|e| format!("Cannot write artwork: {e}"))?;
+    std::fs::rename(&tmp, out).map_err(|e| format!("Cannot write artwork: {e}"))
+}
+
+// ── Online fallback ───────────────────────────────────────────────────────────
+
+fn http_client() -> Option<reqwest::Client> {
+    reqwest::Client::builder()
+        .user_agent(USER_AGENT)
+        .timeout(Duration::from_secs(15))
+        .build()
+        .ok()
+}
+
+async fn download_image(client: &reqwest::Client, url: &str) -> Option<Vec<u8>> {
+    let resp = client.get(url).send().await.ok()?;
+    if !resp.status().is_success() {
+        return None;
+    }
+    let data = resp.bytes().await.ok()?;
+    (data.len() <= MAX_IMAGE_BYTES && sniff(&data).is_some()).then(|| data.to_vec())
+}
+
+async fn fetch_online(lookup: &ArtworkLookup) -> Option<(Vec<u8>, ArtworkSource, Option<String>)> {
+    let client = http_client()?;
+    if !lookup.album.trim().is_empty() {
+        if let Some(url) = musicbrainz_cover_url(&client, lookup).await {
+            if let Some(data) = download_image(&client, &url).await {
+                return Some((data, ArtworkSource::MusicBrainz, Some(url)));
+            }
+        }
+    }
+    let url = itunes_cover_url(&client, lookup).await?;
+    let data = download_image(&client, &url).await?;
+    Some((data, ArtworkSource::Itunes, Some(url)))
+}
+
+/// Space MusicBrainz calls out to its published rate limit.
+pub(crate) async fn musicbrainz_throttle() {
+    static LAST: tokio::sync::Mutex<Option<Instant>> = tokio::sync::Mutex::const_new(None);
+    let mut last = LAST.lock().await;
+    if let Some(at) = *last {
+        let since = at.elapsed();
+        if since < MUSICBRAINZ_INTERVAL {
+            tokio::time::sleep(MUSICBRAINZ_INTERVAL - since).await;
+        }
+    }
+    *last = Some(Instant::now());
+}
+
+pub(crate) fn lucene_quote(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Cover Art Archive front image of the best-matching release.
+async fn musicbrainz_cover_url(client: &reqwest::Client, lookup: &ArtworkLookup) -> Option<String> {
+    musicbrainz_throttle().await;
+    let query = format!(
+        "release:{} AND artist:{}",
+        lucene_quote(lookup.album.trim()),
+        lucene_quote(lookup.artist.trim())
+    );
+    let body: serde_json::Value = client
+        .get("https://musicbrainz.org/ws/2/release/")
+        .query(&[("query", query.as_str()), ("fmt", "json"), ("limit", "5")])
+        .send()
+        .await
+        .ok()?
+        .json()
+        .await
+        .ok()?;
+    let releases = body.get("releases")?.as_array()?;
+    for release in releases.iter().take(3) {
+        let score = release.get("score").and_then(|s| s.as_i64()).unwrap_or(0);
+        let Some(id) = release.get("id").and_then(|v| v.as_str()) else {
+            continue;
+        };
+        if score < 90 {
+            break;
+        }
+        let url = format!("https://coverartarchive.org/release/{id}/front-500");
+        // Releases without art answer 404; a HEAD keeps the check cheap.
+        if let Ok(resp) = client.head(&url).send().await {
+            if resp.status().is_success() {
+                return Some(url);
+            }
+        }
+    }
+    None
+}
+
+async fn itunes_cover_url(client: &reqwest::Client, lookup: &ArtworkLookup) -> Option<String> {
+    let (term, entity) = if lookup.album.trim().is_empty() {
+        (format!("{} {}", lookup.artist, lookup.title), "song")
+    } else {
+        (format!("{} {}", lookup.artist, lookup.album), "album")
+    };
+    let body: serde_json::Value = client
+        .get("https://itunes.apple.com/search")
+        .query(&[
+            ("term", term.trim()),
+            ("entity", entity),
+            ("media", "music"),
+            ("limit", "1"),
+        ])
+        .send()
+        .await
+        .ok()?
+        .json()
+        .await
+        .ok()?;
+    let small = body
+        .get("results")?
+        .as_array()?
+        .first()?
+        .get("artworkUrl100")?
+        .as_str()?;
+    Some(small.replace("100x100bb", "600x600bb"))
+}
+
+// ── Public links ──────────────────────────────────────────────────────────────
+
+/// Link a listener's client can load: the web source for downloaded art,
+/// otherwise the request API's `/api/artwork/<song_id>` under
+/// `public_base_url`.
+pub fn public_url(art: &SongArtwork, config: &ArtworkConfig) -> Option<String> {
+    if let Some(url) = &art.remote_url {
+        return Some(url.clone());
+    }
+    let base = config.public_base_url.trim().trim_end_matches('/');
+    (!base.is_empty()).then(|| format!("{base}/api/artwork/{}?size=600", art.song_id))
+}
+
+/// Resolve art for `lookup` and return its public link, if it has one.
+pub async fn public_url_for(local: &SqlitePool, lookup: &ArtworkLookup) -> Option<String> {
+    let config = get_config(local).await.unwrap_or_default();
+    match resolve(lookup, Some(600), &config).await {
+        Ok(Some(art)) => public_url(&art, &config),
+        Ok(None) => None,
+        Err(e) => {
+            log::debug!("Artwork lookup failed (song_id={}): {e}", lookup.song_id);
+            None
+        }
+    }
+}
+
+// ── DB helpers ────────────────────────────────────────────────────────────────
+
+pub async fn get_config(pool: &SqlitePool) -> Result<ArtworkConfig, sqlx::Error> {
+    let row: Option<String> =
+        sqlx::query_scalar("SELECT config_json FROM artwork_config WHERE id = 1")
+            .fetch_optional(pool)
+            .await?;
+    Ok(row
+        .and_then(|j| serde_json::from_str(&j).ok())
+        .unwrap_or_default())
+}
+
+pub async fn save_config(pool: &SqlitePool, config: &ArtworkConfig) -> Result<(), sqlx::Error> {
+    let json = serde_json::to_string(config).unwrap_or_else(|_| "{}".to_string());
+    sqlx::query(
+        "INSERT INTO artwork_config (id, config_json, updated_at) VALUES (1, ?, strftime('%s','now')) \
+         ON CONFLICT(id) DO UPDATE SET config_json = excluded.config_json, updated_at = excluded.updated_at",
+    )
+    .bind(json)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn variants_and_links() {
+        assert_eq!(variant_for(50), 96);
+        assert_eq!(variant_for(300), 300);
+        assert_eq!(variant_for(2000), 600);
+        assert_eq!(
+            sniff(&[0xFF, 0xD8, 0xFF, 0xE0]),
+            Some(("jpg", "image/jpeg"))
+        );
+        assert_eq!(sniff(b"GIF89a"), None);
+
+        let mut art = SongArtwork {
+            song_id: 7,
+            source: ArtworkSource::Embedded,
+            path: String::new(),
+            mime_type: "image/jpeg".to_string(),
+            size: Some(600),
+            remote_url: None,
+        };
+        let mut config = ArtworkConfig::default();
+        assert_eq!(public_url(&art, &config), None);
+        config.public_base_url = "https://radio.example.com/".to_string();
+        assert_eq!(
+            public_url(&art, &config).as_deref(),
+            Some("https://radio.example.com/api/artwork/7?size=600")
+        );
+        art.remote_url = Some("https://coverartarchive.org/x".to_string());
+        assert_eq!(
+            public_url(&art, &config).as_deref(),
+            Some("https://coverartarchive.org/x")
+        );
+    }
+}
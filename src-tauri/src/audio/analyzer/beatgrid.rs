@@ -22,25 +22,30 @@ pub struct BeatGridComputed {
 
 pub fn analyze_file(path: &Path) -> Result<BeatGridComputed, String> {
     let (samples, sample_rate) = decode_mono(path)?;
+    Ok(analyze_samples(&samples, sample_rate))
+}
+
+/// Beat grid for already-decoded mono samples.
+pub fn analyze_samples(samples: &[f32], sample_rate: u32) -> BeatGridComputed {
     if samples.len() < 2048 || sample_rate == 0 {
-        return Ok(BeatGridComputed {
+        return BeatGridComputed {
             bpm: 120.0,
             first_beat_ms: 0,
             confidence: 0.0,
             beat_times_ms: vec![],
-        });
+        };
     }
 
     let env_sr = 200.0_f32;
     let hop = ((sample_rate as f32 / env_sr).round() as usize).max(1);
-    let envelope = build_envelope(&samples, hop);
+    let envelope = build_envelope(samples, hop);
     if envelope.len() < 64 {
-        return Ok(BeatGridComputed {
+        return BeatGridComputed {
             bpm: 120.0,
             first_beat_ms: 0,
             confidence: 0.0,
             beat_times_ms: vec![],
-        });
+        };
     }
 
     let onset = onset_curve(&envelope);
@@ -91,12 +96,12 @@ pub fn analyze_file(path: &Path) -> Result<BeatGridComputed, String> {
     let denom = onset.iter().map(|v| v * v).sum::<f32>().max(1e-6);
     let confidence = (best_score / denom).clamp(0.0, 1.0);
 
-    Ok(BeatGridComputed {
+    BeatGridComputed {
         bpm,
         first_beat_ms,
         confidence,
         beat_times_ms,
-    })
+    }
 }
 
 pub fn quantize_position_ms(position_ms: i64, beat_times_ms: &[i64], mode: CueQuantize) -> i64 {
@@ -108,6 +113,56 @@ pub fn quantize_position_ms(position_ms: i64, beat_times_ms: &[i64], mode: CueQu
     nearest_value(position_ms.max(0), &candidates)
 }
 
+/// First `mode` grid boundary at or after `position_ms`, if the grid reaches
+/// that far.
+pub fn next_grid_position_ms(
+    position_ms: i64,
+    beat_times_ms: &[i64],
+    mode: CueQuantize,
+) -> Option<i64> {
+    if matches!(mode, CueQuantize::Off) {
+        return None;
+    }
+    grid_candidates(beat_times_ms, mode)
+        .into_iter()
+        .find(|&t| t >= position_ms)
+}
+
+/// Loop of `beats` beats starting on the first beat at or after `position_ms`.
+///
+/// The deck can only loop audio it plays through, so the loop never starts
+/// behind the playhead. Past the last analysed beat the grid is extended at
+/// its average spacing.
+pub fn auto_loop_range_ms(
+    position_ms: i64,
+    beat_times_ms: &[i64],
+    beats: u32,
+) -> Option<(i64, i64)> {
+    if beat_times_ms.len() < 2 || beats == 0 {
+        return None;
+    }
+    let first = *beat_times_ms.first()?;
+    let last = *beat_times_ms.last()?;
+    let period = (last - first) as f64 / (beat_times_ms.len() - 1) as f64;
+    if period <= 0.0 {
+        return None;
+    }
+    let beat_at = |index: usize| -> i64 {
+        beat_times_ms.get(index).copied().unwrap_or_else(|| {
+            let beyond = (index - (beat_times_ms.len() - 1)) as f64;
+            last + (beyond * period).round() as i64
+        })
+    };
+    let start_index = match beat_times_ms.iter().position(|&t| t >= position_ms) {
+        Some(index) => index,
+        None => {
+            let beyond = ((position_ms - last) as f64 / period).ceil() as usize;
+            beat_times_ms.len() - 1 + beyond
+        }
+    };
+    Some((beat_at(start_index), beat_at(start_index + beats as usize)))
+}
+
 fn grid_candidates(beat_times_ms: &[i64], mode: CueQuantize) -> Vec<i64> {
     match mode {
         CueQuantize::Off | CueQuantize::Beat1 => beat_times_ms.to_vec(),
@@ -186,7 +241,7 @@ fn autocorr_at_lag(signal: &[f32], lag: usize) -> f32 {
     s
 }
 
-fn decode_mono(path: &Path) -> Result<(Vec<f32>, u32), String> {
+pub(crate) fn decode_mono(path: &Path) -> Result<(Vec<f32>, u32), String> {
     let file = File::open(path).map_err(|e| format!("Cannot open {}: {e}", path.display()))?;
     let mss = MediaSourceStream::new(Box::new(file), Default::default());
     let mut hint = Hint::new();
@@ -316,4 +371,31 @@ mod tests {
         let snapped = quantize_position_ms(380, &beats, CueQuantize::BeatQuarter);
         assert_eq!(snapped, 500);
     }
+
+    #[test]
+    fn next_grid_position_respects_subdivision() {
+        let beats = vec![0, 1000, 2000];
+        assert_eq!(
+            next_grid_position_ms(260, &beats, CueQuantize::Beat1),
+            Some(1000)
+        );
+        assert_eq!(
+            next_grid_position_ms(260, &beats, CueQuantize::BeatHalf),
+            Some(500)
+        );
+        assert_eq!(
+            next_grid_position_ms(2001, &beats, CueQuantize::Beat1),
+            None
+        );
+        assert_eq!(next_grid_position_ms(260, &beats, CueQuantize::Off), None);
+    }
+
+    #[test]
+    fn auto_loop_starts_on_next_beat_and_extends_grid() {
+        let beats = vec![0, 500, 1000, 1500];
+        assert_eq!(auto_loop_range_ms(500, &beats, 2), Some((500, 1500)));
+        assert_eq!(auto_loop_range_ms(620, &beats, 4), Some((1000, 3000)));
+        assert_eq!(auto_loop_range_ms(1600, &beats, 1), Some((2000, 2500)));
+        assert_eq!(auto_loop_range_ms(0, &beats[..1], 1), None);
+    }
 }
@@ -0,0 +1,241 @@
+/// Automatic transition cue detection
+///
+/// Works on the loudness contour of the decoded track: short-window RMS in
+/// dBFS, smoothed over about a second. The song's "body" level is a high
+/// percentile of that contour. The intro ends where the contour first rises
+/// to the body level (drums or vocals coming in), and the outro starts after
+/// the last point at body level (the energy drop into the fade or tag).
+/// First/last sound bound the audible part of the file. Points land on the
+/// beat grid when one is available.
+use serde::{Deserialize, Serialize};
+
+use crate::db::local::{CueKind, CuePoint};
+
+/// Contour resolution.
+const WINDOW_MS: u32 = 50;
+/// Smoothing span for the intro/outro contour.
+const SMOOTH_MS: u32 = 1_000;
+/// Below this the track is treated as silence.
+const SILENCE_DB: f32 = -48.0;
+/// How far under the body level still counts as "full energy".
+const BODY_MARGIN_DB: f32 = 4.0;
+/// Percentile of the contour taken as the body level.
+const BODY_PERCENTILE: f32 = 0.75;
+/// Intro/outro are only looked for in this share of the track at each end.
+const MAX_SECTION_SHARE: f32 = 0.45;
+/// Shorter intros/outros are not worth a cue.
+const MIN_SECTION_MS: u64 = 2_000;
+
+/// Label prefix marking a cue as written by the detector. Cues without it
+/// were placed by hand and are never overwritten.
+pub const AUTO_CUE_LABEL_PREFIX: &str = "Auto: ";
+const AUTO_CUE_COLOR: &str = "#22c55e";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DetectedCues {
+    pub first_sound_ms: Option<u64>,
+    pub intro_end_ms: Option<u64>,
+    pub outro_start_ms: Option<u64>,
+    pub last_sound_ms: Option<u64>,
+}
+
+impl DetectedCues {
+    /// `(cue name, names the planner also accepts for it, position)`; the
+    /// names are the ones `load_transition_markers` looks up.
+    pub fn entries(&self) -> Vec<(&'static str, &'static [&'static str], u64)> {
+        [
+            (
+                "first_sound",
+                &["first_sound", "start"][..],
+                self.first_sound_ms,
+            ),
+            ("intro_end", &["intro_end"][..], self.intro_end_ms),
+            (
+                "outro_start",
+                &["outro_start", "outro"][..],
+                self.outro_start_ms,
+            ),
+            ("last_sound", &["last_sound", "end"][..], self.last_sound_ms),
+        ]
+        .into_iter()
+        .filter_map(|(name, aliases, pos)| pos.map(|p| (name, aliases, p)))
+        .collect()
+    }
+}
+
+fn rms_db(chunk: &[f32]) -> f32 {
+    let sum: f32 = chunk.iter().map(|s| s * s).sum();
+    let rms = (sum / chunk.len().max(1) as f32).sqrt();
+    20.0 * rms.max(1e-6).log10()
+}
+
+fn smooth(values: &[f32], span: usize) -> Vec<f32> {
+    let half = span / 2;
+    (0..values.len())
+        .map(|i| {
+            let lo = i.saturating_sub(half);
+            let hi = (i + half + 1).min(values.len());
+            values[lo..hi].iter().sum::<f32>() / (hi - lo) as f32
+        })
+        .collect()
+}
+
+fn snap(position_ms: u64, beat_times_ms: &[i64]) -> u64 {
+    beat_times_ms
+        .iter()
+        .min_by_key(|b| (**b - position_ms as i64).abs())
+        .map(|b| (*b).max(0) as u64)
+        .unwrap_or(position_ms)
+}
+
+/// Detect cues in mono `samples`. `beat_times_ms` (may be empty) is used to
+/// snap the intro end and outro start onto beats.
+pub fn detect(samples: &[f32], sample_rate: u32, beat_times_ms: &[i64]) -> DetectedCues {
+    let window = (sample_rate as usize * WINDOW_MS as usize / 1000).max(1);
+    if sample_rate == 0 || samples.len() < window * 20 {
+        return DetectedCues::default();
+    }
+    let contour: Vec<f32> = samples.chunks(window).map(rms_db).collect();
+    let to_ms = |i: usize| i as u64 * u64::from(WINDOW_MS);
+    let duration_ms = to_ms(contour.len());
+
+    let Some(first) = contour.iter().position(|db| *db > SILENCE_DB) else {
+        return DetectedCues::default();
+    };
+    let last = contour
+        .iter()
+        .rposition(|db| *db > SILENCE_DB)
+        .unwrap_or(first);
+    let mut cues = DetectedCues {
+        first_sound_ms: Some(to_ms(first)),
+        last_sound_ms: Some(to_ms(last + 1).min(duration_ms)),
+        ..DetectedCues::default()
+    };
+
+    let smoothed = smooth(&contour, (SMOOTH_MS / WINDOW_MS) as usize);
+    let mut audible: Vec<f32> = smoothed[first..=last]
+        .iter()
+        .copied()
+        .filter(|db| *db > SILENCE_DB)
+        .collect();
+    if audible.is_empty() {
+        return cues;
+    }
+    audible.sort_by(|a, b| a.total_cmp(b));
+    let body_db = audible[((audible.len() - 1) as f32 * BODY_PERCENTILE) as usize];
+    let full = |db: f32| db >= body_db - BODY_MARGIN_DB;
+
+    let section = (contour.len() as f32 * MAX_SECTION_SHARE) as usize;
+    if let Some(rise) = (first..=last.min(first + section)).find(|i| full(smoothed[*i])) {
+        let ms = to_ms(rise);
+        if ms >= to_ms(first) + MIN_SECTION_MS {
+            cues.intro_end_ms = Some(snap(ms, beat_times_ms));
+        }
+    }
+    let outro_floor = last.saturating_sub(section).max(first);
+    if let Some(drop) = (outro_floor..=last).rev().find(|i| full(smoothed[*i])) {
+        let ms = to_ms(drop + 1);
+        if ms + MIN_SECTION_MS <= to_ms(last + 1) {
+            cues.outro_start_ms = Some(snap(ms, beat_times_ms));
+        }
+    }
+    cues
+}
+
+/// Cue points to write for `detected`, leaving hand-placed cues alone: a
+/// cue is skipped when the song already has a non-hot cue under any of its
+/// names that the detector did not write.
+pub fn cue_points_to_write(
+    song_id: i64,
+    detected: &DetectedCues,
+    existing: &[CuePoint],
+) -> Vec<CuePoint> {
+    detected
+        .entries()
+        .into_iter()
+        .filter(|(_, aliases, _)| {
+            !existing.iter().any(|c| {
+                c.cue_kind != CueKind::Hotcue
+                    && aliases.iter().any(|a| c.name.eq_ignore_ascii_case(a))
+                    && !c.label.starts_with(AUTO_CUE_LABEL_PREFIX)
+            })
+        })
+        .map(|(name, _, position_ms)| CuePoint {
+            id: None,
+            song_id,
+            name: name.to_string(),
+            position_ms: position_ms as i64,
+            cue_kind: CueKind::Transition,
+            slot: None,
+            label: format!("{AUTO_CUE_LABEL_PREFIX}{}", name.replace('_', " ")),
+            color_hex: AUTO_CUE_COLOR.to_string(),
+            updated_at: None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 1 kHz-ish square wave at `level` for `secs` seconds.
+    fn tone(level: f32, secs: f32, rate: u32) -> Vec<f32> {
+        (0..(secs * rate as f32) as usize)
+            .map(|i| if (i / 4) % 2 == 0 { level } else { -level })
+            .collect()
+    }
+
+    #[test]
+    fn finds_intro_rise_and_outro_drop() {
+        let rate = 8_000;
+        let mut samples = tone(0.0, 1.0, rate);
+        samples.extend(tone(0.05, 10.0, rate));
+        // Full energy from 11s to 51s.
+        samples.extend(tone(0.6, 40.0, rate));
+        samples.extend(tone(0.05, 8.0, rate));
+        samples.extend(tone(0.0, 1.0, rate));
+
+        let cues = detect(&samples, rate, &[]);
+        assert_eq!(cues.first_sound_ms, Some(1_000));
+        assert_eq!(cues.last_sound_ms, Some(59_000));
+        let intro = cues.intro_end_ms.unwrap();
+        assert!((11_000..=12_000).contains(&intro), "intro_end {intro}");
+        let outro = cues.outro_start_ms.unwrap();
+        assert!((50_000..=51_000).contains(&outro), "outro_start {outro}");
+
+        let snapped = detect(&samples, rate, &[0, 10_000, 12_000, 50_000, 52_000]);
+        assert!(matches!(snapped.intro_end_ms, Some(10_000) | Some(12_000)));
+    }
+
+    #[test]
+    fn hand_placed_cues_are_kept() {
+        let detected = DetectedCues {
+            first_sound_ms: Some(100),
+            intro_end_ms: Some(8_000),
+            outro_start_ms: Some(180_000),
+            last_sound_ms: None,
+        };
+        let manual = CuePoint {
+            id: Some(1),
+            song_id: 7,
+            name: "outro".to_string(),
+            position_ms: 175_000,
+            cue_kind: CueKind::Memory,
+            slot: None,
+            label: "outro".to_string(),
+            color_hex: String::new(),
+            updated_at: None,
+        };
+        let auto = CuePoint {
+            name: "intro_end".to_string(),
+            label: format!("{AUTO_CUE_LABEL_PREFIX}intro end"),
+            cue_kind: CueKind::Transition,
+            ..manual.clone()
+        };
+        let names: Vec<String> = cue_points_to_write(7, &detected, &[manual, auto])
+            .into_iter()
+            .map(|c| c.name)
+            .collect();
+        assert_eq!(names, vec!["first_sound", "intro_end"]);
+    }
+}
@@ -0,0 +1,438 @@
+/// Acoustic fingerprints and duplicate detection
+///
+/// The first minute of each file is fingerprinted with Chromaprint (via
+/// `rusty-chromaprint`) and cached in `song_fingerprints` per file mtime;
+/// fingerprinting runs as an analysis queue job. Duplicates are found by
+/// indexing a few hundred fingerprint items per song, comparing only songs
+/// that share items and have about the same length, and grouping pairs whose
+/// fingerprints agree on enough bits (allowing for a little offset, e.g.
+/// different leading silence).
+///
+/// The groups from the last search are kept in memory so rotation can treat
+/// the songs in a group as one for its song separation rules.
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::sync::{OnceLock, RwLock};
+
+use rusty_chromaprint::{Configuration, Fingerprinter};
+use serde::{Deserialize, Serialize};
+use sqlx::{MySqlPool, Row, SqlitePool};
+
+use super::jobs::{self, AnalysisKind};
+
+/// Audio fingerprinted per file.
+const FINGERPRINT_SECS: usize = 60;
+/// Items compared across offsets (about 1.2 s either way).
+const MAX_OFFSET: isize = 10;
+/// Least overlap, in items, for a comparison to count.
+const MIN_OVERLAP: usize = 40;
+/// Items of each fingerprint put in the candidate index.
+const INDEX_ITEMS: usize = 240;
+/// Item values shared by this many songs (silence, tones) are not indexed.
+const MAX_POSTING: usize = 50;
+/// Shared index items needed before two songs are compared.
+const MIN_SHARED_ITEMS: usize = 3;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DuplicateSearchOptions {
+    /// Share of fingerprint bits that must agree, 0.5 (random) – 1.0
+    pub min_similarity: f64,
+    /// Largest length difference between duplicates
+    pub max_duration_diff_ms: i64,
+}
+
+impl Default for DuplicateSearchOptions {
+    fn default() -> Self {
+        Self {
+            min_similarity: 0.85,
+            max_duration_diff_ms: 3_000,
+        }
+    }
+}
+
+/// A stored fingerprint.
+#[derive(Debug, Clone)]
+pub struct SongPrint {
+    pub song_id: i64,
+    pub file_path: String,
+    pub duration_ms: i64,
+    pub items: Vec<u32>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DuplicateSong {
+    pub song_id: i64,
+    pub file_path: String,
+    pub duration_ms: i64,
+    /// From SAM when connected; empty otherwise
+    pub artist: String,
+    pub title: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DuplicateGroup {
+    pub songs: Vec<DuplicateSong>,
+    /// Lowest similarity among the matched pairs in the group
+    pub similarity: f64,
+}
+
+/// Chromaprint items for mono `samples`, first minute only.
+pub fn fingerprint_samples(samples: &[f32], sample_rate: u32) -> Result<Vec<u32>, String> {
+    let config = Configuration::preset_test2();
+    let mut printer = Fingerprinter::new(&config);
+    printer
+        .start(sample_rate, 1)
+        .map_err(|e| format!("Cannot start fingerprint: {e:?}"))?;
+    let len = samples.len().min(sample_rate as usize * FINGERPRINT_SECS);
+    let pcm: Vec<i16> = samples[..len]
+        .iter()
+        .map(|s| (s.clamp(-1.0, 1.0) * i16::MAX as f32) as i16)
+        .collect();
+    printer.consume(&pcm);
+    printer.finish();
+    Ok(printer.fingerprint().to_vec())
+}
+
+/// Decode and fingerprint `path` (blocking). Returns the items and the
+/// file's length in ms.
+pub fn analyze_file(path: &Path) -> Result<(Vec<u32>, i64), String> {
+    let (samples, sample_rate) = super::beatgrid::decode_mono(path)?;
+    let duration_ms = samples.len() as i64 * 1000 / sample_rate.max(1) as i64;
+    Ok((fingerprint_samples(&samples, sample_rate)?, duration_ms))
+}
+
+/// Share of bits that agree at the best offset, 0.0–1.0.
+pub fn similarity(a: &[u32], b: &[u32]) -> f64 {
+    let mut best = 0.0_f64;
+    for offset in -MAX_OFFSET..=MAX_OFFSET {
+        let (a, b) = if offset >= 0 {
+            (a, b.get(offset as usize..).unwrap_or_default())
+        } else {
+            (a.get((-offset) as usize..).unwrap_or_default(), b)
+        };
+        let overlap = a.len().min(b.len());
+        if overlap < MIN_OVERLAP {
+            continue;
+        }
+        let errors: u32 = a.iter().zip(b).map(|(x, y)| (x ^ y).count_ones()).sum();
+        best = best.max(1.0 - errors as f64 / (32 * overlap) as f64);
+    }
+    best
+}
+
+fn find(parent: &mut [usize], i: usize) -> usize {
+    let mut root = i;
+    while parent[root] != root {
+        root = parent[root];
+    }
+    let mut i = i;
+    while parent[i] != root {
+        let next = parent[i];
+        parent[i] = root;
+        i = next;
+    }
+    root
+}
+
+/// Group fingerprints that are likely the same recording. Groups are
+/// ordered by their lowest song id, songs within a group by id.
+pub fn find_duplicates(
+    prints: &[SongPrint],
+    options: &DuplicateSearchOptions,
+) -> Vec<DuplicateGroup> {
+    let mut index: HashMap<u32, Vec<usize>> = HashMap::new();
+    for (i, print) in prints.iter().enumerate() {
+        let items: HashSet<u32> = print.items.iter().take(INDEX_ITEMS).copied().collect();
+        for item in items {
+            index.entry(item).or_default().push(i);
+        }
+    }
+    let mut shared: HashMap<(usize, usize), usize> = HashMap::new();
+    for posting in index
+        .values()
+        .filter(|p| p.len() > 1 && p.len() <= MAX_POSTING)
+    {
+        for (n, &a) in posting.iter().enumerate() {
+            for &b in &posting[n + 1..] {
+                *shared.entry((a, b)).or_default() += 1;
+            }
+        }
+    }
+
+    let mut parent: Vec<usize> = (0..prints.len()).collect();
+    let mut pair_similarity: Vec<(usize, f64)> = Vec::new();
+    for ((a, b), count) in shared {
+        if count < MIN_SHARED_ITEMS
+            || (prints[a].duration_ms - prints[b].duration_ms).abs() > options.max_duration_diff_ms
+        {
+            continue;
+        }
+        let sim = similarity(&prints[a].items, &prints[b].items);
+        if sim < options.min_similarity {
+            continue;
+        }
+        pair_similarity.push((a, sim));
+        let (ra, rb) = (find(&mut parent, a), find(&mut parent, b));
+        if ra != rb {
+            parent[ra.max(rb)] = ra.min(rb);
+        }
+    }
+
+    let mut groups: HashMap<usize, Vec<usize>> = HashMap::new();
+    for i in 0..prints.len() {
+        let root = find(&mut parent, i);
+        groups.entry(root).or_default().push(i);
+    }
+    let mut lowest: HashMap<usize, f64> = HashMap::new();
+    for (a, sim) in pair_similarity {
+        let root = find(&mut parent, a);
+        let entry = lowest.entry(root).or_insert(1.0);
+        *entry = entry.min(sim);
+    }
+
+    let mut out: Vec<DuplicateGroup> = groups
+        .into_iter()
+        .filter(|(_, members)| members.len() > 1)
+        .map(|(root, members)| {
+            let mut songs: Vec<DuplicateSong> = members
+                .into_iter()
+                .map(|i| DuplicateSong {
+                    song_id: prints[i].song_id,
+                    file_path: prints[i].file_path.clone(),
+                    duration_ms: prints[i].duration_ms,
+                    artist: String::new(),
+                    title: String::new(),
+                })
+                .collect();
+            songs.sort_by_key(|s| s.song_id);
+            DuplicateGroup {
+                songs,
+                similarity: lowest.get(&root).copied().unwrap_or(1.0),
+            }
+        })
+        .collect();
+    out.sort_by_key(|g| g.songs[0].song_id);
+    out
+}
+
+// ── Duplicate groups for rotation ─────────────────────────────────────────────
+
+fn groups() -> &'static RwLock<HashMap<i64, i64>> {
+    static GROUPS: OnceLock<RwLock<HashMap<i64, i64>>> = OnceLock::new();
+    GROUPS.get_or_init(|| RwLock::new(HashMap::new()))
+}
+
+/// Remember `found` as the current duplicate groups.
+pub fn set_groups(found: &[DuplicateGroup]) {
+    let mut map = HashMap::new();
+    for group in found {
+        let key = group.songs[0].song_id;
+        for song in &group.songs {
+            map.insert(song.song_id, key);
+        }
+    }
+    if let Ok(mut groups) = groups().write() {
+        *groups = map;
+    }
+}
+
+/// Whether two song ids are the same song or known duplicates.
+pub fn same_recording(a: i64, b: i64) -> bool {
+    if a == b {
+        return true;
+    }
+    let Ok(groups) = groups().read() else {
+        return false;
+    };
+    matches!((groups.get(&a), groups.get(&b)), (Some(x), Some(y)) if x == y)
+}
+
+// ── Storage ───────────────────────────────────────────────────────────────────
+
+fn to_blob(items: &[u32]) -> Vec<u8> {
+    items.iter().flat_map(|i| i.to_le_bytes()).collect()
+}
+
+fn from_blob(blob: &[u8]) -> Vec<u32> {
+    blob.chunks_exact(4)
+        .map(|c| u32::from_le_bytes([c[0], c[1], c[2], c[3]]))
+        .collect()
+}
+
+pub async fn is_stored(pool: &SqlitePool, file_path: &str, mtime_ms: i64) -> bool {
+    sqlx::query_scalar::<_, i64>(
+        "SELECT COUNT(*) FROM song_fingerprints WHERE file_path = ? AND mtime_ms = ?",
+    )
+    .bind(file_path)
+    .bind(mtime_ms)
+    .fetch_one(pool)
+    .await
+    .is_ok_and(|n| n > 0)
+}
+
+pub async fn save(
+    pool: &SqlitePool,
+    song_id: i64,
+    file_path: &str,
+    mtime_ms: i64,
+    duration_ms: i64,
+    items: &[u32],
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO song_fingerprints
+             (file_path, song_id, mtime_ms, duration_ms, fingerprint, updated_at)
+         VALUES (?, ?, ?, ?, ?, strftime('%s','now'))
+         ON CONFLICT(file_path) DO UPDATE SET
+             song_id = excluded.song_id,
+             mtime_ms = excluded.mtime_ms,
+             duration_ms = excluded.duration_ms,
+             fingerprint = excluded.fingerprint,
+             updated_at = excluded.updated_at",
+    )
+    .bind(file_path)
+    .bind(song_id)
+    .bind(mtime_ms)
+    .bind(duration_ms)
+    .bind(to_blob(items))
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Every stored fingerprint that belongs to a song.
+pub async fn load_all(pool: &SqlitePool) -> Result<Vec<SongPrint>, sqlx::Error> {
+    let rows = sqlx::query(
+        "SELECT song_id, file_path, duration_ms, fingerprint FROM song_fingerprints
+         WHERE song_id > 0",
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(rows
+        .iter()
+        .map(|r| SongPrint {
+            song_id: r.get("song_id"),
+            file_path: r.get("file_path"),
+            duration_ms: r.get("duration_ms"),
+            items: from_blob(&r.get::<Vec<u8>, _>("fingerprint")),
+        })
+        .collect())
+}
+
+/// Queue fingerprinting for every song in SAM's song list whose file is
+/// reachable and not fingerprinted yet. Returns how many jobs were queued.
+pub async fn enqueue_library(pool: &SqlitePool, sam: &MySqlPool) -> Result<usize, String> {
+    let translator = crate::db::path_rules::load_translator(pool).await;
+    let mut queued = 0;
+    let mut last_id = 0i64;
+    loop {
+        let rows =
+            sqlx::query("SELECT ID, filename FROM songlist WHERE ID > ? ORDER BY ID LIMIT 500")
+                .bind(last_id)
+                .fetch_all(sam)
+                .await
+                .map_err(|e| e.to_string())?;
+        let Some(last) = rows.last() else {
+            break;
+        };
+        last_id = last.try_get::<i64, _>("ID").unwrap_or_else(|_| {
+            last.try_get::<i32, _>("ID")
+                .map(|v| v as i64)
+                .unwrap_or(i64::MAX)
+        });
+        for row in &rows {
+            let song_id = row
+                .try_get::<i64, _>("ID")
+                .or_else(|_| row.try_get::<i32, _>("ID").map(|v| v as i64))
+                .unwrap_or(0);
+            let filename: String = row.try_get("filename").unwrap_or_default();
+            let path = translator.translate(&filename);
+            if song_id <= 0 || !Path::new(&path).is_file() {
+                continue;
+            }
+            let mtime_ms = super::peaks::file_mtime_ms(Path::new(&path));
+            if is_stored(pool, &path, mtime_ms).await {
+                continue;
+            }
+            jobs::enqueue(
+                pool,
+                Some(song_id),
+                &path,
+                AnalysisKind::Fingerprint,
+                jobs::PRIORITY_LIBRARY,
+            )
+            .await
+            .map_err(|e| e.to_string())?;
+            queued += 1;
+        }
+        if rows.len() < 500 {
+            break;
+        }
+    }
+    Ok(queued)
+}
+
+/// Search the stored fingerprints and make the result the current groups.
+pub async fn refresh_duplicates(
+    pool: &SqlitePool,
+    options: DuplicateSearchOptions,
+) -> Result<Vec<DuplicateGroup>, String> {
+    let prints = load_all(pool).await.map_err(|e| e.to_string())?;
+    let found = tauri::async_runtime::spawn_blocking(move || find_duplicates(&prints, &options))
+        .await
+        .map_err(|e| format!("Duplicate search join failed: {e}"))?;
+    set_groups(&found);
+    Ok(found)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn print(song_id: i64, duration_ms: i64, items: Vec<u32>) -> SongPrint {
+        SongPrint {
+            song_id,
+            file_path: format!("/music/{song_id}.mp3"),
+            duration_ms,
+            items,
+        }
+    }
+
+    #[test]
+    fn groups_shifted_copies_of_similar_length() {
+        let mut seed = 0x9e37_79b9_u32;
+        let mut next = || {
+            seed ^= seed << 13;
+            seed ^= seed >> 17;
+            seed ^= seed << 5;
+            seed
+        };
+        let original: Vec<u32> = (0..300).map(|_| next()).collect();
+        let other: Vec<u32> = (0..300).map(|_| next()).collect();
+        // Same recording with two items of extra lead-in and some flipped bits.
+        let mut copy = vec![next(), next()];
+        copy.extend(
+            original
+                .iter()
+                .enumerate()
+                .map(|(n, i)| if n % 10 == 0 { i ^ 0b101 } else { *i }),
+        );
+
+        let prints = vec![
+            print(10, 200_000, original.clone()),
+            print(11, 201_000, other),
+            print(12, 200_500, copy.clone()),
+            // Same audio, but a much longer edit
+            print(13, 260_000, copy),
+        ];
+        let groups = find_duplicates(&prints, &DuplicateSearchOptions::default());
+        assert_eq!(groups.len(), 1);
+        let ids: Vec<i64> = groups[0].songs.iter().map(|s| s.song_id).collect();
+        assert_eq!(ids, vec![10, 12]);
+        assert!(groups[0].similarity > 0.9);
+
+        set_groups(&groups);
+        assert!(same_recording(12, 10));
+        assert!(!same_recording(10, 11));
+    }
+}
@@ -0,0 +1,784 @@
+/// Background analysis job queue
+///
+/// Beat grid, waveform, loudness, stem and fingerprint analysis run as prioritised jobs
+/// in `analysis_jobs`, so they survive restarts (jobs left `running` by a
+/// crash go back to `pending`). A job is unique per file and kind while
+/// pending or running; enqueuing it again only raises its priority. A small
+/// worker pool — one worker by default, to keep CPU free for playout — claims
+/// the highest-priority job, runs it on a blocking thread and emits
+/// `analysis_progress`. Stem separation has its own, lower concurrency limit
+/// and reports percent done (`analysis_job_progress`); cancelling a running
+/// stem job stops the model. A feeder enqueues the upcoming SAM queue items and
+/// the rotation picks planned in the ghost queue, so their analysis is ready
+/// before they air. Files whose jobs keep failing are retried with a growing
+/// backoff and left alone after `MAX_AUTO_ATTEMPTS` failures; a manual enqueue
+/// still retries them.
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use sqlx::{Row, SqlitePool};
+use tauri::{AppHandle, Emitter, Manager};
+
+use super::stems::{SeparationControl, SeparationOptions, SEPARATION_CANCELLED};
+use crate::state::AppState;
+
+pub const PRIORITY_MANUAL: i64 = 1_000;
+/// Whole-library passes run after everything else.
+pub const PRIORITY_LIBRARY: i64 = 0;
+/// Queue position `n` gets `PRIORITY_QUEUE - n`.
+const PRIORITY_QUEUE: i64 = 500;
+/// Ghost queue position `n` gets `PRIORITY_ROTATION - n`, behind the queue.
+const PRIORITY_ROTATION: i64 = 250;
+const DISPATCH_INTERVAL: Duration = Duration::from_secs(2);
+const FEED_INTERVAL: Duration = Duration::from_secs(30);
+/// Finished jobs are kept this long for the UI.
+const FINISHED_RETENTION_SECS: i64 = 24 * 3600;
+/// Failed jobs are kept longer, so the feeder remembers what keeps failing.
+const FAILED_RETENTION_SECS: i64 = 7 * 24 * 3600;
+/// The first automatic retry waits this long; each further failure doubles it.
+const RETRY_BACKOFF_SECS: i64 = 15 * 60;
+/// The feeder stops retrying a file and kind after this many failures.
+const MAX_AUTO_ATTEMPTS: i64 = 4;
+pub const MAX_WORKERS: u32 = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AnalysisKind {
+    Beatgrid,
+    Waveform,
+    Loudness,
+    Stems,
+    Fingerprint,
+}
+
+impl AnalysisKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Beatgrid => "beatgrid",
+            Self::Waveform => "waveform",
+            Self::Loudness => "loudness",
+            Self::Stems => "stems",
+            Self::Fingerprint => "fingerprint",
+        }
+    }
+
+    fn parse(s: &str) -> Option<Self> {
+        match s {
+            "beatgrid" => Some(Self::Beatgrid),
+            "waveform" => Some(Self::Waveform),
+            "loudness" => Some(Self::Loudness),
+            "stems" => Some(Self::Stems),
+            "fingerprint" => Some(Self::Fingerprint),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AnalysisQueueConfig {
+    /// Run jobs at all (manual and automatic)
+    pub enabled: bool,
+    /// Concurrent jobs, 1–4
+    pub max_workers: u32,
+    /// Pause each worker takes between jobs
+    pub cooldown_ms: u64,
+    /// Upcoming SAM queue items analysed ahead of time
+    pub lookahead: usize,
+    /// Kinds the feeder enqueues; stems are heavy and off by default
+    pub auto_kinds: Vec<AnalysisKind>,
+    pub stems: StemJobConfig,
+}
+
+/// Stem separation can take minutes of full CPU (or GPU) per song, so it
+/// gets its own limits to leave room for on-air audio.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StemJobConfig {
+    #[serde(flatten)]
+    pub separation: SeparationOptions,
+    /// Stem jobs running at once, within `max_workers`
+    pub max_concurrent: u32,
+}
+
+impl Default for StemJobConfig {
+    fn default() -> Self {
+        Self {
+            separation: SeparationOptions::default(),
+            max_concurrent: 1,
+        }
+    }
+}
+
+impl Default for AnalysisQueueConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_workers: 1,
+            cooldown_ms: 500,
+            lookahead: 4,
+            auto_kinds: vec![
+                AnalysisKind::Waveform,
+                AnalysisKind::Beatgrid,
+                AnalysisKind::Loudness,
+            ],
+            stems: StemJobConfig::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnalysisJob {
+    pub id: i64,
+    pub song_id: Option<i64>,
+    pub file_path: String,
+    pub kind: AnalysisKind,
+    pub priority: i64,
+    /// "pending" | "running" | "done" | "failed" | "cancelled"
+    pub status: String,
+    pub error: Option<String>,
+    pub created_at: i64,
+    pub started_at: Option<i64>,
+    pub finished_at: Option<i64>,
+    /// 0–100 while running, for kinds that report it (stems)
+    pub progress_pct: Option<f32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnalysisQueueStatus {
+    pub config: AnalysisQueueConfig,
+    pub pending: usize,
+    pub running: usize,
+    /// Pending and running jobs by priority, then recent finished ones
+    pub jobs: Vec<AnalysisJob>,
+}
+
+/// Payload of `analysis_progress`.
+#[derive(Debug, Clone, Serialize)]
+pub struct AnalysisProgressEvent {
+    pub job: AnalysisJob,
+    pub pending: usize,
+    pub running: usize,
+}
+
+/// Payload of `analysis_job_progress`, sent as a running job advances.
+#[derive(Debug, Clone, Serialize)]
+pub struct AnalysisJobProgress {
+    pub job_id: i64,
+    pub kind: AnalysisKind,
+    pub percent: f32,
+}
+
+static ACTIVE_WORKERS: AtomicUsize = AtomicUsize::new(0);
+static ACTIVE_STEM_WORKERS: AtomicUsize = AtomicUsize::new(0);
+
+#[derive(Default)]
+struct RunningJob {
+    cancel: Arc<AtomicBool>,
+    progress_pct: Option<f32>,
+}
+
+/// Cancel flags and progress of the jobs this process is running.
+fn running() -> &'static Mutex<HashMap<i64, RunningJob>> {
+    static RUNNING: OnceLock<Mutex<HashMap<i64, RunningJob>>> = OnceLock::new();
+    RUNNING.get_or_init(Default::default)
+}
+
+fn signal_cancel(ids: &[i64]) {
+    let running = running().lock().unwrap();
+    for id in ids {
+        if let Some(job) = running.get(id) {
+            job.cancel.store(true, Ordering::Relaxed);
+        }
+    }
+}
+
+// ── Storage ───────────────────────────────────────────────────────────────────
+
+pub async fn get_config(pool: &SqlitePool) -> Result<AnalysisQueueConfig, sqlx::Error> {
+    let row: Option<String> =
+        sqlx::query_scalar("SELECT config_json FROM analysis_queue_config WHERE id = 1")
+            .fetch_optional(pool)
+            .await?;
+    Ok(row
+        .and_then(|j| serde_json::from_str(&j).ok())
+        .unwrap_or_default())
+}
+
+pub async fn save_config(
+    pool: &SqlitePool,
+    config: &AnalysisQueueConfig,
+) -> Result<(), sqlx::Error> {
+    let json = serde_json::to_string(config).unwrap_or_else(|_| "{}".to_string());
+    sqlx::query(
+        "INSERT INTO analysis_queue_config (id, config_json, updated_at) \
+         VALUES (1, ?, strftime('%s','now')) \
+         ON CONFLICT(id) DO UPDATE SET config_json = excluded.config_json, \
+         updated_at = excluded.updated_at",
+    )
+    .bind(json)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+fn job_from_row(r: &sqlx::sqlite::SqliteRow) -> Option<AnalysisJob> {
+    Some(AnalysisJob {
+        id: r.get("id"),
+        song_id: r.get("song_id"),
+        file_path: r.get("file_path"),
+        kind: AnalysisKind::parse(&r.get::<String, _>("kind"))?,
+        priority: r.get("priority"),
+        status: r.get("status"),
+        error: r.get("error"),
+        created_at: r.get("created_at"),
+        started_at: r.get("started_at"),
+        finished_at: r.get("finished_at"),
+        progress_pct: running()
+            .lock()
+            .unwrap()
+            .get(&r.get::<i64, _>("id"))
+            .and_then(|j| j.progress_pct),
+    })
+}
+
+const JOB_COLUMNS: &str = "id, song_id, file_path, kind, priority, status, error, \
+                           created_at, started_at, finished_at";
+
+/// Add a job, or raise the priority of the same pending/running job.
+/// Returns the job id.
+pub async fn enqueue(
+    pool: &SqlitePool,
+    song_id: Option<i64>,
+    file_path: &str,
+    kind: AnalysisKind,
+    priority: i64,
+) -> Result<i64, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    let existing: Option<i64> = sqlx::query_scalar(
+        "SELECT id FROM analysis_jobs
+         WHERE file_path = ? AND kind = ? AND status IN ('pending', 'running')",
+    )
+    .bind(file_path)
+    .bind(kind.as_str())
+    .fetch_optional(&mut *tx)
+    .await?;
+    let id = match existing {
+        Some(id) => {
+            sqlx::query("UPDATE analysis_jobs SET priority = MAX(priority, ?) WHERE id = ?")
+                .bind(priority)
+                .bind(id)
+                .execute(&mut *tx)
+                .await?;
+            id
+        }
+        None => sqlx::query(
+            "INSERT INTO analysis_jobs (song_id, file_path, kind, priority, status, created_at)
+             VALUES (?, ?, ?, ?, 'pending', strftime('%s','now'))",
+        )
+        .bind(song_id)
+        .bind(file_path)
+        .bind(kind.as_str())
+        .bind(priority)
+        .execute(&mut *tx)
+        .await?
+        .last_insert_rowid(),
+    };
+    tx.commit().await?;
+    Ok(id)
+}
+
+/// Mark the best pending job running and return it. Stem jobs are passed
+/// over while `allow_stems` is off.
+async fn claim_next(
+    pool: &SqlitePool,
+    allow_stems: bool,
+) -> Result<Option<AnalysisJob>, sqlx::Error> {
+    let row = sqlx::query(&format!(
+        "UPDATE analysis_jobs SET status = 'running', started_at = strftime('%s','now')
+         WHERE id = (SELECT id FROM analysis_jobs WHERE status = 'pending'
+                       AND (? OR kind != 'stems')
+                     ORDER BY priority DESC, id LIMIT 1)
+         RETURNING {JOB_COLUMNS}"
+    ))
+    .bind(allow_stems)
+    .fetch_optional(pool)
+    .await?;
+    Ok(row.as_ref().and_then(job_from_row))
+}
+
+async fn finish(
+    pool: &SqlitePool,
+    id: i64,
+    error: Option<&str>,
+) -> Result<Option<AnalysisJob>, sqlx::Error> {
+    // A job cancelled while running stays cancelled.
+    let row = sqlx::query(&format!(
+        "UPDATE analysis_jobs
+         SET status = CASE WHEN status = 'cancelled' THEN status
+                           WHEN ? IS NULL THEN 'done' ELSE 'failed' END,
+             error = ?, finished_at = strftime('%s','now')
+         WHERE id = ?
+         RETURNING {JOB_COLUMNS}"
+    ))
+    .bind(error)
+    .bind(error)
+    .bind(id)
+    .fetch_optional(pool)
+    .await?;
+    Ok(row.as_ref().and_then(job_from_row))
+}
+
+/// Cancel a pending job. A running stem separation is stopped; other running
+/// jobs are flagged and their result discarded from the queue view.
+pub async fn cancel(pool: &SqlitePool, id: i64) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query(
+        "UPDATE analysis_jobs SET status = 'cancelled', finished_at = strftime('%s','now')
+         WHERE id = ? AND status IN ('pending', 'running')",
+    )
+    .bind(id)
+    .execute(pool)
+    .await?;
+    signal_cancel(&[id]);
+    Ok(result.rows_affected() > 0)
+}
+
+/// Cancel every pending and running job of `kind`. Returns how many.
+pub async fn cancel_kind(pool: &SqlitePool, kind: AnalysisKind) -> Result<usize, sqlx::Error> {
+    let ids: Vec<i64> = sqlx::query_scalar(
+        "UPDATE analysis_jobs SET status = 'cancelled', finished_at = strftime('%s','now')
+         WHERE kind = ? AND status IN ('pending', 'running')
+         RETURNING id",
+    )
+    .bind(kind.as_str())
+    .fetch_all(pool)
+    .await?;
+    signal_cancel(&ids);
+    Ok(ids.len())
+}
+
+/// Put jobs interrupted by a shutdown back in the queue.
+pub async fn requeue_interrupted(pool: &SqlitePool) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query(
+        "UPDATE analysis_jobs SET status = 'pending', started_at = NULL WHERE status = 'running'",
+    )
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected())
+}
+
+async fn prune_finished(pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    let now = chrono::Utc::now().timestamp();
+    sqlx::query(
+        "DELETE FROM analysis_jobs
+         WHERE (status IN ('done', 'cancelled') AND finished_at < ?)
+            OR (status = 'failed' AND finished_at < ?)",
+    )
+    .bind(now - FINISHED_RETENTION_SECS)
+    .bind(now - FAILED_RETENTION_SECS)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// How long after its last failure a job may be retried automatically, or
+/// `None` once it has failed too often.
+fn retry_backoff(failures: i64) -> Option<i64> {
+    match failures {
+        0 => Some(0),
+        n if n >= MAX_AUTO_ATTEMPTS => None,
+        n => Some(RETRY_BACKOFF_SECS << (n - 1)),
+    }
+}
+
+/// Whether the feeder may enqueue `file_path`/`kind` again, given its
+/// recorded failures.
+async fn may_retry(pool: &SqlitePool, file_path: &str, kind: AnalysisKind) -> bool {
+    let row = sqlx::query(
+        "SELECT COUNT(*) AS failures, MAX(finished_at) AS last_failed FROM analysis_jobs
+         WHERE file_path = ? AND kind = ? AND status = 'failed'",
+    )
+    .bind(file_path)
+    .bind(kind.as_str())
+    .fetch_one(pool)
+    .await;
+    let Ok(row) = row else {
+        return true;
+    };
+    let failures: i64 = row.try_get("failures").unwrap_or(0);
+    let last_failed: i64 = row
+        .try_get::<Option<i64>, _>("last_failed")
+        .ok()
+        .flatten()
+        .unwrap_or(0);
+    match retry_backoff(failures) {
+        Some(wait) => chrono::Utc::now().timestamp() >= last_failed + wait,
+        None => false,
+    }
+}
+
+async fn counts(pool: &SqlitePool) -> Result<(usize, usize), sqlx::Error> {
+    let row = sqlx::query(
+        "SELECT SUM(status = 'pending') AS pending, SUM(status = 'running') AS running
+         FROM analysis_jobs",
+    )
+    .fetch_one(pool)
+    .await?;
+    let count = |col: &str| row.get::<Option<i64>, _>(col).unwrap_or(0).max(0) as usize;
+    Ok((count("pending"), count("running")))
+}
+
+pub async fn queue_status(
+    pool: &SqlitePool,
+    limit: i64,
+) -> Result<AnalysisQueueStatus, sqlx::Error> {
+    let (pending, running) = counts(pool).await?;
+    let rows = sqlx::query(&format!(
+        "SELECT {JOB_COLUMNS} FROM analysis_jobs
+         ORDER BY status = 'running' DESC, status = 'pending' DESC,
+                  priority DESC, COALESCE(finished_at, 0) DESC, id
+         LIMIT ?"
+    ))
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+    Ok(AnalysisQueueStatus {
+        config: get_config(pool).await?,
+        pending,
+        running,
+        jobs: rows.iter().filter_map(job_from_row).collect(),
+    })
+}
+
+// ── Execution ─────────────────────────────────────────────────────────────────
+
+/// Whether `kind` already has a current result for `file_path`.
+async fn is_analyzed(pool: &SqlitePool, song_id: i64, file_path: &str, kind: AnalysisKind) -> bool {
+    let mtime_ms = super::peaks::file_mtime_ms(Path::new(file_path));
+    match kind {
+        AnalysisKind::Waveform => super::peaks::is_stored(pool, file_path).await,
+        AnalysisKind::Beatgrid => {
+            crate::db::local::get_beatgrid_analysis(pool, song_id, file_path, mtime_ms)
+                .await
+                .is_ok_and(|a| a.is_some())
+        }
+        AnalysisKind::Loudness => super::loudness::get_track_loudness(pool, file_path, mtime_ms)
+            .await
+            .is_ok_and(|l| l.is_some()),
+        AnalysisKind::Stems => {
+            crate::db::local::get_stem_analysis(pool, song_id, file_path, mtime_ms)
+                .await
+                .is_ok_and(|a| a.is_some())
+        }
+        AnalysisKind::Fingerprint => super::fingerprint::is_stored(pool, file_path, mtime_ms).await,
+    }
+}
+
+async fn run_job(
+    app: &AppHandle,
+    pool: &SqlitePool,
+    job: &AnalysisJob,
+    separation: &SeparationOptions,
+    cancel: Arc<AtomicBool>,
+) -> Result<(), String> {
+    let song_id = job.song_id.unwrap_or(0);
+    let path = job.file_path.clone();
+    match job.kind {
+        AnalysisKind::Waveform => super::peaks::load_or_analyze(Some(pool), &path)
+            .await
+            .map(|_| ()),
+        AnalysisKind::Beatgrid => {
+            crate::commands::beatgrid_commands::run_beatgrid_analysis(pool, song_id, path, false)
+                .await
+                .map(|_| ())
+                .map_err(|e| e.to_string())
+        }
+        AnalysisKind::Stems => {
+            let control = SeparationControl {
+                cancel,
+                on_progress: progress_reporter(app.clone(), job),
+            };
+            crate::commands::stem_commands::run_stem_analysis(
+                pool, song_id, path, false, separation, control,
+            )
+            .await
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+        }
+        AnalysisKind::Loudness => {
+            let mtime_ms = super::peaks::file_mtime_ms(Path::new(&path));
+            if super::loudness::get_track_loudness(pool, &path, mtime_ms)
+                .await
+                .is_ok_and(|l| l.is_some())
+            {
+                return Ok(());
+            }
+            let file = path.clone();
+            let (loudness_db, peak_db) = tauri::async_runtime::spawn_blocking(move || {
+                super::loudness::analyze_file(Path::new(&file))
+            })
+            .await
+            .map_err(|e| format!("Loudness worker join failed: {e}"))??;
+            super::loudness::save_track_loudness(
+                pool,
+                &super::loudness::TrackLoudness {
+                    song_id,
+                    file_path: path,
+                    mtime_ms,
+                    loudness_db,
+                    peak_db,
+                },
+            )
+            .await
+            .map_err(|e| e.to_string())
+        }
+        AnalysisKind::Fingerprint => {
+            let mtime_ms = super::peaks::file_mtime_ms(Path::new(&path));
+            if super::fingerprint::is_stored(pool, &path, mtime_ms).await {
+                return Ok(());
+            }
+            let file = path.clone();
+            let (items, duration_ms) = tauri::async_runtime::spawn_blocking(move || {
+                super::fingerprint::analyze_file(Path::new(&file))
+            })
+            .await
+            .map_err(|e| format!("Fingerprint worker join failed: {e}"))??;
+            super::fingerprint::save(pool, song_id, &path, mtime_ms, duration_ms, &items)
+                .await
+                .map_err(|e| e.to_string())
+        }
+    }
+}
+
+/// Record a running job's percent and emit `analysis_job_progress` each
+/// time it passes a whole percent.
+fn progress_reporter(app: AppHandle, job: &AnalysisJob) -> Arc<dyn Fn(f32) + Send + Sync> {
+    let (job_id, kind) = (job.id, job.kind);
+    Arc::new(move |percent: f32| {
+        {
+            let mut running = running().lock().unwrap();
+            let Some(entry) = running.get_mut(&job_id) else {
+                return;
+            };
+            if entry.progress_pct.map(f32::floor) == Some(percent.floor()) {
+                return;
+            }
+            entry.progress_pct = Some(percent);
+        }
+        let _ = app.emit(
+            "analysis_job_progress",
+            AnalysisJobProgress {
+                job_id,
+                kind,
+                percent,
+            },
+        );
+    })
+}
+
+async fn emit_progress(app: &AppHandle, pool: &SqlitePool, job: AnalysisJob) {
+    let (pending, running) = counts(pool).await.unwrap_or_default();
+    let _ = app.emit(
+        "analysis_progress",
+        AnalysisProgressEvent {
+            job,
+            pending,
+            running,
+        },
+    );
+}
+
+async fn work(
+    app: AppHandle,
+    pool: SqlitePool,
+    job: AnalysisJob,
+    cooldown: Duration,
+    separation: SeparationOptions,
+) {
+    let cancel = Arc::new(AtomicBool::new(false));
+    running().lock().unwrap().insert(
+        job.id,
+        RunningJob {
+            cancel: Arc::clone(&cancel),
+            progress_pct: None,
+        },
+    );
+    emit_progress(&app, &pool, job.clone()).await;
+    let result = if Path::new(&job.file_path).is_file() {
+        run_job(&app, &pool, &job, &separation, cancel).await
+    } else {
+        Err(format!("File not found: {}", job.file_path))
+    };
+    running().lock().unwrap().remove(&job.id);
+    if job.kind == AnalysisKind::Stems {
+        ACTIVE_STEM_WORKERS.fetch_sub(1, Ordering::SeqCst);
+    }
+    if matches!(&result, Err(e) if e == SEPARATION_CANCELLED) {
+        log::info!("Analysis job {} cancelled", job.id);
+    } else if let Err(e) = &result {
+        log::warn!(
+            "Analysis job {} ({} {}) failed: {e}",
+            job.id,
+            job.kind.as_str(),
+            job.file_path
+        );
+    }
+    match finish(&pool, job.id, result.err().as_deref()).await {
+        Ok(Some(done)) => emit_progress(&app, &pool, done).await,
+        Ok(None) => {}
+        Err(e) => log::warn!("Failed to record analysis job {}: {e}", job.id),
+    }
+    tokio::time::sleep(cooldown).await;
+    ACTIVE_WORKERS.fetch_sub(1, Ordering::SeqCst);
+}
+
+/// Claim jobs until the worker limit is reached.
+async fn dispatch(app: &AppHandle, pool: &SqlitePool) {
+    let config = get_config(pool).await.unwrap_or_default();
+    if !config.enabled {
+        return;
+    }
+    let limit = config.max_workers.clamp(1, MAX_WORKERS) as usize;
+    let stem_limit = config.stems.max_concurrent.clamp(1, MAX_WORKERS) as usize;
+    while ACTIVE_WORKERS.load(Ordering::SeqCst) < limit {
+        let allow_stems = ACTIVE_STEM_WORKERS.load(Ordering::SeqCst) < stem_limit;
+        let job = match claim_next(pool, allow_stems).await {
+            Ok(Some(job)) => job,
+            Ok(None) => return,
+            Err(e) => {
+                log::warn!("Analysis queue claim failed: {e}");
+                return;
+            }
+        };
+        ACTIVE_WORKERS.fetch_add(1, Ordering::SeqCst);
+        if job.kind == AnalysisKind::Stems {
+            ACTIVE_STEM_WORKERS.fetch_add(1, Ordering::SeqCst);
+        }
+        tauri::async_runtime::spawn(work(
+            app.clone(),
+            pool.clone(),
+            job,
+            Duration::from_millis(config.cooldown_ms),
+            config.stems.separation.clone(),
+        ));
+    }
+}
+
+/// Enqueue the configured kinds for upcoming queue items and ghost queue
+/// picks, skipping files that are already analysed or keep failing.
+async fn feed(state: &AppState, pool: &SqlitePool) {
+    let config = get_config(pool).await.unwrap_or_default();
+    if !config.enabled || config.auto_kinds.is_empty() {
+        return;
+    }
+    let sam_pool = { state.sam_db.read().await.as_ref().cloned() };
+    let Some(sam_pool) = sam_pool else {
+        return;
+    };
+    let translator = crate::db::path_rules::load_translator(pool).await;
+
+    let mut upcoming: Vec<(i64, String, i64)> = Vec::new();
+    if let Ok(queue) = crate::db::sam::get_queue(&sam_pool).await {
+        for (position, entry) in queue.into_iter().take(config.lookahead).enumerate() {
+            let song = match entry.song {
+                Some(song) => Some(song),
+                None => crate::db::sam::get_song(&sam_pool, entry.song_id)
+                    .await
+                    .ok()
+                    .flatten(),
+            };
+            if let Some(song) = song {
+                let priority = PRIORITY_QUEUE - position as i64;
+                upcoming.push((song.id, translator.translate(&song.filename), priority));
+            }
+        }
+    }
+    let planned = crate::scheduler::rotation::get_ghost_queue();
+    for (position, entry) in planned.into_iter().take(config.lookahead).enumerate() {
+        if upcoming.iter().any(|(id, _, _)| *id == entry.song.song_id) {
+            continue;
+        }
+        let priority = PRIORITY_ROTATION - position as i64;
+        let path = translator.translate(&entry.song.file_path);
+        upcoming.push((entry.song.song_id, path, priority));
+    }
+
+    for (song_id, path, priority) in upcoming {
+        if !Path::new(&path).is_file() {
+            continue;
+        }
+        for kind in &config.auto_kinds {
+            if is_analyzed(pool, song_id, &path, *kind).await
+                || !may_retry(pool, &path, *kind).await
+            {
+                continue;
+            }
+            if let Err(e) = enqueue(pool, Some(song_id), &path, *kind, priority).await {
+                log::warn!("Failed to enqueue analysis for {path}: {e}");
+            }
+        }
+    }
+}
+
+/// Start the dispatcher and feeder loops.
+pub fn start(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let state = app.state::<AppState>();
+        let Some(pool) = state.local_db.clone() else {
+            return;
+        };
+        match requeue_interrupted(&pool).await {
+            Ok(0) => {}
+            Ok(n) => log::info!("Analysis queue: {n} interrupted jobs requeued"),
+            Err(e) => log::warn!("Analysis queue recovery failed: {e}"),
+        }
+        if let Err(e) = super::fingerprint::refresh_duplicates(&pool, Default::default()).await {
+            log::warn!("Duplicate song groups not loaded: {e}");
+        }
+        let mut dispatch_tick = tokio::time::interval(DISPATCH_INTERVAL);
+        let mut feed_tick = tokio::time::interval(FEED_INTERVAL);
+        loop {
+            tokio::select! {
+                _ = dispatch_tick.tick() => dispatch(&app, &pool).await,
+                _ = feed_tick.tick() => {
+                    feed(&state, &pool).await;
+                    if let Err(e) = prune_finished(&pool).await {
+                        log::debug!("Analysis queue prune failed: {e}");
+                    }
+                }
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn kinds_round_trip_and_config_defaults_are_light() {
+        for kind in [
+            AnalysisKind::Beatgrid,
+            AnalysisKind::Waveform,
+            AnalysisKind::Loudness,
+            AnalysisKind::Stems,
+        ] {
+            assert_eq!(AnalysisKind::parse(kind.as_str()), Some(kind));
+        }
+        let config: AnalysisQueueConfig = serde_json::from_str("{}").unwrap();
+        assert_eq!(config.max_workers, 1);
+        assert!(!config.auto_kinds.contains(&AnalysisKind::Stems));
+    }
+
+    #[test]
+    fn failed_jobs_back_off_then_stop_retrying() {
+        assert_eq!(retry_backoff(0), Some(0));
+        assert_eq!(retry_backoff(1), Some(RETRY_BACKOFF_SECS));
+        assert_eq!(retry_backoff(2), Some(RETRY_BACKOFF_SECS * 2));
+        assert_eq!(retry_backoff(MAX_AUTO_ATTEMPTS), None);
+    }
+}
@@ -0,0 +1,141 @@
+/// Per-track loudness
+///
+/// Gated loudness over 400 ms blocks with 75% overlap, using the BS.1770
+/// gating scheme (absolute gate at -70 dB, relative gate 10 dB under the
+/// ungated mean) on the mono mix. There is no K-weighting filter, so values
+/// are dBFS-based rather than true LUFS, but they rank tracks consistently
+/// for level matching. Results are cached in `track_loudness` per file mtime.
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use sqlx::{Row, SqlitePool};
+
+pub const BLOCK_MS: u32 = 400;
+pub const BLOCK_STEP_MS: u32 = 100;
+pub const ABSOLUTE_GATE_DB: f64 = -70.0;
+const RELATIVE_GATE_DB: f64 = -10.0;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrackLoudness {
+    pub song_id: i64,
+    pub file_path: String,
+    pub mtime_ms: i64,
+    pub loudness_db: f64,
+    pub peak_db: f64,
+}
+
+fn to_db(mean_square: f64) -> f64 {
+    10.0 * mean_square.max(1e-12).log10()
+}
+
+/// `(gated loudness, sample peak)` in dB for mono `samples`.
+pub fn measure(samples: &[f32], sample_rate: u32) -> (f64, f64) {
+    let peak = samples.iter().fold(0.0_f32, |p, s| p.max(s.abs()));
+    let peak_db = 20.0 * f64::from(peak).max(1e-6).log10();
+
+    let block = (sample_rate as usize * BLOCK_MS as usize / 1000).max(1);
+    let step = (sample_rate as usize * BLOCK_STEP_MS as usize / 1000).max(1);
+    let mut blocks = Vec::new();
+    let mut start = 0;
+    while start + block <= samples.len() {
+        let sum: f64 = samples[start..start + block]
+            .iter()
+            .map(|s| f64::from(*s) * f64::from(*s))
+            .sum();
+        blocks.push(sum / block as f64);
+        start += step;
+    }
+    (gated_loudness_db(blocks), peak_db)
+}
+
+/// Gated loudness in dB from the mean squares of overlapping 400 ms blocks.
+pub fn gated_loudness_db(blocks: Vec<f64>) -> f64 {
+    let gated: Vec<f64> = blocks
+        .into_iter()
+        .filter(|ms| to_db(*ms) > ABSOLUTE_GATE_DB)
+        .collect();
+    if gated.is_empty() {
+        return ABSOLUTE_GATE_DB;
+    }
+    let ungated = gated.iter().sum::<f64>() / gated.len() as f64;
+    let threshold = to_db(ungated) + RELATIVE_GATE_DB;
+    let loud: Vec<f64> = gated
+        .into_iter()
+        .filter(|ms| to_db(*ms) > threshold)
+        .collect();
+    let mean = loud.iter().sum::<f64>() / loud.len().max(1) as f64;
+    to_db(mean)
+}
+
+/// Decode and measure `path` (blocking).
+pub fn analyze_file(path: &Path) -> Result<(f64, f64), String> {
+    let (samples, sample_rate) = super::beatgrid::decode_mono(path)?;
+    Ok(measure(&samples, sample_rate))
+}
+
+pub async fn get_track_loudness(
+    pool: &SqlitePool,
+    file_path: &str,
+    mtime_ms: i64,
+) -> Result<Option<TrackLoudness>, sqlx::Error> {
+    let row = sqlx::query(
+        "SELECT song_id, file_path, mtime_ms, loudness_db, peak_db
+         FROM track_loudness WHERE file_path = ? AND mtime_ms = ?",
+    )
+    .bind(file_path)
+    .bind(mtime_ms)
+    .fetch_optional(pool)
+    .await?;
+    Ok(row.map(|r| TrackLoudness {
+        song_id: r.get("song_id"),
+        file_path: r.get("file_path"),
+        mtime_ms: r.get("mtime_ms"),
+        loudness_db: r.get("loudness_db"),
+        peak_db: r.get("peak_db"),
+    }))
+}
+
+pub async fn save_track_loudness(
+    pool: &SqlitePool,
+    loudness: &TrackLoudness,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO track_loudness
+             (file_path, song_id, mtime_ms, loudness_db, peak_db, updated_at)
+         VALUES (?, ?, ?, ?, ?, strftime('%s','now'))
+         ON CONFLICT(file_path) DO UPDATE SET
+             song_id = excluded.song_id,
+             mtime_ms = excluded.mtime_ms,
+             loudness_db = excluded.loudness_db,
+             peak_db = excluded.peak_db,
+             updated_at = excluded.updated_at",
+    )
+    .bind(&loudness.file_path)
+    .bind(loudness.song_id)
+    .bind(loudness.mtime_ms)
+    .bind(loudness.loudness_db)
+    .bind(loudness.peak_db)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gating_ignores_silence() {
+        let rate = 8_000;
+        // Half-scale square wave (-6 dB) with silence either side.
+        let mut samples = vec![0.0_f32; rate as usize * 3];
+        samples.extend((0..rate * 4).map(|i| if i % 2 == 0 { 0.5 } else { -0.5 }));
+        samples.extend(vec![0.0_f32; rate as usize * 3]);
+
+        let (loudness, peak) = measure(&samples, rate);
+        assert!((loudness - to_db(0.25)).abs() < 0.5, "loudness {loudness}");
+        assert!((peak - 20.0 * 0.5_f64.log10()).abs() < 0.01);
+
+        assert_eq!(measure(&[0.0; 16_000], rate).0, ABSOLUTE_GATE_DB);
+    }
+}
@@ -1,2 +1,8 @@
+pub mod artwork;
 pub mod beatgrid;
+pub mod cue_detect;
+pub mod fingerprint;
+pub mod jobs;
+pub mod loudness;
+pub mod peaks;
 pub mod stems;
@@ -0,0 +1,442 @@
+/// Multi-resolution waveform peaks
+///
+/// A track is decoded once into 10 ms peak bins; each further level halves
+/// the previous one (max of pairs) until it is small enough for an overview.
+/// Any requested width is served from the coarsest level that still has at
+/// least that many bins, so zoomed deck views and the overview strip come
+/// from the same analysis. Peaks are stored as 8-bit values in a compact
+/// binary blob in `waveform_peaks`, keyed by file path and mtime.
+use std::{fs::File, path::Path};
+
+use serde::{Deserialize, Serialize};
+use sqlx::{Row, SqlitePool};
+use symphonia::core::{
+    audio::{AudioBufferRef, Signal},
+    codecs::{DecoderOptions, CODEC_TYPE_NULL},
+    errors::Error as SymphoniaError,
+    formats::FormatOptions,
+    io::MediaSourceStream,
+    meta::MetadataOptions,
+    probe::Hint,
+};
+
+const MAGIC: &[u8; 4] = b"DZPK";
+const FORMAT_VERSION: u8 = 1;
+/// Time covered by one bin of the finest level.
+const BASE_BIN_MS: u32 = 10;
+/// Halving stops once a level is this short.
+const MIN_LEVEL_LEN: usize = 256;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PeakPyramid {
+    pub sample_rate: u32,
+    /// Source frames per bin of level 0
+    pub bin_frames: u32,
+    pub total_frames: u64,
+    /// Finest first; peaks scaled to 0–255
+    pub levels: Vec<Vec<u8>>,
+}
+
+/// One page of peaks at a given width, for incremental drawing.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WaveformChunk {
+    pub resolution: usize,
+    pub offset: usize,
+    /// Bins at this resolution (equal to `resolution`)
+    pub total: usize,
+    pub duration_ms: u64,
+    pub peaks: Vec<f32>,
+}
+
+/// Accumulates decoded samples into level-0 bins.
+struct PeakBuilder {
+    bin_frames: u32,
+    current: f32,
+    filled: u32,
+    total_frames: u64,
+    base: Vec<u8>,
+}
+
+impl PeakBuilder {
+    fn new(sample_rate: u32) -> Self {
+        Self {
+            bin_frames: (sample_rate * BASE_BIN_MS / 1000).max(1),
+            current: 0.0,
+            filled: 0,
+            total_frames: 0,
+            base: Vec::new(),
+        }
+    }
+
+    fn push(&mut self, sample: f32) {
+        self.current = self.current.max(sample.abs());
+        self.filled += 1;
+        self.total_frames += 1;
+        if self.filled == self.bin_frames {
+            self.flush();
+        }
+    }
+
+    fn flush(&mut self) {
+        self.base
+            .push((self.current.clamp(0.0, 1.0) * 255.0).round() as u8);
+        self.current = 0.0;
+        self.filled = 0;
+    }
+
+    fn finish(mut self, sample_rate: u32) -> PeakPyramid {
+        if self.filled > 0 {
+            self.flush();
+        }
+        PeakPyramid::from_base(sample_rate, self.bin_frames, self.total_frames, self.base)
+    }
+}
+
+impl PeakPyramid {
+    fn from_base(sample_rate: u32, bin_frames: u32, total_frames: u64, base: Vec<u8>) -> Self {
+        let mut levels = vec![base];
+        while levels.last().map_or(0, Vec::len) > MIN_LEVEL_LEN {
+            let next = levels
+                .last()
+                .unwrap()
+                .chunks(2)
+                .map(|pair| pair.iter().copied().max().unwrap_or(0))
+                .collect();
+            levels.push(next);
+        }
+        Self {
+            sample_rate,
+            bin_frames,
+            total_frames,
+            levels,
+        }
+    }
+
+    pub fn duration_ms(&self) -> u64 {
+        if self.sample_rate == 0 {
+            return 0;
+        }
+        self.total_frames * 1000 / u64::from(self.sample_rate)
+    }
+
+    /// `resolution` peaks in 0.0–1.0 covering the whole track.
+    pub fn resample(&self, resolution: usize) -> Vec<f32> {
+        let resolution = resolution.max(1);
+        let source = self
+            .levels
+            .iter()
+            .rev()
+            .find(|l| l.len() >= resolution)
+            .or_else(|| self.levels.first())
+            .map(Vec::as_slice)
+            .unwrap_or(&[]);
+        if source.is_empty() {
+            return vec![0.0; resolution];
+        }
+        let step = source.len() as f64 / resolution as f64;
+        (0..resolution)
+            .map(|i| {
+                let start = ((i as f64 * step) as usize).min(source.len() - 1);
+                let end = (((i + 1) as f64 * step) as usize).clamp(start + 1, source.len());
+                let peak = source[start..end].iter().copied().max().unwrap_or(0);
+                f32::from(peak) / 255.0
+            })
+            .collect()
+    }
+
+    /// `count` peaks from `offset` of the `resolution`-wide rendering.
+    pub fn chunk(&self, resolution: usize, offset: usize, count: usize) -> WaveformChunk {
+        let all = self.resample(resolution);
+        let start = offset.min(all.len());
+        let end = start.saturating_add(count).min(all.len());
+        WaveformChunk {
+            resolution,
+            offset: start,
+            total: all.len(),
+            duration_ms: self.duration_ms(),
+            peaks: all[start..end].to_vec(),
+        }
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let payload: usize = self.levels.iter().map(|l| l.len() + 4).sum();
+        let mut out = Vec::with_capacity(22 + payload);
+        out.extend_from_slice(MAGIC);
+        out.push(FORMAT_VERSION);
+        out.extend_from_slice(&self.sample_rate.to_le_bytes());
+        out.extend_from_slice(&self.bin_frames.to_le_bytes());
+        out.extend_from_slice(&self.total_frames.to_le_bytes());
+        out.push(self.levels.len() as u8);
+        for level in &self.levels {
+            out.extend_from_slice(&(level.len() as u32).to_le_bytes());
+            out.extend_from_slice(level);
+        }
+        out
+    }
+
+    /// `None` for foreign or truncated data, or an older format version.
+    pub fn decode(data: &[u8]) -> Option<Self> {
+        let mut pos = 0usize;
+        let mut take = |n: usize| {
+            let slice = data.get(pos..pos + n)?;
+            pos += n;
+            Some(slice)
+        };
+        if take(4)? != MAGIC || take(1)?[0] != FORMAT_VERSION {
+            return None;
+        }
+        let sample_rate = u32::from_le_bytes(take(4)?.try_into().ok()?);
+        let bin_frames = u32::from_le_bytes(take(4)?.try_into().ok()?);
+        let total_frames = u64::from_le_bytes(take(8)?.try_into().ok()?);
+        let level_count = take(1)?[0] as usize;
+        let mut levels = Vec::with_capacity(level_count);
+        for _ in 0..level_count {
+            let len = u32::from_le_bytes(take(4)?.try_into().ok()?) as usize;
+            levels.push(take(len)?.to_vec());
+        }
+        Some(Self {
+            sample_rate,
+            bin_frames,
+            total_frames,
+            levels,
+        })
+    }
+}
+
+// ── Analysis ──────────────────────────────────────────────────────────────────
+
+/// Decode `path` once and build its peak pyramid (blocking).
+pub fn analyze_file(path: &Path) -> Result<PeakPyramid, String> {
+    let file = File::open(path).map_err(|e| format!("Cannot open {}: {e}", path.display()))?;
+    let mss = MediaSourceStream::new(Box::new(file), Default::default());
+    let mut hint = Hint::new();
+    if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
+        hint.with_extension(ext);
+    }
+
+    let mut probed = symphonia::default::get_probe()
+        .format(
+            &hint,
+            mss,
+            &FormatOptions::default(),
+            &MetadataOptions::default(),
+        )
+        .map_err(|e| format!("Probe failed: {e}"))?;
+
+    let track = probed
+        .format
+        .tracks()
+        .iter()
+        .find(|t| t.codec_params.codec != CODEC_TYPE_NULL)
+        .ok_or("No audio track found")?
+        .clone();
+    let track_id = track.id;
+    let n_channels = track
+        .codec_params
+        .channels
+        .map(|c| c.count())
+        .unwrap_or(2)
+        .max(1);
+    let sample_rate = track.codec_params.sample_rate.unwrap_or(44100);
+
+    let mut decoder = symphonia::default::get_codecs()
+        .make(&track.codec_params, &DecoderOptions::default())
+        .map_err(|e| format!("Codec init failed: {e}"))?;
+
+    let mut builder = PeakBuilder::new(sample_rate);
+    loop {
+        let packet = match probed.format.next_packet() {
+            Ok(p) => p,
+            Err(SymphoniaError::IoError(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof => {
+                break;
+            }
+            Err(SymphoniaError::ResetRequired) => {
+                decoder.reset();
+                continue;
+            }
+            Err(e) => return Err(format!("Read packet failed: {e}")),
+        };
+        if packet.track_id() != track_id {
+            continue;
+        }
+
+        let decoded = match decoder.decode(&packet) {
+            Ok(d) => d,
+            Err(SymphoniaError::DecodeError(_)) => continue,
+            Err(e) => return Err(format!("Decode failed: {e}")),
+        };
+
+        push_mono(decoded, n_channels, &mut builder);
+    }
+
+    Ok(builder.finish(sample_rate))
+}
+
+fn push_mono(buf: AudioBufferRef<'_>, n_channels: usize, out: &mut PeakBuilder) {
+    let frames = buf.frames();
+    match buf {
+        AudioBufferRef::F32(b) => {
+            let c0 = b.chan(0);
+            let c1 = if n_channels > 1 { b.chan(1) } else { b.chan(0) };
+            for i in 0..frames {
+                out.push((c0[i] + c1[i]) * 0.5);
+            }
+        }
+        AudioBufferRef::F64(b) => {
+            let c0 = b.chan(0);
+            let c1 = if n_channels > 1 { b.chan(1) } else { b.chan(0) };
+            for i in 0..frames {
+                out.push(((c0[i] + c1[i]) * 0.5) as f32);
+            }
+        }
+        AudioBufferRef::S32(b) => {
+            let norm = 1.0 / i32::MAX as f32;
+            let c0 = b.chan(0);
+            let c1 = if n_channels > 1 { b.chan(1) } else { b.chan(0) };
+            for i in 0..frames {
+                out.push(((c0[i] as f32 + c1[i] as f32) * 0.5) * norm);
+            }
+        }
+        AudioBufferRef::S16(b) => {
+            let norm = 1.0 / i16::MAX as f32;
+            let c0 = b.chan(0);
+            let c1 = if n_channels > 1 { b.chan(1) } else { b.chan(0) };
+            for i in 0..frames {
+                out.push(((c0[i] as f32 + c1[i] as f32) * 0.5) * norm);
+            }
+        }
+        AudioBufferRef::U8(b) => {
+            let c0 = b.chan(0);
+            let c1 = if n_channels > 1 { b.chan(1) } else { b.chan(0) };
+            for i in 0..frames {
+                let l = (c0[i] as f32 - 128.0) / 128.0;
+                let r = (c1[i] as f32 - 128.0) / 128.0;
+                out.push((l + r) * 0.5);
+            }
+        }
+        _ => {}
+    }
+}
+
+// ── Storage ───────────────────────────────────────────────────────────────────
+
+pub fn file_mtime_ms(path: &Path) -> i64 {
+    path.metadata()
+        .ok()
+        .and_then(|m| m.modified().ok())
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or(0)
+}
+
+pub async fn get_stored(
+    pool: &SqlitePool,
+    file_path: &str,
+    mtime_ms: i64,
+) -> Result<Option<PeakPyramid>, sqlx::Error> {
+    let row = sqlx::query("SELECT data FROM waveform_peaks WHERE file_path = ? AND mtime_ms = ?")
+        .bind(file_path)
+        .bind(mtime_ms)
+        .fetch_optional(pool)
+        .await?;
+    Ok(row.and_then(|r| PeakPyramid::decode(&r.get::<Vec<u8>, _>("data"))))
+}
+
+pub async fn save(
+    pool: &SqlitePool,
+    file_path: &str,
+    mtime_ms: i64,
+    pyramid: &PeakPyramid,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO waveform_peaks (file_path, mtime_ms, data, updated_at)
+         VALUES (?, ?, ?, strftime('%s','now'))
+         ON CONFLICT(file_path) DO UPDATE SET
+             mtime_ms = excluded.mtime_ms,
+             data = excluded.data,
+             updated_at = excluded.updated_at",
+    )
+    .bind(file_path)
+    .bind(mtime_ms)
+    .bind(pyramid.encode())
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Stored pyramid for `file_path`, analysing the file when there is none
+/// for its current mtime. Without a pool the result is just not cached.
+pub async fn load_or_analyze(
+    pool: Option<&SqlitePool>,
+    file_path: &str,
+) -> Result<PeakPyramid, String> {
+    let path = Path::new(file_path).to_path_buf();
+    let mtime_ms = file_mtime_ms(&path);
+    if let Some(pool) = pool {
+        if let Ok(Some(stored)) = get_stored(pool, file_path, mtime_ms).await {
+            return Ok(stored);
+        }
+    }
+    let pyramid = tauri::async_runtime::spawn_blocking(move || analyze_file(&path))
+        .await
+        .map_err(|e| format!("Waveform worker join failed: {e}"))??;
+    if let Some(pool) = pool {
+        if let Err(e) = save(pool, file_path, mtime_ms, &pyramid).await {
+            log::debug!("Failed to store waveform peaks for {file_path}: {e}");
+        }
+    }
+    Ok(pyramid)
+}
+
+/// Whether `file_path` already has peaks for its current mtime.
+pub async fn is_stored(pool: &SqlitePool, file_path: &str) -> bool {
+    let mtime_ms = file_mtime_ms(Path::new(file_path));
+    sqlx::query_scalar::<_, i64>(
+        "SELECT 1 FROM waveform_peaks WHERE file_path = ? AND mtime_ms = ?",
+    )
+    .bind(file_path)
+    .bind(mtime_ms)
+    .fetch_optional(pool)
+    .await
+    .map(|r| r.is_some())
+    .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pyramid(base: Vec<u8>) -> PeakPyramid {
+        let frames = base.len() as u64 * 441;
+        PeakPyramid::from_base(44_100, 441, frames, base)
+    }
+
+    #[test]
+    fn levels_halve_down_to_overview_size() {
+        let p = pyramid((0..2_000u32).map(|i| (i % 256) as u8).collect());
+        let lens: Vec<usize> = p.levels.iter().map(Vec::len).collect();
+        assert_eq!(lens, vec![2_000, 1_000, 500, 250]);
+        assert_eq!(p.levels[1][0], 1);
+        assert_eq!(p.duration_ms(), 20_000);
+
+        // Served from the 500-bin level, keeping the loudest bin per slot.
+        let peaks = p.resample(300);
+        assert_eq!(peaks.len(), 300);
+        assert!(peaks.iter().all(|v| (0.0..=1.0).contains(v)));
+
+        let chunk = p.chunk(300, 290, 50);
+        assert_eq!(
+            (chunk.offset, chunk.total, chunk.peaks.len()),
+            (290, 300, 10)
+        );
+    }
+
+    #[test]
+    fn binary_format_round_trips() {
+        let p = pyramid(vec![0, 128, 255, 64, 32]);
+        let encoded = p.encode();
+        assert_eq!(PeakPyramid::decode(&encoded), Some(p));
+        assert_eq!(PeakPyramid::decode(&encoded[..encoded.len() - 1]), None);
+        assert_eq!(PeakPyramid::decode(b"[0.1,0.2]"), None);
+    }
+}
@@ -1,21 +1,146 @@
 use std::{
     fs,
+    io::Read,
     path::{Path, PathBuf},
-    process::Command,
-    time::SystemTime,
+    process::{Command, Stdio},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread,
+    time::{Duration, SystemTime},
 };
 
+use serde::{Deserialize, Serialize};
+
+/// File names Demucs gives the four stems of `htdemucs`.
+const FOUR_STEMS: [&str; 4] = ["vocals", "drums", "bass", "other"];
+/// Written next to the stems: drums + bass + other, for the instrumental source.
+const INSTRUMENTAL_STEM: &str = "no_vocals";
+/// Error returned when `SeparationControl::cancel` stopped the run.
+pub const SEPARATION_CANCELLED: &str = "Stem separation cancelled";
+const CANCEL_POLL: Duration = Duration::from_millis(200);
+
+/// 4-stem Demucs models. The fine-tuned and MDX variants are slower and
+/// sometimes cleaner; `htdemucs` is the default.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StemModel {
+    #[default]
+    Htdemucs,
+    HtdemucsFt,
+    HdemucsMmi,
+    MdxExtra,
+    MdxExtraQ,
+}
+
+impl StemModel {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Htdemucs => "htdemucs",
+            Self::HtdemucsFt => "htdemucs_ft",
+            Self::HdemucsMmi => "hdemucs_mmi",
+            Self::MdxExtra => "mdx_extra",
+            Self::MdxExtraQ => "mdx_extra_q",
+        }
+    }
+}
+
+/// Where Demucs runs. `Auto` lets it pick CUDA when available.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StemDevice {
+    #[default]
+    Auto,
+    Cpu,
+    /// NVIDIA GPU
+    Cuda,
+    /// Apple silicon GPU
+    Mps,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SeparationOptions {
+    pub model: StemModel,
+    pub device: StemDevice,
+    /// CPU threads the model may use (0 = Demucs default, all cores)
+    pub cpu_threads: u32,
+}
+
+impl Default for SeparationOptions {
+    fn default() -> Self {
+        Self {
+            model: StemModel::default(),
+            device: StemDevice::default(),
+            cpu_threads: 2,
+        }
+    }
+}
+
+impl SeparationOptions {
+    fn demucs_args(&self, output_root: &Path, input_file: &Path) -> Vec<String> {
+        let mut args = vec!["-n".to_string(), self.model.as_str().to_string()];
+        let device = match self.device {
+            StemDevice::Auto => None,
+            StemDevice::Cpu => Some("cpu"),
+            StemDevice::Cuda => Some("cuda"),
+            StemDevice::Mps => Some("mps"),
+        };
+        if let Some(device) = device {
+            args.extend(["-d".to_string(), device.to_string()]);
+        }
+        args.extend([
+            "--out".to_string(),
+            output_root.to_string_lossy().to_string(),
+            input_file.to_string_lossy().to_string(),
+        ]);
+        args
+    }
+}
+
+/// Cancellation and progress for a running separation.
+#[derive(Clone)]
+pub struct SeparationControl {
+    pub cancel: Arc<AtomicBool>,
+    /// Called with 0–100 as Demucs reports progress
+    pub on_progress: Arc<dyn Fn(f32) + Send + Sync>,
+}
+
+impl Default for SeparationControl {
+    fn default() -> Self {
+        Self {
+            cancel: Arc::new(AtomicBool::new(false)),
+            on_progress: Arc::new(|_| {}),
+        }
+    }
+}
+
+impl SeparationControl {
+    fn cancelled(&self) -> bool {
+        self.cancel.load(Ordering::Relaxed)
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct StemSeparationResult {
     pub model_name: String,
     pub vocals_path: PathBuf,
+    pub drums_path: PathBuf,
+    pub bass_path: PathBuf,
+    pub other_path: PathBuf,
+    /// Sum of the non-vocal stems
     pub instrumental_path: PathBuf,
 }
 
-pub fn separate_two_stems_vocals(
+/// Split `input_file` into vocals, drums, bass and other with a 4-stem
+/// Demucs model, then mix the last three into an instrumental.
+pub fn separate_four_stems(
     input_file: &Path,
     output_root: &Path,
     preferred_python: Option<&Path>,
+    options: &SeparationOptions,
+    control: &SeparationControl,
 ) -> Result<StemSeparationResult, String> {
     if !input_file.exists() {
         return Err(format!(
@@ -36,76 +161,36 @@ pub fn separate_two_stems_vocals(
         )
     })?;
 
+    let model_root = output_root.join(options.model.as_str());
     let mut errors = Vec::<String>::new();
-    let out = output_root.to_string_lossy().to_string();
-    let input = input_file.to_string_lossy().to_string();
-
-    let demucs_args = vec![
-        "--two-stems".to_string(),
-        "vocals".to_string(),
-        "-n".to_string(),
-        "htdemucs".to_string(),
-        "--out".to_string(),
-        out.clone(),
-        input.clone(),
-    ];
-    if let Err(e) = run_command("demucs", &demucs_args) {
-        if let Ok(result) = resolve_generated_stems(output_root) {
-            return Ok(result);
-        }
-        errors.push(format!("demucs: {e}"));
-    } else {
-        return resolve_generated_stems(output_root);
-    }
-
-    let py_args = vec![
-        "-m".to_string(),
-        "demucs.separate".to_string(),
-        "--two-stems".to_string(),
-        "vocals".to_string(),
-        "-n".to_string(),
-        "htdemucs".to_string(),
-        "--out".to_string(),
-        out.clone(),
-        input.clone(),
-    ];
+    let demucs_args = options.demucs_args(output_root, input_file);
+    let mut py_args = vec!["-m".to_string(), "demucs.separate".to_string()];
+    py_args.extend(demucs_args.iter().cloned());
 
+    let mut attempts = vec![(PathBuf::from("demucs"), &demucs_args)];
     if let Some(py) = preferred_python {
-        if let Err(e) = run_command_path(py, &py_args) {
-            if let Ok(result) = resolve_generated_stems(output_root) {
-                return Ok(result);
-            }
-            errors.push(format!("{} -m demucs.separate: {e}", py.display()));
-        } else {
-            return resolve_generated_stems(output_root);
-        }
+        attempts.push((py.to_path_buf(), &py_args));
     }
-
-    if let Err(e) = run_command("python3.11", &py_args) {
-        if let Ok(result) = resolve_generated_stems(output_root) {
-            return Ok(result);
-        }
-        errors.push(format!("python3.11 -m demucs.separate: {e}"));
-    } else {
-        return resolve_generated_stems(output_root);
+    for python in ["python3.11", "python3", "python"] {
+        attempts.push((PathBuf::from(python), &py_args));
     }
 
-    if let Err(e) = run_command("python3", &py_args) {
-        if let Ok(result) = resolve_generated_stems(output_root) {
-            return Ok(result);
-        }
-        errors.push(format!("python3 -m demucs.separate: {e}"));
-    } else {
-        return resolve_generated_stems(output_root);
-    }
-
-    if let Err(e) = run_command("python", &py_args) {
-        if let Ok(result) = resolve_generated_stems(output_root) {
-            return Ok(result);
+    for (program, args) in attempts {
+        match run_command(&program, args, options, control) {
+            Ok(()) => return resolve_generated_stems(&model_root),
+            Err(e) if e == SEPARATION_CANCELLED => return Err(e),
+            Err(e) => {
+                if let Ok(result) = resolve_generated_stems(&model_root) {
+                    return Ok(result);
+                }
+                let module = if *args == py_args {
+                    " -m demucs.separate"
+                } else {
+                    ""
+                };
+                errors.push(format!("{}{module}: {e}", program.display()));
+            }
         }
-        errors.push(format!("python -m demucs.separate: {e}"));
-    } else {
-        return resolve_generated_stems(output_root);
     }
 
     Err(format!(
@@ -114,38 +199,88 @@ pub fn separate_two_stems_vocals(
     ))
 }
 
-fn run_command(command: &str, args: &[String]) -> Result<(), String> {
-    let output = Command::new(command)
+/// Run to completion, reporting tqdm percentages from stderr and killing
+/// the process if the run is cancelled.
+fn run_command(
+    program: &Path,
+    args: &[String],
+    options: &SeparationOptions,
+    control: &SeparationControl,
+) -> Result<(), String> {
+    let mut command = Command::new(program);
+    command
         .args(args)
-        .output()
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped());
+    if options.cpu_threads > 0 {
+        let threads = options.cpu_threads.to_string();
+        for var in ["OMP_NUM_THREADS", "MKL_NUM_THREADS", "OPENBLAS_NUM_THREADS"] {
+            command.env(var, &threads);
+        }
+    }
+    let mut child = command
+        .spawn()
         .map_err(|e| format!("failed to spawn command: {e}"))?;
-    map_command_output(output)
-}
 
-fn run_command_path(command: &Path, args: &[String]) -> Result<(), String> {
-    let output = Command::new(command)
-        .args(args)
-        .output()
-        .map_err(|e| format!("failed to spawn command: {e}"))?;
-    map_command_output(output)
-}
+    let mut stderr = child.stderr.take().ok_or("stderr not captured")?;
+    let on_progress = Arc::clone(&control.on_progress);
+    let reader = thread::spawn(move || {
+        let mut raw = Vec::new();
+        let mut line = Vec::new();
+        let mut buf = [0u8; 1024];
+        while let Ok(n) = stderr.read(&mut buf) {
+            if n == 0 {
+                break;
+            }
+            for &b in &buf[..n] {
+                if b == b'\r' || b == b'\n' {
+                    if let Some(pct) = parse_progress(&String::from_utf8_lossy(&line)) {
+                        on_progress(pct);
+                    }
+                    line.clear();
+                } else {
+                    line.push(b);
+                }
+            }
+            raw.extend_from_slice(&buf[..n]);
+        }
+        String::from_utf8_lossy(&raw).to_string()
+    });
 
-fn map_command_output(output: std::process::Output) -> Result<(), String> {
-    if output.status.success() {
+    let status = loop {
+        if control.cancelled() {
+            let _ = child.kill();
+            let _ = child.wait();
+            let _ = reader.join();
+            return Err(SEPARATION_CANCELLED.to_string());
+        }
+        match child.try_wait() {
+            Ok(Some(status)) => break status,
+            Ok(None) => thread::sleep(CANCEL_POLL),
+            Err(e) => return Err(format!("failed to wait for command: {e}")),
+        }
+    };
+    let output = reader.join().unwrap_or_default();
+    if status.success() {
         return Ok(());
     }
-
-    let stderr = String::from_utf8_lossy(&output.stderr).to_string();
-    let stdout = String::from_utf8_lossy(&output.stdout).to_string();
-    let raw = if !stderr.trim().is_empty() {
-        stderr
-    } else if !stdout.trim().is_empty() {
-        stdout
-    } else {
+    let detail = if output.trim().is_empty() {
         "no output".to_string()
+    } else {
+        summarize_command_output(&output)
     };
-    let detail = summarize_command_output(&raw);
-    Err(format!("exit code {:?}: {}", output.status.code(), detail))
+    Err(format!("exit code {:?}: {}", status.code(), detail))
+}
+
+/// Percentage from a tqdm line such as ` 42%|████      | 12.0/28.5 [..]`.
+fn parse_progress(line: &str) -> Option<f32> {
+    let (head, _) = line.split_once("%|")?;
+    let digits = head.trim_end().rsplit(' ').next()?;
+    digits
+        .parse::<f32>()
+        .ok()
+        .filter(|p| (0.0..=100.0).contains(p))
 }
 
 fn summarize_command_output(raw: &str) -> String {
@@ -166,15 +301,14 @@ fn summarize_command_output(raw: &str) -> String {
 
 fn resolve_generated_stems(output_root: &Path) -> Result<StemSeparationResult, String> {
     let mut stack = vec![output_root.to_path_buf()];
-    let mut best: Option<(PathBuf, PathBuf, SystemTime)> = None;
+    let mut best: Option<([PathBuf; 4], SystemTime)> = None;
 
     while let Some(dir) = stack.pop() {
         let entries = match fs::read_dir(&dir) {
             Ok(v) => v,
             Err(_) => continue,
         };
-        let mut vocals: Option<PathBuf> = None;
-        let mut instrumental: Option<PathBuf> = None;
+        let mut found: [Option<PathBuf>; 4] = Default::default();
 
         for entry in entries.flatten() {
             let path = entry.path();
@@ -182,30 +316,28 @@ fn resolve_generated_stems(output_root: &Path) -> Result<StemSeparationResult, S
                 stack.push(path);
                 continue;
             }
-            let Some(name) = path.file_name().and_then(|v| v.to_str()) else {
+            let Some(stem) = path.file_stem().and_then(|v| v.to_str()) else {
                 continue;
             };
-            if name.starts_with("vocals.") {
-                vocals = Some(path);
-            } else if name.starts_with("no_vocals.") {
-                instrumental = Some(path);
+            if let Some(i) = FOUR_STEMS.iter().position(|s| *s == stem) {
+                found[i] = Some(path);
             }
         }
 
-        if let (Some(v), Some(i)) = (vocals, instrumental) {
+        if let [Some(v), Some(d), Some(b), Some(o)] = found {
             let mtime = fs::metadata(&v)
                 .and_then(|m| m.modified())
                 .unwrap_or(SystemTime::UNIX_EPOCH);
-            let replace = best.as_ref().map(|(_, _, t)| mtime > *t).unwrap_or(true);
+            let replace = best.as_ref().map(|(_, t)| mtime > *t).unwrap_or(true);
             if replace {
-                best = Some((v, i, mtime));
+                best = Some(([v, d, b, o], mtime));
             }
         }
     }
 
-    let Some((vocals_path, instrumental_path, _)) = best else {
+    let Some(([vocals_path, drums_path, bass_path, other_path], _)) = best else {
         return Err(format!(
-            "Demucs completed but no vocals/no_vocals outputs were found under {}",
+            "Demucs completed but no vocals/drums/bass/other outputs were found under {}",
             output_root.display()
         ));
     };
@@ -218,9 +350,108 @@ fn resolve_generated_stems(output_root: &Path) -> Result<StemSeparationResult, S
         .map(|s| s.to_string())
         .unwrap_or_else(|| "htdemucs".to_string());
 
+    let instrumental_path = vocals_path.with_file_name(format!("{INSTRUMENTAL_STEM}.wav"));
+    if !instrumental_path.exists() {
+        mix_wav_files(
+            &[
+                drums_path.as_path(),
+                bass_path.as_path(),
+                other_path.as_path(),
+            ],
+            &instrumental_path,
+        )?;
+    }
+
     Ok(StemSeparationResult {
         model_name,
         vocals_path,
+        drums_path,
+        bass_path,
+        other_path,
         instrumental_path,
     })
 }
+
+/// Sum same-format WAV files into a float WAV at `output` (no clipping:
+/// the stems of one track add back up to the original's level).
+fn mix_wav_files(inputs: &[&Path], output: &Path) -> Result<(), String> {
+    let mut readers = inputs
+        .iter()
+        .map(|p| hound::WavReader::open(p).map_err(|e| format!("Cannot read {}: {e}", p.display())))
+        .collect::<Result<Vec<_>, _>>()?;
+    let spec = readers.first().ok_or("No stems to mix")?.spec();
+    if readers.iter().any(|r| {
+        let s = r.spec();
+        s.channels != spec.channels || s.sample_rate != spec.sample_rate
+    }) {
+        return Err("Stems differ in channel count or sample rate".to_string());
+    }
+
+    let out_spec = hound::WavSpec {
+        channels: spec.channels,
+        sample_rate: spec.sample_rate,
+        bits_per_sample: 32,
+        sample_format: hound::SampleFormat::Float,
+    };
+    let tmp = output.with_extension("wav.part");
+    let mut writer = hound::WavWriter::create(&tmp, out_spec)
+        .map_err(|e| format!("Cannot create {}: {e}", tmp.display()))?;
+    let mut sources = readers.iter_mut().map(wav_samples_f32).collect::<Vec<_>>();
+    loop {
+        let mut sum = 0.0_f32;
+        let mut any = false;
+        for source in sources.iter_mut() {
+            match source.next() {
+                Some(Ok(v)) => {
+                    sum += v;
+                    any = true;
+                }
+                Some(Err(e)) => return Err(format!("Stem decode failed: {e}")),
+                None => {}
+            }
+        }
+        if !any {
+            break;
+        }
+        writer
+            .write_sample(sum)
+            .map_err(|e| format!("Cannot write instrumental: {e}"))?;
+    }
+    writer
+        .finalize()
+        .map_err(|e| format!("Cannot finish instrumental: {e}"))?;
+    fs::rename(&tmp, output).map_err(|e| format!("Cannot move {}: {e}", tmp.display()))
+}
+
+fn wav_samples_f32<'a, R: std::io::Read + 'a>(
+    reader: &'a mut hound::WavReader<R>,
+) -> Box<dyn Iterator<Item = Result<f32, hound::Error>> + 'a> {
+    let spec = reader.spec();
+    match spec.sample_format {
+        hound::SampleFormat::Float => Box::new(reader.samples::<f32>()),
+        hound::SampleFormat::Int => {
+            let scale = 1.0 / (1_i64 << (spec.bits_per_sample - 1)) as f32;
+            Box::new(
+                reader
+                    .samples::<i32>()
+                    .map(move |s| s.map(|v| v as f32 * scale)),
+            )
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_tqdm_percentages_only() {
+        assert_eq!(
+            parse_progress(" 42%|████      | 12.0/28.5 [00:03<00:04]"),
+            Some(42.0)
+        );
+        assert_eq!(parse_progress("100%|██████████| 28.5/28.5"), Some(100.0));
+        assert_eq!(parse_progress("Separating track song.mp3"), None);
+        assert_eq!(parse_progress("Selected model is a bag of 1 models."), None);
+    }
+}
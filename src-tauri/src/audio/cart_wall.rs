@@ -0,0 +1,747 @@
+/// Cart wall — a grid of one-shot audio carts (jingles, drops, beds)
+///
+/// Carts are decoded fully into memory when saved (and at startup), so a
+/// trigger only has to hand the engine an `Arc` of ready PCM. Playing carts
+/// are mixed into the Sound FX channel before its DSP chain, alongside the
+/// Sound FX deck, with a fixed pool of voices so several carts can overlap.
+use std::collections::HashMap;
+use std::fs::File;
+use std::path::Path;
+use std::sync::{
+    atomic::{AtomicU32, Ordering},
+    Arc, Mutex, OnceLock,
+};
+
+use serde::{Deserialize, Serialize};
+use sqlx::{Row, SqlitePool};
+use symphonia::core::{
+    audio::SampleBuffer,
+    codecs::{DecoderOptions, CODEC_TYPE_NULL},
+    errors::Error as SymphoniaError,
+    formats::FormatOptions,
+    io::MediaSourceStream,
+    meta::MetadataOptions,
+    probe::Hint,
+};
+
+use super::engine::AudioEngine;
+
+/// Carts that can sound at once; the oldest voice is stolen beyond this.
+pub const MAX_CART_VOICES: usize = 16;
+/// Finished samples held until the engine hands them back to the control
+/// side: one per voice plus one per trigger the command ring can deliver, so
+/// the list cannot fill before it is drained.
+const RETIRED_CAPACITY: usize = MAX_CART_VOICES + AudioEngine::CMD_RING_SIZE;
+/// Longest file accepted as a cart (keeps the in-memory bank bounded).
+pub const MAX_CART_SECS: u64 = 600;
+pub const MAX_CART_FADE_MS: u32 = 10_000;
+
+// ── Layout ───────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct CartWallLayout {
+    pub rows: u32,
+    pub cols: u32,
+    pub pages: u32,
+    /// Page used by controller pads
+    pub active_page: u32,
+}
+
+impl Default for CartWallLayout {
+    fn default() -> Self {
+        Self {
+            rows: 4,
+            cols: 8,
+            pages: 4,
+            active_page: 0,
+        }
+    }
+}
+
+impl CartWallLayout {
+    pub fn slots_per_page(&self) -> u32 {
+        self.rows * self.cols
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if !(1..=8).contains(&self.rows) || !(1..=16).contains(&self.cols) {
+            return Err("Cart wall grid must be 1–8 rows by 1–16 columns".to_string());
+        }
+        if !(1..=32).contains(&self.pages) {
+            return Err("Cart wall must have 1–32 pages".to_string());
+        }
+        if self.active_page >= self.pages {
+            return Err(format!("Active page {} out of range", self.active_page));
+        }
+        Ok(())
+    }
+
+    pub fn contains(&self, page: u32, slot: u32) -> bool {
+        page < self.pages && slot < self.slots_per_page()
+    }
+}
+
+static ACTIVE_PAGE: AtomicU32 = AtomicU32::new(0);
+
+pub fn active_page() -> u32 {
+    ACTIVE_PAGE.load(Ordering::Relaxed)
+}
+
+pub fn set_active_page(page: u32) {
+    ACTIVE_PAGE.store(page, Ordering::Relaxed);
+}
+
+// ── Cart definitions ─────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CartSlot {
+    pub page: u32,
+    /// Row-major index within the page
+    pub slot: u32,
+    pub label: String,
+    pub file_path: String,
+    /// SAM song the cart was picked from, if any
+    pub song_id: Option<i64>,
+    pub gain_db: f64,
+    /// Fade applied when the cart is stopped (0 = cut)
+    pub fade_out_ms: u32,
+    pub loop_enabled: bool,
+    pub color_hex: Option<String>,
+    /// Global shortcut, e.g. `CommandOrControl+F1`
+    pub hotkey: Option<String>,
+}
+
+impl CartSlot {
+    pub fn key(&self) -> CartKey {
+        CartKey {
+            page: self.page,
+            slot: self.slot,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct CartKey {
+    pub page: u32,
+    pub slot: u32,
+}
+
+// ── Decoded samples ──────────────────────────────────────────────────────────
+
+/// A cart decoded to interleaved stereo f32 at the file's own rate; the
+/// player resamples on the fly.
+pub struct CartSample {
+    pub samples: Vec<f32>,
+    pub sample_rate: u32,
+}
+
+impl CartSample {
+    pub fn frames(&self) -> usize {
+        self.samples.len() / 2
+    }
+
+    pub fn duration_ms(&self) -> u64 {
+        if self.sample_rate == 0 {
+            return 0;
+        }
+        self.frames() as u64 * 1000 / self.sample_rate as u64
+    }
+
+    /// Decode a whole file. Blocking — call from `spawn_blocking`.
+    pub fn decode(path: &Path) -> Result<Self, String> {
+        let file = File::open(path).map_err(|e| format!("Cannot open {}: {e}", path.display()))?;
+        let mss = MediaSourceStream::new(Box::new(file), Default::default());
+        let mut hint = Hint::new();
+        if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
+            hint.with_extension(ext);
+        }
+        let mut probed = symphonia::default::get_probe()
+            .format(
+                &hint,
+                mss,
+                &FormatOptions::default(),
+                &MetadataOptions::default(),
+            )
+            .map_err(|e| format!("Probe failed: {e}"))?;
+        let track = probed
+            .format
+            .tracks()
+            .iter()
+            .find(|t| t.codec_params.codec != CODEC_TYPE_NULL)
+            .ok_or("No audio track found")?
+            .clone();
+        let track_id = track.id;
+        let sample_rate = track.codec_params.sample_rate.unwrap_or(44100);
+        let max_frames = MAX_CART_SECS * sample_rate as u64;
+        if track.codec_params.n_frames.is_some_and(|n| n > max_frames) {
+            return Err(format!("Carts are limited to {MAX_CART_SECS} s"));
+        }
+
+        let mut decoder = symphonia::default::get_codecs()
+            .make(&track.codec_params, &DecoderOptions::default())
+            .map_err(|e| format!("Codec init failed: {e}"))?;
+
+        let mut samples = Vec::new();
+        let mut scratch: Option<SampleBuffer<f32>> = None;
+        loop {
+            let packet = match probed.format.next_packet() {
+                Ok(p) => p,
+                Err(SymphoniaError::IoError(e))
+                    if e.kind() == std::io::ErrorKind::UnexpectedEof =>
+                {
+                    break;
+                }
+                Err(SymphoniaError::ResetRequired) => {
+                    decoder.reset();
+                    continue;
+                }
+                Err(e) => return Err(format!("Read packet failed: {e}")),
+            };
+            if packet.track_id() != track_id {
+                continue;
+            }
+            let decoded = match decoder.decode(&packet) {
+                Ok(d) => d,
+                Err(SymphoniaError::DecodeError(_)) => continue,
+                Err(e) => return Err(format!("Decode failed: {e}")),
+            };
+            let spec = *decoded.spec();
+            let channels = spec.channels.count().max(1);
+            let needed = decoded.capacity() as u64;
+            if scratch
+                .as_ref()
+                .is_none_or(|b| (b.capacity() as u64) < needed * channels as u64)
+            {
+                scratch = Some(SampleBuffer::new(needed, spec));
+            }
+            let buf = scratch.as_mut().expect("scratch buffer");
+            buf.copy_interleaved_ref(decoded);
+            for frame in buf.samples().chunks_exact(channels) {
+                let l = frame[0];
+                let r = if channels > 1 { frame[1] } else { l };
+                samples.push(l);
+                samples.push(r);
+            }
+            if samples.len() as u64 / 2 > max_frames {
+                return Err(format!("Carts are limited to {MAX_CART_SECS} s"));
+            }
+        }
+        if samples.is_empty() {
+            return Err("Cart file decoded to silence".to_string());
+        }
+        Ok(Self {
+            samples,
+            sample_rate,
+        })
+    }
+}
+
+// ── Real-time player ─────────────────────────────────────────────────────────
+
+/// Everything the RT thread needs to start a cart.
+pub struct CartTrigger {
+    pub key: CartKey,
+    pub sample: Arc<CartSample>,
+    pub gain: f32,
+    pub looped: bool,
+    pub fade_out_ms: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CartVoiceState {
+    pub page: u32,
+    pub slot: u32,
+    pub position_ms: u64,
+    pub duration_ms: u64,
+    pub looped: bool,
+    pub fading: bool,
+}
+
+struct Voice {
+    key: CartKey,
+    sample: Arc<CartSample>,
+    gain: f32,
+    looped: bool,
+    head: Playhead,
+}
+
+/// Read position and fade of one playing sample. Cart voices and Sound FX
+/// voices both render through it.
+pub(super) struct Playhead {
+    /// Fractional read position in source frames
+    pub pos: f64,
+    /// Fade applied once `fading` is set (0 = cut)
+    pub fade_ms: u32,
+    pub fading: bool,
+    pub fade_gain: f32,
+}
+
+impl Playhead {
+    pub fn new(fade_ms: u32) -> Self {
+        Self {
+            pos: 0.0,
+            fade_ms,
+            fading: false,
+            fade_gain: 1.0,
+        }
+    }
+
+    pub fn position_ms(&self, sample: &CartSample) -> u64 {
+        (self.pos * 1000.0 / sample.sample_rate.max(1) as f64) as u64
+    }
+
+    /// Add `sample` into `out` (interleaved stereo at `device_sr`), resampling
+    /// linearly. Returns false once the voice has finished.
+    pub fn render(
+        &mut self,
+        sample: &CartSample,
+        gain: f32,
+        looped: bool,
+        out: &mut [f32],
+        device_sr: u32,
+    ) -> bool {
+        let frames = sample.frames();
+        if frames == 0 {
+            return false;
+        }
+        let step = sample.sample_rate as f64 / device_sr as f64;
+        let fade_step = if self.fading {
+            if self.fade_ms == 0 {
+                return false;
+            }
+            1.0 / (self.fade_ms as f32 * device_sr as f32 / 1000.0).max(1.0)
+        } else {
+            0.0
+        };
+        let s = &sample.samples;
+        for frame in out.chunks_exact_mut(2) {
+            if self.pos >= frames as f64 {
+                if !looped {
+                    return false;
+                }
+                self.pos -= frames as f64;
+            }
+            let i = self.pos as usize;
+            let frac = (self.pos - i as f64) as f32;
+            let j = if i + 1 < frames {
+                i + 1
+            } else if looped {
+                0
+            } else {
+                i
+            };
+            let l = s[i * 2] + (s[j * 2] - s[i * 2]) * frac;
+            let r = s[i * 2 + 1] + (s[j * 2 + 1] - s[i * 2 + 1]) * frac;
+            let g = gain * self.fade_gain;
+            frame[0] += l * g;
+            frame[1] += r * g;
+            if self.fading {
+                self.fade_gain -= fade_step;
+                if self.fade_gain <= 0.0 {
+                    return false;
+                }
+            }
+            self.pos += step;
+        }
+        true
+    }
+}
+
+/// Voice pool owned by the RT state. Never allocates after construction.
+///
+/// Samples of voices that end, restart or are stolen are parked in
+/// `retired` rather than dropped: the bank may have replaced its copy, which
+/// would leave the voice holding the last `Arc`. The engine sends them back
+/// to the control side to be freed (see [`CartPlayer::pop_retired`]).
+pub struct CartPlayer {
+    voices: Vec<Voice>,
+    retired: Vec<Arc<CartSample>>,
+}
+
+impl Default for CartPlayer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CartPlayer {
+    pub fn new() -> Self {
+        Self {
+            voices: Vec::with_capacity(MAX_CART_VOICES),
+            retired: Vec::with_capacity(RETIRED_CAPACITY),
+        }
+    }
+
+    /// Start a cart. Re-triggering a playing cart restarts it, except a
+    /// looping cart, which is faded out instead (pads act as on/off).
+    pub fn trigger(&mut self, t: CartTrigger) {
+        if let Some(v) = self.voices.iter_mut().find(|v| v.key == t.key) {
+            if v.looped && !v.head.fading {
+                v.head.fading = true;
+                return;
+            }
+            let previous = std::mem::replace(&mut v.sample, t.sample);
+            v.gain = t.gain;
+            v.looped = t.looped;
+            v.head = Playhead::new(t.fade_out_ms);
+            self.retire(previous);
+            return;
+        }
+        if self.voices.len() >= MAX_CART_VOICES {
+            let stolen = self.voices.remove(0);
+            self.retire(stolen.sample);
+        }
+        self.voices.push(Voice {
+            key: t.key,
+            sample: t.sample,
+            gain: t.gain,
+            looped: t.looped,
+            head: Playhead::new(t.fade_out_ms),
+        });
+    }
+
+    /// Stop a cart using its configured fade-out.
+    pub fn stop(&mut self, key: CartKey) {
+        self.retain_voices(|v| {
+            if v.key != key {
+                return true;
+            }
+            v.head.fading = true;
+            v.head.fade_ms > 0
+        });
+    }
+
+    pub fn stop_all(&mut self) {
+        self.retain_voices(|v| {
+            v.head.fading = true;
+            v.head.fade_ms > 0
+        });
+    }
+
+    pub fn is_active(&self) -> bool {
+        !self.voices.is_empty()
+    }
+
+    /// Add all voices into `out` (interleaved stereo at `device_sr`).
+    pub fn render(&mut self, out: &mut [f32], device_sr: u32) {
+        if self.voices.is_empty() || device_sr == 0 {
+            return;
+        }
+        self.retain_voices(|v| v.head.render(&v.sample, v.gain, v.looped, out, device_sr));
+    }
+
+    /// A finished sample for the engine to send back.
+    pub fn pop_retired(&mut self) -> Option<Arc<CartSample>> {
+        self.retired.pop()
+    }
+
+    /// `Vec::retain_mut` that retires the samples of dropped voices.
+    fn retain_voices(&mut self, mut keep: impl FnMut(&mut Voice) -> bool) {
+        let mut i = 0;
+        while i < self.voices.len() {
+            if keep(&mut self.voices[i]) {
+                i += 1;
+            } else {
+                let voice = self.voices.remove(i);
+                self.retire(voice.sample);
+            }
+        }
+    }
+
+    fn retire(&mut self, sample: Arc<CartSample>) {
+        // `RETIRED_CAPACITY` leaves room for everything one drain can bring
+        // in; the check only keeps `push` from ever allocating.
+        if self.retired.len() < RETIRED_CAPACITY {
+            self.retired.push(sample);
+        }
+    }
+
+    pub fn states(&self) -> Vec<CartVoiceState> {
+        let mut out = Vec::with_capacity(self.voices.len());
+        self.write_states(&mut out);
+        out
+    }
+
+    /// `states` into `out`, reusing its allocation (for the audio thread).
+    pub fn write_states(&self, out: &mut Vec<CartVoiceState>) {
+        out.clear();
+        out.extend(self.voices.iter().map(|v| CartVoiceState {
+            page: v.key.page,
+            slot: v.key.slot,
+            position_ms: v.head.position_ms(&v.sample),
+            duration_ms: v.sample.duration_ms(),
+            looped: v.looped,
+            fading: v.head.fading,
+        }));
+    }
+}
+
+fn db_to_linear(db: f32) -> f32 {
+    10.0_f32.powf(db / 20.0)
+}
+
+// ── Preload bank ─────────────────────────────────────────────────────────────
+
+#[derive(Clone)]
+pub struct LoadedCart {
+    pub cart: CartSlot,
+    pub sample: Arc<CartSample>,
+}
+
+impl LoadedCart {
+    pub fn trigger(&self) -> CartTrigger {
+        CartTrigger {
+            key: self.cart.key(),
+            sample: Arc::clone(&self.sample),
+            gain: db_to_linear(self.cart.gain_db as f32),
+            looped: self.cart.loop_enabled,
+            fade_out_ms: self.cart.fade_out_ms,
+        }
+    }
+}
+
+fn bank() -> &'static Mutex<HashMap<CartKey, LoadedCart>> {
+    static BANK: OnceLock<Mutex<HashMap<CartKey, LoadedCart>>> = OnceLock::new();
+    BANK.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Decode (unless the file is unchanged) and keep a cart ready to fire.
+pub async fn preload(cart: CartSlot) -> Result<u64, String> {
+    let key = cart.key();
+    let existing = bank()
+        .lock()
+        .unwrap()
+        .get(&key)
+        .filter(|l| l.cart.file_path == cart.file_path)
+        .map(|l| Arc::clone(&l.sample));
+    let sample = match existing {
+        Some(sample) => sample,
+        None => {
+            let path = cart.file_path.clone();
+            let sample = tokio::task::spawn_blocking(move || CartSample::decode(Path::new(&path)))
+                .await
+                .map_err(|e| e.to_string())??;
+            Arc::new(sample)
+        }
+    };
+    let duration_ms = sample.duration_ms();
+    bank()
+        .lock()
+        .unwrap()
+        .insert(key, LoadedCart { cart, sample });
+    Ok(duration_ms)
+}
+
+pub fn loaded(key: CartKey) -> Option<LoadedCart> {
+    bank().lock().unwrap().get(&key).cloned()
+}
+
+pub fn loaded_carts() -> Vec<CartSlot> {
+    let mut carts: Vec<CartSlot> = bank()
+        .lock()
+        .unwrap()
+        .values()
+        .map(|l| l.cart.clone())
+        .collect();
+    carts.sort_by_key(|c| (c.page, c.slot));
+    carts
+}
+
+pub fn evict(key: CartKey) {
+    bank().lock().unwrap().remove(&key);
+}
+
+/// Drop carts that fall outside a (shrunk) layout.
+pub fn retain_within(layout: &CartWallLayout) {
+    bank()
+        .lock()
+        .unwrap()
+        .retain(|k, _| layout.contains(k.page, k.slot));
+}
+
+/// Load every saved cart. Failures are logged and the cart left unloaded.
+pub async fn preload_all(pool: &SqlitePool) -> Result<usize, sqlx::Error> {
+    let layout = get_layout(pool).await?;
+    set_active_page(layout.active_page);
+    let mut loaded = 0;
+    for cart in get_carts(pool).await? {
+        if !layout.contains(cart.page, cart.slot) {
+            continue;
+        }
+        match preload(cart.clone()).await {
+            Ok(_) => loaded += 1,
+            Err(e) => log::warn!(
+                "Cart {}/{} ({}) not loaded: {e}",
+                cart.page,
+                cart.slot,
+                cart.file_path
+            ),
+        }
+    }
+    Ok(loaded)
+}
+
+// ── DB helpers ────────────────────────────────────────────────────────────────
+
+pub async fn get_layout(pool: &SqlitePool) -> Result<CartWallLayout, sqlx::Error> {
+    let row: Option<String> =
+        sqlx::query_scalar("SELECT config_json FROM cart_wall_config WHERE id = 1")
+            .fetch_optional(pool)
+            .await?;
+    Ok(row
+        .and_then(|j| serde_json::from_str(&j).ok())
+        .unwrap_or_default())
+}
+
+pub async fn save_layout(pool: &SqlitePool, layout: &CartWallLayout) -> Result<(), sqlx::Error> {
+    let json = serde_json::to_string(layout).unwrap_or_else(|_| "{}".to_string());
+    sqlx::query(
+        "INSERT INTO cart_wall_config (id, config_json, updated_at) VALUES (1, ?, strftime('%s','now')) \
+         ON CONFLICT(id) DO UPDATE SET config_json = excluded.config_json, updated_at = excluded.updated_at",
+    )
+    .bind(json)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+pub async fn get_carts(pool: &SqlitePool) -> Result<Vec<CartSlot>, sqlx::Error> {
+    let rows = sqlx::query(
+        "SELECT page, slot, label, file_path, song_id, gain_db, fade_out_ms, loop_enabled, \
+         color_hex, hotkey FROM carts ORDER BY page, slot",
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(rows
+        .into_iter()
+        .map(|r| CartSlot {
+            page: r.get::<i64, _>("page") as u32,
+            slot: r.get::<i64, _>("slot") as u32,
+            label: r.get("label"),
+            file_path: r.get("file_path"),
+            song_id: r.get("song_id"),
+            gain_db: r.get("gain_db"),
+            fade_out_ms: r.get::<i64, _>("fade_out_ms").max(0) as u32,
+            loop_enabled: r.get::<i64, _>("loop_enabled") != 0,
+            color_hex: r.get("color_hex"),
+            hotkey: r.get("hotkey"),
+        })
+        .collect())
+}
+
+pub async fn upsert_cart(pool: &SqlitePool, cart: &CartSlot) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        INSERT INTO carts
+            (page, slot, label, file_path, song_id, gain_db, fade_out_ms,
+             loop_enabled, color_hex, hotkey, updated_at)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, strftime('%s','now'))
+        ON CONFLICT(page, slot) DO UPDATE SET
+            label        = excluded.label,
+            file_path    = excluded.file_path,
+            song_id      = excluded.song_id,
+            gain_db      = excluded.gain_db,
+            fade_out_ms  = excluded.fade_out_ms,
+            loop_enabled = excluded.loop_enabled,
+            color_hex    = excluded.color_hex,
+            hotkey       = excluded.hotkey,
+            updated_at   = excluded.updated_at
+        "#,
+    )
+    .bind(cart.page as i64)
+    .bind(cart.slot as i64)
+    .bind(&cart.label)
+    .bind(&cart.file_path)
+    .bind(cart.song_id)
+    .bind(cart.gain_db)
+    .bind(cart.fade_out_ms as i64)
+    .bind(cart.loop_enabled as i64)
+    .bind(&cart.color_hex)
+    .bind(&cart.hotkey)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+pub async fn delete_cart(pool: &SqlitePool, page: u32, slot: u32) -> Result<(), sqlx::Error> {
+    sqlx::query("DELETE FROM carts WHERE page = ? AND slot = ?")
+        .bind(page as i64)
+        .bind(slot as i64)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trigger(slot: u32, frames: usize, looped: bool, fade_out_ms: u32) -> CartTrigger {
+        CartTrigger {
+            key: CartKey { page: 0, slot },
+            sample: Arc::new(CartSample {
+                samples: vec![0.5; frames * 2],
+                sample_rate: 1000,
+            }),
+            gain: 1.0,
+            looped,
+            fade_out_ms,
+        }
+    }
+
+    #[test]
+    fn one_shot_ends_and_loop_pad_toggles_off() {
+        let mut player = CartPlayer::new();
+        let mut out = vec![0.0; 20];
+        player.trigger(trigger(0, 5, false, 0));
+        player.render(&mut out, 1000);
+        assert!((out[0] - 0.5).abs() < 1e-6);
+        assert_eq!(out[10], 0.0);
+        assert!(!player.is_active());
+
+        player.trigger(trigger(1, 5, true, 0));
+        player.render(&mut out, 1000);
+        assert!(player.is_active());
+        // Second press on a looping cart stops it.
+        player.trigger(trigger(1, 5, true, 0));
+        player.render(&mut out, 1000);
+        assert!(!player.is_active());
+    }
+
+    #[test]
+    fn stop_fades_and_voices_are_capped() {
+        let mut player = CartPlayer::new();
+        player.trigger(trigger(0, 1000, false, 5));
+        player.stop(CartKey { page: 0, slot: 0 });
+        let mut out = vec![0.0; 20];
+        player.render(&mut out, 1000);
+        assert!(out[2] < out[0]);
+        assert!(!player.is_active());
+
+        for slot in 0..(MAX_CART_VOICES as u32 + 4) {
+            player.trigger(trigger(slot, 1000, false, 0));
+        }
+        assert_eq!(player.states().len(), MAX_CART_VOICES);
+        assert_eq!(player.states()[0].slot, 4);
+    }
+
+    #[test]
+    fn ended_and_stolen_voices_retire_their_samples() {
+        let mut player = CartPlayer::new();
+        let t = trigger(0, 5, false, 0);
+        let sample = Arc::clone(&t.sample);
+        player.trigger(t);
+        player.render(&mut [0.0; 20], 1000);
+        assert!(!player.is_active());
+        // The voice let go of its reference without freeing it.
+        let retired = player.pop_retired().expect("retired sample");
+        assert!(Arc::ptr_eq(&retired, &sample));
+        assert!(player.pop_retired().is_none());
+
+        for slot in 0..=(MAX_CART_VOICES as u32) {
+            player.trigger(trigger(slot, 1000, false, 0));
+        }
+        assert!(player.pop_retired().is_some());
+    }
+}
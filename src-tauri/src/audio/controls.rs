@@ -0,0 +1,140 @@
+/// Latest-value slots for continuous controls.
+///
+/// Faders, knobs and the crossfader send a stream of values where only the
+/// newest matters. Instead of queueing each one on the engine command ring
+/// (where a jog wheel or fader sweep could fill it and crowd out transport
+/// commands), the control side stores the value in its slot and marks it
+/// dirty; the audio callback applies whatever is dirty once per block.
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+
+use super::crossfade::DeckId;
+
+/// Per-deck continuous controls.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeckControl {
+    Gain,
+    Pitch,
+    Tempo,
+    Bass,
+    Filter,
+}
+
+impl DeckControl {
+    const ALL: [DeckControl; 5] = [
+        DeckControl::Gain,
+        DeckControl::Pitch,
+        DeckControl::Tempo,
+        DeckControl::Bass,
+        DeckControl::Filter,
+    ];
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Control {
+    Deck(DeckId, DeckControl),
+    ManualCrossfade,
+    MasterLevel,
+    HeadphoneMix,
+    HeadphoneLevel,
+}
+
+const GLOBALS: [Control; 4] = [
+    Control::ManualCrossfade,
+    Control::MasterLevel,
+    Control::HeadphoneMix,
+    Control::HeadphoneLevel,
+];
+const DECK_SLOTS: usize = DeckId::ALL.len() * DeckControl::ALL.len();
+const SLOTS: usize = DECK_SLOTS + GLOBALS.len();
+
+impl Control {
+    fn slot(self) -> usize {
+        match self {
+            Control::Deck(deck, control) => {
+                let deck = DeckId::ALL.iter().position(|d| *d == deck).unwrap_or(0);
+                let control = DeckControl::ALL
+                    .iter()
+                    .position(|c| *c == control)
+                    .unwrap_or(0);
+                deck * DeckControl::ALL.len() + control
+            }
+            global => DECK_SLOTS + GLOBALS.iter().position(|g| *g == global).unwrap_or(0),
+        }
+    }
+
+    fn from_slot(slot: usize) -> Self {
+        if slot < DECK_SLOTS {
+            let per_deck = DeckControl::ALL.len();
+            Control::Deck(
+                DeckId::ALL[slot / per_deck],
+                DeckControl::ALL[slot % per_deck],
+            )
+        } else {
+            GLOBALS[slot - DECK_SLOTS]
+        }
+    }
+}
+
+/// Shared between `AudioEngine` and the audio callback.
+pub struct ControlSlots {
+    /// f32 bits, indexed by `Control::slot`
+    values: [AtomicU32; SLOTS],
+    /// One bit per slot written since the callback last took it
+    dirty: AtomicU64,
+}
+
+impl Default for ControlSlots {
+    fn default() -> Self {
+        Self {
+            values: std::array::from_fn(|_| AtomicU32::new(0)),
+            dirty: AtomicU64::new(0),
+        }
+    }
+}
+
+impl ControlSlots {
+    /// Replace any value not yet applied.
+    pub fn set(&self, control: Control, value: f32) {
+        let slot = control.slot();
+        self.values[slot].store(value.to_bits(), Ordering::Relaxed);
+        self.dirty.fetch_or(1 << slot, Ordering::Release);
+    }
+
+    /// Values set since the last call, newest per control. Wait-free.
+    pub fn take(&self) -> impl Iterator<Item = (Control, f32)> + '_ {
+        let mut dirty = self.dirty.swap(0, Ordering::Acquire);
+        std::iter::from_fn(move || {
+            if dirty == 0 {
+                return None;
+            }
+            let slot = dirty.trailing_zeros() as usize;
+            dirty &= dirty - 1;
+            let value = f32::from_bits(self.values[slot].load(Ordering::Relaxed));
+            Some((Control::from_slot(slot), value))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_only_the_newest_value_per_control() {
+        let slots = ControlSlots::default();
+        for i in 0..100 {
+            slots.set(Control::Deck(DeckId::Aux2, DeckControl::Gain), i as f32);
+        }
+        slots.set(Control::HeadphoneLevel, 0.5);
+
+        let taken: Vec<_> = slots.take().collect();
+        assert_eq!(
+            taken,
+            vec![
+                (Control::Deck(DeckId::Aux2, DeckControl::Gain), 99.0),
+                (Control::HeadphoneLevel, 0.5),
+            ]
+        );
+        assert_eq!(slots.take().count(), 0);
+    }
+}
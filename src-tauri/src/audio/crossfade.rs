@@ -175,6 +175,11 @@ pub struct CrossfadeConfig {
     /// If `Some`, crossfade begins this many ms before the track's xfade cue
     /// point (or end).  Overrides auto-detect when set.
     pub fixed_crossfade_point_ms: Option<u32>,
+
+    // ── Crossfader assignment ─────────────────────────────────────────────
+    /// Which crossfader side each playback deck follows.
+    #[serde(default)]
+    pub crossfader_assign: CrossfaderAssign,
 }
 
 impl Default for CrossfadeConfig {
@@ -206,10 +211,93 @@ impl Default for CrossfadeConfig {
             auto_detect_min_ms: 500,
             auto_detect_max_ms: 15000,
             fixed_crossfade_point_ms: Some(8000),
+
+            crossfader_assign: CrossfaderAssign::default(),
         }
     }
 }
 
+// ── Crossfader assignment ─────────────────────────────────────────────────────
+
+/// Crossfader side a deck follows. `Thru` decks ignore the fader and stay at
+/// full level, which suits beds layered under the A/B rotation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CrossfaderSide {
+    A,
+    B,
+    Thru,
+}
+
+impl CrossfaderSide {
+    /// Gain at manual crossfader position `pos` ∈ [-1.0, 1.0] (−1 = full A).
+    pub fn manual_gain(self, pos: f32) -> f32 {
+        let pos = pos.clamp(-1.0, 1.0);
+        match self {
+            CrossfaderSide::A => ((1.0 - pos) * 0.5).clamp(0.0, 1.0),
+            CrossfaderSide::B => ((1.0 + pos) * 0.5).clamp(0.0, 1.0),
+            CrossfaderSide::Thru => 1.0,
+        }
+    }
+
+    /// Fader position that fully opens this side; `None` for `Thru`.
+    pub fn position(self) -> Option<f32> {
+        match self {
+            CrossfaderSide::A => Some(-1.0),
+            CrossfaderSide::B => Some(1.0),
+            CrossfaderSide::Thru => None,
+        }
+    }
+}
+
+/// Crossfader assignment of the four playback decks. Defaults keep the
+/// classic layout: A and B on their sides, decks C/D (Aux 1/2) thru.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CrossfaderAssign {
+    pub deck_a: CrossfaderSide,
+    pub deck_b: CrossfaderSide,
+    pub deck_c: CrossfaderSide,
+    pub deck_d: CrossfaderSide,
+}
+
+impl Default for CrossfaderAssign {
+    fn default() -> Self {
+        Self {
+            deck_a: CrossfaderSide::A,
+            deck_b: CrossfaderSide::B,
+            deck_c: CrossfaderSide::Thru,
+            deck_d: CrossfaderSide::Thru,
+        }
+    }
+}
+
+impl CrossfaderAssign {
+    /// Side for `deck`; channels that are not playback decks are always thru.
+    pub fn side(&self, deck: DeckId) -> CrossfaderSide {
+        match deck {
+            DeckId::DeckA => self.deck_a,
+            DeckId::DeckB => self.deck_b,
+            DeckId::Aux1 => self.deck_c,
+            DeckId::Aux2 => self.deck_d,
+            _ => CrossfaderSide::Thru,
+        }
+    }
+
+    /// Returns false if `deck` is not a playback deck.
+    pub fn set(&mut self, deck: DeckId, side: CrossfaderSide) -> bool {
+        let slot = match deck {
+            DeckId::DeckA => &mut self.deck_a,
+            DeckId::DeckB => &mut self.deck_b,
+            DeckId::Aux1 => &mut self.deck_c,
+            DeckId::Aux2 => &mut self.deck_d,
+            _ => return false,
+        };
+        *slot = side;
+        true
+    }
+}
+
 // ── SongFadeOverride ──────────────────────────────────────────────────────────
 
 /// Per-song fade overrides — if all fields are `None`, inherit from
@@ -246,17 +334,41 @@ impl SongFadeOverride {
 // ── DeckId ────────────────────────────────────────────────────────────────────
 
 /// Audio channel / deck identifier used throughout the engine.
+///
+/// Aux 1 and Aux 2 are full playback decks and double as decks C and D.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum DeckId {
     DeckA,
     DeckB,
     SoundFx,
+    #[serde(rename = "aux_1", alias = "aux1", alias = "deck_c")]
     Aux1,
+    #[serde(rename = "aux_2", alias = "aux2", alias = "deck_d")]
     Aux2,
     VoiceFx,
 }
 
+impl DeckId {
+    /// Decks that play library tracks with the full deck feature set
+    /// (pitch, loops, hot cues, crossfader, AutoDJ rotation).
+    pub const PLAYBACK: [DeckId; 4] = [DeckId::DeckA, DeckId::DeckB, DeckId::Aux1, DeckId::Aux2];
+
+    /// Every mixer channel, in mixer order.
+    pub const ALL: [DeckId; 6] = [
+        DeckId::DeckA,
+        DeckId::DeckB,
+        DeckId::SoundFx,
+        DeckId::Aux1,
+        DeckId::Aux2,
+        DeckId::VoiceFx,
+    ];
+
+    pub fn is_playback(self) -> bool {
+        Self::PLAYBACK.contains(&self)
+    }
+}
+
 impl std::fmt::Display for DeckId {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -1014,4 +1126,28 @@ mod tests {
         assert_eq!(cfg.skip_short_tracks_secs, Some(65));
         assert_eq!(cfg.fixed_crossfade_point_ms, Some(8000));
     }
+
+    #[test]
+    fn crossfader_assignment_gains() {
+        let mut assign = CrossfaderAssign::default();
+        assert_eq!(assign.side(DeckId::Aux1), CrossfaderSide::Thru);
+        assert_eq!(assign.side(DeckId::SoundFx), CrossfaderSide::Thru);
+        assert!(assign.set(DeckId::Aux1, CrossfaderSide::B));
+        assert!(!assign.set(DeckId::VoiceFx, CrossfaderSide::A));
+
+        let side = assign.side(DeckId::Aux1);
+        assert!((side.manual_gain(-1.0)).abs() < 1e-6);
+        assert!((side.manual_gain(1.0) - 1.0).abs() < 1e-6);
+        assert_eq!(CrossfaderSide::Thru.manual_gain(-1.0), 1.0);
+
+        // Configs saved before the assignment existed keep the A/B layout.
+        let mut json = serde_json::to_value(CrossfadeConfig::default()).unwrap();
+        json.as_object_mut().unwrap().remove("crossfader_assign");
+        let cfg: CrossfadeConfig = serde_json::from_value(json).unwrap();
+        assert_eq!(cfg.crossfader_assign, CrossfaderAssign::default());
+
+        let deck: DeckId = serde_json::from_str("\"deck_c\"").unwrap();
+        assert_eq!(deck, DeckId::Aux1);
+        assert_eq!(serde_json::to_string(&deck).unwrap(), "\"aux_1\"");
+    }
 }
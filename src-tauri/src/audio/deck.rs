@@ -1,4 +1,7 @@
-use std::{path::PathBuf, sync::atomic::Ordering};
+use std::{
+    path::PathBuf,
+    sync::{atomic::Ordering, Arc},
+};
 
 use ringbuf::traits::Observer as _;
 
@@ -7,12 +10,17 @@ use serde::{Deserialize, Serialize};
 use super::{
     crossfade::DeckId,
     decoder::{spawn_decoder, DecoderHandle},
+    dsp::keylock::Keylock,
+    remote_stream::RemoteStreamStatus,
+    reverse::{Direction, ReverseHistory, HISTORY_FRAMES},
+    stem_mix::{StemLayer, StemMix, StemPaths, StemSources},
 };
 
 /// Deck playback states — exposed to the frontend via IPC events
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum DeckState {
+    #[default]
     Idle,
     Loading,
     Ready,
@@ -32,6 +40,11 @@ pub struct Deck {
 
     // Active decoder (None when Idle/Stopped)
     decoder: Option<DecoderHandle>,
+    /// Drums/bass/other followers while playing separated stems; the decoder
+    /// above then plays the vocals stem.
+    stems: Option<StemLayer>,
+    /// Operator stem levels (reset on load)
+    pub stem_mix: StemMix,
 
     // Current track info
     pub file_path: Option<PathBuf>,
@@ -47,13 +60,31 @@ pub struct Deck {
     pub frames_consumed: u64,
     /// Per-channel operator gain (volume fader).
     pub channel_gain: f32,
+    /// Load-time trim (song gain override + category trim) in dB, applied
+    /// ahead of the fader.
+    pub track_gain_db: f32,
+    /// Linear form of `track_gain_db`.
+    track_gain: f32,
     /// Crossfade/manual-xfade gain multiplier.
     pub xfade_gain: f32,
     /// Linked transport controls for this phase.
     pub pitch_pct: f32,
     pub tempo_pct: f32,
     pub playback_rate: f32,
-    /// Rolling RMS level (dBFS) before channel/crossfade gain scaling.
+    /// Hold pitch when the playback rate moves off 1.0 (kept across loads).
+    pub keylock: bool,
+    keylock_shifter: Keylock,
+    /// Recently played frames for reverse/censor (playback decks only)
+    history: ReverseHistory,
+    /// Slip mode: loops, jumps and reverse run over a notional playhead that
+    /// keeps moving; leaving slip returns playback to it.
+    pub slip: bool,
+    /// Notional playhead in source frames (tracked while `slip` is on)
+    slip_frames: u64,
+    /// The next seek is the return to `slip_frames`; resync on attach
+    slip_return_pending: bool,
+    /// Rolling RMS level (dBFS) after the track trim, before channel/crossfade
+    /// gain scaling.
     pub rms_db_pre_fader: f32,
 
     // Pause state: when paused we stop pulling from the ring buffer
@@ -83,11 +114,30 @@ pub struct Deck {
     loop_state: Option<LoopState>,
 }
 
+/// Why a deck stopped with a completion record.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StopReason {
+    /// Decoder ran out of audio
+    #[default]
+    Ended,
+    /// Operator pressed next/stop
+    Skipped,
+    /// Faded out by a crossfade, gap segue or hard timed event
+    Crossfade,
+    /// Decoder gave up on the file before its end
+    Error,
+}
+
 #[derive(Debug, Clone)]
 pub struct TrackCompletion {
     pub song_id: i64,
     pub queue_id: Option<i64>,
     pub from_rotation: bool,
+    /// Position reached when the track stopped
+    pub played_ms: u64,
+    pub duration_ms: u64,
+    pub reason: StopReason,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -104,11 +154,20 @@ pub struct PreparedTrack {
     pub from_rotation: bool,
     pub declared_duration_ms: Option<u64>,
     pub initial_frames_consumed: u64,
+    /// Trim to apply when this track is loaded (ignored for seeks)
+    pub track_gain_db: f32,
+    /// Follower stems when `decoder` plays the vocals of a separation
+    pub stems: Option<StemSources>,
 }
 
 struct PendingSwap {
     prepared: PreparedTrack,
     op: AttachOp,
+    /// Quantized jump: finish the swap-out on this source frame
+    at_frame: Option<u64>,
+    /// Position when the jump was requested; moving back past it (loop wrap,
+    /// reverse) means `at_frame` may never come, so swap straight away
+    requested_at: u64,
 }
 
 struct LoopState {
@@ -117,12 +176,23 @@ struct LoopState {
     cached_frames: u64,
     play_frame: u64,
     playing_from_buffer: bool,
+    /// Doubled past the captured audio: end moves here at the next wrap and
+    /// playback runs on into the decoder, which is parked at the capture end
+    grow_to_frame: Option<u64>,
     buffer: Vec<f32>,
 }
 
 const SWAP_OUT_MS: u64 = 10;
 const SWAP_PREROLL_MS: u64 = 20;
-const MAX_LOOP_SECONDS: u64 = 64;
+/// A seek on a playing deck lands this much later (preroll + swap-out ramp).
+const SEEK_SWAP_LATENCY_MS: u64 = SWAP_PREROLL_MS + SWAP_OUT_MS;
+/// Quantized jumps target a grid boundary at least this far ahead, leaving
+/// time to preroll the new decoder and for the position to be a callback old.
+pub const QUANTIZE_LEAD_MS: u64 = SEEK_SWAP_LATENCY_MS + 20;
+/// Notional and real playheads closer than this count as in step.
+const SLIP_RESYNC_MS: u64 = 5;
+pub const MAX_LOOP_SECONDS: u64 = 64;
+const MIN_LOOP_FRAMES: u64 = 16;
 const LOOP_WRAP_MIN_XFADE_FRAMES: u64 = 24;
 const LOOP_WRAP_MAX_XFADE_FRAMES: u64 = 160;
 
@@ -132,6 +202,8 @@ impl Deck {
             id,
             state: DeckState::Idle,
             decoder: None,
+            stems: None,
+            stem_mix: StemMix::default(),
             file_path: None,
             song_id: None,
             queue_id: None,
@@ -140,10 +212,18 @@ impl Deck {
             declared_duration_ms: None,
             frames_consumed: 0,
             channel_gain: 1.0,
+            track_gain_db: 0.0,
+            track_gain: 1.0,
             xfade_gain: 1.0,
             pitch_pct: 0.0,
             tempo_pct: 0.0,
             playback_rate: 1.0,
+            keylock: false,
+            keylock_shifter: Keylock::new(),
+            history: ReverseHistory::new(if id.is_playback() { HISTORY_FRAMES } else { 0 }),
+            slip: false,
+            slip_frames: 0,
+            slip_return_pending: false,
             rms_db_pre_fader: -96.0,
             paused: false,
             ended_naturally: false,
@@ -182,6 +262,8 @@ impl Deck {
             from_rotation,
             declared_duration_ms,
             initial_frames_consumed: 0,
+            track_gain_db: 0.0,
+            stems: None,
         })
     }
 
@@ -203,18 +285,94 @@ impl Deck {
             from_rotation,
             declared_duration_ms,
             initial_frames_consumed,
+            track_gain_db: 0.0,
+            stems: None,
+        })
+    }
+
+    /// Like `prepare_seek`, but playing the separated `stems` of the track at
+    /// `path` in sync. The deck keeps reporting `path` as its file.
+    pub fn prepare_stems(
+        path: PathBuf,
+        stems: StemPaths,
+        song_id: Option<i64>,
+        queue_id: Option<i64>,
+        from_rotation: bool,
+        declared_duration_ms: Option<u64>,
+        position_ms: u64,
+    ) -> Result<PreparedTrack, String> {
+        let decoder = spawn_decoder(stems[0].clone(), Some(position_ms))?;
+        let followers = match StemSources::spawn(stems, Some(position_ms), decoder.sample_rate) {
+            Ok(followers) => followers,
+            Err(e) => {
+                decoder.stop_flag.store(true, Ordering::Relaxed);
+                return Err(e);
+            }
+        };
+        let initial_frames_consumed = position_ms.saturating_mul(decoder.sample_rate as u64) / 1000;
+        Ok(PreparedTrack {
+            decoder,
+            file_path: path,
+            song_id,
+            queue_id,
+            from_rotation,
+            declared_duration_ms,
+            initial_frames_consumed,
+            track_gain_db: 0.0,
+            stems: Some(followers),
         })
     }
 
     pub fn request_attach(&mut self, prepared: PreparedTrack, op: AttachOp) {
+        self.request_attach_at(prepared, op, None);
+    }
+
+    /// Attach, holding a playing deck's swap until `at_frame` (a beat) so the
+    /// jump lands in time. Stopped or paused decks jump straight away.
+    pub fn request_attach_at(
+        &mut self,
+        prepared: PreparedTrack,
+        op: AttachOp,
+        at_frame: Option<u64>,
+    ) {
         if matches!(self.state, DeckState::Playing | DeckState::Crossfading) {
-            self.pending_swap = Some(PendingSwap { prepared, op });
+            self.pending_swap = Some(PendingSwap {
+                prepared,
+                op,
+                at_frame,
+                requested_at: self.frames_consumed,
+            });
             self.maybe_begin_pending_swap();
         } else {
             self.apply_prepared(prepared, op);
         }
     }
 
+    /// Set the load-time trim. While a load is waiting to swap in, the trim
+    /// belongs to that incoming track, not the one still playing.
+    pub fn set_track_gain_db(&mut self, gain_db: f32) {
+        let gain_db = gain_db.clamp(-24.0, 12.0);
+        match self.pending_swap.as_mut() {
+            Some(pending) if matches!(pending.op, AttachOp::Load) => {
+                pending.prepared.track_gain_db = gain_db;
+            }
+            _ => {
+                self.track_gain_db = gain_db;
+                self.track_gain = 10f32.powf(gain_db / 20.0);
+            }
+        }
+    }
+
+    /// Stem files being mixed, while the deck plays separated stems.
+    pub fn stem_paths(&self) -> Option<&StemPaths> {
+        self.stems.as_ref().map(|s| s.paths())
+    }
+
+    /// Set stem levels; they glide in over a few milliseconds.
+    pub fn set_stem_mix(&mut self, mix: StemMix) {
+        self.stem_mix = mix.clamped();
+    }
+
     /// Load a new track. Stops any existing playback.
     pub fn load(
         &mut self,
@@ -233,6 +391,9 @@ impl Deck {
         self.declared_duration_ms = declared_duration_ms;
         self.frames_consumed = 0;
         self.xfade_gain = 1.0;
+        self.track_gain_db = 0.0;
+        self.track_gain = 1.0;
+        self.stem_mix = StemMix::default();
         self.ended_naturally = false;
         self.completion_pending = None;
         self.reset_resampler();
@@ -250,14 +411,22 @@ impl Deck {
     /// Seek to a position (stops current decoder and spawns a new one at the target).
     pub fn seek(&mut self, position_ms: u64) -> Result<(), String> {
         let path = self.file_path.clone().ok_or("No track loaded")?;
+        let stem_paths = self.stem_paths().cloned();
         self.stop_decoder();
         self.frames_consumed = (position_ms * self.sample_rate as u64) / 1000;
         self.reset_resampler();
         self.reset_swap_state();
 
-        let handle = spawn_decoder(path, Some(position_ms))?;
+        let handle = match &stem_paths {
+            Some(stems) => spawn_decoder(stems[0].clone(), Some(position_ms))?,
+            None => spawn_decoder(path, Some(position_ms))?,
+        };
         self.sample_rate = handle.sample_rate;
         self.decoder = Some(handle);
+        if let Some(stems) = stem_paths {
+            let sources = StemSources::spawn(stems, Some(position_ms), self.sample_rate)?;
+            self.stems = Some(StemLayer::new(sources, &self.stem_mix));
+        }
 
         if self.state == DeckState::Playing || self.state == DeckState::Crossfading {
             // Keep playing state — the render thread will pick up the new ring buffer
@@ -306,6 +475,13 @@ impl Deck {
         }
     }
 
+    /// Undo `set_crossfading` when a fade is abandoned.
+    pub fn clear_crossfading(&mut self) {
+        if self.state == DeckState::Crossfading {
+            self.state = DeckState::Playing;
+        }
+    }
+
     pub fn set_linked_playback_pct(&mut self, pct: f32) {
         self.set_pitch_pct(pct);
         self.set_tempo_pct(pct);
@@ -321,6 +497,85 @@ impl Deck {
         self.playback_rate = (1.0 + self.tempo_pct / 100.0).clamp(0.5, 1.5);
     }
 
+    pub fn set_keylock(&mut self, enabled: bool) {
+        self.keylock = enabled;
+        if !enabled {
+            self.keylock_shifter.reset();
+        }
+    }
+
+    /// Play backwards from the current position (position moves back).
+    /// Only playback decks keep the history this needs.
+    pub fn set_reverse(&mut self, enabled: bool) {
+        let direction = if enabled {
+            Direction::Reverse
+        } else if self.history.direction() == Direction::Reverse {
+            Direction::Forward
+        } else {
+            return;
+        };
+        self.history.set_direction(direction);
+    }
+
+    /// Momentary slip-reverse: plays backwards while the position carries on,
+    /// then drops back in at the live position.
+    pub fn set_censor(&mut self, active: bool) {
+        let direction = if active {
+            Direction::Censor
+        } else if self.history.direction() == Direction::Censor {
+            Direction::Forward
+        } else {
+            return;
+        };
+        if self.history.set_direction(direction) {
+            // The output jumps between two points of the track.
+            self.arm_play_ramp_ms(4);
+        }
+    }
+
+    pub fn set_slip(&mut self, enabled: bool) {
+        if enabled && !self.slip {
+            self.slip_frames = self.frames_consumed;
+        }
+        self.slip = enabled;
+    }
+
+    /// Where the track "would have been", while slip is on.
+    pub fn slip_position_ms(&self) -> Option<u64> {
+        if !self.slip || self.sample_rate == 0 {
+            return None;
+        }
+        Some(self.slip_frames * 1000 / self.sample_rate as u64)
+    }
+
+    /// Seek target for returning to the notional playhead, or `None` when
+    /// the real playhead is already there. Allows for the swap latency of a
+    /// seek on a playing deck.
+    pub fn slip_return_target_ms(&self) -> Option<u64> {
+        let notional = self.slip_position_ms()?;
+        let diverged = notional.abs_diff(self.position_ms()) > SLIP_RESYNC_MS
+            || self
+                .loop_state
+                .as_ref()
+                .is_some_and(|s| s.playing_from_buffer)
+            || self.history.direction() != Direction::Forward;
+        if !diverged {
+            return None;
+        }
+        let playing = matches!(self.state, DeckState::Playing | DeckState::Crossfading);
+        Some(notional + if playing { SEEK_SWAP_LATENCY_MS } else { 0 })
+    }
+
+    /// Attach the seek prepared by `slip_return_target_ms`.
+    pub fn request_slip_return(&mut self, prepared: PreparedTrack) {
+        self.slip_return_pending = true;
+        self.request_attach(prepared, AttachOp::Seek);
+    }
+
+    pub fn direction(&self) -> Direction {
+        self.history.direction()
+    }
+
     pub fn set_loop_range_ms(&mut self, start_ms: u64, end_ms: u64) -> Result<(), String> {
         if self.sample_rate == 0 {
             return Err("Invalid sample rate for loop".to_string());
@@ -330,7 +585,7 @@ impl Deck {
         }
         let start_frame = start_ms.saturating_mul(self.sample_rate as u64) / 1000;
         let end_frame = end_ms.saturating_mul(self.sample_rate as u64) / 1000;
-        if end_frame <= start_frame + 16 {
+        if end_frame <= start_frame + MIN_LOOP_FRAMES {
             return Err("Loop range too short".to_string());
         }
         let loop_frames = end_frame.saturating_sub(start_frame);
@@ -345,15 +600,63 @@ impl Deck {
             cached_frames: 0,
             play_frame: 0,
             playing_from_buffer: false,
+            grow_to_frame: None,
             buffer: vec![0.0; sample_len],
         });
         Ok(())
     }
 
+    /// Loop end (in frames) after halving or doubling the active loop, which
+    /// keeps its start.
+    pub fn resized_loop_end(&self, double: bool) -> Result<u64, String> {
+        let frames = self.loop_frames().ok_or("No active loop")?;
+        resized_loop_end(frames, self.sample_rate, double)
+    }
+
+    /// Active loop as `(start, end)` frames, `end` being where it is headed
+    /// while it still grows.
+    pub fn loop_frames(&self) -> Option<(u64, u64)> {
+        let loop_state = self.loop_state.as_ref()?;
+        Some((
+            loop_state.start_frame,
+            loop_state.grow_to_frame.unwrap_or(loop_state.end_frame),
+        ))
+    }
+
+    pub fn resize_loop(&mut self, double: bool) -> Result<(), String> {
+        let new_end = self.resized_loop_end(double)?;
+        let frames_consumed = self.frames_consumed;
+        let Some(loop_state) = self.loop_state.as_mut() else {
+            return Ok(());
+        };
+        let start = loop_state.start_frame;
+        let new_len = new_end - start;
+        let sample_len = (new_len * 2) as usize;
+        if loop_state.buffer.len() < sample_len {
+            loop_state.buffer.resize(sample_len, 0.0);
+        }
+        loop_state.grow_to_frame = None;
+        if !loop_state.playing_from_buffer {
+            loop_state.end_frame = new_end;
+            if frames_consumed >= new_end {
+                // Already past the new end: start repeating now, in phase.
+                loop_state.playing_from_buffer = true;
+                loop_state.play_frame = (frames_consumed - start) % new_len;
+            }
+        } else if new_len <= loop_state.cached_frames {
+            loop_state.end_frame = new_end;
+            loop_state.play_frame %= new_len;
+        } else {
+            loop_state.grow_to_frame = Some(new_end);
+        }
+        Ok(())
+    }
+
     pub fn clear_loop(&mut self) {
         if let Some(loop_state) = self.loop_state.take() {
             if loop_state.playing_from_buffer {
-                self.frames_consumed = loop_state.end_frame;
+                // The decoder is parked where capture stopped.
+                self.frames_consumed = loop_state.start_frame + loop_state.cached_frames;
                 if matches!(self.state, DeckState::Playing | DeckState::Crossfading) {
                     self.arm_play_ramp_ms(4);
                 }
@@ -367,23 +670,38 @@ impl Deck {
             return None;
         }
         let sr = self.sample_rate as u64;
+        let end_frame = loop_state.grow_to_frame.unwrap_or(loop_state.end_frame);
         let start_ms = loop_state.start_frame.saturating_mul(1000) / sr;
-        let end_ms = loop_state.end_frame.saturating_mul(1000) / sr;
+        let end_ms = end_frame.saturating_mul(1000) / sr;
         Some((start_ms, end_ms))
     }
 
-    pub fn stop_with_completion(&mut self) {
+    pub fn stop_with_completion(&mut self, reason: StopReason) {
         let completion = self.song_id.map(|song_id| TrackCompletion {
             song_id,
             queue_id: self.queue_id,
             from_rotation: self.from_rotation,
+            played_ms: self.position_ms(),
+            duration_ms: self.duration_ms(),
+            reason,
         });
         self.stop();
-        self.completion_pending = completion;
+        // A deck already stopped at EOF keeps its original record.
+        if completion.is_some() {
+            self.completion_pending = completion;
+        }
     }
 
     pub fn mark_eof_stop(&mut self) {
-        self.stop_with_completion();
+        let failed = self
+            .decoder
+            .as_ref()
+            .is_some_and(|d| d.decode_failed.load(Ordering::Relaxed));
+        self.stop_with_completion(if failed {
+            StopReason::Error
+        } else {
+            StopReason::Ended
+        });
         self.ended_naturally = true;
     }
 
@@ -396,7 +714,28 @@ impl Deck {
         if self.sample_rate == 0 {
             return 0;
         }
-        self.frames_consumed * 1000 / self.sample_rate as u64
+        let heard = self
+            .frames_consumed
+            .saturating_sub(self.keylock_lag_frames());
+        heard * 1000 / self.sample_rate as u64
+    }
+
+    /// Source frames keylock has read ahead of what is heard.
+    fn keylock_lag_frames(&self) -> u64 {
+        let device_sr = self.keylock_shifter.sample_rate();
+        if !self.keylock || device_sr == 0 {
+            return 0;
+        }
+        self.keylock_shifter.lag_frames() * self.sample_rate as u64 / device_sr as u64
+    }
+
+    /// Average delay keylock's lookahead adds to the source position.
+    pub fn keylock_latency_ms(&self) -> f32 {
+        let device_sr = self.keylock_shifter.sample_rate();
+        if !self.keylock || device_sr == 0 {
+            return 0.0;
+        }
+        Keylock::latency_frames(device_sr) as f32 * 1000.0 / device_sr as f32
     }
 
     /// Total duration in ms (0 if unknown)
@@ -444,6 +783,12 @@ impl Deck {
         frames * 1000 / self.sample_rate as u64
     }
 
+    /// Live status of the HTTP(S) stream being played. Read it off the
+    /// audio thread: `info` takes locks.
+    pub fn remote_status(&self) -> Option<&Arc<RemoteStreamStatus>> {
+        self.decoder.as_ref()?.remote.as_ref()
+    }
+
     /// Whether the decoder ring buffer is exhausted and the track has ended
     pub fn is_eof(&self) -> bool {
         match &self.decoder {
@@ -452,7 +797,9 @@ impl Deck {
                 let written = d.frames_written.load(Ordering::Relaxed);
                 let total = d.total_frames.load(Ordering::Relaxed);
                 let done = d.decode_done.load(Ordering::Relaxed);
-                ((total > 0 && written >= total) || done) && d.consumer.is_empty()
+                ((total > 0 && written >= total) || done)
+                    && d.consumer.is_empty()
+                    && !self.history.is_behind_live()
             }
             None => true,
         }
@@ -504,8 +851,10 @@ impl Deck {
 
         use ringbuf::traits::Consumer as _;
 
+        // Keylock stays in the path at unity too, so crossing 1.0 never resets it.
         let use_fast_path = (file_sr == device_sr || file_sr == 0 || device_sr == 0)
-            && (self.playback_rate - 1.0).abs() < 1e-6;
+            && (self.playback_rate - 1.0).abs() < 1e-6
+            && !self.keylock;
 
         if use_fast_path {
             // ── Fast path: rates match, direct copy ──────────────────────
@@ -513,43 +862,23 @@ impl Deck {
             // playback starts from the current decoder position.
             self.resample_seeded = false;
             self.resample_phase = 0.0;
+            self.keylock_shifter.reset();
             let mut out_i = 0usize;
             while out_i < output.len() {
+                self.poll_timed_swap(device_sr);
                 if self.swap_out_total_frames > 0
                     && self.swap_out_remaining_frames == 0
                     && self.pending_swap.is_some()
                 {
                     self.apply_pending_swap();
                 }
-                let pair = if let Some(loop_pair) = self.next_loop_buffer_frame() {
-                    Some(loop_pair)
-                } else {
-                    let decoder = self.decoder.as_mut().unwrap();
-                    if decoder.consumer.occupied_len() < 2 {
-                        None
-                    } else {
-                        Some((
-                            decoder.consumer.try_pop().unwrap_or(0.0),
-                            decoder.consumer.try_pop().unwrap_or(0.0),
-                        ))
-                    }
-                };
-                let Some((l, r)) = pair else {
+                let Some((l, r)) = self.next_source_frame() else {
                     output[out_i..].fill(0.0);
                     break;
                 };
-                if !self
-                    .loop_state
-                    .as_ref()
-                    .is_some_and(|s| s.playing_from_buffer)
-                {
-                    let frame_index = self.frames_consumed;
-                    self.frames_consumed = self.frames_consumed.saturating_add(1);
-                    self.capture_loop_frame(frame_index, l, r);
-                }
                 let start_gain = self.next_play_ramp_gain();
                 let swap_gain = self.next_swap_out_gain();
-                let tap_gain = start_gain * swap_gain;
+                let tap_gain = start_gain * swap_gain * self.track_gain;
                 let tap_l = l * tap_gain;
                 let tap_r = r * tap_gain;
                 output[out_i] = tap_l * self.channel_gain * self.xfade_gain;
@@ -558,8 +887,8 @@ impl Deck {
                     tap[out_i] = tap_l;
                     tap[out_i + 1] = tap_r;
                 }
-                let l64 = l as f64;
-                let r64 = r as f64;
+                let l64 = (l * self.track_gain) as f64;
+                let r64 = (r * self.track_gain) as f64;
                 rms_sum_sq += l64 * l64 + r64 * r64;
                 rms_samples += 2;
                 out_i += 2;
@@ -572,6 +901,8 @@ impl Deck {
             // For each output frame we interpolate between prev and next, then
             // advance phase by `ratio = file_sr / device_sr`.
             // Each time phase crosses 1.0 we consume the next source frame.
+            // With keylock the resampler only converts rates; the stretcher
+            // pulls from it and applies the tempo.
             //
             // Example: file=44100, device=48000 → ratio≈0.919
             //   Each output frame advances phase by 0.919; a new source frame
@@ -603,29 +934,39 @@ impl Deck {
                 }
             }
 
-            let ratio = file_sr as f64 * self.playback_rate as f64 / device_sr as f64;
+            let keylock = self.keylock;
+            let ratio = if keylock {
+                self.keylock_shifter.set_sample_rate(device_sr);
+                file_sr as f64 / device_sr as f64
+            } else {
+                self.keylock_shifter.reset();
+                file_sr as f64 * self.playback_rate as f64 / device_sr as f64
+            };
 
             for out_i in 0..out_frames {
+                self.poll_timed_swap(device_sr);
                 if self.swap_out_total_frames > 0
                     && self.swap_out_remaining_frames == 0
                     && self.pending_swap.is_some()
                 {
                     self.apply_pending_swap();
                 }
-                let t = self.resample_phase as f32;
-
-                // Interpolate L and R channels
-                let out_l =
-                    self.resample_prev_l + t * (self.resample_next_l - self.resample_prev_l);
-                let out_r =
-                    self.resample_prev_r + t * (self.resample_next_r - self.resample_prev_r);
-                let out_l64 = out_l as f64;
-                let out_r64 = out_r as f64;
+                let (out_l, out_r) = if keylock {
+                    while self.keylock_shifter.wants_input() {
+                        let (l, r) = self.next_resampled(ratio);
+                        self.keylock_shifter.push(l, r);
+                    }
+                    self.keylock_shifter.pop(self.playback_rate)
+                } else {
+                    self.next_resampled(ratio)
+                };
+                let out_l64 = (out_l * self.track_gain) as f64;
+                let out_r64 = (out_r * self.track_gain) as f64;
                 rms_sum_sq += out_l64 * out_l64 + out_r64 * out_r64;
                 rms_samples += 2;
                 let start_gain = self.next_play_ramp_gain();
                 let swap_gain = self.next_swap_out_gain();
-                let tap_gain = start_gain * swap_gain;
+                let tap_gain = start_gain * swap_gain * self.track_gain;
                 let tap_l = out_l * tap_gain;
                 let tap_r = out_r * tap_gain;
                 output[out_i * 2] = tap_l * self.channel_gain * self.xfade_gain;
@@ -635,48 +976,6 @@ impl Deck {
                     tap[i] = tap_l;
                     tap[i + 1] = tap_r;
                 }
-
-                // Advance fractional phase
-                self.resample_phase += ratio;
-
-                // Consume as many source frames as the phase advance requires.
-                // Usually 0–1 per output frame; occasionally 2 when ratio > 1.
-                while self.resample_phase >= 1.0 {
-                    self.resample_prev_l = self.resample_next_l;
-                    self.resample_prev_r = self.resample_next_r;
-
-                    let loop_playing = self
-                        .loop_state
-                        .as_ref()
-                        .is_some_and(|s| s.playing_from_buffer);
-
-                    let next_pair = if loop_playing {
-                        self.next_loop_buffer_frame()
-                    } else {
-                        let decoder = self.decoder.as_mut().unwrap();
-                        if decoder.consumer.occupied_len() >= 2 {
-                            Some((
-                                decoder.consumer.try_pop().unwrap_or(0.0),
-                                decoder.consumer.try_pop().unwrap_or(0.0),
-                            ))
-                        } else {
-                            None
-                        }
-                    };
-                    if let Some((next_l, next_r)) = next_pair {
-                        self.resample_next_l = next_l;
-                        self.resample_next_r = next_r;
-                        if !loop_playing {
-                            let frame_index = self.frames_consumed;
-                            self.frames_consumed = self.frames_consumed.saturating_add(1);
-                            self.capture_loop_frame(frame_index, next_l, next_r);
-                        }
-                    }
-                    // On underrun: keep next == prev (repeat last frame).
-                    // This is a gentle hold — better than a hard silence click.
-
-                    self.resample_phase -= 1.0;
-                }
             }
         }
 
@@ -690,11 +989,93 @@ impl Deck {
 
     // ── Private helpers ──────────────────────────────────────────────────
 
+    /// Interpolate one frame between the resampler's source frames, then
+    /// advance its phase by `ratio`.
+    fn next_resampled(&mut self, ratio: f64) -> (f32, f32) {
+        let t = self.resample_phase as f32;
+        let l = self.resample_prev_l + t * (self.resample_next_l - self.resample_prev_l);
+        let r = self.resample_prev_r + t * (self.resample_next_r - self.resample_prev_r);
+
+        self.resample_phase += ratio;
+        // Consume as many source frames as the phase advance requires.
+        // Usually 0–1 per output frame; occasionally 2 when ratio > 1.
+        while self.resample_phase >= 1.0 {
+            self.resample_prev_l = self.resample_next_l;
+            self.resample_prev_r = self.resample_next_r;
+
+            if let Some((next_l, next_r)) = self.next_source_frame() {
+                self.resample_next_l = next_l;
+                self.resample_next_r = next_r;
+            }
+            // On underrun: keep next == prev (repeat last frame).
+            // This is a gentle hold — better than a hard silence click.
+
+            self.resample_phase -= 1.0;
+        }
+        (l, r)
+    }
+
+    /// Next source frame in play order, honouring reverse/censor.
+    fn next_source_frame(&mut self) -> Option<(f32, f32)> {
+        if self.slip {
+            self.slip_frames = self.slip_frames.saturating_add(1);
+        }
+        match self.history.direction() {
+            Direction::Forward => {
+                if let Some(pair) = self.history.replay() {
+                    self.frames_consumed = self.frames_consumed.saturating_add(1);
+                    return Some(pair);
+                }
+                let (l, r) = self.next_forward_frame()?;
+                self.history.record(l, r);
+                Some((l, r))
+            }
+            Direction::Reverse => {
+                let pair = self.history.step_back()?;
+                self.frames_consumed = self.frames_consumed.saturating_sub(1);
+                Some(pair)
+            }
+            Direction::Censor => {
+                let (l, r) = self.next_forward_frame()?;
+                self.history.record(l, r);
+                Some(self.history.censor_frame())
+            }
+        }
+    }
+
+    /// Next frame from the loop buffer or decoder, advancing the position.
+    fn next_forward_frame(&mut self) -> Option<(f32, f32)> {
+        use ringbuf::traits::Consumer as _;
+
+        let loop_playing = self
+            .loop_state
+            .as_ref()
+            .is_some_and(|s| s.playing_from_buffer);
+        if loop_playing {
+            return self.next_loop_buffer_frame();
+        }
+        let decoder = self.decoder.as_mut()?;
+        if decoder.consumer.occupied_len() < 2 {
+            return None;
+        }
+        let mut l = decoder.consumer.try_pop().unwrap_or(0.0);
+        let mut r = decoder.consumer.try_pop().unwrap_or(0.0);
+        if let Some(stems) = self.stems.as_mut() {
+            (l, r) = stems.mix_frame((l, r), &self.stem_mix);
+        }
+        let frame_index = self.frames_consumed;
+        self.frames_consumed = self.frames_consumed.saturating_add(1);
+        self.capture_loop_frame(frame_index, l, r);
+        Some((l, r))
+    }
+
     fn stop_decoder(&mut self) {
         if let Some(d) = self.decoder.take() {
             d.stop_flag.store(true, Ordering::Relaxed);
             // Thread will exit on its own after seeing stop_flag
         }
+        // Dropping the layer stops its follower decoders the same way.
+        self.stems = None;
     }
 
     /// Reset linear-interpolation resampler state. Call on every load/seek so
@@ -706,12 +1087,20 @@ impl Deck {
         self.resample_prev_r = 0.0;
         self.resample_next_l = 0.0;
         self.resample_next_r = 0.0;
+        self.keylock_shifter.reset();
+        self.history.clear();
     }
 
     fn apply_prepared(&mut self, prepared: PreparedTrack, op: AttachOp) {
         let was_paused = self.state == DeckState::Paused;
         self.stop_decoder();
         self.decoder = Some(prepared.decoder);
+        if matches!(op, AttachOp::Load) {
+            self.stem_mix = StemMix::default();
+        }
+        self.stems = prepared
+            .stems
+            .map(|sources| StemLayer::new(sources, &self.stem_mix));
         self.file_path = Some(prepared.file_path);
         self.song_id = prepared.song_id;
         self.queue_id = prepared.queue_id;
@@ -723,6 +1112,14 @@ impl Deck {
             .map(|d| d.sample_rate)
             .unwrap_or(self.sample_rate);
         self.frames_consumed = prepared.initial_frames_consumed;
+        // Slip jumps (hot cues, jog) leave the notional playhead running.
+        if std::mem::take(&mut self.slip_return_pending) || matches!(op, AttachOp::Load) {
+            self.slip_frames = self.frames_consumed;
+        }
+        if matches!(op, AttachOp::Load) {
+            self.track_gain_db = prepared.track_gain_db;
+            self.track_gain = 10f32.powf(prepared.track_gain_db / 20.0);
+        }
         self.ended_naturally = false;
         self.completion_pending = None;
         self.reset_resampler();
@@ -776,18 +1173,47 @@ impl Deck {
         {
             return;
         }
-        if self.pending_swap_ready() {
+        if self.pending_swap_ready() && self.pending_swap_due() {
             self.arm_swap_out();
         }
     }
 
+    /// A quantized jump starts its swap-out mid-buffer, on the exact frame.
+    fn poll_timed_swap(&mut self, device_sr: u32) {
+        if self.swap_out_total_frames == 0
+            && self
+                .pending_swap
+                .as_ref()
+                .is_some_and(|p| p.at_frame.is_some())
+        {
+            self.maybe_begin_pending_swap();
+            self.ensure_swap_out(device_sr);
+        }
+    }
+
+    fn pending_swap_due(&self) -> bool {
+        let Some(pending) = self.pending_swap.as_ref() else {
+            return false;
+        };
+        let Some(at_frame) = pending.at_frame else {
+            return true;
+        };
+        // The swap-out runs this many source frames before the new audio.
+        let lead = (self.sample_rate as u64 * SWAP_OUT_MS / 1000) as f32 * self.playback_rate;
+        self.frames_consumed < pending.requested_at
+            || self.frames_consumed + lead.round() as u64 >= at_frame
+    }
+
     fn pending_swap_ready(&self) -> bool {
         let Some(pending) = self.pending_swap.as_ref() else {
             return false;
         };
         let sr = pending.prepared.decoder.sample_rate.max(1) as u64;
         let needed_frames = ((sr * SWAP_PREROLL_MS) / 1000).max(32);
-        let buffered_frames = pending.prepared.decoder.consumer.occupied_len() as u64 / 2;
+        let mut buffered_frames = pending.prepared.decoder.consumer.occupied_len() as u64 / 2;
+        if let Some(stems) = &pending.prepared.stems {
+            buffered_frames = buffered_frames.min(stems.buffered_frames());
+        }
         buffered_frames >= needed_frames
     }
 
@@ -894,8 +1320,11 @@ impl Deck {
         if !loop_state.playing_from_buffer || loop_state.cached_frames == 0 {
             return None;
         }
-        let len = loop_state.cached_frames;
+        let len = loop_state
+            .cached_frames
+            .min(loop_state.end_frame - loop_state.start_frame);
         let play = loop_state.play_frame % len;
+        let growing = loop_state.grow_to_frame.is_some();
         let target_wrap_blend = (sr.saturating_mul(3) / 1000)
             .clamp(LOOP_WRAP_MIN_XFADE_FRAMES, LOOP_WRAP_MAX_XFADE_FRAMES);
         let wrap_blend = target_wrap_blend
@@ -910,7 +1339,7 @@ impl Deck {
         // Crossfade tail->head across a short region to smooth the loop seam.
         // After wrapping, skip already blended head frames to avoid replaying
         // the same transient twice at the boundary.
-        if play >= len.saturating_sub(wrap_blend) && wrap_blend > 0 {
+        if !growing && play >= len.saturating_sub(wrap_blend) && wrap_blend > 0 {
             let blend_pos = play.saturating_sub(len.saturating_sub(wrap_blend));
             let head_idx = blend_pos.saturating_mul(2) as usize;
             if head_idx + 1 < loop_state.buffer.len() {
@@ -926,19 +1355,45 @@ impl Deck {
         }
         self.frames_consumed = loop_state.start_frame + play;
         let next_play = play + 1;
-        loop_state.play_frame = if next_play >= len {
-            if wrap_blend > 0 {
-                wrap_blend % len
-            } else {
-                0
+        if next_play >= len {
+            if let Some(end) = loop_state.grow_to_frame.take() {
+                loop_state.end_frame = end;
             }
-        } else {
+        }
+        let loop_len = loop_state.end_frame - loop_state.start_frame;
+        loop_state.play_frame = if next_play < loop_len.min(loop_state.cached_frames) {
             next_play
+        } else if loop_state.cached_frames < loop_len {
+            // Grown past the captured audio: carry on from the decoder.
+            loop_state.playing_from_buffer = false;
+            self.frames_consumed = loop_state.start_frame + loop_state.cached_frames;
+            0
+        } else if wrap_blend > 0 {
+            wrap_blend % len
+        } else {
+            0
         };
         Some((l, r))
     }
 }
 
+/// Loop end after halving or doubling the `(start, end)` frame range.
+pub fn resized_loop_end(
+    (start, end): (u64, u64),
+    sample_rate: u32,
+    double: bool,
+) -> Result<u64, String> {
+    let len = end - start;
+    let new_len = if double { len * 2 } else { len / 2 };
+    if new_len <= MIN_LOOP_FRAMES {
+        return Err("Loop range too short".to_string());
+    }
+    if new_len > sample_rate as u64 * MAX_LOOP_SECONDS {
+        return Err(format!("Loop too long (max {MAX_LOOP_SECONDS}s)"));
+    }
+    Ok(start + new_len)
+}
+
 impl Drop for Deck {
     fn drop(&mut self) {
         self.stop_decoder();
@@ -984,4 +1439,32 @@ mod tests {
         assert_eq!(deck.next_swap_out_gain(), 0.0);
         assert_eq!(deck.next_swap_out_gain(), 1.0);
     }
+
+    #[test]
+    fn doubled_loop_runs_on_into_the_decoder() {
+        let mut deck = Deck::new(DeckId::DeckA);
+        deck.sample_rate = 1_000;
+        deck.set_loop_range_ms(0, 100).unwrap();
+        let loop_state = deck.loop_state.as_mut().unwrap();
+        loop_state.cached_frames = 100;
+        loop_state.playing_from_buffer = true;
+
+        deck.resize_loop(false).unwrap();
+        for _ in 0..60 {
+            deck.next_loop_buffer_frame().unwrap();
+            assert!(deck.frames_consumed < 50);
+        }
+
+        // Back to the captured 100 frames, then past them.
+        deck.resize_loop(true).unwrap();
+        deck.resize_loop(true).unwrap();
+        assert_eq!(deck.loop_range_ms(), Some((0, 200)));
+        while deck.next_loop_buffer_frame().is_some() {
+            if !deck.loop_state.as_ref().unwrap().playing_from_buffer {
+                break;
+            }
+        }
+        assert_eq!(deck.frames_consumed, 100);
+        assert_eq!(deck.loop_state.as_ref().unwrap().end_frame, 200);
+    }
 }
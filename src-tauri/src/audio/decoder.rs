@@ -14,17 +14,19 @@ use ringbuf::{
 };
 use symphonia::core::{
     audio::AudioBufferRef,
-    codecs::{DecoderOptions, CODEC_TYPE_NULL},
+    codecs::{Decoder, DecoderOptions, CODEC_TYPE_NULL},
     errors::Error as SymphoniaError,
-    formats::{FormatOptions, SeekMode, SeekTo},
+    formats::{FormatOptions, FormatReader, SeekMode, SeekTo},
     io::MediaSourceStream,
     meta::MetadataOptions,
     probe::Hint,
     units::Time,
 };
 
+use super::remote_stream::{self, RemoteStreamStatus};
+
 /// Stereo f32 samples buffered ahead of the playback thread (~12 s at 44.1 kHz)
-const RING_CAPACITY: usize = 44100 * 2 * 12;
+pub(crate) const RING_CAPACITY: usize = 44100 * 2 * 12;
 
 /// Consumer-side handle owned by the audio render thread.
 pub struct DecoderHandle {
@@ -32,12 +34,16 @@ pub struct DecoderHandle {
     pub stop_flag: Arc<AtomicBool>,
     /// Set true when decode thread reaches terminal end (EOF or fatal error).
     pub decode_done: Arc<AtomicBool>,
+    /// Set true when the decode thread stopped on an error rather than EOF.
+    pub decode_failed: Arc<AtomicBool>,
     /// Total frames written by decoder (used for position estimates)
     pub frames_written: Arc<AtomicU64>,
     /// Total frames in the file (0 until probed)
     pub total_frames: Arc<AtomicU64>,
     pub sample_rate: u32,
     pub channels: u32,
+    /// Connection state and ICY title when the source is an HTTP(S) URL
+    pub remote: Option<Arc<RemoteStreamStatus>>,
 }
 
 impl DecoderHandle {
@@ -50,14 +56,19 @@ impl DecoderHandle {
     }
 }
 
-/// Spawn a background Symphonia decode thread for `path`.
+/// Spawn a background Symphonia decode thread for `path`, which may also be
+/// an HTTP(S) URL (see `remote_stream`).
 /// Returns a `DecoderHandle` the audio thread uses to pull PCM.
 pub fn spawn_decoder(path: PathBuf, seek_ms: Option<u64>) -> Result<DecoderHandle, String> {
+    if let Some(url) = path.to_str().filter(|p| remote_stream::is_remote_url(p)) {
+        return remote_stream::spawn(url, seek_ms);
+    }
     let rb = HeapRb::<f32>::new(RING_CAPACITY);
     let (mut producer, consumer) = rb.split();
 
     let stop_flag = Arc::new(AtomicBool::new(false));
     let decode_done = Arc::new(AtomicBool::new(false));
+    let decode_failed = Arc::new(AtomicBool::new(false));
     let frames_written = Arc::new(AtomicU64::new(0));
     let total_frames = Arc::new(AtomicU64::new(0));
 
@@ -67,14 +78,17 @@ pub fn spawn_decoder(path: PathBuf, seek_ms: Option<u64>) -> Result<DecoderHandl
         consumer,
         stop_flag: Arc::clone(&stop_flag),
         decode_done: Arc::clone(&decode_done),
+        decode_failed: Arc::clone(&decode_failed),
         frames_written: Arc::clone(&frames_written),
         total_frames: Arc::clone(&total_frames),
         sample_rate,
         channels,
+        remote: None,
     };
 
     let stop_flag_t = Arc::clone(&stop_flag);
     let decode_done_t = Arc::clone(&decode_done);
+    let decode_failed_t = Arc::clone(&decode_failed);
     let fw_t = Arc::clone(&frames_written);
     let tf_t = Arc::clone(&total_frames);
 
@@ -86,6 +100,7 @@ pub fn spawn_decoder(path: PathBuf, seek_ms: Option<u64>) -> Result<DecoderHandl
         .spawn(move || {
             if let Err(e) = decode_loop(path, seek_ms, &mut producer, &stop_flag_t, &fw_t, &tf_t) {
                 log::warn!("Decoder exited: {e}");
+                decode_failed_t.store(true, Ordering::Relaxed);
             }
             decode_done_t.store(true, Ordering::Relaxed);
         })
@@ -178,11 +193,32 @@ fn decode_loop(
         );
     }
 
+    pump_packets(
+        probed.format.as_mut(),
+        decoder.as_mut(),
+        track_id,
+        n_channels,
+        producer,
+        stop_flag,
+        frames_written,
+    )
+}
+
+/// Decode `track_id` into the ring until end of stream (`Ok`) or stop.
+pub(crate) fn pump_packets(
+    format: &mut dyn FormatReader,
+    decoder: &mut dyn Decoder,
+    track_id: u32,
+    n_channels: usize,
+    producer: &mut ringbuf::HeapProd<f32>,
+    stop_flag: &AtomicBool,
+    frames_written: &AtomicU64,
+) -> Result<(), String> {
     loop {
         if stop_flag.load(Ordering::Relaxed) {
             break;
         }
-        let packet = match probed.format.next_packet() {
+        let packet = match format.next_packet() {
             Ok(p) => p,
             Err(SymphoniaError::IoError(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof => {
                 break;
@@ -191,10 +227,7 @@ fn decode_loop(
                 decoder.reset();
                 continue;
             }
-            Err(e) => {
-                log::warn!("Format read: {e}");
-                break;
-            }
+            Err(e) => return Err(format!("Format read: {e}")),
         };
         if packet.track_id() != track_id {
             continue;
@@ -205,10 +238,7 @@ fn decode_loop(
                 log::warn!("Decode error (skip): {e}");
                 continue;
             }
-            Err(e) => {
-                log::warn!("Fatal decode: {e}");
-                break;
-            }
+            Err(e) => return Err(format!("Fatal decode: {e}")),
         };
         let n = push_decoded(decoded, n_channels, producer, stop_flag);
         frames_written.fetch_add(n as u64, Ordering::Relaxed);
@@ -51,6 +51,7 @@ pub struct AudioOutputStatus {
     pub master_device_id: Option<String>,
     pub master_device_name: Option<String>,
     pub cue_device_id: Option<String>,
+    pub cue_device_name: Option<String>,
     pub cue_available: bool,
     pub fallback_active: bool,
     pub last_error: Option<String>,
@@ -63,6 +64,7 @@ impl Default for AudioOutputStatus {
             master_device_id: None,
             master_device_name: None,
             cue_device_id: None,
+            cue_device_name: None,
             cue_available: false,
             fallback_active: false,
             last_error: None,
@@ -79,6 +81,14 @@ pub struct OutputSelection {
     pub active_mode: AudioOutputMode,
 }
 
+/// Secondary (headphone) output used by `DualDeviceSplit`.
+pub struct CueOutputSelection {
+    pub device_id: String,
+    pub device_name: String,
+    pub device: Device,
+    pub config: StreamConfig,
+}
+
 pub fn list_audio_output_devices() -> Result<Vec<AudioOutputDevice>, String> {
     let host = cpal::default_host();
     let default_name = host
@@ -194,7 +204,12 @@ pub fn select_output_stream(
         return Err("Unable to select output device".to_string());
     };
 
+    // Dual-device split keeps the master device stereo and opens the cue device
+    // separately; without a distinct cue device it degrades to 4-channel routing.
     let desired_mode = match routing.mode {
+        AudioOutputMode::DualDeviceSplit if has_distinct_cue_device(routing) => {
+            AudioOutputMode::SingleDeviceStereo
+        }
         AudioOutputMode::DualDeviceSplit => AudioOutputMode::SingleDeviceFourChannel,
         ref m => m.clone(),
     };
@@ -251,6 +266,62 @@ pub fn select_output_stream(
     ))
 }
 
+/// Whether the routing config names a cue device different from the master.
+pub fn has_distinct_cue_device(routing: &AudioOutputRoutingConfig) -> bool {
+    let Some(cue_id) = routing
+        .cue_device_id
+        .as_deref()
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+    else {
+        return false;
+    };
+    routing.master_device_id.as_deref() != Some(cue_id)
+}
+
+/// Open-ready config for the cue device at the master's sample rate.
+/// The cue bus is fed sample-for-sample from the master callback, so the two
+/// devices must run at the same nominal rate.
+pub fn select_cue_output_stream(
+    cue_device_id: &str,
+    sample_rate: u32,
+) -> Result<CueOutputSelection, String> {
+    let host = cpal::default_host();
+    let (idx, device, name) = host
+        .output_devices()
+        .map_err(|e| format!("Failed to enumerate output devices: {e}"))?
+        .enumerate()
+        .find_map(|(idx, d)| {
+            let name = d.name().ok()?;
+            (device_id(idx, &name) == cue_device_id).then_some((idx, d, name))
+        })
+        .ok_or_else(|| format!("Cue device '{cue_device_id}' not found"))?;
+
+    let mut candidates: Vec<_> = device
+        .supported_output_configs()
+        .map_err(|e| format!("Failed to query cue device configs: {e}"))?
+        .filter(|cfg| {
+            cfg.sample_format() == SampleFormat::F32
+                && cfg.min_sample_rate().0 <= sample_rate
+                && cfg.max_sample_rate().0 >= sample_rate
+        })
+        .collect();
+    // Prefer plain stereo, then the smallest channel count above it.
+    candidates.sort_by_key(|cfg| (cfg.channels() != 2, cfg.channels()));
+    let range = candidates.into_iter().next().ok_or_else(|| {
+        format!("Cue device '{name}' does not support {sample_rate} Hz f32 output")
+    })?;
+
+    Ok(CueOutputSelection {
+        device_id: device_id(idx, &name),
+        device_name: name,
+        device,
+        config: range
+            .with_sample_rate(cpal::SampleRate(sample_rate))
+            .config(),
+    })
+}
+
 fn choose_stream_config(
     device: &Device,
     desired_mode: &AudioOutputMode,
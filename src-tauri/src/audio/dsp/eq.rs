@@ -10,6 +10,144 @@ pub struct ChannelEQ {
     high_shelf: DirectForm2Transposed<f32>,
     // Keep config for re-init on parameter change
     config: EqConfig,
+    kill: BandKill,
+}
+
+/// One of the three EQ bands, used to address kill switches.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EqBand {
+    Low,
+    Mid,
+    High,
+}
+
+/// Current kill switch positions for a channel.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EqKillState {
+    pub low: bool,
+    pub mid: bool,
+    pub high: bool,
+}
+
+/// Ramp length for kill toggles; long enough to avoid clicks, short enough to feel instant.
+const KILL_RAMP_MS: f32 = 6.0;
+
+/// Band isolator used for kill switches.
+///
+/// The signal is split with a low-pass at the low corner and a high-pass at the
+/// high corner; mid is the remainder, so the three bands always sum back to
+/// the input. Each band gain ramps linearly toward 0 or 1.
+struct BandKill {
+    low_pass: [DirectForm2Transposed<f32>; 2],
+    high_pass: [DirectForm2Transposed<f32>; 2],
+    gains: [f32; 3],
+    targets: [f32; 3],
+    step: f32,
+    low_freq_hz: f32,
+    high_freq_hz: f32,
+}
+
+impl BandKill {
+    fn new(sample_rate: f32, low_freq_hz: f32, high_freq_hz: f32) -> Self {
+        let (lp, hp) = Self::crossover_coeffs(sample_rate, low_freq_hz, high_freq_hz);
+        Self {
+            low_pass: [
+                DirectForm2Transposed::<f32>::new(lp),
+                DirectForm2Transposed::<f32>::new(lp),
+            ],
+            high_pass: [
+                DirectForm2Transposed::<f32>::new(hp),
+                DirectForm2Transposed::<f32>::new(hp),
+            ],
+            gains: [1.0; 3],
+            targets: [1.0; 3],
+            step: 1.0 / (sample_rate * KILL_RAMP_MS / 1000.0).max(1.0),
+            low_freq_hz,
+            high_freq_hz,
+        }
+    }
+
+    fn set_crossovers(&mut self, sample_rate: f32, low_freq_hz: f32, high_freq_hz: f32) {
+        if low_freq_hz == self.low_freq_hz && high_freq_hz == self.high_freq_hz {
+            return;
+        }
+        let (lp, hp) = Self::crossover_coeffs(sample_rate, low_freq_hz, high_freq_hz);
+        for f in &mut self.low_pass {
+            f.update_coefficients(lp);
+        }
+        for f in &mut self.high_pass {
+            f.update_coefficients(hp);
+        }
+        self.low_freq_hz = low_freq_hz;
+        self.high_freq_hz = high_freq_hz;
+    }
+
+    fn crossover_coeffs(
+        sample_rate: f32,
+        low_freq_hz: f32,
+        high_freq_hz: f32,
+    ) -> (Coefficients<f32>, Coefficients<f32>) {
+        let fs = sample_rate.hz();
+        let nyquist = sample_rate / 2.0 - 1.0;
+        let lp = Coefficients::<f32>::from_params(
+            Type::LowPass,
+            fs,
+            low_freq_hz.clamp(20.0, nyquist).hz(),
+            Q_BUTTERWORTH_F32,
+        )
+        .unwrap_or_else(|_| ChannelEQ::unity_coeffs());
+        let hp = Coefficients::<f32>::from_params(
+            Type::HighPass,
+            fs,
+            high_freq_hz.clamp(20.0, nyquist).hz(),
+            Q_BUTTERWORTH_F32,
+        )
+        .unwrap_or_else(|_| ChannelEQ::unity_coeffs());
+        (lp, hp)
+    }
+
+    fn set(&mut self, band: EqBand, killed: bool) {
+        self.targets[band as usize] = if killed { 0.0 } else { 1.0 };
+    }
+
+    /// Set a band without ramping (used when carrying state into a rebuilt chain).
+    fn set_immediate(&mut self, band: EqBand, killed: bool) {
+        self.set(band, killed);
+        self.gains[band as usize] = self.targets[band as usize];
+    }
+
+    fn state(&self) -> EqKillState {
+        EqKillState {
+            low: self.targets[EqBand::Low as usize] == 0.0,
+            mid: self.targets[EqBand::Mid as usize] == 0.0,
+            high: self.targets[EqBand::High as usize] == 0.0,
+        }
+    }
+
+    #[inline]
+    fn process(&mut self, ch: usize, sample: f32) -> f32 {
+        // Crossovers always run so their state is warm when a kill engages.
+        let low = self.low_pass[ch].run(sample);
+        let high = self.high_pass[ch].run(sample);
+        if self.gains == [1.0; 3] {
+            return sample;
+        }
+        let mid = sample - low - high;
+        low * self.gains[0] + mid * self.gains[1] + high * self.gains[2]
+    }
+
+    /// Advance gain ramps by one frame.
+    #[inline]
+    fn tick(&mut self) {
+        for (g, t) in self.gains.iter_mut().zip(self.targets) {
+            if *g < t {
+                *g = (*g + self.step).min(t);
+            } else if *g > t {
+                *g = (*g - self.step).max(t);
+            }
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -49,12 +187,14 @@ impl Default for EqConfig {
 impl ChannelEQ {
     pub fn new(sample_rate: f32, config: EqConfig) -> Self {
         let (low_shelf, peak_mid, high_shelf) = Self::build_filters(sample_rate, &config);
+        let kill = BandKill::new(sample_rate, config.low_freq_hz, config.high_freq_hz);
         Self {
             sample_rate,
             low_shelf,
             peak_mid,
             high_shelf,
             config,
+            kill,
         }
     }
 
@@ -68,6 +208,8 @@ impl ChannelEQ {
         self.low_shelf = low_shelf;
         self.peak_mid = peak_mid;
         self.high_shelf = high_shelf;
+        self.kill
+            .set_crossovers(self.sample_rate, config.low_freq_hz, config.high_freq_hz);
         self.config = config;
     }
 
@@ -75,11 +217,29 @@ impl ChannelEQ {
         &self.config
     }
 
+    /// Engage or release a band kill. The change ramps in over a few ms.
+    pub fn set_kill(&mut self, band: EqBand, killed: bool) {
+        self.kill.set(band, killed);
+    }
+
+    /// Apply kill positions instantly, e.g. after the pipeline was rebuilt.
+    pub fn restore_kills(&mut self, kills: EqKillState) {
+        self.kill.set_immediate(EqBand::Low, kills.low);
+        self.kill.set_immediate(EqBand::Mid, kills.mid);
+        self.kill.set_immediate(EqBand::High, kills.high);
+    }
+
+    pub fn kill_state(&self) -> EqKillState {
+        self.kill.state()
+    }
+
     /// Process a single stereo frame (in-place). Call once per audio sample.
     #[inline]
     pub fn process_stereo(&mut self, left: &mut f32, right: &mut f32) {
-        *left = self.process_mono(*left);
-        *right = self.process_mono(*right);
+        let (l, r) = (self.process_mono(*left), self.process_mono(*right));
+        *left = self.kill.process(0, l);
+        *right = self.kill.process(1, r);
+        self.kill.tick();
     }
 
     /// Process a mono sample.
@@ -179,4 +339,32 @@ mod tests {
         let output = eq.process_mono(0.5);
         assert!(output > 0.5, "6 dB boost should increase level");
     }
+
+    #[test]
+    fn kill_all_bands_ramps_to_silence_without_step() {
+        let mut eq = ChannelEQ::with_defaults(44100.0);
+        let (mut l, mut r) = (0.5_f32, 0.5_f32);
+        eq.process_stereo(&mut l, &mut r);
+        let before = l;
+
+        eq.set_kill(EqBand::Low, true);
+        eq.set_kill(EqBand::Mid, true);
+        eq.set_kill(EqBand::High, true);
+        assert!(eq.kill_state().low && eq.kill_state().mid && eq.kill_state().high);
+
+        let (mut l, mut r) = (0.5_f32, 0.5_f32);
+        eq.process_stereo(&mut l, &mut r);
+        assert!(
+            (l - before).abs() < 0.05,
+            "kill should ramp, not jump: {before} -> {l}"
+        );
+
+        for _ in 0..1024 {
+            let (mut l, mut r) = (0.5_f32, 0.5_f32);
+            eq.process_stereo(&mut l, &mut r);
+        }
+        let (mut l, mut r) = (0.5_f32, 0.5_f32);
+        eq.process_stereo(&mut l, &mut r);
+        assert!(l.abs() < 1e-4, "all bands killed should be silent: {l}");
+    }
 }
@@ -0,0 +1,337 @@
+/// `audio/dsp/keylock.rs` — Keylock (master tempo) for the playback decks
+///
+/// Without keylock a deck changes tempo by resampling, which moves pitch
+/// along with speed. With keylock on, the deck only converts the file to the
+/// device rate and this time-stretcher changes the tempo, so beat-matching
+/// adjustments keep vocals at their original key.
+///
+/// The stretcher is WSOLA (waveform-similarity overlap-add). It copies
+/// `SEQUENCE_MS` grains of the input at their own pitch, advancing through
+/// the input `tempo` times as fast as it writes output. Each grain starts
+/// within `SEEK_MS` of its nominal position, where it best lines up with the
+/// end of the previous grain, and the two are crossfaded over `OVERLAP_MS`.
+/// Grain positions never drift: a grain's search offset does not carry into
+/// the next one, and at unity tempo the best offset is always zero, so the
+/// audio passes through unchanged. The stretcher stays engaged at every tempo
+/// while keylock is on, so crossing 1.0 never resets it.
+///
+/// The deck pulls frames: it feeds input while [`Keylock::wants_input`] and
+/// takes one output frame per device frame. The input read ahead of what is
+/// heard averages [`Keylock::latency_frames`], which is fixed for a sample
+/// rate; [`Keylock::lag_frames`] gives the exact amount for the position
+/// readout. Nothing allocates after construction.
+///
+/// Input ring per channel; holds a grain plus the seek range at 192 kHz.
+const BUF_FRAMES: usize = 16_384;
+const BUF_MASK: usize = BUF_FRAMES - 1;
+/// Sizes are worked out for at most this rate, so the buffers always fit.
+const MAX_SIZING_RATE: u32 = 192_000;
+/// Grain length. Longer smooths sustained tones, shorter blurs transients less.
+const SEQUENCE_MS: u32 = 40;
+/// Crossfade between consecutive grains.
+const OVERLAP_MS: u32 = 8;
+/// Range searched (centred on the nominal position) for the best grain start.
+const SEEK_MS: u32 = 15;
+/// Coarse search step in frames; the best coarse offset is then refined.
+const SEEK_STEP: usize = 4;
+
+/// Grain sizes in frames at one sample rate.
+#[derive(Clone, Copy, PartialEq, Eq)]
+struct Sizes {
+    sample_rate: u32,
+    sequence: usize,
+    overlap: usize,
+    /// Half the seek range; offsets run from `-half_seek` to `+half_seek`.
+    half_seek: usize,
+}
+
+impl Sizes {
+    fn new(sample_rate: u32) -> Self {
+        let sr = sample_rate.clamp(8_000, MAX_SIZING_RATE) as usize;
+        Self {
+            sample_rate,
+            sequence: sr * SEQUENCE_MS as usize / 1000,
+            overlap: (sr * OVERLAP_MS as usize / 1000).max(1),
+            half_seek: sr * SEEK_MS as usize / 2000,
+        }
+    }
+
+    /// Output frames written per grain.
+    fn hop(&self) -> usize {
+        self.sequence - self.overlap
+    }
+}
+
+pub struct Keylock {
+    sizes: Sizes,
+    in_l: Vec<f32>,
+    in_r: Vec<f32>,
+    /// Absolute index of the next input frame to be pushed
+    in_end: u64,
+    /// Nominal absolute input position of the next grain
+    pos: f64,
+    /// Input frames that follow the last grain, crossfaded into the next
+    mid_l: Vec<f32>,
+    mid_r: Vec<f32>,
+    out_l: Vec<f32>,
+    out_r: Vec<f32>,
+    out_read: usize,
+    out_len: usize,
+    /// Absolute input index of the grain being played out
+    grain_start: u64,
+    /// A grain has been made since the last reset
+    primed: bool,
+}
+
+impl Default for Keylock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Keylock {
+    pub fn new() -> Self {
+        let max = Sizes::new(MAX_SIZING_RATE);
+        Self {
+            sizes: Sizes::new(48_000),
+            in_l: vec![0.0; BUF_FRAMES],
+            in_r: vec![0.0; BUF_FRAMES],
+            in_end: 0,
+            pos: 0.0,
+            mid_l: vec![0.0; max.overlap],
+            mid_r: vec![0.0; max.overlap],
+            out_l: vec![0.0; max.sequence],
+            out_r: vec![0.0; max.sequence],
+            out_read: 0,
+            out_len: 0,
+            grain_start: 0,
+            primed: false,
+        }
+    }
+
+    /// Drop everything buffered (on seek/load, or when keylock is turned off).
+    pub fn reset(&mut self) {
+        self.in_end = 0;
+        self.pos = 0.0;
+        self.out_read = 0;
+        self.out_len = 0;
+        self.grain_start = 0;
+        self.primed = false;
+    }
+
+    /// Input read ahead of the output, on average, at `sample_rate`.
+    pub fn latency_frames(sample_rate: u32) -> usize {
+        let sizes = Sizes::new(sample_rate);
+        sizes.half_seek + sizes.sequence - sizes.hop() / 2
+    }
+
+    /// Input frames pushed but not yet heard.
+    pub fn lag_frames(&self) -> u64 {
+        if !self.primed {
+            return self.in_end;
+        }
+        self.in_end
+            .saturating_sub(self.grain_start + self.out_read as u64)
+    }
+
+    /// Whether the next output frame needs more input first. Set the rate
+    /// with [`Keylock::set_sample_rate`] before feeding.
+    #[inline]
+    pub fn wants_input(&self) -> bool {
+        self.out_read >= self.out_len && self.in_end < self.needed_end()
+    }
+
+    /// Append one input frame (at the device rate).
+    #[inline]
+    pub fn push(&mut self, l: f32, r: f32) {
+        let i = self.in_end as usize & BUF_MASK;
+        self.in_l[i] = l;
+        self.in_r[i] = r;
+        self.in_end += 1;
+    }
+
+    /// Device rate the grain sizes follow.
+    pub fn sample_rate(&self) -> u32 {
+        self.sizes.sample_rate
+    }
+
+    /// Change the rate the grain sizes follow. Resets when it changes.
+    pub fn set_sample_rate(&mut self, sample_rate: u32) {
+        if self.sizes.sample_rate != sample_rate {
+            self.sizes = Sizes::new(sample_rate);
+            self.reset();
+        }
+    }
+
+    /// Next output frame, consuming input `tempo` times as fast on average.
+    #[inline]
+    pub fn pop(&mut self, tempo: f32) -> (f32, f32) {
+        if self.out_read >= self.out_len && !self.make_grain(tempo) {
+            return (0.0, 0.0);
+        }
+        let frame = (self.out_l[self.out_read], self.out_r[self.out_read]);
+        self.out_read += 1;
+        frame
+    }
+
+    /// Input needed before the next grain can be made.
+    fn needed_end(&self) -> u64 {
+        self.pos as u64 + (self.sizes.half_seek + self.sizes.sequence) as u64
+    }
+
+    fn make_grain(&mut self, tempo: f32) -> bool {
+        if self.in_end < self.needed_end() {
+            return false;
+        }
+        let Sizes {
+            sequence, overlap, ..
+        } = self.sizes;
+        let nominal = self.pos as u64;
+        let start = if self.primed {
+            self.best_start(nominal)
+        } else {
+            // Nothing to line up with yet: crossfade the grain with itself.
+            for i in 0..overlap {
+                self.mid_l[i] = self.in_l[(nominal as usize + i) & BUF_MASK];
+                self.mid_r[i] = self.in_r[(nominal as usize + i) & BUF_MASK];
+            }
+            nominal
+        };
+        let base = start as usize;
+        for i in 0..overlap {
+            let w = i as f32 / overlap as f32;
+            let j = (base + i) & BUF_MASK;
+            self.out_l[i] = self.mid_l[i] * (1.0 - w) + self.in_l[j] * w;
+            self.out_r[i] = self.mid_r[i] * (1.0 - w) + self.in_r[j] * w;
+        }
+        for i in overlap..sequence - overlap {
+            let j = (base + i) & BUF_MASK;
+            self.out_l[i] = self.in_l[j];
+            self.out_r[i] = self.in_r[j];
+        }
+        for i in 0..overlap {
+            let j = (base + sequence - overlap + i) & BUF_MASK;
+            self.mid_l[i] = self.in_l[j];
+            self.mid_r[i] = self.in_r[j];
+        }
+        self.out_read = 0;
+        self.out_len = self.sizes.hop();
+        self.grain_start = start;
+        self.primed = true;
+        self.pos += self.sizes.hop() as f64 * tempo.clamp(0.25, 4.0) as f64;
+        true
+    }
+
+    /// The grain start near `nominal` that best continues the last grain.
+    fn best_start(&self, nominal: u64) -> u64 {
+        let half = self.sizes.half_seek as i64;
+        // Input older than one ring (minus the lookahead) has been overwritten.
+        let oldest = self
+            .in_end
+            .saturating_sub((BUF_FRAMES - self.sizes.sequence) as u64);
+        let lo = (-half).max(oldest as i64 - nominal as i64);
+        let offset_of = |o: i64| (nominal as i64 + o) as u64;
+
+        let mut best = 0_i64;
+        let mut best_score = self.similarity(offset_of(0));
+        let step = SEEK_STEP as i64;
+        let mut o = -(half / step) * step;
+        while o <= half {
+            if o != 0 && o >= lo {
+                let score = self.similarity(offset_of(o));
+                if score > best_score {
+                    best = o;
+                    best_score = score;
+                }
+            }
+            o += step;
+        }
+        let coarse = best;
+        for o in (coarse - step + 1)..(coarse + step) {
+            if o == coarse || o < lo || o > half {
+                continue;
+            }
+            let score = self.similarity(offset_of(o));
+            if score > best_score {
+                best = o;
+                best_score = score;
+            }
+        }
+        offset_of(best)
+    }
+
+    /// Normalised correlation of the input at `start` with the overlap tail.
+    fn similarity(&self, start: u64) -> f32 {
+        let mut corr = 0.0_f32;
+        let mut energy = 0.0_f32;
+        for i in 0..self.sizes.overlap {
+            let j = (start as usize + i) & BUF_MASK;
+            let (l, r) = (self.in_l[j], self.in_r[j]);
+            corr += l * self.mid_l[i] + r * self.mid_r[i];
+            energy += l * l + r * r;
+        }
+        corr / (energy + 1e-9).sqrt()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Run `input` through the stretcher at `tempo`; returns the output and
+    /// how many input frames were consumed.
+    fn stretch(input: &[f32], tempo: f32, out_frames: usize) -> (Vec<f32>, usize) {
+        let mut keylock = Keylock::new();
+        keylock.set_sample_rate(48_000);
+        let mut fed = 0;
+        let out = (0..out_frames)
+            .map(|_| {
+                while keylock.wants_input() && fed < input.len() {
+                    keylock.push(input[fed], input[fed]);
+                    fed += 1;
+                }
+                keylock.pop(tempo).0
+            })
+            .collect();
+        (out, fed)
+    }
+
+    /// Count rising zero crossings in the second half of `samples`.
+    fn crossings(samples: &[f32]) -> usize {
+        let tail = &samples[samples.len() / 2..];
+        tail.windows(2)
+            .filter(|w| w[0] < 0.0 && w[1] >= 0.0)
+            .count()
+    }
+
+    #[test]
+    fn changes_tempo_but_keeps_pitch() {
+        let sr = 48_000;
+        let tone: Vec<f32> = (0..3 * sr)
+            .map(|i| (2.0 * std::f32::consts::PI * 400.0 * i as f32 / sr as f32).sin())
+            .collect();
+        let (out, fed) = stretch(&tone, 1.25, sr);
+        // A second of output used 1.25 s of input (plus the lookahead)...
+        let lookahead = Keylock::latency_frames(sr as u32);
+        assert!((fed as i64 - (1.25 * sr as f32) as i64 - lookahead as i64).abs() < 2_400);
+        // ...and is still a 400 Hz tone (200 cycles per half second).
+        let cycles = crossings(&out);
+        assert!((198..=202).contains(&cycles), "cycles {cycles}");
+    }
+
+    #[test]
+    fn unity_tempo_passes_audio_through_unchanged() {
+        // Deterministic noise, so only offset zero lines grains up.
+        let mut seed = 1_u32;
+        let noise: Vec<f32> = (0..20_000)
+            .map(|_| {
+                seed = seed.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+                (seed >> 8) as f32 / (1 << 23) as f32 - 1.0
+            })
+            .collect();
+        let (out, _) = stretch(&noise, 1.0, 12_000);
+        for (i, (a, b)) in out.iter().zip(&noise).enumerate() {
+            assert!((a - b).abs() < 1e-5, "frame {i}: {a} vs {b}");
+        }
+    }
+}
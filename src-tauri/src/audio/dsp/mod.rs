@@ -2,6 +2,7 @@ pub mod agc;
 pub mod compressor;
 pub mod deesser;
 pub mod eq;
+pub mod keylock;
 pub mod pipeline;
 pub mod reverb;
 pub mod stem_filter;
@@ -0,0 +1,167 @@
+use serde::{Deserialize, Serialize};
+
+/// Mic-to-air ducking: Deck A/B are pulled down while the live mic is open.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DuckConfig {
+    pub enabled: bool,
+    /// Gain reduction applied to the music decks while the mic is open (dB, ≤ 0)
+    pub amount_db: f32,
+    /// Time to reach the ducked level after the mic opens
+    pub attack_ms: f32,
+    /// Time to recover to full level after the mic closes
+    pub release_ms: f32,
+}
+
+impl Default for DuckConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            amount_db: -12.0,
+            attack_ms: 80.0,
+            release_ms: 600.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DuckStateEvent {
+    pub mic_open: bool,
+    pub ducking: bool,
+    pub gain_db: f32,
+}
+
+/// Per-frame gain envelope for the music decks.
+pub struct Ducker {
+    config: DuckConfig,
+    sample_rate: f32,
+    gain: f32,
+    attack_coef: f32,
+    release_coef: f32,
+}
+
+impl Ducker {
+    pub fn new(sample_rate: f32, config: DuckConfig) -> Self {
+        let mut ducker = Self {
+            config: DuckConfig::default(),
+            sample_rate,
+            gain: 1.0,
+            attack_coef: 0.0,
+            release_coef: 0.0,
+        };
+        ducker.set_config(config);
+        ducker
+    }
+
+    pub fn set_config(&mut self, mut config: DuckConfig) {
+        config.amount_db = config.amount_db.clamp(-60.0, 0.0);
+        config.attack_ms = config.attack_ms.clamp(1.0, 5_000.0);
+        config.release_ms = config.release_ms.clamp(1.0, 10_000.0);
+        self.attack_coef = time_coef(config.attack_ms, self.sample_rate);
+        self.release_coef = time_coef(config.release_ms, self.sample_rate);
+        self.config = config;
+    }
+
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        if (sample_rate - self.sample_rate).abs() > f32::EPSILON {
+            self.sample_rate = sample_rate;
+            self.set_config(self.config.clone());
+        }
+    }
+
+    pub fn config(&self) -> &DuckConfig {
+        &self.config
+    }
+
+    /// Current linear gain applied to the music decks.
+    pub fn gain(&self) -> f32 {
+        self.gain
+    }
+
+    /// Advance the envelope by one frame and return the gain to apply.
+    #[inline]
+    pub fn next_gain(&mut self, mic_open: bool) -> f32 {
+        let target = if mic_open && self.config.enabled {
+            db_to_linear(self.config.amount_db)
+        } else {
+            1.0
+        };
+        let coef = if target < self.gain {
+            self.attack_coef
+        } else {
+            self.release_coef
+        };
+        self.gain += (target - self.gain) * coef;
+        if (self.gain - target).abs() < 1e-5 {
+            self.gain = target;
+        }
+        self.gain
+    }
+
+    /// Apply the envelope to interleaved stereo buffers in-place (same gain on all).
+    pub fn process(&mut self, mic_open: bool, bufs: &mut [&mut [f32]]) {
+        let frames = bufs.iter().map(|b| b.len() / 2).min().unwrap_or(0);
+        for frame in 0..frames {
+            let g = self.next_gain(mic_open);
+            if g == 1.0 {
+                continue;
+            }
+            for buf in bufs.iter_mut() {
+                buf[frame * 2] *= g;
+                buf[frame * 2 + 1] *= g;
+            }
+        }
+    }
+}
+
+fn time_coef(ms: f32, sample_rate: f32) -> f32 {
+    1.0 - (-1.0 / (ms * 0.001 * sample_rate)).exp()
+}
+
+#[inline]
+fn db_to_linear(db: f32) -> f32 {
+    10.0f32.powf(db / 20.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ducks_while_open_and_recovers_after_close() {
+        let mut ducker = Ducker::new(
+            1000.0,
+            DuckConfig {
+                amount_db: -20.0,
+                attack_ms: 10.0,
+                release_ms: 10.0,
+                ..Default::default()
+            },
+        );
+        for _ in 0..500 {
+            ducker.next_gain(true);
+        }
+        assert!((ducker.gain() - 0.1).abs() < 1e-3, "gain {}", ducker.gain());
+
+        for _ in 0..500 {
+            ducker.next_gain(false);
+        }
+        assert_eq!(ducker.gain(), 1.0);
+    }
+
+    #[test]
+    fn disabled_ducker_is_transparent() {
+        let mut ducker = Ducker::new(
+            44100.0,
+            DuckConfig {
+                enabled: false,
+                ..Default::default()
+            },
+        );
+        let mut a = vec![0.5_f32; 64];
+        let mut b = vec![0.25_f32; 64];
+        ducker.process(true, &mut [a.as_mut_slice(), b.as_mut_slice()]);
+        assert!(a.iter().all(|&s| s == 0.5));
+        assert!(b.iter().all(|&s| s == 0.25));
+    }
+}
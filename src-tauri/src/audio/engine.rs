@@ -1,7 +1,8 @@
 use std::{
-    collections::HashMap,
+    cell::RefCell,
+    collections::{HashMap, VecDeque},
     path::PathBuf,
-    sync::{Arc, Mutex},
+    sync::{atomic::AtomicU64, Arc, Mutex},
 };
 
 use cpal::{
@@ -11,17 +12,32 @@ use cpal::{
 use ringbuf::{traits::Split, HeapRb};
 use serde::{Deserialize, Serialize};
 
-use crate::db::local::MonitorRoutingConfig;
+use crate::db::local::{CueQuantize, MonitorRoutingConfig, QuantizeConfig};
+use crate::error::{AppError, ErrorCode};
 
 use super::{
-    crossfade::{CrossfadeConfig, CrossfadeState, CrossfadeTriggerMode, DeckId},
-    deck::{AttachOp, Deck, DeckState, PreparedTrack, TrackCompletion},
+    cart_wall::{CartKey, CartPlayer, CartTrigger, CartVoiceState},
+    controls::{Control, ControlSlots, DeckControl},
+    crossfade::{CrossfadeConfig, CrossfadeState, CrossfadeTriggerMode, CrossfaderSide, DeckId},
+    deck::{
+        resized_loop_end, AttachOp, Deck, DeckState, PreparedTrack, StopReason, TrackCompletion,
+        QUANTIZE_LEAD_MS,
+    },
     device_manager::{self, AudioOutputMode, AudioOutputRoutingConfig, AudioOutputStatus},
     dsp::{
+        eq::{EqBand, EqKillState},
         pipeline::{ChannelPipeline, PipelineSettings},
         stem_filter::{StemFilterConfig, StemFilterMode},
     },
+    ducking::{DuckConfig, DuckStateEvent, Ducker},
     mixer::Mixer,
+    multitrack::{BusTap, MultitrackTap, RecordBus},
+    remote_stream::{self, RemoteStreamInfo, RemoteStreamStatus},
+    reverse::Direction,
+    sfx_player::{SfxPlayer, SfxStop, SfxTrigger, SfxVoiceState},
+    snapshot::{snapshot_buffer, SnapshotReader, SnapshotWriter},
+    spectrum::{SpectrumSource, SpectrumTap},
+    stem_mix::{StemMix, StemPaths},
 };
 
 // ── VU event ────────────────────────────────────────────────────────────────
@@ -51,9 +67,25 @@ pub struct DeckStateEvent {
     pub playback_rate: f32,
     pub pitch_pct: f32,
     pub tempo_pct: f32,
+    pub keylock: bool,
+    /// Source lookahead keylock adds (0 while off); `position_ms` already
+    /// accounts for it
+    pub keylock_latency_ms: f32,
+    /// Playing backwards (`set_deck_reverse`)
+    pub reverse: bool,
+    /// Slip-reverse held (`censor_deck`)
+    pub censoring: bool,
+    pub slip: bool,
+    /// Where the track would have been, while slip is on
+    pub slip_position_ms: Option<u64>,
     pub channel_gain: f32,
+    /// Load-time trim (song gain override + category trim) in dB
+    pub track_gain_db: f32,
+    /// Trim plus fader, in dB
+    pub effective_gain_db: f32,
     pub bass_db: f32,
     pub filter_amount: f32,
+    pub eq_kill: EqKillState,
     pub master_level: f32,
     pub decoder_buffer_ms: u64,
     pub rms_db_pre_fader: f32,
@@ -61,6 +93,17 @@ pub struct DeckStateEvent {
     pub loop_enabled: bool,
     pub loop_start_ms: Option<u64>,
     pub loop_end_ms: Option<u64>,
+    pub crossfader_side: CrossfaderSide,
+    /// Set while the deck plays an HTTP(S) stream
+    pub remote_stream: Option<RemoteStreamInfo>,
+    /// Stem levels, while the deck plays separated stems
+    pub stems: Option<StemMix>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct LoopRange {
+    pub start_ms: u64,
+    pub end_ms: u64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -69,6 +112,12 @@ pub struct TrackCompletionEvent {
     pub song_id: i64,
     pub queue_id: Option<i64>,
     pub from_rotation: bool,
+    #[serde(default)]
+    pub played_ms: u64,
+    #[serde(default)]
+    pub duration_ms: u64,
+    #[serde(default)]
+    pub reason: StopReason,
 }
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
@@ -80,7 +129,8 @@ pub enum ManualFadeDirection {
 
 // ── Engine ───────────────────────────────────────────────────────────────────
 
-/// Callback-safe shared state — no Mutex, all accessed only from the CPAL thread.
+/// Engine state owned by the CPAL callback. The control side changes it only
+/// through `EngineCmd` and reads it from the published `EngineSnapshot`.
 struct RtState {
     decks: HashMap<DeckId, Deck>,
     pipelines: HashMap<DeckId, ChannelPipeline>,
@@ -105,6 +155,8 @@ struct RtState {
     buf_deck_b: Vec<f32>,
     buf_deck_a_cue_tap: Vec<f32>,
     buf_deck_b_cue_tap: Vec<f32>,
+    buf_aux1_cue_tap: Vec<f32>,
+    buf_aux2_cue_tap: Vec<f32>,
     buf_sound_fx: Vec<f32>,
     buf_aux1: Vec<f32>,
     buf_aux2: Vec<f32>,
@@ -112,46 +164,498 @@ struct RtState {
     buf_silence: Vec<f32>,
     buf_master: Vec<f32>,
     buf_cue: Vec<f32>,
+    // On-air mix; the only source for the encoder feed
+    buf_program: Vec<f32>,
     // Encoder ring buffer producer (to stream/icecast thread)
     encoder_prod: ringbuf::HeapProd<f32>,
+    // Secondary cue output (dual-device split); fed from `buf_cue`
+    cue_prod: Option<ringbuf::HeapProd<f32>>,
+    // Live mic samples (interleaved stereo) mixed into the Voice FX channel
+    live_input_cons: Option<ringbuf::HeapCons<f32>>,
+    // Remote DJ audio (interleaved stereo) mixed into the Aux 2 channel
+    remote_input_cons: Option<ringbuf::HeapCons<f32>>,
+    // Program minus Aux 2, returned to the remote DJ
+    mix_minus_prod: Option<ringbuf::HeapProd<f32>>,
+    // Analyzer feeds (master and optionally each channel), mono
+    spectrum_tap: Option<SpectrumTap>,
+    // Program bus copy for the loudness meter (interleaved stereo)
+    loudness_prod: Option<ringbuf::HeapProd<f32>>,
+    // Per-bus feeds for a multitrack recording (interleaved stereo)
+    multitrack_tap: Option<MultitrackTap>,
+    mic_open: bool,
+    ducker: Ducker,
+    // Playback deck fade-to-stop ramps: (current gain, per-frame step)
+    deck_fade_outs: HashMap<DeckId, (f32, f32)>,
+    // Channel gain ramps (voice track fade points): (target gain, per-frame step)
+    deck_gain_ramps: HashMap<DeckId, (f32, f32)>,
+    // Cart wall voices, mixed into the Sound FX channel
+    carts: CartPlayer,
+    // One-shot stinger voices, mixed into the Sound FX channel
+    sfx: SfxPlayer,
+    // Read-only copy for the control side, published after every block
+    snapshot: SnapshotWriter<EngineSnapshot>,
+    // Finished tracks, drained by `take_track_completions`
+    completions: ringbuf::HeapProd<(DeckId, TrackCompletion)>,
+    // Buffers the callback is done with, freed by `flush_commands`
+    retired: ringbuf::HeapProd<Retired>,
+    // Replaced spectrum tap waiting for room in `retired`
+    retired_tap: Option<SpectrumTap>,
+    // Continuous control values, applied at the start of each block
+    controls: Arc<ControlSlots>,
 }
 
+impl RtState {
+    fn new(
+        sample_rate: u32,
+        channels: usize,
+        encoder_prod: ringbuf::HeapProd<f32>,
+    ) -> (Self, EngineView) {
+        let (snapshot, snapshot_reader) = snapshot_buffer();
+        let (completions, completions_cons) =
+            HeapRb::<(DeckId, TrackCompletion)>::new(COMPLETION_RING_SIZE).split();
+        let (retired, retired_cons) = HeapRb::<Retired>::new(RETIRED_RING_SIZE).split();
+        let controls = Arc::new(ControlSlots::default());
+        let mut rt = Self {
+            decks: {
+                let mut m = HashMap::new();
+                m.insert(DeckId::DeckA, Deck::new(DeckId::DeckA));
+                m.insert(DeckId::DeckB, Deck::new(DeckId::DeckB));
+                m.insert(DeckId::SoundFx, Deck::new(DeckId::SoundFx));
+                m.insert(DeckId::Aux1, Deck::new(DeckId::Aux1));
+                m.insert(DeckId::Aux2, Deck::new(DeckId::Aux2));
+                m.insert(DeckId::VoiceFx, Deck::new(DeckId::VoiceFx));
+                m
+            },
+            pipelines: {
+                let mut m = HashMap::new();
+                for id in [
+                    DeckId::DeckA,
+                    DeckId::DeckB,
+                    DeckId::SoundFx,
+                    DeckId::Aux1,
+                    DeckId::Aux2,
+                    DeckId::VoiceFx,
+                ] {
+                    let mut pipeline = ChannelPipeline::new(sample_rate as f32);
+                    // Tuned defaults per channel type (mode remains OFF).
+                    match id {
+                        DeckId::DeckA | DeckId::DeckB => {
+                            pipeline.stem_filter.set_config(StemFilterConfig {
+                                mode: StemFilterMode::Off,
+                                amount: 0.82,
+                            });
+                        }
+                        DeckId::VoiceFx => {
+                            pipeline.stem_filter.set_config(StemFilterConfig {
+                                mode: StemFilterMode::Off,
+                                amount: 0.55,
+                            });
+                        }
+                        _ => {}
+                    }
+                    m.insert(id, pipeline);
+                }
+                m
+            },
+            master_pipeline: ChannelPipeline::new(sample_rate as f32),
+            mixer: Mixer::new(),
+            crossfade: CrossfadeState::default(),
+            crossfade_config: CrossfadeConfig::default(),
+            manual_crossfade_pos: -1.0,
+            deck_bass_db: DeckId::PLAYBACK.into_iter().map(|id| (id, 0.0)).collect(),
+            deck_filter_amount: DeckId::PLAYBACK.into_iter().map(|id| (id, 0.0)).collect(),
+            cue_preview_enabled: DeckId::PLAYBACK.into_iter().map(|id| (id, false)).collect(),
+            cue_split_active: false,
+            cue_available: channels >= 4,
+            cue_level: 1.0,
+            headphone_mix: -1.0,
+            master_level: 1.0,
+            local_monitor_muted: false,
+            sample_rate,
+            output_channels: channels.max(2),
+            buf_deck_a: Vec::new(),
+            buf_deck_b: Vec::new(),
+            buf_deck_a_cue_tap: Vec::new(),
+            buf_deck_b_cue_tap: Vec::new(),
+            buf_aux1_cue_tap: Vec::new(),
+            buf_aux2_cue_tap: Vec::new(),
+            buf_sound_fx: Vec::new(),
+            buf_aux1: Vec::new(),
+            buf_aux2: Vec::new(),
+            buf_voice_fx: Vec::new(),
+            buf_silence: Vec::new(),
+            buf_master: Vec::new(),
+            buf_cue: Vec::new(),
+            buf_program: Vec::new(),
+            encoder_prod,
+            cue_prod: None,
+            live_input_cons: None,
+            remote_input_cons: None,
+            mix_minus_prod: None,
+            spectrum_tap: None,
+            loudness_prod: None,
+            multitrack_tap: None,
+            mic_open: false,
+            ducker: Ducker::new(sample_rate as f32, DuckConfig::default()),
+            deck_fade_outs: HashMap::new(),
+            deck_gain_ramps: HashMap::new(),
+            carts: CartPlayer::new(),
+            sfx: SfxPlayer::new(),
+            snapshot,
+            completions,
+            retired,
+            retired_tap: None,
+            controls: Arc::clone(&controls),
+        };
+        rt.publish_snapshot();
+        let view = EngineView {
+            snapshot: snapshot_reader,
+            completions: completions_cons,
+            retired: retired_cons,
+            controls,
+        };
+        (rt, view)
+    }
+
+    /// Adopt a new output device format (stream rebuild).
+    fn set_output_format(&mut self, sample_rate: u32, channels: usize) {
+        self.sample_rate = sample_rate;
+        self.ducker.set_sample_rate(sample_rate as f32);
+        self.output_channels = channels.max(2);
+    }
+
+    /// Resize scratch buffers (only happens on first call or config change).
+    fn resize_buffers(&mut self, stereo_len: usize) {
+        if self.buf_deck_a.len() == stereo_len {
+            return;
+        }
+        for buf in [
+            &mut self.buf_deck_a,
+            &mut self.buf_deck_b,
+            &mut self.buf_deck_a_cue_tap,
+            &mut self.buf_deck_b_cue_tap,
+            &mut self.buf_aux1_cue_tap,
+            &mut self.buf_aux2_cue_tap,
+            &mut self.buf_sound_fx,
+            &mut self.buf_aux1,
+            &mut self.buf_aux2,
+            &mut self.buf_voice_fx,
+            &mut self.buf_silence,
+            &mut self.buf_master,
+            &mut self.buf_cue,
+            &mut self.buf_program,
+        ] {
+            buf.resize(stereo_len, 0.0);
+        }
+    }
+
+    /// Copy what the control side may query into the back snapshot and
+    /// publish it. Allocation-free once the snapshot slots have grown to fit
+    /// the longest paths and voice lists.
+    fn publish_snapshot(&mut self) {
+        let snap = self.snapshot.back();
+        for (slot, id) in snap.decks.iter_mut().zip(DeckId::ALL) {
+            if let Some(deck) = self.decks.get(&id) {
+                slot.capture(deck);
+            }
+            slot.bass_db = self.deck_bass_db.get(&id).copied().unwrap_or(0.0);
+            slot.filter_amount = self.deck_filter_amount.get(&id).copied().unwrap_or(0.0);
+            slot.eq_kill = self
+                .pipelines
+                .get(&id)
+                .map(|p| p.eq.kill_state())
+                .unwrap_or_default();
+            slot.cue_preview_enabled = self.cue_preview_enabled.get(&id).copied().unwrap_or(false);
+            let ch = self.mixer.channel(id);
+            slot.vu_db = (ch.vu_left_db, ch.vu_right_db);
+        }
+
+        let mut peak = (0.0_f32, 0.0_f32);
+        for frame in self.buf_master.chunks_exact(2) {
+            peak.0 = peak.0.max(frame[0].abs());
+            peak.1 = peak.1.max(frame[1].abs());
+        }
+        snap.master_peak = peak;
+
+        snap.crossfade_decks = self.crossfade.outgoing().zip(self.crossfade.incoming());
+        snap.crossfade_progress = self.crossfade.progress();
+        snap.crossfade_config.clone_from(&self.crossfade_config);
+        snap.manual_crossfade_pos = self.manual_crossfade_pos;
+        snap.master_level = self.master_level;
+        snap.headphone_mix = self.headphone_mix;
+        snap.cue_level = self.cue_level;
+        snap.local_monitor_muted = self.local_monitor_muted;
+        snap.mic_open = self.mic_open;
+        snap.duck_gain = self.ducker.gain();
+        snap.duck_config.clone_from(self.ducker.config());
+        self.carts.write_states(&mut snap.carts);
+        self.sfx.write_states(&mut snap.sfx);
+        self.snapshot.publish();
+    }
+
+    /// Hand finished tracks to the control side. While the ring is full they
+    /// wait on their deck for a later block.
+    fn publish_completions(&mut self) {
+        use ringbuf::traits::{Observer as _, Producer as _};
+        for id in DeckId::ALL {
+            if self.completions.is_full() {
+                return;
+            }
+            if let Some(completion) = self.decks.get_mut(&id).and_then(|d| d.take_completion()) {
+                let _ = self.completions.try_push((id, completion));
+            }
+        }
+    }
+
+    /// Send finished buffers back so they are freed off the audio thread.
+    /// What does not fit waits for a later block.
+    fn publish_retired(&mut self) {
+        use ringbuf::traits::{Observer as _, Producer as _};
+        if let Some(tap) = self.retired_tap.take() {
+            self.retire_spectrum_tap(tap);
+        }
+        while !self.retired.is_full() {
+            let Some(sample) = self.carts.pop_retired().or_else(|| self.sfx.pop_retired()) else {
+                return;
+            };
+            let _ = self.retired.try_push(Retired::CartSample(sample));
+        }
+    }
+
+    /// Hand a replaced spectrum tap (and its rings) back to the control side.
+    /// If the ring is full it waits for `publish_retired`.
+    fn retire_spectrum_tap(&mut self, tap: SpectrumTap) {
+        use ringbuf::traits::Producer as _;
+        if let Err(Retired::SpectrumTap(tap)) = self.retired.try_push(Retired::SpectrumTap(tap)) {
+            self.retired_tap = Some(tap);
+        }
+    }
+}
+
+/// Control-side view of the RT state, as last published by the callback.
+#[derive(Default)]
+struct EngineSnapshot {
+    /// In `DeckId::ALL` order
+    decks: [DeckSnapshot; 6],
+    crossfade_decks: Option<(DeckId, DeckId)>,
+    crossfade_progress: Option<f32>,
+    crossfade_config: CrossfadeConfig,
+    manual_crossfade_pos: f32,
+    master_level: f32,
+    /// Linear peak of the device master over the last block
+    master_peak: (f32, f32),
+    headphone_mix: f32,
+    cue_level: f32,
+    local_monitor_muted: bool,
+    mic_open: bool,
+    duck_gain: f32,
+    duck_config: DuckConfig,
+    carts: Vec<CartVoiceState>,
+    sfx: Vec<SfxVoiceState>,
+}
+
+impl EngineSnapshot {
+    fn deck(&self, id: DeckId) -> &DeckSnapshot {
+        let index = DeckId::ALL.iter().position(|d| *d == id).unwrap_or(0);
+        &self.decks[index]
+    }
+}
+
+#[derive(Default)]
+struct DeckSnapshot {
+    state: DeckState,
+    file_path: Option<PathBuf>,
+    stem_paths: Option<StemPaths>,
+    song_id: Option<i64>,
+    queue_id: Option<i64>,
+    from_rotation: bool,
+    declared_duration_ms: Option<u64>,
+    sample_rate: u32,
+    position_ms: u64,
+    duration_ms: u64,
+    playback_rate: f32,
+    pitch_pct: f32,
+    tempo_pct: f32,
+    keylock: bool,
+    keylock_latency_ms: f32,
+    direction: Direction,
+    slip: bool,
+    slip_position_ms: Option<u64>,
+    slip_return_target_ms: Option<u64>,
+    /// Active loop in frames (`Deck::loop_frames`)
+    loop_frames: Option<(u64, u64)>,
+    loop_range_ms: Option<(u64, u64)>,
+    channel_gain: f32,
+    track_gain_db: f32,
+    rms_db_pre_fader: f32,
+    decoder_buffer_ms: u64,
+    remote: Option<Arc<RemoteStreamStatus>>,
+    stem_mix: StemMix,
+    bass_db: f32,
+    filter_amount: f32,
+    eq_kill: EqKillState,
+    cue_preview_enabled: bool,
+    vu_db: (f32, f32),
+}
+
+impl DeckSnapshot {
+    fn capture(&mut self, d: &Deck) {
+        self.state.clone_from(&d.state);
+        self.file_path.clone_from(&d.file_path);
+        match (&mut self.stem_paths, d.stem_paths()) {
+            (Some(paths), Some(current)) => {
+                for (path, current) in paths.iter_mut().zip(current) {
+                    path.clone_from(current);
+                }
+            }
+            (paths, current) => *paths = current.cloned(),
+        }
+        self.song_id = d.song_id;
+        self.queue_id = d.queue_id;
+        self.from_rotation = d.from_rotation;
+        self.declared_duration_ms = d.declared_duration_ms;
+        self.sample_rate = d.sample_rate;
+        self.position_ms = d.position_ms();
+        self.duration_ms = d.duration_ms();
+        self.playback_rate = d.playback_rate;
+        self.pitch_pct = d.pitch_pct;
+        self.tempo_pct = d.tempo_pct;
+        self.keylock = d.keylock;
+        self.keylock_latency_ms = d.keylock_latency_ms();
+        self.direction = d.direction();
+        self.slip = d.slip;
+        self.slip_position_ms = d.slip_position_ms();
+        self.slip_return_target_ms = d.slip_return_target_ms();
+        self.loop_frames = d.loop_frames();
+        self.loop_range_ms = d.loop_range_ms();
+        self.channel_gain = d.channel_gain;
+        self.track_gain_db = d.track_gain_db;
+        self.rms_db_pre_fader = d.rms_db_pre_fader;
+        self.decoder_buffer_ms = d.decoder_buffered_ms();
+        // Compare first: swapping Arcs every block is needless refcount traffic.
+        let remote = d.remote_status();
+        if self.remote.as_ref().map(Arc::as_ptr) != remote.map(Arc::as_ptr) {
+            self.remote = remote.cloned();
+        }
+        self.stem_mix = d.stem_mix;
+    }
+
+    fn is_playing(&self) -> bool {
+        is_playing_like(&self.state)
+    }
+}
+
+/// Control-side ends of what the callback publishes.
+struct EngineView {
+    snapshot: SnapshotReader<EngineSnapshot>,
+    completions: ringbuf::HeapCons<(DeckId, TrackCompletion)>,
+    retired: ringbuf::HeapCons<Retired>,
+    controls: Arc<ControlSlots>,
+}
+
+/// Heap-owning values the callback has finished with. Dropping one could
+/// free a large buffer, so they travel back to the control side instead.
+// The payloads are never read: holding them only moves the drop off the
+// audio thread.
+#[allow(dead_code)]
+enum Retired {
+    CartSample(Arc<crate::audio::cart_wall::CartSample>),
+    SpectrumTap(SpectrumTap),
+}
+
+/// The callback's state and command queue. When the output stream (and with
+/// it the callback) is dropped they are parked in `park`, so a rebuilt
+/// stream carries on with the same decks and pending commands.
+struct RtOwner {
+    rt: Box<RtState>,
+    cmds: ringbuf::HeapCons<EngineCmd>,
+}
+
+type RtPark = Arc<Mutex<Option<RtOwner>>>;
+
+struct RtHandle {
+    owner: Option<RtOwner>,
+    park: RtPark,
+}
+
+impl Drop for RtHandle {
+    fn drop(&mut self) {
+        if let Some(owner) = self.owner.take() {
+            if let Ok(mut park) = self.park.lock() {
+                *park = Some(owner);
+            }
+        }
+    }
+}
+
+/// Upper bound on queued live mic audio before old samples are dropped.
+const LIVE_INPUT_MAX_LATENCY_MS: usize = 60;
+/// Same for remote DJ audio; the ingest side keeps its own jitter buffer.
+const REMOTE_INPUT_MAX_LATENCY_MS: usize = 250;
+/// Completed tracks waiting for `take_track_completions`.
+const COMPLETION_RING_SIZE: usize = 32;
+/// Retired buffers waiting to be freed by `flush_commands`.
+const RETIRED_RING_SIZE: usize = 64;
+
 /// Commands sent from the main thread → real-time thread via a lock-free channel.
-/// Kept small; heavy state lives in `AudioEngine` behind the Mutex.
+/// Continuous controls (faders, pitch, crossfader) go through `ControlSlots`
+/// instead, so a burst of them cannot fill the ring.
 enum EngineCmd {
     AttachPreparedTrack {
         deck: DeckId,
         prepared: PreparedTrack,
         op: AttachOp,
     },
+    QuantizedSeek {
+        deck: DeckId,
+        prepared: PreparedTrack,
+        at_frame: u64,
+    },
     Play(DeckId),
     Pause(DeckId),
-    StopWithCompletion(DeckId),
-    SetGain {
+    StopWithCompletion {
         deck: DeckId,
-        gain: f32,
+        reason: StopReason,
     },
-    SetDeckBass {
+    SetTrackGain {
         deck: DeckId,
-        bass_db: f32,
+        gain_db: f32,
     },
-    SetDeckFilter {
+    SetDeckEqKill {
         deck: DeckId,
-        amount: f32,
-    },
-    SetMasterLevel {
-        level: f32,
+        band: EqBand,
+        killed: bool,
     },
     SetLocalMonitorMuted {
         muted: bool,
     },
-    SetDeckPitch {
+    SetMicOpen {
+        open: bool,
+    },
+    SetDuckConfig(DuckConfig),
+    SetDeckKeylock {
         deck: DeckId,
-        pct: f32,
+        enabled: bool,
+    },
+    SetDeckStemMix {
+        deck: DeckId,
+        mix: StemMix,
+    },
+    SetDeckReverse {
+        deck: DeckId,
+        enabled: bool,
+    },
+    SetDeckCensor {
+        deck: DeckId,
+        active: bool,
+    },
+    SetDeckSlip {
+        deck: DeckId,
+        enabled: bool,
     },
-    SetDeckTempo {
+    SlipReturn {
         deck: DeckId,
-        pct: f32,
+        prepared: PreparedTrack,
     },
     SetDeckLoop {
         deck: DeckId,
@@ -159,18 +663,41 @@ enum EngineCmd {
         end_ms: u64,
     },
     ClearDeckLoop(DeckId),
+    ResizeDeckLoop {
+        deck: DeckId,
+        double: bool,
+    },
     StartCrossfade {
         outgoing: DeckId,
         incoming: DeckId,
     },
-    SetManualCrossfade {
-        position: f32,
-    },
     TriggerManualFade {
         direction: ManualFadeDirection,
         duration_ms: u32,
     },
+    StartTimedCrossfade {
+        outgoing: DeckId,
+        incoming: DeckId,
+        duration_ms: u32,
+        /// Crossfade profile for this transition instead of the station config
+        profile: Option<Box<CrossfadeConfig>>,
+    },
+    FadeOutDeck {
+        deck: DeckId,
+        duration_ms: u32,
+    },
+    /// Move a channel fader to `gain` over `duration_ms`
+    RampChannelGain {
+        deck: DeckId,
+        gain: f32,
+        duration_ms: u32,
+    },
     SetCrossfadeConfig(CrossfadeConfig),
+    /// End an in-flight fade now: jump to its end state, or abandon it and
+    /// keep the outgoing deck on air
+    ResolveCrossfade {
+        complete: bool,
+    },
     SetChannelPipeline {
         deck: DeckId,
         settings: PipelineSettings,
@@ -182,37 +709,125 @@ enum EngineCmd {
         deck: DeckId,
         enabled: bool,
     },
-    SetHeadphoneMix {
-        value: f32,
+    SetMonitorRoutingConfig(MonitorRoutingConfig),
+    TriggerCart(CartTrigger),
+    StopCart(CartKey),
+    StopAllCarts,
+    PlaySfx(SfxTrigger),
+    StopSfx {
+        which: SfxStop,
+        fade_ms: u32,
     },
-    SetHeadphoneLevel {
-        value: f32,
+    /// Output routing applied: whether a cue output exists and is split off
+    SetCueRouting {
+        available: bool,
+        split_active: bool,
     },
-    SetMonitorRoutingConfig(MonitorRoutingConfig),
+    SetCueOutput(Option<ringbuf::HeapProd<f32>>),
+    SetLiveInput(Option<ringbuf::HeapCons<f32>>),
+    SetRemoteInput(Option<ringbuf::HeapCons<f32>>),
+    SetMixMinus(Option<ringbuf::HeapProd<f32>>),
+    SetSpectrumTap(Option<SpectrumTap>),
+    SetLoudnessTap(Option<ringbuf::HeapProd<f32>>),
+    SetMultitrackTap(Option<MultitrackTap>),
 }
 
-/// The main audio engine — lives behind `Arc<Mutex<AudioEngine>>` in `AppState`.
+impl EngineCmd {
+    /// Commands that must reach the engine however far behind it is:
+    /// transport, fades and live audio routing.
+    fn is_critical(&self) -> bool {
+        matches!(
+            self,
+            EngineCmd::AttachPreparedTrack {
+                op: AttachOp::Load,
+                ..
+            } | EngineCmd::Play(_)
+                | EngineCmd::Pause(_)
+                | EngineCmd::StopWithCompletion { .. }
+                | EngineCmd::StartCrossfade { .. }
+                | EngineCmd::TriggerManualFade { .. }
+                | EngineCmd::StartTimedCrossfade { .. }
+                | EngineCmd::ResolveCrossfade { .. }
+                | EngineCmd::FadeOutDeck { .. }
+                | EngineCmd::RampChannelGain { .. }
+                | EngineCmd::SetMicOpen { .. }
+                | EngineCmd::SetCueRouting { .. }
+                | EngineCmd::SetCueOutput(_)
+                | EngineCmd::SetLiveInput(_)
+                | EngineCmd::SetRemoteInput(_)
+                | EngineCmd::SetMixMinus(_)
+                | EngineCmd::SetLoudnessTap(_)
+                | EngineCmd::SetMultitrackTap(_)
+        )
+    }
+
+    /// Whether `self` makes a still-queued `older` pointless: a seek (jog
+    /// spam) replaces an earlier seek of the same deck.
+    fn supersedes(&self, older: &EngineCmd) -> bool {
+        let seek_deck = |cmd: &EngineCmd| match cmd {
+            EngineCmd::AttachPreparedTrack {
+                deck,
+                op: AttachOp::Seek,
+                ..
+            }
+            | EngineCmd::QuantizedSeek { deck, .. } => Some(*deck),
+            _ => None,
+        };
+        seek_deck(self).is_some() && seek_deck(self) == seek_deck(older)
+    }
+}
+
+/// The main audio engine — lives behind `Mutex<AudioEngine>` in `AppState`.
 pub struct AudioEngine {
     _stream: Option<Stream>,
+    // Headphone stream on a separate device when routing is `DualDeviceSplit`
+    _cue_stream: Option<Stream>,
+    cue_output: Option<CueOutputInfo>,
     // Encoder consumer (icecast thread reads from here)
     pub encoder_consumer: Option<ringbuf::HeapCons<f32>>,
     // Command sender to the RT thread
     cmd_tx: ringbuf::HeapProd<EngineCmd>,
-    // Shared state accessible from both the main thread (for queries) and
-    // the CPAL callback (for audio).
-    rt_state: Arc<Mutex<RtState>>,
+    // Commands waiting for room in the ring, oldest first
+    cmd_backlog: VecDeque<EngineCmd>,
+    // RT state between output streams (see `RtOwner`)
+    rt_park: RtPark,
+    // Snapshots and completions published by the callback
+    view: RefCell<EngineView>,
+    output_channels: usize,
     routing_config: AudioOutputRoutingConfig,
     output_status: AudioOutputStatus,
+    quantize: QuantizeConfig,
     #[allow(dead_code)]
     sample_rate: u32,
 }
 
+/// Identity of the currently open secondary cue stream.
+struct CueOutputInfo {
+    device_id: String,
+    device_name: String,
+    sample_rate: u32,
+}
+
+fn no_track_loaded() -> AppError {
+    AppError::engine("No track loaded")
+}
+
 impl AudioEngine {
     const ENCODER_RING_SIZE: usize = 44100 * 2 * 10; // 10 s encoder buffer
-    const CMD_RING_SIZE: usize = 64;
+    /// Also bounds how many samples one drain of the ring can bring in.
+    pub(crate) const CMD_RING_SIZE: usize = 1024;
+    /// Beyond this many waiting commands only critical ones are accepted.
+    const MAX_CMD_BACKLOG: usize = 4096;
+    /// Cue ring depth; small so headphones stay close to the master output.
+    const CUE_RING_MS: usize = 120;
+    const LIVE_INPUT_RING_MS: usize = 500;
+    /// Lets the loudness meter skip a few UI ticks without losing audio
+    const LOUDNESS_RING_MS: usize = 2_000;
+    /// Rides out disk stalls in the multitrack writer threads
+    const MULTITRACK_RING_MS: usize = 4_000;
 
     /// Initialise and start the CPAL output stream.
-    pub fn new() -> Result<Self, String> {
+    pub fn new() -> Result<Self, AppError> {
         let host = cpal::default_host();
         let default_name = host
             .default_output_device()
@@ -220,11 +835,11 @@ impl AudioEngine {
             .unwrap_or_default();
         let device = host
             .default_output_device()
-            .ok_or("No default audio output device found")?;
+            .ok_or_else(|| AppError::device("No default audio output device found"))?;
 
         let config = device
             .default_output_config()
-            .map_err(|e| format!("Default config error: {e}"))?;
+            .map_err(|e| AppError::device(format!("Default config error: {e}")))?;
 
         let sample_rate = config.sample_rate().0;
         let channels = config.channels() as usize;
@@ -253,107 +868,31 @@ impl AudioEngine {
         let cmd_rb = HeapRb::<EngineCmd>::new(Self::CMD_RING_SIZE);
         let (cmd_prod, cmd_cons) = cmd_rb.split();
 
-        // Shared RT state (wrapped in Arc<Mutex> so the main thread can query it)
-        let rt_arc = Arc::new(Mutex::new(RtState {
-            decks: {
-                let mut m = HashMap::new();
-                m.insert(DeckId::DeckA, Deck::new(DeckId::DeckA));
-                m.insert(DeckId::DeckB, Deck::new(DeckId::DeckB));
-                m.insert(DeckId::SoundFx, Deck::new(DeckId::SoundFx));
-                m.insert(DeckId::Aux1, Deck::new(DeckId::Aux1));
-                m.insert(DeckId::Aux2, Deck::new(DeckId::Aux2));
-                m.insert(DeckId::VoiceFx, Deck::new(DeckId::VoiceFx));
-                m
-            },
-            pipelines: {
-                let mut m = HashMap::new();
-                for id in [
-                    DeckId::DeckA,
-                    DeckId::DeckB,
-                    DeckId::SoundFx,
-                    DeckId::Aux1,
-                    DeckId::Aux2,
-                    DeckId::VoiceFx,
-                ] {
-                    let mut pipeline = ChannelPipeline::new(sample_rate as f32);
-                    // Tuned defaults per channel type (mode remains OFF).
-                    match id {
-                        DeckId::DeckA | DeckId::DeckB => {
-                            pipeline.stem_filter.set_config(StemFilterConfig {
-                                mode: StemFilterMode::Off,
-                                amount: 0.82,
-                            });
-                        }
-                        DeckId::VoiceFx => {
-                            pipeline.stem_filter.set_config(StemFilterConfig {
-                                mode: StemFilterMode::Off,
-                                amount: 0.55,
-                            });
-                        }
-                        _ => {}
-                    }
-                    m.insert(id, pipeline);
-                }
-                m
-            },
-            master_pipeline: ChannelPipeline::new(sample_rate as f32),
-            mixer: Mixer::new(),
-            crossfade: CrossfadeState::default(),
-            crossfade_config: CrossfadeConfig::default(),
-            manual_crossfade_pos: -1.0,
-            deck_bass_db: {
-                let mut m = HashMap::new();
-                m.insert(DeckId::DeckA, 0.0);
-                m.insert(DeckId::DeckB, 0.0);
-                m
-            },
-            deck_filter_amount: {
-                let mut m = HashMap::new();
-                m.insert(DeckId::DeckA, 0.0);
-                m.insert(DeckId::DeckB, 0.0);
-                m
-            },
-            cue_preview_enabled: {
-                let mut m = HashMap::new();
-                m.insert(DeckId::DeckA, false);
-                m.insert(DeckId::DeckB, false);
-                m
-            },
-            cue_split_active: false,
-            cue_available: channels >= 4,
-            cue_level: 1.0,
-            headphone_mix: -1.0,
-            master_level: 1.0,
-            local_monitor_muted: false,
-            sample_rate,
-            output_channels: channels.max(2),
-            buf_deck_a: Vec::new(),
-            buf_deck_b: Vec::new(),
-            buf_deck_a_cue_tap: Vec::new(),
-            buf_deck_b_cue_tap: Vec::new(),
-            buf_sound_fx: Vec::new(),
-            buf_aux1: Vec::new(),
-            buf_aux2: Vec::new(),
-            buf_voice_fx: Vec::new(),
-            buf_silence: Vec::new(),
-            buf_master: Vec::new(),
-            buf_cue: Vec::new(),
-            encoder_prod: enc_prod,
-        }));
-
-        let rt_arc_cb = Arc::clone(&rt_arc);
+        // RT state, owned by the callback from here on
+        let (rt, view) = RtState::new(sample_rate, channels, enc_prod);
+        let owner = RtOwner {
+            rt: Box::new(rt),
+            cmds: cmd_cons,
+        };
+        let rt_park = RtPark::default();
 
-        let stream = Self::build_stream(&device, &config.into(), rt_arc_cb, cmd_cons)?;
+        let stream = Self::build_stream(&device, &config.into(), owner, Arc::clone(&rt_park))?;
         stream
             .play()
-            .map_err(|e| format!("Stream play error: {e}"))?;
+            .map_err(|e| AppError::device(format!("Stream play error: {e}")))?;
 
         Ok(Self {
             _stream: Some(stream),
+            _cue_stream: None,
+            cue_output: None,
             encoder_consumer: Some(enc_cons),
             cmd_tx: cmd_prod,
-            rt_state: rt_arc,
+            cmd_backlog: VecDeque::new(),
+            rt_park,
+            view: RefCell::new(view),
+            output_channels: channels.max(2),
             routing_config: AudioOutputRoutingConfig::default(),
+            quantize: QuantizeConfig::default(),
             output_status: AudioOutputStatus {
                 active_mode: if channels >= 4 {
                     AudioOutputMode::SingleDeviceFourChannel
@@ -363,6 +902,7 @@ impl AudioEngine {
                 master_device_id: device_id.clone(),
                 master_device_name: Some(device_name),
                 cue_device_id: device_id,
+                cue_device_name: None,
                 cue_available: channels >= 4,
                 fallback_active: false,
                 last_error: None,
@@ -378,7 +918,7 @@ impl AudioEngine {
         deck: DeckId,
         path: PathBuf,
         song_id: Option<i64>,
-    ) -> Result<(), String> {
+    ) -> Result<(), AppError> {
         self.load_track_with_source(deck, path, song_id, None, false, None)
     }
 
@@ -390,9 +930,16 @@ impl AudioEngine {
         queue_id: Option<i64>,
         from_rotation: bool,
         declared_duration_ms: Option<u64>,
-    ) -> Result<(), String> {
+    ) -> Result<(), AppError> {
         let prepared =
-            Deck::prepare_load(path, song_id, queue_id, from_rotation, declared_duration_ms)?;
+            Deck::prepare_load(path, song_id, queue_id, from_rotation, declared_duration_ms)
+                .map_err(AppError::decode)?;
+        self.load_prepared(deck, prepared)
+    }
+
+    /// Load a track prepared off the engine lock (remote streams connect and
+    /// prebuffer in `Deck::prepare_load`, which can take seconds).
+    pub fn load_prepared(&mut self, deck: DeckId, prepared: PreparedTrack) -> Result<(), AppError> {
         self.send_cmd(EngineCmd::AttachPreparedTrack {
             deck,
             prepared,
@@ -400,39 +947,183 @@ impl AudioEngine {
         })
     }
 
-    pub fn play(&mut self, deck: DeckId) -> Result<(), String> {
+    /// Whether `deck` is playing an HTTP(S) stream (which cannot seek).
+    pub fn is_remote_stream(&self, deck: DeckId) -> bool {
+        self.with_snapshot(|snap| {
+            snap.deck(deck)
+                .file_path
+                .as_ref()
+                .and_then(|p| p.to_str())
+                .is_some_and(remote_stream::is_remote_url)
+        })
+    }
+
+    /// Load a track already positioned at `position_ms` (session recovery).
+    pub fn load_track_at(
+        &mut self,
+        deck: DeckId,
+        path: PathBuf,
+        song_id: Option<i64>,
+        position_ms: u64,
+    ) -> Result<(), AppError> {
+        let prepared = Deck::prepare_seek(path, song_id, None, false, None, position_ms)
+            .map_err(AppError::decode)?;
+        self.send_cmd(EngineCmd::AttachPreparedTrack {
+            deck,
+            prepared,
+            op: AttachOp::Load,
+        })
+    }
+
+    pub fn play(&mut self, deck: DeckId) -> Result<(), AppError> {
         self.send_cmd(EngineCmd::Play(deck))
     }
 
-    pub fn pause(&mut self, deck: DeckId) -> Result<(), String> {
+    pub fn pause(&mut self, deck: DeckId) -> Result<(), AppError> {
         self.send_cmd(EngineCmd::Pause(deck))
     }
 
-    pub fn stop_with_completion(&mut self, deck: DeckId) -> Result<(), String> {
-        self.send_cmd(EngineCmd::StopWithCompletion(deck))
+    pub fn stop_with_completion(
+        &mut self,
+        deck: DeckId,
+        reason: StopReason,
+    ) -> Result<(), AppError> {
+        self.send_cmd(EngineCmd::StopWithCompletion { deck, reason })
     }
 
-    pub fn seek(&mut self, deck: DeckId, position_ms: u64) -> Result<(), String> {
-        let (path, song_id, queue_id, from_rotation, declared_duration_ms) = {
-            let rt = self.rt_state.lock().unwrap();
-            let d = rt.decks.get(&deck).ok_or("Unknown deck")?;
-            let path = d.file_path.clone().ok_or("No track loaded")?;
-            (
-                path,
-                d.song_id,
-                d.queue_id,
-                d.from_rotation,
-                d.declared_duration_ms,
+    pub fn seek(&mut self, deck: DeckId, position_ms: u64) -> Result<(), AppError> {
+        let prepared = self.prepare_deck_seek(deck, position_ms)?;
+        self.send_cmd(EngineCmd::AttachPreparedTrack {
+            deck,
+            prepared,
+            op: AttachOp::Seek,
+        })
+    }
+
+    /// Seek for a hot-cue or loop jump. With `mode` on and the deck playing,
+    /// the jump waits for the next grid boundary so the beat carries on in
+    /// phase; otherwise (or past the end of the grid) it is a plain seek.
+    pub fn jump(
+        &mut self,
+        deck: DeckId,
+        position_ms: u64,
+        beat_times_ms: &[i64],
+        mode: CueQuantize,
+    ) -> Result<(), AppError> {
+        let at_frame = self.with_snapshot(|snap| {
+            let d = snap.deck(deck);
+            let earliest_ms = (d.position_ms + QUANTIZE_LEAD_MS) as i64;
+            crate::audio::analyzer::beatgrid::next_grid_position_ms(
+                earliest_ms,
+                beat_times_ms,
+                mode,
             )
+            .filter(|_| d.is_playing())
+            .map(|ms| ms as u64 * d.sample_rate as u64 / 1000)
+        });
+        let Some(at_frame) = at_frame else {
+            return self.seek(deck, position_ms);
         };
+        let prepared = self.prepare_deck_seek(deck, position_ms)?;
+        self.send_cmd(EngineCmd::QuantizedSeek {
+            deck,
+            prepared,
+            at_frame,
+        })
+    }
+
+    pub fn quantize_config(&self) -> QuantizeConfig {
+        self.quantize.clone()
+    }
+
+    pub fn set_quantize_config(&mut self, config: QuantizeConfig) {
+        self.quantize = config;
+    }
+
+    pub fn quantize_mode(&self, deck: DeckId) -> CueQuantize {
+        self.quantize.mode_for(deck)
+    }
+
+    fn prepare_deck_seek(&self, deck: DeckId, position_ms: u64) -> Result<PreparedTrack, AppError> {
+        let (path, stems, song_id, queue_id, from_rotation, declared_duration_ms) = self
+            .with_snapshot(|snap| {
+                let d = snap.deck(deck);
+                let path = d.file_path.clone().ok_or_else(no_track_loaded)?;
+                Ok::<_, AppError>((
+                    path,
+                    d.stem_paths.clone(),
+                    d.song_id,
+                    d.queue_id,
+                    d.from_rotation,
+                    d.declared_duration_ms,
+                ))
+            })?;
+        // A deck playing stems keeps playing them from the new position.
+        match stems {
+            Some(stems) => Deck::prepare_stems(
+                path,
+                stems,
+                song_id,
+                queue_id,
+                from_rotation,
+                declared_duration_ms,
+                position_ms,
+            ),
+            None => Deck::prepare_seek(
+                path,
+                song_id,
+                queue_id,
+                from_rotation,
+                declared_duration_ms,
+                position_ms,
+            ),
+        }
+        .map_err(AppError::decode)
+    }
+
+    pub fn switch_deck_track_source(
+        &mut self,
+        deck: DeckId,
+        new_path: PathBuf,
+    ) -> Result<(), AppError> {
+        if !new_path.exists() {
+            return Err(AppError::file_not_found(new_path.display()));
+        }
+        if !new_path.is_file() {
+            return Err(AppError::invalid_input(format!(
+                "Path is not a file: {}",
+                new_path.display()
+            )));
+        }
+
+        let (current_path, song_id, queue_id, from_rotation, declared_duration_ms, position_ms) =
+            self.with_snapshot(|snap| {
+                let d = snap.deck(deck);
+                let current_path = d.file_path.clone().ok_or_else(no_track_loaded)?;
+                Ok::<_, AppError>((
+                    // A deck on stems reports the original but isn't playing it.
+                    d.stem_paths.is_none().then_some(current_path),
+                    d.song_id,
+                    d.queue_id,
+                    d.from_rotation,
+                    d.declared_duration_ms,
+                    d.position_ms,
+                ))
+            })?;
+
+        if current_path.as_ref() == Some(&new_path) {
+            return Ok(());
+        }
+
         let prepared = Deck::prepare_seek(
-            path,
+            new_path,
             song_id,
             queue_id,
             from_rotation,
             declared_duration_ms,
             position_ms,
-        )?;
+        )
+        .map_err(AppError::decode)?;
         self.send_cmd(EngineCmd::AttachPreparedTrack {
             deck,
             prepared,
@@ -440,44 +1131,51 @@ impl AudioEngine {
         })
     }
 
-    pub fn switch_deck_track_source(
+    /// Swap the deck over to the separated stems of its track, in sync with
+    /// the current position. `set_deck_stem_mix` then rides each stem. The
+    /// deck reports `original` as its file while on stems.
+    pub fn load_deck_stems(
         &mut self,
         deck: DeckId,
-        new_path: PathBuf,
-    ) -> Result<(), String> {
-        if !new_path.exists() {
-            return Err(format!("File not found: {}", new_path.display()));
+        original: PathBuf,
+        stems: StemPaths,
+    ) -> Result<(), AppError> {
+        if let Some(missing) = stems.iter().find(|p| !p.is_file()) {
+            return Err(AppError::new(
+                ErrorCode::FileNotFound,
+                format!("Stem file not found: {}", missing.display()),
+            ));
         }
-        if !new_path.is_file() {
-            return Err(format!("Path is not a file: {}", new_path.display()));
-        }
-
-        let (current_path, song_id, queue_id, from_rotation, declared_duration_ms, position_ms) = {
-            let rt = self.rt_state.lock().unwrap();
-            let d = rt.decks.get(&deck).ok_or("Unknown deck")?;
-            let current_path = d.file_path.clone().ok_or("No track loaded")?;
-            (
-                current_path,
+        let current = self.with_snapshot(|snap| {
+            let d = snap.deck(deck);
+            if d.file_path.is_none() {
+                return Err(no_track_loaded());
+            }
+            if d.stem_paths.as_ref() == Some(&stems) {
+                return Ok(None);
+            }
+            Ok(Some((
                 d.song_id,
                 d.queue_id,
                 d.from_rotation,
                 d.declared_duration_ms,
-                d.position_ms(),
-            )
-        };
-
-        if current_path == new_path {
+                d.position_ms,
+            )))
+        })?;
+        let Some((song_id, queue_id, from_rotation, declared_duration_ms, position_ms)) = current
+        else {
             return Ok(());
-        }
-
-        let prepared = Deck::prepare_seek(
-            new_path,
+        };
+        let prepared = Deck::prepare_stems(
+            original,
+            stems,
             song_id,
             queue_id,
             from_rotation,
             declared_duration_ms,
             position_ms,
-        )?;
+        )
+        .map_err(AppError::decode)?;
         self.send_cmd(EngineCmd::AttachPreparedTrack {
             deck,
             prepared,
@@ -485,42 +1183,64 @@ impl AudioEngine {
         })
     }
 
-    pub fn set_channel_gain(&mut self, deck: DeckId, gain: f32) -> Result<(), String> {
-        self.send_cmd(EngineCmd::SetGain { deck, gain })
+    pub fn set_deck_stem_mix(&mut self, deck: DeckId, mix: StemMix) -> Result<(), AppError> {
+        self.send_cmd(EngineCmd::SetDeckStemMix { deck, mix })
     }
 
-    pub fn set_deck_bass(&mut self, deck: DeckId, bass_db: f32) -> Result<(), String> {
-        self.send_cmd(EngineCmd::SetDeckBass {
-            deck,
-            bass_db: bass_db.clamp(-12.0, 12.0),
-        })
+    pub fn set_channel_gain(&mut self, deck: DeckId, gain: f32) -> Result<(), AppError> {
+        self.set_control(Control::Deck(deck, DeckControl::Gain), gain)
     }
 
-    pub fn set_deck_filter(&mut self, deck: DeckId, amount: f32) -> Result<(), String> {
-        self.send_cmd(EngineCmd::SetDeckFilter {
-            deck,
-            amount: amount.clamp(-1.0, 1.0),
-        })
+    /// Trim for the track just loaded on `deck` (send right after the load).
+    pub fn set_track_gain_db(&mut self, deck: DeckId, gain_db: f32) -> Result<(), AppError> {
+        self.send_cmd(EngineCmd::SetTrackGain { deck, gain_db })
     }
 
-    pub fn set_master_level(&mut self, level: f32) -> Result<(), String> {
-        self.send_cmd(EngineCmd::SetMasterLevel {
-            level: level.clamp(0.0, 1.0),
-        })
+    pub fn set_deck_bass(&mut self, deck: DeckId, bass_db: f32) -> Result<(), AppError> {
+        self.set_control(
+            Control::Deck(deck, DeckControl::Bass),
+            bass_db.clamp(-12.0, 12.0),
+        )
     }
 
-    pub fn set_deck_pitch(&mut self, deck: DeckId, pitch_pct: f32) -> Result<(), String> {
-        self.send_cmd(EngineCmd::SetDeckPitch {
-            deck,
-            pct: pitch_pct,
-        })
+    pub fn set_deck_filter(&mut self, deck: DeckId, amount: f32) -> Result<(), AppError> {
+        self.set_control(
+            Control::Deck(deck, DeckControl::Filter),
+            amount.clamp(-1.0, 1.0),
+        )
     }
 
-    pub fn set_deck_tempo(&mut self, deck: DeckId, tempo_pct: f32) -> Result<(), String> {
-        self.send_cmd(EngineCmd::SetDeckTempo {
-            deck,
-            pct: tempo_pct,
-        })
+    pub fn set_deck_eq_kill(
+        &mut self,
+        deck: DeckId,
+        band: EqBand,
+        killed: bool,
+    ) -> Result<(), AppError> {
+        self.send_cmd(EngineCmd::SetDeckEqKill { deck, band, killed })
+    }
+
+    pub fn set_master_level(&mut self, level: f32) -> Result<(), AppError> {
+        self.set_control(Control::MasterLevel, level.clamp(0.0, 1.0))
+    }
+
+    pub fn set_deck_pitch(&mut self, deck: DeckId, pitch_pct: f32) -> Result<(), AppError> {
+        self.set_control(Control::Deck(deck, DeckControl::Pitch), pitch_pct)
+    }
+
+    pub fn set_deck_tempo(&mut self, deck: DeckId, tempo_pct: f32) -> Result<(), AppError> {
+        self.set_control(Control::Deck(deck, DeckControl::Tempo), tempo_pct)
+    }
+
+    pub fn set_deck_keylock(&mut self, deck: DeckId, enabled: bool) -> Result<(), AppError> {
+        self.send_cmd(EngineCmd::SetDeckKeylock { deck, enabled })
+    }
+
+    pub fn set_deck_reverse(&mut self, deck: DeckId, enabled: bool) -> Result<(), AppError> {
+        self.send_cmd(EngineCmd::SetDeckReverse { deck, enabled })
+    }
+
+    pub fn set_deck_censor(&mut self, deck: DeckId, active: bool) -> Result<(), AppError> {
+        self.send_cmd(EngineCmd::SetDeckCensor { deck, active })
     }
 
     pub fn set_deck_loop(
@@ -528,7 +1248,7 @@ impl AudioEngine {
         deck: DeckId,
         start_ms: u64,
         end_ms: u64,
-    ) -> Result<(), String> {
+    ) -> Result<(), AppError> {
         self.send_cmd(EngineCmd::SetDeckLoop {
             deck,
             start_ms,
@@ -536,42 +1256,163 @@ impl AudioEngine {
         })
     }
 
-    pub fn clear_deck_loop(&mut self, deck: DeckId) -> Result<(), String> {
+    pub fn clear_deck_loop(&mut self, deck: DeckId) -> Result<(), AppError> {
         self.send_cmd(EngineCmd::ClearDeckLoop(deck))
     }
 
-    pub fn start_crossfade(&mut self, outgoing: DeckId, incoming: DeckId) -> Result<(), String> {
+    /// Halve or double the active loop around its start.
+    pub fn resize_deck_loop(&mut self, deck: DeckId, double: bool) -> Result<LoopRange, AppError> {
+        let range = self.with_snapshot(|snap| {
+            let d = snap.deck(deck);
+            let no_loop = || AppError::engine("No active loop");
+            let (start_ms, _) = d.loop_range_ms.ok_or_else(no_loop)?;
+            let frames = d.loop_frames.ok_or_else(no_loop)?;
+            let end_frame =
+                resized_loop_end(frames, d.sample_rate, double).map_err(AppError::invalid_input)?;
+            Ok::<_, AppError>(LoopRange {
+                start_ms,
+                end_ms: end_frame * 1000 / d.sample_rate.max(1) as u64,
+            })
+        })?;
+        self.send_cmd(EngineCmd::ResizeDeckLoop { deck, double })?;
+        Ok(range)
+    }
+
+    /// Leave a loop. Without slip, playback carries on from the current loop
+    /// position; with slip on it returns to the notional playhead.
+    pub fn exit_deck_loop(&mut self, deck: DeckId) -> Result<(), AppError> {
+        let (slip, position_ms) = self.with_snapshot(|snap| {
+            let d = snap.deck(deck);
+            (d.slip, d.position_ms)
+        });
+        self.clear_deck_loop(deck)?;
+        if slip {
+            self.slip_return(deck)
+        } else {
+            // Best effort: an empty deck has nothing to seek.
+            let _ = self.seek(deck, position_ms);
+            Ok(())
+        }
+    }
+
+    /// Turning slip off returns playback to where the track would have been
+    /// (ending any loop or reverse on the way).
+    pub fn set_deck_slip(&mut self, deck: DeckId, enabled: bool) -> Result<(), AppError> {
+        if !enabled {
+            self.send_cmd(EngineCmd::ClearDeckLoop(deck))?;
+            self.slip_return(deck)?;
+        }
+        self.send_cmd(EngineCmd::SetDeckSlip { deck, enabled })
+    }
+
+    fn slip_return(&mut self, deck: DeckId) -> Result<(), AppError> {
+        let target_ms = self.with_snapshot(|snap| {
+            let d = snap.deck(deck);
+            d.file_path.as_ref().and(d.slip_return_target_ms)
+        });
+        let Some(target_ms) = target_ms else {
+            return Ok(());
+        };
+        let prepared = self.prepare_deck_seek(deck, target_ms)?;
+        self.send_cmd(EngineCmd::SlipReturn { deck, prepared })
+    }
+
+    pub fn start_crossfade(&mut self, outgoing: DeckId, incoming: DeckId) -> Result<(), AppError> {
         self.send_cmd(EngineCmd::StartCrossfade { outgoing, incoming })
     }
 
-    pub fn set_crossfade_config(&mut self, config: CrossfadeConfig) -> Result<(), String> {
+    pub fn set_crossfade_config(&mut self, config: CrossfadeConfig) -> Result<(), AppError> {
         self.send_cmd(EngineCmd::SetCrossfadeConfig(config))
     }
 
-    pub fn set_manual_crossfade(&mut self, position: f32) -> Result<(), String> {
-        self.send_cmd(EngineCmd::SetManualCrossfade { position })
+    pub fn set_manual_crossfade(&mut self, position: f32) -> Result<(), AppError> {
+        self.set_control(Control::ManualCrossfade, position)
     }
 
     pub fn trigger_manual_fade(
         &mut self,
         direction: ManualFadeDirection,
         duration_ms: u32,
-    ) -> Result<(), String> {
+    ) -> Result<(), AppError> {
         self.send_cmd(EngineCmd::TriggerManualFade {
             direction,
             duration_ms,
         })
     }
 
+    /// Crossfade between any two playback decks over `duration_ms`,
+    /// overriding the configured fade times.
+    pub fn start_timed_crossfade(
+        &mut self,
+        outgoing: DeckId,
+        incoming: DeckId,
+        duration_ms: u32,
+    ) -> Result<(), AppError> {
+        self.start_profiled_crossfade(outgoing, incoming, duration_ms, None)
+    }
+
+    /// Timed crossfade shaped by `profile` (curves, levels, mode); the
+    /// station config when `None`. Crossfader assignment always comes from
+    /// the station config.
+    pub fn start_profiled_crossfade(
+        &mut self,
+        outgoing: DeckId,
+        incoming: DeckId,
+        duration_ms: u32,
+        profile: Option<CrossfadeConfig>,
+    ) -> Result<(), AppError> {
+        self.send_cmd(EngineCmd::StartTimedCrossfade {
+            outgoing,
+            incoming,
+            duration_ms,
+            profile: profile.map(Box::new),
+        })
+    }
+
+    /// Swing the manual crossfader fully to `deck`'s side. Thru decks leave
+    /// it where it is.
+    pub fn focus_crossfader(&mut self, deck: DeckId) -> Result<(), AppError> {
+        let side = self.get_crossfade_config().crossfader_assign.side(deck);
+        match side.position() {
+            Some(position) => self.set_manual_crossfade(position),
+            None => Ok(()),
+        }
+    }
+
+    /// Finish (`complete`) or abandon an in-flight crossfade immediately.
+    /// An abandoned incoming deck is paused where it is.
+    pub fn resolve_crossfade(&mut self, complete: bool) -> Result<(), AppError> {
+        self.send_cmd(EngineCmd::ResolveCrossfade { complete })
+    }
+
+    /// Fade a playing deck to silence, then stop it (with a completion record).
+    pub fn fade_out_deck(&mut self, deck: DeckId, duration_ms: u32) -> Result<(), AppError> {
+        self.send_cmd(EngineCmd::FadeOutDeck { deck, duration_ms })
+    }
+
+    /// Ramp the channel fader of `deck` to `gain` (0–1) over `duration_ms`.
+    pub fn ramp_channel_gain(
+        &mut self,
+        deck: DeckId,
+        gain: f32,
+        duration_ms: u32,
+    ) -> Result<(), AppError> {
+        self.send_cmd(EngineCmd::RampChannelGain {
+            deck,
+            gain: gain.clamp(0.0, 1.0),
+            duration_ms,
+        })
+    }
+
     pub fn set_channel_pipeline(
         &mut self,
         deck: DeckId,
         settings: PipelineSettings,
-    ) -> Result<(), String> {
+    ) -> Result<(), AppError> {
         self.send_cmd(EngineCmd::SetChannelPipeline { deck, settings })
     }
 
-    pub fn set_master_pipeline(&mut self, settings: PipelineSettings) -> Result<(), String> {
+    pub fn set_master_pipeline(&mut self, settings: PipelineSettings) -> Result<(), AppError> {
         self.send_cmd(EngineCmd::SetMasterPipeline { settings })
     }
 
@@ -579,20 +1420,16 @@ impl AudioEngine {
         &mut self,
         deck: DeckId,
         enabled: bool,
-    ) -> Result<(), String> {
+    ) -> Result<(), AppError> {
         self.send_cmd(EngineCmd::SetDeckCuePreview { deck, enabled })
     }
 
-    pub fn set_headphone_mix(&mut self, value: f32) -> Result<(), String> {
-        self.send_cmd(EngineCmd::SetHeadphoneMix {
-            value: value.clamp(-1.0, 1.0),
-        })
+    pub fn set_headphone_mix(&mut self, value: f32) -> Result<(), AppError> {
+        self.set_control(Control::HeadphoneMix, value.clamp(-1.0, 1.0))
     }
 
-    pub fn set_headphone_level(&mut self, value: f32) -> Result<(), String> {
-        self.send_cmd(EngineCmd::SetHeadphoneLevel {
-            value: value.clamp(0.0, 1.0),
-        })
+    pub fn set_headphone_level(&mut self, value: f32) -> Result<(), AppError> {
+        self.set_control(Control::HeadphoneLevel, value.clamp(0.0, 1.0))
     }
 
     pub fn set_monitor_routing_config(&mut self, config: MonitorRoutingConfig) {
@@ -607,8 +1444,40 @@ impl AudioEngine {
         let _ = self.send_cmd(EngineCmd::SetMonitorRoutingConfig(config));
     }
 
-    pub fn list_audio_output_devices() -> Result<Vec<device_manager::AudioOutputDevice>, String> {
-        device_manager::list_audio_output_devices()
+    // ── Cart wall ─────────────────────────────────────────────────────────
+
+    pub fn trigger_cart(&mut self, trigger: CartTrigger) -> Result<(), AppError> {
+        self.send_cmd(EngineCmd::TriggerCart(trigger))
+    }
+
+    pub fn stop_cart(&mut self, key: CartKey) -> Result<(), AppError> {
+        self.send_cmd(EngineCmd::StopCart(key))
+    }
+
+    pub fn stop_all_carts(&mut self) -> Result<(), AppError> {
+        self.send_cmd(EngineCmd::StopAllCarts)
+    }
+
+    pub fn cart_states(&self) -> Vec<CartVoiceState> {
+        self.with_snapshot(|snap| snap.carts.clone())
+    }
+
+    // ── Sound FX voices ───────────────────────────────────────────────────
+
+    pub fn play_sfx(&mut self, trigger: SfxTrigger) -> Result<(), AppError> {
+        self.send_cmd(EngineCmd::PlaySfx(trigger))
+    }
+
+    pub fn stop_sfx(&mut self, which: SfxStop, fade_ms: u32) -> Result<(), AppError> {
+        self.send_cmd(EngineCmd::StopSfx { which, fade_ms })
+    }
+
+    pub fn sfx_states(&self) -> Vec<SfxVoiceState> {
+        self.with_snapshot(|snap| snap.sfx.clone())
+    }
+
+    pub fn list_audio_output_devices() -> Result<Vec<device_manager::AudioOutputDevice>, AppError> {
+        device_manager::list_audio_output_devices().map_err(AppError::device)
     }
 
     pub fn get_audio_output_status(&self) -> AudioOutputStatus {
@@ -618,18 +1487,19 @@ impl AudioEngine {
     pub fn apply_audio_output_routing(
         &mut self,
         mut config: AudioOutputRoutingConfig,
-    ) -> Result<AudioOutputStatus, String> {
+    ) -> Result<AudioOutputStatus, AppError> {
         let original = config.clone();
         let selected = device_manager::select_output_stream(&config);
         let (selection, warning, had_explicit_selection) = match selected {
             Ok(ok) => ok,
             Err(err) => {
                 if !config.auto_fallback {
-                    return Err(err);
+                    return Err(AppError::device(err));
                 }
                 config.mode = AudioOutputMode::SingleDeviceStereo;
                 config.master_device_id = None;
-                let (fallback_sel, warn, _) = device_manager::select_output_stream(&config)?;
+                let (fallback_sel, warn, _) =
+                    device_manager::select_output_stream(&config).map_err(AppError::device)?;
                 let fallback_warning = Some(format!("{err}; fallback engaged"));
                 (
                     fallback_sel,
@@ -639,49 +1509,62 @@ impl AudioEngine {
             }
         };
 
-        let current_channels = {
-            let rt = self.rt_state.lock().unwrap();
-            rt.output_channels
-        };
         let should_rebuild = self.output_status.master_device_id.as_deref()
             != Some(selection.device_id.as_str())
             || self.sample_rate != selection.config.sample_rate.0
-            || current_channels != selection.config.channels as usize;
+            || self.output_channels != selection.config.channels.max(2) as usize;
 
         if should_rebuild {
             self.rebuild_stream(selection.device, &selection.config)?;
         }
 
-        {
-            let mut rt = self.rt_state.lock().unwrap();
-            rt.sample_rate = selection.config.sample_rate.0;
-            rt.output_channels = selection.config.channels as usize;
-            rt.cue_available = selection.cue_available;
-            let wants_split = matches!(
-                config.mode,
-                AudioOutputMode::SingleDeviceFourChannel | AudioOutputMode::DualDeviceSplit
-            );
-            rt.cue_split_active = wants_split && rt.cue_available;
-            if !rt.cue_available {
-                rt.cue_preview_enabled.insert(DeckId::DeckA, false);
-                rt.cue_preview_enabled.insert(DeckId::DeckB, false);
-            }
-        }
+        let cue_error = self
+            .apply_cue_output(
+                &config,
+                &selection.device_id,
+                selection.config.sample_rate.0,
+            )
+            .err()
+            .map(|e| e.message);
+        let cue_external = self.cue_output.is_some();
+        let cue_available = selection.cue_available || cue_external;
+
+        let wants_split = matches!(
+            config.mode,
+            AudioOutputMode::SingleDeviceFourChannel | AudioOutputMode::DualDeviceSplit
+        );
+        self.send_cmd(EngineCmd::SetCueRouting {
+            available: cue_available,
+            split_active: wants_split && cue_available,
+        })?;
 
         self.sample_rate = selection.config.sample_rate.0;
         self.routing_config = config.clone();
+        let (active_mode, cue_device_id, cue_device_name) = match &self.cue_output {
+            Some(cue) => (
+                AudioOutputMode::DualDeviceSplit,
+                Some(cue.device_id.clone()),
+                Some(cue.device_name.clone()),
+            ),
+            None if selection.cue_available => (
+                selection.active_mode,
+                Some(selection.device_id.clone()),
+                Some(selection.device_name.clone()),
+            ),
+            None => (selection.active_mode, None, None),
+        };
+        let warning = match (warning, cue_error) {
+            (Some(w), Some(c)) => Some(format!("{w}; {c}")),
+            (w, c) => w.or(c),
+        };
         let status = AudioOutputStatus {
-            active_mode: selection.active_mode,
+            active_mode,
             master_device_id: Some(selection.device_id.clone()),
             master_device_name: Some(selection.device_name.clone()),
-            cue_device_id: if selection.cue_available {
-                Some(selection.device_id.clone())
-            } else {
-                None
-            },
-            cue_available: selection.cue_available,
-            fallback_active: warning.is_some()
-                || (had_explicit_selection && !selection.cue_available),
+            cue_device_id,
+            cue_device_name,
+            cue_available,
+            fallback_active: warning.is_some() || (had_explicit_selection && !cue_available),
             last_error: warning,
         };
         self.output_status = status.clone();
@@ -697,7 +1580,25 @@ impl AudioEngine {
         };
         let devices = device_manager::list_audio_output_devices().ok()?;
         if devices.iter().any(|d| d.id == active_id) {
-            return None;
+            let cue_lost = self
+                .cue_output
+                .as_ref()
+                .is_some_and(|cue| !devices.iter().any(|d| d.id == cue.device_id));
+            if !cue_lost {
+                return None;
+            }
+            // Master is fine but the headphone device vanished: drop back to
+            // master-only monitoring without touching the main stream.
+            self.close_cue_output();
+            let mut status = self.output_status.clone();
+            status.active_mode = AudioOutputMode::SingleDeviceStereo;
+            status.cue_device_id = None;
+            status.cue_device_name = None;
+            status.cue_available = false;
+            status.fallback_active = true;
+            status.last_error = Some("Cue output device disconnected".to_string());
+            self.output_status = status.clone();
+            return Some(status);
         }
         let mut fallback_cfg = self.routing_config.clone();
         fallback_cfg.mode = AudioOutputMode::SingleDeviceStereo;
@@ -716,22 +1617,18 @@ impl AudioEngine {
     }
 
     pub fn get_crossfade_config(&self) -> CrossfadeConfig {
-        self.rt_state.lock().unwrap().crossfade_config.clone()
+        self.with_snapshot(|snap| snap.crossfade_config.clone())
     }
 
     pub fn get_deck_state(&self, deck: DeckId) -> Option<DeckStateEvent> {
-        let rt = self.rt_state.lock().unwrap();
-        rt.decks.get(&deck).map(|d| {
-            let loop_range = d.loop_range_ms();
-            let (bass_db, filter_amount) = (
-                rt.deck_bass_db.get(&deck).copied().unwrap_or(0.0),
-                rt.deck_filter_amount.get(&deck).copied().unwrap_or(0.0),
-            );
-            DeckStateEvent {
+        self.with_snapshot(|snap| {
+            let d = snap.deck(deck);
+            let loop_range = d.loop_range_ms;
+            Some(DeckStateEvent {
                 deck: deck.to_string(),
                 state: format!("{:?}", d.state).to_lowercase(),
-                position_ms: d.position_ms(),
-                duration_ms: d.duration_ms(),
+                position_ms: d.position_ms,
+                duration_ms: d.duration_ms,
                 song_id: d.song_id,
                 file_path: d
                     .file_path
@@ -740,46 +1637,198 @@ impl AudioEngine {
                 playback_rate: d.playback_rate,
                 pitch_pct: d.pitch_pct,
                 tempo_pct: d.tempo_pct,
+                keylock: d.keylock,
+                keylock_latency_ms: d.keylock_latency_ms,
+                reverse: d.direction == Direction::Reverse,
+                censoring: d.direction == Direction::Censor,
+                slip: d.slip,
+                slip_position_ms: d.slip_position_ms,
                 channel_gain: d.channel_gain,
-                bass_db,
-                filter_amount,
-                master_level: rt.master_level,
-                decoder_buffer_ms: d.decoder_buffered_ms(),
+                track_gain_db: d.track_gain_db,
+                effective_gain_db: if d.channel_gain > 0.0 {
+                    d.track_gain_db + 20.0 * d.channel_gain.log10()
+                } else {
+                    -96.0
+                },
+                bass_db: d.bass_db,
+                filter_amount: d.filter_amount,
+                eq_kill: d.eq_kill,
+                master_level: snap.master_level,
+                decoder_buffer_ms: d.decoder_buffer_ms,
                 rms_db_pre_fader: d.rms_db_pre_fader,
-                cue_preview_enabled: rt.cue_preview_enabled.get(&deck).copied().unwrap_or(false),
+                cue_preview_enabled: d.cue_preview_enabled,
                 loop_enabled: loop_range.is_some(),
                 loop_start_ms: loop_range.map(|(start, _)| start),
                 loop_end_ms: loop_range.map(|(_, end)| end),
-            }
+                crossfader_side: snap.crossfade_config.crossfader_assign.side(deck),
+                remote_stream: d.remote.as_ref().map(|r| r.info()),
+                stems: d.stem_paths.as_ref().map(|_| d.stem_mix),
+            })
         })
     }
 
+    /// `(outgoing, incoming)` while a crossfade is running.
+    pub fn crossfade_decks(&self) -> Option<(DeckId, DeckId)> {
+        self.with_snapshot(|snap| snap.crossfade_decks)
+    }
+
     pub fn get_crossfade_progress_event(&self) -> Option<CrossfadeProgressEvent> {
-        let rt = self.rt_state.lock().unwrap();
-        let progress = rt.crossfade.progress()?;
-        let outgoing = rt.crossfade.outgoing()?;
-        let incoming = rt.crossfade.incoming()?;
-        Some(CrossfadeProgressEvent {
-            progress,
-            outgoing_deck: outgoing.to_string(),
-            incoming_deck: incoming.to_string(),
+        self.with_snapshot(|snap| {
+            let progress = snap.crossfade_progress?;
+            let (outgoing, incoming) = snap.crossfade_decks?;
+            Some(CrossfadeProgressEvent {
+                progress,
+                outgoing_deck: outgoing.to_string(),
+                incoming_deck: incoming.to_string(),
+            })
         })
     }
 
     pub fn get_manual_crossfade_pos(&self) -> f32 {
-        self.rt_state.lock().unwrap().manual_crossfade_pos
+        self.with_snapshot(|snap| snap.manual_crossfade_pos)
     }
 
     pub fn get_master_level(&self) -> f32 {
-        self.rt_state.lock().unwrap().master_level
+        self.with_snapshot(|snap| snap.master_level)
+    }
+
+    pub fn output_sample_rate(&self) -> u32 {
+        self.sample_rate
     }
 
-    pub fn set_local_monitor_muted(&mut self, muted: bool) -> Result<(), String> {
+    // ── Live mic input ────────────────────────────────────────────────────
+
+    /// Create a fresh live-input ring and return its producer for the mic stream.
+    /// Samples must be interleaved stereo at `output_sample_rate()`.
+    pub fn attach_live_input(&mut self) -> ringbuf::HeapProd<f32> {
+        let len = (self.sample_rate as usize * 2 * Self::LIVE_INPUT_RING_MS / 1000).max(1024);
+        let (prod, cons) = HeapRb::<f32>::new(len).split();
+        let _ = self.send_cmd(EngineCmd::SetLiveInput(Some(cons)));
+        prod
+    }
+
+    pub fn detach_live_input(&mut self) {
+        let _ = self.send_cmd(EngineCmd::SetLiveInput(None));
+    }
+
+    /// Create a remote-DJ input ring feeding the Aux 2 channel and return its
+    /// producer. Samples must be interleaved stereo at `output_sample_rate()`.
+    pub fn attach_remote_input(&mut self) -> ringbuf::HeapProd<f32> {
+        let len = (self.sample_rate as usize * 2 * Self::LIVE_INPUT_RING_MS / 1000).max(1024);
+        let (prod, cons) = HeapRb::<f32>::new(len).split();
+        let _ = self.send_cmd(EngineCmd::SetRemoteInput(Some(cons)));
+        prod
+    }
+
+    pub fn detach_remote_input(&mut self) {
+        let _ = self.send_cmd(EngineCmd::SetRemoteInput(None));
+    }
+
+    /// Tap the program bus without Aux 2 (the remote DJ) so it can be sent
+    /// back as their monitor. Interleaved stereo at `output_sample_rate()`.
+    pub fn attach_mix_minus(&mut self) -> ringbuf::HeapCons<f32> {
+        let len = (self.sample_rate as usize * 2 * Self::LIVE_INPUT_RING_MS / 1000).max(1024);
+        let (prod, cons) = HeapRb::<f32>::new(len).split();
+        let _ = self.send_cmd(EngineCmd::SetMixMinus(Some(prod)));
+        cons
+    }
+
+    pub fn detach_mix_minus(&mut self) {
+        let _ = self.send_cmd(EngineCmd::SetMixMinus(None));
+    }
+
+    // ── Spectrum analyzer ─────────────────────────────────────────────────
+
+    /// Tap `sources` for the spectrum analyzer, replacing any earlier taps.
+    /// Each ring holds mono samples at `output_sample_rate()`.
+    pub fn attach_spectrum(
+        &mut self,
+        sources: &[SpectrumSource],
+    ) -> Result<Vec<(SpectrumSource, ringbuf::HeapCons<f32>)>, AppError> {
+        let len = (self.sample_rate as usize).max(16_384);
+        let (feeds, taps) = sources
+            .iter()
+            .map(|&source| {
+                let (prod, cons) = HeapRb::<f32>::new(len).split();
+                ((source, prod), (source, cons))
+            })
+            .unzip();
+        self.send_cmd(EngineCmd::SetSpectrumTap(Some(SpectrumTap::new(feeds))))?;
+        Ok(taps)
+    }
+
+    pub fn detach_spectrum(&mut self) {
+        let _ = self.send_cmd(EngineCmd::SetSpectrumTap(None));
+    }
+
+    // ── Loudness meter ────────────────────────────────────────────────────
+
+    /// Tap the program bus (after master processing) for loudness metering.
+    /// Interleaved stereo at `output_sample_rate()`; replaces any earlier tap.
+    pub fn attach_loudness_tap(&mut self) -> ringbuf::HeapCons<f32> {
+        let len = (self.sample_rate as usize * 2 * Self::LOUDNESS_RING_MS / 1000).max(16_384);
+        let (prod, cons) = HeapRb::<f32>::new(len).split();
+        let _ = self.send_cmd(EngineCmd::SetLoudnessTap(Some(prod)));
+        cons
+    }
+
+    // ── Multitrack recording ──────────────────────────────────────────────
+
+    /// Tap `buses` for a multitrack take, replacing any earlier taps. Returns
+    /// each bus's ring (interleaved stereo at `output_sample_rate()`) and its
+    /// dropped-frame counter, in the order given.
+    pub fn attach_multitrack(&mut self, buses: &[RecordBus]) -> Result<Vec<BusTap>, AppError> {
+        let len = (self.sample_rate as usize * 2 * Self::MULTITRACK_RING_MS / 1000).max(16_384);
+        let (feeds, taps) = buses
+            .iter()
+            .map(|&bus| {
+                let (prod, cons) = HeapRb::<f32>::new(len).split();
+                let dropped = Arc::new(AtomicU64::new(0));
+                ((bus, prod, dropped.clone()), (cons, dropped))
+            })
+            .unzip();
+        self.send_cmd(EngineCmd::SetMultitrackTap(Some(MultitrackTap::new(feeds))))?;
+        Ok(taps)
+    }
+
+    pub fn detach_multitrack(&mut self) {
+        let _ = self.send_cmd(EngineCmd::SetMultitrackTap(None));
+    }
+
+    /// Mic on-air state (PTT held or latched open); drives deck ducking.
+    pub fn set_mic_open(&mut self, open: bool) -> Result<(), AppError> {
+        self.send_cmd(EngineCmd::SetMicOpen { open })
+    }
+
+    pub fn set_duck_config(&mut self, config: DuckConfig) -> Result<(), AppError> {
+        self.send_cmd(EngineCmd::SetDuckConfig(config))
+    }
+
+    pub fn get_duck_config(&self) -> DuckConfig {
+        self.with_snapshot(|snap| snap.duck_config.clone())
+    }
+
+    pub fn get_duck_state(&self) -> DuckStateEvent {
+        self.with_snapshot(|snap| {
+            let gain = snap.duck_gain;
+            DuckStateEvent {
+                mic_open: snap.mic_open,
+                ducking: gain < 0.999,
+                gain_db: if gain > 0.0 {
+                    20.0 * gain.log10()
+                } else {
+                    -96.0
+                },
+            }
+        })
+    }
+
+    pub fn set_local_monitor_muted(&mut self, muted: bool) -> Result<(), AppError> {
         self.send_cmd(EngineCmd::SetLocalMonitorMuted { muted })
     }
 
     pub fn get_local_monitor_muted(&self) -> bool {
-        self.rt_state.lock().unwrap().local_monitor_muted
+        self.with_snapshot(|snap| snap.local_monitor_muted)
     }
 
     pub fn get_output_sample_rate(&self) -> u32 {
@@ -787,74 +1836,40 @@ impl AudioEngine {
     }
 
     pub fn get_headphone_mix(&self) -> f32 {
-        self.rt_state.lock().unwrap().headphone_mix
+        self.with_snapshot(|snap| snap.headphone_mix)
     }
 
     pub fn get_headphone_level(&self) -> f32 {
-        self.rt_state.lock().unwrap().cue_level
+        self.with_snapshot(|snap| snap.cue_level)
     }
 
     pub fn take_track_completions(&self) -> Vec<TrackCompletionEvent> {
-        let mut rt = self.rt_state.lock().unwrap();
-        let mut out = Vec::new();
-        for id in [
-            DeckId::DeckA,
-            DeckId::DeckB,
-            DeckId::SoundFx,
-            DeckId::Aux1,
-            DeckId::Aux2,
-            DeckId::VoiceFx,
-        ] {
-            if let Some(deck) = rt.decks.get_mut(&id) {
-                if let Some(TrackCompletion {
+        use ringbuf::traits::Consumer as _;
+        let mut view = self.view.borrow_mut();
+        std::iter::from_fn(|| view.completions.try_pop())
+            .map(|(id, completion)| {
+                let TrackCompletion {
                     song_id,
                     queue_id,
                     from_rotation,
-                }) = deck.take_completion()
-                {
-                    out.push(TrackCompletionEvent {
-                        deck: id.to_string(),
-                        song_id,
-                        queue_id,
-                        from_rotation,
-                    });
+                    played_ms,
+                    duration_ms,
+                    reason,
+                } = completion;
+                TrackCompletionEvent {
+                    deck: id.to_string(),
+                    song_id,
+                    queue_id,
+                    from_rotation,
+                    played_ms,
+                    duration_ms,
+                    reason,
                 }
-            }
-        }
-        out
+            })
+            .collect()
     }
 
     pub fn get_vu_readings(&self) -> Vec<VuEvent> {
-        let rt = self.rt_state.lock().unwrap();
-        let mut events: Vec<VuEvent> = [
-            DeckId::DeckA,
-            DeckId::DeckB,
-            DeckId::SoundFx,
-            DeckId::Aux1,
-            DeckId::Aux2,
-            DeckId::VoiceFx,
-        ]
-        .iter()
-        .map(|&id| {
-            let ch = rt.mixer.channel(id);
-            VuEvent {
-                channel: id.to_string(),
-                left_db: ch.vu_left_db,
-                right_db: ch.vu_right_db,
-            }
-        })
-        .collect();
-
-        let mut peak_l = 0.0_f32;
-        let mut peak_r = 0.0_f32;
-        for frame in rt.buf_master.chunks_exact(2) {
-            peak_l = peak_l.max(frame[0].abs());
-            peak_r = peak_r.max(frame[1].abs());
-        }
-        if rt.buf_master.len() % 2 == 1 {
-            peak_l = peak_l.max(rt.buf_master[rt.buf_master.len() - 1].abs());
-        }
-
         let to_db = |linear: f32| {
             if linear < 1e-10 {
                 -96.0
@@ -862,57 +1877,212 @@ impl AudioEngine {
                 20.0 * linear.log10()
             }
         };
-
-        events.push(VuEvent {
-            channel: "master".to_string(),
-            left_db: to_db(peak_l),
-            right_db: to_db(peak_r),
-        });
-
-        events
+        self.with_snapshot(|snap| {
+            let mut events: Vec<VuEvent> = DeckId::ALL
+                .iter()
+                .map(|&id| {
+                    let (left_db, right_db) = snap.deck(id).vu_db;
+                    VuEvent {
+                        channel: id.to_string(),
+                        left_db,
+                        right_db,
+                    }
+                })
+                .collect();
+            let (peak_l, peak_r) = snap.master_peak;
+            events.push(VuEvent {
+                channel: "master".to_string(),
+                left_db: to_db(peak_l),
+                right_db: to_db(peak_r),
+            });
+            events
+        })
     }
 
     // ── Private helpers ───────────────────────────────────────────────────
 
-    fn send_cmd(&mut self, cmd: EngineCmd) -> Result<(), String> {
+    /// Run `f` on the latest snapshot published by the callback.
+    fn with_snapshot<R>(&self, f: impl FnOnce(&EngineSnapshot) -> R) -> R {
+        f(self.view.borrow_mut().snapshot.read())
+    }
+
+    /// Queue `cmd` for the RT thread. When the ring is full it waits in the
+    /// backlog (in order, after anything already waiting) and is retried on
+    /// the next send or `flush_commands`. A full backlog refuses everything
+    /// but critical commands.
+    fn send_cmd(&mut self, cmd: EngineCmd) -> Result<(), AppError> {
         use ringbuf::traits::Producer as _;
-        self.cmd_tx
-            .try_push(cmd)
-            .map_err(|_| "Command queue full".to_string())
+        self.flush_commands();
+        if self.cmd_backlog.is_empty() {
+            let Err(cmd) = self.cmd_tx.try_push(cmd) else {
+                return Ok(());
+            };
+            log::warn!("Engine command ring full; holding commands back");
+            self.cmd_backlog.push_back(cmd);
+            return Ok(());
+        }
+        if let Some(older) = self.cmd_backlog.iter_mut().find(|c| cmd.supersedes(c)) {
+            *older = cmd;
+            return Ok(());
+        }
+        if self.cmd_backlog.len() >= Self::MAX_CMD_BACKLOG && !cmd.is_critical() {
+            return Err(AppError::engine_queue_full());
+        }
+        self.cmd_backlog.push_back(cmd);
+        Ok(())
     }
 
-    fn rebuild_stream(&mut self, device: Device, config: &StreamConfig) -> Result<(), String> {
-        let cmd_rb = HeapRb::<EngineCmd>::new(Self::CMD_RING_SIZE);
-        let (cmd_prod, cmd_cons) = cmd_rb.split();
-        self.cmd_tx = cmd_prod;
+    /// Move held-back commands into the ring as it drains, and free what the
+    /// callback has retired. Called on every send and from the engine
+    /// polling loop.
+    pub fn flush_commands(&mut self) {
+        use ringbuf::traits::{Consumer as _, Producer as _};
+        self.view.borrow_mut().retired.clear();
+        while let Some(cmd) = self.cmd_backlog.pop_front() {
+            if let Err(cmd) = self.cmd_tx.try_push(cmd) {
+                self.cmd_backlog.push_front(cmd);
+                return;
+            }
+        }
+    }
+
+    fn set_control(&self, control: Control, value: f32) -> Result<(), AppError> {
+        self.view.borrow().controls.set(control, value);
+        Ok(())
+    }
 
-        let rt_arc_cb = Arc::clone(&self.rt_state);
-        let stream = Self::build_stream(&device, config, rt_arc_cb, cmd_cons)?;
+    fn rebuild_stream(&mut self, device: Device, config: &StreamConfig) -> Result<(), AppError> {
+        // Dropping the old stream parks its state; no callback can run while
+        // it is adjusted for the new device.
+        self._stream = None;
+        let mut owner = self
+            .rt_park
+            .lock()
+            .unwrap()
+            .take()
+            .ok_or_else(|| AppError::engine("Audio engine state unavailable"))?;
+        owner
+            .rt
+            .set_output_format(config.sample_rate.0, config.channels as usize);
+        self.output_channels = owner.rt.output_channels;
+
+        let stream = Self::build_stream(&device, config, owner, Arc::clone(&self.rt_park))?;
         stream
             .play()
-            .map_err(|e| format!("Stream play error: {e}"))?;
+            .map_err(|e| AppError::device(format!("Stream play error: {e}")))?;
         self._stream = Some(stream);
         Ok(())
     }
 
+    /// Open, keep or close the secondary cue stream to match `config`.
+    /// Errors leave the cue stream closed; the master stream is unaffected.
+    fn apply_cue_output(
+        &mut self,
+        config: &AudioOutputRoutingConfig,
+        master_device_id: &str,
+        sample_rate: u32,
+    ) -> Result<(), AppError> {
+        let wanted = match (&config.mode, config.cue_device_id.as_deref()) {
+            (AudioOutputMode::DualDeviceSplit, Some(id))
+                if !id.trim().is_empty() && id != master_device_id =>
+            {
+                Some(id.to_string())
+            }
+            _ => None,
+        };
+        let Some(cue_id) = wanted else {
+            self.close_cue_output();
+            return Ok(());
+        };
+        if self
+            .cue_output
+            .as_ref()
+            .is_some_and(|c| c.device_id == cue_id && c.sample_rate == sample_rate)
+        {
+            return Ok(());
+        }
+        self.close_cue_output();
+
+        let selection = device_manager::select_cue_output_stream(&cue_id, sample_rate)
+            .map_err(AppError::device)?;
+        let ring_len = (sample_rate as usize * 2 * Self::CUE_RING_MS / 1000).max(1024);
+        let (cue_prod, cue_cons) = HeapRb::<f32>::new(ring_len).split();
+        let stream = Self::build_cue_stream(&selection.device, &selection.config, cue_cons)?;
+        stream
+            .play()
+            .map_err(|e| AppError::device(format!("Cue stream play error: {e}")))?;
+
+        self.send_cmd(EngineCmd::SetCueOutput(Some(cue_prod)))?;
+        self._cue_stream = Some(stream);
+        log::info!(
+            "Cue output: {} | sample rate: {} | channels: {}",
+            selection.device_name,
+            sample_rate,
+            selection.config.channels
+        );
+        self.cue_output = Some(CueOutputInfo {
+            device_id: selection.device_id,
+            device_name: selection.device_name,
+            sample_rate,
+        });
+        Ok(())
+    }
+
+    fn close_cue_output(&mut self) {
+        let _ = self.send_cmd(EngineCmd::SetCueOutput(None));
+        self._cue_stream = None;
+        self.cue_output = None;
+    }
+
+    fn build_cue_stream(
+        device: &Device,
+        config: &StreamConfig,
+        mut cue_cons: ringbuf::HeapCons<f32>,
+    ) -> Result<Stream, AppError> {
+        let channels = config.channels as usize;
+        let err_fn = |e| log::error!("CPAL cue stream error: {e}");
+
+        let stream = device
+            .build_output_stream(
+                config,
+                move |output: &mut [f32], _info: &cpal::OutputCallbackInfo| {
+                    cue_callback(output, channels, &mut cue_cons);
+                },
+                err_fn,
+                None,
+            )
+            .map_err(|e| AppError::device(format!("Build cue stream error: {e}")))?;
+
+        Ok(stream)
+    }
+
+    /// Open the output stream; its callback owns `owner` until the stream is
+    /// dropped, then parks it in `park`.
     fn build_stream(
         device: &Device,
         config: &StreamConfig,
-        rt_arc: Arc<Mutex<RtState>>,
-        mut cmd_cons: ringbuf::HeapCons<EngineCmd>,
-    ) -> Result<Stream, String> {
+        owner: RtOwner,
+        park: RtPark,
+    ) -> Result<Stream, AppError> {
         let err_fn = |e| log::error!("CPAL stream error: {e}");
+        let mut handle = RtHandle {
+            owner: Some(owner),
+            park,
+        };
 
         let stream = device
             .build_output_stream(
                 config,
                 move |output: &mut [f32], _info: &cpal::OutputCallbackInfo| {
-                    audio_callback(output, &rt_arc, &mut cmd_cons);
+                    // Only empty while being dropped.
+                    if let Some(owner) = handle.owner.as_mut() {
+                        audio_callback(output, &mut owner.rt, &mut owner.cmds);
+                    }
                 },
                 err_fn,
                 None,
             )
-            .map_err(|e| format!("Build stream error: {e}"))?;
+            .map_err(|e| AppError::device(format!("Build stream error: {e}")))?;
 
         Ok(stream)
     }
@@ -930,25 +2100,23 @@ unsafe impl Send for AudioEngine {}
 //
 // This function runs on the CPAL real-time thread.
 // Rules: no allocations (except first call to resize scratch bufs), no locks
-// that could block, no I/O.
+// that could block, no I/O. The callback owns `rt` outright, so nothing on
+// the control side can hold it up.
 fn audio_callback(
     output: &mut [f32],
-    rt_arc: &Arc<Mutex<RtState>>,
+    rt: &mut RtState,
     cmd_cons: &mut ringbuf::HeapCons<EngineCmd>,
 ) {
-    // Try to acquire the lock. If it's held by the main thread, output silence
-    // to avoid glitching rather than blocking the RT thread.
-    let mut rt = match rt_arc.try_lock() {
-        Ok(g) => g,
-        Err(_) => {
-            output.fill(0.0);
-            return;
-        }
-    };
-
     // Process pending commands (non-blocking)
-    process_commands(&mut rt, cmd_cons);
+    process_commands(rt, cmd_cons);
+    render_block(output, rt);
+    rt.publish_completions();
+    rt.publish_retired();
+    rt.publish_snapshot();
+}
 
+/// Render one output block from the decks, voices and live inputs.
+fn render_block(output: &mut [f32], rt: &mut RtState) {
     let out_channels = rt.output_channels.max(2);
     if output.is_empty() || output.len() % out_channels != 0 {
         output.fill(0.0);
@@ -957,25 +2125,14 @@ fn audio_callback(
     let render_frames = output.len() / out_channels;
     let stereo_len = render_frames * 2;
 
-    // Resize scratch buffers if needed (only happens on first call or config change)
-    if rt.buf_deck_a.len() != stereo_len {
-        rt.buf_deck_a.resize(stereo_len, 0.0);
-        rt.buf_deck_b.resize(stereo_len, 0.0);
-        rt.buf_deck_a_cue_tap.resize(stereo_len, 0.0);
-        rt.buf_deck_b_cue_tap.resize(stereo_len, 0.0);
-        rt.buf_sound_fx.resize(stereo_len, 0.0);
-        rt.buf_aux1.resize(stereo_len, 0.0);
-        rt.buf_aux2.resize(stereo_len, 0.0);
-        rt.buf_voice_fx.resize(stereo_len, 0.0);
-        rt.buf_silence.resize(stereo_len, 0.0);
-        rt.buf_master.resize(stereo_len, 0.0);
-        rt.buf_cue.resize(stereo_len, 0.0);
-    }
+    rt.resize_buffers(stereo_len);
     rt.buf_silence.fill(0.0);
     rt.buf_master.fill(0.0);
     rt.buf_cue.fill(0.0);
     rt.buf_deck_a_cue_tap.fill(0.0);
     rt.buf_deck_b_cue_tap.fill(0.0);
+    rt.buf_aux1_cue_tap.fill(0.0);
+    rt.buf_aux2_cue_tap.fill(0.0);
 
     // ── Crossfade gain computation ──────────────────────────────────────
     let frames = render_frames as u64;
@@ -985,8 +2142,25 @@ fn audio_callback(
     let crossfade_active = rt.crossfade.is_fading();
     let (xf_gain_out, xf_gain_in, mut xf_complete) = rt.crossfade.advance(frames);
     let manual_pos = rt.manual_crossfade_pos.clamp(-1.0, 1.0);
-    let manual_gain_a = ((1.0 - manual_pos) * 0.5).clamp(0.0, 1.0);
-    let manual_gain_b = ((1.0 + manual_pos) * 0.5).clamp(0.0, 1.0);
+    let assign = rt.crossfade_config.crossfader_assign;
+
+    // ── Channel gain ramps ───────────────────────────────────────────────
+    {
+        let decks = &mut rt.decks;
+        rt.deck_gain_ramps.retain(|id, (target, step)| {
+            let Some(deck) = decks.get_mut(id) else {
+                return false;
+            };
+            let delta = *step * frames as f32;
+            let gap = *target - deck.channel_gain;
+            if gap.abs() <= delta {
+                deck.channel_gain = *target;
+                return false;
+            }
+            deck.channel_gain += delta.copysign(gap);
+            true
+        });
+    }
 
     // ── Fill per-deck buffers ────────────────────────────────────────────
     // Cache device_sr before the loop — borrowing rt.sample_rate while
@@ -1005,35 +2179,37 @@ fn audio_callback(
             Some(&mut rt.buf_deck_b_cue_tap as *mut Vec<f32>),
         ),
         (DeckId::SoundFx, &mut rt.buf_sound_fx as *mut Vec<f32>, None),
-        (DeckId::Aux1, &mut rt.buf_aux1 as *mut Vec<f32>, None),
-        (DeckId::Aux2, &mut rt.buf_aux2 as *mut Vec<f32>, None),
+        (
+            DeckId::Aux1,
+            &mut rt.buf_aux1 as *mut Vec<f32>,
+            Some(&mut rt.buf_aux1_cue_tap as *mut Vec<f32>),
+        ),
+        (
+            DeckId::Aux2,
+            &mut rt.buf_aux2 as *mut Vec<f32>,
+            Some(&mut rt.buf_aux2_cue_tap as *mut Vec<f32>),
+        ),
         (DeckId::VoiceFx, &mut rt.buf_voice_fx as *mut Vec<f32>, None),
     ] {
         let buf = unsafe { &mut *buf };
         let cue_tap = cue_tap.map(|ptr| unsafe { &mut *ptr });
+        let side = assign.side(id);
         if let Some(deck) = rt.decks.get_mut(&id) {
             if crossfade_active {
-                // Active auto/timed fade owns Deck A/B crossfade gain.
+                // Active auto/timed fade owns the gain of every deck on a
+                // crossfader side; thru decks keep playing underneath.
                 if Some(id) == outgoing_id {
                     deck.xfade_gain = xf_gain_out;
                 } else if Some(id) == incoming_id {
                     deck.xfade_gain = xf_gain_in;
-                } else if matches!(id, DeckId::DeckA | DeckId::DeckB) {
+                } else if side != CrossfaderSide::Thru {
                     deck.xfade_gain = 0.0;
                 } else {
                     deck.xfade_gain = 1.0;
                 }
             } else {
                 // Manual crossfader when no active auto/timed fade.
-                match id {
-                    DeckId::DeckA => {
-                        deck.xfade_gain = manual_gain_a;
-                    }
-                    DeckId::DeckB => {
-                        deck.xfade_gain = manual_gain_b;
-                    }
-                    _ => deck.xfade_gain = 1.0,
-                }
+                deck.xfade_gain = side.manual_gain(manual_pos);
             }
             match cue_tap {
                 Some(tap) => deck.fill_buffer_with_tap(buf, device_sr, Some(tap.as_mut_slice())),
@@ -1056,6 +2232,85 @@ fn audio_callback(
         xf_complete = true;
     }
 
+    // ── Fade-to-stop ramps (hard timed events) ──────────────────────────
+    {
+        let state: &mut RtState = rt;
+        let decks = &mut state.decks;
+        let (buf_a, buf_b) = (&mut state.buf_deck_a, &mut state.buf_deck_b);
+        let (buf_c, buf_d) = (&mut state.buf_aux1, &mut state.buf_aux2);
+        state.deck_fade_outs.retain(|id, (gain, step)| {
+            let buf = match id {
+                DeckId::DeckA => &mut *buf_a,
+                DeckId::DeckB => &mut *buf_b,
+                DeckId::Aux1 => &mut *buf_c,
+                DeckId::Aux2 => &mut *buf_d,
+                _ => return false,
+            };
+            for frame in buf.chunks_exact_mut(2) {
+                *gain = (*gain - *step).max(0.0);
+                frame[0] *= *gain;
+                frame[1] *= *gain;
+            }
+            if *gain > 0.0 {
+                return true;
+            }
+            if let Some(deck) = decks.get_mut(id) {
+                if matches!(deck.state, DeckState::Playing | DeckState::Crossfading) {
+                    deck.stop_with_completion(StopReason::Crossfade);
+                }
+            }
+            false
+        });
+    }
+
+    // ── Cart wall + SFX voices → Sound FX channel (before its pipeline) ──
+    {
+        let state: &mut RtState = rt;
+        state.carts.render(&mut state.buf_sound_fx, device_sr);
+        state.sfx.render(&mut state.buf_sound_fx, device_sr);
+    }
+
+    // ── Live mic → Voice FX channel (before its pipeline) ───────────────
+    {
+        use ringbuf::traits::{Consumer as _, Observer as _};
+        let state: &mut RtState = rt;
+        if let Some(cons) = state.live_input_cons.as_mut() {
+            // Keep mic latency bounded if the input clock runs ahead of the output.
+            let max_queued =
+                stereo_len + state.sample_rate as usize * 2 * LIVE_INPUT_MAX_LATENCY_MS / 1000;
+            let queued = cons.occupied_len();
+            if queued > max_queued {
+                cons.skip((queued - max_queued) & !1);
+            }
+            for s in state.buf_voice_fx.iter_mut() {
+                match cons.try_pop() {
+                    Some(v) => *s += v,
+                    None => break,
+                }
+            }
+        }
+    }
+
+    // ── Remote DJ → Aux 2 channel (before its pipeline) ─────────────────
+    {
+        use ringbuf::traits::{Consumer as _, Observer as _};
+        let state: &mut RtState = rt;
+        if let Some(cons) = state.remote_input_cons.as_mut() {
+            let max_queued =
+                stereo_len + state.sample_rate as usize * 2 * REMOTE_INPUT_MAX_LATENCY_MS / 1000;
+            let queued = cons.occupied_len();
+            if queued > max_queued {
+                cons.skip((queued - max_queued) & !1);
+            }
+            for s in state.buf_aux2.iter_mut() {
+                match cons.try_pop() {
+                    Some(v) => *s += v,
+                    None => break,
+                }
+            }
+        }
+    }
+
     // ── Per-channel DSP (EQ → AGC → Compressor) ─────────────────────────
     for (id, buf) in [
         (DeckId::DeckA, &mut rt.buf_deck_a as *mut Vec<f32>),
@@ -1071,90 +2326,63 @@ fn audio_callback(
         }
     }
 
-    // ── Mix into master ──────────────────────────────────────────────────
-    // SAFETY: we hold an exclusive &mut RtState from try_lock().
-    // The buf_* fields and mixer.mix_into are disjoint fields; no aliasing.
-    let (a, b, a_cue_tap, b_cue_tap, sfx, aux1, aux2, vfx, silence) = unsafe {
-        (
-            std::slice::from_raw_parts(rt.buf_deck_a.as_ptr(), rt.buf_deck_a.len()),
-            std::slice::from_raw_parts(rt.buf_deck_b.as_ptr(), rt.buf_deck_b.len()),
-            std::slice::from_raw_parts(rt.buf_deck_a_cue_tap.as_ptr(), rt.buf_deck_a_cue_tap.len()),
-            std::slice::from_raw_parts(rt.buf_deck_b_cue_tap.as_ptr(), rt.buf_deck_b_cue_tap.len()),
-            std::slice::from_raw_parts(rt.buf_sound_fx.as_ptr(), rt.buf_sound_fx.len()),
-            std::slice::from_raw_parts(rt.buf_aux1.as_ptr(), rt.buf_aux1.len()),
-            std::slice::from_raw_parts(rt.buf_aux2.as_ptr(), rt.buf_aux2.len()),
-            std::slice::from_raw_parts(rt.buf_voice_fx.as_ptr(), rt.buf_voice_fx.len()),
-            std::slice::from_raw_parts(rt.buf_silence.as_ptr(), rt.buf_silence.len()),
-        )
-    };
-    let cue_a = rt
-        .cue_preview_enabled
-        .get(&DeckId::DeckA)
-        .copied()
-        .unwrap_or(false);
-    let cue_b = rt
-        .cue_preview_enabled
-        .get(&DeckId::DeckB)
-        .copied()
-        .unwrap_or(false);
-    let split_available = rt.cue_split_active && rt.cue_available && out_channels >= 4;
-    let a_mix = if !split_available && cue_a {
-        silence
-    } else {
-        a
-    };
-    let b_mix = if !split_available && cue_b {
-        silence
-    } else {
-        b
-    };
-    // SAFETY: mixer and buf_master are disjoint RtState fields.
-    unsafe {
-        let mixer = &mut *(&mut rt.mixer as *mut Mixer);
-        let master = &mut *(&mut rt.buf_master as *mut Vec<f32>);
-        mixer.mix_into(master, a_mix, b_mix, sfx, aux1, aux2, vfx);
-    }
-    let master_level = rt.master_level;
-    if (master_level - 1.0).abs() > 1e-6 {
-        for s in rt.buf_master.iter_mut() {
-            *s *= master_level;
+    // ── Mic ducking (music decks only, post-DSP) ─────────────────────────
+    // Deck D carries the remote DJ while one is connected; their voice is
+    // not ducked under the local mic.
+    {
+        let state: &mut RtState = rt;
+        let mic_open = state.mic_open;
+        if state.remote_input_cons.is_some() {
+            state.ducker.process(
+                mic_open,
+                &mut [
+                    state.buf_deck_a.as_mut_slice(),
+                    state.buf_deck_b.as_mut_slice(),
+                    state.buf_aux1.as_mut_slice(),
+                ],
+            );
+        } else {
+            state.ducker.process(
+                mic_open,
+                &mut [
+                    state.buf_deck_a.as_mut_slice(),
+                    state.buf_deck_b.as_mut_slice(),
+                    state.buf_aux1.as_mut_slice(),
+                    state.buf_aux2.as_mut_slice(),
+                ],
+            );
         }
     }
 
-    // ── Master DSP (limiter / output chain) ─────────────────────────────
-    // SAFETY: master_pipeline and buf_master are disjoint RtState fields.
-    unsafe {
-        let pipeline = &mut *(&mut rt.master_pipeline as *mut ChannelPipeline);
-        let master = &mut *(&mut rt.buf_master as *mut Vec<f32>);
-        pipeline.process(master);
+    // ── Mix program / device master / cue buses ─────────────────────────
+    let cue_external = rt.cue_prod.is_some();
+    let split_available =
+        rt.cue_split_active && rt.cue_available && (out_channels >= 4 || cue_external);
+    mix_buses(rt, split_available);
+
+    if let Some(tap) = rt.spectrum_tap.as_mut() {
+        tap.feed(|source| match source {
+            SpectrumSource::Master => &rt.buf_program[..],
+            SpectrumSource::Deck(DeckId::DeckA) => &rt.buf_deck_a[..],
+            SpectrumSource::Deck(DeckId::DeckB) => &rt.buf_deck_b[..],
+            SpectrumSource::Deck(DeckId::SoundFx) => &rt.buf_sound_fx[..],
+            SpectrumSource::Deck(DeckId::Aux1) => &rt.buf_aux1[..],
+            SpectrumSource::Deck(DeckId::Aux2) => &rt.buf_aux2[..],
+            SpectrumSource::Deck(DeckId::VoiceFx) => &rt.buf_voice_fx[..],
+        });
     }
-
-    // Build cue bus only when split output is available.
-    if split_available {
-        if cue_a {
-            accumulate_stereo(&mut rt.buf_cue, a_cue_tap);
-        }
-        if cue_b {
-            accumulate_stereo(&mut rt.buf_cue, b_cue_tap);
-        }
-        let master_blend = ((rt.headphone_mix + 1.0) * 0.5).clamp(0.0, 1.0);
-        let cue_blend = 1.0 - master_blend;
-        let cue_level = rt.cue_level;
-        // SAFETY: immutable master slice and mutable cue slice point to disjoint buffers.
-        let (master, cue) = unsafe {
-            (
-                std::slice::from_raw_parts(rt.buf_master.as_ptr(), rt.buf_master.len()),
-                std::slice::from_raw_parts_mut(rt.buf_cue.as_mut_ptr(), rt.buf_cue.len()),
-            )
-        };
-        for i in 0..cue.len() {
-            cue[i] = (cue[i] * cue_blend + master[i] * master_blend) * cue_level;
-        }
+    if let Some(tap) = rt.multitrack_tap.as_mut() {
+        tap.feed(|bus| match bus {
+            RecordBus::DeckA => &rt.buf_deck_a[..],
+            RecordBus::DeckB => &rt.buf_deck_b[..],
+            RecordBus::VoiceFx => &rt.buf_voice_fx[..],
+            RecordBus::Master => &rt.buf_program[..],
+        });
     }
 
     if rt.local_monitor_muted {
         output.fill(0.0);
-    } else if split_available {
+    } else if split_available && !cue_external {
         for frame in 0..render_frames {
             let out_i = frame * out_channels;
             let src_i = frame * 2;
@@ -1186,14 +2414,20 @@ fn audio_callback(
         }
     }
 
-    // ── Feed encoder ring buffer ─────────────────────────────────────────
-    use ringbuf::traits::Producer as _;
-    let master_ptr = rt.buf_master.as_ptr();
-    let master_len = rt.buf_master.len();
-    for i in 0..master_len {
-        // SAFETY: master_ptr is valid for master_len for this callback scope.
-        let s = unsafe { *master_ptr.add(i) };
-        let _ = rt.encoder_prod.try_push(s);
+    // ── Feed secondary cue stream ────────────────────────────────────────
+    use ringbuf::traits::{Observer as _, Producer as _};
+    let state: &mut RtState = rt;
+    if let Some(cue_prod) = state.cue_prod.as_mut() {
+        // Whole blocks only, so a full ring never splits a stereo frame.
+        if cue_prod.vacant_len() >= state.buf_cue.len() {
+            if split_available && !state.local_monitor_muted {
+                cue_prod.push_slice(&state.buf_cue);
+            } else {
+                for _ in 0..state.buf_cue.len() {
+                    let _ = cue_prod.try_push(0.0);
+                }
+            }
+        }
     }
 
     // ── Handle crossfade completion ──────────────────────────────────────
@@ -1207,11 +2441,11 @@ fn audio_callback(
         }
         if let Some(id) = outgoing_id {
             if let Some(deck) = rt.decks.get_mut(&id) {
-                deck.stop_with_completion();
+                deck.stop_with_completion(StopReason::Crossfade);
             }
         }
-        if let Some(id) = incoming_id {
-            rt.manual_crossfade_pos = if id == DeckId::DeckB { 1.0 } else { -1.0 };
+        if let Some(position) = incoming_id.and_then(|id| assign.side(id).position()) {
+            rt.manual_crossfade_pos = position;
         }
     }
 
@@ -1221,7 +2455,7 @@ fn audio_callback(
         && rt.crossfade_config.auto_detect_enabled
         && autodj_mode != crate::scheduler::autodj::DjMode::AutoDj
     {
-        check_auto_crossfade(&mut rt);
+        check_auto_crossfade(rt);
     }
 }
 
@@ -1229,14 +2463,30 @@ fn audio_callback(
 fn process_commands(rt: &mut RtState, cmd_cons: &mut ringbuf::HeapCons<EngineCmd>) {
     use ringbuf::traits::Consumer as _;
 
+    apply_controls(rt);
+
     while let Some(cmd) = cmd_cons.try_pop() {
         match cmd {
             EngineCmd::AttachPreparedTrack { deck, prepared, op } => {
+                if matches!(op, AttachOp::Load) {
+                    rt.deck_fade_outs.remove(&deck);
+                    rt.deck_gain_ramps.remove(&deck);
+                }
                 if let Some(d) = rt.decks.get_mut(&deck) {
                     d.request_attach(prepared, op);
                 }
             }
+            EngineCmd::QuantizedSeek {
+                deck,
+                prepared,
+                at_frame,
+            } => {
+                if let Some(d) = rt.decks.get_mut(&deck) {
+                    d.request_attach_at(prepared, AttachOp::Seek, Some(at_frame));
+                }
+            }
             EngineCmd::Play(deck) => {
+                rt.deck_fade_outs.remove(&deck);
                 if let Some(d) = rt.decks.get_mut(&deck) {
                     d.play();
                 }
@@ -1246,38 +2496,58 @@ fn process_commands(rt: &mut RtState, cmd_cons: &mut ringbuf::HeapCons<EngineCmd
                     d.pause();
                 }
             }
-            EngineCmd::StopWithCompletion(deck) => {
+            EngineCmd::StopWithCompletion { deck, reason } => {
                 if let Some(d) = rt.decks.get_mut(&deck) {
-                    d.stop_with_completion();
+                    d.stop_with_completion(reason);
                 }
             }
-            EngineCmd::SetGain { deck, gain } => {
+            EngineCmd::SetTrackGain { deck, gain_db } => {
                 if let Some(d) = rt.decks.get_mut(&deck) {
-                    d.channel_gain = gain.clamp(0.0, 1.0);
+                    d.set_track_gain_db(gain_db);
                 }
             }
-            EngineCmd::SetDeckBass { deck, bass_db } => {
-                rt.deck_bass_db.insert(deck, bass_db.clamp(-12.0, 12.0));
-                apply_deck_tone(rt, deck);
-            }
-            EngineCmd::SetDeckFilter { deck, amount } => {
-                rt.deck_filter_amount.insert(deck, amount.clamp(-1.0, 1.0));
-                apply_deck_tone(rt, deck);
-            }
-            EngineCmd::SetMasterLevel { level } => {
-                rt.master_level = level.clamp(0.0, 1.0);
+            EngineCmd::SetDeckEqKill { deck, band, killed } => {
+                if let Some(p) = rt.pipelines.get_mut(&deck) {
+                    p.eq.set_kill(band, killed);
+                }
             }
             EngineCmd::SetLocalMonitorMuted { muted } => {
                 rt.local_monitor_muted = muted;
             }
-            EngineCmd::SetDeckPitch { deck, pct } => {
+            EngineCmd::SetMicOpen { open } => {
+                rt.mic_open = open;
+            }
+            EngineCmd::SetDuckConfig(config) => {
+                rt.ducker.set_config(config);
+            }
+            EngineCmd::SetDeckKeylock { deck, enabled } => {
+                if let Some(d) = rt.decks.get_mut(&deck) {
+                    d.set_keylock(enabled);
+                }
+            }
+            EngineCmd::SetDeckStemMix { deck, mix } => {
                 if let Some(d) = rt.decks.get_mut(&deck) {
-                    d.set_pitch_pct(pct);
+                    d.set_stem_mix(mix);
                 }
             }
-            EngineCmd::SetDeckTempo { deck, pct } => {
+            EngineCmd::SetDeckReverse { deck, enabled } => {
                 if let Some(d) = rt.decks.get_mut(&deck) {
-                    d.set_tempo_pct(pct);
+                    d.set_reverse(enabled);
+                }
+            }
+            EngineCmd::SetDeckCensor { deck, active } => {
+                if let Some(d) = rt.decks.get_mut(&deck) {
+                    d.set_censor(active);
+                }
+            }
+            EngineCmd::SetDeckSlip { deck, enabled } => {
+                if let Some(d) = rt.decks.get_mut(&deck) {
+                    d.set_slip(enabled);
+                }
+            }
+            EngineCmd::SlipReturn { deck, prepared } => {
+                if let Some(d) = rt.decks.get_mut(&deck) {
+                    d.request_slip_return(prepared);
                 }
             }
             EngineCmd::SetDeckLoop {
@@ -1296,6 +2566,13 @@ fn process_commands(rt: &mut RtState, cmd_cons: &mut ringbuf::HeapCons<EngineCmd
                     d.clear_loop();
                 }
             }
+            EngineCmd::ResizeDeckLoop { deck, double } => {
+                if let Some(d) = rt.decks.get_mut(&deck) {
+                    if let Err(err) = d.resize_loop(double) {
+                        log::warn!("resize_loop failed for {deck}: {err}");
+                    }
+                }
+            }
             EngineCmd::StartCrossfade { outgoing, incoming } => {
                 if rt.crossfade.is_fading() {
                     continue;
@@ -1319,41 +2596,88 @@ fn process_commands(rt: &mut RtState, cmd_cons: &mut ringbuf::HeapCons<EngineCmd
             EngineCmd::SetCrossfadeConfig(config) => {
                 rt.crossfade_config = config;
             }
-            EngineCmd::SetManualCrossfade { position } => {
-                rt.manual_crossfade_pos = position.clamp(-1.0, 1.0);
+            EngineCmd::ResolveCrossfade { complete } => {
+                let (Some(outgoing), Some(incoming)) =
+                    (rt.crossfade.outgoing(), rt.crossfade.incoming())
+                else {
+                    continue;
+                };
+                rt.crossfade.reset();
+                let on_air = if complete { incoming } else { outgoing };
+                if let Some(d) = rt.decks.get_mut(&on_air) {
+                    d.xfade_gain = 1.0;
+                    d.clear_crossfading();
+                }
+                if complete {
+                    if let Some(d) = rt.decks.get_mut(&outgoing) {
+                        d.stop_with_completion(StopReason::Crossfade);
+                    }
+                } else if let Some(d) = rt.decks.get_mut(&incoming) {
+                    d.pause();
+                }
+                if let Some(position) = rt
+                    .crossfade_config
+                    .crossfader_assign
+                    .side(on_air)
+                    .position()
+                {
+                    rt.manual_crossfade_pos = position;
+                }
             }
             EngineCmd::TriggerManualFade {
                 direction,
                 duration_ms,
             } => {
-                if rt.crossfade.is_fading() {
-                    continue;
-                }
-                let (requested_outgoing, requested_incoming) = match direction {
-                    ManualFadeDirection::AtoB => (DeckId::DeckA, DeckId::DeckB),
-                    ManualFadeDirection::BtoA => (DeckId::DeckB, DeckId::DeckA),
-                };
-                let Some((outgoing, incoming)) =
-                    resolve_crossfade_pair(rt, requested_outgoing, requested_incoming)
-                else {
-                    log::warn!("Ignoring manual fade: no valid outgoing/incoming deck pair");
-                    continue;
+                // Fade from the deck on air on one crossfader side to the
+                // loaded deck on the other.
+                let (from_side, to_side, from_default, to_default) = match direction {
+                    ManualFadeDirection::AtoB => (
+                        CrossfaderSide::A,
+                        CrossfaderSide::B,
+                        DeckId::DeckA,
+                        DeckId::DeckB,
+                    ),
+                    ManualFadeDirection::BtoA => (
+                        CrossfaderSide::B,
+                        CrossfaderSide::A,
+                        DeckId::DeckB,
+                        DeckId::DeckA,
+                    ),
                 };
-                let mut config = rt.crossfade_config.clone();
-                config.fade_out_time_ms = duration_ms.max(100);
-                config.fade_in_time_ms = duration_ms.max(100);
-                cap_fade_window_to_outgoing_remaining(rt, outgoing, &mut config);
-                rt.crossfade = CrossfadeState::start(outgoing, incoming, config, rt.sample_rate);
-                if let Some(d) = rt.decks.get_mut(&outgoing) {
-                    d.set_crossfading();
+                let outgoing = side_deck(rt, from_side, is_playing_like).unwrap_or(from_default);
+                let incoming = side_deck(rt, to_side, is_loaded_like).unwrap_or(to_default);
+                start_timed_fade(rt, outgoing, incoming, duration_ms, None);
+            }
+            EngineCmd::StartTimedCrossfade {
+                outgoing,
+                incoming,
+                duration_ms,
+                profile,
+            } => start_timed_fade(rt, outgoing, incoming, duration_ms, profile.map(|p| *p)),
+            EngineCmd::FadeOutDeck { deck, duration_ms } => {
+                if deck.is_playback() {
+                    let frames =
+                        (rt.sample_rate as f32 * duration_ms.max(10) as f32 / 1000.0).max(1.0);
+                    rt.deck_fade_outs.insert(deck, (1.0, 1.0 / frames));
                 }
-                if let Some(d) = rt.decks.get_mut(&incoming) {
-                    d.play();
+            }
+            EngineCmd::RampChannelGain {
+                deck,
+                gain,
+                duration_ms,
+            } => {
+                if let Some(d) = rt.decks.get(&deck) {
+                    let frames = (rt.sample_rate as f32 * duration_ms as f32 / 1000.0).max(1.0);
+                    let step = (gain - d.channel_gain).abs() / frames;
+                    rt.deck_gain_ramps.insert(deck, (gain, step));
                 }
             }
             EngineCmd::SetChannelPipeline { deck, settings } => {
                 if let Some(p) = rt.pipelines.get_mut(&deck) {
+                    // Kill switches are live state, not settings; keep them across rebuilds.
+                    let kills = p.eq.kill_state();
                     *p = ChannelPipeline::from_settings(rt.sample_rate as f32, settings);
+                    p.eq.restore_kills(kills);
                 }
                 apply_deck_tone(rt, deck);
             }
@@ -1362,7 +2686,7 @@ fn process_commands(rt: &mut RtState, cmd_cons: &mut ringbuf::HeapCons<EngineCmd
                     ChannelPipeline::from_settings(rt.sample_rate as f32, settings);
             }
             EngineCmd::SetDeckCuePreview { deck, enabled } => {
-                if matches!(deck, DeckId::DeckA | DeckId::DeckB) {
+                if deck.is_playback() {
                     let effective = if rt.cue_split_active || rt.cue_available {
                         enabled
                     } else {
@@ -1371,12 +2695,6 @@ fn process_commands(rt: &mut RtState, cmd_cons: &mut ringbuf::HeapCons<EngineCmd
                     rt.cue_preview_enabled.insert(deck, effective);
                 }
             }
-            EngineCmd::SetHeadphoneMix { value } => {
-                rt.headphone_mix = value.clamp(-1.0, 1.0);
-            }
-            EngineCmd::SetHeadphoneLevel { value } => {
-                rt.cue_level = value.clamp(0.0, 1.0);
-            }
             EngineCmd::SetMonitorRoutingConfig(config) => {
                 let wants_split = matches!(
                     config.cue_mix_mode.as_str(),
@@ -1389,10 +2707,104 @@ fn process_commands(rt: &mut RtState, cmd_cons: &mut ringbuf::HeapCons<EngineCmd
                 rt.cue_level = config.cue_level.clamp(0.0, 1.0);
                 rt.master_level = config.master_level.clamp(0.0, 1.0);
                 if !rt.cue_split_active {
-                    rt.cue_preview_enabled.insert(DeckId::DeckA, false);
-                    rt.cue_preview_enabled.insert(DeckId::DeckB, false);
+                    for id in DeckId::PLAYBACK {
+                        rt.cue_preview_enabled.insert(id, false);
+                    }
+                }
+            }
+            EngineCmd::TriggerCart(trigger) => rt.carts.trigger(trigger),
+            EngineCmd::StopCart(key) => rt.carts.stop(key),
+            EngineCmd::StopAllCarts => rt.carts.stop_all(),
+            EngineCmd::PlaySfx(trigger) => rt.sfx.trigger(trigger),
+            EngineCmd::StopSfx { which, fade_ms } => rt.sfx.stop(which, fade_ms),
+            EngineCmd::SetCueRouting {
+                available,
+                split_active,
+            } => {
+                rt.cue_available = available;
+                rt.cue_split_active = split_active;
+                if !available {
+                    for id in DeckId::PLAYBACK {
+                        rt.cue_preview_enabled.insert(id, false);
+                    }
+                }
+            }
+            EngineCmd::SetCueOutput(prod) => rt.cue_prod = prod,
+            EngineCmd::SetLiveInput(cons) => rt.live_input_cons = cons,
+            EngineCmd::SetRemoteInput(cons) => rt.remote_input_cons = cons,
+            EngineCmd::SetMixMinus(prod) => rt.mix_minus_prod = prod,
+            EngineCmd::SetSpectrumTap(tap) => {
+                if let Some(old) = std::mem::replace(&mut rt.spectrum_tap, tap) {
+                    rt.retire_spectrum_tap(old);
+                }
+            }
+            EngineCmd::SetLoudnessTap(prod) => rt.loudness_prod = prod,
+            EngineCmd::SetMultitrackTap(tap) => rt.multitrack_tap = tap,
+        }
+    }
+}
+
+/// Apply continuous control values set since the last block.
+fn apply_controls(rt: &mut RtState) {
+    // A second handle so `rt` stays free to mutate while taking values.
+    let controls = Arc::clone(&rt.controls);
+    for (control, value) in controls.take() {
+        match control {
+            Control::Deck(deck, DeckControl::Gain) => {
+                // A fader move takes over from any ramp in progress.
+                rt.deck_gain_ramps.remove(&deck);
+                if let Some(d) = rt.decks.get_mut(&deck) {
+                    d.channel_gain = value.clamp(0.0, 1.0);
                 }
             }
+            Control::Deck(deck, DeckControl::Pitch) => {
+                if let Some(d) = rt.decks.get_mut(&deck) {
+                    d.set_pitch_pct(value);
+                }
+            }
+            Control::Deck(deck, DeckControl::Tempo) => {
+                if let Some(d) = rt.decks.get_mut(&deck) {
+                    d.set_tempo_pct(value);
+                }
+            }
+            Control::Deck(deck, DeckControl::Bass) => {
+                rt.deck_bass_db.insert(deck, value.clamp(-12.0, 12.0));
+                apply_deck_tone(rt, deck);
+            }
+            Control::Deck(deck, DeckControl::Filter) => {
+                rt.deck_filter_amount.insert(deck, value.clamp(-1.0, 1.0));
+                apply_deck_tone(rt, deck);
+            }
+            Control::ManualCrossfade => rt.manual_crossfade_pos = value.clamp(-1.0, 1.0),
+            Control::MasterLevel => rt.master_level = value.clamp(0.0, 1.0),
+            Control::HeadphoneMix => rt.headphone_mix = value.clamp(-1.0, 1.0),
+            Control::HeadphoneLevel => rt.cue_level = value.clamp(0.0, 1.0),
+        }
+    }
+}
+
+/// Real-time callback for the secondary cue device. Plays stereo frames from
+/// the cue ring and outputs silence on underrun.
+fn cue_callback(output: &mut [f32], channels: usize, cue_cons: &mut ringbuf::HeapCons<f32>) {
+    use ringbuf::traits::{Consumer as _, Observer as _};
+    let channels = channels.max(1);
+    for frame in output.chunks_mut(channels) {
+        let (l, r) = if cue_cons.occupied_len() >= 2 {
+            (
+                cue_cons.try_pop().unwrap_or(0.0),
+                cue_cons.try_pop().unwrap_or(0.0),
+            )
+        } else {
+            (0.0, 0.0)
+        };
+        if channels == 1 {
+            frame[0] = (l + r) * 0.5;
+            continue;
+        }
+        frame[0] = l;
+        frame[1] = r;
+        for s in frame.iter_mut().skip(2) {
+            *s = 0.0;
         }
     }
 }
@@ -1413,6 +2825,119 @@ fn apply_deck_tone(rt: &mut RtState, deck: DeckId) {
     pipeline.eq.set_config(eq);
 }
 
+/// Build the buses from the per-channel buffers and feed the encoder.
+///
+/// * program — the on-air mix. Decks with cue preview enabled are always left
+///   out, and neither the cue taps nor `buf_cue` are ever read, so PFL and
+///   headphone talkback cannot reach `encoder_prod`.
+/// * device master — program plus, when the cue bus has its own output, the
+///   cued decks (the local DJ keeps hearing them on the monitors).
+/// * cue — PFL taps blended with the device master for headphones.
+fn mix_buses(rt: &mut RtState, split_available: bool) {
+    use ringbuf::traits::{Observer as _, Producer as _};
+
+    let cued = |id: DeckId| rt.cue_preview_enabled.get(&id).copied().unwrap_or(false);
+    let (cue_a, cue_b, cue_c, cue_d) = (
+        cued(DeckId::DeckA),
+        cued(DeckId::DeckB),
+        cued(DeckId::Aux1),
+        cued(DeckId::Aux2),
+    );
+
+    // ── Program bus ──────────────────────────────────────────────────────
+    let a_prog = if cue_a {
+        &rt.buf_silence
+    } else {
+        &rt.buf_deck_a
+    };
+    let b_prog = if cue_b {
+        &rt.buf_silence
+    } else {
+        &rt.buf_deck_b
+    };
+    let c_prog = if cue_c { &rt.buf_silence } else { &rt.buf_aux1 };
+    let d_prog = if cue_d { &rt.buf_silence } else { &rt.buf_aux2 };
+    rt.mixer.mix_into(
+        &mut rt.buf_program,
+        a_prog,
+        b_prog,
+        &rt.buf_sound_fx,
+        c_prog,
+        d_prog,
+        &rt.buf_voice_fx,
+    );
+    let master_level = rt.master_level;
+
+    // Mix-minus: the program as mixed, less the remote DJ's own channel.
+    let aux2_gain = if cue_d {
+        0.0
+    } else {
+        rt.mixer.effective_gain(DeckId::Aux2)
+    };
+    if let Some(prod) = rt.mix_minus_prod.as_mut() {
+        if prod.vacant_len() >= rt.buf_program.len() {
+            for (&p, &a) in rt.buf_program.iter().zip(rt.buf_aux2.iter()) {
+                let _ = prod.try_push((p - a * aux2_gain) * master_level);
+            }
+        }
+    }
+
+    if (master_level - 1.0).abs() > 1e-6 {
+        for s in rt.buf_program.iter_mut() {
+            *s *= master_level;
+        }
+    }
+
+    // Master DSP (limiter / output chain) runs on the program bus only.
+    rt.master_pipeline.process(&mut rt.buf_program);
+
+    for &s in rt.buf_program.iter() {
+        let _ = rt.encoder_prod.try_push(s);
+    }
+    // The loudness meter measures exactly what the encoders get.
+    if let Some(prod) = rt.loudness_prod.as_mut() {
+        if prod.vacant_len() >= rt.buf_program.len() {
+            prod.push_slice(&rt.buf_program);
+        }
+    }
+
+    // ── Device master ────────────────────────────────────────────────────
+    rt.buf_master.copy_from_slice(&rt.buf_program);
+    if split_available {
+        for (cued, buf, id) in [
+            (cue_a, &rt.buf_deck_a, DeckId::DeckA),
+            (cue_b, &rt.buf_deck_b, DeckId::DeckB),
+            (cue_c, &rt.buf_aux1, DeckId::Aux1),
+            (cue_d, &rt.buf_aux2, DeckId::Aux2),
+        ] {
+            if cued {
+                rt.mixer
+                    .add_channel(&mut rt.buf_master, buf, id, master_level);
+            }
+        }
+    }
+
+    // ── Cue bus (only when split output is available) ───────────────────
+    if split_available {
+        for (cued, tap) in [
+            (cue_a, &rt.buf_deck_a_cue_tap),
+            (cue_b, &rt.buf_deck_b_cue_tap),
+            (cue_c, &rt.buf_aux1_cue_tap),
+            (cue_d, &rt.buf_aux2_cue_tap),
+        ] {
+            if cued {
+                accumulate_stereo(&mut rt.buf_cue, tap);
+            }
+        }
+        let master_blend = ((rt.headphone_mix + 1.0) * 0.5).clamp(0.0, 1.0);
+        let cue_blend = 1.0 - master_blend;
+        let cue_level = rt.cue_level;
+        for (cue, &master) in rt.buf_cue.iter_mut().zip(rt.buf_master.iter()) {
+            *cue = (*cue * cue_blend + master * master_blend) * cue_level;
+        }
+    }
+}
+
 #[inline]
 fn accumulate_stereo(dest: &mut [f32], src: &[f32]) {
     for (d, s) in dest.iter_mut().zip(src.iter()) {
@@ -1454,15 +2979,16 @@ fn resolve_crossfade_pair(
         return Some((incoming, outgoing));
     }
 
-    let a_state = rt.decks.get(&DeckId::DeckA).map(|d| d.state.clone())?;
-    let b_state = rt.decks.get(&DeckId::DeckB).map(|d| d.state.clone())?;
-
-    if is_playing_like(&a_state) && is_loaded_like(&b_state) {
-        Some((DeckId::DeckA, DeckId::DeckB))
-    } else if is_playing_like(&b_state) && is_loaded_like(&a_state) {
-        Some((DeckId::DeckB, DeckId::DeckA))
-    } else {
-        None
+    // Fall back to the crossfader sides: whichever side is on air fades to
+    // the loaded deck on the other.
+    let on_a = side_deck(rt, CrossfaderSide::A, is_playing_like);
+    let on_b = side_deck(rt, CrossfaderSide::B, is_playing_like);
+    let loaded_a = side_deck(rt, CrossfaderSide::A, is_loaded_like);
+    let loaded_b = side_deck(rt, CrossfaderSide::B, is_loaded_like);
+    match (on_a, on_b) {
+        (Some(out), None) => loaded_b.map(|incoming| (out, incoming)),
+        (None, Some(out)) => loaded_a.map(|incoming| (out, incoming)),
+        _ => None,
     }
 }
 
@@ -1492,6 +3018,71 @@ fn cap_fade_window_to_outgoing_remaining(
     }
 }
 
+/// First playback deck on crossfader `side` whose state passes `pred`.
+fn side_deck(rt: &RtState, side: CrossfaderSide, pred: fn(&DeckState) -> bool) -> Option<DeckId> {
+    let assign = rt.crossfade_config.crossfader_assign;
+    DeckId::PLAYBACK
+        .into_iter()
+        .find(|id| assign.side(*id) == side && rt.decks.get(id).is_some_and(|d| pred(&d.state)))
+}
+
+/// Start an `outgoing` → `incoming` fade lasting `duration_ms`, unless one is
+/// already running or the pair does not match the decks' actual states.
+fn start_timed_fade(
+    rt: &mut RtState,
+    outgoing: DeckId,
+    incoming: DeckId,
+    duration_ms: u32,
+    profile: Option<CrossfadeConfig>,
+) {
+    if rt.crossfade.is_fading() {
+        return;
+    }
+    let Some((outgoing, incoming)) = resolve_crossfade_pair(rt, outgoing, incoming) else {
+        log::warn!("Ignoring timed fade: no valid outgoing/incoming deck pair");
+        return;
+    };
+    let mut config = match profile {
+        Some(mut profile) => {
+            profile.crossfader_assign = rt.crossfade_config.crossfader_assign;
+            profile
+        }
+        None => rt.crossfade_config.clone(),
+    };
+    config.fade_out_time_ms = duration_ms.max(100);
+    config.fade_in_time_ms = duration_ms.max(100);
+    cap_fade_window_to_outgoing_remaining(rt, outgoing, &mut config);
+    rt.crossfade = CrossfadeState::start(outgoing, incoming, config, rt.sample_rate);
+    if let Some(d) = rt.decks.get_mut(&outgoing) {
+        d.set_crossfading();
+    }
+    if let Some(d) = rt.decks.get_mut(&incoming) {
+        d.play();
+    }
+}
+
+/// Ready deck to fade into from `outgoing`: one on the opposite crossfader
+/// side first, then any other ready playback deck.
+fn next_ready_deck(rt: &RtState, outgoing: DeckId) -> Option<DeckId> {
+    let assign = rt.crossfade_config.crossfader_assign;
+    let out_side = assign.side(outgoing);
+    let ready = |id: &DeckId| {
+        *id != outgoing
+            && rt
+                .decks
+                .get(id)
+                .is_some_and(|d| d.state == DeckState::Ready)
+    };
+    DeckId::PLAYBACK
+        .into_iter()
+        .filter(ready)
+        .find(|id| {
+            let side = assign.side(*id);
+            side != CrossfaderSide::Thru && side != out_side
+        })
+        .or_else(|| DeckId::PLAYBACK.into_iter().find(ready))
+}
+
 /// Check if the active deck's RMS has dropped below the auto-detect threshold.
 fn check_auto_crossfade(rt: &mut RtState) {
     let cfg = &rt.crossfade_config;
@@ -1500,34 +3091,22 @@ fn check_auto_crossfade(rt: &mut RtState) {
     }
 
     // Find the currently playing deck with the lowest remaining time within window
-    let trigger_deck = [DeckId::DeckA, DeckId::DeckB]
-        .iter()
-        .find(|&&id| {
-            let deck = match rt.decks.get(&id) {
-                Some(d) => d,
-                None => return false,
-            };
-            if deck.state != DeckState::Playing {
-                return false;
-            }
-            let remaining = deck.remaining_ms();
-            remaining > 0
-                && remaining <= cfg.auto_detect_max_ms as u64
-                && deck.position_ms() >= cfg.auto_detect_min_ms as u64
-        })
-        .copied();
+    let trigger_deck = DeckId::PLAYBACK.into_iter().find(|id| {
+        let deck = match rt.decks.get(id) {
+            Some(d) => d,
+            None => return false,
+        };
+        if deck.state != DeckState::Playing {
+            return false;
+        }
+        let remaining = deck.remaining_ms();
+        remaining > 0
+            && remaining <= cfg.auto_detect_max_ms as u64
+            && deck.position_ms() >= cfg.auto_detect_min_ms as u64
+    });
 
     if let Some(outgoing) = trigger_deck {
-        let incoming = match outgoing {
-            DeckId::DeckA => DeckId::DeckB,
-            _ => DeckId::DeckA,
-        };
-        if rt
-            .decks
-            .get(&incoming)
-            .map(|d| d.state == DeckState::Ready)
-            .unwrap_or(false)
-        {
+        if let Some(incoming) = next_ready_deck(rt, outgoing) {
             let config = rt.crossfade_config.clone();
             rt.crossfade = CrossfadeState::start(outgoing, incoming, config, rt.sample_rate);
             if let Some(d) = rt.decks.get_mut(&outgoing) {
@@ -1539,3 +3118,76 @@ fn check_auto_crossfade(rt: &mut RtState) {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ringbuf::traits::Consumer as _;
+
+    const LEN: usize = 256;
+
+    fn rt_with_decks(deck_a: f32, deck_b: f32) -> (RtState, ringbuf::HeapCons<f32>) {
+        let (prod, cons) = HeapRb::<f32>::new(LEN * 4).split();
+        let (mut rt, _view) = RtState::new(44100, 4, prod);
+        rt.resize_buffers(LEN);
+        rt.buf_deck_a.fill(deck_a);
+        rt.buf_deck_a_cue_tap.fill(deck_a);
+        rt.buf_deck_b.fill(deck_b);
+        rt.cue_available = true;
+        rt.cue_split_active = true;
+        (rt, cons)
+    }
+
+    fn encoded(cons: &mut ringbuf::HeapCons<f32>) -> Vec<f32> {
+        std::iter::from_fn(|| cons.try_pop()).collect()
+    }
+
+    #[test]
+    fn cue_enabled_deck_is_absent_from_encoder_feed() {
+        for split in [true, false] {
+            let (mut cued, mut cued_cons) = rt_with_decks(0.5, 0.25);
+            cued.cue_preview_enabled.insert(DeckId::DeckA, true);
+            mix_buses(&mut cued, split);
+
+            let (mut reference, mut reference_cons) = rt_with_decks(0.0, 0.25);
+            mix_buses(&mut reference, split);
+
+            let got = encoded(&mut cued_cons);
+            assert_eq!(got.len(), LEN);
+            assert_eq!(got, encoded(&mut reference_cons), "split = {split}");
+        }
+    }
+
+    #[test]
+    fn callback_applies_commands_and_publishes_a_snapshot() {
+        use ringbuf::traits::Producer as _;
+        let (prod, _enc) = HeapRb::<f32>::new(LEN * 4).split();
+        let (mut rt, mut view) = RtState::new(44100, 2, prod);
+        let (mut cmd_tx, mut cmd_rx) = HeapRb::<EngineCmd>::new(8).split();
+        assert_eq!(view.snapshot.read().master_level, 1.0);
+
+        let _ = cmd_tx.try_push(EngineCmd::SetMicOpen { open: true });
+        view.controls.set(Control::MasterLevel, 0.5);
+        view.controls.set(Control::ManualCrossfade, 0.1);
+        view.controls.set(Control::ManualCrossfade, 0.25);
+        let mut output = vec![0.0; LEN];
+        audio_callback(&mut output, &mut rt, &mut cmd_rx);
+
+        let snap = view.snapshot.read();
+        assert_eq!(snap.master_level, 0.5);
+        assert_eq!(snap.manual_crossfade_pos, 0.25);
+        assert!(snap.mic_open);
+        assert_eq!(snap.deck(DeckId::Aux1).state, DeckState::Idle);
+    }
+
+    #[test]
+    fn cued_deck_stays_on_device_master_when_split() {
+        let (mut rt, _cons) = rt_with_decks(0.5, 0.0);
+        rt.cue_preview_enabled.insert(DeckId::DeckA, true);
+        mix_buses(&mut rt, true);
+
+        assert!(rt.buf_program.iter().all(|&s| s == 0.0));
+        assert!(rt.buf_master.iter().any(|&s| s.abs() > 0.1));
+        assert!(rt.buf_cue.iter().any(|&s| s.abs() > 0.1));
+    }
+}
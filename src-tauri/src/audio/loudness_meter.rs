@@ -0,0 +1,531 @@
+/// EBU R128 / ITU BS.1770 loudness and true-peak metering of the master bus.
+///
+/// The audio callback copies the program bus (exactly what the encoders get)
+/// into a ring; `poll` drains it on the UI polling loop. Samples are
+/// K-weighted and summed into 100 ms blocks, from which momentary (400 ms),
+/// short-term (3 s) and gated integrated loudness follow. Integrated loudness
+/// keeps a 0.1 LU histogram of block loudness, so it can run for days in
+/// constant memory. True peak is the largest sample of a 4× oversampled
+/// signal; each excursion above the configured ceiling counts as one over.
+///
+/// The health monitor takes the short-term and integrated loudness plus the
+/// interval's true peak and overs for every snapshot it records.
+use std::collections::VecDeque;
+use std::f64::consts::PI;
+use std::path::PathBuf;
+use std::sync::{Mutex, OnceLock};
+
+use ringbuf::traits::Consumer as _;
+use serde::{Deserialize, Serialize};
+
+use super::engine::AudioEngine;
+
+const CONFIG_FILE: &str = "loudness.json";
+/// Reported for silence and anything under the absolute gate.
+pub const SILENCE_LUFS: f32 = -70.0;
+const ABSOLUTE_GATE_LUFS: f64 = -70.0;
+const RELATIVE_GATE_LU: f64 = -10.0;
+/// Blocks per momentary (400 ms) and short-term (3 s) window
+const MOMENTARY_BLOCKS: usize = 4;
+const SHORT_TERM_BLOCKS: usize = 30;
+/// Integrated-loudness histogram: 0.1 LU bins from the absolute gate to +5
+const HISTOGRAM_STEP: f64 = 0.1;
+const HISTOGRAM_BINS: usize = 750;
+const OVERSAMPLE: usize = 4;
+const TAPS_PER_PHASE: usize = 12;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LoudnessConfig {
+    /// Station loudness target, shown against the meters
+    pub target_lufs: f32,
+    /// True-peak level above which a sample counts as an over
+    pub true_peak_ceiling_dbtp: f32,
+}
+
+impl Default for LoudnessConfig {
+    fn default() -> Self {
+        Self {
+            target_lufs: -23.0,
+            true_peak_ceiling_dbtp: -1.0,
+        }
+    }
+}
+
+impl LoudnessConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(-40.0..=-5.0).contains(&self.target_lufs) {
+            return Err("Loudness target must be between -40 and -5 LUFS".to_string());
+        }
+        if !(-12.0..=0.0).contains(&self.true_peak_ceiling_dbtp) {
+            return Err("True-peak ceiling must be between -12 and 0 dBTP".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Meter values, emitted as `loudness`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LoudnessReading {
+    pub momentary_lufs: f32,
+    pub short_term_lufs: f32,
+    /// `None` until a block has passed the gates
+    pub integrated_lufs: Option<f32>,
+    /// Over the momentary window
+    pub true_peak_dbtp: f32,
+    /// Since the last reset
+    pub max_true_peak_dbtp: f32,
+    pub overs: u64,
+    /// Seconds of audio measured since the last reset
+    pub measured_secs: f64,
+}
+
+/// Loudness for one health snapshot.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct LoudnessHealth {
+    pub short_term_lufs: Option<f32>,
+    pub integrated_lufs: Option<f32>,
+    /// Highest true peak since the previous snapshot
+    pub true_peak_dbtp: Option<f32>,
+    /// Overs since the previous snapshot
+    pub overs: u64,
+}
+
+/// Direct-form II transposed biquad.
+#[derive(Debug, Clone, Copy, Default)]
+struct Biquad {
+    b: [f64; 3],
+    a: [f64; 2],
+    z: [f64; 2],
+}
+
+impl Biquad {
+    fn process(&mut self, x: f64) -> f64 {
+        let y = self.b[0] * x + self.z[0];
+        self.z[0] = self.b[1] * x - self.a[0] * y + self.z[1];
+        self.z[1] = self.b[2] * x - self.a[1] * y;
+        y
+    }
+}
+
+/// BS.1770 K-weighting: a high shelf for the head's acoustic effect followed
+/// by the RLB high-pass, both re-derived for `sample_rate`.
+fn k_weighting(sample_rate: u32) -> [Biquad; 2] {
+    let rate = f64::from(sample_rate.max(1));
+
+    let (f0, gain_db, q) = (
+        1_681.974_450_955_533,
+        3.999_843_853_973_347,
+        0.707_175_236_955_419_6,
+    );
+    let k = (PI * f0 / rate).tan();
+    let vh = 10f64.powf(gain_db / 20.0);
+    let vb = vh.powf(0.499_666_774_154_541_6);
+    let a0 = 1.0 + k / q + k * k;
+    let shelf = Biquad {
+        b: [
+            (vh + vb * k / q + k * k) / a0,
+            2.0 * (k * k - vh) / a0,
+            (vh - vb * k / q + k * k) / a0,
+        ],
+        a: [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+        z: [0.0; 2],
+    };
+
+    let (f0, q) = (38.135_470_876_024_44, 0.500_327_037_323_877_3);
+    let k = (PI * f0 / rate).tan();
+    let a0 = 1.0 + k / q + k * k;
+    let high_pass = Biquad {
+        b: [1.0, -2.0, 1.0],
+        a: [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+        z: [0.0; 2],
+    };
+    [shelf, high_pass]
+}
+
+/// Polyphase windowed-sinc interpolator for 4× true-peak oversampling.
+/// The cutoff sits a little under the original Nyquist; each phase is
+/// normalised to unity gain at DC.
+fn interpolation_phases() -> [[f32; TAPS_PER_PHASE]; OVERSAMPLE] {
+    const CUTOFF: f64 = 0.9;
+    let len = OVERSAMPLE * TAPS_PER_PHASE;
+    let centre = (len - 1) as f64 / 2.0;
+    let mut phases = [[0.0_f32; TAPS_PER_PHASE]; OVERSAMPLE];
+    for i in 0..len {
+        let t = CUTOFF * (i as f64 - centre) / OVERSAMPLE as f64;
+        let sinc = if t == 0.0 {
+            1.0
+        } else {
+            (PI * t).sin() / (PI * t)
+        };
+        let x = 2.0 * PI * i as f64 / (len - 1) as f64;
+        let blackman = 0.42 - 0.5 * x.cos() + 0.08 * (2.0 * x).cos();
+        phases[i % OVERSAMPLE][i / OVERSAMPLE] = (sinc * blackman) as f32;
+    }
+    for phase in &mut phases {
+        let sum: f32 = phase.iter().sum();
+        phase.iter_mut().for_each(|c| *c /= sum);
+    }
+    phases
+}
+
+#[derive(Debug, Clone, Default)]
+struct ChannelState {
+    filters: [Biquad; 2],
+    /// Newest raw sample first, for the interpolator
+    history: [f32; TAPS_PER_PHASE],
+}
+
+fn lufs(energy: f64) -> f64 {
+    if energy <= 0.0 {
+        f64::NEG_INFINITY
+    } else {
+        -0.691 + 10.0 * energy.log10()
+    }
+}
+
+fn energy(lufs: f64) -> f64 {
+    10f64.powf((lufs + 0.691) / 10.0)
+}
+
+fn to_dbtp(peak: f32) -> f32 {
+    if peak < 1e-10 {
+        -200.0
+    } else {
+        20.0 * peak.log10()
+    }
+}
+
+/// Meter state for one interleaved stereo stream.
+pub struct LoudnessMeter {
+    sample_rate: u32,
+    channels: [ChannelState; 2],
+    phases: [[f32; TAPS_PER_PHASE]; OVERSAMPLE],
+    ceiling: f32,
+    block_frames: usize,
+    /// Running sums for the 100 ms block being filled
+    block_energy: f64,
+    block_peak: f32,
+    block_filled: usize,
+    /// Newest last: (mean channel-summed energy, true peak) per block
+    blocks: VecDeque<(f64, f32)>,
+    histogram: Vec<u64>,
+    max_peak: f32,
+    overs: u64,
+    was_over: bool,
+    measured_frames: u64,
+    interval_peak: Option<f32>,
+    interval_overs: u64,
+}
+
+impl LoudnessMeter {
+    pub fn new(sample_rate: u32, ceiling_dbtp: f32) -> Self {
+        let filters = k_weighting(sample_rate);
+        Self {
+            sample_rate,
+            channels: std::array::from_fn(|_| ChannelState {
+                filters,
+                history: [0.0; TAPS_PER_PHASE],
+            }),
+            phases: interpolation_phases(),
+            ceiling: 10f32.powf(ceiling_dbtp / 20.0),
+            block_frames: (sample_rate as usize / 10).max(1),
+            block_energy: 0.0,
+            block_peak: 0.0,
+            block_filled: 0,
+            blocks: VecDeque::with_capacity(SHORT_TERM_BLOCKS),
+            histogram: vec![0; HISTOGRAM_BINS],
+            max_peak: 0.0,
+            overs: 0,
+            was_over: false,
+            measured_frames: 0,
+            interval_peak: None,
+            interval_overs: 0,
+        }
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    pub fn set_ceiling(&mut self, ceiling_dbtp: f32) {
+        self.ceiling = 10f32.powf(ceiling_dbtp / 20.0);
+    }
+
+    /// Restart integrated loudness, the maximum true peak and the over count.
+    pub fn reset(&mut self) {
+        self.histogram.iter_mut().for_each(|c| *c = 0);
+        self.max_peak = 0.0;
+        self.overs = 0;
+        self.measured_frames = 0;
+    }
+
+    pub fn process(&mut self, interleaved: &[f32]) {
+        for frame in interleaved.chunks_exact(2) {
+            let mut frame_peak = 0.0_f32;
+            for (channel, &sample) in self.channels.iter_mut().zip(frame) {
+                let weighted = channel
+                    .filters
+                    .iter_mut()
+                    .fold(f64::from(sample), |x, f| f.process(x));
+                self.block_energy += weighted * weighted;
+
+                channel.history.copy_within(..TAPS_PER_PHASE - 1, 1);
+                channel.history[0] = sample;
+                frame_peak = frame_peak.max(sample.abs());
+                for phase in &self.phases {
+                    let y: f32 = phase.iter().zip(&channel.history).map(|(c, x)| c * x).sum();
+                    frame_peak = frame_peak.max(y.abs());
+                }
+            }
+
+            let over = frame_peak > self.ceiling;
+            if over && !self.was_over {
+                self.overs += 1;
+                self.interval_overs += 1;
+            }
+            self.was_over = over;
+            self.block_peak = self.block_peak.max(frame_peak);
+            self.max_peak = self.max_peak.max(frame_peak);
+            self.interval_peak = Some(self.interval_peak.unwrap_or(0.0).max(frame_peak));
+            self.measured_frames += 1;
+
+            self.block_filled += 1;
+            if self.block_filled == self.block_frames {
+                self.finish_block();
+            }
+        }
+    }
+
+    fn finish_block(&mut self) {
+        if self.blocks.len() == SHORT_TERM_BLOCKS {
+            self.blocks.pop_front();
+        }
+        self.blocks.push_back((
+            self.block_energy / self.block_frames as f64,
+            self.block_peak,
+        ));
+        self.block_energy = 0.0;
+        self.block_peak = 0.0;
+        self.block_filled = 0;
+
+        // Gating blocks are 400 ms long and start every 100 ms.
+        if self.blocks.len() >= MOMENTARY_BLOCKS {
+            let loudness = lufs(self.window_energy(MOMENTARY_BLOCKS));
+            if loudness > ABSOLUTE_GATE_LUFS {
+                let bin = ((loudness - ABSOLUTE_GATE_LUFS) / HISTOGRAM_STEP) as usize;
+                self.histogram[bin.min(HISTOGRAM_BINS - 1)] += 1;
+            }
+        }
+    }
+
+    /// Mean energy of the newest `blocks` blocks (fewer while starting up).
+    fn window_energy(&self, blocks: usize) -> f64 {
+        let n = blocks.min(self.blocks.len()).max(1);
+        self.blocks.iter().rev().take(n).map(|b| b.0).sum::<f64>() / n as f64
+    }
+
+    fn window_lufs(&self, blocks: usize) -> f32 {
+        (lufs(self.window_energy(blocks)) as f32).max(SILENCE_LUFS)
+    }
+
+    pub fn integrated_lufs(&self) -> Option<f32> {
+        let bin_lufs = |i: usize| ABSOLUTE_GATE_LUFS + (i as f64 + 0.5) * HISTOGRAM_STEP;
+        let gated_mean = |from: usize| {
+            let (count, sum) = self.histogram[from..]
+                .iter()
+                .enumerate()
+                .fold((0_u64, 0.0), |(count, sum), (i, &n)| {
+                    (count + n, sum + n as f64 * energy(bin_lufs(from + i)))
+                });
+            (count > 0).then(|| sum / count as f64)
+        };
+        let relative_gate = lufs(gated_mean(0)?) + RELATIVE_GATE_LU;
+        let from = ((relative_gate - ABSOLUTE_GATE_LUFS) / HISTOGRAM_STEP)
+            .ceil()
+            .max(0.0) as usize;
+        gated_mean(from.min(HISTOGRAM_BINS)).map(|e| lufs(e) as f32)
+    }
+
+    pub fn reading(&self) -> LoudnessReading {
+        let peak = self
+            .blocks
+            .iter()
+            .rev()
+            .take(MOMENTARY_BLOCKS)
+            .fold(self.block_peak, |p, b| p.max(b.1));
+        LoudnessReading {
+            momentary_lufs: self.window_lufs(MOMENTARY_BLOCKS),
+            short_term_lufs: self.window_lufs(SHORT_TERM_BLOCKS),
+            integrated_lufs: self.integrated_lufs(),
+            true_peak_dbtp: to_dbtp(peak),
+            max_true_peak_dbtp: to_dbtp(self.max_peak),
+            overs: self.overs,
+            measured_secs: self.measured_frames as f64 / f64::from(self.sample_rate.max(1)),
+        }
+    }
+
+    /// Values for a health snapshot; restarts the interval peak and overs.
+    pub fn take_health(&mut self) -> LoudnessHealth {
+        let health = LoudnessHealth {
+            short_term_lufs: (!self.blocks.is_empty()).then(|| self.window_lufs(SHORT_TERM_BLOCKS)),
+            integrated_lufs: self.integrated_lufs(),
+            true_peak_dbtp: self.interval_peak.map(to_dbtp),
+            overs: self.interval_overs,
+        };
+        self.interval_peak = None;
+        self.interval_overs = 0;
+        health
+    }
+}
+
+// ── Shared meter ─────────────────────────────────────────────────────────────
+
+struct State {
+    config: LoudnessConfig,
+    tap: Option<ringbuf::HeapCons<f32>>,
+    meter: Option<LoudnessMeter>,
+}
+
+fn config_path() -> PathBuf {
+    PathBuf::from(crate::compute_app_data_dir()).join(CONFIG_FILE)
+}
+
+fn state() -> &'static Mutex<State> {
+    static STATE: OnceLock<Mutex<State>> = OnceLock::new();
+    STATE.get_or_init(|| {
+        let config = std::fs::read(config_path())
+            .ok()
+            .and_then(|bytes| serde_json::from_slice::<LoudnessConfig>(&bytes).ok())
+            .filter(|c| c.validate().is_ok())
+            .unwrap_or_default();
+        Mutex::new(State {
+            config,
+            tap: None,
+            meter: None,
+        })
+    })
+}
+
+/// Start metering the engine's program bus.
+pub fn attach(engine: &mut AudioEngine) {
+    let tap = engine.attach_loudness_tap();
+    state().lock().unwrap().tap = Some(tap);
+}
+
+pub fn get_config() -> LoudnessConfig {
+    state().lock().unwrap().config.clone()
+}
+
+/// Validate, save and apply to the running meter.
+pub fn set_config(config: LoudnessConfig) -> Result<(), String> {
+    config.validate()?;
+    let json = serde_json::to_vec_pretty(&config).map_err(|e| e.to_string())?;
+    std::fs::write(config_path(), json)
+        .map_err(|e| format!("Cannot save loudness settings: {e}"))?;
+    let mut state = state().lock().unwrap();
+    if let Some(meter) = state.meter.as_mut() {
+        meter.set_ceiling(config.true_peak_ceiling_dbtp);
+    }
+    state.config = config;
+    Ok(())
+}
+
+/// Meter everything the engine produced since the last call and return the
+/// current reading. A new output sample rate restarts the meter.
+pub fn poll(sample_rate: u32) -> Option<LoudnessReading> {
+    let mut state = state().lock().unwrap();
+    let State { config, tap, meter } = &mut *state;
+    let tap = tap.as_mut()?;
+    if meter.as_ref().map(LoudnessMeter::sample_rate) != Some(sample_rate) {
+        *meter = Some(LoudnessMeter::new(
+            sample_rate,
+            config.true_peak_ceiling_dbtp,
+        ));
+    }
+    let meter = meter.as_mut()?;
+    let mut block = [0.0_f32; 2048];
+    loop {
+        let n = tap.pop_slice(&mut block);
+        if n == 0 {
+            break;
+        }
+        meter.process(&block[..n - n % 2]);
+    }
+    Some(meter.reading())
+}
+
+pub fn latest() -> Option<LoudnessReading> {
+    state()
+        .lock()
+        .unwrap()
+        .meter
+        .as_ref()
+        .map(LoudnessMeter::reading)
+}
+
+pub fn reset() {
+    if let Some(meter) = state().lock().unwrap().meter.as_mut() {
+        meter.reset();
+    }
+}
+
+pub fn take_health() -> LoudnessHealth {
+    state()
+        .lock()
+        .unwrap()
+        .meter
+        .as_mut()
+        .map(LoudnessMeter::take_health)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stereo_sine(rate: u32, hz: f32, dbfs: f32, secs: f32) -> Vec<f32> {
+        let amplitude = 10f32.powf(dbfs / 20.0);
+        (0..(rate as f32 * secs) as usize)
+            .flat_map(|i| {
+                let s = amplitude * (std::f32::consts::TAU * hz * i as f32 / rate as f32).sin();
+                [s, s]
+            })
+            .collect()
+    }
+
+    #[test]
+    fn stereo_sine_at_minus_23_dbfs_reads_minus_23_lufs() {
+        // EBU Tech 3341 case 1: 1 kHz, -23 dBFS on both channels.
+        let mut meter = LoudnessMeter::new(48_000, -1.0);
+        meter.process(&stereo_sine(48_000, 997.0, -23.0, 10.0));
+        let reading = meter.reading();
+
+        for lufs in [
+            reading.momentary_lufs,
+            reading.short_term_lufs,
+            reading.integrated_lufs.unwrap(),
+        ] {
+            assert!((lufs + 23.0).abs() < 0.2, "{reading:?}");
+        }
+        assert!((reading.true_peak_dbtp + 23.0).abs() < 0.3, "{reading:?}");
+        assert_eq!(reading.overs, 0);
+    }
+
+    #[test]
+    fn counts_overs_and_gates_silence() {
+        let mut meter = LoudnessMeter::new(44_100, -1.0);
+        meter.process(&stereo_sine(44_100, 440.0, 0.0, 0.5));
+        meter.process(&vec![0.0; 44_100 * 2 * 5]);
+        let reading = meter.reading();
+        assert!(reading.overs > 0);
+        assert!(reading.max_true_peak_dbtp > -0.5);
+        assert_eq!(reading.momentary_lufs, SILENCE_LUFS);
+        // Silence is gated out: integrated stays at the tone's loudness.
+        assert!(reading.integrated_lufs.unwrap() > -5.0, "{reading:?}");
+
+        let health = meter.take_health();
+        assert_eq!(health.overs, reading.overs);
+        assert_eq!(meter.take_health().overs, 0);
+    }
+}
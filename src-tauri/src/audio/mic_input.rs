@@ -7,8 +7,12 @@
 /// Voice track recording writes raw samples to a temp WAV file via `hound`.
 use std::sync::{Arc, Mutex};
 
-use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use cpal::{
+    traits::{DeviceTrait, HostTrait, StreamTrait},
+    SampleFormat,
+};
 use hound::{WavSpec, WavWriter};
+use ringbuf::traits::{Observer as _, Producer as _};
 use serde::{Deserialize, Serialize};
 
 use crate::audio::dsp::{deesser::Deesser, reverb::Reverb};
@@ -90,6 +94,8 @@ pub fn list_input_devices() -> Vec<AudioDevice> {
 pub struct MicState {
     pub config: MicConfig,
     pub ptt_active: bool,
+    /// Mic held open without PTT (on-air toggle)
+    pub latched: bool,
     pub muted: bool,
     pub mic_level_l: f32,
     pub mic_level_r: f32,
@@ -98,6 +104,8 @@ pub struct MicState {
     pub reverb: Reverb,
     /// If recording, samples are written here
     pub wav_writer: Option<WavWriter<std::io::BufWriter<std::fs::File>>>,
+    /// Live-to-air feed into the engine's Voice FX channel (interleaved stereo)
+    pub live_prod: Option<ringbuf::HeapProd<f32>>,
 }
 
 impl MicState {
@@ -108,13 +116,20 @@ impl MicState {
             reverb: Reverb::new(sr),
             config,
             ptt_active: false,
+            latched: false,
             muted: false,
             mic_level_l: 0.0,
             mic_level_r: 0.0,
             recording: false,
             wav_writer: None,
+            live_prod: None,
         }
     }
+
+    /// Whether the mic should currently be heard on air.
+    pub fn on_air(&self) -> bool {
+        !self.muted && (self.latched || self.ptt_active)
+    }
 }
 
 // ── MicInput ──────────────────────────────────────────────────────────────────
@@ -148,6 +163,24 @@ impl MicInput {
         self.state.lock().unwrap().muted = muted;
     }
 
+    pub fn set_latched(&self, open: bool) {
+        self.state.lock().unwrap().latched = open;
+    }
+
+    pub fn is_latched(&self) -> bool {
+        self.state.lock().unwrap().latched
+    }
+
+    /// True when the input stream is running and the mic is open to air.
+    pub fn is_on_air(&self) -> bool {
+        self.stream.lock().unwrap().is_some() && self.state.lock().unwrap().on_air()
+    }
+
+    /// Attach (or detach) the engine's live input ring.
+    pub fn set_live_output(&self, prod: Option<ringbuf::HeapProd<f32>>) {
+        self.state.lock().unwrap().live_prod = prod;
+    }
+
     // ── Levels (for VU meter) ─────────────────────────────────────────────
 
     pub fn get_levels(&self) -> (f32, f32) {
@@ -167,7 +200,9 @@ impl MicInput {
 
     // ── Start / Stop ──────────────────────────────────────────────────────
 
-    pub fn start(&self) -> Result<(), String> {
+    /// Start capturing. When `output_sample_rate` is given, the device is opened
+    /// at that rate if it supports it so the live feed can go straight to air.
+    pub fn start(&self, output_sample_rate: Option<u32>) -> Result<(), String> {
         let host = cpal::default_host();
         let config_guard = self.state.lock().unwrap();
         let device_name = config_guard.config.device_name.clone();
@@ -184,14 +219,35 @@ impl MicInput {
         };
 
         let supported = device.default_input_config().map_err(|e| e.to_string())?;
+        let matched = output_sample_rate.and_then(|rate| {
+            device
+                .supported_input_configs()
+                .ok()?
+                .filter(|cfg| {
+                    cfg.sample_format() == SampleFormat::F32
+                        && cfg.min_sample_rate().0 <= rate
+                        && cfg.max_sample_rate().0 >= rate
+                })
+                .min_by_key(|cfg| cfg.channels())
+                .map(|cfg| cfg.with_sample_rate(cpal::SampleRate(rate)).config())
+        });
+        let live_ok = matched.is_some();
+        if output_sample_rate.is_some() && !live_ok {
+            log::warn!(
+                "Input device cannot run at {} Hz; live mic to air disabled",
+                output_sample_rate.unwrap_or_default()
+            );
+        }
+        let stream_config = matched.unwrap_or_else(|| supported.config());
+        let channels = stream_config.channels as usize;
 
         let state = Arc::clone(&self.state);
 
         let stream = device
             .build_input_stream(
-                &supported.config(),
+                &stream_config,
                 move |data: &[f32], _info: &cpal::InputCallbackInfo| {
-                    Self::audio_callback(data, &state);
+                    Self::audio_callback(data, channels, live_ok, &state);
                 },
                 |e| log::error!("Mic input error: {e}"),
                 None,
@@ -209,9 +265,9 @@ impl MicInput {
         log::info!("Microphone input stopped");
     }
 
-    fn audio_callback(data: &[f32], state: &Arc<Mutex<MicState>>) {
+    fn audio_callback(data: &[f32], channels: usize, live_ok: bool, state: &Arc<Mutex<MicState>>) {
         let mut st = state.lock().unwrap();
-        let channels = st.config.channels as usize;
+        let channels = channels.max(1);
         let sr = st.config.sample_rate as f32;
 
         // Simple peak envelope for VU
@@ -221,6 +277,7 @@ impl MicInput {
 
         // Gate: if muted or PTT not active (when PTT enabled), output silence
         let pass = !st.muted && (!st.config.ptt_enabled || st.ptt_active);
+        let on_air = live_ok && st.on_air();
 
         // Noise gate (simple threshold)
         let gate_thr = db_to_linear(st.config.gate_threshold_db);
@@ -235,7 +292,7 @@ impl MicInput {
             }
         }
 
-        if !pass || !gate_pass {
+        if !(pass || on_air) || !gate_pass {
             // Silence — no further processing needed for voice chain
             drop(st);
             return;
@@ -255,6 +312,19 @@ impl MicInput {
             st.reverb.process(frame.as_mut_slice());
         }
 
+        // Live feed: whole frames only so the engine never sees a split L/R pair.
+        if on_air {
+            if let Some(prod) = st.live_prod.as_mut() {
+                for frame in &frames {
+                    if prod.vacant_len() < 2 {
+                        break;
+                    }
+                    let _ = prod.try_push(frame[0]);
+                    let _ = prod.try_push(frame[1]);
+                }
+            }
+        }
+
         let _ = sr; // used for tick-based envelope calculations in future
     }
 
@@ -78,6 +78,16 @@ impl Mixer {
         }
     }
 
+    /// Gain a channel's signal receives on the master bus (fader × mute × master).
+    pub fn effective_gain(&self, id: DeckId) -> f32 {
+        let strip = self.channel(id);
+        if strip.muted {
+            0.0
+        } else {
+            strip.fader * self.master_gain
+        }
+    }
+
     /// Sum six channel buffers into `master_buf` (in-place add with gain scaling).
     ///
     /// Each channel buffer must be the same length as `master_buf` and is
@@ -116,6 +126,35 @@ impl Mixer {
         }
     }
 
+    /// Add one channel on top of an already mixed bus, scaled by `bus_gain`
+    /// and the master gain. Fader, mute and VU behave as in `mix_into`.
+    pub fn add_channel(&mut self, dest: &mut [f32], src: &[f32], id: DeckId, bus_gain: f32) {
+        let gain = self.master_gain * bus_gain;
+        let strip = self.channel_mut(id);
+        if strip.muted {
+            strip.vu_left_db = -96.0;
+            strip.vu_right_db = -96.0;
+            return;
+        }
+
+        let fader = strip.fader;
+        let mut peak_l = 0.0_f32;
+        let mut peak_r = 0.0_f32;
+
+        for (i, (&s, d)) in src.iter().zip(dest.iter_mut()).enumerate() {
+            let scaled = s * fader;
+            *d += scaled * gain;
+            if i % 2 == 0 {
+                peak_l = peak_l.max(scaled.abs());
+            } else {
+                peak_r = peak_r.max(scaled.abs());
+            }
+        }
+
+        strip.vu_left_db = linear_to_db(peak_l);
+        strip.vu_right_db = linear_to_db(peak_r);
+    }
+
     /// Apply channel gain + mute, accumulate into `dest`, update VU readings.
     #[inline]
     fn accumulate(dest: &mut [f32], src: &[f32], ch: &mut ChannelStrip) {
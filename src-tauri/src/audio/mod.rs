@@ -1,9 +1,20 @@
 pub mod analyzer;
+pub mod cart_wall;
+pub mod controls;
 pub mod crossfade;
 pub mod deck;
 pub mod decoder;
 pub mod device_manager;
 pub mod dsp;
+pub mod ducking;
 pub mod engine;
+pub mod loudness_meter;
 pub mod mic_input;
 pub mod mixer;
+pub mod multitrack;
+pub mod remote_stream;
+pub mod reverse;
+pub mod sfx_player;
+pub mod snapshot;
+pub mod spectrum;
+pub mod stem_mix;
@@ -0,0 +1,437 @@
+/// Multitrack capture: Deck A, Deck B, the mic/voice FX channel and the
+/// master bus recorded to separate files at the same time, so a show can be
+/// remixed or edited later.
+///
+/// The audio callback copies each tapped bus into its own ring
+/// (`MultitrackTap`); a writer thread per bus drains the ring into a
+/// `RecordingWriter`. All taps are installed by one engine command, so every
+/// file starts on the same block and the tracks stay sample-aligned.
+/// Channel buses are captured after their DSP chain and mic ducking, before
+/// the fader and crossfader; the master is the program bus as the encoders
+/// get it.
+///
+/// A take goes into its own folder: `{output_dir}/{date}-{time}/deck-a.flac`,
+/// with the chosen chapter sidecar (`master.cue` or `master.chapters.json`)
+/// next to the master track once the take stops.
+use std::{
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, Mutex, OnceLock,
+    },
+    thread::JoinHandle,
+    time::Duration,
+};
+
+use ringbuf::traits::{Consumer as _, Observer as _, Producer as _};
+use serde::{Deserialize, Serialize};
+
+use super::engine::AudioEngine;
+use crate::stream::{
+    encoder_manager::Codec,
+    recording_chapters::{self, RecordingSidecar},
+    recording_writer::{self, RecordingWriter},
+};
+
+const CONFIG_FILE: &str = "multitrack.json";
+/// How often an idle writer thread checks its ring
+const WRITER_POLL: Duration = Duration::from_millis(20);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RecordBus {
+    DeckA,
+    DeckB,
+    /// Mic through the voice FX chain
+    VoiceFx,
+    /// Program bus after master processing
+    Master,
+}
+
+impl RecordBus {
+    fn file_stem(self) -> &'static str {
+        match self {
+            RecordBus::DeckA => "deck-a",
+            RecordBus::DeckB => "deck-b",
+            RecordBus::VoiceFx => "mic",
+            RecordBus::Master => "master",
+        }
+    }
+}
+
+/// Control-side end of one bus tap: its ring and dropped-frame counter.
+pub type BusTap = (ringbuf::HeapCons<f32>, Arc<AtomicU64>);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MultitrackConfig {
+    /// Default: `recordings/multitrack` in the app data dir
+    pub output_dir: Option<String>,
+    /// WAV, FLAC, MP3 or Opus
+    pub codec: Codec,
+    /// For MP3 and Opus
+    pub bitrate_kbps: u32,
+    pub buses: Vec<RecordBus>,
+    /// Chapter list written next to the master track
+    pub sidecar: RecordingSidecar,
+}
+
+impl Default for MultitrackConfig {
+    fn default() -> Self {
+        Self {
+            output_dir: None,
+            codec: Codec::Flac,
+            bitrate_kbps: 192,
+            buses: vec![
+                RecordBus::DeckA,
+                RecordBus::DeckB,
+                RecordBus::VoiceFx,
+                RecordBus::Master,
+            ],
+            sidecar: RecordingSidecar::Cue,
+        }
+    }
+}
+
+impl MultitrackConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        recording_writer::extension(&self.codec)?;
+        if self.buses.is_empty() {
+            return Err("Pick at least one bus to record".into());
+        }
+        if !(32..=320).contains(&self.bitrate_kbps) {
+            return Err("Multitrack bitrate must be between 32 and 320 kbps".into());
+        }
+        Ok(())
+    }
+}
+
+/// Audio-thread end of a take: one interleaved stereo ring per bus.
+pub struct MultitrackTap {
+    feeds: Vec<(RecordBus, ringbuf::HeapProd<f32>, Arc<AtomicU64>)>,
+}
+
+impl MultitrackTap {
+    pub fn new(feeds: Vec<(RecordBus, ringbuf::HeapProd<f32>, Arc<AtomicU64>)>) -> Self {
+        Self { feeds }
+    }
+
+    /// Append each bus's interleaved stereo block. A block that does not fit
+    /// is dropped whole and counted; the writer has fallen behind.
+    ///
+    /// Called on the real-time audio thread.
+    pub fn feed<'a>(&mut self, block: impl Fn(RecordBus) -> &'a [f32]) {
+        for (bus, prod, dropped) in &mut self.feeds {
+            let stereo = block(*bus);
+            if prod.vacant_len() < stereo.len() {
+                dropped.fetch_add((stereo.len() / 2) as u64, Ordering::Relaxed);
+                continue;
+            }
+            prod.push_slice(stereo);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MultitrackTrack {
+    pub bus: RecordBus,
+    pub path: String,
+    pub bytes_written: u64,
+    /// Frames lost because the writer fell behind
+    pub dropped_frames: u64,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct MultitrackStatus {
+    pub recording: bool,
+    /// Unix ms
+    pub started_at: Option<i64>,
+    pub folder: Option<String>,
+    pub sample_rate: Option<u32>,
+    pub tracks: Vec<MultitrackTrack>,
+}
+
+struct Track {
+    bus: RecordBus,
+    path: PathBuf,
+    bytes: Arc<AtomicU64>,
+    dropped: Arc<AtomicU64>,
+    error: Arc<Mutex<Option<String>>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Track {
+    fn status(&self) -> MultitrackTrack {
+        MultitrackTrack {
+            bus: self.bus,
+            path: self.path.to_string_lossy().into_owned(),
+            bytes_written: self.bytes.load(Ordering::Relaxed),
+            dropped_frames: self.dropped.load(Ordering::Relaxed),
+            error: self.error.lock().unwrap().clone(),
+        }
+    }
+}
+
+struct Take {
+    started_at: i64,
+    folder: PathBuf,
+    sample_rate: u32,
+    sidecar: RecordingSidecar,
+    stop: Arc<AtomicBool>,
+    tracks: Vec<Track>,
+}
+
+struct State {
+    config: MultitrackConfig,
+    take: Option<Take>,
+    /// Tracks of the last finished take, for the status view
+    last: Vec<MultitrackTrack>,
+}
+
+fn config_path() -> PathBuf {
+    PathBuf::from(crate::compute_app_data_dir()).join(CONFIG_FILE)
+}
+
+fn state() -> &'static Mutex<State> {
+    static STATE: OnceLock<Mutex<State>> = OnceLock::new();
+    STATE.get_or_init(|| {
+        let config = std::fs::read(config_path())
+            .ok()
+            .and_then(|bytes| serde_json::from_slice::<MultitrackConfig>(&bytes).ok())
+            .filter(|c| c.validate().is_ok())
+            .unwrap_or_default();
+        Mutex::new(State {
+            config,
+            take: None,
+            last: Vec::new(),
+        })
+    })
+}
+
+pub fn get_config() -> MultitrackConfig {
+    state().lock().unwrap().config.clone()
+}
+
+/// Validate and save; applies from the next take.
+pub fn set_config(config: MultitrackConfig) -> Result<(), String> {
+    config.validate()?;
+    let json = serde_json::to_vec_pretty(&config).map_err(|e| e.to_string())?;
+    std::fs::write(config_path(), json)
+        .map_err(|e| format!("Cannot save multitrack settings: {e}"))?;
+    state().lock().unwrap().config = config;
+    Ok(())
+}
+
+pub fn status() -> MultitrackStatus {
+    let state = state().lock().unwrap();
+    match &state.take {
+        Some(take) => MultitrackStatus {
+            recording: true,
+            started_at: Some(take.started_at),
+            folder: Some(take.folder.to_string_lossy().into_owned()),
+            sample_rate: Some(take.sample_rate),
+            tracks: take.tracks.iter().map(Track::status).collect(),
+        },
+        None => MultitrackStatus {
+            tracks: state.last.clone(),
+            ..Default::default()
+        },
+    }
+}
+
+/// Open one file per configured bus and start tapping the engine.
+pub fn start(engine: &mut AudioEngine) -> Result<MultitrackStatus, String> {
+    let mut state = state().lock().unwrap();
+    if state.take.is_some() {
+        return Err("A multitrack recording is already running".into());
+    }
+    let config = state.config.clone();
+    let ext = recording_writer::extension(&config.codec)?;
+    let sample_rate = engine.output_sample_rate();
+    let now = chrono::Local::now();
+    let root = config
+        .output_dir
+        .as_deref()
+        .filter(|d| !d.trim().is_empty())
+        .map(PathBuf::from)
+        .unwrap_or_else(|| {
+            Path::new(&crate::compute_app_data_dir())
+                .join("recordings")
+                .join("multitrack")
+        });
+    let folder = root.join(now.format("%Y%m%d-%H%M%S").to_string());
+    std::fs::create_dir_all(&folder)
+        .map_err(|e| format!("Cannot create {}: {e}", folder.display()))?;
+
+    let mut buses: Vec<RecordBus> = Vec::new();
+    for bus in &config.buses {
+        if !buses.contains(bus) {
+            buses.push(*bus);
+        }
+    }
+    let writers = buses
+        .iter()
+        .map(|&bus| {
+            let path = folder.join(bus.file_stem()).with_extension(ext);
+            RecordingWriter::create(&path, &config.codec, sample_rate, 2, config.bitrate_kbps)
+                .map(|writer| (bus, path, writer))
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+
+    let stop = Arc::new(AtomicBool::new(false));
+    let taps = engine.attach_multitrack(&buses)?;
+    let tracks = writers
+        .into_iter()
+        .zip(taps)
+        .map(|((bus, path, writer), (cons, dropped))| {
+            let bytes = Arc::new(AtomicU64::new(0));
+            let error = Arc::new(Mutex::new(None));
+            let thread = spawn_writer(
+                bus,
+                writer,
+                cons,
+                stop.clone(),
+                bytes.clone(),
+                error.clone(),
+            )?;
+            Ok(Track {
+                bus,
+                path,
+                bytes,
+                dropped,
+                error,
+                thread: Some(thread),
+            })
+        })
+        .collect::<Result<Vec<_>, String>>();
+    let tracks = match tracks {
+        Ok(tracks) => tracks,
+        Err(e) => {
+            engine.detach_multitrack();
+            stop.store(true, Ordering::Release);
+            return Err(e);
+        }
+    };
+
+    log::info!(
+        "Multitrack recording started: {} ({} buses)",
+        folder.display(),
+        buses.len()
+    );
+    state.take = Some(Take {
+        started_at: now.timestamp_millis(),
+        folder,
+        sample_rate,
+        sidecar: config.sidecar,
+        stop,
+        tracks,
+    });
+    drop(state);
+    Ok(status())
+}
+
+/// Remove the taps, let the writers drain and close their files.
+/// Blocks until every file is finalised; the engine is only locked to
+/// remove the taps.
+pub fn stop(engine: &Mutex<AudioEngine>) -> MultitrackStatus {
+    let take = state().lock().unwrap().take.take();
+    let Some(mut take) = take else {
+        return status();
+    };
+    engine.lock().unwrap().detach_multitrack();
+    take.stop.store(true, Ordering::Release);
+    for track in &mut take.tracks {
+        if let Some(thread) = track.thread.take() {
+            if thread.join().is_err() {
+                *track.error.lock().unwrap() = Some("Writer thread panicked".into());
+            }
+        }
+    }
+    let ended_at = chrono::Utc::now().timestamp_millis();
+    if let Some(master) = take.tracks.iter().find(|t| t.bus == RecordBus::Master) {
+        if let Err(e) = recording_chapters::write_sidecar(
+            take.sidecar,
+            &master.path,
+            "",
+            take.started_at,
+            ended_at,
+        ) {
+            log::warn!("{e}");
+        }
+    }
+    log::info!("Multitrack recording stopped: {}", take.folder.display());
+    let tracks: Vec<MultitrackTrack> = take.tracks.iter().map(Track::status).collect();
+    state().lock().unwrap().last = tracks.clone();
+    MultitrackStatus {
+        recording: false,
+        started_at: Some(take.started_at),
+        folder: Some(take.folder.to_string_lossy().into_owned()),
+        sample_rate: Some(take.sample_rate),
+        tracks,
+    }
+}
+
+fn spawn_writer(
+    bus: RecordBus,
+    mut writer: RecordingWriter,
+    mut cons: ringbuf::HeapCons<f32>,
+    stop: Arc<AtomicBool>,
+    bytes: Arc<AtomicU64>,
+    error: Arc<Mutex<Option<String>>>,
+) -> Result<JoinHandle<()>, String> {
+    std::thread::Builder::new()
+        .name(format!("rec:{}", bus.file_stem()))
+        .spawn(move || {
+            let mut block = vec![0.0_f32; 8192];
+            let mut failed = false;
+            loop {
+                // Read the flag first so audio pushed before the detach is
+                // still drained.
+                let stopping = stop.load(Ordering::Acquire);
+                let n = cons.pop_slice(&mut block);
+                if n > 0 {
+                    if !failed {
+                        if let Err(e) = writer.write(&block[..n]) {
+                            log::error!("Multitrack {bus:?}: {e}");
+                            *error.lock().unwrap() = Some(e);
+                            failed = true;
+                        }
+                        bytes.store(writer.bytes_written(), Ordering::Relaxed);
+                    }
+                    continue;
+                }
+                if stopping {
+                    break;
+                }
+                std::thread::sleep(WRITER_POLL);
+            }
+            if let Err(e) = writer.finish() {
+                log::error!("Multitrack {bus:?}: {e}");
+                error.lock().unwrap().get_or_insert(e);
+            }
+        })
+        .map_err(|e| format!("Failed to spawn multitrack writer: {e}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ringbuf::{traits::Split, HeapRb};
+
+    #[test]
+    fn tap_drops_whole_blocks_when_a_writer_falls_behind() {
+        let (prod, mut cons) = HeapRb::<f32>::new(6).split();
+        let dropped = Arc::new(AtomicU64::new(0));
+        let mut tap = MultitrackTap::new(vec![(RecordBus::Master, prod, dropped.clone())]);
+        let block = [0.1, 0.2, 0.3, 0.4];
+
+        tap.feed(|_| &block[..]);
+        tap.feed(|_| &block[..]);
+
+        assert_eq!(dropped.load(Ordering::Relaxed), 2);
+        let mut out = [0.0; 6];
+        assert_eq!(cons.pop_slice(&mut out), 4);
+        assert_eq!(out[..4], block);
+    }
+}
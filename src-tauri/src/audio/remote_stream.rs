@@ -0,0 +1,445 @@
+/// `audio/remote_stream.rs` — HTTP(S) audio sources for decks and aux channels
+///
+/// A URL "path" makes `spawn_decoder` hand off here. The stream is fetched
+/// with a blocking client on the decoder thread and fed through Symphonia
+/// like a file, so the rest of the deck cannot tell the difference apart
+/// from three things:
+///
+/// - **No seeking.** The stream only runs forwards; decks reject seeks.
+/// - **ICY metadata.** Shoutcast/Icecast interleave `StreamTitle` blocks
+///   every `icy-metaint` bytes. They are stripped before the demuxer and the
+///   latest title is kept in `RemoteStreamStatus` for the UI and encoders.
+/// - **Reconnects.** A live stream that drops or ends is reconnected with
+///   backoff; audio carries on once the new connection decodes. A response
+///   with a `Content-Length` (a plain file over HTTP) ends normally instead.
+use std::{
+    io::{self, Read},
+    sync::{
+        atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use ringbuf::{traits::Split, HeapRb};
+use serde::{Deserialize, Serialize};
+use symphonia::core::{
+    codecs::{Decoder, DecoderOptions, CODEC_TYPE_NULL},
+    formats::{FormatOptions, FormatReader},
+    io::{MediaSourceStream, ReadOnlySource},
+    meta::MetadataOptions,
+    probe::Hint,
+};
+
+use super::decoder::{pump_packets, DecoderHandle, RING_CAPACITY};
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+/// A blocking body applies this to each read, so a stalled server counts as
+/// a dropped connection rather than hanging the decoder thread.
+const READ_TIMEOUT: Duration = Duration::from_secs(15);
+/// Audio buffered before the load returns, so play does not start on an underrun
+const PREBUFFER_MS: u64 = 1_500;
+const PREBUFFER_TIMEOUT: Duration = Duration::from_secs(8);
+/// Wait before reconnect attempt N (0-based); later attempts reuse the last value.
+const RECONNECT_BACKOFF_SECS: [u64; 5] = [1, 2, 5, 10, 30];
+/// Consecutive failed reconnects before the deck gives up (~5 minutes).
+const MAX_RECONNECT_ATTEMPTS: u32 = 12;
+
+pub fn is_remote_url(path: &str) -> bool {
+    let lower = path.trim_start().to_ascii_lowercase();
+    lower.starts_with("http://") || lower.starts_with("https://")
+}
+
+/// Live view of a remote stream, shared between its decoder thread and the engine.
+#[derive(Default)]
+pub struct RemoteStreamStatus {
+    title: Mutex<Option<String>>,
+    station: Mutex<Option<String>>,
+    connected: AtomicBool,
+    reconnects: AtomicU32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RemoteStreamInfo {
+    /// Latest ICY `StreamTitle`
+    pub title: Option<String>,
+    /// `icy-name` header
+    pub station: Option<String>,
+    /// False while reconnecting
+    pub connected: bool,
+    pub reconnects: u32,
+}
+
+impl RemoteStreamStatus {
+    pub fn info(&self) -> RemoteStreamInfo {
+        RemoteStreamInfo {
+            title: self.title.lock().unwrap().clone(),
+            station: self.station.lock().unwrap().clone(),
+            connected: self.connected.load(Ordering::Relaxed),
+            reconnects: self.reconnects.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Split an ICY title into `(artist, title)`. Stations send "Artist - Title"
+/// by convention; anything else is all title.
+pub fn split_stream_title(stream_title: &str) -> (String, String) {
+    match stream_title.split_once(" - ") {
+        Some((artist, title)) if !artist.trim().is_empty() => {
+            (artist.trim().to_string(), title.trim().to_string())
+        }
+        _ => (String::new(), stream_title.trim().to_string()),
+    }
+}
+
+/// Pull `StreamTitle` out of an ICY metadata block
+/// (`StreamTitle='Artist - Title';StreamUrl='...';`, NUL padded).
+fn parse_stream_title(block: &[u8]) -> Option<String> {
+    let text = String::from_utf8_lossy(block);
+    let text = text.trim_end_matches('\0');
+    let start = text.find("StreamTitle='")? + "StreamTitle='".len();
+    let rest = &text[start..];
+    // Titles may contain apostrophes, so end at the field terminator.
+    let end = rest.find("';").or_else(|| rest.rfind('\''))?;
+    let title = rest[..end].trim();
+    (!title.is_empty()).then(|| title.to_string())
+}
+
+/// Strips ICY metadata blocks out of the byte stream and records the title.
+struct IcyReader<R> {
+    inner: R,
+    metaint: Option<usize>,
+    until_meta: usize,
+    status: Arc<RemoteStreamStatus>,
+}
+
+impl<R: Read> IcyReader<R> {
+    fn new(inner: R, metaint: Option<usize>, status: Arc<RemoteStreamStatus>) -> Self {
+        Self {
+            inner,
+            metaint,
+            until_meta: metaint.unwrap_or(0),
+            status,
+        }
+    }
+
+    fn read_metadata(&mut self) -> io::Result<()> {
+        let mut len = [0u8; 1];
+        self.inner.read_exact(&mut len)?;
+        if len[0] == 0 {
+            return Ok(());
+        }
+        let mut block = vec![0u8; len[0] as usize * 16];
+        self.inner.read_exact(&mut block)?;
+        if let Some(title) = parse_stream_title(&block) {
+            *self.status.title.lock().unwrap() = Some(title);
+        }
+        Ok(())
+    }
+}
+
+impl<R: Read> Read for IcyReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let Some(metaint) = self.metaint else {
+            return self.inner.read(buf);
+        };
+        if self.until_meta == 0 {
+            self.read_metadata()?;
+            self.until_meta = metaint;
+        }
+        let want = buf.len().min(self.until_meta);
+        let n = self.inner.read(&mut buf[..want])?;
+        self.until_meta -= n;
+        Ok(n)
+    }
+}
+
+/// One open HTTP response, probed and ready to decode.
+struct Connection {
+    format: Box<dyn FormatReader>,
+    decoder: Box<dyn Decoder>,
+    track_id: u32,
+    n_channels: usize,
+    sample_rate: u32,
+    /// Sized response: its end is the end of the track, not a drop
+    finite: bool,
+    total_frames: Option<u64>,
+}
+
+/// Symphonia hint from the content type, falling back to the URL extension.
+fn format_hint(content_type: Option<&str>, url: &str) -> Option<String> {
+    let from_type = content_type.and_then(|ct| {
+        let mime = ct.split(';').next()?.trim().to_ascii_lowercase();
+        let ext = match mime.as_str() {
+            "audio/mpeg" | "audio/mp3" | "audio/mpeg3" => "mp3",
+            "audio/aac" | "audio/aacp" | "audio/x-aac" => "aac",
+            "audio/mp4" | "audio/x-m4a" => "m4a",
+            "audio/ogg" | "application/ogg" | "audio/opus" => "ogg",
+            "audio/flac" | "audio/x-flac" => "flac",
+            "audio/wav" | "audio/x-wav" | "audio/wave" => "wav",
+            _ => return None,
+        };
+        Some(ext.to_string())
+    });
+    from_type.or_else(|| {
+        let path = url.split(['?', '#']).next().unwrap_or(url);
+        let last = path.rsplit('/').next()?;
+        let (_, ext) = last.rsplit_once('.')?;
+        (!ext.is_empty() && ext.len() <= 4).then(|| ext.to_ascii_lowercase())
+    })
+}
+
+fn connect(url: &str, status: &Arc<RemoteStreamStatus>) -> Result<Connection, String> {
+    let client = reqwest::blocking::Client::builder()
+        .connect_timeout(CONNECT_TIMEOUT)
+        .timeout(READ_TIMEOUT)
+        .user_agent(concat!("DesiZoneBroadcaster/", env!("CARGO_PKG_VERSION")))
+        .build()
+        .map_err(|e| format!("HTTP client: {e}"))?;
+    let response = client
+        .get(url)
+        .header("Icy-MetaData", "1")
+        .send()
+        .and_then(|r| r.error_for_status())
+        .map_err(|e| format!("Cannot open {url}: {e}"))?;
+
+    let header = |name: &str| {
+        response
+            .headers()
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .map(|v| v.trim().to_string())
+    };
+    let metaint = header("icy-metaint")
+        .and_then(|v| v.parse::<usize>().ok())
+        .filter(|n| *n > 0);
+    if let Some(name) = header("icy-name").filter(|n| !n.is_empty()) {
+        *status.station.lock().unwrap() = Some(name);
+    }
+    let finite = metaint.is_none() && response.content_length().is_some();
+
+    let mut hint = Hint::new();
+    if let Some(ext) = format_hint(header("content-type").as_deref(), url) {
+        hint.with_extension(&ext);
+    }
+    let reader = IcyReader::new(response, metaint, Arc::clone(status));
+    let mss = MediaSourceStream::new(Box::new(ReadOnlySource::new(reader)), Default::default());
+    let probed = symphonia::default::get_probe()
+        .format(
+            &hint,
+            mss,
+            &FormatOptions::default(),
+            &MetadataOptions::default(),
+        )
+        .map_err(|e| format!("Probe failed: {e}"))?;
+    let track = probed
+        .format
+        .tracks()
+        .iter()
+        .find(|t| t.codec_params.codec != CODEC_TYPE_NULL)
+        .ok_or("No audio track found")?
+        .clone();
+    let decoder = symphonia::default::get_codecs()
+        .make(&track.codec_params, &DecoderOptions::default())
+        .map_err(|e| format!("Codec init: {e}"))?;
+
+    Ok(Connection {
+        format: probed.format,
+        decoder,
+        track_id: track.id,
+        n_channels: track.codec_params.channels.map(|c| c.count()).unwrap_or(2),
+        sample_rate: track.codec_params.sample_rate.unwrap_or(44100),
+        finite,
+        total_frames: track.codec_params.n_frames.filter(|_| finite),
+    })
+}
+
+/// Connect to `url` and start streaming it into a decoder ring. Blocks for
+/// the connection and up to `PREBUFFER_TIMEOUT` of buffering, so call it off
+/// the engine lock.
+pub fn spawn(url: &str, seek_ms: Option<u64>) -> Result<DecoderHandle, String> {
+    if seek_ms.is_some() {
+        return Err("Remote streams cannot seek".into());
+    }
+    let status = Arc::new(RemoteStreamStatus::default());
+    let conn = connect(url, &status)?;
+    status.connected.store(true, Ordering::Relaxed);
+
+    let rb = HeapRb::<f32>::new(RING_CAPACITY);
+    let (mut producer, consumer) = rb.split();
+    let stop_flag = Arc::new(AtomicBool::new(false));
+    let decode_done = Arc::new(AtomicBool::new(false));
+    let decode_failed = Arc::new(AtomicBool::new(false));
+    let frames_written = Arc::new(AtomicU64::new(0));
+    let total_frames = Arc::new(AtomicU64::new(conn.total_frames.unwrap_or(0)));
+    let sample_rate = conn.sample_rate;
+
+    let handle = DecoderHandle {
+        consumer,
+        stop_flag: Arc::clone(&stop_flag),
+        decode_done: Arc::clone(&decode_done),
+        decode_failed: Arc::clone(&decode_failed),
+        frames_written: Arc::clone(&frames_written),
+        total_frames,
+        sample_rate,
+        channels: conn.n_channels as u32,
+        remote: Some(Arc::clone(&status)),
+    };
+
+    let url_t = url.to_string();
+    let decode_done_t = Arc::clone(&decode_done);
+    let decode_failed_t = Arc::clone(&decode_failed);
+    let fw_t = Arc::clone(&frames_written);
+    thread::Builder::new()
+        .name("dec:stream".into())
+        .spawn(move || {
+            if let Err(e) = stream_loop(&url_t, conn, &mut producer, &stop_flag, &fw_t, &status) {
+                log::warn!("Stream {url_t} stopped: {e}");
+                decode_failed_t.store(true, Ordering::Relaxed);
+            }
+            status.connected.store(false, Ordering::Relaxed);
+            decode_done_t.store(true, Ordering::Relaxed);
+        })
+        .map_err(|e| format!("Failed to spawn decoder thread: {e}"))?;
+
+    let target = sample_rate as u64 * PREBUFFER_MS / 1000;
+    let deadline = Instant::now() + PREBUFFER_TIMEOUT;
+    while frames_written.load(Ordering::Relaxed) < target
+        && !decode_done.load(Ordering::Relaxed)
+        && Instant::now() < deadline
+    {
+        thread::sleep(Duration::from_millis(20));
+    }
+    if decode_done.load(Ordering::Relaxed) && frames_written.load(Ordering::Relaxed) == 0 {
+        return Err(format!("{url} ended before any audio arrived"));
+    }
+    Ok(handle)
+}
+
+/// Sleep in short steps; false if stopped meanwhile.
+fn sleep_unless_stopped(total: Duration, stop_flag: &AtomicBool) -> bool {
+    let deadline = Instant::now() + total;
+    while Instant::now() < deadline {
+        if stop_flag.load(Ordering::Relaxed) {
+            return false;
+        }
+        thread::sleep(Duration::from_millis(100));
+    }
+    !stop_flag.load(Ordering::Relaxed)
+}
+
+fn stream_loop(
+    url: &str,
+    mut conn: Connection,
+    producer: &mut ringbuf::HeapProd<f32>,
+    stop_flag: &AtomicBool,
+    frames_written: &AtomicU64,
+    status: &Arc<RemoteStreamStatus>,
+) -> Result<(), String> {
+    let sample_rate = conn.sample_rate;
+    let mut failures: u32 = 0;
+    loop {
+        status.connected.store(true, Ordering::Relaxed);
+        let before = frames_written.load(Ordering::Relaxed);
+        let result = pump_packets(
+            conn.format.as_mut(),
+            conn.decoder.as_mut(),
+            conn.track_id,
+            conn.n_channels,
+            producer,
+            stop_flag,
+            frames_written,
+        );
+        status.connected.store(false, Ordering::Relaxed);
+        if stop_flag.load(Ordering::Relaxed) {
+            return Ok(());
+        }
+        match result {
+            // Reconnecting a sized download would restart it from the top.
+            Ok(()) if conn.finite => return Ok(()),
+            Err(e) if conn.finite => return Err(e),
+            Ok(()) => log::warn!("Stream {url} ended; reconnecting"),
+            Err(e) => log::warn!("Stream {url} dropped ({e}); reconnecting"),
+        }
+        if frames_written.load(Ordering::Relaxed) > before {
+            failures = 0;
+        }
+
+        conn = loop {
+            let wait =
+                RECONNECT_BACKOFF_SECS[(failures as usize).min(RECONNECT_BACKOFF_SECS.len() - 1)];
+            if !sleep_unless_stopped(Duration::from_secs(wait), stop_flag) {
+                return Ok(());
+            }
+            match connect(url, status) {
+                // The deck resamples at a fixed source rate for the whole track.
+                Ok(next) if next.sample_rate != sample_rate => {
+                    return Err(format!(
+                        "sample rate changed from {sample_rate} to {} Hz",
+                        next.sample_rate
+                    ));
+                }
+                Ok(next) => {
+                    status.reconnects.fetch_add(1, Ordering::Relaxed);
+                    break next;
+                }
+                Err(e) => {
+                    failures += 1;
+                    if failures >= MAX_RECONNECT_ATTEMPTS {
+                        return Err(format!("gave up after {failures} reconnects: {e}"));
+                    }
+                    log::warn!("Stream {url} reconnect {failures} failed: {e}");
+                }
+            }
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn icy_reader_strips_metadata_and_keeps_title() {
+        let mut raw = b"abcd".to_vec();
+        let meta = b"StreamTitle='Ali Zafar - Channo';StreamUrl='';";
+        let blocks = meta.len().div_ceil(16);
+        raw.push(blocks as u8);
+        raw.extend_from_slice(meta);
+        raw.resize(raw.len() + blocks * 16 - meta.len(), 0);
+        raw.extend_from_slice(b"efgh");
+        raw.push(0);
+        raw.extend_from_slice(b"ij");
+
+        let status = Arc::new(RemoteStreamStatus::default());
+        let mut reader = IcyReader::new(raw.as_slice(), Some(4), Arc::clone(&status));
+        let mut audio = Vec::new();
+        reader.read_to_end(&mut audio).unwrap();
+        assert_eq!(audio, b"abcdefghij");
+        assert_eq!(status.info().title.as_deref(), Some("Ali Zafar - Channo"));
+    }
+
+    #[test]
+    fn titles_with_apostrophes_and_splitting() {
+        let block = b"StreamTitle='Guns N' Roses - Don't Cry';\0\0\0";
+        let title = parse_stream_title(block).unwrap();
+        assert_eq!(title, "Guns N' Roses - Don't Cry");
+        assert_eq!(
+            split_stream_title(&title),
+            ("Guns N' Roses".to_string(), "Don't Cry".to_string())
+        );
+        assert_eq!(split_stream_title("Station ID").0, "");
+        assert_eq!(parse_stream_title(b"StreamTitle='';"), None);
+    }
+
+    #[test]
+    fn hint_prefers_content_type() {
+        let url = "https://radio.example/live.mp3?sid=1";
+        assert_eq!(format_hint(Some("audio/aacp"), url).as_deref(), Some("aac"));
+        assert_eq!(format_hint(Some("text/plain"), url).as_deref(), Some("mp3"));
+        assert_eq!(format_hint(None, "http://radio.example/stream"), None);
+        assert!(is_remote_url("HTTPS://radio.example/"));
+        assert!(!is_remote_url("/music/https.mp3"));
+    }
+}
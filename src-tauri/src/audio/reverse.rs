@@ -0,0 +1,207 @@
+/// Reverse playback and censor (slip-reverse) for a deck
+///
+/// Decoders only stream forwards, so a deck keeps a ring of the source frames
+/// it has just played and runs backwards through that instead.
+///
+/// - **Reverse** stops pulling from the decoder and steps back through the
+///   history; the position moves backwards. When reverse is released the deck
+///   replays the history forwards until it catches up with the decoder, so no
+///   seek is needed. Reverse stops (silence) at the start of the history.
+/// - **Censor** keeps pulling and recording at normal speed — the position
+///   carries on as if nothing happened — but outputs the history backwards
+///   from the moment censor was engaged. On release, output jumps back to the
+///   live position, so a masked word costs no time on air.
+///
+/// Stereo frames remembered per deck (~10.9 s at 48 kHz).
+pub const HISTORY_FRAMES: usize = 1 << 19;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Direction {
+    #[default]
+    Forward,
+    Reverse,
+    Censor,
+}
+
+pub struct ReverseHistory {
+    /// Interleaved stereo ring; empty for decks without reverse support
+    buf: Vec<f32>,
+    /// Frames ever recorded since the last clear
+    written: u64,
+    /// Recorded frames ahead of the play position (reverse / catch-up)
+    lag: u64,
+    /// Distance from the live head to the censor read position
+    slip: u64,
+    direction: Direction,
+}
+
+impl ReverseHistory {
+    pub fn new(frames: usize) -> Self {
+        Self {
+            buf: vec![0.0; frames * 2],
+            written: 0,
+            lag: 0,
+            slip: 0,
+            direction: Direction::Forward,
+        }
+    }
+
+    pub fn is_supported(&self) -> bool {
+        !self.buf.is_empty()
+    }
+
+    pub fn direction(&self) -> Direction {
+        self.direction
+    }
+
+    /// Still playing from the history rather than the decoder (reversed, or
+    /// catching up after a reverse), so decoder EOF is not the end yet.
+    pub fn is_behind_live(&self) -> bool {
+        self.direction == Direction::Reverse || self.lag > 0
+    }
+
+    fn capacity(&self) -> u64 {
+        (self.buf.len() / 2) as u64
+    }
+
+    /// Frames that can still be read back from the ring.
+    fn available(&self) -> u64 {
+        self.written.min(self.capacity())
+    }
+
+    /// Forget everything and play forwards (load/seek: the history no longer
+    /// leads up to the play position).
+    pub fn clear(&mut self) {
+        self.written = 0;
+        self.lag = 0;
+        self.slip = 0;
+        self.direction = Direction::Forward;
+    }
+
+    /// Switch direction. Returns whether anything changed.
+    pub fn set_direction(&mut self, direction: Direction) -> bool {
+        if !self.is_supported() || direction == self.direction {
+            return false;
+        }
+        // Censor only masks live playback, not a reverse or its catch-up.
+        if direction == Direction::Censor && self.is_behind_live() {
+            return false;
+        }
+        self.slip = 0;
+        self.direction = direction;
+        true
+    }
+
+    fn at(&self, index: u64) -> (f32, f32) {
+        let i = (index % self.capacity()) as usize * 2;
+        (self.buf[i], self.buf[i + 1])
+    }
+
+    /// Remember a frame just pulled from the decoder (or loop buffer).
+    pub fn record(&mut self, l: f32, r: f32) {
+        if !self.is_supported() {
+            return;
+        }
+        let i = (self.written % self.capacity()) as usize * 2;
+        self.buf[i] = l;
+        self.buf[i + 1] = r;
+        self.written += 1;
+    }
+
+    /// Next frame while going forward after a reverse; `None` once caught up
+    /// with the live position (pull from the decoder again).
+    pub fn replay(&mut self) -> Option<(f32, f32)> {
+        if self.lag == 0 {
+            return None;
+        }
+        self.lag -= 1;
+        Some(self.at(self.written - 1 - self.lag))
+    }
+
+    /// Next frame in reverse; `None` at the start of the history.
+    pub fn step_back(&mut self) -> Option<(f32, f32)> {
+        if self.lag >= self.available() {
+            return None;
+        }
+        let frame = self.at(self.written - 1 - self.lag);
+        self.lag += 1;
+        Some(frame)
+    }
+
+    /// Censor output for one live frame (call after `record`). The read head
+    /// moves back one frame while the live head moves on one, so the gap grows
+    /// by two; silence once it runs past the history.
+    pub fn censor_frame(&mut self) -> (f32, f32) {
+        self.slip += 2;
+        if self.slip >= self.available() {
+            return (0.0, 0.0);
+        }
+        self.at(self.written - 1 - self.slip)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn recorded(frames: u64) -> ReverseHistory {
+        let mut history = ReverseHistory::new(8);
+        for i in 0..frames {
+            history.record(i as f32, -(i as f32));
+        }
+        history
+    }
+
+    #[test]
+    fn reverse_then_catch_up() {
+        let mut history = recorded(6);
+        assert!(history.set_direction(Direction::Reverse));
+        let back: Vec<f32> = (0..3).map(|_| history.step_back().unwrap().0).collect();
+        assert_eq!(back, vec![5.0, 4.0, 3.0]);
+
+        history.set_direction(Direction::Forward);
+        let fwd: Vec<f32> = std::iter::from_fn(|| history.replay())
+            .map(|f| f.0)
+            .collect();
+        assert_eq!(fwd, vec![3.0, 4.0, 5.0]);
+    }
+
+    #[test]
+    fn reverse_stops_at_ring_start() {
+        // 12 frames through an 8-frame ring: only 4..=11 survive.
+        let mut history = recorded(12);
+        history.set_direction(Direction::Reverse);
+        let back: Vec<f32> = std::iter::from_fn(|| history.step_back())
+            .map(|f| f.0)
+            .collect();
+        assert_eq!(back, (4..12).rev().map(|i| i as f32).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn censor_runs_backwards_while_live_advances() {
+        let mut history = recorded(4);
+        assert!(history.set_direction(Direction::Censor));
+        let mut out = Vec::new();
+        for i in 4..7 {
+            history.record(i as f32, 0.0);
+            out.push(history.censor_frame().0);
+        }
+        assert_eq!(out, vec![2.0, 1.0, 0.0]);
+        history.record(7.0, 0.0);
+        assert_eq!(history.censor_frame(), (0.0, 0.0));
+
+        history.set_direction(Direction::Forward);
+        assert!(
+            history.replay().is_none(),
+            "censor never delays the live position"
+        );
+    }
+
+    #[test]
+    fn unsupported_history_ignores_requests() {
+        let mut history = ReverseHistory::new(0);
+        history.record(1.0, 1.0);
+        assert!(!history.set_direction(Direction::Reverse));
+        assert_eq!(history.direction(), Direction::Forward);
+    }
+}
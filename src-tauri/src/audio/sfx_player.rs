@@ -0,0 +1,301 @@
+/// Polyphonic Sound FX player — one-shot stingers on the Sound FX channel
+///
+/// The Sound FX deck plays one file at a time, so firing a second stinger
+/// through it cuts off the first. `play_sfx` instead starts a voice here:
+/// up to `MAX_SFX_VOICES` samples sound at once, each with its own gain,
+/// and are summed into the Sound FX bus before its DSP chain (alongside the
+/// deck and the cart wall). A voice may belong to a choke group; starting a
+/// voice in a group quickly fades out the others in that group, like an
+/// open/closed hi-hat pair on a sampler. Automated sweepers and timed
+/// events play through the same pool, so they never cut each other off.
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc, Mutex, OnceLock,
+};
+
+use serde::{Deserialize, Serialize};
+
+use super::cart_wall::{CartSample, Playhead};
+use super::engine::AudioEngine;
+
+/// Stingers that can sound at once; the oldest voice is stolen beyond this.
+pub const MAX_SFX_VOICES: usize = 12;
+/// Fade used when a voice is choked or stolen, to avoid clicks.
+const CHOKE_FADE_MS: u32 = 15;
+/// Decoded files kept for re-triggering.
+const MAX_CACHED_SAMPLES: usize = 64;
+/// Voices kept in the pool, counting choked ones still fading out.
+const VOICE_SLOTS: usize = MAX_SFX_VOICES * 2;
+/// Finished samples held until the engine hands them back to the control
+/// side; sized like the cart wall's, so the list cannot fill.
+const RETIRED_CAPACITY: usize = VOICE_SLOTS + AudioEngine::CMD_RING_SIZE;
+
+pub type SfxVoiceId = u64;
+
+/// Everything the RT thread needs to start a voice.
+pub struct SfxTrigger {
+    pub voice_id: SfxVoiceId,
+    pub sample: Arc<CartSample>,
+    pub gain: f32,
+    pub choke_group: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SfxVoiceState {
+    pub voice_id: SfxVoiceId,
+    pub choke_group: Option<u32>,
+    pub gain: f32,
+    pub position_ms: u64,
+    pub duration_ms: u64,
+    pub fading: bool,
+}
+
+/// Which voices a stop request applies to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SfxStop {
+    Voice(SfxVoiceId),
+    Group(u32),
+    All,
+}
+
+struct Voice {
+    id: SfxVoiceId,
+    sample: Arc<CartSample>,
+    choke_group: Option<u32>,
+    gain: f32,
+    head: Playhead,
+}
+
+impl Voice {
+    fn release(&mut self, fade_ms: u32) {
+        if !self.head.fading {
+            self.head.fading = true;
+            self.head.fade_ms = fade_ms;
+        }
+    }
+}
+
+/// Voice pool owned by the RT state. Never allocates after construction.
+///
+/// As on the cart wall, samples of finished voices are parked in `retired`:
+/// the cache may have dropped its copy meanwhile, and the engine sends them
+/// back to be freed off the audio thread (see [`SfxPlayer::pop_retired`]).
+pub struct SfxPlayer {
+    voices: Vec<Voice>,
+    retired: Vec<Arc<CartSample>>,
+}
+
+impl Default for SfxPlayer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SfxPlayer {
+    pub fn new() -> Self {
+        Self {
+            voices: Vec::with_capacity(VOICE_SLOTS),
+            retired: Vec::with_capacity(RETIRED_CAPACITY),
+        }
+    }
+
+    pub fn trigger(&mut self, t: SfxTrigger) {
+        if let Some(group) = t.choke_group {
+            for v in self
+                .voices
+                .iter_mut()
+                .filter(|v| v.choke_group == Some(group))
+            {
+                v.release(CHOKE_FADE_MS);
+            }
+        }
+        // Steal the oldest sounding voice; fading ones finish on their own.
+        if self.voices.iter().filter(|v| !v.head.fading).count() >= MAX_SFX_VOICES {
+            if let Some(v) = self.voices.iter_mut().find(|v| !v.head.fading) {
+                v.release(CHOKE_FADE_MS);
+            }
+        }
+        if self.voices.len() >= VOICE_SLOTS {
+            let stolen = self.voices.remove(0);
+            self.retire(stolen.sample);
+        }
+        self.voices.push(Voice {
+            id: t.voice_id,
+            sample: t.sample,
+            choke_group: t.choke_group,
+            gain: t.gain,
+            head: Playhead::new(0),
+        });
+    }
+
+    /// Fade out the matching voices over `fade_ms` (0 = cut).
+    pub fn stop(&mut self, which: SfxStop, fade_ms: u32) {
+        self.retain_voices(|v| {
+            let hit = match which {
+                SfxStop::Voice(id) => v.id == id,
+                SfxStop::Group(group) => v.choke_group == Some(group),
+                SfxStop::All => true,
+            };
+            if !hit {
+                return true;
+            }
+            v.release(fade_ms);
+            v.head.fade_ms > 0
+        });
+    }
+
+    pub fn is_active(&self) -> bool {
+        !self.voices.is_empty()
+    }
+
+    /// Add all voices into `out` (interleaved stereo at `device_sr`).
+    pub fn render(&mut self, out: &mut [f32], device_sr: u32) {
+        if self.voices.is_empty() || device_sr == 0 {
+            return;
+        }
+        self.retain_voices(|v| v.head.render(&v.sample, v.gain, false, out, device_sr));
+    }
+
+    /// A finished sample for the engine to send back.
+    pub fn pop_retired(&mut self) -> Option<Arc<CartSample>> {
+        self.retired.pop()
+    }
+
+    /// `Vec::retain_mut` that retires the samples of dropped voices.
+    fn retain_voices(&mut self, mut keep: impl FnMut(&mut Voice) -> bool) {
+        let mut i = 0;
+        while i < self.voices.len() {
+            if keep(&mut self.voices[i]) {
+                i += 1;
+            } else {
+                let voice = self.voices.remove(i);
+                self.retire(voice.sample);
+            }
+        }
+    }
+
+    fn retire(&mut self, sample: Arc<CartSample>) {
+        if self.retired.len() < RETIRED_CAPACITY {
+            self.retired.push(sample);
+        }
+    }
+
+    pub fn states(&self) -> Vec<SfxVoiceState> {
+        let mut out = Vec::with_capacity(self.voices.len());
+        self.write_states(&mut out);
+        out
+    }
+
+    /// `states` into `out`, reusing its allocation (for the audio thread).
+    pub fn write_states(&self, out: &mut Vec<SfxVoiceState>) {
+        out.clear();
+        out.extend(self.voices.iter().map(|v| SfxVoiceState {
+            voice_id: v.id,
+            choke_group: v.choke_group,
+            gain: v.gain,
+            position_ms: v.head.position_ms(&v.sample),
+            duration_ms: v.sample.duration_ms(),
+            fading: v.head.fading,
+        }));
+    }
+}
+
+// ── Sample cache ─────────────────────────────────────────────────────────────
+
+static NEXT_VOICE_ID: AtomicU64 = AtomicU64::new(1);
+
+pub fn next_voice_id() -> SfxVoiceId {
+    NEXT_VOICE_ID.fetch_add(1, Ordering::Relaxed)
+}
+
+/// Decoded samples keyed by path, with the file mtime they were decoded at.
+type SampleCache = HashMap<String, (i64, Arc<CartSample>)>;
+
+fn cache() -> &'static Mutex<SampleCache> {
+    static CACHE: OnceLock<Mutex<SampleCache>> = OnceLock::new();
+    CACHE.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Decoded PCM for `path`, decoding on first use or after the file changes.
+/// Voices retire their samples back to the control side, so evicting an
+/// entry that is still playing never frees PCM on the audio thread.
+pub async fn load(path: &str) -> Result<Arc<CartSample>, String> {
+    let mtime_ms = super::analyzer::peaks::file_mtime_ms(Path::new(path));
+    if let Some((cached_mtime, sample)) = cache().lock().unwrap().get(path) {
+        if *cached_mtime == mtime_ms {
+            return Ok(Arc::clone(sample));
+        }
+    }
+    let owned = path.to_string();
+    let sample = tokio::task::spawn_blocking(move || CartSample::decode(Path::new(&owned)))
+        .await
+        .map_err(|e| e.to_string())??;
+    let sample = Arc::new(sample);
+    let mut cache = cache().lock().unwrap();
+    if cache.len() >= MAX_CACHED_SAMPLES {
+        // Only evict samples no voice is holding.
+        cache.retain(|_, (_, s)| Arc::strong_count(s) > 1);
+        if cache.len() >= MAX_CACHED_SAMPLES {
+            cache.clear();
+        }
+    }
+    cache.insert(path.to_string(), (mtime_ms, Arc::clone(&sample)));
+    Ok(sample)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trigger(voice_id: SfxVoiceId, choke_group: Option<u32>) -> SfxTrigger {
+        SfxTrigger {
+            voice_id,
+            sample: Arc::new(CartSample {
+                samples: vec![0.5; 2 * 48_000],
+                sample_rate: 48_000,
+            }),
+            gain: 1.0,
+            choke_group,
+        }
+    }
+
+    #[test]
+    fn voices_overlap_and_choke_by_group() {
+        let mut player = SfxPlayer::new();
+        player.trigger(trigger(1, Some(7)));
+        player.trigger(trigger(2, None));
+        let mut out = vec![0.0_f32; 64];
+        player.render(&mut out, 48_000);
+        assert!((out[0] - 1.0).abs() < 1e-6, "two voices summed");
+
+        player.trigger(trigger(3, Some(7)));
+        let states = player.states();
+        assert!(states.iter().find(|s| s.voice_id == 1).unwrap().fading);
+        assert!(!states.iter().find(|s| s.voice_id == 2).unwrap().fading);
+
+        // The choked voice is gone once its short fade has run.
+        let mut out = vec![0.0_f32; 48_000 / 10 * 2];
+        player.render(&mut out, 48_000);
+        let ids: Vec<_> = player.states().iter().map(|s| s.voice_id).collect();
+        assert_eq!(ids, vec![2, 3]);
+
+        player.stop(SfxStop::Voice(2), 0);
+        player.stop(SfxStop::Group(7), 0);
+        assert!(!player.is_active());
+        // Stopped voices hand their samples back instead of freeing them.
+        assert!(player.pop_retired().is_some());
+    }
+
+    #[test]
+    fn pool_steals_oldest_voice() {
+        let mut player = SfxPlayer::new();
+        for id in 0..=MAX_SFX_VOICES as u64 {
+            player.trigger(trigger(id, None));
+        }
+        let sounding: Vec<_> = player.states().into_iter().filter(|s| !s.fading).collect();
+        assert_eq!(sounding.len(), MAX_SFX_VOICES);
+        assert!(sounding.iter().all(|s| s.voice_id != 0));
+    }
+}
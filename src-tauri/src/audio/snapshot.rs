@@ -0,0 +1,117 @@
+/// Wait-free triple buffer for publishing real-time state.
+///
+/// The audio callback owns the engine state outright and writes a copy of
+/// what the rest of the app may ask about into the back slot once per block,
+/// then swaps it in as the latest. Readers pick up the latest slot whenever
+/// they query. Neither side ever waits for the other: the writer always has
+/// a free slot, and the reader keeps seeing the previous snapshot until a
+/// newer one is published.
+use std::cell::UnsafeCell;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::Arc;
+
+const INDEX_MASK: u8 = 0b011;
+/// Set on `latest` when it holds a snapshot the reader has not picked up.
+const FRESH: u8 = 0b100;
+
+struct Shared<T> {
+    slots: [UnsafeCell<T>; 3],
+    /// Index of the most recently published slot, plus `FRESH`
+    latest: AtomicU8,
+}
+
+// SAFETY: each slot is owned by exactly one of writer (back), reader (front)
+// or `latest` at any time; ownership only moves through the atomic swap.
+unsafe impl<T: Send> Sync for Shared<T> {}
+
+/// Writer half, held by the real-time thread.
+pub struct SnapshotWriter<T> {
+    shared: Arc<Shared<T>>,
+    back: u8,
+}
+
+/// Reader half, held by the control side.
+pub struct SnapshotReader<T> {
+    shared: Arc<Shared<T>>,
+    front: u8,
+}
+
+pub fn snapshot_buffer<T: Default>() -> (SnapshotWriter<T>, SnapshotReader<T>) {
+    let shared = Arc::new(Shared {
+        slots: Default::default(),
+        latest: AtomicU8::new(1),
+    });
+    (
+        SnapshotWriter {
+            shared: Arc::clone(&shared),
+            back: 2,
+        },
+        SnapshotReader { shared, front: 0 },
+    )
+}
+
+impl<T> SnapshotWriter<T> {
+    /// The slot to fill before `publish`. It holds an older snapshot, so
+    /// fields updated with `clone_from` reuse their allocations.
+    pub fn back(&mut self) -> &mut T {
+        // SAFETY: the back slot belongs to the writer until published.
+        unsafe { &mut *self.shared.slots[self.back as usize].get() }
+    }
+
+    /// Make the back slot the latest snapshot.
+    pub fn publish(&mut self) {
+        let previous = self.shared.latest.swap(self.back | FRESH, Ordering::AcqRel);
+        self.back = previous & INDEX_MASK;
+    }
+}
+
+impl<T> SnapshotReader<T> {
+    /// The latest published snapshot.
+    pub fn read(&mut self) -> &T {
+        if self.shared.latest.load(Ordering::Relaxed) & FRESH != 0 {
+            let previous = self.shared.latest.swap(self.front, Ordering::AcqRel);
+            self.front = previous & INDEX_MASK;
+        }
+        // SAFETY: the front slot belongs to the reader until the next swap,
+        // which needs `&mut self`.
+        unsafe { &*self.shared.slots[self.front as usize].get() }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reader_sees_latest_publish_and_keeps_it_until_the_next() {
+        let (mut writer, mut reader) = snapshot_buffer::<u32>();
+        assert_eq!(*reader.read(), 0);
+
+        *writer.back() = 1;
+        writer.publish();
+        *writer.back() = 2;
+        writer.publish();
+        assert_eq!(*reader.read(), 2);
+        assert_eq!(*reader.read(), 2);
+
+        // An unpublished write is invisible.
+        *writer.back() = 3;
+        assert_eq!(*reader.read(), 2);
+        writer.publish();
+        assert_eq!(*reader.read(), 3);
+
+        let handle = std::thread::spawn(move || {
+            for i in 4..10_000 {
+                *writer.back() = i;
+                writer.publish();
+            }
+        });
+        let mut last = 3;
+        while last < 9_999 {
+            let seen = *reader.read();
+            assert!(seen >= last);
+            last = seen;
+        }
+        handle.join().unwrap();
+    }
+}
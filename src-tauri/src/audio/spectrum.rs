@@ -0,0 +1,385 @@
+/// Spectrum analyzer for the master bus and, optionally, each channel.
+///
+/// The audio callback only copies mono samples into a ring per tapped
+/// channel (`SpectrumTap`). Everything else runs on the engine polling loop:
+/// `poll` drains the rings on every tick and, once per `interval_ms`,
+/// windows the newest `fft_size` samples of each channel, takes an FFT and
+/// folds the bins into log-spaced bands. Bands rise instantly and fall at
+/// `RELEASE_DB_PER_SEC`, like a hardware analyzer.
+///
+/// Taps exist only while the analyzer is enabled, so an idle analyzer costs
+/// the callback nothing.
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Instant;
+
+use realfft::{num_complex::Complex, RealFftPlanner, RealToComplex};
+use ringbuf::traits::{Consumer as _, Observer as _, Producer as _};
+use serde::{Deserialize, Serialize};
+
+use super::{crossfade::DeckId, engine::AudioEngine};
+
+const FLOOR_DB: f32 = -96.0;
+const RELEASE_DB_PER_SEC: f32 = 40.0;
+const LOW_HZ: f32 = 20.0;
+const HIGH_HZ: f32 = 20_000.0;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SpectrumConfig {
+    pub enabled: bool,
+    /// Analyze every channel, not just the master bus
+    pub per_deck: bool,
+    /// Log-spaced bands between 20 Hz and 20 kHz
+    pub bands: u32,
+    /// FFT window, a power of two
+    pub fft_size: u32,
+    /// Time between analyses
+    pub interval_ms: u64,
+}
+
+impl Default for SpectrumConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            per_deck: false,
+            bands: 32,
+            fft_size: 2048,
+            interval_ms: 50,
+        }
+    }
+}
+
+impl SpectrumConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(8..=128).contains(&self.bands) {
+            return Err("Spectrum bands must be between 8 and 128".into());
+        }
+        if !self.fft_size.is_power_of_two() || !(512..=8192).contains(&self.fft_size) {
+            return Err("Spectrum FFT size must be a power of two from 512 to 8192".into());
+        }
+        if !(20..=1000).contains(&self.interval_ms) {
+            return Err("Spectrum interval must be between 20 and 1000 ms".into());
+        }
+        Ok(())
+    }
+}
+
+/// Channel an analyzer tap reads.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpectrumSource {
+    /// The on-air program bus, after master processing
+    Master,
+    /// A channel after its DSP chain, before the fader
+    Deck(DeckId),
+}
+
+impl std::fmt::Display for SpectrumSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SpectrumSource::Master => write!(f, "master"),
+            SpectrumSource::Deck(id) => write!(f, "{id}"),
+        }
+    }
+}
+
+/// Audio-thread end of the analyzer: one mono ring per source.
+pub struct SpectrumTap {
+    feeds: Vec<(SpectrumSource, ringbuf::HeapProd<f32>)>,
+}
+
+impl SpectrumTap {
+    pub fn new(feeds: Vec<(SpectrumSource, ringbuf::HeapProd<f32>)>) -> Self {
+        Self { feeds }
+    }
+
+    /// Append each source's interleaved stereo block as mono. A block that
+    /// does not fit is dropped whole; the analyzer has fallen behind.
+    ///
+    /// Called on the real-time audio thread.
+    pub fn feed<'a>(&mut self, block: impl Fn(SpectrumSource) -> &'a [f32]) {
+        for (source, prod) in &mut self.feeds {
+            let stereo = block(*source);
+            if prod.vacant_len() < stereo.len() / 2 {
+                continue;
+            }
+            for frame in stereo.chunks_exact(2) {
+                let _ = prod.try_push((frame[0] + frame[1]) * 0.5);
+            }
+        }
+    }
+}
+
+/// Band levels of one channel, emitted as `spectrum`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SpectrumFrame {
+    /// "master" or a deck id, as in `vu_meter`
+    pub channel: String,
+    /// Peak level per band, dBFS
+    pub bands_db: Vec<f32>,
+}
+
+/// Latest analysis, for `get_spectrum_data`.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SpectrumData {
+    pub enabled: bool,
+    /// Geometric centre of each band, Hz
+    pub band_centres_hz: Vec<f32>,
+    pub channels: Vec<SpectrumFrame>,
+}
+
+struct Channel {
+    source: SpectrumSource,
+    cons: ringbuf::HeapCons<f32>,
+    /// Newest `fft_size` samples, oldest first
+    history: Vec<f32>,
+    levels_db: Vec<f32>,
+}
+
+struct Analyzer {
+    fft: Arc<dyn RealToComplex<f32>>,
+    window: Vec<f32>,
+    /// Scales a bin magnitude to the amplitude of a full-scale sine
+    norm: f32,
+    input: Vec<f32>,
+    output: Vec<Complex<f32>>,
+    /// Bin range `[start, end)` per band
+    bands: Vec<(usize, usize)>,
+    centres_hz: Vec<f32>,
+    channels: Vec<Channel>,
+    last_run: Option<Instant>,
+}
+
+impl Analyzer {
+    fn new(
+        config: &SpectrumConfig,
+        sample_rate: u32,
+        taps: Vec<(SpectrumSource, ringbuf::HeapCons<f32>)>,
+    ) -> Self {
+        let size = config.fft_size as usize;
+        let fft = RealFftPlanner::<f32>::new().plan_fft_forward(size);
+        let window: Vec<f32> = (0..size)
+            .map(|i| {
+                let phase = std::f32::consts::TAU * i as f32 / size as f32;
+                0.5 - 0.5 * phase.cos()
+            })
+            .collect();
+        let norm = 2.0 / window.iter().sum::<f32>();
+        let (bands, centres_hz) = band_layout(config.bands as usize, size, sample_rate);
+        let channels = taps
+            .into_iter()
+            .map(|(source, cons)| Channel {
+                source,
+                cons,
+                history: vec![0.0; size],
+                levels_db: vec![FLOOR_DB; bands.len()],
+            })
+            .collect();
+        Self {
+            input: fft.make_input_vec(),
+            output: fft.make_output_vec(),
+            fft,
+            window,
+            norm,
+            bands,
+            centres_hz,
+            channels,
+            last_run: None,
+        }
+    }
+
+    fn drain(&mut self) {
+        let mut block = [0.0_f32; 1024];
+        for channel in &mut self.channels {
+            loop {
+                let n = channel.cons.pop_slice(&mut block);
+                if n == 0 {
+                    break;
+                }
+                let len = channel.history.len();
+                let keep = len.saturating_sub(n);
+                channel.history.copy_within(n.min(len).., 0);
+                let fresh = &block[n.saturating_sub(len)..n];
+                channel.history[keep..].copy_from_slice(fresh);
+            }
+        }
+    }
+
+    fn analyze(&mut self, elapsed_secs: f32) {
+        let fall = RELEASE_DB_PER_SEC * elapsed_secs;
+        for channel in &mut self.channels {
+            for ((x, s), w) in self
+                .input
+                .iter_mut()
+                .zip(&channel.history)
+                .zip(&self.window)
+            {
+                *x = s * w;
+            }
+            if self.fft.process(&mut self.input, &mut self.output).is_err() {
+                continue;
+            }
+            for (level, &(start, end)) in channel.levels_db.iter_mut().zip(&self.bands) {
+                let peak = self.output[start..end]
+                    .iter()
+                    .map(|c| c.norm())
+                    .fold(0.0_f32, f32::max);
+                let db = to_db(peak * self.norm);
+                *level = db.max(*level - fall).max(FLOOR_DB);
+            }
+        }
+    }
+
+    fn frames(&self) -> Vec<SpectrumFrame> {
+        self.channels
+            .iter()
+            .map(|c| SpectrumFrame {
+                channel: c.source.to_string(),
+                bands_db: c.levels_db.clone(),
+            })
+            .collect()
+    }
+}
+
+fn to_db(amplitude: f32) -> f32 {
+    if amplitude < 1e-10 {
+        FLOOR_DB
+    } else {
+        20.0 * amplitude.log10()
+    }
+}
+
+/// Log-spaced bands from 20 Hz to 20 kHz (or Nyquist), as FFT bin ranges.
+/// Every band gets at least one bin, so low bands at small FFT sizes repeat
+/// the nearest bin rather than reading nothing.
+fn band_layout(bands: usize, fft_size: usize, sample_rate: u32) -> (Vec<(usize, usize)>, Vec<f32>) {
+    let bin_hz = sample_rate.max(1) as f32 / fft_size as f32;
+    let last_bin = fft_size / 2;
+    let high = HIGH_HZ.min(sample_rate as f32 / 2.0);
+    let ratio = (high / LOW_HZ).powf(1.0 / bands as f32);
+    (0..bands)
+        .map(|i| {
+            let lo = LOW_HZ * ratio.powi(i as i32);
+            let hi = lo * ratio;
+            let start = ((lo / bin_hz).round() as usize).clamp(1, last_bin);
+            let end = ((hi / bin_hz).round() as usize).clamp(start + 1, last_bin + 1);
+            ((start, end), (lo * hi).sqrt())
+        })
+        .unzip()
+}
+
+#[derive(Default)]
+struct State {
+    config: SpectrumConfig,
+    analyzer: Option<Analyzer>,
+    latest: Vec<SpectrumFrame>,
+}
+
+fn state() -> &'static Mutex<State> {
+    static STATE: OnceLock<Mutex<State>> = OnceLock::new();
+    STATE.get_or_init(Default::default)
+}
+
+pub fn get_config() -> SpectrumConfig {
+    state().lock().unwrap().config.clone()
+}
+
+/// Apply `config`: (re)attach the engine taps, or remove them when disabled.
+pub fn configure(engine: &mut AudioEngine, config: SpectrumConfig) -> Result<(), String> {
+    config.validate()?;
+    let mut state = state().lock().unwrap();
+    engine.detach_spectrum();
+    state.analyzer = None;
+    state.latest.clear();
+    if config.enabled {
+        let mut sources = vec![SpectrumSource::Master];
+        if config.per_deck {
+            sources.extend(DeckId::ALL.map(SpectrumSource::Deck));
+        }
+        let taps = engine.attach_spectrum(&sources)?;
+        state.analyzer = Some(Analyzer::new(&config, engine.output_sample_rate(), taps));
+    }
+    state.config = config;
+    Ok(())
+}
+
+/// Drain the taps; every `interval_ms`, analyze and return fresh frames.
+/// Empty while disabled or between analyses.
+pub fn poll() -> Vec<SpectrumFrame> {
+    let mut state = state().lock().unwrap();
+    let interval_ms = state.config.interval_ms;
+    let Some(analyzer) = state.analyzer.as_mut() else {
+        return Vec::new();
+    };
+    analyzer.drain();
+    let now = Instant::now();
+    let elapsed = match analyzer.last_run {
+        Some(last) if now.duration_since(last).as_millis() < interval_ms as u128 => {
+            return Vec::new();
+        }
+        Some(last) => now.duration_since(last).as_secs_f32(),
+        None => 0.0,
+    };
+    analyzer.last_run = Some(now);
+    analyzer.analyze(elapsed);
+    let frames = analyzer.frames();
+    state.latest.clone_from(&frames);
+    frames
+}
+
+pub fn latest() -> SpectrumData {
+    let state = state().lock().unwrap();
+    SpectrumData {
+        enabled: state.analyzer.is_some(),
+        band_centres_hz: state
+            .analyzer
+            .as_ref()
+            .map(|a| a.centres_hz.clone())
+            .unwrap_or_default(),
+        channels: state.latest.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ringbuf::{traits::Split, HeapRb};
+
+    #[test]
+    fn sine_peaks_in_its_band() {
+        let config = SpectrumConfig {
+            enabled: true,
+            ..Default::default()
+        };
+        let (prod, cons) = HeapRb::<f32>::new(8192).split();
+        let mut tap = SpectrumTap::new(vec![(SpectrumSource::Master, prod)]);
+        let mut analyzer = Analyzer::new(&config, 48_000, vec![(SpectrumSource::Master, cons)]);
+
+        let stereo: Vec<f32> = (0..4096)
+            .flat_map(|i| {
+                let s = (std::f32::consts::TAU * 1000.0 * i as f32 / 48_000.0).sin();
+                [s, s]
+            })
+            .collect();
+        tap.feed(|_| &stereo);
+        analyzer.drain();
+        analyzer.analyze(0.0);
+
+        let frame = &analyzer.frames()[0];
+        assert_eq!(frame.channel, "master");
+        let loudest = (0..frame.bands_db.len())
+            .max_by(|&a, &b| frame.bands_db[a].total_cmp(&frame.bands_db[b]))
+            .unwrap();
+        let (start, end) = analyzer.bands[loudest];
+        let bin = (1000.0 * config.fft_size as f32 / 48_000.0).round() as usize;
+        assert!(
+            (start..end).contains(&bin),
+            "{start}..{end} misses bin {bin}"
+        );
+        // A full-scale sine reads close to 0 dBFS.
+        assert!(
+            frame.bands_db[loudest] > -3.0,
+            "{}",
+            frame.bands_db[loudest]
+        );
+    }
+}
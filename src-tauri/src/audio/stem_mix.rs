@@ -0,0 +1,227 @@
+/// Per-stem deck playback.
+///
+/// A deck playing a 4-stem separation decodes every stem in step and sums
+/// them with operator levels, so vocals, drums, bass and other can be ridden
+/// or muted live: drop everything but the vocals for an acapella outro, or
+/// mute the vocals for an instrumental bed under a talk break.
+///
+/// The deck's own decoder plays the vocals stem and drives position, EOF
+/// and looping; `StemLayer` holds the other three and is pulled one frame at
+/// a time alongside it.
+use std::path::PathBuf;
+use std::sync::atomic::Ordering;
+
+use ringbuf::traits::{Consumer as _, Observer as _};
+use serde::{Deserialize, Serialize};
+
+use super::decoder::{spawn_decoder, DecoderHandle};
+
+/// Level changes glide over roughly this many frames to avoid zipper noise.
+const LEVEL_GLIDE_FRAMES: f32 = 441.0;
+const MAX_STEM_GAIN: f32 = 2.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Stem {
+    Vocals,
+    Drums,
+    Bass,
+    Other,
+}
+
+impl Stem {
+    pub const ALL: [Stem; 4] = [Stem::Vocals, Stem::Drums, Stem::Bass, Stem::Other];
+}
+
+/// Stem files in `Stem::ALL` order.
+pub type StemPaths = [PathBuf; 4];
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StemLevel {
+    /// Linear gain, 0.0–2.0
+    pub gain: f32,
+    pub muted: bool,
+}
+
+impl Default for StemLevel {
+    fn default() -> Self {
+        Self {
+            gain: 1.0,
+            muted: false,
+        }
+    }
+}
+
+impl StemLevel {
+    fn target(&self) -> f32 {
+        if self.muted {
+            0.0
+        } else {
+            self.gain
+        }
+    }
+}
+
+/// Operator levels for the four stems of a deck.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StemMix {
+    pub vocals: StemLevel,
+    pub drums: StemLevel,
+    pub bass: StemLevel,
+    pub other: StemLevel,
+}
+
+impl StemMix {
+    pub fn level(&self, stem: Stem) -> StemLevel {
+        match stem {
+            Stem::Vocals => self.vocals,
+            Stem::Drums => self.drums,
+            Stem::Bass => self.bass,
+            Stem::Other => self.other,
+        }
+    }
+
+    pub fn clamped(mut self) -> Self {
+        for level in [
+            &mut self.vocals,
+            &mut self.drums,
+            &mut self.bass,
+            &mut self.other,
+        ] {
+            level.gain = if level.gain.is_finite() {
+                level.gain.clamp(0.0, MAX_STEM_GAIN)
+            } else {
+                1.0
+            };
+        }
+        self
+    }
+
+    fn targets(&self) -> [f32; 4] {
+        Stem::ALL.map(|s| self.level(s).target())
+    }
+}
+
+/// Decoders for the drums, bass and other stems, opened at the same point
+/// as the vocals decoder they follow.
+pub struct StemSources {
+    pub paths: StemPaths,
+    followers: Vec<DecoderHandle>,
+}
+
+impl StemSources {
+    /// Open the follower stems at `seek_ms`. The vocals stem (`paths[0]`) is
+    /// the deck's own decoder and is opened by the caller.
+    pub fn spawn(paths: StemPaths, seek_ms: Option<u64>, sample_rate: u32) -> Result<Self, String> {
+        let mut sources = Self {
+            paths,
+            followers: Vec::with_capacity(3),
+        };
+        for path in &sources.paths[1..] {
+            let handle = spawn_decoder(path.clone(), seek_ms)?;
+            if handle.sample_rate != sample_rate {
+                handle.stop_flag.store(true, Ordering::Relaxed);
+                return Err(format!(
+                    "Stem {} is {} Hz, expected {sample_rate} Hz",
+                    path.display(),
+                    handle.sample_rate
+                ));
+            }
+            sources.followers.push(handle);
+        }
+        Ok(sources)
+    }
+
+    /// Frames every follower has ready.
+    pub fn buffered_frames(&self) -> u64 {
+        self.followers
+            .iter()
+            .map(|d| d.consumer.occupied_len() as u64 / 2)
+            .min()
+            .unwrap_or(0)
+    }
+}
+
+impl Drop for StemSources {
+    fn drop(&mut self) {
+        for d in &self.followers {
+            d.stop_flag.store(true, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Live stem mixing state on a deck.
+pub struct StemLayer {
+    sources: StemSources,
+    gains: [f32; 4],
+}
+
+impl StemLayer {
+    pub fn new(sources: StemSources, mix: &StemMix) -> Self {
+        Self {
+            sources,
+            gains: mix.targets(),
+        }
+    }
+
+    pub fn paths(&self) -> &StemPaths {
+        &self.sources.paths
+    }
+
+    /// Mix one vocals frame with the matching frame of each follower.
+    /// A follower that has run dry contributes silence.
+    ///
+    /// Called on the real-time audio thread.
+    #[inline]
+    pub fn mix_frame(&mut self, vocals: (f32, f32), mix: &StemMix) -> (f32, f32) {
+        let targets = mix.targets();
+        for (gain, target) in self.gains.iter_mut().zip(targets) {
+            *gain += (target - *gain) / LEVEL_GLIDE_FRAMES;
+            if (target - *gain).abs() < 1e-4 {
+                *gain = target;
+            }
+        }
+        let mut l = vocals.0 * self.gains[0];
+        let mut r = vocals.1 * self.gains[0];
+        for (decoder, gain) in self.sources.followers.iter_mut().zip(&self.gains[1..]) {
+            if decoder.consumer.occupied_len() >= 2 {
+                l += decoder.consumer.try_pop().unwrap_or(0.0) * gain;
+                r += decoder.consumer.try_pop().unwrap_or(0.0) * gain;
+            }
+        }
+        (l, r)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn muting_glides_to_silence_and_clamp_tames_gains() {
+        let mut mix = StemMix::default();
+        mix.vocals.muted = true;
+        let mut layer = StemLayer {
+            sources: StemSources {
+                paths: Default::default(),
+                followers: Vec::new(),
+            },
+            gains: [1.0; 4],
+        };
+        let (first, _) = layer.mix_frame((1.0, 1.0), &mix);
+        assert!(first > 0.9 && first < 1.0);
+        let mut last = first;
+        for _ in 0..10_000 {
+            last = layer.mix_frame((1.0, 1.0), &mix).0;
+        }
+        assert_eq!(last, 0.0);
+
+        mix.drums.gain = 9.0;
+        mix.bass.gain = f32::NAN;
+        let mix = mix.clamped();
+        assert_eq!(mix.drums.gain, MAX_STEM_GAIN);
+        assert_eq!(mix.bass.gain, 1.0);
+    }
+}
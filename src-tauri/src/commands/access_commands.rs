@@ -0,0 +1,303 @@
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tauri::State;
+
+use crate::access::{self, Actor, AuditEntry, AuditFilter, Capability, Role, User};
+use crate::error::AppError;
+use crate::state::AppState;
+
+const MIN_PASSWORD_LEN: usize = 4;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccessStatus {
+    /// False until the first account is created
+    pub enforced: bool,
+    pub current: Option<Actor>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct NewUser {
+    pub username: String,
+    pub display_name: Option<String>,
+    pub role: Role,
+    pub password: String,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct UserUpdate {
+    pub display_name: Option<String>,
+    pub role: Option<Role>,
+    pub enabled: Option<bool>,
+    /// New password; omitted = unchanged
+    pub password: Option<String>,
+}
+
+fn check_password(password: &str) -> Result<(), AppError> {
+    if password.chars().count() < MIN_PASSWORD_LEN {
+        return Err(AppError::invalid_input(format!(
+            "Password must be at least {MIN_PASSWORD_LEN} characters"
+        )));
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_access_status(state: State<'_, AppState>) -> Result<AccessStatus, AppError> {
+    Ok(AccessStatus {
+        enforced: state.access.is_enforced(),
+        current: state.access.current(),
+    })
+}
+
+#[tauri::command]
+pub async fn sign_in(
+    username: String,
+    password: String,
+    state: State<'_, AppState>,
+) -> Result<Actor, AppError> {
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    let login = access::find_login(pool, username.trim())
+        .await
+        .map_err(AppError::db)?;
+    let user = match login {
+        Some((user, hash)) if user.enabled && access::verify_password(&password, &hash) => user,
+        _ => {
+            log::warn!("Sign-in refused for '{}'", username.trim());
+            return Err(AppError::permission_denied(
+                "Unknown user or wrong password",
+            ));
+        }
+    };
+    access::touch_last_login(pool, user.id)
+        .await
+        .map_err(AppError::db)?;
+    let actor = user.actor();
+    state.access.sign_in(actor.clone());
+    access::audit(&state, &actor, "user.sign_in", None, json!({})).await;
+    Ok(actor)
+}
+
+#[tauri::command]
+pub async fn sign_out(state: State<'_, AppState>) -> Result<(), AppError> {
+    if let Some(actor) = state.access.sign_out() {
+        access::audit(&state, &actor, "user.sign_out", None, json!({})).await;
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn list_users(state: State<'_, AppState>) -> Result<Vec<User>, AppError> {
+    state.access.require(Capability::ManageUsers)?;
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    access::list_users(pool).await.map_err(AppError::db)
+}
+
+/// Create an account. The first account must be an admin; creating it turns
+/// sign-in on and signs that admin in.
+#[tauri::command]
+pub async fn create_user(user: NewUser, state: State<'_, AppState>) -> Result<User, AppError> {
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    let first = access::count_users(pool).await.map_err(AppError::db)? == 0;
+    let actor = if first {
+        if user.role != Role::Admin {
+            return Err(AppError::invalid_input(
+                "The first account must be an admin",
+            ));
+        }
+        Actor::local()
+    } else {
+        state.access.require(Capability::ManageUsers)?
+    };
+
+    let username = user.username.trim();
+    if username.is_empty() {
+        return Err(AppError::invalid_input("Username is required"));
+    }
+    check_password(&user.password)?;
+    if access::find_login(pool, username)
+        .await
+        .map_err(AppError::db)?
+        .is_some()
+    {
+        return Err(AppError::conflict(format!(
+            "User '{username}' already exists"
+        )));
+    }
+
+    let hash = access::hash_password(&user.password)?;
+    let display_name = user
+        .display_name
+        .as_deref()
+        .map(str::trim)
+        .filter(|s| !s.is_empty());
+    let id = access::insert_user(pool, username, display_name, user.role, &hash)
+        .await
+        .map_err(AppError::db)?;
+    let created = access::get_user(pool, id)
+        .await
+        .map_err(AppError::db)?
+        .ok_or_else(|| AppError::not_found(format!("User {id} missing after insert")))?;
+
+    access::audit(
+        &state,
+        &actor,
+        "user.create",
+        Some(format!("user:{id}")),
+        json!({ "username": created.username, "role": created.role }),
+    )
+    .await;
+    if first {
+        state.access.set_enforced(true);
+        state.access.sign_in(created.actor());
+        log::info!(
+            "First account '{}' created; sign-in enabled",
+            created.username
+        );
+    }
+    Ok(created)
+}
+
+#[tauri::command]
+pub async fn update_user(
+    id: i64,
+    changes: UserUpdate,
+    state: State<'_, AppState>,
+) -> Result<User, AppError> {
+    let actor = state.access.require(Capability::ManageUsers)?;
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    let mut user = access::get_user(pool, id)
+        .await
+        .map_err(AppError::db)?
+        .ok_or_else(|| AppError::not_found(format!("User {id} not found")))?;
+    if let Some(password) = &changes.password {
+        check_password(password)?;
+    }
+    let was_active_admin = user.role == Role::Admin && user.enabled;
+
+    if let Some(display_name) = changes.display_name {
+        let trimmed = display_name.trim();
+        user.display_name = (!trimmed.is_empty()).then(|| trimmed.to_string());
+    }
+    if let Some(role) = changes.role {
+        user.role = role;
+    }
+    if let Some(enabled) = changes.enabled {
+        user.enabled = enabled;
+    }
+    let still_active_admin = user.role == Role::Admin && user.enabled;
+    if was_active_admin
+        && !still_active_admin
+        && access::count_enabled_admins(pool)
+            .await
+            .map_err(AppError::db)?
+            <= 1
+    {
+        return Err(AppError::conflict("At least one enabled admin is required"));
+    }
+
+    access::update_user(pool, &user)
+        .await
+        .map_err(AppError::db)?;
+    let password_changed = match changes.password {
+        Some(password) => {
+            let hash = access::hash_password(&password)?;
+            access::set_password_hash(pool, id, &hash)
+                .await
+                .map_err(AppError::db)?;
+            true
+        }
+        None => false,
+    };
+
+    // Keep the signed-in session in step with its own account.
+    if state
+        .access
+        .current()
+        .is_some_and(|c| c.user_id == Some(user.id))
+    {
+        if user.enabled {
+            state.access.sign_in(user.actor());
+        } else {
+            state.access.sign_out();
+        }
+    }
+
+    access::audit(
+        &state,
+        &actor,
+        "user.update",
+        Some(format!("user:{id}")),
+        json!({
+            "username": user.username,
+            "role": user.role,
+            "enabled": user.enabled,
+            "password_changed": password_changed,
+        }),
+    )
+    .await;
+    Ok(user)
+}
+
+#[tauri::command]
+pub async fn delete_user(id: i64, state: State<'_, AppState>) -> Result<(), AppError> {
+    let actor = state.access.require(Capability::ManageUsers)?;
+    if actor.user_id == Some(id) {
+        return Err(AppError::conflict("You cannot delete your own account"));
+    }
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    let user = access::get_user(pool, id)
+        .await
+        .map_err(AppError::db)?
+        .ok_or_else(|| AppError::not_found(format!("User {id} not found")))?;
+    if user.role == Role::Admin
+        && user.enabled
+        && access::count_enabled_admins(pool)
+            .await
+            .map_err(AppError::db)?
+            <= 1
+    {
+        return Err(AppError::conflict("At least one enabled admin is required"));
+    }
+
+    access::delete_user(pool, id).await.map_err(AppError::db)?;
+    access::audit(
+        &state,
+        &actor,
+        "user.delete",
+        Some(format!("user:{id}")),
+        json!({ "username": user.username, "role": user.role }),
+    )
+    .await;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_audit_log(
+    filter: Option<AuditFilter>,
+    state: State<'_, AppState>,
+) -> Result<Vec<AuditEntry>, AppError> {
+    state.access.require(Capability::ViewAuditLog)?;
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    access::get_audit_log(pool, &filter.unwrap_or_default())
+        .await
+        .map_err(AppError::db)
+}
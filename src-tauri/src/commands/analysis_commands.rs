@@ -0,0 +1,181 @@
+use std::path::Path;
+
+use tauri::State;
+
+use crate::access::Capability;
+use crate::audio::analyzer::{
+    fingerprint::{self, DuplicateGroup, DuplicateSearchOptions},
+    jobs::{self, AnalysisKind, AnalysisQueueConfig, AnalysisQueueStatus},
+    loudness::{self, TrackLoudness},
+    peaks,
+};
+use crate::error::AppError;
+use crate::state::AppState;
+
+/// Jobs listed by `get_analysis_queue`.
+const QUEUE_VIEW_LIMIT: i64 = 200;
+
+#[tauri::command]
+pub async fn get_analysis_queue(
+    state: State<'_, AppState>,
+) -> Result<AnalysisQueueStatus, AppError> {
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    jobs::queue_status(pool, QUEUE_VIEW_LIMIT)
+        .await
+        .map_err(AppError::db)
+}
+
+/// Queue analysis of one file ahead of automatic work. Returns the job ids,
+/// one per kind; a job already waiting for the same file is reused.
+#[tauri::command]
+pub async fn enqueue_analysis(
+    song_id: Option<i64>,
+    file_path: String,
+    kinds: Vec<AnalysisKind>,
+    state: State<'_, AppState>,
+) -> Result<Vec<i64>, AppError> {
+    state.access.require(Capability::EditLibrary)?;
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    if !Path::new(&file_path).is_file() {
+        return Err(AppError::file_not_found(&file_path));
+    }
+    if kinds.is_empty() {
+        return Err(AppError::invalid_input("No analysis kinds selected"));
+    }
+    let mut ids = Vec::with_capacity(kinds.len());
+    for kind in kinds {
+        ids.push(jobs::enqueue(pool, song_id, &file_path, kind, jobs::PRIORITY_MANUAL).await?);
+    }
+    Ok(ids)
+}
+
+#[tauri::command]
+pub async fn cancel_analysis_job(id: i64, state: State<'_, AppState>) -> Result<(), AppError> {
+    state.access.require(Capability::EditLibrary)?;
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    if !jobs::cancel(pool, id).await? {
+        return Err(AppError::not_found(format!(
+            "Analysis job {id} is not pending or running"
+        )));
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_analysis_queue_config(
+    state: State<'_, AppState>,
+) -> Result<AnalysisQueueConfig, AppError> {
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    jobs::get_config(pool).await.map_err(AppError::db)
+}
+
+#[tauri::command]
+pub async fn set_analysis_queue_config(
+    config: AnalysisQueueConfig,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    state.access.require(Capability::ManageSettings)?;
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    if !(1..=jobs::MAX_WORKERS).contains(&config.max_workers) {
+        return Err(AppError::invalid_input(format!(
+            "Workers must be between 1 and {}",
+            jobs::MAX_WORKERS
+        )));
+    }
+    if !(1..=config.max_workers).contains(&config.stems.max_concurrent) {
+        return Err(AppError::invalid_input(
+            "Stem jobs must be between 1 and the worker count",
+        ));
+    }
+    if config.stems.separation.cpu_threads > 64 {
+        return Err(AppError::invalid_input(
+            "Stem CPU threads must be 64 or fewer",
+        ));
+    }
+    jobs::save_config(pool, &config).await.map_err(AppError::db)
+}
+
+/// Stored loudness for a file; `None` until the analysis has run.
+#[tauri::command]
+pub async fn get_track_loudness(
+    file_path: String,
+    state: State<'_, AppState>,
+) -> Result<Option<TrackLoudness>, AppError> {
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    let mtime_ms = peaks::file_mtime_ms(Path::new(&file_path));
+    loudness::get_track_loudness(pool, &file_path, mtime_ms)
+        .await
+        .map_err(AppError::db)
+}
+
+/// Queue fingerprinting of the whole SAM library behind other analysis.
+/// Returns how many songs were queued.
+#[tauri::command]
+pub async fn fingerprint_library(state: State<'_, AppState>) -> Result<usize, AppError> {
+    state.access.require(Capability::EditLibrary)?;
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    let sam =
+        { state.sam_db.read().await.as_ref().cloned() }.ok_or_else(AppError::sam_db_unavailable)?;
+    fingerprint::enqueue_library(pool, &sam)
+        .await
+        .map_err(AppError::from)
+}
+
+/// Group fingerprinted songs that are likely the same recording. The
+/// result also becomes the duplicate set rotation separates by.
+#[tauri::command]
+pub async fn find_duplicate_songs(
+    options: Option<DuplicateSearchOptions>,
+    state: State<'_, AppState>,
+) -> Result<Vec<DuplicateGroup>, AppError> {
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    let options = options.unwrap_or_default();
+    if !(0.5..=1.0).contains(&options.min_similarity) {
+        return Err(AppError::invalid_input(
+            "Similarity must be between 0.5 and 1.0",
+        ));
+    }
+    let mut groups = fingerprint::refresh_duplicates(pool, options).await?;
+
+    let sam = { state.sam_db.read().await.as_ref().cloned() };
+    if let Some(sam) = sam {
+        let ids: Vec<i64> = groups
+            .iter()
+            .flat_map(|g| g.songs.iter().map(|s| s.song_id))
+            .collect();
+        let songs = crate::db::sam::get_songs_by_ids(&sam, &ids)
+            .await
+            .unwrap_or_default();
+        for song in groups.iter_mut().flat_map(|g| g.songs.iter_mut()) {
+            if let Some(sam_song) = songs.iter().find(|s| s.id == song.song_id) {
+                song.artist = sam_song.artist.clone();
+                song.title = sam_song.title.clone();
+            }
+        }
+    }
+    Ok(groups)
+}
@@ -1,19 +1,42 @@
 use serde::{Deserialize, Serialize};
 use tauri::State;
 
+use crate::access::{self, Capability};
 use crate::analytics::{
-    event_logger::{self, EventLogEntry},
-    health_monitor::{HealthMonitor, SystemHealthSnapshot},
-    listener_stats::{self, ListenerPeak, ListenerSnapshot},
+    alerts::{self, AlertConfig, AlertTarget},
+    emit_metrics::{self, EmitterMetrics},
+    event_logger::{self, EventLogEntry, EventLogFilter},
+    event_retention::{self, EventLogRetention, PruneReport},
+    health_monitor::{AlertKind, HealthAlert, HealthMonitor, SystemHealthSnapshot},
+    library_storage::{self, LibraryEntry, LibraryStorageReport},
+    listener_stats::{
+        self, ListenerBreakdown, ListenerGeoBreakdown, ListenerPeak, ListenerSnapshot,
+    },
+    missing_files::{self, IntegrityScanReport, MissingFile, MissingFileConfig, Relinked},
+    play_log::{self, PlayLogEntry, PlayLogFilter},
     play_stats::{self, HeatmapData, PlayHistoryEntry, TopSong},
+    report_digest::{self, DigestConfig},
     reports::{self, ReportData, ReportType},
+    royalty::RoyaltyReportRequest,
+    scrobbler::{self, ScrobblerConfig, ScrobblerStatus},
 };
+use crate::error::AppError;
+use crate::logging::{self, AppLogFilter, AppLogResponse};
 use crate::state::AppState;
+use crate::stats::geoip::{self, GeoIpConfig, GeoIpStatus};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlayLogResponse {
+    pub entries: Vec<PlayLogEntry>,
+    pub total: i64,
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EventLogResponse {
     pub events: Vec<EventLogEntry>,
     pub total: i64,
+    /// Pass as `before_id` for the next page; `None` on the last one
+    pub next_before_id: Option<i64>,
 }
 
 // ── Play Stats ───────────────────────────────────────────────────────────────
@@ -23,15 +46,15 @@ pub async fn get_top_songs(
     period: String,
     limit: i64,
     state: State<'_, AppState>,
-) -> Result<Vec<TopSong>, String> {
+) -> Result<Vec<TopSong>, AppError> {
     let pool = state
         .local_db
         .as_ref()
-        .ok_or("Local database not available")?;
+        .ok_or_else(AppError::db_unavailable)?;
 
     play_stats::get_top_songs(pool, &period, limit)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(AppError::from)
 }
 
 #[tauri::command]
@@ -39,15 +62,15 @@ pub async fn get_hourly_heatmap(
     start_date: String,
     end_date: String,
     state: State<'_, AppState>,
-) -> Result<Vec<HeatmapData>, String> {
+) -> Result<Vec<HeatmapData>, AppError> {
     let pool = state
         .local_db
         .as_ref()
-        .ok_or("Local database not available")?;
+        .ok_or_else(AppError::db_unavailable)?;
 
     play_stats::get_hourly_heatmap(pool, &start_date, &end_date)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(AppError::from)
 }
 
 #[tauri::command]
@@ -55,15 +78,15 @@ pub async fn get_song_play_history(
     song_id: i64,
     limit: i64,
     state: State<'_, AppState>,
-) -> Result<Vec<PlayHistoryEntry>, String> {
+) -> Result<Vec<PlayHistoryEntry>, AppError> {
     let pool = state
         .local_db
         .as_ref()
-        .ok_or("Local database not available")?;
+        .ok_or_else(AppError::db_unavailable)?;
 
     play_stats::get_song_play_history(pool, song_id, limit)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(AppError::from)
 }
 
 // ── Listener Stats ───────────────────────────────────────────────────────────
@@ -73,15 +96,15 @@ pub async fn get_listener_graph(
     encoder_id: i64,
     period: String,
     state: State<'_, AppState>,
-) -> Result<Vec<ListenerSnapshot>, String> {
+) -> Result<Vec<ListenerSnapshot>, AppError> {
     let pool = state
         .local_db
         .as_ref()
-        .ok_or("Local database not available")?;
+        .ok_or_else(AppError::db_unavailable)?;
 
     listener_stats::get_listener_graph(pool, encoder_id, &period)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(AppError::from)
 }
 
 #[tauri::command]
@@ -89,20 +112,104 @@ pub async fn get_listener_peak(
     encoder_id: i64,
     period: String,
     state: State<'_, AppState>,
-) -> Result<ListenerPeak, String> {
+) -> Result<ListenerPeak, AppError> {
     let pool = state
         .local_db
         .as_ref()
-        .ok_or("Local database not available")?;
+        .ok_or_else(AppError::db_unavailable)?;
 
     listener_stats::get_listener_peak(pool, encoder_id, &period)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(AppError::from)
+}
+
+/// Unique listeners plus top user agents / countries from the admin client lists.
+#[tauri::command]
+pub async fn get_listener_breakdown(
+    encoder_id: i64,
+    period: String,
+    limit: Option<i64>,
+    state: State<'_, AppState>,
+) -> Result<ListenerBreakdown, AppError> {
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+
+    listener_stats::get_listener_breakdown(pool, encoder_id, &period, limit.unwrap_or(10))
+        .await
+        .map_err(AppError::from)
+}
+
+/// Listener countries and cities from GeoIP; `encoder_id: None` covers all encoders.
+#[tauri::command]
+pub async fn get_listener_geo_breakdown(
+    encoder_id: Option<i64>,
+    period: String,
+    limit: Option<i64>,
+    state: State<'_, AppState>,
+) -> Result<ListenerGeoBreakdown, AppError> {
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+
+    listener_stats::get_listener_geo_breakdown(pool, encoder_id, &period, limit.unwrap_or(10))
+        .await
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn get_geoip_status() -> Result<GeoIpStatus, AppError> {
+    Ok(geoip::status())
+}
+
+/// Point lookups at a MaxMind `.mmdb` file; fails if it cannot be opened.
+#[tauri::command]
+pub async fn set_geoip_config(
+    config: GeoIpConfig,
+    state: State<'_, AppState>,
+) -> Result<GeoIpStatus, AppError> {
+    state.access.require(Capability::ManageSettings)?;
+    Ok(geoip::set_config(config)?)
+}
+
+/// Completed, partial and skipped plays, newest first.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn get_play_log(
+    limit: i64,
+    offset: i64,
+    start_time: Option<i64>,
+    end_time: Option<i64>,
+    song_id: Option<i64>,
+    deck: Option<String>,
+    outcome: Option<String>,
+    reason: Option<String>,
+    state: State<'_, AppState>,
+) -> Result<PlayLogResponse, AppError> {
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+
+    let filter = PlayLogFilter {
+        start_time,
+        end_time,
+        song_id,
+        deck,
+        outcome,
+        reason,
+    };
+    let (entries, total) = play_log::get_play_log(pool, &filter, limit, offset).await?;
+
+    Ok(PlayLogResponse { entries, total })
 }
 
 // ── Event Log ────────────────────────────────────────────────────────────────
 
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub async fn get_event_log(
     limit: i64,
     offset: i64,
@@ -112,43 +219,98 @@ pub async fn get_event_log(
     end_time: Option<i64>,
     search: Option<String>,
     deck: Option<String>,
+    before_id: Option<i64>,
     state: State<'_, AppState>,
-) -> Result<EventLogResponse, String> {
+) -> Result<EventLogResponse, AppError> {
     let pool = state
         .local_db
         .as_ref()
-        .ok_or("Local database not available")?;
+        .ok_or_else(AppError::db_unavailable)?;
 
-    let (events, total) = event_logger::get_event_log(
-        pool,
-        limit,
-        offset,
-        level.as_deref(),
-        category.as_deref(),
+    let filter = EventLogFilter {
+        level,
+        category,
         start_time,
         end_time,
-        search.as_deref(),
-        deck.as_deref(),
-    )
-    .await
-    .map_err(|e| e.to_string())?;
+        search,
+        deck,
+        before_id,
+    };
+    let (events, total) = event_logger::get_event_log(pool, limit, offset, &filter).await?;
+    let next_before_id = events
+        .last()
+        .filter(|_| events.len() as i64 >= limit.max(1))
+        .map(|e| e.id);
+
+    Ok(EventLogResponse {
+        events,
+        total,
+        next_before_id,
+    })
+}
 
-    Ok(EventLogResponse { events, total })
+/// Recent lines from the rotating app log files, newest first.
+#[tauri::command]
+pub async fn get_app_logs(
+    limit: Option<usize>,
+    level: Option<String>,
+    category: Option<String>,
+    search: Option<String>,
+) -> Result<AppLogResponse, AppError> {
+    let filter = AppLogFilter {
+        level,
+        category,
+        search,
+    };
+    let limit = limit.unwrap_or(500).clamp(1, 5_000);
+    let entries =
+        tokio::task::spawn_blocking(move || logging::read_app_logs(&filter, limit)).await??;
+
+    Ok(AppLogResponse {
+        log_dir: logging::log_dir().map(|dir| dir.display().to_string()),
+        entries,
+    })
 }
 
 #[tauri::command]
 pub async fn clear_event_log(
     older_than_days: i64,
     state: State<'_, AppState>,
-) -> Result<u64, String> {
+) -> Result<u64, AppError> {
+    state.access.require(Capability::ManageSettings)?;
     let pool = state
         .local_db
         .as_ref()
-        .ok_or("Local database not available")?;
+        .ok_or_else(AppError::db_unavailable)?;
 
     event_logger::clear_event_log(pool, older_than_days)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn get_event_log_retention() -> Result<EventLogRetention, AppError> {
+    Ok(event_retention::load_config())
+}
+
+#[tauri::command]
+pub async fn set_event_log_retention(
+    config: EventLogRetention,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    state.access.require(Capability::ManageSettings)?;
+    Ok(event_retention::save_config(&config)?)
+}
+
+/// Apply the saved retention policy now, archiving first if it says to.
+#[tauri::command]
+pub async fn prune_event_log(state: State<'_, AppState>) -> Result<PruneReport, AppError> {
+    state.access.require(Capability::ManageSettings)?;
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    Ok(event_retention::prune(pool, &event_retention::load_config()).await?)
 }
 
 // ── System Health ────────────────────────────────────────────────────────────
@@ -156,23 +318,347 @@ pub async fn clear_event_log(
 #[tauri::command]
 pub async fn get_health_snapshot(
     state: State<'_, AppState>,
-) -> Result<SystemHealthSnapshot, String> {
+) -> Result<SystemHealthSnapshot, AppError> {
     Ok(state.health_monitor.get_current_snapshot().await)
 }
 
+#[tauri::command]
+pub async fn get_emitter_metrics() -> Result<EmitterMetrics, AppError> {
+    Ok(emit_metrics::get_emitter_metrics())
+}
+
 #[tauri::command]
 pub async fn get_health_history(
     period_minutes: i64,
     state: State<'_, AppState>,
-) -> Result<Vec<SystemHealthSnapshot>, String> {
+) -> Result<Vec<SystemHealthSnapshot>, AppError> {
     let pool = state
         .local_db
         .as_ref()
-        .ok_or("Local database not available")?;
+        .ok_or_else(AppError::db_unavailable)?;
 
     HealthMonitor::get_health_history(pool, period_minutes)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(AppError::from)
+}
+
+// ── Health alerts ────────────────────────────────────────────────────────────
+
+#[tauri::command]
+pub async fn get_alert_config(state: State<'_, AppState>) -> Result<AlertConfig, AppError> {
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    alerts::get_config(pool).await.map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn set_alert_config(
+    config: AlertConfig,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    state.access.require(Capability::ManageSettings)?;
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    alerts::save_config(pool, &config)
+        .await
+        .map_err(AppError::from)
+}
+
+/// Send a sample alert to one target so the operator can check delivery.
+#[tauri::command]
+pub async fn test_alert_target(
+    target: AlertTarget,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    let now = chrono::Utc::now().timestamp_millis();
+    let alert = HealthAlert {
+        kind: target.kinds.first().copied().unwrap_or(AlertKind::DeadAir),
+        resolved: false,
+        message: "Test alert from DesiZone Broadcaster".to_string(),
+        encoder_id: None,
+        deck: None,
+        since: now,
+        timestamp: now,
+    };
+    alerts::send(&target, &alert, &state.script_engine)
+        .await
+        .map_err(AppError::from)
+}
+
+// ── Report digest ────────────────────────────────────────────────────────────
+
+#[tauri::command]
+pub async fn get_report_digest_config() -> Result<DigestConfig, AppError> {
+    Ok(report_digest::load_config_redacted())
+}
+
+#[tauri::command]
+pub async fn set_report_digest_config(
+    config: DigestConfig,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    state.access.require(Capability::ManageSettings)?;
+    Ok(report_digest::save_config(config)?)
+}
+
+/// Mail the latest digest now with `config`, saved or not.
+#[tauri::command]
+pub async fn test_report_digest(
+    config: DigestConfig,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    state.access.require(Capability::ManageSettings)?;
+    Ok(report_digest::send_test(&state, &config).await?)
+}
+
+// ── Library storage ──────────────────────────────────────────────────────────
+
+/// Every SAM song with its translated local path.
+async fn load_library_entries(
+    state: &AppState,
+    sam_pool: &sqlx::MySqlPool,
+) -> Result<Vec<LibraryEntry>, AppError> {
+    let songs = crate::db::sam::get_all_songs(sam_pool).await?;
+    let categories = crate::db::sam::get_song_category_names(sam_pool, None)
+        .await
+        .unwrap_or_default();
+    let translator = match &state.local_db {
+        Some(pool) => crate::db::path_rules::load_translator(pool).await,
+        None => Default::default(),
+    };
+
+    Ok(songs
+        .into_iter()
+        .map(|song| LibraryEntry {
+            song_id: song.id,
+            category: categories.get(&song.id).cloned().unwrap_or_default(),
+            path: translator.translate(&song.filename),
+            artist: song.artist,
+            title: song.title,
+            duration_secs: song.duration.max(0) as u32,
+        })
+        .collect())
+}
+
+#[tauri::command]
+pub async fn get_library_storage_report(
+    state: State<'_, AppState>,
+) -> Result<LibraryStorageReport, AppError> {
+    let sam_pool = {
+        let guard = state.sam_db.read().await;
+        guard.clone()
+    }
+    .ok_or_else(AppError::sam_db_unavailable)?;
+    let entries = load_library_entries(&state, &sam_pool).await?;
+
+    let (files, missing) =
+        tokio::task::spawn_blocking(move || library_storage::scan_files(entries)).await?;
+    Ok(library_storage::analyze(
+        files,
+        missing,
+        chrono::Utc::now().timestamp_millis(),
+    ))
+}
+
+// ── Missing files ────────────────────────────────────────────────────────────
+
+/// Check every song's file, relink confident matches under the music roots
+/// (when enabled) and store what is still missing.
+#[tauri::command]
+pub async fn scan_missing_files(
+    state: State<'_, AppState>,
+) -> Result<IntegrityScanReport, AppError> {
+    let actor = state.access.require(Capability::EditRotation)?;
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    let sam_pool = {
+        let guard = state.sam_db.read().await;
+        guard.clone()
+    }
+    .ok_or_else(AppError::sam_db_unavailable)?;
+    let config = missing_files::get_config(pool)
+        .await
+        .map_err(AppError::db)?;
+    let started_at = chrono::Utc::now().timestamp_millis();
+    let entries = load_library_entries(&state, &sam_pool).await?;
+    let scanned = entries.len();
+
+    let roots = config.music_roots.clone();
+    let tolerance = config.duration_tolerance_secs;
+    let found = tokio::task::spawn_blocking(move || {
+        let missing = missing_files::find_missing(entries);
+        let index = if missing.is_empty() {
+            Default::default()
+        } else {
+            missing_files::index_roots(&roots)
+        };
+        missing
+            .into_iter()
+            .map(|entry| {
+                let candidates = missing_files::candidates_for(&entry, &index, tolerance);
+                (entry, candidates)
+            })
+            .collect::<Vec<_>>()
+    })
+    .await?;
+
+    let translator = crate::db::path_rules::load_translator(pool).await;
+    let mut relinked = Vec::new();
+    let mut still_missing = Vec::new();
+    for (entry, candidates) in found {
+        let pick = config
+            .auto_relink
+            .then(|| missing_files::auto_pick(&candidates))
+            .flatten();
+        // SAM keeps its own view of the path; a match no rule maps back
+        // stays listed for a manual decision.
+        let sam_path = pick.and_then(|p| Some((p, translator.untranslate(&p.path)?)));
+        if let Some((pick, sam_path)) = sam_path {
+            crate::db::sam::set_song_filename(&sam_pool, entry.song_id, &sam_path)
+                .await
+                .map_err(AppError::db)?;
+            relinked.push(Relinked {
+                song_id: entry.song_id,
+                old_path: entry.path,
+                new_path: pick.path.clone(),
+            });
+            continue;
+        }
+        still_missing.push(MissingFile {
+            song_id: entry.song_id,
+            artist: entry.artist,
+            title: entry.title,
+            path: entry.path,
+            duration_secs: entry.duration_secs,
+            detected_at: started_at,
+            candidates,
+        });
+    }
+    missing_files::replace_missing(pool, &still_missing)
+        .await
+        .map_err(AppError::db)?;
+
+    let report = IntegrityScanReport {
+        scanned,
+        missing: still_missing.len(),
+        relinked,
+        started_at,
+        finished_at: chrono::Utc::now().timestamp_millis(),
+    };
+    log::info!(
+        "Missing-file scan: {} songs, {} missing, {} relinked",
+        report.scanned,
+        report.missing,
+        report.relinked.len()
+    );
+    if !report.relinked.is_empty() {
+        access::audit(
+            &state,
+            &actor,
+            "library.auto_relink",
+            None,
+            serde_json::json!({ "relinked": report.relinked }),
+        )
+        .await;
+    }
+    Ok(report)
+}
+
+/// Songs flagged by the last scan, with relink candidates.
+#[tauri::command]
+pub async fn get_missing_files(state: State<'_, AppState>) -> Result<Vec<MissingFile>, AppError> {
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    missing_files::get_missing(pool).await.map_err(AppError::db)
+}
+
+/// Point a song at the local file `path`, written to the SAM songlist in
+/// SAM's form through the path rules, and clear its missing flag.
+#[tauri::command]
+pub async fn relink_song(
+    song_id: i64,
+    path: String,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    let actor = state.access.require(Capability::EditRotation)?;
+    if !std::path::Path::new(&path).is_file() {
+        return Err(AppError::invalid_input(format!("File not found: {path}")));
+    }
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    let sam_path = crate::db::path_rules::load_translator(pool)
+        .await
+        .untranslate(&path)
+        .ok_or_else(|| {
+            AppError::invalid_input(format!(
+                "No prefix path rule maps {path} back to a SAM path; add one for its folder"
+            ))
+        })?;
+    let previous = missing_files::get_missing_song(pool, song_id)
+        .await
+        .map_err(AppError::db)?;
+    {
+        let guard = state.sam_db.read().await;
+        let sam_pool = guard.as_ref().ok_or_else(AppError::sam_db_unavailable)?;
+        if !crate::db::sam::set_song_filename(sam_pool, song_id, &sam_path)
+            .await
+            .map_err(AppError::db)?
+        {
+            return Err(AppError::not_found(format!("Song {song_id} not found")));
+        }
+    }
+    missing_files::clear_missing(pool, song_id)
+        .await
+        .map_err(AppError::db)?;
+    access::audit(
+        &state,
+        &actor,
+        "library.relink",
+        Some(format!("song:{song_id}")),
+        serde_json::json!({
+            "old_path": previous.map(|m| m.path),
+            "new_path": path,
+            "sam_path": sam_path,
+        }),
+    )
+    .await;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_missing_file_config(
+    state: State<'_, AppState>,
+) -> Result<MissingFileConfig, AppError> {
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    missing_files::get_config(pool).await.map_err(AppError::db)
+}
+
+#[tauri::command]
+pub async fn set_missing_file_config(
+    config: MissingFileConfig,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    state.access.require(Capability::ManageSettings)?;
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    missing_files::save_config(pool, &config)
+        .await
+        .map_err(AppError::db)
 }
 
 // ── Reports ──────────────────────────────────────────────────────────────────
@@ -181,11 +667,11 @@ pub async fn get_health_history(
 pub async fn generate_report(
     report_type: ReportType,
     state: State<'_, AppState>,
-) -> Result<ReportData, String> {
+) -> Result<ReportData, AppError> {
     let pool = state
         .local_db
         .as_ref()
-        .ok_or("Local database not available")?;
+        .ok_or_else(AppError::db_unavailable)?;
 
     let sam_pool = {
         let guard = state.sam_db.read().await;
@@ -194,12 +680,84 @@ pub async fn generate_report(
 
     reports::generate_report(pool, sam_pool.as_ref(), report_type)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub fn export_report_csv(report_data: ReportData) -> Result<String, AppError> {
+    reports::export_report_csv(&report_data).map_err(AppError::from)
+}
+
+/// Proof-of-play CSV for traffic affidavits (local dates, inclusive).
+#[tauri::command]
+pub async fn export_traffic_affidavit_csv(
+    start_date: String,
+    end_date: String,
+    campaign_id: Option<i64>,
+    state: State<'_, AppState>,
+) -> Result<String, AppError> {
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    reports::export_proof_of_play_csv(pool, &start_date, &end_date, campaign_id)
+        .await
+        .map_err(AppError::from)
 }
 
+/// Per-show audience CSV (local dates, inclusive).
 #[tauri::command]
-pub fn export_report_csv(report_data: ReportData) -> Result<String, String> {
-    reports::export_report_csv(&report_data)
+pub async fn export_show_audience_csv(
+    start_date: String,
+    end_date: String,
+    show_id: Option<i64>,
+    compare_weeks: Option<u32>,
+    state: State<'_, AppState>,
+) -> Result<String, AppError> {
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    reports::export_show_audience_csv(
+        pool,
+        &start_date,
+        &end_date,
+        show_id,
+        compare_weeks.unwrap_or(4),
+    )
+    .await
+    .map_err(AppError::from)
+}
+
+/// SoundExchange / PPL / BMI spin report (CSV or TSV) for a reporting period.
+#[tauri::command]
+pub async fn export_royalty_report(
+    request: RoyaltyReportRequest,
+    state: State<'_, AppState>,
+) -> Result<String, AppError> {
+    let sam_pool =
+        { state.sam_db.read().await.as_ref().cloned() }.ok_or_else(AppError::sam_db_unavailable)?;
+    request.period.dates().map_err(AppError::invalid_input)?;
+    reports::export_royalty_report(state.local_db.as_ref(), &sam_pool, &request)
+        .await
+        .map_err(AppError::from)
+}
+
+/// Hourly listening hours / retention CSV with session KPIs (local dates, inclusive).
+#[tauri::command]
+pub async fn export_listener_kpis_csv(
+    start_date: String,
+    end_date: String,
+    encoder_id: Option<i64>,
+    state: State<'_, AppState>,
+) -> Result<String, AppError> {
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    reports::export_listener_kpis_csv(pool, &start_date, &end_date, encoder_id)
+        .await
+        .map_err(AppError::from)
 }
 
 #[tauri::command]
@@ -211,11 +769,11 @@ pub async fn write_event_log(
     deck: Option<String>,
     song_id: Option<i64>,
     state: State<'_, AppState>,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     let pool = state
         .local_db
         .as_ref()
-        .ok_or("Local database not available")?;
+        .ok_or_else(AppError::db_unavailable)?;
 
     let log_level = match level.as_str() {
         "error" => event_logger::LogLevel::Error,
@@ -245,5 +803,121 @@ pub async fn write_event_log(
         None,
     )
     .await
-    .map_err(|e| e.to_string())
+    .map_err(AppError::from)
+}
+
+// ── Scrobbling ────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LastFmAuthRequest {
+    pub token: String,
+    /// Page the user must open to approve the app
+    pub auth_url: String,
+}
+
+#[tauri::command]
+pub async fn get_scrobbler_config(state: State<'_, AppState>) -> Result<ScrobblerConfig, AppError> {
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    scrobbler::get_config(pool).await.map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn set_scrobbler_config(
+    config: ScrobblerConfig,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    state.access.require(Capability::ManageSettings)?;
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    let mut config = config;
+    let current = scrobbler::get_config(pool).await?;
+    // A session belongs to the API key it was issued for.
+    if config.lastfm.api_key != current.lastfm.api_key {
+        config.lastfm.session_key = None;
+        config.lastfm.username = None;
+    }
+    if config.listenbrainz.user_token != current.listenbrainz.user_token {
+        config.listenbrainz.username = None;
+    }
+    scrobbler::save_config(pool, &config)
+        .await
+        .map_err(AppError::from)
+}
+
+/// Step 1 of Last.fm auth: open `auth_url`, then call `lastfm_complete_auth`.
+#[tauri::command]
+pub async fn lastfm_begin_auth(state: State<'_, AppState>) -> Result<LastFmAuthRequest, AppError> {
+    state.access.require(Capability::ManageSettings)?;
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    let config = scrobbler::get_config(pool).await?;
+    if config.lastfm.api_key.is_empty() || config.lastfm.api_secret.is_empty() {
+        return Err(AppError::invalid_input(
+            "Last.fm API key and secret are required",
+        ));
+    }
+    let (token, auth_url) = scrobbler::lastfm_begin_auth(&config.lastfm).await?;
+    Ok(LastFmAuthRequest { token, auth_url })
+}
+
+/// Step 2 of Last.fm auth: exchange the approved token for a session key.
+#[tauri::command]
+pub async fn lastfm_complete_auth(
+    token: String,
+    state: State<'_, AppState>,
+) -> Result<ScrobblerConfig, AppError> {
+    state.access.require(Capability::ManageSettings)?;
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    let mut config = scrobbler::get_config(pool).await?;
+    let (username, session_key) = scrobbler::lastfm_complete_auth(&config.lastfm, &token).await?;
+    config.lastfm.username = Some(username);
+    config.lastfm.session_key = Some(session_key);
+    scrobbler::save_config(pool, &config).await?;
+    Ok(config)
+}
+
+#[tauri::command]
+pub async fn listenbrainz_validate_token(
+    state: State<'_, AppState>,
+) -> Result<ScrobblerConfig, AppError> {
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    let mut config = scrobbler::get_config(pool).await?;
+    let username = scrobbler::listenbrainz_validate(&config.listenbrainz).await?;
+    config.listenbrainz.username = Some(username);
+    scrobbler::save_config(pool, &config).await?;
+    Ok(config)
+}
+
+#[tauri::command]
+pub async fn get_scrobbler_status(state: State<'_, AppState>) -> Result<ScrobblerStatus, AppError> {
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    scrobbler::get_status(pool).await.map_err(AppError::from)
+}
+
+/// Submit queued scrobbles now instead of waiting for the next drain.
+#[tauri::command]
+pub async fn flush_scrobble_queue(state: State<'_, AppState>) -> Result<ScrobblerStatus, AppError> {
+    state.access.require(Capability::ManageSettings)?;
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    scrobbler::drain_queue(pool).await?;
+    scrobbler::get_status(pool).await.map_err(AppError::from)
 }
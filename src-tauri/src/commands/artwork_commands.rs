@@ -0,0 +1,88 @@
+use tauri::State;
+
+use crate::access::Capability;
+use crate::audio::analyzer::artwork::{self, ArtworkConfig, ArtworkLookup, SongArtwork};
+use crate::error::AppError;
+use crate::state::AppState;
+
+/// Build the artwork lookup for a song: SAM metadata when connected,
+/// otherwise just the file (embedded art only).
+pub(crate) async fn lookup_for(
+    state: &AppState,
+    song_id: i64,
+    file_path: Option<String>,
+) -> ArtworkLookup {
+    let sam_pool = { state.sam_db.read().await.as_ref().cloned() };
+    let song = match sam_pool {
+        Some(pool) => crate::db::sam::get_song(&pool, song_id)
+            .await
+            .ok()
+            .flatten(),
+        None => None,
+    };
+    let mut lookup = match (song, &state.local_db) {
+        (Some(song), Some(local)) => ArtworkLookup::for_song(local, &song).await,
+        _ => ArtworkLookup {
+            song_id,
+            ..Default::default()
+        },
+    };
+    // A path from a loaded deck is already local; prefer it.
+    if let Some(path) = file_path.filter(|p| !p.is_empty()) {
+        lookup.file_path = Some(path);
+    }
+    lookup
+}
+
+/// Cover art for a song. `size` picks a cached JPEG variant (96/300/600);
+/// omit it for the original image.
+#[tauri::command]
+pub async fn get_song_artwork(
+    song_id: i64,
+    file_path: Option<String>,
+    size: Option<u32>,
+    state: State<'_, AppState>,
+) -> Result<Option<SongArtwork>, AppError> {
+    let local = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    let config = artwork::get_config(local).await?;
+    let lookup = lookup_for(&state, song_id, file_path).await;
+    artwork::resolve(&lookup, size, &config)
+        .await
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn clear_artwork_cache(
+    song_id: Option<i64>,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    state.access.require(Capability::ManageSettings)?;
+    artwork::clear_cache(song_id).await.map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn get_artwork_config(state: State<'_, AppState>) -> Result<ArtworkConfig, AppError> {
+    let local = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    artwork::get_config(local).await.map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn set_artwork_config(
+    config: ArtworkConfig,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    state.access.require(Capability::ManageSettings)?;
+    let local = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    artwork::save_config(local, &config)
+        .await
+        .map_err(AppError::from)
+}
@@ -1,25 +1,30 @@
 use std::path::PathBuf;
 use tauri::{AppHandle, Emitter, State};
 
+use crate::access::Capability;
+use crate::error::AppError;
 use crate::{
     audio::{
         crossfade::DeckId,
+        deck::{Deck, StopReason},
         device_manager::{AudioOutputDevice, AudioOutputRoutingConfig, AudioOutputStatus},
-        engine::DeckStateEvent,
+        dsp::eq::EqBand,
+        engine::{DeckStateEvent, LoopRange},
+        loudness_meter, multitrack, remote_stream, spectrum,
     },
-    db::local::MonitorRoutingConfig,
+    db::local::{CategoryGainTrim, GainTrimKind, MonitorRoutingConfig},
     state::AppState,
 };
 
-pub(crate) fn parse_deck(deck: &str) -> Result<DeckId, String> {
+pub(crate) fn parse_deck(deck: &str) -> Result<DeckId, AppError> {
     match deck {
         "deck_a" => Ok(DeckId::DeckA),
         "deck_b" => Ok(DeckId::DeckB),
         "sound_fx" => Ok(DeckId::SoundFx),
-        "aux_1" => Ok(DeckId::Aux1),
-        "aux_2" => Ok(DeckId::Aux2),
+        "aux_1" | "deck_c" => Ok(DeckId::Aux1),
+        "aux_2" | "deck_d" => Ok(DeckId::Aux2),
         "voice_fx" => Ok(DeckId::VoiceFx),
-        _ => Err(format!("Unknown deck: {deck}")),
+        _ => Err(AppError::invalid_input(format!("Unknown deck: {deck}"))),
     }
 }
 
@@ -29,50 +34,155 @@ pub async fn load_track(
     file_path: String,
     song_id: Option<i64>,
     state: State<'_, AppState>,
-) -> Result<(), String> {
-    let deck_id = parse_deck(&deck)?;
+) -> Result<(), AppError> {
+    state.access.require(Capability::ControlPlayout)?;
+    load(&state, parse_deck(&deck)?, file_path, song_id).await
+}
+
+/// Load without a capability check, for scripts and other station-side callers.
+pub(crate) async fn load(
+    state: &AppState,
+    deck_id: DeckId,
+    file_path: String,
+    song_id: Option<i64>,
+) -> Result<(), AppError> {
     let path = PathBuf::from(&file_path);
 
+    if remote_stream::is_remote_url(&file_path) {
+        return load_stream(deck_id, path, song_id, state).await;
+    }
+
     // Validate before handing off to the RT ring buffer so the frontend
     // receives an immediate, descriptive error instead of silent failure.
     if !path.exists() {
-        return Err(format!("File not found: {file_path}"));
+        return Err(AppError::file_not_found(&file_path));
     }
     if !path.is_file() {
-        return Err(format!("Path is not a file: {file_path}"));
+        return Err(AppError::invalid_input(format!(
+            "Path is not a file: {file_path}"
+        )));
     }
+    crate::scheduler::track_preflight::check_manual_load(&path, None).await?;
 
-    state
-        .engine
-        .lock()
-        .unwrap()
-        .load_track(deck_id, path, song_id)
+    let trim_db = crate::resolve_track_gain_db(state, song_id).await;
+    let mut engine = state.engine.lock().unwrap();
+    engine.load_track(deck_id, path, song_id)?;
+    engine.set_track_gain_db(deck_id, trim_db)
+}
+
+/// HTTP(S) streams connect and prebuffer before the engine lock is taken, so
+/// a slow server does not stall the other decks' commands.
+async fn load_stream(
+    deck_id: DeckId,
+    url: PathBuf,
+    song_id: Option<i64>,
+    state: &AppState,
+) -> Result<(), AppError> {
+    let prepared =
+        tokio::task::spawn_blocking(move || Deck::prepare_load(url, song_id, None, false, None))
+            .await
+            .map_err(|e| e.to_string())??;
+    let trim_db = crate::resolve_track_gain_db(state, song_id).await;
+    let mut engine = state.engine.lock().unwrap();
+    engine.load_prepared(deck_id, prepared)?;
+    engine.set_track_gain_db(deck_id, trim_db)
 }
 
+// ── Category gain trims ──────────────────────────────────────────────────────
+
 #[tauri::command]
-pub async fn play_deck(deck: String, state: State<'_, AppState>) -> Result<(), String> {
+pub async fn get_category_gain_trims(
+    state: State<'_, AppState>,
+) -> Result<Vec<CategoryGainTrim>, AppError> {
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    crate::db::local::get_category_gain_trims(pool)
+        .await
+        .map_err(AppError::from)
+}
+
+/// Set the trim for a SAM category or song type. Applies from the next load.
+#[tauri::command]
+pub async fn set_category_gain_trim(
+    trim: CategoryGainTrim,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    state.access.require(Capability::EditLibrary)?;
+    if trim.name.trim().is_empty() {
+        return Err(AppError::invalid_input("Category name is required"));
+    }
+    if !(-24.0..=12.0).contains(&trim.gain_db) {
+        return Err(AppError::invalid_input(
+            "Gain trim must be between -24 and +12 dB",
+        ));
+    }
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    crate::db::local::upsert_category_gain_trim(pool, &trim)
+        .await
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn delete_category_gain_trim(
+    kind: GainTrimKind,
+    name: String,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    state.access.require(Capability::EditLibrary)?;
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    crate::db::local::delete_category_gain_trim(pool, kind, &name)
+        .await
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn play_deck(deck: String, state: State<'_, AppState>) -> Result<(), AppError> {
+    state.access.require(Capability::ControlPlayout)?;
     let deck_id = parse_deck(&deck)?;
     state.engine.lock().unwrap().play(deck_id)
 }
 
 #[tauri::command]
-pub async fn pause_deck(deck: String, state: State<'_, AppState>) -> Result<(), String> {
+pub async fn pause_deck(deck: String, state: State<'_, AppState>) -> Result<(), AppError> {
+    state.access.require(Capability::ControlPlayout)?;
     let deck_id = parse_deck(&deck)?;
     state.engine.lock().unwrap().pause(deck_id)
 }
 
 #[tauri::command]
-pub async fn stop_deck(deck: String, state: State<'_, AppState>) -> Result<(), String> {
-    let deck_id = parse_deck(&deck)?;
+pub async fn stop_deck(deck: String, state: State<'_, AppState>) -> Result<(), AppError> {
+    state.access.require(Capability::ControlPlayout)?;
+    stop(&state, parse_deck(&deck)?)
+}
+
+/// Pause and rewind to the top, without a capability check.
+pub(crate) fn stop(state: &AppState, deck_id: DeckId) -> Result<(), AppError> {
     let mut engine = state.engine.lock().unwrap();
     let _ = engine.pause(deck_id);
+    // A stream has nowhere to rewind to; stopping it is a pause.
+    if engine.is_remote_stream(deck_id) {
+        return Ok(());
+    }
     engine.seek(deck_id, 0)
 }
 
 #[tauri::command]
-pub async fn next_deck(deck: String, state: State<'_, AppState>) -> Result<(), String> {
+pub async fn next_deck(deck: String, state: State<'_, AppState>) -> Result<(), AppError> {
+    state.access.require(Capability::ControlPlayout)?;
     let deck_id = parse_deck(&deck)?;
-    state.engine.lock().unwrap().stop_with_completion(deck_id)
+    state
+        .engine
+        .lock()
+        .unwrap()
+        .stop_with_completion(deck_id, StopReason::Skipped)
 }
 
 #[tauri::command]
@@ -80,7 +190,8 @@ pub async fn seek_deck(
     deck: String,
     position_ms: u64,
     state: State<'_, AppState>,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
+    state.access.require(Capability::ControlPlayout)?;
     let deck_id = parse_deck(&deck)?;
     state.engine.lock().unwrap().seek(deck_id, position_ms)
 }
@@ -90,7 +201,8 @@ pub async fn jog_deck(
     deck: String,
     delta_steps: i8,
     state: State<'_, AppState>,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
+    state.access.require(Capability::ControlPlayout)?;
     let deck_id = parse_deck(&deck)?;
     if delta_steps == 0 {
         return Ok(());
@@ -122,7 +234,8 @@ pub async fn set_channel_gain(
     deck: String,
     gain: f32,
     state: State<'_, AppState>,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
+    state.access.require(Capability::ControlPlayout)?;
     let deck_id = parse_deck(&deck)?;
     state.engine.lock().unwrap().set_channel_gain(deck_id, gain)
 }
@@ -132,7 +245,8 @@ pub async fn set_deck_bass(
     deck: String,
     bass_db: f32,
     state: State<'_, AppState>,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
+    state.access.require(Capability::ControlPlayout)?;
     let deck_id = parse_deck(&deck)?;
     state.engine.lock().unwrap().set_deck_bass(deck_id, bass_db)
 }
@@ -142,7 +256,8 @@ pub async fn set_deck_filter(
     deck: String,
     amount: f32,
     state: State<'_, AppState>,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
+    state.access.require(Capability::ControlPlayout)?;
     let deck_id = parse_deck(&deck)?;
     state
         .engine
@@ -152,12 +267,29 @@ pub async fn set_deck_filter(
 }
 
 #[tauri::command]
-pub async fn set_master_level(level: f32, state: State<'_, AppState>) -> Result<(), String> {
+pub async fn set_deck_eq_kill(
+    deck: String,
+    band: EqBand,
+    killed: bool,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    state.access.require(Capability::ControlPlayout)?;
+    let deck_id = parse_deck(&deck)?;
+    state
+        .engine
+        .lock()
+        .unwrap()
+        .set_deck_eq_kill(deck_id, band, killed)
+}
+
+#[tauri::command]
+pub async fn set_master_level(level: f32, state: State<'_, AppState>) -> Result<(), AppError> {
+    state.access.require(Capability::ControlPlayout)?;
     state.engine.lock().unwrap().set_master_level(level)
 }
 
 #[tauri::command]
-pub async fn get_master_level(state: State<'_, AppState>) -> Result<f32, String> {
+pub async fn get_master_level(state: State<'_, AppState>) -> Result<f32, AppError> {
     Ok(state.engine.lock().unwrap().get_master_level())
 }
 
@@ -165,32 +297,35 @@ pub async fn get_master_level(state: State<'_, AppState>) -> Result<f32, String>
 pub async fn set_local_monitor_muted(
     muted: bool,
     state: State<'_, AppState>,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
+    state.access.require(Capability::ControlPlayout)?;
     state.engine.lock().unwrap().set_local_monitor_muted(muted)
 }
 
 #[tauri::command]
-pub async fn get_local_monitor_muted(state: State<'_, AppState>) -> Result<bool, String> {
+pub async fn get_local_monitor_muted(state: State<'_, AppState>) -> Result<bool, AppError> {
     Ok(state.engine.lock().unwrap().get_local_monitor_muted())
 }
 
 #[tauri::command]
-pub async fn set_headphone_mix(value: f32, state: State<'_, AppState>) -> Result<(), String> {
+pub async fn set_headphone_mix(value: f32, state: State<'_, AppState>) -> Result<(), AppError> {
+    state.access.require(Capability::ControlPlayout)?;
     state.engine.lock().unwrap().set_headphone_mix(value)
 }
 
 #[tauri::command]
-pub async fn set_headphone_level(value: f32, state: State<'_, AppState>) -> Result<(), String> {
+pub async fn set_headphone_level(value: f32, state: State<'_, AppState>) -> Result<(), AppError> {
+    state.access.require(Capability::ControlPlayout)?;
     state.engine.lock().unwrap().set_headphone_level(value)
 }
 
 #[tauri::command]
-pub async fn get_headphone_mix(state: State<'_, AppState>) -> Result<f32, String> {
+pub async fn get_headphone_mix(state: State<'_, AppState>) -> Result<f32, AppError> {
     Ok(state.engine.lock().unwrap().get_headphone_mix())
 }
 
 #[tauri::command]
-pub async fn get_headphone_level(state: State<'_, AppState>) -> Result<f32, String> {
+pub async fn get_headphone_level(state: State<'_, AppState>) -> Result<f32, AppError> {
     Ok(state.engine.lock().unwrap().get_headphone_level())
 }
 
@@ -199,7 +334,8 @@ pub async fn set_deck_cue_enabled(
     deck: String,
     enabled: bool,
     state: State<'_, AppState>,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
+    state.access.require(Capability::ControlPlayout)?;
     let deck_id = parse_deck(&deck)?;
     state
         .engine
@@ -209,14 +345,14 @@ pub async fn set_deck_cue_enabled(
 }
 
 #[tauri::command]
-pub async fn list_audio_output_devices() -> Result<Vec<AudioOutputDevice>, String> {
+pub async fn list_audio_output_devices() -> Result<Vec<AudioOutputDevice>, AppError> {
     crate::audio::engine::AudioEngine::list_audio_output_devices()
 }
 
 #[tauri::command]
 pub async fn get_audio_output_status(
     state: State<'_, AppState>,
-) -> Result<AudioOutputStatus, String> {
+) -> Result<AudioOutputStatus, AppError> {
     Ok(state.engine.lock().unwrap().get_audio_output_status())
 }
 
@@ -225,7 +361,8 @@ pub async fn apply_audio_output_routing(
     config: AudioOutputRoutingConfig,
     app: AppHandle,
     state: State<'_, AppState>,
-) -> Result<AudioOutputStatus, String> {
+) -> Result<AudioOutputStatus, AppError> {
+    state.access.require(Capability::ManageSettings)?;
     let auto_fallback = config.auto_fallback;
     let (cue_level, master_level) = {
         let engine = state.engine.lock().unwrap();
@@ -280,7 +417,8 @@ pub async fn set_deck_pitch(
     deck: String,
     pitch_pct: f32,
     state: State<'_, AppState>,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
+    state.access.require(Capability::ControlPlayout)?;
     let deck_id = parse_deck(&deck)?;
     state
         .engine
@@ -294,7 +432,8 @@ pub async fn set_deck_tempo(
     deck: String,
     tempo_pct: f32,
     state: State<'_, AppState>,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
+    state.access.require(Capability::ControlPlayout)?;
     let deck_id = parse_deck(&deck)?;
     state
         .engine
@@ -303,13 +442,74 @@ pub async fn set_deck_tempo(
         .set_deck_tempo(deck_id, tempo_pct)
 }
 
+/// Hold a deck's pitch while its tempo is moved (master tempo).
+#[tauri::command]
+pub async fn set_deck_keylock(
+    deck: String,
+    enabled: bool,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    state.access.require(Capability::ControlPlayout)?;
+    let deck_id = parse_deck(&deck)?;
+    state
+        .engine
+        .lock()
+        .unwrap()
+        .set_deck_keylock(deck_id, enabled)
+}
+
+fn parse_reversible_deck(deck: &str) -> Result<DeckId, AppError> {
+    let deck_id = parse_deck(deck)?;
+    if !deck_id.is_playback() {
+        return Err(AppError::invalid_input(format!(
+            "Reverse and censor are only available on decks A–D, not {deck_id}"
+        )));
+    }
+    Ok(deck_id)
+}
+
+/// Play a deck backwards; turning it off resumes forwards from where the
+/// reverse got to.
+#[tauri::command]
+pub async fn set_deck_reverse(
+    deck: String,
+    enabled: bool,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    state.access.require(Capability::ControlPlayout)?;
+    let deck_id = parse_reversible_deck(&deck)?;
+    state
+        .engine
+        .lock()
+        .unwrap()
+        .set_deck_reverse(deck_id, enabled)
+}
+
+/// Hold (`active: true`) to mask audio by playing backwards while the track
+/// keeps its place; release drops back in at the live position.
+#[tauri::command]
+pub async fn censor_deck(
+    deck: String,
+    active: bool,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    state.access.require(Capability::ControlPlayout)?;
+    let deck_id = parse_reversible_deck(&deck)?;
+    state
+        .engine
+        .lock()
+        .unwrap()
+        .set_deck_censor(deck_id, active)
+}
+
 #[tauri::command]
 pub async fn set_deck_loop(
     deck: String,
     start_ms: u64,
     end_ms: u64,
     state: State<'_, AppState>,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
+    state.access.require(Capability::ControlPlayout)?;
     let deck_id = parse_deck(&deck)?;
     state
         .engine
@@ -319,22 +519,56 @@ pub async fn set_deck_loop(
 }
 
 #[tauri::command]
-pub async fn clear_deck_loop(deck: String, state: State<'_, AppState>) -> Result<(), String> {
+pub async fn clear_deck_loop(deck: String, state: State<'_, AppState>) -> Result<(), AppError> {
+    state.access.require(Capability::ControlPlayout)?;
     let deck_id = parse_deck(&deck)?;
-    let mut engine = state.engine.lock().unwrap();
-    let current_pos = engine.get_deck_state(deck_id).map(|s| s.position_ms);
-    engine.clear_deck_loop(deck_id)?;
-    if let Some(position_ms) = current_pos {
-        let _ = engine.seek(deck_id, position_ms);
-    }
-    Ok(())
+    state.engine.lock().unwrap().exit_deck_loop(deck_id)
+}
+
+#[tauri::command]
+pub async fn halve_deck_loop(
+    deck: String,
+    state: State<'_, AppState>,
+) -> Result<LoopRange, AppError> {
+    state.access.require(Capability::ControlPlayout)?;
+    let deck_id = parse_deck(&deck)?;
+    state
+        .engine
+        .lock()
+        .unwrap()
+        .resize_deck_loop(deck_id, false)
+}
+
+/// Doubling a loop that is already repeating plays on past its old end at the
+/// next pass, rather than jumping.
+#[tauri::command]
+pub async fn double_deck_loop(
+    deck: String,
+    state: State<'_, AppState>,
+) -> Result<LoopRange, AppError> {
+    state.access.require(Capability::ControlPlayout)?;
+    let deck_id = parse_deck(&deck)?;
+    state.engine.lock().unwrap().resize_deck_loop(deck_id, true)
+}
+
+/// With slip on, loops, hot-cue jumps, jog and reverse leave a notional
+/// playhead running; turning slip off returns playback to it.
+#[tauri::command]
+pub async fn set_deck_slip(
+    deck: String,
+    enabled: bool,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    state.access.require(Capability::ControlPlayout)?;
+    let deck_id = parse_deck(&deck)?;
+    state.engine.lock().unwrap().set_deck_slip(deck_id, enabled)
 }
 
 #[tauri::command]
 pub async fn get_deck_state(
     deck: String,
     state: State<'_, AppState>,
-) -> Result<Option<DeckStateEvent>, String> {
+) -> Result<Option<DeckStateEvent>, AppError> {
     let deck_id = parse_deck(&deck)?;
     Ok(state.engine.lock().unwrap().get_deck_state(deck_id))
 }
@@ -342,6 +576,95 @@ pub async fn get_deck_state(
 #[tauri::command]
 pub async fn get_vu_readings(
     state: State<'_, AppState>,
-) -> Result<Vec<crate::audio::engine::VuEvent>, String> {
+) -> Result<Vec<crate::audio::engine::VuEvent>, AppError> {
     Ok(state.engine.lock().unwrap().get_vu_readings())
 }
+
+#[tauri::command]
+pub async fn get_spectrum_config() -> Result<spectrum::SpectrumConfig, AppError> {
+    Ok(spectrum::get_config())
+}
+
+/// Enable, disable or reshape the analyzer. Bands arrive in `spectrum`
+/// events every `interval_ms`.
+#[tauri::command]
+pub async fn set_spectrum_config(
+    state: State<'_, AppState>,
+    config: spectrum::SpectrumConfig,
+) -> Result<(), AppError> {
+    state.access.require(Capability::ManageSettings)?;
+    let mut engine = state.engine.lock().unwrap();
+    Ok(spectrum::configure(&mut engine, config)?)
+}
+
+#[tauri::command]
+pub async fn get_spectrum_data() -> Result<spectrum::SpectrumData, AppError> {
+    Ok(spectrum::latest())
+}
+
+#[tauri::command]
+pub async fn get_loudness_config() -> Result<loudness_meter::LoudnessConfig, AppError> {
+    Ok(loudness_meter::get_config())
+}
+
+#[tauri::command]
+pub async fn set_loudness_config(
+    config: loudness_meter::LoudnessConfig,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    state.access.require(Capability::ManageSettings)?;
+    Ok(loudness_meter::set_config(config)?)
+}
+
+/// `None` until the engine has produced audio.
+#[tauri::command]
+pub async fn get_loudness_reading() -> Result<Option<loudness_meter::LoudnessReading>, AppError> {
+    Ok(loudness_meter::latest())
+}
+
+/// Restart integrated loudness, maximum true peak and the over count.
+#[tauri::command]
+pub async fn reset_loudness_meter(state: State<'_, AppState>) -> Result<(), AppError> {
+    state.access.require(Capability::ControlPlayout)?;
+    loudness_meter::reset();
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_multitrack_config() -> Result<multitrack::MultitrackConfig, AppError> {
+    Ok(multitrack::get_config())
+}
+
+/// Applies from the next take.
+#[tauri::command]
+pub async fn set_multitrack_config(
+    config: multitrack::MultitrackConfig,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    state.access.require(Capability::ManageSettings)?;
+    Ok(multitrack::set_config(config)?)
+}
+
+/// Record each configured bus to its own file until stopped.
+#[tauri::command]
+pub async fn start_multitrack_recording(
+    state: State<'_, AppState>,
+) -> Result<multitrack::MultitrackStatus, AppError> {
+    state.access.require(Capability::ControlEncoders)?;
+    let mut engine = state.engine.lock().unwrap();
+    Ok(multitrack::start(&mut engine)?)
+}
+
+/// Returns once every file of the take is closed.
+#[tauri::command]
+pub async fn stop_multitrack_recording(
+    state: State<'_, AppState>,
+) -> Result<multitrack::MultitrackStatus, AppError> {
+    state.access.require(Capability::ControlEncoders)?;
+    Ok(multitrack::stop(&state.engine))
+}
+
+#[tauri::command]
+pub async fn get_multitrack_status() -> Result<multitrack::MultitrackStatus, AppError> {
+    Ok(multitrack::status())
+}
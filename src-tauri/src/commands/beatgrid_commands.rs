@@ -2,7 +2,42 @@ use std::path::Path;
 
 use tauri::State;
 
-use crate::{db::local::BeatGridAnalysis, state::AppState};
+use crate::access::Capability;
+use crate::audio::analyzer::{
+    beatgrid::BeatGridComputed,
+    cue_detect::{self, DetectedCues},
+};
+use crate::error::AppError;
+use crate::{
+    db::local::{BeatGridAnalysis, CuePoint},
+    state::AppState,
+};
+
+/// Grids below this confidence are not used to snap detected cues.
+const BEATGRID_CONFIDENCE_MIN: f32 = 0.55;
+
+fn trusted_beats(grid: &BeatGridComputed) -> &[i64] {
+    if grid.confidence >= BEATGRID_CONFIDENCE_MIN {
+        &grid.beat_times_ms
+    } else {
+        &[]
+    }
+}
+
+/// Store detected cues as `transition` cue points, keeping hand-placed ones.
+async fn write_detected_cues(
+    pool: &sqlx::SqlitePool,
+    song_id: i64,
+    detected: &DetectedCues,
+) -> Result<Vec<CuePoint>, sqlx::Error> {
+    let existing = crate::db::local::get_cue_points(pool, song_id).await?;
+    let cues = cue_detect::cue_points_to_write(song_id, detected, &existing);
+    for cue in &cues {
+        crate::db::local::upsert_cue_point(pool, cue).await?;
+    }
+    crate::scheduler::transition_planner::invalidate_markers(Some(song_id));
+    Ok(cues)
+}
 
 fn file_mtime_ms(path: &Path) -> i64 {
     path.metadata()
@@ -19,35 +54,56 @@ pub async fn analyze_beatgrid(
     file_path: String,
     force_reanalyze: Option<bool>,
     state: State<'_, AppState>,
-) -> Result<BeatGridAnalysis, String> {
+) -> Result<BeatGridAnalysis, AppError> {
+    state.access.require(Capability::EditLibrary)?;
     let local = state
         .local_db
         .as_ref()
-        .ok_or("Local DB not initialised")?
-        .clone();
+        .ok_or_else(AppError::db_unavailable)?;
+    run_beatgrid_analysis(local, song_id, file_path, force_reanalyze.unwrap_or(false)).await
+}
+
+/// Beat grid (plus detected transition cues) for one file, reusing a cached
+/// grid unless `force`. Shared by the command and the analysis queue.
+pub(crate) async fn run_beatgrid_analysis(
+    local: &sqlx::SqlitePool,
+    song_id: i64,
+    file_path: String,
+    force: bool,
+) -> Result<BeatGridAnalysis, AppError> {
     let path = Path::new(&file_path);
     if !path.exists() {
-        return Err(format!("File not found: {file_path}"));
+        return Err(AppError::file_not_found(&file_path));
     }
     if !path.is_file() {
-        return Err(format!("Path is not a file: {file_path}"));
+        return Err(AppError::invalid_input(format!(
+            "Path is not a file: {file_path}"
+        )));
     }
 
     let mtime_ms = file_mtime_ms(path);
-    if !force_reanalyze.unwrap_or(false) {
+    if !force {
         if let Ok(Some(cached)) =
-            crate::db::local::get_beatgrid_analysis(&local, song_id, &file_path, mtime_ms).await
+            crate::db::local::get_beatgrid_analysis(local, song_id, &file_path, mtime_ms).await
         {
             return Ok(cached);
         }
     }
 
+    // One decode feeds both the beat grid and the transition cue detector.
     let analyze_path = path.to_path_buf();
-    let computed = tauri::async_runtime::spawn_blocking(move || {
-        crate::audio::analyzer::beatgrid::analyze_file(&analyze_path)
+    let (computed, detected) = tauri::async_runtime::spawn_blocking(move || {
+        let (samples, sample_rate) = crate::audio::analyzer::beatgrid::decode_mono(&analyze_path)?;
+        let grid = crate::audio::analyzer::beatgrid::analyze_samples(&samples, sample_rate);
+        let beats = trusted_beats(&grid);
+        let cues = cue_detect::detect(&samples, sample_rate, beats);
+        Ok::<_, String>((grid, cues))
     })
     .await
     .map_err(|e| format!("Beat-grid worker join failed: {e}"))??;
+    if let Err(e) = write_detected_cues(local, song_id, &detected).await {
+        log::warn!("Failed to save detected cues (song_id={song_id}): {e}");
+    }
 
     let analysis = BeatGridAnalysis {
         song_id,
@@ -59,14 +115,14 @@ pub async fn analyze_beatgrid(
         beat_times_ms: computed.beat_times_ms,
         updated_at: None,
     };
-    crate::db::local::save_beatgrid_analysis(&local, &analysis)
+    crate::db::local::save_beatgrid_analysis(local, &analysis)
         .await
-        .map_err(|e| format!("DB error: {e}"))?;
+        .map_err(AppError::db)?;
 
-    crate::db::local::get_beatgrid_analysis(&local, song_id, &file_path, mtime_ms)
+    crate::db::local::get_beatgrid_analysis(local, song_id, &file_path, mtime_ms)
         .await
-        .map_err(|e| format!("DB error: {e}"))?
-        .ok_or("Failed to read saved beat-grid".to_string())
+        .map_err(AppError::db)?
+        .ok_or_else(|| "Failed to read saved beat-grid".into())
 }
 
 #[tauri::command]
@@ -74,8 +130,11 @@ pub async fn get_beatgrid(
     song_id: i64,
     file_path: String,
     state: State<'_, AppState>,
-) -> Result<Option<BeatGridAnalysis>, String> {
-    let local = state.local_db.as_ref().ok_or("Local DB not initialised")?;
+) -> Result<Option<BeatGridAnalysis>, AppError> {
+    let local = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
     let path = Path::new(&file_path);
     if !path.exists() || !path.is_file() {
         return Ok(None);
@@ -83,5 +142,43 @@ pub async fn get_beatgrid(
     let mtime_ms = file_mtime_ms(path);
     crate::db::local::get_beatgrid_analysis(local, song_id, &file_path, mtime_ms)
         .await
-        .map_err(|e| format!("DB error: {e}"))
+        .map_err(AppError::db)
+}
+
+/// Re-run cue detection for one song and return the cue points written.
+/// Cues placed by hand are left as they are.
+#[tauri::command]
+pub async fn detect_transition_cues(
+    song_id: i64,
+    file_path: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<CuePoint>, AppError> {
+    state.access.require(Capability::EditLibrary)?;
+    let local = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?
+        .clone();
+    let path = Path::new(&file_path);
+    if !path.is_file() {
+        return Err(AppError::file_not_found(&file_path));
+    }
+
+    let beats = crate::db::local::get_latest_beatgrid_by_song_id(&local, song_id)
+        .await
+        .map_err(AppError::db)?
+        .filter(|g| g.confidence >= BEATGRID_CONFIDENCE_MIN)
+        .map(|g| g.beat_times_ms)
+        .unwrap_or_default();
+    let analyze_path = path.to_path_buf();
+    let detected = tauri::async_runtime::spawn_blocking(move || {
+        let (samples, sample_rate) = crate::audio::analyzer::beatgrid::decode_mono(&analyze_path)?;
+        Ok::<_, String>(cue_detect::detect(&samples, sample_rate, &beats))
+    })
+    .await
+    .map_err(|e| format!("Cue detection worker join failed: {e}"))??;
+
+    write_detected_cues(&local, song_id, &detected)
+        .await
+        .map_err(AppError::db)
 }
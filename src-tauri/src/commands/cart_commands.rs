@@ -0,0 +1,235 @@
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, State};
+
+use crate::access::Capability;
+use crate::audio::cart_wall::{
+    self, CartKey, CartSlot, CartVoiceState, CartWallLayout, MAX_CART_FADE_MS,
+};
+use crate::controller::hotkeys;
+use crate::error::AppError;
+use crate::state::AppState;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CartWallEntry {
+    #[serde(flatten)]
+    pub cart: CartSlot,
+    /// Decoded and ready to fire
+    pub loaded: bool,
+    pub duration_ms: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CartWall {
+    pub layout: CartWallLayout,
+    pub carts: Vec<CartWallEntry>,
+}
+
+/// Fire a preloaded cart. No DB access, so pads and hotkeys stay instant.
+pub(crate) fn trigger(state: &AppState, key: CartKey) -> Result<(), AppError> {
+    let loaded = cart_wall::loaded(key)
+        .ok_or_else(|| AppError::not_found(format!("Cart {}/{} is empty", key.page, key.slot)))?;
+    state.engine.lock().unwrap().trigger_cart(loaded.trigger())
+}
+
+async fn validate_cart(
+    state: &AppState,
+    local: &sqlx::SqlitePool,
+    cart: &mut CartSlot,
+) -> Result<(), AppError> {
+    let layout = cart_wall::get_layout(local).await?;
+    if !layout.contains(cart.page, cart.slot) {
+        return Err(AppError::invalid_input(format!(
+            "Cart {}/{} is outside the {}×{}×{} wall",
+            cart.page, cart.slot, layout.pages, layout.rows, layout.cols
+        )));
+    }
+    if !(-24.0..=12.0).contains(&cart.gain_db) {
+        return Err(AppError::invalid_input(
+            "Cart gain must be between -24 and +12 dB",
+        ));
+    }
+    if cart.fade_out_ms > MAX_CART_FADE_MS {
+        return Err(AppError::invalid_input(format!(
+            "Cart fade-out is limited to {MAX_CART_FADE_MS} ms"
+        )));
+    }
+    if let Some(color) = cart.color_hex.as_deref().filter(|c| !c.is_empty()) {
+        let hex = color.strip_prefix('#').unwrap_or(color);
+        if hex.len() != 6 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(AppError::invalid_input(format!(
+                "Invalid cart colour: {color}"
+            )));
+        }
+    }
+    if let Some(hotkey) = cart.hotkey.as_deref().filter(|h| !h.trim().is_empty()) {
+        hotkey
+            .parse::<tauri_plugin_global_shortcut::Shortcut>()
+            .map_err(|e| AppError::invalid_input(format!("Invalid hotkey {hotkey}: {e}")))?;
+        if let Some(owner) = hotkeys::binding_owner(hotkey) {
+            return Err(AppError::conflict(format!(
+                "Hotkey {hotkey} is already bound to {owner}"
+            )));
+        }
+        let carts = cart_wall::get_carts(local).await?;
+        if let Some(other) = carts.iter().find(|c| {
+            c.key() != cart.key()
+                && c.hotkey
+                    .as_deref()
+                    .is_some_and(|h| h.eq_ignore_ascii_case(hotkey))
+        }) {
+            return Err(AppError::conflict(format!(
+                "Hotkey {hotkey} is already used by cart {}/{}",
+                other.page, other.slot
+            )));
+        }
+    }
+
+    if cart.file_path.trim().is_empty() {
+        let song_id = cart
+            .song_id
+            .ok_or_else(|| AppError::invalid_input("Cart needs a file or a SAM song"))?;
+        let sam_pool = { state.sam_db.read().await.as_ref().cloned() };
+        let pool = sam_pool.ok_or_else(AppError::sam_db_unavailable)?;
+        let song = crate::db::sam::get_song(&pool, song_id)
+            .await?
+            .ok_or_else(|| AppError::not_found(format!("Song {song_id} not found")))?;
+        cart.file_path = crate::translate_sam_file_path(local, song.filename).await;
+        if cart.label.trim().is_empty() {
+            cart.label = song.title;
+        }
+    }
+    if !std::path::Path::new(&cart.file_path).is_file() {
+        return Err(AppError::file_not_found(&cart.file_path));
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_cart_wall(state: State<'_, AppState>) -> Result<CartWall, AppError> {
+    let local = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    let layout = cart_wall::get_layout(local).await?;
+    let carts = cart_wall::get_carts(local)
+        .await?
+        .into_iter()
+        .map(|cart| {
+            let loaded = cart_wall::loaded(cart.key());
+            CartWallEntry {
+                loaded: loaded.is_some(),
+                duration_ms: loaded.map(|l| l.sample.duration_ms()),
+                cart,
+            }
+        })
+        .collect();
+    Ok(CartWall { layout, carts })
+}
+
+/// Save a cart and decode it into memory. Rejected if the file cannot be
+/// decoded, so a saved cart is always ready to fire.
+#[tauri::command]
+pub async fn save_cart(
+    mut cart: CartSlot,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<CartWallEntry, AppError> {
+    state.access.require(Capability::EditSchedule)?;
+    let local = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    validate_cart(&state, local, &mut cart).await?;
+    let duration_ms = cart_wall::preload(cart.clone()).await?;
+    cart_wall::upsert_cart(local, &cart).await?;
+    hotkeys::sync(&app);
+    Ok(CartWallEntry {
+        cart,
+        loaded: true,
+        duration_ms: Some(duration_ms),
+    })
+}
+
+#[tauri::command]
+pub async fn clear_cart(
+    page: u32,
+    slot: u32,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    state.access.require(Capability::EditSchedule)?;
+    let local = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    cart_wall::delete_cart(local, page, slot).await?;
+    let key = CartKey { page, slot };
+    let _ = state.engine.lock().unwrap().stop_cart(key);
+    cart_wall::evict(key);
+    hotkeys::sync(&app);
+    Ok(())
+}
+
+/// Change the grid. Carts outside a smaller grid stay saved but are
+/// unloaded until the grid grows again.
+#[tauri::command]
+pub async fn set_cart_wall_layout(
+    layout: CartWallLayout,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    state.access.require(Capability::EditSchedule)?;
+    let local = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    layout.validate()?;
+    cart_wall::save_layout(local, &layout).await?;
+    cart_wall::set_active_page(layout.active_page);
+    cart_wall::retain_within(&layout);
+    cart_wall::preload_all(local).await?;
+    hotkeys::sync(&app);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn set_active_cart_page(page: u32, state: State<'_, AppState>) -> Result<(), AppError> {
+    state.access.require(Capability::ControlPlayout)?;
+    let local = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    let mut layout = cart_wall::get_layout(local).await?;
+    layout.active_page = page;
+    layout.validate()?;
+    cart_wall::save_layout(local, &layout).await?;
+    cart_wall::set_active_page(page);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn trigger_cart(page: u32, slot: u32, state: State<'_, AppState>) -> Result<(), AppError> {
+    state.access.require(Capability::ControlPlayout)?;
+    trigger(&state, CartKey { page, slot })
+}
+
+#[tauri::command]
+pub fn stop_cart(page: u32, slot: u32, state: State<'_, AppState>) -> Result<(), AppError> {
+    state.access.require(Capability::ControlPlayout)?;
+    state
+        .engine
+        .lock()
+        .unwrap()
+        .stop_cart(CartKey { page, slot })
+}
+
+#[tauri::command]
+pub fn stop_all_carts(state: State<'_, AppState>) -> Result<(), AppError> {
+    state.access.require(Capability::ControlPlayout)?;
+    state.engine.lock().unwrap().stop_all_carts()
+}
+
+#[tauri::command]
+pub fn get_cart_states(state: State<'_, AppState>) -> Result<Vec<CartVoiceState>, AppError> {
+    Ok(state.engine.lock().unwrap().cart_states())
+}
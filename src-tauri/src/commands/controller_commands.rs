@@ -1,9 +1,16 @@
 use tauri::State;
 
+use crate::access::Capability;
+use crate::error::AppError;
 use crate::{
-    controller::types::{ControllerConfig, ControllerDevice, ControllerStatus},
+    controller::{
+        mapping::{self, MidiProfile},
+        osc::{self, OscConfig, OscStatus},
+        profiles,
+        types::{ControllerConfig, ControllerDevice, ControllerStatus},
+    },
     db::local::{
-        get_controller_config as db_get_controller_config,
+        self as local_db, get_controller_config as db_get_controller_config,
         save_controller_config as db_save_controller_config, ControllerConfigRow,
     },
     state::AppState,
@@ -30,21 +37,26 @@ fn to_row(config: &ControllerConfig) -> ControllerConfigRow {
 #[tauri::command]
 pub async fn list_controller_devices(
     state: State<'_, AppState>,
-) -> Result<Vec<ControllerDevice>, String> {
-    state.controller_service.list_devices()
+) -> Result<Vec<ControllerDevice>, AppError> {
+    state
+        .controller_service
+        .list_devices()
+        .map_err(AppError::from)
 }
 
 #[tauri::command]
-pub async fn get_controller_status(state: State<'_, AppState>) -> Result<ControllerStatus, String> {
+pub async fn get_controller_status(
+    state: State<'_, AppState>,
+) -> Result<ControllerStatus, AppError> {
     Ok(state.controller_service.get_status())
 }
 
 #[tauri::command]
-pub async fn get_controller_config(state: State<'_, AppState>) -> Result<ControllerConfig, String> {
+pub async fn get_controller_config(
+    state: State<'_, AppState>,
+) -> Result<ControllerConfig, AppError> {
     if let Some(pool) = &state.local_db {
-        let row = db_get_controller_config(pool)
-            .await
-            .map_err(|e| format!("DB error: {e}"))?;
+        let row = db_get_controller_config(pool).await.map_err(AppError::db)?;
         Ok(to_public_config(row))
     } else {
         Ok(state.controller_service.get_config())
@@ -56,11 +68,14 @@ pub async fn save_controller_config_cmd(
     config: ControllerConfig,
     state: State<'_, AppState>,
     app: tauri::AppHandle,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
+    state.access.require(Capability::ManageSettings)?;
     if let Some(pool) = &state.local_db {
+        let profile = mapping::load_profile(pool, &config.profile).await?;
         db_save_controller_config(pool, &to_row(&config))
             .await
-            .map_err(|e| format!("DB error: {e}"))?;
+            .map_err(AppError::db)?;
+        state.controller_service.set_mapping_profile(profile);
     }
 
     state
@@ -80,14 +95,138 @@ pub async fn connect_controller(
     device_id: Option<String>,
     state: State<'_, AppState>,
     app: tauri::AppHandle,
-) -> Result<ControllerStatus, String> {
-    state.controller_service.connect(device_id, &app)
+) -> Result<ControllerStatus, AppError> {
+    state.access.require(Capability::ManageSettings)?;
+    state
+        .controller_service
+        .connect(device_id, &app)
+        .map_err(AppError::from)
 }
 
 #[tauri::command]
 pub async fn disconnect_controller(
     state: State<'_, AppState>,
     app: tauri::AppHandle,
-) -> Result<ControllerStatus, String> {
-    state.controller_service.disconnect(&app)
+) -> Result<ControllerStatus, AppError> {
+    state.access.require(Capability::ManageSettings)?;
+    state
+        .controller_service
+        .disconnect(&app)
+        .map_err(AppError::from)
+}
+
+/// Built-in profiles followed by custom (learned) ones. The Starlight profile
+/// has its own decoder and is not listed.
+#[tauri::command]
+pub async fn list_controller_profiles(
+    state: State<'_, AppState>,
+) -> Result<Vec<MidiProfile>, AppError> {
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    let mut out = profiles::builtin_profiles();
+    for json in local_db::list_controller_profiles(pool).await? {
+        match serde_json::from_str::<MidiProfile>(&json) {
+            Ok(profile) => out.push(profile),
+            Err(e) => log::warn!("Skipping unreadable controller profile: {e}"),
+        }
+    }
+    Ok(out)
+}
+
+#[tauri::command]
+pub async fn save_controller_profile(
+    profile: MidiProfile,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    state.access.require(Capability::ManageSettings)?;
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    profile.validate().map_err(AppError::invalid_input)?;
+    let json = serde_json::to_string(&profile)?;
+    local_db::save_controller_profile(pool, profile.id.trim(), profile.name.trim(), &json).await?;
+    // Edits to the active profile apply immediately.
+    if state.controller_service.get_config().profile == profile.id {
+        state.controller_service.set_mapping_profile(Some(profile));
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn delete_controller_profile(
+    id: String,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    state.access.require(Capability::ManageSettings)?;
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    if profiles::builtin(&id).is_some() {
+        return Err(AppError::invalid_input(format!(
+            "Controller profile {id} is built in"
+        )));
+    }
+    if state.controller_service.get_config().profile == id {
+        return Err(AppError::conflict(format!(
+            "Controller profile {id} is active; select another profile first"
+        )));
+    }
+    if !local_db::delete_controller_profile(pool, &id).await? {
+        return Err(AppError::not_found(format!(
+            "Controller profile {id} not found"
+        )));
+    }
+    Ok(())
+}
+
+/// Toggle MIDI learn: raw messages are emitted as `controller_midi_event`
+/// and mapped actions are suppressed until it is turned off.
+#[tauri::command]
+pub async fn set_controller_learn_mode(
+    enabled: bool,
+    state: State<'_, AppState>,
+    app: tauri::AppHandle,
+) -> Result<ControllerStatus, AppError> {
+    state.access.require(Capability::ManageSettings)?;
+    Ok(state.controller_service.set_learn_mode(enabled, &app))
+}
+
+#[tauri::command]
+pub async fn get_osc_config(state: State<'_, AppState>) -> Result<OscConfig, AppError> {
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    osc::get_config(pool).await.map_err(AppError::from)
+}
+
+/// Save the OSC config and start, restart or stop the server to match.
+#[tauri::command]
+pub async fn set_osc_config(
+    app: tauri::AppHandle,
+    state: State<'_, AppState>,
+    config: OscConfig,
+) -> Result<OscStatus, AppError> {
+    state.access.require(Capability::ManageSettings)?;
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    config.validate().map_err(AppError::invalid_input)?;
+    osc::save_config(pool, &config).await?;
+    if config.enabled {
+        osc::start(app, config).await.map_err(AppError::from)
+    } else {
+        osc::stop();
+        Ok(osc::status())
+    }
+}
+
+#[tauri::command]
+pub async fn get_osc_status() -> Result<OscStatus, AppError> {
+    Ok(osc::status())
 }
@@ -1,17 +1,23 @@
 use tauri::State;
 
+use crate::access::Capability;
+use crate::error::AppError;
 use crate::{
     audio::{
-        crossfade::{CrossfadeConfig, CrossfadeMode, CrossfadeTriggerMode, FadeCurve},
+        crossfade::{
+            CrossfadeConfig, CrossfadeMode, CrossfadeTriggerMode, CrossfaderSide, FadeCurve,
+        },
         engine::ManualFadeDirection,
     },
+    db::local::{CrossfadeProfile, CrossfadeProfileAssignment, GainTrimKind},
+    scheduler::autodj,
     state::AppState,
 };
 
 use super::audio_commands::parse_deck;
 
 #[tauri::command]
-pub async fn get_crossfade_config(state: State<'_, AppState>) -> Result<CrossfadeConfig, String> {
+pub async fn get_crossfade_config(state: State<'_, AppState>) -> Result<CrossfadeConfig, AppError> {
     if let Some(pool) = &state.local_db {
         if let Ok(Some(json)) = crate::db::local::load_crossfade_config(pool).await {
             let cfg = parse_crossfade_config_json(&json);
@@ -32,31 +38,55 @@ pub async fn get_crossfade_config(state: State<'_, AppState>) -> Result<Crossfad
 pub async fn set_crossfade_config(
     config: CrossfadeConfig,
     state: State<'_, AppState>,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
+    state.access.require(Capability::EditSchedule)?;
     let config = normalize_crossfade_config(config);
     // Persist to SQLite
     if let Some(pool) = &state.local_db {
         let json = serde_json::to_string(&config).map_err(|e| format!("Serialize error: {e}"))?;
         crate::db::local::save_crossfade_config(pool, &json)
             .await
-            .map_err(|e| format!("DB error: {e}"))?;
+            .map_err(AppError::db)?;
     }
     state.engine.lock().unwrap().set_crossfade_config(config)
 }
 
+/// Put one playback deck on crossfader side A, B or thru.
+#[tauri::command]
+pub async fn set_crossfader_assignment(
+    deck: String,
+    side: CrossfaderSide,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    state.access.require(Capability::ControlPlayout)?;
+    let deck_id = parse_deck(&deck)?;
+    let mut config = state.engine.lock().unwrap().get_crossfade_config();
+    if !config.crossfader_assign.set(deck_id, side) {
+        return Err(AppError::invalid_input(format!(
+            "{deck} is not a playback deck"
+        )));
+    }
+    set_crossfade_config(config, state).await
+}
+
 #[tauri::command]
 pub async fn start_crossfade(
     outgoing: String,
     incoming: String,
     state: State<'_, AppState>,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
+    state.access.require(Capability::ControlPlayout)?;
     let out_id = parse_deck(&outgoing)?;
     let in_id = parse_deck(&incoming)?;
     state.engine.lock().unwrap().start_crossfade(out_id, in_id)
 }
 
 #[tauri::command]
-pub async fn set_manual_crossfade(position: f32, state: State<'_, AppState>) -> Result<(), String> {
+pub async fn set_manual_crossfade(
+    position: f32,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    state.access.require(Capability::ControlPlayout)?;
     state.engine.lock().unwrap().set_manual_crossfade(position)
 }
 
@@ -65,11 +95,16 @@ pub async fn trigger_manual_fade(
     direction: String,
     duration_ms: u32,
     state: State<'_, AppState>,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
+    state.access.require(Capability::ControlPlayout)?;
     let dir = match direction.as_str() {
         "a_to_b" => ManualFadeDirection::AtoB,
         "b_to_a" => ManualFadeDirection::BtoA,
-        _ => return Err(format!("Unknown fade direction: {direction}")),
+        _ => {
+            return Err(AppError::invalid_input(format!(
+                "Unknown fade direction: {direction}"
+            )))
+        }
     };
     state
         .engine
@@ -78,12 +113,128 @@ pub async fn trigger_manual_fade(
         .trigger_manual_fade(dir, duration_ms)
 }
 
+// ── Crossfade profiles ───────────────────────────────────────────────────────
+
+#[tauri::command]
+pub async fn get_crossfade_profiles(
+    state: State<'_, AppState>,
+) -> Result<Vec<CrossfadeProfile>, AppError> {
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    crate::db::local::get_crossfade_profiles(pool)
+        .await
+        .map_err(AppError::from)
+}
+
+/// Create or update a profile; returns its id. Applies from the next
+/// AutoDJ transition.
+#[tauri::command]
+pub async fn save_crossfade_profile(
+    mut profile: CrossfadeProfile,
+    state: State<'_, AppState>,
+) -> Result<i64, AppError> {
+    state.access.require(Capability::EditSchedule)?;
+    if profile.name.trim().is_empty() {
+        return Err(AppError::invalid_input("Profile name is required"));
+    }
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    let existing = crate::db::local::get_crossfade_profiles(pool).await?;
+    if existing
+        .iter()
+        .any(|p| p.id != profile.id && p.name.eq_ignore_ascii_case(profile.name.trim()))
+    {
+        return Err(AppError::conflict(format!(
+            "A crossfade profile named '{}' already exists",
+            profile.name.trim()
+        )));
+    }
+    profile.config = normalize_crossfade_config(profile.config);
+    let id = crate::db::local::upsert_crossfade_profile(pool, &profile).await?;
+    autodj::request_replan();
+    Ok(id)
+}
+
+#[tauri::command]
+pub async fn delete_crossfade_profile(id: i64, state: State<'_, AppState>) -> Result<(), AppError> {
+    state.access.require(Capability::EditSchedule)?;
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    crate::db::local::delete_crossfade_profile(pool, id).await?;
+    autodj::request_replan();
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_crossfade_profile_assignments(
+    state: State<'_, AppState>,
+) -> Result<Vec<CrossfadeProfileAssignment>, AppError> {
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    crate::db::local::get_crossfade_profile_assignments(pool)
+        .await
+        .map_err(AppError::from)
+}
+
+/// Use a profile for transitions into songs of a SAM category or song type.
+/// A category assignment wins over a song type one.
+#[tauri::command]
+pub async fn set_crossfade_profile_assignment(
+    assignment: CrossfadeProfileAssignment,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    state.access.require(Capability::EditSchedule)?;
+    if assignment.name.trim().is_empty() {
+        return Err(AppError::invalid_input("Category name is required"));
+    }
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    if crate::db::local::get_crossfade_profile(pool, assignment.profile_id)
+        .await?
+        .is_none()
+    {
+        return Err(AppError::not_found(format!(
+            "Crossfade profile {}",
+            assignment.profile_id
+        )));
+    }
+    crate::db::local::upsert_crossfade_profile_assignment(pool, &assignment).await?;
+    autodj::request_replan();
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn delete_crossfade_profile_assignment(
+    kind: GainTrimKind,
+    name: String,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    state.access.require(Capability::EditSchedule)?;
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    crate::db::local::delete_crossfade_profile_assignment(pool, kind, &name).await?;
+    autodj::request_replan();
+    Ok(())
+}
+
 /// Returns a preview of the crossfade curve pair for the frontend visualiser.
 #[tauri::command]
 pub async fn get_fade_curve_preview(
     curve: FadeCurve,
     steps: Option<usize>,
-) -> Result<Vec<crate::audio::crossfade::CurvePoint>, String> {
+) -> Result<Vec<crate::audio::crossfade::CurvePoint>, AppError> {
     Ok(curve.preview(steps.unwrap_or(50)))
 }
 
@@ -1,23 +1,62 @@
-use tauri::State;
+use tauri::{AppHandle, State};
 
+use crate::access::Capability;
+use crate::audio::{crossfade::DeckId, deck::MAX_LOOP_SECONDS, engine::LoopRange};
+use crate::error::AppError;
 use crate::{
-    db::local::{CueKind, CuePoint, CueQuantize, HotCue, MonitorRoutingConfig},
+    db::{
+        local::{
+            CueKind, CuePoint, CueQuantize, HotCue, MonitorRoutingConfig, QuantizeConfig,
+            SavedLoop, SongPlaybackFlags,
+        },
+        sam_cues::{self, SamCueImportOptions, SamCueImportProgress},
+    },
     state::AppState,
 };
 
-const HOT_CUE_MIN_SLOT: u8 = 1;
-const HOT_CUE_MAX_SLOT: u8 = 8;
+pub(crate) const HOT_CUE_MIN_SLOT: u8 = 1;
+pub(crate) const HOT_CUE_MAX_SLOT: u8 = 8;
 const BEATGRID_CONFIDENCE_MIN: f32 = 0.55;
+const AUTO_LOOP_BEATS: [u32; 5] = [1, 2, 4, 8, 16];
 
-fn validate_slot(slot: u8) -> Result<(), String> {
+fn validate_slot(slot: u8) -> Result<(), AppError> {
     if (HOT_CUE_MIN_SLOT..=HOT_CUE_MAX_SLOT).contains(&slot) {
         Ok(())
     } else {
-        Err(format!(
+        Err(AppError::invalid_input(format!(
             "Hot cue slot must be between {} and {}",
             HOT_CUE_MIN_SLOT, HOT_CUE_MAX_SLOT
-        ))
+        )))
+    }
+}
+
+fn validate_loop_range(start_ms: i64, end_ms: i64) -> Result<(), AppError> {
+    if start_ms < 0 || end_ms <= start_ms + 10 {
+        return Err(AppError::invalid_input(
+            "Loop end must be greater than loop start",
+        ));
+    }
+    if (end_ms - start_ms) as u64 > MAX_LOOP_SECONDS * 1000 {
+        return Err(AppError::invalid_input(format!(
+            "Loop too long (max {MAX_LOOP_SECONDS}s)"
+        )));
     }
+    Ok(())
+}
+
+/// Beat times of the song's grid, or none if it is missing or unreliable.
+async fn trusted_beat_times(state: &AppState, song_id: i64) -> Result<Vec<i64>, AppError> {
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    let grid = crate::db::local::get_latest_beatgrid_by_song_id(pool, song_id)
+        .await
+        .map_err(AppError::db)?;
+    Ok(grid
+        .filter(|g| g.confidence >= BEATGRID_CONFIDENCE_MIN)
+        .map(|g| g.beat_times_ms)
+        .unwrap_or_default())
 }
 
 async fn maybe_quantize_position(
@@ -25,37 +64,51 @@ async fn maybe_quantize_position(
     song_id: i64,
     position_ms: i64,
     mode: CueQuantize,
-) -> Result<(i64, bool), String> {
+) -> Result<(i64, bool), AppError> {
     if matches!(mode, CueQuantize::Off) {
         return Ok((position_ms.max(0), false));
     }
-    let pool = state.local_db.as_ref().ok_or("Local DB not initialised")?;
-    let Some(grid) = crate::db::local::get_latest_beatgrid_by_song_id(pool, song_id)
-        .await
-        .map_err(|e| format!("DB error: {e}"))?
-    else {
-        return Ok((position_ms.max(0), false));
-    };
-    if grid.confidence < BEATGRID_CONFIDENCE_MIN || grid.beat_times_ms.is_empty() {
+    let beats = trusted_beat_times(state, song_id).await?;
+    if beats.is_empty() {
         return Ok((position_ms.max(0), false));
     }
-    let snapped = crate::audio::analyzer::beatgrid::quantize_position_ms(
-        position_ms,
-        &grid.beat_times_ms,
-        mode,
-    );
+    let snapped = crate::audio::analyzer::beatgrid::quantize_position_ms(position_ms, &beats, mode);
     Ok((snapped.max(0), true))
 }
 
+/// Jump a deck to `position_ms`, on the next grid boundary when `mode` is on
+/// and the deck is playing. Shared with the controller's hot-cue pads.
+pub(crate) async fn quantized_jump(
+    state: &AppState,
+    deck: DeckId,
+    song_id: i64,
+    position_ms: i64,
+    mode: CueQuantize,
+) -> Result<(), AppError> {
+    let beats = if matches!(mode, CueQuantize::Off) {
+        Vec::new()
+    } else {
+        trusted_beat_times(state, song_id).await?
+    };
+    state
+        .engine
+        .lock()
+        .unwrap()
+        .jump(deck, position_ms.max(0) as u64, &beats, mode)
+}
+
 #[tauri::command]
 pub async fn get_cue_points(
     song_id: i64,
     state: State<'_, AppState>,
-) -> Result<Vec<CuePoint>, String> {
-    let pool = state.local_db.as_ref().ok_or("Local DB not initialised")?;
+) -> Result<Vec<CuePoint>, AppError> {
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
     crate::db::local::get_cue_points(pool, song_id)
         .await
-        .map_err(|e| format!("DB error: {e}"))
+        .map_err(AppError::db)
 }
 
 #[tauri::command]
@@ -64,8 +117,12 @@ pub async fn set_cue_point(
     name: String,
     position_ms: i64,
     state: State<'_, AppState>,
-) -> Result<(), String> {
-    let pool = state.local_db.as_ref().ok_or("Local DB not initialised")?;
+) -> Result<(), AppError> {
+    state.access.require(Capability::EditLibrary)?;
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
     crate::db::local::upsert_cue_point(
         pool,
         &CuePoint {
@@ -81,7 +138,9 @@ pub async fn set_cue_point(
         },
     )
     .await
-    .map_err(|e| format!("DB error: {e}"))
+    .map_err(AppError::db)?;
+    crate::scheduler::transition_planner::invalidate_markers(Some(song_id));
+    Ok(())
 }
 
 #[tauri::command]
@@ -89,11 +148,41 @@ pub async fn delete_cue_point(
     song_id: i64,
     name: String,
     state: State<'_, AppState>,
-) -> Result<(), String> {
-    let pool = state.local_db.as_ref().ok_or("Local DB not initialised")?;
+) -> Result<(), AppError> {
+    state.access.require(Capability::EditLibrary)?;
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
     crate::db::local::delete_cue_point(pool, song_id, &name)
         .await
-        .map_err(|e| format!("DB error: {e}"))
+        .map_err(AppError::db)?;
+    crate::scheduler::transition_planner::invalidate_markers(Some(song_id));
+    Ok(())
+}
+
+/// Import intro / outro / xfade cues from SAM's song list for every song.
+/// Progress is emitted as `sam_cue_import_progress`; the final tally is
+/// returned.
+#[tauri::command]
+pub async fn import_sam_cues(
+    options: Option<SamCueImportOptions>,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<SamCueImportProgress, AppError> {
+    state.access.require(Capability::EditLibrary)?;
+    if sam_cues::is_running() {
+        return Err(AppError::conflict("A SAM cue import is already running"));
+    }
+    let local = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    let sam =
+        { state.sam_db.read().await.as_ref().cloned() }.ok_or_else(AppError::sam_db_unavailable)?;
+    let result = sam_cues::import_all(&app, &sam, local, options.unwrap_or_default()).await;
+    crate::scheduler::transition_planner::invalidate_markers(None);
+    result.map_err(AppError::from)
 }
 
 /// Jump a deck to a named cue point (seeks the deck to the stored position).
@@ -103,11 +192,15 @@ pub async fn jump_to_cue(
     song_id: i64,
     cue_name: String,
     state: State<'_, AppState>,
-) -> Result<(), String> {
-    let pool = state.local_db.as_ref().ok_or("Local DB not initialised")?;
+) -> Result<(), AppError> {
+    state.access.require(Capability::ControlPlayout)?;
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
     let cues = crate::db::local::get_cue_points(pool, song_id)
         .await
-        .map_err(|e| format!("DB error: {e}"))?;
+        .map_err(AppError::db)?;
 
     let cue = cues
         .into_iter()
@@ -123,11 +216,17 @@ pub async fn jump_to_cue(
 }
 
 #[tauri::command]
-pub async fn get_hot_cues(song_id: i64, state: State<'_, AppState>) -> Result<Vec<HotCue>, String> {
-    let pool = state.local_db.as_ref().ok_or("Local DB not initialised")?;
+pub async fn get_hot_cues(
+    song_id: i64,
+    state: State<'_, AppState>,
+) -> Result<Vec<HotCue>, AppError> {
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
     crate::db::local::get_hot_cues(pool, song_id)
         .await
-        .map_err(|e| format!("DB error: {e}"))
+        .map_err(AppError::db)
 }
 
 #[tauri::command]
@@ -139,7 +238,8 @@ pub async fn set_hot_cue(
     color_hex: Option<String>,
     quantize_mode: Option<CueQuantize>,
     state: State<'_, AppState>,
-) -> Result<HotCue, String> {
+) -> Result<HotCue, AppError> {
+    state.access.require(Capability::EditLibrary)?;
     validate_slot(slot)?;
     let (position_ms, quantized) = maybe_quantize_position(
         &state,
@@ -158,10 +258,13 @@ pub async fn set_hot_cue(
         quantized,
     };
 
-    let pool = state.local_db.as_ref().ok_or("Local DB not initialised")?;
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
     crate::db::local::upsert_hot_cue(pool, &cue)
         .await
-        .map_err(|e| format!("DB error: {e}"))?;
+        .map_err(AppError::db)?;
     Ok(cue)
 }
 
@@ -170,12 +273,16 @@ pub async fn clear_hot_cue(
     song_id: i64,
     slot: u8,
     state: State<'_, AppState>,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
+    state.access.require(Capability::EditLibrary)?;
     validate_slot(slot)?;
-    let pool = state.local_db.as_ref().ok_or("Local DB not initialised")?;
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
     crate::db::local::clear_hot_cue(pool, song_id, slot)
         .await
-        .map_err(|e| format!("DB error: {e}"))
+        .map_err(AppError::db)
 }
 
 #[tauri::command]
@@ -185,30 +292,28 @@ pub async fn trigger_hot_cue(
     slot: u8,
     quantize_mode: Option<CueQuantize>,
     state: State<'_, AppState>,
-) -> Result<HotCue, String> {
+) -> Result<HotCue, AppError> {
+    state.access.require(Capability::ControlPlayout)?;
     validate_slot(slot)?;
     let deck_id = super::audio_commands::parse_deck(&deck)?;
-    let pool = state.local_db.as_ref().ok_or("Local DB not initialised")?;
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
     let mut cue = crate::db::local::get_hot_cue(pool, song_id, slot)
         .await
-        .map_err(|e| format!("DB error: {e}"))?
-        .ok_or_else(|| format!("Hot cue {slot} not found for song {song_id}"))?;
+        .map_err(AppError::db)?
+        .ok_or_else(|| {
+            AppError::not_found(format!("Hot cue {slot} not found for song {song_id}"))
+        })?;
 
-    let (snapped, quantized) = maybe_quantize_position(
-        &state,
-        song_id,
-        cue.position_ms,
-        quantize_mode.unwrap_or(CueQuantize::Off),
-    )
-    .await?;
+    let mode = quantize_mode.unwrap_or_else(|| state.engine.lock().unwrap().quantize_mode(deck_id));
+    let (snapped, quantized) =
+        maybe_quantize_position(&state, song_id, cue.position_ms, mode).await?;
     cue.position_ms = snapped;
     cue.quantized = quantized;
 
-    state
-        .engine
-        .lock()
-        .unwrap()
-        .seek(deck_id, cue.position_ms as u64)?;
+    quantized_jump(&state, deck_id, song_id, cue.position_ms, mode).await?;
     Ok(cue)
 }
 
@@ -218,12 +323,16 @@ pub async fn rename_hot_cue(
     slot: u8,
     label: String,
     state: State<'_, AppState>,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
+    state.access.require(Capability::EditLibrary)?;
     validate_slot(slot)?;
-    let pool = state.local_db.as_ref().ok_or("Local DB not initialised")?;
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
     crate::db::local::rename_hot_cue(pool, song_id, slot, &label)
         .await
-        .map_err(|e| format!("DB error: {e}"))
+        .map_err(AppError::db)
 }
 
 #[tauri::command]
@@ -232,33 +341,225 @@ pub async fn recolor_hot_cue(
     slot: u8,
     color_hex: String,
     state: State<'_, AppState>,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
+    state.access.require(Capability::EditLibrary)?;
     validate_slot(slot)?;
-    let pool = state.local_db.as_ref().ok_or("Local DB not initialised")?;
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
     crate::db::local::recolor_hot_cue(pool, song_id, slot, &color_hex)
         .await
-        .map_err(|e| format!("DB error: {e}"))
+        .map_err(AppError::db)
+}
+
+#[tauri::command]
+pub async fn get_saved_loops(
+    song_id: i64,
+    state: State<'_, AppState>,
+) -> Result<Vec<SavedLoop>, AppError> {
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    crate::db::local::get_saved_loops(pool, song_id)
+        .await
+        .map_err(AppError::db)
+}
+
+/// Store a loop for a song in one of the hot-cue slots; it is kept with the
+/// song's cue points and can be recalled whenever the track is loaded.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn set_saved_loop(
+    song_id: i64,
+    slot: u8,
+    start_ms: i64,
+    end_ms: i64,
+    label: Option<String>,
+    color_hex: Option<String>,
+    quantize_mode: Option<CueQuantize>,
+    state: State<'_, AppState>,
+) -> Result<SavedLoop, AppError> {
+    state.access.require(Capability::EditLibrary)?;
+    validate_slot(slot)?;
+    let mode = quantize_mode.unwrap_or(CueQuantize::Off);
+    let (start_ms, _) = maybe_quantize_position(&state, song_id, start_ms, mode).await?;
+    let (end_ms, _) = maybe_quantize_position(&state, song_id, end_ms, mode).await?;
+    validate_loop_range(start_ms, end_ms)?;
+
+    let saved = SavedLoop {
+        song_id,
+        slot,
+        start_ms,
+        end_ms,
+        label: label.unwrap_or_else(|| format!("Loop {slot}")),
+        color_hex: color_hex.unwrap_or_else(|| "#f59e0b".to_string()),
+    };
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    crate::db::local::upsert_saved_loop(pool, &saved)
+        .await
+        .map_err(AppError::db)?;
+    Ok(saved)
+}
+
+#[tauri::command]
+pub async fn clear_saved_loop(
+    song_id: i64,
+    slot: u8,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    state.access.require(Capability::EditLibrary)?;
+    validate_slot(slot)?;
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    crate::db::local::clear_saved_loop(pool, song_id, slot)
+        .await
+        .map_err(AppError::db)
+}
+
+/// Jump to a saved loop's start and engage it.
+#[tauri::command]
+pub async fn trigger_saved_loop(
+    deck: String,
+    song_id: i64,
+    slot: u8,
+    state: State<'_, AppState>,
+) -> Result<SavedLoop, AppError> {
+    state.access.require(Capability::ControlPlayout)?;
+    validate_slot(slot)?;
+    let deck_id = super::audio_commands::parse_deck(&deck)?;
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    let saved = crate::db::local::get_saved_loop(pool, song_id, slot)
+        .await
+        .map_err(AppError::db)?
+        .ok_or_else(|| AppError::not_found(format!("Loop {slot} not found for song {song_id}")))?;
+
+    let mode = state.engine.lock().unwrap().quantize_mode(deck_id);
+    quantized_jump(&state, deck_id, song_id, saved.start_ms, mode).await?;
+    state.engine.lock().unwrap().set_deck_loop(
+        deck_id,
+        saved.start_ms as u64,
+        saved.end_ms as u64,
+    )?;
+    Ok(saved)
+}
+
+/// Loop `beats` beats (1, 2, 4, 8 or 16) from the next beat of the deck's
+/// beat grid.
+#[tauri::command]
+pub async fn set_deck_auto_loop(
+    deck: String,
+    beats: u32,
+    state: State<'_, AppState>,
+) -> Result<LoopRange, AppError> {
+    state.access.require(Capability::ControlPlayout)?;
+    if !AUTO_LOOP_BEATS.contains(&beats) {
+        return Err(AppError::invalid_input(
+            "Auto-loop length must be 1, 2, 4, 8 or 16 beats",
+        ));
+    }
+    let deck_id = super::audio_commands::parse_deck(&deck)?;
+    let (song_id, position_ms) = {
+        let engine = state.engine.lock().unwrap();
+        let deck_state = engine
+            .get_deck_state(deck_id)
+            .ok_or_else(|| AppError::not_found(format!("Unknown deck: {deck}")))?;
+        let song_id = deck_state
+            .song_id
+            .ok_or_else(|| AppError::invalid_input("No track loaded on deck"))?;
+        (song_id, deck_state.position_ms)
+    };
+
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    let grid = crate::db::local::get_latest_beatgrid_by_song_id(pool, song_id)
+        .await
+        .map_err(AppError::db)?
+        .filter(|g| g.confidence >= BEATGRID_CONFIDENCE_MIN)
+        .ok_or_else(|| AppError::invalid_input("Track has no reliable beat grid"))?;
+    let (start_ms, end_ms) = crate::audio::analyzer::beatgrid::auto_loop_range_ms(
+        position_ms as i64,
+        &grid.beat_times_ms,
+        beats,
+    )
+    .ok_or_else(|| AppError::invalid_input("Track has no reliable beat grid"))?;
+    validate_loop_range(start_ms, end_ms)?;
+
+    let range = LoopRange {
+        start_ms: start_ms as u64,
+        end_ms: end_ms as u64,
+    };
+    state
+        .engine
+        .lock()
+        .unwrap()
+        .set_deck_loop(deck_id, range.start_ms, range.end_ms)?;
+    Ok(range)
+}
+
+#[tauri::command]
+pub async fn get_quantize_config(state: State<'_, AppState>) -> Result<QuantizeConfig, AppError> {
+    Ok(state.engine.lock().unwrap().quantize_config())
+}
+
+/// Apply and persist the quantize mode for hot-cue and loop jumps.
+#[tauri::command]
+pub async fn set_quantize_config(
+    config: QuantizeConfig,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    state.access.require(Capability::ManageSettings)?;
+    state
+        .engine
+        .lock()
+        .unwrap()
+        .set_quantize_config(config.clone());
+    if let Some(pool) = &state.local_db {
+        let json = serde_json::to_string(&config).map_err(|e| e.to_string())?;
+        crate::db::local::save_quantize_config(pool, &json)
+            .await
+            .map_err(AppError::db)?;
+    }
+    Ok(())
 }
 
 #[tauri::command]
 pub async fn get_monitor_routing_config(
     state: State<'_, AppState>,
-) -> Result<MonitorRoutingConfig, String> {
-    let pool = state.local_db.as_ref().ok_or("Local DB not initialised")?;
+) -> Result<MonitorRoutingConfig, AppError> {
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
     crate::db::local::get_monitor_routing_config(pool)
         .await
-        .map_err(|e| format!("DB error: {e}"))
+        .map_err(AppError::db)
 }
 
 #[tauri::command]
 pub async fn set_monitor_routing_config(
     config: MonitorRoutingConfig,
     state: State<'_, AppState>,
-) -> Result<(), String> {
-    let pool = state.local_db.as_ref().ok_or("Local DB not initialised")?;
+) -> Result<(), AppError> {
+    state.access.require(Capability::ManageSettings)?;
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
     crate::db::local::save_monitor_routing_config(pool, &config)
         .await
-        .map_err(|e| format!("DB error: {e}"))?;
+        .map_err(AppError::db)?;
     state
         .engine
         .lock()
@@ -272,7 +573,8 @@ pub async fn set_deck_cue_preview_enabled(
     deck: String,
     enabled: bool,
     state: State<'_, AppState>,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
+    state.access.require(Capability::ControlPlayout)?;
     let deck_id = super::audio_commands::parse_deck(&deck)?;
     state
         .engine
@@ -280,3 +582,39 @@ pub async fn set_deck_cue_preview_enabled(
         .unwrap()
         .set_deck_cue_preview_enabled(deck_id, enabled)
 }
+
+#[tauri::command]
+pub async fn get_song_playback_flags(
+    song_id: i64,
+    state: State<'_, AppState>,
+) -> Result<SongPlaybackFlags, AppError> {
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    crate::db::local::get_song_playback_flags(pool, song_id)
+        .await
+        .map_err(AppError::db)
+}
+
+#[tauri::command]
+pub async fn set_song_playback_flags(
+    flags: SongPlaybackFlags,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    state.access.require(Capability::EditLibrary)?;
+    if flags.never_crossfade && flags.always_segue {
+        return Err(AppError::invalid_input(
+            "A song cannot be both 'never crossfade' and 'always segue'",
+        ));
+    }
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    crate::db::local::upsert_song_playback_flags(pool, &flags)
+        .await
+        .map_err(AppError::db)?;
+    crate::scheduler::autodj::request_replan();
+    Ok(())
+}
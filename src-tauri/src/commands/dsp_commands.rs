@@ -1,5 +1,7 @@
 use tauri::State;
 
+use crate::access::Capability;
+use crate::error::AppError;
 use crate::{
     audio::dsp::{
         agc::AgcConfig, eq::EqConfig, pipeline::PipelineSettings, stem_filter::StemFilterMode,
@@ -26,11 +28,14 @@ fn parse_channel_target(channel: &str) -> Result<ChannelTarget, String> {
 pub async fn get_channel_dsp(
     channel: String,
     state: State<'_, AppState>,
-) -> Result<Option<crate::db::local::ChannelDspRow>, String> {
-    let pool = state.local_db.as_ref().ok_or("Local DB not initialised")?;
+) -> Result<Option<crate::db::local::ChannelDspRow>, AppError> {
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
     crate::db::local::get_channel_dsp(pool, &channel)
         .await
-        .map_err(|e| format!("DB error: {e}"))
+        .map_err(AppError::db)
 }
 
 #[tauri::command]
@@ -40,7 +45,8 @@ pub async fn set_channel_eq(
     mid_gain_db: f32,
     high_gain_db: f32,
     state: State<'_, AppState>,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
+    state.access.require(Capability::ControlPlayout)?;
     let target = parse_channel_target(&channel)?;
     let mut settings = get_pipeline_settings(&channel, &state).await?;
     settings.eq.low_gain_db = low_gain_db;
@@ -56,7 +62,8 @@ pub async fn set_channel_agc(
     gate_db: Option<f32>,
     max_gain_db: Option<f32>,
     state: State<'_, AppState>,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
+    state.access.require(Capability::ControlPlayout)?;
     let target = parse_channel_target(&channel)?;
     let mut settings = get_pipeline_settings(&channel, &state).await?;
     settings.agc.enabled = enabled;
@@ -74,7 +81,8 @@ pub async fn set_pipeline_settings(
     channel: String,
     settings: PipelineSettings,
     state: State<'_, AppState>,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
+    state.access.require(Capability::ManageSettings)?;
     let target = parse_channel_target(&channel)?;
     apply_and_persist(target, settings, &channel, &state).await
 }
@@ -85,7 +93,8 @@ pub async fn set_channel_stem_filter(
     mode: StemFilterMode,
     amount: Option<f32>,
     state: State<'_, AppState>,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
+    state.access.require(Capability::ControlPlayout)?;
     let target = parse_channel_target(&channel)?;
     let mut settings = get_pipeline_settings(&channel, &state).await?;
     settings.stem_filter.mode = mode;
@@ -139,7 +148,7 @@ async fn apply_and_persist(
     settings: PipelineSettings,
     channel: &str,
     state: &AppState,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     // Apply to audio engine
     match target {
         ChannelTarget::Deck(deck_id) => {
@@ -163,7 +172,7 @@ async fn apply_and_persist(
         let row = settings_to_row(channel.to_string(), &settings);
         crate::db::local::upsert_channel_dsp(pool, &row)
             .await
-            .map_err(|e| format!("DB persist: {e}"))?;
+            .map_err(AppError::db)?;
     }
     Ok(())
 }
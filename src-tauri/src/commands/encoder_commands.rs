@@ -1,18 +1,38 @@
 /// Phase 4 — Encoder & Stats Tauri commands
 ///
 /// All commands operate on `AppState.encoder_manager` (EncoderManager).
-use std::time::Duration;
+use std::{
+    path::{Path, PathBuf},
+    time::{Duration, UNIX_EPOCH},
+};
 
 use tauri::State;
 
+use crate::error::AppError;
 use crate::{
+    access::{self, Capability},
+    analytics::{
+        event_logger::{self, EventCategory, LogLevel},
+        play_log,
+    },
     db::local,
     state::AppState,
     stats::icecast_stats::{self, ListenerSnapshot},
-    stream::{broadcaster::EncoderRuntimeState, encoder_manager::EncoderConfig},
+    stream::{
+        broadcaster::{EncoderRuntimeState, EncoderStatus},
+        encoder_manager::{EncoderConfig, OutputType},
+        encoder_probe::EncoderDiagnosis,
+        export_upload,
+        metadata_fanout::{self, MetadataPushTarget, PushTargetStatus, PushTrack},
+        show_export::{
+            self, ExportPlan, ShowExportConfig, ShowExportRequest, ShowExportResult, ShowTags,
+        },
+        station_id_gate::{self, StationIdGateConfig, StationIdGateStatus},
+        watermark::{self, WatermarkMatch},
+    },
 };
 
-fn ensure_broadcast_loop(state: &AppState) {
+pub(crate) fn ensure_broadcast_loop(state: &AppState) {
     let mut started = state.broadcaster_loop_started.lock().unwrap();
     if *started {
         return;
@@ -40,14 +60,105 @@ fn ensure_broadcast_loop(state: &AppState) {
     log::info!("Encoder broadcast loop started");
 }
 
-fn current_engine_sample_rate(state: &AppState) -> u32 {
+pub(crate) fn current_engine_sample_rate(state: &AppState) -> u32 {
     state.engine.lock().unwrap().get_output_sample_rate()
 }
 
+/// Apply the station ID gate before `ids` go live. Only network outputs are
+/// gated, and only on sign-on (no network encoder streaming yet).
+pub(crate) async fn enforce_station_id_gate(
+    state: &AppState,
+    ids: &[i64],
+    override_gate: bool,
+) -> Result<(), String> {
+    let Some(pool) = &state.local_db else {
+        return Ok(());
+    };
+    let config = station_id_gate::get_config(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    if !config.enabled {
+        return Ok(());
+    }
+
+    let manager = &state.encoder_manager;
+    let is_network = |id: &i64| {
+        manager
+            .get_encoder(*id)
+            .is_some_and(|c| !matches!(c.output_type, OutputType::File))
+    };
+    if !ids.iter().any(is_network) {
+        return Ok(());
+    }
+    let on_air = manager.get_all_runtime().iter().any(|rt| {
+        is_network(&rt.id)
+            && matches!(
+                rt.status,
+                EncoderStatus::Streaming | EncoderStatus::Retrying { .. }
+            )
+    });
+    if on_air {
+        return Ok(());
+    }
+
+    let sam_pool = { state.sam_db.read().await.as_ref().cloned() };
+    let status = station_id_gate::evaluate(&config, sam_pool.as_ref()).await;
+    if status.satisfied {
+        return Ok(());
+    }
+
+    let metadata = serde_json::json!({
+        "encoder_ids": ids,
+        "window_minutes": status.window_minutes,
+        "last_played_secs_ago": status.last_played_secs_ago,
+        "sam_offline": status.sam_offline,
+    });
+    let (level, event, message, result) = if status.auto_play_available {
+        station_id_gate::request_sign_on(config.clone());
+        (
+            LogLevel::Info,
+            "station_id_gate_auto_play",
+            "No recent station ID; playing one on sign-on".to_string(),
+            Ok(()),
+        )
+    } else if override_gate && config.allow_override {
+        (
+            LogLevel::Warn,
+            "station_id_gate_override",
+            "Encoders started without a recent station ID (operator override)".to_string(),
+            Ok(()),
+        )
+    } else {
+        let message = format!(
+            "Station ID required: none aired in the last {} minutes",
+            status.window_minutes
+        );
+        (
+            LogLevel::Warn,
+            "station_id_gate_blocked",
+            message.clone(),
+            Err(message),
+        )
+    };
+    let _ = event_logger::log_event(
+        pool,
+        level,
+        EventCategory::Stream,
+        event,
+        &message,
+        Some(metadata),
+        None,
+        config.song_id,
+        ids.first().copied().filter(|_| ids.len() == 1),
+    )
+    .await;
+    result
+}
+
 // ── Encoder CRUD ──────────────────────────────────────────────────────────────
 
 #[tauri::command]
-pub async fn get_encoders(state: State<'_, AppState>) -> Result<Vec<EncoderConfig>, String> {
+pub async fn get_encoders(state: State<'_, AppState>) -> Result<Vec<EncoderConfig>, AppError> {
     Ok(state.encoder_manager.get_encoders())
 }
 
@@ -55,87 +166,230 @@ pub async fn get_encoders(state: State<'_, AppState>) -> Result<Vec<EncoderConfi
 pub async fn save_encoder(
     encoder: EncoderConfig,
     state: State<'_, AppState>,
-) -> Result<i64, String> {
+) -> Result<i64, AppError> {
+    let actor = state.access.require(Capability::EditEncoders)?;
     log::info!("save_encoder: request for id={}", encoder.id);
+    let before = state
+        .encoder_manager
+        .get_encoder(encoder.id)
+        .and_then(|c| serde_json::to_value(c).ok())
+        .unwrap_or_default();
     let id = state.encoder_manager.save_encoder(encoder);
+    let cfg = state
+        .encoder_manager
+        .get_encoder(id)
+        .ok_or_else(|| format!("Encoder {id} missing after save"))?;
     if let Some(pool) = &state.local_db {
-        let cfg = state
-            .encoder_manager
-            .get_encoder(id)
-            .ok_or_else(|| format!("Encoder {id} missing after save"))?;
         local::save_encoder_config(pool, &cfg).await?;
         log::info!("save_encoder: persisted encoder id={id}");
     }
+    let after = serde_json::to_value(&cfg).unwrap_or_default();
+    access::audit(
+        &state,
+        &actor,
+        if before.is_null() {
+            "encoder.create"
+        } else {
+            "encoder.update"
+        },
+        Some(format!("encoder:{id}")),
+        serde_json::json!({ "name": cfg.name, "changes": access::changed_fields(&before, &after) }),
+    )
+    .await;
     Ok(id)
 }
 
 #[tauri::command]
-pub async fn delete_encoder(id: i64, state: State<'_, AppState>) -> Result<(), String> {
+pub async fn delete_encoder(id: i64, state: State<'_, AppState>) -> Result<(), AppError> {
+    let actor = state.access.require(Capability::EditEncoders)?;
+    let name = state.encoder_manager.get_encoder(id).map(|c| c.name);
     state.encoder_manager.delete_encoder(id);
     if let Some(pool) = &state.local_db {
         local::delete_encoder_config(pool, id).await?;
         log::info!("delete_encoder: removed persisted encoder id={id}");
     }
+    access::audit(
+        &state,
+        &actor,
+        "encoder.delete",
+        Some(format!("encoder:{id}")),
+        serde_json::json!({ "name": name }),
+    )
+    .await;
     Ok(())
 }
 
 // ── Start / Stop ──────────────────────────────────────────────────────────────
 
 #[tauri::command]
-pub async fn start_encoder(id: i64, state: State<'_, AppState>) -> Result<(), String> {
+pub async fn start_encoder(
+    id: i64,
+    override_gate: Option<bool>,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    let actor = state.access.require(Capability::ControlEncoders)?;
+    enforce_station_id_gate(&state, &[id], override_gate.unwrap_or(false)).await?;
     ensure_broadcast_loop(&state);
     let source_sr = current_engine_sample_rate(&state);
     state
         .encoder_manager
         .start_encoder_with_sample_rate(id, Some(source_sr), None);
+    audit_control(&state, &actor, "encoder.start", Some(id)).await;
     Ok(())
 }
 
 #[tauri::command]
-pub async fn stop_encoder(id: i64, state: State<'_, AppState>) -> Result<(), String> {
+pub async fn stop_encoder(id: i64, state: State<'_, AppState>) -> Result<(), AppError> {
+    let actor = state.access.require(Capability::ControlEncoders)?;
     state.encoder_manager.stop_encoder(id);
+    audit_control(&state, &actor, "encoder.stop", Some(id)).await;
     Ok(())
 }
 
 #[tauri::command]
-pub async fn start_all_encoders(state: State<'_, AppState>) -> Result<(), String> {
+pub async fn start_all_encoders(
+    override_gate: Option<bool>,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    let actor = state.access.require(Capability::ControlEncoders)?;
+    let ids: Vec<i64> = state
+        .encoder_manager
+        .get_encoders()
+        .iter()
+        .filter(|c| c.enabled)
+        .map(|c| c.id)
+        .collect();
+    enforce_station_id_gate(&state, &ids, override_gate.unwrap_or(false)).await?;
     ensure_broadcast_loop(&state);
     let source_sr = current_engine_sample_rate(&state);
     state
         .encoder_manager
         .start_all_with_sample_rate(Some(source_sr));
+    audit_control(&state, &actor, "encoder.start_all", None).await;
     Ok(())
 }
 
 #[tauri::command]
-pub async fn stop_all_encoders(state: State<'_, AppState>) -> Result<(), String> {
+pub async fn stop_all_encoders(state: State<'_, AppState>) -> Result<(), AppError> {
+    let actor = state.access.require(Capability::ControlEncoders)?;
     state.encoder_manager.stop_all();
+    audit_control(&state, &actor, "encoder.stop_all", None).await;
     Ok(())
 }
 
+async fn audit_control(state: &AppState, actor: &access::Actor, action: &str, id: Option<i64>) {
+    let name = id.and_then(|id| state.encoder_manager.get_encoder(id).map(|c| c.name));
+    access::audit(
+        state,
+        actor,
+        action,
+        id.map(|id| format!("encoder:{id}")),
+        serde_json::json!({ "name": name }),
+    )
+    .await;
+}
+
+// ── Station ID gate ───────────────────────────────────────────────────────────
+
+#[tauri::command]
+pub async fn get_station_id_gate_config(
+    state: State<'_, AppState>,
+) -> Result<StationIdGateConfig, AppError> {
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    station_id_gate::get_config(pool)
+        .await
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn set_station_id_gate_config(
+    config: StationIdGateConfig,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    state.access.require(Capability::EditEncoders)?;
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    if config.enabled && config.song_types.is_empty() {
+        return Err(AppError::invalid_input(
+            "At least one station ID song type is required",
+        ));
+    }
+    if config.auto_play && !config.can_auto_play() {
+        return Err(AppError::invalid_input(
+            "Auto-play needs a song or file to play",
+        ));
+    }
+    station_id_gate::save_config(pool, &config)
+        .await
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn get_station_id_gate_status(
+    state: State<'_, AppState>,
+) -> Result<StationIdGateStatus, AppError> {
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    let config = station_id_gate::get_config(pool).await?;
+    let sam_pool = { state.sam_db.read().await.as_ref().cloned() };
+    Ok(station_id_gate::evaluate(&config, sam_pool.as_ref()).await)
+}
+
 // ── Connection test ───────────────────────────────────────────────────────────
 
 #[tauri::command]
-pub async fn test_encoder_connection(id: i64, state: State<'_, AppState>) -> Result<bool, String> {
+pub async fn test_encoder_connection(
+    id: i64,
+    state: State<'_, AppState>,
+) -> Result<EncoderDiagnosis, AppError> {
     log::info!("test_encoder_connection: starting test for encoder_id={id}");
     match state.encoder_manager.test_connection(id).await {
-        Ok(()) => {
-            log::info!("test_encoder_connection: success for encoder_id={id}");
-            Ok(true)
+        Ok(diagnosis) => {
+            log::info!(
+                "test_encoder_connection: encoder_id={id} ok={} checks={}",
+                diagnosis.ok,
+                diagnosis.checks.len()
+            );
+            Ok(diagnosis)
         }
         Err(e) => {
             log::warn!("test_encoder_connection: failed for encoder_id={id}: {e}");
-            Err(e)
+            Err(e.into())
         }
     }
 }
 
+// ── Watermark ─────────────────────────────────────────────────────────────────
+
+/// Scan a recording (e.g. a stream rip) for encoder watermarks and report
+/// which encoders' codes were found.
+#[tauri::command]
+pub async fn detect_stream_watermark(
+    file_path: String,
+    state: State<'_, AppState>,
+) -> Result<Vec<WatermarkMatch>, AppError> {
+    let encoders = state.encoder_manager.get_encoders();
+    tokio::task::spawn_blocking(move || {
+        let (mono, sample_rate) =
+            crate::audio::analyzer::beatgrid::decode_mono(std::path::Path::new(&file_path))?;
+        Ok::<_, AppError>(watermark::attribute(&mono, sample_rate, &encoders))
+    })
+    .await?
+}
+
 // ── Runtime state ─────────────────────────────────────────────────────────────
 
 #[tauri::command]
 pub async fn get_encoder_runtime(
     state: State<'_, AppState>,
-) -> Result<Vec<EncoderRuntimeState>, String> {
+) -> Result<Vec<EncoderRuntimeState>, AppError> {
     Ok(state.encoder_manager.get_all_runtime())
 }
 
@@ -144,21 +398,237 @@ pub async fn get_encoder_runtime(
 /// Recording is managed via the same start/stop_encoder commands (file encoders).
 /// Convenience aliases for explicit UI calls.
 #[tauri::command]
-pub async fn start_recording(encoder_id: i64, state: State<'_, AppState>) -> Result<(), String> {
+pub async fn start_recording(encoder_id: i64, state: State<'_, AppState>) -> Result<(), AppError> {
+    let actor = state.access.require(Capability::ControlEncoders)?;
     ensure_broadcast_loop(&state);
     let source_sr = current_engine_sample_rate(&state);
     state
         .encoder_manager
         .start_encoder_with_sample_rate(encoder_id, Some(source_sr), None);
+    audit_control(&state, &actor, "recording.start", Some(encoder_id)).await;
     Ok(())
 }
 
 #[tauri::command]
-pub async fn stop_recording(encoder_id: i64, state: State<'_, AppState>) -> Result<(), String> {
+pub async fn stop_recording(encoder_id: i64, state: State<'_, AppState>) -> Result<(), AppError> {
+    let actor = state.access.require(Capability::ControlEncoders)?;
     state.encoder_manager.stop_encoder(encoder_id);
+    audit_control(&state, &actor, "recording.stop", Some(encoder_id)).await;
     Ok(())
 }
 
+// ── Show export ───────────────────────────────────────────────────────────────
+
+async fn read_show_export_config(pool: &sqlx::SqlitePool) -> Result<ShowExportConfig, AppError> {
+    let json = local::load_show_export_config(pool)
+        .await
+        .map_err(AppError::db)?;
+    Ok(json
+        .and_then(|j| serde_json::from_str(&j).ok())
+        .unwrap_or_default())
+}
+
+#[tauri::command]
+pub async fn get_show_export_config(
+    state: State<'_, AppState>,
+) -> Result<ShowExportConfig, AppError> {
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    read_show_export_config(pool).await
+}
+
+#[tauri::command]
+pub async fn set_show_export_config(
+    config: ShowExportConfig,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    state.access.require(Capability::ControlEncoders)?;
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    if !(32..=320).contains(&config.bitrate_kbps) {
+        return Err(AppError::invalid_input(
+            "Export bitrate must be 32-320 kbps",
+        ));
+    }
+    if !(-40.0..=0.0).contains(&config.target_loudness_db) {
+        return Err(AppError::invalid_input(
+            "Loudness target must be between -40 and 0 dB",
+        ));
+    }
+    if !state
+        .encoder_manager
+        .get_encoders()
+        .iter()
+        .any(show_export::exportable_recorder)
+    {
+        return Err(AppError::invalid_input(
+            "Show export needs a file encoder recording WAV without track or duration splits",
+        ));
+    }
+    let json = serde_json::to_string(&config).map_err(|e| e.to_string())?;
+    local::save_show_export_config(pool, &json)
+        .await
+        .map_err(AppError::db)
+}
+
+/// How far show boundaries may fall outside the recording (its start time is
+/// often estimated from the file's mtime) before the show counts as split.
+const SHOW_BOUNDARY_SLACK_MS: i64 = 5_000;
+
+/// Trim a finished recording to the show, normalise it, encode it with
+/// chapters from the play log and optionally upload it.
+#[tauri::command]
+pub async fn export_show(
+    request: ShowExportRequest,
+    state: State<'_, AppState>,
+) -> Result<ShowExportResult, AppError> {
+    let actor = state.access.require(Capability::ControlEncoders)?;
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    let config = read_show_export_config(pool).await?;
+    if request.title.trim().is_empty() {
+        return Err(AppError::invalid_input("Show title is required"));
+    }
+    let source = PathBuf::from(&request.recording_path);
+    if !source.is_file() {
+        return Err(AppError::file_not_found(&request.recording_path));
+    }
+    let upload_target = match (request.upload, &config.upload) {
+        (false, _) => None,
+        (true, Some(target)) => Some(target.clone()),
+        (true, None) => return Err(AppError::invalid_input("No upload target is configured")),
+    };
+
+    let probe_path = source.clone();
+    let info = tokio::task::spawn_blocking(move || show_export::probe(&probe_path))
+        .await
+        .map_err(|e| e.to_string())??;
+    let recording_started_at = match request.recording_started_at {
+        Some(at) => at,
+        None => {
+            let modified = std::fs::metadata(&source)
+                .and_then(|m| m.modified())
+                .map_err(|e| e.to_string())?;
+            let modified_ms = modified
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_millis() as i64;
+            modified_ms - info.duration_ms as i64
+        }
+    };
+    let recording_ended_at = recording_started_at + info.duration_ms as i64;
+    // A show running well past either end of the file continues in another
+    // part of a split recording, which cannot be exported.
+    let show_start = request.start_at.unwrap_or(recording_started_at);
+    let show_end = request.end_at.unwrap_or(recording_ended_at);
+    if show_start < recording_started_at - SHOW_BOUNDARY_SLACK_MS
+        || show_end > recording_ended_at + SHOW_BOUNDARY_SLACK_MS
+    {
+        return Err(AppError::invalid_input(
+            "The show runs past this recording file; split recordings cannot be exported",
+        ));
+    }
+    let show_start = show_start.max(recording_started_at);
+    let show_end = show_end.min(recording_ended_at);
+    if show_end <= show_start {
+        return Err(AppError::invalid_input(
+            "Show boundaries fall outside the recording",
+        ));
+    }
+
+    let plays = play_log::get_plays_between(pool, show_start, show_end)
+        .await
+        .map_err(AppError::db)?;
+    let format = request.format.unwrap_or(config.format);
+    let output_dir = config.output_dir.map(PathBuf::from).unwrap_or_else(|| {
+        source
+            .parent()
+            .unwrap_or_else(|| Path::new("."))
+            .join("exports")
+    });
+    let year = {
+        use chrono::TimeZone;
+        chrono::Local
+            .timestamp_millis_opt(show_start)
+            .single()
+            .map(|t| t.format("%Y").to_string())
+    };
+    let plan = ExportPlan {
+        output: show_export::output_path(&output_dir, &request.title, show_start, format),
+        source,
+        format,
+        bitrate_kbps: config.bitrate_kbps,
+        start_ms: (show_start - recording_started_at) as u64,
+        end_ms: (show_end - recording_started_at) as u64,
+        target_loudness_db: request
+            .normalize
+            .unwrap_or(true)
+            .then_some(config.target_loudness_db),
+        tags: ShowTags {
+            title: request.title.trim().to_string(),
+            artist: request.artist,
+            album: request.album,
+            year,
+            comment: request.comment,
+        },
+        chapters: show_export::chapters_from_plays(&plays, show_start, show_end),
+    };
+    let mut result = tokio::task::spawn_blocking(move || show_export::run(&plan))
+        .await
+        .map_err(|e| e.to_string())??;
+
+    if let Some(target) = upload_target {
+        match export_upload::upload(Path::new(&result.output_path), &target).await {
+            Ok(location) => result.uploaded_to = Some(location),
+            Err(e) => {
+                return Err(AppError::from(format!(
+                    "Exported to {} but the upload failed: {e}",
+                    result.output_path
+                )))
+            }
+        }
+    }
+
+    let _ = event_logger::log_event(
+        pool,
+        LogLevel::Info,
+        EventCategory::Stream,
+        "show_exported",
+        &format!(
+            "Exported \"{}\" to {}",
+            request.title.trim(),
+            result.output_path
+        ),
+        Some(serde_json::json!({
+            "output_path": result.output_path,
+            "duration_ms": result.duration_ms,
+            "loudness_db": result.loudness_db,
+            "gain_db": result.gain_db,
+            "chapters": result.chapters.len(),
+            "uploaded_to": result.uploaded_to,
+        })),
+        None,
+        None,
+        None,
+    )
+    .await;
+    access::audit(
+        &state,
+        &actor,
+        "recording.export",
+        None,
+        serde_json::json!({ "output_path": result.output_path }),
+    )
+    .await;
+    Ok(result)
+}
+
 // ── Stats ─────────────────────────────────────────────────────────────────────
 
 /// period: '1h' | '6h' | '24h' | '7d'
@@ -167,19 +637,25 @@ pub async fn get_listener_stats(
     encoder_id: i64,
     period: String,
     state: State<'_, AppState>,
-) -> Result<Vec<ListenerSnapshot>, String> {
+) -> Result<Vec<ListenerSnapshot>, AppError> {
     let period_secs = match period.as_str() {
         "1h" => 3600,
         "6h" => 6 * 3600,
         "24h" => 24 * 3600,
         "7d" => 7 * 24 * 3600,
-        other => return Err(format!("Unknown period: {other}. Use 1h, 6h, 24h, or 7d")),
+        other => {
+            return Err(AppError::invalid_input(format!(
+                "Unknown period: {other}. Use 1h, 6h, 24h, or 7d"
+            )))
+        }
     };
 
     if let Some(pool) = &state.local_db {
-        icecast_stats::get_snapshots(pool, encoder_id, period_secs).await
+        icecast_stats::get_snapshots(pool, encoder_id, period_secs)
+            .await
+            .map_err(AppError::from)
     } else {
-        Err("Local database not initialised".to_string())
+        Err(AppError::db_unavailable())
     }
 }
 
@@ -187,7 +663,7 @@ pub async fn get_listener_stats(
 pub async fn get_current_listeners(
     encoder_id: i64,
     state: State<'_, AppState>,
-) -> Result<u32, String> {
+) -> Result<u32, AppError> {
     Ok(state
         .encoder_manager
         .get_runtime(encoder_id)
@@ -197,12 +673,104 @@ pub async fn get_current_listeners(
 
 // ── Metadata push  ────────────────────────────────────────────────────────────
 
+/// `song_id`, when given, lets `$artwork$` in encoder URL templates resolve.
 #[tauri::command]
 pub async fn push_track_metadata(
     artist: String,
     title: String,
+    song_id: Option<i64>,
     state: State<'_, AppState>,
-) -> Result<(), String> {
-    state.encoder_manager.push_metadata(&artist, &title).await;
+) -> Result<(), AppError> {
+    state.access.require(Capability::ControlEncoders)?;
+    let artwork_url = match (song_id, &state.local_db) {
+        (Some(song_id), Some(local)) => {
+            let lookup = crate::commands::artwork_commands::lookup_for(&state, song_id, None).await;
+            crate::audio::analyzer::artwork::public_url_for(local, &lookup).await
+        }
+        _ => None,
+    };
+    state
+        .encoder_manager
+        .push_metadata(&artist, &title, artwork_url.as_deref())
+        .await;
     Ok(())
 }
+
+// ── Now-playing push targets ──────────────────────────────────────────────────
+
+#[tauri::command]
+pub async fn get_metadata_push_targets(
+    state: State<'_, AppState>,
+) -> Result<Vec<MetadataPushTarget>, AppError> {
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    metadata_fanout::get_targets(pool)
+        .await
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn save_metadata_push_target(
+    target: MetadataPushTarget,
+    state: State<'_, AppState>,
+) -> Result<i64, AppError> {
+    state.access.require(Capability::EditEncoders)?;
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    metadata_fanout::upsert_target(pool, &target)
+        .await
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn delete_metadata_push_target(
+    id: i64,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    state.access.require(Capability::EditEncoders)?;
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    metadata_fanout::delete_target(pool, id)
+        .await
+        .map_err(AppError::from)
+}
+
+/// Send the track on air (or a sample) to one target once, without retries.
+#[tauri::command]
+pub async fn test_metadata_push_target(
+    target: MetadataPushTarget,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    let track = match crate::scheduler::request_api::now_playing(&state).await {
+        Some(np) => PushTrack {
+            song_id: np.song_id,
+            artist: np.artist,
+            title: np.title,
+            album: np.album,
+            duration_secs: (np.duration_ms / 1000) as u32,
+            song_type: "S".to_string(),
+            artwork_url: np.artwork_url.unwrap_or_default(),
+            ..Default::default()
+        },
+        None => PushTrack {
+            artist: "DesiZone".to_string(),
+            title: "Metadata test".to_string(),
+            song_type: "S".to_string(),
+            ..Default::default()
+        },
+    };
+    metadata_fanout::deliver(&target.destination, &track)
+        .await
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn get_metadata_push_status() -> Result<Vec<PushTargetStatus>, AppError> {
+    Ok(metadata_fanout::get_status())
+}
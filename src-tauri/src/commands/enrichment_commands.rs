@@ -0,0 +1,132 @@
+use tauri::{AppHandle, State};
+
+use crate::access::{self, Capability};
+use crate::db::tag_enrichment::{
+    self, ApplySummary, ProposalStatus, TagEnrichmentConfig, TagEnrichmentOptions,
+    TagEnrichmentProgress, TagProposal,
+};
+use crate::error::AppError;
+use crate::state::AppState;
+
+#[tauri::command]
+pub async fn get_tag_enrichment_config(
+    state: State<'_, AppState>,
+) -> Result<TagEnrichmentConfig, AppError> {
+    let local = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    tag_enrichment::get_config(local)
+        .await
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn set_tag_enrichment_config(
+    config: TagEnrichmentConfig,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    let actor = state.access.require(Capability::ManageSettings)?;
+    if !(0..=100).contains(&config.min_score) {
+        return Err(AppError::invalid_input("Match score must be 0–100"));
+    }
+    let local = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    tag_enrichment::save_config(local, &config).await?;
+    access::audit(
+        &state,
+        &actor,
+        "tag_enrichment.config",
+        None,
+        serde_json::json!({
+            "enabled": config.enabled,
+            "musicbrainz": config.musicbrainz,
+            "discogs": !config.discogs_token.trim().is_empty(),
+            "min_score": config.min_score,
+        }),
+    )
+    .await;
+    Ok(())
+}
+
+/// Look up poorly tagged songs and queue what was found for review.
+/// Progress is emitted as `tag_enrichment_progress`; the final tally is
+/// returned.
+#[tauri::command]
+pub async fn start_tag_enrichment(
+    options: Option<TagEnrichmentOptions>,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<TagEnrichmentProgress, AppError> {
+    state.access.require(Capability::EditLibrary)?;
+    if tag_enrichment::is_running() {
+        return Err(AppError::conflict("A tag lookup is already running"));
+    }
+    let local = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    let sam =
+        { state.sam_db.read().await.as_ref().cloned() }.ok_or_else(AppError::sam_db_unavailable)?;
+    tag_enrichment::scan(&app, &sam, local, options.unwrap_or_default())
+        .await
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn cancel_tag_enrichment(state: State<'_, AppState>) -> Result<(), AppError> {
+    state.access.require(Capability::EditLibrary)?;
+    tag_enrichment::cancel();
+    Ok(())
+}
+
+/// The review queue: proposals in `status` (all when omitted), oldest first.
+#[tauri::command]
+pub async fn get_tag_proposals(
+    status: Option<ProposalStatus>,
+    limit: Option<i64>,
+    state: State<'_, AppState>,
+) -> Result<Vec<TagProposal>, AppError> {
+    let local = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    tag_enrichment::list_proposals(local, status, limit.unwrap_or(500).clamp(1, 5000))
+        .await
+        .map_err(AppError::from)
+}
+
+/// Write the chosen proposals to SAM's song list.
+#[tauri::command]
+pub async fn apply_tag_proposals(
+    ids: Vec<i64>,
+    state: State<'_, AppState>,
+) -> Result<ApplySummary, AppError> {
+    state.access.require(Capability::EditLibrary)?;
+    let local = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    let sam =
+        { state.sam_db.read().await.as_ref().cloned() }.ok_or_else(AppError::sam_db_unavailable)?;
+    tag_enrichment::apply(&sam, local, &ids)
+        .await
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn reject_tag_proposals(
+    ids: Vec<i64>,
+    state: State<'_, AppState>,
+) -> Result<u64, AppError> {
+    state.access.require(Capability::EditLibrary)?;
+    let local = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    tag_enrichment::reject(local, &ids)
+        .await
+        .map_err(AppError::from)
+}
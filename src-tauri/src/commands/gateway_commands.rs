@@ -1,6 +1,9 @@
 use serde::{Deserialize, Serialize};
-use tauri::State;
+use tauri::{AppHandle, State};
 
+use crate::access::Capability;
+use crate::error::AppError;
+use crate::gateway::audio_ingest::{RemoteAudioIngest, RemoteAudioStatus};
 use crate::gateway::client::{GatewayClient, GatewayMessage, GatewayStatus};
 use crate::gateway::remote_dj::{DjPermissions, RemoteSession};
 use crate::state::AppState;
@@ -18,7 +21,14 @@ pub async fn connect_gateway(
     url: String,
     token: String,
     state: State<'_, AppState>,
-) -> Result<GatewayStatus, String> {
+) -> Result<GatewayStatus, AppError> {
+    state.access.require(Capability::ManageSettings)?;
+    // Replace any existing link rather than leaving its reconnect loop running.
+    let previous = state.gateway_client.lock().unwrap().take();
+    if let Some(mut previous) = previous {
+        previous.disconnect().await;
+    }
+
     let mut client = GatewayClient::new(url.clone(), token);
 
     // Create message handler
@@ -58,7 +68,8 @@ pub async fn connect_gateway(
 
 /// Disconnect from gateway
 #[tauri::command]
-pub async fn disconnect_gateway(state: State<'_, AppState>) -> Result<(), String> {
+pub async fn disconnect_gateway(state: State<'_, AppState>) -> Result<(), AppError> {
+    state.access.require(Capability::ManageSettings)?;
     let mut client = {
         let mut client_guard = state.gateway_client.lock().unwrap();
         client_guard.take()
@@ -70,9 +81,10 @@ pub async fn disconnect_gateway(state: State<'_, AppState>) -> Result<(), String
     Ok(())
 }
 
-/// Get gateway connection status
+/// Get gateway connection status, including reconnect progress and the
+/// offline queue depth
 #[tauri::command]
-pub async fn get_gateway_status(state: State<'_, AppState>) -> Result<GatewayStatus, String> {
+pub async fn get_gateway_status(state: State<'_, AppState>) -> Result<GatewayStatus, AppError> {
     let client = {
         let client_guard = state.gateway_client.lock().unwrap();
         client_guard.as_ref().cloned()
@@ -82,10 +94,8 @@ pub async fn get_gateway_status(state: State<'_, AppState>) -> Result<GatewaySta
         Ok(c.get_status().await)
     } else {
         Ok(GatewayStatus {
-            connected: false,
-            url: String::new(),
-            reconnecting: false,
             last_error: Some("Not connected".to_string()),
+            ..Default::default()
         })
     }
 }
@@ -96,7 +106,8 @@ pub async fn set_autopilot(
     enabled: bool,
     mode: String,
     state: State<'_, AppState>,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
+    state.access.require(Capability::ControlPlayout)?;
     let mut autopilot = state.autopilot_status.lock().unwrap();
     autopilot.enabled = enabled;
     autopilot.mode = mode;
@@ -112,23 +123,29 @@ pub async fn set_autopilot(
 
 /// Get AutoPilot status
 #[tauri::command]
-pub fn get_autopilot_status(state: State<'_, AppState>) -> Result<AutoPilotStatus, String> {
+pub fn get_autopilot_status(state: State<'_, AppState>) -> Result<AutoPilotStatus, AppError> {
     let autopilot = state.autopilot_status.lock().unwrap();
     Ok(autopilot.clone())
 }
 
 /// Get active remote DJ sessions
 #[tauri::command]
-pub fn get_remote_sessions(state: State<'_, AppState>) -> Result<Vec<RemoteSession>, String> {
+pub fn get_remote_sessions(state: State<'_, AppState>) -> Result<Vec<RemoteSession>, AppError> {
     let sessions = state.remote_sessions.lock().unwrap();
     Ok(sessions.values().cloned().collect())
 }
 
 /// Kick a remote DJ session
 #[tauri::command]
-pub async fn kick_remote_dj(session_id: String, state: State<'_, AppState>) -> Result<(), String> {
+pub async fn kick_remote_dj(
+    session_id: String,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    state.access.require(Capability::ControlPlayout)?;
     let mut sessions = state.remote_sessions.lock().unwrap();
     sessions.remove(&session_id);
+    drop(sessions);
+    stop_remote_audio_for(&state, Some(&session_id));
 
     // TODO: Send kick message to gateway
     log::info!("Kicked remote DJ session: {}", session_id);
@@ -142,7 +159,8 @@ pub fn set_remote_dj_permissions(
     session_id: String,
     permissions: DjPermissions,
     state: State<'_, AppState>,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
+    state.access.require(Capability::ManageSettings)?;
     let mut perms = state.remote_dj_permissions.lock().unwrap();
     perms.insert(session_id.clone(), permissions);
 
@@ -156,7 +174,7 @@ pub fn set_remote_dj_permissions(
 pub fn get_remote_dj_permissions(
     session_id: String,
     state: State<'_, AppState>,
-) -> Result<DjPermissions, String> {
+) -> Result<DjPermissions, AppError> {
     let perms = state.remote_dj_permissions.lock().unwrap();
     Ok(perms
         .get(&session_id)
@@ -166,7 +184,8 @@ pub fn get_remote_dj_permissions(
 
 /// Start live talk mode (mic to air)
 #[tauri::command]
-pub fn start_live_talk(channel: String, state: State<'_, AppState>) -> Result<(), String> {
+pub fn start_live_talk(channel: String, state: State<'_, AppState>) -> Result<(), AppError> {
+    state.access.require(Capability::ControlPlayout)?;
     let mut live_talk = state.live_talk_active.lock().unwrap();
     *live_talk = Some(channel.clone());
 
@@ -182,7 +201,8 @@ pub fn start_live_talk(channel: String, state: State<'_, AppState>) -> Result<()
 
 /// Stop live talk mode
 #[tauri::command]
-pub fn stop_live_talk(state: State<'_, AppState>) -> Result<(), String> {
+pub fn stop_live_talk(state: State<'_, AppState>) -> Result<(), AppError> {
+    state.access.require(Capability::ControlPlayout)?;
     let mut live_talk = state.live_talk_active.lock().unwrap();
     *live_talk = None;
 
@@ -198,7 +218,8 @@ pub fn stop_live_talk(state: State<'_, AppState>) -> Result<(), String> {
 
 /// Set mix-minus (audio without mic return for remote callers)
 #[tauri::command]
-pub fn set_mix_minus(enabled: bool, state: State<'_, AppState>) -> Result<(), String> {
+pub fn set_mix_minus(enabled: bool, state: State<'_, AppState>) -> Result<(), AppError> {
+    state.access.require(Capability::ControlPlayout)?;
     let mut mix_minus = state.mix_minus_enabled.lock().unwrap();
     *mix_minus = enabled;
 
@@ -209,3 +230,71 @@ pub fn set_mix_minus(enabled: bool, state: State<'_, AppState>) -> Result<(), St
 
     Ok(())
 }
+
+/// Take a remote DJ's live audio into Aux 2. The session needs the
+/// `can_go_live` permission; any other remote DJ already live is dropped.
+#[tauri::command]
+pub async fn start_remote_audio(
+    session_id: String,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<RemoteAudioStatus, AppError> {
+    state.access.require(Capability::ControlPlayout)?;
+    let allowed = state
+        .remote_dj_permissions
+        .lock()
+        .unwrap()
+        .get(&session_id)
+        .is_some_and(|p| p.can_go_live);
+    if !allowed {
+        return Err(AppError::permission_denied(format!(
+            "Session {session_id} is not allowed to go live"
+        )));
+    }
+    let (url, token) = {
+        let client = state.gateway_client.lock().unwrap();
+        let client = client
+            .as_ref()
+            .filter(|c| c.is_connected())
+            .ok_or_else(|| AppError::invalid_input("Gateway not connected"))?;
+        (client.url().to_string(), client.token().to_string())
+    };
+
+    stop_remote_audio_for(&state, None);
+    let ingest = RemoteAudioIngest::start(app, &url, &token, session_id.clone());
+    let status = ingest.status();
+    *state.remote_audio.lock().unwrap() = Some(ingest);
+    log::info!("Remote audio started for session: {}", session_id);
+    Ok(status)
+}
+
+/// Stop the remote DJ live audio ingest
+#[tauri::command]
+pub fn stop_remote_audio(state: State<'_, AppState>) -> Result<(), AppError> {
+    state.access.require(Capability::ControlPlayout)?;
+    stop_remote_audio_for(&state, None);
+    Ok(())
+}
+
+/// Status of the current (or last) remote DJ live audio ingest
+#[tauri::command]
+pub fn get_remote_audio_status(
+    state: State<'_, AppState>,
+) -> Result<Option<RemoteAudioStatus>, AppError> {
+    Ok(state
+        .remote_audio
+        .lock()
+        .unwrap()
+        .as_ref()
+        .map(RemoteAudioIngest::status))
+}
+
+/// Stop the ingest, optionally only if it belongs to `session_id`. The
+/// handle is kept so its final status stays readable.
+fn stop_remote_audio_for(state: &AppState, session_id: Option<&str>) {
+    if let Some(ingest) = state.remote_audio.lock().unwrap().as_ref() {
+        if session_id.is_none_or(|id| id == ingest.session_id()) {
+            ingest.stop();
+        }
+    }
+}
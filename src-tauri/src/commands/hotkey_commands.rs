@@ -0,0 +1,53 @@
+use tauri::{AppHandle, State};
+
+use crate::access::Capability;
+use crate::error::AppError;
+use crate::{
+    audio::cart_wall,
+    controller::hotkeys::{self, HotkeyBinding, HotkeyConflict, HotkeyRegistration},
+    state::AppState,
+};
+
+#[tauri::command]
+pub fn get_hotkey_bindings() -> Result<Vec<HotkeyBinding>, AppError> {
+    Ok(hotkeys::get_bindings())
+}
+
+/// Replace the whole binding table. Rejected with `CONFLICT` when two
+/// bindings (or a binding and a cart) share a chord; chords another
+/// application already holds are reported per entry instead.
+#[tauri::command]
+pub async fn set_hotkey_bindings(
+    bindings: Vec<HotkeyBinding>,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<Vec<HotkeyRegistration>, AppError> {
+    state.access.require(Capability::ManageSettings)?;
+    let local = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    let carts = cart_wall::get_carts(local).await?;
+    hotkeys::save(local, bindings, &carts).await?;
+    hotkeys::sync(&app);
+    Ok(hotkeys::get_registrations())
+}
+
+/// Dry-run conflict check for the binding editor; nothing is saved.
+#[tauri::command]
+pub async fn check_hotkey_conflicts(
+    bindings: Vec<HotkeyBinding>,
+    state: State<'_, AppState>,
+) -> Result<Vec<HotkeyConflict>, AppError> {
+    let carts = match state.local_db.as_ref() {
+        Some(local) => cart_wall::get_carts(local).await?,
+        None => Vec::new(),
+    };
+    Ok(hotkeys::find_conflicts(&hotkeys::claims(&bindings, &carts)))
+}
+
+/// What the last registration pass actually got from the OS.
+#[tauri::command]
+pub fn get_hotkey_registrations() -> Result<Vec<HotkeyRegistration>, AppError> {
+    Ok(hotkeys::get_registrations())
+}
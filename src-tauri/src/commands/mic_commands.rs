@@ -1,40 +1,95 @@
 /// `commands/mic_commands.rs` — Phase 5 Tauri commands for microphone/voice
 use tauri::{Emitter, State};
 
+use crate::access::Capability;
+use crate::error::AppError;
 use crate::{
-    audio::mic_input::{list_input_devices, AudioDevice, MicConfig},
+    audio::{
+        ducking::DuckConfig,
+        mic_input::{list_input_devices, AudioDevice, MicConfig},
+    },
+    scheduler::voice_track::{self, VoiceTrackPlacement},
     state::AppState,
 };
 
 /// List all available audio input devices.
 #[tauri::command]
-pub async fn get_audio_input_devices() -> Result<Vec<AudioDevice>, String> {
+pub async fn get_audio_input_devices() -> Result<Vec<AudioDevice>, AppError> {
     Ok(list_input_devices())
 }
 
 /// Return the current mic configuration.
 #[tauri::command]
-pub async fn get_mic_config(state: State<'_, AppState>) -> Result<MicConfig, String> {
+pub async fn get_mic_config(state: State<'_, AppState>) -> Result<MicConfig, AppError> {
     Ok(state.mic_input.get_config())
 }
 
 /// Save a new mic configuration (does not restart the stream).
 #[tauri::command]
-pub async fn set_mic_config(state: State<'_, AppState>, config: MicConfig) -> Result<(), String> {
+pub async fn set_mic_config(state: State<'_, AppState>, config: MicConfig) -> Result<(), AppError> {
+    state.access.require(Capability::ManageSettings)?;
     state.mic_input.set_config(config);
     Ok(())
 }
 
-/// Start the microphone input stream.
+/// Start the microphone input stream and attach it to the live Voice FX channel.
 #[tauri::command]
-pub async fn start_mic(state: State<'_, AppState>) -> Result<(), String> {
-    state.mic_input.start()
+pub async fn start_mic(state: State<'_, AppState>) -> Result<(), AppError> {
+    state.access.require(Capability::ControlPlayout)?;
+    let sample_rate = state.engine.lock().unwrap().output_sample_rate();
+    state.mic_input.start(Some(sample_rate))?;
+    let prod = state.engine.lock().unwrap().attach_live_input();
+    state.mic_input.set_live_output(Some(prod));
+    sync_mic_open(&state).map_err(AppError::from)
 }
 
 /// Stop the microphone input stream.
 #[tauri::command]
-pub async fn stop_mic(state: State<'_, AppState>) -> Result<(), String> {
+pub async fn stop_mic(state: State<'_, AppState>) -> Result<(), AppError> {
+    state.access.require(Capability::ControlPlayout)?;
     state.mic_input.stop();
+    state.mic_input.set_live_output(None);
+    let mut engine = state.engine.lock().unwrap();
+    engine.detach_live_input();
+    engine.set_mic_open(false)
+}
+
+/// Latch the mic open to air (or close it) without holding PTT.
+#[tauri::command]
+pub async fn set_mic_open(
+    state: State<'_, AppState>,
+    open: bool,
+    app: tauri::AppHandle,
+) -> Result<(), AppError> {
+    state.access.require(Capability::ControlPlayout)?;
+    state.mic_input.set_latched(open);
+    sync_mic_open(&state)?;
+    let _ = app.emit("mic_open_changed", serde_json::json!({ "open": open }));
+    Ok(())
+}
+
+/// Return the Deck A/B ducking settings used while the mic is open.
+#[tauri::command]
+pub async fn get_mic_duck_config(state: State<'_, AppState>) -> Result<DuckConfig, AppError> {
+    Ok(state.engine.lock().unwrap().get_duck_config())
+}
+
+/// Apply and persist the mic ducking settings.
+#[tauri::command]
+pub async fn set_mic_duck_config(
+    state: State<'_, AppState>,
+    config: DuckConfig,
+) -> Result<(), AppError> {
+    state.access.require(Capability::ManageSettings)?;
+    state
+        .engine
+        .lock()
+        .unwrap()
+        .set_duck_config(config.clone())?;
+    if let Some(pool) = &state.local_db {
+        let json = serde_json::to_string(&config).map_err(|e| e.to_string())?;
+        crate::db::local::save_mic_duck_config(pool, &json).await?;
+    }
     Ok(())
 }
 
@@ -44,15 +99,18 @@ pub async fn set_ptt(
     state: State<'_, AppState>,
     active: bool,
     app: tauri::AppHandle,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
+    state.access.require(Capability::ControlPlayout)?;
     state.mic_input.set_ptt(active);
+    sync_mic_open(&state)?;
     let _ = app.emit("ptt_state_changed", serde_json::json!({ "active": active }));
     Ok(())
 }
 
 /// Start recording a voice track to a temp file.
 #[tauri::command]
-pub async fn start_voice_recording(state: State<'_, AppState>) -> Result<(), String> {
+pub async fn start_voice_recording(state: State<'_, AppState>) -> Result<(), AppError> {
+    state.access.require(Capability::ControlPlayout)?;
     let path = std::env::temp_dir()
         .join(format!(
             "voice_track_{}.wav",
@@ -65,12 +123,18 @@ pub async fn start_voice_recording(state: State<'_, AppState>) -> Result<(), Str
         .lock()
         .unwrap()
         .replace(path.clone());
-    state.mic_input.start_recording(&path)
+    state
+        .mic_input
+        .start_recording(&path)
+        .map_err(AppError::from)
 }
 
 /// Stop recording a voice track; returns the file path and duration.
 #[tauri::command]
-pub async fn stop_voice_recording(state: State<'_, AppState>) -> Result<serde_json::Value, String> {
+pub async fn stop_voice_recording(
+    state: State<'_, AppState>,
+) -> Result<serde_json::Value, AppError> {
+    state.access.require(Capability::ControlPlayout)?;
     let duration_ms = state.mic_input.stop_recording()?;
     let file_path = state
         .voice_recording_path
@@ -84,15 +148,89 @@ pub async fn stop_voice_recording(state: State<'_, AppState>) -> Result<serde_js
     }))
 }
 
-/// Import a voice track file into the library (saves metadata to SAM DB / local DB).
+/// Register a recorded voice track; returns its placement id.
+/// Placement (previous/next song, overlaps, trim, gain, fades) is set afterwards.
 #[tauri::command]
 pub async fn save_voice_track(
     state: State<'_, AppState>,
     file_path: String,
     title: String,
-) -> Result<i64, String> {
-    // In full implementation, this would call the SAM MySQL importer or local library.
-    // For now, save to local SQLite with a stub song_id.
-    let _ = (&state, file_path, title); // used
-    Ok(-1) // stub id until library import is wired
+) -> Result<i64, AppError> {
+    state.access.require(Capability::EditLibrary)?;
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    let placement = VoiceTrackPlacement {
+        id: None,
+        title,
+        file_path,
+        duration_ms: None,
+        prev_song_id: None,
+        next_song_id: None,
+        prev_overlap_ms: 0,
+        next_overlap_ms: 0,
+        trim_start_ms: 0,
+        trim_end_ms: 0,
+        gain_db: 0.0,
+        fades: Default::default(),
+        played_at: None,
+    };
+    voice_track::save_voice_track(pool, &placement)
+        .await
+        .map_err(AppError::from)
+}
+
+/// Create or update a voice track placement.
+#[tauri::command]
+pub async fn save_voice_track_placement(
+    state: State<'_, AppState>,
+    placement: VoiceTrackPlacement,
+) -> Result<i64, AppError> {
+    state.access.require(Capability::EditSchedule)?;
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    voice_track::save_voice_track(pool, &placement)
+        .await
+        .map_err(AppError::from)
+}
+
+/// List voice track placements (unplayed only unless `include_played`).
+#[tauri::command]
+pub async fn get_voice_tracks(
+    state: State<'_, AppState>,
+    include_played: Option<bool>,
+) -> Result<Vec<VoiceTrackPlacement>, AppError> {
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    voice_track::get_voice_tracks(pool, include_played.unwrap_or(false))
+        .await
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn delete_voice_track(state: State<'_, AppState>, id: i64) -> Result<(), AppError> {
+    state.access.require(Capability::EditLibrary)?;
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    voice_track::delete_voice_track(pool, id)
+        .await
+        .map_err(AppError::from)
+}
+
+/// Push the mic's on-air state to the engine so it can duck the music decks.
+pub(crate) fn sync_mic_open(state: &AppState) -> Result<(), String> {
+    let open = state.mic_input.is_on_air();
+    state
+        .engine
+        .lock()
+        .unwrap()
+        .set_mic_open(open)
+        .map_err(Into::into)
 }
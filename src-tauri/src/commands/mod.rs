@@ -1,17 +1,26 @@
+pub mod access_commands;
+pub mod analysis_commands;
 pub mod analytics_commands;
+pub mod artwork_commands;
 pub mod audio_commands;
 pub mod beatgrid_commands;
+pub mod cart_commands;
 pub mod controller_commands;
 pub mod crossfade_commands;
 pub mod cue_commands;
 pub mod dsp_commands;
 pub mod encoder_commands;
+pub mod enrichment_commands;
 pub mod gateway_commands;
+pub mod hotkey_commands;
 pub mod mic_commands;
 pub mod queue_commands;
 pub mod sam_db_commands;
 pub mod scheduler_commands;
 pub mod script_commands;
+pub mod session_commands;
+pub mod settings_commands;
+pub mod sfx_commands;
 pub mod stem_commands;
 pub mod stream_commands;
 pub mod waveform_commands;
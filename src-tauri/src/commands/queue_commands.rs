@@ -1,30 +1,45 @@
 use tauri::State;
 
+use crate::error::AppError;
 use crate::{
+    access::{self, Capability},
     db::{
-        local::get_sam_db_config,
+        path_rules,
         sam::{self, HistoryEntry, QueueEntry, SamSong, SongUpdateFields},
     },
-    scheduler::rotation,
+    scheduler::{autodj_plan, rotation},
     state::AppState,
 };
 
+/// Rewrite SAM filenames to local paths with the path translation rules.
+async fn translate_song_paths(state: &AppState, songs: &mut [SamSong]) {
+    let Some(local) = &state.local_db else {
+        return;
+    };
+    let translator = path_rules::load_translator(local).await;
+    if translator.is_empty() {
+        return;
+    }
+    for song in songs {
+        song.filename = translator.translate(&song.filename);
+    }
+}
+
 #[tauri::command]
-pub async fn get_queue(state: State<'_, AppState>) -> Result<Vec<QueueEntry>, String> {
+pub async fn get_queue(state: State<'_, AppState>) -> Result<Vec<QueueEntry>, AppError> {
     let guard = state.sam_db.read().await;
-    let pool = guard.as_ref().ok_or("SAM DB not connected")?;
-    sam::get_queue(pool)
-        .await
-        .map_err(|e| format!("DB error: {e}"))
+    let pool = guard.as_ref().ok_or_else(AppError::sam_db_unavailable)?;
+    sam::get_queue(pool).await.map_err(AppError::db)
 }
 
 #[tauri::command]
-pub async fn add_to_queue(song_id: i64, state: State<'_, AppState>) -> Result<i64, String> {
+pub async fn add_to_queue(song_id: i64, state: State<'_, AppState>) -> Result<i64, AppError> {
+    let actor = state.access.require(Capability::EditQueue)?;
     let guard = state.sam_db.read().await;
-    let pool = guard.as_ref().ok_or("SAM DB not connected")?;
+    let pool = guard.as_ref().ok_or_else(AppError::sam_db_unavailable)?;
     let queue_id = sam::add_to_queue(pool, song_id)
         .await
-        .map_err(|e| format!("DB error: {e}"))?;
+        .map_err(AppError::db)?;
 
     if let Some(local) = &state.local_db {
         if let Err(err) = rotation::apply_weight_delta_on_request(local, pool, song_id).await {
@@ -36,25 +51,57 @@ pub async fn add_to_queue(song_id: i64, state: State<'_, AppState>) -> Result<i6
         }
     }
 
+    access::audit(
+        &state,
+        &actor,
+        "queue.add",
+        Some(format!("queue:{queue_id}")),
+        serde_json::json!({ "song_id": song_id }),
+    )
+    .await;
     Ok(queue_id)
 }
 
 #[tauri::command]
-pub async fn remove_from_queue(queue_id: i64, state: State<'_, AppState>) -> Result<(), String> {
+pub async fn remove_from_queue(queue_id: i64, state: State<'_, AppState>) -> Result<(), AppError> {
+    let actor = state.access.require(Capability::EditQueue)?;
     let guard = state.sam_db.read().await;
-    let pool = guard.as_ref().ok_or("SAM DB not connected")?;
+    let pool = guard.as_ref().ok_or_else(AppError::sam_db_unavailable)?;
     sam::remove_from_queue(pool, queue_id)
         .await
-        .map_err(|e| format!("DB error: {e}"))
+        .map_err(AppError::db)?;
+    access::audit(
+        &state,
+        &actor,
+        "queue.remove",
+        Some(format!("queue:{queue_id}")),
+        serde_json::json!({}),
+    )
+    .await;
+    Ok(())
 }
 
 #[tauri::command]
-pub async fn reorder_queue(queue_ids: Vec<i64>, state: State<'_, AppState>) -> Result<(), String> {
+pub async fn reorder_queue(
+    queue_ids: Vec<i64>,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    let actor = state.access.require(Capability::EditQueue)?;
     let guard = state.sam_db.read().await;
-    let pool = guard.as_ref().ok_or("SAM DB not connected")?;
+    let pool = guard.as_ref().ok_or_else(AppError::sam_db_unavailable)?;
+    autodj_plan::check_queue_order(pool, &queue_ids).await?;
     sam::reorder_queue(pool, &queue_ids)
         .await
-        .map_err(|e| format!("DB error: {e}"))
+        .map_err(AppError::db)?;
+    access::audit(
+        &state,
+        &actor,
+        "queue.reorder",
+        None,
+        serde_json::json!({ "order": queue_ids }),
+    )
+    .await;
+    Ok(())
 }
 
 /// Mark a queue entry as completed: removes it from `queuelist` and writes a
@@ -64,9 +111,10 @@ pub async fn complete_queue_item(
     queue_id: i64,
     song_id: i64,
     state: State<'_, AppState>,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
+    state.access.require(Capability::EditQueue)?;
     let guard = state.sam_db.read().await;
-    let pool = guard.as_ref().ok_or("SAM DB not connected")?;
+    let pool = guard.as_ref().ok_or_else(AppError::sam_db_unavailable)?;
 
     // Fetch the full song record so we can snapshot metadata into historylist
     let song = sam::get_song(pool, song_id)
@@ -84,7 +132,7 @@ pub async fn complete_queue_item(
 
     sam::complete_track(pool, queue_id, &song, listener_snapshot)
         .await
-        .map_err(|e| format!("DB error completing track: {e}"))
+        .map_err(AppError::db)
 }
 
 #[tauri::command]
@@ -98,9 +146,9 @@ pub async fn search_songs(
     limit: Option<u32>,
     offset: Option<u32>,
     state: State<'_, AppState>,
-) -> Result<Vec<SamSong>, String> {
+) -> Result<Vec<SamSong>, AppError> {
     let guard = state.sam_db.read().await;
-    let pool = guard.as_ref().ok_or("SAM DB not connected")?;
+    let pool = guard.as_ref().ok_or_else(AppError::sam_db_unavailable)?;
 
     let mut songs = sam::search_songs(
         pool,
@@ -114,22 +162,10 @@ pub async fn search_songs(
         offset.unwrap_or(0),
     )
     .await
-    .map_err(|e| format!("DB error: {e}"))?;
+    .map_err(AppError::db)?;
 
-    // Apply path prefix translation if configured
-    if let Some(local) = &state.local_db {
-        if let Ok(cfg) = get_sam_db_config(local).await {
-            if !cfg.path_prefix_from.is_empty() {
-                for song in &mut songs {
-                    song.filename = sam::translate_path(
-                        &song.filename,
-                        &cfg.path_prefix_from,
-                        &cfg.path_prefix_to,
-                    );
-                }
-            }
-        }
-    }
+    // Apply path translation rules
+    translate_song_paths(&state, &mut songs).await;
 
     Ok(songs)
 }
@@ -141,9 +177,9 @@ pub async fn get_songs_by_weight_range(
     limit: Option<u32>,
     offset: Option<u32>,
     state: State<'_, AppState>,
-) -> Result<Vec<SamSong>, String> {
+) -> Result<Vec<SamSong>, AppError> {
     let guard = state.sam_db.read().await;
-    let pool = guard.as_ref().ok_or("SAM DB not connected")?;
+    let pool = guard.as_ref().ok_or_else(AppError::sam_db_unavailable)?;
 
     let mut songs = sam::get_songs_by_weight_range(
         pool,
@@ -153,45 +189,33 @@ pub async fn get_songs_by_weight_range(
         offset.unwrap_or(0),
     )
     .await
-    .map_err(|e| format!("DB error: {e}"))?;
+    .map_err(AppError::db)?;
 
     // Apply same path translation
-    if let Some(local) = &state.local_db {
-        if let Ok(cfg) = get_sam_db_config(local).await {
-            if !cfg.path_prefix_from.is_empty() {
-                for song in &mut songs {
-                    song.filename = sam::translate_path(
-                        &song.filename,
-                        &cfg.path_prefix_from,
-                        &cfg.path_prefix_to,
-                    );
-                }
-            }
-        }
-    }
+    translate_song_paths(&state, &mut songs).await;
 
     Ok(songs)
 }
 
 #[tauri::command]
-pub async fn get_song_types(state: State<'_, AppState>) -> Result<Vec<String>, String> {
+pub async fn get_song_types(state: State<'_, AppState>) -> Result<Vec<String>, AppError> {
     let guard = state.sam_db.read().await;
-    let pool = guard.as_ref().ok_or("SAM DB not connected")?;
+    let pool = guard.as_ref().ok_or_else(AppError::sam_db_unavailable)?;
     sam::get_distinct_song_types(pool)
         .await
-        .map_err(|e| format!("DB error: {e}"))
+        .map_err(AppError::db)
 }
 
 #[tauri::command]
 pub async fn get_history(
     limit: Option<u32>,
     state: State<'_, AppState>,
-) -> Result<Vec<HistoryEntry>, String> {
+) -> Result<Vec<HistoryEntry>, AppError> {
     let guard = state.sam_db.read().await;
-    let pool = guard.as_ref().ok_or("SAM DB not connected")?;
+    let pool = guard.as_ref().ok_or_else(AppError::sam_db_unavailable)?;
     sam::get_history(pool, limit.unwrap_or(20))
         .await
-        .map_err(|e| format!("DB error: {e}"))
+        .map_err(AppError::db)
 }
 
 #[tauri::command]
@@ -200,52 +224,34 @@ pub async fn get_songs_in_category(
     limit: Option<u32>,
     offset: Option<u32>,
     state: State<'_, AppState>,
-) -> Result<Vec<SamSong>, String> {
+) -> Result<Vec<SamSong>, AppError> {
     let guard = state.sam_db.read().await;
-    let pool = guard.as_ref().ok_or("SAM DB not connected")?;
+    let pool = guard.as_ref().ok_or_else(AppError::sam_db_unavailable)?;
 
     let mut songs =
         sam::get_songs_in_category(pool, category_id, limit.unwrap_or(500), offset.unwrap_or(0))
             .await
-            .map_err(|e| format!("DB error: {e}"))?;
+            .map_err(AppError::db)?;
 
     // Apply same path translation as search_songs
-    if let Some(local) = &state.local_db {
-        if let Ok(cfg) = get_sam_db_config(local).await {
-            if !cfg.path_prefix_from.is_empty() {
-                for song in &mut songs {
-                    song.filename = sam::translate_path(
-                        &song.filename,
-                        &cfg.path_prefix_from,
-                        &cfg.path_prefix_to,
-                    );
-                }
-            }
-        }
-    }
+    translate_song_paths(&state, &mut songs).await;
 
     Ok(songs)
 }
 
 #[tauri::command]
-pub async fn get_song(song_id: i64, state: State<'_, AppState>) -> Result<Option<SamSong>, String> {
+pub async fn get_song(
+    song_id: i64,
+    state: State<'_, AppState>,
+) -> Result<Option<SamSong>, AppError> {
     let guard = state.sam_db.read().await;
-    let pool = guard.as_ref().ok_or("SAM DB not connected")?;
-    let song = sam::get_song(pool, song_id)
-        .await
-        .map_err(|e| format!("DB error: {e}"))?;
+    let pool = guard.as_ref().ok_or_else(AppError::sam_db_unavailable)?;
+    let song = sam::get_song(pool, song_id).await.map_err(AppError::db)?;
     let Some(mut song) = song else {
         return Ok(None);
     };
     // Apply path translation
-    if let Some(local) = &state.local_db {
-        if let Ok(cfg) = get_sam_db_config(local).await {
-            if !cfg.path_prefix_from.is_empty() {
-                song.filename =
-                    sam::translate_path(&song.filename, &cfg.path_prefix_from, &cfg.path_prefix_to);
-            }
-        }
-    }
+    translate_song_paths(&state, std::slice::from_mut(&mut song)).await;
     Ok(Some(song))
 }
 
@@ -254,10 +260,11 @@ pub async fn update_song(
     song_id: i64,
     fields: SongUpdateFields,
     state: State<'_, AppState>,
-) -> Result<bool, String> {
+) -> Result<bool, AppError> {
+    state.access.require(Capability::EditLibrary)?;
     let guard = state.sam_db.read().await;
-    let pool = guard.as_ref().ok_or("SAM DB not connected")?;
+    let pool = guard.as_ref().ok_or_else(AppError::sam_db_unavailable)?;
     sam::update_song(pool, song_id, fields)
         .await
-        .map_err(|e| format!("DB error: {e}"))
+        .map_err(AppError::db)
 }
@@ -1,10 +1,17 @@
 use serde::{Deserialize, Serialize};
-use tauri::State;
+use tauri::{AppHandle, State};
 
+use crate::error::{AppError, ErrorCode};
 use crate::{
+    access::{self, Capability},
     db::{
         local::{get_sam_db_config, save_sam_db_config, SamDbConfig},
-        sam::{connect, create_category, get_categories, SamCategory},
+        path_rules::{self, PathRule, PathTranslation},
+        sam::{connect, create_category, get_categories, mysql_url, SamCategory},
+        sam_cache::{self, CacheStats},
+        sam_health::{self, SamHealth},
+        sam_outbox::{self, FailedWrite, OutboxStatus},
+        sam_sync::{self, SongEdit, SyncError, SyncedSong},
     },
     state::AppState,
 };
@@ -35,8 +42,8 @@ pub struct SamDbStatus {
 
 /// Test a SAM DB connection without saving or storing it.
 #[tauri::command]
-pub async fn test_sam_db_connection(args: SamDbConnectArgs) -> Result<SamDbStatus, String> {
-    let url = build_mysql_url(
+pub async fn test_sam_db_connection(args: SamDbConnectArgs) -> Result<SamDbStatus, AppError> {
+    let url = mysql_url(
         &args.host,
         args.port,
         &args.username,
@@ -67,9 +74,11 @@ pub async fn test_sam_db_connection(args: SamDbConnectArgs) -> Result<SamDbStatu
 #[tauri::command]
 pub async fn connect_sam_db(
     args: SamDbConnectArgs,
+    app: AppHandle,
     state: State<'_, AppState>,
-) -> Result<SamDbStatus, String> {
-    let url = build_mysql_url(
+) -> Result<SamDbStatus, AppError> {
+    state.access.require(Capability::ManageSettings)?;
+    let url = mysql_url(
         &args.host,
         args.port,
         &args.username,
@@ -77,12 +86,17 @@ pub async fn connect_sam_db(
         &args.database,
     );
 
-    let pool = connect(&url)
-        .await
-        .map_err(|e| format!("SAM DB connect failed: {e}"))?;
+    let pool = connect(&url).await.map_err(|e| {
+        AppError::new(
+            ErrorCode::SamDbUnavailable,
+            format!("SAM DB connect failed: {e}"),
+        )
+    })?;
 
     // Store pool in AppState
     *state.sam_db.write().await = Some(pool);
+    sam_cache::reset();
+    sam_health::mark_connected(&app).await;
 
     // Persist config (including password) to local SQLite
     if let Some(local) = &state.local_db {
@@ -99,7 +113,12 @@ pub async fn connect_sam_db(
         };
         save_sam_db_config(local, &cfg, &args.password)
             .await
-            .map_err(|e| format!("Failed to save SAM DB config: {e}"))?;
+            .map_err(|e| {
+                AppError::new(
+                    ErrorCode::DbError,
+                    format!("Failed to save SAM DB config: {e}"),
+                )
+            })?;
     }
 
     Ok(SamDbStatus {
@@ -112,9 +131,12 @@ pub async fn connect_sam_db(
 
 /// Disconnect from SAM DB and drop the pool.
 #[tauri::command]
-pub async fn disconnect_sam_db(state: State<'_, AppState>) -> Result<(), String> {
-    let mut guard = state.sam_db.write().await;
-    if let Some(pool) = guard.take() {
+pub async fn disconnect_sam_db(app: AppHandle, state: State<'_, AppState>) -> Result<(), AppError> {
+    state.access.require(Capability::ManageSettings)?;
+    sam_health::mark_disconnected(&app).await;
+    let pool = state.sam_db.write().await.take();
+    sam_cache::reset();
+    if let Some(pool) = pool {
         pool.close().await;
     }
     Ok(())
@@ -122,11 +144,12 @@ pub async fn disconnect_sam_db(state: State<'_, AppState>) -> Result<(), String>
 
 /// Return the saved SAM DB config (no password).
 #[tauri::command]
-pub async fn get_sam_db_config_cmd(state: State<'_, AppState>) -> Result<SamDbConfig, String> {
-    let local = state.local_db.as_ref().ok_or("Local DB not initialised")?;
-    get_sam_db_config(local)
-        .await
-        .map_err(|e| format!("DB error: {e}"))
+pub async fn get_sam_db_config_cmd(state: State<'_, AppState>) -> Result<SamDbConfig, AppError> {
+    let local = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    get_sam_db_config(local).await.map_err(AppError::db)
 }
 
 /// Save connection config to SQLite without actually connecting.
@@ -136,16 +159,20 @@ pub async fn save_sam_db_config_cmd(
     config: SamDbConfig,
     password: String,
     state: State<'_, AppState>,
-) -> Result<(), String> {
-    let local = state.local_db.as_ref().ok_or("Local DB not initialised")?;
+) -> Result<(), AppError> {
+    state.access.require(Capability::ManageSettings)?;
+    let local = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
     save_sam_db_config(local, &config, &password)
         .await
-        .map_err(|e| format!("DB error: {e}"))
+        .map_err(AppError::db)
 }
 
 /// Return live connection status.
 #[tauri::command]
-pub async fn get_sam_db_status(state: State<'_, AppState>) -> Result<SamDbStatus, String> {
+pub async fn get_sam_db_status(state: State<'_, AppState>) -> Result<SamDbStatus, AppError> {
     let guard = state.sam_db.read().await;
     if guard.is_some() {
         // Load saved config to show host/database info (no password)
@@ -158,10 +185,10 @@ pub async fn get_sam_db_status(state: State<'_, AppState>) -> Result<SamDbStatus
             (None, None)
         };
         Ok(SamDbStatus {
-            connected: true,
+            connected: !sam_health::is_degraded(),
             host,
             database,
-            error: None,
+            error: sam_health::snapshot().last_error,
         })
     } else {
         Ok(SamDbStatus {
@@ -173,14 +200,24 @@ pub async fn get_sam_db_status(state: State<'_, AppState>) -> Result<SamDbStatus
     }
 }
 
+/// Health-check state: connected, degraded (reconnecting) or disconnected.
+#[tauri::command]
+pub async fn get_sam_db_health() -> Result<SamHealth, AppError> {
+    Ok(sam_health::snapshot())
+}
+
+/// Hit/miss counters for the SAM query cache.
+#[tauri::command]
+pub async fn get_sam_cache_stats() -> Result<CacheStats, AppError> {
+    Ok(sam_cache::stats())
+}
+
 /// Return SAM categories.  Empty Vec if catlist table doesn't exist.
 #[tauri::command]
-pub async fn get_sam_categories(state: State<'_, AppState>) -> Result<Vec<SamCategory>, String> {
+pub async fn get_sam_categories(state: State<'_, AppState>) -> Result<Vec<SamCategory>, AppError> {
     let guard = state.sam_db.read().await;
-    let pool = guard.as_ref().ok_or("SAM DB not connected")?;
-    get_categories(pool)
-        .await
-        .map_err(|e| format!("SAM DB error: {e}"))
+    let pool = guard.as_ref().ok_or_else(AppError::sam_db_unavailable)?;
+    get_categories(pool).await.map_err(AppError::db)
 }
 
 #[tauri::command]
@@ -188,16 +225,265 @@ pub async fn create_sam_category(
     name: String,
     parent_id: Option<i64>,
     state: State<'_, AppState>,
-) -> Result<SamCategory, String> {
+) -> Result<SamCategory, AppError> {
+    state.access.require(Capability::EditLibrary)?;
     let guard = state.sam_db.read().await;
-    let pool = guard.as_ref().ok_or("SAM DB not connected")?;
-    create_category(pool, &name, parent_id).await
+    let pool = guard.as_ref().ok_or_else(AppError::sam_db_unavailable)?;
+    create_category(pool, &name, parent_id)
+        .await
+        .map_err(AppError::from)
 }
 
-// ── Helpers ───────────────────────────────────────────────────────────────────
+// ── Path translation rules ────────────────────────────────────────────────────
 
-fn build_mysql_url(host: &str, port: i64, user: &str, password: &str, database: &str) -> String {
-    // URL-encode the password to handle special characters
-    let enc_password = urlencoding::encode(password);
-    format!("mysql://{user}:{enc_password}@{host}:{port}/{database}")
+#[tauri::command]
+pub async fn get_path_translation_rules(
+    state: State<'_, AppState>,
+) -> Result<Vec<PathRule>, AppError> {
+    let local = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    path_rules::get_path_rules(local)
+        .await
+        .map_err(AppError::db)
+}
+
+/// Create or update a rule. New rules are appended after the existing ones.
+#[tauri::command]
+pub async fn save_path_translation_rule(
+    rule: PathRule,
+    state: State<'_, AppState>,
+) -> Result<i64, AppError> {
+    let actor = state.access.require(Capability::ManageSettings)?;
+    path_rules::validate_rule(&rule).map_err(AppError::invalid_input)?;
+    let local = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    let id = path_rules::upsert_path_rule(local, &rule)
+        .await
+        .map_err(AppError::db)?;
+    access::audit(
+        &state,
+        &actor,
+        "path_rule.save",
+        Some(format!("path_rule:{id}")),
+        serde_json::json!({
+            "kind": rule.kind,
+            "pattern": rule.pattern,
+            "replacement": rule.replacement,
+            "enabled": rule.enabled,
+        }),
+    )
+    .await;
+    Ok(id)
+}
+
+#[tauri::command]
+pub async fn delete_path_translation_rule(
+    id: i64,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    let actor = state.access.require(Capability::ManageSettings)?;
+    let local = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    path_rules::delete_path_rule(local, id)
+        .await
+        .map_err(AppError::db)?;
+    access::audit(
+        &state,
+        &actor,
+        "path_rule.delete",
+        Some(format!("path_rule:{id}")),
+        serde_json::json!({}),
+    )
+    .await;
+    Ok(())
+}
+
+/// Set the evaluation order; `ids` lists rules first-to-last.
+#[tauri::command]
+pub async fn reorder_path_translation_rules(
+    ids: Vec<i64>,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    let actor = state.access.require(Capability::ManageSettings)?;
+    let local = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    path_rules::reorder_path_rules(local, &ids)
+        .await
+        .map_err(AppError::db)?;
+    access::audit(
+        &state,
+        &actor,
+        "path_rule.reorder",
+        None,
+        serde_json::json!({ "ids": ids }),
+    )
+    .await;
+    Ok(())
+}
+
+/// Run SAM paths through the saved rules and report which rule matched and
+/// whether the result exists on this machine.
+#[tauri::command]
+pub async fn test_path_translation(
+    paths: Vec<String>,
+    state: State<'_, AppState>,
+) -> Result<Vec<PathTranslation>, AppError> {
+    let local = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    let translator = path_rules::load_translator(local).await;
+    Ok(paths.iter().map(|p| translator.explain(p)).collect())
+}
+
+// ── Write-behind outbox ───────────────────────────────────────────────────────
+
+/// Backlog of SAM writes not yet applied, and the error holding up the head.
+#[tauri::command]
+pub async fn get_sam_outbox_status(state: State<'_, AppState>) -> Result<OutboxStatus, AppError> {
+    let local = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    sam_outbox::status(local).await.map_err(AppError::db)
+}
+
+/// Writes SAM kept rejecting, parked so the rest of the outbox could drain.
+#[tauri::command]
+pub async fn get_failed_sam_writes(
+    state: State<'_, AppState>,
+) -> Result<Vec<FailedWrite>, AppError> {
+    let local = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    sam_outbox::list_failed(local).await.map_err(AppError::db)
+}
+
+/// Re-queue parked writes (all of them when `ids` is omitted).
+#[tauri::command]
+pub async fn retry_failed_sam_writes(
+    ids: Option<Vec<i64>>,
+    state: State<'_, AppState>,
+) -> Result<u64, AppError> {
+    let actor = state.access.require(Capability::ManageSettings)?;
+    let local = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    let count = sam_outbox::retry_failed(local, ids.as_deref())
+        .await
+        .map_err(AppError::db)?;
+    access::audit(
+        &state,
+        &actor,
+        "sam_outbox.retry",
+        None,
+        serde_json::json!({ "ids": ids, "count": count }),
+    )
+    .await;
+    Ok(count)
+}
+
+/// Drop parked writes for good (all of them when `ids` is omitted).
+#[tauri::command]
+pub async fn discard_failed_sam_writes(
+    ids: Option<Vec<i64>>,
+    state: State<'_, AppState>,
+) -> Result<u64, AppError> {
+    let actor = state.access.require(Capability::ManageSettings)?;
+    let local = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    let count = sam_outbox::discard_failed(local, ids.as_deref())
+        .await
+        .map_err(AppError::db)?;
+    access::audit(
+        &state,
+        &actor,
+        "sam_outbox.discard",
+        None,
+        serde_json::json!({ "ids": ids, "count": count }),
+    )
+    .await;
+    Ok(count)
+}
+
+// ── Two-way song sync ─────────────────────────────────────────────────────────
+
+fn sync_error(song_id: i64, e: SyncError) -> AppError {
+    match e {
+        SyncError::NotFound => AppError::not_found(format!("Song {song_id} is not in SAM")),
+        SyncError::Conflict(current) => AppError::conflict(format!(
+            "'{}' was changed since it was opened; reload it and try again",
+            current.title
+        )),
+        SyncError::Sam(e) => AppError::db(e),
+        SyncError::Local(e) => AppError::db(e),
+    }
+}
+
+/// A song's synced fields, fresh from SAM. Pass `modified_at` back as the
+/// edit's `base_modified_at`.
+#[tauri::command]
+pub async fn get_synced_song(
+    song_id: i64,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<SyncedSong, AppError> {
+    let local = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    let sam =
+        { state.sam_db.read().await.as_ref().cloned() }.ok_or_else(AppError::sam_db_unavailable)?;
+    sam_sync::get_song(&app, &sam, local, song_id)
+        .await
+        .map_err(|e| sync_error(song_id, e))
+}
+
+/// Write title / artist / album / weight / category back to SAM. Fails with
+/// a conflict when the song changed since `edit.base_modified_at`.
+#[tauri::command]
+pub async fn save_synced_song(
+    song_id: i64,
+    edit: SongEdit,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<SyncedSong, AppError> {
+    state.access.require(Capability::EditLibrary)?;
+    let local = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    let sam =
+        { state.sam_db.read().await.as_ref().cloned() }.ok_or_else(AppError::sam_db_unavailable)?;
+    sam_sync::save_edit(&app, &sam, local, song_id, edit)
+        .await
+        .map_err(|e| sync_error(song_id, e))
+}
+
+/// Songs whose synced fields changed after `since` (unix ms), oldest first.
+#[tauri::command]
+pub async fn get_sam_song_changes(
+    since: i64,
+    limit: Option<i64>,
+    state: State<'_, AppState>,
+) -> Result<Vec<SyncedSong>, AppError> {
+    let local = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    sam_sync::changes_since(local, since, limit.unwrap_or(500).clamp(1, 5000))
+        .await
+        .map_err(AppError::db)
 }
@@ -1,15 +1,31 @@
+use crate::access::{self, Capability};
+use crate::error::AppError;
 use crate::scheduler::{
     autodj::{
         self, AutoTransitionConfig, AutoTransitionMode, AutodjTransitionEngine, DjMode,
         GapKillerConfig, MixxxPlannerConfig, TransitionDecisionDebug,
     },
-    request_policy::{self, RequestLogEntry, RequestPolicy, RequestStatus},
-    rotation::{self, ClockwheelConfig, Playlist, RotationRuleRow},
-    show_scheduler::{self, ScheduledEvent, Show},
+    autodj_plan::{self, PlanItem, PlanItemRef},
+    mode_transition::{self, DjModeTransitionEvent, ModeChangeRequest, PendingModeChange},
+    playlist_io::{self, PlaylistEntry, PlaylistFormat, SongPathIndex},
+    relay::{self, RelayEvent, RelayStatus},
+    request_api::{self, RequestApiConfig, RequestApiStatus},
+    request_policy::{
+        self, RequestDecision, RequestLogEntry, RequestPolicy, RequestStatus, RequestSubject,
+        TriageSummary,
+    },
+    rotation::{
+        self, ClockwheelConfig, ClockwheelHourAssignment, ClockwheelTemplate, Playlist,
+        PlaylistCursor, PlaylistSong, RotationRuleRow,
+    },
+    show_scheduler::{self, ActiveShowOverrides, ScheduledEvent, Show},
+    timed_events::{self, TimedEvent},
+    track_preflight::{self, BadTrack},
+    traffic::{self, AdBreak, Campaign, SpotLogEntry},
 };
 use crate::state::AppState;
 /// Phase 3 — Automation & Scheduling commands
-use tauri::State;
+use tauri::{AppHandle, State};
 #[derive(Debug, Clone, serde::Serialize)]
 pub struct EnqueuedClockwheelTrack {
     pub queue_id: i64,
@@ -19,7 +35,7 @@ pub struct EnqueuedClockwheelTrack {
 // ── DJ Mode ───────────────────────────────────────────────────────────────────
 
 #[tauri::command]
-pub async fn get_dj_mode(state: State<'_, AppState>) -> Result<String, String> {
+pub async fn get_dj_mode(state: State<'_, AppState>) -> Result<String, AppError> {
     if let Some(pool) = &state.local_db {
         if let Ok(saved) = crate::db::local::get_runtime_dj_mode(pool).await {
             let mode = DjMode::from_str(&saved);
@@ -30,22 +46,49 @@ pub async fn get_dj_mode(state: State<'_, AppState>) -> Result<String, String> {
     Ok(autodj::get_dj_mode().as_str().to_string())
 }
 
+/// Immediate switch; an in-flight crossfade is allowed to finish first.
 #[tauri::command]
-pub async fn set_dj_mode(mode: String, state: State<'_, AppState>) -> Result<(), String> {
-    let mode_enum = DjMode::from_str(&mode);
-    autodj::set_dj_mode(mode_enum);
-    if let Some(pool) = &state.local_db {
-        crate::db::local::save_runtime_dj_mode(pool, mode_enum.as_str())
-            .await
-            .map_err(|e| format!("Failed to persist DJ mode: {e}"))?;
-    }
+pub async fn set_dj_mode(
+    mode: String,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    state.access.require(Capability::ControlPlayout)?;
+    let request = ModeChangeRequest::immediate(DjMode::from_str(&mode));
+    mode_transition::request_change(&app, request).await?;
     Ok(())
 }
 
+#[tauri::command]
+pub async fn request_dj_mode_change(
+    request: ModeChangeRequest,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<DjModeTransitionEvent, AppError> {
+    state.access.require(Capability::ControlPlayout)?;
+    mode_transition::request_change(&app, request)
+        .await
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn get_pending_dj_mode_change() -> Result<Option<PendingModeChange>, AppError> {
+    Ok(mode_transition::get_pending())
+}
+
+#[tauri::command]
+pub async fn cancel_pending_dj_mode_change(
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<Option<DjModeTransitionEvent>, AppError> {
+    state.access.require(Capability::ControlPlayout)?;
+    Ok(mode_transition::cancel_pending(&app))
+}
+
 #[tauri::command]
 pub async fn get_autodj_transition_config(
     state: State<'_, AppState>,
-) -> Result<AutoTransitionConfig, String> {
+) -> Result<AutoTransitionConfig, AppError> {
     if let Some(pool) = &state.local_db {
         if let Ok(Some(json)) = crate::db::local::load_autodj_transition_config(pool).await {
             let cfg = parse_autodj_transition_config_json(&json);
@@ -60,28 +103,42 @@ pub async fn get_autodj_transition_config(
 pub async fn set_autodj_transition_config(
     config: AutoTransitionConfig,
     state: State<'_, AppState>,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
+    state.access.require(Capability::EditSchedule)?;
+    if config.deck_rotation() != config.deck_rotation {
+        return Err(AppError::invalid_input(
+            "Deck rotation needs at least two distinct playback decks",
+        ));
+    }
     autodj::set_auto_transition_config(config.clone());
     if let Some(pool) = &state.local_db {
         let json = serde_json::to_string(&config).map_err(|e| format!("Serialize error: {e}"))?;
         crate::db::local::save_autodj_transition_config(pool, &json)
             .await
-            .map_err(|e| format!("DB error: {e}"))?;
+            .map_err(AppError::db)?;
     }
     Ok(())
 }
 
 #[tauri::command]
-pub async fn recalculate_autodj_plan_now() -> Result<(), String> {
+pub async fn recalculate_autodj_plan_now(state: State<'_, AppState>) -> Result<(), AppError> {
+    state.access.require(Capability::ControlPlayout)?;
     autodj::request_replan();
     Ok(())
 }
 
 #[tauri::command]
-pub async fn get_last_transition_decision() -> Result<TransitionDecisionDebug, String> {
+pub async fn get_last_transition_decision() -> Result<TransitionDecisionDebug, AppError> {
     Ok(autodj::get_last_transition_decision())
 }
 
+/// Trace of the last rotation pick. Recorded only while the clockwheel's
+/// `verbose_logging` is on.
+#[tauri::command]
+pub async fn get_last_rotation_decision() -> Result<Option<rotation::RotationDecision>, AppError> {
+    Ok(rotation::get_last_rotation_decision())
+}
+
 #[derive(Debug, serde::Deserialize)]
 struct LegacyAutoTransitionConfig {
     enabled: Option<bool>,
@@ -117,6 +174,7 @@ pub(crate) fn parse_autodj_transition_config_json(json: &str) -> AutoTransitionC
             transition_time_sec: legacy.transition_time_sec.unwrap_or(10),
             min_track_duration_ms: legacy.min_track_duration_ms.unwrap_or(200),
         },
+        ..AutoTransitionConfig::default()
     }
 }
 
@@ -125,121 +183,724 @@ pub(crate) fn parse_autodj_transition_config_json(json: &str) -> AutoTransitionC
 #[tauri::command]
 pub async fn get_rotation_rules(
     state: State<'_, AppState>,
-) -> Result<Vec<RotationRuleRow>, String> {
-    let pool = state.local_db.as_ref().ok_or("Local DB not initialised")?;
+) -> Result<Vec<RotationRuleRow>, AppError> {
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
     rotation::get_rotation_rules(pool)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(AppError::from)
 }
 
 #[tauri::command]
 pub async fn save_rotation_rule(
     state: State<'_, AppState>,
     rule: RotationRuleRow,
-) -> Result<i64, String> {
-    let pool = state.local_db.as_ref().ok_or("Local DB not initialised")?;
-    rotation::upsert_rotation_rule(pool, &rule)
+) -> Result<i64, AppError> {
+    let actor = state.access.require(Capability::EditRotation)?;
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    let before = find_rotation_rule(pool, rule.id).await?;
+    let id = rotation::upsert_rotation_rule(pool, &rule)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(AppError::from)?;
+    let after = serde_json::to_value(&rule).unwrap_or_default();
+    access::audit(
+        &state,
+        &actor,
+        if before.is_null() {
+            "rotation_rule.create"
+        } else {
+            "rotation_rule.update"
+        },
+        Some(format!("rotation_rule:{id}")),
+        serde_json::json!({ "name": rule.name, "changes": access::changed_fields(&before, &after) }),
+    )
+    .await;
+    Ok(id)
 }
 
 #[tauri::command]
-pub async fn delete_rotation_rule(state: State<'_, AppState>, id: i64) -> Result<(), String> {
-    let pool = state.local_db.as_ref().ok_or("Local DB not initialised")?;
+pub async fn delete_rotation_rule(state: State<'_, AppState>, id: i64) -> Result<(), AppError> {
+    let actor = state.access.require(Capability::EditRotation)?;
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    let before = find_rotation_rule(pool, Some(id)).await?;
     rotation::delete_rotation_rule(pool, id)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(AppError::from)?;
+    access::audit(
+        &state,
+        &actor,
+        "rotation_rule.delete",
+        Some(format!("rotation_rule:{id}")),
+        serde_json::json!({ "name": before.get("name") }),
+    )
+    .await;
+    Ok(())
+}
+
+/// The stored rule as JSON for audit diffs; `Null` when new or missing.
+async fn find_rotation_rule(
+    pool: &sqlx::SqlitePool,
+    id: Option<i64>,
+) -> Result<serde_json::Value, AppError> {
+    let Some(id) = id else {
+        return Ok(serde_json::Value::Null);
+    };
+    let rules = rotation::get_rotation_rules(pool)
+        .await
+        .map_err(AppError::from)?;
+    Ok(rules
+        .iter()
+        .find(|r| r.id == Some(id))
+        .and_then(|r| serde_json::to_value(r).ok())
+        .unwrap_or_default())
 }
 
 // ── Playlists ─────────────────────────────────────────────────────────────────
 
 #[tauri::command]
-pub async fn get_playlists(state: State<'_, AppState>) -> Result<Vec<Playlist>, String> {
-    let pool = state.local_db.as_ref().ok_or("Local DB not initialised")?;
-    rotation::get_playlists(pool)
-        .await
-        .map_err(|e| e.to_string())
+pub async fn get_playlists(state: State<'_, AppState>) -> Result<Vec<Playlist>, AppError> {
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    rotation::get_playlists(pool).await.map_err(AppError::from)
 }
 
 #[tauri::command]
-pub async fn save_playlist(state: State<'_, AppState>, playlist: Playlist) -> Result<i64, String> {
-    let pool = state.local_db.as_ref().ok_or("Local DB not initialised")?;
+pub async fn save_playlist(
+    state: State<'_, AppState>,
+    playlist: Playlist,
+) -> Result<i64, AppError> {
+    state.access.require(Capability::EditSchedule)?;
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
     rotation::upsert_playlist(pool, &playlist)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(AppError::from)
 }
 
 #[tauri::command]
 pub async fn set_active_playlist(
     state: State<'_, AppState>,
     playlist_id: i64,
-) -> Result<(), String> {
-    let pool = state.local_db.as_ref().ok_or("Local DB not initialised")?;
+) -> Result<(), AppError> {
+    state.access.require(Capability::EditSchedule)?;
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
     rotation::set_active_playlist(pool, playlist_id)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn get_playlist_songs(
+    state: State<'_, AppState>,
+    playlist_id: i64,
+) -> Result<Vec<PlaylistSong>, AppError> {
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    rotation::get_playlist_songs(pool, playlist_id)
+        .await
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn set_playlist_songs(
+    state: State<'_, AppState>,
+    playlist_id: i64,
+    song_ids: Vec<i64>,
+) -> Result<(), AppError> {
+    state.access.require(Capability::EditSchedule)?;
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    rotation::set_playlist_songs(pool, playlist_id, &song_ids)
+        .await
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn get_playlist_cursor(
+    state: State<'_, AppState>,
+    playlist_id: i64,
+) -> Result<PlaylistCursor, AppError> {
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    rotation::get_playlist_cursor(pool, playlist_id)
+        .await
+        .map_err(AppError::from)
+}
+
+/// Move the ordered-playback cursor (0 restarts the playlist from the top).
+#[tauri::command]
+pub async fn set_playlist_cursor(
+    state: State<'_, AppState>,
+    playlist_id: i64,
+    position: i64,
+) -> Result<(), AppError> {
+    state.access.require(Capability::EditSchedule)?;
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    rotation::save_playlist_cursor(pool, playlist_id, position, None)
+        .await
+        .map_err(AppError::from)
+}
+
+// ── Playlist files ────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct PlaylistImportReport {
+    pub format: PlaylistFormat,
+    pub entries: usize,
+    pub matched: usize,
+    /// Paths with no matching SAM song
+    pub unmatched: Vec<String>,
+    /// Rotation playlist that received the songs
+    pub playlist_id: Option<i64>,
+    /// Queue rows created when importing into the queue
+    pub queue_ids: Vec<i64>,
+}
+
+/// Parse `path` and match its entries to SAM song ids, in file order.
+async fn read_playlist_file(
+    path: &str,
+    state: &AppState,
+) -> Result<(PlaylistFormat, Vec<PlaylistEntry>, Vec<Option<i64>>), AppError> {
+    let bytes = tokio::fs::read(path).await?;
+    let text = String::from_utf8_lossy(&bytes);
+    let format = PlaylistFormat::detect(path, &text);
+    let entries = playlist_io::parse_playlist(&text, format, std::path::Path::new(path).parent());
+
+    let translator = match &state.local_db {
+        Some(local) => crate::db::path_rules::load_translator(local).await,
+        None => Default::default(),
+    };
+    let guard = state.sam_db.read().await;
+    let sam_pool = guard.as_ref().ok_or_else(AppError::sam_db_unavailable)?;
+    let songs = crate::db::sam::get_all_songs(sam_pool)
+        .await
+        .map_err(AppError::db)?;
+    let index = SongPathIndex::new(&songs, &translator);
+    let ids = entries.iter().map(|e| index.find(&e.path)).collect();
+    Ok((format, entries, ids))
+}
+
+fn import_report(
+    format: PlaylistFormat,
+    entries: &[PlaylistEntry],
+    ids: &[Option<i64>],
+) -> PlaylistImportReport {
+    PlaylistImportReport {
+        format,
+        entries: entries.len(),
+        matched: ids.iter().flatten().count(),
+        unmatched: entries
+            .iter()
+            .zip(ids)
+            .filter(|(_, id)| id.is_none())
+            .map(|(e, _)| e.path.clone())
+            .collect(),
+        playlist_id: None,
+        queue_ids: Vec::new(),
+    }
+}
+
+/// Import an M3U/M3U8, PLS or CSV playlist into a rotation playlist.
+/// `playlist_id` replaces that playlist's songs; otherwise a new playlist is
+/// created, named `name` or after the file.
+#[tauri::command]
+pub async fn import_playlist_file(
+    state: State<'_, AppState>,
+    path: String,
+    playlist_id: Option<i64>,
+    name: Option<String>,
+) -> Result<PlaylistImportReport, AppError> {
+    let actor = state.access.require(Capability::EditRotation)?;
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    let (format, entries, ids) = read_playlist_file(&path, &state).await?;
+    let song_ids: Vec<i64> = ids.iter().flatten().copied().collect();
+    if song_ids.is_empty() {
+        return Err(AppError::invalid_input(format!(
+            "None of the {} entries in {path} match a song in the library",
+            entries.len()
+        )));
+    }
+
+    let playlist_id = match playlist_id {
+        Some(id) => id,
+        None => {
+            let name = name
+                .map(|n| n.trim().to_string())
+                .filter(|n| !n.is_empty())
+                .or_else(|| {
+                    std::path::Path::new(&path)
+                        .file_stem()
+                        .map(|s| s.to_string_lossy().to_string())
+                })
+                .unwrap_or_else(|| "Imported playlist".to_string());
+            let playlist = Playlist {
+                id: None,
+                name,
+                description: Some(format!("Imported from {path}")),
+                is_active: false,
+                config_json: "{}".to_string(),
+            };
+            rotation::upsert_playlist(pool, &playlist).await?
+        }
+    };
+    rotation::set_playlist_songs(pool, playlist_id, &song_ids).await?;
+
+    let mut report = import_report(format, &entries, &ids);
+    report.playlist_id = Some(playlist_id);
+    access::audit(
+        &state,
+        &actor,
+        "playlist.import",
+        Some(format!("playlist:{playlist_id}")),
+        serde_json::json!({
+            "file": path,
+            "matched": report.matched,
+            "unmatched": report.unmatched.len(),
+        }),
+    )
+    .await;
+    Ok(report)
+}
+
+/// Append the songs of a playlist file to the SAM queue, in file order.
+#[tauri::command]
+pub async fn queue_playlist_file(
+    state: State<'_, AppState>,
+    path: String,
+) -> Result<PlaylistImportReport, AppError> {
+    let actor = state.access.require(Capability::EditQueue)?;
+    let (format, entries, ids) = read_playlist_file(&path, &state).await?;
+    let mut report = import_report(format, &entries, &ids);
+
+    let guard = state.sam_db.read().await;
+    let sam_pool = guard.as_ref().ok_or_else(AppError::sam_db_unavailable)?;
+    for song_id in ids.into_iter().flatten() {
+        let queue_id = crate::db::sam::add_to_queue(sam_pool, song_id)
+            .await
+            .map_err(AppError::db)?;
+        report.queue_ids.push(queue_id);
+    }
+    access::audit(
+        &state,
+        &actor,
+        "queue.import",
+        Some(path),
+        serde_json::json!({
+            "queued": report.queue_ids.len(),
+            "unmatched": report.unmatched.len(),
+        }),
+    )
+    .await;
+    Ok(report)
+}
+
+/// Write `song_ids` as an extended M3U with paths translated for this machine.
+async fn write_songs_m3u(
+    state: &AppState,
+    song_ids: &[i64],
+    path: &str,
+) -> Result<usize, AppError> {
+    let songs = {
+        let guard = state.sam_db.read().await;
+        let sam_pool = guard.as_ref().ok_or_else(AppError::sam_db_unavailable)?;
+        crate::db::sam::get_songs_by_ids(sam_pool, song_ids)
+            .await
+            .map_err(AppError::db)?
+    };
+    let by_id: std::collections::HashMap<i64, _> = songs.into_iter().map(|s| (s.id, s)).collect();
+
+    let mut entries = Vec::with_capacity(song_ids.len());
+    for song in song_ids.iter().filter_map(|id| by_id.get(id)) {
+        let path = match &state.local_db {
+            Some(local) => crate::translate_sam_file_path(local, song.filename.clone()).await,
+            None => song.filename.clone(),
+        };
+        entries.push(PlaylistEntry {
+            path,
+            artist: (!song.artist.is_empty()).then(|| song.artist.clone()),
+            title: (!song.title.is_empty()).then(|| song.title.clone()),
+            duration_secs: (song.duration > 0).then_some(song.duration as i64),
+        });
+    }
+    tokio::fs::write(path, playlist_io::write_m3u(&entries)).await?;
+    Ok(entries.len())
+}
+
+/// Export a rotation playlist to M3U. Returns the number of entries written.
+#[tauri::command]
+pub async fn export_playlist_m3u(
+    state: State<'_, AppState>,
+    playlist_id: i64,
+    path: String,
+) -> Result<usize, AppError> {
+    state.access.require(Capability::EditSchedule)?;
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    let song_ids: Vec<i64> = rotation::get_playlist_songs(pool, playlist_id)
+        .await?
+        .into_iter()
+        .map(|s| s.song_id)
+        .collect();
+    write_songs_m3u(&state, &song_ids, &path).await
+}
+
+/// Export the pending SAM queue to M3U. Returns the number of entries written.
+#[tauri::command]
+pub async fn export_queue_m3u(state: State<'_, AppState>, path: String) -> Result<usize, AppError> {
+    state.access.require(Capability::EditQueue)?;
+    let song_ids: Vec<i64> = {
+        let guard = state.sam_db.read().await;
+        let sam_pool = guard.as_ref().ok_or_else(AppError::sam_db_unavailable)?;
+        crate::db::sam::get_queue(sam_pool)
+            .await
+            .map_err(AppError::db)?
+            .into_iter()
+            .map(|e| e.song_id)
+            .collect()
+    };
+    write_songs_m3u(&state, &song_ids, &path).await
 }
 
 #[tauri::command]
 pub async fn get_next_autodj_track(
     state: State<'_, AppState>,
-) -> Result<Option<rotation::SongCandidate>, String> {
-    let local_pool = state.local_db.as_ref().ok_or("Local DB not initialised")?;
+) -> Result<Option<rotation::SongCandidate>, AppError> {
+    let local_pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    let sam_guard = state.sam_db.read().await;
+    let sam_pool = sam_guard
+        .as_ref()
+        .ok_or_else(AppError::sam_db_unavailable)?;
+    rotation::select_next_track(local_pool, sam_pool, None, &mut rotation::selection_rng())
+        .await
+        .map_err(AppError::from)
+}
+
+/// Project the next `hours` of AutoDJ picks without queueing anything.
+#[tauri::command]
+pub async fn simulate_rotation(
+    state: State<'_, AppState>,
+    hours: u32,
+    start_at: Option<i64>,
+    seed: Option<u64>,
+) -> Result<rotation::RotationSimulation, AppError> {
+    if hours == 0 || hours > rotation::SIMULATION_MAX_HOURS {
+        return Err(AppError::invalid_input(format!(
+            "hours must be between 1 and {}",
+            rotation::SIMULATION_MAX_HOURS
+        )));
+    }
+    let start = match start_at {
+        Some(ms) => chrono::DateTime::from_timestamp_millis(ms)
+            .ok_or_else(|| AppError::invalid_input("start_at is out of range"))?,
+        None => chrono::Utc::now(),
+    };
+    let local_pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
     let sam_guard = state.sam_db.read().await;
-    let sam_pool = sam_guard.as_ref().ok_or("SAM DB not connected")?;
-    rotation::select_next_track(local_pool, sam_pool, None)
+    let sam_pool = sam_guard
+        .as_ref()
+        .ok_or_else(AppError::sam_db_unavailable)?;
+    rotation::simulate_rotation(local_pool, sam_pool, start, hours, seed)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(AppError::from)
+}
+
+/// Rotation picks planned after the SAM queue (empty unless `use_ghost_queue`).
+#[tauri::command]
+pub async fn get_ghost_queue() -> Result<Vec<rotation::GhostQueueEntry>, AppError> {
+    Ok(rotation::get_ghost_queue())
 }
 
+/// Take a planned song out of the ghost queue; the slot is re-planned.
 #[tauri::command]
-pub async fn get_clockwheel_config(state: State<'_, AppState>) -> Result<ClockwheelConfig, String> {
-    let pool = state.local_db.as_ref().ok_or("Local DB not initialised")?;
+pub async fn veto_ghost_queue_entry(
+    state: State<'_, AppState>,
+    song_id: i64,
+) -> Result<(), AppError> {
+    let actor = state.access.require(Capability::EditRotation)?;
+    if !rotation::veto_ghost_entry(song_id) {
+        return Err(AppError::not_found(format!("Ghost queue song {song_id}")));
+    }
+    access::audit(
+        &state,
+        &actor,
+        "ghost_queue.veto",
+        Some(format!("song:{song_id}")),
+        serde_json::json!({}),
+    )
+    .await;
+    Ok(())
+}
+
+// ── Upcoming plan ─────────────────────────────────────────────────────────────
+
+#[tauri::command]
+pub async fn get_autodj_plan(state: State<'_, AppState>) -> Result<Vec<PlanItem>, AppError> {
+    let guard = state.sam_db.read().await;
+    let pool = guard.as_ref().ok_or_else(AppError::sam_db_unavailable)?;
+    autodj_plan::get_plan(pool).await
+}
+
+#[tauri::command]
+pub async fn set_autodj_plan_pin(
+    state: State<'_, AppState>,
+    item: PlanItemRef,
+    pinned: bool,
+) -> Result<(), AppError> {
+    let actor = state.access.require(Capability::EditQueue)?;
+    let guard = state.sam_db.read().await;
+    let pool = guard.as_ref().ok_or_else(AppError::sam_db_unavailable)?;
+    autodj_plan::set_pinned(pool, item, pinned).await?;
+    access::audit(
+        &state,
+        &actor,
+        "plan.pin",
+        None,
+        serde_json::json!({ "item": item, "pinned": pinned }),
+    )
+    .await;
+    Ok(())
+}
+
+/// `order` lists every unclaimed plan item; pinned items must stay put.
+#[tauri::command]
+pub async fn reorder_autodj_plan(
+    state: State<'_, AppState>,
+    order: Vec<PlanItemRef>,
+) -> Result<(), AppError> {
+    let actor = state.access.require(Capability::EditQueue)?;
+    let guard = state.sam_db.read().await;
+    let pool = guard.as_ref().ok_or_else(AppError::sam_db_unavailable)?;
+    autodj_plan::reorder(pool, &order).await?;
+    access::audit(
+        &state,
+        &actor,
+        "plan.reorder",
+        None,
+        serde_json::json!({ "order": order }),
+    )
+    .await;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn replace_autodj_plan_item(
+    state: State<'_, AppState>,
+    item: PlanItemRef,
+    song_id: i64,
+) -> Result<(), AppError> {
+    let actor = state.access.require(Capability::EditQueue)?;
+    let guard = state.sam_db.read().await;
+    let pool = guard.as_ref().ok_or_else(AppError::sam_db_unavailable)?;
+    autodj_plan::replace(pool, item, song_id).await?;
+    access::audit(
+        &state,
+        &actor,
+        "plan.replace",
+        Some(format!("song:{song_id}")),
+        serde_json::json!({ "item": item }),
+    )
+    .await;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_bad_tracks(state: State<'_, AppState>) -> Result<Vec<BadTrack>, AppError> {
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    track_preflight::get_bad_tracks(pool)
+        .await
+        .map_err(AppError::from)
+}
+
+/// Lift a song's quarantine so AutoDJ may pick it again.
+#[tauri::command]
+pub async fn release_bad_track(state: State<'_, AppState>, song_id: i64) -> Result<(), AppError> {
+    let actor = state.access.require(Capability::EditRotation)?;
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    if !track_preflight::release(pool, song_id).await? {
+        return Err(AppError::not_found(format!("Bad track {song_id}")));
+    }
+    access::audit(
+        &state,
+        &actor,
+        "library.release_bad_track",
+        Some(format!("song:{song_id}")),
+        serde_json::json!({}),
+    )
+    .await;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_clockwheel_config(
+    state: State<'_, AppState>,
+) -> Result<ClockwheelConfig, AppError> {
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
     rotation::get_clockwheel_config(pool)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(AppError::from)
 }
 
 #[tauri::command]
 pub async fn save_clockwheel_config(
     state: State<'_, AppState>,
     config: ClockwheelConfig,
-) -> Result<(), String> {
-    let pool = state.local_db.as_ref().ok_or("Local DB not initialised")?;
+) -> Result<(), AppError> {
+    state.access.require(Capability::EditSchedule)?;
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
     rotation::save_clockwheel_config(pool, &config)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn get_clockwheel_templates(
+    state: State<'_, AppState>,
+) -> Result<Vec<ClockwheelTemplate>, AppError> {
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    rotation::get_clockwheel_templates(pool)
+        .await
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn save_clockwheel_template(
+    state: State<'_, AppState>,
+    template: ClockwheelTemplate,
+) -> Result<i64, AppError> {
+    state.access.require(Capability::EditSchedule)?;
+    if template.name.trim().is_empty() {
+        return Err(AppError::invalid_input("Template name is required"));
+    }
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    rotation::upsert_clockwheel_template(pool, &template)
+        .await
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn get_clockwheel_hour_grid(
+    state: State<'_, AppState>,
+) -> Result<Vec<ClockwheelHourAssignment>, AppError> {
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    rotation::get_clockwheel_hour_grid(pool)
+        .await
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn assign_clockwheel_hour(
+    state: State<'_, AppState>,
+    day: u8,
+    hour: u8,
+    template_id: Option<i64>,
+) -> Result<(), AppError> {
+    state.access.require(Capability::EditSchedule)?;
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    rotation::assign_clockwheel_hour(
+        pool,
+        ClockwheelHourAssignment {
+            day,
+            hour,
+            template_id,
+        },
+    )
+    .await
+    .map_err(AppError::from)
 }
 
 #[tauri::command]
 pub async fn get_song_directories(
     state: State<'_, AppState>,
     limit: Option<u32>,
-) -> Result<Vec<String>, String> {
+) -> Result<Vec<String>, AppError> {
     let sam_guard = state.sam_db.read().await;
-    let sam_pool = sam_guard.as_ref().ok_or("SAM DB not connected")?;
+    let sam_pool = sam_guard
+        .as_ref()
+        .ok_or_else(AppError::sam_db_unavailable)?;
     rotation::get_song_directories(sam_pool, limit.unwrap_or(3000))
         .await
-        .map_err(|e| e.to_string())
+        .map_err(AppError::from)
 }
 
 #[tauri::command]
 pub async fn enqueue_next_clockwheel_track(
     state: State<'_, AppState>,
     slot_id: Option<String>,
-) -> Result<Option<EnqueuedClockwheelTrack>, String> {
-    let local_pool = state.local_db.as_ref().ok_or("Local DB not initialised")?;
+) -> Result<Option<EnqueuedClockwheelTrack>, AppError> {
+    state.access.require(Capability::EditQueue)?;
+    let local_pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
     let sam_guard = state.sam_db.read().await;
-    let sam_pool = sam_guard.as_ref().ok_or("SAM DB not connected")?;
+    let sam_pool = sam_guard
+        .as_ref()
+        .ok_or_else(AppError::sam_db_unavailable)?;
 
+    let mut rng = rotation::selection_rng();
     let candidate = if let Some(slot_id) = slot_id.as_deref() {
-        rotation::select_next_track_for_slot(local_pool, sam_pool, slot_id)
-            .await
-            .map_err(|e| e.to_string())?
+        rotation::select_next_track_for_slot(local_pool, sam_pool, slot_id, &mut rng).await?
     } else {
-        rotation::select_next_track(local_pool, sam_pool, None)
-            .await
-            .map_err(|e| e.to_string())?
+        rotation::select_next_track(local_pool, sam_pool, None, &mut rng).await?
     };
 
     let Some(song) = candidate else {
@@ -256,50 +917,310 @@ pub async fn enqueue_next_clockwheel_track(
 // ── Show Scheduler ────────────────────────────────────────────────────────────
 
 #[tauri::command]
-pub async fn get_shows(state: State<'_, AppState>) -> Result<Vec<Show>, String> {
-    let pool = state.local_db.as_ref().ok_or("Local DB not initialised")?;
+pub async fn get_shows(state: State<'_, AppState>) -> Result<Vec<Show>, AppError> {
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
     show_scheduler::get_shows(pool)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(AppError::from)
 }
 
 #[tauri::command]
-pub async fn save_show(state: State<'_, AppState>, show: Show) -> Result<i64, String> {
-    let pool = state.local_db.as_ref().ok_or("Local DB not initialised")?;
-    show_scheduler::upsert_show(pool, &show)
-        .await
-        .map_err(|e| e.to_string())
+pub async fn save_show(state: State<'_, AppState>, show: Show) -> Result<i64, AppError> {
+    state.access.require(Capability::EditSchedule)?;
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    let id = show_scheduler::upsert_show(pool, &show).await?;
+    show_scheduler::request_reload();
+    Ok(id)
 }
 
 #[tauri::command]
-pub async fn delete_show(state: State<'_, AppState>, id: i64) -> Result<(), String> {
-    let pool = state.local_db.as_ref().ok_or("Local DB not initialised")?;
-    show_scheduler::delete_show(pool, id)
-        .await
-        .map_err(|e| e.to_string())
+pub async fn delete_show(state: State<'_, AppState>, id: i64) -> Result<(), AppError> {
+    state.access.require(Capability::EditSchedule)?;
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    show_scheduler::delete_show(pool, id).await?;
+    show_scheduler::request_reload();
+    Ok(())
+}
+
+/// Overrides of the show on air, if it sets any.
+#[tauri::command]
+pub async fn get_active_show_overrides(
+    state: State<'_, AppState>,
+) -> Result<Option<ActiveShowOverrides>, AppError> {
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    let shows = show_scheduler::get_shows(pool).await?;
+    Ok(show_scheduler::get_active_overrides(&shows))
 }
 
 #[tauri::command]
 pub async fn get_upcoming_events(
     state: State<'_, AppState>,
     hours: u32,
-) -> Result<Vec<ScheduledEvent>, String> {
-    let pool = state.local_db.as_ref().ok_or("Local DB not initialised")?;
+) -> Result<Vec<ScheduledEvent>, AppError> {
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
     show_scheduler::get_upcoming_events(pool, hours)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(AppError::from)
+}
+
+// ── Exact-time events ─────────────────────────────────────────────────────────
+
+#[tauri::command]
+pub async fn get_timed_events(state: State<'_, AppState>) -> Result<Vec<TimedEvent>, AppError> {
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    timed_events::get_timed_events(pool)
+        .await
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn save_timed_event(
+    state: State<'_, AppState>,
+    event: TimedEvent,
+) -> Result<i64, AppError> {
+    state.access.require(Capability::EditSchedule)?;
+    if event.minute > 59 {
+        return Err(AppError::invalid_input("Minute must be between 0 and 59"));
+    }
+    let has_element = event.song_id.is_some()
+        || event
+            .file_path
+            .as_deref()
+            .is_some_and(|p| !p.trim().is_empty());
+    if !has_element {
+        return Err(AppError::invalid_input(
+            "A timed event needs a song or file to play",
+        ));
+    }
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    let id = timed_events::upsert_timed_event(pool, &event).await?;
+    autodj::request_replan();
+    Ok(id)
+}
+
+#[tauri::command]
+pub async fn delete_timed_event(state: State<'_, AppState>, id: i64) -> Result<(), AppError> {
+    state.access.require(Capability::EditSchedule)?;
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    timed_events::delete_timed_event(pool, id).await?;
+    autodj::request_replan();
+    Ok(())
+}
+
+// ── Relays ────────────────────────────────────────────────────────────────────
+
+#[tauri::command]
+pub async fn get_relay_events(state: State<'_, AppState>) -> Result<Vec<RelayEvent>, AppError> {
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    relay::get_relay_events(pool).await.map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn save_relay_event(
+    state: State<'_, AppState>,
+    event: RelayEvent,
+) -> Result<i64, AppError> {
+    state.access.require(Capability::EditSchedule)?;
+    if event.minute > 59 {
+        return Err(AppError::invalid_input("Minute must be between 0 and 59"));
+    }
+    if !crate::audio::remote_stream::is_remote_url(&event.url) {
+        return Err(AppError::invalid_input(
+            "A relay needs an http:// or https:// stream URL",
+        ));
+    }
+    if !(1..=24 * 60).contains(&event.duration_minutes) {
+        return Err(AppError::invalid_input(
+            "Relay duration must be between 1 minute and 24 hours",
+        ));
+    }
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    let id = relay::upsert_relay_event(pool, &event).await?;
+    relay::request_reload();
+    Ok(id)
+}
+
+#[tauri::command]
+pub async fn delete_relay_event(state: State<'_, AppState>, id: i64) -> Result<(), AppError> {
+    state.access.require(Capability::EditSchedule)?;
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    relay::delete_relay_event(pool, id).await?;
+    relay::request_reload();
+    Ok(())
+}
+
+/// The relay window in progress, if any.
+#[tauri::command]
+pub async fn get_relay_status() -> Result<Option<RelayStatus>, AppError> {
+    Ok(relay::status())
+}
+
+/// End the current relay early and hand back to AutoDJ.
+#[tauri::command]
+pub async fn stop_relay(state: State<'_, AppState>) -> Result<(), AppError> {
+    state.access.require(Capability::ControlPlayout)?;
+    relay::request_stop();
+    Ok(())
+}
+
+// ── Traffic ───────────────────────────────────────────────────────────────────
+
+#[tauri::command]
+pub async fn get_ad_breaks(state: State<'_, AppState>) -> Result<Vec<AdBreak>, AppError> {
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    traffic::get_ad_breaks(pool).await.map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn save_ad_break(state: State<'_, AppState>, ad_break: AdBreak) -> Result<i64, AppError> {
+    state.access.require(Capability::EditSchedule)?;
+    if ad_break.minute > 59 {
+        return Err(AppError::invalid_input("Minute must be between 0 and 59"));
+    }
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    let id = traffic::upsert_ad_break(pool, &ad_break).await?;
+    autodj::request_replan();
+    Ok(id)
+}
+
+#[tauri::command]
+pub async fn delete_ad_break(state: State<'_, AppState>, id: i64) -> Result<(), AppError> {
+    state.access.require(Capability::EditSchedule)?;
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    traffic::delete_ad_break(pool, id).await?;
+    autodj::request_replan();
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_traffic_campaigns(state: State<'_, AppState>) -> Result<Vec<Campaign>, AppError> {
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    traffic::get_campaigns(pool).await.map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn save_traffic_campaign(
+    state: State<'_, AppState>,
+    campaign: Campaign,
+) -> Result<i64, AppError> {
+    state.access.require(Capability::EditSchedule)?;
+    let parse = |d: &str| chrono::NaiveDate::parse_from_str(d, "%Y-%m-%d");
+    let (Ok(start), Ok(end)) = (parse(&campaign.start_date), parse(&campaign.end_date)) else {
+        return Err(AppError::invalid_input("Campaign dates must be YYYY-MM-DD"));
+    };
+    if end < start {
+        return Err(AppError::invalid_input("Campaign ends before it starts"));
+    }
+    let has_element = campaign.song_id.is_some()
+        || campaign
+            .file_path
+            .as_deref()
+            .is_some_and(|p| !p.trim().is_empty());
+    if !has_element {
+        return Err(AppError::invalid_input(
+            "A campaign needs a song or file to play",
+        ));
+    }
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    traffic::upsert_campaign(pool, &campaign)
+        .await
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn delete_traffic_campaign(state: State<'_, AppState>, id: i64) -> Result<(), AppError> {
+    state.access.require(Capability::EditSchedule)?;
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    traffic::delete_campaign(pool, id)
+        .await
+        .map_err(AppError::from)
+}
+
+/// Spot log for the local dates `start_date..=end_date`.
+#[tauri::command]
+pub async fn get_traffic_spot_log(
+    state: State<'_, AppState>,
+    start_date: String,
+    end_date: String,
+    campaign_id: Option<i64>,
+) -> Result<Vec<SpotLogEntry>, AppError> {
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    let (start_utc, end_utc) =
+        crate::analytics::play_stats::local_date_range_utc(&chrono::Local, &start_date, &end_date)
+            .ok_or("Dates must be YYYY-MM-DD")?;
+    traffic::get_spot_log(pool, start_utc, end_utc, campaign_id)
+        .await
+        .map_err(AppError::from)
 }
 
 // ── GAP Killer ────────────────────────────────────────────────────────────────
 
 #[tauri::command]
-pub async fn get_gap_killer_config(state: State<'_, AppState>) -> Result<GapKillerConfig, String> {
-    let pool = state.local_db.as_ref().ok_or("Local DB not initialised")?;
+pub async fn get_gap_killer_config(
+    state: State<'_, AppState>,
+) -> Result<GapKillerConfig, AppError> {
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
     let row: Option<String> =
         sqlx::query_scalar("SELECT gap_killer_json FROM gap_killer_config WHERE id = 1")
             .fetch_optional(pool)
-            .await
-            .map_err(|e| e.to_string())?;
+            .await?;
 
     Ok(row
         .and_then(|j| serde_json::from_str(&j).ok())
@@ -310,57 +1231,284 @@ pub async fn get_gap_killer_config(state: State<'_, AppState>) -> Result<GapKill
 pub async fn set_gap_killer_config(
     state: State<'_, AppState>,
     config: GapKillerConfig,
-) -> Result<(), String> {
-    let pool = state.local_db.as_ref().ok_or("Local DB not initialised")?;
-    let json = serde_json::to_string(&config).map_err(|e| e.to_string())?;
-    sqlx::query(
-        "INSERT INTO gap_killer_config (id, gap_killer_json) VALUES (1, ?) \
-         ON CONFLICT(id) DO UPDATE SET gap_killer_json = excluded.gap_killer_json",
-    )
-    .bind(&json)
-    .execute(pool)
-    .await
-    .map_err(|e| e.to_string())?;
-    Ok(())
+) -> Result<(), AppError> {
+    state.access.require(Capability::EditSchedule)?;
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    let json = serde_json::to_string(&config)?;
+    crate::db::local::save_gap_killer_config(pool, &json)
+        .await
+        .map_err(AppError::db)
 }
 
 // ── Request Policy ────────────────────────────────────────────────────────────
 
 #[tauri::command]
-pub async fn get_request_policy(state: State<'_, AppState>) -> Result<RequestPolicy, String> {
-    let pool = state.local_db.as_ref().ok_or("Local DB not initialised")?;
+pub async fn get_request_policy(state: State<'_, AppState>) -> Result<RequestPolicy, AppError> {
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
     request_policy::load_policy(pool)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(AppError::from)
 }
 
 #[tauri::command]
 pub async fn set_request_policy(
     state: State<'_, AppState>,
     policy: RequestPolicy,
-) -> Result<(), String> {
-    let pool = state.local_db.as_ref().ok_or("Local DB not initialised")?;
+) -> Result<(), AppError> {
+    state.access.require(Capability::EditRotation)?;
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
     request_policy::save_policy(pool, &policy)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(AppError::from)
+}
+
+/// Library data the request policy needs for `song_ids` (empty if SAM is offline).
+pub(crate) async fn request_subjects(
+    state: &AppState,
+    song_ids: &[i64],
+) -> std::collections::HashMap<i64, RequestSubject> {
+    let sam_pool = { state.sam_db.read().await.as_ref().cloned() };
+    let Some(sam_pool) = sam_pool else {
+        return Default::default();
+    };
+    let songs = crate::db::sam::get_songs_by_ids(&sam_pool, song_ids)
+        .await
+        .unwrap_or_default();
+    let categories = crate::db::sam::get_song_category_names(&sam_pool, Some(song_ids))
+        .await
+        .unwrap_or_default();
+    songs
+        .iter()
+        .map(|song| {
+            let cats = categories.get(&song.id).cloned().into_iter().collect();
+            (song.id, RequestSubject::from_sam_song(song, cats))
+        })
+        .collect()
+}
+
+#[tauri::command]
+pub async fn submit_song_request(
+    state: State<'_, AppState>,
+    song_id: i64,
+    requester_name: Option<String>,
+    requester_platform: Option<String>,
+    requester_ip: Option<String>,
+) -> Result<(RequestLogEntry, RequestDecision), AppError> {
+    state.access.require(Capability::EditQueue)?;
+    submit_request_as(
+        &state,
+        song_id,
+        requester_name,
+        requester_platform,
+        requester_ip,
+    )
+    .await
+}
+
+/// Log a listener request and run it through the request policy. Shared by
+/// the `submit_song_request` command and the embedded request HTTP API.
+pub(crate) async fn submit_request_as(
+    state: &AppState,
+    song_id: i64,
+    requester_name: Option<String>,
+    requester_platform: Option<String>,
+    requester_ip: Option<String>,
+) -> Result<(RequestLogEntry, RequestDecision), AppError> {
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    let policy = request_policy::load_policy(pool).await?;
+    let subject = request_subjects(state, &[song_id])
+        .await
+        .remove(&song_id)
+        .ok_or_else(|| AppError::not_found(format!("Song {song_id} not found")))?;
+    let entry = RequestLogEntry {
+        id: None,
+        song_id,
+        song_title: None,
+        artist: Some(subject.artist.clone()).filter(|a| !a.is_empty()),
+        album: Some(subject.album.clone()).filter(|a| !a.is_empty()),
+        requester_name,
+        requester_platform,
+        requester_ip,
+        requested_at: 0,
+        status: RequestStatus::Pending,
+        rejection_reason: None,
+        played_at: None,
+    };
+    let (entry, decision) = request_policy::submit_request(pool, &policy, &subject, entry).await?;
+    if let (RequestStatus::Accepted, Some(request_id)) = (&entry.status, entry.id) {
+        queue_accepted_request(pool, &policy, request_id, song_id, 0).await;
+    }
+    if !matches!(entry.status, RequestStatus::Rejected) {
+        let sam_pool = { state.sam_db.read().await.as_ref().cloned() };
+        let song_title = match sam_pool {
+            Some(sam_pool) => crate::db::sam::get_song(&sam_pool, song_id)
+                .await
+                .ok()
+                .flatten()
+                .map(|s| s.title),
+            None => None,
+        };
+        state
+            .script_engine
+            .fire(crate::scripting::trigger::ScriptEvent::RequestReceived {
+                song_id,
+                song_title: song_title.unwrap_or_default(),
+                requester: entry.requester_name.clone().unwrap_or_default(),
+            });
+    }
+    Ok((entry, decision))
+}
+
+/// Queue an auto-accepted request into SAM at the policy's position (moved
+/// down `offset` places, keeping a batch in order), with the on-request
+/// weight bump. Both go through the SAM outbox, so they land once SAM is
+/// reachable; a write SAM keeps rejecting shows up with the outbox failures.
+async fn queue_accepted_request(
+    pool: &sqlx::SqlitePool,
+    policy: &RequestPolicy,
+    request_id: i64,
+    song_id: i64,
+    offset: usize,
+) {
+    use crate::db::sam_outbox::{self, SamWrite};
+
+    let mut writes = vec![SamWrite::QueueRequest {
+        song_id,
+        request_id,
+        position: policy.queue_position.index().saturating_add(offset),
+    }];
+    let delta = rotation::on_request_weight_delta(pool).await;
+    if delta.abs() >= f64::EPSILON {
+        writes.push(SamWrite::WeightDelta { song_id, delta });
+    }
+    if let Err(err) = sam_outbox::enqueue(pool, &writes).await {
+        log::warn!("Failed to queue accepted request {request_id} for song {song_id}: {err}");
+    }
+}
+
+// ── Request HTTP API ──────────────────────────────────────────────────────────
+
+#[tauri::command]
+pub async fn get_request_api_config(
+    state: State<'_, AppState>,
+) -> Result<RequestApiConfig, AppError> {
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    request_api::get_config(pool).await.map_err(AppError::from)
+}
+
+/// Save the request API config and start, restart or stop the server to match.
+#[tauri::command]
+pub async fn set_request_api_config(
+    app: tauri::AppHandle,
+    state: State<'_, AppState>,
+    config: RequestApiConfig,
+) -> Result<RequestApiStatus, AppError> {
+    state.access.require(Capability::ManageSettings)?;
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    if config.enabled && config.token.trim().is_empty() {
+        return Err(AppError::invalid_input(
+            "Set a token before enabling the request API",
+        ));
+    }
+    request_api::save_config(pool, &config).await?;
+    if config.enabled {
+        request_api::start(app, config)
+            .await
+            .map_err(AppError::from)
+    } else {
+        request_api::stop();
+        Ok(request_api::status())
+    }
+}
+
+#[tauri::command]
+pub async fn get_request_api_status() -> Result<RequestApiStatus, AppError> {
+    Ok(request_api::status())
+}
+
+/// Auto-reject (and, with auto-accept on, accept and queue) pending requests.
+#[tauri::command]
+pub async fn triage_pending_requests(
+    state: State<'_, AppState>,
+) -> Result<TriageSummary, AppError> {
+    state.access.require(Capability::EditQueue)?;
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    triage_pending(&state, pool).await.map_err(AppError::from)
+}
+
+async fn triage_pending(
+    state: &AppState,
+    pool: &sqlx::SqlitePool,
+) -> Result<TriageSummary, String> {
+    let policy = request_policy::load_policy(pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    let pending = request_policy::get_requests(pool, "pending")
+        .await
+        .map_err(|e| e.to_string())?;
+    if pending.is_empty() {
+        return Ok(TriageSummary::default());
+    }
+    let mut song_ids: Vec<i64> = pending.iter().map(|r| r.song_id).collect();
+    song_ids.sort_unstable();
+    song_ids.dedup();
+    let subjects = request_subjects(state, &song_ids).await;
+    let summary = request_policy::triage_pending_requests(pool, &policy, &subjects)
+        .await
+        .map_err(|e| e.to_string())?;
+    for (offset, &(request_id, song_id)) in summary.accepted_requests.iter().enumerate() {
+        queue_accepted_request(pool, &policy, request_id, song_id, offset).await;
+    }
+    Ok(summary)
 }
 
 #[tauri::command]
 pub async fn get_pending_requests(
     state: State<'_, AppState>,
-) -> Result<Vec<RequestLogEntry>, String> {
-    let pool = state.local_db.as_ref().ok_or("Local DB not initialised")?;
+) -> Result<Vec<RequestLogEntry>, AppError> {
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    if let Err(err) = triage_pending(&state, pool).await {
+        log::warn!("Request triage failed: {}", err);
+    }
     request_policy::get_requests(pool, "pending")
         .await
-        .map_err(|e| e.to_string())
+        .map_err(AppError::from)
 }
 
 #[tauri::command]
-pub async fn accept_request_p3(state: State<'_, AppState>, id: i64) -> Result<(), String> {
-    let pool = state.local_db.as_ref().ok_or("Local DB not initialised")?;
+pub async fn accept_request_p3(state: State<'_, AppState>, id: i64) -> Result<(), AppError> {
+    state.access.require(Capability::EditQueue)?;
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
     request_policy::update_request_status(pool, id, RequestStatus::Accepted, None)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(AppError::from)
 }
 
 #[tauri::command]
@@ -368,11 +1516,15 @@ pub async fn reject_request_p3(
     state: State<'_, AppState>,
     id: i64,
     reason: Option<String>,
-) -> Result<(), String> {
-    let pool = state.local_db.as_ref().ok_or("Local DB not initialised")?;
+) -> Result<(), AppError> {
+    state.access.require(Capability::EditQueue)?;
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
     request_policy::update_request_status(pool, id, RequestStatus::Rejected, reason.as_deref())
         .await
-        .map_err(|e| e.to_string())
+        .map_err(AppError::from)
 }
 
 #[tauri::command]
@@ -380,11 +1532,14 @@ pub async fn get_request_history(
     state: State<'_, AppState>,
     limit: i64,
     offset: i64,
-) -> Result<Vec<RequestLogEntry>, String> {
-    let pool = state.local_db.as_ref().ok_or("Local DB not initialised")?;
+) -> Result<Vec<RequestLogEntry>, AppError> {
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
     request_policy::get_request_history(pool, limit, offset)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(AppError::from)
 }
 
 use sqlx;
@@ -1,41 +1,64 @@
 /// `commands/script_commands.rs` — Phase 5 Tauri commands for scripting
 use tauri::State;
 
+use crate::access::Capability;
+use crate::db::scripts::{self, ScriptRun};
+use crate::error::AppError;
 use crate::{
-    scripting::engine::{Script, ScriptEngine, ScriptRunResult},
+    scripting::{
+        api::{ApiEntry, API_REFERENCE},
+        engine::{Script, ScriptRunResult},
+    },
     state::AppState,
 };
 
 /// Return all scripts (enabled + disabled).
 #[tauri::command]
-pub async fn get_scripts(state: State<'_, AppState>) -> Result<Vec<Script>, String> {
+pub async fn get_scripts(state: State<'_, AppState>) -> Result<Vec<Script>, AppError> {
     let mut scripts = state.script_engine.get_scripts();
     scripts.sort_by_key(|s| s.id);
     Ok(scripts)
 }
 
 /// Create or update a script. Returns the script id.
+///
+/// Granting `engine_control` lets the script start and stop encoders, so it
+/// takes an operator allowed to do that.
 #[tauri::command]
-pub async fn save_script(state: State<'_, AppState>, script: Script) -> Result<i64, String> {
-    let is_new = script.id == 0;
+pub async fn save_script(state: State<'_, AppState>, mut script: Script) -> Result<i64, AppError> {
+    for trigger in &script.triggers {
+        trigger.validate().map_err(AppError::invalid_input)?;
+    }
+    let had_engine_control = state
+        .script_engine
+        .get_script(script.id)
+        .is_some_and(|s| s.permissions.engine_control);
+    if script.permissions.engine_control && !had_engine_control {
+        state.access.require(Capability::ControlEncoders)?;
+    }
+    script.limits = script.limits.clamped();
     let id = state.script_engine.save_script(script);
-    // Start event listener loop for new scripts
-    if is_new {
-        state.script_engine.start_event_loop(id);
+    if let (Some(pool), Some(saved)) = (&state.local_db, state.script_engine.get_script(id)) {
+        scripts::save_script(pool, &saved).await?;
     }
     Ok(id)
 }
 
 /// Delete a script by id.
 #[tauri::command]
-pub async fn delete_script(state: State<'_, AppState>, id: i64) -> Result<(), String> {
+pub async fn delete_script(state: State<'_, AppState>, id: i64) -> Result<(), AppError> {
+    state.access.require(Capability::ManageSettings)?;
     state.script_engine.delete_script(id);
+    if let Some(pool) = &state.local_db {
+        scripts::delete_script(pool, id).await?;
+    }
     Ok(())
 }
 
 /// Run a script immediately (manual trigger).
 #[tauri::command]
-pub async fn run_script(state: State<'_, AppState>, id: i64) -> Result<ScriptRunResult, String> {
+pub async fn run_script(state: State<'_, AppState>, id: i64) -> Result<ScriptRunResult, AppError> {
+    state.access.require(Capability::ManageSettings)?;
     Ok(state.script_engine.run_script(id).await)
 }
 
@@ -45,7 +68,7 @@ pub async fn get_script_log(
     state: State<'_, AppState>,
     id: i64,
     limit: Option<usize>,
-) -> Result<Vec<serde_json::Value>, String> {
+) -> Result<Vec<serde_json::Value>, AppError> {
     let entries = state.script_engine.get_log(id, limit.unwrap_or(50));
     let json = entries
         .into_iter()
@@ -59,3 +82,25 @@ pub async fn get_script_log(
         .collect();
     Ok(json)
 }
+
+/// Run history for a script, newest first.
+#[tauri::command]
+pub async fn get_script_runs(
+    state: State<'_, AppState>,
+    id: i64,
+    limit: Option<i64>,
+) -> Result<Vec<ScriptRun>, AppError> {
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    scripts::get_runs(pool, id, limit.unwrap_or(50).clamp(1, 200))
+        .await
+        .map_err(AppError::from)
+}
+
+/// The documented Lua API, for the editor's reference panel.
+#[tauri::command]
+pub async fn get_script_api() -> Result<Vec<ApiEntry>, AppError> {
+    Ok(API_REFERENCE.to_vec())
+}
@@ -0,0 +1,32 @@
+use tauri::{AppHandle, State};
+
+use crate::access::Capability;
+use crate::error::AppError;
+use crate::recovery::{self, ResumeReport, SessionSnapshot};
+use crate::state::AppState;
+
+/// Snapshot left by a run that did not exit cleanly, if one is pending.
+#[tauri::command]
+pub async fn get_previous_session() -> Result<Option<SessionSnapshot>, AppError> {
+    Ok(recovery::previous())
+}
+
+/// Put the previous session back on air. Live encoders go through the
+/// station ID gate; `override_gate` works as for `start_encoder`.
+#[tauri::command]
+pub async fn resume_previous_session(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    override_gate: Option<bool>,
+) -> Result<ResumeReport, AppError> {
+    state.access.require(Capability::ControlPlayout)?;
+    recovery::resume_previous(&app, override_gate.unwrap_or(false))
+        .await
+        .map_err(AppError::from)
+}
+
+#[tauri::command]
+pub async fn discard_previous_session(state: State<'_, AppState>) -> Result<bool, AppError> {
+    state.access.require(Capability::ControlPlayout)?;
+    Ok(recovery::discard_previous())
+}
@@ -0,0 +1,306 @@
+use std::path::PathBuf;
+
+use serde_json::json;
+use tauri::State;
+
+use crate::access::{self, Capability};
+use crate::analytics::emit_metrics::{self, EmitRateConfig};
+use crate::commands::crossfade_commands::normalize_crossfade_config;
+use crate::db::{
+    backup::{self, BackupConfig, BackupInfo},
+    local,
+    migrations::{self, SchemaInfo},
+    sam,
+    sam_import::{self, SamImportPlan, SamImportResult, SamImportSelection},
+};
+use crate::error::AppError;
+use crate::scheduler::rotation;
+use crate::settings_archive::{self, ArchiveSummary, ImportReport, Section};
+use crate::state::AppState;
+
+/// Write a settings archive to `path`. `sections` = `None` exports everything.
+#[tauri::command]
+pub async fn export_settings(
+    path: String,
+    sections: Option<Vec<Section>>,
+    state: State<'_, AppState>,
+) -> Result<ArchiveSummary, AppError> {
+    let actor = state.access.require(Capability::ManageSettings)?;
+    let sections = sections.unwrap_or_else(|| Section::ALL.to_vec());
+    let archive = settings_archive::export(&state, &sections).await?;
+    settings_archive::write_archive(&PathBuf::from(&path), &archive)?;
+    access::audit(
+        &state,
+        &actor,
+        "settings.export",
+        Some(path),
+        json!({ "sections": sections }),
+    )
+    .await;
+    Ok(archive.summary())
+}
+
+/// List what an archive contains, for choosing sections to restore.
+#[tauri::command]
+pub async fn inspect_settings_archive(path: String) -> Result<ArchiveSummary, AppError> {
+    let archive = settings_archive::read_archive(&PathBuf::from(path))?;
+    Ok(archive.summary())
+}
+
+/// Replace local settings with `sections` from the archive at `path`.
+/// `sections` = `None` restores everything the archive holds.
+#[tauri::command]
+pub async fn import_settings(
+    path: String,
+    sections: Option<Vec<Section>>,
+    state: State<'_, AppState>,
+) -> Result<ImportReport, AppError> {
+    let actor = state.access.require(Capability::ManageSettings)?;
+    let archive = settings_archive::read_archive(&PathBuf::from(&path))?;
+    let sections = sections.unwrap_or_else(|| archive.sections.keys().copied().collect());
+    let report = settings_archive::import(&state, &archive, &sections).await?;
+    access::audit(
+        &state,
+        &actor,
+        "settings.import",
+        Some(path),
+        json!({
+            "sections": report.restored.iter().map(|s| s.section).collect::<Vec<_>>(),
+            "archive_app_version": archive.app_version,
+            "archive_created_at": archive.created_at,
+        }),
+    )
+    .await;
+    Ok(report)
+}
+
+// ── Database backups ──────────────────────────────────────────────────────────
+
+#[tauri::command]
+pub async fn get_backup_config() -> Result<BackupConfig, AppError> {
+    Ok(backup::load_config())
+}
+
+#[tauri::command]
+pub async fn set_backup_config(
+    config: BackupConfig,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    state.access.require(Capability::ManageSettings)?;
+    if !(1..=24 * 7).contains(&config.interval_hours) {
+        return Err(AppError::invalid_input(
+            "Backup interval must be 1-168 hours",
+        ));
+    }
+    if config.keep_last == 0 {
+        return Err(AppError::invalid_input("Keep at least one backup"));
+    }
+    std::fs::create_dir_all(backup::backup_dir(&config))
+        .map_err(|e| AppError::invalid_input(format!("Backup folder is not usable: {e}")))?;
+    backup::save_config(&config)?;
+    backup::prune(&config);
+    Ok(())
+}
+
+/// Snapshots in the backup folder, newest first.
+#[tauri::command]
+pub async fn list_backups() -> Result<Vec<BackupInfo>, AppError> {
+    Ok(backup::list_backups(&backup::load_config()))
+}
+
+/// Take a snapshot now, outside the schedule.
+#[tauri::command]
+pub async fn create_backup(state: State<'_, AppState>) -> Result<BackupInfo, AppError> {
+    let actor = state.access.require(Capability::ManageSettings)?;
+    let info = backup::create_backup(&state).await?;
+    access::audit(
+        &state,
+        &actor,
+        "settings.backup",
+        Some(info.id.clone()),
+        json!({ "db_bytes": info.db_bytes }),
+    )
+    .await;
+    Ok(info)
+}
+
+/// Stage snapshot `id` to replace the local database on the next launch.
+/// The app must be restarted for it to take effect.
+#[tauri::command]
+pub async fn restore_backup(
+    id: String,
+    state: State<'_, AppState>,
+) -> Result<BackupInfo, AppError> {
+    let actor = state.access.require(Capability::ManageSettings)?;
+    let info = backup::stage_restore(&id)
+        .await
+        .map_err(AppError::invalid_input)?;
+    access::audit(
+        &state,
+        &actor,
+        "settings.restore_backup",
+        Some(id),
+        json!({ "created_at": info.created_at }),
+    )
+    .await;
+    Ok(info)
+}
+
+/// Schema version, migration history and integrity of the local database.
+#[tauri::command]
+pub async fn get_db_schema_info(state: State<'_, AppState>) -> Result<SchemaInfo, AppError> {
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    migrations::schema_info(pool).await.map_err(AppError::db)
+}
+
+// ── UI event rates ────────────────────────────────────────────────────────────
+
+#[tauri::command]
+pub async fn get_emit_rate_config() -> Result<EmitRateConfig, AppError> {
+    Ok(emit_metrics::get_emit_rates())
+}
+
+/// Set how often deck, VU and crossfade updates reach the UI; applies from
+/// the next poll.
+#[tauri::command]
+pub async fn set_emit_rate_config(
+    config: EmitRateConfig,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    state.access.require(Capability::ManageSettings)?;
+    config.validate().map_err(AppError::invalid_input)?;
+    emit_metrics::set_emit_rates(config)?;
+    Ok(())
+}
+
+// ── SAM Broadcaster import ────────────────────────────────────────────────────
+
+async fn plan_sam_import(paths: &[String], state: &AppState) -> Result<SamImportPlan, AppError> {
+    let guard = state.sam_db.read().await;
+    let mut warnings = Vec::new();
+    let tables = match guard.as_ref() {
+        Some(pool) => sam_import::read_sam_tables(pool).await.unwrap_or_else(|e| {
+            warnings.push(format!("Could not read SAM settings tables: {e}"));
+            Vec::new()
+        }),
+        None => Vec::new(),
+    };
+    if paths.is_empty() && tables.is_empty() {
+        return Err(AppError::invalid_input(
+            "No SAM configuration files selected and no SAM settings tables found",
+        ));
+    }
+    let mut plan = sam_import::plan_import(paths, &tables).map_err(AppError::invalid_input)?;
+    plan.warnings.extend(warnings);
+    if !plan.clockwheels.is_empty() {
+        if let Some(pool) = guard.as_ref() {
+            match sam::get_categories(pool).await {
+                Ok(categories) => {
+                    let names: Vec<String> = categories.into_iter().map(|c| c.catname).collect();
+                    sam_import::check_categories(&mut plan, &names);
+                }
+                Err(e) => plan
+                    .warnings
+                    .push(format!("Could not check categories against SAM: {e}")),
+            }
+        } else {
+            plan.warnings.push(
+                "SAM database not connected; clockwheel categories were not checked".to_string(),
+            );
+        }
+    }
+    Ok(plan)
+}
+
+/// Read SAM Broadcaster configuration files and the connected samdb's
+/// settings tables, and show what would be imported.
+#[tauri::command]
+pub async fn preview_sam_import(
+    paths: Vec<String>,
+    state: State<'_, AppState>,
+) -> Result<SamImportPlan, AppError> {
+    state.access.require(Capability::ManageSettings)?;
+    plan_sam_import(&paths, &state).await
+}
+
+/// Apply the selected parts of a SAM import. Clockwheels replace any
+/// template with the same name so the import can be re-run.
+#[tauri::command]
+pub async fn apply_sam_import(
+    paths: Vec<String>,
+    selection: SamImportSelection,
+    state: State<'_, AppState>,
+) -> Result<SamImportResult, AppError> {
+    let actor = state.access.require(Capability::ManageSettings)?;
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    let plan = plan_sam_import(&paths, &state).await?;
+    let mut result = SamImportResult {
+        warnings: plan.warnings.clone(),
+        ..Default::default()
+    };
+
+    if selection.crossfade {
+        if let Some(config) = plan.crossfade {
+            let config = normalize_crossfade_config(config);
+            let json = serde_json::to_string(&config)?;
+            local::save_crossfade_config(pool, &json)
+                .await
+                .map_err(AppError::db)?;
+            state.engine.lock().unwrap().set_crossfade_config(config)?;
+            result.crossfade = true;
+        }
+    }
+    if selection.gap_killer {
+        if let Some(config) = plan.gap_killer {
+            let json = serde_json::to_string(&config)?;
+            local::save_gap_killer_config(pool, &json)
+                .await
+                .map_err(AppError::db)?;
+            result.gap_killer = true;
+        }
+    }
+    if selection.clockwheels && !plan.clockwheels.is_empty() {
+        let existing = rotation::get_clockwheel_templates(pool)
+            .await
+            .map_err(AppError::db)?;
+        for mut template in plan.clockwheels {
+            template.id = existing
+                .iter()
+                .find(|t| t.name.eq_ignore_ascii_case(&template.name))
+                .and_then(|t| t.id);
+            if template.id.is_some() {
+                result.clockwheels_updated += 1;
+            } else {
+                result.clockwheels_created += 1;
+            }
+            rotation::upsert_clockwheel_template(pool, &template)
+                .await
+                .map_err(AppError::db)?;
+        }
+    }
+
+    access::audit(
+        &state,
+        &actor,
+        "settings.sam_import",
+        Some(if paths.is_empty() {
+            "samdb".to_string()
+        } else {
+            paths.join(", ")
+        }),
+        json!({
+            "crossfade": result.crossfade,
+            "gap_killer": result.gap_killer,
+            "clockwheels_created": result.clockwheels_created,
+            "clockwheels_updated": result.clockwheels_updated,
+        }),
+    )
+    .await;
+    Ok(result)
+}
@@ -0,0 +1,70 @@
+use tauri::State;
+
+use crate::access::Capability;
+use crate::audio::cart_wall::MAX_CART_FADE_MS;
+use crate::audio::sfx_player::{self, SfxStop, SfxTrigger, SfxVoiceId, SfxVoiceState};
+use crate::error::AppError;
+use crate::state::AppState;
+
+/// Start a one-shot stinger on the Sound FX channel without cutting off
+/// anything already playing there. Returns the voice id for `stop_sfx`.
+#[tauri::command]
+pub async fn play_sfx(
+    file_path: String,
+    gain_db: Option<f64>,
+    choke_group: Option<u32>,
+    state: State<'_, AppState>,
+) -> Result<SfxVoiceId, AppError> {
+    state.access.require(Capability::ControlPlayout)?;
+    let gain_db = gain_db.unwrap_or(0.0);
+    if !(-24.0..=12.0).contains(&gain_db) {
+        return Err(AppError::invalid_input(
+            "SFX gain must be between -24 and +12 dB",
+        ));
+    }
+    if !std::path::Path::new(&file_path).is_file() {
+        return Err(AppError::file_not_found(&file_path));
+    }
+    let sample = sfx_player::load(&file_path).await?;
+    let voice_id = sfx_player::next_voice_id();
+    state.engine.lock().unwrap().play_sfx(SfxTrigger {
+        voice_id,
+        sample,
+        gain: 10.0_f32.powf(gain_db as f32 / 20.0),
+        choke_group,
+    })?;
+    Ok(voice_id)
+}
+
+/// Stop one voice, a whole choke group, or (with neither) every SFX voice.
+#[tauri::command]
+pub fn stop_sfx(
+    voice_id: Option<SfxVoiceId>,
+    choke_group: Option<u32>,
+    fade_ms: Option<u32>,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    state.access.require(Capability::ControlPlayout)?;
+    let fade_ms = fade_ms.unwrap_or(0);
+    if fade_ms > MAX_CART_FADE_MS {
+        return Err(AppError::invalid_input(format!(
+            "SFX fade-out is limited to {MAX_CART_FADE_MS} ms"
+        )));
+    }
+    let which = match (voice_id, choke_group) {
+        (Some(id), None) => SfxStop::Voice(id),
+        (None, Some(group)) => SfxStop::Group(group),
+        (None, None) => SfxStop::All,
+        (Some(_), Some(_)) => {
+            return Err(AppError::invalid_input(
+                "Pass either a voice id or a choke group, not both",
+            ))
+        }
+    };
+    state.engine.lock().unwrap().stop_sfx(which, fade_ms)
+}
+
+#[tauri::command]
+pub fn get_sfx_voices(state: State<'_, AppState>) -> Result<Vec<SfxVoiceState>, AppError> {
+    Ok(state.engine.lock().unwrap().sfx_states())
+}
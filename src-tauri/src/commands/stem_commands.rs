@@ -8,8 +8,20 @@ use flate2::read::GzDecoder;
 use serde::{Deserialize, Serialize};
 use tauri::State;
 
+use crate::access::Capability;
+use crate::error::AppError;
 use crate::{
-    audio::analyzer::stems::separate_two_stems_vocals, db::local::StemAnalysis, state::AppState,
+    audio::{
+        analyzer::{
+            jobs::{self, AnalysisKind},
+            stems::{
+                separate_four_stems, SeparationControl, SeparationOptions, SEPARATION_CANCELLED,
+            },
+        },
+        stem_mix::{StemMix, StemPaths},
+    },
+    db::local::StemAnalysis,
+    state::AppState,
 };
 
 use super::audio_commands::parse_deck;
@@ -23,6 +35,8 @@ pub enum StemPlaybackSource {
     Original,
     Vocals,
     Instrumental,
+    /// All four stems in sync, levels set with `set_deck_stem_mix`
+    Stems,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -41,15 +55,19 @@ pub struct StemsRuntimeStatus {
 }
 
 #[tauri::command]
-pub async fn get_stems_runtime_status() -> Result<StemsRuntimeStatus, String> {
+pub async fn get_stems_runtime_status() -> Result<StemsRuntimeStatus, AppError> {
     Ok(read_stems_runtime_status())
 }
 
 #[tauri::command]
-pub async fn install_stems_runtime() -> Result<StemsRuntimeStatus, String> {
+pub async fn install_stems_runtime(
+    state: State<'_, AppState>,
+) -> Result<StemsRuntimeStatus, AppError> {
+    state.access.require(Capability::ManageSettings)?;
     tauri::async_runtime::spawn_blocking(install_stems_runtime_blocking)
         .await
         .map_err(|e| format!("Stems runtime installer join failed: {e}"))?
+        .map_err(AppError::from)
 }
 
 #[tauri::command]
@@ -58,28 +76,106 @@ pub async fn analyze_stems(
     file_path: String,
     force_reanalyze: Option<bool>,
     state: State<'_, AppState>,
-) -> Result<StemAnalysis, String> {
+) -> Result<StemAnalysis, AppError> {
+    state.access.require(Capability::EditLibrary)?;
+    let local = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    let options = jobs::get_config(local).await?.stems.separation;
+    run_stem_analysis(
+        local,
+        song_id,
+        file_path,
+        force_reanalyze.unwrap_or(false),
+        &options,
+        SeparationControl::default(),
+    )
+    .await
+}
+
+/// Queue stem separation for several SAM songs behind other analysis, to
+/// run within the analysis queue's stem limits. Returns the job ids.
+#[tauri::command]
+pub async fn queue_stem_analysis(
+    song_ids: Vec<i64>,
+    state: State<'_, AppState>,
+) -> Result<Vec<i64>, AppError> {
+    state.access.require(Capability::EditLibrary)?;
+    if song_ids.is_empty() {
+        return Err(AppError::invalid_input("No songs selected"));
+    }
     let local = state
         .local_db
         .as_ref()
-        .ok_or("Local DB not initialised")?
-        .clone();
+        .ok_or_else(AppError::db_unavailable)?;
+    let sam =
+        { state.sam_db.read().await.as_ref().cloned() }.ok_or_else(AppError::sam_db_unavailable)?;
+    let translator = crate::db::path_rules::load_translator(local).await;
+    let songs = crate::db::sam::get_songs_by_ids(&sam, &song_ids).await?;
+    let mut ids = Vec::with_capacity(songs.len());
+    for song in songs {
+        let path = translator.translate(&song.filename);
+        if !Path::new(&path).is_file() {
+            log::warn!("Stem job skipped, file not found: {path}");
+            continue;
+        }
+        ids.push(
+            jobs::enqueue(
+                local,
+                Some(song.id),
+                &path,
+                AnalysisKind::Stems,
+                jobs::PRIORITY_LIBRARY,
+            )
+            .await?,
+        );
+    }
+    Ok(ids)
+}
+
+/// Cancel all waiting stem jobs and stop the running ones.
+#[tauri::command]
+pub async fn cancel_stem_jobs(state: State<'_, AppState>) -> Result<usize, AppError> {
+    state.access.require(Capability::EditLibrary)?;
+    let local = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    jobs::cancel_kind(local, AnalysisKind::Stems)
+        .await
+        .map_err(AppError::db)
+}
+
+/// Separate stems for one file, reusing a cached result from the same model
+/// unless `force`. Shared by the command and the background analysis queue.
+pub(crate) async fn run_stem_analysis(
+    local: &sqlx::SqlitePool,
+    song_id: i64,
+    file_path: String,
+    force: bool,
+    options: &SeparationOptions,
+    control: SeparationControl,
+) -> Result<StemAnalysis, AppError> {
     let input_path = PathBuf::from(&file_path);
     if !input_path.exists() {
-        return Err(format!("File not found: {file_path}"));
+        return Err(AppError::file_not_found(&file_path));
     }
     if !input_path.is_file() {
-        return Err(format!("Path is not a file: {file_path}"));
+        return Err(AppError::invalid_input(format!(
+            "Path is not a file: {file_path}"
+        )));
     }
 
     let mtime_ms = file_mtime_ms(&input_path);
-    let force = force_reanalyze.unwrap_or(false);
     if !force {
         if let Ok(Some(cached)) =
-            crate::db::local::get_stem_analysis(&local, song_id, &file_path, mtime_ms).await
+            crate::db::local::get_stem_analysis(local, song_id, &file_path, mtime_ms).await
         {
-            if Path::new(&cached.vocals_file_path).exists()
+            // Two-stem analyses from before the 4-stem model are redone.
+            if cached.model_name == options.model.as_str()
                 && Path::new(&cached.instrumental_file_path).exists()
+                && four_stem_paths(&cached).is_some()
             {
                 return Ok(cached);
             }
@@ -93,15 +189,28 @@ pub async fn analyze_stems(
     let separate_input = input_path.clone();
     let separate_output = output_root.clone();
     let preferred_python = resolve_runtime_python_bin();
+    let separate_options = options.clone();
     let computed = tauri::async_runtime::spawn_blocking(move || {
-        separate_two_stems_vocals(
+        separate_four_stems(
             &separate_input,
             &separate_output,
             preferred_python.as_deref(),
+            &separate_options,
+            &control,
         )
     })
     .await
-    .map_err(|e| format!("Stem worker join failed: {e}"))??;
+    .map_err(|e| format!("Stem worker join failed: {e}"))?;
+    let computed = match computed {
+        Ok(computed) => computed,
+        Err(e) => {
+            if e == SEPARATION_CANCELLED {
+                // Drop the half-written model output; other models' stems stay.
+                let _ = fs::remove_dir_all(output_root.join(options.model.as_str()));
+            }
+            return Err(e.into());
+        }
+    };
 
     let analysis = StemAnalysis {
         song_id,
@@ -109,17 +218,20 @@ pub async fn analyze_stems(
         source_mtime_ms: mtime_ms,
         vocals_file_path: computed.vocals_path.to_string_lossy().to_string(),
         instrumental_file_path: computed.instrumental_path.to_string_lossy().to_string(),
+        drums_file_path: Some(computed.drums_path.to_string_lossy().to_string()),
+        bass_file_path: Some(computed.bass_path.to_string_lossy().to_string()),
+        other_file_path: Some(computed.other_path.to_string_lossy().to_string()),
         model_name: computed.model_name,
         updated_at: None,
     };
-    crate::db::local::save_stem_analysis(&local, &analysis)
+    crate::db::local::save_stem_analysis(local, &analysis)
         .await
-        .map_err(|e| format!("DB error: {e}"))?;
+        .map_err(AppError::db)?;
 
-    crate::db::local::get_stem_analysis(&local, song_id, &file_path, mtime_ms)
+    crate::db::local::get_stem_analysis(local, song_id, &file_path, mtime_ms)
         .await
-        .map_err(|e| format!("DB error: {e}"))?
-        .ok_or("Failed to read saved stem analysis".to_string())
+        .map_err(AppError::db)?
+        .ok_or_else(|| "Failed to read saved stem analysis".into())
 }
 
 #[tauri::command]
@@ -127,8 +239,11 @@ pub async fn get_stem_analysis(
     song_id: i64,
     file_path: String,
     state: State<'_, AppState>,
-) -> Result<Option<StemAnalysis>, String> {
-    let local = state.local_db.as_ref().ok_or("Local DB not initialised")?;
+) -> Result<Option<StemAnalysis>, AppError> {
+    let local = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
     let input_path = PathBuf::from(&file_path);
     if !input_path.exists() || !input_path.is_file() {
         return Ok(None);
@@ -136,7 +251,7 @@ pub async fn get_stem_analysis(
     let mtime_ms = file_mtime_ms(&input_path);
     let row = crate::db::local::get_stem_analysis(local, song_id, &file_path, mtime_ms)
         .await
-        .map_err(|e| format!("DB error: {e}"))?;
+        .map_err(AppError::db)?;
 
     Ok(row.and_then(validate_stem_files))
 }
@@ -145,11 +260,14 @@ pub async fn get_stem_analysis(
 pub async fn get_latest_stem_analysis(
     song_id: i64,
     state: State<'_, AppState>,
-) -> Result<Option<StemAnalysis>, String> {
-    let local = state.local_db.as_ref().ok_or("Local DB not initialised")?;
+) -> Result<Option<StemAnalysis>, AppError> {
+    let local = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
     let row = crate::db::local::get_latest_stem_analysis_by_song_id(local, song_id)
         .await
-        .map_err(|e| format!("DB error: {e}"))?;
+        .map_err(AppError::db)?;
     Ok(row.and_then(validate_stem_files))
 }
 
@@ -160,13 +278,14 @@ pub async fn set_deck_stem_source(
     song_id: Option<i64>,
     original_file_path: Option<String>,
     state: State<'_, AppState>,
-) -> Result<DeckStemSourceResult, String> {
+) -> Result<DeckStemSourceResult, AppError> {
+    state.access.require(Capability::ControlPlayout)?;
     let deck_id = parse_deck(&deck)?;
     let latest = if let Some(local) = &state.local_db {
         if let Some(id) = song_id {
             crate::db::local::get_latest_stem_analysis_by_song_id(local, id)
                 .await
-                .map_err(|e| format!("DB error: {e}"))?
+                .map_err(AppError::db)?
                 .and_then(validate_stem_files)
         } else {
             None
@@ -189,15 +308,39 @@ pub async fn set_deck_stem_source(
             .filter(|p| !p.trim().is_empty())
             .or_else(|| latest.as_ref().map(|r| r.source_file_path.clone()))
             .or(current_loaded)
-            .ok_or("No original track path available for this deck")?,
+            .ok_or_else(|| AppError::not_found("No original track path available for this deck"))?,
         StemPlaybackSource::Vocals => latest
             .as_ref()
             .map(|r| r.vocals_file_path.clone())
-            .ok_or("No generated stems found. Run Generate Stems first.")?,
+            .ok_or_else(|| {
+                AppError::not_found("No generated stems found. Run Generate Stems first.")
+            })?,
         StemPlaybackSource::Instrumental => latest
             .as_ref()
             .map(|r| r.instrumental_file_path.clone())
-            .ok_or("No generated stems found. Run Generate Stems first.")?,
+            .ok_or_else(|| {
+                AppError::not_found("No generated stems found. Run Generate Stems first.")
+            })?,
+        StemPlaybackSource::Stems => {
+            let analysis = latest.as_ref().ok_or_else(|| {
+                AppError::not_found("No generated stems found. Run Generate Stems first.")
+            })?;
+            let stems = four_stem_paths(analysis).ok_or_else(|| {
+                AppError::not_found("No 4-stem separation found. Run Generate Stems again.")
+            })?;
+            let original = original_file_path
+                .filter(|p| !p.trim().is_empty())
+                .unwrap_or_else(|| analysis.source_file_path.clone());
+            state.engine.lock().unwrap().load_deck_stems(
+                deck_id,
+                PathBuf::from(&original),
+                stems,
+            )?;
+            return Ok(DeckStemSourceResult {
+                source,
+                file_path: original,
+            });
+        }
     };
 
     state
@@ -212,6 +355,19 @@ pub async fn set_deck_stem_source(
     })
 }
 
+/// Set the level and mute of each stem on a deck playing `Stems`. Levels
+/// carry over seeks and reset when a new track loads.
+#[tauri::command]
+pub async fn set_deck_stem_mix(
+    deck: String,
+    mix: StemMix,
+    state: State<'_, AppState>,
+) -> Result<(), AppError> {
+    state.access.require(Capability::ControlPlayout)?;
+    let deck_id = parse_deck(&deck)?;
+    state.engine.lock().unwrap().set_deck_stem_mix(deck_id, mix)
+}
+
 fn install_stems_runtime_blocking() -> Result<StemsRuntimeStatus, String> {
     let runtime_root = stems_runtime_root();
     fs::create_dir_all(&runtime_root).map_err(|e| {
@@ -507,6 +663,22 @@ fn validate_stem_files(row: StemAnalysis) -> Option<StemAnalysis> {
     }
 }
 
+/// The four stem files, in `Stem::ALL` order, when all are on disk.
+fn four_stem_paths(row: &StemAnalysis) -> Option<StemPaths> {
+    let paths = [
+        Some(&row.vocals_file_path),
+        row.drums_file_path.as_ref(),
+        row.bass_file_path.as_ref(),
+        row.other_file_path.as_ref(),
+    ]
+    .map(|p| p.map(PathBuf::from));
+    if paths.iter().all(|p| p.as_ref().is_some_and(|p| p.exists())) {
+        Some(paths.map(Option::unwrap))
+    } else {
+        None
+    }
+}
+
 fn file_mtime_ms(path: &Path) -> i64 {
     path.metadata()
         .ok()
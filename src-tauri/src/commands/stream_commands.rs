@@ -1,6 +1,14 @@
 use tauri::State;
 
-use crate::{state::AppState, stream::icecast::IcecastConfig};
+use crate::access::Capability;
+use crate::error::AppError;
+use crate::{
+    state::AppState,
+    stream::{
+        icecast::IcecastConfig,
+        overlay_server::{self, OverlayServerConfig, OverlayServerStatus},
+    },
+};
 
 /// Start streaming to an Icecast server.
 #[tauri::command]
@@ -13,10 +21,11 @@ pub async fn start_stream(
     stream_name: Option<String>,
     genre: Option<String>,
     state: State<'_, AppState>,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
+    state.access.require(Capability::ControlEncoders)?;
     let mut guard = state.stream_handle.lock().unwrap();
     if guard.is_some() {
-        return Err("Stream already running".to_string());
+        return Err(AppError::conflict("Stream already running"));
     }
 
     let consumer = state
@@ -46,18 +55,64 @@ pub async fn start_stream(
 
 /// Stop the active stream.
 #[tauri::command]
-pub async fn stop_stream(state: State<'_, AppState>) -> Result<(), String> {
+pub async fn stop_stream(state: State<'_, AppState>) -> Result<(), AppError> {
+    state.access.require(Capability::ControlEncoders)?;
     let mut guard = state.stream_handle.lock().unwrap();
     match guard.take() {
         Some(handle) => {
             handle.stop();
             Ok(())
         }
-        None => Err("No stream running".to_string()),
+        None => Err(AppError::not_found("No stream running")),
     }
 }
 
 #[tauri::command]
-pub async fn get_stream_status(state: State<'_, AppState>) -> Result<bool, String> {
+pub async fn get_stream_status(state: State<'_, AppState>) -> Result<bool, AppError> {
     Ok(state.stream_handle.lock().unwrap().is_some())
 }
+
+// ── Overlay WebSocket server ──────────────────────────────────────────────────
+
+#[tauri::command]
+pub async fn get_overlay_server_config(
+    state: State<'_, AppState>,
+) -> Result<OverlayServerConfig, AppError> {
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    overlay_server::get_config(pool)
+        .await
+        .map_err(AppError::from)
+}
+
+/// Save the overlay server config and start, restart or stop the server to
+/// match.
+#[tauri::command]
+pub async fn set_overlay_server_config(
+    app: tauri::AppHandle,
+    state: State<'_, AppState>,
+    config: OverlayServerConfig,
+) -> Result<OverlayServerStatus, AppError> {
+    state.access.require(Capability::ManageSettings)?;
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    config.validate().map_err(AppError::invalid_input)?;
+    overlay_server::save_config(pool, &config).await?;
+    if config.enabled {
+        overlay_server::start(app, config)
+            .await
+            .map_err(AppError::from)
+    } else {
+        overlay_server::stop();
+        Ok(overlay_server::status())
+    }
+}
+
+#[tauri::command]
+pub async fn get_overlay_server_status() -> Result<OverlayServerStatus, AppError> {
+    Ok(overlay_server::status())
+}
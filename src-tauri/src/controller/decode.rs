@@ -135,6 +135,20 @@ fn decode_pads(shift_pressed: bool, status: u8, note: u8) -> Vec<ControllerActio
         return vec![ControllerAction::ClearLoop { deck }];
     }
 
+    // Sampler pads fire the first eight carts: deck A 1–4, deck B 5–8.
+    let cart_base = if deck == DeckId::DeckA { 0 } else { 4 };
+    if (map::SAMPLER_PAD_1_NOTE..=map::SAMPLER_PAD_4_NOTE).contains(&note) {
+        let slot = cart_base + note - map::SAMPLER_PAD_1_NOTE;
+        if shift_pressed {
+            return vec![ControllerAction::StopCart { slot }];
+        }
+        return vec![ControllerAction::TriggerCart { slot }];
+    }
+    if (map::SAMPLER_PAD_SHIFT_1_NOTE..=map::SAMPLER_PAD_SHIFT_4_NOTE).contains(&note) {
+        let slot = cart_base + note - map::SAMPLER_PAD_SHIFT_1_NOTE;
+        return vec![ControllerAction::StopCart { slot }];
+    }
+
     let (slot, explicit_shift) = if (map::PAD_1_NOTE..=map::PAD_4_NOTE).contains(&note) {
         (note - map::PAD_1_NOTE + 1, false)
     } else if (map::PAD_SHIFT_1_NOTE..=map::PAD_SHIFT_4_NOTE).contains(&note) {
@@ -284,6 +298,27 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn decode_sampler_pads_map_to_carts() {
+        let mut state = DecodeState::default();
+        let actions = decode_message(
+            &mut state,
+            &[map::DECK_B_PAD_STATUS, map::SAMPLER_PAD_1_NOTE + 2, 0x7F],
+        );
+        assert!(matches!(
+            actions.first(),
+            Some(ControllerAction::TriggerCart { slot: 6 })
+        ));
+        let actions = decode_message(
+            &mut state,
+            &[map::DECK_A_PAD_STATUS, map::SAMPLER_PAD_SHIFT_1_NOTE, 0x7F],
+        );
+        assert!(matches!(
+            actions.first(),
+            Some(ControllerAction::StopCart { slot: 0 })
+        ));
+    }
+
     #[test]
     fn decode_tempo_14_bit() {
         let mut state = DecodeState::default();
@@ -1,12 +1,18 @@
-use tauri::{AppHandle, Manager};
+use std::sync::{Mutex, OnceLock};
+
+use tauri::{AppHandle, Emitter, Manager};
 
 use crate::{
-    audio::crossfade::DeckId,
+    audio::{
+        cart_wall::{self, CartKey},
+        crossfade::DeckId,
+        dsp::eq::EqBand,
+    },
     db::local::{BeatGridAnalysis, HotCue},
     state::AppState,
 };
 
-use super::types::ControllerAction;
+use super::types::{ControllerAction, ControllerBrowseEvent};
 
 const BEATGRID_CONFIDENCE_MIN: f32 = 0.55;
 const LOOP_TOGGLE_TOLERANCE_MS: u64 = 35;
@@ -59,7 +65,34 @@ pub async fn execute_action(app_handle: AppHandle, action: ControllerAction) {
             set_beat_loop(&state, deck, beats).await;
         }
         ControllerAction::ClearLoop { deck } => {
-            clear_loop_preserve_position(&state, deck);
+            exit_loop(&state, deck);
+        }
+        ControllerAction::ToggleReverse { deck } => {
+            let reversed = {
+                let engine = state.engine.lock().unwrap();
+                engine
+                    .get_deck_state(deck)
+                    .map(|s| s.reverse)
+                    .unwrap_or(false)
+            };
+            if deck.is_playback() {
+                let mut engine = state.engine.lock().unwrap();
+                let _ = engine.set_deck_reverse(deck, !reversed);
+            }
+        }
+        ControllerAction::ToggleSlip { deck } => {
+            let slip = {
+                let engine = state.engine.lock().unwrap();
+                engine.get_deck_state(deck).map(|s| s.slip).unwrap_or(false)
+            };
+            let mut engine = state.engine.lock().unwrap();
+            let _ = engine.set_deck_slip(deck, !slip);
+        }
+        ControllerAction::Censor { deck, active } => {
+            if deck.is_playback() {
+                let mut engine = state.engine.lock().unwrap();
+                let _ = engine.set_deck_censor(deck, active);
+            }
         }
         ControllerAction::SetTempo {
             deck, tempo_pct, ..
@@ -79,6 +112,21 @@ pub async fn execute_action(app_handle: AppHandle, action: ControllerAction) {
             let mut engine = state.engine.lock().unwrap();
             let _ = engine.set_deck_filter(deck, amount.clamp(-1.0, 1.0));
         }
+        ControllerAction::ToggleEqKill { deck, band } => {
+            let current = {
+                let engine = state.engine.lock().unwrap();
+                engine
+                    .get_deck_state(deck)
+                    .map(|s| match band {
+                        EqBand::Low => s.eq_kill.low,
+                        EqBand::Mid => s.eq_kill.mid,
+                        EqBand::High => s.eq_kill.high,
+                    })
+                    .unwrap_or(false)
+            };
+            let mut engine = state.engine.lock().unwrap();
+            let _ = engine.set_deck_eq_kill(deck, band, !current);
+        }
         ControllerAction::SetCrossfader { position, .. } => {
             let mut engine = state.engine.lock().unwrap();
             let _ = engine.set_manual_crossfade(position.clamp(-1.0, 1.0));
@@ -95,10 +143,138 @@ pub async fn execute_action(app_handle: AppHandle, action: ControllerAction) {
             let mut engine = state.engine.lock().unwrap();
             let _ = engine.set_headphone_level(level.clamp(0.0, 1.0));
         }
+        ControllerAction::TriggerCart { slot } => {
+            let key = CartKey {
+                page: cart_wall::active_page(),
+                slot: slot as u32,
+            };
+            if let Err(e) = crate::commands::cart_commands::trigger(&state, key) {
+                log::debug!("Controller cart pad: {e}");
+            }
+        }
+        ControllerAction::StopCart { slot } => {
+            let key = CartKey {
+                page: cart_wall::active_page(),
+                slot: slot as u32,
+            };
+            let _ = state.engine.lock().unwrap().stop_cart(key);
+        }
         ControllerAction::JogNudge { deck, delta_steps } => {
             jog_nudge(&state, deck, delta_steps);
         }
+        ControllerAction::BrowseQueue { delta } => {
+            browse_queue(&app_handle, &state, delta).await;
+        }
+        ControllerAction::LoadBrowsed { deck } => {
+            if let Err(e) = load_browsed(&app_handle, &state, deck).await {
+                log::warn!("Controller load to {deck}: {e}");
+            }
+        }
+    }
+}
+
+/// Index into the SAM play queue selected with the browse encoder.
+fn browse_cursor() -> &'static Mutex<usize> {
+    static CURSOR: OnceLock<Mutex<usize>> = OnceLock::new();
+    CURSOR.get_or_init(|| Mutex::new(0))
+}
+
+async fn browse_queue(app_handle: &AppHandle, state: &AppState, delta: i8) {
+    let Some(pool) = ({ state.sam_db.read().await.as_ref().cloned() }) else {
+        return;
+    };
+    let queue = match crate::db::sam::get_queue(&pool).await {
+        Ok(q) if !q.is_empty() => q,
+        _ => return,
+    };
+    let index = {
+        let mut cursor = browse_cursor().lock().unwrap();
+        let moved = (*cursor as i64 + delta as i64).clamp(0, queue.len() as i64 - 1);
+        *cursor = moved as usize;
+        *cursor
+    };
+    let entry = &queue[index];
+    let _ = app_handle.emit(
+        "controller_browse",
+        ControllerBrowseEvent {
+            index,
+            queue_id: entry.id,
+            song_id: entry.song_id,
+            artist: entry.song.as_ref().map(|s| s.artist.clone()),
+            title: entry.song.as_ref().map(|s| s.title.clone()),
+            loaded_deck: None,
+        },
+    );
+}
+
+async fn load_browsed(
+    app_handle: &AppHandle,
+    state: &AppState,
+    deck: DeckId,
+) -> Result<(), String> {
+    let busy = {
+        let engine = state.engine.lock().unwrap();
+        engine
+            .get_deck_state(deck)
+            .is_some_and(|s| s.state == "playing" || s.state == "crossfading")
+    };
+    if busy {
+        return Err("deck is on air".to_string());
     }
+    let pool = { state.sam_db.read().await.as_ref().cloned() }
+        .ok_or_else(|| "SAM DB not connected".to_string())?;
+    let local = state
+        .local_db
+        .as_ref()
+        .ok_or_else(|| "Local DB not initialised".to_string())?;
+    let queue = crate::db::sam::get_queue(&pool)
+        .await
+        .map_err(|e| e.to_string())?;
+    let index = *browse_cursor().lock().unwrap();
+    let entry = queue
+        .get(index)
+        .ok_or_else(|| "queue is empty".to_string())?;
+    if crate::db::sam_outbox::removal_pending(entry.id) {
+        return Err(format!("queue entry {} is already loaded", entry.id));
+    }
+    let song = match entry.song.clone() {
+        Some(song) => song,
+        None => crate::db::sam::get_song(&pool, entry.song_id)
+            .await
+            .map_err(|e| e.to_string())?
+            .ok_or_else(|| format!("Song {} not found", entry.song_id))?,
+    };
+    let path = crate::translate_sam_file_path(local, song.filename.clone()).await;
+    let declared_ms = (song.duration > 0).then_some(song.duration as u64 * 1000);
+    crate::scheduler::track_preflight::check_manual_load(std::path::Path::new(&path), declared_ms)
+        .await?;
+    let trim_db = crate::resolve_track_gain_db(state, Some(song.id)).await;
+    {
+        let mut engine = state.engine.lock().unwrap();
+        engine.load_track_with_source(
+            deck,
+            path.into(),
+            Some(song.id),
+            Some(entry.id),
+            false,
+            declared_ms,
+        )?;
+        engine.set_track_gain_db(deck, trim_db)?;
+    }
+    // Taken off the queue like an AutoDJ load, so it can't be loaded twice.
+    crate::claim_queue_item(state, entry.id).await;
+    let _ = app_handle.emit(
+        "controller_browse",
+        ControllerBrowseEvent {
+            index,
+            queue_id: entry.id,
+            song_id: song.id,
+            artist: Some(song.artist),
+            title: Some(song.title),
+            loaded_deck: Some(deck),
+        },
+    );
+    Ok(())
 }
 
 fn jog_nudge(state: &AppState, deck: DeckId, delta_steps: i8) {
@@ -149,8 +325,10 @@ async fn trigger_hotcue(state: &AppState, deck: DeckId, slot: u8) {
     let Some(cue) = cue else {
         return;
     };
-    let mut engine = state.engine.lock().unwrap();
-    let _ = engine.seek(deck, cue.position_ms.max(0) as u64);
+    let mode = state.engine.lock().unwrap().quantize_mode(deck);
+    let _ =
+        crate::commands::cue_commands::quantized_jump(state, deck, song_id, cue.position_ms, mode)
+            .await;
 }
 
 async fn set_hotcue(state: &AppState, deck: DeckId, slot: u8) {
@@ -330,7 +508,7 @@ async fn set_beat_loop(state: &AppState, deck: DeckId, beats: u8) {
             if (start_diff <= LOOP_TOGGLE_TOLERANCE_MS && end_diff <= LOOP_TOGGLE_TOLERANCE_MS)
                 || len_diff <= LOOP_TOGGLE_TOLERANCE_MS
             {
-                clear_loop_preserve_position(state, deck);
+                exit_loop(state, deck);
                 return;
             }
         }
@@ -470,13 +648,7 @@ fn build_loop_range_ms(
     Some((start_ms, end_ms))
 }
 
-fn clear_loop_preserve_position(state: &AppState, deck: DeckId) {
+fn exit_loop(state: &AppState, deck: DeckId) {
     let mut engine = state.engine.lock().unwrap();
-    let current_pos = engine.get_deck_state(deck).map(|s| s.position_ms);
-    if engine.clear_deck_loop(deck).is_err() {
-        return;
-    }
-    if let Some(position_ms) = current_pos {
-        let _ = engine.seek(deck, position_ms);
-    }
+    let _ = engine.exit_deck_loop(deck);
 }
@@ -0,0 +1,440 @@
+/// Global keyboard shortcuts mapped to broadcast actions.
+///
+/// Bindings are stored in SQLite as one JSON list and registered through
+/// `tauri_plugin_global_shortcut`. Cart hotkeys live on the carts themselves
+/// but share the same OS-level registry, so both are registered (and checked
+/// for conflicts) together here.
+use std::sync::{Mutex, OnceLock};
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutState};
+
+use crate::{
+    audio::{
+        cart_wall::{self, CartKey, CartSlot},
+        crossfade::DeckId,
+    },
+    commands::cue_commands::{HOT_CUE_MAX_SLOT, HOT_CUE_MIN_SLOT},
+    error::AppError,
+    state::AppState,
+};
+
+use super::{executor::execute_action, types::ControllerAction};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum HotkeyAction {
+    TogglePlay {
+        deck: DeckId,
+    },
+    CueToStart {
+        deck: DeckId,
+    },
+    HotCue {
+        deck: DeckId,
+        slot: u8,
+    },
+    /// Crossfade from whichever of Deck A/B is playing into the other
+    StartCrossfade,
+    /// Latch the mic open / closed
+    ToggleMic,
+    /// Mic open only while the chord is held
+    PushToTalk,
+    TriggerCart {
+        page: u32,
+        slot: u32,
+    },
+    StopAllCarts,
+}
+
+impl HotkeyAction {
+    /// Short description used in conflict and registration reports.
+    pub fn label(&self) -> String {
+        match self {
+            HotkeyAction::TogglePlay { deck } => format!("play/pause {deck}"),
+            HotkeyAction::CueToStart { deck } => format!("cue to start {deck}"),
+            HotkeyAction::HotCue { deck, slot } => format!("hot cue {slot} on {deck}"),
+            HotkeyAction::StartCrossfade => "start crossfade".to_string(),
+            HotkeyAction::ToggleMic => "toggle mic".to_string(),
+            HotkeyAction::PushToTalk => "push-to-talk".to_string(),
+            HotkeyAction::TriggerCart { page, slot } => format!("cart {page}/{slot}"),
+            HotkeyAction::StopAllCarts => "stop all carts".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HotkeyBinding {
+    pub action: HotkeyAction,
+    /// Accelerator string, e.g. `CommandOrControl+Shift+1`
+    pub chord: String,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HotkeyConflict {
+    pub chord: String,
+    /// Labels of every binding / cart claiming the chord
+    pub owners: Vec<String>,
+}
+
+/// Outcome of registering one chord with the OS.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HotkeyRegistration {
+    pub chord: String,
+    pub owner: String,
+    pub registered: bool,
+    /// Set when another application already holds the chord, etc.
+    pub error: Option<String>,
+}
+
+fn bindings() -> &'static Mutex<Vec<HotkeyBinding>> {
+    static BINDINGS: OnceLock<Mutex<Vec<HotkeyBinding>>> = OnceLock::new();
+    BINDINGS.get_or_init(|| Mutex::new(Vec::new()))
+}
+
+fn registered() -> &'static Mutex<Vec<String>> {
+    static REGISTERED: OnceLock<Mutex<Vec<String>>> = OnceLock::new();
+    REGISTERED.get_or_init(|| Mutex::new(Vec::new()))
+}
+
+fn registrations() -> &'static Mutex<Vec<HotkeyRegistration>> {
+    static REGISTRATIONS: OnceLock<Mutex<Vec<HotkeyRegistration>>> = OnceLock::new();
+    REGISTRATIONS.get_or_init(|| Mutex::new(Vec::new()))
+}
+
+pub fn get_bindings() -> Vec<HotkeyBinding> {
+    bindings().lock().unwrap().clone()
+}
+
+pub fn get_registrations() -> Vec<HotkeyRegistration> {
+    registrations().lock().unwrap().clone()
+}
+
+/// Load saved bindings into memory. Call [`sync`] afterwards to register them.
+pub async fn load(pool: &sqlx::SqlitePool) -> Result<usize, String> {
+    let loaded = match crate::db::local::load_hotkey_config(pool)
+        .await
+        .map_err(|e| e.to_string())?
+    {
+        Some(json) => serde_json::from_str::<Vec<HotkeyBinding>>(&json)
+            .map_err(|e| format!("Invalid hotkey config: {e}"))?,
+        None => Vec::new(),
+    };
+    let n = loaded.len();
+    *bindings().lock().unwrap() = loaded;
+    Ok(n)
+}
+
+/// Validate, persist and cache a full binding table. `carts` are the saved
+/// cart wall entries whose hotkeys the bindings must not collide with.
+pub async fn save(
+    pool: &sqlx::SqlitePool,
+    new_bindings: Vec<HotkeyBinding>,
+    carts: &[CartSlot],
+) -> Result<(), AppError> {
+    validate(&new_bindings)?;
+    if let Some(conflict) = find_conflicts(&claims(&new_bindings, carts))
+        .into_iter()
+        .next()
+    {
+        return Err(AppError::conflict(format!(
+            "Hotkey {} is bound to {}",
+            conflict.chord,
+            conflict.owners.join(" and ")
+        )));
+    }
+    let json = serde_json::to_string(&new_bindings)?;
+    crate::db::local::save_hotkey_config(pool, &json).await?;
+    *bindings().lock().unwrap() = new_bindings;
+    Ok(())
+}
+
+fn validate(list: &[HotkeyBinding]) -> Result<(), AppError> {
+    for binding in list {
+        let chord = binding.chord.trim();
+        if chord.is_empty() {
+            return Err(AppError::invalid_input(format!(
+                "Hotkey for {} is empty",
+                binding.action.label()
+            )));
+        }
+        chord
+            .parse::<Shortcut>()
+            .map_err(|e| AppError::invalid_input(format!("Invalid hotkey {chord}: {e}")))?;
+        if let HotkeyAction::HotCue { slot, .. } = binding.action {
+            if !(HOT_CUE_MIN_SLOT..=HOT_CUE_MAX_SLOT).contains(&slot) {
+                return Err(AppError::invalid_input(format!(
+                    "Hot cue slot must be between {HOT_CUE_MIN_SLOT} and {HOT_CUE_MAX_SLOT}"
+                )));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Every (chord, owner) pair that would be registered: enabled bindings plus
+/// cart hotkeys.
+pub fn claims(list: &[HotkeyBinding], carts: &[CartSlot]) -> Vec<(String, String)> {
+    let mut out: Vec<(String, String)> = list
+        .iter()
+        .filter(|b| b.enabled)
+        .map(|b| (b.chord.trim().to_string(), b.action.label()))
+        .collect();
+    for cart in carts {
+        if let Some(hotkey) = cart.hotkey.as_deref().filter(|h| !h.trim().is_empty()) {
+            out.push((
+                hotkey.trim().to_string(),
+                HotkeyAction::TriggerCart {
+                    page: cart.page,
+                    slot: cart.slot,
+                }
+                .label(),
+            ));
+        }
+    }
+    out
+}
+
+/// Group claims by the shortcut they resolve to, so `Ctrl+1` and
+/// `control+Digit1` count as the same chord. Unparseable chords are ignored.
+pub fn find_conflicts(claims: &[(String, String)]) -> Vec<HotkeyConflict> {
+    let mut groups: Vec<(Shortcut, HotkeyConflict)> = Vec::new();
+    for (chord, owner) in claims {
+        let Ok(shortcut) = chord.parse::<Shortcut>() else {
+            continue;
+        };
+        match groups.iter_mut().find(|(s, _)| *s == shortcut) {
+            Some((_, group)) => group.owners.push(owner.clone()),
+            None => groups.push((
+                shortcut,
+                HotkeyConflict {
+                    chord: chord.clone(),
+                    owners: vec![owner.clone()],
+                },
+            )),
+        }
+    }
+    groups
+        .into_iter()
+        .map(|(_, group)| group)
+        .filter(|group| group.owners.len() > 1)
+        .collect()
+}
+
+/// Label of the enabled binding already using `chord`, if any. Used by the
+/// cart editor so a cart hotkey cannot shadow a binding.
+pub fn binding_owner(chord: &str) -> Option<String> {
+    let shortcut = chord.trim().parse::<Shortcut>().ok()?;
+    bindings()
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|b| b.enabled)
+        .find(|b| b.chord.trim().parse::<Shortcut>().ok() == Some(shortcut))
+        .map(|b| b.action.label())
+}
+
+/// Re-register every enabled binding and every loaded cart hotkey.
+pub fn sync(app: &AppHandle) {
+    let mut entries: Vec<(String, HotkeyAction)> = get_bindings()
+        .into_iter()
+        .filter(|b| b.enabled)
+        .map(|b| (b.chord.trim().to_string(), b.action))
+        .collect();
+    for cart in cart_wall::loaded_carts() {
+        if let Some(hotkey) = cart.hotkey.filter(|h| !h.trim().is_empty()) {
+            entries.push((
+                hotkey.trim().to_string(),
+                HotkeyAction::TriggerCart {
+                    page: cart.page,
+                    slot: cart.slot,
+                },
+            ));
+        }
+    }
+
+    let shortcuts = app.global_shortcut();
+    let mut registered = registered().lock().unwrap();
+    for chord in registered.drain(..) {
+        let _ = shortcuts.unregister(chord.as_str());
+    }
+
+    let mut taken: Vec<(Shortcut, String)> = Vec::new();
+    let mut report = Vec::with_capacity(entries.len());
+    for (chord, action) in entries {
+        let owner = action.label();
+        let shortcut = match chord.parse::<Shortcut>() {
+            Ok(s) => s,
+            Err(e) => {
+                report.push(failed(chord, owner, format!("Invalid hotkey: {e}")));
+                continue;
+            }
+        };
+        if let Some((_, other)) = taken.iter().find(|(s, _)| *s == shortcut) {
+            let error = format!("Already bound to {other}");
+            report.push(failed(chord, owner, error));
+            continue;
+        }
+        let result = shortcuts.on_shortcut(chord.as_str(), move |app, _shortcut, event| {
+            dispatch(app, &action, event.state());
+        });
+        match result {
+            Ok(()) => {
+                taken.push((shortcut, owner.clone()));
+                registered.push(chord.clone());
+                report.push(HotkeyRegistration {
+                    chord,
+                    owner,
+                    registered: true,
+                    error: None,
+                });
+            }
+            Err(e) => {
+                log::warn!("Hotkey {chord} ({owner}) not registered: {e}");
+                report.push(failed(chord, owner, e.to_string()));
+            }
+        }
+    }
+    *registrations().lock().unwrap() = report;
+}
+
+fn failed(chord: String, owner: String, error: String) -> HotkeyRegistration {
+    HotkeyRegistration {
+        chord,
+        owner,
+        registered: false,
+        error: Some(error),
+    }
+}
+
+fn dispatch(app: &AppHandle, action: &HotkeyAction, key_state: ShortcutState) {
+    let pressed = key_state == ShortcutState::Pressed;
+    match action {
+        HotkeyAction::PushToTalk => {
+            if let Err(e) = set_ptt(app, pressed) {
+                log::warn!("PTT hotkey: {e}");
+            }
+        }
+        _ if !pressed => {}
+        // Carts fire inline — no DB access, so they stay instant.
+        HotkeyAction::TriggerCart { page, slot } => {
+            let state = app.state::<AppState>();
+            let key = CartKey {
+                page: *page,
+                slot: *slot,
+            };
+            if let Err(e) = crate::commands::cart_commands::trigger(&state, key) {
+                log::warn!("Cart hotkey: {e}");
+            }
+        }
+        action => {
+            let app = app.clone();
+            let action = action.clone();
+            tauri::async_runtime::spawn(async move {
+                if let Err(e) = run(app, action).await {
+                    log::warn!("Hotkey: {e}");
+                }
+            });
+        }
+    }
+}
+
+async fn run(app: AppHandle, action: HotkeyAction) -> Result<(), String> {
+    match action {
+        HotkeyAction::TogglePlay { deck } => {
+            execute_action(app, ControllerAction::TogglePlay { deck }).await;
+        }
+        HotkeyAction::CueToStart { deck } => {
+            execute_action(app, ControllerAction::CueToStart { deck }).await;
+        }
+        HotkeyAction::HotCue { deck, slot } => {
+            execute_action(app, ControllerAction::HotCueTrigger { deck, slot }).await;
+        }
+        HotkeyAction::StartCrossfade => {
+            let state = app.state::<AppState>();
+            start_crossfade(&state)?;
+        }
+        HotkeyAction::ToggleMic => {
+            let state = app.state::<AppState>();
+            let open = !state.mic_input.is_latched();
+            state.mic_input.set_latched(open);
+            crate::commands::mic_commands::sync_mic_open(&state)?;
+            let _ = app.emit("mic_open_changed", serde_json::json!({ "open": open }));
+        }
+        HotkeyAction::StopAllCarts => {
+            let state = app.state::<AppState>();
+            state.engine.lock().unwrap().stop_all_carts()?;
+        }
+        HotkeyAction::PushToTalk | HotkeyAction::TriggerCart { .. } => {}
+    }
+    Ok(())
+}
+
+pub(crate) fn set_ptt(app: &AppHandle, active: bool) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    state.mic_input.set_ptt(active);
+    crate::commands::mic_commands::sync_mic_open(&state)?;
+    let _ = app.emit("ptt_state_changed", serde_json::json!({ "active": active }));
+    Ok(())
+}
+
+pub(crate) fn start_crossfade(state: &AppState) -> Result<(), String> {
+    let mut engine = state.engine.lock().unwrap();
+    let playing = |deck| {
+        engine
+            .get_deck_state(deck)
+            .is_some_and(|s| s.state == "playing")
+    };
+    let (outgoing, incoming) = match (playing(DeckId::DeckA), playing(DeckId::DeckB)) {
+        (true, false) => (DeckId::DeckA, DeckId::DeckB),
+        (false, true) => (DeckId::DeckB, DeckId::DeckA),
+        _ => return Err("Crossfade needs exactly one of Deck A/B playing".to_string()),
+    };
+    engine
+        .start_crossfade(outgoing, incoming)
+        .map_err(Into::into)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn claim(chord: &str, owner: &str) -> (String, String) {
+        (chord.to_string(), owner.to_string())
+    }
+
+    #[test]
+    fn conflicts_match_equivalent_chords() {
+        let conflicts = find_conflicts(&[
+            claim("Control+Shift+1", "play/pause deck_a"),
+            claim("shift+ctrl+Digit1", "cart 1/1"),
+            claim("Control+Shift+2", "play/pause deck_b"),
+            claim("not a chord", "toggle mic"),
+        ]);
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(
+            conflicts[0].owners,
+            vec!["play/pause deck_a".to_string(), "cart 1/1".to_string()]
+        );
+    }
+
+    #[test]
+    fn rejects_bad_hot_cue_slot() {
+        let binding = HotkeyBinding {
+            action: HotkeyAction::HotCue {
+                deck: DeckId::DeckA,
+                slot: 9,
+            },
+            chord: "F9".to_string(),
+            enabled: true,
+        };
+        assert!(validate(&[binding]).is_err());
+    }
+}
@@ -0,0 +1,878 @@
+/// Generic, user-editable MIDI mappings.
+///
+/// A [`MidiProfile`] binds any note / CC / pitch-bend control to a
+/// [`MappedAction`] and optionally drives LEDs from engine state. Profiles are
+/// built in the UI with learn mode (raw events are streamed to the frontend
+/// while it is on) and stored as JSON in SQLite. The hardcoded Starlight
+/// decoder in `decode.rs` stays the default profile.
+use std::collections::{HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+
+use crate::audio::{crossfade::DeckId, dsp::eq::EqBand};
+use crate::commands::cue_commands::{HOT_CUE_MAX_SLOT, HOT_CUE_MIN_SLOT};
+
+use super::types::{ControllerAction, STARLIGHT_PROFILE};
+
+const DEFAULT_TEMPO_RANGE_PCT: f32 = 8.0;
+const BUTTON_THRESHOLD: u8 = 0x40;
+/// Sampler pads: deck A fires carts 0–7, deck B carts 8–15.
+const SAMPLER_PADS_PER_DECK: u8 = 8;
+const SLIP_CENSOR_PAD: u8 = 0;
+const SLIP_REVERSE_PAD: u8 = 1;
+/// Meter floor for VU LEDs
+const VU_FLOOR_DB: f32 = -48.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MidiKind {
+    Note,
+    Cc,
+    PitchBend,
+}
+
+/// One physical control, independent of its current value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct MidiControl {
+    pub kind: MidiKind,
+    /// 0–15
+    pub channel: u8,
+    /// Note or CC number; 0 for pitch bend
+    #[serde(default)]
+    pub number: u8,
+}
+
+/// A decoded inbound message.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MidiMessage {
+    pub control: MidiControl,
+    /// 7-bit value (velocity / CC value / pitch-bend MSB)
+    pub value: u8,
+    /// 0.0–1.0; pitch bend uses all 14 bits
+    pub normalized: f32,
+}
+
+impl MidiMessage {
+    pub fn parse(message: &[u8]) -> Option<Self> {
+        if message.len() < 3 {
+            return None;
+        }
+        let channel = message[0] & 0x0F;
+        let (data1, data2) = (message[1] & 0x7F, message[2] & 0x7F);
+        let (kind, number, value, normalized) = match message[0] & 0xF0 {
+            0x80 => (MidiKind::Note, data1, 0, 0.0),
+            0x90 => (MidiKind::Note, data1, data2, data2 as f32 / 127.0),
+            0xB0 => (MidiKind::Cc, data1, data2, data2 as f32 / 127.0),
+            0xE0 => {
+                let value14 = ((data2 as u16) << 7) | data1 as u16;
+                (MidiKind::PitchBend, 0, data2, value14 as f32 / 16383.0)
+            }
+            _ => return None,
+        };
+        Some(Self {
+            control: MidiControl {
+                kind,
+                channel,
+                number,
+            },
+            value,
+            normalized,
+        })
+    }
+
+    fn pressed(&self) -> bool {
+        self.value >= BUTTON_THRESHOLD || (self.control.kind == MidiKind::Note && self.value > 0)
+    }
+}
+
+impl MidiControl {
+    /// Outbound message setting this control (LED) to `value`.
+    pub fn message(&self, value: u8) -> Option<[u8; 3]> {
+        let value = value & 0x7F;
+        match self.kind {
+            MidiKind::Note => Some([0x90 | self.channel, self.number, value]),
+            MidiKind::Cc => Some([0xB0 | self.channel, self.number, value]),
+            MidiKind::PitchBend => None,
+        }
+    }
+}
+
+/// What the performance pads of a deck do.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PadMode {
+    /// Pad n triggers hot cue n + 1; with shift it sets it
+    #[default]
+    HotCue,
+    /// Pad n sets a 2^n beat loop; with shift it clears the loop
+    BeatLoop,
+    /// Pads fire carts on the active page; with shift they stop them
+    Sampler,
+    /// Pad 1 censors while held, pad 2 toggles reverse
+    Slip,
+}
+
+/// Relative encoder formats used by jog wheels.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JogEncoding {
+    /// 1–63 forward, 65–127 backward (Hercules, Pioneer)
+    #[default]
+    TwosComplement,
+    /// 64 is rest; above forward, below backward (Native Instruments)
+    BinaryOffset,
+    /// Bit 6 set means backward
+    SignMagnitude,
+}
+
+impl JogEncoding {
+    pub fn delta(self, value: u8) -> i8 {
+        let value = value & 0x7F;
+        match self {
+            JogEncoding::TwosComplement if value < 0x40 => value as i8,
+            JogEncoding::TwosComplement => (value as i16 - 0x80) as i8,
+            JogEncoding::BinaryOffset => value as i8 - 0x40,
+            JogEncoding::SignMagnitude if value & 0x40 != 0 => -((value & 0x3F) as i8),
+            JogEncoding::SignMagnitude => value as i8,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum MappedAction {
+    /// Modifier: selects `shift` bindings while held
+    Shift,
+    TogglePlay {
+        deck: DeckId,
+    },
+    CueToStart {
+        deck: DeckId,
+    },
+    /// Headphone (PFL) cue
+    ToggleCue {
+        deck: DeckId,
+    },
+    SyncToOther {
+        deck: DeckId,
+    },
+    HotCue {
+        deck: DeckId,
+        slot: u8,
+    },
+    HotCueSet {
+        deck: DeckId,
+        slot: u8,
+    },
+    BeatLoop {
+        deck: DeckId,
+        beats: u8,
+    },
+    ClearLoop {
+        deck: DeckId,
+    },
+    /// Toggle reverse playback
+    Reverse {
+        deck: DeckId,
+    },
+    /// Momentary: slip-reverse while held
+    Censor {
+        deck: DeckId,
+    },
+    /// Toggle slip mode
+    Slip {
+        deck: DeckId,
+    },
+    Tempo {
+        deck: DeckId,
+        /// Fader travel in ± percent
+        #[serde(default = "default_tempo_range")]
+        range_pct: f32,
+    },
+    Gain {
+        deck: DeckId,
+    },
+    Bass {
+        deck: DeckId,
+    },
+    Filter {
+        deck: DeckId,
+    },
+    EqKill {
+        deck: DeckId,
+        band: EqBand,
+    },
+    Crossfader,
+    MasterVolume,
+    HeadphoneMix,
+    HeadphoneLevel,
+    Jog {
+        deck: DeckId,
+        #[serde(default)]
+        encoding: JogEncoding,
+    },
+    /// Cart slot on the active cart wall page
+    TriggerCart {
+        slot: u8,
+    },
+    StopCart {
+        slot: u8,
+    },
+    /// Switch the deck's pads to `mode` (for controllers whose pads send the
+    /// same notes in every mode)
+    PadMode {
+        deck: DeckId,
+        mode: PadMode,
+    },
+    /// Performance pad `index` (0-based), resolved through the deck's pad mode
+    Pad {
+        deck: DeckId,
+        index: u8,
+    },
+    /// Browse encoder: moves the selection in the play queue
+    Browse {
+        #[serde(default)]
+        encoding: JogEncoding,
+    },
+    /// Load the browsed queue entry onto a stopped deck
+    BrowseLoad {
+        deck: DeckId,
+    },
+}
+
+fn default_tempo_range() -> f32 {
+    DEFAULT_TEMPO_RANGE_PCT
+}
+
+impl MappedAction {
+    fn is_continuous(&self) -> bool {
+        matches!(
+            self,
+            MappedAction::Tempo { .. }
+                | MappedAction::Gain { .. }
+                | MappedAction::Bass { .. }
+                | MappedAction::Filter { .. }
+                | MappedAction::Crossfader
+                | MappedAction::MasterVolume
+                | MappedAction::HeadphoneMix
+                | MappedAction::HeadphoneLevel
+                | MappedAction::Jog { .. }
+                | MappedAction::Browse { .. }
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MidiBinding {
+    pub control: MidiControl,
+    /// Only active while a `Shift` control is held
+    #[serde(default)]
+    pub shift: bool,
+    pub action: MappedAction,
+    /// Reverse fader / knob direction
+    #[serde(default)]
+    pub invert: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "source", rename_all = "snake_case")]
+pub enum LedSource {
+    Playing {
+        deck: DeckId,
+    },
+    CuePreview {
+        deck: DeckId,
+    },
+    LoopActive {
+        deck: DeckId,
+    },
+    /// Deck is reversed or censoring
+    Reversing {
+        deck: DeckId,
+    },
+    /// Cart slot on the active page is sounding
+    CartPlaying {
+        slot: u8,
+    },
+    MicOpen,
+    Shift,
+    PadMode {
+        deck: DeckId,
+        mode: PadMode,
+    },
+    /// Level meter; `deck: None` is the master bus. Lights between
+    /// `off_value` and `on_value` in proportion to the level.
+    VuMeter {
+        #[serde(default)]
+        deck: Option<DeckId>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LedMapping {
+    pub source: LedSource,
+    pub control: MidiControl,
+    #[serde(default = "default_led_on")]
+    pub on_value: u8,
+    #[serde(default)]
+    pub off_value: u8,
+}
+
+fn default_led_on() -> u8 {
+    0x7F
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MidiProfile {
+    pub id: String,
+    pub name: String,
+    /// Case-insensitive substring of the MIDI port name, used to pick the
+    /// port on auto-connect
+    #[serde(default)]
+    pub device_name_hint: Option<String>,
+    #[serde(default)]
+    pub bindings: Vec<MidiBinding>,
+    #[serde(default)]
+    pub leds: Vec<LedMapping>,
+}
+
+impl MidiProfile {
+    pub fn validate(&self) -> Result<(), String> {
+        let id = self.id.trim();
+        if id.is_empty()
+            || !id
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+        {
+            return Err("Invalid profile id: use letters, digits, '-' or '_'".to_string());
+        }
+        if id == STARLIGHT_PROFILE || super::profiles::builtin(id).is_some() {
+            return Err(format!("Profile id {id} is reserved"));
+        }
+        if self.name.trim().is_empty() {
+            return Err("Profile name is required".to_string());
+        }
+        let controls = self
+            .bindings
+            .iter()
+            .map(|b| b.control)
+            .chain(self.leds.iter().map(|l| l.control));
+        for control in controls {
+            if control.channel > 15 || control.number > 127 {
+                return Err(format!(
+                    "Invalid MIDI control: channel {} number {}",
+                    control.channel, control.number
+                ));
+            }
+        }
+        let mut seen = HashSet::new();
+        for binding in &self.bindings {
+            if !seen.insert((binding.control, binding.shift)) {
+                return Err(format!(
+                    "MIDI {:?} {} on channel {} is already mapped{}",
+                    binding.control.kind,
+                    binding.control.number,
+                    binding.control.channel + 1,
+                    if binding.shift { " (shift)" } else { "" }
+                ));
+            }
+            match binding.action {
+                MappedAction::HotCue { slot, .. } | MappedAction::HotCueSet { slot, .. }
+                    if !(HOT_CUE_MIN_SLOT..=HOT_CUE_MAX_SLOT).contains(&slot) =>
+                {
+                    return Err(format!(
+                        "Hot cue slot must be between {HOT_CUE_MIN_SLOT} and {HOT_CUE_MAX_SLOT}"
+                    ));
+                }
+                MappedAction::Pad { index, .. } if index >= SAMPLER_PADS_PER_DECK => {
+                    return Err(format!("Pad index must be below {SAMPLER_PADS_PER_DECK}"));
+                }
+                MappedAction::BeatLoop { beats: 0, .. } => {
+                    return Err("Beat loop length must be at least 1 beat".to_string());
+                }
+                MappedAction::Tempo { range_pct, .. } if !(1.0..=100.0).contains(&range_pct) => {
+                    return Err("Tempo range must be between 1 and 100 %".to_string());
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn binding(&self, control: MidiControl, shift: bool) -> Option<&MidiBinding> {
+        let find = |shift| {
+            self.bindings
+                .iter()
+                .find(|b| b.control == control && b.shift == shift)
+        };
+        // Shifted controls without a shift binding keep their normal action.
+        if shift {
+            find(true).or_else(|| find(false))
+        } else {
+            find(false)
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct MappingState {
+    pub shift_pressed: bool,
+    pub pad_modes: HashMap<DeckId, PadMode>,
+}
+
+pub fn decode(
+    profile: &MidiProfile,
+    state: &mut MappingState,
+    message: &[u8],
+) -> Vec<ControllerAction> {
+    let Some(input) = MidiMessage::parse(message) else {
+        return Vec::new();
+    };
+    let Some(binding) = profile.binding(input.control, state.shift_pressed) else {
+        return Vec::new();
+    };
+    if binding.action == MappedAction::Shift {
+        state.shift_pressed = input.pressed();
+        return Vec::new();
+    }
+    // Momentary controls also act on release.
+    let censor_deck = match binding.action {
+        MappedAction::Censor { deck } => Some(deck),
+        MappedAction::Pad { deck, index }
+            if index == SLIP_CENSOR_PAD
+                && state.pad_modes.get(&deck).copied() == Some(PadMode::Slip) =>
+        {
+            Some(deck)
+        }
+        _ => None,
+    };
+    if let Some(deck) = censor_deck {
+        return vec![ControllerAction::Censor {
+            deck,
+            active: input.pressed(),
+        }];
+    }
+    if !binding.action.is_continuous() && !input.pressed() {
+        return Vec::new();
+    }
+    match binding.action {
+        MappedAction::PadMode { deck, mode } => {
+            state.pad_modes.insert(deck, mode);
+            return Vec::new();
+        }
+        MappedAction::Pad { deck, index } => {
+            let mode = state.pad_modes.get(&deck).copied().unwrap_or_default();
+            return pad_action(deck, index, mode, state.shift_pressed)
+                .into_iter()
+                .collect();
+        }
+        _ => {}
+    }
+    let normalized = if binding.invert {
+        1.0 - input.normalized
+    } else {
+        input.normalized
+    }
+    .clamp(0.0, 1.0);
+    to_controller_action(&binding.action, input.value, normalized)
+        .into_iter()
+        .collect()
+}
+
+fn pad_action(deck: DeckId, index: u8, mode: PadMode, shift: bool) -> Option<ControllerAction> {
+    if index >= SAMPLER_PADS_PER_DECK {
+        return None;
+    }
+    Some(match (mode, shift) {
+        (PadMode::HotCue, false) => ControllerAction::HotCueTrigger {
+            deck,
+            slot: index + 1,
+        },
+        (PadMode::HotCue, true) => ControllerAction::HotCueSet {
+            deck,
+            slot: index + 1,
+        },
+        (PadMode::BeatLoop, false) => ControllerAction::SetBeatLoop {
+            deck,
+            beats: 1 << index,
+        },
+        (PadMode::BeatLoop, true) => ControllerAction::ClearLoop { deck },
+        (PadMode::Slip, _) => match index {
+            SLIP_CENSOR_PAD => ControllerAction::Censor { deck, active: true },
+            SLIP_REVERSE_PAD => ControllerAction::ToggleReverse { deck },
+            _ => return None,
+        },
+        (PadMode::Sampler, shift) => {
+            let base = if deck == DeckId::DeckB {
+                SAMPLER_PADS_PER_DECK
+            } else {
+                0
+            };
+            let slot = base + index;
+            if shift {
+                ControllerAction::StopCart { slot }
+            } else {
+                ControllerAction::TriggerCart { slot }
+            }
+        }
+    })
+}
+
+fn to_controller_action(
+    action: &MappedAction,
+    value: u8,
+    normalized: f32,
+) -> Option<ControllerAction> {
+    let bipolar = normalized * 2.0 - 1.0;
+    Some(match *action {
+        MappedAction::Shift => return None,
+        MappedAction::TogglePlay { deck } => ControllerAction::TogglePlay { deck },
+        MappedAction::CueToStart { deck } => ControllerAction::CueToStart { deck },
+        MappedAction::ToggleCue { deck } => ControllerAction::ToggleCue { deck },
+        MappedAction::SyncToOther { deck } => ControllerAction::SyncToOther { deck },
+        MappedAction::HotCue { deck, slot } => ControllerAction::HotCueTrigger { deck, slot },
+        MappedAction::HotCueSet { deck, slot } => ControllerAction::HotCueSet { deck, slot },
+        MappedAction::BeatLoop { deck, beats } => ControllerAction::SetBeatLoop { deck, beats },
+        MappedAction::ClearLoop { deck } => ControllerAction::ClearLoop { deck },
+        MappedAction::Reverse { deck } => ControllerAction::ToggleReverse { deck },
+        MappedAction::Censor { deck } => ControllerAction::Censor { deck, active: true },
+        MappedAction::Slip { deck } => ControllerAction::ToggleSlip { deck },
+        MappedAction::Tempo { deck, range_pct } => ControllerAction::SetTempo {
+            deck,
+            tempo_pct: bipolar * range_pct,
+            normalized,
+        },
+        MappedAction::Gain { deck } => ControllerAction::SetGain {
+            deck,
+            gain: normalized,
+            normalized,
+        },
+        MappedAction::Bass { deck } => ControllerAction::SetBass {
+            deck,
+            bass_db: normalized * 24.0 - 12.0,
+            normalized,
+        },
+        MappedAction::Filter { deck } => ControllerAction::SetFilter {
+            deck,
+            amount: bipolar,
+            normalized,
+        },
+        MappedAction::EqKill { deck, band } => ControllerAction::ToggleEqKill { deck, band },
+        MappedAction::Crossfader => ControllerAction::SetCrossfader {
+            position: bipolar,
+            normalized,
+        },
+        MappedAction::MasterVolume => ControllerAction::SetMasterVolume {
+            level: normalized,
+            normalized,
+        },
+        MappedAction::HeadphoneMix => ControllerAction::SetHeadphoneMix {
+            value: bipolar,
+            normalized,
+        },
+        MappedAction::HeadphoneLevel => ControllerAction::SetHeadphoneLevel {
+            level: normalized,
+            normalized,
+        },
+        MappedAction::Jog { deck, encoding } => {
+            let delta_steps = encoding.delta(value);
+            if delta_steps == 0 {
+                return None;
+            }
+            ControllerAction::JogNudge { deck, delta_steps }
+        }
+        MappedAction::TriggerCart { slot } => ControllerAction::TriggerCart { slot },
+        MappedAction::StopCart { slot } => ControllerAction::StopCart { slot },
+        MappedAction::Browse { encoding } => {
+            let delta = encoding.delta(value);
+            if delta == 0 {
+                return None;
+            }
+            ControllerAction::BrowseQueue { delta }
+        }
+        MappedAction::BrowseLoad { deck } => ControllerAction::LoadBrowsed { deck },
+        // Resolved against `MappingState` in `decode`.
+        MappedAction::PadMode { .. } | MappedAction::Pad { .. } => return None,
+    })
+}
+
+/// Resolve the profile selected by `profile_id`: built-in profiles first,
+/// then custom ones from SQLite. Returns `Ok(None)` for the Starlight profile,
+/// which keeps its dedicated decoder.
+pub async fn load_profile(
+    pool: &sqlx::SqlitePool,
+    profile_id: &str,
+) -> Result<Option<MidiProfile>, String> {
+    if profile_id == STARLIGHT_PROFILE {
+        return Ok(None);
+    }
+    if let Some(profile) = super::profiles::builtin(profile_id) {
+        return Ok(Some(profile));
+    }
+    let json = crate::db::local::get_controller_profile(pool, profile_id)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Controller profile {profile_id} not found"))?;
+    serde_json::from_str(&json)
+        .map(Some)
+        .map_err(|e| format!("Invalid controller profile {profile_id}: {e}"))
+}
+
+/// Engine state the LED outputs are derived from.
+#[derive(Debug, Default)]
+pub struct LedSnapshot {
+    pub playing: HashSet<DeckId>,
+    pub cue_preview: HashSet<DeckId>,
+    pub looping: HashSet<DeckId>,
+    pub reversing: HashSet<DeckId>,
+    /// Sounding cart slots on the active page
+    pub carts: HashSet<u32>,
+    pub mic_open: bool,
+    pub shift: bool,
+    pub pad_modes: HashMap<DeckId, PadMode>,
+    /// Peak level in dBFS per deck; `None` is the master bus
+    pub vu_db: HashMap<Option<DeckId>, f32>,
+}
+
+impl LedSnapshot {
+    /// Outbound value for one LED.
+    pub fn value(&self, led: &LedMapping) -> u8 {
+        let lit = match &led.source {
+            LedSource::Playing { deck } => self.playing.contains(deck),
+            LedSource::CuePreview { deck } => self.cue_preview.contains(deck),
+            LedSource::LoopActive { deck } => self.looping.contains(deck),
+            LedSource::Reversing { deck } => self.reversing.contains(deck),
+            LedSource::CartPlaying { slot } => self.carts.contains(&(*slot as u32)),
+            LedSource::MicOpen => self.mic_open,
+            LedSource::Shift => self.shift,
+            LedSource::PadMode { deck, mode } => {
+                self.pad_modes.get(deck).copied().unwrap_or_default() == *mode
+            }
+            LedSource::VuMeter { deck } => {
+                let db = self.vu_db.get(deck).copied().unwrap_or(VU_FLOOR_DB);
+                let level = ((db - VU_FLOOR_DB) / -VU_FLOOR_DB).clamp(0.0, 1.0);
+                let span = led.on_value.saturating_sub(led.off_value) as f32;
+                return led.off_value + (level * span).round() as u8;
+            }
+        };
+        if lit {
+            led.on_value
+        } else {
+            led.off_value
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn note(channel: u8, number: u8) -> MidiControl {
+        MidiControl {
+            kind: MidiKind::Note,
+            channel,
+            number,
+        }
+    }
+
+    fn cc(channel: u8, number: u8) -> MidiControl {
+        MidiControl {
+            kind: MidiKind::Cc,
+            channel,
+            number,
+        }
+    }
+
+    fn profile() -> MidiProfile {
+        MidiProfile {
+            id: "custom".to_string(),
+            name: "Custom".to_string(),
+            device_name_hint: None,
+            bindings: vec![
+                MidiBinding {
+                    control: note(0, 0x40),
+                    shift: false,
+                    action: MappedAction::Shift,
+                    invert: false,
+                },
+                MidiBinding {
+                    control: note(0, 0x0B),
+                    shift: false,
+                    action: MappedAction::HotCue {
+                        deck: DeckId::DeckA,
+                        slot: 1,
+                    },
+                    invert: false,
+                },
+                MidiBinding {
+                    control: note(0, 0x0B),
+                    shift: true,
+                    action: MappedAction::HotCueSet {
+                        deck: DeckId::DeckA,
+                        slot: 1,
+                    },
+                    invert: false,
+                },
+                MidiBinding {
+                    control: cc(1, 0x13),
+                    shift: false,
+                    action: MappedAction::Crossfader,
+                    invert: true,
+                },
+                MidiBinding {
+                    control: cc(0, 0x21),
+                    shift: false,
+                    action: MappedAction::Jog {
+                        deck: DeckId::DeckB,
+                        encoding: JogEncoding::BinaryOffset,
+                    },
+                    invert: false,
+                },
+            ],
+            leds: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn shift_selects_shift_binding() {
+        let profile = profile();
+        let mut state = MappingState::default();
+        let plain = decode(&profile, &mut state, &[0x90, 0x0B, 0x7F]);
+        assert!(matches!(
+            plain.first(),
+            Some(ControllerAction::HotCueTrigger { slot: 1, .. })
+        ));
+        decode(&profile, &mut state, &[0x90, 0x40, 0x7F]);
+        let shifted = decode(&profile, &mut state, &[0x90, 0x0B, 0x7F]);
+        assert!(matches!(
+            shifted.first(),
+            Some(ControllerAction::HotCueSet { slot: 1, .. })
+        ));
+        decode(&profile, &mut state, &[0x80, 0x40, 0x00]);
+        assert!(!state.shift_pressed);
+        // Release of a button does nothing.
+        assert!(decode(&profile, &mut state, &[0x80, 0x0B, 0x00]).is_empty());
+    }
+
+    #[test]
+    fn continuous_controls_and_jog() {
+        let profile = profile();
+        let mut state = MappingState::default();
+        let xfade = decode(&profile, &mut state, &[0xB1, 0x13, 0x00]);
+        assert!(matches!(
+            xfade.first(),
+            Some(ControllerAction::SetCrossfader { position, .. }) if (*position - 1.0).abs() < 0.001
+        ));
+        let jog = decode(&profile, &mut state, &[0xB0, 0x21, 0x3E]);
+        assert!(matches!(
+            jog.first(),
+            Some(ControllerAction::JogNudge {
+                delta_steps: -2,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn pads_follow_pad_mode() {
+        let mut profile = profile();
+        profile.bindings.push(MidiBinding {
+            control: note(1, 0x00),
+            shift: false,
+            action: MappedAction::PadMode {
+                deck: DeckId::DeckB,
+                mode: PadMode::Sampler,
+            },
+            invert: false,
+        });
+        profile.bindings.push(MidiBinding {
+            control: note(1, 0x14),
+            shift: false,
+            action: MappedAction::Pad {
+                deck: DeckId::DeckB,
+                index: 2,
+            },
+            invert: false,
+        });
+        let mut state = MappingState::default();
+        let hot_cue = decode(&profile, &mut state, &[0x91, 0x14, 0x7F]);
+        assert!(matches!(
+            hot_cue.first(),
+            Some(ControllerAction::HotCueTrigger { slot: 3, .. })
+        ));
+        decode(&profile, &mut state, &[0x91, 0x00, 0x7F]);
+        let cart = decode(&profile, &mut state, &[0x91, 0x14, 0x7F]);
+        assert!(matches!(
+            cart.first(),
+            Some(ControllerAction::TriggerCart { slot: 10 })
+        ));
+    }
+
+    #[test]
+    fn slip_pads_censor_while_held() {
+        let mut profile = profile();
+        for index in [0, 1] {
+            profile.bindings.push(MidiBinding {
+                control: note(1, 0x14 + index),
+                shift: false,
+                action: MappedAction::Pad {
+                    deck: DeckId::DeckB,
+                    index,
+                },
+                invert: false,
+            });
+        }
+        let mut state = MappingState::default();
+        state.pad_modes.insert(DeckId::DeckB, PadMode::Slip);
+
+        let press = decode(&profile, &mut state, &[0x91, 0x14, 0x7F]);
+        let release = decode(&profile, &mut state, &[0x81, 0x14, 0x00]);
+        assert!(matches!(
+            press.first(),
+            Some(ControllerAction::Censor { active: true, .. })
+        ));
+        assert!(matches!(
+            release.first(),
+            Some(ControllerAction::Censor { active: false, .. })
+        ));
+        let reverse = decode(&profile, &mut state, &[0x91, 0x15, 0x7F]);
+        assert!(matches!(
+            reverse.first(),
+            Some(ControllerAction::ToggleReverse {
+                deck: DeckId::DeckB
+            })
+        ));
+        assert!(decode(&profile, &mut state, &[0x81, 0x15, 0x00]).is_empty());
+    }
+
+    #[test]
+    fn vu_led_scales_between_values() {
+        let led = LedMapping {
+            source: LedSource::VuMeter { deck: None },
+            control: cc(0, 0x02),
+            on_value: 100,
+            off_value: 0,
+        };
+        let mut snapshot = LedSnapshot::default();
+        assert_eq!(snapshot.value(&led), 0);
+        snapshot.vu_db.insert(None, 0.0);
+        assert_eq!(snapshot.value(&led), 100);
+        snapshot.vu_db.insert(None, -24.0);
+        assert_eq!(snapshot.value(&led), 50);
+    }
+
+    #[test]
+    fn jog_encodings() {
+        assert_eq!(JogEncoding::TwosComplement.delta(0x01), 1);
+        assert_eq!(JogEncoding::TwosComplement.delta(0x7F), -1);
+        assert_eq!(JogEncoding::BinaryOffset.delta(0x41), 1);
+        assert_eq!(JogEncoding::SignMagnitude.delta(0x42), -2);
+    }
+
+    #[test]
+    fn rejects_duplicate_bindings() {
+        let mut profile = profile();
+        profile.bindings.push(profile.bindings[1].clone());
+        assert!(profile.validate().is_err());
+    }
+}
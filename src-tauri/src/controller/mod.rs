@@ -1,5 +1,9 @@
 pub mod decode;
 pub mod executor;
+pub mod hotkeys;
+pub mod mapping;
+pub mod osc;
+pub mod profiles;
 pub mod service;
 pub mod starlight_profile;
 pub mod types;
@@ -0,0 +1,342 @@
+/// OSC address map.
+///
+/// Addresses mirror the Tauri command set so a TouchOSC / QLab layout reads
+/// like the frontend calls (`/deck/deck_a/play` ↔ `play_deck("deck_a")`).
+/// `<deck>` accepts the full id (`deck_a`, `sound_fx`, …) or `a` / `b`.
+///
+///   /deck/<deck>/play | pause | stop | next | toggle_play | cue_to_start
+///   /deck/<deck>/seek          <ms>
+///   /deck/<deck>/gain          <0..1>
+///   /deck/<deck>/bass          <dB>
+///   /deck/<deck>/filter        <-1..1>
+///   /deck/<deck>/pitch         <%>
+///   /deck/<deck>/tempo         <%>
+///   /deck/<deck>/cue           <bool>
+///   /deck/<deck>/eq_kill/<low|mid|high> <bool>
+///   /deck/<deck>/hot_cue/<1-8>       trigger
+///   /deck/<deck>/hot_cue/<1-8>/set   store at the current position
+///   /deck/<deck>/loop/<beats>  |  /deck/<deck>/loop/clear
+///   /crossfader                <-1..1>
+///   /crossfade/start           fade out of whichever of Deck A/B is playing
+///   /master/level              <0..1>
+///   /headphone/mix             <-1..1>
+///   /headphone/level           <0..1>
+///   /cart/<slot>/trigger | /cart/<slot>/stop | /cart/stop_all
+///   /mic/open                  <bool>
+///   /mic/ptt                   <bool>
+///
+/// Buttons on most surfaces send 1 on press and 0 on release; trigger
+/// addresses act on the press (or on a message with no argument) and ignore
+/// the release.
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::{
+    audio::{cart_wall::CartKey, crossfade::DeckId, deck::StopReason, dsp::eq::EqBand},
+    commands::{
+        audio_commands::parse_deck,
+        cue_commands::{HOT_CUE_MAX_SLOT, HOT_CUE_MIN_SLOT},
+    },
+    controller::{executor::execute_action, hotkeys, types::ControllerAction},
+    state::AppState,
+};
+
+use super::codec::{OscArg, OscMessage};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum OscCommand {
+    /// Anything the controller executor already knows how to do
+    Action(ControllerAction),
+    Play(DeckId),
+    Pause(DeckId),
+    Stop(DeckId),
+    Next(DeckId),
+    Seek(DeckId, u64),
+    Pitch(DeckId, f32),
+    CueEnabled(DeckId, bool),
+    EqKill(DeckId, EqBand, bool),
+    StartCrossfade,
+    TriggerCart(u32),
+    StopCart(u32),
+    StopAllCarts,
+    MicOpen(bool),
+    PushToTalk(bool),
+}
+
+/// Map a message onto a command. `Ok(None)` means the address is known but
+/// the message should be ignored (a button release).
+pub fn parse(msg: &OscMessage) -> Result<Option<OscCommand>, String> {
+    let parts: Vec<&str> = msg.address.trim_matches('/').split('/').collect();
+    let arg = msg.args.first();
+    let value = || {
+        arg.and_then(OscArg::as_f32)
+            .filter(|v| v.is_finite())
+            .ok_or_else(|| format!("{} needs a numeric argument", msg.address))
+    };
+    let flag = || value().map(|v| v >= 0.5);
+    // Trigger addresses fire on press, or when sent without arguments.
+    let pressed = arg.and_then(OscArg::as_f32).is_none_or(|v| v >= 0.5);
+    let trigger = |cmd: OscCommand| Ok(pressed.then_some(cmd));
+
+    let cmd = match parts.as_slice() {
+        ["deck", deck, rest @ ..] => {
+            let deck = parse_osc_deck(deck)?;
+            match rest {
+                ["play"] => return trigger(OscCommand::Play(deck)),
+                ["pause"] => return trigger(OscCommand::Pause(deck)),
+                ["stop"] => return trigger(OscCommand::Stop(deck)),
+                ["next"] => return trigger(OscCommand::Next(deck)),
+                ["toggle_play"] => {
+                    return trigger(OscCommand::Action(ControllerAction::TogglePlay { deck }))
+                }
+                ["cue_to_start"] => {
+                    return trigger(OscCommand::Action(ControllerAction::CueToStart { deck }))
+                }
+                ["seek"] => OscCommand::Seek(deck, value()?.max(0.0) as u64),
+                ["gain"] => {
+                    let gain = value()?.clamp(0.0, 1.0);
+                    OscCommand::Action(ControllerAction::SetGain {
+                        deck,
+                        gain,
+                        normalized: gain,
+                    })
+                }
+                ["bass"] => {
+                    let bass_db = value()?.clamp(-12.0, 12.0);
+                    OscCommand::Action(ControllerAction::SetBass {
+                        deck,
+                        bass_db,
+                        normalized: (bass_db + 12.0) / 24.0,
+                    })
+                }
+                ["filter"] => {
+                    let amount = value()?.clamp(-1.0, 1.0);
+                    OscCommand::Action(ControllerAction::SetFilter {
+                        deck,
+                        amount,
+                        normalized: (amount + 1.0) / 2.0,
+                    })
+                }
+                ["pitch"] => OscCommand::Pitch(deck, value()?),
+                ["tempo"] => {
+                    let tempo_pct = value()?.clamp(-8.0, 8.0);
+                    OscCommand::Action(ControllerAction::SetTempo {
+                        deck,
+                        tempo_pct,
+                        normalized: (tempo_pct + 8.0) / 16.0,
+                    })
+                }
+                ["cue"] => OscCommand::CueEnabled(deck, flag()?),
+                ["eq_kill", band] => OscCommand::EqKill(deck, parse_band(band)?, flag()?),
+                ["hot_cue", slot] => {
+                    let slot = parse_hot_cue_slot(slot)?;
+                    return trigger(OscCommand::Action(ControllerAction::HotCueTrigger {
+                        deck,
+                        slot,
+                    }));
+                }
+                ["hot_cue", slot, "set"] => {
+                    let slot = parse_hot_cue_slot(slot)?;
+                    return trigger(OscCommand::Action(ControllerAction::HotCueSet {
+                        deck,
+                        slot,
+                    }));
+                }
+                ["loop", "clear"] => {
+                    return trigger(OscCommand::Action(ControllerAction::ClearLoop { deck }))
+                }
+                ["loop", beats] => {
+                    let beats = beats
+                        .parse::<u8>()
+                        .ok()
+                        .filter(|b| *b > 0)
+                        .ok_or_else(|| format!("Invalid loop length: {beats}"))?;
+                    return trigger(OscCommand::Action(ControllerAction::SetBeatLoop {
+                        deck,
+                        beats,
+                    }));
+                }
+                _ => return Err(format!("Unknown OSC address: {}", msg.address)),
+            }
+        }
+        ["crossfader"] => {
+            let position = value()?.clamp(-1.0, 1.0);
+            OscCommand::Action(ControllerAction::SetCrossfader {
+                position,
+                normalized: (position + 1.0) / 2.0,
+            })
+        }
+        ["crossfade", "start"] => return trigger(OscCommand::StartCrossfade),
+        ["master", "level"] => {
+            let level = value()?.clamp(0.0, 1.0);
+            OscCommand::Action(ControllerAction::SetMasterVolume {
+                level,
+                normalized: level,
+            })
+        }
+        ["headphone", "mix"] => {
+            let mix = value()?.clamp(-1.0, 1.0);
+            OscCommand::Action(ControllerAction::SetHeadphoneMix {
+                value: mix,
+                normalized: (mix + 1.0) / 2.0,
+            })
+        }
+        ["headphone", "level"] => {
+            let level = value()?.clamp(0.0, 1.0);
+            OscCommand::Action(ControllerAction::SetHeadphoneLevel {
+                level,
+                normalized: level,
+            })
+        }
+        ["cart", "stop_all"] => return trigger(OscCommand::StopAllCarts),
+        ["cart", slot, "trigger"] => {
+            return trigger(OscCommand::TriggerCart(parse_cart_slot(slot)?))
+        }
+        ["cart", slot, "stop"] => return trigger(OscCommand::StopCart(parse_cart_slot(slot)?)),
+        ["mic", "open"] => OscCommand::MicOpen(flag()?),
+        ["mic", "ptt"] => OscCommand::PushToTalk(flag()?),
+        _ => return Err(format!("Unknown OSC address: {}", msg.address)),
+    };
+    Ok(Some(cmd))
+}
+
+pub async fn execute(app: &AppHandle, cmd: OscCommand) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    match cmd {
+        OscCommand::Action(action) => execute_action(app.clone(), action).await,
+        OscCommand::Play(deck) => state.engine.lock().unwrap().play(deck)?,
+        OscCommand::Pause(deck) => state.engine.lock().unwrap().pause(deck)?,
+        OscCommand::Stop(deck) => {
+            let mut engine = state.engine.lock().unwrap();
+            let _ = engine.pause(deck);
+            engine.seek(deck, 0)?;
+        }
+        OscCommand::Next(deck) => state
+            .engine
+            .lock()
+            .unwrap()
+            .stop_with_completion(deck, StopReason::Skipped)?,
+        OscCommand::Seek(deck, position_ms) => {
+            state.engine.lock().unwrap().seek(deck, position_ms)?
+        }
+        OscCommand::Pitch(deck, pitch_pct) => state
+            .engine
+            .lock()
+            .unwrap()
+            .set_deck_pitch(deck, pitch_pct)?,
+        OscCommand::CueEnabled(deck, enabled) => state
+            .engine
+            .lock()
+            .unwrap()
+            .set_deck_cue_preview_enabled(deck, enabled)?,
+        OscCommand::EqKill(deck, band, killed) => state
+            .engine
+            .lock()
+            .unwrap()
+            .set_deck_eq_kill(deck, band, killed)?,
+        OscCommand::StartCrossfade => hotkeys::start_crossfade(&state)?,
+        OscCommand::TriggerCart(slot) => {
+            let key = CartKey {
+                page: crate::audio::cart_wall::active_page(),
+                slot,
+            };
+            crate::commands::cart_commands::trigger(&state, key)?;
+        }
+        OscCommand::StopCart(slot) => {
+            let key = CartKey {
+                page: crate::audio::cart_wall::active_page(),
+                slot,
+            };
+            state.engine.lock().unwrap().stop_cart(key)?;
+        }
+        OscCommand::StopAllCarts => state.engine.lock().unwrap().stop_all_carts()?,
+        OscCommand::MicOpen(open) => {
+            state.mic_input.set_latched(open);
+            crate::commands::mic_commands::sync_mic_open(&state)?;
+            let _ = app.emit("mic_open_changed", serde_json::json!({ "open": open }));
+        }
+        OscCommand::PushToTalk(active) => hotkeys::set_ptt(app, active)?,
+    }
+    Ok(())
+}
+
+fn parse_osc_deck(deck: &str) -> Result<DeckId, String> {
+    match deck {
+        "a" | "A" => Ok(DeckId::DeckA),
+        "b" | "B" => Ok(DeckId::DeckB),
+        "c" | "C" => Ok(DeckId::Aux1),
+        "d" | "D" => Ok(DeckId::Aux2),
+        other => parse_deck(other).map_err(Into::into),
+    }
+}
+
+fn parse_band(band: &str) -> Result<EqBand, String> {
+    match band {
+        "low" => Ok(EqBand::Low),
+        "mid" => Ok(EqBand::Mid),
+        "high" => Ok(EqBand::High),
+        _ => Err(format!("Unknown EQ band: {band}")),
+    }
+}
+
+fn parse_hot_cue_slot(slot: &str) -> Result<u8, String> {
+    slot.parse::<u8>()
+        .ok()
+        .filter(|s| (HOT_CUE_MIN_SLOT..=HOT_CUE_MAX_SLOT).contains(s))
+        .ok_or_else(|| {
+            format!("Hot cue slot must be {HOT_CUE_MIN_SLOT}-{HOT_CUE_MAX_SLOT}, got {slot}")
+        })
+}
+
+fn parse_cart_slot(slot: &str) -> Result<u32, String> {
+    slot.parse::<u32>()
+        .map_err(|_| format!("Invalid cart slot: {slot}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn msg(address: &str, args: Vec<OscArg>) -> OscMessage {
+        OscMessage::new(address, args)
+    }
+
+    #[test]
+    fn maps_addresses_to_commands() {
+        assert_eq!(
+            parse(&msg("/deck/a/play", vec![])).unwrap(),
+            Some(OscCommand::Play(DeckId::DeckA))
+        );
+        assert_eq!(
+            parse(&msg("/deck/deck_b/seek", vec![OscArg::Int(15_000)])).unwrap(),
+            Some(OscCommand::Seek(DeckId::DeckB, 15_000))
+        );
+        assert_eq!(
+            parse(&msg("/deck/b/eq_kill/low", vec![OscArg::Bool(true)])).unwrap(),
+            Some(OscCommand::EqKill(DeckId::DeckB, EqBand::Low, true))
+        );
+        assert_eq!(
+            parse(&msg("/crossfader", vec![OscArg::Float(2.0)])).unwrap(),
+            Some(OscCommand::Action(ControllerAction::SetCrossfader {
+                position: 1.0,
+                normalized: 1.0,
+            }))
+        );
+        assert!(parse(&msg("/deck/c/play", vec![])).is_err());
+        assert!(parse(&msg("/deck/a/hot_cue/9", vec![])).is_err());
+        assert!(parse(&msg("/deck/a/gain", vec![])).is_err());
+    }
+
+    #[test]
+    fn button_release_is_ignored() {
+        let press = msg("/deck/a/hot_cue/3", vec![OscArg::Float(1.0)]);
+        let release = msg("/deck/a/hot_cue/3", vec![OscArg::Float(0.0)]);
+        assert_eq!(
+            parse(&press).unwrap(),
+            Some(OscCommand::Action(ControllerAction::HotCueTrigger {
+                deck: DeckId::DeckA,
+                slot: 3,
+            }))
+        );
+        assert_eq!(parse(&release).unwrap(), None);
+    }
+}
@@ -0,0 +1,260 @@
+/// OSC 1.0 wire format: messages and (nested) bundles.
+#[derive(Debug, Clone, PartialEq)]
+pub enum OscArg {
+    Int(i32),
+    Float(f32),
+    Str(String),
+    Blob(Vec<u8>),
+    Long(i64),
+    Double(f64),
+    Bool(bool),
+    Nil,
+}
+
+impl OscArg {
+    /// Numeric value of the argument; booleans map to 0 / 1.
+    pub fn as_f32(&self) -> Option<f32> {
+        match self {
+            OscArg::Int(v) => Some(*v as f32),
+            OscArg::Float(v) => Some(*v),
+            OscArg::Long(v) => Some(*v as f32),
+            OscArg::Double(v) => Some(*v as f32),
+            OscArg::Bool(v) => Some(if *v { 1.0 } else { 0.0 }),
+            OscArg::Str(s) => s.trim().parse().ok(),
+            OscArg::Blob(_) | OscArg::Nil => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            OscArg::Str(s) => Some(s),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct OscMessage {
+    pub address: String,
+    pub args: Vec<OscArg>,
+}
+
+impl OscMessage {
+    pub fn new(address: impl Into<String>, args: Vec<OscArg>) -> Self {
+        Self {
+            address: address.into(),
+            args,
+        }
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(32 + self.args.len() * 8);
+        write_str(&mut out, &self.address);
+        let mut tags = String::from(",");
+        for arg in &self.args {
+            tags.push(match arg {
+                OscArg::Int(_) => 'i',
+                OscArg::Float(_) => 'f',
+                OscArg::Str(_) => 's',
+                OscArg::Blob(_) => 'b',
+                OscArg::Long(_) => 'h',
+                OscArg::Double(_) => 'd',
+                OscArg::Bool(true) => 'T',
+                OscArg::Bool(false) => 'F',
+                OscArg::Nil => 'N',
+            });
+        }
+        write_str(&mut out, &tags);
+        for arg in &self.args {
+            match arg {
+                OscArg::Int(v) => out.extend_from_slice(&v.to_be_bytes()),
+                OscArg::Float(v) => out.extend_from_slice(&v.to_be_bytes()),
+                OscArg::Str(s) => write_str(&mut out, s),
+                OscArg::Blob(b) => {
+                    out.extend_from_slice(&(b.len() as i32).to_be_bytes());
+                    out.extend_from_slice(b);
+                    pad(&mut out);
+                }
+                OscArg::Long(v) => out.extend_from_slice(&v.to_be_bytes()),
+                OscArg::Double(v) => out.extend_from_slice(&v.to_be_bytes()),
+                OscArg::Bool(_) | OscArg::Nil => {}
+            }
+        }
+        out
+    }
+}
+
+/// Decode a UDP packet into its messages, flattening bundles. Time tags are
+/// ignored: everything is applied on arrival.
+pub fn decode_packet(packet: &[u8]) -> Result<Vec<OscMessage>, String> {
+    let mut out = Vec::new();
+    decode_into(packet, &mut out, 0)?;
+    Ok(out)
+}
+
+const BUNDLE_TAG: &[u8] = b"#bundle\0";
+const MAX_BUNDLE_DEPTH: usize = 8;
+
+fn decode_into(packet: &[u8], out: &mut Vec<OscMessage>, depth: usize) -> Result<(), String> {
+    if packet.starts_with(BUNDLE_TAG) {
+        if depth >= MAX_BUNDLE_DEPTH {
+            return Err("OSC bundle nesting too deep".to_string());
+        }
+        let mut r = Reader::new(&packet[BUNDLE_TAG.len()..]);
+        r.take(8)?; // time tag
+        while !r.is_empty() {
+            let len = r.i32()?;
+            if len < 0 {
+                return Err("Negative OSC bundle element size".to_string());
+            }
+            decode_into(r.take(len as usize)?, out, depth + 1)?;
+        }
+        return Ok(());
+    }
+
+    let mut r = Reader::new(packet);
+    let address = r.string()?;
+    if !address.starts_with('/') {
+        return Err(format!("Invalid OSC address: {address}"));
+    }
+    // A message without a type tag string is allowed by OSC 1.0.
+    let tags = if r.is_empty() {
+        String::new()
+    } else {
+        r.string()?
+    };
+    let mut args = Vec::new();
+    for tag in tags.chars().skip_while(|c| *c == ',') {
+        args.push(match tag {
+            'i' => OscArg::Int(r.i32()?),
+            'f' => OscArg::Float(f32::from_be_bytes(r.array()?)),
+            's' | 'S' => OscArg::Str(r.string()?),
+            'b' => {
+                let len = r.i32()?.max(0) as usize;
+                let blob = r.take(len)?.to_vec();
+                r.align()?;
+                OscArg::Blob(blob)
+            }
+            'h' => OscArg::Long(i64::from_be_bytes(r.array()?)),
+            'd' => OscArg::Double(f64::from_be_bytes(r.array()?)),
+            't' => OscArg::Long(i64::from_be_bytes(r.array()?)),
+            'c' | 'r' | 'm' => OscArg::Int(r.i32()?),
+            'T' => OscArg::Bool(true),
+            'F' => OscArg::Bool(false),
+            'N' | 'I' => OscArg::Nil,
+            // Array brackets carry no data of their own.
+            '[' | ']' => continue,
+            other => return Err(format!("Unsupported OSC type tag '{other}'")),
+        });
+    }
+    out.push(OscMessage { address, args });
+    Ok(())
+}
+
+fn write_str(out: &mut Vec<u8>, s: &str) {
+    out.extend_from_slice(s.as_bytes());
+    out.push(0);
+    pad(out);
+}
+
+fn pad(out: &mut Vec<u8>) {
+    while !out.len().is_multiple_of(4) {
+        out.push(0);
+    }
+}
+
+struct Reader<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(buf: &'a [u8]) -> Self {
+        Self { buf, pos: 0 }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.pos >= self.buf.len()
+    }
+
+    fn take(&mut self, n: usize) -> Result<&'a [u8], String> {
+        let end = self
+            .pos
+            .checked_add(n)
+            .filter(|end| *end <= self.buf.len())
+            .ok_or_else(|| "Truncated OSC packet".to_string())?;
+        let slice = &self.buf[self.pos..end];
+        self.pos = end;
+        Ok(slice)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], String> {
+        let mut out = [0u8; N];
+        out.copy_from_slice(self.take(N)?);
+        Ok(out)
+    }
+
+    fn i32(&mut self) -> Result<i32, String> {
+        Ok(i32::from_be_bytes(self.array()?))
+    }
+
+    fn align(&mut self) -> Result<(), String> {
+        let rem = self.pos % 4;
+        if rem != 0 {
+            self.take(4 - rem)?;
+        }
+        Ok(())
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        let rest = &self.buf[self.pos.min(self.buf.len())..];
+        let len = rest
+            .iter()
+            .position(|b| *b == 0)
+            .ok_or_else(|| "Unterminated OSC string".to_string())?;
+        let s = std::str::from_utf8(&rest[..len])
+            .map_err(|_| "OSC string is not UTF-8".to_string())?
+            .to_string();
+        self.take(len + 1)?;
+        self.align()?;
+        Ok(s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_message() {
+        let msg = OscMessage::new(
+            "/deck/a/gain",
+            vec![
+                OscArg::Float(0.5),
+                OscArg::Int(-3),
+                OscArg::Str("abc".to_string()),
+                OscArg::Bool(true),
+                OscArg::Blob(vec![1, 2, 3]),
+            ],
+        );
+        let bytes = msg.encode();
+        assert_eq!(bytes.len() % 4, 0);
+        assert_eq!(decode_packet(&bytes).unwrap(), vec![msg]);
+    }
+
+    #[test]
+    fn flattens_bundles() {
+        let a = OscMessage::new("/deck/a/play", Vec::new()).encode();
+        let b = OscMessage::new("/crossfader", vec![OscArg::Float(-1.0)]).encode();
+        let mut packet = BUNDLE_TAG.to_vec();
+        packet.extend_from_slice(&[0, 0, 0, 0, 0, 0, 0, 1]);
+        for element in [&a, &b] {
+            packet.extend_from_slice(&(element.len() as i32).to_be_bytes());
+            packet.extend_from_slice(element);
+        }
+        let messages = decode_packet(&packet).unwrap();
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[1].address, "/crossfader");
+        assert!(decode_packet(&packet[..packet.len() - 2]).is_err());
+    }
+}
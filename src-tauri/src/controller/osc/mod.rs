@@ -0,0 +1,468 @@
+/// OSC remote control surface
+///
+/// A UDP server so TouchOSC, QLab, Open Stage Control and friends can drive
+/// decks, faders, the crossfader and hot cues over the network. Incoming
+/// messages are mapped by [`address`]; outgoing feedback (deck state and
+/// position, VU meters) goes to the configured targets and, optionally, back
+/// to every surface that has talked to us recently. Feedback is throttled to
+/// `feedback_interval_ms` and only changed values are sent between full
+/// resyncs, so a phone on Wi-Fi is not flooded with identical packets.
+///
+/// OSC has no authentication; `allowed_hosts` limits which addresses may send
+/// commands. The server listens on loopback by default and refuses any other
+/// address until `allowed_hosts` is filled in.
+pub mod address;
+pub mod codec;
+
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use tauri::{AppHandle, Manager};
+use tokio::net::UdpSocket;
+use tokio::sync::oneshot;
+
+use crate::audio::crossfade::DeckId;
+use crate::state::AppState;
+
+use codec::{OscArg, OscMessage};
+
+const MAX_PACKET_BYTES: usize = 8 * 1024;
+const MIN_FEEDBACK_INTERVAL_MS: u64 = 20;
+/// Unchanged values are re-sent this often so late joiners catch up
+const FEEDBACK_RESYNC_MS: u64 = 5_000;
+/// Senders drop off the reply list after this long without a message
+const CLIENT_IDLE_SECS: u64 = 60;
+const MAX_CLIENTS: usize = 16;
+const VU_FLOOR_DB: f32 = -48.0;
+const FEEDBACK_DECKS: [DeckId; 6] = [
+    DeckId::DeckA,
+    DeckId::DeckB,
+    DeckId::SoundFx,
+    DeckId::Aux1,
+    DeckId::Aux2,
+    DeckId::VoiceFx,
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OscConfig {
+    pub enabled: bool,
+    pub bind_address: String,
+    pub port: u16,
+    /// Only accept commands from these IPs (empty = anyone who can reach the
+    /// socket; only allowed on a loopback bind address)
+    pub allowed_hosts: Vec<String>,
+    /// Extra feedback destinations as `host:port`
+    pub feedback_targets: Vec<String>,
+    /// Send feedback back to the address of every recent sender
+    pub reply_to_senders: bool,
+    pub feedback_interval_ms: u64,
+    pub send_meters: bool,
+    pub send_positions: bool,
+}
+
+impl Default for OscConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            bind_address: "127.0.0.1".to_string(),
+            port: 9000,
+            allowed_hosts: Vec::new(),
+            feedback_targets: Vec::new(),
+            reply_to_senders: true,
+            feedback_interval_ms: 100,
+            send_meters: true,
+            send_positions: true,
+        }
+    }
+}
+
+impl OscConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.enabled && self.port == 0 {
+            return Err("OSC port must be set".to_string());
+        }
+        if self.allowed_hosts.is_empty() && !is_loopback(&self.bind_address) {
+            return Err(format!(
+                "OSC on {} needs allowed hosts; without them only a loopback address is allowed",
+                self.bind_address
+            ));
+        }
+        for host in &self.allowed_hosts {
+            host.parse::<IpAddr>()
+                .map_err(|_| format!("Invalid allowed host (expected an IP): {host}"))?;
+        }
+        for target in &self.feedback_targets {
+            parse_target(target)?;
+        }
+        Ok(())
+    }
+}
+
+fn is_loopback(bind_address: &str) -> bool {
+    bind_address.eq_ignore_ascii_case("localhost")
+        || bind_address
+            .parse::<IpAddr>()
+            .is_ok_and(|ip| ip.is_loopback())
+}
+
+fn parse_target(target: &str) -> Result<SocketAddr, String> {
+    target
+        .parse::<SocketAddr>()
+        .map_err(|_| format!("Invalid feedback target (expected ip:port): {target}"))
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OscStatus {
+    pub running: bool,
+    pub listening_on: Option<String>,
+    pub messages_received: u64,
+    pub messages_rejected: u64,
+    /// Surfaces currently receiving feedback
+    pub clients: Vec<String>,
+    pub last_error: Option<String>,
+}
+
+struct Runtime {
+    shutdown: Option<oneshot::Sender<()>>,
+    status: OscStatus,
+}
+
+static RUNTIME: OnceLock<Mutex<Runtime>> = OnceLock::new();
+
+fn runtime() -> &'static Mutex<Runtime> {
+    RUNTIME.get_or_init(|| {
+        Mutex::new(Runtime {
+            shutdown: None,
+            status: OscStatus::default(),
+        })
+    })
+}
+
+pub fn status() -> OscStatus {
+    runtime().lock().unwrap().status.clone()
+}
+
+/// Bind and start serving; a server that is already running is replaced.
+pub async fn start(app: AppHandle, config: OscConfig) -> Result<OscStatus, String> {
+    config.validate()?;
+    if stop() {
+        // Give the previous loop a moment to release the port.
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+
+    let socket = match UdpSocket::bind((config.bind_address.as_str(), config.port)).await {
+        Ok(s) => Arc::new(s),
+        Err(e) => {
+            let msg = format!(
+                "OSC: cannot bind {}:{}: {e}",
+                config.bind_address, config.port
+            );
+            runtime().lock().unwrap().status.last_error = Some(msg.clone());
+            return Err(msg);
+        }
+    };
+    let local_addr = socket.local_addr().map_err(|e| e.to_string())?;
+    let (tx, mut rx) = oneshot::channel();
+    {
+        let mut rt = runtime().lock().unwrap();
+        rt.shutdown = Some(tx);
+        rt.status = OscStatus {
+            running: true,
+            listening_on: Some(local_addr.to_string()),
+            ..OscStatus::default()
+        };
+    }
+    log::info!("OSC listening on {local_addr}");
+
+    let allowed: Vec<IpAddr> = config
+        .allowed_hosts
+        .iter()
+        .filter_map(|h| h.parse().ok())
+        .collect();
+    let targets: Vec<SocketAddr> = config
+        .feedback_targets
+        .iter()
+        .filter_map(|t| parse_target(t).ok())
+        .collect();
+    let interval = Duration::from_millis(config.feedback_interval_ms.max(MIN_FEEDBACK_INTERVAL_MS));
+
+    tauri::async_runtime::spawn(async move {
+        let mut buf = vec![0u8; MAX_PACKET_BYTES];
+        let mut clients: HashMap<SocketAddr, Instant> = HashMap::new();
+        let mut feedback = Feedback::default();
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+
+        loop {
+            tokio::select! {
+                _ = &mut rx => break,
+                received = socket.recv_from(&mut buf) => match received {
+                    Ok((len, peer)) => {
+                        if !allowed.is_empty() && !allowed.contains(&peer.ip()) {
+                            runtime().lock().unwrap().status.messages_rejected += 1;
+                            log::debug!("OSC: ignoring packet from {peer} (not an allowed host)");
+                            continue;
+                        }
+                        if config.reply_to_senders {
+                            let is_new = clients.insert(peer, Instant::now()).is_none();
+                            if is_new {
+                                // A new surface gets the full picture on the next tick.
+                                feedback.force_resync();
+                                if clients.len() > MAX_CLIENTS {
+                                    evict_oldest(&mut clients);
+                                }
+                            }
+                        }
+                        handle_packet(&app, &buf[..len], peer).await;
+                    }
+                    Err(e) => {
+                        // Windows reports ICMP port-unreachable from an earlier
+                        // send as a receive error; just keep going.
+                        log::debug!("OSC receive failed: {e}");
+                    }
+                },
+                _ = ticker.tick() => {
+                    clients.retain(|_, seen| seen.elapsed() < Duration::from_secs(CLIENT_IDLE_SECS));
+                    let mut destinations = targets.clone();
+                    destinations.extend(clients.keys().copied().filter(|c| !targets.contains(c)));
+                    runtime().lock().unwrap().status.clients =
+                        destinations.iter().map(|d| d.to_string()).collect();
+                    if destinations.is_empty() {
+                        continue;
+                    }
+                    let packets = feedback.collect(&app, &config);
+                    for packet in &packets {
+                        for dest in &destinations {
+                            if let Err(e) = socket.send_to(packet, dest).await {
+                                log::debug!("OSC feedback to {dest} failed: {e}");
+                            }
+                        }
+                    }
+                },
+            }
+        }
+        log::info!("OSC on {local_addr} stopped");
+    });
+
+    Ok(status())
+}
+
+/// Stop the server. Returns whether one was running.
+pub fn stop() -> bool {
+    let mut rt = runtime().lock().unwrap();
+    rt.status.running = false;
+    rt.status.listening_on = None;
+    rt.status.clients.clear();
+    match rt.shutdown.take() {
+        Some(tx) => {
+            let _ = tx.send(());
+            true
+        }
+        None => false,
+    }
+}
+
+fn evict_oldest(clients: &mut HashMap<SocketAddr, Instant>) {
+    if let Some(oldest) = clients
+        .iter()
+        .min_by_key(|(_, seen)| **seen)
+        .map(|(addr, _)| *addr)
+    {
+        clients.remove(&oldest);
+    }
+}
+
+async fn handle_packet(app: &AppHandle, packet: &[u8], peer: SocketAddr) {
+    let messages = match codec::decode_packet(packet) {
+        Ok(m) => m,
+        Err(e) => {
+            runtime().lock().unwrap().status.messages_rejected += 1;
+            log::debug!("OSC: bad packet from {peer}: {e}");
+            return;
+        }
+    };
+    for msg in messages {
+        let result = match address::parse(&msg) {
+            Ok(Some(cmd)) => address::execute(app, cmd).await,
+            Ok(None) => Ok(()),
+            Err(e) => Err(e),
+        };
+        let mut rt = runtime().lock().unwrap();
+        match result {
+            Ok(()) => rt.status.messages_received += 1,
+            Err(e) => {
+                rt.status.messages_rejected += 1;
+                log::debug!("OSC {} from {peer}: {e}", msg.address);
+            }
+        }
+    }
+}
+
+// ── Feedback ──────────────────────────────────────────────────────────────────
+
+/// Last value sent per address, so ticks only carry what changed.
+#[derive(Default)]
+struct Feedback {
+    sent: HashMap<String, Vec<OscArg>>,
+    last_resync: Option<Instant>,
+}
+
+impl Feedback {
+    fn force_resync(&mut self) {
+        self.last_resync = None;
+    }
+
+    fn collect(&mut self, app: &AppHandle, config: &OscConfig) -> Vec<Vec<u8>> {
+        let resync = self
+            .last_resync
+            .is_none_or(|t| t.elapsed() >= Duration::from_millis(FEEDBACK_RESYNC_MS));
+        if resync {
+            self.last_resync = Some(Instant::now());
+        }
+
+        let messages = snapshot(app, config);
+        messages
+            .into_iter()
+            .filter(|msg| {
+                let changed = self.sent.get(&msg.address) != Some(&msg.args);
+                if changed {
+                    self.sent.insert(msg.address.clone(), msg.args.clone());
+                }
+                changed || resync
+            })
+            .map(|msg| msg.encode())
+            .collect()
+    }
+}
+
+/// Current feedback values, as addresses mirroring the inbound map.
+fn snapshot(app: &AppHandle, config: &OscConfig) -> Vec<OscMessage> {
+    let state = app.state::<AppState>();
+    let engine = state.engine.lock().unwrap();
+    let mut out = Vec::new();
+
+    for deck in FEEDBACK_DECKS {
+        let Some(s) = engine.get_deck_state(deck) else {
+            continue;
+        };
+        let prefix = format!("/deck/{deck}");
+        out.push(OscMessage::new(
+            format!("{prefix}/state"),
+            vec![OscArg::Str(s.state.clone())],
+        ));
+        out.push(OscMessage::new(
+            format!("{prefix}/gain"),
+            vec![OscArg::Float(s.channel_gain)],
+        ));
+        out.push(OscMessage::new(
+            format!("{prefix}/cue"),
+            vec![OscArg::Bool(s.cue_preview_enabled)],
+        ));
+        if config.send_positions {
+            let progress = if s.duration_ms > 0 {
+                (s.position_ms as f32 / s.duration_ms as f32).clamp(0.0, 1.0)
+            } else {
+                0.0
+            };
+            out.push(OscMessage::new(
+                format!("{prefix}/position"),
+                vec![OscArg::Int(s.position_ms.min(i32::MAX as u64) as i32)],
+            ));
+            out.push(OscMessage::new(
+                format!("{prefix}/remaining"),
+                vec![OscArg::Int(
+                    s.duration_ms
+                        .saturating_sub(s.position_ms)
+                        .min(i32::MAX as u64) as i32,
+                )],
+            ));
+            out.push(OscMessage::new(
+                format!("{prefix}/progress"),
+                vec![OscArg::Float(progress)],
+            ));
+        }
+    }
+
+    if config.send_meters {
+        for vu in engine.get_vu_readings() {
+            let peak_db = vu.left_db.max(vu.right_db);
+            // Round so meter noise below a tenth of a dB doesn't count as a change.
+            let round = |db: f32| (db.max(VU_FLOOR_DB) * 10.0).round() / 10.0;
+            out.push(OscMessage::new(
+                format!("/vu/{}", vu.channel),
+                vec![
+                    OscArg::Float(round(vu.left_db)),
+                    OscArg::Float(round(vu.right_db)),
+                    OscArg::Float(meter_level(peak_db)),
+                ],
+            ));
+        }
+    }
+
+    out
+}
+
+/// dB → 0..1 for fader/meter widgets, linear over the last 48 dB.
+fn meter_level(db: f32) -> f32 {
+    let level = (db.max(VU_FLOOR_DB) - VU_FLOOR_DB) / -VU_FLOOR_DB;
+    (level.clamp(0.0, 1.0) * 100.0).round() / 100.0
+}
+
+// ── Persistence ───────────────────────────────────────────────────────────────
+
+pub async fn get_config(pool: &SqlitePool) -> Result<OscConfig, sqlx::Error> {
+    let row: Option<String> = sqlx::query_scalar("SELECT config_json FROM osc_config WHERE id = 1")
+        .fetch_optional(pool)
+        .await?;
+    Ok(row
+        .and_then(|j| serde_json::from_str(&j).ok())
+        .unwrap_or_default())
+}
+
+pub async fn save_config(pool: &SqlitePool, config: &OscConfig) -> Result<(), sqlx::Error> {
+    let json = serde_json::to_string(config).unwrap_or_else(|_| "{}".to_string());
+    sqlx::query(
+        "INSERT INTO osc_config (id, config_json, updated_at) VALUES (1, ?, strftime('%s','now')) \
+         ON CONFLICT(id) DO UPDATE SET config_json = excluded.config_json, updated_at = excluded.updated_at",
+    )
+    .bind(json)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validates_hosts_and_targets() {
+        let mut config = OscConfig {
+            allowed_hosts: vec!["192.168.1.20".to_string()],
+            feedback_targets: vec!["192.168.1.20:9001".to_string()],
+            ..OscConfig::default()
+        };
+        assert!(config.validate().is_ok());
+        config.feedback_targets.push("tablet.local".to_string());
+        assert!(config.validate().is_err());
+
+        let open = OscConfig {
+            bind_address: "0.0.0.0".to_string(),
+            ..OscConfig::default()
+        };
+        assert!(open.validate().is_err());
+        assert!(OscConfig::default().validate().is_ok());
+    }
+
+    #[test]
+    fn meter_level_spans_floor_to_zero() {
+        assert_eq!(meter_level(-90.0), 0.0);
+        assert_eq!(meter_level(-24.0), 0.5);
+        assert_eq!(meter_level(3.0), 1.0);
+    }
+}
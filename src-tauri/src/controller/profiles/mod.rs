@@ -0,0 +1,119 @@
+/// Built-in mapping profiles for common controllers.
+///
+/// Each profile is an ordinary [`MidiProfile`] so it goes through the same
+/// decoder and LED path as learned ones, and can be copied as a starting
+/// point in the mapping editor. Select one by id with
+/// `save_controller_config_cmd`.
+mod numark_mixtrack;
+mod pioneer_ddj;
+mod traktor_s2;
+
+use crate::audio::crossfade::DeckId;
+
+use super::mapping::{
+    LedMapping, LedSource, MappedAction, MidiBinding, MidiControl, MidiKind, MidiProfile,
+};
+
+pub use numark_mixtrack::MIXTRACK_PROFILE;
+pub use pioneer_ddj::{DDJ_400_PROFILE, DDJ_FLX4_PROFILE};
+pub use traktor_s2::TRAKTOR_S2_PROFILE;
+
+pub fn builtin_profiles() -> Vec<MidiProfile> {
+    vec![
+        pioneer_ddj::ddj_400(),
+        pioneer_ddj::ddj_flx4(),
+        numark_mixtrack::profile(),
+        traktor_s2::profile(),
+    ]
+}
+
+pub fn builtin(id: &str) -> Option<MidiProfile> {
+    match id {
+        DDJ_400_PROFILE => Some(pioneer_ddj::ddj_400()),
+        DDJ_FLX4_PROFILE => Some(pioneer_ddj::ddj_flx4()),
+        MIXTRACK_PROFILE => Some(numark_mixtrack::profile()),
+        TRAKTOR_S2_PROFILE => Some(traktor_s2::profile()),
+        _ => None,
+    }
+}
+
+// ── Builders shared by the profile files ─────────────────────────────────────
+
+fn note(channel: u8, number: u8) -> MidiControl {
+    MidiControl {
+        kind: MidiKind::Note,
+        channel,
+        number,
+    }
+}
+
+fn cc(channel: u8, number: u8) -> MidiControl {
+    MidiControl {
+        kind: MidiKind::Cc,
+        channel,
+        number,
+    }
+}
+
+fn bind(control: MidiControl, action: MappedAction) -> MidiBinding {
+    MidiBinding {
+        control,
+        shift: false,
+        action,
+        invert: false,
+    }
+}
+
+fn bind_shift(control: MidiControl, action: MappedAction) -> MidiBinding {
+    MidiBinding {
+        shift: true,
+        ..bind(control, action)
+    }
+}
+
+fn bind_inverted(control: MidiControl, action: MappedAction) -> MidiBinding {
+    MidiBinding {
+        invert: true,
+        ..bind(control, action)
+    }
+}
+
+fn led(source: LedSource, control: MidiControl) -> LedMapping {
+    LedMapping {
+        source,
+        control,
+        on_value: 0x7F,
+        off_value: 0x00,
+    }
+}
+
+fn vu(deck: Option<DeckId>, control: MidiControl, full_scale: u8) -> LedMapping {
+    LedMapping {
+        source: LedSource::VuMeter { deck },
+        control,
+        on_value: full_scale,
+        off_value: 0x00,
+    }
+}
+
+/// The two mixer decks and their per-deck MIDI channel.
+fn decks(a: u8, b: u8) -> [(DeckId, u8); 2] {
+    [(DeckId::DeckA, a), (DeckId::DeckB, b)]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builtin_profiles_are_valid_and_resolvable() {
+        for profile in builtin_profiles() {
+            // Built-in ids are reserved for custom profiles, so validate a copy.
+            let mut copy = profile.clone();
+            copy.id = format!("{}_copy", profile.id);
+            copy.validate()
+                .unwrap_or_else(|e| panic!("{}: {e}", profile.id));
+            assert_eq!(builtin(&profile.id).as_ref(), Some(&profile));
+        }
+    }
+}
@@ -0,0 +1,131 @@
+/// Numark Mixtrack Pro 3 / Platinum.
+///
+/// The pads send the same notes in every mode; the Cue / Loop / Sample mode
+/// buttons only change what the software does with them, so pads go through
+/// `PadMode`. Decks are on channels 1/2, pads on 5/6, the mixer on 16.
+use crate::audio::dsp::eq::EqBand;
+
+use super::super::mapping::{JogEncoding, LedSource, MappedAction, MidiProfile, PadMode};
+use super::{bind, bind_inverted, cc, decks, led, note, vu};
+
+pub const MIXTRACK_PROFILE: &str = "numark_mixtrack";
+
+const MIXER_CH: u8 = 15;
+
+const PLAY_NOTE: u8 = 0x00;
+const CUE_NOTE: u8 = 0x01;
+const SYNC_NOTE: u8 = 0x02;
+const PFL_NOTE: u8 = 0x1B;
+const SHIFT_NOTE: u8 = 0x20;
+const LOAD_NOTE: u8 = 0x06;
+const BASS_KILL_NOTE: u8 = 0x1D;
+
+const TEMPO_CC: u8 = 0x09;
+const JOG_CC: u8 = 0x06;
+const FADER_CC: u8 = 0x1C;
+const LOW_EQ_CC: u8 = 0x18;
+const FILTER_CC: u8 = 0x1A;
+const VU_CC: u8 = 0x1F;
+/// Meter LEDs light fully at this value
+const VU_MAX: u8 = 0x51;
+
+const XFADER_CC: u8 = 0x08;
+const MASTER_LEVEL_CC: u8 = 0x0B;
+const HEADPHONE_MIX_CC: u8 = 0x0C;
+const BROWSE_CC: u8 = 0x00;
+
+const PAD_CH_OFFSET: u8 = 4;
+const HOT_CUE_MODE_NOTE: u8 = 0x00;
+const LOOP_MODE_NOTE: u8 = 0x0D;
+const SAMPLE_MODE_NOTE: u8 = 0x07;
+const PAD_1_NOTE: u8 = 0x14;
+const PADS: u8 = 4;
+
+pub fn profile() -> MidiProfile {
+    let mut bindings = Vec::new();
+    let mut leds = Vec::new();
+
+    for (deck, ch) in decks(0, 1) {
+        bindings.extend([
+            bind(note(ch, SHIFT_NOTE), MappedAction::Shift),
+            bind(note(ch, PLAY_NOTE), MappedAction::TogglePlay { deck }),
+            bind(note(ch, CUE_NOTE), MappedAction::CueToStart { deck }),
+            bind(note(ch, SYNC_NOTE), MappedAction::SyncToOther { deck }),
+            bind(note(ch, PFL_NOTE), MappedAction::ToggleCue { deck }),
+            bind(note(ch, LOAD_NOTE), MappedAction::BrowseLoad { deck }),
+            bind(
+                note(ch, BASS_KILL_NOTE),
+                MappedAction::EqKill {
+                    deck,
+                    band: EqBand::Low,
+                },
+            ),
+            bind_inverted(
+                cc(ch, TEMPO_CC),
+                MappedAction::Tempo {
+                    deck,
+                    range_pct: 8.0,
+                },
+            ),
+            bind(
+                cc(ch, JOG_CC),
+                MappedAction::Jog {
+                    deck,
+                    encoding: JogEncoding::TwosComplement,
+                },
+            ),
+            bind(cc(ch, FADER_CC), MappedAction::Gain { deck }),
+            bind(cc(ch, LOW_EQ_CC), MappedAction::Bass { deck }),
+            bind(cc(ch, FILTER_CC), MappedAction::Filter { deck }),
+        ]);
+
+        let pad_ch = ch + PAD_CH_OFFSET;
+        for (mode, mode_note) in [
+            (PadMode::HotCue, HOT_CUE_MODE_NOTE),
+            (PadMode::BeatLoop, LOOP_MODE_NOTE),
+            (PadMode::Sampler, SAMPLE_MODE_NOTE),
+        ] {
+            bindings.push(bind(
+                note(pad_ch, mode_note),
+                MappedAction::PadMode { deck, mode },
+            ));
+            leds.push(led(
+                LedSource::PadMode { deck, mode },
+                note(pad_ch, mode_note),
+            ));
+        }
+        for index in 0..PADS {
+            bindings.push(bind(
+                note(pad_ch, PAD_1_NOTE + index),
+                MappedAction::Pad { deck, index },
+            ));
+        }
+
+        leds.extend([
+            led(LedSource::Playing { deck }, note(ch, PLAY_NOTE)),
+            led(LedSource::CuePreview { deck }, note(ch, PFL_NOTE)),
+            led(LedSource::Shift, note(ch, SHIFT_NOTE)),
+            vu(Some(deck), cc(ch, VU_CC), VU_MAX),
+        ]);
+    }
+
+    bindings.extend([
+        bind(cc(MIXER_CH, XFADER_CC), MappedAction::Crossfader),
+        bind(cc(MIXER_CH, MASTER_LEVEL_CC), MappedAction::MasterVolume),
+        bind(cc(MIXER_CH, HEADPHONE_MIX_CC), MappedAction::HeadphoneMix),
+        bind(
+            cc(MIXER_CH, BROWSE_CC),
+            MappedAction::Browse {
+                encoding: JogEncoding::TwosComplement,
+            },
+        ),
+    ]);
+
+    MidiProfile {
+        id: MIXTRACK_PROFILE.to_string(),
+        name: "Numark Mixtrack".to_string(),
+        device_name_hint: Some("mixtrack".to_string()),
+        bindings,
+        leds,
+    }
+}
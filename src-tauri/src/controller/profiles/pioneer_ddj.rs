@@ -0,0 +1,178 @@
+/// Pioneer DDJ-400 and DDJ-FLX4.
+///
+/// Both use the same rekordbox MIDI layout: decks on channels 1/2, the mixer
+/// section on channel 7, and pads on channels 8/10 (shifted pads one channel
+/// up). Pad modes are switched in hardware and each mode sends its own note
+/// bank, so pads are bound per bank rather than through `PadMode`.
+use crate::audio::dsp::eq::EqBand;
+
+use super::super::mapping::{JogEncoding, LedSource, MappedAction, MidiProfile};
+use super::{bind, bind_inverted, bind_shift, cc, decks, led, note, vu};
+
+pub const DDJ_400_PROFILE: &str = "pioneer_ddj_400";
+pub const DDJ_FLX4_PROFILE: &str = "pioneer_ddj_flx4";
+
+const MIXER_CH: u8 = 6;
+
+const SHIFT_NOTE: u8 = 0x3F;
+const PLAY_NOTE: u8 = 0x0B;
+const CUE_NOTE: u8 = 0x0C;
+const SYNC_NOTE: u8 = 0x58;
+const PFL_NOTE: u8 = 0x54;
+const LOOP_EXIT_NOTE: u8 = 0x4D;
+const LOW_KILL_NOTE: u8 = 0x0F;
+
+const TEMPO_CC: u8 = 0x00;
+const VU_CC: u8 = 0x02;
+const LOW_EQ_CC: u8 = 0x0F;
+const FADER_CC: u8 = 0x13;
+const JOG_WHEEL_CC: u8 = 0x21;
+const JOG_PLATTER_CC: u8 = 0x22;
+
+const XFADER_CC: u8 = 0x1F;
+const HEADPHONE_MIX_CC: u8 = 0x0C;
+const MASTER_LEVEL_CC: u8 = 0x08;
+const CFX_DECK_A_CC: u8 = 0x17;
+const BROWSE_CC: u8 = 0x40;
+const LOAD_DECK_A_NOTE: u8 = 0x46;
+
+const HOT_CUE_BANK: u8 = 0x00;
+const BEAT_LOOP_BANK: u8 = 0x60;
+const SAMPLER_BANK: u8 = 0x30;
+const PADS: u8 = 8;
+
+pub fn ddj_400() -> MidiProfile {
+    build(DDJ_400_PROFILE, "Pioneer DDJ-400", "ddj-400")
+}
+
+pub fn ddj_flx4() -> MidiProfile {
+    build(DDJ_FLX4_PROFILE, "Pioneer DDJ-FLX4", "ddj-flx4")
+}
+
+fn build(id: &str, name: &str, hint: &str) -> MidiProfile {
+    let mut bindings = Vec::new();
+    let mut leds = Vec::new();
+
+    for (i, (deck, ch)) in decks(0, 1).into_iter().enumerate() {
+        let i = i as u8;
+        bindings.extend([
+            bind(note(ch, SHIFT_NOTE), MappedAction::Shift),
+            bind(note(ch, PLAY_NOTE), MappedAction::TogglePlay { deck }),
+            bind(note(ch, CUE_NOTE), MappedAction::CueToStart { deck }),
+            bind(note(ch, SYNC_NOTE), MappedAction::SyncToOther { deck }),
+            bind(note(ch, PFL_NOTE), MappedAction::ToggleCue { deck }),
+            bind(note(ch, LOOP_EXIT_NOTE), MappedAction::ClearLoop { deck }),
+            bind_shift(
+                note(ch, LOW_KILL_NOTE),
+                MappedAction::EqKill {
+                    deck,
+                    band: EqBand::Low,
+                },
+            ),
+            // Tempo slider reads -% at the top.
+            bind_inverted(
+                cc(ch, TEMPO_CC),
+                MappedAction::Tempo {
+                    deck,
+                    range_pct: 8.0,
+                },
+            ),
+            bind(cc(ch, FADER_CC), MappedAction::Gain { deck }),
+            bind(cc(ch, LOW_EQ_CC), MappedAction::Bass { deck }),
+            bind(
+                cc(ch, JOG_WHEEL_CC),
+                MappedAction::Jog {
+                    deck,
+                    encoding: JogEncoding::BinaryOffset,
+                },
+            ),
+            bind(
+                cc(ch, JOG_PLATTER_CC),
+                MappedAction::Jog {
+                    deck,
+                    encoding: JogEncoding::BinaryOffset,
+                },
+            ),
+            bind(
+                cc(MIXER_CH, CFX_DECK_A_CC + i),
+                MappedAction::Filter { deck },
+            ),
+            bind(
+                note(MIXER_CH, LOAD_DECK_A_NOTE + i),
+                MappedAction::BrowseLoad { deck },
+            ),
+        ]);
+
+        // Deck 1 pads on channel 8, deck 2 on channel 10; shift is +1.
+        let pad_ch = 7 + 2 * i;
+        for pad in 0..PADS {
+            let slot = pad + 1;
+            bindings.extend([
+                bind(
+                    note(pad_ch, HOT_CUE_BANK + pad),
+                    MappedAction::HotCue { deck, slot },
+                ),
+                bind(
+                    note(pad_ch + 1, HOT_CUE_BANK + pad),
+                    MappedAction::HotCueSet { deck, slot },
+                ),
+                bind(
+                    note(pad_ch, BEAT_LOOP_BANK + pad),
+                    MappedAction::BeatLoop {
+                        deck,
+                        beats: 1 << pad,
+                    },
+                ),
+                bind(
+                    note(pad_ch + 1, BEAT_LOOP_BANK + pad),
+                    MappedAction::ClearLoop { deck },
+                ),
+                bind(
+                    note(pad_ch, SAMPLER_BANK + pad),
+                    MappedAction::TriggerCart {
+                        slot: i * PADS + pad,
+                    },
+                ),
+                bind(
+                    note(pad_ch + 1, SAMPLER_BANK + pad),
+                    MappedAction::StopCart {
+                        slot: i * PADS + pad,
+                    },
+                ),
+            ]);
+            leds.push(led(
+                LedSource::CartPlaying {
+                    slot: i * PADS + pad,
+                },
+                note(pad_ch, SAMPLER_BANK + pad),
+            ));
+        }
+
+        leds.extend([
+            led(LedSource::Playing { deck }, note(ch, PLAY_NOTE)),
+            led(LedSource::CuePreview { deck }, note(ch, PFL_NOTE)),
+            led(LedSource::LoopActive { deck }, note(ch, LOOP_EXIT_NOTE)),
+            vu(Some(deck), cc(ch, VU_CC), 0x7F),
+        ]);
+    }
+
+    bindings.extend([
+        bind(cc(MIXER_CH, XFADER_CC), MappedAction::Crossfader),
+        bind(cc(MIXER_CH, HEADPHONE_MIX_CC), MappedAction::HeadphoneMix),
+        bind(cc(MIXER_CH, MASTER_LEVEL_CC), MappedAction::MasterVolume),
+        bind(
+            cc(MIXER_CH, BROWSE_CC),
+            MappedAction::Browse {
+                encoding: JogEncoding::TwosComplement,
+            },
+        ),
+    ]);
+
+    MidiProfile {
+        id: id.to_string(),
+        name: name.to_string(),
+        device_name_hint: Some(hint.to_string()),
+        bindings,
+        leds,
+    }
+}
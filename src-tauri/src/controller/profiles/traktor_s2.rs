@@ -0,0 +1,124 @@
+/// Native Instruments Traktor Kontrol S2 in MIDI mode.
+///
+/// Uses the default MIDI template from Controller Editor: decks on channels
+/// 1/2, mixer on 3. The four pads per deck follow `PadMode`, switched with the
+/// Hotcue / Loop / Samples buttons. Encoders are binary-offset.
+use super::super::mapping::{JogEncoding, LedSource, MappedAction, MidiProfile, PadMode};
+use super::{bind, bind_inverted, bind_shift, cc, decks, led, note, vu};
+
+pub const TRAKTOR_S2_PROFILE: &str = "traktor_kontrol_s2";
+
+const MIXER_CH: u8 = 2;
+
+const PLAY_NOTE: u8 = 0x0C;
+const CUE_NOTE: u8 = 0x0D;
+const SHIFT_NOTE: u8 = 0x0E;
+const SYNC_NOTE: u8 = 0x0F;
+const PFL_NOTE: u8 = 0x1A;
+const LOOP_PUSH_NOTE: u8 = 0x17;
+const HOT_CUE_MODE_NOTE: u8 = 0x15;
+const LOOP_MODE_NOTE: u8 = 0x16;
+const SAMPLE_MODE_NOTE: u8 = 0x14;
+const PAD_1_NOTE: u8 = 0x10;
+const PADS: u8 = 4;
+
+const TEMPO_CC: u8 = 0x0A;
+const FADER_CC: u8 = 0x11;
+const LOW_EQ_CC: u8 = 0x14;
+const FX_CC: u8 = 0x15;
+const JOG_CC: u8 = 0x1E;
+const VU_CC: u8 = 0x40;
+
+const XFADER_CC: u8 = 0x01;
+const MASTER_LEVEL_CC: u8 = 0x02;
+const HEADPHONE_MIX_CC: u8 = 0x03;
+const BROWSE_CC: u8 = 0x04;
+const LOAD_DECK_A_NOTE: u8 = 0x05;
+const MASTER_VU_CC: u8 = 0x41;
+
+pub fn profile() -> MidiProfile {
+    let mut bindings = Vec::new();
+    let mut leds = Vec::new();
+
+    for (i, (deck, ch)) in decks(0, 1).into_iter().enumerate() {
+        bindings.extend([
+            bind(note(ch, SHIFT_NOTE), MappedAction::Shift),
+            bind(note(ch, PLAY_NOTE), MappedAction::TogglePlay { deck }),
+            bind(note(ch, CUE_NOTE), MappedAction::CueToStart { deck }),
+            bind(note(ch, SYNC_NOTE), MappedAction::SyncToOther { deck }),
+            bind(note(ch, PFL_NOTE), MappedAction::ToggleCue { deck }),
+            bind(note(ch, LOOP_PUSH_NOTE), MappedAction::ClearLoop { deck }),
+            bind_shift(
+                note(ch, LOOP_PUSH_NOTE),
+                MappedAction::BeatLoop { deck, beats: 4 },
+            ),
+            bind_inverted(
+                cc(ch, TEMPO_CC),
+                MappedAction::Tempo {
+                    deck,
+                    range_pct: 8.0,
+                },
+            ),
+            bind(cc(ch, FADER_CC), MappedAction::Gain { deck }),
+            bind(cc(ch, LOW_EQ_CC), MappedAction::Bass { deck }),
+            bind(cc(ch, FX_CC), MappedAction::Filter { deck }),
+            bind(
+                cc(ch, JOG_CC),
+                MappedAction::Jog {
+                    deck,
+                    encoding: JogEncoding::BinaryOffset,
+                },
+            ),
+            bind(
+                note(MIXER_CH, LOAD_DECK_A_NOTE + i as u8),
+                MappedAction::BrowseLoad { deck },
+            ),
+        ]);
+
+        for (mode, mode_note) in [
+            (PadMode::HotCue, HOT_CUE_MODE_NOTE),
+            (PadMode::BeatLoop, LOOP_MODE_NOTE),
+            (PadMode::Sampler, SAMPLE_MODE_NOTE),
+        ] {
+            bindings.push(bind(
+                note(ch, mode_note),
+                MappedAction::PadMode { deck, mode },
+            ));
+            leds.push(led(LedSource::PadMode { deck, mode }, note(ch, mode_note)));
+        }
+        for index in 0..PADS {
+            bindings.push(bind(
+                note(ch, PAD_1_NOTE + index),
+                MappedAction::Pad { deck, index },
+            ));
+        }
+
+        leds.extend([
+            led(LedSource::Playing { deck }, note(ch, PLAY_NOTE)),
+            led(LedSource::CuePreview { deck }, note(ch, PFL_NOTE)),
+            led(LedSource::LoopActive { deck }, note(ch, LOOP_PUSH_NOTE)),
+            vu(Some(deck), cc(ch, VU_CC), 0x7F),
+        ]);
+    }
+
+    bindings.extend([
+        bind(cc(MIXER_CH, XFADER_CC), MappedAction::Crossfader),
+        bind(cc(MIXER_CH, MASTER_LEVEL_CC), MappedAction::MasterVolume),
+        bind(cc(MIXER_CH, HEADPHONE_MIX_CC), MappedAction::HeadphoneMix),
+        bind(
+            cc(MIXER_CH, BROWSE_CC),
+            MappedAction::Browse {
+                encoding: JogEncoding::BinaryOffset,
+            },
+        ),
+    ]);
+    leds.push(vu(None, cc(MIXER_CH, MASTER_VU_CC), 0x7F));
+
+    MidiProfile {
+        id: TRAKTOR_S2_PROFILE.to_string(),
+        name: "Traktor Kontrol S2".to_string(),
+        device_name_hint: Some("traktor kontrol s2".to_string()),
+        bindings,
+        leds,
+    }
+}
@@ -8,16 +8,22 @@ use std::{
     time::{Duration, Instant},
 };
 
-use midir::{Ignore, MidiInput, MidiInputConnection};
-use tauri::{AppHandle, Emitter};
+use midir::{Ignore, MidiInput, MidiInputConnection, MidiOutput, MidiOutputConnection};
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::{
+    audio::{cart_wall, crossfade::DeckId},
+    state::AppState,
+};
 
 use super::{
     decode::{decode_message, DecodeState},
     executor::execute_action,
+    mapping::{self, LedSnapshot, MappingState, MidiControl, MidiMessage, MidiProfile},
     starlight_profile::{DEVICE_NAME_HINT, MASTER_VOLUME_CC, XFADE_CC, XFADE_STATUS},
     types::{
         now_ts_ms, ControllerAction, ControllerConfig, ControllerDevice, ControllerErrorEvent,
-        ControllerStatus,
+        ControllerStatus, RawMidiEvent,
     },
 };
 
@@ -31,6 +37,9 @@ const XFADE_RELATIVE_DEADZONE: f32 = 0.02;
 const JOG_FLUSH_INTERVAL_MS: u64 = 80;
 const JOG_FLUSH_STEP_TRIGGER: i16 = 4;
 const JOG_MAX_BATCH_STEPS: i16 = 12;
+const LED_REFRESH_MS: u64 = 100;
+/// Resend every LED this often in case the device dropped a message.
+const LED_RESYNC_MS: u64 = 5_000;
 
 struct AnalogState {
     last_sent_at: Instant,
@@ -75,11 +84,17 @@ struct ControllerInner {
     connection: Option<MidiInputConnection<()>>,
     decode_state: DecodeState,
     analog_state: HashMap<String, AnalogState>,
-    jog_state: HashMap<crate::audio::crossfade::DeckId, JogState>,
+    jog_state: HashMap<DeckId, JogState>,
     crossfader_state: CrossfaderState,
     learned_headphone_level_cc: Option<u8>,
+    /// Custom profile; `None` uses the built-in Starlight decoder
+    mapping_profile: Option<Arc<MidiProfile>>,
+    mapping_state: MappingState,
+    output: Option<MidiOutputConnection>,
+    led_values: HashMap<MidiControl, u8>,
     worker_started: bool,
     reconnect_started: bool,
+    led_started: bool,
 }
 
 #[derive(Clone)]
@@ -102,8 +117,13 @@ impl ControllerService {
                 jog_state: HashMap::new(),
                 crossfader_state: CrossfaderState::default(),
                 learned_headphone_level_cc: None,
+                mapping_profile: None,
+                mapping_state: MappingState::default(),
+                output: None,
+                led_values: HashMap::new(),
                 worker_started: false,
                 reconnect_started: false,
+                led_started: false,
             })),
             action_tx,
             action_rx: Arc::new(Mutex::new(Some(action_rx))),
@@ -146,6 +166,7 @@ impl ControllerService {
         };
         if should_start_reconnect {
             let service = self.clone();
+            let app_handle = app_handle.clone();
             thread::Builder::new()
                 .name("controller-reconnect".to_string())
                 .spawn(move || loop {
@@ -160,6 +181,29 @@ impl ControllerService {
                 })
                 .ok();
         }
+
+        let should_start_leds = {
+            let mut inner = self.inner.lock().unwrap();
+            !std::mem::replace(&mut inner.led_started, true)
+        };
+        if should_start_leds {
+            let service = self.clone();
+            let app = app_handle.clone();
+            thread::Builder::new()
+                .name("controller-led-feedback".to_string())
+                .spawn(move || {
+                    let mut last_resync = Instant::now();
+                    loop {
+                        thread::sleep(Duration::from_millis(LED_REFRESH_MS));
+                        let resync = last_resync.elapsed() >= Duration::from_millis(LED_RESYNC_MS);
+                        if resync {
+                            last_resync = Instant::now();
+                        }
+                        service.refresh_leds(&app, resync);
+                    }
+                })
+                .ok();
+        }
     }
 
     pub fn get_config(&self) -> ControllerConfig {
@@ -187,6 +231,32 @@ impl ControllerService {
         self.inner.lock().unwrap().status.clone()
     }
 
+    /// Switch to a custom mapping profile, or back to the built-in decoder.
+    pub fn set_mapping_profile(&self, profile: Option<MidiProfile>) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.mapping_profile = profile.map(Arc::new);
+        inner.mapping_state = MappingState::default();
+        inner.analog_state.clear();
+        inner.led_values.clear();
+    }
+
+    pub fn mapping_profile_id(&self) -> Option<String> {
+        let inner = self.inner.lock().unwrap();
+        inner.mapping_profile.as_ref().map(|p| p.id.clone())
+    }
+
+    /// In learn mode every inbound message is emitted as
+    /// `controller_midi_event` and nothing is dispatched to the engine.
+    pub fn set_learn_mode(&self, enabled: bool, app_handle: &AppHandle) -> ControllerStatus {
+        let status = {
+            let mut inner = self.inner.lock().unwrap();
+            inner.status.learn_mode = enabled;
+            inner.status.clone()
+        };
+        let _ = app_handle.emit("controller_status_changed", status.clone());
+        status
+    }
+
     pub fn list_devices(&self) -> Result<Vec<ControllerDevice>, String> {
         let input = MidiInput::new("desizone-controller-discovery")
             .map_err(|e| format!("MIDI init failed: {e}"))?;
@@ -218,6 +288,20 @@ impl ControllerService {
         app_handle: &AppHandle,
     ) -> Result<ControllerStatus, String> {
         let preferred_device_id = self.get_config().preferred_device_id;
+        let name_hint = {
+            let inner = self.inner.lock().unwrap();
+            inner
+                .mapping_profile
+                .as_ref()
+                .and_then(|p| p.device_name_hint.clone())
+                .filter(|h| !h.trim().is_empty())
+        };
+        let matches_profile = |name: &str| match name_hint.as_deref() {
+            Some(hint) => name
+                .to_ascii_lowercase()
+                .contains(&hint.trim().to_ascii_lowercase()),
+            None => is_starlight_name(name),
+        };
         let mut input = MidiInput::new("desizone-controller-input")
             .map_err(|e| format!("MIDI init failed: {e}"))?;
         input.ignore(Ignore::None);
@@ -247,7 +331,7 @@ impl ControllerService {
         if selected.is_none() {
             for (idx, port) in ports.iter().enumerate() {
                 if let Ok(name) = input.port_name(port) {
-                    if is_starlight_name(&name) {
+                    if matches_profile(&name) {
                         selected = Some((idx, name));
                         break;
                     }
@@ -295,12 +379,16 @@ impl ControllerService {
                 (),
             )
             .map_err(|e| format!("Failed to connect MIDI input: {e}"))?;
+        let output = open_output(&name);
 
         let status = {
             let mut inner = self.inner.lock().unwrap();
             // Drop any previous connection first.
             let _ = inner.connection.take();
             inner.connection = Some(conn);
+            inner.output = output;
+            inner.led_values.clear();
+            inner.mapping_state = MappingState::default();
             inner.jog_state.clear();
             inner.crossfader_state = CrossfaderState::default();
             inner.learned_headphone_level_cc = None;
@@ -321,6 +409,8 @@ impl ControllerService {
         let status = {
             let mut inner = self.inner.lock().unwrap();
             let _ = inner.connection.take();
+            let _ = inner.output.take();
+            inner.led_values.clear();
             inner.jog_state.clear();
             inner.crossfader_state = CrossfaderState::default();
             inner.learned_headphone_level_cc = None;
@@ -338,8 +428,27 @@ impl ControllerService {
         let actions = {
             let mut inner = self.inner.lock().unwrap();
             inner.status.last_event_at = Some(now_ts_ms());
+            if inner.status.learn_mode {
+                drop(inner);
+                let parsed = MidiMessage::parse(message);
+                let _ = app_handle.emit(
+                    "controller_midi_event",
+                    RawMidiEvent {
+                        control: parsed.map(|m| m.control),
+                        value: parsed.map(|m| m.value).unwrap_or(0),
+                        bytes: message.to_vec(),
+                        timestamp: now_ts_ms(),
+                    },
+                );
+                return;
+            }
+            let profile = inner.mapping_profile.clone();
+            let decoded = match profile.as_deref() {
+                Some(profile) => mapping::decode(profile, &mut inner.mapping_state, message),
+                None => decode_message(&mut inner.decode_state, message),
+            };
             let mut actions = Vec::new();
-            for action in decode_message(&mut inner.decode_state, message) {
+            for action in decoded {
                 match action {
                     ControllerAction::JogNudge { deck, delta_steps } => {
                         if let Some(jog_action) =
@@ -351,7 +460,7 @@ impl ControllerService {
                     ControllerAction::SetCrossfader {
                         position,
                         normalized,
-                    } => {
+                    } if profile.is_none() => {
                         if let Some(mapped) = self.normalize_crossfader_action(
                             &mut inner, position, normalized, app_handle,
                         ) {
@@ -367,10 +476,13 @@ impl ControllerService {
                     }
                 }
             }
-            if let Some(action) = self.maybe_decode_headphone_level(&mut inner, message, app_handle)
-            {
-                if self.should_dispatch_action(&mut inner, &action) {
-                    actions.push(action);
+            if profile.is_none() {
+                if let Some(action) =
+                    self.maybe_decode_headphone_level(&mut inner, message, app_handle)
+                {
+                    if self.should_dispatch_action(&mut inner, &action) {
+                        actions.push(action);
+                    }
                 }
             }
             self.flush_due_jog_actions(&mut inner, &mut actions);
@@ -511,7 +623,7 @@ impl ControllerService {
     fn accumulate_jog_action(
         &self,
         inner: &mut ControllerInner,
-        deck: crate::audio::crossfade::DeckId,
+        deck: DeckId,
         delta_steps: i8,
     ) -> Option<ControllerAction> {
         if delta_steps == 0 {
@@ -567,6 +679,47 @@ impl ControllerService {
         }
     }
 
+    /// Push changed LED values for the active custom profile.
+    fn refresh_leds(&self, app_handle: &AppHandle, resync: bool) {
+        let (profile, shift, pad_modes) = {
+            let inner = self.inner.lock().unwrap();
+            if inner.output.is_none() || inner.status.learn_mode {
+                return;
+            }
+            match inner.mapping_profile.clone() {
+                Some(p) if !p.leds.is_empty() => (
+                    p,
+                    inner.mapping_state.shift_pressed,
+                    inner.mapping_state.pad_modes.clone(),
+                ),
+                _ => return,
+            }
+        };
+        let mut snapshot = led_snapshot(app_handle);
+        snapshot.shift = shift;
+        snapshot.pad_modes = pad_modes;
+
+        let mut inner = self.inner.lock().unwrap();
+        let ControllerInner {
+            output, led_values, ..
+        } = &mut *inner;
+        let Some(output) = output.as_mut() else {
+            return;
+        };
+        for led in &profile.leds {
+            let value = snapshot.value(led);
+            if !resync && led_values.get(&led.control) == Some(&value) {
+                continue;
+            }
+            let Some(bytes) = led.control.message(value) else {
+                continue;
+            };
+            if output.send(&bytes).is_ok() {
+                led_values.insert(led.control, value);
+            }
+        }
+    }
+
     fn set_last_error(&self, message: String, app_handle: &AppHandle) {
         let status = {
             let mut inner = self.inner.lock().unwrap();
@@ -630,6 +783,66 @@ impl ControllerService {
     }
 }
 
+fn led_snapshot(app_handle: &AppHandle) -> LedSnapshot {
+    let state = app_handle.state::<AppState>();
+    let mut snapshot = LedSnapshot {
+        mic_open: state.mic_input.is_on_air(),
+        ..LedSnapshot::default()
+    };
+    let engine = state.engine.lock().unwrap();
+    for deck in [DeckId::DeckA, DeckId::DeckB] {
+        let Some(deck_state) = engine.get_deck_state(deck) else {
+            continue;
+        };
+        if deck_state.state == "playing" || deck_state.state == "crossfading" {
+            snapshot.playing.insert(deck);
+        }
+        if deck_state.cue_preview_enabled {
+            snapshot.cue_preview.insert(deck);
+        }
+        if deck_state.loop_enabled {
+            snapshot.looping.insert(deck);
+        }
+        if deck_state.reverse || deck_state.censoring {
+            snapshot.reversing.insert(deck);
+        }
+    }
+    for vu in engine.get_vu_readings() {
+        let deck = match vu.channel.as_str() {
+            "master" => None,
+            "deck_a" => Some(DeckId::DeckA),
+            "deck_b" => Some(DeckId::DeckB),
+            _ => continue,
+        };
+        snapshot.vu_db.insert(deck, vu.left_db.max(vu.right_db));
+    }
+    let page = cart_wall::active_page();
+    snapshot.carts = engine
+        .cart_states()
+        .into_iter()
+        .filter(|c| c.page == page)
+        .map(|c| c.slot)
+        .collect();
+    snapshot
+}
+
+/// Open the output side of the controller for LED feedback. Most devices
+/// expose input and output ports under the same name.
+fn open_output(input_name: &str) -> Option<MidiOutputConnection> {
+    let output = MidiOutput::new("desizone-controller-output").ok()?;
+    let port = output
+        .ports()
+        .into_iter()
+        .find(|p| output.port_name(p).is_ok_and(|n| n == input_name))?;
+    match output.connect(&port, "desizone-controller-leds") {
+        Ok(conn) => Some(conn),
+        Err(e) => {
+            log::warn!("Controller LED output not opened: {e}");
+            None
+        }
+    }
+}
+
 fn is_starlight_name(name: &str) -> bool {
     name.to_ascii_lowercase().contains(DEVICE_NAME_HINT)
 }
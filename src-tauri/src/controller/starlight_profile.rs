@@ -24,6 +24,10 @@ pub const LOOP_PAD_1_NOTE: u8 = 0x10;
 pub const LOOP_PAD_4_NOTE: u8 = 0x13;
 pub const LOOP_PAD_SHIFT_1_NOTE: u8 = 0x18;
 pub const LOOP_PAD_SHIFT_4_NOTE: u8 = 0x1B;
+pub const SAMPLER_PAD_1_NOTE: u8 = 0x30;
+pub const SAMPLER_PAD_4_NOTE: u8 = 0x33;
+pub const SAMPLER_PAD_SHIFT_1_NOTE: u8 = 0x38;
+pub const SAMPLER_PAD_SHIFT_4_NOTE: u8 = 0x3B;
 
 pub const XFADE_STATUS: u8 = 0xB0;
 pub const XFADE_CC: u8 = 0x00;
@@ -1,6 +1,8 @@
 use serde::{Deserialize, Serialize};
 
-use crate::audio::crossfade::DeckId;
+use crate::audio::{crossfade::DeckId, dsp::eq::EqBand};
+
+use super::mapping::MidiControl;
 
 pub const STARLIGHT_PROFILE: &str = "hercules_djcontrol_starlight";
 
@@ -40,6 +42,9 @@ pub struct ControllerStatus {
     pub profile: String,
     pub last_error: Option<String>,
     pub last_event_at: Option<i64>,
+    /// Raw MIDI is streamed to the UI and no actions fire
+    #[serde(default)]
+    pub learn_mode: bool,
 }
 
 impl Default for ControllerStatus {
@@ -52,6 +57,7 @@ impl Default for ControllerStatus {
             profile: STARLIGHT_PROFILE.to_string(),
             last_error: None,
             last_event_at: None,
+            learn_mode: false,
         }
     }
 }
@@ -62,7 +68,29 @@ pub struct ControllerErrorEvent {
     pub timestamp: i64,
 }
 
-#[derive(Debug, Clone)]
+/// Queue entry under the browse encoder.
+#[derive(Debug, Clone, Serialize)]
+pub struct ControllerBrowseEvent {
+    pub index: usize,
+    pub queue_id: i64,
+    pub song_id: i64,
+    pub artist: Option<String>,
+    pub title: Option<String>,
+    /// Set once the entry has been loaded onto this deck
+    pub loaded_deck: Option<DeckId>,
+}
+
+/// Inbound message forwarded to the UI while learn mode is on.
+#[derive(Debug, Clone, Serialize)]
+pub struct RawMidiEvent {
+    /// `None` for system / unsupported messages
+    pub control: Option<MidiControl>,
+    pub value: u8,
+    pub bytes: Vec<u8>,
+    pub timestamp: i64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum ControllerAction {
     TogglePlay {
         deck: DeckId,
@@ -111,6 +139,10 @@ pub enum ControllerAction {
         amount: f32,
         normalized: f32,
     },
+    ToggleEqKill {
+        deck: DeckId,
+        band: EqBand,
+    },
     SetCrossfader {
         position: f32,
         normalized: f32,
@@ -131,6 +163,32 @@ pub enum ControllerAction {
         deck: DeckId,
         delta_steps: i8,
     },
+    /// Cart slot on the active cart wall page
+    TriggerCart {
+        slot: u8,
+    },
+    StopCart {
+        slot: u8,
+    },
+    ToggleReverse {
+        deck: DeckId,
+    },
+    ToggleSlip {
+        deck: DeckId,
+    },
+    /// Slip-reverse while held
+    Censor {
+        deck: DeckId,
+        active: bool,
+    },
+    /// Move the browse selection through the play queue
+    BrowseQueue {
+        delta: i8,
+    },
+    /// Load the browsed queue entry onto a deck
+    LoadBrowsed {
+        deck: DeckId,
+    },
 }
 
 impl ControllerAction {
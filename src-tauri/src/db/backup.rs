@@ -0,0 +1,426 @@
+/// Scheduled snapshots of the local database.
+///
+/// Each backup is a folder named by its local timestamp holding `app.db` — a
+/// consistent copy taken with `VACUUM INTO` while the app runs, verified with
+/// `PRAGMA quick_check` — and `settings.json.gz`, a full settings archive
+/// that also carries the in-memory script library. Snapshots are assembled in
+/// a `.partial` folder and renamed into place, so a power cut mid-backup never
+/// leaves a half-written one behind.
+///
+/// The database can't be swapped under a live pool: `stage_restore` copies
+/// the chosen snapshot beside `app.db` and `apply_pending_restore` moves it
+/// into place on the next launch, before the pool opens. When `app.db` won't
+/// open at all, `recover_from_latest` does the same with the newest snapshot.
+/// The backup config is a JSON file next to the database for the same reason:
+/// it has to be readable when the database isn't.
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use chrono::{Local, NaiveDateTime, TimeZone};
+use serde::{Deserialize, Serialize};
+use sqlx::{sqlite::SqliteConnectOptions, ConnectOptions, Connection, SqlitePool};
+use tauri::{AppHandle, Manager};
+
+use crate::{
+    analytics::event_logger::{log_event, EventCategory, LogLevel},
+    settings_archive::{self, Section, SettingsArchive},
+    state::AppState,
+};
+
+const CONFIG_FILE: &str = "backup_config.json";
+const DB_FILE: &str = "app.db";
+const SETTINGS_FILE: &str = "settings.json.gz";
+const ID_FORMAT: &str = "%Y%m%d-%H%M%S";
+const PARTIAL_SUFFIX: &str = ".partial";
+const TICK: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BackupConfig {
+    pub enabled: bool,
+    pub interval_hours: u32,
+    /// Default: `backups` in the app data folder
+    pub backup_dir: Option<String>,
+    /// Newest snapshots always kept
+    pub keep_last: u32,
+    /// Older snapshots beyond `keep_last` are deleted (0 = keep by count only)
+    pub max_age_days: u32,
+}
+
+impl Default for BackupConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            interval_hours: 24,
+            backup_dir: None,
+            keep_last: 7,
+            max_age_days: 30,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupInfo {
+    /// Folder name, `YYYYMMDD-HHMMSS` local time
+    pub id: String,
+    /// Unix ms
+    pub created_at: i64,
+    pub path: String,
+    pub db_bytes: u64,
+    pub has_settings: bool,
+}
+
+fn app_data_dir() -> PathBuf {
+    PathBuf::from(crate::compute_app_data_dir())
+}
+
+pub fn load_config() -> BackupConfig {
+    std::fs::read(app_data_dir().join(CONFIG_FILE))
+        .ok()
+        .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+        .unwrap_or_default()
+}
+
+pub fn save_config(config: &BackupConfig) -> Result<(), String> {
+    let json = serde_json::to_vec_pretty(config).map_err(|e| e.to_string())?;
+    std::fs::write(app_data_dir().join(CONFIG_FILE), json)
+        .map_err(|e| format!("Cannot save backup config: {e}"))
+}
+
+pub fn backup_dir(config: &BackupConfig) -> PathBuf {
+    config
+        .backup_dir
+        .as_deref()
+        .filter(|d| !d.trim().is_empty())
+        .map(PathBuf::from)
+        .unwrap_or_else(|| app_data_dir().join("backups"))
+}
+
+fn parse_id(id: &str) -> Option<i64> {
+    let naive = NaiveDateTime::parse_from_str(id, ID_FORMAT).ok()?;
+    Local
+        .from_local_datetime(&naive)
+        .earliest()
+        .map(|t| t.timestamp_millis())
+}
+
+fn backup_info(path: &Path) -> Option<BackupInfo> {
+    let id = path.file_name()?.to_str()?.to_string();
+    let created_at = parse_id(&id)?;
+    let db_bytes = path.join(DB_FILE).metadata().ok()?.len();
+    Some(BackupInfo {
+        created_at,
+        path: path.to_string_lossy().to_string(),
+        db_bytes,
+        has_settings: path.join(SETTINGS_FILE).is_file(),
+        id,
+    })
+}
+
+/// Complete snapshots in the configured folder, newest first.
+pub fn list_backups(config: &BackupConfig) -> Vec<BackupInfo> {
+    let Ok(entries) = std::fs::read_dir(backup_dir(config)) else {
+        return Vec::new();
+    };
+    let mut backups: Vec<BackupInfo> = entries
+        .flatten()
+        .filter_map(|entry| backup_info(&entry.path()))
+        .collect();
+    backups.sort_by_key(|b| std::cmp::Reverse(b.created_at));
+    backups
+}
+
+/// Snapshots (newest first) that fall outside the retention policy.
+fn expired<'a>(backups: &'a [BackupInfo], config: &BackupConfig, now_ms: i64) -> Vec<&'a str> {
+    let max_age_ms = i64::from(config.max_age_days) * 86_400_000;
+    backups
+        .iter()
+        .enumerate()
+        .skip(config.keep_last.max(1) as usize)
+        .filter(|(_, b)| max_age_ms == 0 || now_ms - b.created_at > max_age_ms)
+        .map(|(_, b)| b.id.as_str())
+        .collect()
+}
+
+/// Delete snapshots outside the retention policy; returns how many went.
+pub fn prune(config: &BackupConfig) -> usize {
+    let backups = list_backups(config);
+    let dir = backup_dir(config);
+    let mut removed = 0;
+    for id in expired(&backups, config, chrono::Utc::now().timestamp_millis()) {
+        match std::fs::remove_dir_all(dir.join(id)) {
+            Ok(()) => removed += 1,
+            Err(e) => log::warn!("Cannot remove old backup {id}: {e}"),
+        }
+    }
+    removed
+}
+
+async fn quick_check(path: &Path) -> Result<(), String> {
+    let mut conn = SqliteConnectOptions::new()
+        .filename(path)
+        .read_only(true)
+        .connect()
+        .await
+        .map_err(|e| format!("Cannot open {}: {e}", path.display()))?;
+    let result: String = sqlx::query_scalar("PRAGMA quick_check")
+        .fetch_one(&mut conn)
+        .await
+        .map_err(|e| format!("Integrity check failed: {e}"))?;
+    let _ = conn.close().await;
+    if result == "ok" {
+        Ok(())
+    } else {
+        Err(format!("Integrity check failed: {result}"))
+    }
+}
+
+/// Snapshot the database and settings now, then apply retention.
+pub async fn create_backup(state: &AppState) -> Result<BackupInfo, String> {
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or("Local database unavailable")?;
+    let archive = settings_archive::export(state, &Section::ALL).await?;
+    write_backup(pool, Some(&archive)).await
+}
+
+/// Snapshot the database alone, for use before the app state exists (e.g.
+/// ahead of schema migrations).
+pub async fn snapshot_database(pool: &SqlitePool) -> Result<BackupInfo, String> {
+    write_backup(pool, None).await
+}
+
+async fn write_backup(
+    pool: &SqlitePool,
+    archive: Option<&SettingsArchive>,
+) -> Result<BackupInfo, String> {
+    let config = load_config();
+    let dir = backup_dir(&config);
+    let id = Local::now().format(ID_FORMAT).to_string();
+    let target = dir.join(&id);
+    if target.exists() {
+        return Err(format!("Backup {id} already exists"));
+    }
+    let partial = dir.join(format!("{id}{PARTIAL_SUFFIX}"));
+    let _ = std::fs::remove_dir_all(&partial);
+    std::fs::create_dir_all(&partial).map_err(|e| format!("Cannot create backup folder: {e}"))?;
+
+    let result = async {
+        let db_file = partial.join(DB_FILE);
+        sqlx::query("VACUUM INTO ?")
+            .bind(db_file.to_string_lossy().to_string())
+            .execute(pool)
+            .await
+            .map_err(|e| format!("Database snapshot failed: {e}"))?;
+        quick_check(&db_file).await?;
+        if let Some(archive) = archive {
+            settings_archive::write_archive(&partial.join(SETTINGS_FILE), archive)?;
+        }
+        std::fs::rename(&partial, &target).map_err(|e| format!("Cannot finish backup: {e}"))
+    }
+    .await;
+    if let Err(e) = result {
+        let _ = std::fs::remove_dir_all(&partial);
+        return Err(e);
+    }
+
+    let removed = prune(&config);
+    if removed > 0 {
+        log::info!("Removed {removed} old backup(s)");
+    }
+    backup_info(&target).ok_or_else(|| "Backup finished but cannot be read back".to_string())
+}
+
+fn find_backup(config: &BackupConfig, id: &str) -> Result<BackupInfo, String> {
+    // Ids are timestamps; anything else could be a path escape.
+    if parse_id(id).is_none() {
+        return Err(format!("Unknown backup {id}"));
+    }
+    backup_info(&backup_dir(config).join(id)).ok_or_else(|| format!("Unknown backup {id}"))
+}
+
+fn pending_restore_path(db_path: &Path) -> PathBuf {
+    db_path.with_extension("db.restore")
+}
+
+/// Copy snapshot `id` beside the live database, to be swapped in at the next
+/// launch by `apply_pending_restore`.
+pub async fn stage_restore(id: &str) -> Result<BackupInfo, String> {
+    let backup = find_backup(&load_config(), id)?;
+    let source = Path::new(&backup.path).join(DB_FILE);
+    quick_check(&source).await?;
+    let staged = pending_restore_path(&app_data_dir().join(DB_FILE));
+    std::fs::copy(&source, &staged).map_err(|e| format!("Cannot stage restore: {e}"))?;
+    Ok(backup)
+}
+
+/// Replace `db_path` with `source`, keeping the old file as `aside`.
+fn swap_in(db_path: &Path, source: &Path, aside: &Path, keep_source: bool) -> Result<(), String> {
+    if db_path.exists() {
+        std::fs::rename(db_path, aside)
+            .map_err(|e| format!("Cannot move {} aside: {e}", db_path.display()))?;
+    }
+    // A stale WAL would be replayed on top of the restored file.
+    for suffix in ["-wal", "-shm", "-journal"] {
+        let mut sidecar = db_path.as_os_str().to_owned();
+        sidecar.push(suffix);
+        let _ = std::fs::remove_file(PathBuf::from(sidecar));
+    }
+    let copied = if keep_source {
+        std::fs::copy(source, db_path).map(|_| ())
+    } else {
+        std::fs::rename(source, db_path)
+    };
+    copied.map_err(|e| format!("Cannot restore database: {e}"))
+}
+
+/// Swap in a restore staged by `stage_restore`. Call before opening the pool.
+pub fn apply_pending_restore(db_path: &Path) {
+    let staged = pending_restore_path(db_path);
+    if !staged.is_file() {
+        return;
+    }
+    let aside = db_path.with_extension("db.pre-restore");
+    match swap_in(db_path, &staged, &aside, false) {
+        Ok(()) => log::warn!(
+            "Restored local database from backup; previous copy kept at {}",
+            aside.display()
+        ),
+        Err(e) => log::error!("Staged database restore failed: {e}"),
+    }
+}
+
+/// Whether opening the database failed because the file itself is damaged
+/// (as opposed to locked, missing permissions, …).
+pub fn is_corruption(e: &sqlx::Error) -> bool {
+    let db_err = match e {
+        sqlx::Error::Database(db_err) => db_err,
+        // Raised by the pre-migration integrity check
+        sqlx::Error::Protocol(message) => return message.contains("malformed"),
+        _ => return false,
+    };
+    // SQLITE_CORRUPT / SQLITE_NOTADB, including extended codes
+    let code = db_err
+        .code()
+        .and_then(|c| c.parse::<i32>().ok())
+        .map(|c| c & 0xFF);
+    matches!(code, Some(11) | Some(26))
+        || db_err.message().contains("malformed")
+        || db_err.message().contains("not a database")
+}
+
+/// Replace an unreadable database with the newest snapshot.
+pub fn recover_from_latest(db_path: &Path) -> Result<BackupInfo, String> {
+    let backup = list_backups(&load_config())
+        .into_iter()
+        .next()
+        .ok_or("No backups available")?;
+    let stamp = Local::now().format(ID_FORMAT);
+    let aside = db_path.with_extension(format!("db.corrupt-{stamp}"));
+    swap_in(
+        db_path,
+        &Path::new(&backup.path).join(DB_FILE),
+        &aside,
+        true,
+    )?;
+    Ok(backup)
+}
+
+/// Take a snapshot whenever the newest one is older than the configured
+/// interval.
+pub fn start(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(TICK).await;
+            let config = load_config();
+            if !config.enabled {
+                continue;
+            }
+            let interval_ms = i64::from(config.interval_hours.max(1)) * 3_600_000;
+            let now_ms = chrono::Utc::now().timestamp_millis();
+            let newest = list_backups(&config).first().map(|b| b.created_at);
+            if newest.is_some_and(|at| now_ms - at < interval_ms) {
+                continue;
+            }
+
+            let state = app.state::<AppState>();
+            let Some(pool) = state.local_db.clone() else {
+                continue;
+            };
+            let (level, event, message, metadata) = match create_backup(&state).await {
+                Ok(backup) => (
+                    LogLevel::Info,
+                    "backup_created",
+                    format!("Database backup {} created", backup.id),
+                    serde_json::json!({ "id": backup.id, "db_bytes": backup.db_bytes }),
+                ),
+                Err(e) => (
+                    LogLevel::Error,
+                    "backup_failed",
+                    format!("Database backup failed: {e}"),
+                    serde_json::json!({ "error": e }),
+                ),
+            };
+            let _ = log_event(
+                &pool,
+                level,
+                EventCategory::Database,
+                event,
+                &message,
+                Some(metadata),
+                None,
+                None,
+                None,
+            )
+            .await;
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn backup(id: &str, created_at: i64) -> BackupInfo {
+        BackupInfo {
+            id: id.to_string(),
+            created_at,
+            path: String::new(),
+            db_bytes: 0,
+            has_settings: true,
+        }
+    }
+
+    #[test]
+    fn retention_keeps_newest_and_drops_old() {
+        let day = 86_400_000;
+        let now = 100 * day;
+        let backups: Vec<BackupInfo> = (0..6)
+            .map(|i| backup(&format!("b{i}"), now - i * 10 * day))
+            .collect();
+        let config = BackupConfig {
+            keep_last: 2,
+            max_age_days: 25,
+            ..Default::default()
+        };
+        // b2 is beyond keep_last but only 20 days old
+        assert_eq!(expired(&backups, &config, now), vec!["b3", "b4", "b5"]);
+
+        let by_count = BackupConfig {
+            keep_last: 4,
+            max_age_days: 0,
+            ..Default::default()
+        };
+        assert_eq!(expired(&backups, &by_count, now), vec!["b4", "b5"]);
+
+        let keep_none = BackupConfig {
+            keep_last: 0,
+            max_age_days: 1,
+            ..Default::default()
+        };
+        assert_eq!(expired(&backups, &keep_none, now).len(), 5);
+        assert!(parse_id("../../etc").is_none());
+        assert!(parse_id("20260101-120000").is_some());
+    }
+}
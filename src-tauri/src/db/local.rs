@@ -1,25 +1,28 @@
 use serde::{Deserialize, Serialize};
 use sqlx::{sqlite::SqlitePool, Row};
 
+use crate::audio::crossfade::CrossfadeConfig;
 use crate::stream::encoder_manager::EncoderConfig;
 
 /// Initialise (or migrate) the local SQLite database at `db_path`.
-/// Creates all tables if they don't exist.
+/// Applies any pending schema migrations.
 pub async fn init_db(db_path: &str) -> Result<SqlitePool, sqlx::Error> {
     let url = format!("sqlite:{db_path}?mode=rwc");
     let pool = SqlitePool::connect(&url).await?;
-    run_migrations(&pool).await?;
+    super::migrations::run(&pool).await?;
     Ok(pool)
 }
 
-async fn run_migrations(pool: &SqlitePool) -> Result<(), sqlx::Error> {
-    sqlx::query(
-        r#"
+/// Every table as of schema version 1. Idempotent, so it also adopts
+/// databases created before versioned migrations; later changes go in
+/// `migrations::MIGRATIONS`.
+pub(super) const BASELINE_SCHEMA: &str = r#"
         CREATE TABLE IF NOT EXISTS cue_points (
             id          INTEGER PRIMARY KEY AUTOINCREMENT,
             song_id     INTEGER NOT NULL,
             name        TEXT    NOT NULL,
             position_ms INTEGER NOT NULL,
+            end_ms      INTEGER,
             cue_kind    TEXT    NOT NULL DEFAULT 'memory',
             slot        INTEGER,
             label       TEXT    NOT NULL DEFAULT '',
@@ -40,6 +43,24 @@ async fn run_migrations(pool: &SqlitePool) -> Result<(), sqlx::Error> {
             gain_db             REAL
         );
 
+        -- Default gain trim per SAM category or song type, added to the
+        -- per-song gain override on deck load
+        CREATE TABLE IF NOT EXISTS category_gain_trims (
+            kind        TEXT    NOT NULL,
+            name        TEXT    NOT NULL COLLATE NOCASE,
+            gain_db     REAL    NOT NULL,
+            updated_at  INTEGER NOT NULL DEFAULT (strftime('%s','now')),
+            PRIMARY KEY (kind, name)
+        );
+
+        CREATE TABLE IF NOT EXISTS song_playback_flags (
+            song_id                 INTEGER PRIMARY KEY,
+            never_crossfade         INTEGER NOT NULL DEFAULT 0,
+            always_segue            INTEGER NOT NULL DEFAULT 0,
+            attached_sweeper_path   TEXT,
+            updated_at              INTEGER NOT NULL DEFAULT (strftime('%s','now'))
+        );
+
         CREATE TABLE IF NOT EXISTS channel_dsp_settings (
             channel             TEXT    PRIMARY KEY,
             eq_low_gain_db      REAL    DEFAULT 0.0,
@@ -92,6 +113,14 @@ async fn run_migrations(pool: &SqlitePool) -> Result<(), sqlx::Error> {
             PRIMARY KEY (playlist_id, song_id)
         );
 
+        -- Per-playlist cursor for PlaylistOrder clockwheel slots
+        CREATE TABLE IF NOT EXISTS playlist_cursor (
+            playlist_id   INTEGER PRIMARY KEY,
+            next_position INTEGER NOT NULL DEFAULT 0,
+            last_song_id  INTEGER,
+            updated_at    INTEGER NOT NULL DEFAULT (strftime('%s','now'))
+        );
+
         -- Phase 3: Show Scheduler
         CREATE TABLE IF NOT EXISTS scheduled_shows (
             id               INTEGER PRIMARY KEY AUTOINCREMENT,
@@ -104,6 +133,79 @@ async fn run_migrations(pool: &SqlitePool) -> Result<(), sqlx::Error> {
             created_at       DATETIME DEFAULT CURRENT_TIMESTAMP
         );
 
+        -- Phase 3: Timed events (top-of-hour ID, news, ads)
+        CREATE TABLE IF NOT EXISTS timed_events (
+            id               INTEGER PRIMARY KEY AUTOINCREMENT,
+            name             TEXT    NOT NULL,
+            minute           INTEGER NOT NULL DEFAULT 0,
+            hours_json       TEXT    NOT NULL DEFAULT '[]',
+            days_json        TEXT    NOT NULL DEFAULT '[]',
+            song_id          INTEGER,
+            file_path        TEXT,
+            mode             TEXT    NOT NULL DEFAULT 'soft',
+            fade_ms          INTEGER NOT NULL DEFAULT 1500,
+            grace_minutes    INTEGER NOT NULL DEFAULT 10,
+            enabled          INTEGER NOT NULL DEFAULT 1,
+            last_fired_at    INTEGER
+        );
+
+        -- Relays: external streams rebroadcast at set times
+        CREATE TABLE IF NOT EXISTS relay_events (
+            id               INTEGER PRIMARY KEY AUTOINCREMENT,
+            name             TEXT    NOT NULL,
+            url              TEXT    NOT NULL,
+            minute           INTEGER NOT NULL DEFAULT 0,
+            hours_json       TEXT    NOT NULL DEFAULT '[]',
+            days_json        TEXT    NOT NULL DEFAULT '[]',
+            duration_minutes INTEGER NOT NULL DEFAULT 5,
+            fade_ms          INTEGER NOT NULL DEFAULT 1500,
+            enabled          INTEGER NOT NULL DEFAULT 1,
+            last_fired_at    INTEGER
+        );
+
+        -- Traffic: ad breaks, campaigns and proof-of-play log
+        CREATE TABLE IF NOT EXISTS traffic_breaks (
+            id                 INTEGER PRIMARY KEY AUTOINCREMENT,
+            name               TEXT    NOT NULL,
+            minute             INTEGER NOT NULL DEFAULT 0,
+            hours_json         TEXT    NOT NULL DEFAULT '[]',
+            days_json          TEXT    NOT NULL DEFAULT '[]',
+            max_spots          INTEGER NOT NULL DEFAULT 3,
+            max_duration_secs  INTEGER NOT NULL DEFAULT 0,
+            grace_minutes      INTEGER NOT NULL DEFAULT 10,
+            enabled            INTEGER NOT NULL DEFAULT 1,
+            last_fired_at      INTEGER
+        );
+
+        CREATE TABLE IF NOT EXISTS traffic_campaigns (
+            id                  INTEGER PRIMARY KEY AUTOINCREMENT,
+            advertiser          TEXT    NOT NULL,
+            title               TEXT    NOT NULL DEFAULT '',
+            sponsor_category    TEXT    NOT NULL DEFAULT '',
+            song_id             INTEGER,
+            file_path           TEXT,
+            duration_secs       INTEGER NOT NULL DEFAULT 30,
+            start_date          TEXT    NOT NULL,
+            end_date            TEXT    NOT NULL,
+            max_plays_per_day   INTEGER NOT NULL DEFAULT 0,
+            separation_minutes  INTEGER NOT NULL DEFAULT 0,
+            priority            INTEGER NOT NULL DEFAULT 0,
+            enabled             INTEGER NOT NULL DEFAULT 1
+        );
+
+        CREATE TABLE IF NOT EXISTS traffic_spot_log (
+            id             INTEGER PRIMARY KEY AUTOINCREMENT,
+            campaign_id    INTEGER NOT NULL,
+            break_id       INTEGER,
+            scheduled_at   INTEGER NOT NULL,
+            aired_at       INTEGER,
+            played_ms      INTEGER,
+            status         TEXT    NOT NULL DEFAULT 'scheduled',
+            make_good_for  INTEGER
+        );
+        CREATE INDEX IF NOT EXISTS idx_traffic_spot_log_scheduled ON traffic_spot_log(scheduled_at);
+        CREATE INDEX IF NOT EXISTS idx_traffic_spot_log_campaign ON traffic_spot_log(campaign_id, status);
+
         -- Phase 3: Request Policy
         CREATE TABLE IF NOT EXISTS request_policy (
             id          INTEGER PRIMARY KEY DEFAULT 1,
@@ -115,6 +217,7 @@ async fn run_migrations(pool: &SqlitePool) -> Result<(), sqlx::Error> {
             song_id            INTEGER NOT NULL,
             song_title         TEXT,
             artist             TEXT,
+            album              TEXT,
             requester_name     TEXT,
             requester_platform TEXT,
             requester_ip       TEXT,
@@ -152,17 +255,33 @@ async fn run_migrations(pool: &SqlitePool) -> Result<(), sqlx::Error> {
         CREATE TABLE IF NOT EXISTS autodj_clockwheel_state (
             id           INTEGER PRIMARY KEY DEFAULT 1,
             next_index   INTEGER NOT NULL DEFAULT 0,
+            template_id  INTEGER,
+            updated_at   INTEGER NOT NULL DEFAULT (strftime('%s','now'))
+        );
+
+        -- Named clockwheel slot lists and their 7x24 hour assignment
+        CREATE TABLE IF NOT EXISTS clockwheel_templates (
+            id           INTEGER PRIMARY KEY AUTOINCREMENT,
+            name         TEXT    NOT NULL,
+            slots_json   TEXT    NOT NULL DEFAULT '[]',
             updated_at   INTEGER NOT NULL DEFAULT (strftime('%s','now'))
         );
 
-        -- Cached waveform peaks for deck visualisation
-        CREATE TABLE IF NOT EXISTS waveform_cache (
-            file_path    TEXT    NOT NULL,
+        CREATE TABLE IF NOT EXISTS clockwheel_hour_grid (
+            day          INTEGER NOT NULL,
+            hour         INTEGER NOT NULL,
+            template_id  INTEGER NOT NULL REFERENCES clockwheel_templates(id),
+            PRIMARY KEY (day, hour)
+        );
+
+        -- Waveform peak pyramids (binary, see audio::analyzer::peaks); these
+        -- replace the per-resolution JSON rows of waveform_cache
+        DROP TABLE IF EXISTS waveform_cache;
+        CREATE TABLE IF NOT EXISTS waveform_peaks (
+            file_path    TEXT    PRIMARY KEY,
             mtime_ms     INTEGER NOT NULL,
-            resolution   INTEGER NOT NULL,
-            peaks_json   TEXT    NOT NULL,
-            updated_at   INTEGER NOT NULL,
-            PRIMARY KEY (file_path, mtime_ms, resolution)
+            data         BLOB    NOT NULL,
+            updated_at   INTEGER NOT NULL
         );
 
         CREATE TABLE IF NOT EXISTS beatgrid_analysis (
@@ -196,6 +315,42 @@ async fn run_migrations(pool: &SqlitePool) -> Result<(), sqlx::Error> {
             auto_fallback    INTEGER NOT NULL DEFAULT 1
         );
 
+        -- Voice track placements between songs
+        CREATE TABLE IF NOT EXISTS voice_tracks (
+            id               INTEGER PRIMARY KEY AUTOINCREMENT,
+            title            TEXT    NOT NULL,
+            file_path        TEXT    NOT NULL,
+            duration_ms      INTEGER,
+            prev_song_id     INTEGER,
+            next_song_id     INTEGER,
+            prev_overlap_ms  INTEGER NOT NULL DEFAULT 0,
+            next_overlap_ms  INTEGER NOT NULL DEFAULT 0,
+            trim_start_ms    INTEGER NOT NULL DEFAULT 0,
+            trim_end_ms      INTEGER NOT NULL DEFAULT 0,
+            gain_db          REAL    NOT NULL DEFAULT 0.0,
+            created_at       INTEGER NOT NULL DEFAULT (strftime('%s','now')),
+            played_at        INTEGER
+        );
+        CREATE INDEX IF NOT EXISTS idx_voice_tracks_prev ON voice_tracks(prev_song_id, played_at);
+
+        -- Live mic ducking of Deck A/B
+        CREATE TABLE IF NOT EXISTS mic_duck_config (
+            id          INTEGER PRIMARY KEY DEFAULT 1,
+            config_json TEXT    NOT NULL
+        );
+
+        -- Beat quantize for hot-cue/loop jumps (global mode + per-deck overrides)
+        CREATE TABLE IF NOT EXISTS quantize_config (
+            id          INTEGER PRIMARY KEY DEFAULT 1,
+            config_json TEXT    NOT NULL
+        );
+
+        -- Show export defaults (format, loudness target, upload target)
+        CREATE TABLE IF NOT EXISTS show_export_config (
+            id          INTEGER PRIMARY KEY DEFAULT 1,
+            config_json TEXT    NOT NULL
+        );
+
         CREATE TABLE IF NOT EXISTS controller_config (
             id                  INTEGER PRIMARY KEY DEFAULT 1,
             enabled             INTEGER NOT NULL DEFAULT 1,
@@ -205,6 +360,28 @@ async fn run_migrations(pool: &SqlitePool) -> Result<(), sqlx::Error> {
             updated_at          INTEGER NOT NULL DEFAULT (strftime('%s','now'))
         );
 
+        -- Custom MIDI controller mappings (learn mode)
+        CREATE TABLE IF NOT EXISTS controller_profiles (
+            id           TEXT PRIMARY KEY,
+            name         TEXT    NOT NULL,
+            profile_json TEXT    NOT NULL,
+            updated_at   INTEGER NOT NULL DEFAULT (strftime('%s','now'))
+        );
+
+        -- Global keyboard shortcut → action bindings
+        CREATE TABLE IF NOT EXISTS hotkey_config (
+            id          INTEGER PRIMARY KEY DEFAULT 1,
+            config_json TEXT    NOT NULL,
+            updated_at  INTEGER NOT NULL DEFAULT (strftime('%s','now'))
+        );
+
+        -- OSC remote control surface
+        CREATE TABLE IF NOT EXISTS osc_config (
+            id          INTEGER PRIMARY KEY DEFAULT 1,
+            config_json TEXT    NOT NULL,
+            updated_at  INTEGER NOT NULL DEFAULT (strftime('%s','now'))
+        );
+
         -- Phase 6: Gateway connection settings
         CREATE TABLE IF NOT EXISTS gateway_config (
             id              INTEGER PRIMARY KEY DEFAULT 1,
@@ -226,7 +403,8 @@ async fn run_migrations(pool: &SqlitePool) -> Result<(), sqlx::Error> {
             can_queue_add           INTEGER DEFAULT 1,
             can_queue_remove        INTEGER DEFAULT 0,
             can_trigger_crossfade   INTEGER DEFAULT 0,
-            can_set_autopilot       INTEGER DEFAULT 0
+            can_set_autopilot       INTEGER DEFAULT 0,
+            can_go_live             INTEGER DEFAULT 0
         );
 
         -- Phase 6: Remote DJ session log
@@ -252,17 +430,110 @@ async fn run_migrations(pool: &SqlitePool) -> Result<(), sqlx::Error> {
         );
 
         -- Phase 7: Hourly play counts
+        -- Keyed on the UTC start of the local hour; date/hour are station-local at play time
         CREATE TABLE IF NOT EXISTS hourly_play_counts (
+            hour_start_utc  INTEGER PRIMARY KEY,
             date            TEXT    NOT NULL,
             hour            INTEGER NOT NULL,
+            utc_offset_min  INTEGER NOT NULL DEFAULT 0,
             play_count      INTEGER DEFAULT 0,
-            unique_songs    INTEGER DEFAULT 0,
-            PRIMARY KEY (date, hour)
+            unique_songs    INTEGER DEFAULT 0
         );
 
         -- Phase 4: Encoder configurations
         CREATE TABLE IF NOT EXISTS encoder_configs (
-            id           INTEGER PRIMARY KEY,
+            id              INTEGER PRIMARY KEY,
+            config_json     TEXT    NOT NULL,
+            failover_group  TEXT,
+            priority        INTEGER NOT NULL DEFAULT 0,
+            updated_at      INTEGER NOT NULL DEFAULT (strftime('%s','now'))
+        );
+
+        -- Now-playing push targets (TuneIn, Live365, webhooks)
+        CREATE TABLE IF NOT EXISTS metadata_push_targets (
+            id           INTEGER PRIMARY KEY AUTOINCREMENT,
+            name         TEXT    NOT NULL,
+            enabled      INTEGER NOT NULL DEFAULT 1,
+            target_json  TEXT    NOT NULL,
+            updated_at   INTEGER NOT NULL DEFAULT (strftime('%s','now'))
+        );
+
+        -- Embedded listener request API
+        CREATE TABLE IF NOT EXISTS request_api_config (
+            id           INTEGER PRIMARY KEY DEFAULT 1,
+            config_json  TEXT    NOT NULL,
+            updated_at   INTEGER NOT NULL DEFAULT (strftime('%s','now'))
+        );
+
+        -- Transition markers derived from cue points, keyed by song.
+        -- Rows are dropped whenever the song's cue points change; a file
+        -- mtime or duration mismatch also forces a recompute.
+        CREATE TABLE IF NOT EXISTS transition_marker_cache (
+            song_id      INTEGER PRIMARY KEY,
+            markers_json TEXT    NOT NULL,
+            duration_ms  INTEGER NOT NULL,
+            file_mtime   INTEGER,
+            computed_at  INTEGER NOT NULL DEFAULT (strftime('%s','now'))
+        );
+        CREATE TRIGGER IF NOT EXISTS trg_cue_points_ins_marker_cache
+            AFTER INSERT ON cue_points
+            BEGIN DELETE FROM transition_marker_cache WHERE song_id = NEW.song_id; END;
+        CREATE TRIGGER IF NOT EXISTS trg_cue_points_upd_marker_cache
+            AFTER UPDATE ON cue_points
+            BEGIN DELETE FROM transition_marker_cache WHERE song_id IN (OLD.song_id, NEW.song_id); END;
+        CREATE TRIGGER IF NOT EXISTS trg_cue_points_del_marker_cache
+            AFTER DELETE ON cue_points
+            BEGIN DELETE FROM transition_marker_cache WHERE song_id = OLD.song_id; END;
+
+        -- Album art lookup settings
+        CREATE TABLE IF NOT EXISTS artwork_config (
+            id           INTEGER PRIMARY KEY DEFAULT 1,
+            config_json  TEXT    NOT NULL,
+            updated_at   INTEGER NOT NULL DEFAULT (strftime('%s','now'))
+        );
+
+        -- Cart wall: one-shot carts by page and row-major slot
+        CREATE TABLE IF NOT EXISTS carts (
+            page          INTEGER NOT NULL,
+            slot          INTEGER NOT NULL,
+            label         TEXT    NOT NULL DEFAULT '',
+            file_path     TEXT    NOT NULL,
+            song_id       INTEGER,
+            gain_db       REAL    NOT NULL DEFAULT 0,
+            fade_out_ms   INTEGER NOT NULL DEFAULT 0,
+            loop_enabled  INTEGER NOT NULL DEFAULT 0,
+            color_hex     TEXT,
+            hotkey        TEXT,
+            updated_at    INTEGER NOT NULL DEFAULT (strftime('%s','now')),
+            PRIMARY KEY (page, slot)
+        );
+
+        CREATE TABLE IF NOT EXISTS cart_wall_config (
+            id           INTEGER PRIMARY KEY DEFAULT 1,
+            config_json  TEXT    NOT NULL,
+            updated_at   INTEGER NOT NULL DEFAULT (strftime('%s','now'))
+        );
+
+        -- Last.fm / ListenBrainz scrobbling
+        CREATE TABLE IF NOT EXISTS scrobbler_config (
+            id           INTEGER PRIMARY KEY DEFAULT 1,
+            config_json  TEXT    NOT NULL,
+            updated_at   INTEGER NOT NULL DEFAULT (strftime('%s','now'))
+        );
+
+        CREATE TABLE IF NOT EXISTS scrobble_queue (
+            id           INTEGER PRIMARY KEY AUTOINCREMENT,
+            service      TEXT    NOT NULL,
+            payload_json TEXT    NOT NULL,
+            created_at   INTEGER NOT NULL,
+            attempts     INTEGER NOT NULL DEFAULT 0,
+            last_error   TEXT
+        );
+        CREATE INDEX IF NOT EXISTS idx_scrobble_queue_service ON scrobble_queue(service, id);
+
+        -- Station ID sign-on gate
+        CREATE TABLE IF NOT EXISTS station_id_gate_config (
+            id           INTEGER PRIMARY KEY DEFAULT 1,
             config_json  TEXT    NOT NULL,
             updated_at   INTEGER NOT NULL DEFAULT (strftime('%s','now'))
         );
@@ -285,6 +556,25 @@ async fn run_migrations(pool: &SqlitePool) -> Result<(), sqlx::Error> {
         CREATE INDEX IF NOT EXISTS idx_event_log_category ON event_log(category);
         CREATE INDEX IF NOT EXISTS idx_event_log_level ON event_log(level);
 
+        -- Per-completion play log: full plays, partial plays and skips
+        CREATE TABLE IF NOT EXISTS play_log (
+            id              INTEGER PRIMARY KEY AUTOINCREMENT,
+            ended_at        INTEGER NOT NULL,
+            song_id         INTEGER NOT NULL,
+            queue_id        INTEGER,
+            deck            TEXT    NOT NULL,
+            artist          TEXT,
+            title           TEXT,
+            played_ms       INTEGER NOT NULL,
+            duration_ms     INTEGER NOT NULL,
+            outcome         TEXT    NOT NULL,
+            reason          TEXT    NOT NULL,
+            from_rotation   INTEGER NOT NULL DEFAULT 0
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_play_log_ended_at ON play_log(ended_at DESC);
+        CREATE INDEX IF NOT EXISTS idx_play_log_song ON play_log(song_id);
+
         -- Phase 7: System health snapshots
         CREATE TABLE IF NOT EXISTS system_health_snapshots (
             id                      INTEGER PRIMARY KEY AUTOINCREMENT,
@@ -299,6 +589,46 @@ async fn run_migrations(pool: &SqlitePool) -> Result<(), sqlx::Error> {
             active_encoders         INTEGER
         );
 
+        -- Health watchdog thresholds and alert targets
+        CREATE TABLE IF NOT EXISTS alert_config (
+            id           INTEGER PRIMARY KEY DEFAULT 1,
+            config_json  TEXT    NOT NULL,
+            updated_at   INTEGER NOT NULL DEFAULT (strftime('%s','now'))
+        );
+
+        -- Crash recovery: latest runtime snapshot, marked on clean exit
+        CREATE TABLE IF NOT EXISTS session_snapshot (
+            id              INTEGER PRIMARY KEY DEFAULT 1,
+            snapshot_json   TEXT    NOT NULL,
+            saved_at        INTEGER NOT NULL,
+            clean_exit      INTEGER NOT NULL DEFAULT 0
+        );
+
+        -- Operator accounts; none = single-operator station with no sign-in
+        CREATE TABLE IF NOT EXISTS users (
+            id              INTEGER PRIMARY KEY AUTOINCREMENT,
+            username        TEXT    NOT NULL UNIQUE COLLATE NOCASE,
+            display_name    TEXT,
+            role            TEXT    NOT NULL,
+            password_hash   TEXT    NOT NULL,
+            enabled         INTEGER NOT NULL DEFAULT 1,
+            created_at      INTEGER NOT NULL,
+            last_login_at   INTEGER
+        );
+
+        -- Who changed what, and when
+        CREATE TABLE IF NOT EXISTS audit_log (
+            id              INTEGER PRIMARY KEY AUTOINCREMENT,
+            timestamp       INTEGER NOT NULL,
+            user_id         INTEGER,
+            username        TEXT    NOT NULL,
+            role            TEXT    NOT NULL,
+            action          TEXT    NOT NULL,
+            target          TEXT,
+            details_json    TEXT    NOT NULL DEFAULT '{}'
+        );
+        CREATE INDEX IF NOT EXISTS idx_audit_log_timestamp ON audit_log(timestamp);
+
         -- SAM Broadcaster MySQL connection settings
         CREATE TABLE IF NOT EXISTS sam_db_config (
             id               INTEGER PRIMARY KEY DEFAULT 1,
@@ -311,46 +641,147 @@ async fn run_migrations(pool: &SqlitePool) -> Result<(), sqlx::Error> {
             path_prefix_from TEXT    NOT NULL DEFAULT '',
             path_prefix_to   TEXT    NOT NULL DEFAULT ''
         );
-        "#,
+
+        -- Missing-file audit: last scan result and relink settings
+        CREATE TABLE IF NOT EXISTS missing_files (
+            song_id         INTEGER PRIMARY KEY,
+            artist          TEXT    NOT NULL DEFAULT '',
+            title           TEXT    NOT NULL DEFAULT '',
+            path            TEXT    NOT NULL,
+            duration_secs   INTEGER NOT NULL DEFAULT 0,
+            detected_at     INTEGER NOT NULL,
+            candidates_json TEXT    NOT NULL DEFAULT '[]'
+        );
+
+        CREATE TABLE IF NOT EXISTS missing_file_config (
+            id           INTEGER PRIMARY KEY DEFAULT 1,
+            config_json  TEXT    NOT NULL,
+            updated_at   INTEGER NOT NULL DEFAULT (strftime('%s','now'))
+        );
+
+        -- Tracks that failed pre-flight validation or a deck load
+        CREATE TABLE IF NOT EXISTS bad_tracks (
+            song_id           INTEGER PRIMARY KEY,
+            file_path         TEXT    NOT NULL,
+            reason            TEXT    NOT NULL DEFAULT '',
+            failures          INTEGER NOT NULL DEFAULT 1,
+            first_failed_at   INTEGER NOT NULL,
+            last_failed_at    INTEGER NOT NULL,
+            quarantined_until INTEGER NOT NULL
+        );
+
+        -- Background analysis jobs (audio::analyzer::jobs)
+        CREATE TABLE IF NOT EXISTS analysis_jobs (
+            id          INTEGER PRIMARY KEY AUTOINCREMENT,
+            song_id     INTEGER,
+            file_path   TEXT    NOT NULL,
+            kind        TEXT    NOT NULL,
+            priority    INTEGER NOT NULL DEFAULT 0,
+            status      TEXT    NOT NULL DEFAULT 'pending',
+            error       TEXT,
+            created_at  INTEGER NOT NULL,
+            started_at  INTEGER,
+            finished_at INTEGER
+        );
+        CREATE INDEX IF NOT EXISTS idx_analysis_jobs_status
+            ON analysis_jobs(status, priority DESC);
+        CREATE INDEX IF NOT EXISTS idx_analysis_jobs_file
+            ON analysis_jobs(file_path, kind);
+
+        CREATE TABLE IF NOT EXISTS analysis_queue_config (
+            id           INTEGER PRIMARY KEY DEFAULT 1,
+            config_json  TEXT    NOT NULL,
+            updated_at   INTEGER NOT NULL DEFAULT (strftime('%s','now'))
+        );
+
+        -- Gated per-track loudness, per file mtime
+        CREATE TABLE IF NOT EXISTS track_loudness (
+            file_path    TEXT    PRIMARY KEY,
+            song_id      INTEGER NOT NULL DEFAULT 0,
+            mtime_ms     INTEGER NOT NULL,
+            loudness_db  REAL    NOT NULL,
+            peak_db      REAL    NOT NULL,
+            updated_at   INTEGER NOT NULL
+        );
+
+        -- Ordered SAM → local path translation rules (first match wins)
+        CREATE TABLE IF NOT EXISTS path_translation_rules (
+            id          INTEGER PRIMARY KEY AUTOINCREMENT,
+            position    INTEGER NOT NULL DEFAULT 0,
+            kind        TEXT    NOT NULL DEFAULT 'prefix',
+            pattern     TEXT    NOT NULL,
+            replacement TEXT    NOT NULL DEFAULT '',
+            enabled     INTEGER NOT NULL DEFAULT 1,
+            label       TEXT
+        );
+"#;
+
+/// Older DBs keyed `hourly_play_counts` on the naive local (date, hour), which
+/// merges the repeated hour at a DST fall-back. Re-key those rows on UTC.
+pub(super) async fn migrate_hourly_play_counts_utc(
+    conn: &mut sqlx::SqliteConnection,
+) -> Result<(), sqlx::Error> {
+    let has_utc: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM pragma_table_info('hourly_play_counts') WHERE name = 'hour_start_utc'",
     )
-    .execute(pool)
+    .fetch_one(&mut *conn)
     .await?;
+    if has_utc > 0 {
+        return Ok(());
+    }
 
-    // Backward-compat migration for older DBs created before `pipeline_settings_json`.
-    let _ = sqlx::query("ALTER TABLE channel_dsp_settings ADD COLUMN pipeline_settings_json TEXT")
-        .execute(pool)
-        .await;
-    // Backward-compat migrations for cue_points schema expansion.
-    let _ =
-        sqlx::query("ALTER TABLE cue_points ADD COLUMN cue_kind TEXT NOT NULL DEFAULT 'memory'")
-            .execute(pool)
-            .await;
-    let _ = sqlx::query("ALTER TABLE cue_points ADD COLUMN slot INTEGER")
-        .execute(pool)
-        .await;
-    let _ = sqlx::query("ALTER TABLE cue_points ADD COLUMN label TEXT NOT NULL DEFAULT ''")
-        .execute(pool)
-        .await;
-    let _ =
-        sqlx::query("ALTER TABLE cue_points ADD COLUMN color_hex TEXT NOT NULL DEFAULT '#f59e0b'")
-            .execute(pool)
-            .await;
-    let _ = sqlx::query(
-        "ALTER TABLE cue_points ADD COLUMN updated_at INTEGER NOT NULL DEFAULT (strftime('%s','now'))",
-    )
-    .execute(pool)
-    .await;
-    let _ = sqlx::query(
-        "ALTER TABLE monitor_routing_config ADD COLUMN auto_fallback INTEGER NOT NULL DEFAULT 1",
-    )
-    .execute(pool)
-    .await;
-    let _ = sqlx::query(
-        "CREATE UNIQUE INDEX IF NOT EXISTS idx_cue_points_song_kind_slot ON cue_points(song_id, cue_kind, slot) WHERE slot IS NOT NULL",
+    sqlx::query("ALTER TABLE hourly_play_counts RENAME TO hourly_play_counts_legacy")
+        .execute(&mut *conn)
+        .await?;
+    sqlx::query(
+        r#"
+        CREATE TABLE hourly_play_counts (
+            hour_start_utc  INTEGER PRIMARY KEY,
+            date            TEXT    NOT NULL,
+            hour            INTEGER NOT NULL,
+            utc_offset_min  INTEGER NOT NULL DEFAULT 0,
+            play_count      INTEGER DEFAULT 0,
+            unique_songs    INTEGER DEFAULT 0
+        )
+        "#,
     )
-    .execute(pool)
-    .await;
+    .execute(&mut *conn)
+    .await?;
 
+    let legacy = sqlx::query_as::<_, (String, i32, i64, i64)>(
+        "SELECT date, hour, COALESCE(play_count, 0), COALESCE(unique_songs, 0) FROM hourly_play_counts_legacy",
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+    for (date, hour, play_count, unique_songs) in legacy {
+        let Some(slot) =
+            crate::analytics::play_stats::station_hour_from_local(&chrono::Local, &date, hour)
+        else {
+            log::warn!("Dropping unparseable hourly play row {date} {hour}");
+            continue;
+        };
+        sqlx::query(
+            r#"
+            INSERT INTO hourly_play_counts
+                (hour_start_utc, date, hour, utc_offset_min, play_count, unique_songs)
+            VALUES (?, ?, ?, ?, ?, ?)
+            ON CONFLICT(hour_start_utc) DO UPDATE SET
+                play_count   = play_count + excluded.play_count,
+                unique_songs = unique_songs + excluded.unique_songs
+            "#,
+        )
+        .bind(slot.hour_start_utc)
+        .bind(&slot.date)
+        .bind(slot.hour)
+        .bind(slot.utc_offset_min)
+        .bind(play_count)
+        .bind(unique_songs)
+        .execute(&mut *conn)
+        .await?;
+    }
+    sqlx::query("DROP TABLE hourly_play_counts_legacy")
+        .execute(&mut *conn)
+        .await?;
     Ok(())
 }
 
@@ -362,6 +793,8 @@ pub enum CueKind {
     Hotcue,
     Memory,
     Transition,
+    /// Saved loop; `position_ms` is the loop start
+    Loop,
 }
 
 impl CueKind {
@@ -369,6 +802,7 @@ impl CueKind {
         match value.to_ascii_lowercase().as_str() {
             "hotcue" => Self::Hotcue,
             "transition" => Self::Transition,
+            "loop" => Self::Loop,
             _ => Self::Memory,
         }
     }
@@ -378,6 +812,7 @@ impl CueKind {
             Self::Hotcue => "hotcue",
             Self::Memory => "memory",
             Self::Transition => "transition",
+            Self::Loop => "loop",
         }
     }
 }
@@ -388,15 +823,32 @@ impl Default for CueKind {
     }
 }
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "snake_case")]
 pub enum CueQuantize {
+    #[default]
     Off,
     Beat1,
     BeatHalf,
     BeatQuarter,
 }
 
+/// When quantize is on, hot-cue and saved-loop jumps on a playing deck wait
+/// for the next grid boundary.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct QuantizeConfig {
+    pub mode: CueQuantize,
+    /// Per-deck overrides of `mode`
+    #[serde(default)]
+    pub decks: std::collections::HashMap<crate::audio::crossfade::DeckId, CueQuantize>,
+}
+
+impl QuantizeConfig {
+    pub fn mode_for(&self, deck: crate::audio::crossfade::DeckId) -> CueQuantize {
+        self.decks.get(&deck).copied().unwrap_or(self.mode)
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CuePoint {
     pub id: Option<i64>,
@@ -421,6 +873,16 @@ pub struct HotCue {
     pub quantized: bool,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedLoop {
+    pub song_id: i64,
+    pub slot: u8,
+    pub start_ms: i64,
+    pub end_ms: i64,
+    pub label: String,
+    pub color_hex: String,
+}
+
 pub async fn get_cue_points(pool: &SqlitePool, song_id: i64) -> Result<Vec<CuePoint>, sqlx::Error> {
     let rows = sqlx::query(
         "SELECT id, song_id, name, position_ms, cue_kind, slot, label, color_hex, updated_at
@@ -493,6 +955,64 @@ pub async fn delete_cue_point(
     Ok(())
 }
 
+// ── Transition marker cache ──────────────────────────────────────────────────
+
+/// Persisted `TransitionMarkers` for one song, with the inputs they were
+/// computed against.
+#[derive(Debug, Clone, Copy)]
+pub struct CachedTransitionMarkers {
+    pub markers: crate::scheduler::transition_planner::TransitionMarkers,
+    pub duration_ms: u64,
+    /// Audio file mtime (unix secs) when computed, if the file was readable
+    pub file_mtime: Option<i64>,
+}
+
+pub async fn get_cached_transition_markers(
+    pool: &SqlitePool,
+    song_id: i64,
+) -> Result<Option<CachedTransitionMarkers>, sqlx::Error> {
+    let row = sqlx::query(
+        "SELECT markers_json, duration_ms, file_mtime FROM transition_marker_cache WHERE song_id = ?",
+    )
+    .bind(song_id)
+    .fetch_optional(pool)
+    .await?;
+    Ok(row.and_then(|r| {
+        let markers = serde_json::from_str(&r.get::<String, _>("markers_json")).ok()?;
+        Some(CachedTransitionMarkers {
+            markers,
+            duration_ms: r.get::<i64, _>("duration_ms").max(0) as u64,
+            file_mtime: r.get("file_mtime"),
+        })
+    }))
+}
+
+pub async fn save_cached_transition_markers(
+    pool: &SqlitePool,
+    song_id: i64,
+    cached: &CachedTransitionMarkers,
+) -> Result<(), sqlx::Error> {
+    let json = serde_json::to_string(&cached.markers).unwrap_or_else(|_| "{}".to_string());
+    sqlx::query(
+        r#"
+        INSERT INTO transition_marker_cache (song_id, markers_json, duration_ms, file_mtime, computed_at)
+        VALUES (?, ?, ?, ?, strftime('%s','now'))
+        ON CONFLICT(song_id) DO UPDATE SET
+            markers_json = excluded.markers_json,
+            duration_ms = excluded.duration_ms,
+            file_mtime = excluded.file_mtime,
+            computed_at = excluded.computed_at
+        "#,
+    )
+    .bind(song_id)
+    .bind(json)
+    .bind(cached.duration_ms as i64)
+    .bind(cached.file_mtime)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
 // ── Song fade overrides ──────────────────────────────────────────────────────
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -530,36 +1050,337 @@ pub async fn get_song_fade_override(
     }))
 }
 
-pub async fn upsert_song_fade_override(
+pub async fn upsert_song_fade_override(
+    pool: &SqlitePool,
+    row: &SongFadeOverrideRow,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        INSERT INTO song_fade_overrides
+            (song_id, fade_out_enabled, fade_out_curve, fade_out_time_ms,
+             fade_in_enabled, fade_in_curve, fade_in_time_ms, crossfade_mode, gain_db)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+        ON CONFLICT(song_id) DO UPDATE SET
+            fade_out_enabled = excluded.fade_out_enabled,
+            fade_out_curve   = excluded.fade_out_curve,
+            fade_out_time_ms = excluded.fade_out_time_ms,
+            fade_in_enabled  = excluded.fade_in_enabled,
+            fade_in_curve    = excluded.fade_in_curve,
+            fade_in_time_ms  = excluded.fade_in_time_ms,
+            crossfade_mode   = excluded.crossfade_mode,
+            gain_db          = excluded.gain_db
+        "#,
+    )
+    .bind(row.song_id)
+    .bind(row.fade_out_enabled.map(|v| v as i64))
+    .bind(&row.fade_out_curve)
+    .bind(row.fade_out_time_ms)
+    .bind(row.fade_in_enabled.map(|v| v as i64))
+    .bind(&row.fade_in_curve)
+    .bind(row.fade_in_time_ms)
+    .bind(&row.crossfade_mode)
+    .bind(row.gain_db)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+// ── Category gain trims ──────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum GainTrimKind {
+    /// SAM category (by name)
+    Category,
+    /// SAM song type (`S`, `J`, `A`, …)
+    SongType,
+}
+
+impl GainTrimKind {
+    pub fn as_db(&self) -> &'static str {
+        match self {
+            GainTrimKind::Category => "category",
+            GainTrimKind::SongType => "song_type",
+        }
+    }
+
+    pub fn from_db(s: &str) -> Self {
+        match s {
+            "song_type" => GainTrimKind::SongType,
+            _ => GainTrimKind::Category,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CategoryGainTrim {
+    pub kind: GainTrimKind,
+    pub name: String,
+    pub gain_db: f64,
+}
+
+/// The configured trim of `kind` for `name` (case- and space-insensitive).
+pub fn find_gain_trim(trims: &[CategoryGainTrim], kind: GainTrimKind, name: &str) -> Option<f64> {
+    trims
+        .iter()
+        .find(|t| t.kind == kind && t.name.trim().eq_ignore_ascii_case(name.trim()))
+        .map(|t| t.gain_db)
+}
+
+pub async fn get_category_gain_trims(
+    pool: &SqlitePool,
+) -> Result<Vec<CategoryGainTrim>, sqlx::Error> {
+    let rows =
+        sqlx::query("SELECT kind, name, gain_db FROM category_gain_trims ORDER BY kind, name")
+            .fetch_all(pool)
+            .await?;
+    Ok(rows
+        .into_iter()
+        .map(|r| CategoryGainTrim {
+            kind: GainTrimKind::from_db(r.get::<String, _>("kind").as_str()),
+            name: r.get("name"),
+            gain_db: r.get("gain_db"),
+        })
+        .collect())
+}
+
+pub async fn upsert_category_gain_trim(
+    pool: &SqlitePool,
+    trim: &CategoryGainTrim,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        INSERT INTO category_gain_trims (kind, name, gain_db, updated_at)
+        VALUES (?, ?, ?, strftime('%s','now'))
+        ON CONFLICT(kind, name) DO UPDATE SET
+            gain_db = excluded.gain_db,
+            updated_at = excluded.updated_at
+        "#,
+    )
+    .bind(trim.kind.as_db())
+    .bind(trim.name.trim())
+    .bind(trim.gain_db)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+pub async fn delete_category_gain_trim(
+    pool: &SqlitePool,
+    kind: GainTrimKind,
+    name: &str,
+) -> Result<(), sqlx::Error> {
+    sqlx::query("DELETE FROM category_gain_trims WHERE kind = ? AND name = ?")
+        .bind(kind.as_db())
+        .bind(name.trim())
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+// ── Crossfade profiles ───────────────────────────────────────────────────────
+
+/// A named crossfade config. Transitions into songs of an assigned SAM
+/// category or song type use it instead of the station config.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CrossfadeProfile {
+    pub id: Option<i64>,
+    pub name: String,
+    pub config: CrossfadeConfig,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CrossfadeProfileAssignment {
+    pub kind: GainTrimKind,
+    pub name: String,
+    pub profile_id: i64,
+}
+
+pub async fn get_crossfade_profiles(
+    pool: &SqlitePool,
+) -> Result<Vec<CrossfadeProfile>, sqlx::Error> {
+    let rows = sqlx::query("SELECT id, name, config_json FROM crossfade_profiles ORDER BY name")
+        .fetch_all(pool)
+        .await?;
+    Ok(rows
+        .into_iter()
+        .map(|r| CrossfadeProfile {
+            id: r.get("id"),
+            name: r.get("name"),
+            config: serde_json::from_str(r.get::<&str, _>("config_json")).unwrap_or_default(),
+        })
+        .collect())
+}
+
+pub async fn get_crossfade_profile(
+    pool: &SqlitePool,
+    id: i64,
+) -> Result<Option<CrossfadeProfile>, sqlx::Error> {
+    Ok(get_crossfade_profiles(pool)
+        .await?
+        .into_iter()
+        .find(|p| p.id == Some(id)))
+}
+
+pub async fn upsert_crossfade_profile(
+    pool: &SqlitePool,
+    profile: &CrossfadeProfile,
+) -> Result<i64, sqlx::Error> {
+    let config_json = serde_json::to_string(&profile.config).unwrap_or_default();
+    match profile.id {
+        Some(id) => {
+            sqlx::query(
+                r#"
+                UPDATE crossfade_profiles
+                SET name = ?, config_json = ?, updated_at = strftime('%s','now')
+                WHERE id = ?
+                "#,
+            )
+            .bind(profile.name.trim())
+            .bind(&config_json)
+            .bind(id)
+            .execute(pool)
+            .await?;
+            Ok(id)
+        }
+        None => {
+            let r = sqlx::query("INSERT INTO crossfade_profiles (name, config_json) VALUES (?, ?)")
+                .bind(profile.name.trim())
+                .bind(&config_json)
+                .execute(pool)
+                .await?;
+            Ok(r.last_insert_rowid())
+        }
+    }
+}
+
+/// Delete a profile and its category / song type assignments.
+pub async fn delete_crossfade_profile(pool: &SqlitePool, id: i64) -> Result<(), sqlx::Error> {
+    sqlx::query("DELETE FROM crossfade_profile_assignments WHERE profile_id = ?")
+        .bind(id)
+        .execute(pool)
+        .await?;
+    sqlx::query("DELETE FROM crossfade_profiles WHERE id = ?")
+        .bind(id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+pub async fn get_crossfade_profile_assignments(
+    pool: &SqlitePool,
+) -> Result<Vec<CrossfadeProfileAssignment>, sqlx::Error> {
+    let rows = sqlx::query(
+        "SELECT kind, name, profile_id FROM crossfade_profile_assignments ORDER BY kind, name",
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(rows
+        .into_iter()
+        .map(|r| CrossfadeProfileAssignment {
+            kind: GainTrimKind::from_db(r.get::<String, _>("kind").as_str()),
+            name: r.get("name"),
+            profile_id: r.get("profile_id"),
+        })
+        .collect())
+}
+
+pub async fn upsert_crossfade_profile_assignment(
+    pool: &SqlitePool,
+    assignment: &CrossfadeProfileAssignment,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        INSERT INTO crossfade_profile_assignments (kind, name, profile_id)
+        VALUES (?, ?, ?)
+        ON CONFLICT(kind, name) DO UPDATE SET profile_id = excluded.profile_id
+        "#,
+    )
+    .bind(assignment.kind.as_db())
+    .bind(assignment.name.trim())
+    .bind(assignment.profile_id)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+pub async fn delete_crossfade_profile_assignment(
+    pool: &SqlitePool,
+    kind: GainTrimKind,
+    name: &str,
+) -> Result<(), sqlx::Error> {
+    sqlx::query("DELETE FROM crossfade_profile_assignments WHERE kind = ? AND name = ?")
+        .bind(kind.as_db())
+        .bind(name.trim())
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+// ── Song playback flags ──────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct SongPlaybackFlags {
+    pub song_id: i64,
+    /// Never overlap this song with its neighbours: always a hard cut
+    pub never_crossfade: bool,
+    /// Always mix into/out of this song, even in manual trigger mode or for short tracks
+    pub always_segue: bool,
+    /// Imaging element that must play immediately before this song
+    pub attached_sweeper_path: Option<String>,
+}
+
+impl SongPlaybackFlags {
+    pub fn attached_sweeper(&self) -> Option<&str> {
+        self.attached_sweeper_path
+            .as_deref()
+            .map(str::trim)
+            .filter(|p| !p.is_empty())
+    }
+}
+
+pub async fn get_song_playback_flags(
+    pool: &SqlitePool,
+    song_id: i64,
+) -> Result<SongPlaybackFlags, sqlx::Error> {
+    let row = sqlx::query("SELECT * FROM song_playback_flags WHERE song_id = ?")
+        .bind(song_id)
+        .fetch_optional(pool)
+        .await?;
+
+    Ok(row
+        .map(|r| SongPlaybackFlags {
+            song_id: r.get("song_id"),
+            never_crossfade: r.get::<i64, _>("never_crossfade") != 0,
+            always_segue: r.get::<i64, _>("always_segue") != 0,
+            attached_sweeper_path: r.get("attached_sweeper_path"),
+        })
+        .unwrap_or(SongPlaybackFlags {
+            song_id,
+            ..Default::default()
+        }))
+}
+
+pub async fn upsert_song_playback_flags(
     pool: &SqlitePool,
-    row: &SongFadeOverrideRow,
+    flags: &SongPlaybackFlags,
 ) -> Result<(), sqlx::Error> {
     sqlx::query(
         r#"
-        INSERT INTO song_fade_overrides
-            (song_id, fade_out_enabled, fade_out_curve, fade_out_time_ms,
-             fade_in_enabled, fade_in_curve, fade_in_time_ms, crossfade_mode, gain_db)
-        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+        INSERT INTO song_playback_flags
+            (song_id, never_crossfade, always_segue, attached_sweeper_path, updated_at)
+        VALUES (?, ?, ?, ?, strftime('%s','now'))
         ON CONFLICT(song_id) DO UPDATE SET
-            fade_out_enabled = excluded.fade_out_enabled,
-            fade_out_curve   = excluded.fade_out_curve,
-            fade_out_time_ms = excluded.fade_out_time_ms,
-            fade_in_enabled  = excluded.fade_in_enabled,
-            fade_in_curve    = excluded.fade_in_curve,
-            fade_in_time_ms  = excluded.fade_in_time_ms,
-            crossfade_mode   = excluded.crossfade_mode,
-            gain_db          = excluded.gain_db
+            never_crossfade       = excluded.never_crossfade,
+            always_segue          = excluded.always_segue,
+            attached_sweeper_path = excluded.attached_sweeper_path,
+            updated_at            = excluded.updated_at
         "#,
     )
-    .bind(row.song_id)
-    .bind(row.fade_out_enabled.map(|v| v as i64))
-    .bind(&row.fade_out_curve)
-    .bind(row.fade_out_time_ms)
-    .bind(row.fade_in_enabled.map(|v| v as i64))
-    .bind(&row.fade_in_curve)
-    .bind(row.fade_in_time_ms)
-    .bind(&row.crossfade_mode)
-    .bind(row.gain_db)
+    .bind(flags.song_id)
+    .bind(flags.never_crossfade as i64)
+    .bind(flags.always_segue as i64)
+    .bind(flags.attached_sweeper())
     .execute(pool)
     .await?;
     Ok(())
@@ -698,6 +1519,97 @@ pub async fn recolor_hot_cue(
     Ok(())
 }
 
+fn saved_loop_from_row(r: &sqlx::sqlite::SqliteRow) -> SavedLoop {
+    SavedLoop {
+        song_id: r.get("song_id"),
+        slot: r.get::<i64, _>("slot") as u8,
+        start_ms: r.get("position_ms"),
+        end_ms: r.get("end_ms"),
+        label: r.get("label"),
+        color_hex: r.get("color_hex"),
+    }
+}
+
+pub async fn get_saved_loops(
+    pool: &SqlitePool,
+    song_id: i64,
+) -> Result<Vec<SavedLoop>, sqlx::Error> {
+    let rows = sqlx::query(
+        "SELECT song_id, slot, position_ms, end_ms, label, color_hex
+         FROM cue_points
+         WHERE song_id = ? AND cue_kind = 'loop' AND slot IS NOT NULL AND end_ms IS NOT NULL
+         ORDER BY slot ASC",
+    )
+    .bind(song_id)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows.iter().map(saved_loop_from_row).collect())
+}
+
+pub async fn get_saved_loop(
+    pool: &SqlitePool,
+    song_id: i64,
+    slot: u8,
+) -> Result<Option<SavedLoop>, sqlx::Error> {
+    let row = sqlx::query(
+        "SELECT song_id, slot, position_ms, end_ms, label, color_hex
+         FROM cue_points
+         WHERE song_id = ? AND cue_kind = 'loop' AND slot = ? AND end_ms IS NOT NULL",
+    )
+    .bind(song_id)
+    .bind(slot as i64)
+    .fetch_optional(pool)
+    .await?;
+    Ok(row.as_ref().map(saved_loop_from_row))
+}
+
+pub async fn upsert_saved_loop(pool: &SqlitePool, saved: &SavedLoop) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        INSERT INTO cue_points (song_id, name, position_ms, end_ms, cue_kind, slot, label, color_hex, updated_at)
+        VALUES (?, ?, ?, ?, 'loop', ?, ?, ?, strftime('%s','now'))
+        ON CONFLICT(song_id, cue_kind, slot) DO UPDATE SET
+            name = excluded.name,
+            position_ms = excluded.position_ms,
+            end_ms = excluded.end_ms,
+            label = excluded.label,
+            color_hex = excluded.color_hex,
+            updated_at = excluded.updated_at
+        "#,
+    )
+    .bind(saved.song_id)
+    .bind(format!("loop_{}", saved.slot))
+    .bind(saved.start_ms)
+    .bind(saved.end_ms)
+    .bind(saved.slot as i64)
+    .bind(if saved.label.is_empty() {
+        format!("Loop {}", saved.slot)
+    } else {
+        saved.label.clone()
+    })
+    .bind(if saved.color_hex.is_empty() {
+        "#f59e0b".to_string()
+    } else {
+        saved.color_hex.clone()
+    })
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+pub async fn clear_saved_loop(
+    pool: &SqlitePool,
+    song_id: i64,
+    slot: u8,
+) -> Result<(), sqlx::Error> {
+    sqlx::query("DELETE FROM cue_points WHERE song_id = ? AND cue_kind = 'loop' AND slot = ?")
+        .bind(song_id)
+        .bind(slot as i64)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
 // ── Channel DSP settings ─────────────────────────────────────────────────────
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -852,57 +1764,6 @@ pub async fn save_autodj_transition_config(
     Ok(())
 }
 
-// ── Waveform cache ───────────────────────────────────────────────────────────
-
-pub async fn get_waveform_cache(
-    pool: &SqlitePool,
-    file_path: &str,
-    mtime_ms: i64,
-    resolution: i64,
-) -> Result<Option<Vec<f32>>, sqlx::Error> {
-    let row = sqlx::query(
-        "SELECT peaks_json FROM waveform_cache WHERE file_path = ? AND mtime_ms = ? AND resolution = ?",
-    )
-    .bind(file_path)
-    .bind(mtime_ms)
-    .bind(resolution)
-    .fetch_optional(pool)
-    .await?;
-
-    let Some(r) = row else {
-        return Ok(None);
-    };
-    let json: String = r.get("peaks_json");
-    let parsed = serde_json::from_str::<Vec<f32>>(&json).unwrap_or_default();
-    Ok(Some(parsed))
-}
-
-pub async fn save_waveform_cache(
-    pool: &SqlitePool,
-    file_path: &str,
-    mtime_ms: i64,
-    resolution: i64,
-    peaks: &[f32],
-) -> Result<(), sqlx::Error> {
-    let peaks_json = serde_json::to_string(peaks).unwrap_or_else(|_| "[]".to_string());
-    sqlx::query(
-        r#"
-        INSERT INTO waveform_cache (file_path, mtime_ms, resolution, peaks_json, updated_at)
-        VALUES (?, ?, ?, ?, strftime('%s','now'))
-        ON CONFLICT(file_path, mtime_ms, resolution) DO UPDATE SET
-            peaks_json = excluded.peaks_json,
-            updated_at = excluded.updated_at
-        "#,
-    )
-    .bind(file_path)
-    .bind(mtime_ms)
-    .bind(resolution)
-    .bind(peaks_json)
-    .execute(pool)
-    .await?;
-    Ok(())
-}
-
 // ── Beat-grid cache ──────────────────────────────────────────────────────────
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -971,6 +1832,31 @@ pub async fn get_latest_beatgrid_by_song_id(
     }))
 }
 
+/// Analysed BPM per song, for the given songs that have a beat grid.
+pub async fn get_beatgrid_bpms(
+    pool: &SqlitePool,
+    song_ids: &[i64],
+) -> Result<std::collections::HashMap<i64, f32>, sqlx::Error> {
+    if song_ids.is_empty() {
+        return Ok(Default::default());
+    }
+    let mut qb = sqlx::QueryBuilder::<sqlx::Sqlite>::new(
+        "SELECT song_id, bpm FROM beatgrid_analysis WHERE bpm > 0 AND song_id IN (",
+    );
+    {
+        let mut ids = qb.separated(", ");
+        for id in song_ids {
+            ids.push_bind(*id);
+        }
+    }
+    qb.push(")");
+    let rows = qb.build().fetch_all(pool).await?;
+    Ok(rows
+        .into_iter()
+        .map(|r| (r.get::<i64, _>("song_id"), r.get::<f64, _>("bpm") as f32))
+        .collect())
+}
+
 pub async fn save_beatgrid_analysis(
     pool: &SqlitePool,
     analysis: &BeatGridAnalysis,
@@ -1013,6 +1899,10 @@ pub struct StemAnalysis {
     pub source_mtime_ms: i64,
     pub vocals_file_path: String,
     pub instrumental_file_path: String,
+    /// 4-stem outputs; `None` for analyses from the older two-stem model
+    pub drums_file_path: Option<String>,
+    pub bass_file_path: Option<String>,
+    pub other_file_path: Option<String>,
     pub model_name: String,
     pub updated_at: Option<i64>,
 }
@@ -1024,7 +1914,8 @@ pub async fn get_stem_analysis(
     source_mtime_ms: i64,
 ) -> Result<Option<StemAnalysis>, sqlx::Error> {
     let row = sqlx::query(
-        "SELECT song_id, source_file_path, source_mtime_ms, vocals_file_path, instrumental_file_path, model_name, updated_at
+        "SELECT song_id, source_file_path, source_mtime_ms, vocals_file_path, instrumental_file_path,
+                drums_file_path, bass_file_path, other_file_path, model_name, updated_at
          FROM stem_analysis WHERE song_id = ? AND source_file_path = ? AND source_mtime_ms = ?",
     )
     .bind(song_id)
@@ -1041,7 +1932,8 @@ pub async fn get_latest_stem_analysis_by_song_id(
     song_id: i64,
 ) -> Result<Option<StemAnalysis>, sqlx::Error> {
     let row = sqlx::query(
-        "SELECT song_id, source_file_path, source_mtime_ms, vocals_file_path, instrumental_file_path, model_name, updated_at
+        "SELECT song_id, source_file_path, source_mtime_ms, vocals_file_path, instrumental_file_path,
+                drums_file_path, bass_file_path, other_file_path, model_name, updated_at
          FROM stem_analysis WHERE song_id = ? LIMIT 1",
     )
     .bind(song_id)
@@ -1058,13 +1950,17 @@ pub async fn save_stem_analysis(
     sqlx::query(
         r#"
         INSERT INTO stem_analysis
-            (song_id, source_file_path, source_mtime_ms, vocals_file_path, instrumental_file_path, model_name, updated_at)
-        VALUES (?, ?, ?, ?, ?, ?, strftime('%s','now'))
+            (song_id, source_file_path, source_mtime_ms, vocals_file_path, instrumental_file_path,
+             drums_file_path, bass_file_path, other_file_path, model_name, updated_at)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, strftime('%s','now'))
         ON CONFLICT(song_id) DO UPDATE SET
             source_file_path = excluded.source_file_path,
             source_mtime_ms = excluded.source_mtime_ms,
             vocals_file_path = excluded.vocals_file_path,
             instrumental_file_path = excluded.instrumental_file_path,
+            drums_file_path = excluded.drums_file_path,
+            bass_file_path = excluded.bass_file_path,
+            other_file_path = excluded.other_file_path,
             model_name = excluded.model_name,
             updated_at = excluded.updated_at
         "#,
@@ -1074,6 +1970,9 @@ pub async fn save_stem_analysis(
     .bind(analysis.source_mtime_ms)
     .bind(&analysis.vocals_file_path)
     .bind(&analysis.instrumental_file_path)
+    .bind(&analysis.drums_file_path)
+    .bind(&analysis.bass_file_path)
+    .bind(&analysis.other_file_path)
     .bind(&analysis.model_name)
     .execute(pool)
     .await?;
@@ -1087,6 +1986,9 @@ fn map_stem_analysis_row(r: sqlx::sqlite::SqliteRow) -> StemAnalysis {
         source_mtime_ms: r.get("source_mtime_ms"),
         vocals_file_path: r.get("vocals_file_path"),
         instrumental_file_path: r.get("instrumental_file_path"),
+        drums_file_path: r.get("drums_file_path"),
+        bass_file_path: r.get("bass_file_path"),
+        other_file_path: r.get("other_file_path"),
         model_name: r.get("model_name"),
         updated_at: r.get("updated_at"),
     }
@@ -1236,6 +2138,59 @@ pub async fn save_controller_config(
     Ok(())
 }
 
+pub async fn list_controller_profiles(pool: &SqlitePool) -> Result<Vec<String>, sqlx::Error> {
+    let rows = sqlx::query("SELECT profile_json FROM controller_profiles ORDER BY name ASC")
+        .fetch_all(pool)
+        .await?;
+    Ok(rows
+        .into_iter()
+        .map(|r| r.get::<String, _>("profile_json"))
+        .collect())
+}
+
+pub async fn get_controller_profile(
+    pool: &SqlitePool,
+    id: &str,
+) -> Result<Option<String>, sqlx::Error> {
+    let row = sqlx::query("SELECT profile_json FROM controller_profiles WHERE id = ?")
+        .bind(id)
+        .fetch_optional(pool)
+        .await?;
+    Ok(row.map(|r| r.get::<String, _>("profile_json")))
+}
+
+pub async fn save_controller_profile(
+    pool: &SqlitePool,
+    id: &str,
+    name: &str,
+    json: &str,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        INSERT INTO controller_profiles (id, name, profile_json, updated_at)
+        VALUES (?, ?, ?, strftime('%s','now'))
+        ON CONFLICT(id) DO UPDATE SET
+            name = excluded.name,
+            profile_json = excluded.profile_json,
+            updated_at = excluded.updated_at
+        "#,
+    )
+    .bind(id)
+    .bind(name)
+    .bind(json)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+pub async fn delete_controller_profile(pool: &SqlitePool, id: &str) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query("DELETE FROM controller_profiles WHERE id = ?")
+        .bind(id)
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected() > 0)
+}
+
 // ── Crossfade config ─────────────────────────────────────────────────────────
 
 pub async fn load_crossfade_config(pool: &SqlitePool) -> Result<Option<String>, sqlx::Error> {
@@ -1258,13 +2213,113 @@ pub async fn save_crossfade_config(pool: &SqlitePool, json: &str) -> Result<(),
     Ok(())
 }
 
+pub async fn save_gap_killer_config(pool: &SqlitePool, json: &str) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        INSERT INTO gap_killer_config (id, gap_killer_json) VALUES (1, ?)
+        ON CONFLICT(id) DO UPDATE SET gap_killer_json = excluded.gap_killer_json
+        "#,
+    )
+    .bind(json)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+// ── Mic ducking config ───────────────────────────────────────────────────────
+
+pub async fn load_mic_duck_config(pool: &SqlitePool) -> Result<Option<String>, sqlx::Error> {
+    let row = sqlx::query("SELECT config_json FROM mic_duck_config WHERE id = 1")
+        .fetch_optional(pool)
+        .await?;
+    Ok(row.map(|r| r.get::<String, _>("config_json")))
+}
+
+pub async fn save_mic_duck_config(pool: &SqlitePool, json: &str) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        INSERT INTO mic_duck_config (id, config_json) VALUES (1, ?)
+        ON CONFLICT(id) DO UPDATE SET config_json = excluded.config_json
+        "#,
+    )
+    .bind(json)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+pub async fn load_quantize_config(pool: &SqlitePool) -> Result<Option<String>, sqlx::Error> {
+    let row = sqlx::query("SELECT config_json FROM quantize_config WHERE id = 1")
+        .fetch_optional(pool)
+        .await?;
+    Ok(row.map(|r| r.get::<String, _>("config_json")))
+}
+
+pub async fn save_quantize_config(pool: &SqlitePool, json: &str) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        INSERT INTO quantize_config (id, config_json) VALUES (1, ?)
+        ON CONFLICT(id) DO UPDATE SET config_json = excluded.config_json
+        "#,
+    )
+    .bind(json)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+pub async fn load_show_export_config(pool: &SqlitePool) -> Result<Option<String>, sqlx::Error> {
+    let row = sqlx::query("SELECT config_json FROM show_export_config WHERE id = 1")
+        .fetch_optional(pool)
+        .await?;
+    Ok(row.map(|r| r.get::<String, _>("config_json")))
+}
+
+pub async fn save_show_export_config(pool: &SqlitePool, json: &str) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        INSERT INTO show_export_config (id, config_json) VALUES (1, ?)
+        ON CONFLICT(id) DO UPDATE SET config_json = excluded.config_json
+        "#,
+    )
+    .bind(json)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+pub async fn load_hotkey_config(pool: &SqlitePool) -> Result<Option<String>, sqlx::Error> {
+    let row = sqlx::query("SELECT config_json FROM hotkey_config WHERE id = 1")
+        .fetch_optional(pool)
+        .await?;
+    Ok(row.map(|r| r.get::<String, _>("config_json")))
+}
+
+pub async fn save_hotkey_config(pool: &SqlitePool, json: &str) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        INSERT INTO hotkey_config (id, config_json, updated_at)
+        VALUES (1, ?, strftime('%s','now'))
+        ON CONFLICT(id) DO UPDATE SET
+            config_json = excluded.config_json,
+            updated_at = excluded.updated_at
+        "#,
+    )
+    .bind(json)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
 // ── Phase 4: Encoder configs ──────────────────────────────────────────────────
 
 pub async fn load_encoder_configs(pool: &SqlitePool) -> Result<Vec<EncoderConfig>, String> {
-    let rows = sqlx::query("SELECT config_json FROM encoder_configs ORDER BY id ASC")
-        .fetch_all(pool)
-        .await
-        .map_err(|e| format!("load_encoder_configs query failed: {e}"))?;
+    let rows = sqlx::query(
+        "SELECT config_json, failover_group, priority FROM encoder_configs ORDER BY id ASC",
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("load_encoder_configs query failed: {e}"))?;
 
     let mut out = Vec::with_capacity(rows.len());
     for row in rows {
@@ -1272,7 +2327,19 @@ pub async fn load_encoder_configs(pool: &SqlitePool) -> Result<Vec<EncoderConfig
             .try_get("config_json")
             .map_err(|e| format!("load_encoder_configs decode failed: {e}"))?;
         match serde_json::from_str::<EncoderConfig>(&json) {
-            Ok(cfg) => out.push(cfg),
+            Ok(mut cfg) => {
+                // The columns are authoritative for grouping.
+                cfg.failover_group = row
+                    .try_get::<Option<String>, _>("failover_group")
+                    .ok()
+                    .flatten()
+                    .filter(|g| !g.trim().is_empty());
+                cfg.priority = row
+                    .try_get::<i64, _>("priority")
+                    .map(|p| p.clamp(0, u32::MAX as i64) as u32)
+                    .unwrap_or(cfg.priority);
+                out.push(cfg);
+            }
             Err(e) => {
                 log::warn!("Skipping invalid encoder config row: {e}");
             }
@@ -1287,15 +2354,25 @@ pub async fn save_encoder_config(pool: &SqlitePool, config: &EncoderConfig) -> R
 
     sqlx::query(
         r#"
-        INSERT INTO encoder_configs (id, config_json, updated_at)
-        VALUES (?, ?, strftime('%s','now'))
+        INSERT INTO encoder_configs (id, config_json, failover_group, priority, updated_at)
+        VALUES (?, ?, ?, ?, strftime('%s','now'))
         ON CONFLICT(id) DO UPDATE SET
             config_json = excluded.config_json,
+            failover_group = excluded.failover_group,
+            priority = excluded.priority,
             updated_at = excluded.updated_at
         "#,
     )
     .bind(config.id)
     .bind(json)
+    .bind(
+        config
+            .failover_group
+            .as_deref()
+            .map(str::trim)
+            .filter(|g| !g.is_empty()),
+    )
+    .bind(config.priority as i64)
     .execute(pool)
     .await
     .map_err(|e| format!("save_encoder_config query failed: {e}"))?;
@@ -1396,7 +2473,8 @@ pub async fn get_dj_permissions(
     let row = sqlx::query(
         r#"
         SELECT can_load_track, can_play_pause, can_seek, can_set_volume,
-               can_queue_add, can_queue_remove, can_trigger_crossfade, can_set_autopilot
+               can_queue_add, can_queue_remove, can_trigger_crossfade, can_set_autopilot,
+               can_go_live
         FROM remote_dj_permissions WHERE user_id = ?
         "#,
     )
@@ -1414,6 +2492,7 @@ pub async fn get_dj_permissions(
             can_queue_remove: r.get::<i64, _>("can_queue_remove") != 0,
             can_trigger_crossfade: r.get::<i64, _>("can_trigger_crossfade") != 0,
             can_set_autopilot: r.get::<i64, _>("can_set_autopilot") != 0,
+            can_go_live: r.get::<i64, _>("can_go_live") != 0,
         }),
         None => Ok(DjPermissions::default()),
     }
@@ -1428,8 +2507,9 @@ pub async fn save_dj_permissions(
         r#"
         INSERT INTO remote_dj_permissions (
             user_id, can_load_track, can_play_pause, can_seek, can_set_volume,
-            can_queue_add, can_queue_remove, can_trigger_crossfade, can_set_autopilot
-        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+            can_queue_add, can_queue_remove, can_trigger_crossfade, can_set_autopilot,
+            can_go_live
+        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
         ON CONFLICT(user_id) DO UPDATE SET
             can_load_track = excluded.can_load_track,
             can_play_pause = excluded.can_play_pause,
@@ -1438,7 +2518,8 @@ pub async fn save_dj_permissions(
             can_queue_add = excluded.can_queue_add,
             can_queue_remove = excluded.can_queue_remove,
             can_trigger_crossfade = excluded.can_trigger_crossfade,
-            can_set_autopilot = excluded.can_set_autopilot
+            can_set_autopilot = excluded.can_set_autopilot,
+            can_go_live = excluded.can_go_live
         "#,
     )
     .bind(user_id)
@@ -1450,6 +2531,7 @@ pub async fn save_dj_permissions(
     .bind(perms.can_queue_remove as i64)
     .bind(perms.can_trigger_crossfade as i64)
     .bind(perms.can_set_autopilot as i64)
+    .bind(perms.can_go_live as i64)
     .execute(pool)
     .await?;
     Ok(())
@@ -1625,5 +2707,40 @@ pub async fn save_sam_db_config(
     .bind(&config.path_prefix_to)
     .execute(pool)
     .await?;
+    super::path_rules::invalidate_translator();
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gain_trims_match_by_kind_and_name() {
+        let trims = vec![
+            CategoryGainTrim {
+                kind: GainTrimKind::Category,
+                name: "Jingles".to_string(),
+                gain_db: -3.0,
+            },
+            CategoryGainTrim {
+                kind: GainTrimKind::SongType,
+                name: "J".to_string(),
+                gain_db: -1.5,
+            },
+        ];
+        assert_eq!(
+            find_gain_trim(&trims, GainTrimKind::Category, " jingles "),
+            Some(-3.0)
+        );
+        assert_eq!(
+            find_gain_trim(&trims, GainTrimKind::SongType, "j"),
+            Some(-1.5)
+        );
+        assert_eq!(
+            find_gain_trim(&trims, GainTrimKind::SongType, "Jingles"),
+            None
+        );
+        assert_eq!(find_gain_trim(&trims, GainTrimKind::Category, "News"), None);
+    }
+}
@@ -0,0 +1,599 @@
+/// Versioned schema migrations for the local database.
+///
+/// Applied versions are recorded in `schema_migrations` with a checksum of
+/// their definition. On open, pending migrations run in version order, each in
+/// its own transaction: a failure rolls that migration back and stops startup,
+/// leaving the earlier ones recorded. Before anything runs on an existing
+/// database it is integrity-checked and snapshotted into the backup folder.
+///
+/// Versions 1–4 adopt databases created by the old ad-hoc migrations. They are
+/// idempotent (`CREATE … IF NOT EXISTS`, columns added only when missing), so
+/// a database at any earlier layout converges on the same schema. New changes
+/// go at the end of `MIGRATIONS`; a released migration is never edited.
+use futures_util::future::BoxFuture;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use sqlx::{Row, SqliteConnection, SqlitePool};
+
+use super::{backup, local};
+
+pub enum Step {
+    /// Statements run as one batch
+    Sql(&'static str),
+    /// `(table, column, definition)` added wherever the column is missing
+    AddColumns(&'static [(&'static str, &'static str, &'static str)]),
+    /// Data migration written in Rust
+    Custom(fn(&mut SqliteConnection) -> BoxFuture<'_, Result<(), sqlx::Error>>),
+}
+
+pub struct Migration {
+    pub version: i64,
+    pub name: &'static str,
+    pub step: Step,
+}
+
+impl Migration {
+    fn checksum(&self) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(self.name.as_bytes());
+        match &self.step {
+            Step::Sql(sql) => hasher.update(sql.as_bytes()),
+            Step::AddColumns(columns) => {
+                for (table, column, definition) in columns.iter() {
+                    hasher.update(format!("{table}.{column} {definition};").as_bytes());
+                }
+            }
+            // Code can't be hashed; the name and version stand in for it.
+            Step::Custom(_) => hasher.update(self.version.to_le_bytes()),
+        }
+        hasher.finalize()[..8]
+            .iter()
+            .map(|b| format!("{b:02x}"))
+            .collect()
+    }
+}
+
+const LEGACY_COLUMNS: &[(&str, &str, &str)] = &[
+    ("channel_dsp_settings", "pipeline_settings_json", "TEXT"),
+    ("cue_points", "cue_kind", "TEXT NOT NULL DEFAULT 'memory'"),
+    ("cue_points", "slot", "INTEGER"),
+    ("cue_points", "label", "TEXT NOT NULL DEFAULT ''"),
+    ("cue_points", "color_hex", "TEXT NOT NULL DEFAULT '#f59e0b'"),
+    // ADD COLUMN can't take the table's `strftime` default (it never applied).
+    ("cue_points", "updated_at", "INTEGER NOT NULL DEFAULT 0"),
+    ("cue_points", "end_ms", "INTEGER"),
+    (
+        "monitor_routing_config",
+        "auto_fallback",
+        "INTEGER NOT NULL DEFAULT 1",
+    ),
+    ("autodj_clockwheel_state", "template_id", "INTEGER"),
+    ("request_log", "album", "TEXT"),
+    ("encoder_configs", "failover_group", "TEXT"),
+    ("encoder_configs", "priority", "INTEGER NOT NULL DEFAULT 0"),
+    ("remote_dj_permissions", "can_go_live", "INTEGER DEFAULT 0"),
+];
+
+/// Older builds could assign one slot twice; keep the newest cue per slot so
+/// the unique index can be built.
+const CUE_SLOT_UNIQUE: &str = r#"
+    UPDATE cue_points SET slot = NULL
+    WHERE slot IS NOT NULL
+      AND id NOT IN (
+        SELECT MAX(id) FROM cue_points WHERE slot IS NOT NULL GROUP BY song_id, cue_kind, slot
+      );
+    CREATE UNIQUE INDEX IF NOT EXISTS idx_cue_points_song_kind_slot
+        ON cue_points(song_id, cue_kind, slot) WHERE slot IS NOT NULL;
+"#;
+
+fn hourly_play_counts_utc(conn: &mut SqliteConnection) -> BoxFuture<'_, Result<(), sqlx::Error>> {
+    Box::pin(async move {
+        local::migrate_hourly_play_counts_utc(&mut *conn).await?;
+        sqlx::query(
+            "CREATE INDEX IF NOT EXISTS idx_hourly_play_counts_local ON hourly_play_counts(date, hour)",
+        )
+        .execute(&mut *conn)
+        .await?;
+        Ok(())
+    })
+}
+
+/// Write-behind queue for SAM MySQL updates (see `db::sam_outbox`).
+const SAM_OUTBOX: &str = r#"
+    CREATE TABLE IF NOT EXISTS sam_outbox (
+        id              INTEGER PRIMARY KEY AUTOINCREMENT,
+        op_json         TEXT    NOT NULL,
+        created_at      INTEGER NOT NULL,
+        attempts        INTEGER NOT NULL DEFAULT 0,
+        next_attempt_at INTEGER NOT NULL DEFAULT 0,
+        last_error      TEXT,
+        failed          INTEGER NOT NULL DEFAULT 0
+    );
+    CREATE INDEX IF NOT EXISTS idx_sam_outbox_pending ON sam_outbox(failed, id);
+"#;
+
+/// Named crossfade configs and their SAM category / song type assignments.
+const CROSSFADE_PROFILES: &str = r#"
+    CREATE TABLE IF NOT EXISTS crossfade_profiles (
+        id          INTEGER PRIMARY KEY AUTOINCREMENT,
+        name        TEXT    NOT NULL UNIQUE COLLATE NOCASE,
+        config_json TEXT    NOT NULL,
+        updated_at  INTEGER NOT NULL DEFAULT (strftime('%s','now'))
+    );
+    CREATE TABLE IF NOT EXISTS crossfade_profile_assignments (
+        kind        TEXT    NOT NULL,
+        name        TEXT    NOT NULL COLLATE NOCASE,
+        profile_id  INTEGER NOT NULL,
+        PRIMARY KEY (kind, name)
+    );
+"#;
+
+const SAM_SONG_SYNC: &str = r#"
+    CREATE TABLE IF NOT EXISTS sam_song_sync (
+        song_id     INTEGER PRIMARY KEY,
+        title       TEXT    NOT NULL DEFAULT '',
+        artist      TEXT    NOT NULL DEFAULT '',
+        album       TEXT    NOT NULL DEFAULT '',
+        weight      REAL    NOT NULL DEFAULT 0,
+        category    TEXT    NOT NULL DEFAULT '',
+        modified_at INTEGER NOT NULL DEFAULT 0
+    );
+    CREATE INDEX IF NOT EXISTS idx_sam_song_sync_modified ON sam_song_sync(modified_at);
+"#;
+
+const TAG_ENRICHMENT: &str = r#"
+    CREATE TABLE IF NOT EXISTS tag_enrichment_config (
+        id          INTEGER PRIMARY KEY CHECK (id = 1),
+        config_json TEXT    NOT NULL,
+        updated_at  INTEGER NOT NULL DEFAULT (strftime('%s','now'))
+    );
+    CREATE TABLE IF NOT EXISTS tag_proposals (
+        id          INTEGER PRIMARY KEY AUTOINCREMENT,
+        song_id     INTEGER NOT NULL,
+        artist      TEXT    NOT NULL DEFAULT '',
+        title       TEXT    NOT NULL DEFAULT '',
+        field       TEXT    NOT NULL,
+        current     TEXT    NOT NULL DEFAULT '',
+        proposed    TEXT    NOT NULL,
+        source      TEXT    NOT NULL,
+        score       INTEGER NOT NULL DEFAULT 0,
+        status      TEXT    NOT NULL DEFAULT 'pending',
+        created_at  INTEGER NOT NULL,
+        decided_at  INTEGER,
+        UNIQUE(song_id, field, proposed)
+    );
+    CREATE INDEX IF NOT EXISTS idx_tag_proposals_status ON tag_proposals(status, id);
+    CREATE TABLE IF NOT EXISTS tag_enrichment_checked (
+        song_id     INTEGER PRIMARY KEY,
+        checked_at  INTEGER NOT NULL
+    );
+"#;
+
+const SONG_FINGERPRINTS: &str = r#"
+    CREATE TABLE IF NOT EXISTS song_fingerprints (
+        file_path   TEXT    PRIMARY KEY,
+        song_id     INTEGER NOT NULL DEFAULT 0,
+        mtime_ms    INTEGER NOT NULL,
+        duration_ms INTEGER NOT NULL,
+        fingerprint BLOB    NOT NULL,
+        updated_at  INTEGER NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS idx_song_fingerprints_song ON song_fingerprints(song_id);
+"#;
+
+const SCRIPTS: &str = r#"
+    CREATE TABLE IF NOT EXISTS scripts (
+        id            INTEGER PRIMARY KEY,
+        name          TEXT    NOT NULL,
+        description   TEXT,
+        content       TEXT    NOT NULL,
+        enabled       INTEGER NOT NULL DEFAULT 1,
+        trigger_type  TEXT    NOT NULL DEFAULT 'manual',
+        triggers_json TEXT    NOT NULL DEFAULT '[]',
+        last_run_at   INTEGER,
+        last_error    TEXT
+    );
+    CREATE TABLE IF NOT EXISTS script_runs (
+        id          INTEGER PRIMARY KEY AUTOINCREMENT,
+        script_id   INTEGER NOT NULL,
+        started_at  INTEGER NOT NULL,
+        duration_ms INTEGER NOT NULL,
+        trigger     TEXT    NOT NULL,
+        success     INTEGER NOT NULL,
+        error       TEXT
+    );
+    CREATE INDEX IF NOT EXISTS idx_script_runs_script ON script_runs(script_id, id);
+"#;
+
+const OVERLAY_SERVER_CONFIG: &str = r#"
+    CREATE TABLE IF NOT EXISTS overlay_server_config (
+        id          INTEGER PRIMARY KEY DEFAULT 1,
+        config_json TEXT    NOT NULL,
+        updated_at  INTEGER NOT NULL DEFAULT (strftime('%s','now'))
+    );
+"#;
+
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        name: "baseline",
+        step: Step::Sql(local::BASELINE_SCHEMA),
+    },
+    Migration {
+        version: 2,
+        name: "legacy_columns",
+        step: Step::AddColumns(LEGACY_COLUMNS),
+    },
+    Migration {
+        version: 3,
+        name: "cue_slot_unique",
+        step: Step::Sql(CUE_SLOT_UNIQUE),
+    },
+    Migration {
+        version: 4,
+        name: "hourly_play_counts_utc",
+        step: Step::Custom(hourly_play_counts_utc),
+    },
+    Migration {
+        version: 5,
+        name: "sam_outbox",
+        step: Step::Sql(SAM_OUTBOX),
+    },
+    Migration {
+        version: 6,
+        name: "show_overrides",
+        step: Step::AddColumns(&[(
+            "scheduled_shows",
+            "overrides_json",
+            "TEXT NOT NULL DEFAULT '{}'",
+        )]),
+    },
+    Migration {
+        version: 7,
+        name: "crossfade_profiles",
+        step: Step::Sql(CROSSFADE_PROFILES),
+    },
+    Migration {
+        version: 8,
+        name: "transition_markers_xfade",
+        // Cached markers predate the per-song xfade cue; rebuild them.
+        step: Step::Sql("DELETE FROM transition_marker_cache"),
+    },
+    Migration {
+        version: 9,
+        name: "sam_song_sync",
+        step: Step::Sql(SAM_SONG_SYNC),
+    },
+    Migration {
+        version: 10,
+        name: "tag_enrichment",
+        step: Step::Sql(TAG_ENRICHMENT),
+    },
+    Migration {
+        version: 11,
+        name: "song_fingerprints",
+        step: Step::Sql(SONG_FINGERPRINTS),
+    },
+    Migration {
+        version: 12,
+        name: "four_stem_analysis",
+        step: Step::AddColumns(&[
+            ("stem_analysis", "drums_file_path", "TEXT"),
+            ("stem_analysis", "bass_file_path", "TEXT"),
+            ("stem_analysis", "other_file_path", "TEXT"),
+        ]),
+    },
+    Migration {
+        version: 13,
+        name: "scripts",
+        step: Step::Sql(SCRIPTS),
+    },
+    Migration {
+        version: 14,
+        name: "script_sandbox",
+        step: Step::AddColumns(&[
+            ("scripts", "permissions_json", "TEXT NOT NULL DEFAULT '{}'"),
+            ("scripts", "limits_json", "TEXT NOT NULL DEFAULT '{}'"),
+        ]),
+    },
+    Migration {
+        version: 15,
+        name: "overlay_server_config",
+        step: Step::Sql(OVERLAY_SERVER_CONFIG),
+    },
+    Migration {
+        version: 16,
+        name: "health_loudness",
+        step: Step::AddColumns(&[
+            ("system_health_snapshots", "short_term_lufs", "REAL"),
+            ("system_health_snapshots", "integrated_lufs", "REAL"),
+            ("system_health_snapshots", "true_peak_dbtp", "REAL"),
+            (
+                "system_health_snapshots",
+                "true_peak_overs",
+                "INTEGER NOT NULL DEFAULT 0",
+            ),
+        ]),
+    },
+    Migration {
+        version: 17,
+        name: "voice_track_fades",
+        step: Step::AddColumns(&[
+            (
+                "voice_tracks",
+                "prev_fade_out_ms",
+                "INTEGER NOT NULL DEFAULT 0",
+            ),
+            ("voice_tracks", "fade_in_ms", "INTEGER NOT NULL DEFAULT 0"),
+            ("voice_tracks", "fade_out_ms", "INTEGER NOT NULL DEFAULT 0"),
+            (
+                "voice_tracks",
+                "next_fade_in_ms",
+                "INTEGER NOT NULL DEFAULT 0",
+            ),
+        ]),
+    },
+];
+
+pub fn latest_version() -> i64 {
+    MIGRATIONS.last().map_or(0, |m| m.version)
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AppliedMigration {
+    pub version: i64,
+    pub name: String,
+    pub checksum: String,
+    /// Unix ms
+    pub applied_at: i64,
+    /// False when the migration was edited after it ran here
+    pub checksum_matches: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SchemaInfo {
+    pub current_version: i64,
+    pub latest_version: i64,
+    pub applied: Vec<AppliedMigration>,
+    /// Versions this build knows but the database hasn't applied
+    pub pending: Vec<i64>,
+    /// `PRAGMA quick_check` result ("ok" when healthy)
+    pub integrity: String,
+    pub sqlite_version: String,
+    pub journal_mode: String,
+    pub size_bytes: i64,
+    pub table_count: i64,
+}
+
+async fn ensure_table(pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS schema_migrations (
+            version     INTEGER PRIMARY KEY,
+            name        TEXT    NOT NULL,
+            checksum    TEXT    NOT NULL,
+            applied_at  INTEGER NOT NULL
+        )
+        "#,
+    )
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+async fn applied(pool: &SqlitePool) -> Result<Vec<AppliedMigration>, sqlx::Error> {
+    let rows = sqlx::query(
+        "SELECT version, name, checksum, applied_at FROM schema_migrations ORDER BY version",
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(rows
+        .into_iter()
+        .map(|row| {
+            let version: i64 = row.get("version");
+            let checksum: String = row.get("checksum");
+            let checksum_matches = !MIGRATIONS
+                .iter()
+                .any(|m| m.version == version && m.checksum() != checksum);
+            AppliedMigration {
+                version,
+                name: row.get("name"),
+                checksum,
+                applied_at: row.get("applied_at"),
+                checksum_matches,
+            }
+        })
+        .collect())
+}
+
+async fn quick_check(pool: &SqlitePool) -> Result<String, sqlx::Error> {
+    let rows: Vec<String> = sqlx::query_scalar("PRAGMA quick_check")
+        .fetch_all(pool)
+        .await?;
+    Ok(rows.join("; "))
+}
+
+async fn apply(conn: &mut SqliteConnection, migration: &Migration) -> Result<(), sqlx::Error> {
+    match &migration.step {
+        Step::Sql(sql) => {
+            sqlx::query(sql).execute(&mut *conn).await?;
+        }
+        Step::AddColumns(columns) => {
+            for (table, column, definition) in columns.iter() {
+                let exists: i64 =
+                    sqlx::query_scalar("SELECT COUNT(*) FROM pragma_table_info(?) WHERE name = ?")
+                        .bind(table)
+                        .bind(column)
+                        .fetch_one(&mut *conn)
+                        .await?;
+                if exists == 0 {
+                    sqlx::query(&format!(
+                        "ALTER TABLE {table} ADD COLUMN {column} {definition}"
+                    ))
+                    .execute(&mut *conn)
+                    .await?;
+                }
+            }
+        }
+        Step::Custom(migrate) => migrate(conn).await?,
+    }
+    sqlx::query(
+        "INSERT INTO schema_migrations (version, name, checksum, applied_at) VALUES (?, ?, ?, ?)",
+    )
+    .bind(migration.version)
+    .bind(migration.name)
+    .bind(migration.checksum())
+    .bind(chrono::Utc::now().timestamp_millis())
+    .execute(&mut *conn)
+    .await?;
+    Ok(())
+}
+
+/// Bring the schema up to `latest_version`.
+pub async fn run(pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    ensure_table(pool).await?;
+    let applied = applied(pool).await?;
+    let current = applied.iter().map(|m| m.version).max().unwrap_or(0);
+    if current > latest_version() {
+        return Err(sqlx::Error::Protocol(format!(
+            "Database schema v{current} is newer than this build supports (v{}); \
+             refusing to open it",
+            latest_version()
+        )));
+    }
+    for migration in applied.iter().filter(|m| !m.checksum_matches) {
+        log::warn!(
+            "Schema migration {} ({}) changed after it was applied",
+            migration.version,
+            migration.name
+        );
+    }
+    let pending: Vec<&Migration> = MIGRATIONS
+        .iter()
+        .filter(|m| !applied.iter().any(|a| a.version == m.version))
+        .collect();
+    if pending.is_empty() {
+        return Ok(());
+    }
+
+    let existing_tables: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name != 'schema_migrations'",
+    )
+    .fetch_one(pool)
+    .await?;
+    // In-memory databases have no file to back up.
+    let file: String = sqlx::query("PRAGMA database_list")
+        .fetch_one(pool)
+        .await?
+        .get("file");
+    if existing_tables > 0 && !file.is_empty() {
+        let integrity = quick_check(pool).await?;
+        if integrity != "ok" {
+            return Err(sqlx::Error::Protocol(format!(
+                "database disk image is malformed (quick_check: {integrity})"
+            )));
+        }
+        match backup::snapshot_database(pool).await {
+            Ok(info) => log::info!("Pre-migration backup {} taken", info.id),
+            Err(e) => log::warn!("Pre-migration backup failed (migrating anyway): {e}"),
+        }
+    }
+
+    for migration in pending {
+        let mut tx = pool.begin().await?;
+        if let Err(e) = apply(&mut tx, migration).await {
+            log::error!(
+                "Schema migration {} ({}) failed and was rolled back: {e}",
+                migration.version,
+                migration.name
+            );
+            return Err(e);
+        }
+        tx.commit().await?;
+        log::info!(
+            "Applied schema migration {} ({})",
+            migration.version,
+            migration.name
+        );
+    }
+    Ok(())
+}
+
+/// Schema version, migration history and health of the local database.
+pub async fn schema_info(pool: &SqlitePool) -> Result<SchemaInfo, sqlx::Error> {
+    let applied = applied(pool).await?;
+    let pending = MIGRATIONS
+        .iter()
+        .filter(|m| !applied.iter().any(|a| a.version == m.version))
+        .map(|m| m.version)
+        .collect();
+    let page_count: i64 = sqlx::query_scalar("PRAGMA page_count")
+        .fetch_one(pool)
+        .await?;
+    let page_size: i64 = sqlx::query_scalar("PRAGMA page_size")
+        .fetch_one(pool)
+        .await?;
+    Ok(SchemaInfo {
+        current_version: applied.iter().map(|m| m.version).max().unwrap_or(0),
+        latest_version: latest_version(),
+        pending,
+        integrity: quick_check(pool).await?,
+        sqlite_version: sqlx::query_scalar("SELECT sqlite_version()")
+            .fetch_one(pool)
+            .await?,
+        journal_mode: sqlx::query_scalar("PRAGMA journal_mode")
+            .fetch_one(pool)
+            .await?,
+        size_bytes: page_count * page_size,
+        table_count: sqlx::query_scalar("SELECT COUNT(*) FROM sqlite_master WHERE type = 'table'")
+            .fetch_one(pool)
+            .await?,
+        applied,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn adopts_legacy_database_and_is_idempotent() {
+        let pool = sqlx::sqlite::SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .expect("in-memory sqlite pool");
+        // cue_points as created before the hot-cue columns existed
+        sqlx::query(
+            r#"
+            CREATE TABLE cue_points (
+                id          INTEGER PRIMARY KEY AUTOINCREMENT,
+                song_id     INTEGER NOT NULL,
+                name        TEXT    NOT NULL,
+                position_ms INTEGER NOT NULL,
+                UNIQUE(song_id, name)
+            )
+            "#,
+        )
+        .execute(&pool)
+        .await
+        .expect("create legacy cue_points");
+
+        run(&pool).await.expect("migrate legacy database");
+        run(&pool).await.expect("re-run is a no-op");
+
+        let info = schema_info(&pool).await.expect("schema info");
+        assert_eq!(info.current_version, latest_version());
+        assert!(info.pending.is_empty());
+        assert_eq!(info.applied.len(), MIGRATIONS.len());
+        assert!(info.applied.iter().all(|m| m.checksum_matches));
+        let columns: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM pragma_table_info('cue_points') \
+             WHERE name IN ('cue_kind', 'slot', 'updated_at', 'end_ms')",
+        )
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+        assert_eq!(columns, 4);
+    }
+}
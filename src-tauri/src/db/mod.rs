@@ -1,2 +1,13 @@
+pub mod backup;
 pub mod local;
+pub mod migrations;
+pub mod path_rules;
 pub mod sam;
+pub mod sam_cache;
+pub mod sam_cues;
+pub mod sam_health;
+pub mod sam_import;
+pub mod sam_outbox;
+pub mod sam_sync;
+pub mod scripts;
+pub mod tag_enrichment;
@@ -0,0 +1,448 @@
+/// Ordered SAM → local path translation rules
+///
+/// A SAM library often spans several drives that are mounted differently on
+/// the playout machine. Rules are tried in `position` order and the first
+/// match wins:
+///
+/// - `prefix` — case-insensitive prefix swap, with `\` and `/` treated alike
+///   (the same comparison as the single `sam_db_config` prefix pair).
+/// - `regex`  — `pattern` is matched against the SAM path as stored and
+///   `replacement` may use `$1` / `${name}` capture references.
+///
+/// The legacy `path_prefix_from/to` pair in `sam_db_config` still applies,
+/// as a final rule after the table.
+///
+/// The compiled rule set is cached; saving rules or the SAM DB config
+/// invalidates it.
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use sqlx::{Row, SqlitePool};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PathRuleKind {
+    Prefix,
+    Regex,
+}
+
+impl PathRuleKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Prefix => "prefix",
+            Self::Regex => "regex",
+        }
+    }
+
+    fn parse(s: &str) -> Self {
+        if s.eq_ignore_ascii_case("regex") {
+            Self::Regex
+        } else {
+            Self::Prefix
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PathRule {
+    pub id: Option<i64>,
+    pub position: i64,
+    pub kind: PathRuleKind,
+    pub pattern: String,
+    pub replacement: String,
+    pub enabled: bool,
+    pub label: Option<String>,
+}
+
+/// Result of running a path through the rules, for the settings UI.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PathTranslation {
+    pub input: String,
+    pub output: String,
+    /// Rule that matched; `None` with `legacy = false` means no rule matched
+    pub rule_id: Option<i64>,
+    /// Matched the `sam_db_config` prefix pair
+    pub legacy: bool,
+    pub exists: bool,
+}
+
+enum Matcher {
+    Prefix(String),
+    Regex(Regex),
+}
+
+struct CompiledRule {
+    id: Option<i64>,
+    matcher: Matcher,
+    replacement: String,
+}
+
+/// Compiled, ordered rule set.
+#[derive(Default)]
+pub struct PathTranslator {
+    rules: Vec<CompiledRule>,
+}
+
+/// Check that a rule can be compiled.
+pub fn validate_rule(rule: &PathRule) -> Result<(), String> {
+    if rule.pattern.trim().is_empty() {
+        return Err("Pattern is required".to_string());
+    }
+    if rule.kind == PathRuleKind::Regex {
+        Regex::new(&rule.pattern).map_err(|e| format!("Invalid regex: {e}"))?;
+    }
+    Ok(())
+}
+
+fn strip_prefix_ci<'a>(path: &'a str, prefix: &str) -> Option<&'a str> {
+    let prefix = prefix.replace('\\', "/");
+    let head = path.get(..prefix.len())?;
+    head.replace('\\', "/")
+        .eq_ignore_ascii_case(&prefix)
+        .then(|| &path[prefix.len()..])
+}
+
+/// `head` + `rest`, keeping exactly one `sep` between them when either side
+/// of the swapped prefix ended on a directory boundary.
+fn join_prefix(head: &str, matched: &str, rest: &str, sep: char) -> String {
+    let at_boundary = matched.ends_with(['\\', '/']) || rest.starts_with(['\\', '/']);
+    if at_boundary {
+        format!(
+            "{}{sep}{}",
+            head.trim_end_matches(['\\', '/']),
+            rest.trim_start_matches(['\\', '/'])
+        )
+    } else {
+        format!("{head}{rest}")
+    }
+}
+
+impl PathTranslator {
+    /// Enabled `rules` in order, then the legacy prefix pair if set. Rules
+    /// whose regex does not compile are skipped with a warning.
+    pub fn new(rules: &[PathRule], legacy_from: &str, legacy_to: &str) -> Self {
+        let mut compiled = Vec::with_capacity(rules.len() + 1);
+        for rule in rules.iter().filter(|r| r.enabled && !r.pattern.is_empty()) {
+            let matcher = match rule.kind {
+                PathRuleKind::Prefix => Matcher::Prefix(rule.pattern.clone()),
+                PathRuleKind::Regex => match Regex::new(&rule.pattern) {
+                    Ok(re) => Matcher::Regex(re),
+                    Err(e) => {
+                        log::warn!("Skipping path rule {:?}: {e}", rule.id);
+                        continue;
+                    }
+                },
+            };
+            compiled.push(CompiledRule {
+                id: rule.id,
+                matcher,
+                replacement: rule.replacement.clone(),
+            });
+        }
+        if !legacy_from.is_empty() {
+            compiled.push(CompiledRule {
+                id: None,
+                matcher: Matcher::Prefix(legacy_from.to_string()),
+                replacement: legacy_to.to_string(),
+            });
+        }
+        Self { rules: compiled }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Translated path and the index of the rule that matched.
+    fn apply(&self, path: &str) -> Option<(String, usize)> {
+        if path.is_empty() {
+            return None;
+        }
+        self.rules.iter().enumerate().find_map(|(i, rule)| {
+            let out = match &rule.matcher {
+                Matcher::Prefix(prefix) => {
+                    let rest = strip_prefix_ci(path, prefix)?.replace('\\', "/");
+                    join_prefix(&rule.replacement, prefix, &rest, '/')
+                }
+                Matcher::Regex(re) => {
+                    if !re.is_match(path) {
+                        return None;
+                    }
+                    re.replace(path, rule.replacement.as_str()).into_owned()
+                }
+            };
+            Some((out, i))
+        })
+    }
+
+    /// Local path for a SAM filename; unmatched paths pass through unchanged.
+    pub fn translate(&self, path: &str) -> String {
+        self.apply(path)
+            .map(|(out, _)| out)
+            .unwrap_or_else(|| path.to_string())
+    }
+
+    /// SAM filename that translates to `local`, for writing a relinked file
+    /// back to the songlist. Only prefix rules can be reversed, and a
+    /// candidate is kept only if it translates back to `local` unchanged.
+    /// With no rules at all SAM and this machine share paths.
+    pub fn untranslate(&self, local: &str) -> Option<String> {
+        let reversed = self.rules.iter().find_map(|rule| {
+            let Matcher::Prefix(prefix) = &rule.matcher else {
+                return None;
+            };
+            let rest = strip_prefix_ci(local, &rule.replacement)?;
+            let sep = if prefix.contains('\\') { '\\' } else { '/' };
+            let rest = rest.replace(['\\', '/'], &sep.to_string());
+            let sam = join_prefix(prefix, &rule.replacement, &rest, sep);
+            (self.translate(&sam) == local).then_some(sam)
+        });
+        reversed.or_else(|| self.is_empty().then(|| local.to_string()))
+    }
+
+    pub fn explain(&self, path: &str) -> PathTranslation {
+        let (output, rule) = match self.apply(path) {
+            Some((out, i)) => (out, Some(&self.rules[i])),
+            None => (path.to_string(), None),
+        };
+        PathTranslation {
+            input: path.to_string(),
+            exists: std::path::Path::new(&output).exists(),
+            output,
+            rule_id: rule.and_then(|r| r.id),
+            legacy: rule.is_some_and(|r| r.id.is_none()),
+        }
+    }
+}
+
+// ── Storage ───────────────────────────────────────────────────────────────────
+
+pub async fn get_path_rules(pool: &SqlitePool) -> Result<Vec<PathRule>, sqlx::Error> {
+    let rows = sqlx::query(
+        "SELECT id, position, kind, pattern, replacement, enabled, label
+         FROM path_translation_rules ORDER BY position, id",
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(rows
+        .into_iter()
+        .map(|r| PathRule {
+            id: r.get("id"),
+            position: r.get("position"),
+            kind: PathRuleKind::parse(&r.get::<String, _>("kind")),
+            pattern: r.get("pattern"),
+            replacement: r.get("replacement"),
+            enabled: r.get::<i64, _>("enabled") != 0,
+            label: r.get("label"),
+        })
+        .collect())
+}
+
+/// Insert or update a rule. New rules go to the end of the list.
+pub async fn upsert_path_rule(pool: &SqlitePool, rule: &PathRule) -> Result<i64, sqlx::Error> {
+    if let Some(id) = rule.id {
+        sqlx::query(
+            "UPDATE path_translation_rules
+             SET kind = ?, pattern = ?, replacement = ?, enabled = ?, label = ?
+             WHERE id = ?",
+        )
+        .bind(rule.kind.as_str())
+        .bind(&rule.pattern)
+        .bind(&rule.replacement)
+        .bind(rule.enabled as i64)
+        .bind(&rule.label)
+        .bind(id)
+        .execute(pool)
+        .await?;
+        invalidate_translator();
+        Ok(id)
+    } else {
+        let result = sqlx::query(
+            "INSERT INTO path_translation_rules
+                 (position, kind, pattern, replacement, enabled, label)
+             VALUES ((SELECT COALESCE(MAX(position), -1) + 1 FROM path_translation_rules),
+                     ?, ?, ?, ?, ?)",
+        )
+        .bind(rule.kind.as_str())
+        .bind(&rule.pattern)
+        .bind(&rule.replacement)
+        .bind(rule.enabled as i64)
+        .bind(&rule.label)
+        .execute(pool)
+        .await?;
+        invalidate_translator();
+        Ok(result.last_insert_rowid())
+    }
+}
+
+pub async fn delete_path_rule(pool: &SqlitePool, id: i64) -> Result<(), sqlx::Error> {
+    sqlx::query("DELETE FROM path_translation_rules WHERE id = ?")
+        .bind(id)
+        .execute(pool)
+        .await?;
+    invalidate_translator();
+    Ok(())
+}
+
+/// Renumber rules to follow `ids`; rules not listed keep their relative
+/// order after them.
+pub async fn reorder_path_rules(pool: &SqlitePool, ids: &[i64]) -> Result<(), sqlx::Error> {
+    let mut ordered = ids.to_vec();
+    for rule in get_path_rules(pool).await? {
+        if let Some(id) = rule.id.filter(|id| !ids.contains(id)) {
+            ordered.push(id);
+        }
+    }
+    let mut tx = pool.begin().await?;
+    for (position, id) in ordered.iter().enumerate() {
+        sqlx::query("UPDATE path_translation_rules SET position = ? WHERE id = ?")
+            .bind(position as i64)
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+    }
+    tx.commit().await?;
+    invalidate_translator();
+    Ok(())
+}
+
+// ── Cache ─────────────────────────────────────────────────────────────────────
+
+/// Bumped on every change to the rules or the legacy prefix pair, so a load
+/// that raced a save does not cache the old rules.
+static GENERATION: AtomicU64 = AtomicU64::new(0);
+/// The compiled translator and the generation it was built from.
+type CachedTranslator = Mutex<Option<(u64, Arc<PathTranslator>)>>;
+static TRANSLATOR: OnceLock<CachedTranslator> = OnceLock::new();
+
+fn translator_cell() -> &'static CachedTranslator {
+    TRANSLATOR.get_or_init(|| Mutex::new(None))
+}
+
+/// Drop the cached translator; the next `load_translator` recompiles.
+pub fn invalidate_translator() {
+    GENERATION.fetch_add(1, Ordering::SeqCst);
+    translator_cell().lock().unwrap().take();
+}
+
+/// Translator for the stored rules plus the `sam_db_config` prefix pair,
+/// compiled once and shared until the rules change. Storage errors are
+/// logged and yield whatever could be loaded, uncached.
+pub async fn load_translator(pool: &SqlitePool) -> Arc<PathTranslator> {
+    let generation = GENERATION.load(Ordering::SeqCst);
+    if let Some((cached_at, translator)) = translator_cell().lock().unwrap().as_ref() {
+        if *cached_at == generation {
+            return translator.clone();
+        }
+    }
+
+    let mut complete = true;
+    let rules = get_path_rules(pool).await.unwrap_or_else(|e| {
+        log::warn!("Failed to load path translation rules: {e}");
+        complete = false;
+        Vec::new()
+    });
+    let cfg = super::local::get_sam_db_config(pool)
+        .await
+        .unwrap_or_else(|_| {
+            complete = false;
+            Default::default()
+        });
+    let translator = Arc::new(PathTranslator::new(
+        &rules,
+        &cfg.path_prefix_from,
+        &cfg.path_prefix_to,
+    ));
+    if complete && GENERATION.load(Ordering::SeqCst) == generation {
+        *translator_cell().lock().unwrap() = Some((generation, translator.clone()));
+    }
+    translator
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(id: i64, kind: PathRuleKind, pattern: &str, replacement: &str) -> PathRule {
+        PathRule {
+            id: Some(id),
+            position: id,
+            kind,
+            pattern: pattern.to_string(),
+            replacement: replacement.to_string(),
+            enabled: true,
+            label: None,
+        }
+    }
+
+    #[test]
+    fn first_matching_rule_wins_and_legacy_comes_last() {
+        let rules = vec![
+            rule(
+                1,
+                PathRuleKind::Prefix,
+                "D:\\Jingles\\",
+                "/Volumes/Jingles/",
+            ),
+            rule(
+                2,
+                PathRuleKind::Regex,
+                r"^[EF]:\\Archive\\(\d{4})\\",
+                "/Volumes/Archive/$1/",
+            ),
+        ];
+        let t = PathTranslator::new(&rules, "C:\\Music\\", "/Volumes/Music/");
+
+        assert_eq!(
+            t.translate("d:\\jingles\\id.mp3"),
+            "/Volumes/Jingles/id.mp3"
+        );
+        assert_eq!(
+            t.translate("F:\\Archive\\1999\\x.mp3"),
+            "/Volumes/Archive/1999/x.mp3"
+        );
+        assert_eq!(t.translate("C:\\Music\\a\\b.mp3"), "/Volumes/Music/a/b.mp3");
+        assert_eq!(t.translate("Z:\\other.mp3"), "Z:\\other.mp3");
+
+        let explained = t.explain("C:\\Music\\b.mp3");
+        assert!(explained.legacy);
+        assert_eq!(explained.rule_id, None);
+        assert_eq!(t.explain("D:\\Jingles\\a.mp3").rule_id, Some(1));
+    }
+
+    #[test]
+    fn relinked_paths_reverse_through_prefix_rules_only() {
+        let rules = vec![rule(
+            1,
+            PathRuleKind::Regex,
+            r"^E:\\Archive\\(.*)$",
+            "/Volumes/Archive/$1",
+        )];
+        let t = PathTranslator::new(&rules, "C:\\Music\\", "/Volumes/Music/");
+
+        assert_eq!(
+            t.untranslate("/Volumes/Music/new/b.mp3").as_deref(),
+            Some("C:\\Music\\new\\b.mp3")
+        );
+        assert_eq!(t.untranslate("/Volumes/Archive/x.mp3"), None);
+        assert_eq!(t.untranslate("/srv/other/c.mp3"), None);
+        assert_eq!(
+            PathTranslator::default()
+                .untranslate("/srv/other/c.mp3")
+                .as_deref(),
+            Some("/srv/other/c.mp3")
+        );
+    }
+
+    #[test]
+    fn disabled_and_invalid_rules_are_skipped() {
+        let mut disabled = rule(1, PathRuleKind::Prefix, "C:\\", "/a/");
+        disabled.enabled = false;
+        let invalid = rule(2, PathRuleKind::Regex, "(", "");
+        assert!(validate_rule(&invalid).is_err());
+        let t = PathTranslator::new(&[disabled, invalid], "", "");
+        assert!(t.is_empty());
+    }
+}
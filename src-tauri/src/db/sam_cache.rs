@@ -0,0 +1,166 @@
+/// In-process cache for SAM read queries.
+///
+/// Song selection runs once per track and the queue top-up every second, and
+/// each run used to re-read candidates, history, categories and schema probes
+/// from MySQL. Those results are now kept here with a TTL.
+///
+/// Any SAM write that changes what selection sees (history append, weight or
+/// play-stat update, song edit, new category) calls [`note_write`], which
+/// retires every data entry at once. Schema probes ignore writes and only
+/// expire by TTL or on [`reset`], which runs whenever the pool is replaced.
+use std::collections::HashMap;
+use std::future::Future;
+use std::hash::Hash;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+
+/// Candidates, history and song lookups.
+pub const DATA_TTL: Duration = Duration::from_secs(60);
+/// Category list.
+pub const CATEGORY_TTL: Duration = Duration::from_secs(300);
+/// `information_schema` probes.
+pub const SCHEMA_TTL: Duration = Duration::from_secs(600);
+
+static WRITE_GENERATION: AtomicU64 = AtomicU64::new(0);
+static RESET_GENERATION: AtomicU64 = AtomicU64::new(0);
+static HITS: AtomicU64 = AtomicU64::new(0);
+static MISSES: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    /// Writes that invalidated cached data since startup
+    pub invalidations: u64,
+}
+
+/// A SAM write happened; cached query results may be stale.
+pub fn note_write() {
+    WRITE_GENERATION.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Drop everything, schema probes included (new or rebuilt pool).
+pub fn reset() {
+    RESET_GENERATION.fetch_add(1, Ordering::Relaxed);
+    note_write();
+}
+
+pub fn stats() -> CacheStats {
+    CacheStats {
+        hits: HITS.load(Ordering::Relaxed),
+        misses: MISSES.load(Ordering::Relaxed),
+        invalidations: WRITE_GENERATION.load(Ordering::Relaxed),
+    }
+}
+
+struct Entry<V> {
+    stored_at: Instant,
+    write_generation: u64,
+    reset_generation: u64,
+    value: V,
+}
+
+pub struct TtlCache<K, V> {
+    ttl: Duration,
+    /// Entries are retired by [`note_write`] as well as by age
+    follows_writes: bool,
+    entries: OnceLock<Mutex<HashMap<K, Entry<V>>>>,
+}
+
+impl<K: Eq + Hash, V: Clone> TtlCache<K, V> {
+    pub const fn new(ttl: Duration, follows_writes: bool) -> Self {
+        Self {
+            ttl,
+            follows_writes,
+            entries: OnceLock::new(),
+        }
+    }
+
+    fn entries(&self) -> &Mutex<HashMap<K, Entry<V>>> {
+        self.entries.get_or_init(|| Mutex::new(HashMap::new()))
+    }
+
+    fn is_fresh(&self, entry: &Entry<V>) -> bool {
+        entry.stored_at.elapsed() < self.ttl
+            && entry.reset_generation == RESET_GENERATION.load(Ordering::Relaxed)
+            && (!self.follows_writes
+                || entry.write_generation == WRITE_GENERATION.load(Ordering::Relaxed))
+    }
+
+    pub fn get(&self, key: &K) -> Option<V> {
+        let mut entries = self.entries().lock().unwrap();
+        match entries.get(key) {
+            Some(entry) if self.is_fresh(entry) => {
+                HITS.fetch_add(1, Ordering::Relaxed);
+                Some(entry.value.clone())
+            }
+            Some(_) => {
+                entries.remove(key);
+                None
+            }
+            None => None,
+        }
+    }
+
+    /// Cached value for `key`, or the result of `fetch`. Only successes are
+    /// stored. A write that lands while `fetch` runs leaves the new entry
+    /// already stale, so it is never served.
+    pub async fn get_or_fetch<E, F>(&self, key: K, fetch: F) -> Result<V, E>
+    where
+        F: Future<Output = Result<V, E>>,
+    {
+        if let Some(value) = self.get(&key) {
+            return Ok(value);
+        }
+        MISSES.fetch_add(1, Ordering::Relaxed);
+        let write_generation = WRITE_GENERATION.load(Ordering::Relaxed);
+        let reset_generation = RESET_GENERATION.load(Ordering::Relaxed);
+        let value = fetch.await?;
+        let mut entries = self.entries().lock().unwrap();
+        entries.retain(|_, entry| self.is_fresh(entry));
+        entries.insert(
+            key,
+            Entry {
+                stored_at: Instant::now(),
+                write_generation,
+                reset_generation,
+                value: value.clone(),
+            },
+        );
+        Ok(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn writes_retire_data_but_not_schema_entries() {
+        static DATA: TtlCache<&str, i32> = TtlCache::new(DATA_TTL, true);
+        static SCHEMA: TtlCache<&str, bool> = TtlCache::new(SCHEMA_TTL, false);
+
+        let fetched = DATA.get_or_fetch("k", async { Ok::<_, ()>(1) }).await;
+        assert_eq!(fetched, Ok(1));
+        let cached = DATA.get_or_fetch("k", async { Ok::<_, ()>(2) }).await;
+        assert_eq!(cached, Ok(1));
+        let _ = SCHEMA.get_or_fetch("t", async { Ok::<_, ()>(true) }).await;
+
+        note_write();
+        assert_eq!(DATA.get(&"k"), None);
+        assert_eq!(SCHEMA.get(&"t"), Some(true));
+
+        // Failures are not cached.
+        let failed = DATA
+            .get_or_fetch("e", async { Err::<i32, _>("down") })
+            .await;
+        assert_eq!(failed, Err("down"));
+        assert_eq!(DATA.get(&"e"), None);
+
+        reset();
+        assert_eq!(SCHEMA.get(&"t"), None);
+    }
+}
@@ -0,0 +1,265 @@
+/// `db/sam_cues.rs` — import SAM's per-song cue fields
+///
+/// SAM keeps a song's intro length, outro length and crossfade point in the
+/// `intro`, `outro` and `xfade` columns of `songlist`. This module walks the
+/// whole song list and writes them into `cue_points` as transition cues, so
+/// the transition planner has markers for songs nobody cued by hand:
+///
+/// - `intro` → `intro_end` at the intro length
+/// - `outro` → `outro_start` that long before the end
+/// - `xfade` → `xfade` at the crossfade point
+///
+/// Whole numbers are milliseconds, decimals seconds, and `m:ss(.fff)` is read
+/// as a time. Columns a SAM install doesn't have are skipped. Existing cues
+/// are kept unless the import is told to overwrite them.
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use serde::{Deserialize, Serialize};
+use sqlx::{mysql::MySqlRow, MySqlPool, Row, SqlitePool};
+use tauri::{AppHandle, Emitter};
+
+use super::local::{self, CueKind, CuePoint};
+
+const BATCH_SIZE: i64 = 500;
+
+static RUNNING: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SamCueImportOptions {
+    /// Replace cues that already exist under the same name
+    pub overwrite: bool,
+}
+
+/// Emitted as `sam_cue_import_progress` after every batch; the last one has
+/// `done` set.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SamCueImportProgress {
+    pub total: i64,
+    pub processed: i64,
+    /// Songs that got at least one cue
+    pub songs_updated: i64,
+    pub cues_written: i64,
+    /// Cues left alone because the song already had one by that name
+    pub cues_kept: i64,
+    /// Non-empty fields that could not be read as a time
+    pub unreadable: i64,
+    pub done: bool,
+}
+
+/// A field value in milliseconds. Empty and zero values mean "not set".
+pub fn parse_cue_ms(raw: &str) -> Option<u64> {
+    let raw = raw.trim();
+    if raw.is_empty() {
+        return None;
+    }
+    let ms = if let Some((min, sec)) = raw.split_once(':') {
+        let min: u64 = min.trim().parse().ok()?;
+        let sec: f64 = sec.trim().parse().ok()?;
+        if !(0.0..60.0).contains(&sec) {
+            return None;
+        }
+        min * 60_000 + (sec * 1000.0).round() as u64
+    } else if raw.contains('.') {
+        let sec: f64 = raw.parse().ok()?;
+        if !sec.is_finite() || sec < 0.0 {
+            return None;
+        }
+        (sec * 1000.0).round() as u64
+    } else {
+        raw.parse().ok()?
+    };
+    (ms > 0).then_some(ms)
+}
+
+/// Raw column text; numeric columns are read as numbers. `None` when the
+/// column is missing or NULL.
+fn column_text(row: &MySqlRow, column: &str) -> Option<String> {
+    row.try_get::<Option<i64>, _>(column)
+        .map(|v| v.map(|v| v.to_string()))
+        .or_else(|_| {
+            row.try_get::<Option<i32>, _>(column)
+                .map(|v| v.map(|v| v.to_string()))
+        })
+        .or_else(|_| {
+            row.try_get::<Option<f64>, _>(column)
+                .map(|v| v.map(|v| v.to_string()))
+        })
+        .or_else(|_| row.try_get::<Option<String>, _>(column))
+        .ok()
+        .flatten()
+}
+
+/// Cue name and position for each SAM field set on a song.
+fn cues_for_row(row: &MySqlRow, progress: &mut SamCueImportProgress) -> Vec<(&'static str, u64)> {
+    let duration_ms = row
+        .try_get::<i32, _>("duration")
+        .map(|v| v as i64)
+        .or_else(|_| row.try_get::<i64, _>("duration"))
+        .unwrap_or(0)
+        .max(0) as u64
+        * 1000;
+    let mut field = |column: &str| {
+        let text = column_text(row, column)?;
+        let ms = parse_cue_ms(&text);
+        if ms.is_none() && !matches!(text.trim(), "" | "0") {
+            progress.unreadable += 1;
+        }
+        ms
+    };
+    let intro = field("intro");
+    let outro = field("outro");
+    let xfade = field("xfade");
+
+    let within = |ms: u64| duration_ms == 0 || ms < duration_ms;
+    let mut cues = Vec::new();
+    if let Some(ms) = intro.filter(|ms| within(*ms)) {
+        cues.push(("intro_end", ms));
+    }
+    if let Some(ms) = outro.filter(|ms| duration_ms > *ms) {
+        cues.push(("outro_start", duration_ms - ms));
+    }
+    if let Some(ms) = xfade.filter(|ms| within(*ms)) {
+        cues.push(("xfade", ms));
+    }
+    cues
+}
+
+fn emit(app: &AppHandle, progress: &SamCueImportProgress) {
+    let _ = app.emit("sam_cue_import_progress", progress);
+}
+
+pub fn is_running() -> bool {
+    RUNNING.load(Ordering::Relaxed)
+}
+
+/// Import cues for every song in SAM's song list.
+pub async fn import_all(
+    app: &AppHandle,
+    sam: &MySqlPool,
+    local: &SqlitePool,
+    options: SamCueImportOptions,
+) -> Result<SamCueImportProgress, String> {
+    if RUNNING.swap(true, Ordering::AcqRel) {
+        return Err("A SAM cue import is already running".to_string());
+    }
+    let result = run(app, sam, local, options).await;
+    RUNNING.store(false, Ordering::Release);
+    result
+}
+
+async fn run(
+    app: &AppHandle,
+    sam: &MySqlPool,
+    local: &SqlitePool,
+    options: SamCueImportOptions,
+) -> Result<SamCueImportProgress, String> {
+    let mut progress = SamCueImportProgress {
+        total: sqlx::query_scalar("SELECT COUNT(*) FROM songlist")
+            .fetch_one(sam)
+            .await
+            .map_err(|e| e.to_string())?,
+        ..Default::default()
+    };
+    emit(app, &progress);
+
+    let mut last_id = 0i64;
+    loop {
+        let rows = sqlx::query("SELECT * FROM songlist WHERE ID > ? ORDER BY ID LIMIT ?")
+            .bind(last_id)
+            .bind(BATCH_SIZE)
+            .fetch_all(sam)
+            .await
+            .map_err(|e| e.to_string())?;
+        let Some(last) = rows.last() else {
+            break;
+        };
+        last_id = last.try_get::<i64, _>("ID").unwrap_or_else(|_| {
+            last.try_get::<i32, _>("ID")
+                .map(|v| v as i64)
+                .unwrap_or(i64::MAX)
+        });
+
+        for row in &rows {
+            progress.processed += 1;
+            let song_id = row
+                .try_get::<i64, _>("ID")
+                .or_else(|_| row.try_get::<i32, _>("ID").map(|v| v as i64))
+                .unwrap_or(0);
+            let cues = cues_for_row(row, &mut progress);
+            if song_id <= 0 || cues.is_empty() {
+                continue;
+            }
+            let existing: HashSet<String> = if options.overwrite {
+                HashSet::new()
+            } else {
+                local::get_cue_points(local, song_id)
+                    .await
+                    .map_err(|e| e.to_string())?
+                    .into_iter()
+                    .map(|c| c.name.to_ascii_lowercase())
+                    .collect()
+            };
+            let mut wrote = false;
+            for (name, position_ms) in cues {
+                if existing.contains(name) {
+                    progress.cues_kept += 1;
+                    continue;
+                }
+                local::upsert_cue_point(
+                    local,
+                    &CuePoint {
+                        id: None,
+                        song_id,
+                        name: name.to_string(),
+                        position_ms: position_ms as i64,
+                        cue_kind: CueKind::Transition,
+                        slot: None,
+                        label: String::new(),
+                        color_hex: String::new(),
+                        updated_at: None,
+                    },
+                )
+                .await
+                .map_err(|e| e.to_string())?;
+                progress.cues_written += 1;
+                wrote = true;
+            }
+            if wrote {
+                progress.songs_updated += 1;
+            }
+        }
+        emit(app, &progress);
+        if (rows.len() as i64) < BATCH_SIZE {
+            break;
+        }
+    }
+
+    progress.done = true;
+    emit(app, &progress);
+    log::info!(
+        "SAM cue import: {} cue(s) written for {} song(s), {} kept, {} unreadable",
+        progress.cues_written,
+        progress.songs_updated,
+        progress.cues_kept,
+        progress.unreadable
+    );
+    Ok(progress)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cue_fields_parse_as_ms_seconds_or_time() {
+        assert_eq!(parse_cue_ms("12500"), Some(12_500));
+        assert_eq!(parse_cue_ms("12.5"), Some(12_500));
+        assert_eq!(parse_cue_ms("3:05.250"), Some(185_250));
+        assert_eq!(parse_cue_ms(" 0 "), None);
+        assert_eq!(parse_cue_ms(""), None);
+        assert_eq!(parse_cue_ms("Default"), None);
+        assert_eq!(parse_cue_ms("1:75"), None);
+    }
+}
@@ -0,0 +1,312 @@
+/// SAM MySQL connection health.
+///
+/// A background task pings the pool every few seconds. When MySQL stops
+/// answering the link is marked degraded:
+///
+/// - AutoDJ skips the SAM queue and picks from the rotation candidate cache
+///   (`rotation::select_cached_track`), so playout continues.
+/// - SAM writes wait in the outbox (`db::sam_outbox`).
+/// - The pool is rebuilt from the saved config, with backoff, until MySQL
+///   answers again.
+///
+/// Every state change is emitted as `sam_db_status_changed`.
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use sqlx::MySqlPool;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::{
+    analytics::event_logger::{log_event, EventCategory, LogLevel},
+    state::AppState,
+};
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(10);
+/// Quick re-check after a single failed ping, before declaring an outage.
+const RECHECK_INTERVAL: Duration = Duration::from_secs(2);
+const PING_TIMEOUT: Duration = Duration::from_secs(5);
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+const FAILURES_BEFORE_DEGRADED: u32 = 2;
+const RECONNECT_BACKOFF_SECS: [u64; 5] = [2, 5, 10, 30, 60];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SamLinkState {
+    /// Pool answers health checks
+    Connected,
+    /// Connection wanted but MySQL isn't answering; reconnecting
+    Degraded,
+    /// Not configured, or disconnected by the user
+    Disconnected,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SamHealth {
+    pub state: SamLinkState,
+    /// Unix ms of the last state change
+    pub since: i64,
+    /// Unix ms of the last successful ping
+    pub last_ok_at: Option<i64>,
+    pub latency_ms: Option<u64>,
+    pub last_error: Option<String>,
+    /// Failed reconnects in the current outage
+    pub reconnect_attempts: u32,
+    /// Songs AutoDJ can pick from while degraded
+    pub cached_candidates: usize,
+}
+
+static DEGRADED: AtomicBool = AtomicBool::new(false);
+
+fn health_cell() -> &'static Mutex<SamHealth> {
+    static HEALTH: OnceLock<Mutex<SamHealth>> = OnceLock::new();
+    HEALTH.get_or_init(|| {
+        Mutex::new(SamHealth {
+            state: SamLinkState::Disconnected,
+            since: now_ms(),
+            last_ok_at: None,
+            latency_ms: None,
+            last_error: None,
+            reconnect_attempts: 0,
+            cached_candidates: 0,
+        })
+    })
+}
+
+fn now_ms() -> i64 {
+    chrono::Utc::now().timestamp_millis()
+}
+
+/// MySQL is configured but unreachable; callers on the playout path should
+/// not query SAM.
+pub fn is_degraded() -> bool {
+    DEGRADED.load(Ordering::Relaxed)
+}
+
+pub fn snapshot() -> SamHealth {
+    let mut health = health_cell().lock().unwrap().clone();
+    health.cached_candidates = crate::scheduler::rotation::cached_candidate_count();
+    health
+}
+
+async fn transition(app: &AppHandle, to: SamLinkState, error: Option<String>) {
+    let changed = {
+        let mut health = health_cell().lock().unwrap();
+        let changed = health.state != to;
+        if changed {
+            health.state = to;
+            health.since = now_ms();
+        }
+        match to {
+            SamLinkState::Connected => {
+                health.last_ok_at = Some(now_ms());
+                health.last_error = None;
+                health.reconnect_attempts = 0;
+            }
+            SamLinkState::Degraded => {
+                if error.is_some() {
+                    health.last_error = error.clone();
+                }
+            }
+            SamLinkState::Disconnected => {
+                health.last_error = None;
+                health.reconnect_attempts = 0;
+                health.latency_ms = None;
+            }
+        }
+        changed
+    };
+    DEGRADED.store(to == SamLinkState::Degraded, Ordering::Relaxed);
+    if !changed {
+        return;
+    }
+
+    let _ = app.emit("sam_db_status_changed", snapshot());
+    let (level, event, message) = match to {
+        SamLinkState::Connected => (
+            LogLevel::Info,
+            "sam_db_connected",
+            "SAM DB connection is healthy".to_string(),
+        ),
+        SamLinkState::Degraded => (
+            LogLevel::Warn,
+            "sam_db_degraded",
+            format!(
+                "SAM DB unreachable, AutoDJ using cached candidates: {}",
+                error.as_deref().unwrap_or("unknown error")
+            ),
+        ),
+        SamLinkState::Disconnected => (
+            LogLevel::Info,
+            "sam_db_disconnected",
+            "SAM DB disconnected".to_string(),
+        ),
+    };
+    log::info!("{message}");
+    if let Some(local) = &app.state::<AppState>().local_db {
+        let _ = log_event(
+            local,
+            level,
+            EventCategory::Database,
+            event,
+            &message,
+            error.map(|e| serde_json::json!({ "error": e })),
+            None,
+            None,
+            None,
+        )
+        .await;
+    }
+}
+
+/// A pool was just connected by hand.
+pub async fn mark_connected(app: &AppHandle) {
+    transition(app, SamLinkState::Connected, None).await;
+}
+
+/// The user dropped the connection; stop reconnecting.
+pub async fn mark_disconnected(app: &AppHandle) {
+    transition(app, SamLinkState::Disconnected, None).await;
+}
+
+/// Round-trip time of `SELECT 1`.
+async fn ping(pool: &MySqlPool) -> Result<Duration, String> {
+    let started = Instant::now();
+    match tokio::time::timeout(PING_TIMEOUT, sqlx::query("SELECT 1").execute(pool)).await {
+        Ok(Ok(_)) => Ok(started.elapsed()),
+        Ok(Err(e)) => Err(e.to_string()),
+        Err(_) => Err(format!("No answer within {}s", PING_TIMEOUT.as_secs())),
+    }
+}
+
+fn record_ping(latency: Duration) {
+    let mut health = health_cell().lock().unwrap();
+    health.last_ok_at = Some(now_ms());
+    health.latency_ms = Some(latency.as_millis() as u64);
+}
+
+/// Get a working pool back into `AppState`: the current one if it has
+/// recovered by itself, otherwise a fresh one from the saved config.
+async fn reconnect(state: &AppState) -> Result<(), String> {
+    let current = state.sam_db.read().await.clone();
+    if let Some(pool) = &current {
+        if let Ok(latency) = ping(pool).await {
+            record_ping(latency);
+            return Ok(());
+        }
+    }
+
+    let local = state
+        .local_db
+        .as_ref()
+        .ok_or("Local database unavailable")?;
+    let cfg = super::local::load_sam_db_config_full(local)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or("No saved SAM DB config")?;
+    let url = super::sam::mysql_url(
+        &cfg.config.host,
+        cfg.config.port,
+        &cfg.config.username,
+        &cfg.password,
+        &cfg.config.database_name,
+    );
+    let pool = tokio::time::timeout(CONNECT_TIMEOUT, super::sam::connect(&url))
+        .await
+        .map_err(|_| format!("Connect timed out after {}s", CONNECT_TIMEOUT.as_secs()))?
+        .map_err(|e| e.to_string())?;
+    record_ping(ping(&pool).await?);
+
+    let old = {
+        let mut guard = state.sam_db.write().await;
+        // The user may have disconnected while we were connecting.
+        if health_cell().lock().unwrap().state != SamLinkState::Degraded {
+            drop(guard);
+            pool.close().await;
+            return Ok(());
+        }
+        super::sam_cache::reset();
+        guard.replace(pool)
+    };
+    if let Some(old) = old {
+        tauri::async_runtime::spawn(async move { old.close().await });
+    }
+    Ok(())
+}
+
+fn reconnect_backoff(attempts: u32) -> Duration {
+    let idx = (attempts.max(1) as usize - 1).min(RECONNECT_BACKOFF_SECS.len() - 1);
+    Duration::from_secs(RECONNECT_BACKOFF_SECS[idx])
+}
+
+/// Watch the SAM connection for as long as the app runs.
+pub fn start(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let state = app.state::<AppState>();
+        if state.sam_db.read().await.is_some() {
+            transition(&app, SamLinkState::Connected, None).await;
+        } else if let Some(local) = &state.local_db {
+            // Auto-connect failed at startup: treat it as an outage.
+            if let Ok(Some(cfg)) = super::local::load_sam_db_config_full(local).await {
+                if cfg.config.auto_connect {
+                    let error = "Auto-connect failed at startup".to_string();
+                    transition(&app, SamLinkState::Degraded, Some(error)).await;
+                }
+            }
+        }
+
+        let mut failures = 0;
+        loop {
+            let current = health_cell().lock().unwrap().state;
+            match current {
+                SamLinkState::Disconnected => {
+                    failures = 0;
+                    tokio::time::sleep(CHECK_INTERVAL).await;
+                }
+                SamLinkState::Connected => {
+                    let wait = if failures > 0 {
+                        RECHECK_INTERVAL
+                    } else {
+                        CHECK_INTERVAL
+                    };
+                    tokio::time::sleep(wait).await;
+                    let pool = state.sam_db.read().await.clone();
+                    let Some(pool) = pool else {
+                        continue;
+                    };
+                    match ping(&pool).await {
+                        Ok(latency) => {
+                            failures = 0;
+                            record_ping(latency);
+                        }
+                        Err(e) => {
+                            failures += 1;
+                            log::warn!("SAM DB health check failed ({failures}): {e}");
+                            if failures >= FAILURES_BEFORE_DEGRADED {
+                                failures = 0;
+                                transition(&app, SamLinkState::Degraded, Some(e)).await;
+                            }
+                        }
+                    }
+                }
+                SamLinkState::Degraded => match reconnect(&state).await {
+                    Ok(()) => {
+                        if health_cell().lock().unwrap().state == SamLinkState::Degraded {
+                            transition(&app, SamLinkState::Connected, None).await;
+                        }
+                    }
+                    Err(e) => {
+                        let attempts = {
+                            let mut health = health_cell().lock().unwrap();
+                            health.reconnect_attempts += 1;
+                            health.last_error = Some(e);
+                            health.reconnect_attempts
+                        };
+                        tokio::time::sleep(reconnect_backoff(attempts)).await;
+                    }
+                },
+            }
+        }
+    });
+}
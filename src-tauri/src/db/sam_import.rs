@@ -0,0 +1,961 @@
+/// `db/sam_import.rs` — import SAM Broadcaster settings
+///
+/// Categories and songs already come across through the SAM MySQL schema;
+/// the rest of a SAM station (cross-fading, GAP killer, clockwheels) lives in
+/// settings tables some installs keep in samdb and in SAM's configuration
+/// files. This module reads both — tables, XML exports or INI-style
+/// `key=value` files — into a flat key list, maps the settings it recognises
+/// onto this app's config types and reports what it could not place, so the
+/// operator can review a preview before anything is written.
+///
+/// Keys are matched by their last path segment, case- and punctuation-
+/// insensitively, against the names used in SAM's Cross-Fading and GAP
+/// killer dialogs. Clockwheels are taken from XML elements named like a
+/// clockwheel whose children carry a category and a selection rule; a
+/// clockwheel table contributes one such element per wheel, one row per slot.
+use std::collections::HashSet;
+use std::path::Path;
+
+use quick_xml::{events::Event, reader::Reader};
+use serde::{Deserialize, Serialize};
+use sqlx::{mysql::MySqlPool, Row};
+
+use crate::{
+    audio::crossfade::{CrossfadeConfig, CrossfadeMode, CrossfadeTriggerMode, FadeCurve},
+    scheduler::{
+        autodj::GapKillerConfig,
+        rotation::{
+            ClockwheelSelectionMethod, ClockwheelSlot, ClockwheelSlotKind, ClockwheelTemplate,
+        },
+    },
+};
+
+/// Unplaced keys listed in the preview; the rest are counted only.
+const MAX_UNMAPPED_LISTED: usize = 200;
+const CLOCKWHEEL_PREFIX: &str = "SAM: ";
+/// Rows read from any one samdb settings table.
+const MAX_TABLE_ROWS: i64 = 5000;
+
+// ── Preview ───────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MappedSetting {
+    /// Target field, e.g. `crossfade.fade_out_time_ms`
+    pub setting: String,
+    pub source_file: String,
+    pub source_key: String,
+    pub value: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SamImportPlan {
+    pub crossfade: Option<CrossfadeConfig>,
+    pub gap_killer: Option<GapKillerConfig>,
+    pub clockwheels: Vec<ClockwheelTemplate>,
+    pub mapped: Vec<MappedSetting>,
+    pub unmapped_keys: Vec<String>,
+    pub unmapped_count: usize,
+    pub warnings: Vec<String>,
+}
+
+/// Which parts of a plan to apply.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SamImportSelection {
+    pub crossfade: bool,
+    pub gap_killer: bool,
+    pub clockwheels: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SamImportResult {
+    pub crossfade: bool,
+    pub gap_killer: bool,
+    pub clockwheels_created: usize,
+    /// Existing templates with the same name that were overwritten
+    pub clockwheels_updated: usize,
+    pub warnings: Vec<String>,
+}
+
+// ── Parsed source ─────────────────────────────────────────────────────────────
+
+#[derive(Debug, Default)]
+struct Node {
+    name: String,
+    attrs: Vec<(String, String)>,
+    text: String,
+    children: Vec<Node>,
+}
+
+impl Node {
+    fn field(&self, aliases: &[&str]) -> Option<String> {
+        for alias in aliases {
+            if let Some((_, v)) = self.attrs.iter().find(|(k, _)| normalize(k) == *alias) {
+                return Some(v.clone());
+            }
+            if let Some(child) = self.children.iter().find(|c| normalize(&c.name) == *alias) {
+                return Some(child.text.clone());
+            }
+        }
+        None
+    }
+}
+
+/// One scalar setting from a source file, keyed by its element/section path.
+#[derive(Debug, Clone)]
+struct Entry {
+    file: String,
+    path: String,
+    key: String,
+    value: String,
+}
+
+fn normalize(s: &str) -> String {
+    s.chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
+fn parse_xml(text: &str) -> Result<Node, String> {
+    let mut reader = Reader::from_str(text);
+    reader.trim_text(true);
+    let mut stack = vec![Node::default()];
+
+    fn open(e: &quick_xml::events::BytesStart<'_>) -> Node {
+        Node {
+            name: String::from_utf8_lossy(e.local_name().as_ref()).to_string(),
+            attrs: e
+                .attributes()
+                .flatten()
+                .map(|a| {
+                    (
+                        String::from_utf8_lossy(a.key.local_name().as_ref()).to_string(),
+                        a.unescape_value()
+                            .map(|v| v.into_owned())
+                            .unwrap_or_default(),
+                    )
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(e)) => stack.push(open(&e)),
+            Ok(Event::Empty(e)) => {
+                let node = open(&e);
+                if let Some(parent) = stack.last_mut() {
+                    parent.children.push(node);
+                }
+            }
+            Ok(Event::End(_)) => {
+                if stack.len() > 1 {
+                    let node = stack.pop().unwrap_or_default();
+                    if let Some(parent) = stack.last_mut() {
+                        parent.children.push(node);
+                    }
+                }
+            }
+            Ok(Event::Text(t)) => {
+                if let (Some(node), Ok(text)) = (stack.last_mut(), t.unescape()) {
+                    node.text.push_str(text.trim());
+                }
+            }
+            Ok(Event::CData(t)) => {
+                if let Some(node) = stack.last_mut() {
+                    node.text
+                        .push_str(String::from_utf8_lossy(&t.into_inner()).trim());
+                }
+            }
+            Ok(Event::Eof) => break,
+            Ok(_) => {}
+            Err(e) => {
+                return Err(format!(
+                    "XML error at byte {}: {e}",
+                    reader.buffer_position()
+                ))
+            }
+        }
+    }
+    Ok(stack.into_iter().next().unwrap_or_default())
+}
+
+/// `[Section]` headers become parents of their `key=value` lines.
+fn parse_ini(text: &str) -> Node {
+    let mut root = Node::default();
+    let mut section: Option<Node> = None;
+    for line in text.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with(';') || line.starts_with('#') {
+            continue;
+        }
+        if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            if let Some(done) = section.take() {
+                root.children.push(done);
+            }
+            section = Some(Node {
+                name: name.trim().to_string(),
+                ..Default::default()
+            });
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let node = Node {
+            name: key.trim().to_string(),
+            text: value.trim().trim_matches('"').to_string(),
+            ..Default::default()
+        };
+        match section.as_mut() {
+            Some(s) => s.children.push(node),
+            None => root.children.push(node),
+        }
+    }
+    if let Some(done) = section {
+        root.children.push(done);
+    }
+    root
+}
+
+fn flatten(node: &Node, prefix: &str, file: &str, out: &mut Vec<Entry>) {
+    let path = if node.name.is_empty() {
+        prefix.to_string()
+    } else if prefix.is_empty() {
+        node.name.clone()
+    } else {
+        format!("{prefix}/{}", node.name)
+    };
+    for (key, value) in &node.attrs {
+        out.push(Entry {
+            file: file.to_string(),
+            path: format!("{path}@{key}"),
+            key: normalize(key),
+            value: value.clone(),
+        });
+    }
+    if node.children.is_empty() && !node.text.is_empty() {
+        out.push(Entry {
+            file: file.to_string(),
+            path: path.clone(),
+            key: normalize(&node.name),
+            value: node.text.clone(),
+        });
+    }
+    for child in &node.children {
+        flatten(child, &path, file, out);
+    }
+}
+
+// ── Value parsing ─────────────────────────────────────────────────────────────
+
+fn parse_bool(s: &str) -> Option<bool> {
+    match s.trim().to_ascii_lowercase().as_str() {
+        "1" | "true" | "yes" | "on" | "checked" | "enabled" => Some(true),
+        "0" | "false" | "no" | "off" | "unchecked" | "disabled" => Some(false),
+        _ => None,
+    }
+}
+
+/// Leading number of `s`, ignoring a trailing unit (`ms`, `s`, `dB`, `%`).
+fn parse_number(s: &str) -> Option<f64> {
+    let s = s.trim();
+    let end = s
+        .char_indices()
+        .find(|(i, c)| !(c.is_ascii_digit() || *c == '.' || (*i == 0 && (*c == '-' || *c == '+'))))
+        .map_or(s.len(), |(i, _)| i);
+    s[..end].parse().ok()
+}
+
+/// SAM stores fade times in milliseconds; values that only make sense as
+/// seconds (or carry an `s` unit) are scaled up.
+fn parse_duration_ms(s: &str) -> Option<u32> {
+    let n = parse_number(s)?;
+    let lower = s.trim().to_ascii_lowercase();
+    let seconds = (lower.ends_with('s') && !lower.ends_with("ms")) || n < 100.0;
+    let ms = if seconds { n * 1000.0 } else { n };
+    (ms >= 0.0).then_some(ms.round() as u32)
+}
+
+fn parse_curve(s: &str) -> Option<FadeCurve> {
+    let n = normalize(s);
+    Some(match n.as_str() {
+        "linear" => FadeCurve::Linear,
+        "exponential" | "exp" => FadeCurve::Exponential,
+        "scurve" | "s" | "sine" => FadeCurve::SCurve,
+        "logarithmic" | "log" => FadeCurve::Logarithmic,
+        "constantpower" | "equalpower" => FadeCurve::ConstantPower,
+        _ => return None,
+    })
+}
+
+fn parse_selection(s: &str) -> Option<ClockwheelSelectionMethod> {
+    use ClockwheelSelectionMethod::*;
+    let n = normalize(s);
+    Some(match n.as_str() {
+        "weighted" | "weightedrandom" => Weighted,
+        "priority" => Priority,
+        "random" => Random,
+        "mostrecentlyplayedsong" => MostRecentlyPlayedSong,
+        "leastrecentlyplayedsong" | "leastrecentlyplayed" => LeastRecentlyPlayedSong,
+        "mostrecentlyplayedartist" => MostRecentlyPlayedArtist,
+        "leastrecentlyplayedartist" => LeastRecentlyPlayedArtist,
+        "lemming" | "lemmingrandom" => Lemming,
+        _ => return None,
+    })
+}
+
+// ── Mapping ───────────────────────────────────────────────────────────────────
+
+struct Mapper<'a> {
+    entries: &'a [Entry],
+    used: HashSet<usize>,
+    mapped: Vec<MappedSetting>,
+    warnings: Vec<String>,
+}
+
+impl<'a> Mapper<'a> {
+    /// First entry whose key matches one of `aliases`, recorded as mapped
+    /// to `setting` once `parse` accepts it.
+    fn take<T>(
+        &mut self,
+        setting: &str,
+        aliases: &[&str],
+        parse: impl Fn(&str) -> Option<T>,
+    ) -> Option<T> {
+        for (i, entry) in self.entries.iter().enumerate() {
+            if !aliases.contains(&entry.key.as_str()) {
+                continue;
+            }
+            match parse(&entry.value) {
+                Some(v) => {
+                    self.used.insert(i);
+                    self.mapped.push(MappedSetting {
+                        setting: setting.to_string(),
+                        source_file: entry.file.clone(),
+                        source_key: entry.path.clone(),
+                        value: entry.value.clone(),
+                    });
+                    return Some(v);
+                }
+                None => {
+                    self.used.insert(i);
+                    self.warnings.push(format!(
+                        "{}: '{}' is not a valid value for {setting}",
+                        entry.path, entry.value
+                    ));
+                }
+            }
+        }
+        None
+    }
+}
+
+fn map_crossfade(m: &mut Mapper<'_>) -> Option<CrossfadeConfig> {
+    let mut cfg = CrossfadeConfig::default();
+    let mut any = false;
+
+    if let Some(v) = m.take(
+        "crossfade.fade_out_enabled",
+        &["fadeoutenabled", "fadeout", "usefadeout"],
+        parse_bool,
+    ) {
+        cfg.fade_out_enabled = v;
+        any = true;
+    }
+    if let Some(v) = m.take(
+        "crossfade.fade_out_time_ms",
+        &[
+            "fadeouttime",
+            "fadeouttimems",
+            "fadeoutms",
+            "fadeoutduration",
+        ],
+        parse_duration_ms,
+    ) {
+        cfg.fade_out_time_ms = v;
+        any = true;
+    }
+    if let Some(v) = m.take(
+        "crossfade.fade_out_level_pct",
+        &["fadeoutlevel", "fadeoutlevelpct", "fadeoutvolume"],
+        parse_number,
+    ) {
+        cfg.fade_out_level_pct = v.clamp(0.0, 100.0) as u8;
+        any = true;
+    }
+    if let Some(v) = m.take(
+        "crossfade.fade_out_curve",
+        &["fadeoutcurve", "fadeouttype", "fadeoutshape"],
+        parse_curve,
+    ) {
+        cfg.fade_out_curve = v;
+        any = true;
+    }
+    if let Some(v) = m.take(
+        "crossfade.fade_in_enabled",
+        &["fadeinenabled", "fadein", "usefadein"],
+        parse_bool,
+    ) {
+        cfg.fade_in_enabled = v;
+        any = true;
+    }
+    if let Some(v) = m.take(
+        "crossfade.fade_in_time_ms",
+        &["fadeintime", "fadeintimems", "fadeinms", "fadeinduration"],
+        parse_duration_ms,
+    ) {
+        cfg.fade_in_time_ms = v;
+        any = true;
+    }
+    if let Some(v) = m.take(
+        "crossfade.fade_in_level_pct",
+        &["fadeinlevel", "fadeinlevelpct", "fadeinvolume"],
+        parse_number,
+    ) {
+        cfg.fade_in_level_pct = v.clamp(0.0, 100.0) as u8;
+        any = true;
+    }
+    if let Some(v) = m.take(
+        "crossfade.fade_in_curve",
+        &["fadeincurve", "fadeintype", "fadeinshape"],
+        parse_curve,
+    ) {
+        cfg.fade_in_curve = v;
+        any = true;
+    }
+    if let Some((mode, trigger)) = m.take(
+        "crossfade.trigger_mode",
+        &["crossfademode", "xfademode", "crossfadetype", "crossfade"],
+        |s| match normalize(s).as_str() {
+            "auto" | "autodetect" | "smart" => {
+                Some((CrossfadeMode::Overlap, CrossfadeTriggerMode::AutoDetectDb))
+            }
+            "fixed" | "fixedtime" => {
+                Some((CrossfadeMode::Overlap, CrossfadeTriggerMode::FixedPointMs))
+            }
+            "none" | "off" | "disabled" => {
+                Some((CrossfadeMode::Instant, CrossfadeTriggerMode::Manual))
+            }
+            _ => None,
+        },
+    ) {
+        cfg.crossfade_mode = mode;
+        cfg.trigger_mode = trigger;
+        any = true;
+    }
+    if let Some(v) = m.take(
+        "crossfade.fixed_crossfade_point_ms",
+        &[
+            "fixedcrossfadetime",
+            "fixedtime",
+            "crossfadetime",
+            "xfadetime",
+        ],
+        parse_duration_ms,
+    ) {
+        cfg.fixed_crossfade_ms = v;
+        cfg.fixed_crossfade_point_ms = Some(v);
+        any = true;
+    }
+    if let Some(v) = m.take(
+        "crossfade.auto_detect_db",
+        &[
+            "autodetectlevel",
+            "autodetectdb",
+            "detectlevel",
+            "triggerlevel",
+        ],
+        parse_number,
+    ) {
+        cfg.auto_detect_db = v as f32;
+        any = true;
+    }
+    if let Some(v) = m.take(
+        "crossfade.min_fade_time_ms",
+        &["minfadetime", "minimumfadetime", "mincrossfadetime"],
+        parse_duration_ms,
+    ) {
+        cfg.min_fade_time_ms = v;
+        any = true;
+    }
+    if let Some(v) = m.take(
+        "crossfade.max_fade_time_ms",
+        &["maxfadetime", "maximumfadetime", "maxcrossfadetime"],
+        parse_duration_ms,
+    ) {
+        cfg.max_fade_time_ms = v;
+        any = true;
+    }
+    if let Some(v) = m.take(
+        "crossfade.skip_short_tracks_secs",
+        &["skipshorttracks", "shorttracklength", "minimumtracklength"],
+        parse_number,
+    ) {
+        cfg.skip_short_tracks_secs = (v > 0.0).then_some(v as u32);
+        any = true;
+    }
+    any.then_some(cfg)
+}
+
+fn map_gap_killer(m: &mut Mapper<'_>) -> Option<GapKillerConfig> {
+    let mut cfg = GapKillerConfig::default();
+    let mut any = false;
+    if let Some(v) = m.take(
+        "gap_killer.mode",
+        &["gapkiller", "gapkillermode", "gapkill"],
+        |s| match normalize(s).as_str() {
+            "off" | "0" | "disabled" | "none" => Some("off"),
+            "smart" | "1" | "normal" => Some("smart"),
+            "aggressive" | "2" => Some("aggressive"),
+            _ => None,
+        },
+    ) {
+        cfg.mode = v.to_string();
+        any = true;
+    }
+    if let Some(v) = m.take(
+        "gap_killer.threshold_db",
+        &[
+            "gapkillerlevel",
+            "gapkillerthreshold",
+            "silencelevel",
+            "silencethreshold",
+        ],
+        parse_number,
+    ) {
+        cfg.threshold_db = v as f32;
+        any = true;
+    }
+    if let Some(v) = m.take(
+        "gap_killer.min_silence_ms",
+        &[
+            "gapkillertime",
+            "minsilence",
+            "minsilencetime",
+            "silencetime",
+        ],
+        parse_duration_ms,
+    ) {
+        cfg.min_silence_ms = v;
+        any = true;
+    }
+    any.then_some(cfg)
+}
+
+fn is_clockwheel(name: &str) -> bool {
+    let n = normalize(name);
+    n.contains("clockwheel") || n == "clock" || n == "rotation" || n == "playlistrotation"
+}
+
+/// Collect clockwheels: elements named like a clockwheel whose children
+/// each name a category (or request / directory source).
+fn find_clockwheels(
+    node: &Node,
+    file: &str,
+    out: &mut Vec<ClockwheelTemplate>,
+    warnings: &mut Vec<String>,
+) {
+    if is_clockwheel(&node.name) && !node.children.is_empty() {
+        let name = node
+            .field(&["name", "title", "caption"])
+            .filter(|n| !n.trim().is_empty())
+            .unwrap_or_else(|| format!("Clockwheel {}", out.len() + 1));
+        let mut slots = Vec::new();
+        for (i, child) in node.children.iter().enumerate() {
+            let Some(source) = child.field(&["category", "categoryname", "cat", "source"]) else {
+                continue;
+            };
+            let kind = match normalize(&source).as_str() {
+                "request" | "requests" | "requestedsongs" => ClockwheelSlotKind::Request,
+                _ if child.field(&["directory", "folder", "path"]).is_some() => {
+                    ClockwheelSlotKind::Directory
+                }
+                _ => ClockwheelSlotKind::Category,
+            };
+            let target = match kind {
+                ClockwheelSlotKind::Directory => child
+                    .field(&["directory", "folder", "path"])
+                    .unwrap_or_default(),
+                ClockwheelSlotKind::Request => String::new(),
+                _ => source.trim().to_string(),
+            };
+            let rule = child.field(&["rule", "selection", "selectionmethod", "logic", "method"]);
+            let selection_method = match rule.as_deref() {
+                None => ClockwheelSelectionMethod::Weighted,
+                Some(r) => parse_selection(r).unwrap_or_else(|| {
+                    warnings.push(format!(
+                        "{file}: clockwheel '{name}' slot {}: unknown rule '{r}', using weighted",
+                        i + 1
+                    ));
+                    ClockwheelSelectionMethod::Weighted
+                }),
+            };
+            let enforce_rules = child
+                .field(&["enforcerules", "userules", "obeyrules"])
+                .and_then(|v| parse_bool(&v))
+                .unwrap_or(true);
+            slots.push(ClockwheelSlot {
+                id: format!("slot-{}", slots.len() + 1),
+                kind,
+                target,
+                selection_method,
+                enforce_rules,
+                ..ClockwheelSlot::default()
+            });
+        }
+        if !slots.is_empty() {
+            out.push(ClockwheelTemplate {
+                id: None,
+                name: format!("{CLOCKWHEEL_PREFIX}{}", name.trim()),
+                slots,
+            });
+            return;
+        }
+    }
+    for child in &node.children {
+        find_clockwheels(child, file, out, warnings);
+    }
+}
+
+// ── samdb tables ──────────────────────────────────────────────────────────────
+
+/// A settings table read from the SAM database, every value as text.
+#[derive(Debug, Clone, Default)]
+pub struct SamTable {
+    pub name: String,
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+}
+
+impl SamTable {
+    fn column(&self, aliases: &[&str]) -> Option<usize> {
+        aliases
+            .iter()
+            .find_map(|alias| self.columns.iter().position(|c| normalize(c) == *alias))
+    }
+}
+
+/// Tables that may hold cross-fading, GAP killer or clockwheel settings.
+fn is_settings_table(name: &str) -> bool {
+    let n = normalize(name);
+    [
+        "clockwheel",
+        "rotation",
+        "crossfade",
+        "xfade",
+        "gapkill",
+        "setting",
+        "config",
+        "option",
+    ]
+    .iter()
+    .any(|k| n.contains(k))
+}
+
+/// Read every settings-like table in the connected samdb schema.
+pub async fn read_sam_tables(pool: &MySqlPool) -> Result<Vec<SamTable>, sqlx::Error> {
+    let names: Vec<String> = sqlx::query_scalar(
+        "SELECT table_name FROM information_schema.tables \
+         WHERE table_schema = DATABASE() ORDER BY table_name",
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let mut tables = Vec::new();
+    for name in names.into_iter().filter(|n| is_settings_table(n)) {
+        let columns: Vec<String> = sqlx::query_scalar(
+            "SELECT column_name FROM information_schema.columns \
+             WHERE table_schema = DATABASE() AND table_name = ? ORDER BY ordinal_position",
+        )
+        .bind(&name)
+        .fetch_all(pool)
+        .await?;
+        if columns.is_empty() {
+            continue;
+        }
+        let select = columns
+            .iter()
+            .map(|c| format!("CAST(`{}` AS CHAR)", c.replace('`', "``")))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let sql = format!(
+            "SELECT {select} FROM `{}` LIMIT {MAX_TABLE_ROWS}",
+            name.replace('`', "``")
+        );
+        let rows = sqlx::query(&sql)
+            .fetch_all(pool)
+            .await?
+            .iter()
+            .map(|row| {
+                (0..columns.len())
+                    .map(|i| {
+                        row.try_get::<Option<String>, _>(i)
+                            .ok()
+                            .flatten()
+                            .unwrap_or_default()
+                    })
+                    .collect()
+            })
+            .collect();
+        tables.push(SamTable {
+            name,
+            columns,
+            rows,
+        });
+    }
+    Ok(tables)
+}
+
+/// Turn a table into the same shape the file parsers produce. Key/value
+/// tables become `key=value` children; clockwheel tables become one
+/// clockwheel element per wheel with a child per slot; anything else keeps
+/// its rows, so the columns still show up as unmapped keys.
+fn table_node(table: &SamTable) -> Node {
+    let row_node = |row: &Vec<String>| Node {
+        name: "row".to_string(),
+        attrs: table
+            .columns
+            .iter()
+            .cloned()
+            .zip(row.iter().cloned())
+            .collect(),
+        ..Default::default()
+    };
+    let children = if is_clockwheel(&table.name) {
+        clockwheel_nodes(table, row_node)
+    } else {
+        let key = table.column(&["name", "key", "setting", "option", "variable", "param"]);
+        let value = table.column(&["value", "val", "data", "settingvalue", "optionvalue"]);
+        match (key, value) {
+            (Some(key), Some(value)) => table
+                .rows
+                .iter()
+                .map(|row| Node {
+                    name: row[key].clone(),
+                    text: row[value].clone(),
+                    ..Default::default()
+                })
+                .collect(),
+            _ => table.rows.iter().map(row_node).collect(),
+        }
+    };
+    Node {
+        name: table.name.clone(),
+        children,
+        ..Default::default()
+    }
+}
+
+/// Group slot rows by wheel name, in slot order when the table has one.
+fn clockwheel_nodes(table: &SamTable, row_node: impl Fn(&Vec<String>) -> Node) -> Vec<Node> {
+    let wheel = table.column(&["clockwheel", "clockwheelname", "wheel", "wheelname", "name"]);
+    let order = table.column(&["position", "sortid", "itemindex", "slot", "seq", "sequence"]);
+    let mut rows: Vec<&Vec<String>> = table.rows.iter().collect();
+    if let Some(order) = order {
+        let position = |row: &Vec<String>| row[order].trim().parse::<f64>().unwrap_or(f64::MAX);
+        rows.sort_by(|a, b| position(a).total_cmp(&position(b)));
+    }
+
+    let mut wheels: Vec<(String, Node)> = Vec::new();
+    for row in rows {
+        let name = wheel.map(|w| row[w].trim().to_string()).unwrap_or_default();
+        let slot = row_node(row);
+        match wheels.iter_mut().find(|(n, _)| *n == name) {
+            Some((_, node)) => node.children.push(slot),
+            None => {
+                let attrs = if name.is_empty() {
+                    Vec::new()
+                } else {
+                    vec![("name".to_string(), name.clone())]
+                };
+                let node = Node {
+                    name: "clockwheel".to_string(),
+                    attrs,
+                    children: vec![slot],
+                    ..Default::default()
+                };
+                wheels.push((name, node));
+            }
+        }
+    }
+    wheels.into_iter().map(|(_, node)| node).collect()
+}
+
+/// Read and map the given SAM configuration files and samdb tables.
+pub fn plan_import(paths: &[String], tables: &[SamTable]) -> Result<SamImportPlan, String> {
+    let mut entries = Vec::new();
+    let mut clockwheels = Vec::new();
+    let mut warnings = Vec::new();
+
+    for path in paths {
+        let bytes =
+            std::fs::read(Path::new(path)).map_err(|e| format!("Cannot read {path}: {e}"))?;
+        let text = String::from_utf8_lossy(&bytes);
+        let text = text.trim_start_matches('\u{feff}');
+        let file = Path::new(path)
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| path.clone());
+        let root = if text.trim_start().starts_with('<') {
+            parse_xml(text).map_err(|e| format!("{file}: {e}"))?
+        } else {
+            parse_ini(text)
+        };
+        flatten(&root, "", &file, &mut entries);
+        find_clockwheels(&root, &file, &mut clockwheels, &mut warnings);
+    }
+    for table in tables {
+        let root = table_node(table);
+        let source = format!("samdb.{}", table.name);
+        flatten(&root, "", &source, &mut entries);
+        find_clockwheels(&root, &source, &mut clockwheels, &mut warnings);
+    }
+
+    let mut mapper = Mapper {
+        entries: &entries,
+        used: HashSet::new(),
+        mapped: Vec::new(),
+        warnings,
+    };
+    let crossfade = map_crossfade(&mut mapper);
+    let gap_killer = map_gap_killer(&mut mapper);
+
+    let unmapped: Vec<String> = entries
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| !mapper.used.contains(i))
+        .map(|(_, e)| format!("{}: {}", e.file, e.path))
+        .collect();
+    Ok(SamImportPlan {
+        crossfade,
+        gap_killer,
+        clockwheels,
+        mapped: mapper.mapped,
+        unmapped_count: unmapped.len(),
+        unmapped_keys: unmapped.into_iter().take(MAX_UNMAPPED_LISTED).collect(),
+        warnings: mapper.warnings,
+    })
+}
+
+/// Warn about clockwheel slots naming categories the SAM database lacks.
+pub fn check_categories(plan: &mut SamImportPlan, known: &[String]) {
+    let known: HashSet<String> = known.iter().map(|c| normalize(c)).collect();
+    let missing: Vec<String> = plan
+        .clockwheels
+        .iter()
+        .flat_map(|wheel| wheel.slots.iter().map(move |slot| (wheel, slot)))
+        .filter(|(_, slot)| {
+            slot.kind == ClockwheelSlotKind::Category && !known.contains(&normalize(&slot.target))
+        })
+        .map(|(wheel, slot)| {
+            format!(
+                "{}: category '{}' does not exist in the SAM database",
+                wheel.name, slot.target
+            )
+        })
+        .collect();
+    plan.warnings.extend(missing);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plan_from(name: &str, text: &str) -> SamImportPlan {
+        let path = std::env::temp_dir().join(format!("sam-import-{}-{name}", std::process::id()));
+        std::fs::write(&path, text).unwrap();
+        let plan = plan_import(&[path.to_string_lossy().to_string()], &[]);
+        let _ = std::fs::remove_file(&path);
+        plan.unwrap()
+    }
+
+    #[test]
+    fn maps_ini_crossfade_and_gap_killer() {
+        let plan = plan_from(
+            "sam.ini",
+            "[CrossFade]\nFadeOutTime=4000\nFade_In_Time=2.5s\nCrossfadeMode=Fixed\n\
+             MinFadeTime=1500\nSkipShortTracks=45\nUnrelated=1\n\
+             [Misc]\nGapKiller=Aggressive\nGapKillerLevel=-45 dB\n",
+        );
+        let cf = plan.crossfade.expect("crossfade mapped");
+        assert_eq!(cf.fade_out_time_ms, 4000);
+        assert_eq!(cf.fade_in_time_ms, 2500);
+        assert_eq!(cf.trigger_mode, CrossfadeTriggerMode::FixedPointMs);
+        assert_eq!(cf.min_fade_time_ms, 1500);
+        assert_eq!(cf.skip_short_tracks_secs, Some(45));
+
+        let gk = plan.gap_killer.expect("gap killer mapped");
+        assert_eq!(gk.mode, "aggressive");
+        assert_eq!(gk.threshold_db, -45.0);
+        assert_eq!(plan.unmapped_count, 1);
+    }
+
+    #[test]
+    fn reads_clockwheels_from_xml() {
+        let plan = plan_from(
+            "clock.xml",
+            r#"<?xml version="1.0"?>
+            <Config>
+              <Clockwheel Name="Daytime">
+                <Item Category="Power Hits" Rule="Least recently played song"/>
+                <Item><Category>Requests</Category></Item>
+                <Item Category="Recurrent" Rule="Shuffle"/>
+              </Clockwheel>
+            </Config>"#,
+        );
+        let wheel = &plan.clockwheels[0];
+        assert_eq!(wheel.name, "SAM: Daytime");
+        assert_eq!(wheel.slots.len(), 3);
+        assert_eq!(wheel.slots[0].target, "Power Hits");
+        assert_eq!(
+            wheel.slots[0].selection_method,
+            ClockwheelSelectionMethod::LeastRecentlyPlayedSong
+        );
+        assert_eq!(wheel.slots[1].kind, ClockwheelSlotKind::Request);
+        assert_eq!(plan.warnings.len(), 1);
+    }
+
+    #[test]
+    fn reads_settings_and_clockwheels_from_samdb_tables() {
+        let settings = SamTable {
+            name: "settings".to_string(),
+            columns: vec!["ID".to_string(), "Name".to_string(), "Value".to_string()],
+            rows: vec![
+                vec![
+                    "1".to_string(),
+                    "FadeOutTime".to_string(),
+                    "3000".to_string(),
+                ],
+                vec![
+                    "2".to_string(),
+                    "GapKiller".to_string(),
+                    "Smart".to_string(),
+                ],
+            ],
+        };
+        let wheel = SamTable {
+            name: "clockwheel".to_string(),
+            columns: vec![
+                "Name".to_string(),
+                "Position".to_string(),
+                "Category".to_string(),
+            ],
+            rows: vec![
+                vec!["Night".to_string(), "2".to_string(), "Chill".to_string()],
+                vec!["Night".to_string(), "1".to_string(), "Ambient".to_string()],
+            ],
+        };
+        let plan = plan_import(&[], &[settings, wheel]).unwrap();
+        assert_eq!(
+            plan.crossfade.expect("crossfade mapped").fade_out_time_ms,
+            3000
+        );
+        assert_eq!(plan.gap_killer.expect("gap killer mapped").mode, "smart");
+        assert_eq!(plan.clockwheels.len(), 1);
+        assert_eq!(plan.clockwheels[0].name, "SAM: Night");
+        assert_eq!(plan.clockwheels[0].slots[0].target, "Ambient");
+        assert_eq!(plan.mapped[0].source_file, "samdb.settings");
+    }
+}
@@ -0,0 +1,475 @@
+/// Write-behind outbox for SAM MySQL updates.
+///
+/// The AutoDJ loop used to write play history, play stats, weight changes and
+/// queue removals straight to SAM, so a slow or distant MySQL server stalled
+/// playout. Those writes are now appended to `sam_outbox` in the local
+/// database and applied by one background worker in insertion order.
+/// Auto-accepted listener requests reach the SAM queue the same way.
+///
+/// - While SAM is unreachable the head of the queue is retried with backoff
+///   and nothing behind it runs, so ordering survives outages and restarts.
+/// - A write SAM rejects (constraint, syntax, missing column) is retried a few
+///   times and then parked as failed so it can't hold up the rest.
+use std::collections::HashSet;
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use sqlx::{MySqlPool, Row, SqlitePool};
+use tauri::{AppHandle, Manager};
+use tokio::sync::Notify;
+
+use crate::{
+    analytics::event_logger::{log_event, EventCategory, LogLevel},
+    state::AppState,
+};
+
+/// Attempts before a write SAM rejects is parked.
+const MAX_REJECTED_ATTEMPTS: i64 = 5;
+/// A single write taking longer than this counts as a connection failure.
+const WRITE_TIMEOUT: Duration = Duration::from_secs(30);
+/// Re-check interval when idle or when SAM is not connected.
+const IDLE_POLL: Duration = Duration::from_secs(5);
+const BACKOFF_SECS: [u64; 6] = [1, 2, 5, 10, 30, 60];
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum SamWrite {
+    /// `historylist` row for a completed play
+    History {
+        song_id: i64,
+        listeners: i32,
+        /// Unix ms when the play ended
+        played_at: i64,
+    },
+    RemoveFromQueue {
+        queue_id: i64,
+    },
+    /// Accepted request queued at `position` (0 = head)
+    QueueRequest {
+        song_id: i64,
+        request_id: i64,
+        position: usize,
+    },
+    PlayStats {
+        song_id: i64,
+        listeners: i32,
+        request_origin: bool,
+    },
+    WeightDelta {
+        song_id: i64,
+        delta: f64,
+    },
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OutboxStatus {
+    pub pending: i64,
+    pub failed: i64,
+    /// Unix ms of the oldest pending write
+    pub oldest_pending_at: Option<i64>,
+    /// Attempts on the write at the head of the queue
+    pub head_attempts: i64,
+    pub last_error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FailedWrite {
+    pub id: i64,
+    pub write: Option<SamWrite>,
+    pub created_at: i64,
+    pub attempts: i64,
+    pub last_error: Option<String>,
+}
+
+fn wake() -> &'static Notify {
+    static WAKE: OnceLock<Notify> = OnceLock::new();
+    WAKE.get_or_init(Notify::new)
+}
+
+fn pending_removals() -> &'static Mutex<HashSet<i64>> {
+    static REMOVALS: OnceLock<Mutex<HashSet<i64>>> = OnceLock::new();
+    REMOVALS.get_or_init(|| Mutex::new(HashSet::new()))
+}
+
+/// True while a queue entry's removal is still waiting in the outbox, i.e.
+/// SAM may still list an entry that has already been played.
+pub fn removal_pending(queue_id: i64) -> bool {
+    pending_removals().lock().unwrap().contains(&queue_id)
+}
+
+fn now_ms() -> i64 {
+    chrono::Utc::now().timestamp_millis()
+}
+
+fn backoff(attempts: i64) -> Duration {
+    let idx = (attempts.max(1) - 1).min(BACKOFF_SECS.len() as i64 - 1) as usize;
+    Duration::from_secs(BACKOFF_SECS[idx])
+}
+
+/// Errors where SAM answered and refused the write. Anything else (I/O, pool
+/// timeout, closed pool) means it never got there and is retried forever.
+fn is_rejection(err: &sqlx::Error) -> bool {
+    matches!(
+        err,
+        sqlx::Error::Database(_) | sqlx::Error::ColumnNotFound(_) | sqlx::Error::Decode(_)
+    )
+}
+
+/// Queue `writes` in order. Returns once they are stored locally.
+pub async fn enqueue(pool: &SqlitePool, writes: &[SamWrite]) -> Result<(), sqlx::Error> {
+    if writes.is_empty() {
+        return Ok(());
+    }
+    let created_at = now_ms();
+    let mut tx = pool.begin().await?;
+    for write in writes {
+        let op_json =
+            serde_json::to_string(write).map_err(|e| sqlx::Error::Protocol(e.to_string()))?;
+        sqlx::query("INSERT INTO sam_outbox (op_json, created_at) VALUES (?, ?)")
+            .bind(op_json)
+            .bind(created_at)
+            .execute(&mut *tx)
+            .await?;
+    }
+    tx.commit().await?;
+
+    let mut removals = pending_removals().lock().unwrap();
+    for write in writes {
+        if let SamWrite::RemoveFromQueue { queue_id } = write {
+            removals.insert(*queue_id);
+        }
+    }
+    drop(removals);
+    wake().notify_one();
+    Ok(())
+}
+
+pub async fn status(pool: &SqlitePool) -> Result<OutboxStatus, sqlx::Error> {
+    let row = sqlx::query(
+        "SELECT
+             COALESCE(SUM(failed = 0), 0) AS pending,
+             COALESCE(SUM(failed = 1), 0) AS failed,
+             MIN(CASE WHEN failed = 0 THEN created_at END) AS oldest_pending_at
+         FROM sam_outbox",
+    )
+    .fetch_one(pool)
+    .await?;
+    let head = sqlx::query(
+        "SELECT attempts, last_error FROM sam_outbox WHERE failed = 0 ORDER BY id LIMIT 1",
+    )
+    .fetch_optional(pool)
+    .await?;
+    Ok(OutboxStatus {
+        pending: row.get("pending"),
+        failed: row.get("failed"),
+        oldest_pending_at: row.get("oldest_pending_at"),
+        head_attempts: head.as_ref().map_or(0, |r| r.get("attempts")),
+        last_error: head.and_then(|r| r.get("last_error")),
+    })
+}
+
+pub async fn list_failed(pool: &SqlitePool) -> Result<Vec<FailedWrite>, sqlx::Error> {
+    let rows = sqlx::query(
+        "SELECT id, op_json, created_at, attempts, last_error
+         FROM sam_outbox WHERE failed = 1 ORDER BY id",
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(rows
+        .into_iter()
+        .map(|r| FailedWrite {
+            id: r.get("id"),
+            write: serde_json::from_str(r.get::<&str, _>("op_json")).ok(),
+            created_at: r.get("created_at"),
+            attempts: r.get("attempts"),
+            last_error: r.get("last_error"),
+        })
+        .collect())
+}
+
+/// Put parked writes back in the queue (`ids = None` for all of them). They
+/// keep their original position, so they run before anything newer.
+pub async fn retry_failed(pool: &SqlitePool, ids: Option<&[i64]>) -> Result<u64, sqlx::Error> {
+    let mut qb = sqlx::QueryBuilder::<sqlx::Sqlite>::new(
+        "UPDATE sam_outbox SET failed = 0, attempts = 0, next_attempt_at = 0 WHERE failed = 1",
+    );
+    if let Some(ids) = ids {
+        if ids.is_empty() {
+            return Ok(0);
+        }
+        qb.push(" AND id IN (");
+        let mut sep = qb.separated(", ");
+        for id in ids {
+            sep.push_bind(*id);
+        }
+        qb.push(")");
+    }
+    let affected = qb.build().execute(pool).await?.rows_affected();
+    load_pending_removals(pool).await?;
+    wake().notify_one();
+    Ok(affected)
+}
+
+/// Drop parked writes (`ids = None` for all of them).
+pub async fn discard_failed(pool: &SqlitePool, ids: Option<&[i64]>) -> Result<u64, sqlx::Error> {
+    let mut qb = sqlx::QueryBuilder::<sqlx::Sqlite>::new("DELETE FROM sam_outbox WHERE failed = 1");
+    if let Some(ids) = ids {
+        if ids.is_empty() {
+            return Ok(0);
+        }
+        qb.push(" AND id IN (");
+        let mut sep = qb.separated(", ");
+        for id in ids {
+            sep.push_bind(*id);
+        }
+        qb.push(")");
+    }
+    Ok(qb.build().execute(pool).await?.rows_affected())
+}
+
+async fn apply(sam: &MySqlPool, write: &SamWrite) -> Result<(), sqlx::Error> {
+    match write {
+        SamWrite::History {
+            song_id,
+            listeners,
+            played_at,
+        } => {
+            // A song deleted from SAM since it played has nothing to record.
+            let Some(song) = super::sam::get_song(sam, *song_id).await? else {
+                return Ok(());
+            };
+            let secs_ago = (now_ms() - played_at) / 1000;
+            super::sam::add_to_history_played_ago(sam, &song, *listeners, secs_ago).await
+        }
+        SamWrite::RemoveFromQueue { queue_id } => {
+            super::sam::remove_from_queue(sam, *queue_id).await
+        }
+        SamWrite::QueueRequest {
+            song_id,
+            request_id,
+            position,
+        } => super::sam::insert_request_into_queue(sam, *song_id, *request_id, *position)
+            .await
+            .map(|_| ()),
+        SamWrite::PlayStats {
+            song_id,
+            listeners,
+            request_origin,
+        } => {
+            super::sam::update_songlist_play_stats(sam, *song_id, *listeners, *request_origin).await
+        }
+        SamWrite::WeightDelta { song_id, delta } => {
+            crate::scheduler::rotation::update_song_weight_by_delta(sam, *song_id, *delta).await
+        }
+    }
+}
+
+struct Head {
+    id: i64,
+    op_json: String,
+    attempts: i64,
+    next_attempt_at: i64,
+}
+
+async fn next_write(pool: &SqlitePool) -> Result<Option<Head>, sqlx::Error> {
+    let row = sqlx::query(
+        "SELECT id, op_json, attempts, next_attempt_at
+         FROM sam_outbox WHERE failed = 0 ORDER BY id LIMIT 1",
+    )
+    .fetch_optional(pool)
+    .await?;
+    Ok(row.map(|r| Head {
+        id: r.get("id"),
+        op_json: r.get("op_json"),
+        attempts: r.get("attempts"),
+        next_attempt_at: r.get("next_attempt_at"),
+    }))
+}
+
+async fn load_pending_removals(pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    let rows = sqlx::query("SELECT op_json FROM sam_outbox WHERE failed = 0")
+        .fetch_all(pool)
+        .await?;
+    let mut removals = pending_removals().lock().unwrap();
+    for row in rows {
+        if let Ok(SamWrite::RemoveFromQueue { queue_id }) =
+            serde_json::from_str(row.get::<&str, _>("op_json"))
+        {
+            removals.insert(queue_id);
+        }
+    }
+    Ok(())
+}
+
+fn forget_removal(write: Option<&SamWrite>) {
+    if let Some(SamWrite::RemoveFromQueue { queue_id }) = write {
+        pending_removals().lock().unwrap().remove(queue_id);
+    }
+}
+
+async fn park(pool: &SqlitePool, head: &Head, write: Option<&SamWrite>, error: &str) {
+    let attempts = head.attempts + 1;
+    let _ =
+        sqlx::query("UPDATE sam_outbox SET failed = 1, attempts = ?, last_error = ? WHERE id = ?")
+            .bind(attempts)
+            .bind(error)
+            .bind(head.id)
+            .execute(pool)
+            .await;
+    // A parked removal won't run until retried, so stop hiding the entry.
+    forget_removal(write);
+    let _ = log_event(
+        pool,
+        LogLevel::Error,
+        EventCategory::Database,
+        "sam_write_failed",
+        &format!("SAM rejected a queued write after {attempts} attempts: {error}"),
+        Some(serde_json::json!({
+            "outbox_id": head.id,
+            "op": serde_json::from_str::<serde_json::Value>(&head.op_json).ok(),
+            "attempts": attempts,
+            "error": error,
+        })),
+        None,
+        None,
+        None,
+    )
+    .await;
+}
+
+/// Drain the outbox in order for as long as the app runs.
+pub fn start(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let state = app.state::<AppState>();
+        let Some(local) = state.local_db.clone() else {
+            return;
+        };
+        if let Err(e) = load_pending_removals(&local).await {
+            log::warn!("SAM outbox: cannot read pending removals: {e}");
+        }
+
+        loop {
+            let head = match next_write(&local).await {
+                Ok(Some(head)) => head,
+                Ok(None) => {
+                    let _ = tokio::time::timeout(IDLE_POLL, wake().notified()).await;
+                    continue;
+                }
+                Err(e) => {
+                    log::warn!("SAM outbox: cannot read queue: {e}");
+                    tokio::time::sleep(IDLE_POLL).await;
+                    continue;
+                }
+            };
+            let wait_ms = head.next_attempt_at - now_ms();
+            if wait_ms > 0 {
+                tokio::time::sleep(Duration::from_millis(wait_ms as u64).min(IDLE_POLL)).await;
+                continue;
+            }
+            let sam = state.sam_db.read().await.clone();
+            let Some(sam) = sam else {
+                let _ = tokio::time::timeout(IDLE_POLL, wake().notified()).await;
+                continue;
+            };
+
+            let write = match serde_json::from_str::<SamWrite>(&head.op_json) {
+                Ok(write) => write,
+                Err(e) => {
+                    park(&local, &head, None, &format!("Unreadable entry: {e}")).await;
+                    continue;
+                }
+            };
+            let result = match tokio::time::timeout(WRITE_TIMEOUT, apply(&sam, &write)).await {
+                Ok(result) => result,
+                Err(_) => Err(sqlx::Error::PoolTimedOut),
+            };
+            match result {
+                Ok(()) => {
+                    if let Err(e) = sqlx::query("DELETE FROM sam_outbox WHERE id = ?")
+                        .bind(head.id)
+                        .execute(&local)
+                        .await
+                    {
+                        // Writes are idempotent enough that repeating this one
+                        // is better than skipping past it.
+                        log::warn!("SAM outbox: cannot remove applied write {}: {e}", head.id);
+                        tokio::time::sleep(IDLE_POLL).await;
+                        continue;
+                    }
+                    forget_removal(Some(&write));
+                }
+                Err(e) if is_rejection(&e) && head.attempts + 1 >= MAX_REJECTED_ATTEMPTS => {
+                    park(&local, &head, Some(&write), &e.to_string()).await;
+                }
+                Err(e) => {
+                    let attempts = head.attempts + 1;
+                    if attempts == 1 {
+                        log::warn!("SAM outbox: write {} failed, will retry: {e}", head.id);
+                    }
+                    let next_attempt_at = now_ms() + backoff(attempts).as_millis() as i64;
+                    let _ = sqlx::query(
+                        "UPDATE sam_outbox SET attempts = ?, last_error = ?, next_attempt_at = ?
+                         WHERE id = ?",
+                    )
+                    .bind(attempts)
+                    .bind(e.to_string())
+                    .bind(next_attempt_at)
+                    .bind(head.id)
+                    .execute(&local)
+                    .await;
+                }
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    #[tokio::test]
+    async fn enqueue_keeps_order_and_tracks_removals() {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        crate::db::migrations::run(&pool).await.unwrap();
+
+        let writes = [
+            SamWrite::RemoveFromQueue { queue_id: 42 },
+            SamWrite::History {
+                song_id: 7,
+                listeners: 12,
+                played_at: 1_700_000_000_000,
+            },
+            SamWrite::WeightDelta {
+                song_id: 7,
+                delta: -0.5,
+            },
+        ];
+        enqueue(&pool, &writes).await.unwrap();
+        assert!(removal_pending(42));
+
+        let head = next_write(&pool).await.unwrap().unwrap();
+        assert_eq!(
+            serde_json::from_str::<SamWrite>(&head.op_json).unwrap(),
+            writes[0]
+        );
+        let info = status(&pool).await.unwrap();
+        assert_eq!((info.pending, info.failed), (3, 0));
+
+        park(&pool, &head, Some(&writes[0]), "rejected").await;
+        assert!(!removal_pending(42));
+        let next = next_write(&pool).await.unwrap().unwrap();
+        assert!(next.op_json.contains("\"history\""));
+
+        assert_eq!(retry_failed(&pool, None).await.unwrap(), 1);
+        assert_eq!(next_write(&pool).await.unwrap().unwrap().id, head.id);
+
+        assert_eq!(backoff(1), Duration::from_secs(1));
+        assert_eq!(backoff(99), Duration::from_secs(60));
+    }
+}
@@ -0,0 +1,489 @@
+/// `db/sam_sync.rs` — two-way sync of song metadata with SAM's song list
+///
+/// The synced fields are title, artist, album, weight and (first) category.
+/// `sam_song_sync` keeps the last copy of each song's fields this app saw in
+/// SAM, with `modified_at` set whenever they changed:
+///
+/// - Edits made here go through [`save_edit`], which refuses to write when
+///   the song changed since the editor loaded it (`base_modified_at` no
+///   longer matches), then writes the fields back to `songlist` /
+///   `categorylist`.
+/// - [`start`] rescans the song list every few minutes; songs edited in SAM
+///   itself get a new `modified_at`, the SAM read caches are dropped, and the
+///   changes go out as a `sam_song_changes` event.
+///
+/// Songs first seen by a scan are stored with `modified_at = 0`, so a fresh
+/// library doesn't show up as one big change.
+use std::collections::HashMap;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use sqlx::{sqlite::SqliteRow, MySqlPool, Row, SqlitePool};
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::sync::Mutex;
+
+use super::sam::{self, SongUpdateFields};
+use super::sam_cache;
+use crate::state::AppState;
+
+const SCAN_INTERVAL: Duration = Duration::from_secs(300);
+const BATCH_SIZE: i64 = 1000;
+
+/// Serialises scans and edits so an edit can't land between a scan's read
+/// and its snapshot write.
+static SYNC: Mutex<()> = Mutex::const_new(());
+
+/// One song's synced fields as last seen in SAM.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SyncedSong {
+    pub song_id: i64,
+    pub title: String,
+    pub artist: String,
+    pub album: String,
+    pub weight: f64,
+    /// First category the song belongs to; empty when it has none
+    pub category: String,
+    /// Unix ms of the last change seen, 0 if unchanged since first seen
+    pub modified_at: i64,
+}
+
+/// An edit from this app. Fields left `None` are not touched.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct SongEdit {
+    /// `modified_at` of the copy the editor started from
+    pub base_modified_at: i64,
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    pub album: Option<String>,
+    pub weight: Option<f64>,
+    pub category: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SongChange {
+    pub song: SyncedSong,
+    /// Names of the fields that differ from the previous copy
+    pub fields: Vec<&'static str>,
+}
+
+/// Payload of the `sam_song_changes` event.
+#[derive(Debug, Clone, Serialize)]
+pub struct SongChanges {
+    /// `"sam"` for edits made in SAM, `"local"` for edits saved here
+    pub source: &'static str,
+    pub changes: Vec<SongChange>,
+    /// Songs that are gone from SAM's song list
+    pub removed: Vec<i64>,
+}
+
+#[derive(Debug)]
+pub enum SyncError {
+    NotFound,
+    /// The song changed since the editor loaded it; carries the current copy
+    Conflict(SyncedSong),
+    Sam(String),
+    Local(sqlx::Error),
+}
+
+impl From<sqlx::Error> for SyncError {
+    fn from(e: sqlx::Error) -> Self {
+        SyncError::Local(e)
+    }
+}
+
+fn now_ms() -> i64 {
+    chrono::Utc::now().timestamp_millis()
+}
+
+/// Fields of `new` that differ from `old`.
+pub fn changed_fields(old: &SyncedSong, new: &SyncedSong) -> Vec<&'static str> {
+    let mut fields = Vec::new();
+    if old.title != new.title {
+        fields.push("title");
+    }
+    if old.artist != new.artist {
+        fields.push("artist");
+    }
+    if old.album != new.album {
+        fields.push("album");
+    }
+    if (old.weight - new.weight).abs() > 1e-6 {
+        fields.push("weight");
+    }
+    if !old.category.eq_ignore_ascii_case(&new.category) {
+        fields.push("category");
+    }
+    fields
+}
+
+/// The next batch of songs after `after_id`, in `ID` order.
+async fn read_batch(sam: &MySqlPool, after_id: i64) -> Result<Vec<SyncedSong>, sqlx::Error> {
+    let rows = sqlx::query(
+        "SELECT ID, title, artist, album, weight FROM songlist WHERE ID > ? ORDER BY ID LIMIT ?",
+    )
+    .bind(after_id)
+    .bind(BATCH_SIZE)
+    .fetch_all(sam)
+    .await?;
+    let songs = rows
+        .iter()
+        .map(|r| SyncedSong {
+            song_id: r
+                .try_get::<i64, _>("ID")
+                .or_else(|_| r.try_get::<i32, _>("ID").map(|v| v as i64))
+                .unwrap_or(0),
+            title: r.try_get("title").unwrap_or_default(),
+            artist: r.try_get("artist").unwrap_or_default(),
+            album: r.try_get("album").unwrap_or_default(),
+            weight: r.try_get("weight").unwrap_or(1.0),
+            category: String::new(),
+            modified_at: 0,
+        })
+        .collect();
+    with_categories(sam, songs).await
+}
+
+async fn read_one(sam: &MySqlPool, song_id: i64) -> Result<SyncedSong, SyncError> {
+    let song = sam::get_song(sam, song_id)
+        .await
+        .map_err(|e| SyncError::Sam(e.to_string()))?
+        .ok_or(SyncError::NotFound)?;
+    let song = SyncedSong {
+        song_id: song.id,
+        title: song.title,
+        artist: song.artist,
+        album: song.album,
+        weight: song.weight,
+        category: String::new(),
+        modified_at: 0,
+    };
+    with_categories(sam, vec![song])
+        .await
+        .map_err(|e| SyncError::Sam(e.to_string()))?
+        .pop()
+        .ok_or(SyncError::NotFound)
+}
+
+async fn with_categories(
+    sam: &MySqlPool,
+    mut songs: Vec<SyncedSong>,
+) -> Result<Vec<SyncedSong>, sqlx::Error> {
+    let ids: Vec<i64> = songs.iter().map(|s| s.song_id).collect();
+    let mut categories = sam::get_song_category_names(sam, Some(&ids)).await?;
+    for song in &mut songs {
+        song.category = categories.remove(&song.song_id).unwrap_or_default();
+    }
+    Ok(songs)
+}
+
+fn row_to_synced(r: &SqliteRow) -> SyncedSong {
+    SyncedSong {
+        song_id: r.get("song_id"),
+        title: r.get("title"),
+        artist: r.get("artist"),
+        album: r.get("album"),
+        weight: r.get("weight"),
+        category: r.get("category"),
+        modified_at: r.get("modified_at"),
+    }
+}
+
+/// Stored copies of songs with `lo < song_id <= hi`.
+async fn stored(
+    local: &SqlitePool,
+    lo: i64,
+    hi: i64,
+) -> Result<HashMap<i64, SyncedSong>, sqlx::Error> {
+    let rows = sqlx::query(
+        "SELECT song_id, title, artist, album, weight, category, modified_at
+         FROM sam_song_sync WHERE song_id > ? AND song_id <= ?",
+    )
+    .bind(lo)
+    .bind(hi)
+    .fetch_all(local)
+    .await?;
+    Ok(rows
+        .iter()
+        .map(row_to_synced)
+        .map(|song| (song.song_id, song))
+        .collect())
+}
+
+async fn store(local: &SqlitePool, song: &SyncedSong) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"INSERT INTO sam_song_sync
+               (song_id, title, artist, album, weight, category, modified_at)
+           VALUES (?, ?, ?, ?, ?, ?, ?)
+           ON CONFLICT(song_id) DO UPDATE SET
+               title = excluded.title, artist = excluded.artist,
+               album = excluded.album, weight = excluded.weight,
+               category = excluded.category, modified_at = excluded.modified_at"#,
+    )
+    .bind(song.song_id)
+    .bind(&song.title)
+    .bind(&song.artist)
+    .bind(&song.album)
+    .bind(song.weight)
+    .bind(&song.category)
+    .bind(song.modified_at)
+    .execute(local)
+    .await?;
+    Ok(())
+}
+
+/// Compare SAM's copy of a song with the stored one and record it. Returns
+/// the change if there was one; a song seen for the first time isn't one.
+async fn reconcile(
+    local: &SqlitePool,
+    previous: Option<&SyncedSong>,
+    mut current: SyncedSong,
+    now: i64,
+) -> Result<Option<SongChange>, sqlx::Error> {
+    let Some(previous) = previous else {
+        current.modified_at = 0;
+        store(local, &current).await?;
+        return Ok(None);
+    };
+    let fields = changed_fields(previous, &current);
+    if fields.is_empty() {
+        return Ok(None);
+    }
+    current.modified_at = now;
+    store(local, &current).await?;
+    Ok(Some(SongChange {
+        song: current,
+        fields,
+    }))
+}
+
+/// A song's synced fields, refreshed from SAM. Editors pass the returned
+/// `modified_at` back as `base_modified_at`.
+pub async fn get_song(
+    app: &AppHandle,
+    sam: &MySqlPool,
+    local: &SqlitePool,
+    song_id: i64,
+) -> Result<SyncedSong, SyncError> {
+    let _sync = SYNC.lock().await;
+    refresh_one(app, sam, local, song_id).await
+}
+
+async fn refresh_one(
+    app: &AppHandle,
+    sam: &MySqlPool,
+    local: &SqlitePool,
+    song_id: i64,
+) -> Result<SyncedSong, SyncError> {
+    let current = read_one(sam, song_id).await?;
+    let previous = stored(local, song_id - 1, song_id).await?.remove(&song_id);
+    match reconcile(local, previous.as_ref(), current.clone(), now_ms()).await? {
+        Some(change) => {
+            let song = change.song.clone();
+            sam_cache::note_write();
+            emit(app, "sam", vec![change], Vec::new());
+            Ok(song)
+        }
+        None => Ok(previous.unwrap_or(current)),
+    }
+}
+
+/// Write an edit back to SAM unless the song changed since the editor
+/// loaded it.
+pub async fn save_edit(
+    app: &AppHandle,
+    sam: &MySqlPool,
+    local: &SqlitePool,
+    song_id: i64,
+    edit: SongEdit,
+) -> Result<SyncedSong, SyncError> {
+    let _sync = SYNC.lock().await;
+    let before = refresh_one(app, sam, local, song_id).await?;
+    if before.modified_at != edit.base_modified_at {
+        return Err(SyncError::Conflict(before));
+    }
+
+    let fields = SongUpdateFields {
+        title: edit.title,
+        artist: edit.artist,
+        album: edit.album,
+        weight: edit.weight,
+        ..Default::default()
+    };
+    sam::update_song(sam, song_id, fields)
+        .await
+        .map_err(|e| SyncError::Sam(e.to_string()))?;
+    if let Some(category) = &edit.category {
+        sam::set_song_category(sam, song_id, category)
+            .await
+            .map_err(SyncError::Sam)?;
+    }
+
+    let mut after = read_one(sam, song_id).await?;
+    let changed = changed_fields(&before, &after);
+    after.modified_at = if changed.is_empty() {
+        before.modified_at
+    } else {
+        now_ms()
+    };
+    store(local, &after).await?;
+    if !changed.is_empty() {
+        emit(
+            app,
+            "local",
+            vec![SongChange {
+                song: after.clone(),
+                fields: changed,
+            }],
+            Vec::new(),
+        );
+    }
+    Ok(after)
+}
+
+/// Songs whose synced fields changed after `since` (unix ms), oldest first.
+pub async fn changes_since(
+    local: &SqlitePool,
+    since: i64,
+    limit: i64,
+) -> Result<Vec<SyncedSong>, sqlx::Error> {
+    let rows = sqlx::query(
+        "SELECT song_id, title, artist, album, weight, category, modified_at
+         FROM sam_song_sync WHERE modified_at > ? ORDER BY modified_at LIMIT ?",
+    )
+    .bind(since)
+    .bind(limit)
+    .fetch_all(local)
+    .await?;
+    Ok(rows.iter().map(row_to_synced).collect())
+}
+
+fn emit(app: &AppHandle, source: &'static str, changes: Vec<SongChange>, removed: Vec<i64>) {
+    if changes.is_empty() && removed.is_empty() {
+        return;
+    }
+    let _ = app.emit(
+        "sam_song_changes",
+        SongChanges {
+            source,
+            changes,
+            removed,
+        },
+    );
+}
+
+/// Walk the whole song list once, recording and announcing SAM-side edits.
+async fn scan(app: &AppHandle, sam: &MySqlPool, local: &SqlitePool) -> Result<usize, String> {
+    let _sync = SYNC.lock().await;
+    let now = now_ms();
+    let mut seen = 0usize;
+    let mut last_id = 0i64;
+    loop {
+        let batch = read_batch(sam, last_id).await.map_err(|e| e.to_string())?;
+        let complete = (batch.len() as i64) < BATCH_SIZE;
+        // The final batch also owns every stored id past the end of SAM's list.
+        let hi = match batch.last() {
+            Some(last) if !complete => last.song_id,
+            _ => i64::MAX,
+        };
+        let mut previous = stored(local, last_id, hi)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        let mut changes = Vec::new();
+        for song in batch {
+            let before = previous.remove(&song.song_id);
+            if let Some(change) = reconcile(local, before.as_ref(), song, now)
+                .await
+                .map_err(|e| e.to_string())?
+            {
+                changes.push(change);
+            }
+            seen += 1;
+        }
+        let removed: Vec<i64> = previous.into_keys().collect();
+        for song_id in &removed {
+            sqlx::query("DELETE FROM sam_song_sync WHERE song_id = ?")
+                .bind(song_id)
+                .execute(local)
+                .await
+                .map_err(|e| e.to_string())?;
+        }
+        if !changes.is_empty() || !removed.is_empty() {
+            sam_cache::note_write();
+            emit(app, "sam", changes, removed);
+        }
+        if complete {
+            break;
+        }
+        last_id = hi;
+    }
+    Ok(seen)
+}
+
+/// Rescan SAM's song list for outside edits for as long as the app runs.
+pub fn start(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let state = app.state::<AppState>();
+        let Some(local) = state.local_db.clone() else {
+            return;
+        };
+        loop {
+            let sam = state.sam_db.read().await.clone();
+            if let Some(sam) = sam {
+                if let Err(e) = scan(&app, &sam, &local).await {
+                    log::warn!("SAM sync: scan failed: {e}");
+                }
+            }
+            tokio::time::sleep(SCAN_INTERVAL).await;
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    fn song(title: &str, weight: f64, category: &str) -> SyncedSong {
+        SyncedSong {
+            song_id: 7,
+            title: title.to_string(),
+            artist: "Artist".to_string(),
+            album: String::new(),
+            weight,
+            category: category.to_string(),
+            modified_at: 0,
+        }
+    }
+
+    #[tokio::test]
+    async fn first_sight_is_not_a_change_but_later_edits_are() {
+        let local = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .expect("in-memory sqlite pool");
+        crate::db::migrations::run(&local).await.expect("migrate");
+
+        let seeded = reconcile(&local, None, song("A", 50.0, "Pop"), 1_000)
+            .await
+            .expect("seed");
+        assert!(seeded.is_none());
+        let stored_song = stored(&local, 6, 7).await.expect("read").remove(&7);
+        assert_eq!(stored_song.as_ref().map(|s| s.modified_at), Some(0));
+
+        let same = reconcile(&local, stored_song.as_ref(), song("A", 50.0, "pop"), 2_000)
+            .await
+            .expect("unchanged");
+        assert!(same.is_none());
+
+        let change = reconcile(&local, stored_song.as_ref(), song("B", 60.0, "Pop"), 3_000)
+            .await
+            .expect("changed")
+            .expect("a change");
+        assert_eq!(change.fields, vec!["title", "weight"]);
+        assert_eq!(change.song.modified_at, 3_000);
+        assert_eq!(changes_since(&local, 0, 10).await.expect("feed").len(), 1);
+    }
+}
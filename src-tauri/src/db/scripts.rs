@@ -0,0 +1,157 @@
+/// `db/scripts.rs` — saved Lua scripts and their run history
+///
+/// The script engine keeps the working copy in memory; every edit is
+/// written here and the table is loaded back into the engine at startup.
+/// Each run (manual, event or schedule) is appended to `script_runs`; only
+/// the latest `RUNS_KEPT` per script are kept. Permissions and limits are
+/// stored as JSON; missing fields take their defaults.
+use serde::{Deserialize, Serialize};
+use sqlx::{Row, SqlitePool};
+
+use crate::scripting::{engine::Script, trigger::ScriptTrigger};
+
+/// Runs kept per script.
+const RUNS_KEPT: i64 = 200;
+
+/// One finished run of a script.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScriptRun {
+    pub id: i64,
+    pub script_id: i64,
+    /// Unix ms
+    pub started_at: i64,
+    pub duration_ms: i64,
+    /// What started the run: "manual", an event trigger type ("on_track_start")
+    /// or "schedule:<cron>"
+    pub trigger: String,
+    pub success: bool,
+    pub error: Option<String>,
+}
+
+pub async fn load_scripts(pool: &SqlitePool) -> Result<Vec<Script>, sqlx::Error> {
+    let rows = sqlx::query(
+        "SELECT id, name, description, content, enabled, trigger_type, triggers_json, \
+         permissions_json, limits_json, last_run_at, last_error FROM scripts ORDER BY id",
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(rows
+        .into_iter()
+        .map(|r| Script {
+            id: r.get("id"),
+            name: r.get("name"),
+            description: r.get("description"),
+            content: r.get("content"),
+            enabled: r.get::<i64, _>("enabled") != 0,
+            trigger_type: r.get("trigger_type"),
+            triggers: serde_json::from_str::<Vec<ScriptTrigger>>(r.get("triggers_json"))
+                .unwrap_or_default(),
+            permissions: serde_json::from_str(r.get("permissions_json")).unwrap_or_default(),
+            limits: serde_json::from_str(r.get("limits_json")).unwrap_or_default(),
+            last_run_at: r.get("last_run_at"),
+            last_error: r.get("last_error"),
+        })
+        .collect())
+}
+
+pub async fn save_script(pool: &SqlitePool, script: &Script) -> Result<(), sqlx::Error> {
+    let triggers = serde_json::to_string(&script.triggers).unwrap_or_else(|_| "[]".into());
+    let permissions = serde_json::to_string(&script.permissions).unwrap_or_else(|_| "{}".into());
+    let limits = serde_json::to_string(&script.limits).unwrap_or_else(|_| "{}".into());
+    sqlx::query(
+        "INSERT INTO scripts (id, name, description, content, enabled, trigger_type, triggers_json, permissions_json, limits_json, last_run_at, last_error) \
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?) \
+         ON CONFLICT(id) DO UPDATE SET name = excluded.name, description = excluded.description, \
+         content = excluded.content, enabled = excluded.enabled, trigger_type = excluded.trigger_type, \
+         triggers_json = excluded.triggers_json, permissions_json = excluded.permissions_json, \
+         limits_json = excluded.limits_json",
+    )
+    .bind(script.id)
+    .bind(&script.name)
+    .bind(&script.description)
+    .bind(&script.content)
+    .bind(script.enabled as i64)
+    .bind(&script.trigger_type)
+    .bind(triggers)
+    .bind(permissions)
+    .bind(limits)
+    .bind(script.last_run_at)
+    .bind(&script.last_error)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+pub async fn delete_script(pool: &SqlitePool, id: i64) -> Result<(), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    sqlx::query("DELETE FROM script_runs WHERE script_id = ?")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query("DELETE FROM scripts WHERE id = ?")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await
+}
+
+/// Append a run, update the script's last run/error and drop old runs.
+pub async fn record_run(pool: &SqlitePool, run: &ScriptRun) -> Result<(), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    sqlx::query(
+        "INSERT INTO script_runs (script_id, started_at, duration_ms, trigger, success, error) \
+         VALUES (?, ?, ?, ?, ?, ?)",
+    )
+    .bind(run.script_id)
+    .bind(run.started_at)
+    .bind(run.duration_ms)
+    .bind(&run.trigger)
+    .bind(run.success as i64)
+    .bind(&run.error)
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query("UPDATE scripts SET last_run_at = ?, last_error = ? WHERE id = ?")
+        .bind(run.started_at / 1000)
+        .bind(&run.error)
+        .bind(run.script_id)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query(
+        "DELETE FROM script_runs WHERE script_id = ? AND id NOT IN \
+         (SELECT id FROM script_runs WHERE script_id = ? ORDER BY id DESC LIMIT ?)",
+    )
+    .bind(run.script_id)
+    .bind(run.script_id)
+    .bind(RUNS_KEPT)
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await
+}
+
+/// Latest runs of a script, newest first.
+pub async fn get_runs(
+    pool: &SqlitePool,
+    script_id: i64,
+    limit: i64,
+) -> Result<Vec<ScriptRun>, sqlx::Error> {
+    let rows = sqlx::query(
+        "SELECT id, script_id, started_at, duration_ms, trigger, success, error \
+         FROM script_runs WHERE script_id = ? ORDER BY id DESC LIMIT ?",
+    )
+    .bind(script_id)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows
+        .into_iter()
+        .map(|r| ScriptRun {
+            id: r.get("id"),
+            script_id: r.get("script_id"),
+            started_at: r.get("started_at"),
+            duration_ms: r.get("duration_ms"),
+            trigger: r.get("trigger"),
+            success: r.get::<i64, _>("success") != 0,
+            error: r.get("error"),
+        })
+        .collect())
+}
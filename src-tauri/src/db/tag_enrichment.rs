@@ -0,0 +1,829 @@
+/// `db/tag_enrichment.rs` — fill in missing tags from MusicBrainz / Discogs
+///
+/// Optional and off by default. A scan picks songs in SAM's song list that
+/// are missing an album, year or ISRC and looks them up:
+///
+/// - MusicBrainz by ISRC when the song has one, otherwise by artist + title
+///   (recording search, matches below `min_score` are ignored)
+/// - Discogs by artist + title, when a personal access token is configured
+///   and MusicBrainz left a field empty
+///
+/// Nothing is written during a scan. Each value found for an empty field is
+/// stored in `tag_proposals` for review; accepted proposals are written to
+/// the song list in one batch per song, after checking the field is still
+/// what it was when the proposal was made. SAM's song list is the library
+/// here, so there is no separate local copy to update.
+use std::collections::{BTreeMap, HashSet};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::{MySqlPool, Row, SqlitePool};
+use tauri::{AppHandle, Emitter};
+
+use super::sam::{self, SamSong, SongUpdateFields};
+use crate::audio::analyzer::artwork::{lucene_quote, musicbrainz_throttle, USER_AGENT};
+
+const BATCH_SIZE: i64 = 500;
+/// Songs looked up without result are left alone this long.
+const RECHECK_SECS: i64 = 30 * 24 * 3600;
+/// Discogs allows 60 authenticated requests a minute.
+const DISCOGS_INTERVAL: Duration = Duration::from_millis(1100);
+
+static RUNNING: AtomicBool = AtomicBool::new(false);
+static CANCEL: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TagEnrichmentConfig {
+    pub enabled: bool,
+    pub musicbrainz: bool,
+    /// Discogs personal access token; empty = Discogs is not asked
+    pub discogs_token: String,
+    /// Lowest MusicBrainz search score (0–100) accepted as a match
+    pub min_score: i64,
+}
+
+impl Default for TagEnrichmentConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            musicbrainz: true,
+            discogs_token: String::new(),
+            min_score: 90,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TagEnrichmentOptions {
+    /// Look up just these songs (checked recently or not)
+    pub song_ids: Option<Vec<i64>>,
+    /// Most songs to look up in one scan
+    pub limit: i64,
+}
+
+impl Default for TagEnrichmentOptions {
+    fn default() -> Self {
+        Self {
+            song_ids: None,
+            limit: 200,
+        }
+    }
+}
+
+/// Emitted as `tag_enrichment_progress`; the last one has `done` set.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct TagEnrichmentProgress {
+    pub total: i64,
+    pub processed: i64,
+    pub proposed: i64,
+    /// Songs no source had anything for
+    pub not_found: i64,
+    pub failed: i64,
+    pub cancelled: bool,
+    pub done: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProposalStatus {
+    Pending,
+    Applied,
+    Rejected,
+    /// The field changed before the proposal was applied
+    Stale,
+}
+
+impl ProposalStatus {
+    fn as_str(self) -> &'static str {
+        match self {
+            ProposalStatus::Pending => "pending",
+            ProposalStatus::Applied => "applied",
+            ProposalStatus::Rejected => "rejected",
+            ProposalStatus::Stale => "stale",
+        }
+    }
+
+    fn parse(s: &str) -> Self {
+        match s {
+            "applied" => ProposalStatus::Applied,
+            "rejected" => ProposalStatus::Rejected,
+            "stale" => ProposalStatus::Stale,
+            _ => ProposalStatus::Pending,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TagProposal {
+    pub id: i64,
+    pub song_id: i64,
+    pub artist: String,
+    pub title: String,
+    /// `album`, `albumyear` or `isrc`
+    pub field: String,
+    pub current: String,
+    pub proposed: String,
+    /// `musicbrainz` or `discogs`
+    pub source: String,
+    pub score: i64,
+    pub status: ProposalStatus,
+    pub created_at: i64,
+}
+
+/// What one source found for a song.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Candidate {
+    pub source: &'static str,
+    pub score: i64,
+    pub album: Option<String>,
+    pub year: Option<String>,
+    pub isrc: Option<String>,
+}
+
+fn now_secs() -> i64 {
+    chrono::Utc::now().timestamp()
+}
+
+fn blank(s: &str) -> bool {
+    s.trim().is_empty()
+}
+
+fn missing_year(year: &str) -> bool {
+    let year = year.trim();
+    !(year.len() == 4 && year.bytes().all(|b| b.is_ascii_digit()) && year != "0000")
+}
+
+/// Whether a song lacks anything a lookup could fill in.
+pub fn needs_enrichment(song: &SamSong) -> bool {
+    !blank(&song.artist)
+        && !blank(&song.title)
+        && (blank(&song.album) || missing_year(&song.albumyear) || blank(&song.isrc))
+}
+
+/// `(field, current, proposed)` for each empty field the candidate fills.
+pub fn proposals_for(song: &SamSong, candidate: &Candidate) -> Vec<(&'static str, String, String)> {
+    let mut out = Vec::new();
+    if let Some(album) = candidate.album.as_ref().filter(|a| !blank(a)) {
+        if blank(&song.album) {
+            out.push(("album", song.album.clone(), album.trim().to_string()));
+        }
+    }
+    if let Some(year) = candidate.year.as_ref().filter(|y| !missing_year(y)) {
+        if missing_year(&song.albumyear) {
+            out.push(("albumyear", song.albumyear.clone(), year.trim().to_string()));
+        }
+    }
+    if let Some(isrc) = candidate.isrc.as_ref().filter(|i| !blank(i)) {
+        if blank(&song.isrc) {
+            out.push(("isrc", song.isrc.clone(), isrc.trim().to_uppercase()));
+        }
+    }
+    out
+}
+
+/// Title and year of the release a recording most likely came from: the
+/// earliest official album, else the earliest release of any kind.
+pub fn best_release(releases: &[Value]) -> Option<(String, Option<String>)> {
+    let dated = |r: &&Value| {
+        r.get("date")
+            .and_then(|d| d.as_str())
+            .filter(|d| d.len() >= 4)
+            .map(|d| d[..4].to_string())
+    };
+    let is_album = |r: &&Value| {
+        r.get("status").and_then(|s| s.as_str()) == Some("Official")
+            && r.pointer("/release-group/primary-type")
+                .and_then(|t| t.as_str())
+                == Some("Album")
+    };
+    let earliest = |only_albums: bool| {
+        releases
+            .iter()
+            .filter(|r| !only_albums || is_album(r))
+            .min_by_key(|r| dated(r).unwrap_or_else(|| "9999".to_string()))
+    };
+    let release = earliest(true).or_else(|| earliest(false))?;
+    let title = release.get("title")?.as_str()?.to_string();
+    Some((title, dated(&release)))
+}
+
+// ── Lookups ───────────────────────────────────────────────────────────────────
+
+fn http_client() -> Option<reqwest::Client> {
+    reqwest::Client::builder()
+        .user_agent(USER_AGENT)
+        .timeout(Duration::from_secs(15))
+        .build()
+        .ok()
+}
+
+async fn get_json(
+    client: &reqwest::Client,
+    url: &str,
+    query: &[(&str, &str)],
+) -> Result<Option<Value>, String> {
+    let resp = client
+        .get(url)
+        .query(query)
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    if resp.status() == reqwest::StatusCode::NOT_FOUND {
+        return Ok(None);
+    }
+    if !resp.status().is_success() {
+        return Err(format!("{url} answered {}", resp.status()));
+    }
+    resp.json().await.map(Some).map_err(|e| e.to_string())
+}
+
+async fn musicbrainz(
+    client: &reqwest::Client,
+    song: &SamSong,
+    min_score: i64,
+) -> Result<Option<Candidate>, String> {
+    musicbrainz_throttle().await;
+    if !blank(&song.isrc) {
+        let url = format!(
+            "https://musicbrainz.org/ws/2/isrc/{}",
+            urlencoding::encode(song.isrc.trim())
+        );
+        let body = get_json(client, &url, &[("inc", "releases"), ("fmt", "json")]).await?;
+        let releases: Vec<Value> = body
+            .as_ref()
+            .and_then(|b| b.get("recordings"))
+            .and_then(|r| r.as_array())
+            .into_iter()
+            .flatten()
+            .filter_map(|r| r.get("releases").and_then(|r| r.as_array()))
+            .flatten()
+            .cloned()
+            .collect();
+        return Ok(best_release(&releases).map(|(album, year)| Candidate {
+            source: "musicbrainz",
+            score: 100,
+            album: Some(album),
+            year,
+            isrc: None,
+        }));
+    }
+
+    let query = format!(
+        "recording:{} AND artist:{}",
+        lucene_quote(song.title.trim()),
+        lucene_quote(song.artist.trim())
+    );
+    let Some(body) = get_json(
+        client,
+        "https://musicbrainz.org/ws/2/recording/",
+        &[("query", query.as_str()), ("fmt", "json"), ("limit", "5")],
+    )
+    .await?
+    else {
+        return Ok(None);
+    };
+    let Some(recording) = body
+        .get("recordings")
+        .and_then(|r| r.as_array())
+        .and_then(|r| r.first())
+    else {
+        return Ok(None);
+    };
+    let score = recording.get("score").and_then(|s| s.as_i64()).unwrap_or(0);
+    if score < min_score {
+        return Ok(None);
+    }
+    let releases = recording
+        .get("releases")
+        .and_then(|r| r.as_array())
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+    let (album, year) = best_release(releases).unzip();
+    Ok(Some(Candidate {
+        source: "musicbrainz",
+        score,
+        album,
+        year: year.flatten(),
+        isrc: recording
+            .get("isrcs")
+            .and_then(|i| i.as_array())
+            .and_then(|i| i.first())
+            .and_then(|i| i.as_str())
+            .map(str::to_string),
+    }))
+}
+
+async fn discogs_throttle() {
+    static LAST: tokio::sync::Mutex<Option<Instant>> = tokio::sync::Mutex::const_new(None);
+    let mut last = LAST.lock().await;
+    if let Some(at) = *last {
+        let since = at.elapsed();
+        if since < DISCOGS_INTERVAL {
+            tokio::time::sleep(DISCOGS_INTERVAL - since).await;
+        }
+    }
+    *last = Some(Instant::now());
+}
+
+async fn discogs(
+    client: &reqwest::Client,
+    song: &SamSong,
+    token: &str,
+) -> Result<Option<Candidate>, String> {
+    discogs_throttle().await;
+    let Some(body) = get_json(
+        client,
+        "https://api.discogs.com/database/search",
+        &[
+            ("type", "release"),
+            ("artist", song.artist.trim()),
+            ("track", song.title.trim()),
+            ("per_page", "1"),
+            ("token", token),
+        ],
+    )
+    .await?
+    else {
+        return Ok(None);
+    };
+    let Some(result) = body
+        .get("results")
+        .and_then(|r| r.as_array())
+        .and_then(|r| r.first())
+    else {
+        return Ok(None);
+    };
+    // Release titles come back as "Artist - Album".
+    let album = result.get("title").and_then(|t| t.as_str()).map(|t| {
+        t.split_once(" - ")
+            .map_or(t, |(_, album)| album)
+            .to_string()
+    });
+    let year = result.get("year").and_then(|y| match y {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    });
+    Ok(Some(Candidate {
+        source: "discogs",
+        score: 0,
+        album,
+        year,
+        isrc: None,
+    }))
+}
+
+// ── Config ────────────────────────────────────────────────────────────────────
+
+pub async fn get_config(pool: &SqlitePool) -> Result<TagEnrichmentConfig, sqlx::Error> {
+    let row: Option<String> =
+        sqlx::query_scalar("SELECT config_json FROM tag_enrichment_config WHERE id = 1")
+            .fetch_optional(pool)
+            .await?;
+    Ok(row
+        .and_then(|j| serde_json::from_str(&j).ok())
+        .unwrap_or_default())
+}
+
+pub async fn save_config(
+    pool: &SqlitePool,
+    config: &TagEnrichmentConfig,
+) -> Result<(), sqlx::Error> {
+    let json = serde_json::to_string(config).unwrap_or_else(|_| "{}".to_string());
+    sqlx::query(
+        "INSERT INTO tag_enrichment_config (id, config_json, updated_at) \
+         VALUES (1, ?, strftime('%s','now')) \
+         ON CONFLICT(id) DO UPDATE SET config_json = excluded.config_json, \
+         updated_at = excluded.updated_at",
+    )
+    .bind(json)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+// ── Scan ──────────────────────────────────────────────────────────────────────
+
+pub fn is_running() -> bool {
+    RUNNING.load(Ordering::Relaxed)
+}
+
+/// Stop a running scan after the song it is looking up.
+pub fn cancel() {
+    CANCEL.store(true, Ordering::Relaxed);
+}
+
+/// Songs to look up: the ones asked for, or the first `limit` poorly tagged
+/// songs not checked in the last month.
+async fn pick_songs(
+    sam: &MySqlPool,
+    local: &SqlitePool,
+    options: &TagEnrichmentOptions,
+) -> Result<Vec<SamSong>, String> {
+    if let Some(ids) = &options.song_ids {
+        return sam::get_songs_by_ids(sam, ids)
+            .await
+            .map_err(|e| e.to_string());
+    }
+    let recent: HashSet<i64> =
+        sqlx::query_scalar("SELECT song_id FROM tag_enrichment_checked WHERE checked_at > ?")
+            .bind(now_secs() - RECHECK_SECS)
+            .fetch_all(local)
+            .await
+            .map_err(|e| e.to_string())?
+            .into_iter()
+            .collect();
+    let limit = options.limit.max(1) as usize;
+    let mut picked = Vec::new();
+    let mut last_id = 0i64;
+    while picked.len() < limit {
+        let ids: Vec<i64> = sqlx::query_scalar(
+            "SELECT ID FROM songlist WHERE ID > ? AND songtype = 'S' ORDER BY ID LIMIT ?",
+        )
+        .bind(last_id)
+        .bind(BATCH_SIZE)
+        .fetch_all(sam)
+        .await
+        .map_err(|e| e.to_string())?;
+        let Some(&last) = ids.last() else {
+            break;
+        };
+        last_id = last;
+        let ids: Vec<i64> = ids.into_iter().filter(|id| !recent.contains(id)).collect();
+        let mut songs = sam::get_songs_by_ids(sam, &ids)
+            .await
+            .map_err(|e| e.to_string())?;
+        songs.sort_by_key(|s| s.id);
+        picked.extend(songs.into_iter().filter(needs_enrichment));
+    }
+    picked.truncate(limit);
+    Ok(picked)
+}
+
+async fn store_proposals(
+    local: &SqlitePool,
+    song: &SamSong,
+    candidate: &Candidate,
+) -> Result<i64, sqlx::Error> {
+    let mut stored = 0;
+    for (field, current, proposed) in proposals_for(song, candidate) {
+        let result = sqlx::query(
+            r#"INSERT OR IGNORE INTO tag_proposals
+                   (song_id, artist, title, field, current, proposed, source, score, created_at)
+               VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)"#,
+        )
+        .bind(song.id)
+        .bind(&song.artist)
+        .bind(&song.title)
+        .bind(field)
+        .bind(current)
+        .bind(proposed)
+        .bind(candidate.source)
+        .bind(candidate.score)
+        .bind(now_secs())
+        .execute(local)
+        .await?;
+        stored += result.rows_affected() as i64;
+    }
+    Ok(stored)
+}
+
+/// Look up poorly tagged songs and store what was found for review.
+pub async fn scan(
+    app: &AppHandle,
+    sam: &MySqlPool,
+    local: &SqlitePool,
+    options: TagEnrichmentOptions,
+) -> Result<TagEnrichmentProgress, String> {
+    let config = get_config(local).await.map_err(|e| e.to_string())?;
+    if !config.enabled {
+        return Err("Tag enrichment is turned off".to_string());
+    }
+    if !config.musicbrainz && blank(&config.discogs_token) {
+        return Err("No lookup source is enabled".to_string());
+    }
+    if RUNNING.swap(true, Ordering::AcqRel) {
+        return Err("A tag lookup is already running".to_string());
+    }
+    CANCEL.store(false, Ordering::Relaxed);
+    let result = run(app, sam, local, &config, options).await;
+    RUNNING.store(false, Ordering::Release);
+    result
+}
+
+async fn run(
+    app: &AppHandle,
+    sam: &MySqlPool,
+    local: &SqlitePool,
+    config: &TagEnrichmentConfig,
+    options: TagEnrichmentOptions,
+) -> Result<TagEnrichmentProgress, String> {
+    let client = http_client().ok_or_else(|| "Cannot create HTTP client".to_string())?;
+    let songs = pick_songs(sam, local, &options).await?;
+    let mut progress = TagEnrichmentProgress {
+        total: songs.len() as i64,
+        ..Default::default()
+    };
+    let _ = app.emit("tag_enrichment_progress", &progress);
+
+    for song in &songs {
+        if CANCEL.load(Ordering::Relaxed) {
+            progress.cancelled = true;
+            break;
+        }
+        let mut found = 0;
+        let mut failed = false;
+        let mut filled = song.clone();
+        if config.musicbrainz {
+            match musicbrainz(&client, song, config.min_score).await {
+                Ok(Some(candidate)) => {
+                    found += store_proposals(local, song, &candidate)
+                        .await
+                        .map_err(|e| e.to_string())?;
+                    // Only ask Discogs for what MusicBrainz didn't have.
+                    for (field, _, value) in proposals_for(song, &candidate) {
+                        match field {
+                            "album" => filled.album = value,
+                            "albumyear" => filled.albumyear = value,
+                            _ => {}
+                        }
+                    }
+                }
+                Ok(None) => {}
+                Err(e) => {
+                    log::debug!("MusicBrainz lookup failed (song_id={}): {e}", song.id);
+                    failed = true;
+                }
+            }
+        }
+        let wants_discogs = blank(&filled.album) || missing_year(&filled.albumyear);
+        if wants_discogs && !blank(&config.discogs_token) {
+            match discogs(&client, &filled, config.discogs_token.trim()).await {
+                Ok(Some(candidate)) => {
+                    found += store_proposals(local, &filled, &candidate)
+                        .await
+                        .map_err(|e| e.to_string())?;
+                }
+                Ok(None) => {}
+                Err(e) => {
+                    log::debug!("Discogs lookup failed (song_id={}): {e}", song.id);
+                    failed = true;
+                }
+            }
+        }
+
+        progress.processed += 1;
+        progress.proposed += found;
+        if failed {
+            // Try again next scan.
+            progress.failed += 1;
+        } else {
+            if found == 0 {
+                progress.not_found += 1;
+            }
+            sqlx::query(
+                "INSERT INTO tag_enrichment_checked (song_id, checked_at) VALUES (?, ?) \
+                 ON CONFLICT(song_id) DO UPDATE SET checked_at = excluded.checked_at",
+            )
+            .bind(song.id)
+            .bind(now_secs())
+            .execute(local)
+            .await
+            .map_err(|e| e.to_string())?;
+        }
+        if progress.processed % 10 == 0 {
+            let _ = app.emit("tag_enrichment_progress", &progress);
+        }
+    }
+
+    progress.done = true;
+    let _ = app.emit("tag_enrichment_progress", &progress);
+    log::info!(
+        "Tag enrichment: {} proposal(s) for {} song(s), {} without match, {} failed",
+        progress.proposed,
+        progress.processed,
+        progress.not_found,
+        progress.failed
+    );
+    Ok(progress)
+}
+
+// ── Review queue ──────────────────────────────────────────────────────────────
+
+fn row_to_proposal(r: &sqlx::sqlite::SqliteRow) -> TagProposal {
+    TagProposal {
+        id: r.get("id"),
+        song_id: r.get("song_id"),
+        artist: r.get("artist"),
+        title: r.get("title"),
+        field: r.get("field"),
+        current: r.get("current"),
+        proposed: r.get("proposed"),
+        source: r.get("source"),
+        score: r.get("score"),
+        status: ProposalStatus::parse(&r.get::<String, _>("status")),
+        created_at: r.get("created_at"),
+    }
+}
+
+/// Proposals in `status` (all when `None`), oldest first.
+pub async fn list_proposals(
+    local: &SqlitePool,
+    status: Option<ProposalStatus>,
+    limit: i64,
+) -> Result<Vec<TagProposal>, sqlx::Error> {
+    let rows = sqlx::query(
+        "SELECT * FROM tag_proposals WHERE (? IS NULL OR status = ?) ORDER BY id LIMIT ?",
+    )
+    .bind(status.map(ProposalStatus::as_str))
+    .bind(status.map(ProposalStatus::as_str))
+    .bind(limit)
+    .fetch_all(local)
+    .await?;
+    Ok(rows.iter().map(row_to_proposal).collect())
+}
+
+async fn set_status(
+    local: &SqlitePool,
+    id: i64,
+    status: ProposalStatus,
+) -> Result<(), sqlx::Error> {
+    sqlx::query("UPDATE tag_proposals SET status = ?, decided_at = ? WHERE id = ?")
+        .bind(status.as_str())
+        .bind(now_secs())
+        .bind(id)
+        .execute(local)
+        .await?;
+    Ok(())
+}
+
+async fn pending(local: &SqlitePool, ids: &[i64]) -> Result<Vec<TagProposal>, sqlx::Error> {
+    let mut proposals = Vec::with_capacity(ids.len());
+    for id in ids {
+        let row = sqlx::query("SELECT * FROM tag_proposals WHERE id = ? AND status = 'pending'")
+            .bind(id)
+            .fetch_optional(local)
+            .await?;
+        proposals.extend(row.as_ref().map(row_to_proposal));
+    }
+    Ok(proposals)
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ApplySummary {
+    pub applied: i64,
+    /// Proposals whose field had changed in the meantime
+    pub stale: i64,
+    pub songs_updated: i64,
+}
+
+/// Write accepted proposals to the song list, one update per song. Other
+/// pending proposals for a field that gets a value are rejected.
+pub async fn apply(
+    sam: &MySqlPool,
+    local: &SqlitePool,
+    ids: &[i64],
+) -> Result<ApplySummary, String> {
+    let mut by_song: BTreeMap<i64, Vec<TagProposal>> = BTreeMap::new();
+    for proposal in pending(local, ids).await.map_err(|e| e.to_string())? {
+        by_song.entry(proposal.song_id).or_default().push(proposal);
+    }
+
+    let mut summary = ApplySummary::default();
+    for (song_id, proposals) in by_song {
+        let song = sam::get_song(sam, song_id)
+            .await
+            .map_err(|e| e.to_string())?;
+        let mut fields = SongUpdateFields::default();
+        let mut applied = Vec::new();
+        for proposal in proposals {
+            let current = song.as_ref().map(|s| match proposal.field.as_str() {
+                "album" => s.album.as_str(),
+                "albumyear" => s.albumyear.as_str(),
+                _ => s.isrc.as_str(),
+            });
+            let slot = match proposal.field.as_str() {
+                "album" => &mut fields.album,
+                "albumyear" => &mut fields.albumyear,
+                _ => &mut fields.isrc,
+            };
+            if slot.is_some() {
+                // Another source's value for the same field won; it is
+                // rejected below.
+                continue;
+            }
+            if current != Some(proposal.current.as_str()) {
+                set_status(local, proposal.id, ProposalStatus::Stale)
+                    .await
+                    .map_err(|e| e.to_string())?;
+                summary.stale += 1;
+                continue;
+            }
+            *slot = Some(proposal.proposed.clone());
+            applied.push(proposal);
+        }
+        if applied.is_empty() {
+            continue;
+        }
+        sam::update_song(sam, song_id, fields)
+            .await
+            .map_err(|e| e.to_string())?;
+        summary.songs_updated += 1;
+        for proposal in applied {
+            set_status(local, proposal.id, ProposalStatus::Applied)
+                .await
+                .map_err(|e| e.to_string())?;
+            sqlx::query(
+                "UPDATE tag_proposals SET status = 'rejected', decided_at = ? \
+                 WHERE song_id = ? AND field = ? AND status = 'pending'",
+            )
+            .bind(now_secs())
+            .bind(song_id)
+            .bind(&proposal.field)
+            .execute(local)
+            .await
+            .map_err(|e| e.to_string())?;
+            summary.applied += 1;
+        }
+    }
+    Ok(summary)
+}
+
+pub async fn reject(local: &SqlitePool, ids: &[i64]) -> Result<u64, sqlx::Error> {
+    let mut count = 0;
+    for proposal in pending(local, ids).await? {
+        set_status(local, proposal.id, ProposalStatus::Rejected).await?;
+        count += 1;
+    }
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn proposes_only_empty_fields_from_the_earliest_album() {
+        let releases = vec![
+            json!({ "title": "Hits Live", "date": "1995", "status": "Official",
+                    "release-group": { "primary-type": "Live" } }),
+            json!({ "title": "Debut", "date": "1998-03-02", "status": "Official",
+                    "release-group": { "primary-type": "Album" } }),
+            json!({ "title": "Remaster", "date": "2010", "status": "Official",
+                    "release-group": { "primary-type": "Album" } }),
+        ];
+        let (album, year) = best_release(&releases).expect("a release");
+        assert_eq!(album, "Debut");
+        assert_eq!(year.as_deref(), Some("1998"));
+
+        let song = SamSong {
+            id: 1,
+            filename: String::new(),
+            songtype: "S".to_string(),
+            status: 1,
+            weight: 50.0,
+            artist: "Artist".to_string(),
+            title: "Title".to_string(),
+            album: "Own Album".to_string(),
+            genre: String::new(),
+            albumyear: "0".to_string(),
+            duration: 200,
+            bpm: 0,
+            xfade: String::new(),
+            mood: String::new(),
+            mood_ai: None,
+            rating: 0,
+            count_played: 0,
+            date_played: None,
+            label: String::new(),
+            isrc: String::new(),
+            upc: String::new(),
+            picture: None,
+            overlay: String::new(),
+        };
+        assert!(needs_enrichment(&song));
+        let candidate = Candidate {
+            source: "musicbrainz",
+            score: 100,
+            album: Some(album),
+            year,
+            isrc: Some("gbaym9800001".to_string()),
+        };
+        let fields: Vec<_> = proposals_for(&song, &candidate)
+            .into_iter()
+            .map(|(field, _, value)| (field, value))
+            .collect();
+        assert_eq!(
+            fields,
+            vec![
+                ("albumyear", "1998".to_string()),
+                ("isrc", "GBAYM9800001".to_string())
+            ]
+        );
+    }
+}
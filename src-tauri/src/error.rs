@@ -0,0 +1,235 @@
+use serde::{Deserialize, Serialize};
+
+/// Machine-readable failure class, serialized as `SCREAMING_SNAKE_CASE` so
+/// UIs, scripts and remote callers can branch on it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ErrorCode {
+    /// Local SQLite database not initialised
+    DbUnavailable,
+    /// SAM MySQL database not connected
+    SamDbUnavailable,
+    /// Query or write failed
+    DbError,
+    FileNotFound,
+    InvalidInput,
+    NotFound,
+    /// Audio engine command ring is full
+    EngineQueueFull,
+    EngineError,
+    /// Track could not be opened or decoded
+    DecodeError,
+    DeviceError,
+    NetworkError,
+    PermissionDenied,
+    Conflict,
+    Unsupported,
+    Internal,
+}
+
+impl ErrorCode {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ErrorCode::DbUnavailable => "DB_UNAVAILABLE",
+            ErrorCode::SamDbUnavailable => "SAM_DB_UNAVAILABLE",
+            ErrorCode::DbError => "DB_ERROR",
+            ErrorCode::FileNotFound => "FILE_NOT_FOUND",
+            ErrorCode::InvalidInput => "INVALID_INPUT",
+            ErrorCode::NotFound => "NOT_FOUND",
+            ErrorCode::EngineQueueFull => "ENGINE_QUEUE_FULL",
+            ErrorCode::EngineError => "ENGINE_ERROR",
+            ErrorCode::DecodeError => "DECODE_ERROR",
+            ErrorCode::DeviceError => "DEVICE_ERROR",
+            ErrorCode::NetworkError => "NETWORK_ERROR",
+            ErrorCode::PermissionDenied => "PERMISSION_DENIED",
+            ErrorCode::Conflict => "CONFLICT",
+            ErrorCode::Unsupported => "UNSUPPORTED",
+            ErrorCode::Internal => "INTERNAL",
+        }
+    }
+}
+
+/// Error returned by every Tauri command.
+///
+/// Serializes as `{ "code": "DB_UNAVAILABLE", "message": "..." }`. The code is
+/// fixed where the error is built (the constructors below, or the `From`
+/// impls for typed library errors); a bare `String` is always `INTERNAL`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AppError {
+    pub code: ErrorCode,
+    pub message: String,
+}
+
+impl AppError {
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+
+    pub fn db_unavailable() -> Self {
+        Self::new(ErrorCode::DbUnavailable, "Local DB not initialised")
+    }
+
+    pub fn sam_db_unavailable() -> Self {
+        Self::new(ErrorCode::SamDbUnavailable, "SAM DB not connected")
+    }
+
+    pub fn db(e: impl std::fmt::Display) -> Self {
+        Self::new(ErrorCode::DbError, format!("DB error: {e}"))
+    }
+
+    pub fn invalid_input(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::InvalidInput, message)
+    }
+
+    pub fn not_found(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::NotFound, message)
+    }
+
+    pub fn file_not_found(path: impl std::fmt::Display) -> Self {
+        Self::new(ErrorCode::FileNotFound, format!("File not found: {path}"))
+    }
+
+    pub fn permission_denied(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::PermissionDenied, message)
+    }
+
+    pub fn conflict(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::Conflict, message)
+    }
+
+    pub fn engine_queue_full() -> Self {
+        Self::new(ErrorCode::EngineQueueFull, "Command queue full")
+    }
+
+    pub fn engine(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::EngineError, message)
+    }
+
+    pub fn device(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::DeviceError, message)
+    }
+
+    /// The track could not be opened or decoded.
+    pub fn decode(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::DecodeError, message)
+    }
+}
+
+impl std::fmt::Display for AppError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for AppError {}
+
+impl From<String> for AppError {
+    fn from(message: String) -> Self {
+        Self::new(ErrorCode::Internal, message)
+    }
+}
+
+impl From<&str> for AppError {
+    fn from(message: &str) -> Self {
+        message.to_string().into()
+    }
+}
+
+/// Lets helpers that return `Result<_, String>` call command-side code with `?`.
+impl From<AppError> for String {
+    fn from(e: AppError) -> Self {
+        e.message
+    }
+}
+
+impl From<sqlx::Error> for AppError {
+    fn from(e: sqlx::Error) -> Self {
+        let code = match &e {
+            sqlx::Error::RowNotFound => ErrorCode::NotFound,
+            sqlx::Error::PoolTimedOut | sqlx::Error::PoolClosed | sqlx::Error::Io(_) => {
+                ErrorCode::DbUnavailable
+            }
+            _ => ErrorCode::DbError,
+        };
+        Self::new(code, e.to_string())
+    }
+}
+
+/// Rotation and other legacy helpers box their errors.
+impl From<Box<dyn std::error::Error + Send + Sync>> for AppError {
+    fn from(e: Box<dyn std::error::Error + Send + Sync>) -> Self {
+        e.to_string().into()
+    }
+}
+
+impl From<std::io::Error> for AppError {
+    fn from(e: std::io::Error) -> Self {
+        let code = match e.kind() {
+            std::io::ErrorKind::NotFound => ErrorCode::FileNotFound,
+            std::io::ErrorKind::PermissionDenied => ErrorCode::PermissionDenied,
+            _ => ErrorCode::Internal,
+        };
+        Self::new(code, e.to_string())
+    }
+}
+
+impl From<serde_json::Error> for AppError {
+    fn from(e: serde_json::Error) -> Self {
+        Self::new(ErrorCode::InvalidInput, e.to_string())
+    }
+}
+
+impl From<reqwest::Error> for AppError {
+    fn from(e: reqwest::Error) -> Self {
+        Self::new(ErrorCode::NetworkError, e.to_string())
+    }
+}
+
+impl From<tokio::task::JoinError> for AppError {
+    fn from(e: tokio::task::JoinError) -> Self {
+        Self::new(ErrorCode::Internal, e.to_string())
+    }
+}
+
+impl From<tauri::Error> for AppError {
+    fn from(e: tauri::Error) -> Self {
+        Self::new(ErrorCode::Internal, e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serializes_code_and_message() {
+        let json = serde_json::to_value(AppError::db_unavailable()).unwrap();
+        assert_eq!(json["code"], "DB_UNAVAILABLE");
+        assert_eq!(json["message"], "Local DB not initialised");
+        assert_eq!(
+            serde_json::to_value(ErrorCode::EngineQueueFull).unwrap(),
+            ErrorCode::EngineQueueFull.as_str()
+        );
+    }
+
+    #[test]
+    fn codes_come_from_the_source_not_the_wording() {
+        assert_eq!(
+            AppError::from("Command queue full").code,
+            ErrorCode::Internal
+        );
+        assert_eq!(
+            AppError::engine_queue_full().code,
+            ErrorCode::EngineQueueFull
+        );
+        assert_eq!(
+            AppError::from(sqlx::Error::RowNotFound).code,
+            ErrorCode::NotFound
+        );
+        let missing = std::io::Error::new(std::io::ErrorKind::NotFound, "gone");
+        assert_eq!(AppError::from(missing).code, ErrorCode::FileNotFound);
+    }
+}
@@ -0,0 +1,478 @@
+/// `gateway/audio_ingest.rs` — remote DJ live audio over WebSocket
+///
+/// The gateway relays the remote DJ's microphone as Opus packets on a
+/// dedicated socket. Packets are reordered in a small jitter buffer, decoded
+/// (losses concealed by the decoder) and fed to the engine's Aux 2 channel.
+/// The program minus Aux 2 is encoded back on the same socket so the remote
+/// DJ hears the show without their own voice delayed.
+///
+/// Wire format, both directions: `[kind: u8 = 1][seq: u16 BE][Opus payload]`,
+/// 48 kHz stereo, 20 ms frames.
+use futures_util::{SinkExt, StreamExt};
+use ringbuf::traits::{Consumer as _, Observer as _, Producer as _};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tokio::sync::watch;
+use tokio_tungstenite::{connect_async, tungstenite::protocol::Message};
+
+use crate::state::AppState;
+
+const PACKET_KIND_OPUS: u8 = 1;
+const OPUS_SAMPLE_RATE: u32 = 48_000;
+const FRAME_MS: u32 = 20;
+/// Samples per channel in one 20 ms frame at 48 kHz
+const FRAME_SAMPLES: usize = (OPUS_SAMPLE_RATE * FRAME_MS / 1000) as usize;
+/// Largest Opus frame (120 ms), interleaved stereo
+const MAX_DECODED: usize = FRAME_SAMPLES * 6 * 2;
+/// Packets held before playout starts (and again after an underrun)
+const JITTER_TARGET_PACKETS: usize = 3;
+/// Packets held at most; older ones are dropped to bound latency
+const JITTER_MAX_PACKETS: usize = 25;
+/// Decoded audio kept queued ahead of the engine
+const ENGINE_TARGET_MS: u32 = 60;
+const PUMP_INTERVAL: Duration = Duration::from_millis(10);
+const RETURN_BITRATE: i32 = 64_000;
+
+// ── Status ────────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RemoteAudioStatus {
+    pub session_id: String,
+    pub active: bool,
+    pub connected: bool,
+    pub packets_received: u64,
+    pub packets_lost: u64,
+    pub packets_late: u64,
+    pub underruns: u64,
+    /// Audio waiting in the jitter buffer
+    pub jitter_ms: u32,
+    pub mix_minus: bool,
+    pub last_error: Option<String>,
+}
+
+// ── Framing ───────────────────────────────────────────────────────────────────
+
+pub fn parse_packet(data: &[u8]) -> Option<(u16, &[u8])> {
+    if data.len() < 4 || data[0] != PACKET_KIND_OPUS {
+        return None;
+    }
+    Some((u16::from_be_bytes([data[1], data[2]]), &data[3..]))
+}
+
+pub fn build_packet(seq: u16, payload: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(payload.len() + 3);
+    out.push(PACKET_KIND_OPUS);
+    out.extend_from_slice(&seq.to_be_bytes());
+    out.extend_from_slice(payload);
+    out
+}
+
+// ── Jitter buffer ─────────────────────────────────────────────────────────────
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Slot {
+    Packet(Vec<u8>),
+    /// The next packet never arrived; conceal it
+    Lost,
+}
+
+/// Reorders packets by sequence number and releases them in order once
+/// `target` are buffered. Sequence numbers wrap at 16 bits.
+#[derive(Debug)]
+pub struct JitterBuffer {
+    packets: BTreeMap<u64, Vec<u8>>,
+    next: Option<u64>,
+    highest: Option<u64>,
+    target: usize,
+    max: usize,
+    primed: bool,
+    pub received: u64,
+    pub lost: u64,
+    pub late: u64,
+    pub underruns: u64,
+}
+
+impl JitterBuffer {
+    pub fn new(target: usize, max: usize) -> Self {
+        Self {
+            packets: BTreeMap::new(),
+            next: None,
+            highest: None,
+            target: target.max(1),
+            max: max.max(target.max(1)),
+            primed: false,
+            received: 0,
+            lost: 0,
+            late: 0,
+            underruns: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.packets.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.packets.is_empty()
+    }
+
+    /// Extend a 16-bit sequence number relative to the highest seen so far.
+    fn unwrap_seq(&mut self, seq: u16) -> u64 {
+        let ext = match self.highest {
+            // Start one wrap in so early reordering cannot go below zero.
+            None => (1 << 16) + u64::from(seq),
+            Some(high) => {
+                let delta = i64::from(seq.wrapping_sub(high as u16) as i16);
+                (high as i64 + delta).max(0) as u64
+            }
+        };
+        self.highest = Some(self.highest.map_or(ext, |h| h.max(ext)));
+        ext
+    }
+
+    pub fn push(&mut self, seq: u16, payload: Vec<u8>) {
+        self.received += 1;
+        let ext = self.unwrap_seq(seq);
+        if self.next.is_some_and(|next| ext < next) {
+            self.late += 1;
+            return;
+        }
+        self.packets.entry(ext).or_insert(payload);
+        while self.packets.len() > self.max {
+            if let Some((dropped, _)) = self.packets.pop_first() {
+                if self.next.is_some_and(|next| dropped >= next) {
+                    self.next = Some(dropped + 1);
+                }
+            }
+        }
+    }
+
+    /// Next frame to play, or `None` while (re)buffering.
+    pub fn pop(&mut self) -> Option<Slot> {
+        if !self.primed {
+            if self.packets.len() < self.target {
+                return None;
+            }
+            self.primed = true;
+            self.next = self.packets.keys().next().copied();
+        }
+        let next = self.next?;
+        if let Some(packet) = self.packets.remove(&next) {
+            self.next = Some(next + 1);
+            return Some(Slot::Packet(packet));
+        }
+        if self.packets.is_empty() {
+            self.primed = false;
+            self.underruns += 1;
+            return None;
+        }
+        self.next = Some(next + 1);
+        self.lost += 1;
+        Some(Slot::Lost)
+    }
+}
+
+// ── Resampler ─────────────────────────────────────────────────────────────────
+
+/// Streaming linear-interpolation resampler for interleaved stereo.
+pub(crate) struct LinearResampler {
+    step: f64,
+    pos: f64,
+    prev: [f32; 2],
+}
+
+impl LinearResampler {
+    pub(crate) fn new(from_sr: u32, to_sr: u32) -> Self {
+        Self {
+            step: f64::from(from_sr.max(1)) / f64::from(to_sr.max(1)),
+            pos: 0.0,
+            prev: [0.0; 2],
+        }
+    }
+
+    pub(crate) fn process(&mut self, input: &[f32], out: &mut Vec<f32>) {
+        let frames = input.len() / 2;
+        if frames == 0 {
+            return;
+        }
+        if (self.step - 1.0).abs() < 1e-9 {
+            out.extend_from_slice(&input[..frames * 2]);
+            return;
+        }
+        // Index 0 is the last frame of the previous block, k is input[k - 1].
+        let frame = |k: usize| -> [f32; 2] {
+            if k == 0 {
+                self.prev
+            } else {
+                [input[(k - 1) * 2], input[(k - 1) * 2 + 1]]
+            }
+        };
+        while (self.pos as usize) < frames {
+            let i = self.pos as usize;
+            let t = (self.pos - i as f64) as f32;
+            let (a, b) = (frame(i), frame(i + 1));
+            out.push(a[0] + (b[0] - a[0]) * t);
+            out.push(a[1] + (b[1] - a[1]) * t);
+            self.pos += self.step;
+        }
+        self.pos -= frames as f64;
+        self.prev = [input[(frames - 1) * 2], input[(frames - 1) * 2 + 1]];
+    }
+}
+
+// ── Ingest session ────────────────────────────────────────────────────────────
+
+/// One remote DJ's live audio link. Dropping the handle does not stop it;
+/// call `stop`.
+pub struct RemoteAudioIngest {
+    session_id: String,
+    stop_tx: watch::Sender<bool>,
+    status: Arc<Mutex<RemoteAudioStatus>>,
+}
+
+impl RemoteAudioIngest {
+    pub fn start(app: AppHandle, gateway_url: &str, token: &str, session_id: String) -> Self {
+        let ws_url = format!(
+            "{}/remote-audio?token={}&session={}",
+            gateway_url,
+            token,
+            urlencoding::encode(&session_id)
+        );
+        let status = Arc::new(Mutex::new(RemoteAudioStatus {
+            session_id: session_id.clone(),
+            active: true,
+            ..Default::default()
+        }));
+        let (stop_tx, stop_rx) = watch::channel(false);
+
+        let task_status = status.clone();
+        let task_session = session_id.clone();
+        tokio::spawn(async move {
+            let result = run(&app, &ws_url, &task_session, stop_rx, &task_status).await;
+            {
+                let state = app.state::<AppState>();
+                let mut engine = state.engine.lock().unwrap();
+                engine.detach_remote_input();
+                engine.detach_mix_minus();
+            }
+            let mut s = task_status.lock().unwrap();
+            s.active = false;
+            s.connected = false;
+            if let Err(e) = result {
+                log::warn!("Remote audio for session {task_session} ended: {e}");
+                s.last_error = Some(e);
+            } else {
+                log::info!("Remote audio for session {task_session} stopped");
+            }
+        });
+
+        Self {
+            session_id,
+            stop_tx,
+            status,
+        }
+    }
+
+    pub fn session_id(&self) -> &str {
+        &self.session_id
+    }
+
+    pub fn stop(&self) {
+        self.stop_tx.send_replace(true);
+    }
+
+    pub fn status(&self) -> RemoteAudioStatus {
+        self.status.lock().unwrap().clone()
+    }
+}
+
+fn opus_err(e: opus::Error) -> String {
+    format!("Opus: {e}")
+}
+
+async fn run(
+    app: &AppHandle,
+    ws_url: &str,
+    session_id: &str,
+    mut stop_rx: watch::Receiver<bool>,
+    status: &Mutex<RemoteAudioStatus>,
+) -> Result<(), String> {
+    let (ws, _) = connect_async(ws_url)
+        .await
+        .map_err(|e| format!("Remote audio connection failed: {e}"))?;
+    let (mut write, mut read) = ws.split();
+    status.lock().unwrap().connected = true;
+
+    let (mut input, mut mix_minus, out_sr) = {
+        let state = app.state::<AppState>();
+        let mut engine = state.engine.lock().unwrap();
+        (
+            engine.attach_remote_input(),
+            engine.attach_mix_minus(),
+            engine.output_sample_rate(),
+        )
+    };
+
+    let mut decoder =
+        opus::Decoder::new(OPUS_SAMPLE_RATE, opus::Channels::Stereo).map_err(opus_err)?;
+    let mut encoder = opus::Encoder::new(
+        OPUS_SAMPLE_RATE,
+        opus::Channels::Stereo,
+        opus::Application::Voip,
+    )
+    .map_err(opus_err)?;
+    let _ = encoder.set_bitrate(opus::Bitrate::Bits(RETURN_BITRATE));
+
+    let mut jitter = JitterBuffer::new(JITTER_TARGET_PACKETS, JITTER_MAX_PACKETS);
+    let mut to_engine = LinearResampler::new(OPUS_SAMPLE_RATE, out_sr);
+    let mut to_remote = LinearResampler::new(out_sr, OPUS_SAMPLE_RATE);
+    let target_queued = (out_sr * 2 * ENGINE_TARGET_MS / 1000) as usize;
+
+    let mut decoded = vec![0.0_f32; MAX_DECODED];
+    let mut resampled: Vec<f32> = Vec::new();
+    let mut tap = vec![0.0_f32; 4096];
+    let mut return_pcm: Vec<f32> = Vec::new();
+    let mut return_seq: u16 = 0;
+    let mut pump = tokio::time::interval(PUMP_INTERVAL);
+
+    loop {
+        tokio::select! {
+            _ = stop_rx.changed() => {
+                let _ = write.send(Message::Close(None)).await;
+                return Ok(());
+            }
+            frame = read.next() => match frame {
+                Some(Ok(Message::Binary(data))) => {
+                    if let Some((seq, payload)) = parse_packet(&data) {
+                        jitter.push(seq, payload.to_vec());
+                    }
+                }
+                Some(Ok(Message::Close(_))) | None => {
+                    return Err("remote audio closed by gateway".to_string());
+                }
+                Some(Err(e)) => return Err(e.to_string()),
+                Some(Ok(_)) => {}
+            },
+            _ = pump.tick() => {
+                let (allowed, mix_minus_on) = {
+                    let state = app.state::<AppState>();
+                    let allowed = state
+                        .remote_dj_permissions
+                        .lock()
+                        .unwrap()
+                        .get(session_id)
+                        .is_some_and(|p| p.can_go_live);
+                    let mix_minus_on = *state.mix_minus_enabled.lock().unwrap();
+                    (allowed, mix_minus_on)
+                };
+                if !allowed {
+                    let _ = write.send(Message::Close(None)).await;
+                    return Err("live audio permission revoked".to_string());
+                }
+
+                // Remote DJ → Aux 2
+                while input.occupied_len() < target_queued {
+                    let Some(slot) = jitter.pop() else {
+                        break;
+                    };
+                    let result = match &slot {
+                        Slot::Packet(packet) => decoder.decode_float(packet, &mut decoded, false),
+                        Slot::Lost => decoder.decode_float(&[], &mut decoded, false),
+                    };
+                    let frames = match result {
+                        Ok(frames) => frames,
+                        Err(e) => {
+                            log::debug!("Remote audio decode failed: {e}");
+                            continue;
+                        }
+                    };
+                    resampled.clear();
+                    to_engine.process(&decoded[..frames * 2], &mut resampled);
+                    input.push_slice(&resampled);
+                }
+
+                // Program minus Aux 2 → remote DJ
+                loop {
+                    let n = mix_minus.pop_slice(&mut tap) & !1;
+                    if n == 0 {
+                        break;
+                    }
+                    if mix_minus_on {
+                        to_remote.process(&tap[..n], &mut return_pcm);
+                    }
+                }
+                while return_pcm.len() >= FRAME_SAMPLES * 2 {
+                    let packet = encoder
+                        .encode_vec_float(&return_pcm[..FRAME_SAMPLES * 2], 4000)
+                        .map_err(opus_err)?;
+                    return_pcm.drain(..FRAME_SAMPLES * 2);
+                    write
+                        .send(Message::Binary(build_packet(return_seq, &packet)))
+                        .await
+                        .map_err(|e| format!("mix-minus send failed: {e}"))?;
+                    return_seq = return_seq.wrapping_add(1);
+                }
+
+                let mut s = status.lock().unwrap();
+                s.packets_received = jitter.received;
+                s.packets_lost = jitter.lost;
+                s.packets_late = jitter.late;
+                s.underruns = jitter.underruns;
+                s.jitter_ms = jitter.len() as u32 * FRAME_MS;
+                s.mix_minus = mix_minus_on;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pkt(n: u8) -> Vec<u8> {
+        vec![n]
+    }
+
+    #[test]
+    fn jitter_buffer_reorders_conceals_and_rebuffers() {
+        let mut jb = JitterBuffer::new(3, 10);
+        jb.push(65_534, pkt(1));
+        jb.push(0, pkt(3)); // wraps past 65_535
+        assert_eq!(jb.pop(), None);
+        jb.push(65_535, pkt(2));
+
+        assert_eq!(jb.pop(), Some(Slot::Packet(pkt(1))));
+        assert_eq!(jb.pop(), Some(Slot::Packet(pkt(2))));
+        assert_eq!(jb.pop(), Some(Slot::Packet(pkt(3))));
+
+        jb.push(2, pkt(5)); // seq 1 missing
+        assert_eq!(jb.pop(), Some(Slot::Lost));
+        assert_eq!(jb.pop(), Some(Slot::Packet(pkt(5))));
+        jb.push(1, pkt(4)); // too late now
+        assert_eq!(jb.late, 1);
+
+        assert_eq!(jb.pop(), None);
+        assert_eq!(jb.underruns, 1);
+        assert_eq!(jb.lost, 1);
+    }
+
+    #[test]
+    fn packets_round_trip_and_resampler_keeps_rate() {
+        let packet = build_packet(513, &[9, 8, 7]);
+        assert_eq!(parse_packet(&packet), Some((513, &[9u8, 8, 7][..])));
+        assert_eq!(parse_packet(&[2, 0, 1, 5]), None);
+
+        let mut rs = LinearResampler::new(48_000, 44_100);
+        let block = vec![0.25_f32; FRAME_SAMPLES * 2];
+        let mut out = Vec::new();
+        for _ in 0..50 {
+            rs.process(&block, &mut out);
+        }
+        // One second in, within a frame of one second out.
+        let frames = out.len() / 2;
+        assert!((44_099..=44_101).contains(&frames), "{frames}");
+    }
+}
@@ -1,12 +1,30 @@
 use futures_util::{SinkExt, StreamExt};
 use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::Arc;
-use tokio::sync::mpsc;
-use tokio_tungstenite::{connect_async, tungstenite::protocol::Message};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::net::TcpStream;
+use tokio::sync::{watch, Notify};
+use tokio_tungstenite::{
+    connect_async,
+    tungstenite::{protocol::Message, Error as WsError},
+    MaybeTlsStream, WebSocketStream,
+};
 
 use super::remote_dj::RemoteDjCommand;
 
+/// Messages held while the link is down; the oldest are dropped beyond this.
+pub const OUTBOX_CAPACITY: usize = 512;
+const BACKOFF_BASE: Duration = Duration::from_secs(1);
+const BACKOFF_MAX: Duration = Duration::from_secs(60);
+const PING_INTERVAL: Duration = Duration::from_secs(20);
+/// No frame from the gateway for this long means the link is dead.
+const IDLE_TIMEOUT: Duration = Duration::from_secs(60);
+
+type WsStream = WebSocketStream<MaybeTlsStream<TcpStream>>;
+type MessageHandler = Arc<dyn Fn(GatewayMessage) + Send + Sync>;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct QueueItem {
     pub queue_id: i64,
@@ -71,21 +89,126 @@ pub enum GatewayMessage {
         song_id: i64,
         requested_by: String,
     },
+    /// Sent by the gateway after each handshake; `resumed` is true when the
+    /// previous bridge session (and its remote DJs) was picked up again.
+    SessionWelcome {
+        session_id: String,
+        resumed: bool,
+    },
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl GatewayMessage {
+    /// Live-only readings; not worth sending late, so never queued offline.
+    fn is_transient(&self) -> bool {
+        matches!(
+            self,
+            GatewayMessage::VuMeter { .. } | GatewayMessage::CrossfadeProgress { .. }
+        )
+    }
+
+    /// State pushes where only the latest value matters: a newer message with
+    /// the same key replaces the queued one.
+    fn coalesce_key(&self) -> Option<String> {
+        match self {
+            GatewayMessage::NowPlaying { .. } => Some("now_playing".to_string()),
+            GatewayMessage::QueueUpdated { .. } => Some("queue".to_string()),
+            GatewayMessage::DeckState { deck, .. } => Some(format!("deck:{deck}")),
+            GatewayMessage::ListenerCount { .. } => Some("listeners".to_string()),
+            GatewayMessage::StreamStatus { mount, .. } => Some(format!("stream:{mount}")),
+            _ => None,
+        }
+    }
+}
+
+/// Bounded outbound queue shared by senders and the connection task.
+#[derive(Debug, Default)]
+struct Outbox {
+    queue: VecDeque<GatewayMessage>,
+    dropped: u64,
+}
+
+impl Outbox {
+    fn push(&mut self, message: GatewayMessage, online: bool) {
+        if !online && message.is_transient() {
+            return;
+        }
+        if let Some(key) = message.coalesce_key() {
+            if let Some(slot) = self
+                .queue
+                .iter_mut()
+                .find(|m| m.coalesce_key().as_deref() == Some(key.as_str()))
+            {
+                *slot = message;
+                return;
+            }
+        }
+        if self.queue.len() >= OUTBOX_CAPACITY {
+            self.queue.pop_front();
+            self.dropped += 1;
+        }
+        self.queue.push_back(message);
+    }
+}
+
+/// Delay before reconnect `attempt` (1-based): exponential, capped, with
+/// `jitter` in [0, 1) spreading it over the upper half of the window.
+pub fn backoff_delay(attempt: u32, jitter: f64) -> Duration {
+    let exp = BACKOFF_BASE.saturating_mul(1u32 << attempt.saturating_sub(1).min(16));
+    let window = exp.min(BACKOFF_MAX);
+    window.mul_f64(0.5 + 0.5 * jitter.clamp(0.0, 1.0))
+}
+
+fn jitter() -> f64 {
+    use std::hash::{BuildHasher, Hasher};
+    let mut hasher = std::collections::hash_map::RandomState::new().build_hasher();
+    hasher.write_u128(
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos(),
+    );
+    (hasher.finish() >> 11) as f64 / (1u64 << 53) as f64
+}
+
+fn now_ms() -> i64 {
+    chrono::Utc::now().timestamp_millis()
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct GatewayStatus {
     pub connected: bool,
     pub url: String,
     pub reconnecting: bool,
     pub last_error: Option<String>,
+    /// Attempts since the link dropped (0 while connected)
+    pub reconnect_attempt: u32,
+    /// Unix ms of the next reconnect attempt
+    pub next_retry_at: Option<i64>,
+    /// Successful reconnects since `connect_gateway`
+    pub reconnect_count: u32,
+    /// Unix ms the current link came up
+    pub connected_since: Option<i64>,
+    /// Bridge session presented on reconnect so remote DJs stay attached
+    pub session_id: String,
+    pub session_resumed: bool,
+    pub queued_messages: usize,
+    pub dropped_messages: u64,
+}
+
+/// Why a connection attempt failed.
+enum ConnectError {
+    /// The gateway refused the token; retrying will not help.
+    Rejected(String),
+    Transient(String),
 }
 
 pub struct GatewayClient {
     url: String,
     token: String,
     connected: Arc<AtomicBool>,
-    tx: Option<mpsc::UnboundedSender<GatewayMessage>>,
+    outbox: Arc<Mutex<Outbox>>,
+    outbox_ready: Arc<Notify>,
+    stop_tx: Arc<watch::Sender<bool>>,
     status: Arc<tokio::sync::Mutex<GatewayStatus>>,
 }
 
@@ -95,7 +218,9 @@ impl Clone for GatewayClient {
             url: self.url.clone(),
             token: self.token.clone(),
             connected: self.connected.clone(),
-            tx: self.tx.clone(),
+            outbox: self.outbox.clone(),
+            outbox_ready: self.outbox_ready.clone(),
+            stop_tx: self.stop_tx.clone(),
             status: self.status.clone(),
         }
     }
@@ -103,111 +228,312 @@ impl Clone for GatewayClient {
 
 impl GatewayClient {
     pub fn new(url: String, token: String) -> Self {
+        let session_id = format!(
+            "{:x}{:04x}",
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_nanos(),
+            std::process::id() & 0xffff
+        );
         let status = GatewayStatus {
-            connected: false,
             url: url.clone(),
-            reconnecting: false,
-            last_error: None,
+            session_id,
+            ..Default::default()
         };
+        let (stop_tx, _) = watch::channel(false);
 
         Self {
             url,
             token,
             connected: Arc::new(AtomicBool::new(false)),
-            tx: None,
+            outbox: Arc::new(Mutex::new(Outbox::default())),
+            outbox_ready: Arc::new(Notify::new()),
+            stop_tx: Arc::new(stop_tx),
             status: Arc::new(tokio::sync::Mutex::new(status)),
         }
     }
 
-    /// Connect to the gateway WebSocket
+    /// Connect to the gateway WebSocket. The first attempt is made inline so
+    /// a bad URL or token is reported; afterwards dropped links are retried
+    /// with jittered backoff until `disconnect`.
     pub async fn connect(
         &mut self,
-        on_message: impl Fn(GatewayMessage) + Send + 'static,
+        on_message: impl Fn(GatewayMessage) + Send + Sync + 'static,
     ) -> Result<(), String> {
-        let ws_url = format!("{}/desktop-bridge?token={}", self.url, self.token);
-
-        let (ws_stream, _) = connect_async(&ws_url)
-            .await
-            .map_err(|e| format!("WebSocket connection failed: {}", e))?;
-
-        let (mut write, mut read) = ws_stream.split();
-        let (tx, mut rx) = mpsc::unbounded_channel::<GatewayMessage>();
-
-        self.connected.store(true, Ordering::SeqCst);
-        self.tx = Some(tx);
-
-        // Update status
-        {
-            let mut status = self.status.lock().await;
-            status.connected = true;
-            status.last_error = None;
-        }
-
-        let connected = self.connected.clone();
-        let status = self.status.clone();
+        let ws = match self.open().await {
+            Ok(ws) => ws,
+            Err(ConnectError::Rejected(e) | ConnectError::Transient(e)) => {
+                self.status.lock().await.last_error = Some(e.clone());
+                return Err(e);
+            }
+        };
+        self.stop_tx.send_replace(false);
 
-        // Spawn task to send messages to gateway
+        let client = self.clone();
+        let on_message: MessageHandler = Arc::new(on_message);
         tokio::spawn(async move {
-            while let Some(msg) = rx.recv().await {
-                let json = serde_json::to_string(&msg).unwrap();
-                if write.send(Message::Text(json)).await.is_err() {
+            let mut stop_rx = client.stop_tx.subscribe();
+            let mut ws = ws;
+            loop {
+                let reason = client.run_session(ws, &on_message, &mut stop_rx).await;
+                client.connected.store(false, Ordering::SeqCst);
+                let Some(reason) = reason else {
                     break;
+                };
+                log::warn!("Gateway link lost: {reason}");
+                {
+                    let mut s = client.status.lock().await;
+                    s.connected = false;
+                    s.connected_since = None;
+                    s.reconnecting = true;
+                    s.last_error = Some(reason);
                 }
+
+                let mut attempt = 0;
+                ws = loop {
+                    attempt += 1;
+                    let delay = backoff_delay(attempt, jitter());
+                    {
+                        let mut s = client.status.lock().await;
+                        s.reconnect_attempt = attempt;
+                        s.next_retry_at = Some(now_ms() + delay.as_millis() as i64);
+                    }
+                    tokio::select! {
+                        _ = tokio::time::sleep(delay) => {}
+                        _ = stop_rx.changed() => return,
+                    }
+                    match client.open().await {
+                        Ok(ws) => break ws,
+                        Err(ConnectError::Rejected(e)) => {
+                            log::error!("Gateway rejected reconnect: {e}");
+                            client.stop_tx.send_replace(true);
+                            let mut s = client.status.lock().await;
+                            s.reconnecting = false;
+                            s.next_retry_at = None;
+                            s.last_error = Some(e);
+                            return;
+                        }
+                        Err(ConnectError::Transient(e)) => {
+                            log::debug!("Gateway reconnect attempt {attempt} failed: {e}");
+                            client.status.lock().await.last_error = Some(e);
+                        }
+                    }
+                };
+                client.status.lock().await.reconnect_count += 1;
+                log::info!("Gateway reconnected after {attempt} attempt(s)");
             }
         });
 
-        let connected_clone = connected.clone();
-        let status_clone = status.clone();
+        Ok(())
+    }
 
-        // Spawn task to receive messages from gateway
-        tokio::spawn(async move {
-            while let Some(Ok(msg)) = read.next().await {
-                match msg {
-                    Message::Text(text) => {
-                        if let Ok(gateway_msg) = serde_json::from_str::<GatewayMessage>(&text) {
-                            on_message(gateway_msg);
+    async fn open(&self) -> Result<WsStream, ConnectError> {
+        let session_id = self.status.lock().await.session_id.clone();
+        let ws_url = format!(
+            "{}/desktop-bridge?token={}&session={}",
+            self.url,
+            self.token,
+            urlencoding::encode(&session_id)
+        );
+        match connect_async(&ws_url).await {
+            Ok((ws, _)) => Ok(ws),
+            Err(WsError::Http(response)) if matches!(response.status().as_u16(), 401 | 403) => {
+                Err(ConnectError::Rejected(format!(
+                    "Gateway refused the token (HTTP {})",
+                    response.status()
+                )))
+            }
+            Err(e) => Err(ConnectError::Transient(format!(
+                "WebSocket connection failed: {}",
+                e
+            ))),
+        }
+    }
+
+    /// Pump one live connection. Returns `None` when stopped on purpose and
+    /// the reason otherwise.
+    async fn run_session(
+        &self,
+        ws: WsStream,
+        on_message: &MessageHandler,
+        stop_rx: &mut watch::Receiver<bool>,
+    ) -> Option<String> {
+        let (mut write, mut read) = ws.split();
+        self.connected.store(true, Ordering::SeqCst);
+        {
+            let mut s = self.status.lock().await;
+            s.connected = true;
+            s.reconnecting = false;
+            s.reconnect_attempt = 0;
+            s.next_retry_at = None;
+            s.connected_since = Some(now_ms());
+            s.last_error = None;
+        }
+        // Flush whatever queued while offline.
+        self.outbox_ready.notify_one();
+
+        let mut ping = tokio::time::interval(PING_INTERVAL);
+        let mut last_seen = Instant::now();
+        loop {
+            tokio::select! {
+                _ = stop_rx.changed() => {
+                    let _ = write.send(Message::Close(None)).await;
+                    return None;
+                }
+                _ = self.outbox_ready.notified() => {
+                    loop {
+                        let Some(msg) = self.outbox.lock().unwrap().queue.pop_front() else {
+                            break;
+                        };
+                        let json = match serde_json::to_string(&msg) {
+                            Ok(json) => json,
+                            Err(e) => {
+                                log::warn!("Gateway message not serialisable: {e}");
+                                continue;
+                            }
+                        };
+                        if let Err(e) = write.send(Message::Text(json)).await {
+                            self.outbox.lock().unwrap().queue.push_front(msg);
+                            return Some(format!("send failed: {e}"));
+                        }
+                    }
+                }
+                frame = read.next() => {
+                    last_seen = Instant::now();
+                    match frame {
+                        Some(Ok(Message::Text(text))) => {
+                            if let Ok(gateway_msg) = serde_json::from_str::<GatewayMessage>(&text) {
+                                if let GatewayMessage::SessionWelcome {
+                                    session_id,
+                                    resumed,
+                                } = &gateway_msg
+                                {
+                                    let mut s = self.status.lock().await;
+                                    s.session_id = session_id.clone();
+                                    s.session_resumed = *resumed;
+                                }
+                                on_message(gateway_msg);
+                            }
+                        }
+                        Some(Ok(Message::Close(frame))) => {
+                            return Some(frame.map_or_else(
+                                || "closed by gateway".to_string(),
+                                |f| format!("closed by gateway: {}", f.reason),
+                            ));
                         }
+                        Some(Ok(_)) => {}
+                        Some(Err(e)) => return Some(e.to_string()),
+                        None => return Some("connection closed".to_string()),
                     }
-                    Message::Close(_) => {
-                        connected_clone.store(false, Ordering::SeqCst);
-                        let mut s = status_clone.lock().await;
-                        s.connected = false;
-                        break;
+                }
+                _ = ping.tick() => {
+                    if last_seen.elapsed() > IDLE_TIMEOUT {
+                        return Some("no response from gateway".to_string());
+                    }
+                    if let Err(e) = write.send(Message::Ping(Vec::new())).await {
+                        return Some(format!("ping failed: {e}"));
                     }
-                    _ => {}
                 }
             }
-        });
-
-        Ok(())
+        }
     }
 
-    /// Send a message to the gateway
+    /// Send a message to the gateway. While the link is down, state pushes
+    /// are queued (bounded, latest value per key) and sent on reconnect.
     pub async fn send(&self, message: GatewayMessage) -> Result<(), String> {
-        if let Some(tx) = &self.tx {
-            tx.send(message)
-                .map_err(|e| format!("Failed to send message: {}", e))?;
-            Ok(())
-        } else {
-            Err("Not connected".to_string())
+        if !self.is_running() {
+            return Err("Not connected".to_string());
         }
+        self.outbox
+            .lock()
+            .unwrap()
+            .push(message, self.is_connected());
+        self.outbox_ready.notify_one();
+        Ok(())
     }
 
     /// Check if connected
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    pub fn token(&self) -> &str {
+        &self.token
+    }
+
     pub fn is_connected(&self) -> bool {
         self.connected.load(Ordering::SeqCst)
     }
 
+    /// Connected or reconnecting; false once `disconnect` has been called.
+    pub fn is_running(&self) -> bool {
+        !*self.stop_tx.borrow()
+    }
+
     /// Get status
     pub async fn get_status(&self) -> GatewayStatus {
-        self.status.lock().await.clone()
+        let mut status = self.status.lock().await.clone();
+        let outbox = self.outbox.lock().unwrap();
+        status.queued_messages = outbox.queue.len();
+        status.dropped_messages = outbox.dropped;
+        status
     }
 
     /// Disconnect from gateway
     pub async fn disconnect(&mut self) {
-        self.tx = None;
+        self.stop_tx.send_replace(true);
         self.connected.store(false, Ordering::SeqCst);
+        self.outbox.lock().unwrap().queue.clear();
         let mut status = self.status.lock().await;
         status.connected = false;
+        status.reconnecting = false;
+        status.next_retry_at = None;
+        status.connected_since = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_grows_with_jitter_and_caps() {
+        assert_eq!(backoff_delay(1, 0.0), Duration::from_millis(500));
+        assert_eq!(backoff_delay(1, 1.0), Duration::from_secs(1));
+        assert_eq!(backoff_delay(4, 1.0), Duration::from_secs(8));
+        assert_eq!(backoff_delay(30, 1.0), BACKOFF_MAX);
+        assert!(backoff_delay(30, 0.0) >= BACKOFF_MAX / 2);
+    }
+
+    #[test]
+    fn offline_outbox_coalesces_state_and_skips_meters() {
+        let mut outbox = Outbox::default();
+        let vu = GatewayMessage::VuMeter {
+            channel: "master".to_string(),
+            left_db: -6.0,
+            right_db: -6.0,
+        };
+        outbox.push(vu, false);
+        for count in [10, 12] {
+            outbox.push(GatewayMessage::ListenerCount { count }, false);
+        }
+        assert_eq!(outbox.queue.len(), 1);
+        assert!(matches!(
+            outbox.queue[0],
+            GatewayMessage::ListenerCount { count: 12 }
+        ));
+
+        for i in 0..OUTBOX_CAPACITY as i64 + 3 {
+            outbox.push(
+                GatewayMessage::RequestReceived {
+                    song_id: i,
+                    requested_by: "web".to_string(),
+                },
+                false,
+            );
+        }
+        assert_eq!(outbox.queue.len(), OUTBOX_CAPACITY);
+        assert_eq!(outbox.dropped, 4);
     }
 }
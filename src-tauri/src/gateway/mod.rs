@@ -1,3 +1,4 @@
+pub mod audio_ingest;
 pub mod auth;
 pub mod client;
 pub mod remote_dj;
@@ -49,6 +49,9 @@ pub struct DjPermissions {
     pub can_queue_remove: bool,
     pub can_trigger_crossfade: bool,
     pub can_set_autopilot: bool,
+    /// Send live microphone audio into the mix (Aux 2)
+    #[serde(default)]
+    pub can_go_live: bool,
 }
 
 impl Default for DjPermissions {
@@ -62,6 +65,7 @@ impl Default for DjPermissions {
             can_queue_remove: false,
             can_trigger_crossfade: false,
             can_set_autopilot: false,
+            can_go_live: false,
         }
     }
 }
@@ -133,9 +133,13 @@ impl StateSyncer {
         loop {
             ticker.tick().await;
 
-            if !self.client.is_connected() {
+            if !self.client.is_running() {
                 break;
             }
+            // Meters are not queued while reconnecting; just wait it out.
+            if !self.client.is_connected() {
+                continue;
+            }
 
             let readings = get_vu();
             for (channel, left_db, right_db) in readings {
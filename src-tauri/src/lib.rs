@@ -1,60 +1,117 @@
+pub mod access;
 pub mod analytics;
 pub mod audio;
 pub mod commands;
 pub mod controller;
 pub mod db;
+pub mod error;
 pub mod gateway;
+pub mod logging;
+pub mod recovery;
 pub mod scheduler;
 pub mod scripting;
+pub mod settings_archive;
 pub mod state;
 pub mod stats;
 pub mod stream;
 
 use commands::{
+    access_commands::{
+        create_user, delete_user, get_access_status, get_audit_log, list_users, sign_in, sign_out,
+        update_user,
+    },
+    analysis_commands::{
+        cancel_analysis_job, enqueue_analysis, find_duplicate_songs, fingerprint_library,
+        get_analysis_queue, get_analysis_queue_config, get_track_loudness,
+        set_analysis_queue_config,
+    },
     analytics_commands::{
-        clear_event_log, export_report_csv, generate_report, get_event_log, get_health_history,
-        get_health_snapshot, get_hourly_heatmap, get_listener_graph, get_listener_peak,
-        get_song_play_history, get_top_songs, write_event_log,
+        clear_event_log, export_listener_kpis_csv, export_report_csv, export_royalty_report,
+        export_show_audience_csv, export_traffic_affidavit_csv, flush_scrobble_queue,
+        generate_report, get_alert_config, get_app_logs, get_emitter_metrics, get_event_log,
+        get_event_log_retention, get_geoip_status, get_health_history, get_health_snapshot,
+        get_hourly_heatmap, get_library_storage_report, get_listener_breakdown,
+        get_listener_geo_breakdown, get_listener_graph, get_listener_peak, get_missing_file_config,
+        get_missing_files, get_play_log, get_report_digest_config, get_scrobbler_config,
+        get_scrobbler_status, get_song_play_history, get_top_songs, lastfm_begin_auth,
+        lastfm_complete_auth, listenbrainz_validate_token, prune_event_log, relink_song,
+        scan_missing_files, set_alert_config, set_event_log_retention, set_geoip_config,
+        set_missing_file_config, set_report_digest_config, set_scrobbler_config, test_alert_target,
+        test_report_digest, write_event_log,
+    },
+    artwork_commands::{
+        clear_artwork_cache, get_artwork_config, get_song_artwork, set_artwork_config,
     },
     audio_commands::{
-        apply_audio_output_routing, clear_deck_loop, get_audio_output_status, get_deck_state,
-        get_headphone_level, get_headphone_mix, get_local_monitor_muted, get_master_level,
-        get_vu_readings, jog_deck, list_audio_output_devices, load_track, next_deck, pause_deck,
-        play_deck, seek_deck, set_channel_gain, set_deck_bass, set_deck_cue_enabled,
-        set_deck_filter, set_deck_loop, set_deck_pitch, set_deck_tempo, set_headphone_level,
-        set_headphone_mix, set_local_monitor_muted, set_master_level, stop_deck,
+        apply_audio_output_routing, censor_deck, clear_deck_loop, delete_category_gain_trim,
+        double_deck_loop, get_audio_output_status, get_category_gain_trims, get_deck_state,
+        get_headphone_level, get_headphone_mix, get_local_monitor_muted, get_loudness_config,
+        get_loudness_reading, get_master_level, get_multitrack_config, get_multitrack_status,
+        get_spectrum_config, get_spectrum_data, get_vu_readings, halve_deck_loop, jog_deck,
+        list_audio_output_devices, load_track, next_deck, pause_deck, play_deck,
+        reset_loudness_meter, seek_deck, set_category_gain_trim, set_channel_gain, set_deck_bass,
+        set_deck_cue_enabled, set_deck_eq_kill, set_deck_filter, set_deck_keylock, set_deck_loop,
+        set_deck_pitch, set_deck_reverse, set_deck_slip, set_deck_tempo, set_headphone_level,
+        set_headphone_mix, set_local_monitor_muted, set_loudness_config, set_master_level,
+        set_multitrack_config, set_spectrum_config, start_multitrack_recording, stop_deck,
+        stop_multitrack_recording,
+    },
+    beatgrid_commands::{analyze_beatgrid, detect_transition_cues, get_beatgrid},
+    cart_commands::{
+        clear_cart, get_cart_states, get_cart_wall, save_cart, set_active_cart_page,
+        set_cart_wall_layout, stop_all_carts, stop_cart, trigger_cart,
     },
-    beatgrid_commands::{analyze_beatgrid, get_beatgrid},
     controller_commands::{
-        connect_controller, disconnect_controller, get_controller_config, get_controller_status,
-        list_controller_devices, save_controller_config_cmd,
+        connect_controller, delete_controller_profile, disconnect_controller,
+        get_controller_config, get_controller_status, get_osc_config, get_osc_status,
+        list_controller_devices, list_controller_profiles, save_controller_config_cmd,
+        save_controller_profile, set_controller_learn_mode, set_osc_config,
     },
     crossfade_commands::{
-        get_crossfade_config, get_fade_curve_preview, set_crossfade_config, set_manual_crossfade,
-        start_crossfade, trigger_manual_fade,
+        delete_crossfade_profile, delete_crossfade_profile_assignment, get_crossfade_config,
+        get_crossfade_profile_assignments, get_crossfade_profiles, get_fade_curve_preview,
+        save_crossfade_profile, set_crossfade_config, set_crossfade_profile_assignment,
+        set_crossfader_assignment, set_manual_crossfade, start_crossfade, trigger_manual_fade,
     },
     cue_commands::{
-        clear_hot_cue, delete_cue_point, get_cue_points, get_hot_cues, get_monitor_routing_config,
-        jump_to_cue, recolor_hot_cue, rename_hot_cue, set_cue_point, set_deck_cue_preview_enabled,
-        set_hot_cue, set_monitor_routing_config, trigger_hot_cue,
+        clear_hot_cue, clear_saved_loop, delete_cue_point, get_cue_points, get_hot_cues,
+        get_monitor_routing_config, get_quantize_config, get_saved_loops, get_song_playback_flags,
+        import_sam_cues, jump_to_cue, recolor_hot_cue, rename_hot_cue, set_cue_point,
+        set_deck_auto_loop, set_deck_cue_preview_enabled, set_hot_cue, set_monitor_routing_config,
+        set_quantize_config, set_saved_loop, set_song_playback_flags, trigger_hot_cue,
+        trigger_saved_loop,
     },
     dsp_commands::{
         get_channel_dsp, set_channel_agc, set_channel_eq, set_channel_stem_filter,
         set_pipeline_settings,
     },
     encoder_commands::{
-        delete_encoder, get_current_listeners, get_encoder_runtime, get_encoders,
-        get_listener_stats, push_track_metadata, save_encoder, start_all_encoders, start_encoder,
-        start_recording, stop_all_encoders, stop_encoder, stop_recording, test_encoder_connection,
+        delete_encoder, delete_metadata_push_target, detect_stream_watermark, export_show,
+        get_current_listeners, get_encoder_runtime, get_encoders, get_listener_stats,
+        get_metadata_push_status, get_metadata_push_targets, get_show_export_config,
+        get_station_id_gate_config, get_station_id_gate_status, push_track_metadata, save_encoder,
+        save_metadata_push_target, set_show_export_config, set_station_id_gate_config,
+        start_all_encoders, start_encoder, start_recording, stop_all_encoders, stop_encoder,
+        stop_recording, test_encoder_connection, test_metadata_push_target,
+    },
+    enrichment_commands::{
+        apply_tag_proposals, cancel_tag_enrichment, get_tag_enrichment_config, get_tag_proposals,
+        reject_tag_proposals, set_tag_enrichment_config, start_tag_enrichment,
     },
     gateway_commands::{
         connect_gateway, disconnect_gateway, get_autopilot_status, get_gateway_status,
-        get_remote_dj_permissions, get_remote_sessions, kick_remote_dj, set_autopilot,
-        set_mix_minus, set_remote_dj_permissions, start_live_talk, stop_live_talk,
+        get_remote_audio_status, get_remote_dj_permissions, get_remote_sessions, kick_remote_dj,
+        set_autopilot, set_mix_minus, set_remote_dj_permissions, start_live_talk,
+        start_remote_audio, stop_live_talk, stop_remote_audio,
+    },
+    hotkey_commands::{
+        check_hotkey_conflicts, get_hotkey_bindings, get_hotkey_registrations, set_hotkey_bindings,
     },
     mic_commands::{
-        get_audio_input_devices, get_mic_config, save_voice_track, set_mic_config, set_ptt,
-        start_mic, start_voice_recording, stop_mic, stop_voice_recording,
+        delete_voice_track, get_audio_input_devices, get_mic_config, get_mic_duck_config,
+        get_voice_tracks, save_voice_track, save_voice_track_placement, set_mic_config,
+        set_mic_duck_config, set_mic_open, set_ptt, start_mic, start_voice_recording, stop_mic,
+        stop_voice_recording,
     },
     queue_commands::{
         add_to_queue, complete_queue_item, get_history, get_queue, get_song, get_song_types,
@@ -62,32 +119,66 @@ use commands::{
         search_songs, update_song,
     },
     sam_db_commands::{
-        connect_sam_db, create_sam_category, disconnect_sam_db, get_sam_categories,
-        get_sam_db_config_cmd, get_sam_db_status, save_sam_db_config_cmd, test_sam_db_connection,
+        connect_sam_db, create_sam_category, delete_path_translation_rule,
+        discard_failed_sam_writes, disconnect_sam_db, get_failed_sam_writes,
+        get_path_translation_rules, get_sam_cache_stats, get_sam_categories, get_sam_db_config_cmd,
+        get_sam_db_health, get_sam_db_status, get_sam_outbox_status, get_sam_song_changes,
+        get_synced_song, reorder_path_translation_rules, retry_failed_sam_writes,
+        save_path_translation_rule, save_sam_db_config_cmd, save_synced_song,
+        test_path_translation, test_sam_db_connection,
     },
     scheduler_commands::{
-        accept_request_p3, delete_rotation_rule, delete_show, enqueue_next_clockwheel_track,
-        get_autodj_transition_config, get_clockwheel_config, get_dj_mode, get_gap_killer_config,
-        get_last_transition_decision, get_next_autodj_track, get_pending_requests, get_playlists,
-        get_request_history, get_request_policy, get_rotation_rules, get_shows,
-        get_song_directories, get_upcoming_events, recalculate_autodj_plan_now, reject_request_p3,
-        save_clockwheel_config, save_playlist, save_rotation_rule, save_show, set_active_playlist,
-        set_autodj_transition_config, set_dj_mode, set_gap_killer_config, set_request_policy,
+        accept_request_p3, assign_clockwheel_hour, cancel_pending_dj_mode_change, delete_ad_break,
+        delete_relay_event, delete_rotation_rule, delete_show, delete_timed_event,
+        delete_traffic_campaign, enqueue_next_clockwheel_track, export_playlist_m3u,
+        export_queue_m3u, get_active_show_overrides, get_ad_breaks, get_autodj_plan,
+        get_autodj_transition_config, get_bad_tracks, get_clockwheel_config,
+        get_clockwheel_hour_grid, get_clockwheel_templates, get_dj_mode, get_gap_killer_config,
+        get_ghost_queue, get_last_rotation_decision, get_last_transition_decision,
+        get_next_autodj_track, get_pending_dj_mode_change, get_pending_requests,
+        get_playlist_cursor, get_playlist_songs, get_playlists, get_relay_events, get_relay_status,
+        get_request_api_config, get_request_api_status, get_request_history, get_request_policy,
+        get_rotation_rules, get_shows, get_song_directories, get_timed_events,
+        get_traffic_campaigns, get_traffic_spot_log, get_upcoming_events, import_playlist_file,
+        queue_playlist_file, recalculate_autodj_plan_now, reject_request_p3, release_bad_track,
+        reorder_autodj_plan, replace_autodj_plan_item, request_dj_mode_change, save_ad_break,
+        save_clockwheel_config, save_clockwheel_template, save_playlist, save_relay_event,
+        save_rotation_rule, save_show, save_timed_event, save_traffic_campaign,
+        set_active_playlist, set_autodj_plan_pin, set_autodj_transition_config, set_dj_mode,
+        set_gap_killer_config, set_playlist_cursor, set_playlist_songs, set_request_api_config,
+        set_request_policy, simulate_rotation, stop_relay, submit_song_request,
+        triage_pending_requests, veto_ghost_queue_entry,
+    },
+    script_commands::{
+        delete_script, get_script_api, get_script_log, get_script_runs, get_scripts, run_script,
+        save_script,
+    },
+    session_commands::{discard_previous_session, get_previous_session, resume_previous_session},
+    settings_commands::{
+        apply_sam_import, create_backup, export_settings, get_backup_config, get_db_schema_info,
+        get_emit_rate_config, import_settings, inspect_settings_archive, list_backups,
+        preview_sam_import, restore_backup, set_backup_config, set_emit_rate_config,
     },
-    script_commands::{delete_script, get_script_log, get_scripts, run_script, save_script},
+    sfx_commands::{get_sfx_voices, play_sfx, stop_sfx},
     stem_commands::{
-        analyze_stems, get_latest_stem_analysis, get_stem_analysis, get_stems_runtime_status,
-        install_stems_runtime, set_deck_stem_source,
+        analyze_stems, cancel_stem_jobs, get_latest_stem_analysis, get_stem_analysis,
+        get_stems_runtime_status, install_stems_runtime, queue_stem_analysis, set_deck_stem_mix,
+        set_deck_stem_source,
     },
-    stream_commands::{get_stream_status, start_stream, stop_stream},
-    waveform_commands::get_waveform_data,
+    stream_commands::{
+        get_overlay_server_config, get_overlay_server_status, get_stream_status,
+        set_overlay_server_config, start_stream, stop_stream,
+    },
+    waveform_commands::{get_waveform_chunk, get_waveform_data},
 };
 use state::AppState;
 use tauri::{Emitter, Manager};
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
-    init_logging();
+    let app_data_dir = compute_app_data_dir();
+    std::fs::create_dir_all(&app_data_dir).expect("Failed to create app data dir");
+    logging::init(&std::path::Path::new(&app_data_dir).join("logs"));
     let engine = audio::engine::AudioEngine::new().expect("Failed to initialise audio engine");
 
     // ── Database initialisation ──────────────────────────────────────────────
@@ -96,8 +187,6 @@ pub fn run() {
     // Use a short-lived single-thread Tokio runtime for the async init work;
     // Tauri will create its own multi-thread runtime afterward.
 
-    let app_data_dir = compute_app_data_dir();
-    std::fs::create_dir_all(&app_data_dir).expect("Failed to create app data dir");
     let db_path = format!("{app_data_dir}/app.db");
 
     let (
@@ -108,15 +197,32 @@ pub fn run() {
         startup_autodj_cfg,
         startup_monitor_cfg,
         startup_controller_cfg,
+        startup_controller_profile,
+        startup_duck_cfg,
+        startup_quantize_cfg,
+        startup_user_count,
     ) = tokio::runtime::Builder::new_current_thread()
         .enable_all()
         .build()
         .expect("Failed to build init Tokio runtime")
         .block_on(async {
-            // 1. SQLite (always required)
-            let local = db::local::init_db(&db_path)
-                .await
-                .expect("Failed to open local SQLite database");
+            // 1. SQLite (always required). A restore staged by `restore_backup`
+            // is swapped in first; a corrupt database falls back to the newest
+            // backup.
+            db::backup::apply_pending_restore(std::path::Path::new(&db_path));
+            let local = match db::local::init_db(&db_path).await {
+                Ok(pool) => pool,
+                Err(e) if db::backup::is_corruption(&e) => {
+                    log::error!("Local SQLite database failed to open: {e}");
+                    let backup = db::backup::recover_from_latest(std::path::Path::new(&db_path))
+                        .expect("Local SQLite database is unreadable and has no backup");
+                    log::warn!("Recovered local database from backup {}", backup.id);
+                    db::local::init_db(&db_path)
+                        .await
+                        .expect("Failed to open restored local SQLite database")
+                }
+                Err(e) => panic!("Failed to open local SQLite database: {e}"),
+            };
 
             // Load persisted DJ mode into runtime state at startup.
             if let Ok(saved_mode) = db::local::get_runtime_dj_mode(&local).await {
@@ -138,6 +244,18 @@ pub fn run() {
                 startup_crossfade_cfg = Some(cfg);
             }
             let startup_monitor_cfg = db::local::get_monitor_routing_config(&local).await.ok();
+            let startup_duck_cfg = db::local::load_mic_duck_config(&local)
+                .await
+                .ok()
+                .flatten()
+                .and_then(|json| {
+                    serde_json::from_str::<crate::audio::ducking::DuckConfig>(&json).ok()
+                });
+            let startup_quantize_cfg = db::local::load_quantize_config(&local)
+                .await
+                .ok()
+                .flatten()
+                .and_then(|json| serde_json::from_str::<db::local::QuantizeConfig>(&json).ok());
             let startup_controller_cfg =
                 db::local::get_controller_config(&local)
                     .await
@@ -148,41 +266,47 @@ pub fn run() {
                         preferred_device_id: cfg.preferred_device_id,
                         profile: cfg.profile,
                     });
+            let startup_controller_profile = match &startup_controller_cfg {
+                Some(cfg) => crate::controller::mapping::load_profile(&local, &cfg.profile)
+                    .await
+                    .unwrap_or_else(|e| {
+                        log::warn!("{e}; using the built-in controller profile");
+                        None
+                    }),
+                None => None,
+            };
             let startup_encoders = match db::local::load_encoder_configs(&local).await {
                 Ok(v) => v,
                 Err(e) => {
-                    eprintln!("[startup] failed to load encoder configs (continuing): {e}");
+                    log::warn!("Failed to load encoder configs (continuing): {e}");
                     Vec::new()
                 }
             };
-            eprintln!(
-                "[startup] loaded {} encoder config(s)",
-                startup_encoders.len()
-            );
+            log::info!("Loaded {} encoder config(s)", startup_encoders.len());
+            let startup_user_count = crate::access::count_users(&local).await.unwrap_or(0);
 
             // 2. SAM MySQL — attempt auto-connect if configured
             let sam_opt = match db::local::load_sam_db_config_full(&local).await {
                 Ok(Some(cfg)) if cfg.config.auto_connect => {
-                    let enc_pw = urlencoding::encode(&cfg.password);
-                    let url = format!(
-                        "mysql://{}:{}@{}:{}/{}",
-                        cfg.config.username,
-                        enc_pw,
-                        cfg.config.host,
+                    let url = db::sam::mysql_url(
+                        &cfg.config.host,
                         cfg.config.port,
-                        cfg.config.database_name,
+                        &cfg.config.username,
+                        &cfg.password,
+                        &cfg.config.database_name,
                     );
                     match db::sam::connect(&url).await {
                         Ok(pool) => {
-                            eprintln!(
-                                "[startup] SAM DB auto-connected → {}:{}",
-                                cfg.config.host, cfg.config.database_name
+                            log::info!(
+                                "SAM DB auto-connected → {}:{}",
+                                cfg.config.host,
+                                cfg.config.database_name
                             );
                             Some(pool)
                         }
                         Err(e) => {
                             // Non-fatal — app works without SAM DB
-                            eprintln!("[startup] SAM DB auto-connect failed (continuing): {e}");
+                            log::warn!("SAM DB auto-connect failed (continuing): {e}");
                             None
                         }
                     }
@@ -198,17 +322,28 @@ pub fn run() {
                 startup_autodj_cfg,
                 startup_monitor_cfg,
                 startup_controller_cfg,
+                startup_controller_profile,
+                startup_duck_cfg,
+                startup_quantize_cfg,
+                startup_user_count,
             )
         });
 
     // ── AppState assembly ────────────────────────────────────────────────────
     let mut app_state = AppState::new(engine).with_local_db(local_pool);
+    crate::audio::loudness_meter::attach(&mut app_state.engine.lock().unwrap());
     if let Some(cfg) = startup_crossfade_cfg {
         let _ = app_state.engine.lock().unwrap().set_crossfade_config(cfg);
     }
     if let Some(cfg) = startup_autodj_cfg {
         crate::scheduler::autodj::set_auto_transition_config(cfg);
     }
+    if let Some(cfg) = startup_duck_cfg {
+        let _ = app_state.engine.lock().unwrap().set_duck_config(cfg);
+    }
+    if let Some(cfg) = startup_quantize_cfg {
+        app_state.engine.lock().unwrap().set_quantize_config(cfg);
+    }
     if let Some(cfg) = startup_monitor_cfg {
         let mode = match cfg.cue_mix_mode.as_str() {
             "single_device_four_channel" => {
@@ -231,6 +366,9 @@ pub fn run() {
     if let Some(cfg) = startup_controller_cfg {
         app_state.controller_service.set_config(cfg, None);
     }
+    app_state
+        .controller_service
+        .set_mapping_profile(startup_controller_profile);
     for cfg in startup_encoders {
         let assigned = app_state.encoder_manager.save_encoder(cfg.clone());
         if assigned != cfg.id {
@@ -244,6 +382,7 @@ pub fn run() {
     if let Some(pool) = sam_pool_opt {
         app_state = app_state.with_sam_db(pool);
     }
+    app_state.access.set_enforced(startup_user_count > 0);
 
     // ── Tauri app ────────────────────────────────────────────────────────────
     tauri::Builder::default()
@@ -278,24 +417,182 @@ pub fn run() {
                 }
             }
 
+            // ── Error log → event_log ────────────────────────────────────────
+            if let Some(pool) = app.state::<AppState>().local_db.clone() {
+                crate::logging::spawn_event_log_sink(pool);
+            }
+
+            // ── Scripts: station API, event and schedule triggers ────────────
+            app.state::<AppState>()
+                .script_engine
+                .attach(app.handle().clone());
+            crate::scripting::trigger::start(app.handle().clone());
+
+            // ── Crash recovery snapshots ─────────────────────────────────────
+            crate::recovery::start(app.handle().clone());
+
+            // ── Health watchdog ──────────────────────────────────────────────
+            app.state::<AppState>()
+                .health_monitor
+                .clone()
+                .start_monitoring(app.handle().clone());
+
+            // ── Listener request HTTP API ────────────────────────────────────
+            let app_handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                let config = {
+                    let state = app_handle.state::<AppState>();
+                    let Some(pool) = state.local_db.as_ref() else {
+                        return;
+                    };
+                    crate::scheduler::request_api::get_config(pool)
+                        .await
+                        .unwrap_or_default()
+                };
+                if config.enabled {
+                    if let Err(e) = crate::scheduler::request_api::start(app_handle, config).await {
+                        log::warn!("{e}");
+                    }
+                }
+            });
+
+            // ── Overlay WebSocket feed ───────────────────────────────────────
+            let app_handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                let config = {
+                    let state = app_handle.state::<AppState>();
+                    let Some(pool) = state.local_db.as_ref() else {
+                        return;
+                    };
+                    crate::stream::overlay_server::get_config(pool)
+                        .await
+                        .unwrap_or_default()
+                };
+                if config.enabled {
+                    if let Err(e) = crate::stream::overlay_server::start(app_handle, config).await
+                    {
+                        log::warn!("{e}");
+                    }
+                }
+            });
+
+            // ── OSC remote control surface ───────────────────────────────────
+            let app_handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                let config = {
+                    let state = app_handle.state::<AppState>();
+                    let Some(pool) = state.local_db.as_ref() else {
+                        return;
+                    };
+                    crate::controller::osc::get_config(pool)
+                        .await
+                        .unwrap_or_default()
+                };
+                if config.enabled {
+                    if let Err(e) = crate::controller::osc::start(app_handle, config).await {
+                        log::warn!("{e}");
+                    }
+                }
+            });
+
+            // ── Cart wall preload ────────────────────────────────────────────
+            // Decodes saved carts into memory, then registers cart hotkeys and
+            // the global hotkey bindings together.
+            let app_handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                let state = app_handle.state::<AppState>();
+                let Some(pool) = state.local_db.as_ref() else {
+                    return;
+                };
+                match crate::audio::cart_wall::preload_all(pool).await {
+                    Ok(n) => log::info!("Cart wall: {n} carts loaded"),
+                    Err(e) => log::warn!("Cart wall preload failed: {e}"),
+                }
+                if let Err(e) = crate::controller::hotkeys::load(pool).await {
+                    log::warn!("Hotkey bindings not loaded: {e}");
+                }
+                crate::controller::hotkeys::sync(&app_handle);
+            });
+
+            // ── Scrobble queue drain ─────────────────────────────────────────
+            // Retries scrobbles queued while Last.fm / ListenBrainz were
+            // unreachable.
+            let app_handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                let mut interval = tokio::time::interval(std::time::Duration::from_secs(120));
+                loop {
+                    interval.tick().await;
+                    let state = app_handle.state::<AppState>();
+                    if let Some(pool) = state.local_db.as_ref() {
+                        if let Err(e) = crate::analytics::scrobbler::drain_queue(pool).await {
+                            log::warn!("Scrobble queue drain failed: {e}");
+                        }
+                    }
+                }
+            });
+
+            // ── Background analysis queue ────────────────────────────────────
+            // Beat grid / waveform / loudness for upcoming queue items, plus
+            // anything queued from the UI.
+            crate::audio::analyzer::jobs::start(app.handle().clone());
+
+            // ── Relay scheduler ──────────────────────────────────────────────
+            crate::scheduler::relay::start(app.handle().clone());
+
+            // ── Show overrides ───────────────────────────────────────────────
+            crate::scheduler::show_scheduler::start(app.handle().clone());
+
+            // ── Database backups ─────────────────────────────────────────────
+            crate::db::backup::start(app.handle().clone());
+
+            // ── Report digest email ──────────────────────────────────────────
+            crate::analytics::report_digest::start(app.handle().clone());
+
+            // ── Event log search index + retention ───────────────────────────
+            crate::analytics::event_retention::start(app.handle().clone());
+
+            // ── SAM write-behind outbox ──────────────────────────────────────
+            crate::db::sam_outbox::start(app.handle().clone());
+
+            // ── SAM song metadata sync ───────────────────────────────────────
+            crate::db::sam_sync::start(app.handle().clone());
+
+            // ── SAM connection health / auto-reconnect ───────────────────────
+            crate::db::sam_health::start(app.handle().clone());
+
             // ── Background polling loop ──────────────────────────────────────
-            // Emits `deck_state_changed` (every 80 ms) and `vu_meter` events
-            // to the frontend, since the audio engine is poll-based (no push).
+            // Polls the engine every 80 ms, since it is poll-based (no push).
+            // Deck state, VU and crossfade samples that changed go to the
+            // frontend batched as one `engine_tick` event, at the configured
+            // rate (slower while the window is unfocused). Emits go through a
+            // coalescing queue so a stalled webview cannot build an unbounded
+            // backlog or starve the runtime.
             let app_handle = app.handle().clone();
             tauri::async_runtime::spawn(async move {
+                use crate::analytics::emit_metrics::EmitQueue;
                 use crate::audio::crossfade::DeckId;
                 use std::time::Duration;
                 use tauri::{Emitter, Manager};
 
                 let state = app_handle.state::<AppState>();
-                let mut interval = tokio::time::interval(Duration::from_millis(80));
+                let tick_period = Duration::from_millis(80);
+                let mut interval = tokio::time::interval(tick_period);
+                interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+                let mut emit_queue = EmitQueue::new();
                 let mut last_manual_crossfade_pos: Option<f32> = None;
                 let mut last_master_level: Option<f32> = None;
                 let mut last_audio_status: Option<crate::audio::device_manager::AudioOutputStatus> =
                     None;
+                let mut last_duck_state: Option<(bool, bool)> = None;
+                let mut last_on_air: Option<crate::stream::metadata_fanout::TrackKey> = None;
 
                 loop {
-                    interval.tick().await;
+                    let deadline = interval.tick().await;
+                    let tick_started = std::time::Instant::now();
+                    emit_queue.begin_tick(
+                        tick_started.saturating_duration_since(deadline.into_std()),
+                        tick_period,
+                    );
 
                     // Collect data while holding the engine lock briefly,
                     // then release it before emitting (avoid holding across await).
@@ -306,9 +603,12 @@ pub fn run() {
                         manual_crossfade_pos,
                         master_level,
                         audio_status,
+                        duck_state,
+                        output_sample_rate,
                     ) = {
                         let mut engine = state.engine.lock().unwrap();
                         let _ = engine.maybe_auto_fallback_output();
+                        engine.flush_commands();
                         let deck_events: Vec<_> = [
                             DeckId::DeckA,
                             DeckId::DeckB,
@@ -325,6 +625,7 @@ pub fn run() {
                         let manual_crossfade_pos = engine.get_manual_crossfade_pos();
                         let master_level = engine.get_master_level();
                         let audio_status = engine.get_audio_output_status();
+                        let duck_state = engine.get_duck_state();
                         (
                             deck_events,
                             vu_events,
@@ -332,26 +633,77 @@ pub fn run() {
                             manual_crossfade_pos,
                             master_level,
                             audio_status,
+                            duck_state,
+                            engine.output_sample_rate(),
                         )
                     };
 
+                    // Announce track starts to external now-playing services.
+                    let on_air = crate::scheduler::request_api::on_air_deck(&deck_events);
+                    // A remote stream's ICY title changes count as new tracks.
+                    let on_air_key = on_air.map(crate::stream::metadata_fanout::track_key);
+                    if on_air_key != last_on_air {
+                        if let Some(deck) = on_air {
+                            crate::stream::metadata_fanout::track_started(&app_handle, deck);
+                            fire_script_track_start(&app_handle, deck);
+                            let stream_title = deck
+                                .remote_stream
+                                .as_ref()
+                                .and_then(|r| r.title.clone());
+                            if let Some(stream_title) = stream_title {
+                                let app = app_handle.clone();
+                                tauri::async_runtime::spawn(async move {
+                                    let (artist, title) =
+                                        crate::audio::remote_stream::split_stream_title(
+                                            &stream_title,
+                                        );
+                                    app.state::<AppState>()
+                                        .encoder_manager
+                                        .push_metadata(&artist, &title, None)
+                                        .await;
+                                });
+                            }
+                        }
+                        crate::stream::overlay_server::track_changed(&app_handle);
+                        last_on_air = on_air_key;
+                    }
+
                     for ev in &deck_events {
-                        let _ = app_handle.emit("deck_state_changed", ev);
+                        crate::stream::overlay_server::publish("deck_state_changed", ev);
                     }
                     for ev in &vu_events {
-                        let _ = app_handle.emit("vu_meter", ev);
+                        crate::stream::overlay_server::publish("vu_meter", ev);
                     }
-                    if let Some(ev) = &crossfade_event {
-                        let _ = app_handle.emit("crossfade_progress", ev);
+                    let loudness = crate::audio::loudness_meter::poll(output_sample_rate);
+
+                    if emit_queue.stream_due() {
+                        for ev in &deck_events {
+                            emit_queue.offer_stream("deck_state_changed", &ev.deck, ev);
+                        }
+                        for ev in &vu_events {
+                            emit_queue.offer_stream("vu_meter", &ev.channel, ev);
+                        }
+                        if let Some(ev) = &crossfade_event {
+                            emit_queue.offer_stream("crossfade_progress", "", ev);
+                        }
+                        if let Some(reading) = &loudness {
+                            emit_queue.offer_stream("loudness", "", reading);
+                        }
+                    }
+                    // Paced by the analyzer's own interval; still batched
+                    // into `engine_tick` with the other streams.
+                    for frame in crate::audio::spectrum::poll() {
+                        emit_queue.offer_stream("spectrum", &frame.channel, &frame);
                     }
                     let should_emit_manual = last_manual_crossfade_pos
                         .map(|prev| (prev - manual_crossfade_pos).abs() > 0.001)
                         .unwrap_or(true);
                     if should_emit_manual {
                         last_manual_crossfade_pos = Some(manual_crossfade_pos);
-                        let _ = app_handle.emit(
+                        emit_queue.offer_state(
                             "manual_crossfade_changed",
-                            serde_json::json!({ "position": manual_crossfade_pos }),
+                            "",
+                            &serde_json::json!({ "position": manual_crossfade_pos }),
                         );
                     }
                     let should_emit_master = last_master_level
@@ -359,9 +711,10 @@ pub fn run() {
                         .unwrap_or(true);
                     if should_emit_master {
                         last_master_level = Some(master_level);
-                        let _ = app_handle.emit(
+                        emit_queue.offer_state(
                             "master_volume_changed",
-                            serde_json::json!({ "level": master_level }),
+                            "",
+                            &serde_json::json!({ "level": master_level }),
                         );
                     }
                     let should_emit_audio_status = last_audio_status
@@ -370,13 +723,24 @@ pub fn run() {
                         .unwrap_or(true);
                     if should_emit_audio_status {
                         last_audio_status = Some(audio_status.clone());
-                        let _ =
-                            app_handle.emit("audio_output_status_changed", audio_status.clone());
+                        emit_queue.offer_state("audio_output_status_changed", "", &audio_status);
                         if let Some(msg) = audio_status.last_error {
-                            let _ = app_handle
-                                .emit("audio_output_error", serde_json::json!({ "message": msg }));
+                            emit_queue.offer_state(
+                                "audio_output_error",
+                                "",
+                                &serde_json::json!({ "message": msg }),
+                            );
                         }
                     }
+                    let duck_key = (duck_state.mic_open, duck_state.ducking);
+                    if last_duck_state != Some(duck_key) {
+                        last_duck_state = Some(duck_key);
+                        emit_queue.offer_state("mic_duck_changed", "", &duck_state);
+                    }
+
+                    emit_queue.flush(tick_started, |event, payload| {
+                        let _ = app_handle.emit(event, payload);
+                    });
                 }
             });
 
@@ -404,10 +768,33 @@ pub fn run() {
                     crate::stream::broadcaster::EncoderRuntimeState,
                 > = HashMap::new();
 
+                // Client lists (sessions, unique listeners) are heavier than
+                // the counts, so fetch them every sixth tick (30 s).
+                let mut tick: u64 = 0;
+
                 loop {
                     interval.tick().await;
+                    let with_clients = tick.is_multiple_of(6);
+                    tick += 1;
 
                     state.encoder_manager.refresh_runtime_counters();
+                    for action in state.encoder_manager.supervise_failover() {
+                        if let Some(pool) = state.local_db.as_ref() {
+                            let _ = crate::analytics::log_event(
+                                pool,
+                                crate::analytics::LogLevel::Warn,
+                                crate::analytics::EventCategory::Stream,
+                                "encoder_failover",
+                                &action.describe(),
+                                serde_json::to_value(&action).ok(),
+                                None,
+                                None,
+                                Some(action.encoder_id()),
+                            )
+                            .await;
+                        }
+                        let _ = app_handle.emit("encoder_failover", action);
+                    }
                     let runtime_list = state.encoder_manager.get_all_runtime();
                     let mut runtime_map: HashMap<
                         i64,
@@ -461,44 +848,15 @@ pub fn run() {
                             continue;
                         }
 
-                        let host = cfg.server_host.as_deref().unwrap_or("localhost");
-                        let port = cfg.server_port.unwrap_or(8000);
-                        let password = cfg.server_password.as_deref().unwrap_or("");
-                        let poll = match cfg.output_type {
-                            OutputType::Icecast => {
-                                let mount = cfg.mount_point.as_deref().unwrap_or("/stream");
-                                icecast_stats::poll_icecast(host, port, password, mount, cfg.id)
-                                    .await
-                            }
-                            OutputType::Shoutcast => {
-                                icecast_stats::poll_shoutcast(
-                                    host,
-                                    port,
-                                    password,
-                                    cfg.shoutcast_sid,
-                                    cfg.id,
-                                )
-                                .await
-                            }
-                            OutputType::File => unreachable!(),
-                        };
+                        let poll =
+                            icecast_stats::collect(state.local_db.as_ref(), &cfg, with_clients)
+                                .await;
 
                         match poll {
                             Ok(snap) => {
                                 state
                                     .encoder_manager
                                     .update_listeners(cfg.id, snap.current_listeners);
-                                if let Some(pool) = state.local_db.as_ref() {
-                                    if let Err(e) =
-                                        icecast_stats::insert_snapshot(pool, &snap).await
-                                    {
-                                        log::warn!(
-                                            "listener snapshot insert failed for encoder {}: {}",
-                                            cfg.id,
-                                            e
-                                        );
-                                    }
-                                }
                                 let _ = app_handle.emit(
                                     "listener_count_updated",
                                     serde_json::json!({
@@ -512,8 +870,8 @@ pub fn run() {
                                     "listener poll failed for encoder {} (type={:?} host={} port={} sid={} mount={}): {}",
                                     cfg.id,
                                     cfg.output_type,
-                                    host,
-                                    port,
+                                    cfg.server_host.as_deref().unwrap_or("localhost"),
+                                    cfg.server_port.unwrap_or(8000),
                                     cfg.shoutcast_sid,
                                     cfg.mount_point.as_deref().unwrap_or("/stream"),
                                     e
@@ -529,6 +887,7 @@ pub fn run() {
             let app_handle = app.handle().clone();
             tauri::async_runtime::spawn(async move {
                 use crate::audio::crossfade::{CrossfadeTriggerMode, DeckId};
+                use crate::audio::deck::StopReason;
                 use crate::scheduler::autodj::{
                     self, AutodjTransitionEngine, DjMode, TransitionDecisionDebug,
                 };
@@ -541,20 +900,42 @@ pub fn run() {
 
                 let state = app_handle.state::<AppState>();
                 let mut interval = tokio::time::interval(Duration::from_millis(100));
-                let mut marker_cache: HashMap<
-                    i64,
-                    crate::scheduler::transition_planner::TransitionMarkers,
-                > = HashMap::new();
+                let mut marker_cache = crate::scheduler::transition_planner::MarkerCache::default();
                 let mut pending_gap: Option<PendingGapTransition> = None;
                 let mut pending_sam_start: Option<PendingSamTransition> = None;
                 let mut sam_below_threshold_since: HashMap<DeckId, std::time::Instant> =
                     HashMap::new();
                 let mut claimed_queue_ids: HashSet<i64> = HashSet::new();
+                // One RNG for every rotation pick, so a seeded run repeats.
+                let mut selection_rng = crate::scheduler::rotation::selection_rng();
+                let mut active_voice: Option<ActiveVoiceTrack> = None;
+                let mut voice_checked_song: Option<i64> = None;
+                let mut sweeper_checked_song: Option<i64> = None;
+                let mut timed_events: Vec<crate::scheduler::timed_events::TimedEvent> =
+                    Vec::new();
+                let mut timed_events_loaded_at: Option<Instant> = None;
+                // (event id, occurrence) that failed to fire, and when
+                let mut timed_event_failures: HashMap<(Option<i64>, i64), Instant> =
+                    HashMap::new();
+                let mut ad_breaks: Vec<crate::scheduler::traffic::AdBreak> = Vec::new();
+                let mut traffic_spots: std::collections::VecDeque<
+                    crate::scheduler::traffic::PlannedSpot,
+                > = std::collections::VecDeque::new();
+                // The planned break, and whether its cue-out has gone out.
+                let mut ad_break_cue: Option<crate::stream::ad_cues::AdBreakCue> = None;
+                let mut ad_break_on_air = false;
+                let mut flags_cache: HashMap<i64, crate::db::local::SongPlaybackFlags> =
+                    HashMap::new();
+                let mut profile_cache: HashMap<
+                    Option<i64>,
+                    Option<crate::audio::crossfade::CrossfadeConfig>,
+                > = HashMap::new();
                 let mut last_queue_topup_at = Instant::now()
                     .checked_sub(Duration::from_secs(5))
                     .unwrap_or_else(Instant::now);
                 const SAM_HOLD_MS: u32 = 120;
                 const SAM_PREROLL_MIN_MS: u64 = 150;
+                const TIMED_EVENT_RETRY: Duration = Duration::from_secs(5);
                 const SAM_PREROLL_TIMEOUT_MS: u64 = 800;
                 const SAM_RELEASE_HYST_DB: f32 = 0.5;
                 const SAM_RECUE_NEAR_END_MS: u64 = 1000;
@@ -564,9 +945,14 @@ pub fn run() {
 
                     if crate::scheduler::autodj::take_replan_requested() {
                         marker_cache.clear();
+                        flags_cache.clear();
+                        profile_cache.clear();
+                        sweeper_checked_song = None;
+                        timed_events_loaded_at = None;
                         pending_gap = None;
                         pending_sam_start = None;
                         sam_below_threshold_since.clear();
+                        voice_checked_song = None;
                     }
 
                     // Handle completed tracks (EOF) for queue/history bookkeeping
@@ -580,27 +966,88 @@ pub fn run() {
                         }
                     }
 
+                    crate::scheduler::mode_transition::poll_pending(&app_handle).await;
                     let mode = crate::scheduler::autodj::get_dj_mode();
+
+                    // ── Station ID sign-on ──────────────────────────────────
+                    // AutoDJ arms the element as a hard link so it airs first;
+                    // in the other modes it simply plays over the live deck.
+                    if let Some(gate) = crate::stream::station_id_gate::take_sign_on() {
+                        if mode == DjMode::AutoDj
+                            && (active_voice.is_some() || !traffic_spots.is_empty())
+                        {
+                            crate::stream::station_id_gate::request_sign_on(gate);
+                        } else {
+                            use crate::scheduler::timed_events::TimedEventMode;
+                            let rotation = autodj::get_auto_transition_config().deck_rotation();
+                            let (a, b) = {
+                                let engine = state.engine.lock().unwrap();
+                                let (a_deck, b_deck) = autodj_deck_pair(&engine, &rotation);
+                                (engine.get_deck_state(a_deck), engine.get_deck_state(b_deck))
+                            };
+                            let event = gate.sign_on_event(if mode == DjMode::AutoDj {
+                                TimedEventMode::Hard
+                            } else {
+                                TimedEventMode::Soft
+                            });
+                            match fire_timed_event(&state, &event, &a, &b).await {
+                                Some(link) if mode == DjMode::AutoDj => {
+                                    pending_sam_start = None;
+                                    active_voice = Some(link);
+                                }
+                                Some(mut link) => {
+                                    play_link(&mut state.engine.lock().unwrap(), &mut link);
+                                }
+                                None => log::warn!("Station ID sign-on element could not be loaded"),
+                            }
+                        }
+                    }
+
+                    // A break interrupted by leaving AutoDJ is not resumed later.
+                    if mode != DjMode::AutoDj && !traffic_spots.is_empty() {
+                        if let Some(pool) = &state.local_db {
+                            for spot in traffic_spots.drain(..) {
+                                let _ =
+                                    crate::scheduler::traffic::mark_spot_missed(pool, spot.log_id)
+                                        .await;
+                            }
+                        }
+                        traffic_spots.clear();
+                    }
+
                     if mode == DjMode::Manual {
                         continue;
                     }
+                    // A relay has the air; it hands back when it ends or drops.
+                    if crate::scheduler::relay::holds_autodj() {
+                        continue;
+                    }
 
                     if mode == DjMode::AutoDj
                         && last_queue_topup_at.elapsed() >= Duration::from_secs(1)
                     {
-                        top_up_rotation_queue(&state, &claimed_queue_ids).await;
+                        top_up_rotation_queue(&state, &claimed_queue_ids, &mut selection_rng).await;
                         last_queue_topup_at = Instant::now();
                     }
 
-                    let (a, b, crossfade_active): (
+                    // `a` is the rotation deck on air (or next to start) and
+                    // `b` the one after it: Deck A/B unless a longer deck
+                    // rotation is configured.
+                    let rotation = autodj::get_auto_transition_config().deck_rotation();
+                    let (a_deck, b_deck, a, b, crossfade_active): (
+                        DeckId,
+                        DeckId,
                         Option<crate::audio::engine::DeckStateEvent>,
                         Option<crate::audio::engine::DeckStateEvent>,
                         bool,
                     ) = {
                         let engine = state.engine.lock().unwrap();
+                        let (a_deck, b_deck) = autodj_deck_pair(&engine, &rotation);
                         (
-                            engine.get_deck_state(DeckId::DeckA),
-                            engine.get_deck_state(DeckId::DeckB),
+                            a_deck,
+                            b_deck,
+                            engine.get_deck_state(a_deck),
+                            engine.get_deck_state(b_deck),
                             engine.get_crossfade_progress_event().is_some(),
                         )
                     };
@@ -624,60 +1071,231 @@ pub fn run() {
 
                     if let Some(gap) = pending_gap.clone() {
                         if std::time::Instant::now() >= gap.start_at {
-                            let side = if gap.incoming == DeckId::DeckB {
-                                1.0
-                            } else {
-                                -1.0
-                            };
                             let mut engine = state.engine.lock().unwrap();
-                            let _ = engine.set_manual_crossfade(side);
+                            let _ = engine.focus_crossfader(gap.incoming);
                             let _ = engine.play(gap.incoming);
                             pending_gap = None;
                         }
                         continue;
                     }
 
+                    // ── Exact-time events ───────────────────────────────────
+                    // A due event is armed like a link on the Sound FX deck so it
+                    // plays ahead of the next rotation pick.
+                    if mode == DjMode::AutoDj && active_voice.is_none() {
+                        if let Some(pool) = &state.local_db {
+                            if timed_events_loaded_at
+                                .is_none_or(|t| t.elapsed() >= Duration::from_secs(30))
+                            {
+                                timed_events =
+                                    crate::scheduler::timed_events::get_timed_events(pool)
+                                        .await
+                                        .unwrap_or_default();
+                                ad_breaks = crate::scheduler::traffic::get_ad_breaks(pool)
+                                    .await
+                                    .unwrap_or_default();
+                                timed_events_loaded_at = Some(Instant::now());
+                            }
+                            let now = chrono::Local::now();
+                            // An event is only marked fired once it is armed; a
+                            // failed one is retried while it is still in grace.
+                            timed_event_failures.retain(|_, at| at.elapsed() < TIMED_EVENT_RETRY);
+                            let due = timed_events.iter_mut().find_map(|event| {
+                                let occurrence = event.due_at(&now)?;
+                                (!timed_event_failures.contains_key(&(event.id, occurrence)))
+                                    .then_some((event, occurrence))
+                            });
+                            if let Some((event, occurrence)) = due {
+                                active_voice = fire_timed_event(&state, event, &a, &b).await;
+                                if active_voice.is_none() {
+                                    log::warn!(
+                                        "Timed event '{}' could not be armed; retrying",
+                                        event.name
+                                    );
+                                    timed_event_failures
+                                        .insert((event.id, occurrence), Instant::now());
+                                } else {
+                                    event.last_fired_at = Some(occurrence);
+                                    if let Some(id) = event.id {
+                                        let _ =
+                                            crate::scheduler::timed_events::mark_timed_event_fired(
+                                                pool, id, occurrence,
+                                            )
+                                            .await;
+                                    }
+                                    pending_sam_start = None;
+                                    continue;
+                                }
+                            }
+
+                            // Ad breaks: fill a due break, then air its spots
+                            // back to back before releasing the next song.
+                            if traffic_spots.is_empty() {
+                                let due = ad_breaks.iter_mut().find_map(|brk| {
+                                    brk.due_at(&now).map(|occurrence| (brk, occurrence))
+                                });
+                                if let Some((brk, occurrence)) = due {
+                                    brk.last_fired_at = Some(occurrence);
+                                    let brk = brk.clone();
+                                    if let Some(id) = brk.id {
+                                        let _ = crate::scheduler::traffic::mark_ad_break_fired(
+                                            pool, id, occurrence,
+                                        )
+                                        .await;
+                                    }
+                                    match crate::scheduler::traffic::plan_break(
+                                        pool, &brk, occurrence, &now,
+                                    )
+                                    .await
+                                    {
+                                        Ok(spots) => {
+                                            log::info!(
+                                                "Ad break '{}' planned with {} spot(s)",
+                                                brk.name,
+                                                spots.len()
+                                            );
+                                            ad_break_cue = Some(
+                                                crate::stream::ad_cues::AdBreakCue::from_plan(
+                                                    &brk, &spots,
+                                                ),
+                                            );
+                                            ad_break_on_air = false;
+                                            traffic_spots = spots.into();
+                                        }
+                                        Err(err) => {
+                                            log::warn!("Failed to plan ad break '{}': {}", brk.name, err)
+                                        }
+                                    }
+                                }
+                            }
+                            while let Some(spot) = traffic_spots.pop_front() {
+                                let event = spot.as_timed_event();
+                                if let Some(mut link) = fire_timed_event(&state, &event, &a, &b).await {
+                                    link.spot_log_id = Some(spot.log_id);
+                                    link.hold_next = !traffic_spots.is_empty();
+                                    active_voice = Some(link);
+                                    break;
+                                }
+                                let _ =
+                                    crate::scheduler::traffic::mark_spot_missed(pool, spot.log_id)
+                                        .await;
+                            }
+                            if active_voice.is_some() {
+                                pending_sam_start = None;
+                                continue;
+                            }
+                        }
+                    }
+
+                    // ── Voice track links ───────────────────────────────────
+                    // A link placed after the on-air song is armed on the Voice FX
+                    // deck. It holds normal AutoDJ transitions, starts over the
+                    // outgoing outro and releases the next deck under its tail.
+                    if mode == DjMode::AutoDj && active_voice.is_none() {
+                        let on_air = [(a_deck, &a, a_playing), (b_deck, &b, b_playing)]
+                        .into_iter()
+                        .find_map(|(deck, ev, playing)| {
+                            playing
+                                .then(|| ev.as_ref().and_then(|ev| ev.song_id))
+                                .flatten()
+                                .map(|song_id| (deck, song_id))
+                        });
+                        if let (Some((from_deck, song_id)), Some(pool)) = (on_air, &state.local_db)
+                        {
+                            if voice_checked_song != Some(song_id) {
+                                voice_checked_song = Some(song_id);
+                                active_voice =
+                                    arm_voice_track(&state, pool, song_id, from_deck).await;
+                            }
+                        }
+                    }
+                    if let Some(voice) = active_voice.as_mut() {
+                        use crate::stream::ad_cues::{self, CuePhase};
+                        let progress = link_progress(&state, voice);
+                        let was_started = voice.started;
+                        let finished = step_voice_track(&state, voice, progress, &a, &b).await;
+                        // Cue-out as the break's first spot airs, cue-in after its last.
+                        if !was_started && voice.started && voice.spot_log_id.is_some() {
+                            if let Some(cue) = ad_break_cue.clone().filter(|_| !ad_break_on_air) {
+                                ad_cues::send(&app_handle, CuePhase::Out, cue);
+                                ad_break_on_air = true;
+                            }
+                        }
+                        if finished {
+                            if let Some(done) = active_voice.take() {
+                                finish_traffic_spot(&state, &done).await;
+                                if done.spot_log_id.is_some() && traffic_spots.is_empty() {
+                                    if let Some(cue) = ad_break_cue.take() {
+                                        if ad_break_on_air {
+                                            ad_cues::send(&app_handle, CuePhase::In, cue);
+                                        }
+                                    }
+                                    ad_break_on_air = false;
+                                }
+                            }
+                        }
+                    }
+                    let voice_hold = active_voice.as_ref().is_some_and(|v| !v.next_started)
+                        || !traffic_spots.is_empty();
+
                     if no_playing {
+                        if voice_hold {
+                            continue;
+                        }
                         pending_sam_start = None;
                         sam_below_threshold_since.clear();
                         if mode == DjMode::AutoDj {
                             if is_ready(a_state) {
                                 let mut engine = state.engine.lock().unwrap();
-                                let _ = engine.set_manual_crossfade(-1.0);
-                                let _ = engine.play(DeckId::DeckA);
+                                let _ = engine.focus_crossfader(a_deck);
+                                let _ = engine.play(a_deck);
                                 continue;
                             }
                             if is_ready(b_state) {
                                 let mut engine = state.engine.lock().unwrap();
-                                let _ = engine.set_manual_crossfade(1.0);
-                                let _ = engine.play(DeckId::DeckB);
+                                let _ = engine.focus_crossfader(b_deck);
+                                let _ = engine.play(b_deck);
                                 continue;
                             }
-                            if let Some(next) =
-                                pick_next_track(&state, mode, &claimed_queue_ids).await
+                            if let Some(next) = pick_next_track(
+                                &state,
+                                mode,
+                                &claimed_queue_ids,
+                                &mut selection_rng,
+                            )
+                            .await
                             {
                                 let queue_to_claim = next.queue_id;
+                                let trim_db =
+                                    resolve_track_gain_db(&state, Some(next.song_id)).await;
                                 let loaded = {
                                     let mut engine = state.engine.lock().unwrap();
-                                    engine
+                                    let loaded = engine
                                         .load_track_with_source(
-                                            DeckId::DeckA,
+                                            a_deck,
                                             std::path::PathBuf::from(&next.file_path),
                                             Some(next.song_id),
                                             next.queue_id,
                                             next.from_rotation,
                                             next.declared_duration_ms,
-                                        )
-                                        .is_ok()
+                                        );
+                                    if loaded.is_ok() {
+                                        let _ = engine.set_track_gain_db(a_deck, trim_db);
+                                    }
+                                    loaded
                                 };
-                                if loaded {
+                                if let Err(err) = &loaded {
+                                    // The next tick picks again without this song.
+                                    note_failed_load(&state, &next, err).await;
+                                }
+                                if loaded.is_ok() {
                                     if let Some(qid) = next.queue_id {
                                         claimed_queue_ids.insert(qid);
                                         claim_queue_item(&state, qid).await;
                                     }
                                     let mut engine = state.engine.lock().unwrap();
-                                    let _ = engine.set_manual_crossfade(-1.0);
-                                    let _ = engine.play(DeckId::DeckA);
+                                    let _ = engine.focus_crossfader(a_deck);
+                                    let _ = engine.play(a_deck);
                                 } else if let Some(qid) = queue_to_claim {
                                     claimed_queue_ids.remove(&qid);
                                 }
@@ -736,6 +1354,7 @@ pub fn run() {
                                 pending.from,
                                 pending.to,
                                 pending.fade_ms,
+                                pending.profile.clone(),
                             );
                             autodj::set_last_transition_decision(TransitionDecisionDebug {
                                 engine: "sam_classic".to_string(),
@@ -764,6 +1383,7 @@ pub fn run() {
                                 pending.from,
                                 pending.to,
                                 timeout_fade_ms,
+                                pending.profile.clone(),
                             );
                             autodj::set_last_transition_decision(TransitionDecisionDebug {
                                 engine: "sam_classic".to_string(),
@@ -809,24 +1429,38 @@ pub fn run() {
                             .map(|d| d.duration_ms.saturating_sub(d.position_ms))
                             .unwrap_or(0);
                         if rem > 0 && rem <= preload_ms {
-                            if let Some(next) =
-                                pick_next_track(&state, mode, &claimed_queue_ids).await
+                            if let Some(next) = pick_next_track(
+                                &state,
+                                mode,
+                                &claimed_queue_ids,
+                                &mut selection_rng,
+                            )
+                            .await
                             {
                                 let queue_to_claim = next.queue_id;
-                                let loaded = state
-                                    .engine
-                                    .lock()
-                                    .unwrap()
-                                    .load_track_with_source(
-                                        DeckId::DeckB,
-                                        std::path::PathBuf::from(&next.file_path),
-                                        Some(next.song_id),
-                                        next.queue_id,
-                                        next.from_rotation,
-                                        next.declared_duration_ms,
-                                    )
-                                    .is_ok();
-                                if loaded {
+                                let trim_db =
+                                    resolve_track_gain_db(&state, Some(next.song_id)).await;
+                                let loaded = {
+                                    let mut engine = state.engine.lock().unwrap();
+                                    let loaded = engine
+                                        .load_track_with_source(
+                                            b_deck,
+                                            std::path::PathBuf::from(&next.file_path),
+                                            Some(next.song_id),
+                                            next.queue_id,
+                                            next.from_rotation,
+                                            next.declared_duration_ms,
+                                        );
+                                    if loaded.is_ok() {
+                                        let _ = engine.set_track_gain_db(b_deck, trim_db);
+                                    }
+                                    loaded
+                                };
+                                if let Err(err) = &loaded {
+                                    // The next tick picks again without this song.
+                                    note_failed_load(&state, &next, err).await;
+                                }
+                                if loaded.is_ok() {
                                     if let Some(qid) = next.queue_id {
                                         claimed_queue_ids.insert(qid);
                                         claim_queue_item(&state, qid).await;
@@ -842,24 +1476,38 @@ pub fn run() {
                             .map(|d| d.duration_ms.saturating_sub(d.position_ms))
                             .unwrap_or(0);
                         if rem > 0 && rem <= preload_ms {
-                            if let Some(next) =
-                                pick_next_track(&state, mode, &claimed_queue_ids).await
+                            if let Some(next) = pick_next_track(
+                                &state,
+                                mode,
+                                &claimed_queue_ids,
+                                &mut selection_rng,
+                            )
+                            .await
                             {
                                 let queue_to_claim = next.queue_id;
-                                let loaded = state
-                                    .engine
-                                    .lock()
-                                    .unwrap()
-                                    .load_track_with_source(
-                                        DeckId::DeckA,
-                                        std::path::PathBuf::from(&next.file_path),
-                                        Some(next.song_id),
-                                        next.queue_id,
-                                        next.from_rotation,
-                                        next.declared_duration_ms,
-                                    )
-                                    .is_ok();
-                                if loaded {
+                                let trim_db =
+                                    resolve_track_gain_db(&state, Some(next.song_id)).await;
+                                let loaded = {
+                                    let mut engine = state.engine.lock().unwrap();
+                                    let loaded = engine
+                                        .load_track_with_source(
+                                            a_deck,
+                                            std::path::PathBuf::from(&next.file_path),
+                                            Some(next.song_id),
+                                            next.queue_id,
+                                            next.from_rotation,
+                                            next.declared_duration_ms,
+                                        );
+                                    if loaded.is_ok() {
+                                        let _ = engine.set_track_gain_db(a_deck, trim_db);
+                                    }
+                                    loaded
+                                };
+                                if let Err(err) = &loaded {
+                                    // The next tick picks again without this song.
+                                    note_failed_load(&state, &next, err).await;
+                                }
+                                if loaded.is_ok() {
                                     if let Some(qid) = next.queue_id {
                                         claimed_queue_ids.insert(qid);
                                         claim_queue_item(&state, qid).await;
@@ -871,11 +1519,96 @@ pub fn run() {
                         }
                     }
 
-                    if mode != DjMode::AutoDj {
+                    // A pending "switch at track end" lets the current song
+                    // play out instead of starting the next transition.
+                    if mode != DjMode::AutoDj
+                        || voice_hold
+                        || crate::scheduler::mode_transition::holds_autodj()
+                    {
                         continue;
                     }
 
                     let autodj_cfg = autodj::get_auto_transition_config();
+
+                    // ── Per-song playback flags ─────────────────────────────
+                    let mut force_segue = false;
+                    let flag_pair = if a_playing && is_ready(b_state) {
+                        Some((a.as_ref(), b.as_ref()))
+                    } else if b_playing && is_ready(a_state) {
+                        Some((b.as_ref(), a.as_ref()))
+                    } else {
+                        None
+                    };
+                    if let Some((Some(from_ev), Some(to_ev))) = flag_pair {
+                        let from_flags =
+                            load_playback_flags(&state, from_ev.song_id, &mut flags_cache).await;
+                        let to_flags =
+                            load_playback_flags(&state, to_ev.song_id, &mut flags_cache).await;
+
+                        // An attached sweeper bridges the two songs as a Sound FX
+                        // voice: it starts when the outgoing song ends and the
+                        // incoming song starts as soon as it finishes.
+                        if let (Some(path), Some(from_deck)) =
+                            (to_flags.attached_sweeper(), deck_id_from_event(from_ev))
+                        {
+                            // Only one link plays at a time: wait for the one
+                            // still on air to finish, then arm the sweeper.
+                            if active_voice.is_some() {
+                                continue;
+                            }
+                            if sweeper_checked_song != to_ev.song_id {
+                                sweeper_checked_song = to_ev.song_id;
+                                let placement = crate::scheduler::voice_track::VoiceTrackPlacement {
+                                    id: None,
+                                    title: "Attached sweeper".to_string(),
+                                    file_path: path.to_string(),
+                                    duration_ms: None,
+                                    prev_song_id: from_ev.song_id,
+                                    next_song_id: to_ev.song_id,
+                                    prev_overlap_ms: 0,
+                                    next_overlap_ms: 0,
+                                    trim_start_ms: 0,
+                                    trim_end_ms: 0,
+                                    gain_db: 0.0,
+                                    fades: Default::default(),
+                                    played_at: None,
+                                };
+                                active_voice =
+                                    arm_sfx_link(&state, placement, from_deck, None, 0.0).await;
+                                if active_voice.is_some() {
+                                    continue;
+                                }
+                            }
+                        }
+
+                        if from_flags.never_crossfade || to_flags.never_crossfade {
+                            // Let the outgoing song play out; the idle branch above
+                            // starts the ready deck as a hard cut.
+                            sam_below_threshold_since.clear();
+                            autodj::set_last_transition_decision(TransitionDecisionDebug {
+                                engine: match autodj_cfg.engine {
+                                    AutodjTransitionEngine::SamClassic => "sam_classic",
+                                    AutodjTransitionEngine::MixxxPlanner => "mixxx_planner",
+                                }
+                                .to_string(),
+                                from_deck: deck_id_from_event(from_ev).map(|d| d.to_string()),
+                                to_deck: deck_id_from_event(to_ev).map(|d| d.to_string()),
+                                trigger_mode: None,
+                                reason: "never_crossfade_hard_cut".to_string(),
+                                outgoing_rms_db: Some(from_ev.rms_db_pre_fader),
+                                threshold_db: None,
+                                outgoing_remaining_ms: Some(
+                                    from_ev.duration_ms.saturating_sub(from_ev.position_ms),
+                                ),
+                                fixed_point_ms: None,
+                                hold_ms: None,
+                                skip_cause: Some("never_crossfade".to_string()),
+                            });
+                            continue;
+                        }
+                        force_segue = from_flags.always_segue || to_flags.always_segue;
+                    }
+
                     match autodj_cfg.engine {
                         AutodjTransitionEngine::SamClassic => {
                             let maybe_from_to = if a_playing && is_ready(b_state) {
@@ -897,20 +1630,56 @@ pub fn run() {
                             };
                             sam_below_threshold_since.retain(|deck, _| *deck == from_deck);
 
-                            let crossfade_cfg = {
-                                let engine = state.engine.lock().unwrap();
-                                engine.get_crossfade_config()
+                            // The incoming song's category / song type may pick
+                            // a crossfade profile over the station config.
+                            let profile = match profile_cache.get(&to_ev.song_id) {
+                                Some(profile) => profile.clone(),
+                                None => {
+                                    let profile =
+                                        resolve_crossfade_profile(&state, to_ev.song_id).await;
+                                    profile_cache.insert(to_ev.song_id, profile.clone());
+                                    profile
+                                }
+                            };
+                            let crossfade_cfg = match &profile {
+                                Some(profile) => profile.clone(),
+                                None => state.engine.lock().unwrap().get_crossfade_config(),
                             };
                             let remaining_ms =
                                 from_ev.duration_ms.saturating_sub(from_ev.position_ms);
-                            let trigger_mode_str = match crossfade_cfg.trigger_mode {
+                            // "Always segue" songs never wait for a manual trigger.
+                            let trigger_mode = if force_segue
+                                && crossfade_cfg.trigger_mode == CrossfadeTriggerMode::Manual
+                            {
+                                CrossfadeTriggerMode::FixedPointMs
+                            } else {
+                                crossfade_cfg.trigger_mode
+                            };
+                            let trigger_mode_str = match trigger_mode {
                                 CrossfadeTriggerMode::AutoDetectDb => "auto_detect_db",
                                 CrossfadeTriggerMode::FixedPointMs => "fixed_point_ms",
                                 CrossfadeTriggerMode::Manual => "manual",
                             };
 
-                            let should_trigger = match crossfade_cfg.trigger_mode {
-                                CrossfadeTriggerMode::Manual => {
+                            // A song's own xfade cue replaces the global
+                            // auto-detect / fixed trigger point.
+                            let xfade_cue_ms = if trigger_mode == CrossfadeTriggerMode::Manual {
+                                None
+                            } else {
+                                load_transition_markers(
+                                    &state,
+                                    from_ev.song_id,
+                                    from_ev.file_path.as_deref(),
+                                    from_ev.duration_ms,
+                                    &mut marker_cache,
+                                )
+                                .await
+                                .xfade_ms
+                                .filter(|cue| *cue < from_ev.duration_ms)
+                            };
+
+                            let should_trigger = match (trigger_mode, xfade_cue_ms) {
+                                (CrossfadeTriggerMode::Manual, _) => {
                                     autodj::set_last_transition_decision(TransitionDecisionDebug {
                                         engine: "sam_classic".to_string(),
                                         from_deck: Some(from_deck.to_string()),
@@ -926,11 +1695,32 @@ pub fn run() {
                                     });
                                     false
                                 }
-                                CrossfadeTriggerMode::FixedPointMs => {
-                                    let fixed_point_ms = crossfade_cfg
-                                        .fixed_crossfade_point_ms
-                                        .unwrap_or(crossfade_cfg.fixed_crossfade_ms.max(500));
-                                    let trigger = remaining_ms <= fixed_point_ms as u64;
+                                (_, Some(cue_ms)) => {
+                                    let trigger = from_ev.position_ms >= cue_ms;
+                                    autodj::set_last_transition_decision(TransitionDecisionDebug {
+                                        engine: "sam_classic".to_string(),
+                                        from_deck: Some(from_deck.to_string()),
+                                        to_deck: Some(to_deck.to_string()),
+                                        trigger_mode: Some(trigger_mode_str.to_string()),
+                                        reason: if trigger {
+                                            "xfade_cue_triggered".to_string()
+                                        } else {
+                                            "xfade_cue_waiting".to_string()
+                                        },
+                                        outgoing_rms_db: Some(from_ev.rms_db_pre_fader),
+                                        threshold_db: None,
+                                        outgoing_remaining_ms: Some(remaining_ms),
+                                        fixed_point_ms: None,
+                                        hold_ms: None,
+                                        skip_cause: None,
+                                    });
+                                    trigger
+                                }
+                                (CrossfadeTriggerMode::FixedPointMs, None) => {
+                                    let fixed_point_ms = crossfade_cfg
+                                        .fixed_crossfade_point_ms
+                                        .unwrap_or(crossfade_cfg.fixed_crossfade_ms.max(500));
+                                    let trigger = remaining_ms <= fixed_point_ms as u64;
                                     autodj::set_last_transition_decision(TransitionDecisionDebug {
                                         engine: "sam_classic".to_string(),
                                         from_deck: Some(from_deck.to_string()),
@@ -950,7 +1740,7 @@ pub fn run() {
                                     });
                                     trigger
                                 }
-                                CrossfadeTriggerMode::AutoDetectDb => {
+                                (CrossfadeTriggerMode::AutoDetectDb, None) => {
                                     let in_window = from_ev.position_ms
                                         >= crossfade_cfg.auto_detect_min_ms as u64
                                         && remaining_ms <= crossfade_cfg.auto_detect_max_ms as u64;
@@ -1064,7 +1854,10 @@ pub fn run() {
                                 .min(crossfade_cfg.max_fade_time_ms)
                                 .max(100);
                             let mut short_track_fallback = false;
-                            if let Some(skip_secs) = crossfade_cfg.skip_short_tracks_secs {
+                            if let Some(skip_secs) = crossfade_cfg
+                                .skip_short_tracks_secs
+                                .filter(|_| !force_segue)
+                            {
                                 let skip_ms = (skip_secs as u64).saturating_mul(1000);
                                 if from_ev.duration_ms <= skip_ms || to_ev.duration_ms <= skip_ms {
                                     short_track_fallback = true;
@@ -1082,8 +1875,13 @@ pub fn run() {
 
                             if to_ev.decoder_buffer_ms >= SAM_PREROLL_MIN_MS {
                                 let mut engine = state.engine.lock().unwrap();
-                                let _ =
-                                    start_sam_transition(&mut engine, from_deck, to_deck, fade_ms);
+                                let _ = start_sam_transition(
+                                    &mut engine,
+                                    from_deck,
+                                    to_deck,
+                                    fade_ms,
+                                    profile.clone(),
+                                );
                                 autodj::set_last_transition_decision(TransitionDecisionDebug {
                                     engine: "sam_classic".to_string(),
                                     from_deck: Some(from_deck.to_string()),
@@ -1106,6 +1904,7 @@ pub fn run() {
                                     short_track_fallback,
                                     trigger_mode: trigger_mode_str.to_string(),
                                     requested_at: std::time::Instant::now(),
+                                    profile: profile.clone(),
                                 });
                                 autodj::set_last_transition_decision(TransitionDecisionDebug {
                                     engine: "sam_classic".to_string(),
@@ -1154,6 +1953,7 @@ pub fn run() {
                                 let from_markers = load_transition_markers(
                                     &state,
                                     from_ev.song_id,
+                                    from_ev.file_path.as_deref(),
                                     from_ev.duration_ms,
                                     &mut marker_cache,
                                 )
@@ -1161,6 +1961,7 @@ pub fn run() {
                                 let to_markers = load_transition_markers(
                                     &state,
                                     to_ev.song_id,
+                                    to_ev.file_path.as_deref(),
                                     to_ev.duration_ms,
                                     &mut marker_cache,
                                 )
@@ -1186,11 +1987,11 @@ pub fn run() {
                                 }) = plan
                                 {
                                     if from_ev.position_ms >= from_fade_begin_ms {
-                                        if gap_ms > 0 {
+                                        if gap_ms > 0 && !force_segue {
                                             if from_ev.position_ms >= from_fade_end_ms {
                                                 let mut engine = state.engine.lock().unwrap();
                                                 let _ = engine.seek(to_deck, to_start_ms);
-                                                let _ = engine.stop_with_completion(from_deck);
+                                                let _ = engine.stop_with_completion(from_deck, StopReason::Crossfade);
                                                 pending_gap = Some(PendingGapTransition {
                                                     incoming: to_deck,
                                                     start_at: std::time::Instant::now()
@@ -1224,18 +2025,40 @@ pub fn run() {
             seek_deck,
             jog_deck,
             set_channel_gain,
+            get_category_gain_trims,
+            set_category_gain_trim,
+            delete_category_gain_trim,
             set_deck_bass,
             set_deck_filter,
+            set_deck_eq_kill,
             set_deck_pitch,
             set_deck_tempo,
+            set_deck_keylock,
+            set_deck_reverse,
+            censor_deck,
+            set_deck_slip,
             set_master_level,
             get_master_level,
             set_local_monitor_muted,
             get_local_monitor_muted,
             set_deck_loop,
             clear_deck_loop,
+            halve_deck_loop,
+            double_deck_loop,
             get_deck_state,
             get_vu_readings,
+            get_spectrum_config,
+            set_spectrum_config,
+            get_spectrum_data,
+            get_loudness_config,
+            set_loudness_config,
+            get_loudness_reading,
+            reset_loudness_meter,
+            get_multitrack_config,
+            set_multitrack_config,
+            start_multitrack_recording,
+            stop_multitrack_recording,
+            get_multitrack_status,
             set_headphone_mix,
             set_headphone_level,
             get_headphone_mix,
@@ -1247,6 +2070,13 @@ pub fn run() {
             // Phase 1 — Crossfade
             get_crossfade_config,
             set_crossfade_config,
+            get_crossfade_profiles,
+            save_crossfade_profile,
+            delete_crossfade_profile,
+            get_crossfade_profile_assignments,
+            set_crossfade_profile_assignment,
+            delete_crossfade_profile_assignment,
+            set_crossfader_assignment,
             start_crossfade,
             set_manual_crossfade,
             trigger_manual_fade,
@@ -1258,25 +2088,38 @@ pub fn run() {
             set_channel_stem_filter,
             set_pipeline_settings,
             analyze_stems,
+            queue_stem_analysis,
+            cancel_stem_jobs,
             get_stem_analysis,
             get_latest_stem_analysis,
             get_stems_runtime_status,
             install_stems_runtime,
             set_deck_stem_source,
+            set_deck_stem_mix,
             // Phase 1 — Cue points
             get_cue_points,
             set_cue_point,
             delete_cue_point,
             jump_to_cue,
+            import_sam_cues,
             get_hot_cues,
             set_hot_cue,
             clear_hot_cue,
             trigger_hot_cue,
             rename_hot_cue,
             recolor_hot_cue,
+            get_saved_loops,
+            set_saved_loop,
+            clear_saved_loop,
+            trigger_saved_loop,
+            set_deck_auto_loop,
+            get_quantize_config,
+            set_quantize_config,
             get_monitor_routing_config,
             set_monitor_routing_config,
             set_deck_cue_preview_enabled,
+            get_song_playback_flags,
+            set_song_playback_flags,
             // Controller
             list_controller_devices,
             get_controller_status,
@@ -1284,6 +2127,14 @@ pub fn run() {
             save_controller_config_cmd,
             connect_controller,
             disconnect_controller,
+            list_controller_profiles,
+            save_controller_profile,
+            delete_controller_profile,
+            set_controller_learn_mode,
+            // OSC remote control
+            get_osc_config,
+            set_osc_config,
+            get_osc_status,
             // Phase 1 — Queue / SAM
             get_queue,
             add_to_queue,
@@ -1301,6 +2152,10 @@ pub fn run() {
             start_stream,
             stop_stream,
             get_stream_status,
+            // Overlay WebSocket feed
+            get_overlay_server_config,
+            set_overlay_server_config,
+            get_overlay_server_status,
             // Phase 4 — Multi-encoder
             get_encoders,
             save_encoder,
@@ -1310,20 +2165,35 @@ pub fn run() {
             start_all_encoders,
             stop_all_encoders,
             test_encoder_connection,
+            detect_stream_watermark,
+            get_metadata_push_targets,
+            save_metadata_push_target,
+            delete_metadata_push_target,
+            test_metadata_push_target,
+            get_metadata_push_status,
             get_encoder_runtime,
             // Phase 4 — Recording
             start_recording,
             stop_recording,
+            export_show,
+            get_show_export_config,
+            set_show_export_config,
             // Phase 4 — Stats
             get_listener_stats,
             get_current_listeners,
             // Phase 4 — Metadata
             push_track_metadata,
+            // Station ID sign-on gate
+            get_station_id_gate_config,
+            set_station_id_gate_config,
+            get_station_id_gate_status,
             // Phase 5 — Scripts
             get_scripts,
             save_script,
             delete_script,
             run_script,
+            get_script_api,
+            get_script_runs,
             get_script_log,
             // Phase 5 — Microphone / Voice FX
             get_audio_input_devices,
@@ -1332,10 +2202,16 @@ pub fn run() {
             start_mic,
             stop_mic,
             set_ptt,
+            set_mic_open,
+            get_mic_duck_config,
+            set_mic_duck_config,
             // Phase 5 — Voice Track Recording
             start_voice_recording,
             stop_voice_recording,
             save_voice_track,
+            save_voice_track_placement,
+            get_voice_tracks,
+            delete_voice_track,
             // Phase 6 — Gateway
             connect_gateway,
             disconnect_gateway,
@@ -1349,6 +2225,9 @@ pub fn run() {
             start_live_talk,
             stop_live_talk,
             set_mix_minus,
+            start_remote_audio,
+            stop_remote_audio,
+            get_remote_audio_status,
             // Phase 6 — SAM DB connection management
             test_sam_db_connection,
             connect_sam_db,
@@ -1356,48 +2235,204 @@ pub fn run() {
             get_sam_db_config_cmd,
             save_sam_db_config_cmd,
             get_sam_db_status,
+            get_sam_db_health,
+            get_sam_cache_stats,
             get_sam_categories,
             create_sam_category,
+            get_path_translation_rules,
+            save_path_translation_rule,
+            delete_path_translation_rule,
+            reorder_path_translation_rules,
+            test_path_translation,
+            get_sam_outbox_status,
+            get_failed_sam_writes,
+            retry_failed_sam_writes,
+            discard_failed_sam_writes,
+            get_synced_song,
+            save_synced_song,
+            get_sam_song_changes,
             // Phase 7 — Analytics
             get_top_songs,
             get_hourly_heatmap,
             get_song_play_history,
+            get_play_log,
             get_listener_graph,
             get_listener_peak,
+            get_listener_breakdown,
+            get_listener_geo_breakdown,
+            get_geoip_status,
+            set_geoip_config,
             get_event_log,
+            get_app_logs,
             clear_event_log,
             write_event_log,
+            get_event_log_retention,
+            set_event_log_retention,
+            prune_event_log,
             get_health_snapshot,
+            get_alert_config,
+            set_alert_config,
+            test_alert_target,
+            get_report_digest_config,
+            set_report_digest_config,
+            test_report_digest,
+            // Operator accounts & audit trail
+            get_access_status,
+            sign_in,
+            sign_out,
+            list_users,
+            create_user,
+            update_user,
+            delete_user,
+            get_audit_log,
+            // Settings backup / restore
+            export_settings,
+            inspect_settings_archive,
+            import_settings,
+            get_backup_config,
+            set_backup_config,
+            list_backups,
+            create_backup,
+            restore_backup,
+            get_db_schema_info,
+            preview_sam_import,
+            apply_sam_import,
+            get_emitter_metrics,
+            get_emit_rate_config,
+            set_emit_rate_config,
             get_health_history,
             generate_report,
             export_report_csv,
+            get_library_storage_report,
+            scan_missing_files,
+            get_missing_files,
+            relink_song,
+            get_missing_file_config,
+            set_missing_file_config,
+            export_traffic_affidavit_csv,
+            export_show_audience_csv,
+            export_listener_kpis_csv,
+            export_royalty_report,
+            get_scrobbler_config,
+            set_scrobbler_config,
+            lastfm_begin_auth,
+            lastfm_complete_auth,
+            listenbrainz_validate_token,
+            get_scrobbler_status,
+            flush_scrobble_queue,
             // Waveform analysis/cache
             get_waveform_data,
+            get_waveform_chunk,
+            // Background analysis queue
+            get_analysis_queue,
+            enqueue_analysis,
+            cancel_analysis_job,
+            get_analysis_queue_config,
+            set_analysis_queue_config,
+            get_track_loudness,
+            fingerprint_library,
+            find_duplicate_songs,
+            // Album art
+            get_song_artwork,
+            clear_artwork_cache,
+            get_artwork_config,
+            set_artwork_config,
+            // Tag enrichment
+            get_tag_enrichment_config,
+            set_tag_enrichment_config,
+            start_tag_enrichment,
+            cancel_tag_enrichment,
+            get_tag_proposals,
+            apply_tag_proposals,
+            reject_tag_proposals,
+            // Cart wall
+            get_cart_wall,
+            save_cart,
+            clear_cart,
+            set_cart_wall_layout,
+            set_active_cart_page,
+            trigger_cart,
+            stop_cart,
+            stop_all_carts,
+            get_cart_states,
+            // Sound FX voices
+            play_sfx,
+            stop_sfx,
+            get_sfx_voices,
+            // Global hotkeys
+            get_hotkey_bindings,
+            set_hotkey_bindings,
+            check_hotkey_conflicts,
+            get_hotkey_registrations,
             // Beat-grid analysis/cache
             analyze_beatgrid,
+            detect_transition_cues,
             get_beatgrid,
             // Phase 3 — Scheduler / AutoDJ / Requests
             get_dj_mode,
             set_dj_mode,
+            request_dj_mode_change,
+            get_pending_dj_mode_change,
+            cancel_pending_dj_mode_change,
             get_autodj_transition_config,
             set_autodj_transition_config,
             recalculate_autodj_plan_now,
             get_last_transition_decision,
+            get_last_rotation_decision,
             get_rotation_rules,
             save_rotation_rule,
             delete_rotation_rule,
             get_clockwheel_config,
             save_clockwheel_config,
+            get_clockwheel_templates,
+            save_clockwheel_template,
+            get_clockwheel_hour_grid,
+            assign_clockwheel_hour,
             get_song_directories,
             enqueue_next_clockwheel_track,
             get_playlists,
             save_playlist,
             set_active_playlist,
+            get_playlist_songs,
+            set_playlist_songs,
+            get_playlist_cursor,
+            set_playlist_cursor,
+            import_playlist_file,
+            queue_playlist_file,
+            export_playlist_m3u,
+            export_queue_m3u,
             get_next_autodj_track,
+            simulate_rotation,
+            get_ghost_queue,
+            veto_ghost_queue_entry,
+            get_autodj_plan,
+            set_autodj_plan_pin,
+            reorder_autodj_plan,
+            replace_autodj_plan_item,
+            get_bad_tracks,
+            release_bad_track,
             get_shows,
             save_show,
             delete_show,
             get_upcoming_events,
+            get_active_show_overrides,
+            get_timed_events,
+            save_timed_event,
+            delete_timed_event,
+            // Relays
+            get_relay_events,
+            save_relay_event,
+            delete_relay_event,
+            get_relay_status,
+            stop_relay,
+            // Traffic
+            get_ad_breaks,
+            save_ad_break,
+            delete_ad_break,
+            get_traffic_campaigns,
+            save_traffic_campaign,
+            delete_traffic_campaign,
+            get_traffic_spot_log,
             get_gap_killer_config,
             set_gap_killer_config,
             get_request_policy,
@@ -1406,27 +2441,35 @@ pub fn run() {
             accept_request_p3,
             reject_request_p3,
             get_request_history,
+            submit_song_request,
+            triage_pending_requests,
+            get_request_api_config,
+            set_request_api_config,
+            get_request_api_status,
+            get_previous_session,
+            resume_previous_session,
+            discard_previous_session,
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
-}
-
-fn init_logging() {
-    std::panic::set_hook(Box::new(|info| {
-        let bt = std::backtrace::Backtrace::force_capture();
-        log::error!("panic: {info}\n{bt}");
-    }));
-    let mut builder =
-        env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info"));
-    builder
-        .format_timestamp_millis()
-        .format_target(true)
-        .format_module_path(false);
-    if let Err(e) = builder.try_init() {
-        eprintln!("[startup] logger init skipped: {e}");
-    } else {
-        log::info!("Logger initialized");
-    }
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app, event| {
+            if let tauri::RunEvent::WindowEvent {
+                event: tauri::WindowEvent::Focused(focused),
+                ..
+            } = event
+            {
+                crate::analytics::emit_metrics::set_window_focused(focused);
+            }
+            if let tauri::RunEvent::Exit = event {
+                if let Some(pool) = app.state::<AppState>().local_db.as_ref() {
+                    if let Err(e) =
+                        tauri::async_runtime::block_on(crate::recovery::mark_clean_exit(pool))
+                    {
+                        log::warn!("Clean exit not recorded: {e}");
+                    }
+                }
+            }
+        });
 }
 
 // ── Helpers ──────────────────────────────────────────────────────────────────
@@ -1454,16 +2497,488 @@ struct PendingSamTransition {
     short_track_fallback: bool,
     trigger_mode: String,
     requested_at: std::time::Instant,
+    /// Crossfade profile resolved for the incoming song
+    profile: Option<crate::audio::crossfade::CrossfadeConfig>,
+}
+
+#[derive(Debug, Clone)]
+struct ActiveVoiceTrack {
+    placement: crate::scheduler::voice_track::VoiceTrackPlacement,
+    from_deck: crate::audio::crossfade::DeckId,
+    /// Deck the link plays on (Voice FX for voice tracks, Sound FX for
+    /// sweepers and timed events too long for the voice pool)
+    deck: crate::audio::crossfade::DeckId,
+    /// Set when the link plays as a Sound FX voice instead of on `deck`
+    sfx: Option<SfxLink>,
+    started: bool,
+    next_started: bool,
+    /// The link's own fade-out has been started
+    fading_out: bool,
+    /// Unix time the link went to air
+    started_at: Option<i64>,
+    /// More links follow back to back (ad break); keep the next song held
+    hold_next: bool,
+    /// Traffic spot log row to confirm once the link has aired
+    spot_log_id: Option<i64>,
+}
+
+/// A link played through the Sound FX voice pool, so automated stingers
+/// overlap instead of cutting each other off.
+#[derive(Clone)]
+struct SfxLink {
+    sample: std::sync::Arc<crate::audio::cart_wall::CartSample>,
+    voice_id: crate::audio::sfx_player::SfxVoiceId,
+    gain: f32,
+    triggered_at: Option<std::time::Instant>,
+    /// The voice has shown up in an engine snapshot
+    seen: bool,
+}
+
+impl std::fmt::Debug for SfxLink {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SfxLink")
+            .field("voice_id", &self.voice_id)
+            .field("gain", &self.gain)
+            .field("triggered_at", &self.triggered_at)
+            .finish()
+    }
+}
+
+/// How long a triggered Sound FX link counts as playing before its voice
+/// appears in the engine snapshot.
+const SFX_LINK_START_GRACE: std::time::Duration = std::time::Duration::from_secs(2);
+
+/// Playback of a link's own element, read from its deck or its voice.
+struct LinkProgress {
+    ready: bool,
+    playing: bool,
+    position_ms: u64,
+    duration_ms: u64,
+}
+
+/// Load the pending link placed after `song_id` onto the Voice FX deck.
+async fn arm_voice_track(
+    state: &AppState,
+    pool: &sqlx::SqlitePool,
+    song_id: i64,
+    from_deck: crate::audio::crossfade::DeckId,
+) -> Option<ActiveVoiceTrack> {
+    use crate::audio::crossfade::DeckId;
+
+    let placement =
+        match crate::scheduler::voice_track::get_pending_voice_track_after(pool, song_id).await {
+            Ok(Some(p)) => p,
+            Ok(None) => return None,
+            Err(err) => {
+                log::warn!("Voice track lookup failed (song_id={}): {}", song_id, err);
+                return None;
+            }
+        };
+    arm_link(state, placement, from_deck, DeckId::VoiceFx, None, 0.0)
+}
+
+/// Load a link (voice track, sweeper, timed event) onto `deck`, held until
+/// `from_deck` ends. A `song_id` makes the link show up in play history.
+fn arm_link(
+    state: &AppState,
+    placement: crate::scheduler::voice_track::VoiceTrackPlacement,
+    from_deck: crate::audio::crossfade::DeckId,
+    deck: crate::audio::crossfade::DeckId,
+    song_id: Option<i64>,
+    trim_db: f32,
+) -> Option<ActiveVoiceTrack> {
+    let loaded = {
+        let mut engine = state.engine.lock().unwrap();
+        let loaded = engine.load_track_with_source(
+            deck,
+            std::path::PathBuf::from(&placement.file_path),
+            song_id,
+            None,
+            false,
+            placement.duration_ms,
+        );
+        if loaded.is_ok() {
+            let _ = engine.set_track_gain_db(deck, trim_db);
+            let _ = engine.set_channel_gain(deck, placement.fader_gain());
+        }
+        loaded
+    };
+    if let Err(err) = loaded {
+        log::warn!(
+            "Failed to load link {:?} ({}): {}",
+            placement.id,
+            placement.file_path,
+            err
+        );
+        return None;
+    }
+    log::info!(
+        "Armed '{}' on {} after song {:?}",
+        placement.title,
+        deck,
+        placement.prev_song_id
+    );
+    Some(ActiveVoiceTrack {
+        placement,
+        from_deck,
+        deck,
+        sfx: None,
+        started: false,
+        next_started: false,
+        fading_out: false,
+        started_at: None,
+        hold_next: false,
+        spot_log_id: None,
+    })
+}
+
+/// Arm a sweeper or timed event as a Sound FX voice, held until `from_deck`
+/// ends. Elements the voice pool cannot hold (longer than a cart) go to the
+/// Sound FX deck instead.
+async fn arm_sfx_link(
+    state: &AppState,
+    placement: crate::scheduler::voice_track::VoiceTrackPlacement,
+    from_deck: crate::audio::crossfade::DeckId,
+    song_id: Option<i64>,
+    trim_db: f32,
+) -> Option<ActiveVoiceTrack> {
+    use crate::audio::crossfade::DeckId;
+
+    let sample = match crate::audio::sfx_player::load(&placement.file_path).await {
+        Ok(sample) => sample,
+        Err(err) => {
+            log::debug!(
+                "'{}' not playable as a Sound FX voice ({}); using the deck",
+                placement.title,
+                err
+            );
+            return arm_link(
+                state,
+                placement,
+                from_deck,
+                DeckId::SoundFx,
+                song_id,
+                trim_db,
+            );
+        }
+    };
+    log::info!(
+        "Armed '{}' as a Sound FX voice after song {:?}",
+        placement.title,
+        placement.prev_song_id
+    );
+    let gain = placement.fader_gain() * 10.0_f32.powf(trim_db / 20.0);
+    Some(ActiveVoiceTrack {
+        placement,
+        from_deck,
+        deck: DeckId::SoundFx,
+        sfx: Some(SfxLink {
+            sample,
+            voice_id: crate::audio::sfx_player::next_voice_id(),
+            gain,
+            triggered_at: None,
+            seen: false,
+        }),
+        started: false,
+        next_started: false,
+        fading_out: false,
+        started_at: None,
+        hold_next: false,
+        spot_log_id: None,
+    })
+}
+
+/// Start a link's element: trigger its voice, or play its deck from the
+/// trim point with the placement's fade-in.
+fn play_link(engine: &mut crate::audio::engine::AudioEngine, link: &mut ActiveVoiceTrack) {
+    if let Some(sfx) = link.sfx.as_mut() {
+        let _ = engine.play_sfx(crate::audio::sfx_player::SfxTrigger {
+            voice_id: sfx.voice_id,
+            sample: std::sync::Arc::clone(&sfx.sample),
+            gain: sfx.gain,
+            choke_group: None,
+        });
+        sfx.triggered_at = Some(std::time::Instant::now());
+        return;
+    }
+    let fades = link.placement.fades;
+    if link.placement.trim_start_ms > 0 {
+        let _ = engine.seek(link.deck, link.placement.trim_start_ms);
+    }
+    if fades.fade_in_ms > 0 {
+        let _ = engine.set_channel_gain(link.deck, 0.0);
+        let _ = engine.ramp_channel_gain(
+            link.deck,
+            link.placement.fader_gain(),
+            fades.fade_in_ms as u32,
+        );
+    }
+    let _ = engine.play(link.deck);
+}
+
+fn link_progress(state: &AppState, link: &mut ActiveVoiceTrack) -> Option<LinkProgress> {
+    let engine = state.engine.lock().unwrap();
+    let Some(sfx) = link.sfx.as_mut() else {
+        return engine.get_deck_state(link.deck).map(|ev| LinkProgress {
+            ready: matches!(ev.state.as_str(), "ready" | "paused"),
+            playing: matches!(ev.state.as_str(), "playing" | "crossfading"),
+            position_ms: ev.position_ms,
+            duration_ms: ev.duration_ms,
+        });
+    };
+    let duration_ms = sfx.sample.duration_ms();
+    let Some(triggered_at) = sfx.triggered_at else {
+        return Some(LinkProgress {
+            ready: true,
+            playing: false,
+            position_ms: 0,
+            duration_ms,
+        });
+    };
+    let voice = engine
+        .sfx_states()
+        .into_iter()
+        .find(|v| v.voice_id == sfx.voice_id);
+    let (playing, position_ms) = match voice {
+        Some(voice) => {
+            sfx.seen = true;
+            (true, voice.position_ms)
+        }
+        None if !sfx.seen && triggered_at.elapsed() < SFX_LINK_START_GRACE => (true, 0),
+        None => (false, duration_ms),
+    };
+    Some(LinkProgress {
+        ready: false,
+        playing,
+        position_ms,
+        duration_ms,
+    })
+}
+
+/// Arm a due exact-time event as a Sound FX voice. A hard event also fades
+/// the song on air so the element starts right away.
+async fn fire_timed_event(
+    state: &AppState,
+    event: &crate::scheduler::timed_events::TimedEvent,
+    a: &Option<crate::audio::engine::DeckStateEvent>,
+    b: &Option<crate::audio::engine::DeckStateEvent>,
+) -> Option<ActiveVoiceTrack> {
+    use crate::audio::crossfade::DeckId;
+    use crate::scheduler::timed_events::TimedEventMode;
+
+    let (file_path, duration_ms) = match event.song_id {
+        Some(song_id) => {
+            let sam_pool = { state.sam_db.read().await.as_ref().cloned() }?;
+            let local_pool = state.local_db.as_ref()?;
+            let song = crate::db::sam::get_song(&sam_pool, song_id)
+                .await
+                .ok()
+                .flatten()?;
+            let path = translate_sam_file_path(local_pool, song.filename.clone()).await;
+            (
+                path,
+                (song.duration > 0).then_some(song.duration as u64 * 1000),
+            )
+        }
+        None => (event.file_path.clone()?.trim().to_string(), None),
+    };
+    if file_path.is_empty() {
+        return None;
+    }
+
+    let on_air = [a, b].into_iter().flatten().find_map(|ev| {
+        deck_id_from_event(ev)
+            .filter(|_| matches!(ev.state.as_str(), "playing" | "crossfading"))
+            .map(|deck| (deck, ev.song_id))
+    });
+    let from_deck = on_air
+        .map(|(deck, _)| deck)
+        .or_else(|| a.as_ref().and_then(deck_id_from_event))
+        .unwrap_or(DeckId::DeckA);
+    let placement = crate::scheduler::voice_track::VoiceTrackPlacement {
+        id: None,
+        title: event.name.clone(),
+        file_path,
+        duration_ms,
+        prev_song_id: on_air.and_then(|(_, song_id)| song_id),
+        next_song_id: None,
+        prev_overlap_ms: 0,
+        next_overlap_ms: 0,
+        trim_start_ms: 0,
+        trim_end_ms: 0,
+        gain_db: 0.0,
+        fades: Default::default(),
+        played_at: None,
+    };
+    // Library elements get the same load-time trim as songs on the main decks.
+    let trim_db = resolve_track_gain_db(state, event.song_id).await;
+    let link = arm_sfx_link(state, placement, from_deck, event.song_id, trim_db).await?;
+    if event.mode == TimedEventMode::Hard && on_air.is_some() {
+        let _ = state
+            .engine
+            .lock()
+            .unwrap()
+            .fade_out_deck(from_deck, event.fade_ms);
+    }
+    log::info!("Timed event '{}' fired ({:?})", event.name, event.mode);
+    Some(link)
+}
+
+async fn load_playback_flags(
+    state: &AppState,
+    song_id: Option<i64>,
+    cache: &mut std::collections::HashMap<i64, crate::db::local::SongPlaybackFlags>,
+) -> crate::db::local::SongPlaybackFlags {
+    let Some(song_id) = song_id else {
+        return crate::db::local::SongPlaybackFlags::default();
+    };
+    if let Some(cached) = cache.get(&song_id) {
+        return cached.clone();
+    }
+    let flags = match &state.local_db {
+        Some(pool) => crate::db::local::get_song_playback_flags(pool, song_id)
+            .await
+            .unwrap_or_default(),
+        None => crate::db::local::SongPlaybackFlags::default(),
+    };
+    cache.insert(song_id, flags.clone());
+    flags
+}
+
+/// Advance an armed link. Returns true once it is finished (or abandoned).
+async fn step_voice_track(
+    state: &AppState,
+    voice: &mut ActiveVoiceTrack,
+    progress: Option<LinkProgress>,
+    a: &Option<crate::audio::engine::DeckStateEvent>,
+    b: &Option<crate::audio::engine::DeckStateEvent>,
+) -> bool {
+    let Some(progress) = progress else {
+        return true;
+    };
+    let playing_like = |s: &str| matches!(s, "playing" | "crossfading");
+    let ready_like = |s: &str| matches!(s, "ready" | "paused");
+
+    if !voice.started {
+        let from_ev = event_for_deck(a, b, voice.from_deck);
+        let prev_playing = from_ev
+            .map(|ev| playing_like(ev.state.as_str()))
+            .unwrap_or(false);
+        let prev_remaining = from_ev
+            .map(|ev| ev.duration_ms.saturating_sub(ev.position_ms))
+            .unwrap_or(0);
+        if !voice.placement.should_start(prev_remaining, prev_playing) {
+            return false;
+        }
+        if !progress.ready {
+            // Give up rather than stall the station if the file never loaded.
+            if !prev_playing {
+                log::warn!(
+                    "Voice track {:?} not ready when due; skipping",
+                    voice.placement.id
+                );
+                return true;
+            }
+            return false;
+        }
+        let fades = voice.placement.fades;
+        let mut engine = state.engine.lock().unwrap();
+        play_link(&mut engine, voice);
+        if prev_playing && fades.prev_fade_out_ms > 0 {
+            let _ = engine.fade_out_deck(voice.from_deck, fades.prev_fade_out_ms as u32);
+        }
+        voice.started = true;
+        voice.started_at = Some(chrono::Utc::now().timestamp());
+        return false;
+    }
+
+    let duration_ms = voice
+        .placement
+        .duration_ms
+        .filter(|d| *d > 0)
+        .unwrap_or(progress.duration_ms);
+    let position_ms = progress.position_ms;
+    let voice_playing = progress.playing;
+
+    if !voice.next_started
+        && !voice.hold_next
+        && (!voice_playing || voice.placement.should_start_next(position_ms, duration_ms))
+    {
+        let next = [a, b]
+            .into_iter()
+            .flatten()
+            .filter(|ev| ready_like(ev.state.as_str()))
+            .filter_map(|ev| deck_id_from_event(ev).map(|deck| (deck, ev.channel_gain)))
+            .find(|(deck, _)| *deck != voice.from_deck);
+        if let Some((next_deck, next_gain)) = next {
+            let fade_in_ms = voice.placement.fades.next_fade_in_ms;
+            let mut engine = state.engine.lock().unwrap();
+            let _ = engine.focus_crossfader(next_deck);
+            if fade_in_ms > 0 {
+                let _ = engine.set_channel_gain(next_deck, 0.0);
+                let _ = engine.ramp_channel_gain(next_deck, next_gain, fade_in_ms as u32);
+            }
+            let _ = engine.play(next_deck);
+            voice.next_started = true;
+        }
+    }
+
+    if !voice.fading_out && voice_playing {
+        if let Some(fade_ms) = voice.placement.fade_out_due(position_ms, duration_ms) {
+            let mut engine = state.engine.lock().unwrap();
+            let _ = match &voice.sfx {
+                Some(sfx) => engine.stop_sfx(
+                    crate::audio::sfx_player::SfxStop::Voice(sfx.voice_id),
+                    fade_ms as u32,
+                ),
+                None => engine.ramp_channel_gain(voice.deck, 0.0, fade_ms as u32),
+            };
+            voice.fading_out = true;
+        }
+    }
+
+    let finished = !voice_playing || position_ms >= voice.placement.end_ms(duration_ms);
+    if !finished {
+        return false;
+    }
+    if voice_playing {
+        let mut engine = state.engine.lock().unwrap();
+        let _ = match &voice.sfx {
+            Some(sfx) => engine.stop_sfx(crate::audio::sfx_player::SfxStop::Voice(sfx.voice_id), 0),
+            None => engine.stop_with_completion(voice.deck, crate::audio::deck::StopReason::Ended),
+        };
+    }
+    if let (Some(pool), Some(id)) = (&state.local_db, voice.placement.id) {
+        if let Err(err) = crate::scheduler::voice_track::mark_voice_track_played(pool, id).await {
+            log::warn!("Failed to mark voice track {} played: {}", id, err);
+        }
+    }
+    true
+}
+
+/// Confirm (or mark missed) the traffic spot behind a finished link.
+async fn finish_traffic_spot(state: &AppState, link: &ActiveVoiceTrack) {
+    let (Some(pool), Some(log_id)) = (&state.local_db, link.spot_log_id) else {
+        return;
+    };
+    let result = match link.started_at {
+        Some(aired_at) => {
+            let played_ms = (chrono::Utc::now().timestamp() - aired_at).max(0) * 1000;
+            crate::scheduler::traffic::mark_spot_aired(pool, log_id, aired_at, played_ms).await
+        }
+        None => crate::scheduler::traffic::mark_spot_missed(pool, log_id).await,
+    };
+    if let Err(err) = result {
+        log::warn!("Failed to update traffic spot {}: {}", log_id, err);
+    }
 }
 
 fn deck_id_from_event(
     ev: &crate::audio::engine::DeckStateEvent,
 ) -> Option<crate::audio::crossfade::DeckId> {
-    match ev.deck.as_str() {
-        "deck_a" => Some(crate::audio::crossfade::DeckId::DeckA),
-        "deck_b" => Some(crate::audio::crossfade::DeckId::DeckB),
-        _ => None,
-    }
+    crate::commands::audio_commands::parse_deck(&ev.deck)
+        .ok()
+        .filter(|deck| deck.is_playback())
 }
 
 fn event_for_deck<'a>(
@@ -1471,11 +2986,44 @@ fn event_for_deck<'a>(
     b: &'a Option<crate::audio::engine::DeckStateEvent>,
     deck: crate::audio::crossfade::DeckId,
 ) -> Option<&'a crate::audio::engine::DeckStateEvent> {
-    match deck {
-        crate::audio::crossfade::DeckId::DeckA => a.as_ref(),
-        crate::audio::crossfade::DeckId::DeckB => b.as_ref(),
-        _ => None,
+    [a, b]
+        .into_iter()
+        .flatten()
+        .find(|ev| deck_id_from_event(ev) == Some(deck))
+}
+
+/// The two rotation decks AutoDJ drives this tick: the one on air (or the
+/// first ready one when nothing plays) and the deck after it in `rotation`.
+/// A running crossfade pins the pair to its outgoing/incoming decks.
+fn autodj_deck_pair(
+    engine: &crate::audio::engine::AudioEngine,
+    rotation: &[crate::audio::crossfade::DeckId],
+) -> (
+    crate::audio::crossfade::DeckId,
+    crate::audio::crossfade::DeckId,
+) {
+    if let Some(pair) = engine.crossfade_decks() {
+        return pair;
     }
+    let states: Vec<String> = rotation
+        .iter()
+        .map(|deck| {
+            engine
+                .get_deck_state(*deck)
+                .map(|ev| ev.state)
+                .unwrap_or_default()
+        })
+        .collect();
+    let index = states
+        .iter()
+        .position(|s| matches!(s.as_str(), "playing" | "crossfading"))
+        .or_else(|| {
+            states
+                .iter()
+                .position(|s| matches!(s.as_str(), "ready" | "paused"))
+        })
+        .unwrap_or(0);
+    (rotation[index], rotation[(index + 1) % rotation.len()])
 }
 
 fn start_sam_transition(
@@ -1483,19 +3031,11 @@ fn start_sam_transition(
     from: crate::audio::crossfade::DeckId,
     to: crate::audio::crossfade::DeckId,
     fade_ms: u32,
+    profile: Option<crate::audio::crossfade::CrossfadeConfig>,
 ) -> Result<(), String> {
-    use crate::audio::crossfade::DeckId;
-    use crate::audio::engine::ManualFadeDirection;
-
-    match (from, to) {
-        (DeckId::DeckA, DeckId::DeckB) => {
-            engine.trigger_manual_fade(ManualFadeDirection::AtoB, fade_ms)
-        }
-        (DeckId::DeckB, DeckId::DeckA) => {
-            engine.trigger_manual_fade(ManualFadeDirection::BtoA, fade_ms)
-        }
-        _ => engine.start_crossfade(from, to),
-    }
+    engine
+        .start_profiled_crossfade(from, to, fade_ms, profile)
+        .map_err(Into::into)
 }
 
 fn cue_value(cues: &[crate::db::local::CuePoint], names: &[&str]) -> Option<u64> {
@@ -1513,24 +3053,43 @@ fn cue_value(cues: &[crate::db::local::CuePoint], names: &[&str]) -> Option<u64>
     None
 }
 
+/// Markers for a song: in-memory cache first, then the persisted
+/// `transition_marker_cache` row (valid while the file mtime and duration
+/// match), and only then the cue points.
 async fn load_transition_markers(
     state: &AppState,
     song_id: Option<i64>,
+    file_path: Option<&str>,
     duration_ms: u64,
-    cache: &mut std::collections::HashMap<
-        i64,
-        crate::scheduler::transition_planner::TransitionMarkers,
-    >,
+    cache: &mut crate::scheduler::transition_planner::MarkerCache,
 ) -> crate::scheduler::transition_planner::TransitionMarkers {
     let Some(song_id) = song_id else {
         return crate::scheduler::transition_planner::TransitionMarkers::default();
     };
-    if let Some(cached) = cache.get(&song_id).copied() {
+    if let Some(cached) = cache.get(song_id, duration_ms) {
         return cached;
     }
 
     let mut markers = crate::scheduler::transition_planner::TransitionMarkers::default();
     if let Some(pool) = &state.local_db {
+        let metadata = match file_path {
+            Some(p) => tokio::fs::metadata(p).await.ok(),
+            None => None,
+        };
+        let file_mtime = metadata
+            .and_then(|m| m.modified().ok())
+            .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+            .map(|d| d.as_secs() as i64);
+
+        if let Ok(Some(stored)) =
+            crate::db::local::get_cached_transition_markers(pool, song_id).await
+        {
+            if stored.duration_ms == duration_ms && stored.file_mtime == file_mtime {
+                cache.insert(song_id, duration_ms, stored.markers);
+                return stored.markers;
+            }
+        }
+
         if let Ok(cues) = crate::db::local::get_cue_points(pool, song_id).await {
             markers.intro_start_ms = cue_value(&cues, &["intro_start", "intro"]);
             markers.intro_end_ms = cue_value(&cues, &["intro_end"]);
@@ -1538,6 +3097,7 @@ async fn load_transition_markers(
             markers.outro_end_ms = cue_value(&cues, &["outro_end"]);
             markers.first_sound_ms = cue_value(&cues, &["first_sound", "start"]);
             markers.last_sound_ms = cue_value(&cues, &["last_sound", "end"]);
+            markers.xfade_ms = cue_value(&cues, &["xfade"]);
 
             if markers.first_sound_ms.is_none() {
                 markers.first_sound_ms = Some(0);
@@ -1545,30 +3105,189 @@ async fn load_transition_markers(
             if markers.last_sound_ms.is_none() {
                 markers.last_sound_ms = Some(duration_ms);
             }
+
+            let stored = crate::db::local::CachedTransitionMarkers {
+                markers,
+                duration_ms,
+                file_mtime,
+            };
+            if let Err(e) =
+                crate::db::local::save_cached_transition_markers(pool, song_id, &stored).await
+            {
+                log::debug!("Failed to persist transition markers (song_id={song_id}): {e}");
+            }
         }
     }
 
-    cache.insert(song_id, markers);
+    cache.insert(song_id, duration_ms, markers);
     markers
 }
 
+/// Load-time trim for a song: its gain override plus the trim configured for
+/// its SAM category, or failing that for its song type.
+pub(crate) async fn resolve_track_gain_db(state: &AppState, song_id: Option<i64>) -> f32 {
+    use crate::db::local::GainTrimKind;
+
+    let (Some(song_id), Some(local)) = (song_id, state.local_db.as_ref()) else {
+        return 0.0;
+    };
+    let song_gain = crate::db::local::get_song_fade_override(local, song_id)
+        .await
+        .ok()
+        .flatten()
+        .and_then(|o| o.gain_db)
+        .unwrap_or(0.0);
+
+    let trims = crate::db::local::get_category_gain_trims(local)
+        .await
+        .unwrap_or_default();
+    let sam_pool = { state.sam_db.read().await.as_ref().cloned() };
+    let mut category_gain = 0.0;
+    if let (false, Some(sam_pool)) = (trims.is_empty(), sam_pool) {
+        let find = |kind, name: &str| crate::db::local::find_gain_trim(&trims, kind, name);
+        let category = crate::db::sam::get_song_category_names(&sam_pool, Some(&[song_id]))
+            .await
+            .ok()
+            .and_then(|m| m.get(&song_id).cloned());
+        category_gain = match category.and_then(|c| find(GainTrimKind::Category, &c)) {
+            Some(db) => db,
+            None => crate::db::sam::get_song(&sam_pool, song_id)
+                .await
+                .ok()
+                .flatten()
+                .and_then(|s| find(GainTrimKind::SongType, &s.songtype))
+                .unwrap_or(0.0),
+        };
+    }
+    (song_gain + category_gain).clamp(-24.0, 12.0) as f32
+}
+
+/// Crossfade profile for a transition into `song_id`: the one assigned to its
+/// SAM category, or failing that to its song type. `None` keeps the station
+/// config.
+pub(crate) async fn resolve_crossfade_profile(
+    state: &AppState,
+    song_id: Option<i64>,
+) -> Option<crate::audio::crossfade::CrossfadeConfig> {
+    use crate::db::local::GainTrimKind;
+
+    let (song_id, local) = (song_id?, state.local_db.as_ref()?);
+    let assignments = crate::db::local::get_crossfade_profile_assignments(local)
+        .await
+        .unwrap_or_default();
+    if assignments.is_empty() {
+        return None;
+    }
+    let sam_pool = { state.sam_db.read().await.as_ref().cloned() }?;
+    let find = |kind: GainTrimKind, name: &str| {
+        assignments
+            .iter()
+            .find(|a| a.kind == kind && a.name.eq_ignore_ascii_case(name.trim()))
+            .map(|a| a.profile_id)
+    };
+    let category = crate::db::sam::get_song_category_names(&sam_pool, Some(&[song_id]))
+        .await
+        .ok()
+        .and_then(|m| m.get(&song_id).cloned());
+    let profile_id = match category.and_then(|c| find(GainTrimKind::Category, &c)) {
+        Some(id) => id,
+        None => crate::db::sam::get_song(&sam_pool, song_id)
+            .await
+            .ok()
+            .flatten()
+            .and_then(|s| find(GainTrimKind::SongType, &s.songtype))?,
+    };
+    crate::db::local::get_crossfade_profile(local, profile_id)
+        .await
+        .ok()
+        .flatten()
+        .map(|p| crate::commands::crossfade_commands::normalize_crossfade_config(p.config))
+}
+
 async fn translate_sam_file_path(local_pool: &sqlx::SqlitePool, input: String) -> String {
-    if let Ok(cfg) = crate::db::local::get_sam_db_config(local_pool).await {
-        if !cfg.path_prefix_from.is_empty() {
-            return crate::db::sam::translate_path(
-                &input,
-                &cfg.path_prefix_from,
-                &cfg.path_prefix_to,
-            );
+    crate::db::path_rules::load_translator(local_pool)
+        .await
+        .translate(&input)
+}
+
+/// Picks tried per call before AutoDJ gives up until the next tick.
+const MAX_PREFLIGHT_ATTEMPTS: usize = 5;
+
+/// Next track for AutoDJ, validated before it goes near a deck. A pick that
+/// fails pre-flight is quarantined and another one is chosen straight away.
+async fn pick_next_track(
+    state: &AppState,
+    mode: crate::scheduler::autodj::DjMode,
+    claimed_queue_ids: &std::collections::HashSet<i64>,
+    rng: &mut impl rand::Rng,
+) -> Option<RuntimeTrackPick> {
+    let mut rejected = std::collections::HashSet::new();
+    for _ in 0..MAX_PREFLIGHT_ATTEMPTS {
+        let pick = pick_track_candidate(state, mode, claimed_queue_ids, &rejected, rng).await?;
+        let path = std::path::PathBuf::from(&pick.file_path);
+        let declared_ms = pick.declared_duration_ms;
+        let checked = tokio::task::spawn_blocking(move || {
+            crate::scheduler::track_preflight::validate(&path, declared_ms)
+        })
+        .await;
+        match checked {
+            Ok(Ok(())) => return Some(pick),
+            Ok(Err(failure)) => {
+                quarantine_track(state, &pick, &failure.to_string()).await;
+                rejected.insert(pick.song_id);
+            }
+            Err(err) => {
+                log::warn!("Pre-flight check did not run: {}", err);
+                return Some(pick);
+            }
         }
     }
-    input
+    log::warn!(
+        "No playable track after {} pre-flight attempts",
+        MAX_PREFLIGHT_ATTEMPTS
+    );
+    None
 }
 
-async fn pick_next_track(
+/// Log a track that cannot be played, keep AutoDJ away from it for a while
+/// and take its queue entry off the SAM queue.
+async fn quarantine_track(state: &AppState, pick: &RuntimeTrackPick, reason: &str) {
+    log::warn!(
+        "Quarantining song {} ({}): {}",
+        pick.song_id,
+        pick.file_path,
+        reason
+    );
+    if let Some(qid) = pick.queue_id {
+        claim_queue_item(state, qid).await;
+    }
+    let Some(pool) = state.local_db.as_ref() else {
+        return;
+    };
+    if let Err(err) =
+        crate::scheduler::track_preflight::quarantine(pool, pick.song_id, &pick.file_path, reason)
+            .await
+    {
+        log::warn!("Failed to record bad track {}: {}", pick.song_id, err);
+    }
+}
+
+/// A deck load failed. Only a bad file is quarantined; a full command queue
+/// or a lost device says nothing about the track.
+async fn note_failed_load(state: &AppState, pick: &RuntimeTrackPick, err: &crate::error::AppError) {
+    if crate::scheduler::track_preflight::is_track_fault(err) {
+        quarantine_track(state, pick, &err.message).await;
+    } else {
+        log::warn!("Loading song {} failed: {}", pick.song_id, err.message);
+    }
+}
+
+async fn pick_track_candidate(
     state: &AppState,
     mode: crate::scheduler::autodj::DjMode,
     claimed_queue_ids: &std::collections::HashSet<i64>,
+    rejected_song_ids: &std::collections::HashSet<i64>,
+    rng: &mut impl rand::Rng,
 ) -> Option<RuntimeTrackPick> {
     let local_pool = state.local_db.clone()?;
     let sam_pool = {
@@ -1589,15 +3308,52 @@ async fn pick_next_track(
         .filter_map(|deck| engine.get_deck_state(*deck).and_then(|ev| ev.song_id))
         .collect()
     };
+    // Songs flagged by the last missing-file scan would fail to load.
+    let missing_song_ids = crate::analytics::missing_files::missing_song_ids(&local_pool)
+        .await
+        .unwrap_or_default();
+    // Songs that recently failed pre-flight or a deck load, plus this call's
+    // rejects.
+    let mut bad_song_ids = crate::scheduler::track_preflight::quarantined_song_ids(&local_pool)
+        .await
+        .unwrap_or_default();
+    bad_song_ids.extend(rejected_song_ids);
 
-    if let Ok(queue) = crate::db::sam::get_queue(&sam_pool).await {
+    // SAM is unreachable: its queue can't be read, so rotation plays from the
+    // candidates cached while it was up.
+    let degraded = crate::db::sam_health::is_degraded();
+    let queue = if degraded {
+        None
+    } else {
+        crate::db::sam::get_queue(&sam_pool).await.ok()
+    };
+    if let Some(queue) = queue {
         for entry in queue {
-            if claimed_queue_ids.contains(&entry.id) {
+            if claimed_queue_ids.contains(&entry.id)
+                || crate::db::sam_outbox::removal_pending(entry.id)
+            {
                 continue;
             }
             if active_song_ids.contains(&entry.song_id) {
                 continue;
             }
+            if missing_song_ids.contains(&entry.song_id) {
+                log::warn!(
+                    "Skipping queue entry {} — song {} is flagged as missing",
+                    entry.id,
+                    entry.song_id
+                );
+                continue;
+            }
+            if bad_song_ids.contains(&entry.song_id) {
+                log::warn!(
+                    "Removing queue entry {} — song {} is quarantined",
+                    entry.id,
+                    entry.song_id
+                );
+                claim_queue_item(state, entry.id).await;
+                continue;
+            }
             let mut song = entry.song;
             if song.is_none() {
                 song = crate::db::sam::get_song(&sam_pool, entry.song_id)
@@ -1626,15 +3382,30 @@ async fn pick_next_track(
         return None;
     }
 
-    let rotation_pick = crate::scheduler::rotation::select_next_track_with_exclusions(
-        &local_pool,
-        &sam_pool,
-        None,
-        Some(&active_song_ids),
-    )
-    .await
-    .ok()
-    .flatten()?;
+    let excluded: std::collections::HashSet<i64> = active_song_ids
+        .iter()
+        .chain(&missing_song_ids)
+        .chain(&bad_song_ids)
+        .copied()
+        .collect();
+    let rotation_pick = if degraded {
+        crate::scheduler::rotation::select_cached_track(Some(&excluded), rng)
+    } else {
+        match crate::scheduler::rotation::next_rotation_track(
+            &local_pool,
+            &sam_pool,
+            &excluded,
+            rng,
+        )
+        .await
+        {
+            Ok(pick) => pick,
+            Err(err) => {
+                log::warn!("Rotation selection failed, using cached candidates: {err}");
+                crate::scheduler::rotation::select_cached_track(Some(&excluded), rng)
+            }
+        }
+    }?;
     let translated = translate_sam_file_path(&local_pool, rotation_pick.file_path).await;
 
     Some(RuntimeTrackPick {
@@ -1650,6 +3421,7 @@ async fn pick_next_track(
 async fn top_up_rotation_queue(
     state: &AppState,
     claimed_queue_ids: &std::collections::HashSet<i64>,
+    rng: &mut impl rand::Rng,
 ) {
     let Some(local_pool) = state.local_db.clone() else {
         return;
@@ -1662,6 +3434,9 @@ async fn top_up_rotation_queue(
     let Some(sam_pool) = sam_pool else {
         return;
     };
+    if crate::db::sam_health::is_degraded() {
+        return;
+    }
 
     let clockwheel_cfg = crate::scheduler::rotation::get_clockwheel_config(&local_pool)
         .await
@@ -1681,9 +3456,13 @@ async fn top_up_rotation_queue(
 
     let unclaimed_depth = queue
         .iter()
-        .filter(|entry| !claimed_queue_ids.contains(&entry.id))
+        .filter(|entry| {
+            !claimed_queue_ids.contains(&entry.id)
+                && !crate::db::sam_outbox::removal_pending(entry.id)
+        })
         .count();
-    if unclaimed_depth >= target_depth {
+    let use_ghost_queue = clockwheel_cfg.rules.use_ghost_queue;
+    if unclaimed_depth >= target_depth && !use_ghost_queue {
         return;
     }
 
@@ -1704,6 +3483,25 @@ async fn top_up_rotation_queue(
             }
         }
     }
+    // Don't queue songs AutoDJ would only skip.
+    if let Ok(bad) = crate::scheduler::track_preflight::quarantined_song_ids(&local_pool).await {
+        excluded_song_ids.extend(bad);
+    }
+
+    if unclaimed_depth >= target_depth {
+        // SAM queue is full; keep the look-ahead planned for the UI.
+        if let Err(err) = crate::scheduler::rotation::refill_ghost_queue(
+            &local_pool,
+            &sam_pool,
+            &excluded_song_ids,
+            rng,
+        )
+        .await
+        {
+            log::warn!("Ghost queue refill failed: {}", err);
+        }
+        return;
+    }
 
     let mut needed = target_depth.saturating_sub(unclaimed_depth);
     let max_attempts = (needed.saturating_mul(8)).max(8);
@@ -1712,11 +3510,11 @@ async fn top_up_rotation_queue(
             break;
         }
 
-        let next = match crate::scheduler::rotation::select_next_track_with_exclusions(
+        let next = match crate::scheduler::rotation::next_rotation_track(
             &local_pool,
             &sam_pool,
-            None,
-            Some(&excluded_song_ids),
+            &excluded_song_ids,
+            rng,
         )
         .await
         {
@@ -1733,7 +3531,8 @@ async fn top_up_rotation_queue(
         }
 
         match crate::db::sam::add_to_queue(&sam_pool, next.song_id).await {
-            Ok(_) => {
+            Ok(queue_id) => {
+                crate::scheduler::autodj_plan::song_queued(next.song_id, queue_id);
                 excluded_song_ids.insert(next.song_id);
                 needed = needed.saturating_sub(1);
             }
@@ -1749,16 +3548,15 @@ async fn top_up_rotation_queue(
     }
 }
 
-async fn claim_queue_item(state: &AppState, queue_id: i64) {
-    let sam_pool = {
-        let guard = state.sam_db.read().await;
-        guard.as_ref().cloned()
-    };
-    let Some(sam_pool) = sam_pool else {
+pub(crate) async fn claim_queue_item(state: &AppState, queue_id: i64) {
+    if state.sam_db.read().await.is_none() {
+        return;
+    }
+    let Some(local) = &state.local_db else {
         return;
     };
-
-    if let Err(err) = crate::db::sam::remove_from_queue(&sam_pool, queue_id).await {
+    let write = crate::db::sam_outbox::SamWrite::RemoveFromQueue { queue_id };
+    if let Err(err) = crate::db::sam_outbox::enqueue(local, &[write]).await {
         log::warn!(
             "Failed to claim queue item {} after deck load: {}",
             queue_id,
@@ -1767,21 +3565,35 @@ async fn claim_queue_item(state: &AppState, queue_id: i64) {
     }
 }
 
+/// Record completed plays. SAM writes go through the outbox and local
+/// bookkeeping that needs the song's metadata runs in the background, so the
+/// AutoDJ loop never waits on MySQL here.
 async fn process_track_completions(
     state: &AppState,
     completed: Vec<crate::audio::engine::TrackCompletionEvent>,
 ) -> Vec<i64> {
+    use crate::db::sam_outbox::SamWrite;
+
     if completed.is_empty() {
         return Vec::new();
     }
+    let ended_at = chrono::Utc::now().timestamp_millis();
+    for ev in &completed {
+        crate::stream::recording_chapters::complete_track(ev.song_id, ended_at);
+    }
     let sam_pool = {
         let guard = state.sam_db.read().await;
         guard.as_ref().cloned()
     };
+    let Some(local) = state.local_db.clone() else {
+        return Vec::new();
+    };
     let Some(sam_pool) = sam_pool else {
+        for ev in &completed {
+            log_play_completion(&local, ev, None).await;
+        }
         return Vec::new();
     };
-    let local_pool = state.local_db.clone();
     let mut completed_queue_ids = Vec::new();
     let listeners_total: i64 = state
         .encoder_manager
@@ -1790,48 +3602,24 @@ async fn process_track_completions(
         .map(|r| r.listeners.unwrap_or(0) as i64)
         .sum();
     let listener_snapshot = listeners_total.clamp(0, i32::MAX as i64) as i32;
+    let played_at = chrono::Utc::now().timestamp_millis();
+    let weight_delta = crate::scheduler::rotation::on_play_weight_delta(&local).await;
 
-    for ev in completed {
-        let song = match crate::db::sam::get_song(&sam_pool, ev.song_id)
-            .await
-            .ok()
-            .flatten()
-        {
-            Some(s) => s,
-            None => continue,
-        };
-
+    let mut writes = Vec::new();
+    for ev in &completed {
         if let Some(queue_id) = ev.queue_id {
             completed_queue_ids.push(queue_id);
-            if let Err(err) =
-                crate::db::sam::complete_track(&sam_pool, queue_id, &song, listener_snapshot).await
-            {
-                log::warn!(
-                    "Failed to complete queue track (queue_id={}, song_id={}): {}",
-                    queue_id,
-                    ev.song_id,
-                    err
-                );
-                let _ = crate::db::sam::add_to_history_with_listeners(
-                    &sam_pool,
-                    &song,
-                    listener_snapshot,
-                )
-                .await;
-            }
-        } else if let Err(err) =
-            crate::db::sam::add_to_history_with_listeners(&sam_pool, &song, listener_snapshot).await
-        {
-            log::warn!(
-                "Failed to append history for completed track (song_id={}): {}",
-                ev.song_id,
-                err
-            );
+            writes.push(SamWrite::RemoveFromQueue { queue_id });
         }
+        writes.push(SamWrite::History {
+            song_id: ev.song_id,
+            listeners: listener_snapshot,
+            played_at,
+        });
 
-        let request_origin = if let Some(local) = &local_pool {
+        let request_origin =
             match crate::scheduler::request_policy::consume_oldest_accepted_request_for_song(
-                local, ev.song_id,
+                &local, ev.song_id,
             )
             .await
             {
@@ -1852,43 +3640,116 @@ async fn process_track_completions(
                     );
                     false
                 }
-            }
-        } else {
-            false
-        };
-
-        if let Err(err) = crate::db::sam::update_songlist_play_stats(
-            &sam_pool,
-            ev.song_id,
-            listener_snapshot,
+            };
+        writes.push(SamWrite::PlayStats {
+            song_id: ev.song_id,
+            listeners: listener_snapshot,
             request_origin,
-        )
-        .await
-        {
-            log::warn!(
-                "Failed to update songlist playback stats (song_id={}): {}",
-                ev.song_id,
-                err
-            );
+        });
+        if weight_delta.abs() >= f64::EPSILON {
+            writes.push(SamWrite::WeightDelta {
+                song_id: ev.song_id,
+                delta: weight_delta,
+            });
         }
+    }
+    if let Err(err) = crate::db::sam_outbox::enqueue(&local, &writes).await {
+        log::warn!("Failed to queue SAM writes for completed tracks: {}", err);
+    }
 
-        if let Some(local) = &local_pool {
-            if let Err(err) =
-                crate::scheduler::rotation::apply_weight_delta_on_play(local, &sam_pool, ev.song_id)
-                    .await
+    // The play log, scrobbles and scripts want artist/title, which means a
+    // SAM read.
+    let scripts = state.script_engine.clone();
+    tauri::async_runtime::spawn(async move {
+        for ev in completed {
+            let song = crate::db::sam::get_song(&sam_pool, ev.song_id)
+                .await
+                .ok()
+                .flatten();
+            log_play_completion(&local, &ev, song.as_ref()).await;
+            if matches!(ev.reason, crate::audio::deck::StopReason::Ended) {
+                scripts.fire(crate::scripting::trigger::ScriptEvent::TrackEnd {
+                    id: ev.song_id,
+                    title: song.as_ref().map(|s| s.title.clone()).unwrap_or_default(),
+                });
+            }
+            let Some(song) = song else {
+                continue;
+            };
+            match crate::analytics::scrobbler::record_play(
+                &local,
+                &song,
+                ev.played_ms,
+                ev.duration_ms,
+            )
+            .await
             {
-                log::warn!(
-                    "Failed to apply on-play weight adjustment (song_id={}): {}",
-                    ev.song_id,
-                    err
-                );
+                Ok(true) => {
+                    let local = local.clone();
+                    tauri::async_runtime::spawn(async move {
+                        let _ = crate::analytics::scrobbler::drain_queue(&local).await;
+                    });
+                }
+                Ok(false) => {}
+                Err(err) => {
+                    log::warn!("Failed to queue scrobble (song_id={}): {}", ev.song_id, err)
+                }
             }
         }
-    }
+    });
 
     completed_queue_ids
 }
 
+/// Tell scripts a library track went on air.
+fn fire_script_track_start(app: &tauri::AppHandle, deck: &crate::audio::engine::DeckStateEvent) {
+    let Some(song_id) = deck.song_id else {
+        return;
+    };
+    let duration_ms = deck.duration_ms;
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let state = app.state::<AppState>();
+        let Some(pool) = ({ state.sam_db.read().await.as_ref().cloned() }) else {
+            return;
+        };
+        let Ok(Some(song)) = crate::db::sam::get_song(&pool, song_id).await else {
+            return;
+        };
+        let category = crate::db::sam::get_song_category_names(&pool, Some(&[song_id]))
+            .await
+            .ok()
+            .and_then(|mut names| names.remove(&song_id));
+        state
+            .script_engine
+            .fire(crate::scripting::trigger::ScriptEvent::TrackStart {
+                id: song.id,
+                title: song.title,
+                artist: song.artist,
+                album: Some(song.album).filter(|a| !a.is_empty()),
+                duration_ms,
+                category,
+            });
+    });
+}
+
+async fn log_play_completion(
+    pool: &sqlx::SqlitePool,
+    ev: &crate::audio::engine::TrackCompletionEvent,
+    song: Option<&crate::db::sam::SamSong>,
+) {
+    if let Err(err) = crate::analytics::play_log::record_completion(
+        pool,
+        ev,
+        song.map(|s| s.artist.as_str()),
+        song.map(|s| s.title.as_str()),
+    )
+    .await
+    {
+        log::warn!("Failed to write play log (song_id={}): {}", ev.song_id, err);
+    }
+}
+
 /// Return the platform-specific application data directory.
 /// Mirrors what Tauri resolves for `PathResolver::app_data_dir()`.
 fn compute_app_data_dir() -> String {
@@ -0,0 +1,347 @@
+/// Process-wide tracing setup.
+///
+/// The `log::` macros used across the crate are bridged into `tracing` and fanned
+/// out to stderr, a daily-rotated JSON-lines file under `<app data>/logs`, and —
+/// for errors only — the `event_log` table, so support can pull diagnostics
+/// without asking an operator to run the app from a terminal.
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use tokio::sync::mpsc;
+use tracing::{
+    field::{Field, Visit},
+    Event, Level, Subscriber,
+};
+use tracing_appender::{
+    non_blocking::WorkerGuard,
+    rolling::{RollingFileAppender, Rotation},
+};
+use tracing_log::NormalizeEvent;
+use tracing_subscriber::{
+    fmt,
+    layer::{Context, SubscriberExt},
+    util::SubscriberInitExt,
+    EnvFilter, Layer,
+};
+
+use crate::analytics::EventCategory;
+
+const LOG_FILE_PREFIX: &str = "desizone";
+const LOG_FILE_SUFFIX: &str = "log";
+/// Daily files kept before the oldest is deleted.
+const MAX_LOG_FILES: usize = 14;
+/// Errors buffered for `event_log` before the database is attached; extras are dropped.
+const ERROR_BACKLOG: usize = 512;
+const CRATE_PREFIX: &str = "desizone_broadcaster_lib::";
+/// `event_logger::log_event` echoes to `log::`; forwarding those would double-write.
+const EVENT_LOGGER_TARGET: &str = "desizone_broadcaster_lib::analytics::event_logger";
+
+static LOG_DIR: OnceLock<PathBuf> = OnceLock::new();
+static FILE_GUARD: OnceLock<WorkerGuard> = OnceLock::new();
+static ERROR_RX: Mutex<Option<mpsc::Receiver<CapturedError>>> = Mutex::new(None);
+
+#[derive(Debug)]
+struct CapturedError {
+    timestamp_ms: i64,
+    category: String,
+    target: String,
+    message: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AppLogEntry {
+    pub timestamp: String,
+    pub level: String,
+    pub category: String,
+    pub target: String,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AppLogResponse {
+    pub log_dir: Option<String>,
+    pub entries: Vec<AppLogEntry>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct AppLogFilter {
+    /// Minimum severity (`error`, `warn`, `info`, `debug`, `trace`)
+    pub level: Option<String>,
+    pub category: Option<String>,
+    pub search: Option<String>,
+}
+
+/// Install the global subscriber and panic hook. `RUST_LOG` overrides the
+/// default `info` filter.
+pub fn init(log_dir: &Path) {
+    std::panic::set_hook(Box::new(|info| {
+        let bt = std::backtrace::Backtrace::force_capture();
+        log::error!("panic: {info}\n{bt}");
+    }));
+
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
+    let stderr_layer = fmt::layer().with_writer(std::io::stderr).with_target(true);
+
+    let file_layer = match RollingFileAppender::builder()
+        .rotation(Rotation::DAILY)
+        .filename_prefix(LOG_FILE_PREFIX)
+        .filename_suffix(LOG_FILE_SUFFIX)
+        .max_log_files(MAX_LOG_FILES)
+        .build(log_dir)
+    {
+        Ok(appender) => {
+            let (writer, guard) = tracing_appender::non_blocking(appender);
+            let _ = FILE_GUARD.set(guard);
+            let _ = LOG_DIR.set(log_dir.to_path_buf());
+            Some(
+                fmt::layer()
+                    .json()
+                    .flatten_event(true)
+                    .with_current_span(false)
+                    .with_span_list(false)
+                    .with_ansi(false)
+                    .with_writer(writer),
+            )
+        }
+        Err(e) => {
+            eprintln!("[startup] log file disabled ({}): {e}", log_dir.display());
+            None
+        }
+    };
+
+    let (tx, rx) = mpsc::channel(ERROR_BACKLOG);
+    *ERROR_RX.lock().unwrap() = Some(rx);
+
+    match tracing_subscriber::registry()
+        .with(filter)
+        .with(stderr_layer)
+        .with(file_layer)
+        .with(EventLogLayer { tx })
+        .try_init()
+    {
+        Ok(()) => log::info!("Logger initialized ({})", log_dir.display()),
+        Err(e) => eprintln!("[startup] logger init skipped: {e}"),
+    }
+}
+
+/// Start copying captured errors into `event_log`. Errors logged before the
+/// database opened are flushed first.
+pub fn spawn_event_log_sink(pool: SqlitePool) {
+    let Some(mut rx) = ERROR_RX.lock().unwrap().take() else {
+        return;
+    };
+    tauri::async_runtime::spawn(async move {
+        while let Some(err) = rx.recv().await {
+            let metadata = serde_json::json!({ "target": err.target }).to_string();
+            let result = sqlx::query(
+                r#"
+                INSERT INTO event_log (timestamp, level, category, event, message, metadata_json)
+                VALUES (?, 'error', ?, 'log_error', ?, ?)
+                "#,
+            )
+            .bind(err.timestamp_ms)
+            .bind(&err.category)
+            .bind(&err.message)
+            .bind(metadata)
+            .execute(&pool)
+            .await;
+            if let Err(e) = result {
+                // Warn level is not forwarded, so this cannot loop.
+                log::warn!("Failed to persist error to event_log: {e}");
+            }
+        }
+    });
+}
+
+pub fn log_dir() -> Option<&'static Path> {
+    LOG_DIR.get().map(PathBuf::as_path)
+}
+
+/// Read the rotated log files, newest entry first.
+pub fn read_app_logs(filter: &AppLogFilter, limit: usize) -> Result<Vec<AppLogEntry>, String> {
+    let Some(dir) = log_dir() else {
+        return Ok(Vec::new());
+    };
+    let mut files: Vec<PathBuf> = std::fs::read_dir(dir)
+        .map_err(|e| format!("Cannot read log dir {}: {e}", dir.display()))?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| {
+            path.file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|n| n.starts_with(LOG_FILE_PREFIX) && n.ends_with(LOG_FILE_SUFFIX))
+        })
+        .collect();
+    // Date-stamped names sort chronologically.
+    files.sort();
+
+    let min_rank = filter.level.as_deref().map_or(u8::MAX, level_rank);
+    let category = filter
+        .category
+        .as_deref()
+        .map(str::trim)
+        .filter(|c| !c.is_empty())
+        .map(str::to_lowercase);
+    let search = filter
+        .search
+        .as_deref()
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(str::to_lowercase);
+
+    let mut out = Vec::new();
+    for path in files.iter().rev() {
+        let Ok(content) = std::fs::read_to_string(path) else {
+            continue;
+        };
+        for line in content.lines().rev() {
+            let Some(entry) = parse_line(line) else {
+                continue;
+            };
+            if level_rank(&entry.level) > min_rank {
+                continue;
+            }
+            if category.as_deref().is_some_and(|c| c != entry.category) {
+                continue;
+            }
+            if let Some(needle) = search.as_deref() {
+                if !entry.message.to_lowercase().contains(needle)
+                    && !entry.target.to_lowercase().contains(needle)
+                {
+                    continue;
+                }
+            }
+            out.push(entry);
+            if out.len() >= limit {
+                return Ok(out);
+            }
+        }
+    }
+    Ok(out)
+}
+
+/// 1 = error … 5 = trace; unknown levels sort last.
+fn level_rank(level: &str) -> u8 {
+    match level.trim().to_ascii_lowercase().as_str() {
+        "error" => 1,
+        "warn" | "warning" => 2,
+        "info" => 3,
+        "debug" => 4,
+        "trace" => 5,
+        _ => u8::MAX,
+    }
+}
+
+fn category_for_target(target: &str) -> EventCategory {
+    let module = target
+        .strip_prefix(CRATE_PREFIX)
+        .unwrap_or(target)
+        .split("::")
+        .next()
+        .unwrap_or_default();
+    match module {
+        "audio" => EventCategory::Audio,
+        "stream" | "stats" => EventCategory::Stream,
+        "scheduler" => EventCategory::Scheduler,
+        "gateway" => EventCategory::Gateway,
+        "scripting" => EventCategory::Scripting,
+        "db" | "sqlx" => EventCategory::Database,
+        _ => EventCategory::System,
+    }
+}
+
+fn parse_line(line: &str) -> Option<AppLogEntry> {
+    #[derive(Deserialize)]
+    struct Record {
+        timestamp: String,
+        level: String,
+        #[serde(default)]
+        target: String,
+        #[serde(default)]
+        message: String,
+    }
+
+    let record: Record = serde_json::from_str(line).ok()?;
+    Some(AppLogEntry {
+        category: category_for_target(&record.target).as_str().to_string(),
+        timestamp: record.timestamp,
+        level: record.level.to_lowercase(),
+        target: record.target,
+        message: record.message,
+    })
+}
+
+/// Forwards error-level events to the `event_log` sink.
+struct EventLogLayer {
+    tx: mpsc::Sender<CapturedError>,
+}
+
+impl<S: Subscriber> Layer<S> for EventLogLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let normalized = event.normalized_metadata();
+        let meta = normalized.as_ref().unwrap_or_else(|| event.metadata());
+        if *meta.level() != Level::ERROR || meta.target().starts_with(EVENT_LOGGER_TARGET) {
+            return;
+        }
+        let mut visitor = MessageVisitor::default();
+        event.record(&mut visitor);
+        let _ = self.tx.try_send(CapturedError {
+            timestamp_ms: chrono::Utc::now().timestamp_millis(),
+            category: category_for_target(meta.target()).as_str().to_string(),
+            target: meta.target().to_string(),
+            message: visitor.message,
+        });
+    }
+}
+
+#[derive(Default)]
+struct MessageVisitor {
+    message: String,
+}
+
+impl Visit for MessageVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        let name = field.name();
+        if name.starts_with("log.") {
+            return;
+        }
+        if !self.message.is_empty() {
+            self.message.push(' ');
+        }
+        if name == "message" {
+            self.message.push_str(&format!("{value:?}"));
+        } else {
+            self.message.push_str(&format!("{name}={value:?}"));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_json_lines_and_maps_categories() {
+        let line = r#"{"timestamp":"2026-10-18T09:12:03.120Z","level":"WARN","message":"Format read: end of stream","target":"desizone_broadcaster_lib::audio::decoder"}"#;
+        let entry = parse_line(line).expect("valid record");
+        assert_eq!(entry.level, "warn");
+        assert_eq!(entry.category, "audio");
+        assert_eq!(entry.message, "Format read: end of stream");
+
+        assert!(parse_line("not json").is_none());
+        assert_eq!(category_for_target("sqlx::query").as_str(), "database");
+        assert_eq!(
+            category_for_target("desizone_broadcaster_lib::stats::icecast_stats").as_str(),
+            "stream"
+        );
+        assert_eq!(category_for_target("tao::platform").as_str(), "system");
+    }
+
+    #[test]
+    fn level_filter_is_a_minimum_severity() {
+        assert!(level_rank("error") <= level_rank("warn"));
+        assert!(level_rank("INFO") > level_rank("warn"));
+        assert_eq!(level_rank("bogus"), u8::MAX);
+    }
+}
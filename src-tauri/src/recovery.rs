@@ -0,0 +1,328 @@
+/// Crash-safe runtime snapshots.
+///
+/// A background loop saves what is on air (loaded decks and positions, DJ mode,
+/// crossfader, live encoders) every few seconds. A clean exit marks the row; if
+/// the app starts and the last snapshot was never marked, the operator is
+/// offered `resume_previous_session` to put the show back where it stopped.
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+use sqlx::{Row, SqlitePool};
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::{
+    audio::crossfade::DeckId,
+    scheduler::{
+        autodj::{self, DjMode},
+        mode_transition::{self, ModeChangeRequest},
+    },
+    state::AppState,
+    stream::broadcaster::EncoderStatus,
+};
+
+/// How often the runtime snapshot is written.
+pub const SNAPSHOT_INTERVAL_SECS: u64 = 5;
+/// Older crash snapshots are not offered; the show has moved on.
+const MAX_RESUME_AGE_MS: i64 = 12 * 60 * 60 * 1000;
+
+/// Crash snapshot found at startup, held until resumed or discarded.
+static PREVIOUS: Mutex<Option<SessionSnapshot>> = Mutex::new(None);
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DeckSnapshot {
+    pub deck: String,
+    pub file_path: String,
+    pub song_id: Option<i64>,
+    pub position_ms: u64,
+    pub duration_ms: u64,
+    pub playing: bool,
+    pub channel_gain: f32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionSnapshot {
+    /// Unix ms
+    pub saved_at: i64,
+    pub dj_mode: String,
+    pub crossfader: f32,
+    pub decks: Vec<DeckSnapshot>,
+    /// Encoders that were connecting, streaming or recording
+    pub live_encoder_ids: Vec<i64>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ResumeReport {
+    pub decks_restored: Vec<String>,
+    /// Decks whose file is no longer on disk
+    pub decks_missing: Vec<String>,
+    pub encoders_started: Vec<i64>,
+    pub dj_mode: Option<String>,
+}
+
+impl SessionSnapshot {
+    /// Whether this snapshot is worth offering to the operator at `now_ms`.
+    pub fn is_resumable(&self, now_ms: i64) -> bool {
+        let has_content = !self.decks.is_empty() || !self.live_encoder_ids.is_empty();
+        has_content && now_ms.saturating_sub(self.saved_at) <= MAX_RESUME_AGE_MS
+    }
+}
+
+/// Capture the current runtime state.
+pub fn capture(state: &AppState) -> SessionSnapshot {
+    let (decks, crossfader) = {
+        let engine = state.engine.lock().unwrap();
+        let decks = DeckId::PLAYBACK
+            .iter()
+            .filter_map(|id| engine.get_deck_state(*id))
+            .filter_map(|ev| {
+                let file_path = ev.file_path?;
+                Some(DeckSnapshot {
+                    playing: matches!(ev.state.as_str(), "playing" | "crossfading"),
+                    deck: ev.deck,
+                    file_path,
+                    song_id: ev.song_id,
+                    position_ms: ev.position_ms,
+                    duration_ms: ev.duration_ms,
+                    channel_gain: ev.channel_gain,
+                })
+            })
+            .collect();
+        (decks, engine.get_manual_crossfade_pos())
+    };
+    let live_encoder_ids = state
+        .encoder_manager
+        .get_all_runtime()
+        .into_iter()
+        .filter(|r| {
+            matches!(
+                r.status,
+                EncoderStatus::Connecting
+                    | EncoderStatus::Streaming
+                    | EncoderStatus::Retrying { .. }
+                    | EncoderStatus::Recording
+            )
+        })
+        .map(|r| r.id)
+        .collect();
+
+    SessionSnapshot {
+        saved_at: chrono::Utc::now().timestamp_millis(),
+        dj_mode: autodj::get_dj_mode().as_str().to_string(),
+        crossfader,
+        decks,
+        live_encoder_ids,
+    }
+}
+
+pub async fn save_snapshot(pool: &SqlitePool, snapshot: &SessionSnapshot) -> Result<(), String> {
+    let json = serde_json::to_string(snapshot).map_err(|e| e.to_string())?;
+    sqlx::query(
+        r#"
+        INSERT INTO session_snapshot (id, snapshot_json, saved_at, clean_exit)
+        VALUES (1, ?, ?, 0)
+        ON CONFLICT(id) DO UPDATE SET
+            snapshot_json = excluded.snapshot_json,
+            saved_at = excluded.saved_at,
+            clean_exit = 0
+        "#,
+    )
+    .bind(json)
+    .bind(snapshot.saved_at)
+    .execute(pool)
+    .await
+    .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+pub async fn mark_clean_exit(pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    sqlx::query("UPDATE session_snapshot SET clean_exit = 1 WHERE id = 1")
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// Load the last snapshot if the previous run did not exit cleanly.
+pub async fn load_unclean_snapshot(
+    pool: &SqlitePool,
+) -> Result<Option<SessionSnapshot>, sqlx::Error> {
+    let row = sqlx::query("SELECT snapshot_json, clean_exit FROM session_snapshot WHERE id = 1")
+        .fetch_optional(pool)
+        .await?;
+    Ok(row.and_then(|r| {
+        if r.get::<i64, _>("clean_exit") != 0 {
+            return None;
+        }
+        serde_json::from_str(&r.get::<String, _>("snapshot_json")).ok()
+    }))
+}
+
+/// Look for a crash snapshot, stash it for the resume commands, then start
+/// the periodic writer. Must run before the first write replaces the row.
+pub fn start(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let Some(pool) = app.state::<AppState>().local_db.clone() else {
+            return;
+        };
+        match load_unclean_snapshot(&pool).await {
+            Ok(Some(snapshot)) if snapshot.is_resumable(chrono::Utc::now().timestamp_millis()) => {
+                log::warn!(
+                    "Previous session ended unexpectedly ({} deck(s), {} encoder(s) live)",
+                    snapshot.decks.len(),
+                    snapshot.live_encoder_ids.len()
+                );
+                *PREVIOUS.lock().unwrap() = Some(snapshot.clone());
+                let _ = app.emit("previous_session_available", snapshot);
+            }
+            Ok(_) => {}
+            Err(e) => log::warn!("Session snapshot not read: {e}"),
+        }
+
+        let mut interval =
+            tokio::time::interval(std::time::Duration::from_secs(SNAPSHOT_INTERVAL_SECS));
+        loop {
+            interval.tick().await;
+            let snapshot = capture(&app.state::<AppState>());
+            if let Err(e) = save_snapshot(&pool, &snapshot).await {
+                log::warn!("Session snapshot not saved: {e}");
+            }
+        }
+    });
+}
+
+pub fn previous() -> Option<SessionSnapshot> {
+    PREVIOUS.lock().unwrap().clone()
+}
+
+pub fn discard_previous() -> bool {
+    PREVIOUS.lock().unwrap().take().is_some()
+}
+
+/// Reload decks at their saved positions, restore the crossfader and DJ mode,
+/// and restart encoders that were live, through the station ID gate. The
+/// snapshot is only consumed once the resume succeeds.
+pub async fn resume_previous(app: &AppHandle, override_gate: bool) -> Result<ResumeReport, String> {
+    let snapshot = PREVIOUS
+        .lock()
+        .unwrap()
+        .clone()
+        .ok_or("No previous session to resume")?;
+    let state = app.state::<AppState>();
+    let mut report = ResumeReport::default();
+
+    let known: Vec<i64> = state
+        .encoder_manager
+        .get_encoders()
+        .iter()
+        .map(|c| c.id)
+        .collect();
+    let to_start: Vec<i64> = snapshot
+        .live_encoder_ids
+        .iter()
+        .copied()
+        .filter(|id| known.contains(id))
+        .collect();
+    // Checked before anything is touched, so a blocked resume can be retried.
+    if !to_start.is_empty() {
+        crate::commands::encoder_commands::enforce_station_id_gate(
+            &state,
+            &to_start,
+            override_gate,
+        )
+        .await?;
+    }
+
+    for deck in &snapshot.decks {
+        let deck_id = crate::commands::audio_commands::parse_deck(&deck.deck)?;
+        let path = PathBuf::from(&deck.file_path);
+        if !path.is_file() {
+            log::warn!("Resume: {} missing for {}", deck.file_path, deck.deck);
+            report.decks_missing.push(deck.deck.clone());
+            continue;
+        }
+        let trim_db = crate::resolve_track_gain_db(&state, deck.song_id).await;
+        let mut engine = state.engine.lock().unwrap();
+        engine.load_track_at(deck_id, path, deck.song_id, deck.position_ms)?;
+        let _ = engine.set_track_gain_db(deck_id, trim_db);
+        let _ = engine.set_channel_gain(deck_id, deck.channel_gain);
+        if deck.playing {
+            engine.play(deck_id)?;
+        }
+        report.decks_restored.push(deck.deck.clone());
+    }
+    let _ = state
+        .engine
+        .lock()
+        .unwrap()
+        .set_manual_crossfade(snapshot.crossfader);
+
+    let mode = DjMode::from_str(&snapshot.dj_mode);
+    if mode != autodj::get_dj_mode() {
+        mode_transition::request_change(app, ModeChangeRequest::immediate(mode)).await?;
+        report.dj_mode = Some(mode.as_str().to_string());
+    }
+
+    if !to_start.is_empty() {
+        crate::commands::encoder_commands::ensure_broadcast_loop(&state);
+        let source_sr = crate::commands::encoder_commands::current_engine_sample_rate(&state);
+        for id in to_start {
+            state
+                .encoder_manager
+                .start_encoder_with_sample_rate(id, Some(source_sr), None);
+            report.encoders_started.push(id);
+        }
+    }
+
+    PREVIOUS.lock().unwrap().take();
+    log::info!(
+        "Resumed previous session: {} deck(s), {} encoder(s)",
+        report.decks_restored.len(),
+        report.encoders_started.len()
+    );
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot(saved_at: i64) -> SessionSnapshot {
+        SessionSnapshot {
+            saved_at,
+            dj_mode: "autodj".to_string(),
+            crossfader: -1.0,
+            decks: vec![DeckSnapshot {
+                deck: "deck_a".to_string(),
+                file_path: "/music/a.mp3".to_string(),
+                song_id: Some(7),
+                position_ms: 95_000,
+                duration_ms: 210_000,
+                playing: true,
+                channel_gain: 1.0,
+            }],
+            live_encoder_ids: vec![1],
+        }
+    }
+
+    #[test]
+    fn only_recent_non_empty_snapshots_are_resumable() {
+        let now = 1_800_000_000_000;
+        assert!(snapshot(now - 10_000).is_resumable(now));
+        assert!(!snapshot(now - MAX_RESUME_AGE_MS - 1).is_resumable(now));
+
+        let empty = SessionSnapshot {
+            decks: Vec::new(),
+            live_encoder_ids: Vec::new(),
+            ..snapshot(now)
+        };
+        assert!(!empty.is_resumable(now));
+    }
+
+    #[test]
+    fn snapshot_round_trips_through_json() {
+        let original = snapshot(1_800_000_000_000);
+        let json = serde_json::to_string(&original).unwrap();
+        let parsed: SessionSnapshot = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, original);
+    }
+}
@@ -8,6 +8,8 @@ use std::sync::{
     Mutex, OnceLock,
 };
 
+use crate::audio::crossfade::DeckId;
+
 #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum DjMode {
@@ -100,6 +102,13 @@ impl Default for MixxxPlannerConfig {
 pub struct AutoTransitionConfig {
     pub engine: AutodjTransitionEngine,
     pub mixxx_planner_config: MixxxPlannerConfig,
+    /// Playback decks AutoDJ loads and fades through, in order (e.g. A → C → B).
+    #[serde(default = "default_deck_rotation")]
+    pub deck_rotation: Vec<DeckId>,
+}
+
+fn default_deck_rotation() -> Vec<DeckId> {
+    vec![DeckId::DeckA, DeckId::DeckB]
 }
 
 impl Default for AutoTransitionConfig {
@@ -107,7 +116,25 @@ impl Default for AutoTransitionConfig {
         Self {
             engine: AutodjTransitionEngine::SamClassic,
             mixxx_planner_config: MixxxPlannerConfig::default(),
+            deck_rotation: default_deck_rotation(),
+        }
+    }
+}
+
+impl AutoTransitionConfig {
+    /// `deck_rotation` without non-playback or repeated decks; A/B when
+    /// fewer than two decks remain.
+    pub fn deck_rotation(&self) -> Vec<DeckId> {
+        let mut rotation: Vec<DeckId> = Vec::with_capacity(DeckId::PLAYBACK.len());
+        for deck in &self.deck_rotation {
+            if deck.is_playback() && !rotation.contains(deck) {
+                rotation.push(*deck);
+            }
+        }
+        if rotation.len() < 2 {
+            return default_deck_rotation();
         }
+        rotation
     }
 }
 
@@ -0,0 +1,300 @@
+/// The upcoming AutoDJ plan: the SAM queue followed by the ghost queue, as
+/// one list DJs can pin, reorder and edit.
+///
+/// Pins keep manual curation in place. A pinned queue entry keeps its
+/// position through reorders; a pinned ghost entry survives re-planning and
+/// carries its pin into the SAM queue when top-up moves it there. Entries a
+/// deck has already claimed are listed but can't be changed.
+use std::collections::{HashMap, HashSet};
+use std::sync::{Mutex, OnceLock};
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use sqlx::MySqlPool;
+
+use crate::{
+    db::{
+        sam::{self, QueueEntry, SamSong},
+        sam_outbox,
+    },
+    error::AppError,
+    scheduler::rotation::{self, GhostQueueEntry, SongCandidate},
+};
+
+/// Identifies one plan item.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(tag = "source", rename_all = "snake_case")]
+pub enum PlanItemRef {
+    Queue { queue_id: i64 },
+    Ghost { song_id: i64 },
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PlanItem {
+    #[serde(flatten)]
+    pub item: PlanItemRef,
+    pub song_id: i64,
+    pub title: String,
+    pub artist: String,
+    pub duration: i64,
+    pub pinned: bool,
+    /// Loaded on a deck; leaves the queue shortly
+    pub claimed: bool,
+}
+
+fn queue_pins() -> &'static Mutex<HashSet<i64>> {
+    static PINS: OnceLock<Mutex<HashSet<i64>>> = OnceLock::new();
+    PINS.get_or_init(|| Mutex::new(HashSet::new()))
+}
+
+fn queue_pinned(queue_id: i64) -> bool {
+    queue_pins().lock().unwrap().contains(&queue_id)
+}
+
+/// Top-up moved `song_id` from the ghost queue into SAM as `queue_id`.
+pub fn song_queued(song_id: i64, queue_id: i64) {
+    if rotation::take_pinned_handoff(song_id) {
+        queue_pins().lock().unwrap().insert(queue_id);
+    }
+}
+
+fn candidate_from_song(song: SamSong) -> SongCandidate {
+    SongCandidate {
+        song_id: song.id,
+        title: song.title,
+        artist: song.artist,
+        album: Some(song.album),
+        category: None,
+        duration: song.duration as i64,
+        file_path: song.filename,
+        score: song.weight,
+    }
+}
+
+async fn load(pool: &MySqlPool) -> Result<(Vec<QueueEntry>, Vec<GhostQueueEntry>), AppError> {
+    let queue = sam::get_queue(pool).await?;
+    let live: HashSet<i64> = queue.iter().map(|e| e.id).collect();
+    queue_pins().lock().unwrap().retain(|id| live.contains(id));
+    Ok((queue, rotation::get_ghost_queue()))
+}
+
+fn plan_items(queue: &[QueueEntry], ghost: &[GhostQueueEntry]) -> Vec<PlanItem> {
+    let queued = queue.iter().map(|entry| {
+        let song = entry.song.as_ref();
+        PlanItem {
+            item: PlanItemRef::Queue { queue_id: entry.id },
+            song_id: entry.song_id,
+            title: song.map(|s| s.title.clone()).unwrap_or_default(),
+            artist: song.map(|s| s.artist.clone()).unwrap_or_default(),
+            duration: song.map_or(0, |s| s.duration as i64),
+            pinned: queue_pinned(entry.id),
+            claimed: sam_outbox::removal_pending(entry.id),
+        }
+    });
+    let planned = ghost.iter().map(|entry| PlanItem {
+        item: PlanItemRef::Ghost {
+            song_id: entry.song.song_id,
+        },
+        song_id: entry.song.song_id,
+        title: entry.song.title.clone(),
+        artist: entry.song.artist.clone(),
+        duration: entry.song.duration,
+        pinned: entry.pinned,
+        claimed: false,
+    });
+    queued.chain(planned).collect()
+}
+
+/// SAM queue entries then ghost queue entries, in play order.
+pub async fn get_plan(pool: &MySqlPool) -> Result<Vec<PlanItem>, AppError> {
+    let (queue, ghost) = load(pool).await?;
+    Ok(plan_items(&queue, &ghost))
+}
+
+fn find(plan: &[PlanItem], item: PlanItemRef) -> Result<&PlanItem, AppError> {
+    plan.iter()
+        .find(|p| p.item == item)
+        .ok_or_else(|| AppError::not_found("Plan item is no longer upcoming"))
+}
+
+pub async fn set_pinned(pool: &MySqlPool, item: PlanItemRef, pinned: bool) -> Result<(), AppError> {
+    let plan = get_plan(pool).await?;
+    if find(&plan, item)?.claimed {
+        return Err(AppError::conflict("Item is already loaded on a deck"));
+    }
+    match item {
+        PlanItemRef::Queue { queue_id } => {
+            let mut pins = queue_pins().lock().unwrap();
+            if pinned {
+                pins.insert(queue_id);
+            } else {
+                pins.remove(&queue_id);
+            }
+        }
+        PlanItemRef::Ghost { song_id } => {
+            if !rotation::set_ghost_pinned(song_id, pinned) {
+                return Err(AppError::not_found("Plan item is no longer upcoming"));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Put a different song in `item`'s place. The replacement is pinned.
+pub async fn replace(pool: &MySqlPool, item: PlanItemRef, song_id: i64) -> Result<(), AppError> {
+    let plan = get_plan(pool).await?;
+    if find(&plan, item)?.claimed {
+        return Err(AppError::conflict("Item is already loaded on a deck"));
+    }
+    let song = sam::get_song(pool, song_id)
+        .await?
+        .ok_or_else(|| AppError::not_found(format!("Song {song_id}")))?;
+    match item {
+        PlanItemRef::Queue { queue_id } => {
+            if !sam::set_queue_song(pool, queue_id, song_id).await? {
+                return Err(AppError::not_found("Plan item is no longer upcoming"));
+            }
+            queue_pins().lock().unwrap().insert(queue_id);
+        }
+        PlanItemRef::Ghost { song_id: planned } => {
+            if plan.iter().any(|p| p.song_id == song_id && p.item != item) {
+                return Err(AppError::conflict(format!(
+                    "Song {song_id} is already upcoming"
+                )));
+            }
+            if !rotation::replace_ghost_entry(planned, candidate_from_song(song)) {
+                return Err(AppError::not_found("Plan item is no longer upcoming"));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Check a new order of the unclaimed items: a permutation that leaves every
+/// pinned item where it is.
+fn validate_order(current: &[&PlanItem], order: &[PlanItemRef]) -> Result<(), AppError> {
+    let expected: HashSet<PlanItemRef> = current.iter().map(|p| p.item).collect();
+    let given: HashSet<PlanItemRef> = order.iter().copied().collect();
+    if order.len() != current.len() || given != expected {
+        return Err(AppError::invalid_input(
+            "Order must list every upcoming item exactly once",
+        ));
+    }
+    for (item, new) in current.iter().zip(order) {
+        if item.pinned && item.item != *new {
+            return Err(AppError::conflict(format!(
+                "{} – {} is pinned",
+                item.artist, item.title
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Reorder the upcoming plan. The SAM queue keeps its length, so top-up has
+/// nothing to add afterwards: ghost items moved into the queue's range are
+/// queued, and queue entries moved past it go back to the ghost queue.
+pub async fn reorder(pool: &MySqlPool, order: &[PlanItemRef]) -> Result<(), AppError> {
+    let (queue, ghost) = load(pool).await?;
+    let plan = plan_items(&queue, &ghost);
+    let (claimed, open): (Vec<&PlanItem>, Vec<&PlanItem>) = plan.iter().partition(|p| p.claimed);
+    validate_order(&open, order)?;
+
+    let queue_len = open
+        .iter()
+        .filter(|p| matches!(p.item, PlanItemRef::Queue { .. }))
+        .count();
+    let mut songs: HashMap<i64, SamSong> = queue
+        .into_iter()
+        .filter_map(|e| Some((e.id, e.song?)))
+        .collect();
+    let mut planned: HashMap<i64, GhostQueueEntry> =
+        ghost.into_iter().map(|e| (e.song.song_id, e)).collect();
+
+    let mut queue_ids: Vec<i64> = claimed
+        .iter()
+        .filter_map(|p| match p.item {
+            PlanItemRef::Queue { queue_id } => Some(queue_id),
+            PlanItemRef::Ghost { .. } => None,
+        })
+        .collect();
+    let mut new_ghost = Vec::new();
+    for (idx, item) in order.iter().enumerate() {
+        match (*item, idx < queue_len) {
+            (PlanItemRef::Queue { queue_id }, true) => queue_ids.push(queue_id),
+            (PlanItemRef::Ghost { song_id }, true) => {
+                let queue_id = sam::add_to_queue(pool, song_id).await?;
+                if planned.get(&song_id).is_some_and(|e| e.pinned) {
+                    queue_pins().lock().unwrap().insert(queue_id);
+                }
+                queue_ids.push(queue_id);
+            }
+            (PlanItemRef::Ghost { song_id }, false) => {
+                if let Some(entry) = planned.remove(&song_id) {
+                    new_ghost.push(entry);
+                }
+            }
+            (PlanItemRef::Queue { queue_id }, false) => {
+                sam::remove_from_queue(pool, queue_id).await?;
+                if let Some(song) = songs.remove(&queue_id) {
+                    new_ghost.push(GhostQueueEntry {
+                        song: candidate_from_song(song),
+                        planned_at: Utc::now().timestamp_millis(),
+                        pinned: false,
+                    });
+                }
+            }
+        }
+    }
+    sam::reorder_queue(pool, &queue_ids).await?;
+    rotation::set_ghost_queue(new_ghost);
+    Ok(())
+}
+
+/// Reject a plain SAM queue reorder (`queue_ids`, full queue) that would move
+/// a pinned entry.
+pub async fn check_queue_order(pool: &MySqlPool, queue_ids: &[i64]) -> Result<(), AppError> {
+    let (queue, _) = load(pool).await?;
+    for (idx, entry) in queue.iter().enumerate() {
+        if queue_pinned(entry.id) && queue_ids.get(idx) != Some(&entry.id) {
+            return Err(AppError::conflict(format!(
+                "Queue entry {} is pinned",
+                entry.id
+            )));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(item: PlanItemRef, pinned: bool) -> PlanItem {
+        PlanItem {
+            item,
+            song_id: 0,
+            title: String::new(),
+            artist: String::new(),
+            duration: 0,
+            pinned,
+            claimed: false,
+        }
+    }
+
+    #[test]
+    fn order_must_be_permutation_keeping_pins() {
+        let a = PlanItemRef::Queue { queue_id: 1 };
+        let b = PlanItemRef::Queue { queue_id: 2 };
+        let c = PlanItemRef::Ghost { song_id: 30 };
+        let current = [item(a, true), item(b, false), item(c, false)];
+        let current: Vec<&PlanItem> = current.iter().collect();
+
+        assert!(validate_order(&current, &[a, c, b]).is_ok());
+        // Moving the pinned head is refused.
+        assert!(validate_order(&current, &[b, a, c]).is_err());
+        // Missing or duplicated items are refused.
+        assert!(validate_order(&current, &[a, b]).is_err());
+        assert!(validate_order(&current, &[a, b, b]).is_err());
+    }
+}
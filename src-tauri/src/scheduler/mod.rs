@@ -1,5 +1,14 @@
 pub mod autodj;
+pub mod autodj_plan;
+pub mod mode_transition;
+pub mod playlist_io;
+pub mod relay;
+pub mod request_api;
 pub mod request_policy;
 pub mod rotation;
 pub mod show_scheduler;
+pub mod timed_events;
+pub mod track_preflight;
+pub mod traffic;
 pub mod transition_planner;
+pub mod voice_track;
@@ -0,0 +1,381 @@
+/// DJ mode change orchestration
+///
+/// Switching modes while a crossfade is running used to leave decks half
+/// faded with nobody owning the transition. Every mode change now goes
+/// through here: an in-flight crossfade is finished, completed at once or
+/// cancelled, and the switch can be held until the on-air song ends. Each
+/// step is reported to the frontend as a `dj_mode_transition` event.
+use std::sync::{Mutex, OnceLock};
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::audio::crossfade::DeckId;
+use crate::audio::engine::DeckStateEvent;
+use crate::scheduler::autodj::{self, DjMode};
+use crate::state::AppState;
+
+/// What to do with a crossfade that is running when the mode changes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum InFlightPolicy {
+    /// Let the fade run to its end, then switch
+    #[default]
+    Finish,
+    /// Jump to the end of the fade now
+    Complete,
+    /// Abandon the fade; the outgoing deck stays on air and the incoming
+    /// deck is paused
+    Cancel,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum SwitchTiming {
+    #[default]
+    Immediate,
+    /// Wait until the on-air song stops; AutoDJ starts no new transition
+    /// meanwhile
+    TrackEnd,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModeChangeRequest {
+    pub mode: DjMode,
+    #[serde(default)]
+    pub in_flight: InFlightPolicy,
+    #[serde(default)]
+    pub timing: SwitchTiming,
+}
+
+impl ModeChangeRequest {
+    pub fn immediate(mode: DjMode) -> Self {
+        Self {
+            mode,
+            in_flight: InFlightPolicy::default(),
+            timing: SwitchTiming::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", tag = "kind")]
+pub enum WaitFor {
+    CrossfadeEnd,
+    TrackEnd { deck: DeckId, song_id: Option<i64> },
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PendingModeChange {
+    pub from_mode: DjMode,
+    pub request: ModeChangeRequest,
+    pub wait_for: WaitFor,
+    pub requested_at: i64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TransitionStatus {
+    Applied,
+    Scheduled,
+    Cancelled,
+    Unchanged,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CrossfadeResolution {
+    None,
+    /// Ran to its natural end before the switch
+    Finished,
+    /// Cut short at its end state
+    Completed,
+    Cancelled,
+}
+
+/// Payload of the `dj_mode_transition` event.
+#[derive(Debug, Clone, Serialize)]
+pub struct DjModeTransitionEvent {
+    pub from_mode: DjMode,
+    pub to_mode: DjMode,
+    pub status: TransitionStatus,
+    pub crossfade: CrossfadeResolution,
+    pub on_air_deck: Option<DeckId>,
+    /// Incoming deck paused by a cancelled crossfade
+    pub paused_deck: Option<DeckId>,
+    pub wait_for: Option<WaitFor>,
+    pub message: String,
+    pub timestamp: i64,
+}
+
+fn pending() -> &'static Mutex<Option<PendingModeChange>> {
+    static PENDING: OnceLock<Mutex<Option<PendingModeChange>>> = OnceLock::new();
+    PENDING.get_or_init(|| Mutex::new(None))
+}
+
+pub fn get_pending() -> Option<PendingModeChange> {
+    pending().lock().unwrap().clone()
+}
+
+/// True while a track-end switch away from AutoDJ is pending; the AutoDJ
+/// loop must not start the next transition.
+pub fn holds_autodj() -> bool {
+    pending().lock().unwrap().as_ref().is_some_and(|p| {
+        matches!(p.wait_for, WaitFor::TrackEnd { .. })
+            && p.from_mode == DjMode::AutoDj
+            && p.request.mode != DjMode::AutoDj
+    })
+}
+
+fn now_ms() -> i64 {
+    chrono::Utc::now().timestamp_millis()
+}
+
+fn emit(app: &AppHandle, event: &DjModeTransitionEvent) {
+    log::info!(
+        "DJ mode {} → {}: {}",
+        event.from_mode.as_str(),
+        event.to_mode.as_str(),
+        event.message
+    );
+    let _ = app.emit("dj_mode_transition", event);
+}
+
+fn is_playing(state: &str) -> bool {
+    state == "playing" || state == "crossfading"
+}
+
+/// The deck whose song is on air and the song on it.
+fn on_air_deck(state: &AppState) -> Option<(DeckId, Option<i64>)> {
+    let decks: Vec<DeckStateEvent> = {
+        let engine = state.engine.lock().unwrap();
+        DeckId::PLAYBACK
+            .into_iter()
+            .filter_map(|id| engine.get_deck_state(id))
+            .collect()
+    };
+    let on_air = crate::scheduler::request_api::on_air_deck(&decks)?;
+    let deck = crate::commands::audio_commands::parse_deck(&on_air.deck).ok()?;
+    Some((deck, on_air.song_id))
+}
+
+/// Whether a pending switch may go ahead. `deck` is `(playing, song_id)` of
+/// the watched deck for a track-end wait.
+fn is_satisfied(
+    wait_for: &WaitFor,
+    crossfade_active: bool,
+    deck: Option<(bool, Option<i64>)>,
+) -> bool {
+    match wait_for {
+        WaitFor::CrossfadeEnd => !crossfade_active,
+        WaitFor::TrackEnd { song_id, .. } => match deck {
+            Some((playing, current)) => !playing || current != *song_id,
+            None => true,
+        },
+    }
+}
+
+/// Request a mode change. Replaces any pending request.
+pub async fn request_change(
+    app: &AppHandle,
+    request: ModeChangeRequest,
+) -> Result<DjModeTransitionEvent, String> {
+    let state = app.state::<AppState>();
+    let from_mode = autodj::get_dj_mode();
+    let superseded = pending().lock().unwrap().take();
+
+    let mut event = DjModeTransitionEvent {
+        from_mode,
+        to_mode: request.mode,
+        status: TransitionStatus::Unchanged,
+        crossfade: CrossfadeResolution::None,
+        on_air_deck: None,
+        paused_deck: None,
+        wait_for: None,
+        message: String::new(),
+        timestamp: now_ms(),
+    };
+
+    if request.mode == from_mode {
+        event.message = match superseded {
+            Some(p) => format!(
+                "already in {}; dropped pending switch to {}",
+                from_mode.as_str(),
+                p.request.mode.as_str()
+            ),
+            None => format!("already in {}", from_mode.as_str()),
+        };
+        emit(app, &event);
+        return Ok(event);
+    }
+
+    let crossfade = { state.engine.lock().unwrap().crossfade_decks() };
+    let wait_for = match request.timing {
+        SwitchTiming::TrackEnd => {
+            on_air_deck(&state).map(|(deck, song_id)| WaitFor::TrackEnd { deck, song_id })
+        }
+        SwitchTiming::Immediate => None,
+    }
+    .or_else(|| {
+        (crossfade.is_some() && request.in_flight == InFlightPolicy::Finish)
+            .then_some(WaitFor::CrossfadeEnd)
+    });
+
+    if let Some(wait_for) = wait_for {
+        *pending().lock().unwrap() = Some(PendingModeChange {
+            from_mode,
+            request: request.clone(),
+            wait_for,
+            requested_at: event.timestamp,
+        });
+        event.status = TransitionStatus::Scheduled;
+        event.wait_for = Some(wait_for);
+        event.on_air_deck = match wait_for {
+            WaitFor::TrackEnd { deck, .. } => Some(deck),
+            WaitFor::CrossfadeEnd => crossfade.map(|(_, incoming)| incoming),
+        };
+        event.message = match wait_for {
+            WaitFor::TrackEnd { deck, .. } => format!("switching when {deck} ends"),
+            WaitFor::CrossfadeEnd => "switching when the crossfade ends".to_string(),
+        };
+        emit(app, &event);
+        return Ok(event);
+    }
+
+    apply(app, from_mode, &request, false).await
+}
+
+/// Drop a pending switch.
+pub fn cancel_pending(app: &AppHandle) -> Option<DjModeTransitionEvent> {
+    let p = pending().lock().unwrap().take()?;
+    let event = DjModeTransitionEvent {
+        from_mode: p.from_mode,
+        to_mode: p.request.mode,
+        status: TransitionStatus::Cancelled,
+        crossfade: CrossfadeResolution::None,
+        on_air_deck: None,
+        paused_deck: None,
+        wait_for: Some(p.wait_for),
+        message: "pending switch cancelled".to_string(),
+        timestamp: now_ms(),
+    };
+    emit(app, &event);
+    Some(event)
+}
+
+/// Called from the AutoDJ loop each tick; applies a pending switch once its
+/// wait condition is met.
+pub async fn poll_pending(app: &AppHandle) {
+    let Some(p) = get_pending() else {
+        return;
+    };
+    let ready = {
+        let state = app.state::<AppState>();
+        let engine = state.engine.lock().unwrap();
+        let deck = match p.wait_for {
+            WaitFor::TrackEnd { deck, .. } => engine
+                .get_deck_state(deck)
+                .map(|s| (is_playing(&s.state), s.song_id)),
+            WaitFor::CrossfadeEnd => None,
+        };
+        is_satisfied(&p.wait_for, engine.crossfade_decks().is_some(), deck)
+    };
+    if !ready {
+        return;
+    }
+    {
+        let mut slot = pending().lock().unwrap();
+        // A newer request may have replaced this one meanwhile.
+        if slot.as_ref().map(|q| q.requested_at) != Some(p.requested_at) {
+            return;
+        }
+        *slot = None;
+    }
+    let finished = p.wait_for == WaitFor::CrossfadeEnd;
+    if let Err(e) = apply(app, p.from_mode, &p.request, finished).await {
+        log::warn!("Pending DJ mode change failed: {e}");
+    }
+}
+
+async fn apply(
+    app: &AppHandle,
+    from_mode: DjMode,
+    request: &ModeChangeRequest,
+    finished: bool,
+) -> Result<DjModeTransitionEvent, String> {
+    let state = app.state::<AppState>();
+    let mut resolution = if finished {
+        CrossfadeResolution::Finished
+    } else {
+        CrossfadeResolution::None
+    };
+    let mut on_air = None;
+    let mut paused_deck = None;
+    {
+        let mut engine = state.engine.lock().unwrap();
+        if let Some((outgoing, incoming)) = engine.crossfade_decks() {
+            let complete = request.in_flight != InFlightPolicy::Cancel;
+            engine.resolve_crossfade(complete)?;
+            if complete {
+                resolution = CrossfadeResolution::Completed;
+                on_air = Some(incoming);
+            } else {
+                resolution = CrossfadeResolution::Cancelled;
+                on_air = Some(outgoing);
+                paused_deck = Some(incoming);
+            }
+        }
+    }
+    if on_air.is_none() {
+        on_air = on_air_deck(&state).map(|(deck, _)| deck);
+    }
+
+    autodj::set_dj_mode(request.mode);
+    // Drop AutoDJ's half-made plans (pending gaps, SAM starts) either way.
+    autodj::request_replan();
+    if let Some(pool) = &state.local_db {
+        crate::db::local::save_runtime_dj_mode(pool, request.mode.as_str())
+            .await
+            .map_err(|e| format!("Failed to persist DJ mode: {e}"))?;
+    }
+
+    let message = match resolution {
+        CrossfadeResolution::None => "switched".to_string(),
+        CrossfadeResolution::Finished => "switched after the crossfade finished".to_string(),
+        CrossfadeResolution::Completed => "crossfade completed, switched".to_string(),
+        CrossfadeResolution::Cancelled => "crossfade cancelled, switched".to_string(),
+    };
+    let event = DjModeTransitionEvent {
+        from_mode,
+        to_mode: request.mode,
+        status: TransitionStatus::Applied,
+        crossfade: resolution,
+        on_air_deck: on_air,
+        paused_deck,
+        wait_for: None,
+        message,
+        timestamp: now_ms(),
+    };
+    emit(app, &event);
+    Ok(event)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wait_conditions() {
+        assert!(!is_satisfied(&WaitFor::CrossfadeEnd, true, None));
+        assert!(is_satisfied(&WaitFor::CrossfadeEnd, false, None));
+
+        let wait = WaitFor::TrackEnd {
+            deck: DeckId::DeckA,
+            song_id: Some(7),
+        };
+        assert!(!is_satisfied(&wait, false, Some((true, Some(7)))));
+        assert!(is_satisfied(&wait, false, Some((false, Some(7)))));
+        // Same deck, different song: the watched song is gone.
+        assert!(is_satisfied(&wait, true, Some((true, Some(8)))));
+    }
+}
@@ -0,0 +1,350 @@
+/// Playlist interchange — M3U/M3U8, PLS and RadioDJ-style CSV
+///
+/// Imported entries are matched to SAM songs by file path. A path in the
+/// file may be either SAM's own path (as stored in `songlist.filename`) or
+/// the local path produced by the path translation rules, so the index
+/// holds both forms. Files that match nothing by full path fall back to a
+/// unique file-name match, which covers playlists written on another machine.
+use std::collections::HashMap;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::db::{path_rules::PathTranslator, sam::SamSong};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PlaylistFormat {
+    M3u,
+    Pls,
+    Csv,
+}
+
+impl PlaylistFormat {
+    /// Guess from the extension, then from the first meaningful line.
+    pub fn detect(path: &str, text: &str) -> Self {
+        let ext = Path::new(path)
+            .extension()
+            .map(|e| e.to_string_lossy().to_ascii_lowercase())
+            .unwrap_or_default();
+        match ext.as_str() {
+            "m3u" | "m3u8" => return Self::M3u,
+            "pls" => return Self::Pls,
+            "csv" | "txt" if !text.trim_start().starts_with("#EXTM3U") => return Self::Csv,
+            _ => {}
+        }
+        let first = text.lines().map(str::trim).find(|l| !l.is_empty());
+        match first {
+            Some(l) if l.eq_ignore_ascii_case("[playlist]") => Self::Pls,
+            Some(l) if !l.starts_with('#') && (l.contains(',') || l.contains(';')) => Self::Csv,
+            _ => Self::M3u,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PlaylistEntry {
+    pub path: String,
+    pub artist: Option<String>,
+    pub title: Option<String>,
+    pub duration_secs: Option<i64>,
+}
+
+/// Parse a playlist file body. Relative paths are resolved against `base_dir`.
+pub fn parse_playlist(
+    text: &str,
+    format: PlaylistFormat,
+    base_dir: Option<&Path>,
+) -> Vec<PlaylistEntry> {
+    let text = text.trim_start_matches('\u{feff}');
+    let mut entries = match format {
+        PlaylistFormat::M3u => parse_m3u(text),
+        PlaylistFormat::Pls => parse_pls(text),
+        PlaylistFormat::Csv => parse_csv(text),
+    };
+    if let Some(base) = base_dir {
+        for entry in &mut entries {
+            entry.path = resolve(&entry.path, base);
+        }
+    }
+    entries
+}
+
+fn resolve(path: &str, base: &Path) -> String {
+    let is_absolute = path.starts_with('/')
+        || path.starts_with('\\')
+        || path.contains("://")
+        || path.as_bytes().get(1) == Some(&b':');
+    if is_absolute {
+        path.to_string()
+    } else {
+        base.join(path.replace('\\', "/"))
+            .to_string_lossy()
+            .to_string()
+    }
+}
+
+/// `Artist - Title` as written by most players; a bare string is a title.
+fn split_display(display: &str) -> (Option<String>, Option<String>) {
+    let display = display.trim();
+    if display.is_empty() {
+        return (None, None);
+    }
+    match display.split_once(" - ") {
+        Some((artist, title)) => (
+            Some(artist.trim().to_string()),
+            Some(title.trim().to_string()),
+        ),
+        None => (None, Some(display.to_string())),
+    }
+}
+
+fn parse_m3u(text: &str) -> Vec<PlaylistEntry> {
+    let mut entries = Vec::new();
+    let mut pending = PlaylistEntry::default();
+    for line in text.lines().map(str::trim) {
+        if line.is_empty() {
+            continue;
+        }
+        if let Some(info) = line.strip_prefix("#EXTINF:") {
+            let (duration, display) = info.split_once(',').unwrap_or((info, ""));
+            // Attributes (`tvg-id="…"`) may follow the duration.
+            let duration = duration.split_whitespace().next().unwrap_or("");
+            pending.duration_secs = duration.parse::<i64>().ok().filter(|d| *d >= 0);
+            (pending.artist, pending.title) = split_display(display);
+            continue;
+        }
+        if line.starts_with('#') {
+            continue;
+        }
+        pending.path = line.to_string();
+        entries.push(std::mem::take(&mut pending));
+    }
+    entries
+}
+
+fn parse_pls(text: &str) -> Vec<PlaylistEntry> {
+    let mut by_index: HashMap<u32, PlaylistEntry> = HashMap::new();
+    for line in text.lines().map(str::trim) {
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let key = key.trim().to_ascii_lowercase();
+        let value = value.trim();
+        let (field, index) = match ["file", "title", "length"]
+            .iter()
+            .find_map(|f| key.strip_prefix(f).map(|rest| (*f, rest)))
+        {
+            Some((field, rest)) => match rest.parse::<u32>() {
+                Ok(i) => (field, i),
+                Err(_) => continue,
+            },
+            None => continue,
+        };
+        let entry = by_index.entry(index).or_default();
+        match field {
+            "file" => entry.path = value.to_string(),
+            "title" => (entry.artist, entry.title) = split_display(value),
+            _ => entry.duration_secs = value.parse::<i64>().ok().filter(|d| *d >= 0),
+        }
+    }
+    let mut indexed: Vec<_> = by_index
+        .into_iter()
+        .filter(|(_, e)| !e.path.is_empty())
+        .collect();
+    indexed.sort_by_key(|(i, _)| *i);
+    indexed.into_iter().map(|(_, e)| e).collect()
+}
+
+/// Split one CSV record, honouring double-quoted fields.
+fn split_csv(line: &str, delimiter: char) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            c if c == delimiter && !quoted => fields.push(std::mem::take(&mut field)),
+            c => field.push(c),
+        }
+    }
+    fields.push(field);
+    fields.into_iter().map(|f| f.trim().to_string()).collect()
+}
+
+/// RadioDJ-style CSV: a header row naming the path/artist/title/duration
+/// columns, or no header with the path in the first column.
+fn parse_csv(text: &str) -> Vec<PlaylistEntry> {
+    let mut lines = text
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .peekable();
+    let Some(first) = lines.peek().copied() else {
+        return Vec::new();
+    };
+    let delimiter = if first.matches(';').count() > first.matches(',').count() {
+        ';'
+    } else {
+        ','
+    };
+    let header: Vec<String> = split_csv(first, delimiter)
+        .into_iter()
+        .map(|h| h.to_ascii_lowercase().replace([' ', '_'], ""))
+        .collect();
+    let column = |names: &[&str]| header.iter().position(|h| names.contains(&h.as_str()));
+    let path_col = column(&["path", "filename", "file", "location", "filepath"]);
+    let (path_col, artist_col, title_col, duration_col) = match path_col {
+        Some(p) => {
+            lines.next();
+            (
+                p,
+                column(&["artist"]),
+                column(&["title", "name"]),
+                column(&["duration", "length"]),
+            )
+        }
+        None => (0, None, None, None),
+    };
+
+    lines
+        .filter_map(|line| {
+            let fields = split_csv(line, delimiter);
+            let get = |c: Option<usize>| {
+                c.and_then(|c| fields.get(c))
+                    .filter(|v| !v.is_empty())
+                    .cloned()
+            };
+            let path = get(Some(path_col))?;
+            Some(PlaylistEntry {
+                path,
+                artist: get(artist_col),
+                title: get(title_col),
+                duration_secs: get(duration_col).and_then(|d| parse_duration(&d)),
+            })
+        })
+        .collect()
+}
+
+/// Seconds from `123`, `123.4` or `m:ss` / `h:mm:ss`.
+fn parse_duration(s: &str) -> Option<i64> {
+    if s.contains(':') {
+        s.split(':').try_fold(0i64, |acc, part| {
+            Some(acc * 60 + part.trim().parse::<f64>().ok()? as i64)
+        })
+    } else {
+        s.parse::<f64>().ok().map(|d| d as i64)
+    }
+}
+
+/// Render an extended M3U.
+pub fn write_m3u(entries: &[PlaylistEntry]) -> String {
+    let mut out = String::from("#EXTM3U\n");
+    for entry in entries {
+        let display = match (&entry.artist, &entry.title) {
+            (Some(a), Some(t)) if !a.is_empty() => format!("{a} - {t}"),
+            (_, Some(t)) => t.clone(),
+            (Some(a), None) => a.clone(),
+            (None, None) => String::new(),
+        };
+        out.push_str(&format!(
+            "#EXTINF:{},{display}\n{}\n",
+            entry.duration_secs.unwrap_or(-1),
+            entry.path
+        ));
+    }
+    out
+}
+
+// ── Matching ──────────────────────────────────────────────────────────────────
+
+fn path_key(path: &str) -> String {
+    path.trim().replace('\\', "/").to_lowercase()
+}
+
+fn file_name_key(path: &str) -> String {
+    let key = path_key(path);
+    key.rsplit('/').next().unwrap_or(&key).to_string()
+}
+
+/// Lookup from file path to SAM song id.
+pub struct SongPathIndex {
+    by_path: HashMap<String, i64>,
+    /// `None` when several songs share the file name
+    by_name: HashMap<String, Option<i64>>,
+}
+
+impl SongPathIndex {
+    pub fn new(songs: &[SamSong], translator: &PathTranslator) -> Self {
+        let mut by_path = HashMap::new();
+        let mut by_name: HashMap<String, Option<i64>> = HashMap::new();
+        for song in songs {
+            if song.filename.is_empty() {
+                continue;
+            }
+            by_path.insert(path_key(&song.filename), song.id);
+            let local = translator.translate(&song.filename);
+            by_path.insert(path_key(&local), song.id);
+            by_name
+                .entry(file_name_key(&song.filename))
+                .and_modify(|id| *id = None)
+                .or_insert(Some(song.id));
+        }
+        Self { by_path, by_name }
+    }
+
+    pub fn find(&self, path: &str) -> Option<i64> {
+        self.by_path
+            .get(&path_key(path))
+            .copied()
+            .or_else(|| self.by_name.get(&file_name_key(path)).copied().flatten())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_each_format() {
+        let m3u = "#EXTM3U\n#EXTINF:215,Artist A - Song A\nC:\\Music\\a.mp3\n\nb.mp3\n";
+        let entries = parse_playlist(m3u, PlaylistFormat::M3u, Some(Path::new("/lists")));
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].path, "C:\\Music\\a.mp3");
+        assert_eq!(entries[0].artist.as_deref(), Some("Artist A"));
+        assert_eq!(entries[0].duration_secs, Some(215));
+        assert_eq!(entries[1].path, "/lists/b.mp3");
+
+        let pls = "[playlist]\nFile2=/m/b.mp3\nFile1=/m/a.mp3\nTitle1=Song A\n\
+                   Length1=-1\nNumberOfEntries=2\n";
+        let entries = parse_playlist(pls, PlaylistFormat::Pls, None);
+        assert_eq!(entries[0].path, "/m/a.mp3");
+        assert_eq!(entries[0].title.as_deref(), Some("Song A"));
+        assert_eq!(entries[0].duration_secs, None);
+        assert_eq!(entries[1].path, "/m/b.mp3");
+
+        let csv = "Artist;Title;Duration;Path\n\"Doe; J\";Song;3:05;/m/c.mp3\n";
+        assert_eq!(PlaylistFormat::detect("list.csv", csv), PlaylistFormat::Csv);
+        let entries = parse_playlist(csv, PlaylistFormat::Csv, None);
+        assert_eq!(entries[0].artist.as_deref(), Some("Doe; J"));
+        assert_eq!(entries[0].duration_secs, Some(185));
+        assert_eq!(entries[0].path, "/m/c.mp3");
+    }
+
+    #[test]
+    fn m3u_round_trips() {
+        let entries = vec![PlaylistEntry {
+            path: "/m/a.mp3".to_string(),
+            artist: Some("A".to_string()),
+            title: Some("B".to_string()),
+            duration_secs: Some(100),
+        }];
+        let text = write_m3u(&entries);
+        assert_eq!(parse_playlist(&text, PlaylistFormat::M3u, None), entries);
+    }
+}
@@ -0,0 +1,527 @@
+/// Relay / rebroadcast scheduler
+///
+/// At a configured minute (hours and days as for exact-time events) program
+/// output switches to an external stream — network news at the top of the
+/// hour, a syndicated weekend show — for a set number of minutes. The relay
+/// plays on a spare playback deck (one outside the AutoDJ rotation) while
+/// AutoDJ holds; the rotation deck on air fades out. When the window ends
+/// the relay fades and AutoDJ carries on with the next track.
+///
+/// If the stream cannot be reached, or stays disconnected for longer than
+/// `DROPOUT_FALLBACK`, AutoDJ keeps (or takes back) the air and the relay is
+/// retried every `RETRY_SECS` until the window closes. Every switch is
+/// written to the event log.
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, TimeZone};
+use serde::{Deserialize, Serialize};
+use sqlx::{Row, SqlitePool};
+use tauri::{AppHandle, Manager};
+
+use super::autodj::{self, DjMode};
+use super::timed_events::{occurrence_due, schedule_json};
+use crate::analytics::{log_event, EventCategory, LogLevel};
+use crate::audio::{
+    crossfade::DeckId,
+    deck::{Deck, StopReason},
+};
+use crate::state::AppState;
+
+const TICK: Duration = Duration::from_secs(1);
+const RELOAD_INTERVAL: Duration = Duration::from_secs(30);
+/// Wait between attempts while the relay is unreachable
+const RETRY_SECS: i64 = 30;
+/// A relay on air that stays disconnected this long hands back to AutoDJ.
+const DROPOUT_FALLBACK: Duration = Duration::from_secs(8);
+/// Not worth joining a relay with less than this left.
+const MIN_JOIN_SECS: i64 = 60;
+
+// ── Data model ────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RelayEvent {
+    pub id: Option<i64>,
+    pub name: String,
+    /// HTTP(S) stream to rebroadcast
+    pub url: String,
+    /// Minute of the hour the relay starts (0 = top of hour)
+    pub minute: u8,
+    /// Local hours it runs in (empty = every hour)
+    pub hours: Vec<u8>,
+    /// 0=Mon..6=Sun (empty = every day)
+    pub days: Vec<u8>,
+    pub duration_minutes: u32,
+    /// Fade for the song on air at the start and the relay at the end
+    pub fade_ms: u32,
+    pub enabled: bool,
+    pub last_fired_at: Option<i64>,
+}
+
+impl RelayEvent {
+    pub fn duration_secs(&self) -> i64 {
+        i64::from(self.duration_minutes.max(1)) * 60
+    }
+
+    /// Unix start time of the occurrence due at `now`, if any. A relay can
+    /// still be joined late (after a restart, or outside AutoDJ) while at
+    /// least `MIN_JOIN_SECS` of it remain.
+    pub fn due_at<Tz: TimeZone>(&self, now: &DateTime<Tz>) -> Option<i64> {
+        if !self.enabled {
+            return None;
+        }
+        let grace = (self.duration_secs() - MIN_JOIN_SECS).max(0);
+        occurrence_due(
+            now,
+            self.minute,
+            &self.hours,
+            &self.days,
+            grace,
+            self.last_fired_at,
+        )
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RelayStatus {
+    pub relay_id: Option<i64>,
+    pub name: String,
+    pub url: String,
+    /// Deck the relay is playing on; `None` while AutoDJ has the air
+    pub deck: Option<DeckId>,
+    pub ends_at: i64,
+    pub attempts: u32,
+    pub last_error: Option<String>,
+}
+
+// ── Runtime ───────────────────────────────────────────────────────────────────
+
+static HOLD_AUTODJ: AtomicBool = AtomicBool::new(false);
+static STOP_REQUESTED: AtomicBool = AtomicBool::new(false);
+static RELOAD_REQUESTED: AtomicBool = AtomicBool::new(false);
+static STATUS: OnceLock<Mutex<Option<RelayStatus>>> = OnceLock::new();
+
+fn status_cell() -> &'static Mutex<Option<RelayStatus>> {
+    STATUS.get_or_init(|| Mutex::new(None))
+}
+
+/// A relay has the air; AutoDJ must not start anything.
+pub fn holds_autodj() -> bool {
+    HOLD_AUTODJ.load(Ordering::Relaxed)
+}
+
+/// The relay window in progress, if any.
+pub fn status() -> Option<RelayStatus> {
+    status_cell().lock().unwrap().clone()
+}
+
+/// End the current relay early and hand back to AutoDJ.
+pub fn request_stop() {
+    STOP_REQUESTED.store(true, Ordering::Relaxed);
+}
+
+/// Pick up edited relay events on the next tick.
+pub fn request_reload() {
+    RELOAD_REQUESTED.store(true, Ordering::Relaxed);
+}
+
+/// First playback deck AutoDJ does not rotate through.
+fn relay_deck(rotation: &[DeckId]) -> Option<DeckId> {
+    [DeckId::Aux2, DeckId::Aux1, DeckId::DeckB, DeckId::DeckA]
+        .into_iter()
+        .find(|deck| !rotation.contains(deck))
+}
+
+struct RelayWindow {
+    event: RelayEvent,
+    ends_at: i64,
+    deck: Option<DeckId>,
+    next_attempt: i64,
+    attempts: u32,
+    down_since: Option<Instant>,
+    last_error: Option<String>,
+}
+
+impl RelayWindow {
+    fn status(&self) -> RelayStatus {
+        RelayStatus {
+            relay_id: self.event.id,
+            name: self.event.name.clone(),
+            url: self.event.url.clone(),
+            deck: self.deck,
+            ends_at: self.ends_at,
+            attempts: self.attempts,
+            last_error: self.last_error.clone(),
+        }
+    }
+}
+
+async fn log_relay(
+    pool: &SqlitePool,
+    level: LogLevel,
+    event: &str,
+    message: &str,
+    relay: &RelayEvent,
+    deck: Option<DeckId>,
+) {
+    let metadata = serde_json::json!({
+        "relay_id": relay.id,
+        "name": relay.name,
+        "url": relay.url,
+    });
+    let deck = deck.map(|d| d.to_string());
+    let _ = log_event(
+        pool,
+        level,
+        EventCategory::Scheduler,
+        event,
+        message,
+        Some(metadata),
+        deck.as_deref(),
+        None,
+        None,
+    )
+    .await;
+}
+
+/// Connect to the relay (off the engine lock), then switch it to air and
+/// fade out the rotation.
+async fn take_air(state: &AppState, relay: &RelayEvent) -> Result<DeckId, String> {
+    let rotation = autodj::get_auto_transition_config().deck_rotation();
+    let deck = relay_deck(&rotation).ok_or("No playback deck outside the AutoDJ rotation")?;
+    let url = PathBuf::from(relay.url.trim());
+    let prepared =
+        tokio::task::spawn_blocking(move || Deck::prepare_load(url, None, None, false, None))
+            .await
+            .map_err(|e| e.to_string())??;
+
+    HOLD_AUTODJ.store(true, Ordering::Relaxed);
+    let mut engine = state.engine.lock().unwrap();
+    let started = engine
+        .load_prepared(deck, prepared)
+        .and_then(|_| engine.play(deck));
+    if let Err(e) = started {
+        HOLD_AUTODJ.store(false, Ordering::Relaxed);
+        return Err(e.into());
+    }
+    for on_air in rotation {
+        let playing = engine
+            .get_deck_state(on_air)
+            .is_some_and(|ev| matches!(ev.state.as_str(), "playing" | "crossfading"));
+        if playing {
+            let _ = engine.fade_out_deck(on_air, relay.fade_ms);
+        }
+    }
+    Ok(deck)
+}
+
+/// Advance the window one tick. Returns true once it is over.
+async fn step(state: &AppState, pool: &SqlitePool, window: &mut RelayWindow, now: i64) -> bool {
+    let stop = STOP_REQUESTED.swap(false, Ordering::Relaxed);
+    let mode = autodj::get_dj_mode();
+    if stop || now >= window.ends_at || mode != DjMode::AutoDj {
+        if let Some(deck) = window.deck.take() {
+            let _ = state
+                .engine
+                .lock()
+                .unwrap()
+                .fade_out_deck(deck, window.event.fade_ms);
+            HOLD_AUTODJ.store(false, Ordering::Relaxed);
+            autodj::request_replan();
+            let reason = if stop {
+                "stopped by operator"
+            } else if mode != DjMode::AutoDj {
+                "left AutoDJ"
+            } else {
+                "window ended"
+            };
+            let message = format!("Relay '{}' ended ({reason})", window.event.name);
+            log_relay(
+                pool,
+                LogLevel::Info,
+                "relay_ended",
+                &message,
+                &window.event,
+                Some(deck),
+            )
+            .await;
+        }
+        return true;
+    }
+
+    if let Some(deck) = window.deck {
+        let ev = { state.engine.lock().unwrap().get_deck_state(deck) };
+        let playing = ev
+            .as_ref()
+            .is_some_and(|ev| matches!(ev.state.as_str(), "playing" | "crossfading"));
+        let connected = ev
+            .as_ref()
+            .and_then(|ev| ev.remote_stream.as_ref())
+            .is_some_and(|r| r.connected);
+        if playing && connected {
+            window.down_since = None;
+            return false;
+        }
+        // The deck reconnects by itself; give it a moment before falling back.
+        let down_for = window.down_since.get_or_insert_with(Instant::now).elapsed();
+        if playing && down_for < DROPOUT_FALLBACK {
+            return false;
+        }
+        if playing {
+            let _ = state
+                .engine
+                .lock()
+                .unwrap()
+                .stop_with_completion(deck, StopReason::Skipped);
+        }
+        HOLD_AUTODJ.store(false, Ordering::Relaxed);
+        autodj::request_replan();
+        window.deck = None;
+        window.down_since = None;
+        window.next_attempt = now + RETRY_SECS;
+        let message = format!(
+            "Relay '{}' dropped; AutoDJ has the air until it is back",
+            window.event.name
+        );
+        log_relay(
+            pool,
+            LogLevel::Warn,
+            "relay_lost",
+            &message,
+            &window.event,
+            Some(deck),
+        )
+        .await;
+        return false;
+    }
+
+    if now < window.next_attempt || window.ends_at - now < MIN_JOIN_SECS {
+        return false;
+    }
+    window.attempts += 1;
+    match take_air(state, &window.event).await {
+        Ok(deck) => {
+            window.deck = Some(deck);
+            window.last_error = None;
+            let message = format!(
+                "Relay '{}' on air from {}",
+                window.event.name, window.event.url
+            );
+            log_relay(
+                pool,
+                LogLevel::Info,
+                "relay_started",
+                &message,
+                &window.event,
+                Some(deck),
+            )
+            .await;
+        }
+        Err(e) => {
+            window.next_attempt = now + RETRY_SECS;
+            // Log the outage once, not every retry.
+            if window.last_error.is_none() {
+                let message = format!(
+                    "Relay '{}' unavailable, AutoDJ continues: {e}",
+                    window.event.name
+                );
+                log_relay(
+                    pool,
+                    LogLevel::Warn,
+                    "relay_unavailable",
+                    &message,
+                    &window.event,
+                    None,
+                )
+                .await;
+            }
+            window.last_error = Some(e);
+        }
+    }
+    false
+}
+
+/// Start the relay scheduler loop.
+pub fn start(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let state = app.state::<AppState>();
+        let Some(pool) = state.local_db.clone() else {
+            return;
+        };
+        let mut events: Vec<RelayEvent> = Vec::new();
+        let mut loaded_at: Option<Instant> = None;
+        let mut window: Option<RelayWindow> = None;
+        let mut tick = tokio::time::interval(TICK);
+        loop {
+            tick.tick().await;
+            if RELOAD_REQUESTED.swap(false, Ordering::Relaxed)
+                || loaded_at.is_none_or(|t| t.elapsed() >= RELOAD_INTERVAL)
+            {
+                events = get_relay_events(&pool).await.unwrap_or_default();
+                loaded_at = Some(Instant::now());
+            }
+
+            let now = chrono::Local::now();
+            if window.is_none() && autodj::get_dj_mode() == DjMode::AutoDj {
+                let due = events
+                    .iter_mut()
+                    .find_map(|event| event.due_at(&now).map(|occurrence| (event, occurrence)));
+                if let Some((event, occurrence)) = due {
+                    event.last_fired_at = Some(occurrence);
+                    if let Some(id) = event.id {
+                        let _ = mark_relay_event_fired(&pool, id, occurrence).await;
+                    }
+                    window = Some(RelayWindow {
+                        ends_at: occurrence + event.duration_secs(),
+                        event: event.clone(),
+                        deck: None,
+                        next_attempt: 0,
+                        attempts: 0,
+                        down_since: None,
+                        last_error: None,
+                    });
+                }
+            }
+            // A stop with nothing running is stale.
+            if window.is_none() {
+                STOP_REQUESTED.store(false, Ordering::Relaxed);
+            }
+
+            if let Some(w) = window.as_mut() {
+                if step(&state, &pool, w, now.timestamp()).await {
+                    window = None;
+                }
+            }
+            *status_cell().lock().unwrap() = window.as_ref().map(RelayWindow::status);
+        }
+    });
+}
+
+// ── DB helpers ────────────────────────────────────────────────────────────────
+
+pub async fn get_relay_events(pool: &SqlitePool) -> Result<Vec<RelayEvent>, sqlx::Error> {
+    let rows = sqlx::query("SELECT * FROM relay_events ORDER BY minute, id")
+        .fetch_all(pool)
+        .await?;
+
+    Ok(rows
+        .iter()
+        .map(|r| RelayEvent {
+            id: r.get("id"),
+            name: r.get("name"),
+            url: r.get("url"),
+            minute: r.get::<i64, _>("minute").clamp(0, 59) as u8,
+            hours: serde_json::from_str(r.get::<&str, _>("hours_json")).unwrap_or_default(),
+            days: serde_json::from_str(r.get::<&str, _>("days_json")).unwrap_or_default(),
+            duration_minutes: r.get::<i64, _>("duration_minutes").max(1) as u32,
+            fade_ms: r.get::<i64, _>("fade_ms").max(0) as u32,
+            enabled: r.get::<i64, _>("enabled") != 0,
+            last_fired_at: r.get("last_fired_at"),
+        })
+        .collect())
+}
+
+pub async fn upsert_relay_event(pool: &SqlitePool, event: &RelayEvent) -> Result<i64, sqlx::Error> {
+    let (hours_json, days_json) = schedule_json(&event.hours, &event.days);
+    let id = if let Some(id) = event.id {
+        sqlx::query(
+            "UPDATE relay_events SET name=?, url=?, minute=?, hours_json=?, days_json=?, duration_minutes=?, fade_ms=?, enabled=? WHERE id=?",
+        )
+        .bind(&event.name)
+        .bind(event.url.trim())
+        .bind(event.minute.min(59) as i64)
+        .bind(&hours_json)
+        .bind(&days_json)
+        .bind(event.duration_minutes.max(1) as i64)
+        .bind(event.fade_ms as i64)
+        .bind(event.enabled as i64)
+        .bind(id)
+        .execute(pool)
+        .await?;
+        id
+    } else {
+        sqlx::query(
+            "INSERT INTO relay_events (name, url, minute, hours_json, days_json, duration_minutes, fade_ms, enabled) VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(&event.name)
+        .bind(event.url.trim())
+        .bind(event.minute.min(59) as i64)
+        .bind(&hours_json)
+        .bind(&days_json)
+        .bind(event.duration_minutes.max(1) as i64)
+        .bind(event.fade_ms as i64)
+        .bind(event.enabled as i64)
+        .execute(pool)
+        .await?
+        .last_insert_rowid()
+    };
+    Ok(id)
+}
+
+pub async fn delete_relay_event(pool: &SqlitePool, id: i64) -> Result<(), sqlx::Error> {
+    sqlx::query("DELETE FROM relay_events WHERE id = ?")
+        .bind(id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+pub async fn mark_relay_event_fired(
+    pool: &SqlitePool,
+    id: i64,
+    occurrence: i64,
+) -> Result<(), sqlx::Error> {
+    sqlx::query("UPDATE relay_events SET last_fired_at = ? WHERE id = ?")
+        .bind(occurrence)
+        .bind(id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::FixedOffset;
+
+    fn news() -> RelayEvent {
+        RelayEvent {
+            id: Some(1),
+            name: "Network news".to_string(),
+            url: "https://news.example/live.mp3".to_string(),
+            minute: 0,
+            hours: vec![],
+            days: vec![],
+            duration_minutes: 5,
+            fade_ms: 1_500,
+            enabled: true,
+            last_fired_at: None,
+        }
+    }
+
+    #[test]
+    fn joins_late_while_enough_remains() {
+        let tz = FixedOffset::east_opt(0).unwrap();
+        let relay = news();
+        let start = tz.with_ymd_and_hms(2025, 6, 7, 10, 0, 0).unwrap();
+        let late = tz.with_ymd_and_hms(2025, 6, 7, 10, 3, 30).unwrap();
+        let too_late = tz.with_ymd_and_hms(2025, 6, 7, 10, 4, 30).unwrap();
+        assert_eq!(relay.due_at(&late), Some(start.timestamp()));
+        assert_eq!(relay.due_at(&too_late), None);
+    }
+
+    #[test]
+    fn relay_deck_avoids_rotation() {
+        assert_eq!(
+            relay_deck(&[DeckId::DeckA, DeckId::DeckB]),
+            Some(DeckId::Aux2)
+        );
+        assert_eq!(
+            relay_deck(&[DeckId::DeckA, DeckId::DeckB, DeckId::Aux2]),
+            Some(DeckId::Aux1)
+        );
+        assert_eq!(relay_deck(&DeckId::PLAYBACK), None);
+    }
+}
@@ -0,0 +1,683 @@
+/// Embedded listener request API
+///
+/// A small HTTP/1.1 server (hyper) so stations without the DesiZone gateway
+/// can take requests straight from their website:
+///
+///   GET  /api/nowplaying            — the track on air
+///   POST /api/request               — `{ "song_id": 123, "name": "Asha" }`
+///   GET  /api/request?song_id=123   — same, as a query string
+///   GET  /api/artwork/123?size=300  — cover art JPEG (96, 300 or 600 px)
+///
+/// Every call except artwork must carry the configured token in a header
+/// (`Authorization: Bearer …` or `X-Api-Token`), never in the URL where it
+/// would end up in logs and browser history; artwork links are handed to
+/// directories and chat services, so they are public. Requests are written to
+/// `request_log` and run through the request policy exactly like the
+/// `submit_song_request` command, using the caller's address for the per-IP
+/// limits. The server binds to loopback unless configured otherwise and only
+/// sends CORS headers for an explicitly configured origin.
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+use http_body_util::{BodyExt, Full, LengthLimitError, Limited};
+use hyper::body::{Bytes, Incoming};
+use hyper::header::{self, HeaderMap};
+use hyper::server::conn::http1;
+use hyper::service::service_fn;
+use hyper::{Request, Response, StatusCode};
+use hyper_util::rt::{TokioIo, TokioTimer};
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use tauri::{AppHandle, Manager};
+use tokio::net::TcpListener;
+use tokio::sync::oneshot;
+
+use crate::audio::analyzer::artwork::{self, ArtworkLookup};
+use crate::audio::crossfade::DeckId;
+use crate::audio::engine::DeckStateEvent;
+use crate::error::ErrorCode;
+use crate::state::AppState;
+
+const MAX_HEADER_BYTES: usize = 16 * 1024;
+const MAX_BODY_BYTES: usize = 8 * 1024;
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RequestApiConfig {
+    pub enabled: bool,
+    pub bind_address: String,
+    pub port: u16,
+    /// Shared secret the website sends with every call
+    pub token: String,
+    /// Website origin allowed to call from the browser, e.g.
+    /// `https://radio.example` (empty = no CORS headers; `*` is not honoured)
+    pub cors_origin: String,
+    /// Take the client address from `X-Forwarded-For` (behind a reverse proxy)
+    pub trust_forwarded_for: bool,
+    /// Stored as `requester_platform` on every logged request
+    pub platform: String,
+}
+
+impl Default for RequestApiConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            bind_address: "127.0.0.1".to_string(),
+            port: 8095,
+            token: String::new(),
+            cors_origin: String::new(),
+            trust_forwarded_for: false,
+            platform: "web".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RequestApiStatus {
+    pub running: bool,
+    pub listening_on: Option<String>,
+    pub requests_accepted: u64,
+    pub requests_rejected: u64,
+    pub last_error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NowPlaying {
+    pub deck: String,
+    pub song_id: Option<i64>,
+    pub title: String,
+    pub artist: String,
+    pub album: String,
+    pub duration_ms: u64,
+    pub position_ms: u64,
+    /// Public cover art link, when one is available
+    pub artwork_url: Option<String>,
+}
+
+// ── Server lifecycle ──────────────────────────────────────────────────────────
+
+struct Runtime {
+    shutdown: Option<oneshot::Sender<()>>,
+    status: RequestApiStatus,
+}
+
+static RUNTIME: OnceLock<Mutex<Runtime>> = OnceLock::new();
+
+fn runtime() -> &'static Mutex<Runtime> {
+    RUNTIME.get_or_init(|| {
+        Mutex::new(Runtime {
+            shutdown: None,
+            status: RequestApiStatus::default(),
+        })
+    })
+}
+
+pub fn status() -> RequestApiStatus {
+    runtime().lock().unwrap().status.clone()
+}
+
+/// Bind and start serving; a server that is already running is replaced.
+pub async fn start(app: AppHandle, config: RequestApiConfig) -> Result<RequestApiStatus, String> {
+    if config.token.trim().is_empty() {
+        return Err("Request API token must be set before the server can start".to_string());
+    }
+    if stop() {
+        // Give the previous accept loop a moment to release the port.
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+
+    let listener = match TcpListener::bind((config.bind_address.as_str(), config.port)).await {
+        Ok(l) => l,
+        Err(e) => {
+            let msg = format!(
+                "Request API: cannot bind {}:{}: {e}",
+                config.bind_address, config.port
+            );
+            runtime().lock().unwrap().status.last_error = Some(msg.clone());
+            return Err(msg);
+        }
+    };
+    let local_addr = listener.local_addr().map_err(|e| e.to_string())?;
+    if config.cors_origin.trim() == "*" {
+        log::warn!("Request API: wildcard CORS origin ignored; set the website's origin instead");
+    }
+    let (tx, mut rx) = oneshot::channel();
+    {
+        let mut rt = runtime().lock().unwrap();
+        rt.shutdown = Some(tx);
+        rt.status.running = true;
+        rt.status.listening_on = Some(local_addr.to_string());
+        rt.status.last_error = None;
+    }
+    log::info!("Request API listening on {local_addr}");
+
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::select! {
+                _ = &mut rx => break,
+                accepted = listener.accept() => match accepted {
+                    Ok((stream, peer)) => {
+                        let app = app.clone();
+                        let config = config.clone();
+                        tauri::async_runtime::spawn(serve_connection(app, config, stream, peer));
+                    }
+                    Err(e) => {
+                        log::warn!("Request API accept failed: {e}");
+                        tokio::time::sleep(Duration::from_millis(200)).await;
+                    }
+                },
+            }
+        }
+        log::info!("Request API on {local_addr} stopped");
+    });
+
+    Ok(status())
+}
+
+/// Stop the server. Returns whether one was running.
+pub fn stop() -> bool {
+    let mut rt = runtime().lock().unwrap();
+    rt.status.running = false;
+    rt.status.listening_on = None;
+    match rt.shutdown.take() {
+        Some(tx) => {
+            let _ = tx.send(());
+            true
+        }
+        None => false,
+    }
+}
+
+// ── HTTP handling ─────────────────────────────────────────────────────────────
+
+#[derive(Debug, Default)]
+struct HttpRequest {
+    method: String,
+    path: String,
+    query: HashMap<String, String>,
+    headers: HeaderMap,
+    body: Vec<u8>,
+}
+
+impl HttpRequest {
+    fn from_parts(parts: &hyper::http::request::Parts, body: Vec<u8>) -> Self {
+        Self {
+            method: parts.method.as_str().to_string(),
+            path: parts.uri.path().trim_end_matches('/').to_string(),
+            query: parts.uri.query().map(parse_form).unwrap_or_default(),
+            headers: parts.headers.clone(),
+            body,
+        }
+    }
+
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(name).and_then(|v| v.to_str().ok())
+    }
+}
+
+struct HttpResponse {
+    status: u16,
+    content_type: &'static str,
+    body: Vec<u8>,
+    /// Seconds clients may cache the response (0 = `no-store`)
+    max_age: u32,
+}
+
+impl HttpResponse {
+    fn json(status: u16, body: serde_json::Value) -> Self {
+        Self {
+            status,
+            content_type: "application/json",
+            body: if status == 204 {
+                Vec::new()
+            } else {
+                body.to_string().into_bytes()
+            },
+            max_age: 0,
+        }
+    }
+
+    fn jpeg(body: Vec<u8>) -> Self {
+        Self {
+            status: 200,
+            content_type: "image/jpeg",
+            body,
+            max_age: 3600,
+        }
+    }
+
+    fn error(status: u16, message: impl Into<String>) -> Self {
+        Self::json(status, serde_json::json!({ "error": message.into() }))
+    }
+}
+
+/// Serve one connection. hyper does the HTTP/1.1 framing: chunked bodies,
+/// and rejecting malformed or conflicting `Content-Length` headers.
+async fn serve_connection(
+    app: AppHandle,
+    config: RequestApiConfig,
+    stream: tokio::net::TcpStream,
+    peer: SocketAddr,
+) {
+    let service = service_fn(move |req| {
+        let app = app.clone();
+        let config = config.clone();
+        async move { Ok::<_, Infallible>(handle(&app, &config, req, peer).await) }
+    });
+    let result = http1::Builder::new()
+        .timer(TokioTimer::new())
+        .header_read_timeout(READ_TIMEOUT)
+        .max_buf_size(MAX_HEADER_BYTES)
+        .serve_connection(TokioIo::new(stream), service)
+        .await;
+    if let Err(e) = result {
+        log::debug!("Request API connection from {peer}: {e}");
+    }
+}
+
+async fn handle(
+    app: &AppHandle,
+    config: &RequestApiConfig,
+    req: Request<Incoming>,
+    peer: SocketAddr,
+) -> Response<Full<Bytes>> {
+    let response = match read_request(req).await {
+        Err(resp) => resp,
+        Ok(req) if req.method == "OPTIONS" => HttpResponse::json(204, serde_json::Value::Null),
+        Ok(req) => route(app, config, &req, client_ip(config, &req, peer)).await,
+    };
+    into_response(config, response)
+}
+
+/// Collect the body (at most `MAX_BODY_BYTES`); a slow, oversized or
+/// truncated one yields the error response.
+async fn read_request(req: Request<Incoming>) -> Result<HttpRequest, HttpResponse> {
+    let (parts, body) = req.into_parts();
+    let collected =
+        tokio::time::timeout(READ_TIMEOUT, Limited::new(body, MAX_BODY_BYTES).collect());
+    let body = match collected.await {
+        Err(_) => return Err(HttpResponse::error(408, "Request body timed out")),
+        Ok(Err(e)) if e.is::<LengthLimitError>() => {
+            return Err(HttpResponse::error(413, "Request body too large"))
+        }
+        Ok(Err(_)) => return Err(HttpResponse::error(400, "Incomplete request body")),
+        Ok(Ok(collected)) => collected.to_bytes().to_vec(),
+    };
+    Ok(HttpRequest::from_parts(&parts, body))
+}
+
+/// Parse `a=1&b=two+words` (query strings and form bodies).
+fn parse_form(input: &str) -> HashMap<String, String> {
+    let decode = |s: &str| {
+        let s = s.replace('+', " ");
+        urlencoding::decode(&s).map(|c| c.into_owned()).unwrap_or(s)
+    };
+    input
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (k, v) = pair.split_once('=').unwrap_or((pair, ""));
+            (decode(k), decode(v))
+        })
+        .collect()
+}
+
+fn client_ip(config: &RequestApiConfig, req: &HttpRequest, peer: SocketAddr) -> IpAddr {
+    if config.trust_forwarded_for {
+        let forwarded = req
+            .header("x-forwarded-for")
+            .and_then(|v| v.split(',').next())
+            .and_then(|v| v.trim().parse().ok());
+        if let Some(ip) = forwarded {
+            return ip;
+        }
+    }
+    peer.ip()
+}
+
+fn authorized(config: &RequestApiConfig, req: &HttpRequest) -> bool {
+    let supplied = req
+        .header("authorization")
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .or_else(|| req.header("x-api-token"));
+    token_matches(supplied, &config.token)
+}
+
+/// Whether a supplied token equals the configured one (trimmed; an empty
+/// configured token matches nothing).
+pub(crate) fn token_matches(supplied: Option<&str>, expected: &str) -> bool {
+    let expected = expected.trim();
+    !expected.is_empty()
+        && supplied.is_some_and(|t| {
+            // Length check first, then compare every byte so timing does not leak a prefix.
+            t.len() == expected.len()
+                && t.bytes()
+                    .zip(expected.bytes())
+                    .fold(0u8, |acc, (a, b)| acc | (a ^ b))
+                    == 0
+        })
+}
+
+async fn route(
+    app: &AppHandle,
+    config: &RequestApiConfig,
+    req: &HttpRequest,
+    ip: IpAddr,
+) -> HttpResponse {
+    let state = app.state::<AppState>();
+    if let Some(id) = req.path.strip_prefix("/api/artwork/") {
+        if req.method != "GET" {
+            return HttpResponse::error(405, "Method not allowed");
+        }
+        return handle_artwork(&state, id, req).await;
+    }
+    if !authorized(config, req) {
+        return HttpResponse::error(401, "Missing or invalid token");
+    }
+    match (req.method.as_str(), req.path.as_str()) {
+        ("GET", "/api/nowplaying") => HttpResponse::json(
+            200,
+            serde_json::json!({ "now_playing": now_playing(&state).await }),
+        ),
+        ("GET" | "POST", "/api/request") => handle_request(&state, config, req, ip).await,
+        (_, "/api/nowplaying" | "/api/request") => HttpResponse::error(405, "Method not allowed"),
+        _ => HttpResponse::error(404, "Not found"),
+    }
+}
+
+async fn handle_artwork(state: &AppState, id: &str, req: &HttpRequest) -> HttpResponse {
+    let Ok(song_id) = id.trim_end_matches('/').parse::<i64>() else {
+        return HttpResponse::error(404, "Not found");
+    };
+    let Some(local) = state.local_db.as_ref() else {
+        return HttpResponse::error(503, "Local database not available");
+    };
+    let size = req
+        .query
+        .get("size")
+        .and_then(|s| s.parse::<u32>().ok())
+        .unwrap_or(300);
+    let config = artwork::get_config(local).await.unwrap_or_default();
+    let lookup = crate::commands::artwork_commands::lookup_for(state, song_id, None).await;
+    match artwork::resolve(&lookup, Some(size), &config).await {
+        Ok(Some(art)) => match tokio::fs::read(&art.path).await {
+            Ok(data) => HttpResponse::jpeg(data),
+            Err(e) => HttpResponse::error(503, e.to_string()),
+        },
+        Ok(None) => HttpResponse::error(404, "No artwork for this song"),
+        Err(e) => HttpResponse::error(503, e),
+    }
+}
+
+async fn handle_request(
+    state: &AppState,
+    config: &RequestApiConfig,
+    req: &HttpRequest,
+    ip: IpAddr,
+) -> HttpResponse {
+    let is_json = req
+        .header("content-type")
+        .is_some_and(|ct| ct.starts_with("application/json"));
+    let mut params = req.query.clone();
+    if is_json {
+        match serde_json::from_slice::<serde_json::Value>(&req.body) {
+            Ok(serde_json::Value::Object(map)) => {
+                for (k, v) in map {
+                    let v = match v {
+                        serde_json::Value::String(s) => s,
+                        other => other.to_string(),
+                    };
+                    params.insert(k, v);
+                }
+            }
+            _ => return HttpResponse::error(400, "Body must be a JSON object"),
+        }
+    } else if !req.body.is_empty() {
+        params.extend(parse_form(&String::from_utf8_lossy(&req.body)));
+    }
+
+    let Some(song_id) = params
+        .get("song_id")
+        .and_then(|v| v.trim().parse::<i64>().ok())
+    else {
+        return HttpResponse::error(400, "song_id is required");
+    };
+    let name = params
+        .get("name")
+        .map(|n| n.trim().chars().take(100).collect::<String>())
+        .filter(|n| !n.is_empty());
+
+    let result = crate::commands::scheduler_commands::submit_request_as(
+        state,
+        song_id,
+        name,
+        Some(config.platform.clone()).filter(|p| !p.is_empty()),
+        Some(ip.to_string()),
+    )
+    .await;
+    match result {
+        Ok((entry, decision)) => {
+            {
+                let mut rt = runtime().lock().unwrap();
+                if decision.allowed {
+                    rt.status.requests_accepted += 1;
+                } else {
+                    rt.status.requests_rejected += 1;
+                }
+            }
+            HttpResponse::json(
+                if decision.allowed { 201 } else { 403 },
+                serde_json::json!({
+                    "request_id": entry.id,
+                    "status": entry.status,
+                    "allowed": decision.allowed,
+                    "violations": decision.violations,
+                }),
+            )
+        }
+        Err(e) if e.code == ErrorCode::NotFound => HttpResponse::error(404, e.message),
+        Err(e) => {
+            log::warn!("Request API: request for song {song_id} failed: {e}");
+            HttpResponse::error(503, "Requests are unavailable right now")
+        }
+    }
+}
+
+/// The most recently started main deck that is playing.
+pub fn on_air_deck(decks: &[DeckStateEvent]) -> Option<&DeckStateEvent> {
+    decks
+        .iter()
+        .filter(|d| DeckId::PLAYBACK.iter().any(|id| d.deck == id.to_string()))
+        .filter(|d| matches!(d.state.as_str(), "playing" | "crossfading"))
+        .min_by_key(|d| d.position_ms)
+}
+
+pub async fn now_playing(state: &AppState) -> Option<NowPlaying> {
+    let on_air = {
+        let engine = state.engine.lock().unwrap();
+        let decks: Vec<DeckStateEvent> = DeckId::PLAYBACK
+            .into_iter()
+            .filter_map(|d| engine.get_deck_state(d))
+            .collect();
+        on_air_deck(&decks).cloned()
+    }?;
+
+    let song = match on_air.song_id {
+        Some(song_id) => {
+            let sam_pool = { state.sam_db.read().await.as_ref().cloned() };
+            match sam_pool {
+                Some(pool) => crate::db::sam::get_song(&pool, song_id)
+                    .await
+                    .ok()
+                    .flatten(),
+                None => None,
+            }
+        }
+        None => None,
+    };
+    let artwork_url = match (&song, &state.local_db) {
+        (Some(s), Some(local)) => {
+            let mut lookup = ArtworkLookup::for_song(local, s).await;
+            if on_air.file_path.is_some() {
+                lookup.file_path = on_air.file_path.clone();
+            }
+            artwork::public_url_for(local, &lookup).await
+        }
+        _ => None,
+    };
+    let (title, artist, album) = match song {
+        Some(s) => (s.title, s.artist, s.album),
+        None => (
+            on_air
+                .file_path
+                .as_deref()
+                .and_then(|p| std::path::Path::new(p).file_stem())
+                .map(|s| s.to_string_lossy().to_string())
+                .unwrap_or_default(),
+            String::new(),
+            String::new(),
+        ),
+    };
+    Some(NowPlaying {
+        deck: on_air.deck,
+        song_id: on_air.song_id,
+        title,
+        artist,
+        album,
+        duration_ms: on_air.duration_ms,
+        position_ms: on_air.position_ms,
+        artwork_url,
+    })
+}
+
+/// The configured CORS origin, if one may be sent. A wildcard would let any
+/// site script requests with the station's token, so it is never echoed.
+fn cors_origin(config: &RequestApiConfig) -> Option<&str> {
+    Some(config.cors_origin.trim()).filter(|o| !o.is_empty() && *o != "*")
+}
+
+fn into_response(config: &RequestApiConfig, response: HttpResponse) -> Response<Full<Bytes>> {
+    let cache_control = if response.max_age > 0 {
+        format!("public, max-age={}", response.max_age)
+    } else {
+        "no-store".to_string()
+    };
+    let mut builder = Response::builder()
+        .status(response.status)
+        .header(header::CONTENT_TYPE, response.content_type)
+        .header(header::CACHE_CONTROL, cache_control);
+    if let Some(origin) = cors_origin(config) {
+        builder = builder
+            .header(header::ACCESS_CONTROL_ALLOW_ORIGIN, origin)
+            .header(header::ACCESS_CONTROL_ALLOW_METHODS, "GET, POST, OPTIONS")
+            .header(
+                header::ACCESS_CONTROL_ALLOW_HEADERS,
+                "Authorization, Content-Type, X-Api-Token",
+            )
+            .header(header::VARY, "Origin");
+    }
+    builder
+        .body(Full::new(Bytes::from(response.body)))
+        .unwrap_or_else(|e| {
+            log::warn!("Request API: bad response headers: {e}");
+            let mut resp = Response::new(Full::default());
+            *resp.status_mut() = StatusCode::INTERNAL_SERVER_ERROR;
+            resp
+        })
+}
+
+// ── DB helpers ────────────────────────────────────────────────────────────────
+
+pub async fn get_config(pool: &SqlitePool) -> Result<RequestApiConfig, sqlx::Error> {
+    let row: Option<String> =
+        sqlx::query_scalar("SELECT config_json FROM request_api_config WHERE id = 1")
+            .fetch_optional(pool)
+            .await?;
+    Ok(row
+        .and_then(|j| serde_json::from_str(&j).ok())
+        .unwrap_or_default())
+}
+
+pub async fn save_config(pool: &SqlitePool, config: &RequestApiConfig) -> Result<(), sqlx::Error> {
+    let json = serde_json::to_string(config).unwrap_or_else(|_| "{}".to_string());
+    sqlx::query(
+        "INSERT INTO request_api_config (id, config_json, updated_at) VALUES (1, ?, strftime('%s','now')) \
+         ON CONFLICT(id) DO UPDATE SET config_json = excluded.config_json, updated_at = excluded.updated_at",
+    )
+    .bind(json)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(uri: &str, headers: &[(&str, &str)]) -> HttpRequest {
+        let mut builder = Request::builder().uri(uri);
+        for (name, value) in headers {
+            builder = builder.header(*name, *value);
+        }
+        let (parts, ()) = builder.body(()).unwrap().into_parts();
+        HttpRequest::from_parts(&parts, Vec::new())
+    }
+
+    #[test]
+    fn parses_query_and_takes_the_token_from_headers_only() {
+        let req = request(
+            "/api/request/?song_id=42&name=Asha+K%C3%A1&token=s3cret",
+            &[("X-Forwarded-For", "203.0.113.9, 10.0.0.1")],
+        );
+        assert_eq!(req.method, "GET");
+        assert_eq!(req.path, "/api/request");
+        assert_eq!(req.query.get("song_id").map(String::as_str), Some("42"));
+        assert_eq!(req.query.get("name").map(String::as_str), Some("Asha Ká"));
+
+        let mut config = RequestApiConfig {
+            token: "s3cret".to_string(),
+            ..Default::default()
+        };
+        assert!(!authorized(&config, &req));
+        assert!(authorized(
+            &config,
+            &request("/api/nowplaying", &[("Authorization", "Bearer s3cret")])
+        ));
+        assert!(authorized(
+            &config,
+            &request("/api/nowplaying", &[("X-Api-Token", "s3cret")])
+        ));
+
+        let peer: SocketAddr = "127.0.0.1:5000".parse().unwrap();
+        assert_eq!(client_ip(&config, &req, peer), peer.ip());
+        config.trust_forwarded_for = true;
+        assert_eq!(
+            client_ip(&config, &req, peer).to_string(),
+            "203.0.113.9".to_string()
+        );
+    }
+
+    #[test]
+    fn defaults_are_loopback_without_cors() {
+        let mut config = RequestApiConfig::default();
+        assert_eq!(config.bind_address, "127.0.0.1");
+        let resp = into_response(&config, HttpResponse::json(200, serde_json::json!({})));
+        assert!(!resp
+            .headers()
+            .contains_key(header::ACCESS_CONTROL_ALLOW_ORIGIN));
+
+        config.cors_origin = "*".to_string();
+        assert_eq!(cors_origin(&config), None);
+        config.cors_origin = "https://radio.example".to_string();
+        let resp = into_response(&config, HttpResponse::error(403, "no"));
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+        assert_eq!(
+            resp.headers()[header::ACCESS_CONTROL_ALLOW_ORIGIN],
+            "https://radio.example"
+        );
+    }
+}
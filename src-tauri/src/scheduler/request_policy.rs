@@ -1,4 +1,4 @@
-use chrono::Timelike;
+use chrono::{TimeZone, Timelike};
 /// Request Policy Engine
 ///
 /// Evaluates song requests against a configurable policy to auto-accept or
@@ -9,11 +9,15 @@ use sqlx::Row;
 
 // ── Policy ────────────────────────────────────────────────────────────────────
 
+/// Request limits. Counting limits of 0 mean "no limit".
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct RequestPolicy {
     // Song limits
     pub max_requests_per_song_per_day: u32,
     pub min_minutes_between_same_song: u32,
+    /// Reject songs that aired less than this many minutes ago
+    pub min_minutes_since_played: u32,
 
     // Artist limits
     pub max_requests_per_artist_per_hour: u32,
@@ -26,18 +30,32 @@ pub struct RequestPolicy {
     pub max_requests_per_requester_per_day: u32,
     pub max_requests_per_requester_per_hour: u32,
 
+    // Per-IP rate limits (shared by every requester behind one address).
+    // Off by default: saved policies predate them and NAT'd listeners share IPs
+    pub max_requests_per_ip_per_hour: u32,
+    pub max_requests_per_ip_per_day: u32,
+    pub min_seconds_between_ip_requests: u32,
+
+    /// Station-wide cap on accepted/pending requests per day
+    pub max_total_requests_per_day: u32,
+
     // Queue position for accepted request
     pub queue_position: RequestQueuePosition,
 
     // Blacklists
     pub blacklisted_song_ids: Vec<i64>,
     pub blacklisted_categories: Vec<String>,
+    pub blacklisted_artists: Vec<String>,
 
-    // Hours when requests are accepted (start_hour, end_hour in 24h)
+    // Hours when requests are accepted (start_hour, end_hour in 24h; may wrap midnight)
     pub active_hours: Option<(u8, u8)>,
 
     // Auto-accept if all checks pass
     pub auto_accept: bool,
+
+    /// Hours when passing requests are auto-accepted (may wrap midnight);
+    /// outside them they wait for the operator. `None` = around the clock
+    pub auto_accept_hours: Option<(u8, u8)>,
 }
 
 impl Default for RequestPolicy {
@@ -45,28 +63,50 @@ impl Default for RequestPolicy {
         Self {
             max_requests_per_song_per_day: 3,
             min_minutes_between_same_song: 60,
+            min_minutes_since_played: 60,
             max_requests_per_artist_per_hour: 2,
             min_minutes_between_same_artist: 30,
             max_requests_per_album_per_day: 5,
             max_requests_per_requester_per_day: 5,
             max_requests_per_requester_per_hour: 2,
+            max_requests_per_ip_per_hour: 0,
+            max_requests_per_ip_per_day: 0,
+            min_seconds_between_ip_requests: 0,
+            max_total_requests_per_day: 0,
             queue_position: RequestQueuePosition::End,
             blacklisted_song_ids: Vec::new(),
             blacklisted_categories: Vec::new(),
+            blacklisted_artists: Vec::new(),
             active_hours: None,
             auto_accept: false,
+            auto_accept_hours: None,
         }
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(rename_all = "snake_case")]
+/// Where an auto-accepted request goes in the SAM queue.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[serde(tag = "type", rename_all = "snake_case")]
 pub enum RequestQueuePosition {
     Next,
-    After(u32),
+    After {
+        n: u32,
+    },
+    #[default]
     End,
 }
 
+impl RequestQueuePosition {
+    /// Index of the new queue entry; anything past the end appends.
+    pub fn index(&self) -> usize {
+        match self {
+            Self::Next => 0,
+            Self::After { n } => *n as usize,
+            Self::End => usize::MAX,
+        }
+    }
+}
+
 // ── Request log entry ─────────────────────────────────────────────────────────
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -75,6 +115,8 @@ pub struct RequestLogEntry {
     pub song_id: i64,
     pub song_title: Option<String>,
     pub artist: Option<String>,
+    #[serde(default)]
+    pub album: Option<String>,
     pub requester_name: Option<String>,
     pub requester_platform: Option<String>,
     pub requester_ip: Option<String>,
@@ -114,192 +156,407 @@ impl RequestStatus {
 
 // ── Policy validation ─────────────────────────────────────────────────────────
 
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PolicyRule {
+    ActiveHours,
+    BlacklistSong,
+    BlacklistCategory,
+    BlacklistArtist,
+    RecentlyPlayed,
+    SongDayLimit,
+    SongMinGap,
+    ArtistHourLimit,
+    ArtistMinGap,
+    AlbumDayLimit,
+    RequesterHourLimit,
+    RequesterDayLimit,
+    IpRateLimit,
+    IpHourLimit,
+    IpDayLimit,
+    StationDayLimit,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PolicyViolation {
-    pub rule: String,
+    pub rule: PolicyRule,
     pub message: String,
+    /// Seconds until the request would pass this rule, when known
+    pub retry_after_secs: Option<i64>,
+}
+
+/// Result of running a request through every policy rule.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RequestDecision {
+    pub allowed: bool,
+    pub violations: Vec<PolicyViolation>,
+}
+
+impl RequestDecision {
+    /// Reason stored in `request_log.rejection_reason`.
+    pub fn rejection_reason(&self) -> Option<String> {
+        (!self.violations.is_empty()).then(|| {
+            self.violations
+                .iter()
+                .map(|v| v.message.as_str())
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+    }
+}
+
+/// The song being requested, as far as the policy cares.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RequestSubject {
+    pub song_id: i64,
+    pub artist: String,
+    pub album: String,
+    pub categories: Vec<String>,
+    /// Unix time the song last aired
+    pub last_played_at: Option<i64>,
+}
+
+impl RequestSubject {
+    pub fn from_sam_song(song: &crate::db::sam::SamSong, categories: Vec<String>) -> Self {
+        let last_played =
+            crate::scheduler::rotation::parse_sam_datetime_unix(song.date_played.as_deref());
+        Self {
+            song_id: song.id,
+            artist: song.artist.clone(),
+            album: song.album.clone(),
+            categories,
+            last_played_at: (last_played > 0).then_some(last_played),
+        }
+    }
+}
+
+/// Who is asking.
+#[derive(Debug, Clone, Copy)]
+pub struct Requester<'a> {
+    pub name: &'a str,
+    pub ip: Option<&'a str>,
+}
+
+fn local_hour(at: i64) -> u8 {
+    chrono::Local
+        .timestamp_opt(at, 0)
+        .single()
+        .map(|t| t.hour() as u8)
+        .unwrap_or(0)
+}
+
+fn within_active_hours(hour: u8, (start, end): (u8, u8)) -> bool {
+    if start == end {
+        true
+    } else if start < end {
+        hour >= start && hour < end
+    } else {
+        hour >= start || hour < end
+    }
 }
 
-/// Evaluate a new request against the current policy.
-/// Returns Ok(()) if the request is allowed, Err(violation) if not.
+/// Requests matching `column = value` in `(since, at]`, excluding rejected
+/// rows and `exclude_id`: (count, latest requested_at).
+async fn window_stats(
+    pool: &SqlitePool,
+    column: &str,
+    value: Option<&str>,
+    since: i64,
+    at: i64,
+    exclude_id: Option<i64>,
+) -> Result<(i64, Option<i64>), sqlx::Error> {
+    let filter = match value {
+        Some(_) => format!("{column} = ? AND "),
+        None => String::new(),
+    };
+    let sql = format!(
+        "SELECT COUNT(*) AS cnt, MAX(requested_at) AS last_at FROM request_log \
+         WHERE {filter}requested_at > ? AND requested_at <= ? AND status != 'rejected' AND id != ?"
+    );
+    let mut query = sqlx::query(&sql);
+    if let Some(value) = value {
+        query = query.bind(value);
+    }
+    let row = query
+        .bind(since)
+        .bind(at)
+        .bind(exclude_id.unwrap_or(-1))
+        .fetch_one(pool)
+        .await?;
+    Ok((row.get("cnt"), row.get("last_at")))
+}
+
+/// Evaluate a request against the policy as of `at` (Unix seconds).
+///
+/// Every rule is checked so the caller gets the full list of reasons.
+/// `exclude_id` keeps a stored request from counting against itself.
 pub async fn evaluate_request(
     pool: &SqlitePool,
     policy: &RequestPolicy,
-    song_id: i64,
-    song_artist: &str,
-    song_category: &str,
-    requester_name: &str,
-    _requester_ip: Option<&str>,
-) -> Result<(), PolicyViolation> {
-    let now = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .unwrap_or_default()
-        .as_secs() as i64;
+    subject: &RequestSubject,
+    requester: Requester<'_>,
+    at: i64,
+    exclude_id: Option<i64>,
+) -> Result<RequestDecision, sqlx::Error> {
+    let mut violations = Vec::new();
+    let mut violate = |rule: PolicyRule, message: String, retry_after_secs: Option<i64>| {
+        violations.push(PolicyViolation {
+            rule,
+            message,
+            retry_after_secs,
+        })
+    };
+    let minutes = |m: u32| i64::from(m) * 60;
+    let limited = |max: u32, count: i64| max > 0 && count >= i64::from(max);
+    let day_start = at - 86_400;
+    let hour_start = at - 3_600;
 
-    // Check active hours
-    if let Some((start_h, end_h)) = policy.active_hours {
-        let hour = chrono::Local::now().hour() as u8;
-        if hour < start_h || hour >= end_h {
-            return Err(PolicyViolation {
-                rule: "active_hours".to_string(),
-                message: format!(
-                    "Requests only accepted between {:02}:00 and {:02}:00",
-                    start_h, end_h
+    if let Some(window) = policy.active_hours {
+        if !within_active_hours(local_hour(at), window) {
+            violate(
+                PolicyRule::ActiveHours,
+                format!(
+                    "Requests only accepted between {:02}:00 and {:02}:00.",
+                    window.0, window.1
                 ),
-            });
+                None,
+            );
         }
     }
 
-    // Blacklisted song
-    if policy.blacklisted_song_ids.contains(&song_id) {
-        return Err(PolicyViolation {
-            rule: "blacklist_song".to_string(),
-            message: "This song is not requestable.".to_string(),
-        });
+    if policy.blacklisted_song_ids.contains(&subject.song_id) {
+        violate(
+            PolicyRule::BlacklistSong,
+            "This song is not requestable.".to_string(),
+            None,
+        );
+    }
+    if let Some(cat) = policy.blacklisted_categories.iter().find(|cat| {
+        let cat = cat.to_lowercase();
+        subject
+            .categories
+            .iter()
+            .any(|c| c.to_lowercase().contains(&cat))
+    }) {
+        violate(
+            PolicyRule::BlacklistCategory,
+            format!("Category '{}' is not requestable.", cat),
+            None,
+        );
+    }
+    if policy
+        .blacklisted_artists
+        .iter()
+        .any(|a| a.trim().eq_ignore_ascii_case(subject.artist.trim()))
+    {
+        violate(
+            PolicyRule::BlacklistArtist,
+            "This artist is not requestable.".to_string(),
+            None,
+        );
     }
 
-    // Blacklisted category
-    for cat in &policy.blacklisted_categories {
-        if song_category.to_lowercase().contains(&cat.to_lowercase()) {
-            return Err(PolicyViolation {
-                rule: "blacklist_category".to_string(),
-                message: format!("Category '{}' is not requestable.", cat),
-            });
+    if let Some(played) = subject.last_played_at.filter(|t| *t <= at) {
+        let ready_at = played + minutes(policy.min_minutes_since_played);
+        if policy.min_minutes_since_played > 0 && ready_at > at {
+            violate(
+                PolicyRule::RecentlyPlayed,
+                "This song was played recently.".to_string(),
+                Some(ready_at - at),
+            );
         }
     }
 
-    let day_start = now - 86400;
-    let hour_start = now - 3600;
-    let song_min_gap = now - (policy.min_minutes_between_same_song as i64 * 60);
-    let artist_min_gap = now - (policy.min_minutes_between_same_artist as i64 * 60);
-
-    // Same song per day
-    let song_day_count: i64 = sqlx::query_scalar(
-        "SELECT COUNT(*) FROM request_log WHERE song_id = ? AND requested_at > ? AND status != 'rejected'"
+    // Song
+    let song_id = subject.song_id.to_string();
+    let (song_day, _) =
+        window_stats(pool, "song_id", Some(&song_id), day_start, at, exclude_id).await?;
+    if limited(policy.max_requests_per_song_per_day, song_day) {
+        violate(
+            PolicyRule::SongDayLimit,
+            format!("This song has already been requested {song_day} times today."),
+            None,
+        );
+    }
+    let song_gap = minutes(policy.min_minutes_between_same_song);
+    let (_, last_song) = window_stats(
+        pool,
+        "song_id",
+        Some(&song_id),
+        at - song_gap,
+        at,
+        exclude_id,
     )
-    .bind(song_id)
-    .bind(day_start)
-    .fetch_one(pool)
-    .await
-    .unwrap_or(0);
-
-    if song_day_count >= policy.max_requests_per_song_per_day as i64 {
-        return Err(PolicyViolation {
-            rule: "song_day_limit".to_string(),
-            message: format!(
-                "This song has already been requested {} times today.",
-                song_day_count
+    .await?;
+    if let Some(t) = last_song.filter(|_| song_gap > 0) {
+        let wait = t + song_gap - at;
+        violate(
+            PolicyRule::SongMinGap,
+            format!(
+                "Please wait {} more minutes before requesting this song again.",
+                (wait + 59) / 60
             ),
-        });
+            Some(wait),
+        );
     }
 
-    // Same song min gap
-    let last_song_request: Option<i64> = sqlx::query_scalar(
-        "SELECT requested_at FROM request_log WHERE song_id = ? AND status != 'rejected' ORDER BY requested_at DESC LIMIT 1"
-    )
-    .bind(song_id)
-    .fetch_optional(pool)
-    .await
-    .ok()
-    .flatten();
-
-    if let Some(t) = last_song_request {
-        if t > song_min_gap {
-            let wait = (t + policy.min_minutes_between_same_song as i64 * 60 - now) / 60;
-            return Err(PolicyViolation {
-                rule: "song_min_gap".to_string(),
-                message: format!(
-                    "Please wait {} more minutes before requesting this song again.",
-                    wait
+    // Artist
+    if !subject.artist.is_empty() {
+        let artist = Some(subject.artist.as_str());
+        let (artist_hour, _) =
+            window_stats(pool, "artist", artist, hour_start, at, exclude_id).await?;
+        if limited(policy.max_requests_per_artist_per_hour, artist_hour) {
+            violate(
+                PolicyRule::ArtistHourLimit,
+                format!(
+                    "Too many requests for this artist this hour (max {}).",
+                    policy.max_requests_per_artist_per_hour
                 ),
-            });
+                None,
+            );
+        }
+        let artist_gap = minutes(policy.min_minutes_between_same_artist);
+        let (_, last_artist) =
+            window_stats(pool, "artist", artist, at - artist_gap, at, exclude_id).await?;
+        if let Some(t) = last_artist.filter(|_| artist_gap > 0) {
+            let wait = t + artist_gap - at;
+            violate(
+                PolicyRule::ArtistMinGap,
+                format!(
+                    "Please wait {} more minutes before requesting this artist again.",
+                    (wait + 59) / 60
+                ),
+                Some(wait),
+            );
         }
     }
 
-    // Artist per hour
-    let artist_hour_count: i64 = sqlx::query_scalar(
-        "SELECT COUNT(*) FROM request_log WHERE artist = ? AND requested_at > ? AND status != 'rejected'"
-    )
-    .bind(song_artist)
-    .bind(hour_start)
-    .fetch_one(pool)
-    .await
-    .unwrap_or(0);
-
-    if artist_hour_count >= policy.max_requests_per_artist_per_hour as i64 {
-        return Err(PolicyViolation {
-            rule: "artist_hour_limit".to_string(),
-            message: format!(
-                "Too many requests for this artist this hour (max {}).",
-                policy.max_requests_per_artist_per_hour
-            ),
-        });
+    // Album
+    if !subject.album.is_empty() {
+        let (album_day, _) = window_stats(
+            pool,
+            "album",
+            Some(subject.album.as_str()),
+            day_start,
+            at,
+            exclude_id,
+        )
+        .await?;
+        if limited(policy.max_requests_per_album_per_day, album_day) {
+            violate(
+                PolicyRule::AlbumDayLimit,
+                format!(
+                    "Too many requests from this album today (max {}).",
+                    policy.max_requests_per_album_per_day
+                ),
+                None,
+            );
+        }
     }
 
-    // Artist min gap
-    let last_artist_request: Option<i64> = sqlx::query_scalar(
-        "SELECT requested_at FROM request_log WHERE artist = ? AND status != 'rejected' ORDER BY requested_at DESC LIMIT 1"
-    )
-    .bind(song_artist)
-    .fetch_optional(pool)
-    .await
-    .ok()
-    .flatten();
-
-    if let Some(t) = last_artist_request {
-        if t > artist_min_gap {
-            let wait = (t + policy.min_minutes_between_same_artist as i64 * 60 - now) / 60;
-            return Err(PolicyViolation {
-                rule: "artist_min_gap".to_string(),
-                message: format!(
-                    "Please wait {} more minutes before requesting this artist again.",
-                    wait
+    // Requester
+    if !requester.name.is_empty() {
+        let name = Some(requester.name);
+        let (req_hour, _) =
+            window_stats(pool, "requester_name", name, hour_start, at, exclude_id).await?;
+        if limited(policy.max_requests_per_requester_per_hour, req_hour) {
+            violate(
+                PolicyRule::RequesterHourLimit,
+                format!(
+                    "You can only request {} songs per hour.",
+                    policy.max_requests_per_requester_per_hour
                 ),
-            });
+                None,
+            );
+        }
+        let (req_day, _) =
+            window_stats(pool, "requester_name", name, day_start, at, exclude_id).await?;
+        if limited(policy.max_requests_per_requester_per_day, req_day) {
+            violate(
+                PolicyRule::RequesterDayLimit,
+                format!(
+                    "You can only request {} songs per day.",
+                    policy.max_requests_per_requester_per_day
+                ),
+                None,
+            );
         }
     }
 
-    // Requester per hour
-    let req_hour_count: i64 = sqlx::query_scalar(
-        "SELECT COUNT(*) FROM request_log WHERE requester_name = ? AND requested_at > ? AND status != 'rejected'"
-    )
-    .bind(requester_name)
-    .bind(hour_start)
-    .fetch_one(pool)
-    .await
-    .unwrap_or(0);
-
-    if req_hour_count >= policy.max_requests_per_requester_per_hour as i64 {
-        return Err(PolicyViolation {
-            rule: "requester_hour_limit".to_string(),
-            message: format!(
-                "You can only request {} songs per hour.",
-                policy.max_requests_per_requester_per_hour
-            ),
-        });
+    // IP
+    if let Some(ip) = requester.ip.filter(|ip| !ip.is_empty()) {
+        let ip_gap = i64::from(policy.min_seconds_between_ip_requests);
+        let (_, last_ip) =
+            window_stats(pool, "requester_ip", Some(ip), at - ip_gap, at, exclude_id).await?;
+        if let Some(t) = last_ip.filter(|_| ip_gap > 0) {
+            let wait = t + ip_gap - at;
+            violate(
+                PolicyRule::IpRateLimit,
+                format!("Too many requests; try again in {} seconds.", wait.max(1)),
+                Some(wait),
+            );
+        }
+        let (ip_hour, _) =
+            window_stats(pool, "requester_ip", Some(ip), hour_start, at, exclude_id).await?;
+        if limited(policy.max_requests_per_ip_per_hour, ip_hour) {
+            violate(
+                PolicyRule::IpHourLimit,
+                format!(
+                    "Only {} requests per hour are allowed from your connection.",
+                    policy.max_requests_per_ip_per_hour
+                ),
+                None,
+            );
+        }
+        let (ip_day, _) =
+            window_stats(pool, "requester_ip", Some(ip), day_start, at, exclude_id).await?;
+        if limited(policy.max_requests_per_ip_per_day, ip_day) {
+            violate(
+                PolicyRule::IpDayLimit,
+                format!(
+                    "Only {} requests per day are allowed from your connection.",
+                    policy.max_requests_per_ip_per_day
+                ),
+                None,
+            );
+        }
     }
 
-    // Requester per day
-    let req_day_count: i64 = sqlx::query_scalar(
-        "SELECT COUNT(*) FROM request_log WHERE requester_name = ? AND requested_at > ? AND status != 'rejected'"
-    )
-    .bind(requester_name)
-    .bind(day_start)
-    .fetch_one(pool)
-    .await
-    .unwrap_or(0);
-
-    if req_day_count >= policy.max_requests_per_requester_per_day as i64 {
-        return Err(PolicyViolation {
-            rule: "requester_day_limit".to_string(),
-            message: format!(
-                "You can only request {} songs per day.",
-                policy.max_requests_per_requester_per_day
-            ),
-        });
+    // Station
+    if policy.max_total_requests_per_day > 0 {
+        let (total_day, _) = window_stats(pool, "", None, day_start, at, exclude_id).await?;
+        if limited(policy.max_total_requests_per_day, total_day) {
+            violate(
+                PolicyRule::StationDayLimit,
+                "The request line is full for today.".to_string(),
+                None,
+            );
+        }
     }
 
-    Ok(())
+    Ok(RequestDecision {
+        allowed: violations.is_empty(),
+        violations,
+    })
+}
+
+/// Status a request made at `at` (Unix seconds) should get once evaluated.
+pub fn status_for(policy: &RequestPolicy, decision: &RequestDecision, at: i64) -> RequestStatus {
+    status_at_hour(policy, decision, local_hour(at))
+}
+
+fn status_at_hour(policy: &RequestPolicy, decision: &RequestDecision, hour: u8) -> RequestStatus {
+    if !decision.allowed {
+        RequestStatus::Rejected
+    } else if policy.auto_accept
+        && policy
+            .auto_accept_hours
+            .is_none_or(|window| within_active_hours(hour, window))
+    {
+        RequestStatus::Accepted
+    } else {
+        RequestStatus::Pending
+    }
 }
 
 // ── DB helpers ────────────────────────────────────────────────────────────────
@@ -333,7 +590,7 @@ pub async fn get_requests(
     status: &str,
 ) -> Result<Vec<RequestLogEntry>, sqlx::Error> {
     let rows = sqlx::query(
-        "SELECT id, song_id, song_title, artist, requester_name, requester_platform, requester_ip, \
+        "SELECT id, song_id, song_title, artist, album, requester_name, requester_platform, requester_ip, \
          requested_at, status, rejection_reason, played_at \
          FROM request_log WHERE status = ? ORDER BY requested_at DESC LIMIT 200"
     )
@@ -348,6 +605,7 @@ pub async fn get_requests(
             song_id: r.get("song_id"),
             song_title: r.get("song_title"),
             artist: r.get("artist"),
+            album: r.get("album"),
             requester_name: r.get("requester_name"),
             requester_platform: r.get("requester_platform"),
             requester_ip: r.get("requester_ip"),
@@ -369,12 +627,13 @@ pub async fn insert_request(
         .as_secs() as i64;
 
     let r = sqlx::query(
-        "INSERT INTO request_log (song_id, song_title, artist, requester_name, requester_platform, requester_ip, requested_at, status, rejection_reason) \
-         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)"
+        "INSERT INTO request_log (song_id, song_title, artist, album, requester_name, requester_platform, requester_ip, requested_at, status, rejection_reason) \
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"
     )
     .bind(entry.song_id)
     .bind(&entry.song_title)
     .bind(&entry.artist)
+    .bind(&entry.album)
     .bind(&entry.requester_name)
     .bind(&entry.requester_platform)
     .bind(&entry.requester_ip)
@@ -386,6 +645,98 @@ pub async fn insert_request(
     Ok(r.last_insert_rowid())
 }
 
+/// Evaluate and store a new request. Requests that break the policy are
+/// logged as rejected with their reasons; the rest are accepted when the
+/// policy auto-accepts at this hour, otherwise left pending.
+pub async fn submit_request(
+    pool: &SqlitePool,
+    policy: &RequestPolicy,
+    subject: &RequestSubject,
+    mut entry: RequestLogEntry,
+) -> Result<(RequestLogEntry, RequestDecision), sqlx::Error> {
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs() as i64;
+    let requester = Requester {
+        name: entry.requester_name.as_deref().unwrap_or(""),
+        ip: entry.requester_ip.as_deref(),
+    };
+    let decision = evaluate_request(pool, policy, subject, requester, now, None).await?;
+    entry.status = status_for(policy, &decision, now);
+    entry.rejection_reason = decision.rejection_reason();
+    entry.requested_at = now;
+    entry.id = Some(insert_request(pool, &entry).await?);
+    Ok((entry, decision))
+}
+
+/// Counts from a triage pass over pending requests.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TriageSummary {
+    pub evaluated: u32,
+    pub rejected: u32,
+    pub accepted: u32,
+    /// `(request id, song id)` of the requests accepted in this pass, oldest first
+    pub accepted_requests: Vec<(i64, i64)>,
+}
+
+/// Re-check every pending request (oldest first, as of its own request
+/// time) and settle the ones the policy can decide on its own. `subjects`
+/// supplies library data per song; rows without one are checked with what
+/// the log itself records.
+pub async fn triage_pending_requests(
+    pool: &SqlitePool,
+    policy: &RequestPolicy,
+    subjects: &std::collections::HashMap<i64, RequestSubject>,
+) -> Result<TriageSummary, sqlx::Error> {
+    let mut pending = get_requests(pool, "pending").await?;
+    pending.sort_by_key(|r| (r.requested_at, r.id));
+
+    let mut summary = TriageSummary::default();
+    for request in pending {
+        let Some(id) = request.id else {
+            continue;
+        };
+        let subject = subjects
+            .get(&request.song_id)
+            .cloned()
+            .unwrap_or_else(|| RequestSubject {
+                song_id: request.song_id,
+                artist: request.artist.clone().unwrap_or_default(),
+                album: request.album.clone().unwrap_or_default(),
+                ..Default::default()
+            });
+        let requester = Requester {
+            name: request.requester_name.as_deref().unwrap_or(""),
+            ip: request.requester_ip.as_deref(),
+        };
+        let decision = evaluate_request(
+            pool,
+            policy,
+            &subject,
+            requester,
+            request.requested_at,
+            Some(id),
+        )
+        .await?;
+        summary.evaluated += 1;
+        match status_for(policy, &decision, request.requested_at) {
+            RequestStatus::Rejected => {
+                let reason = decision.rejection_reason();
+                update_request_status(pool, id, RequestStatus::Rejected, reason.as_deref()).await?;
+                summary.rejected += 1;
+            }
+            RequestStatus::Accepted => {
+                update_request_status(pool, id, RequestStatus::Accepted, None).await?;
+                summary.accepted += 1;
+                summary.accepted_requests.push((id, request.song_id));
+            }
+            _ => {}
+        }
+    }
+    Ok(summary)
+}
+
 pub async fn update_request_status(
     pool: &SqlitePool,
     id: i64,
@@ -447,7 +798,7 @@ pub async fn get_request_history(
     offset: i64,
 ) -> Result<Vec<RequestLogEntry>, sqlx::Error> {
     let rows = sqlx::query(
-        "SELECT id, song_id, song_title, artist, requester_name, requester_platform, requester_ip, \
+        "SELECT id, song_id, song_title, artist, album, requester_name, requester_platform, requester_ip, \
          requested_at, status, rejection_reason, played_at \
          FROM request_log ORDER BY requested_at DESC LIMIT ? OFFSET ?"
     )
@@ -463,6 +814,7 @@ pub async fn get_request_history(
             song_id: r.get("song_id"),
             song_title: r.get("song_title"),
             artist: r.get("artist"),
+            album: r.get("album"),
             requester_name: r.get("requester_name"),
             requester_platform: r.get("requester_platform"),
             requester_ip: r.get("requester_ip"),
@@ -473,3 +825,215 @@ pub async fn get_request_history(
         })
         .collect())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn active_hours_wrap_past_midnight() {
+        assert!(within_active_hours(10, (9, 17)));
+        assert!(!within_active_hours(17, (9, 17)));
+        assert!(within_active_hours(23, (20, 2)));
+        assert!(within_active_hours(1, (20, 2)));
+        assert!(!within_active_hours(12, (20, 2)));
+        assert!(within_active_hours(5, (0, 0)));
+    }
+
+    #[test]
+    fn auto_accept_only_within_its_hours() {
+        let pass = RequestDecision {
+            allowed: true,
+            violations: Vec::new(),
+        };
+        let mut policy = RequestPolicy::default();
+        assert_eq!(status_at_hour(&policy, &pass, 3), RequestStatus::Pending);
+
+        policy.auto_accept = true;
+        assert_eq!(status_at_hour(&policy, &pass, 15), RequestStatus::Accepted);
+
+        // Overnight only: daytime requests wait for the operator.
+        policy.auto_accept_hours = Some((22, 6));
+        assert_eq!(status_at_hour(&policy, &pass, 3), RequestStatus::Accepted);
+        assert_eq!(status_at_hour(&policy, &pass, 15), RequestStatus::Pending);
+
+        let fail = RequestDecision {
+            allowed: false,
+            violations: Vec::new(),
+        };
+        assert_eq!(status_at_hour(&policy, &fail, 3), RequestStatus::Rejected);
+    }
+
+    #[test]
+    fn queue_position_reads_the_editor_format() {
+        let after: RequestQueuePosition =
+            serde_json::from_str(r#"{ "type": "after", "n": 2 }"#).unwrap();
+        assert_eq!(after.index(), 2);
+        let next: RequestQueuePosition = serde_json::from_str(r#"{ "type": "next" }"#).unwrap();
+        assert_eq!(next.index(), 0);
+        assert_eq!(RequestQueuePosition::End.index(), usize::MAX);
+    }
+
+    async fn request_log_pool() -> SqlitePool {
+        let pool = sqlx::sqlite::SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .expect("in-memory sqlite pool");
+        sqlx::query(
+            r#"
+            CREATE TABLE request_log (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                song_id INTEGER NOT NULL,
+                song_title TEXT,
+                artist TEXT,
+                album TEXT,
+                requester_name TEXT,
+                requester_platform TEXT,
+                requester_ip TEXT,
+                requested_at INTEGER,
+                status TEXT DEFAULT 'pending',
+                rejection_reason TEXT,
+                played_at INTEGER
+            )
+            "#,
+        )
+        .execute(&pool)
+        .await
+        .expect("create request_log table");
+        pool
+    }
+
+    async fn log_request(pool: &SqlitePool, song_id: i64, name: &str, ip: &str, at: i64) {
+        sqlx::query(
+            "INSERT INTO request_log (song_id, artist, requester_name, requester_ip, requested_at) \
+             VALUES (?, 'Artist', ?, ?, ?)",
+        )
+        .bind(song_id)
+        .bind(name)
+        .bind(ip)
+        .bind(at)
+        .execute(pool)
+        .await
+        .expect("insert request");
+    }
+
+    fn rules(decision: &RequestDecision) -> Vec<PolicyRule> {
+        decision.violations.iter().map(|v| v.rule).collect()
+    }
+
+    /// Only the limits under test; everything else unlimited.
+    fn unlimited() -> RequestPolicy {
+        RequestPolicy {
+            max_requests_per_song_per_day: 0,
+            min_minutes_between_same_song: 0,
+            min_minutes_since_played: 0,
+            max_requests_per_artist_per_hour: 0,
+            min_minutes_between_same_artist: 0,
+            max_requests_per_album_per_day: 0,
+            max_requests_per_requester_per_day: 0,
+            max_requests_per_requester_per_hour: 0,
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn ip_limits_are_off_by_default() {
+        let pool = request_log_pool().await;
+        let at = 1_000_000;
+        for i in 0..12 {
+            log_request(&pool, 100 + i, &format!("listener{i}"), "10.0.0.1", at - 10).await;
+        }
+        let subject = RequestSubject {
+            song_id: 1,
+            ..Default::default()
+        };
+        let requester = Requester {
+            name: "someone",
+            ip: Some("10.0.0.1"),
+        };
+        let decision = evaluate_request(&pool, &unlimited(), &subject, requester, at, None)
+            .await
+            .unwrap();
+        assert!(decision.allowed, "{:?}", rules(&decision));
+    }
+
+    #[tokio::test]
+    async fn ip_rules_count_every_requester_behind_the_address() {
+        let pool = request_log_pool().await;
+        let at = 1_000_000;
+        log_request(&pool, 10, "alice", "10.0.0.1", at - 20).await;
+        log_request(&pool, 11, "bob", "10.0.0.1", at - 1_800).await;
+        log_request(&pool, 12, "carol", "10.0.0.2", at - 20).await;
+
+        let policy = RequestPolicy {
+            min_seconds_between_ip_requests: 30,
+            max_requests_per_ip_per_hour: 2,
+            ..unlimited()
+        };
+        let subject = RequestSubject {
+            song_id: 1,
+            ..Default::default()
+        };
+        let requester = Requester {
+            name: "dave",
+            ip: Some("10.0.0.1"),
+        };
+        let decision = evaluate_request(&pool, &policy, &subject, requester, at, None)
+            .await
+            .unwrap();
+        assert_eq!(
+            rules(&decision),
+            vec![PolicyRule::IpRateLimit, PolicyRule::IpHourLimit]
+        );
+        assert_eq!(decision.violations[0].retry_after_secs, Some(10));
+
+        let elsewhere = Requester {
+            name: "dave",
+            ip: Some("10.0.0.3"),
+        };
+        let decision = evaluate_request(&pool, &policy, &subject, elsewhere, at, None)
+            .await
+            .unwrap();
+        assert!(decision.allowed);
+    }
+
+    #[tokio::test]
+    async fn rate_rules_skip_rejected_rows_and_the_request_itself() {
+        let pool = request_log_pool().await;
+        let at = 1_000_000;
+        log_request(&pool, 1, "alice", "", at - 600).await;
+        sqlx::query("UPDATE request_log SET status = 'rejected'")
+            .execute(&pool)
+            .await
+            .unwrap();
+        log_request(&pool, 1, "alice", "", at).await;
+
+        let policy = RequestPolicy {
+            min_minutes_between_same_song: 60,
+            max_requests_per_requester_per_hour: 1,
+            ..unlimited()
+        };
+        let subject = RequestSubject {
+            song_id: 1,
+            ..Default::default()
+        };
+        let requester = Requester {
+            name: "alice",
+            ip: None,
+        };
+        let decision = evaluate_request(&pool, &policy, &subject, requester, at, Some(2))
+            .await
+            .unwrap();
+        assert!(decision.allowed, "{:?}", rules(&decision));
+
+        let decision = evaluate_request(&pool, &policy, &subject, requester, at + 60, None)
+            .await
+            .unwrap();
+        assert_eq!(
+            rules(&decision),
+            vec![PolicyRule::SongMinGap, PolicyRule::RequesterHourLimit]
+        );
+        assert_eq!(decision.violations[0].retry_after_secs, Some(3_540));
+    }
+}
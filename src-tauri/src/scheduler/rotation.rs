@@ -2,14 +2,21 @@
 ///
 /// Selects the next track for AutoDJ based on active rotation rules.
 /// Rules are evaluated against the recent play history to avoid repetition.
-use std::collections::{BTreeSet, HashMap, HashSet};
+use std::collections::{hash_map::Entry, BTreeSet, HashMap, HashSet, VecDeque};
+use std::sync::{Mutex, OnceLock};
 
 use chrono::{Datelike, NaiveDateTime, Timelike, Utc};
+use rand::distributions::{Distribution, WeightedIndex};
+use rand::rngs::SmallRng;
+use rand::{Rng, SeedableRng};
 use serde::{Deserialize, Serialize};
 use sqlx::mysql::MySqlPool;
 use sqlx::sqlite::SqlitePool;
 use sqlx::Row;
 
+use crate::audio::analyzer::fingerprint::same_recording;
+use crate::db::sam_cache::{self, TtlCache};
+
 // ── Rule types ────────────────────────────────────────────────────────────────
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -33,6 +40,68 @@ pub enum RotationRule {
         max: u32,
         window_hours: u32,
     },
+    /// Apply `rule` only inside `daypart`, e.g. a longer artist separation
+    /// overnight than in drive time
+    Dayparted {
+        daypart: Daypart,
+        rule: Box<RotationRule>,
+    },
+    /// Keep songs from these categories off the air during `daypart`
+    CategoryBan {
+        categories: Vec<String>,
+        daypart: Daypart,
+    },
+    /// Limit the tempo change from the previous song. Songs without a known
+    /// BPM (beat-grid or SAM) always pass
+    MaxBpmJump { max_change: f32 },
+    /// No two slow songs back to back: BPM at or below `max_bpm`, or a genre
+    /// or mood containing one of `keywords` (e.g. "ballad")
+    NoConsecutiveSlow {
+        max_bpm: f32,
+        #[serde(default)]
+        keywords: Vec<String>,
+    },
+    /// Consecutive songs must differ in `attribute` (e.g. language categories
+    /// or decades). With `values` set, only those values alternate
+    Alternate {
+        attribute: FlowAttribute,
+        #[serde(default)]
+        values: Vec<String>,
+    },
+}
+
+/// Song property compared by [`RotationRule::Alternate`].
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum FlowAttribute {
+    Category,
+    Genre,
+    Mood,
+    /// Album decade, e.g. "1990s"
+    Era,
+}
+
+/// Station-local hours (and optionally weekdays) a rule is in force.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Daypart {
+    /// Inclusive; `start_hour == end_hour` means all day
+    pub start_hour: u8,
+    /// Exclusive; may be lower than `start_hour` to wrap past midnight
+    pub end_hour: u8,
+    #[serde(default)]
+    pub days: Vec<u8>, // 0=Mon..6=Sun, empty = every day
+}
+
+impl Daypart {
+    fn contains(&self, now_unix: i64) -> bool {
+        let Some(now) = chrono::DateTime::from_timestamp(now_unix, 0) else {
+            return false;
+        };
+        let now = now.with_timezone(&chrono::Local);
+        let day = now.weekday().num_days_from_monday() as u8;
+        (self.days.is_empty() || self.days.contains(&day))
+            && hour_in_window(now.hour() as u8, self.start_hour, self.end_hour)
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -62,6 +131,16 @@ pub struct PlaylistSong {
     pub weight: f64,
 }
 
+/// Persisted playback cursor for ordered playlist slots.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlaylistCursor {
+    pub playlist_id: i64,
+    /// Position (0-based, playlist order) the next pick starts searching from.
+    pub next_position: i64,
+    pub last_song_id: Option<i64>,
+    pub updated_at: Option<i64>,
+}
+
 // ── SAM-style clockwheel config ───────────────────────────────────────────────
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
@@ -70,6 +149,8 @@ pub enum ClockwheelSlotKind {
     Category,
     Directory,
     Request,
+    /// Local rotation playlist; `target` is the playlist id or name.
+    Playlist,
 }
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
@@ -83,6 +164,8 @@ pub enum ClockwheelSelectionMethod {
     MostRecentlyPlayedArtist,
     LeastRecentlyPlayedArtist,
     Lemming,
+    /// On `Playlist` slots: play songs in playlist position order using the
+    /// persisted per-playlist cursor. Other slot kinds fall back to least-played.
     PlaylistOrder,
 }
 
@@ -161,26 +244,44 @@ impl Default for ClockwheelConfig {
     }
 }
 
-impl ClockwheelConfig {
-    fn normalized(mut self) -> Self {
-        self.on_play_reduce_weight_by = self.on_play_reduce_weight_by.max(0.0);
-        self.on_request_increase_weight_by = self.on_request_increase_weight_by.max(0.0);
+/// A named slot list that can be assigned to hours of the week.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClockwheelTemplate {
+    pub id: Option<i64>,
+    pub name: String,
+    pub slots: Vec<ClockwheelSlot>,
+}
 
-        if self.slots.is_empty() {
-            self.slots.push(ClockwheelSlot::default());
-        }
+/// One cell of the 7×24 hour grid. `template_id = None` uses the global slots.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ClockwheelHourAssignment {
+    pub day: u8, // 0=Mon..6=Sun
+    pub hour: u8,
+    pub template_id: Option<i64>,
+}
 
-        for (i, slot) in self.slots.iter_mut().enumerate() {
-            if slot.id.trim().is_empty() {
-                slot.id = format!("slot-{}", i + 1);
-            }
-            slot.start_hour = slot.start_hour.map(|h| h.min(23));
-            slot.end_hour = slot.end_hour.map(|h| h.min(23));
-            slot.active_days.retain(|d| *d <= 6);
-            slot.active_days.sort_unstable();
-            slot.active_days.dedup();
+fn normalize_slots(slots: &mut Vec<ClockwheelSlot>) {
+    if slots.is_empty() {
+        slots.push(ClockwheelSlot::default());
+    }
+
+    for (i, slot) in slots.iter_mut().enumerate() {
+        if slot.id.trim().is_empty() {
+            slot.id = format!("slot-{}", i + 1);
         }
+        slot.start_hour = slot.start_hour.map(|h| h.min(23));
+        slot.end_hour = slot.end_hour.map(|h| h.min(23));
+        slot.active_days.retain(|d| *d <= 6);
+        slot.active_days.sort_unstable();
+        slot.active_days.dedup();
+    }
+}
 
+impl ClockwheelConfig {
+    fn normalized(mut self) -> Self {
+        self.on_play_reduce_weight_by = self.on_play_reduce_weight_by.max(0.0);
+        self.on_request_increase_weight_by = self.on_request_increase_weight_by.max(0.0);
+        normalize_slots(&mut self.slots);
         self
     }
 }
@@ -311,6 +412,134 @@ pub async fn set_active_playlist(pool: &SqlitePool, playlist_id: i64) -> Result<
     Ok(())
 }
 
+pub async fn get_playlist_songs(
+    pool: &SqlitePool,
+    playlist_id: i64,
+) -> Result<Vec<PlaylistSong>, sqlx::Error> {
+    let rows = sqlx::query(
+        "SELECT playlist_id, song_id, position, weight FROM playlist_songs
+         WHERE playlist_id = ?
+         ORDER BY position IS NULL, position ASC, song_id ASC",
+    )
+    .bind(playlist_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|r| PlaylistSong {
+            playlist_id: r.get("playlist_id"),
+            song_id: r.get("song_id"),
+            position: r.get("position"),
+            weight: r.try_get::<f64, _>("weight").unwrap_or(1.0),
+        })
+        .collect())
+}
+
+/// Replace the playlist contents with `song_ids`, assigning positions in the
+/// given order. Duplicate ids keep their first position.
+pub async fn set_playlist_songs(
+    pool: &SqlitePool,
+    playlist_id: i64,
+    song_ids: &[i64],
+) -> Result<(), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    sqlx::query("DELETE FROM playlist_songs WHERE playlist_id = ?")
+        .bind(playlist_id)
+        .execute(&mut *tx)
+        .await?;
+
+    let mut seen = HashSet::new();
+    let mut position = 0i32;
+    for song_id in song_ids {
+        if !seen.insert(*song_id) {
+            continue;
+        }
+        sqlx::query(
+            "INSERT INTO playlist_songs (playlist_id, song_id, position, weight) VALUES (?, ?, ?, 1.0)",
+        )
+        .bind(playlist_id)
+        .bind(song_id)
+        .bind(position)
+        .execute(&mut *tx)
+        .await?;
+        position += 1;
+    }
+
+    tx.commit().await?;
+    Ok(())
+}
+
+pub async fn get_playlist_cursor(
+    pool: &SqlitePool,
+    playlist_id: i64,
+) -> Result<PlaylistCursor, sqlx::Error> {
+    let row = sqlx::query(
+        "SELECT playlist_id, next_position, last_song_id, updated_at
+         FROM playlist_cursor WHERE playlist_id = ?",
+    )
+    .bind(playlist_id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(match row {
+        Some(r) => PlaylistCursor {
+            playlist_id: r.get("playlist_id"),
+            next_position: r.get::<i64, _>("next_position").max(0),
+            last_song_id: r.get("last_song_id"),
+            updated_at: r.get("updated_at"),
+        },
+        None => PlaylistCursor {
+            playlist_id,
+            next_position: 0,
+            last_song_id: None,
+            updated_at: None,
+        },
+    })
+}
+
+pub async fn save_playlist_cursor(
+    pool: &SqlitePool,
+    playlist_id: i64,
+    next_position: i64,
+    last_song_id: Option<i64>,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        r#"
+        INSERT INTO playlist_cursor (playlist_id, next_position, last_song_id, updated_at)
+        VALUES (?, ?, ?, strftime('%s','now'))
+        ON CONFLICT(playlist_id) DO UPDATE SET
+          next_position = excluded.next_position,
+          last_song_id = excluded.last_song_id,
+          updated_at = excluded.updated_at
+        "#,
+    )
+    .bind(playlist_id)
+    .bind(next_position.max(0))
+    .bind(last_song_id)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Resolve a playlist slot target (numeric id or case-insensitive name).
+async fn resolve_playlist_id(pool: &SqlitePool, target: &str) -> Result<Option<i64>, sqlx::Error> {
+    let target = target.trim();
+    if target.is_empty() {
+        return Ok(None);
+    }
+    let playlists = get_playlists(pool).await?;
+    if let Ok(id) = target.parse::<i64>() {
+        if playlists.iter().any(|p| p.id == Some(id)) {
+            return Ok(Some(id));
+        }
+    }
+    Ok(playlists
+        .iter()
+        .find(|p| p.name.eq_ignore_ascii_case(target))
+        .and_then(|p| p.id))
+}
+
 pub async fn get_clockwheel_config(pool: &SqlitePool) -> Result<ClockwheelConfig, sqlx::Error> {
     let row: Option<String> =
         sqlx::query_scalar("SELECT config_json FROM autodj_clockwheel_config WHERE id = 1")
@@ -343,9 +572,188 @@ pub async fn save_clockwheel_config(
     .execute(pool)
     .await?;
 
+    // Planned picks were made against the old slots and rules.
+    clear_ghost_queue();
+    Ok(())
+}
+
+// ── Clockwheel templates / hour grid ─────────────────────────────────────────
+
+pub async fn get_clockwheel_templates(
+    pool: &SqlitePool,
+) -> Result<Vec<ClockwheelTemplate>, sqlx::Error> {
+    let rows = sqlx::query("SELECT id, name, slots_json FROM clockwheel_templates ORDER BY name")
+        .fetch_all(pool)
+        .await?;
+
+    Ok(rows
+        .iter()
+        .map(|r| {
+            let mut slots =
+                serde_json::from_str::<Vec<ClockwheelSlot>>(&r.get::<String, _>("slots_json"))
+                    .unwrap_or_default();
+            normalize_slots(&mut slots);
+            ClockwheelTemplate {
+                id: r.get("id"),
+                name: r.get("name"),
+                slots,
+            }
+        })
+        .collect())
+}
+
+pub async fn upsert_clockwheel_template(
+    pool: &SqlitePool,
+    template: &ClockwheelTemplate,
+) -> Result<i64, sqlx::Error> {
+    let mut slots = template.slots.clone();
+    normalize_slots(&mut slots);
+    let json = serde_json::to_string(&slots).unwrap_or_else(|_| "[]".to_string());
+
+    if let Some(id) = template.id {
+        sqlx::query(
+            "UPDATE clockwheel_templates
+             SET name = ?, slots_json = ?, updated_at = strftime('%s','now')
+             WHERE id = ?",
+        )
+        .bind(template.name.trim())
+        .bind(json)
+        .bind(id)
+        .execute(pool)
+        .await?;
+        Ok(id)
+    } else {
+        let result =
+            sqlx::query("INSERT INTO clockwheel_templates (name, slots_json) VALUES (?, ?)")
+                .bind(template.name.trim())
+                .bind(json)
+                .execute(pool)
+                .await?;
+        Ok(result.last_insert_rowid())
+    }
+}
+
+pub async fn get_clockwheel_hour_grid(
+    pool: &SqlitePool,
+) -> Result<Vec<ClockwheelHourAssignment>, sqlx::Error> {
+    let rows = sqlx::query_as::<_, (i64, i64, Option<i64>)>(
+        "SELECT day, hour, template_id FROM clockwheel_hour_grid ORDER BY day, hour",
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|(day, hour, template_id)| ClockwheelHourAssignment {
+            day: day.clamp(0, 6) as u8,
+            hour: hour.clamp(0, 23) as u8,
+            template_id,
+        })
+        .collect())
+}
+
+/// Assign a template to one hour of the week; `None` reverts it to the global slots.
+pub async fn assign_clockwheel_hour(
+    pool: &SqlitePool,
+    assignment: ClockwheelHourAssignment,
+) -> Result<(), sqlx::Error> {
+    if assignment.day > 6 || assignment.hour > 23 {
+        return Err(sqlx::Error::Protocol(format!(
+            "Invalid clockwheel hour: day {} hour {}",
+            assignment.day, assignment.hour
+        )));
+    }
+    match assignment.template_id {
+        Some(template_id) => {
+            sqlx::query(
+                r#"
+                INSERT INTO clockwheel_hour_grid (day, hour, template_id)
+                VALUES (?, ?, ?)
+                ON CONFLICT(day, hour) DO UPDATE SET template_id = excluded.template_id
+                "#,
+            )
+            .bind(assignment.day as i64)
+            .bind(assignment.hour as i64)
+            .bind(template_id)
+            .execute(pool)
+            .await?;
+        }
+        None => {
+            sqlx::query("DELETE FROM clockwheel_hour_grid WHERE day = ? AND hour = ?")
+                .bind(assignment.day as i64)
+                .bind(assignment.hour as i64)
+                .execute(pool)
+                .await?;
+        }
+    }
     Ok(())
 }
 
+/// Template assigned to the station-local hour containing `now`, if any.
+async fn template_for_hour(
+    pool: &SqlitePool,
+    now: &chrono::DateTime<chrono::Local>,
+) -> Option<ClockwheelTemplate> {
+    let day = now.weekday().num_days_from_monday() as i64;
+    let template_id: Option<i64> = sqlx::query_scalar(
+        "SELECT template_id FROM clockwheel_hour_grid WHERE day = ? AND hour = ?",
+    )
+    .bind(day)
+    .bind(now.hour() as i64)
+    .fetch_optional(pool)
+    .await
+    .ok()
+    .flatten()
+    .flatten();
+    let template_id = template_id?;
+    get_clockwheel_templates(pool)
+        .await
+        .ok()?
+        .into_iter()
+        .find(|t| t.id == Some(template_id))
+}
+
+fn template_override_cell() -> &'static Mutex<Option<i64>> {
+    static TEMPLATE_OVERRIDE: OnceLock<Mutex<Option<i64>>> = OnceLock::new();
+    TEMPLATE_OVERRIDE.get_or_init(|| Mutex::new(None))
+}
+
+/// Use template `template_id` instead of the hour grid (while a show that
+/// sets one is on air). Songs already planned are re-planned.
+pub fn set_template_override(template_id: Option<i64>) {
+    *template_override_cell().lock().unwrap() = template_id;
+    clear_ghost_queue();
+}
+
+async fn active_template(pool: &SqlitePool) -> Option<ClockwheelTemplate> {
+    let override_id = *template_override_cell().lock().unwrap();
+    if let Some(id) = override_id {
+        let templates = get_clockwheel_templates(pool).await.unwrap_or_default();
+        match templates.into_iter().find(|t| t.id == Some(id)) {
+            Some(template) => return Some(template),
+            None => log::warn!("Show clockwheel template {id} not found; using the hour grid"),
+        }
+    }
+    template_for_hour(pool, &chrono::Local::now()).await
+}
+
+/// Global clockwheel config with its slots replaced by the show's template or
+/// the one assigned to the current hour. Also returns that template id, which
+/// scopes the cursor.
+async fn clockwheel_for_now(pool: &SqlitePool) -> (ClockwheelConfig, Option<i64>) {
+    let mut clockwheel = get_clockwheel_config(pool)
+        .await
+        .unwrap_or_default()
+        .normalized();
+    match active_template(pool).await {
+        Some(template) => {
+            clockwheel.slots = template.slots;
+            (clockwheel, template.id)
+        }
+        None => (clockwheel, None),
+    }
+}
+
 pub async fn get_song_directories(
     sam_pool: &MySqlPool,
     limit: u32,
@@ -372,18 +780,10 @@ pub async fn get_song_directories(
     Ok(dirs.into_iter().collect())
 }
 
-pub async fn apply_weight_delta_on_play(
-    local_pool: &SqlitePool,
-    sam_pool: &MySqlPool,
-    song_id: i64,
-) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+/// Weight change for a completed play (zero or negative).
+pub async fn on_play_weight_delta(local_pool: &SqlitePool) -> f64 {
     let cfg = get_clockwheel_config(local_pool).await.unwrap_or_default();
-    let delta = -cfg.on_play_reduce_weight_by.abs();
-    if delta.abs() < f64::EPSILON {
-        return Ok(());
-    }
-    update_song_weight_by_delta(sam_pool, song_id, delta).await?;
-    Ok(())
+    -cfg.on_play_reduce_weight_by.abs()
 }
 
 pub async fn apply_weight_delta_on_request(
@@ -391,8 +791,7 @@ pub async fn apply_weight_delta_on_request(
     sam_pool: &MySqlPool,
     song_id: i64,
 ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-    let cfg = get_clockwheel_config(local_pool).await.unwrap_or_default();
-    let delta = cfg.on_request_increase_weight_by.max(0.0);
+    let delta = on_request_weight_delta(local_pool).await;
     if delta.abs() < f64::EPSILON {
         return Ok(());
     }
@@ -400,7 +799,13 @@ pub async fn apply_weight_delta_on_request(
     Ok(())
 }
 
-async fn update_song_weight_by_delta(
+/// Weight added to a song each time it is requested (0 when off).
+pub async fn on_request_weight_delta(local_pool: &SqlitePool) -> f64 {
+    let cfg = get_clockwheel_config(local_pool).await.unwrap_or_default();
+    cfg.on_request_increase_weight_by.max(0.0)
+}
+
+pub(crate) async fn update_song_weight_by_delta(
     sam_pool: &MySqlPool,
     song_id: i64,
     delta: f64,
@@ -410,6 +815,7 @@ async fn update_song_weight_by_delta(
         .bind(song_id)
         .execute(sam_pool)
         .await?;
+    sam_cache::note_write();
     Ok(())
 }
 
@@ -440,6 +846,14 @@ struct CandidateInternal {
     weight: f64,
     count_played: i64,
     song_last_played_unix: i64,
+    /// 0-based order within the source playlist (playlist slots only).
+    playlist_position: Option<i64>,
+    /// SAM `bpm`, replaced by the analysed beat-grid BPM when flow rules need it
+    bpm: f32,
+    genre: String,
+    mood: String,
+    /// Album year, 0 when unknown
+    year: i32,
 }
 
 #[derive(Debug, Clone)]
@@ -451,14 +865,142 @@ struct HistoryRow {
     played_unix: i64,
 }
 
+/// What flow rules compare against: the song the pick will follow.
+#[derive(Debug, Clone)]
+struct FlowTrack {
+    bpm: f32,
+    genre: String,
+    mood: String,
+    year: i32,
+    category: Option<String>,
+}
+
+impl FlowTrack {
+    fn of(c: &CandidateInternal) -> Self {
+        Self {
+            bpm: c.bpm,
+            genre: c.genre.clone(),
+            mood: c.mood.clone(),
+            year: c.year,
+            category: c.category.clone(),
+        }
+    }
+
+    fn attribute(&self, attribute: FlowAttribute) -> String {
+        match attribute {
+            FlowAttribute::Category => self.category.as_deref().map(normalize_label),
+            FlowAttribute::Genre => Some(normalize_label(&self.genre)),
+            FlowAttribute::Mood => Some(normalize_label(&self.mood)),
+            FlowAttribute::Era => (self.year > 0).then(|| format!("{}s", self.year / 10 * 10)),
+        }
+        .unwrap_or_default()
+    }
+
+    fn is_slow(&self, max_bpm: f32, keywords: &[String]) -> bool {
+        if self.bpm > 0.0 && self.bpm <= max_bpm {
+            return true;
+        }
+        let genre = self.genre.to_lowercase();
+        let mood = self.mood.to_lowercase();
+        keywords
+            .iter()
+            .map(|k| k.trim().to_lowercase())
+            .any(|k| !k.is_empty() && (genre.contains(&k) || mood.contains(&k)))
+    }
+}
+
+/// Whether any enabled rule compares against the previous song.
+fn uses_previous(enabled_rules: &[RotationRuleRow]) -> bool {
+    fn check(rule: &RotationRule) -> bool {
+        match rule {
+            RotationRule::MaxBpmJump { .. }
+            | RotationRule::NoConsecutiveSlow { .. }
+            | RotationRule::Alternate { .. } => true,
+            RotationRule::Dayparted { rule, .. } => check(rule),
+            _ => false,
+        }
+    }
+    enabled_rules.iter().any(|row| {
+        serde_json::from_str::<RotationRule>(&row.config_json).is_ok_and(|rule| check(&rule))
+    })
+}
+
+/// The song a new pick will follow: the last planned `lookahead` song, else
+/// the tail of the SAM queue, else the latest play in `history`.
+async fn previous_track(
+    local_pool: &SqlitePool,
+    sam_pool: &MySqlPool,
+    lookahead: &[SongCandidate],
+    history: &[HistoryRow],
+) -> Option<FlowTrack> {
+    let song_id = match lookahead.last() {
+        Some(planned) => planned.song_id,
+        None => match crate::db::sam::get_queue(sam_pool).await {
+            Ok(queue) if !queue.is_empty() => queue.last()?.song_id,
+            _ => history.first()?.song_id,
+        },
+    };
+    let row =
+        sqlx::query("SELECT category, bpm, genre, mood, albumyear FROM songlist WHERE ID = ?")
+            .bind(song_id)
+            .fetch_optional(sam_pool)
+            .await
+            .ok()??;
+    let sam_bpm = row
+        .try_get::<i32, _>("bpm")
+        .map(|v| v as f32)
+        .or_else(|_| row.try_get::<f64, _>("bpm").map(|v| v as f32))
+        .unwrap_or(0.0);
+    let beatgrid_bpm = crate::db::local::get_beatgrid_bpms(local_pool, &[song_id])
+        .await
+        .ok()
+        .and_then(|bpms| bpms.get(&song_id).copied());
+    Some(FlowTrack {
+        bpm: beatgrid_bpm.unwrap_or(sam_bpm),
+        genre: row.try_get::<String, _>("genre").unwrap_or_default(),
+        mood: row.try_get::<String, _>("mood").unwrap_or_default(),
+        year: parse_album_year(&row.try_get::<String, _>("albumyear").unwrap_or_default()),
+        category: row.try_get::<Option<String>, _>("category").ok().flatten(),
+    })
+}
+
+fn uses_bpm(enabled_rules: &[RotationRuleRow]) -> bool {
+    fn check(rule: &RotationRule) -> bool {
+        match rule {
+            RotationRule::MaxBpmJump { .. } | RotationRule::NoConsecutiveSlow { .. } => true,
+            RotationRule::Dayparted { rule, .. } => check(rule),
+            _ => false,
+        }
+    }
+    enabled_rules.iter().any(|row| {
+        serde_json::from_str::<RotationRule>(&row.config_json).is_ok_and(|rule| check(&rule))
+    })
+}
+
+/// Prefer the analysed beat-grid BPM over SAM's hand-entered one.
+async fn apply_beatgrid_bpm(local_pool: &SqlitePool, candidates: &mut [CandidateInternal]) {
+    let ids: Vec<i64> = candidates.iter().map(|c| c.song_id).collect();
+    match crate::db::local::get_beatgrid_bpms(local_pool, &ids).await {
+        Ok(bpms) => {
+            for c in candidates.iter_mut() {
+                if let Some(bpm) = bpms.get(&c.song_id) {
+                    c.bpm = *bpm;
+                }
+            }
+        }
+        Err(e) => log::warn!("Beat-grid BPM lookup failed: {e}"),
+    }
+}
+
 /// Select the next track for AutoDJ from the SAM `songlist` table,
 /// applying enabled rules from both legacy rules and SAM-style clockwheel config.
 pub async fn select_next_track(
     local_pool: &SqlitePool,
     sam_pool: &MySqlPool,
     active_category: Option<&str>,
+    rng: &mut impl Rng,
 ) -> Result<Option<SongCandidate>, Box<dyn std::error::Error + Send + Sync>> {
-    select_next_track_with_exclusions(local_pool, sam_pool, active_category, None).await
+    select_next_track_with_exclusions(local_pool, sam_pool, active_category, None, rng).await
 }
 
 pub async fn select_next_track_with_exclusions(
@@ -466,14 +1008,33 @@ pub async fn select_next_track_with_exclusions(
     sam_pool: &MySqlPool,
     active_category: Option<&str>,
     excluded_song_ids: Option<&HashSet<i64>>,
+    rng: &mut impl Rng,
+) -> Result<Option<SongCandidate>, Box<dyn std::error::Error + Send + Sync>> {
+    select_with_lookahead(
+        local_pool,
+        sam_pool,
+        active_category,
+        excluded_song_ids,
+        &[],
+        rng,
+    )
+    .await
+}
+
+/// Selection with `lookahead` songs (planned but not yet played) treated as
+/// the most recent plays.
+async fn select_with_lookahead(
+    local_pool: &SqlitePool,
+    sam_pool: &MySqlPool,
+    active_category: Option<&str>,
+    excluded_song_ids: Option<&HashSet<i64>>,
+    lookahead: &[SongCandidate],
+    rng: &mut impl Rng,
 ) -> Result<Option<SongCandidate>, Box<dyn std::error::Error + Send + Sync>> {
     let rules = get_rotation_rules(local_pool).await?;
     let enabled_rules: Vec<RotationRuleRow> = rules.into_iter().filter(|r| r.enabled).collect();
 
-    let mut clockwheel = get_clockwheel_config(local_pool)
-        .await
-        .unwrap_or_default()
-        .normalized();
+    let (mut clockwheel, template_id) = clockwheel_for_now(local_pool).await;
     if let Some(category) = active_category {
         clockwheel.slots = vec![ClockwheelSlot {
             id: "active-category".to_string(),
@@ -487,57 +1048,103 @@ pub async fn select_next_track_with_exclusions(
         }];
     }
 
-    let history = load_history(sam_pool).await;
+    let mut history = load_history(sam_pool).await;
     let now = Utc::now();
+    remember_history(&history, &clockwheel.rules);
+    let previous = if uses_previous(&enabled_rules) {
+        previous_track(local_pool, sam_pool, lookahead, &history).await
+    } else {
+        None
+    };
+    for planned in lookahead {
+        history.insert(
+            0,
+            HistoryRow {
+                song_id: planned.song_id,
+                artist: planned.artist.clone(),
+                title: planned.title.clone(),
+                album: planned.album.clone().unwrap_or_default(),
+                played_unix: now.timestamp(),
+            },
+        );
+    }
+    let needs_bpm = uses_bpm(&enabled_rules);
+    let mut decision = RotationDecision {
+        at: now.timestamp_millis(),
+        template_id,
+        active_category: active_category.map(str::to_string),
+        excluded: excluded_song_ids.map_or(0, HashSet::len),
+        slots: Vec::new(),
+        picked: None,
+        fallback: false,
+    };
 
     let mut slots = clockwheel.slots.clone();
     if slots.is_empty() {
         slots.push(ClockwheelSlot::default());
     }
 
-    let start_cursor = load_clockwheel_cursor(local_pool).await.unwrap_or(0) % slots.len();
+    let start_cursor = load_clockwheel_cursor(local_pool, template_id)
+        .await
+        .unwrap_or(0)
+        % slots.len();
 
     for offset in 0..slots.len() {
         let idx = (start_cursor + offset) % slots.len();
         let slot = &slots[idx];
+        let mut trace = SlotTrace::new(slot);
         if !slot_is_active(slot, &now) {
+            decision.slots.push(trace.skipped("outside its hours"));
             continue;
         }
 
-        let mut candidates = fetch_candidates_for_slot(sam_pool, slot, 300).await?;
+        let mut candidates = fetch_candidates_for_slot(local_pool, sam_pool, slot, 300).await?;
+        trace.candidates = candidates.len();
         if candidates.is_empty() {
+            decision.slots.push(trace.skipped("no songs"));
             continue;
         }
+        remember_candidates(&candidates);
         if let Some(excluded) = excluded_song_ids {
             candidates.retain(|c| !excluded.contains(&c.song_id));
+            trace.excluded = trace.candidates - candidates.len();
             if candidates.is_empty() {
+                decision
+                    .slots
+                    .push(trace.skipped("every song already queued or excluded"));
                 continue;
             }
         }
 
-        if slot.enforce_rules && clockwheel.rules.enforce_playlist_rotation_rules {
-            apply_clockwheel_rules(
-                &mut candidates,
-                &history,
-                &clockwheel.rules,
-                now.timestamp(),
-            );
-        }
-
-        if candidates.is_empty() {
-            continue;
+        if needs_bpm {
+            apply_beatgrid_bpm(local_pool, &mut candidates).await;
         }
-
-        apply_legacy_rotation_rules(&mut candidates, &history, &enabled_rules, now.timestamp());
-
+        let enforce = slot.enforce_rules && clockwheel.rules.enforce_playlist_rotation_rules;
+        trace.filters = filter_candidates_traced(
+            &mut candidates,
+            &history,
+            previous.as_ref(),
+            enforce.then_some(&clockwheel.rules),
+            &enabled_rules,
+            now.timestamp(),
+        );
+        trace.eligible = candidates.len();
         if candidates.is_empty() {
+            decision
+                .slots
+                .push(trace.skipped("every song blocked by rules"));
             continue;
         }
 
+        let odds = PickOdds::of(&candidates);
         if let Some(chosen) =
-            choose_candidate(candidates, slot.selection_method, &history, now.timestamp())
+            choose_for_slot(local_pool, slot, candidates, &history, now.timestamp(), rng).await
         {
-            let _ = save_clockwheel_cursor(local_pool, (idx + 1) % slots.len()).await;
+            let _ = save_clockwheel_cursor(local_pool, template_id, (idx + 1) % slots.len()).await;
+            trace.outcome = "picked".to_string();
+            decision.slots.push(trace);
+            decision.picked = Some(PickTrace::new(&chosen, slot.selection_method, &odds));
+            finish_decision(&clockwheel, decision);
             return Ok(Some(SongCandidate {
                 song_id: chosen.song_id,
                 title: chosen.title,
@@ -549,33 +1156,72 @@ pub async fn select_next_track_with_exclusions(
                 score: chosen.weight,
             }));
         }
+        decision.slots.push(trace.skipped("no playable song"));
     }
 
     // If all slots are currently inactive due time windows, fallback to a generic
     // weighted pick so AutoDJ doesn't stall.
+    decision.fallback = true;
     let fallback_slot = ClockwheelSlot::default();
-    let mut fallback = fetch_candidates_for_slot(sam_pool, &fallback_slot, 300).await?;
+    let mut trace = SlotTrace::new(&fallback_slot);
+    let mut fallback = fetch_candidates_for_slot(local_pool, sam_pool, &fallback_slot, 300).await?;
+    trace.candidates = fallback.len();
     if fallback.is_empty() {
+        decision.slots.push(trace.skipped("no songs"));
+        finish_decision(&clockwheel, decision);
         return Ok(None);
     }
+    remember_candidates(&fallback);
     if let Some(excluded) = excluded_song_ids {
         fallback.retain(|c| !excluded.contains(&c.song_id));
+        trace.excluded = trace.candidates - fallback.len();
         if fallback.is_empty() {
+            decision
+                .slots
+                .push(trace.skipped("every song already queued or excluded"));
+            finish_decision(&clockwheel, decision);
             return Ok(None);
         }
     }
-    if clockwheel.rules.enforce_playlist_rotation_rules {
-        apply_clockwheel_rules(&mut fallback, &history, &clockwheel.rules, now.timestamp());
+    if needs_bpm {
+        apply_beatgrid_bpm(local_pool, &mut fallback).await;
     }
-    apply_legacy_rotation_rules(&mut fallback, &history, &enabled_rules, now.timestamp());
+    trace.filters = filter_candidates_traced(
+        &mut fallback,
+        &history,
+        previous.as_ref(),
+        clockwheel
+            .rules
+            .enforce_playlist_rotation_rules
+            .then_some(&clockwheel.rules),
+        &enabled_rules,
+        now.timestamp(),
+    );
+    trace.eligible = fallback.len();
 
-    Ok(choose_candidate(
+    let odds = PickOdds::of(&fallback);
+    let chosen = choose_candidate(
         fallback,
         ClockwheelSelectionMethod::Weighted,
         &history,
         now.timestamp(),
-    )
-    .map(|chosen| SongCandidate {
+        rng,
+    );
+    match &chosen {
+        Some(chosen) => {
+            trace.outcome = "picked".to_string();
+            decision.picked = Some(PickTrace::new(
+                chosen,
+                ClockwheelSelectionMethod::Weighted,
+                &odds,
+            ));
+        }
+        None => trace.outcome = "every song blocked by rules".to_string(),
+    }
+    decision.slots.push(trace);
+    finish_decision(&clockwheel, decision);
+
+    Ok(chosen.map(|chosen| SongCandidate {
         song_id: chosen.song_id,
         title: chosen.title,
         artist: chosen.artist,
@@ -591,11 +1237,9 @@ pub async fn select_next_track_for_slot(
     local_pool: &SqlitePool,
     sam_pool: &MySqlPool,
     slot_id: &str,
+    rng: &mut impl Rng,
 ) -> Result<Option<SongCandidate>, Box<dyn std::error::Error + Send + Sync>> {
-    let clockwheel = get_clockwheel_config(local_pool)
-        .await
-        .unwrap_or_default()
-        .normalized();
+    let (clockwheel, _) = clockwheel_for_now(local_pool).await;
     let Some(slot) = clockwheel.slots.iter().find(|s| s.id == slot_id).cloned() else {
         return Ok(None);
     };
@@ -606,7 +1250,7 @@ pub async fn select_next_track_for_slot(
         return Ok(None);
     }
 
-    let mut candidates = fetch_candidates_for_slot(sam_pool, &slot, 300).await?;
+    let mut candidates = fetch_candidates_for_slot(local_pool, sam_pool, &slot, 300).await?;
     if candidates.is_empty() {
         return Ok(None);
     }
@@ -625,25 +1269,44 @@ pub async fn select_next_track_for_slot(
 
     let rules = get_rotation_rules(local_pool).await?;
     let enabled_rules: Vec<RotationRuleRow> = rules.into_iter().filter(|r| r.enabled).collect();
-    apply_legacy_rotation_rules(&mut candidates, &history, &enabled_rules, now.timestamp());
+    if uses_bpm(&enabled_rules) {
+        apply_beatgrid_bpm(local_pool, &mut candidates).await;
+    }
+    let previous = if uses_previous(&enabled_rules) {
+        previous_track(local_pool, sam_pool, &[], &history).await
+    } else {
+        None
+    };
+    apply_legacy_rotation_rules(
+        &mut candidates,
+        &history,
+        previous.as_ref(),
+        &enabled_rules,
+        now.timestamp(),
+    );
     if candidates.is_empty() {
         return Ok(None);
     }
 
-    Ok(
-        choose_candidate(candidates, slot.selection_method, &history, now.timestamp()).map(
-            |chosen| SongCandidate {
-                song_id: chosen.song_id,
-                title: chosen.title,
-                artist: chosen.artist,
-                album: Some(chosen.album),
-                category: chosen.category,
-                duration: chosen.duration,
-                file_path: chosen.file_path,
-                score: chosen.weight,
-            },
-        ),
+    let chosen = choose_for_slot(
+        local_pool,
+        &slot,
+        candidates,
+        &history,
+        now.timestamp(),
+        rng,
     )
+    .await;
+    Ok(chosen.map(|chosen| SongCandidate {
+        song_id: chosen.song_id,
+        title: chosen.title,
+        artist: chosen.artist,
+        album: Some(chosen.album),
+        category: chosen.category,
+        duration: chosen.duration,
+        file_path: chosen.file_path,
+        score: chosen.weight,
+    }))
 }
 
 fn slot_is_active(slot: &ClockwheelSlot, now: &chrono::DateTime<Utc>) -> bool {
@@ -655,50 +1318,143 @@ fn slot_is_active(slot: &ClockwheelSlot, now: &chrono::DateTime<Utc>) -> bool {
     }
 
     match (slot.start_hour, slot.end_hour) {
-        (Some(start), Some(end)) => {
-            let h = now.hour() as u8;
-            if start == end {
-                true
-            } else if start < end {
-                h >= start && h < end
-            } else {
-                h >= start || h < end
-            }
-        }
+        (Some(start), Some(end)) => hour_in_window(now.hour() as u8, start, end),
         _ => true,
     }
 }
 
-async fn load_clockwheel_cursor(pool: &SqlitePool) -> Result<usize, sqlx::Error> {
-    let row: Option<i64> =
-        sqlx::query_scalar("SELECT next_index FROM autodj_clockwheel_state WHERE id = 1")
+/// `start..end` in hours, wrapping past midnight when `end < start`.
+fn hour_in_window(h: u8, start: u8, end: u8) -> bool {
+    if start == end {
+        true
+    } else if start < end {
+        h >= start && h < end
+    } else {
+        h >= start || h < end
+    }
+}
+
+/// The cursor restarts at the first slot whenever the hour's template changes.
+async fn load_clockwheel_cursor(
+    pool: &SqlitePool,
+    template_id: Option<i64>,
+) -> Result<usize, sqlx::Error> {
+    let row: Option<(i64, Option<i64>)> =
+        sqlx::query_as("SELECT next_index, template_id FROM autodj_clockwheel_state WHERE id = 1")
             .fetch_optional(pool)
             .await?;
-    Ok(row.unwrap_or(0).max(0) as usize)
+    Ok(match row {
+        Some((next_index, saved_template)) if saved_template == template_id => {
+            next_index.max(0) as usize
+        }
+        _ => 0,
+    })
 }
 
-async fn save_clockwheel_cursor(pool: &SqlitePool, next_index: usize) -> Result<(), sqlx::Error> {
+async fn save_clockwheel_cursor(
+    pool: &SqlitePool,
+    template_id: Option<i64>,
+    next_index: usize,
+) -> Result<(), sqlx::Error> {
     sqlx::query(
         r#"
-        INSERT INTO autodj_clockwheel_state (id, next_index, updated_at)
-        VALUES (1, ?, strftime('%s','now'))
+        INSERT INTO autodj_clockwheel_state (id, next_index, template_id, updated_at)
+        VALUES (1, ?, ?, strftime('%s','now'))
         ON CONFLICT(id) DO UPDATE SET
           next_index = excluded.next_index,
+          template_id = excluded.template_id,
           updated_at = excluded.updated_at
         "#,
     )
     .bind(next_index as i64)
+    .bind(template_id)
     .execute(pool)
     .await?;
     Ok(())
 }
 
+// SAM reads made on every selection, cached (see `db::sam_cache`).
+static SLOT_CANDIDATES: TtlCache<String, Vec<CandidateInternal>> =
+    TtlCache::new(sam_cache::DATA_TTL, true);
+static PLAYLIST_SONGS: TtlCache<u64, Vec<crate::db::sam::SamSong>> =
+    TtlCache::new(sam_cache::DATA_TTL, true);
+static CATEGORIES: TtlCache<(), Vec<crate::db::sam::SamCategory>> =
+    TtlCache::new(sam_cache::CATEGORY_TTL, true);
+static HISTORY: TtlCache<(), Vec<HistoryRow>> = TtlCache::new(sam_cache::DATA_TTL, true);
+
 async fn fetch_candidates_for_slot(
+    local_pool: &SqlitePool,
+    sam_pool: &MySqlPool,
+    slot: &ClockwheelSlot,
+    limit: u32,
+) -> Result<Vec<CandidateInternal>, sqlx::Error> {
+    // Playlist slots read the local playlist on each call; only their SAM
+    // lookup is cached.
+    if slot.kind == ClockwheelSlotKind::Playlist {
+        return query_candidates_for_slot(local_pool, sam_pool, slot, limit).await;
+    }
+    let key = format!("{:?}|{}|{limit}", slot.kind, slot.target.trim());
+    SLOT_CANDIDATES
+        .get_or_fetch(
+            key,
+            query_candidates_for_slot(local_pool, sam_pool, slot, limit),
+        )
+        .await
+}
+
+async fn query_candidates_for_slot(
+    local_pool: &SqlitePool,
     sam_pool: &MySqlPool,
     slot: &ClockwheelSlot,
     limit: u32,
 ) -> Result<Vec<CandidateInternal>, sqlx::Error> {
     let rows = match slot.kind {
+        ClockwheelSlotKind::Playlist => {
+            // Playlist slots keep every entry (no `limit`) so ordered playback
+            // can walk the whole list.
+            let Some(playlist_id) = resolve_playlist_id(local_pool, &slot.target).await? else {
+                return Ok(Vec::new());
+            };
+            let entries = get_playlist_songs(local_pool, playlist_id).await?;
+            let song_ids: Vec<i64> = entries.iter().map(|e| e.song_id).collect();
+            let key = {
+                use std::hash::{Hash, Hasher};
+                let mut hasher = std::collections::hash_map::DefaultHasher::new();
+                song_ids.hash(&mut hasher);
+                hasher.finish()
+            };
+            let songs: HashMap<i64, crate::db::sam::SamSong> = PLAYLIST_SONGS
+                .get_or_fetch(key, crate::db::sam::get_songs_by_ids(sam_pool, &song_ids))
+                .await?
+                .into_iter()
+                .map(|song| (song.id, song))
+                .collect();
+
+            return Ok(entries
+                .iter()
+                .enumerate()
+                .filter_map(|(idx, entry)| {
+                    let song = songs.get(&entry.song_id)?;
+                    Some(CandidateInternal {
+                        song_id: song.id,
+                        title: song.title.clone(),
+                        artist: song.artist.clone(),
+                        album: song.album.clone(),
+                        category: None,
+                        duration: song.duration as i64,
+                        file_path: song.filename.clone(),
+                        weight: entry.weight,
+                        count_played: song.count_played as i64,
+                        song_last_played_unix: parse_sam_datetime_unix(song.date_played.as_deref()),
+                        playlist_position: Some(idx as i64),
+                        bpm: song.bpm as f32,
+                        genre: song.genre.clone(),
+                        mood: song.mood.clone(),
+                        year: parse_album_year(&song.albumyear),
+                    })
+                })
+                .collect());
+        }
         ClockwheelSlotKind::Category => {
             let target = slot.target.trim();
             if target.is_empty() {
@@ -712,6 +1468,8 @@ async fn fetch_candidates_for_slot(
                               filename,
                               weight,
                               count_played,
+                              bpm,
+                              genre,
                               UNIX_TIMESTAMP(date_played) as song_last_played_unix
                        FROM songlist
                        LIMIT ?"#,
@@ -721,7 +1479,9 @@ async fn fetch_candidates_for_slot(
                 .await?
             } else {
                 // Primary path: resolve SAM categories and read songs through `categorylist`.
-                let categories = crate::db::sam::get_categories(sam_pool).await?;
+                let categories = CATEGORIES
+                    .get_or_fetch((), crate::db::sam::get_categories(sam_pool))
+                    .await?;
                 let target_lc = target.to_lowercase();
                 let target_norm = normalize_label(target);
 
@@ -752,33 +1512,33 @@ async fn fetch_candidates_for_slot(
 
                 let mut out: Vec<CandidateInternal> = Vec::new();
                 let mut seen_song_ids = HashSet::new();
-                for (cat_id, cat_name) in &matched {
-                    let songs =
-                        crate::db::sam::get_songs_in_category(sam_pool, *cat_id, limit * 2, 0)
-                            .await
-                            .unwrap_or_default();
-                    for song in songs {
-                        if !seen_song_ids.insert(song.id) {
-                            continue;
-                        }
-                        out.push(CandidateInternal {
-                            song_id: song.id,
-                            title: song.title,
-                            artist: song.artist,
-                            album: song.album,
-                            category: Some(cat_name.clone()),
-                            duration: song.duration as i64,
-                            file_path: song.filename,
-                            weight: song.weight,
-                            count_played: song.count_played as i64,
-                            song_last_played_unix: parse_sam_datetime_unix(
-                                song.date_played.as_deref(),
-                            ),
-                        });
-                        if out.len() >= limit as usize {
-                            break;
-                        }
+                let cat_ids: Vec<i64> = matched.iter().map(|(id, _)| *id).collect();
+                let cat_names: HashMap<i64, &String> =
+                    matched.iter().map(|(id, name)| (*id, name)).collect();
+                let songs = crate::db::sam::get_songs_in_categories(sam_pool, &cat_ids, limit * 2)
+                    .await
+                    .unwrap_or_default();
+                for (cat_id, song) in songs {
+                    if !seen_song_ids.insert(song.id) {
+                        continue;
                     }
+                    out.push(CandidateInternal {
+                        song_id: song.id,
+                        title: song.title,
+                        artist: song.artist,
+                        album: song.album,
+                        category: cat_names.get(&cat_id).map(|name| name.to_string()),
+                        duration: song.duration as i64,
+                        file_path: song.filename,
+                        weight: song.weight,
+                        count_played: song.count_played as i64,
+                        song_last_played_unix: parse_sam_datetime_unix(song.date_played.as_deref()),
+                        playlist_position: None,
+                        bpm: song.bpm as f32,
+                        year: parse_album_year(&song.albumyear),
+                        genre: song.genre,
+                        mood: song.mood,
+                    });
                     if out.len() >= limit as usize {
                         break;
                     }
@@ -796,6 +1556,8 @@ async fn fetch_candidates_for_slot(
                                   filename,
                                   weight,
                                   count_played,
+                                  bpm,
+                                  genre,
                                   UNIX_TIMESTAMP(date_played) as song_last_played_unix
                            FROM songlist
                            WHERE category LIKE ?
@@ -826,6 +1588,8 @@ async fn fetch_candidates_for_slot(
                           filename,
                           weight,
                           count_played,
+                          bpm,
+                          genre,
                           UNIX_TIMESTAMP(date_played) as song_last_played_unix
                    FROM songlist
                    WHERE (filename LIKE ? OR REPLACE(filename, '\\', '/') LIKE ?)
@@ -850,6 +1614,8 @@ async fn fetch_candidates_for_slot(
                           filename,
                           weight,
                           count_played,
+                          bpm,
+                          genre,
                           UNIX_TIMESTAMP(date_played) as song_last_played_unix
                    FROM songlist
                    LIMIT ?"#,
@@ -883,11 +1649,27 @@ async fn fetch_candidates_for_slot(
                 .ok()
                 .flatten()
                 .unwrap_or(0),
+            playlist_position: None,
+            bpm: r
+                .try_get::<i32, _>("bpm")
+                .map(|v| v as f32)
+                .or_else(|_| r.try_get::<f64, _>("bpm").map(|v| v as f32))
+                .unwrap_or(0.0),
+            genre: r.try_get::<String, _>("genre").unwrap_or_default(),
+            mood: String::new(),
+            year: 0,
         })
         .collect())
 }
 
 async fn load_history(sam_pool: &MySqlPool) -> Vec<HistoryRow> {
+    HISTORY
+        .get_or_fetch((), query_history(sam_pool))
+        .await
+        .unwrap_or_default()
+}
+
+async fn query_history(sam_pool: &MySqlPool) -> Result<Vec<HistoryRow>, sqlx::Error> {
     let rows = sqlx::query(
         r#"SELECT songID,
                   artist,
@@ -899,10 +1681,10 @@ async fn load_history(sam_pool: &MySqlPool) -> Vec<HistoryRow> {
            LIMIT 600"#,
     )
     .fetch_all(sam_pool)
-    .await
-    .unwrap_or_default();
+    .await?;
 
-    rows.into_iter()
+    Ok(rows
+        .into_iter()
         .map(|r| HistoryRow {
             song_id: r
                 .try_get::<i64, _>("songID")
@@ -917,7 +1699,7 @@ async fn load_history(sam_pool: &MySqlPool) -> Vec<HistoryRow> {
                 .flatten()
                 .unwrap_or(0),
         })
-        .collect()
+        .collect())
 }
 
 fn apply_clockwheel_rules(
@@ -926,118 +1708,271 @@ fn apply_clockwheel_rules(
     rules: &ClockwheelRules,
     now_unix: i64,
 ) {
-    candidates.retain(|c| {
-        if rules.no_same_track_minutes > 0 {
-            let cutoff = now_unix - (rules.no_same_track_minutes as i64 * 60);
-            if history
-                .iter()
-                .any(|h| h.song_id == c.song_id && h.played_unix >= cutoff)
-            {
-                return false;
-            }
-        }
-
-        if rules.no_same_artist_minutes > 0 && !c.artist.trim().is_empty() {
-            let cutoff = now_unix - (rules.no_same_artist_minutes as i64 * 60);
-            if history.iter().any(|h| {
-                !h.artist.is_empty()
-                    && h.artist.eq_ignore_ascii_case(&c.artist)
-                    && h.played_unix >= cutoff
-            }) {
-                return false;
-            }
-        }
-
-        if rules.no_same_album_minutes > 0 && !c.album.trim().is_empty() {
-            let cutoff = now_unix - (rules.no_same_album_minutes as i64 * 60);
-            if history.iter().any(|h| {
-                !h.album.is_empty()
-                    && h.album.eq_ignore_ascii_case(&c.album)
-                    && h.played_unix >= cutoff
-            }) {
-                return false;
-            }
-        }
+    candidates.retain(|c| clockwheel_block_reason(c, history, rules, now_unix).is_none());
+}
 
-        if rules.no_same_title_minutes > 0 && !c.title.trim().is_empty() {
-            let cutoff = now_unix - (rules.no_same_title_minutes as i64 * 60);
-            if history.iter().any(|h| {
-                !h.title.is_empty()
-                    && h.title.eq_ignore_ascii_case(&c.title)
-                    && h.played_unix >= cutoff
-            }) {
-                return false;
-            }
-        }
+/// The clockwheel separation rule that keeps `c` out, if any.
+fn clockwheel_block_reason(
+    c: &CandidateInternal,
+    history: &[HistoryRow],
+    rules: &ClockwheelRules,
+    now_unix: i64,
+) -> Option<&'static str> {
+    let recent = |minutes: u32, same: &dyn Fn(&HistoryRow) -> bool| {
+        let cutoff = now_unix - (minutes as i64 * 60);
+        history.iter().any(|h| h.played_unix >= cutoff && same(h))
+    };
 
-        true
-    });
+    if rules.no_same_track_minutes > 0
+        && recent(rules.no_same_track_minutes, &|h| {
+            same_recording(h.song_id, c.song_id)
+        })
+    {
+        return Some("no_same_track_minutes");
+    }
+    if rules.no_same_artist_minutes > 0
+        && !c.artist.trim().is_empty()
+        && recent(rules.no_same_artist_minutes, &|h| {
+            !h.artist.is_empty() && h.artist.eq_ignore_ascii_case(&c.artist)
+        })
+    {
+        return Some("no_same_artist_minutes");
+    }
+    if rules.no_same_album_minutes > 0
+        && !c.album.trim().is_empty()
+        && recent(rules.no_same_album_minutes, &|h| {
+            !h.album.is_empty() && h.album.eq_ignore_ascii_case(&c.album)
+        })
+    {
+        return Some("no_same_album_minutes");
+    }
+    if rules.no_same_title_minutes > 0
+        && !c.title.trim().is_empty()
+        && recent(rules.no_same_title_minutes, &|h| {
+            !h.title.is_empty() && h.title.eq_ignore_ascii_case(&c.title)
+        })
+    {
+        return Some("no_same_title_minutes");
+    }
+    None
 }
 
 fn apply_legacy_rotation_rules(
     candidates: &mut Vec<CandidateInternal>,
     history: &[HistoryRow],
+    previous: Option<&FlowTrack>,
     enabled_rules: &[RotationRuleRow],
     now_unix: i64,
 ) {
     for rule_row in enabled_rules {
         let rule: Result<RotationRule, _> = serde_json::from_str(&rule_row.config_json);
         let Ok(rule) = rule else { continue };
+        candidates.retain(|c| !legacy_rule_blocks(&rule, c, history, previous, now_unix));
+    }
+}
 
-        candidates.retain(|c| match &rule {
-            RotationRule::ArtistSeparation { min_songs } => {
-                let recent_artists: Vec<&str> = history
-                    .iter()
-                    .take(*min_songs as usize)
-                    .map(|h| h.artist.as_str())
-                    .collect();
-                !recent_artists
-                    .iter()
-                    .any(|a| !a.is_empty() && a.eq_ignore_ascii_case(&c.artist))
-            }
-            RotationRule::ArtistSeparationTime { min_minutes } => {
-                let cutoff = now_unix - (*min_minutes as i64 * 60);
-                !history
-                    .iter()
-                    .any(|h| h.artist.eq_ignore_ascii_case(&c.artist) && h.played_unix > cutoff)
-            }
-            RotationRule::SongSeparation { min_songs } => !history
+fn legacy_rule_blocks(
+    rule: &RotationRule,
+    c: &CandidateInternal,
+    history: &[HistoryRow],
+    previous: Option<&FlowTrack>,
+    now_unix: i64,
+) -> bool {
+    match rule {
+        RotationRule::ArtistSeparation { min_songs } => history
+            .iter()
+            .take(*min_songs as usize)
+            .any(|h| !h.artist.is_empty() && h.artist.eq_ignore_ascii_case(&c.artist)),
+        RotationRule::ArtistSeparationTime { min_minutes } => {
+            let cutoff = now_unix - (*min_minutes as i64 * 60);
+            history
                 .iter()
-                .take(*min_songs as usize)
-                .any(|h| h.song_id == c.song_id),
-            RotationRule::SongSeparationTime { min_minutes } => {
-                let cutoff = now_unix - (*min_minutes as i64 * 60);
-                !history
-                    .iter()
-                    .any(|h| h.song_id == c.song_id && h.played_unix > cutoff)
-            }
-            RotationRule::AlbumSeparation { min_songs } => {
-                if c.album.is_empty() {
-                    return true;
-                }
-                !history
+                .any(|h| h.artist.eq_ignore_ascii_case(&c.artist) && h.played_unix > cutoff)
+        }
+        RotationRule::SongSeparation { min_songs } => history
+            .iter()
+            .take(*min_songs as usize)
+            .any(|h| same_recording(h.song_id, c.song_id)),
+        RotationRule::SongSeparationTime { min_minutes } => {
+            let cutoff = now_unix - (*min_minutes as i64 * 60);
+            history
+                .iter()
+                .any(|h| same_recording(h.song_id, c.song_id) && h.played_unix > cutoff)
+        }
+        RotationRule::AlbumSeparation { min_songs } => {
+            !c.album.is_empty()
+                && history
                     .iter()
                     .take(*min_songs as usize)
                     .any(|h| !h.album.is_empty() && h.album.eq_ignore_ascii_case(&c.album))
+        }
+        RotationRule::MaxPlaysPerHour {
+            song_id,
+            max,
+            window_hours,
+        } => {
+            if c.song_id != *song_id {
+                return false;
             }
-            RotationRule::MaxPlaysPerHour {
-                song_id,
-                max,
-                window_hours,
-            } => {
-                if c.song_id != *song_id {
-                    return true;
+            let cutoff = now_unix - (*window_hours as i64 * 3600);
+            let plays = history
+                .iter()
+                .filter(|h| h.song_id == c.song_id && h.played_unix > cutoff)
+                .count() as u32;
+            plays >= *max
+        }
+        RotationRule::Dayparted { daypart, rule } => {
+            daypart.contains(now_unix) && legacy_rule_blocks(rule, c, history, previous, now_unix)
+        }
+        RotationRule::CategoryBan {
+            categories,
+            daypart,
+        } => {
+            let Some(category) = c.category.as_deref().map(normalize_label) else {
+                return false;
+            };
+            categories.iter().any(|b| normalize_label(b) == category) && daypart.contains(now_unix)
+        }
+        RotationRule::MaxBpmJump { max_change } => previous
+            .is_some_and(|p| p.bpm > 0.0 && c.bpm > 0.0 && (c.bpm - p.bpm).abs() > *max_change),
+        RotationRule::NoConsecutiveSlow { max_bpm, keywords } => previous.is_some_and(|p| {
+            p.is_slow(*max_bpm, keywords) && FlowTrack::of(c).is_slow(*max_bpm, keywords)
+        }),
+        RotationRule::Alternate { attribute, values } => previous.is_some_and(|p| {
+            let current = FlowTrack::of(c).attribute(*attribute);
+            !current.is_empty()
+                && current == p.attribute(*attribute)
+                && (values.is_empty() || values.iter().any(|v| normalize_label(v) == current))
+        }),
+        RotationRule::CategoryRotation { .. } => false,
+    }
+}
+
+/// What one rule removed from a slot's candidates.
+#[derive(Debug, Clone, Serialize)]
+pub struct FilterStep {
+    /// Clockwheel rule field (e.g. `no_same_artist_minutes`) or rotation rule name
+    pub rule: String,
+    pub removed: usize,
+    /// A few of the removed songs, as "Artist – Title"
+    pub examples: Vec<String>,
+}
+
+const FILTER_EXAMPLES: usize = 3;
+
+/// Clockwheel rules (when given) then the enabled rotation rules, as
+/// `apply_clockwheel_rules` + `apply_legacy_rotation_rules`, recording what
+/// each rule removed.
+fn filter_candidates_traced(
+    candidates: &mut Vec<CandidateInternal>,
+    history: &[HistoryRow],
+    previous: Option<&FlowTrack>,
+    clockwheel_rules: Option<&ClockwheelRules>,
+    enabled_rules: &[RotationRuleRow],
+    now_unix: i64,
+) -> Vec<FilterStep> {
+    let mut steps: Vec<FilterStep> = Vec::new();
+    let mut record = |rule: &str, c: &CandidateInternal| {
+        let step = match steps.iter_mut().position(|s| s.rule == rule) {
+            Some(i) => &mut steps[i],
+            None => {
+                steps.push(FilterStep {
+                    rule: rule.to_string(),
+                    removed: 0,
+                    examples: Vec::new(),
+                });
+                steps.last_mut().unwrap()
+            }
+        };
+        step.removed += 1;
+        if step.examples.len() < FILTER_EXAMPLES {
+            step.examples.push(format!("{} – {}", c.artist, c.title));
+        }
+    };
+
+    if let Some(rules) = clockwheel_rules {
+        candidates.retain(
+            |c| match clockwheel_block_reason(c, history, rules, now_unix) {
+                Some(reason) => {
+                    record(reason, c);
+                    false
                 }
-                let cutoff = now_unix - (*window_hours as i64 * 3600);
-                let plays = history
-                    .iter()
-                    .filter(|h| h.song_id == c.song_id && h.played_unix > cutoff)
-                    .count() as u32;
-                plays < *max
+                None => true,
+            },
+        );
+    }
+    for rule_row in enabled_rules {
+        let Ok(rule) = serde_json::from_str::<RotationRule>(&rule_row.config_json) else {
+            continue;
+        };
+        candidates.retain(|c| {
+            if legacy_rule_blocks(&rule, c, history, previous, now_unix) {
+                record(&rule_row.name, c);
+                false
+            } else {
+                true
             }
-            _ => true,
         });
     }
+    steps
+}
+
+/// Pick a candidate for `slot`. Ordered playlist slots consult and advance the
+/// persisted cursor; everything else goes through `choose_candidate`.
+async fn choose_for_slot(
+    local_pool: &SqlitePool,
+    slot: &ClockwheelSlot,
+    candidates: Vec<CandidateInternal>,
+    history: &[HistoryRow],
+    now_unix: i64,
+    rng: &mut impl Rng,
+) -> Option<CandidateInternal> {
+    if slot.kind == ClockwheelSlotKind::Playlist
+        && slot.selection_method == ClockwheelSelectionMethod::PlaylistOrder
+    {
+        if let Ok(Some(playlist_id)) = resolve_playlist_id(local_pool, &slot.target).await {
+            let cursor = get_playlist_cursor(local_pool, playlist_id)
+                .await
+                .map(|c| c.next_position)
+                .unwrap_or(0);
+            let chosen = choose_in_playlist_order(candidates, cursor)?;
+            let position = chosen.playlist_position.unwrap_or(0);
+            if position != cursor {
+                log::info!(
+                    "Playlist {} order skipped from position {} to {} (song_id={})",
+                    playlist_id,
+                    cursor,
+                    position,
+                    chosen.song_id
+                );
+            }
+            if let Err(e) =
+                save_playlist_cursor(local_pool, playlist_id, position + 1, Some(chosen.song_id))
+                    .await
+            {
+                log::warn!("Failed to persist playlist {} cursor: {}", playlist_id, e);
+            }
+            return Some(chosen);
+        }
+    }
+
+    choose_candidate(candidates, slot.selection_method, history, now_unix, rng)
+}
+
+/// First candidate at or after `cursor` in playlist order, wrapping to the top
+/// once the cursor has run past the end. Entries removed by rules or
+/// exclusions are stepped over, which is how blocked songs get skipped.
+fn choose_in_playlist_order(
+    mut candidates: Vec<CandidateInternal>,
+    cursor: i64,
+) -> Option<CandidateInternal> {
+    let pick = candidates
+        .iter()
+        .enumerate()
+        .filter_map(|(i, c)| c.playlist_position.map(|p| (i, p)))
+        .min_by_key(|(_, p)| (*p < cursor, *p))
+        .map(|(i, _)| i)?;
+    Some(candidates.swap_remove(pick))
 }
 
 fn choose_candidate(
@@ -1045,6 +1980,7 @@ fn choose_candidate(
     method: ClockwheelSelectionMethod,
     history: &[HistoryRow],
     now_unix: i64,
+    rng: &mut impl Rng,
 ) -> Option<CandidateInternal> {
     if candidates.is_empty() {
         return None;
@@ -1061,27 +1997,13 @@ fn choose_candidate(
         }
     }
 
-    let seed = pseudo_random_u64();
-
     let pick = match method {
         ClockwheelSelectionMethod::Weighted => {
-            let total: f64 = candidates
-                .iter()
-                .map(|c| c.weight.max(0.01))
-                .sum::<f64>()
-                .max(0.01);
-            let mut target = (seed as f64 / u64::MAX as f64) * total;
-            let mut chosen = 0usize;
-            for (i, c) in candidates.iter().enumerate() {
-                let w = c.weight.max(0.01);
-                if target <= w {
-                    chosen = i;
-                    break;
-                }
-                target -= w;
-                chosen = i;
+            // Floor keeps zero-weight songs reachable and the index valid.
+            match WeightedIndex::new(candidates.iter().map(|c| c.weight.max(0.01))) {
+                Ok(index) => index.sample(rng),
+                Err(_) => rng.gen_range(0..candidates.len()),
             }
-            chosen
         }
         ClockwheelSelectionMethod::Priority => candidates
             .iter()
@@ -1094,7 +2016,7 @@ fn choose_candidate(
             })
             .map(|(i, _)| i)
             .unwrap_or(0),
-        ClockwheelSelectionMethod::Random => (seed as usize) % candidates.len(),
+        ClockwheelSelectionMethod::Random => rng.gen_range(0..candidates.len()),
         ClockwheelSelectionMethod::MostRecentlyPlayedSong => candidates
             .iter()
             .enumerate()
@@ -1180,7 +2102,7 @@ fn normalize_label(input: &str) -> String {
         .collect()
 }
 
-fn parse_sam_datetime_unix(value: Option<&str>) -> i64 {
+pub(crate) fn parse_sam_datetime_unix(value: Option<&str>) -> i64 {
     let Some(raw) = value.map(str::trim).filter(|s| !s.is_empty()) else {
         return 0;
     };
@@ -1189,10 +2111,1087 @@ fn parse_sam_datetime_unix(value: Option<&str>) -> i64 {
         .unwrap_or(0)
 }
 
-fn pseudo_random_u64() -> u64 {
-    let nanos = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
+/// Leading four-digit year of SAM's free-text `albumyear`, or 0.
+fn parse_album_year(raw: &str) -> i32 {
+    let raw = raw.trim();
+    match raw.get(..4).and_then(|y| y.parse::<i32>().ok()) {
+        Some(year) if year > 0 => year,
+        _ => 0,
+    }
+}
+
+/// Fixes the selection RNG seed (e.g. for reproducing a schedule).
+const SEED_ENV: &str = "DESIZONE_ROTATION_SEED";
+
+/// A new RNG for random picks, seeded from `DESIZONE_ROTATION_SEED` when set
+/// and from OS entropy otherwise. Callers that pick repeatedly (the AutoDJ
+/// loop) keep one and pass it to every selection, so a seeded run repeats.
+pub fn selection_rng() -> SmallRng {
+    let seeded = std::env::var(SEED_ENV)
+        .ok()
+        .and_then(|v| v.trim().parse::<u64>().ok());
+    match seeded {
+        Some(seed) => {
+            log::info!("Rotation RNG seeded from {SEED_ENV}={seed}");
+            SmallRng::seed_from_u64(seed)
+        }
+        None => SmallRng::from_entropy(),
+    }
+}
+
+// ── Ghost queue ───────────────────────────────────────────────────────────────
+
+/// Rotation picks planned beyond the SAM queue when `use_ghost_queue` is on.
+pub const GHOST_QUEUE_DEPTH: usize = 5;
+/// Older plans are re-made; the hour's template or the library may have moved on.
+const GHOST_MAX_AGE_MS: i64 = 30 * 60 * 1000;
+/// How long a DJ veto keeps a song out of the ghost queue.
+const GHOST_VETO_SECS: i64 = 60 * 60;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GhostQueueEntry {
+    #[serde(flatten)]
+    pub song: SongCandidate,
+    /// Unix ms
+    pub planned_at: i64,
+    /// Placed by a DJ: kept through re-planning until it is queued
+    pub pinned: bool,
+}
+
+#[derive(Default)]
+struct GhostQueue {
+    entries: VecDeque<GhostQueueEntry>,
+    /// song_id → unix secs of the veto
+    vetoed: HashMap<i64, i64>,
+    /// Pinned songs handed to the SAM queue, so the pin can follow them
+    pinned_handoffs: HashSet<i64>,
+}
+
+fn ghost_queue() -> &'static Mutex<GhostQueue> {
+    static GHOST: OnceLock<Mutex<GhostQueue>> = OnceLock::new();
+    GHOST.get_or_init(|| Mutex::new(GhostQueue::default()))
+}
+
+/// Upcoming rotation picks, next first.
+pub fn get_ghost_queue() -> Vec<GhostQueueEntry> {
+    ghost_queue()
+        .lock()
+        .unwrap()
+        .entries
+        .iter()
+        .cloned()
+        .collect()
+}
+
+/// Drop `song_id` from the ghost queue and keep it out for a while. Returns
+/// whether it was planned.
+pub fn veto_ghost_entry(song_id: i64) -> bool {
+    let mut ghost = ghost_queue().lock().unwrap();
+    let before = ghost.entries.len();
+    ghost.entries.retain(|e| e.song.song_id != song_id);
+    ghost.vetoed.insert(song_id, Utc::now().timestamp());
+    ghost.entries.len() != before
+}
+
+/// Re-plan everything but pinned entries.
+pub fn clear_ghost_queue() {
+    let mut ghost = ghost_queue().lock().unwrap();
+    ghost.entries.retain(|e| e.pinned);
+}
+
+/// Pin or unpin a planned song. Returns whether it was planned.
+pub fn set_ghost_pinned(song_id: i64, pinned: bool) -> bool {
+    let mut ghost = ghost_queue().lock().unwrap();
+    match ghost.entries.iter_mut().find(|e| e.song.song_id == song_id) {
+        Some(entry) => {
+            entry.pinned = pinned;
+            true
+        }
+        None => false,
+    }
+}
+
+/// Put `song` in place of the planned `song_id`, pinned. Returns whether
+/// `song_id` was planned.
+pub fn replace_ghost_entry(song_id: i64, song: SongCandidate) -> bool {
+    let mut ghost = ghost_queue().lock().unwrap();
+    match ghost.entries.iter_mut().find(|e| e.song.song_id == song_id) {
+        Some(entry) => {
+            *entry = GhostQueueEntry {
+                song,
+                planned_at: Utc::now().timestamp_millis(),
+                pinned: true,
+            };
+            true
+        }
+        None => false,
+    }
+}
+
+/// Replace the whole ghost queue with a curated order.
+pub fn set_ghost_queue(entries: Vec<GhostQueueEntry>) {
+    ghost_queue().lock().unwrap().entries = entries.into();
+}
+
+/// Whether `song_id` left the ghost queue pinned (consumed on read).
+pub fn take_pinned_handoff(song_id: i64) -> bool {
+    ghost_queue()
+        .lock()
+        .unwrap()
+        .pinned_handoffs
+        .remove(&song_id)
+}
+
+/// Plan picks until the ghost queue holds [`GHOST_QUEUE_DEPTH`] songs. Each
+/// new pick sees the earlier ones as already played, so rules space them out
+/// as they would real plays.
+pub async fn refill_ghost_queue(
+    local_pool: &SqlitePool,
+    sam_pool: &MySqlPool,
+    excluded_song_ids: &HashSet<i64>,
+    rng: &mut impl Rng,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    // One planner at a time, or concurrent refills would plan the same slot twice.
+    static REFILL: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+    let _refilling = REFILL.lock().await;
+
+    let now = Utc::now();
+    let (mut lookahead, mut skip) = {
+        let mut ghost = ghost_queue().lock().unwrap();
+        let GhostQueue {
+            entries, vetoed, ..
+        } = &mut *ghost;
+        vetoed.retain(|_, at| now.timestamp() - *at < GHOST_VETO_SECS);
+        // DJ-pinned entries are only dropped when the song is already queued or on air.
+        entries.retain(|e| {
+            !excluded_song_ids.contains(&e.song.song_id)
+                && (e.pinned
+                    || (!vetoed.contains_key(&e.song.song_id)
+                        && now.timestamp_millis() - e.planned_at < GHOST_MAX_AGE_MS))
+        });
+        let lookahead: Vec<SongCandidate> = entries.iter().map(|e| e.song.clone()).collect();
+        let mut skip = excluded_song_ids.clone();
+        skip.extend(lookahead.iter().map(|s| s.song_id));
+        skip.extend(vetoed.keys().copied());
+        (lookahead, skip)
+    };
+
+    while lookahead.len() < GHOST_QUEUE_DEPTH {
+        let Some(pick) =
+            select_with_lookahead(local_pool, sam_pool, None, Some(&skip), &lookahead, rng).await?
+        else {
+            break;
+        };
+        skip.insert(pick.song_id);
+        ghost_queue()
+            .lock()
+            .unwrap()
+            .entries
+            .push_back(GhostQueueEntry {
+                song: pick.clone(),
+                planned_at: Utc::now().timestamp_millis(),
+                pinned: false,
+            });
+        lookahead.push(pick);
+    }
+    Ok(())
+}
+
+/// Next song from rotation: the head of the ghost queue when it is enabled,
+/// otherwise a fresh selection.
+pub async fn next_rotation_track(
+    local_pool: &SqlitePool,
+    sam_pool: &MySqlPool,
+    excluded_song_ids: &HashSet<i64>,
+    rng: &mut impl Rng,
+) -> Result<Option<SongCandidate>, Box<dyn std::error::Error + Send + Sync>> {
+    let config = get_clockwheel_config(local_pool).await.unwrap_or_default();
+    if !config.rules.use_ghost_queue {
+        {
+            let mut ghost = ghost_queue().lock().unwrap();
+            ghost.entries.clear();
+            ghost.pinned_handoffs.clear();
+        }
+        return select_next_track_with_exclusions(
+            local_pool,
+            sam_pool,
+            None,
+            Some(excluded_song_ids),
+            rng,
+        )
+        .await;
+    }
+    refill_ghost_queue(local_pool, sam_pool, excluded_song_ids, rng).await?;
+    let mut ghost = ghost_queue().lock().unwrap();
+    let Some(entry) = ghost.entries.pop_front() else {
+        return Ok(None);
+    };
+    if entry.pinned {
+        ghost.pinned_handoffs.insert(entry.song.song_id);
+    }
+    Ok(Some(entry.song))
+}
+
+// ── Decision trace ────────────────────────────────────────────────────────────
+
+/// How one slot fared in a live selection.
+#[derive(Debug, Clone, Serialize)]
+pub struct SlotTrace {
+    pub slot_id: String,
+    pub kind: ClockwheelSlotKind,
+    pub target: String,
+    pub selection_method: ClockwheelSelectionMethod,
+    /// Songs the slot offered
+    pub candidates: usize,
+    /// Removed because they are already queued or otherwise excluded
+    pub excluded: usize,
+    /// Left after rules ran
+    pub eligible: usize,
+    pub filters: Vec<FilterStep>,
+    /// `picked`, or why the slot was passed over
+    pub outcome: String,
+}
+
+impl SlotTrace {
+    fn new(slot: &ClockwheelSlot) -> Self {
+        Self {
+            slot_id: slot.id.clone(),
+            kind: slot.kind,
+            target: slot.target.clone(),
+            selection_method: slot.selection_method,
+            candidates: 0,
+            excluded: 0,
+            eligible: 0,
+            filters: Vec::new(),
+            outcome: String::new(),
+        }
+    }
+
+    fn skipped(mut self, reason: &str) -> Self {
+        self.outcome = reason.to_string();
+        self
+    }
+}
+
+/// The winning song and why the selection method chose it.
+#[derive(Debug, Clone, Serialize)]
+pub struct PickTrace {
+    pub song_id: i64,
+    pub title: String,
+    pub artist: String,
+    pub weight: f64,
+    pub reason: String,
+}
+
+/// Pool statistics captured before the pick consumes the candidates.
+struct PickOdds {
+    eligible: usize,
+    total_weight: f64,
+}
+
+impl PickOdds {
+    fn of(candidates: &[CandidateInternal]) -> Self {
+        Self {
+            eligible: candidates.len(),
+            // Same floor as the weighted draw in `choose_candidate`.
+            total_weight: candidates.iter().map(|c| c.weight.max(0.01)).sum(),
+        }
+    }
+}
+
+impl PickTrace {
+    fn new(chosen: &CandidateInternal, method: ClockwheelSelectionMethod, odds: &PickOdds) -> Self {
+        let n = odds.eligible;
+        let reason = match method {
+            ClockwheelSelectionMethod::Weighted => {
+                let weight = chosen.weight.max(0.01);
+                format!(
+                    "weighted draw: weight {:.2} of {:.2} across {n} songs ({:.1}% chance)",
+                    weight,
+                    odds.total_weight,
+                    100.0 * weight / odds.total_weight.max(f64::EPSILON)
+                )
+            }
+            ClockwheelSelectionMethod::Priority => {
+                format!("highest weight ({:.2}) of {n} songs", chosen.weight)
+            }
+            ClockwheelSelectionMethod::Random => format!("uniform draw, 1 in {n}"),
+            ClockwheelSelectionMethod::MostRecentlyPlayedSong => {
+                format!("most recently played song of {n}")
+            }
+            ClockwheelSelectionMethod::LeastRecentlyPlayedSong => {
+                format!("least recently played song of {n}")
+            }
+            ClockwheelSelectionMethod::MostRecentlyPlayedArtist => {
+                format!("most recently played artist of {n}")
+            }
+            ClockwheelSelectionMethod::LeastRecentlyPlayedArtist => {
+                format!("least recently played artist of {n}")
+            }
+            ClockwheelSelectionMethod::Lemming => format!("longest unplayed of {n} songs"),
+            ClockwheelSelectionMethod::PlaylistOrder => match chosen.playlist_position {
+                Some(position) => format!("next in playlist order (position {position})"),
+                None => format!("fewest plays of {n} songs"),
+            },
+        };
+        Self {
+            song_id: chosen.song_id,
+            title: chosen.title.clone(),
+            artist: chosen.artist.clone(),
+            weight: chosen.weight,
+            reason,
+        }
+    }
+}
+
+/// Trace of the most recent live selection (`select_next_track_with_exclusions`).
+#[derive(Debug, Clone, Serialize)]
+pub struct RotationDecision {
+    /// Unix ms
+    pub at: i64,
+    pub template_id: Option<i64>,
+    pub active_category: Option<String>,
+    /// Songs excluded up front (already queued)
+    pub excluded: usize,
+    /// Slots in the order they were tried
+    pub slots: Vec<SlotTrace>,
+    pub picked: Option<PickTrace>,
+    /// No clockwheel slot produced a song; the generic fallback ran
+    pub fallback: bool,
+}
+
+static LAST_ROTATION_DECISION: OnceLock<Mutex<Option<RotationDecision>>> = OnceLock::new();
+
+fn rotation_decision_cell() -> &'static Mutex<Option<RotationDecision>> {
+    LAST_ROTATION_DECISION.get_or_init(|| Mutex::new(None))
+}
+
+/// Latest decision recorded while `verbose_logging` was on.
+pub fn get_last_rotation_decision() -> Option<RotationDecision> {
+    rotation_decision_cell().lock().unwrap().clone()
+}
+
+fn finish_decision(clockwheel: &ClockwheelConfig, decision: RotationDecision) {
+    if !clockwheel.verbose_logging {
+        return;
+    }
+    let blocked: Vec<String> = decision
+        .slots
+        .iter()
+        .flat_map(|slot| &slot.filters)
+        .map(|step| format!("{}={}", step.rule, step.removed))
+        .collect();
+    match &decision.picked {
+        Some(pick) => log::info!(
+            "Rotation picked {} – {} (song_id={}): {}; filtered [{}]",
+            pick.artist,
+            pick.title,
+            pick.song_id,
+            pick.reason,
+            blocked.join(", ")
+        ),
+        None => log::info!(
+            "Rotation found no eligible song across {} slot(s); filtered [{}]",
+            decision.slots.len(),
+            blocked.join(", ")
+        ),
+    }
+    *rotation_decision_cell().lock().unwrap() = Some(decision);
+}
+
+// ── Simulation ────────────────────────────────────────────────────────────────
+
+/// Upper bound on picks in one simulation, whatever the track lengths.
+const SIMULATION_MAX_PICKS: usize = 5_000;
+/// Time advanced per pick when a song has no usable duration.
+const SIMULATION_MIN_STEP_SECS: i64 = 30;
+pub const SIMULATION_MAX_HOURS: u32 = 168;
+
+/// One projected AutoDJ pick and why it was made.
+#[derive(Debug, Clone, Serialize)]
+pub struct SimulatedPick {
+    /// Projected start, unix ms
+    pub at: i64,
+    pub song_id: i64,
+    pub title: String,
+    pub artist: String,
+    pub album: String,
+    pub category: Option<String>,
+    pub duration: i64,
+    pub template_id: Option<i64>,
+    pub slot_id: String,
+    pub slot_kind: ClockwheelSlotKind,
+    pub slot_target: String,
+    pub selection_method: ClockwheelSelectionMethod,
+    /// Song weight at pick time, including simulated on-play reductions
+    pub weight: f64,
+    /// Songs the slot offered before rules ran
+    pub candidates: usize,
+    /// Songs left after rules ran
+    pub eligible: usize,
+    pub filters: Vec<FilterStep>,
+    /// Slots passed over before this one, with the reason
+    pub skipped_slots: Vec<String>,
+    /// Every slot came up empty; picked from the generic fallback
+    pub fallback: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RotationSimulation {
+    /// Seed used for random picks; pass it back to repeat the run
+    pub seed: u64,
+    /// Unix ms
+    pub start: i64,
+    /// Unix ms where the last pick ends
+    pub end: i64,
+    pub picks: Vec<SimulatedPick>,
+    /// Unix ms at which no slot could supply a song, if that happened
+    pub stalled_at: Option<i64>,
+    /// Stopped at the pick limit before covering the whole window
+    pub truncated: bool,
+}
+
+/// Run the clockwheel and rules forward from `start` for `hours`, against a
+/// copy of the play history. Nothing is queued, and neither SAM nor the slot
+/// and playlist cursors are written. Each pick is appended to the simulated
+/// history so separation rules see it, and on-play weight reductions are
+/// applied in memory.
+pub async fn simulate_rotation(
+    local_pool: &SqlitePool,
+    sam_pool: &MySqlPool,
+    start: chrono::DateTime<Utc>,
+    hours: u32,
+    seed: Option<u64>,
+) -> Result<RotationSimulation, Box<dyn std::error::Error + Send + Sync>> {
+    let hours = hours.clamp(1, SIMULATION_MAX_HOURS);
+    let end = start + chrono::Duration::hours(hours as i64);
+    let seed = seed.unwrap_or_else(rand::random);
+    let mut rng = SmallRng::seed_from_u64(seed);
+
+    let config = get_clockwheel_config(local_pool)
+        .await
         .unwrap_or_default()
-        .as_nanos();
-    (nanos as u64) ^ ((nanos >> 64) as u64)
+        .normalized();
+    let enabled_rules: Vec<RotationRuleRow> = get_rotation_rules(local_pool)
+        .await?
+        .into_iter()
+        .filter(|r| r.enabled)
+        .collect();
+    let play_delta = -config.on_play_reduce_weight_by.abs();
+    let needs_bpm = uses_bpm(&enabled_rules);
+    let mut history = load_history(sam_pool).await;
+    let mut previous = previous_track(local_pool, sam_pool, &[], &history).await;
+
+    let mut templates: HashMap<(u32, u32), Option<ClockwheelTemplate>> = HashMap::new();
+    let mut playlist_cursors: HashMap<i64, i64> = HashMap::new();
+    let mut weights: HashMap<i64, f64> = HashMap::new();
+    let mut current_template: Option<Option<i64>> = None;
+    let mut slot_cursor = 0usize;
+
+    let mut picks = Vec::new();
+    let mut stalled_at = None;
+    let mut t = start;
+    while t < end {
+        if picks.len() >= SIMULATION_MAX_PICKS {
+            break;
+        }
+        let local_t = t.with_timezone(&chrono::Local);
+        let hour_key = (local_t.weekday().num_days_from_monday(), local_t.hour());
+        if let Entry::Vacant(entry) = templates.entry(hour_key) {
+            entry.insert(template_for_hour(local_pool, &local_t).await);
+        }
+        let template = templates[&hour_key].as_ref();
+        let template_id = template.and_then(|tpl| tpl.id);
+        let mut slots = match template {
+            Some(tpl) => tpl.slots.clone(),
+            None => config.slots.clone(),
+        };
+        if slots.is_empty() {
+            slots.push(ClockwheelSlot::default());
+        }
+        if current_template != Some(template_id) {
+            // Same as the live cursor: restart when the template changes.
+            if current_template.is_some() {
+                slot_cursor = 0;
+            } else {
+                slot_cursor = load_clockwheel_cursor(local_pool, template_id)
+                    .await
+                    .unwrap_or(0);
+            }
+            current_template = Some(template_id);
+        }
+        slot_cursor %= slots.len();
+
+        let now_unix = t.timestamp();
+        let mut skipped_slots = Vec::new();
+        let mut picked = None;
+        for offset in 0..slots.len() {
+            let idx = (slot_cursor + offset) % slots.len();
+            let slot = &slots[idx];
+            if !slot_is_active(slot, &t) {
+                skipped_slots.push(format!("{}: outside its hours", slot.id));
+                continue;
+            }
+            let outcome = simulate_slot_pick(
+                local_pool,
+                sam_pool,
+                slot,
+                &config.rules,
+                &enabled_rules,
+                needs_bpm,
+                &history,
+                previous.as_ref(),
+                &weights,
+                &mut playlist_cursors,
+                now_unix,
+                &mut rng,
+            )
+            .await?;
+            match outcome {
+                SlotOutcome::Picked(pick) => {
+                    slot_cursor = (idx + 1) % slots.len();
+                    picked = Some((slot.clone(), pick, false));
+                    break;
+                }
+                SlotOutcome::Skipped(reason) => {
+                    skipped_slots.push(format!("{}: {reason}", slot.id))
+                }
+            }
+        }
+        if picked.is_none() {
+            let slot = ClockwheelSlot::default();
+            let outcome = simulate_slot_pick(
+                local_pool,
+                sam_pool,
+                &slot,
+                &config.rules,
+                &enabled_rules,
+                needs_bpm,
+                &history,
+                previous.as_ref(),
+                &weights,
+                &mut playlist_cursors,
+                now_unix,
+                &mut rng,
+            )
+            .await?;
+            if let SlotOutcome::Picked(pick) = outcome {
+                picked = Some((slot, pick, true));
+            }
+        }
+        let Some((slot, pick, fallback)) = picked else {
+            stalled_at = Some(t.timestamp_millis());
+            break;
+        };
+        let chosen = pick.chosen;
+        previous = Some(FlowTrack::of(&chosen));
+        if play_delta.abs() >= f64::EPSILON {
+            weights.insert(chosen.song_id, (chosen.weight + play_delta).max(0.0));
+        }
+        history.insert(
+            0,
+            HistoryRow {
+                song_id: chosen.song_id,
+                artist: chosen.artist.clone(),
+                title: chosen.title.clone(),
+                album: chosen.album.clone(),
+                played_unix: now_unix,
+            },
+        );
+        picks.push(SimulatedPick {
+            at: t.timestamp_millis(),
+            song_id: chosen.song_id,
+            title: chosen.title,
+            artist: chosen.artist,
+            album: chosen.album,
+            category: chosen.category,
+            duration: chosen.duration,
+            template_id,
+            slot_id: slot.id,
+            slot_kind: slot.kind,
+            slot_target: slot.target,
+            selection_method: slot.selection_method,
+            weight: chosen.weight,
+            candidates: pick.candidates,
+            eligible: pick.eligible,
+            filters: pick.filters,
+            skipped_slots,
+            fallback,
+        });
+        t += chrono::Duration::seconds(chosen.duration.max(SIMULATION_MIN_STEP_SECS));
+    }
+
+    Ok(RotationSimulation {
+        seed,
+        start: start.timestamp_millis(),
+        end: t.timestamp_millis(),
+        truncated: t < end && stalled_at.is_none(),
+        picks,
+        stalled_at,
+    })
+}
+
+struct SlotPick {
+    chosen: CandidateInternal,
+    /// Songs offered before rules ran
+    candidates: usize,
+    eligible: usize,
+    filters: Vec<FilterStep>,
+}
+
+enum SlotOutcome {
+    Picked(Box<SlotPick>),
+    Skipped(&'static str),
+}
+
+/// Dry-run `choose_for_slot`: ordered playlist slots read the simulated cursor
+/// instead of the stored one.
+#[allow(clippy::too_many_arguments)]
+async fn simulate_slot_pick(
+    local_pool: &SqlitePool,
+    sam_pool: &MySqlPool,
+    slot: &ClockwheelSlot,
+    clockwheel_rules: &ClockwheelRules,
+    enabled_rules: &[RotationRuleRow],
+    needs_bpm: bool,
+    history: &[HistoryRow],
+    previous: Option<&FlowTrack>,
+    weights: &HashMap<i64, f64>,
+    playlist_cursors: &mut HashMap<i64, i64>,
+    now_unix: i64,
+    rng: &mut impl Rng,
+) -> Result<SlotOutcome, Box<dyn std::error::Error + Send + Sync>> {
+    let mut candidates = fetch_candidates_for_slot(local_pool, sam_pool, slot, 300).await?;
+    if candidates.is_empty() {
+        return Ok(SlotOutcome::Skipped("no songs"));
+    }
+    for c in &mut candidates {
+        if let Some(weight) = weights.get(&c.song_id) {
+            c.weight = *weight;
+        }
+    }
+    if needs_bpm {
+        apply_beatgrid_bpm(local_pool, &mut candidates).await;
+    }
+    let offered = candidates.len();
+    let enforce = slot.enforce_rules && clockwheel_rules.enforce_playlist_rotation_rules;
+    let filters = filter_candidates_traced(
+        &mut candidates,
+        history,
+        previous,
+        enforce.then_some(clockwheel_rules),
+        enabled_rules,
+        now_unix,
+    );
+    let eligible = candidates.len();
+    if candidates.is_empty() {
+        return Ok(SlotOutcome::Skipped("every song blocked by rules"));
+    }
+
+    let chosen = if slot.kind == ClockwheelSlotKind::Playlist
+        && slot.selection_method == ClockwheelSelectionMethod::PlaylistOrder
+    {
+        match resolve_playlist_id(local_pool, &slot.target).await? {
+            Some(playlist_id) => {
+                let cursor = match playlist_cursors.get(&playlist_id) {
+                    Some(cursor) => *cursor,
+                    None => get_playlist_cursor(local_pool, playlist_id)
+                        .await
+                        .map(|c| c.next_position)
+                        .unwrap_or(0),
+                };
+                let chosen = choose_in_playlist_order(candidates, cursor);
+                if let Some(c) = &chosen {
+                    playlist_cursors.insert(playlist_id, c.playlist_position.unwrap_or(0) + 1);
+                }
+                chosen
+            }
+            None => choose_candidate(candidates, slot.selection_method, history, now_unix, rng),
+        }
+    } else {
+        choose_candidate(candidates, slot.selection_method, history, now_unix, rng)
+    };
+    Ok(match chosen {
+        Some(chosen) => SlotOutcome::Picked(Box::new(SlotPick {
+            chosen,
+            candidates: offered,
+            eligible,
+            filters,
+        })),
+        None => SlotOutcome::Skipped("no playable song"),
+    })
+}
+
+// ── Offline fallback ──────────────────────────────────────────────────────────
+
+/// Upper bound on songs kept for offline picks.
+const CANDIDATE_CACHE_LIMIT: usize = 2_000;
+
+/// Songs and history seen by recent selections, so AutoDJ can keep picking
+/// while SAM is unreachable.
+#[derive(Default)]
+struct CandidateCache {
+    candidates: HashMap<i64, CandidateInternal>,
+    history: Vec<HistoryRow>,
+    rules: Option<ClockwheelRules>,
+}
+
+fn candidate_cache() -> &'static Mutex<CandidateCache> {
+    static CACHE: OnceLock<Mutex<CandidateCache>> = OnceLock::new();
+    CACHE.get_or_init(|| Mutex::new(CandidateCache::default()))
+}
+
+fn remember_candidates(candidates: &[CandidateInternal]) {
+    let mut cache = candidate_cache().lock().unwrap();
+    for candidate in candidates {
+        if cache.candidates.len() >= CANDIDATE_CACHE_LIMIT
+            && !cache.candidates.contains_key(&candidate.song_id)
+        {
+            continue;
+        }
+        cache
+            .candidates
+            .insert(candidate.song_id, candidate.clone());
+    }
+}
+
+fn remember_history(history: &[HistoryRow], rules: &ClockwheelRules) {
+    // An empty result usually means the query failed; keep what we had.
+    if history.is_empty() {
+        return;
+    }
+    let mut cache = candidate_cache().lock().unwrap();
+    cache.history = history.to_vec();
+    cache.rules = Some(rules.clone());
+}
+
+/// Songs available for offline picks.
+pub fn cached_candidate_count() -> usize {
+    candidate_cache().lock().unwrap().candidates.len()
+}
+
+/// Weighted pick from the cached candidates, without touching SAM. Clockwheel
+/// separation rules are applied against cached history plus earlier offline
+/// picks; if that leaves nothing, they are dropped so playout continues.
+pub fn select_cached_track(
+    excluded_song_ids: Option<&HashSet<i64>>,
+    rng: &mut impl Rng,
+) -> Option<SongCandidate> {
+    let mut cache = candidate_cache().lock().unwrap();
+    let now_unix = Utc::now().timestamp();
+    let mut candidates: Vec<CandidateInternal> = cache
+        .candidates
+        .values()
+        .filter(|c| excluded_song_ids.is_none_or(|ex| !ex.contains(&c.song_id)))
+        .cloned()
+        .collect();
+    if candidates.is_empty() {
+        return None;
+    }
+    let rules = cache.rules.clone().unwrap_or_default();
+    let mut separated = candidates.clone();
+    apply_clockwheel_rules(&mut separated, &cache.history, &rules, now_unix);
+    if !separated.is_empty() {
+        candidates = separated;
+    }
+    // HashMap order varies per process; sort so seeded runs repeat.
+    candidates.sort_by_key(|c| c.song_id);
+    let chosen = choose_candidate(
+        candidates,
+        ClockwheelSelectionMethod::Weighted,
+        &cache.history,
+        now_unix,
+        rng,
+    )?;
+    cache.history.insert(
+        0,
+        HistoryRow {
+            song_id: chosen.song_id,
+            artist: chosen.artist.clone(),
+            title: chosen.title.clone(),
+            album: chosen.album.clone(),
+            played_unix: now_unix,
+        },
+    );
+    Some(SongCandidate {
+        song_id: chosen.song_id,
+        title: chosen.title,
+        artist: chosen.artist,
+        album: Some(chosen.album),
+        category: chosen.category,
+        duration: chosen.duration,
+        file_path: chosen.file_path,
+        score: chosen.weight,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(song_id: i64, position: i64) -> CandidateInternal {
+        CandidateInternal {
+            song_id,
+            title: format!("Song {song_id}"),
+            artist: String::new(),
+            album: String::new(),
+            category: None,
+            duration: 180,
+            file_path: String::new(),
+            weight: 1.0,
+            count_played: 0,
+            song_last_played_unix: 0,
+            playlist_position: Some(position),
+            bpm: 0.0,
+            genre: String::new(),
+            mood: String::new(),
+            year: 0,
+        }
+    }
+
+    #[test]
+    fn playlist_order_picks_entry_at_cursor() {
+        let list = vec![entry(10, 0), entry(11, 1), entry(12, 2)];
+        let chosen = choose_in_playlist_order(list, 1).unwrap();
+        assert_eq!(chosen.song_id, 11);
+    }
+
+    #[test]
+    fn playlist_order_skips_filtered_entries() {
+        // Position 1 was removed by rotation rules; the cursor steps over it.
+        let list = vec![entry(10, 0), entry(12, 2), entry(13, 3)];
+        let chosen = choose_in_playlist_order(list, 1).unwrap();
+        assert_eq!(chosen.song_id, 12);
+    }
+
+    #[test]
+    fn playlist_order_wraps_past_end() {
+        let list = vec![entry(10, 0), entry(11, 1), entry(12, 2)];
+        let chosen = choose_in_playlist_order(list, 3).unwrap();
+        assert_eq!(chosen.song_id, 10);
+    }
+
+    #[test]
+    fn cached_pick_respects_exclusions_and_separation() {
+        let mut a = entry(20, 0);
+        a.artist = "Artist A".into();
+        let mut b = entry(21, 1);
+        b.artist = "Artist B".into();
+        remember_candidates(&[a, b]);
+        assert_eq!(cached_candidate_count(), 2);
+
+        let excluded_21 = HashSet::from([21]);
+        let mut rng = SmallRng::seed_from_u64(7);
+        let pick = select_cached_track(Some(&excluded_21), &mut rng).unwrap();
+        assert_eq!(pick.song_id, 20);
+        // Song 20 just "played", so separation rules now prefer 21.
+        assert_eq!(select_cached_track(None, &mut rng).unwrap().song_id, 21);
+    }
+
+    #[test]
+    fn seeded_selection_is_reproducible_and_follows_weights() {
+        let mut light = entry(30, 0);
+        light.weight = 1.0;
+        let mut heavy = entry(31, 1);
+        heavy.weight = 3.0;
+        let pool = vec![light, heavy];
+
+        let picks = |seed: u64| -> Vec<i64> {
+            let mut rng = SmallRng::seed_from_u64(seed);
+            (0..4_000)
+                .map(|_| {
+                    choose_candidate(
+                        pool.clone(),
+                        ClockwheelSelectionMethod::Weighted,
+                        &[],
+                        0,
+                        &mut rng,
+                    )
+                    .unwrap()
+                    .song_id
+                })
+                .collect()
+        };
+        let run = picks(42);
+        assert_eq!(run, picks(42));
+        let heavy_share = run.iter().filter(|id| **id == 31).count() as f64 / run.len() as f64;
+        assert!(
+            (heavy_share - 0.75).abs() < 0.03,
+            "heavy share {heavy_share}"
+        );
+    }
+
+    #[test]
+    fn traced_filters_attribute_removals_to_rules() {
+        let mut same_track = entry(40, 0);
+        same_track.artist = "Artist A".into();
+        let mut same_artist = entry(41, 1);
+        same_artist.artist = "artist a".into();
+        let mut clear = entry(42, 2);
+        clear.artist = "Artist B".into();
+        let mut earlier = entry(43, 3);
+        earlier.artist = "Artist C".into();
+        let played = |c: &CandidateInternal, played_unix: i64| HistoryRow {
+            song_id: c.song_id,
+            artist: c.artist.clone(),
+            title: c.title.clone(),
+            album: c.album.clone(),
+            played_unix,
+        };
+        // Song 43 is outside every clockwheel window but inside the song gap.
+        let history = vec![played(&same_track, 99_940), played(&earlier, 70_000)];
+        let song_rule = RotationRuleRow {
+            id: Some(1),
+            name: "Two-song gap".into(),
+            rule_type: "song_separation".into(),
+            config_json: r#"{"type":"song_separation","min_songs":2}"#.into(),
+            enabled: true,
+            priority: 0,
+        };
+
+        let mut candidates = vec![same_track, same_artist, clear, earlier];
+        let steps = filter_candidates_traced(
+            &mut candidates,
+            &history,
+            None,
+            Some(&ClockwheelRules::default()),
+            &[song_rule],
+            100_000,
+        );
+        assert_eq!(candidates.len(), 1);
+        assert_eq!(candidates[0].song_id, 42);
+        let rules: Vec<(&str, usize)> =
+            steps.iter().map(|s| (s.rule.as_str(), s.removed)).collect();
+        assert_eq!(
+            rules,
+            vec![
+                ("no_same_track_minutes", 1),
+                ("no_same_artist_minutes", 1),
+                ("Two-song gap", 1)
+            ]
+        );
+        assert_eq!(steps[1].examples, vec!["artist a – Song 41".to_string()]);
+    }
+
+    #[test]
+    fn pick_trace_reports_weighted_odds() {
+        let mut light = entry(50, 0);
+        light.weight = 1.0;
+        let mut heavy = entry(51, 1);
+        heavy.weight = 3.0;
+        let odds = PickOdds::of(&[light, heavy.clone()]);
+        let trace = PickTrace::new(&heavy, ClockwheelSelectionMethod::Weighted, &odds);
+        assert_eq!(
+            trace.reason,
+            "weighted draw: weight 3.00 of 4.00 across 2 songs (75.0% chance)"
+        );
+    }
+
+    #[test]
+    fn dayparted_rules_apply_only_inside_their_hours() {
+        use chrono::TimeZone;
+
+        // Monday 07:00 and 23:00 station time.
+        let drive = chrono::Local
+            .with_ymd_and_hms(2026, 1, 5, 7, 0, 0)
+            .unwrap()
+            .timestamp();
+        let night = drive + 16 * 3600;
+        let mut c = entry(60, 0);
+        c.artist = "Artist A".into();
+        c.category = Some("Holiday Music".into());
+        let played = |at: i64| HistoryRow {
+            song_id: 61,
+            artist: "Artist A".into(),
+            title: "Other".into(),
+            album: String::new(),
+            played_unix: at,
+        };
+        let overnight = Daypart {
+            start_hour: 22,
+            end_hour: 6,
+            days: vec![],
+        };
+        let long_gap = RotationRule::Dayparted {
+            daypart: overnight.clone(),
+            rule: Box::new(RotationRule::ArtistSeparationTime { min_minutes: 120 }),
+        };
+        // Same artist 90 minutes ago: blocked overnight, allowed in drive time.
+        assert!(legacy_rule_blocks(
+            &long_gap,
+            &c,
+            &[played(night - 5400)],
+            None,
+            night
+        ));
+        assert!(!legacy_rule_blocks(
+            &long_gap,
+            &c,
+            &[played(drive - 5400)],
+            None,
+            drive
+        ));
+
+        let ban = RotationRule::CategoryBan {
+            categories: vec!["holiday-music".into()],
+            daypart: Daypart {
+                days: vec![0],
+                ..overnight
+            },
+        };
+        assert!(legacy_rule_blocks(&ban, &c, &[], None, night));
+        assert!(!legacy_rule_blocks(&ban, &c, &[], None, drive));
+        // Tuesday night is outside the banned days.
+        assert!(!legacy_rule_blocks(&ban, &c, &[], None, night + 24 * 3600));
+    }
+
+    #[test]
+    fn flow_rules_compare_against_previous_song() {
+        let mut ballad = entry(70, 0);
+        ballad.bpm = 72.0;
+        ballad.genre = "Ballad".into();
+        ballad.category = Some("Hindi".into());
+        ballad.year = 1994;
+        let previous = FlowTrack::of(&ballad);
+
+        let mut slow = entry(71, 1);
+        slow.bpm = 0.0;
+        slow.mood = "Slow ballad".into();
+        let mut fast = entry(72, 2);
+        fast.bpm = 128.0;
+        fast.category = Some("Punjabi".into());
+        fast.year = 1998;
+
+        let blocks = |rule: &RotationRule, c: &CandidateInternal| {
+            legacy_rule_blocks(rule, c, &[], Some(&previous), 0)
+        };
+        let jump = RotationRule::MaxBpmJump { max_change: 20.0 };
+        assert!(blocks(&jump, &fast));
+        // Unknown BPM never counts as a jump.
+        assert!(!blocks(&jump, &slow));
+        assert!(!legacy_rule_blocks(&jump, &fast, &[], None, 0));
+
+        let no_slow = RotationRule::NoConsecutiveSlow {
+            max_bpm: 85.0,
+            keywords: vec!["ballad".into()],
+        };
+        assert!(blocks(&no_slow, &slow));
+        assert!(!blocks(&no_slow, &fast));
+
+        let by_category = RotationRule::Alternate {
+            attribute: FlowAttribute::Category,
+            values: vec!["Hindi".into(), "Punjabi".into()],
+        };
+        let mut hindi = fast.clone();
+        hindi.category = Some("hindi".into());
+        assert!(blocks(&by_category, &hindi));
+        assert!(!blocks(&by_category, &fast));
+
+        let by_era = RotationRule::Alternate {
+            attribute: FlowAttribute::Era,
+            values: vec![],
+        };
+        assert!(blocks(&by_era, &fast));
+        assert!(!blocks(&by_era, &slow));
+    }
 }
@@ -3,9 +3,25 @@ use chrono::{Datelike, TimeZone};
 ///
 /// Runs as a Tokio background task. Reads the schedule from the local DB
 /// every second, fires show actions at the correct times, emits Tauri events.
+///
+/// While a show is on air its overrides (DJ mode, crossfade config,
+/// clockwheel template) replace the station settings; they revert when the
+/// show ends. A DJ mode the operator changed during the show is left alone.
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
 use serde::{Deserialize, Serialize};
 use sqlx::sqlite::SqlitePool;
 use sqlx::Row;
+use tauri::{AppHandle, Emitter, Manager};
+
+use super::autodj::{self, DjMode};
+use super::mode_transition::{self, ModeChangeRequest};
+use super::rotation;
+use crate::audio::crossfade::CrossfadeConfig;
+use crate::scripting::trigger::ScriptEvent;
+use crate::state::AppState;
 
 // ── Data model ────────────────────────────────────────────────────────────────
 
@@ -21,6 +37,21 @@ pub enum DayOfWeek {
     Sunday,
 }
 
+impl DayOfWeek {
+    pub fn matches(&self, weekday: chrono::Weekday) -> bool {
+        weekday
+            == match self {
+                DayOfWeek::Monday => chrono::Weekday::Mon,
+                DayOfWeek::Tuesday => chrono::Weekday::Tue,
+                DayOfWeek::Wednesday => chrono::Weekday::Wed,
+                DayOfWeek::Thursday => chrono::Weekday::Thu,
+                DayOfWeek::Friday => chrono::Weekday::Fri,
+                DayOfWeek::Saturday => chrono::Weekday::Sat,
+                DayOfWeek::Sunday => chrono::Weekday::Sun,
+            }
+    }
+}
+
 /// Actions a show can trigger when it fires
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type", rename_all = "snake_case")]
@@ -46,6 +77,30 @@ pub struct Show {
     pub duration_minutes: u32,
     pub actions: Vec<ShowAction>,
     pub enabled: bool,
+    #[serde(default)]
+    pub overrides: ShowOverrides,
+}
+
+/// Station settings a show swaps in while it is on air. Unset fields keep
+/// whatever the station is using.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ShowOverrides {
+    pub dj_mode: Option<DjMode>,
+    pub crossfade: Option<CrossfadeConfig>,
+    /// Saved crossfade profile, used when `crossfade` is unset
+    pub crossfade_profile_id: Option<i64>,
+    pub clockwheel_template_id: Option<i64>,
+}
+
+impl ShowOverrides {
+    pub fn is_empty(&self) -> bool {
+        self.dj_mode.is_none() && !self.sets_crossfade() && self.clockwheel_template_id.is_none()
+    }
+
+    fn sets_crossfade(&self) -> bool {
+        self.crossfade.is_some() || self.crossfade_profile_id.is_some()
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -61,7 +116,7 @@ pub struct ScheduledEvent {
 
 pub async fn get_shows(pool: &SqlitePool) -> Result<Vec<Show>, sqlx::Error> {
     let rows = sqlx::query(
-        "SELECT id, name, days_json, start_time, duration_minutes, actions_json, enabled FROM scheduled_shows ORDER BY start_time"
+        "SELECT id, name, days_json, start_time, duration_minutes, actions_json, enabled, overrides_json FROM scheduled_shows ORDER BY start_time"
     )
     .fetch_all(pool)
     .await?;
@@ -72,6 +127,8 @@ pub async fn get_shows(pool: &SqlitePool) -> Result<Vec<Show>, sqlx::Error> {
             serde_json::from_str(r.get::<&str, _>("days_json")).unwrap_or_default();
         let actions: Vec<ShowAction> =
             serde_json::from_str(r.get::<&str, _>("actions_json")).unwrap_or_default();
+        let overrides: ShowOverrides =
+            serde_json::from_str(r.get::<&str, _>("overrides_json")).unwrap_or_default();
         shows.push(Show {
             id: r.get("id"),
             name: r.get("name"),
@@ -80,6 +137,7 @@ pub async fn get_shows(pool: &SqlitePool) -> Result<Vec<Show>, sqlx::Error> {
             duration_minutes: r.get::<i64, _>("duration_minutes") as u32,
             actions,
             enabled: r.get::<i64, _>("enabled") != 0,
+            overrides,
         });
     }
     Ok(shows)
@@ -88,10 +146,11 @@ pub async fn get_shows(pool: &SqlitePool) -> Result<Vec<Show>, sqlx::Error> {
 pub async fn upsert_show(pool: &SqlitePool, show: &Show) -> Result<i64, sqlx::Error> {
     let days_json = serde_json::to_string(&show.days).unwrap_or_default();
     let actions_json = serde_json::to_string(&show.actions).unwrap_or_default();
+    let overrides_json = serde_json::to_string(&show.overrides).unwrap_or_default();
 
     let result = if let Some(id) = show.id {
         sqlx::query(
-            "UPDATE scheduled_shows SET name=?, days_json=?, start_time=?, duration_minutes=?, actions_json=?, enabled=? WHERE id=?"
+            "UPDATE scheduled_shows SET name=?, days_json=?, start_time=?, duration_minutes=?, actions_json=?, enabled=?, overrides_json=? WHERE id=?"
         )
         .bind(&show.name)
         .bind(&days_json)
@@ -99,13 +158,14 @@ pub async fn upsert_show(pool: &SqlitePool, show: &Show) -> Result<i64, sqlx::Er
         .bind(show.duration_minutes as i64)
         .bind(&actions_json)
         .bind(show.enabled as i64)
+        .bind(&overrides_json)
         .bind(id)
         .execute(pool)
         .await?;
         id
     } else {
         let r = sqlx::query(
-            "INSERT INTO scheduled_shows (name, days_json, start_time, duration_minutes, actions_json, enabled) VALUES (?, ?, ?, ?, ?, ?)"
+            "INSERT INTO scheduled_shows (name, days_json, start_time, duration_minutes, actions_json, enabled, overrides_json) VALUES (?, ?, ?, ?, ?, ?, ?)"
         )
         .bind(&show.name)
         .bind(&days_json)
@@ -113,6 +173,7 @@ pub async fn upsert_show(pool: &SqlitePool, show: &Show) -> Result<i64, sqlx::Er
         .bind(show.duration_minutes as i64)
         .bind(&actions_json)
         .bind(show.enabled as i64)
+        .bind(&overrides_json)
         .execute(pool)
         .await?;
         r.last_insert_rowid()
@@ -172,15 +233,7 @@ pub async fn get_upcoming_events(
             for day_offset in 0..=(hours / 24 + 1) {
                 let candidate_date = now.date_naive() + chrono::Duration::days(day_offset as i64);
                 let weekday = candidate_date.weekday();
-                let matches = show.days.iter().any(|d| match d {
-                    DayOfWeek::Monday => weekday == chrono::Weekday::Mon,
-                    DayOfWeek::Tuesday => weekday == chrono::Weekday::Tue,
-                    DayOfWeek::Wednesday => weekday == chrono::Weekday::Wed,
-                    DayOfWeek::Thursday => weekday == chrono::Weekday::Thu,
-                    DayOfWeek::Friday => weekday == chrono::Weekday::Fri,
-                    DayOfWeek::Saturday => weekday == chrono::Weekday::Sat,
-                    DayOfWeek::Sunday => weekday == chrono::Weekday::Sun,
-                });
+                let matches = show.days.iter().any(|d| d.matches(weekday));
                 if !matches {
                     continue;
                 }
@@ -206,3 +259,276 @@ pub async fn get_upcoming_events(
     events.sort_by(|a, b| a.fires_at.cmp(&b.fires_at));
     Ok(events)
 }
+
+// ── Show overrides ────────────────────────────────────────────────────────────
+
+const TICK: Duration = Duration::from_secs(5);
+const RELOAD_INTERVAL: Duration = Duration::from_secs(60);
+
+static RELOAD_REQUESTED: AtomicBool = AtomicBool::new(false);
+
+/// Overrides currently applied, with what they replaced.
+#[derive(Debug, Clone)]
+struct AppliedShow {
+    show_id: i64,
+    overrides: ShowOverrides,
+    /// DJ mode before the show switched it
+    prior_mode: DjMode,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ActiveShowOverrides {
+    pub show_id: i64,
+    pub show_name: String,
+    pub overrides: ShowOverrides,
+}
+
+fn applied_cell() -> &'static Mutex<Option<AppliedShow>> {
+    static APPLIED: OnceLock<Mutex<Option<AppliedShow>>> = OnceLock::new();
+    APPLIED.get_or_init(|| Mutex::new(None))
+}
+
+fn on_air_cell() -> &'static Mutex<Option<String>> {
+    static ON_AIR: OnceLock<Mutex<Option<String>>> = OnceLock::new();
+    ON_AIR.get_or_init(|| Mutex::new(None))
+}
+
+/// Name of the show on air as of the last tick (for stream titles).
+pub fn on_air_show_name() -> Option<String> {
+    on_air_cell().lock().unwrap().clone()
+}
+
+/// Re-read the schedule on the next tick (after a show is saved or deleted).
+pub fn request_reload() {
+    RELOAD_REQUESTED.store(true, Ordering::Relaxed);
+}
+
+fn start_hm(show: &Show) -> Option<(u32, u32)> {
+    let mut parts = show.start_time.split(':').map(|p| p.parse::<u32>().ok());
+    Some((parts.next()??, parts.next()??))
+}
+
+/// Most recent start of `show` at or before `now`, looking back a week.
+fn last_start(
+    show: &Show,
+    now: &chrono::DateTime<chrono::Local>,
+) -> Option<chrono::DateTime<chrono::Local>> {
+    let (h, m) = start_hm(show)?;
+    let days_back = if show.days.is_empty() { 0 } else { 7 };
+    (0..=days_back).find_map(|offset| {
+        let date = now.date_naive() - chrono::Duration::days(offset);
+        if !show.days.is_empty() && !show.days.iter().any(|d| d.matches(date.weekday())) {
+            return None;
+        }
+        let start = chrono::Local
+            .from_local_datetime(&date.and_hms_opt(h, m, 0)?)
+            .earliest()?;
+        (start <= *now).then_some(start)
+    })
+}
+
+/// The show on air at `now`: the enabled show that started most recently,
+/// unless its duration has run out. A zero duration runs until the next show.
+pub fn active_show<'a>(
+    shows: &'a [Show],
+    now: &chrono::DateTime<chrono::Local>,
+) -> Option<&'a Show> {
+    let (show, start) = shows
+        .iter()
+        .filter(|s| s.enabled)
+        .filter_map(|s| last_start(s, now).map(|start| (s, start)))
+        .max_by_key(|(_, start)| *start)?;
+    let on_air = show.duration_minutes == 0
+        || start + chrono::Duration::minutes(show.duration_minutes as i64) > *now;
+    on_air.then_some(show)
+}
+
+pub fn get_active_overrides(shows: &[Show]) -> Option<ActiveShowOverrides> {
+    let applied = applied_cell().lock().unwrap().clone()?;
+    let show = shows.iter().find(|s| s.id == Some(applied.show_id));
+    Some(ActiveShowOverrides {
+        show_id: applied.show_id,
+        show_name: show.map(|s| s.name.clone()).unwrap_or_default(),
+        overrides: applied.overrides,
+    })
+}
+
+/// Station crossfade config, as saved outside any show.
+async fn station_crossfade(pool: &SqlitePool) -> CrossfadeConfig {
+    match crate::db::local::load_crossfade_config(pool).await {
+        Ok(Some(json)) => crate::commands::crossfade_commands::parse_crossfade_config_json(&json),
+        _ => CrossfadeConfig::default(),
+    }
+}
+
+/// The crossfade config a show asks for, inline or as a saved profile.
+async fn show_crossfade(pool: &SqlitePool, overrides: &ShowOverrides) -> Option<CrossfadeConfig> {
+    if let Some(config) = &overrides.crossfade {
+        return Some(
+            crate::commands::crossfade_commands::normalize_crossfade_config(config.clone()),
+        );
+    }
+    let id = overrides.crossfade_profile_id?;
+    match crate::db::local::get_crossfade_profile(pool, id).await {
+        Ok(Some(profile)) => {
+            Some(crate::commands::crossfade_commands::normalize_crossfade_config(profile.config))
+        }
+        _ => {
+            log::warn!("Show crossfade profile {id} not found; keeping the station config");
+            None
+        }
+    }
+}
+
+/// Swap the applied overrides for those of `next` (none when no show with
+/// overrides is on air).
+async fn switch_overrides(app: &AppHandle, pool: &SqlitePool, next: Option<&Show>) {
+    let state = app.state::<AppState>();
+    let prev = applied_cell().lock().unwrap().take();
+    let next_overrides = next.map(|s| s.overrides.clone()).unwrap_or_default();
+
+    // Only a mode the show set is reverted; a DJ's own switch stands.
+    let current = autodj::get_dj_mode();
+    let base_mode = match prev.as_ref() {
+        Some(p) if p.overrides.dj_mode == Some(current) => p.prior_mode,
+        _ => current,
+    };
+    let target_mode = next_overrides.dj_mode.unwrap_or(base_mode);
+    if target_mode != current {
+        if let Err(e) =
+            mode_transition::request_change(app, ModeChangeRequest::immediate(target_mode)).await
+        {
+            log::warn!("Show DJ mode switch failed: {e}");
+        }
+    }
+
+    let had_crossfade = prev.as_ref().is_some_and(|p| p.overrides.sets_crossfade());
+    let crossfade = match show_crossfade(pool, &next_overrides).await {
+        Some(config) => Some(config),
+        None if had_crossfade => Some(station_crossfade(pool).await),
+        None => None,
+    };
+    if let Some(config) = crossfade {
+        if let Err(e) = state.engine.lock().unwrap().set_crossfade_config(config) {
+            log::warn!("Show crossfade config not applied: {e}");
+        }
+    }
+
+    let prev_template = prev
+        .as_ref()
+        .and_then(|p| p.overrides.clockwheel_template_id);
+    if prev_template != next_overrides.clockwheel_template_id {
+        rotation::set_template_override(next_overrides.clockwheel_template_id);
+    }
+
+    let active = next.and_then(|show| {
+        Some(AppliedShow {
+            show_id: show.id?,
+            overrides: next_overrides,
+            prior_mode: base_mode,
+        })
+    });
+    match &active {
+        Some(_) => log::info!("Show overrides applied: {}", next.map_or("", |s| &s.name)),
+        None => log::info!("Show overrides reverted"),
+    }
+    let event = next.map(|show| ActiveShowOverrides {
+        show_id: show.id.unwrap_or(0),
+        show_name: show.name.clone(),
+        overrides: show.overrides.clone(),
+    });
+    *applied_cell().lock().unwrap() = active;
+    let _ = app.emit("show_overrides_changed", event);
+}
+
+/// Apply and revert per-show overrides as shows start and end.
+pub fn start(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let state = app.state::<AppState>();
+        let Some(pool) = state.local_db.clone() else {
+            return;
+        };
+        let mut shows: Vec<Show> = Vec::new();
+        let mut loaded_at: Option<Instant> = None;
+        let mut last_on_air: Option<Option<i64>> = None;
+        let mut tick = tokio::time::interval(TICK);
+        loop {
+            tick.tick().await;
+            let reload = RELOAD_REQUESTED.swap(false, Ordering::Relaxed);
+            if reload || loaded_at.is_none_or(|t| t.elapsed() >= RELOAD_INTERVAL) {
+                shows = get_shows(&pool).await.unwrap_or_default();
+                loaded_at = Some(Instant::now());
+            }
+
+            let now = chrono::Local::now();
+            let on_air = active_show(&shows, &now);
+            let on_air_id = on_air.and_then(|s| s.id);
+            *on_air_cell().lock().unwrap() = on_air.map(|s| s.name.clone());
+            // A show already on air at startup is not announced.
+            if last_on_air.is_some_and(|last| last != on_air_id) {
+                if let (Some(show_id), Some(show)) = (on_air_id, on_air) {
+                    state.script_engine.fire(ScriptEvent::ShowStart {
+                        show_id,
+                        name: show.name.clone(),
+                    });
+                }
+            }
+            last_on_air = Some(on_air_id);
+
+            let next = on_air.filter(|s| s.id.is_some() && !s.overrides.is_empty());
+            let applied_id = applied_cell().lock().unwrap().as_ref().map(|a| a.show_id);
+            // An edited show re-applies so changed overrides take effect.
+            if next.and_then(|s| s.id) != applied_id || (reload && applied_id.is_some()) {
+                switch_overrides(&app, &pool, next).await;
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn show(id: i64, start_time: &str, duration_minutes: u32) -> Show {
+        Show {
+            id: Some(id),
+            name: format!("show {id}"),
+            days: vec![
+                DayOfWeek::Monday,
+                DayOfWeek::Tuesday,
+                DayOfWeek::Wednesday,
+                DayOfWeek::Thursday,
+                DayOfWeek::Friday,
+                DayOfWeek::Saturday,
+                DayOfWeek::Sunday,
+            ],
+            start_time: start_time.into(),
+            duration_minutes,
+            actions: Vec::new(),
+            enabled: true,
+            overrides: ShowOverrides::default(),
+        }
+    }
+
+    #[test]
+    fn active_show_is_latest_started_within_its_duration() {
+        let at = |h, m| {
+            chrono::Local
+                .from_local_datetime(
+                    &chrono::NaiveDate::from_ymd_opt(2026, 1, 14)
+                        .unwrap()
+                        .and_hms_opt(h, m, 0)
+                        .unwrap(),
+                )
+                .unwrap()
+        };
+        let shows = vec![show(1, "06:00", 0), show(2, "12:00", 60)];
+
+        assert_eq!(active_show(&shows, &at(9, 0)).and_then(|s| s.id), Some(1));
+        assert_eq!(active_show(&shows, &at(12, 30)).and_then(|s| s.id), Some(2));
+        // The one-hour show ended and nothing started after it.
+        assert!(active_show(&shows, &at(13, 30)).is_none());
+        // Before 06:00 yesterday's 12:00 show is the latest start, long over.
+        assert!(active_show(&shows, &at(5, 0)).is_none());
+    }
+}
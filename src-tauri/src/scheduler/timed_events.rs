@@ -0,0 +1,273 @@
+/// Exact-time events
+///
+/// Elements (station ID, news bed, ad break) pinned to a minute of the hour.
+/// A hard event fades the song on air as soon as it is due; a soft event waits
+/// for the song to end and plays ahead of the next rotation pick.
+use chrono::{DateTime, Datelike, TimeZone, Timelike};
+use serde::{Deserialize, Serialize};
+use sqlx::sqlite::SqlitePool;
+use sqlx::Row;
+
+/// How long a hard event may still fire after its minute (e.g. app restart).
+const HARD_GRACE_SECS: i64 = 60;
+
+// ── Data model ────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum TimedEventMode {
+    /// Fade the current song out immediately
+    Hard,
+    /// Wait for the current song to end
+    #[default]
+    Soft,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimedEvent {
+    pub id: Option<i64>,
+    pub name: String,
+    /// Minute of the hour the event fires at (0 = top of hour)
+    pub minute: u8,
+    /// Local hours it fires in (empty = every hour)
+    pub hours: Vec<u8>,
+    /// 0=Mon..6=Sun (empty = every day)
+    pub days: Vec<u8>,
+    /// SAM song to play; takes precedence over `file_path`
+    pub song_id: Option<i64>,
+    pub file_path: Option<String>,
+    pub mode: TimedEventMode,
+    /// Hard mode: fade-out time for the song on air
+    pub fade_ms: u32,
+    /// Soft mode: give up if the event could not be armed within this many minutes
+    pub grace_minutes: u32,
+    pub enabled: bool,
+    pub last_fired_at: Option<i64>,
+}
+
+impl TimedEvent {
+    /// Unix time of the occurrence that is due at `now`, if any.
+    pub fn due_at<Tz: TimeZone>(&self, now: &DateTime<Tz>) -> Option<i64> {
+        if !self.enabled {
+            return None;
+        }
+        let grace = match self.mode {
+            TimedEventMode::Hard => HARD_GRACE_SECS,
+            TimedEventMode::Soft => i64::from(self.grace_minutes.max(1)) * 60,
+        };
+        occurrence_due(
+            now,
+            self.minute,
+            &self.hours,
+            &self.days,
+            grace,
+            self.last_fired_at,
+        )
+    }
+}
+
+/// Unix time of the `minute`-of-hour occurrence due at `now`: it started at
+/// most `grace_secs` ago, has not fired yet and falls on one of the local
+/// `hours`/`days` (empty = any).
+pub fn occurrence_due<Tz: TimeZone>(
+    now: &DateTime<Tz>,
+    minute: u8,
+    hours: &[u8],
+    days: &[u8],
+    grace_secs: i64,
+    last_fired_at: Option<i64>,
+) -> Option<i64> {
+    if minute > 59 {
+        return None;
+    }
+    let now_ts = now.timestamp();
+    let hour_start = now_ts - i64::from(now.minute() * 60 + now.second());
+    let mut occurrence = hour_start + i64::from(minute) * 60;
+    if occurrence > now_ts {
+        occurrence -= 3600;
+    }
+
+    if now_ts - occurrence > grace_secs {
+        return None;
+    }
+    if last_fired_at.is_some_and(|t| t >= occurrence) {
+        return None;
+    }
+
+    let local = now.timezone().timestamp_opt(occurrence, 0).single()?;
+    if !hours.is_empty() && !hours.contains(&(local.hour() as u8)) {
+        return None;
+    }
+    let day = local.weekday().num_days_from_monday() as u8;
+    if !days.is_empty() && !days.contains(&day) {
+        return None;
+    }
+    Some(occurrence)
+}
+
+// ── DB helpers ────────────────────────────────────────────────────────────────
+
+/// Sorted, de-duplicated `hours_json` / `days_json` columns.
+pub(crate) fn schedule_json(hours: &[u8], days: &[u8]) -> (String, String) {
+    let mut hours = hours.to_vec();
+    hours.retain(|h| *h <= 23);
+    hours.sort_unstable();
+    hours.dedup();
+    let mut days = days.to_vec();
+    days.retain(|d| *d <= 6);
+    days.sort_unstable();
+    days.dedup();
+    (
+        serde_json::to_string(&hours).unwrap_or_else(|_| "[]".to_string()),
+        serde_json::to_string(&days).unwrap_or_else(|_| "[]".to_string()),
+    )
+}
+
+pub async fn get_timed_events(pool: &SqlitePool) -> Result<Vec<TimedEvent>, sqlx::Error> {
+    let rows = sqlx::query("SELECT * FROM timed_events ORDER BY minute, id")
+        .fetch_all(pool)
+        .await?;
+
+    Ok(rows
+        .iter()
+        .map(|r| TimedEvent {
+            id: r.get("id"),
+            name: r.get("name"),
+            minute: r.get::<i64, _>("minute").clamp(0, 59) as u8,
+            hours: serde_json::from_str(r.get::<&str, _>("hours_json")).unwrap_or_default(),
+            days: serde_json::from_str(r.get::<&str, _>("days_json")).unwrap_or_default(),
+            song_id: r.get("song_id"),
+            file_path: r.get("file_path"),
+            mode: match r.get::<&str, _>("mode") {
+                "hard" => TimedEventMode::Hard,
+                _ => TimedEventMode::Soft,
+            },
+            fade_ms: r.get::<i64, _>("fade_ms").max(0) as u32,
+            grace_minutes: r.get::<i64, _>("grace_minutes").max(0) as u32,
+            enabled: r.get::<i64, _>("enabled") != 0,
+            last_fired_at: r.get("last_fired_at"),
+        })
+        .collect())
+}
+
+pub async fn upsert_timed_event(pool: &SqlitePool, event: &TimedEvent) -> Result<i64, sqlx::Error> {
+    let (hours_json, days_json) = schedule_json(&event.hours, &event.days);
+    let mode = match event.mode {
+        TimedEventMode::Hard => "hard",
+        TimedEventMode::Soft => "soft",
+    };
+
+    let id = if let Some(id) = event.id {
+        sqlx::query(
+            "UPDATE timed_events SET name=?, minute=?, hours_json=?, days_json=?, song_id=?, file_path=?, mode=?, fade_ms=?, grace_minutes=?, enabled=? WHERE id=?",
+        )
+        .bind(&event.name)
+        .bind(event.minute.min(59) as i64)
+        .bind(&hours_json)
+        .bind(&days_json)
+        .bind(event.song_id)
+        .bind(&event.file_path)
+        .bind(mode)
+        .bind(event.fade_ms as i64)
+        .bind(event.grace_minutes as i64)
+        .bind(event.enabled as i64)
+        .bind(id)
+        .execute(pool)
+        .await?;
+        id
+    } else {
+        sqlx::query(
+            "INSERT INTO timed_events (name, minute, hours_json, days_json, song_id, file_path, mode, fade_ms, grace_minutes, enabled) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(&event.name)
+        .bind(event.minute.min(59) as i64)
+        .bind(&hours_json)
+        .bind(&days_json)
+        .bind(event.song_id)
+        .bind(&event.file_path)
+        .bind(mode)
+        .bind(event.fade_ms as i64)
+        .bind(event.grace_minutes as i64)
+        .bind(event.enabled as i64)
+        .execute(pool)
+        .await?
+        .last_insert_rowid()
+    };
+    Ok(id)
+}
+
+pub async fn delete_timed_event(pool: &SqlitePool, id: i64) -> Result<(), sqlx::Error> {
+    sqlx::query("DELETE FROM timed_events WHERE id = ?")
+        .bind(id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+pub async fn mark_timed_event_fired(
+    pool: &SqlitePool,
+    id: i64,
+    occurrence: i64,
+) -> Result<(), sqlx::Error> {
+    sqlx::query("UPDATE timed_events SET last_fired_at = ? WHERE id = ?")
+        .bind(occurrence)
+        .bind(id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{FixedOffset, TimeZone};
+
+    fn top_of_hour(mode: TimedEventMode) -> TimedEvent {
+        TimedEvent {
+            id: Some(1),
+            name: "Station ID".to_string(),
+            minute: 0,
+            hours: vec![],
+            days: vec![],
+            song_id: Some(42),
+            file_path: None,
+            mode,
+            fade_ms: 1_500,
+            grace_minutes: 10,
+            enabled: true,
+            last_fired_at: None,
+        }
+    }
+
+    #[test]
+    fn fires_once_per_occurrence_within_grace() {
+        let tz = FixedOffset::east_opt(0).unwrap();
+        let mut event = top_of_hour(TimedEventMode::Soft);
+        let at = tz.with_ymd_and_hms(2025, 6, 2, 14, 3, 0).unwrap();
+        let occurrence = tz
+            .with_ymd_and_hms(2025, 6, 2, 14, 0, 0)
+            .unwrap()
+            .timestamp();
+        assert_eq!(event.due_at(&at), Some(occurrence));
+
+        event.last_fired_at = Some(occurrence);
+        assert_eq!(event.due_at(&at), None);
+
+        let late = tz.with_ymd_and_hms(2025, 6, 2, 14, 11, 0).unwrap();
+        event.last_fired_at = None;
+        assert_eq!(event.due_at(&late), None);
+    }
+
+    #[test]
+    fn hard_events_have_short_grace_and_respect_hours() {
+        let tz = FixedOffset::east_opt(5 * 3600 + 1800).unwrap();
+        let mut event = top_of_hour(TimedEventMode::Hard);
+        let on_time = tz.with_ymd_and_hms(2025, 6, 2, 9, 0, 30).unwrap();
+        let late = tz.with_ymd_and_hms(2025, 6, 2, 9, 2, 0).unwrap();
+        assert!(event.due_at(&on_time).is_some());
+        assert!(event.due_at(&late).is_none());
+
+        event.hours = vec![10];
+        assert!(event.due_at(&on_time).is_none());
+    }
+}
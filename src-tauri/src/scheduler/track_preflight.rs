@@ -0,0 +1,318 @@
+/// Pre-flight track validation
+///
+/// AutoDJ checks each pick before loading it: the file must exist, its
+/// container must probe, a decoder must open and decode the first packet,
+/// and the container duration must be roughly what SAM declares. A track
+/// that fails — here or when the deck load itself fails — is quarantined in
+/// `bad_tracks` for a while so AutoDJ moves straight on to another pick
+/// instead of retrying the same broken file every tick. Manual loads run
+/// the same check but are refused instead, so the operator sees why.
+use serde::{Deserialize, Serialize};
+use sqlx::{Row, SqlitePool};
+use std::collections::HashSet;
+use std::path::Path;
+
+use symphonia::core::{
+    codecs::{DecoderOptions, CODEC_TYPE_NULL},
+    errors::Error as SymphoniaError,
+    formats::FormatOptions,
+    io::MediaSourceStream,
+    meta::MetadataOptions,
+    probe::Hint,
+};
+
+use crate::error::{AppError, ErrorCode};
+
+/// First quarantine; doubles with each repeat failure up to the cap.
+const QUARANTINE_BASE_SECS: i64 = 6 * 3600;
+const QUARANTINE_MAX_SECS: i64 = 7 * 24 * 3600;
+/// Packets read while looking for the first decodable one.
+const MAX_PROBE_PACKETS: usize = 16;
+/// A file this much shorter or longer than declared is treated as damaged.
+const DURATION_SLACK_MS: u64 = 10_000;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PreflightFailure {
+    Missing,
+    Empty,
+    Unreadable(String),
+    NoAudioTrack,
+    Undecodable(String),
+    DurationMismatch { declared_ms: u64, actual_ms: u64 },
+}
+
+impl std::fmt::Display for PreflightFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Missing => write!(f, "file not found"),
+            Self::Empty => write!(f, "file is empty"),
+            Self::Unreadable(e) => write!(f, "unreadable container: {e}"),
+            Self::NoAudioTrack => write!(f, "no audio track"),
+            Self::Undecodable(e) => write!(f, "cannot decode: {e}"),
+            Self::DurationMismatch {
+                declared_ms,
+                actual_ms,
+            } => write!(
+                f,
+                "duration {}s does not match declared {}s",
+                actual_ms / 1000,
+                declared_ms / 1000
+            ),
+        }
+    }
+}
+
+/// True when `actual_ms` is implausible for a song declared as `declared_ms`:
+/// under half or over double the declared length, beyond a fixed slack.
+pub fn duration_mismatch(declared_ms: u64, actual_ms: u64) -> bool {
+    let too_short = actual_ms < declared_ms / 2 && declared_ms - actual_ms > DURATION_SLACK_MS;
+    let too_long = actual_ms > declared_ms.saturating_mul(2) + DURATION_SLACK_MS;
+    too_short || too_long
+}
+
+/// Check that `path` will load and play (blocking).
+pub fn validate(path: &Path, declared_duration_ms: Option<u64>) -> Result<(), PreflightFailure> {
+    let meta = std::fs::metadata(path).map_err(|_| PreflightFailure::Missing)?;
+    if !meta.is_file() {
+        return Err(PreflightFailure::Missing);
+    }
+    if meta.len() == 0 {
+        return Err(PreflightFailure::Empty);
+    }
+
+    let file =
+        std::fs::File::open(path).map_err(|e| PreflightFailure::Unreadable(e.to_string()))?;
+    let mss = MediaSourceStream::new(Box::new(file), Default::default());
+    let mut hint = Hint::new();
+    if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
+        hint.with_extension(ext);
+    }
+    let mut probed = symphonia::default::get_probe()
+        .format(
+            &hint,
+            mss,
+            &FormatOptions::default(),
+            &MetadataOptions::default(),
+        )
+        .map_err(|e| PreflightFailure::Unreadable(e.to_string()))?;
+    let track = probed
+        .format
+        .tracks()
+        .iter()
+        .find(|t| t.codec_params.codec != CODEC_TYPE_NULL)
+        .ok_or(PreflightFailure::NoAudioTrack)?
+        .clone();
+    let mut decoder = symphonia::default::get_codecs()
+        .make(&track.codec_params, &DecoderOptions::default())
+        .map_err(|e| PreflightFailure::Undecodable(e.to_string()))?;
+
+    let mut last_error = String::from("no audio packets");
+    let mut decoded_any = false;
+    for _ in 0..MAX_PROBE_PACKETS {
+        let packet = match probed.format.next_packet() {
+            Ok(p) => p,
+            Err(SymphoniaError::ResetRequired) => {
+                decoder.reset();
+                continue;
+            }
+            Err(e) => {
+                last_error = e.to_string();
+                break;
+            }
+        };
+        if packet.track_id() != track.id {
+            continue;
+        }
+        match decoder.decode(&packet) {
+            Ok(_) => {
+                decoded_any = true;
+                break;
+            }
+            Err(e) => last_error = e.to_string(),
+        }
+    }
+    if !decoded_any {
+        return Err(PreflightFailure::Undecodable(last_error));
+    }
+
+    let actual_ms = match (track.codec_params.n_frames, track.codec_params.sample_rate) {
+        (Some(frames), Some(rate)) if rate > 0 => Some(frames * 1000 / u64::from(rate)),
+        _ => None,
+    };
+    if let (Some(declared_ms), Some(actual_ms)) = (declared_duration_ms, actual_ms) {
+        if declared_ms > 0 && duration_mismatch(declared_ms, actual_ms) {
+            return Err(PreflightFailure::DurationMismatch {
+                declared_ms,
+                actual_ms,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// [`validate`] on a blocking thread, for a load an operator asked for.
+pub async fn check_manual_load(
+    path: &Path,
+    declared_duration_ms: Option<u64>,
+) -> Result<(), AppError> {
+    let owned = path.to_path_buf();
+    let checked = tokio::task::spawn_blocking(move || validate(&owned, declared_duration_ms)).await;
+    match checked {
+        Ok(Ok(())) => Ok(()),
+        Ok(Err(PreflightFailure::Missing)) => Err(AppError::file_not_found(path.display())),
+        Ok(Err(failure)) => Err(AppError::decode(format!("{}: {failure}", path.display()))),
+        Err(err) => {
+            log::warn!("Pre-flight check did not run: {err}");
+            Ok(())
+        }
+    }
+}
+
+/// Whether a failed deck load says the file itself is bad, as opposed to
+/// the engine being busy or an output device going away.
+pub fn is_track_fault(err: &AppError) -> bool {
+    matches!(err.code, ErrorCode::DecodeError | ErrorCode::FileNotFound)
+}
+
+// ── Quarantine ────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BadTrack {
+    pub song_id: i64,
+    pub file_path: String,
+    pub reason: String,
+    pub failures: i64,
+    pub first_failed_at: i64,
+    pub last_failed_at: i64,
+    /// Unix seconds; AutoDJ may pick the song again after this
+    pub quarantined_until: i64,
+}
+
+fn quarantine_secs(failures: i64) -> i64 {
+    let doublings = (failures - 1).clamp(0, 16) as u32;
+    QUARANTINE_BASE_SECS
+        .saturating_mul(1 << doublings)
+        .min(QUARANTINE_MAX_SECS)
+}
+
+/// Record a failure for `song_id`, extending its quarantine.
+pub async fn quarantine(
+    pool: &SqlitePool,
+    song_id: i64,
+    file_path: &str,
+    reason: &str,
+) -> Result<BadTrack, sqlx::Error> {
+    let now = chrono::Utc::now().timestamp();
+    let failures: i64 = sqlx::query_scalar("SELECT failures FROM bad_tracks WHERE song_id = ?")
+        .bind(song_id)
+        .fetch_optional(pool)
+        .await?
+        .unwrap_or(0)
+        + 1;
+    let quarantined_until = now + quarantine_secs(failures);
+    sqlx::query(
+        "INSERT INTO bad_tracks
+             (song_id, file_path, reason, failures, first_failed_at, last_failed_at,
+              quarantined_until)
+         VALUES (?, ?, ?, ?, ?, ?, ?)
+         ON CONFLICT(song_id) DO UPDATE SET
+             file_path = excluded.file_path,
+             reason = excluded.reason,
+             failures = excluded.failures,
+             last_failed_at = excluded.last_failed_at,
+             quarantined_until = excluded.quarantined_until",
+    )
+    .bind(song_id)
+    .bind(file_path)
+    .bind(reason)
+    .bind(failures)
+    .bind(now)
+    .bind(now)
+    .bind(quarantined_until)
+    .execute(pool)
+    .await?;
+    Ok(BadTrack {
+        song_id,
+        file_path: file_path.to_string(),
+        reason: reason.to_string(),
+        failures,
+        first_failed_at: now,
+        last_failed_at: now,
+        quarantined_until,
+    })
+}
+
+/// Songs still in quarantine.
+pub async fn quarantined_song_ids(pool: &SqlitePool) -> Result<HashSet<i64>, sqlx::Error> {
+    let ids: Vec<i64> =
+        sqlx::query_scalar("SELECT song_id FROM bad_tracks WHERE quarantined_until > ?")
+            .bind(chrono::Utc::now().timestamp())
+            .fetch_all(pool)
+            .await?;
+    Ok(ids.into_iter().collect())
+}
+
+pub async fn get_bad_tracks(pool: &SqlitePool) -> Result<Vec<BadTrack>, sqlx::Error> {
+    let rows = sqlx::query(
+        "SELECT song_id, file_path, reason, failures, first_failed_at, last_failed_at,
+                quarantined_until
+         FROM bad_tracks ORDER BY last_failed_at DESC",
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(rows
+        .into_iter()
+        .map(|r| BadTrack {
+            song_id: r.get("song_id"),
+            file_path: r.get("file_path"),
+            reason: r.get("reason"),
+            failures: r.get("failures"),
+            first_failed_at: r.get("first_failed_at"),
+            last_failed_at: r.get("last_failed_at"),
+            quarantined_until: r.get("quarantined_until"),
+        })
+        .collect())
+}
+
+/// Forget a song's failures so AutoDJ may pick it again right away.
+pub async fn release(pool: &SqlitePool, song_id: i64) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query("DELETE FROM bad_tracks WHERE song_id = ?")
+        .bind(song_id)
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn duration_sanity_allows_normal_drift() {
+        assert!(!duration_mismatch(200_000, 195_000));
+        assert!(!duration_mismatch(200_000, 120_000));
+        assert!(duration_mismatch(200_000, 40_000));
+        assert!(duration_mismatch(200_000, 500_000));
+        // Short jingles: the slack keeps small absolute gaps from tripping.
+        assert!(!duration_mismatch(8_000, 3_000));
+    }
+
+    #[test]
+    fn quarantine_backs_off_to_a_cap() {
+        assert_eq!(quarantine_secs(1), QUARANTINE_BASE_SECS);
+        assert_eq!(quarantine_secs(2), QUARANTINE_BASE_SECS * 2);
+        assert_eq!(quarantine_secs(50), QUARANTINE_MAX_SECS);
+    }
+
+    #[test]
+    fn missing_and_empty_files_fail() {
+        let dir = std::env::temp_dir();
+        let missing = dir.join(format!("preflight-missing-{}.mp3", std::process::id()));
+        assert_eq!(validate(&missing, None), Err(PreflightFailure::Missing));
+
+        let empty = dir.join(format!("preflight-empty-{}.mp3", std::process::id()));
+        std::fs::write(&empty, b"").unwrap();
+        assert_eq!(validate(&empty, None), Err(PreflightFailure::Empty));
+        let _ = std::fs::remove_file(&empty);
+    }
+}
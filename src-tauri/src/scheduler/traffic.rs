@@ -0,0 +1,583 @@
+/// Traffic (ad-break) scheduling
+///
+/// Ad breaks are pinned to a minute of the hour like exact-time events. When a
+/// break comes due it is filled from active campaigns: make-goods first, then
+/// by priority, honouring each campaign's daily cap and competitive separation
+/// between sponsors in the same category. Every spot gets a row in the spot
+/// log, which records the actual air time for affidavits.
+use chrono::{DateTime, TimeZone};
+use serde::{Deserialize, Serialize};
+use sqlx::sqlite::SqlitePool;
+use sqlx::Row;
+
+use super::timed_events::{occurrence_due, schedule_json, TimedEvent, TimedEventMode};
+
+/// A scheduled spot that never aired within this window is counted as missed.
+const STALE_SCHEDULED_SECS: i64 = 3600;
+
+// ── Data model ────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AdBreak {
+    pub id: Option<i64>,
+    pub name: String,
+    /// Minute of the hour the break starts at
+    pub minute: u8,
+    /// Local hours it runs in (empty = every hour)
+    pub hours: Vec<u8>,
+    /// 0=Mon..6=Sun (empty = every day)
+    pub days: Vec<u8>,
+    pub max_spots: u32,
+    /// Total spot length budget (0 = unlimited)
+    pub max_duration_secs: u32,
+    /// Give up on the break if it could not start within this many minutes
+    pub grace_minutes: u32,
+    pub enabled: bool,
+    pub last_fired_at: Option<i64>,
+}
+
+impl AdBreak {
+    /// Unix time of the occurrence that is due at `now`, if any.
+    pub fn due_at<Tz: TimeZone>(&self, now: &DateTime<Tz>) -> Option<i64> {
+        if !self.enabled || self.max_spots == 0 {
+            return None;
+        }
+        occurrence_due(
+            now,
+            self.minute,
+            &self.hours,
+            &self.days,
+            i64::from(self.grace_minutes.max(1)) * 60,
+            self.last_fired_at,
+        )
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Campaign {
+    pub id: Option<i64>,
+    pub advertiser: String,
+    pub title: String,
+    /// Competing sponsors share a category (e.g. "auto", "telecom")
+    pub sponsor_category: String,
+    /// SAM song to play; takes precedence over `file_path`
+    pub song_id: Option<i64>,
+    pub file_path: Option<String>,
+    pub duration_secs: u32,
+    /// Local `YYYY-MM-DD`, inclusive
+    pub start_date: String,
+    pub end_date: String,
+    /// 0 = no daily cap
+    pub max_plays_per_day: u32,
+    /// Minimum gap between spots of the same sponsor category
+    pub separation_minutes: u32,
+    pub priority: i32,
+    pub enabled: bool,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SpotStatus {
+    Scheduled,
+    Aired,
+    Missed,
+}
+
+impl SpotStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SpotStatus::Scheduled => "scheduled",
+            SpotStatus::Aired => "aired",
+            SpotStatus::Missed => "missed",
+        }
+    }
+
+    fn from_str(s: &str) -> Self {
+        match s {
+            "aired" => SpotStatus::Aired,
+            "missed" => SpotStatus::Missed,
+            _ => SpotStatus::Scheduled,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpotLogEntry {
+    pub id: i64,
+    pub campaign_id: i64,
+    pub break_id: Option<i64>,
+    pub advertiser: String,
+    pub title: String,
+    pub sponsor_category: String,
+    pub scheduled_at: i64,
+    pub aired_at: Option<i64>,
+    pub played_ms: Option<i64>,
+    pub status: SpotStatus,
+    /// Missed spot this one makes good for
+    pub make_good_for: Option<i64>,
+}
+
+/// A spot placed in a break, ready to be armed by the AutoDJ runtime.
+#[derive(Debug, Clone)]
+pub struct PlannedSpot {
+    pub log_id: i64,
+    pub campaign: Campaign,
+    pub make_good_for: Option<i64>,
+}
+
+impl PlannedSpot {
+    pub fn as_timed_event(&self) -> TimedEvent {
+        TimedEvent {
+            id: None,
+            name: format!("{} - {}", self.campaign.advertiser, self.campaign.title),
+            minute: 0,
+            hours: Vec::new(),
+            days: Vec::new(),
+            song_id: self.campaign.song_id,
+            file_path: self.campaign.file_path.clone(),
+            mode: TimedEventMode::Soft,
+            fade_ms: 0,
+            grace_minutes: 0,
+            enabled: true,
+            last_fired_at: None,
+        }
+    }
+}
+
+/// A campaign with the counters that decide whether it may run in a break.
+#[derive(Debug, Clone)]
+pub struct SpotCandidate {
+    pub campaign: Campaign,
+    pub plays_today: u32,
+    /// Last airing of any spot in the same sponsor category
+    pub category_last_aired_at: Option<i64>,
+    /// Oldest missed spot still owed to this campaign
+    pub owed_make_good: Option<i64>,
+}
+
+/// Fill one break from `candidates`. Returns each chosen campaign with the
+/// missed spot it makes good for.
+pub fn select_spots(
+    ad_break: &AdBreak,
+    mut candidates: Vec<SpotCandidate>,
+    now: i64,
+) -> Vec<(Campaign, Option<i64>)> {
+    candidates.retain(|c| {
+        let cap_ok =
+            c.campaign.max_plays_per_day == 0 || c.plays_today < c.campaign.max_plays_per_day;
+        let separation_secs = i64::from(c.campaign.separation_minutes) * 60;
+        let separated = c
+            .category_last_aired_at
+            .is_none_or(|t| now - t >= separation_secs);
+        c.campaign.enabled && cap_ok && separated
+    });
+    candidates.sort_by(|a, b| {
+        a.owed_make_good
+            .is_none()
+            .cmp(&b.owed_make_good.is_none())
+            .then(b.campaign.priority.cmp(&a.campaign.priority))
+            .then(a.plays_today.cmp(&b.plays_today))
+            .then(a.owed_make_good.cmp(&b.owed_make_good))
+    });
+
+    let mut spots = Vec::new();
+    let mut categories: Vec<String> = Vec::new();
+    let mut used_secs = 0u32;
+    for candidate in candidates {
+        if spots.len() as u32 >= ad_break.max_spots {
+            break;
+        }
+        let category = candidate.campaign.sponsor_category.trim().to_lowercase();
+        if !category.is_empty() && categories.contains(&category) {
+            continue;
+        }
+        if ad_break.max_duration_secs > 0
+            && used_secs + candidate.campaign.duration_secs > ad_break.max_duration_secs
+        {
+            continue;
+        }
+        used_secs += candidate.campaign.duration_secs;
+        if !category.is_empty() {
+            categories.push(category);
+        }
+        spots.push((candidate.campaign, candidate.owed_make_good));
+    }
+    spots
+}
+
+// ── DB helpers ────────────────────────────────────────────────────────────────
+
+fn row_to_campaign(r: &sqlx::sqlite::SqliteRow) -> Campaign {
+    Campaign {
+        id: r.get("id"),
+        advertiser: r.get("advertiser"),
+        title: r.get("title"),
+        sponsor_category: r.get("sponsor_category"),
+        song_id: r.get("song_id"),
+        file_path: r.get("file_path"),
+        duration_secs: r.get::<i64, _>("duration_secs").max(0) as u32,
+        start_date: r.get("start_date"),
+        end_date: r.get("end_date"),
+        max_plays_per_day: r.get::<i64, _>("max_plays_per_day").max(0) as u32,
+        separation_minutes: r.get::<i64, _>("separation_minutes").max(0) as u32,
+        priority: r.get::<i64, _>("priority") as i32,
+        enabled: r.get::<i64, _>("enabled") != 0,
+    }
+}
+
+pub async fn get_ad_breaks(pool: &SqlitePool) -> Result<Vec<AdBreak>, sqlx::Error> {
+    let rows = sqlx::query("SELECT * FROM traffic_breaks ORDER BY minute, id")
+        .fetch_all(pool)
+        .await?;
+
+    Ok(rows
+        .iter()
+        .map(|r| AdBreak {
+            id: r.get("id"),
+            name: r.get("name"),
+            minute: r.get::<i64, _>("minute").clamp(0, 59) as u8,
+            hours: serde_json::from_str(r.get::<&str, _>("hours_json")).unwrap_or_default(),
+            days: serde_json::from_str(r.get::<&str, _>("days_json")).unwrap_or_default(),
+            max_spots: r.get::<i64, _>("max_spots").max(0) as u32,
+            max_duration_secs: r.get::<i64, _>("max_duration_secs").max(0) as u32,
+            grace_minutes: r.get::<i64, _>("grace_minutes").max(0) as u32,
+            enabled: r.get::<i64, _>("enabled") != 0,
+            last_fired_at: r.get("last_fired_at"),
+        })
+        .collect())
+}
+
+pub async fn upsert_ad_break(pool: &SqlitePool, ad_break: &AdBreak) -> Result<i64, sqlx::Error> {
+    let (hours_json, days_json) = schedule_json(&ad_break.hours, &ad_break.days);
+    let id = if let Some(id) = ad_break.id {
+        sqlx::query(
+            "UPDATE traffic_breaks SET name=?, minute=?, hours_json=?, days_json=?, max_spots=?, max_duration_secs=?, grace_minutes=?, enabled=? WHERE id=?",
+        )
+        .bind(&ad_break.name)
+        .bind(ad_break.minute.min(59) as i64)
+        .bind(&hours_json)
+        .bind(&days_json)
+        .bind(ad_break.max_spots as i64)
+        .bind(ad_break.max_duration_secs as i64)
+        .bind(ad_break.grace_minutes as i64)
+        .bind(ad_break.enabled as i64)
+        .bind(id)
+        .execute(pool)
+        .await?;
+        id
+    } else {
+        sqlx::query(
+            "INSERT INTO traffic_breaks (name, minute, hours_json, days_json, max_spots, max_duration_secs, grace_minutes, enabled) VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(&ad_break.name)
+        .bind(ad_break.minute.min(59) as i64)
+        .bind(&hours_json)
+        .bind(&days_json)
+        .bind(ad_break.max_spots as i64)
+        .bind(ad_break.max_duration_secs as i64)
+        .bind(ad_break.grace_minutes as i64)
+        .bind(ad_break.enabled as i64)
+        .execute(pool)
+        .await?
+        .last_insert_rowid()
+    };
+    Ok(id)
+}
+
+pub async fn delete_ad_break(pool: &SqlitePool, id: i64) -> Result<(), sqlx::Error> {
+    sqlx::query("DELETE FROM traffic_breaks WHERE id = ?")
+        .bind(id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+pub async fn mark_ad_break_fired(
+    pool: &SqlitePool,
+    id: i64,
+    occurrence: i64,
+) -> Result<(), sqlx::Error> {
+    sqlx::query("UPDATE traffic_breaks SET last_fired_at = ? WHERE id = ?")
+        .bind(occurrence)
+        .bind(id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+pub async fn get_campaigns(pool: &SqlitePool) -> Result<Vec<Campaign>, sqlx::Error> {
+    let rows = sqlx::query("SELECT * FROM traffic_campaigns ORDER BY start_date, advertiser, id")
+        .fetch_all(pool)
+        .await?;
+    Ok(rows.iter().map(row_to_campaign).collect())
+}
+
+pub async fn upsert_campaign(pool: &SqlitePool, campaign: &Campaign) -> Result<i64, sqlx::Error> {
+    let id = if let Some(id) = campaign.id {
+        sqlx::query(
+            "UPDATE traffic_campaigns SET advertiser=?, title=?, sponsor_category=?, song_id=?, file_path=?, duration_secs=?, start_date=?, end_date=?, max_plays_per_day=?, separation_minutes=?, priority=?, enabled=? WHERE id=?",
+        )
+        .bind(&campaign.advertiser)
+        .bind(&campaign.title)
+        .bind(&campaign.sponsor_category)
+        .bind(campaign.song_id)
+        .bind(&campaign.file_path)
+        .bind(campaign.duration_secs as i64)
+        .bind(&campaign.start_date)
+        .bind(&campaign.end_date)
+        .bind(campaign.max_plays_per_day as i64)
+        .bind(campaign.separation_minutes as i64)
+        .bind(campaign.priority as i64)
+        .bind(campaign.enabled as i64)
+        .bind(id)
+        .execute(pool)
+        .await?;
+        id
+    } else {
+        sqlx::query(
+            "INSERT INTO traffic_campaigns (advertiser, title, sponsor_category, song_id, file_path, duration_secs, start_date, end_date, max_plays_per_day, separation_minutes, priority, enabled) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(&campaign.advertiser)
+        .bind(&campaign.title)
+        .bind(&campaign.sponsor_category)
+        .bind(campaign.song_id)
+        .bind(&campaign.file_path)
+        .bind(campaign.duration_secs as i64)
+        .bind(&campaign.start_date)
+        .bind(&campaign.end_date)
+        .bind(campaign.max_plays_per_day as i64)
+        .bind(campaign.separation_minutes as i64)
+        .bind(campaign.priority as i64)
+        .bind(campaign.enabled as i64)
+        .execute(pool)
+        .await?
+        .last_insert_rowid()
+    };
+    Ok(id)
+}
+
+pub async fn delete_campaign(pool: &SqlitePool, id: i64) -> Result<(), sqlx::Error> {
+    sqlx::query("DELETE FROM traffic_campaigns WHERE id = ?")
+        .bind(id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// Spot log rows scheduled within `[start_utc, end_utc)`, oldest first.
+pub async fn get_spot_log(
+    pool: &SqlitePool,
+    start_utc: i64,
+    end_utc: i64,
+    campaign_id: Option<i64>,
+) -> Result<Vec<SpotLogEntry>, sqlx::Error> {
+    let rows = sqlx::query(
+        r#"
+        SELECT l.id, l.campaign_id, l.break_id, l.scheduled_at, l.aired_at, l.played_ms,
+               l.status, l.make_good_for,
+               COALESCE(c.advertiser, '') AS advertiser,
+               COALESCE(c.title, '') AS title,
+               COALESCE(c.sponsor_category, '') AS sponsor_category
+        FROM traffic_spot_log l
+        LEFT JOIN traffic_campaigns c ON c.id = l.campaign_id
+        WHERE l.scheduled_at >= ? AND l.scheduled_at < ?
+          AND (? IS NULL OR l.campaign_id = ?)
+        ORDER BY l.scheduled_at ASC, l.id ASC
+        "#,
+    )
+    .bind(start_utc)
+    .bind(end_utc)
+    .bind(campaign_id)
+    .bind(campaign_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .iter()
+        .map(|r| SpotLogEntry {
+            id: r.get("id"),
+            campaign_id: r.get("campaign_id"),
+            break_id: r.get("break_id"),
+            advertiser: r.get("advertiser"),
+            title: r.get("title"),
+            sponsor_category: r.get("sponsor_category"),
+            scheduled_at: r.get("scheduled_at"),
+            aired_at: r.get("aired_at"),
+            played_ms: r.get("played_ms"),
+            status: SpotStatus::from_str(r.get::<&str, _>("status")),
+            make_good_for: r.get("make_good_for"),
+        })
+        .collect())
+}
+
+/// Fill a due break and log its spots as scheduled.
+pub async fn plan_break<Tz: TimeZone>(
+    pool: &SqlitePool,
+    ad_break: &AdBreak,
+    occurrence: i64,
+    now: &DateTime<Tz>,
+) -> Result<Vec<PlannedSpot>, sqlx::Error> {
+    let now_ts = now.timestamp();
+    sqlx::query("UPDATE traffic_spot_log SET status = 'missed' WHERE status = 'scheduled' AND scheduled_at < ?")
+        .bind(now_ts - STALE_SCHEDULED_SECS)
+        .execute(pool)
+        .await?;
+
+    let today = now.date_naive().format("%Y-%m-%d").to_string();
+    let (day_start, day_end) =
+        crate::analytics::play_stats::local_date_range_utc(&now.timezone(), &today, &today)
+            .unwrap_or((now_ts - 86_400, now_ts));
+
+    let rows = sqlx::query(
+        r#"
+        SELECT c.*,
+            (SELECT COUNT(*) FROM traffic_spot_log l
+              WHERE l.campaign_id = c.id AND l.status IN ('aired', 'scheduled')
+                AND l.scheduled_at >= ? AND l.scheduled_at < ?) AS plays_today,
+            (SELECT MAX(COALESCE(l.aired_at, l.scheduled_at)) FROM traffic_spot_log l
+              JOIN traffic_campaigns o ON o.id = l.campaign_id
+              WHERE l.status IN ('aired', 'scheduled')
+                AND (o.id = c.id OR (c.sponsor_category != ''
+                     AND lower(o.sponsor_category) = lower(c.sponsor_category)))) AS category_last_aired_at,
+            (SELECT MIN(m.id) FROM traffic_spot_log m
+              WHERE m.campaign_id = c.id AND m.status = 'missed'
+                AND NOT EXISTS (SELECT 1 FROM traffic_spot_log g
+                                 WHERE g.make_good_for = m.id
+                                   AND g.status IN ('aired', 'scheduled'))) AS owed_make_good
+        FROM traffic_campaigns c
+        WHERE c.enabled = 1 AND c.start_date <= ? AND c.end_date >= ?
+        "#,
+    )
+    .bind(day_start)
+    .bind(day_end)
+    .bind(&today)
+    .bind(&today)
+    .fetch_all(pool)
+    .await?;
+
+    let candidates = rows
+        .iter()
+        .map(|r| SpotCandidate {
+            campaign: row_to_campaign(r),
+            plays_today: r.get::<i64, _>("plays_today").max(0) as u32,
+            category_last_aired_at: r.get("category_last_aired_at"),
+            owed_make_good: r.get("owed_make_good"),
+        })
+        .collect();
+
+    let mut planned = Vec::new();
+    for (campaign, make_good_for) in select_spots(ad_break, candidates, now_ts) {
+        let Some(campaign_id) = campaign.id else {
+            continue;
+        };
+        let log_id = sqlx::query(
+            "INSERT INTO traffic_spot_log (campaign_id, break_id, scheduled_at, status, make_good_for) VALUES (?, ?, ?, 'scheduled', ?)",
+        )
+        .bind(campaign_id)
+        .bind(ad_break.id)
+        .bind(occurrence)
+        .bind(make_good_for)
+        .execute(pool)
+        .await?
+        .last_insert_rowid();
+        planned.push(PlannedSpot {
+            log_id,
+            campaign,
+            make_good_for,
+        });
+    }
+    Ok(planned)
+}
+
+pub async fn mark_spot_aired(
+    pool: &SqlitePool,
+    log_id: i64,
+    aired_at: i64,
+    played_ms: i64,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "UPDATE traffic_spot_log SET status = 'aired', aired_at = ?, played_ms = ? WHERE id = ?",
+    )
+    .bind(aired_at)
+    .bind(played_ms)
+    .bind(log_id)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+pub async fn mark_spot_missed(pool: &SqlitePool, log_id: i64) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "UPDATE traffic_spot_log SET status = 'missed' WHERE id = ? AND status = 'scheduled'",
+    )
+    .bind(log_id)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn campaign(id: i64, category: &str, priority: i32) -> Campaign {
+        Campaign {
+            id: Some(id),
+            advertiser: format!("Advertiser {id}"),
+            title: "Spot".to_string(),
+            sponsor_category: category.to_string(),
+            song_id: Some(id),
+            file_path: None,
+            duration_secs: 30,
+            start_date: "2025-01-01".to_string(),
+            end_date: "2025-12-31".to_string(),
+            max_plays_per_day: 4,
+            separation_minutes: 30,
+            priority,
+            enabled: true,
+        }
+    }
+
+    fn candidate(campaign: Campaign) -> SpotCandidate {
+        SpotCandidate {
+            campaign,
+            plays_today: 0,
+            category_last_aired_at: None,
+            owed_make_good: None,
+        }
+    }
+
+    #[test]
+    fn make_goods_first_and_one_sponsor_per_category() {
+        let ad_break = AdBreak {
+            id: Some(1),
+            name: "Break :20".to_string(),
+            minute: 20,
+            hours: vec![],
+            days: vec![],
+            max_spots: 3,
+            max_duration_secs: 90,
+            grace_minutes: 10,
+            enabled: true,
+            last_fired_at: None,
+        };
+        let now = 1_000_000;
+        let mut owed = candidate(campaign(1, "telecom", 0));
+        owed.owed_make_good = Some(77);
+        let rival = candidate(campaign(2, "Telecom", 9));
+        let auto = candidate(campaign(3, "auto", 5));
+        let mut capped = candidate(campaign(4, "food", 9));
+        capped.plays_today = 4;
+        let mut too_close = candidate(campaign(5, "retail", 9));
+        too_close.category_last_aired_at = Some(now - 10 * 60);
+        let filler = candidate(campaign(6, "", 1));
+
+        let spots = select_spots(
+            &ad_break,
+            vec![rival, auto, capped, too_close, owed, filler],
+            now,
+        );
+        let ids: Vec<_> = spots.iter().map(|(c, mg)| (c.id.unwrap(), *mg)).collect();
+        assert_eq!(ids, vec![(1, Some(77)), (3, None), (6, None)]);
+    }
+}
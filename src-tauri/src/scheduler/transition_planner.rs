@@ -1,3 +1,6 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::{Mutex, OnceLock};
+
 use serde::{Deserialize, Serialize};
 
 use crate::audio::crossfade::DeckId;
@@ -11,7 +14,7 @@ pub struct DeckSnapshot {
     pub duration_ms: u64,
 }
 
-#[derive(Debug, Clone, Copy, Default)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub struct TransitionMarkers {
     pub intro_start_ms: Option<u64>,
     pub intro_end_ms: Option<u64>,
@@ -19,6 +22,60 @@ pub struct TransitionMarkers {
     pub outro_end_ms: Option<u64>,
     pub first_sound_ms: Option<u64>,
     pub last_sound_ms: Option<u64>,
+    /// The song's own crossfade point (SAM `xfade` cue)
+    #[serde(default)]
+    pub xfade_ms: Option<u64>,
+}
+
+/// Songs whose cue points changed since the AutoDJ loop last looked.
+#[derive(Debug, Default)]
+struct StaleMarkers {
+    all: bool,
+    songs: HashSet<i64>,
+}
+
+static STALE_MARKERS: OnceLock<Mutex<StaleMarkers>> = OnceLock::new();
+
+fn stale_cell() -> &'static Mutex<StaleMarkers> {
+    STALE_MARKERS.get_or_init(|| Mutex::new(StaleMarkers::default()))
+}
+
+/// Drop cached markers for `song_id` (`None` = every song) after a cue edit.
+pub fn invalidate_markers(song_id: Option<i64>) {
+    let mut stale = stale_cell().lock().unwrap();
+    match song_id {
+        Some(id) => {
+            stale.songs.insert(id);
+        }
+        None => stale.all = true,
+    }
+}
+
+/// In-memory markers keyed by song and the duration they were computed
+/// for, so repeat lookups skip the DB and the file system.
+#[derive(Debug, Default)]
+pub struct MarkerCache {
+    entries: HashMap<(i64, u64), TransitionMarkers>,
+}
+
+impl MarkerCache {
+    pub fn get(&mut self, song_id: i64, duration_ms: u64) -> Option<TransitionMarkers> {
+        let stale = std::mem::take(&mut *stale_cell().lock().unwrap());
+        if stale.all {
+            self.entries.clear();
+        } else if !stale.songs.is_empty() {
+            self.entries.retain(|(id, _), _| !stale.songs.contains(id));
+        }
+        self.entries.get(&(song_id, duration_ms)).copied()
+    }
+
+    pub fn insert(&mut self, song_id: i64, duration_ms: u64, markers: TransitionMarkers) {
+        self.entries.insert((song_id, duration_ms), markers);
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -279,6 +336,27 @@ pub fn calculate_transition_plan(
 mod tests {
     use super::*;
 
+    #[test]
+    fn marker_cache_hits_until_invalidated() {
+        let markers = TransitionMarkers {
+            xfade_ms: Some(180_000),
+            ..Default::default()
+        };
+        let mut cache = MarkerCache::default();
+        cache.insert(9_001, 200_000, markers);
+        cache.insert(9_002, 150_000, markers);
+        assert_eq!(cache.get(9_001, 200_000), Some(markers));
+        // A re-encoded file with a new length is a miss.
+        assert_eq!(cache.get(9_001, 210_000), None);
+
+        invalidate_markers(Some(9_001));
+        assert_eq!(cache.get(9_001, 200_000), None);
+        assert_eq!(cache.get(9_002, 150_000), Some(markers));
+
+        invalidate_markers(None);
+        assert_eq!(cache.get(9_002, 150_000), None);
+    }
+
     fn cfg(mode: AutoTransitionMode, transition_time_sec: i32) -> MixxxPlannerConfig {
         MixxxPlannerConfig {
             enabled: true,
@@ -0,0 +1,263 @@
+/// Voice track placement
+///
+/// A voice track is a pre-recorded link that sits between two songs. Its
+/// placement says when it starts relative to the end of the previous song
+/// (talking over the outro), how much of the recording is trimmed, its gain,
+/// and how early the next song starts under its tail (talking over the intro).
+/// Fade points shape each segment: the previous song's outro, the link
+/// itself and the next song's intro. The AutoDJ runtime plays it on the
+/// Voice FX deck.
+use serde::{Deserialize, Serialize};
+use sqlx::sqlite::SqlitePool;
+use sqlx::Row;
+
+// ── Data model ────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VoiceTrackPlacement {
+    pub id: Option<i64>,
+    pub title: String,
+    pub file_path: String,
+    /// Recording length; filled from the deck once loaded if unknown
+    pub duration_ms: Option<u64>,
+    /// Song the link follows; the placement fires when this song is on air
+    pub prev_song_id: Option<i64>,
+    /// Song the link introduces (informational; the next deck plays whatever is loaded)
+    pub next_song_id: Option<i64>,
+    /// Start this many ms before the previous song ends (0 = after it ends)
+    pub prev_overlap_ms: u64,
+    /// Start the next song this many ms before the link ends (0 = after it ends)
+    pub next_overlap_ms: u64,
+    /// Skip this much from the head of the recording
+    pub trim_start_ms: u64,
+    /// Drop this much from the tail of the recording
+    pub trim_end_ms: u64,
+    /// Playback gain in dB (capped at 0 dB by the deck fader)
+    pub gain_db: f32,
+    #[serde(default)]
+    pub fades: VoiceTrackFades,
+    pub played_at: Option<i64>,
+}
+
+/// Fade lengths per segment; 0 = cut.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VoiceTrackFades {
+    /// Fade the previous song out over this long once the link starts
+    pub prev_fade_out_ms: u64,
+    /// Fade the link in from silence
+    pub fade_in_ms: u64,
+    /// Fade the link out, ending at its trimmed end
+    pub fade_out_ms: u64,
+    /// Bring the next song up from silence when it starts under the link
+    pub next_fade_in_ms: u64,
+}
+
+impl VoiceTrackPlacement {
+    /// End of the audible part of the recording, in file time.
+    pub fn end_ms(&self, duration_ms: u64) -> u64 {
+        duration_ms
+            .saturating_sub(self.trim_end_ms)
+            .max(self.trim_start_ms)
+    }
+
+    /// Whether the link should start given the previous song's remaining time.
+    pub fn should_start(&self, prev_remaining_ms: u64, prev_playing: bool) -> bool {
+        !prev_playing || prev_remaining_ms <= self.prev_overlap_ms
+    }
+
+    /// Whether the next song should start given the link's file position.
+    pub fn should_start_next(&self, voice_position_ms: u64, duration_ms: u64) -> bool {
+        voice_position_ms
+            >= self
+                .end_ms(duration_ms)
+                .saturating_sub(self.next_overlap_ms)
+    }
+
+    /// Length of the link's fade-out once it is due at `voice_position_ms`
+    /// (what is left of the audible part), otherwise `None`.
+    pub fn fade_out_due(&self, voice_position_ms: u64, duration_ms: u64) -> Option<u64> {
+        let end = self.end_ms(duration_ms);
+        let remaining = end.saturating_sub(voice_position_ms);
+        (self.fades.fade_out_ms > 0 && remaining > 0 && remaining <= self.fades.fade_out_ms)
+            .then_some(remaining)
+    }
+
+    /// Linear fader value for `gain_db`.
+    pub fn fader_gain(&self) -> f32 {
+        10.0f32.powf(self.gain_db.min(0.0) / 20.0)
+    }
+}
+
+// ── DB helpers ────────────────────────────────────────────────────────────────
+
+fn row_to_placement(r: &sqlx::sqlite::SqliteRow) -> VoiceTrackPlacement {
+    VoiceTrackPlacement {
+        id: r.get("id"),
+        title: r.get("title"),
+        file_path: r.get("file_path"),
+        duration_ms: r
+            .get::<Option<i64>, _>("duration_ms")
+            .map(|v| v.max(0) as u64),
+        prev_song_id: r.get("prev_song_id"),
+        next_song_id: r.get("next_song_id"),
+        prev_overlap_ms: r.get::<i64, _>("prev_overlap_ms").max(0) as u64,
+        next_overlap_ms: r.get::<i64, _>("next_overlap_ms").max(0) as u64,
+        trim_start_ms: r.get::<i64, _>("trim_start_ms").max(0) as u64,
+        trim_end_ms: r.get::<i64, _>("trim_end_ms").max(0) as u64,
+        gain_db: r.get::<f64, _>("gain_db") as f32,
+        fades: VoiceTrackFades {
+            prev_fade_out_ms: r.get::<i64, _>("prev_fade_out_ms").max(0) as u64,
+            fade_in_ms: r.get::<i64, _>("fade_in_ms").max(0) as u64,
+            fade_out_ms: r.get::<i64, _>("fade_out_ms").max(0) as u64,
+            next_fade_in_ms: r.get::<i64, _>("next_fade_in_ms").max(0) as u64,
+        },
+        played_at: r.get("played_at"),
+    }
+}
+
+pub async fn get_voice_tracks(
+    pool: &SqlitePool,
+    include_played: bool,
+) -> Result<Vec<VoiceTrackPlacement>, sqlx::Error> {
+    let rows = sqlx::query(
+        "SELECT * FROM voice_tracks WHERE (? OR played_at IS NULL) ORDER BY created_at, id",
+    )
+    .bind(include_played)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows.iter().map(row_to_placement).collect())
+}
+
+/// Oldest unplayed link placed after `prev_song_id`.
+pub async fn get_pending_voice_track_after(
+    pool: &SqlitePool,
+    prev_song_id: i64,
+) -> Result<Option<VoiceTrackPlacement>, sqlx::Error> {
+    let row = sqlx::query(
+        "SELECT * FROM voice_tracks WHERE prev_song_id = ? AND played_at IS NULL \
+         ORDER BY created_at, id LIMIT 1",
+    )
+    .bind(prev_song_id)
+    .fetch_optional(pool)
+    .await?;
+    Ok(row.as_ref().map(row_to_placement))
+}
+
+pub async fn save_voice_track(
+    pool: &SqlitePool,
+    vt: &VoiceTrackPlacement,
+) -> Result<i64, sqlx::Error> {
+    let row = sqlx::query(
+        r#"
+        INSERT INTO voice_tracks
+            (id, title, file_path, duration_ms, prev_song_id, next_song_id,
+             prev_overlap_ms, next_overlap_ms, trim_start_ms, trim_end_ms, gain_db,
+             prev_fade_out_ms, fade_in_ms, fade_out_ms, next_fade_in_ms)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        ON CONFLICT(id) DO UPDATE SET
+            title = excluded.title,
+            file_path = excluded.file_path,
+            duration_ms = excluded.duration_ms,
+            prev_song_id = excluded.prev_song_id,
+            next_song_id = excluded.next_song_id,
+            prev_overlap_ms = excluded.prev_overlap_ms,
+            next_overlap_ms = excluded.next_overlap_ms,
+            trim_start_ms = excluded.trim_start_ms,
+            trim_end_ms = excluded.trim_end_ms,
+            gain_db = excluded.gain_db,
+            prev_fade_out_ms = excluded.prev_fade_out_ms,
+            fade_in_ms = excluded.fade_in_ms,
+            fade_out_ms = excluded.fade_out_ms,
+            next_fade_in_ms = excluded.next_fade_in_ms
+        RETURNING id
+        "#,
+    )
+    .bind(vt.id)
+    .bind(&vt.title)
+    .bind(&vt.file_path)
+    .bind(vt.duration_ms.map(|v| v as i64))
+    .bind(vt.prev_song_id)
+    .bind(vt.next_song_id)
+    .bind(vt.prev_overlap_ms as i64)
+    .bind(vt.next_overlap_ms as i64)
+    .bind(vt.trim_start_ms as i64)
+    .bind(vt.trim_end_ms as i64)
+    .bind(vt.gain_db as f64)
+    .bind(vt.fades.prev_fade_out_ms as i64)
+    .bind(vt.fades.fade_in_ms as i64)
+    .bind(vt.fades.fade_out_ms as i64)
+    .bind(vt.fades.next_fade_in_ms as i64)
+    .fetch_one(pool)
+    .await?;
+    Ok(row.get("id"))
+}
+
+pub async fn delete_voice_track(pool: &SqlitePool, id: i64) -> Result<(), sqlx::Error> {
+    sqlx::query("DELETE FROM voice_tracks WHERE id = ?")
+        .bind(id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+pub async fn mark_voice_track_played(pool: &SqlitePool, id: i64) -> Result<(), sqlx::Error> {
+    sqlx::query("UPDATE voice_tracks SET played_at = strftime('%s','now') WHERE id = ?")
+        .bind(id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn placement() -> VoiceTrackPlacement {
+        VoiceTrackPlacement {
+            id: Some(1),
+            title: "link".to_string(),
+            file_path: "/tmp/link.wav".to_string(),
+            duration_ms: Some(20_000),
+            prev_song_id: Some(10),
+            next_song_id: Some(11),
+            prev_overlap_ms: 4_000,
+            next_overlap_ms: 3_000,
+            trim_start_ms: 500,
+            trim_end_ms: 1_000,
+            gain_db: -3.0,
+            fades: VoiceTrackFades {
+                fade_out_ms: 2_000,
+                ..VoiceTrackFades::default()
+            },
+            played_at: None,
+        }
+    }
+
+    #[test]
+    fn starts_over_previous_outro() {
+        let vt = placement();
+        assert!(!vt.should_start(4_001, true));
+        assert!(vt.should_start(4_000, true));
+        assert!(vt.should_start(30_000, false));
+    }
+
+    #[test]
+    fn next_song_starts_before_trimmed_end() {
+        let vt = placement();
+        // Audible end is 19 s; next song is due 3 s earlier.
+        assert_eq!(vt.end_ms(20_000), 19_000);
+        assert!(!vt.should_start_next(15_999, 20_000));
+        assert!(vt.should_start_next(16_000, 20_000));
+    }
+
+    #[test]
+    fn fade_out_ends_at_trimmed_end() {
+        let vt = placement();
+        // Audible end is 19 s with a 2 s fade-out.
+        assert_eq!(vt.fade_out_due(16_999, 20_000), None);
+        assert_eq!(vt.fade_out_due(17_000, 20_000), Some(2_000));
+        assert_eq!(vt.fade_out_due(18_500, 20_000), Some(500));
+        assert_eq!(vt.fade_out_due(19_000, 20_000), None);
+    }
+}
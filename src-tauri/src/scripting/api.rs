@@ -1,9 +1,26 @@
 /// `scripting/api.rs` — registers all Lua global functions
 ///
 /// Provides the full Phase 5 API surface to each script VM:
-///   deck, queue, media, encoder, schedule, station, log, http, store
-use mlua::{Lua, Result as LuaResult, Value};
+///   deck, queue, media, encoder, timer, schedule, station, log, http, store,
+///   plus the `on_*` event hooks. `API_REFERENCE` documents every call; it is
+///   exposed to scripts as the `api` table and to the editor UI.
+///
+/// Station calls run on the script's blocking thread and wait on the async
+/// DB and engine work they need; without an attached app they raise
+/// "Station is not ready".
+use mlua::{
+    Error as LuaError, FromLuaMulti, Function, IntoLuaMulti, Lua, Result as LuaResult, Table, Value,
+};
+use serde::Serialize;
 use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager};
+
+use super::sandbox;
+use crate::audio::deck::StopReason;
+use crate::commands::{audio_commands, encoder_commands};
+use crate::db::sam;
+use crate::state::AppState;
 
 /// Per-script log output (log.info / log.warn / log.error calls).
 #[derive(Debug, Clone)]
@@ -18,25 +35,274 @@ pub type ScriptLog = Arc<Mutex<Vec<ScriptLogEntry>>>;
 /// Per-script key/value store (persisted to DB externally).
 pub type ScriptStore = Arc<Mutex<std::collections::HashMap<String, serde_json::Value>>>;
 
+/// Event hooks a script can register handlers for, e.g.
+/// `on_track_start(function(e) ... end)`. Names match `ScriptEvent::trigger_type`.
+pub const HOOKS: &[&str] = &[
+    "on_track_start",
+    "on_track_end",
+    "on_crossfade_start",
+    "on_queue_empty",
+    "on_request_received",
+    "on_silence",
+    "on_hour",
+    "on_encoder_connect",
+    "on_encoder_disconnect",
+    "on_health_alert",
+    "on_show_start",
+    "on_schedule",
+];
+
+/// Registry key of the table holding hook handlers.
+const HOOK_REGISTRY: &str = "desizone_hooks";
+
+/// Shortest `timer.every` interval.
+const MIN_TIMER_INTERVAL_MS: u64 = 100;
+/// Longest single `timer.sleep`.
+const MAX_SLEEP_MS: u64 = 60_000;
+const MEDIA_SEARCH_LIMIT: u32 = 50;
+/// Songs considered by `media.get_random` and `queue.add_playlist`.
+const CATEGORY_SCAN_LIMIT: u32 = 5000;
+
+/// One documented API call.
+#[derive(Debug, Clone, Serialize)]
+pub struct ApiEntry {
+    pub name: &'static str,
+    pub signature: &'static str,
+    pub description: &'static str,
+}
+
+macro_rules! api {
+    ($(($name:literal, $sig:literal, $desc:literal)),* $(,)?) => {
+        &[$(ApiEntry { name: $name, signature: $sig, description: $desc }),*]
+    };
+}
+
+/// Every Lua API call, in the order shown to script authors.
+/// Decks are "deck_a", "deck_b", "sound_fx", "aux_1", "aux_2" and "voice_fx";
+/// queue positions are 1-based.
+pub const API_REFERENCE: &[ApiEntry] = api![
+    ("deck.play", "deck.play(deck)", "Start or resume playback"),
+    ("deck.pause", "deck.pause(deck)", "Pause playback"),
+    (
+        "deck.stop",
+        "deck.stop(deck)",
+        "Stop and rewind to the start"
+    ),
+    (
+        "deck.next",
+        "deck.next(deck)",
+        "Skip the current track (counts as a skip)"
+    ),
+    (
+        "deck.load",
+        "deck.load(deck, song_id)",
+        "Load a library song with its gain trim"
+    ),
+    (
+        "deck.seek",
+        "deck.seek(deck, position_ms)",
+        "Jump to a position"
+    ),
+    (
+        "deck.get_position",
+        "deck.get_position(deck) -> ms",
+        "Playback position"
+    ),
+    (
+        "deck.state",
+        "deck.state(deck) -> table|nil",
+        "Full deck state (song_id, state, position_ms, duration_ms, ...)"
+    ),
+    (
+        "queue.get",
+        "queue.get() -> {entry}",
+        "Queue entries in play order, each with its song"
+    ),
+    (
+        "queue.add",
+        "queue.add(song_id) -> queue_id",
+        "Append a song to the queue"
+    ),
+    (
+        "queue.add_at",
+        "queue.add_at(song_id, position) -> queue_id",
+        "Insert a song at a queue position"
+    ),
+    (
+        "queue.remove",
+        "queue.remove(position) -> bool",
+        "Remove the entry at a queue position"
+    ),
+    ("queue.clear", "queue.clear() -> count", "Empty the queue"),
+    (
+        "queue.add_playlist",
+        "queue.add_playlist(category) -> count",
+        "Append every song in a category (id or name)"
+    ),
+    (
+        "media.search",
+        "media.search(query, limit?) -> {song}",
+        "Search artist and title (default 50 results)"
+    ),
+    (
+        "media.get",
+        "media.get(song_id) -> song|nil",
+        "Song details and tags"
+    ),
+    (
+        "media.get_random",
+        "media.get_random(category) -> song|nil",
+        "A random song from a category (id or name)"
+    ),
+    (
+        "encoder.list",
+        "encoder.list() -> {encoder}",
+        "Encoders with status and listeners"
+    ),
+    (
+        "encoder.start",
+        "encoder.start(id)",
+        "Start an encoder (station ID gate applies)"
+    ),
+    ("encoder.stop", "encoder.stop(id)", "Stop an encoder"),
+    (
+        "encoder.set_stream_title",
+        "encoder.set_stream_title(title, artist?)",
+        "Push a stream title to every encoder sending metadata"
+    ),
+    (
+        "encoder.get_listeners",
+        "encoder.get_listeners(id?) -> count",
+        "Listeners on one encoder, or all when id is nil"
+    ),
+    (
+        "timer.after",
+        "timer.after(ms, fn) -> id",
+        "Call fn once after a delay"
+    ),
+    (
+        "timer.every",
+        "timer.every(ms, fn) -> id",
+        "Call fn repeatedly (at least 100 ms apart)"
+    ),
+    ("timer.cancel", "timer.cancel(id)", "Cancel a pending timer"),
+    (
+        "timer.sleep",
+        "timer.sleep(ms)",
+        "Pause the script (at most 60 s per call)"
+    ),
+    (
+        "http.get",
+        "http.get(url) -> {status, body}",
+        "Blocking HTTP GET"
+    ),
+    (
+        "http.post",
+        "http.post(url, body_json) -> {status, body}",
+        "Blocking HTTP POST with a JSON body"
+    ),
+    (
+        "store.set",
+        "store.set(key, value)",
+        "Save a value for this script"
+    ),
+    ("store.get", "store.get(key) -> value", "Read a saved value"),
+    ("store.delete", "store.delete(key)", "Forget a saved value"),
+    ("log.info", "log.info(message)", "Write to the script log"),
+    (
+        "log.warn",
+        "log.warn(message)",
+        "Write a warning to the script log"
+    ),
+    (
+        "log.error",
+        "log.error(message)",
+        "Write an error to the script log"
+    ),
+    (
+        "on_track_start",
+        "on_track_start(fn(e))",
+        "e: id, title, artist, album, duration_ms, category"
+    ),
+    (
+        "on_track_end",
+        "on_track_end(fn(e))",
+        "A track finished naturally; e: id, title"
+    ),
+    (
+        "on_silence",
+        "on_silence(fn(e))",
+        "Dead air raised or cleared; e: resolved, message"
+    ),
+    (
+        "on_request_received",
+        "on_request_received(fn(e))",
+        "e: song_id, song_title, requester"
+    ),
+    (
+        "on_crossfade_start",
+        "on_crossfade_start(fn(e))",
+        "e: outgoing_id, outgoing_title, incoming_id, incoming_title"
+    ),
+    (
+        "on_queue_empty",
+        "on_queue_empty(fn(e))",
+        "The queue ran dry"
+    ),
+    ("on_hour", "on_hour(fn(e))", "e: hour (0-23)"),
+    (
+        "on_encoder_connect",
+        "on_encoder_connect(fn(e))",
+        "e: encoder_id"
+    ),
+    (
+        "on_encoder_disconnect",
+        "on_encoder_disconnect(fn(e))",
+        "e: encoder_id, reason"
+    ),
+    (
+        "on_show_start",
+        "on_show_start(fn(e))",
+        "A scheduled show went on air; e: show_id, name"
+    ),
+    (
+        "on_schedule",
+        "on_schedule(fn(e))",
+        "One of the script's cron schedules came due; e: cron"
+    ),
+    (
+        "on_health_alert",
+        "on_health_alert(fn(e))",
+        "e: kind, message, resolved, encoder_id, deck"
+    ),
+];
+
 /// Register all DesiZone Lua API globals on `lua`.
 ///
 /// `log_sink` — entries written by log.info/warn/error land here.
 /// `store`    — key/value store for the script (pre-loaded from DB).
+/// `app`      — the running station; `None` leaves station calls failing.
+/// `timers`   — callbacks scheduled with timer.after/every, run by the engine.
 pub fn register_all(
     lua: &Lua,
     script_id: i64,
     log_sink: ScriptLog,
     store: ScriptStore,
+    app: Option<AppHandle>,
+    timers: TimerQueue,
 ) -> LuaResult<()> {
     register_log(lua, script_id, log_sink)?;
     register_store(lua, store)?;
-    register_deck(lua)?;
-    register_queue(lua)?;
-    register_media(lua)?;
-    register_encoder(lua)?;
+    register_deck(lua, &app)?;
+    register_queue(lua, &app)?;
+    register_media(lua, &app)?;
+    register_encoder(lua, &app)?;
+    register_timer(lua, timers)?;
     register_schedule(lua)?;
     register_station(lua)?;
     register_http(lua)?;
+    register_hooks(lua)?;
+    register_reference(lua)?;
     Ok(())
 }
 
@@ -108,39 +374,154 @@ fn register_store(lua: &Lua, store: ScriptStore) -> LuaResult<()> {
     Ok(())
 }
 
+// ── Station access ────────────────────────────────────────────────────────────
+
+/// Wrap `f` as a Lua function that needs the running station.
+fn station_fn<A, R, F>(lua: &Lua, app: &Option<AppHandle>, f: F) -> LuaResult<Function>
+where
+    A: FromLuaMulti,
+    R: IntoLuaMulti,
+    F: Fn(&Lua, &AppHandle, A) -> LuaResult<R> + Send + 'static,
+{
+    let app = app.clone();
+    lua.create_function(move |lua, args: A| {
+        let app = app
+            .as_ref()
+            .ok_or_else(|| LuaError::runtime("Station is not ready"))?;
+        f(lua, app, args)
+    })
+}
+
+/// Scripts run on a blocking thread, so async station calls are awaited inline.
+fn block_on<F: std::future::Future>(future: F) -> F::Output {
+    tauri::async_runtime::block_on(future)
+}
+
+fn sam_pool(app: &AppHandle) -> LuaResult<sqlx::MySqlPool> {
+    let state = app.state::<AppState>();
+    block_on(async { state.sam_db.read().await.as_ref().cloned() })
+        .ok_or_else(|| LuaError::runtime("SAM database is not connected"))
+}
+
+fn to_lua<T: Serialize>(lua: &Lua, value: &T) -> LuaResult<Value> {
+    let json = serde_json::to_value(value).map_err(LuaError::runtime)?;
+    json_to_lua_value(lua, &json)
+}
+
+/// Songs in a SAM category given by id or (case-insensitive) name.
+fn category_songs(pool: &sqlx::MySqlPool, category: Value) -> LuaResult<Vec<sam::SamSong>> {
+    let category_id = match category {
+        Value::Integer(id) => id,
+        Value::Number(n) => n as i64,
+        Value::String(name) => {
+            let name = name.to_string_lossy();
+            block_on(sam::get_categories(pool))
+                .map_err(LuaError::runtime)?
+                .into_iter()
+                .find(|c| c.catname.eq_ignore_ascii_case(&name))
+                .map(|c| c.id)
+                .ok_or_else(|| LuaError::runtime(format!("Unknown category: {name}")))?
+        }
+        _ => return Err(LuaError::runtime("Category must be an id or a name")),
+    };
+    block_on(sam::get_songs_in_category(
+        pool,
+        category_id,
+        CATEGORY_SCAN_LIMIT,
+        0,
+    ))
+    .map_err(LuaError::runtime)
+}
+
 // ── deck ──────────────────────────────────────────────────────────────────────
 
-fn register_deck(lua: &Lua) -> LuaResult<()> {
+fn register_deck(lua: &Lua, app: &Option<AppHandle>) -> LuaResult<()> {
     let tbl = lua.create_table()?;
 
-    // Stub implementations — in a full integration these would call into AppState.
-    // Scripts can still call these; no-ops will be logged.
     tbl.set(
         "play",
-        lua.create_function(|_, deck_id: String| {
-            log::info!("[script] deck.play({})", deck_id);
-            Ok(())
+        station_fn(lua, app, |_, app, deck: String| {
+            let deck_id = audio_commands::parse_deck(&deck).map_err(LuaError::runtime)?;
+            let state = app.state::<AppState>();
+            let result = state.engine.lock().unwrap().play(deck_id);
+            result.map_err(LuaError::runtime)
+        })?,
+    )?;
+    tbl.set(
+        "pause",
+        station_fn(lua, app, |_, app, deck: String| {
+            let deck_id = audio_commands::parse_deck(&deck).map_err(LuaError::runtime)?;
+            let state = app.state::<AppState>();
+            let result = state.engine.lock().unwrap().pause(deck_id);
+            result.map_err(LuaError::runtime)
         })?,
     )?;
     tbl.set(
         "stop",
-        lua.create_function(|_, deck_id: String| {
-            log::info!("[script] deck.stop({})", deck_id);
-            Ok(())
+        station_fn(lua, app, |_, app, deck: String| {
+            let deck_id = audio_commands::parse_deck(&deck).map_err(LuaError::runtime)?;
+            audio_commands::stop(&app.state::<AppState>(), deck_id).map_err(LuaError::runtime)
+        })?,
+    )?;
+    tbl.set(
+        "next",
+        station_fn(lua, app, |_, app, deck: String| {
+            let deck_id = audio_commands::parse_deck(&deck).map_err(LuaError::runtime)?;
+            let state = app.state::<AppState>();
+            let result = state
+                .engine
+                .lock()
+                .unwrap()
+                .stop_with_completion(deck_id, StopReason::Skipped);
+            result.map_err(LuaError::runtime)
+        })?,
+    )?;
+    tbl.set(
+        "seek",
+        station_fn(lua, app, |_, app, (deck, position_ms): (String, u64)| {
+            let deck_id = audio_commands::parse_deck(&deck).map_err(LuaError::runtime)?;
+            let state = app.state::<AppState>();
+            let result = state.engine.lock().unwrap().seek(deck_id, position_ms);
+            result.map_err(LuaError::runtime)
         })?,
     )?;
     tbl.set(
         "load",
-        lua.create_function(|_, (deck_id, song_id): (String, i64)| {
-            log::info!("[script] deck.load({}, {})", deck_id, song_id);
-            Ok(())
+        station_fn(lua, app, |_, app, (deck, song_id): (String, i64)| {
+            let deck_id = audio_commands::parse_deck(&deck).map_err(LuaError::runtime)?;
+            let pool = sam_pool(app)?;
+            let song = block_on(sam::get_song(&pool, song_id))
+                .map_err(LuaError::runtime)?
+                .ok_or_else(|| LuaError::runtime(format!("Song {song_id} not found")))?;
+            let state = app.state::<AppState>();
+            let path = match &state.local_db {
+                Some(local) => block_on(async {
+                    crate::db::path_rules::load_translator(local)
+                        .await
+                        .translate(&song.filename)
+                }),
+                None => song.filename,
+            };
+            block_on(audio_commands::load(&state, deck_id, path, Some(song_id)))
+                .map_err(LuaError::runtime)
         })?,
     )?;
     tbl.set(
         "get_position",
-        lua.create_function(|_, deck_id: String| {
-            log::info!("[script] deck.get_position({})", deck_id);
-            Ok(0u64)
+        station_fn(lua, app, |_, app, deck: String| {
+            let deck_id = audio_commands::parse_deck(&deck).map_err(LuaError::runtime)?;
+            let state = app.state::<AppState>();
+            let deck_state = state.engine.lock().unwrap().get_deck_state(deck_id);
+            Ok(deck_state.map(|d| d.position_ms).unwrap_or(0))
+        })?,
+    )?;
+    tbl.set(
+        "state",
+        station_fn(lua, app, |lua, app, deck: String| {
+            let deck_id = audio_commands::parse_deck(&deck).map_err(LuaError::runtime)?;
+            let state = app.state::<AppState>();
+            let deck_state = state.engine.lock().unwrap().get_deck_state(deck_id);
+            to_lua(lua, &deck_state)
         })?,
     )?;
 
@@ -150,48 +531,84 @@ fn register_deck(lua: &Lua) -> LuaResult<()> {
 
 // ── queue ─────────────────────────────────────────────────────────────────────
 
-fn register_queue(lua: &Lua) -> LuaResult<()> {
+fn register_queue(lua: &Lua, app: &Option<AppHandle>) -> LuaResult<()> {
     let tbl = lua.create_table()?;
 
     tbl.set(
         "get",
-        lua.create_function(|lua_ctx, ()| {
-            lua_ctx.create_table() // empty table — full integration would call queue commands
+        station_fn(lua, app, |lua, app, ()| {
+            let pool = sam_pool(app)?;
+            let entries = block_on(sam::get_queue(&pool)).map_err(LuaError::runtime)?;
+            to_lua(lua, &entries)
         })?,
     )?;
     tbl.set(
         "add",
-        lua.create_function(|_, song_id: i64| {
-            log::info!("[script] queue.add({})", song_id);
-            Ok(())
+        station_fn(lua, app, |_, app, song_id: i64| {
+            let pool = sam_pool(app)?;
+            block_on(sam::add_to_queue(&pool, song_id)).map_err(LuaError::runtime)
         })?,
     )?;
     tbl.set(
         "add_at",
-        lua.create_function(|_, (song_id, pos): (i64, u32)| {
-            log::info!("[script] queue.add_at({}, {})", song_id, pos);
-            Ok(())
+        station_fn(lua, app, |_, app, (song_id, position): (i64, usize)| {
+            let pool = sam_pool(app)?;
+            block_on(async {
+                let queue_id = sam::add_to_queue(&pool, song_id).await?;
+                let mut ids: Vec<i64> = sam::get_queue(&pool)
+                    .await?
+                    .into_iter()
+                    .map(|e| e.id)
+                    .filter(|id| *id != queue_id)
+                    .collect();
+                ids.insert(position.clamp(1, ids.len() + 1) - 1, queue_id);
+                sam::reorder_queue(&pool, &ids).await?;
+                Ok::<_, sqlx::Error>(queue_id)
+            })
+            .map_err(LuaError::runtime)
         })?,
     )?;
     tbl.set(
         "remove",
-        lua.create_function(|_, pos: u32| {
-            log::info!("[script] queue.remove({})", pos);
-            Ok(())
+        station_fn(lua, app, |_, app, position: usize| {
+            let pool = sam_pool(app)?;
+            block_on(async {
+                let entries = sam::get_queue(&pool).await?;
+                let Some(entry) = position.checked_sub(1).and_then(|i| entries.get(i)) else {
+                    return Ok(false);
+                };
+                sam::remove_from_queue(&pool, entry.id).await?;
+                Ok::<_, sqlx::Error>(true)
+            })
+            .map_err(LuaError::runtime)
         })?,
     )?;
     tbl.set(
         "clear",
-        lua.create_function(|_, ()| {
-            log::info!("[script] queue.clear()");
-            Ok(())
+        station_fn(lua, app, |_, app, ()| {
+            let pool = sam_pool(app)?;
+            block_on(async {
+                let entries = sam::get_queue(&pool).await?;
+                for entry in &entries {
+                    sam::remove_from_queue(&pool, entry.id).await?;
+                }
+                Ok::<_, sqlx::Error>(entries.len())
+            })
+            .map_err(LuaError::runtime)
         })?,
     )?;
     tbl.set(
         "add_playlist",
-        lua.create_function(|_, playlist_id: i64| {
-            log::info!("[script] queue.add_playlist({})", playlist_id);
-            Ok(())
+        station_fn(lua, app, |_, app, category: Value| {
+            let pool = sam_pool(app)?;
+            let songs = category_songs(&pool, category)?;
+            block_on(async {
+                for song in &songs {
+                    sam::add_to_queue(&pool, song.id).await?;
+                }
+                Ok::<_, sqlx::Error>(songs.len())
+            })
+            .map_err(LuaError::runtime)
         })?,
     )?;
 
@@ -201,25 +618,40 @@ fn register_queue(lua: &Lua) -> LuaResult<()> {
 
 // ── media ─────────────────────────────────────────────────────────────────────
 
-fn register_media(lua: &Lua) -> LuaResult<()> {
+fn register_media(lua: &Lua, app: &Option<AppHandle>) -> LuaResult<()> {
     let tbl = lua.create_table()?;
 
     tbl.set(
         "search",
-        lua.create_function(|lua_ctx, _query: String| {
-            lua_ctx.create_table() // stub
-        })?,
+        station_fn(
+            lua,
+            app,
+            |lua, app, (query, limit): (String, Option<u32>)| {
+                let pool = sam_pool(app)?;
+                let limit = limit.unwrap_or(MEDIA_SEARCH_LIMIT).clamp(1, 500);
+                let songs = block_on(sam::search_songs(
+                    &pool, &query, true, true, false, false, None, limit, 0,
+                ))
+                .map_err(LuaError::runtime)?;
+                to_lua(lua, &songs)
+            },
+        )?,
     )?;
     tbl.set(
         "get",
-        lua.create_function(|lua_ctx, _id: i64| {
-            lua_ctx.create_table() // stub
+        station_fn(lua, app, |lua, app, song_id: i64| {
+            let pool = sam_pool(app)?;
+            let song = block_on(sam::get_song(&pool, song_id)).map_err(LuaError::runtime)?;
+            to_lua(lua, &song)
         })?,
     )?;
     tbl.set(
         "get_random",
-        lua.create_function(|lua_ctx, _category: Value| {
-            lua_ctx.create_table() // stub
+        station_fn(lua, app, |lua, app, category: Value| {
+            use rand::seq::SliceRandom;
+            let pool = sam_pool(app)?;
+            let songs = category_songs(&pool, category)?;
+            to_lua(lua, &songs.choose(&mut rand::thread_rng()))
         })?,
     )?;
 
@@ -229,35 +661,74 @@ fn register_media(lua: &Lua) -> LuaResult<()> {
 
 // ── encoder ───────────────────────────────────────────────────────────────────
 
-fn register_encoder(lua: &Lua) -> LuaResult<()> {
+fn register_encoder(lua: &Lua, app: &Option<AppHandle>) -> LuaResult<()> {
     let tbl = lua.create_table()?;
 
+    tbl.set(
+        "list",
+        station_fn(lua, app, |lua, app, ()| {
+            to_lua(
+                lua,
+                &app.state::<AppState>().encoder_manager.get_all_runtime(),
+            )
+        })?,
+    )?;
     tbl.set(
         "start",
-        lua.create_function(|_, id: i64| {
-            log::info!("[script] encoder.start({})", id);
+        station_fn(lua, app, |_, app, id: i64| {
+            let state = app.state::<AppState>();
+            if state.encoder_manager.get_encoder(id).is_none() {
+                return Err(LuaError::runtime(format!("Encoder {id} not found")));
+            }
+            block_on(encoder_commands::enforce_station_id_gate(
+                &state,
+                &[id],
+                false,
+            ))
+            .map_err(LuaError::runtime)?;
+            encoder_commands::ensure_broadcast_loop(&state);
+            let source_sr = encoder_commands::current_engine_sample_rate(&state);
+            state
+                .encoder_manager
+                .start_encoder_with_sample_rate(id, Some(source_sr), None);
             Ok(())
         })?,
     )?;
     tbl.set(
         "stop",
-        lua.create_function(|_, id: i64| {
-            log::info!("[script] encoder.stop({})", id);
+        station_fn(lua, app, |_, app, id: i64| {
+            app.state::<AppState>().encoder_manager.stop_encoder(id);
             Ok(())
         })?,
     )?;
     tbl.set(
         "set_stream_title",
-        lua.create_function(|_, (id, title): (i64, String)| {
-            log::info!("[script] encoder.set_stream_title({}, '{}')", id, title);
-            Ok(())
-        })?,
+        station_fn(
+            lua,
+            app,
+            |_, app, (title, artist): (String, Option<String>)| {
+                let state = app.state::<AppState>();
+                block_on(state.encoder_manager.push_metadata(
+                    artist.as_deref().unwrap_or(""),
+                    &title,
+                    None,
+                ));
+                Ok(())
+            },
+        )?,
     )?;
     tbl.set(
         "get_listeners",
-        lua.create_function(|_, id: i64| {
-            log::info!("[script] encoder.get_listeners({})", id);
-            Ok(0u32)
+        station_fn(lua, app, |_, app, id: Option<i64>| {
+            let count: u32 = app
+                .state::<AppState>()
+                .encoder_manager
+                .get_all_runtime()
+                .iter()
+                .filter(|r| id.is_none() || id == Some(r.id))
+                .filter_map(|r| r.listeners)
+                .sum();
+            Ok(count)
         })?,
     )?;
 
@@ -265,6 +736,125 @@ fn register_encoder(lua: &Lua) -> LuaResult<()> {
     Ok(())
 }
 
+// ── timer ─────────────────────────────────────────────────────────────────────
+
+struct PendingTimer {
+    id: i64,
+    due: Instant,
+    every: Option<Duration>,
+    callback: Function,
+}
+
+/// Callbacks scheduled during a run. The engine fires them once the script
+/// body and its hooks have returned.
+#[derive(Default)]
+pub struct ScriptTimers {
+    next_id: i64,
+    pending: Vec<PendingTimer>,
+}
+
+pub type TimerQueue = Arc<Mutex<ScriptTimers>>;
+
+impl ScriptTimers {
+    fn add(&mut self, delay: Duration, every: Option<Duration>, callback: Function) -> i64 {
+        self.next_id += 1;
+        self.pending.push(PendingTimer {
+            id: self.next_id,
+            due: Instant::now() + delay,
+            every,
+            callback,
+        });
+        self.next_id
+    }
+
+    /// Take the earliest timer due by `deadline`, rescheduling it if it repeats.
+    fn next_due(&mut self, deadline: Instant) -> Option<(Instant, Function)> {
+        let (i, timer) = self
+            .pending
+            .iter_mut()
+            .enumerate()
+            .min_by_key(|(_, t)| t.due)?;
+        if timer.due > deadline {
+            return None;
+        }
+        let due = timer.due;
+        let callback = timer.callback.clone();
+        match timer.every {
+            Some(every) => timer.due = due + every,
+            None => {
+                self.pending.swap_remove(i);
+            }
+        }
+        Some((due, callback))
+    }
+
+    /// Drop everything still pending (releases the callbacks' VM references).
+    pub fn clear(&mut self) {
+        self.pending.clear();
+    }
+}
+
+/// Fire timers in due order until none are left or the next one falls after
+/// `deadline`. Each callback gets a fresh sandbox budget; an error ends the
+/// run.
+pub fn run_timers(lua: &Lua, timers: &TimerQueue, deadline: Instant) -> LuaResult<()> {
+    loop {
+        let Some((due, callback)) = timers.lock().unwrap().next_due(deadline) else {
+            return Ok(());
+        };
+        let wait = due.saturating_duration_since(Instant::now());
+        if !wait.is_zero() {
+            std::thread::sleep(wait);
+        }
+        sandbox::rearm(lua);
+        callback.call::<()>(())?;
+    }
+}
+
+fn register_timer(lua: &Lua, timers: TimerQueue) -> LuaResult<()> {
+    let tbl = lua.create_table()?;
+
+    let after = {
+        let timers = Arc::clone(&timers);
+        lua.create_function(move |_, (ms, callback): (u64, Function)| {
+            Ok(timers
+                .lock()
+                .unwrap()
+                .add(Duration::from_millis(ms), None, callback))
+        })?
+    };
+    let every = {
+        let timers = Arc::clone(&timers);
+        lua.create_function(move |_, (ms, callback): (u64, Function)| {
+            let interval = Duration::from_millis(ms.max(MIN_TIMER_INTERVAL_MS));
+            Ok(timers
+                .lock()
+                .unwrap()
+                .add(interval, Some(interval), callback))
+        })?
+    };
+    let cancel = {
+        let timers = Arc::clone(&timers);
+        lua.create_function(move |_, id: i64| {
+            timers.lock().unwrap().pending.retain(|t| t.id != id);
+            Ok(())
+        })?
+    };
+    let sleep = lua.create_function(|lua, ms: u64| {
+        let duration = Duration::from_millis(ms.min(MAX_SLEEP_MS));
+        std::thread::sleep(duration);
+        sandbox::pause(lua, duration);
+        Ok(())
+    })?;
+
+    tbl.set("after", after)?;
+    tbl.set("every", every)?;
+    tbl.set("cancel", cancel)?;
+    tbl.set("sleep", sleep)?;
+    lua.globals().set("timer", tbl)?;
+    Ok(())
+}
+
 // ── schedule ──────────────────────────────────────────────────────────────────
 
 fn register_schedule(lua: &Lua) -> LuaResult<()> {
@@ -379,6 +969,60 @@ fn register_http(lua: &Lua) -> LuaResult<()> {
     Ok(())
 }
 
+// ── hooks ─────────────────────────────────────────────────────────────────────
+
+fn register_hooks(lua: &Lua) -> LuaResult<()> {
+    lua.set_named_registry_value(HOOK_REGISTRY, lua.create_table()?)?;
+    for &hook in HOOKS {
+        let register = lua.create_function(move |lua, handler: Function| {
+            let registry: Table = lua.named_registry_value(HOOK_REGISTRY)?;
+            let handlers = match registry.get::<Option<Table>>(hook)? {
+                Some(handlers) => handlers,
+                None => {
+                    let handlers = lua.create_table()?;
+                    registry.set(hook, handlers.clone())?;
+                    handlers
+                }
+            };
+            handlers.push(handler)
+        })?;
+        lua.globals().set(hook, register)?;
+    }
+    Ok(())
+}
+
+/// Call the handlers registered for `hook` with `event`, in registration
+/// order. Returns how many ran.
+pub fn call_hooks(lua: &Lua, hook: &str, event: Value) -> LuaResult<usize> {
+    let Some(registry) = lua.named_registry_value::<Option<Table>>(HOOK_REGISTRY)? else {
+        return Ok(0);
+    };
+    let Some(handlers) = registry.get::<Option<Table>>(hook)? else {
+        return Ok(0);
+    };
+    let mut ran = 0;
+    for handler in handlers.sequence_values::<Function>() {
+        handler?.call::<()>(event.clone())?;
+        ran += 1;
+    }
+    Ok(ran)
+}
+
+// ── api reference ─────────────────────────────────────────────────────────────
+
+/// `api["deck.play"]` → "deck.play(deck) — Start or resume playback"
+fn register_reference(lua: &Lua) -> LuaResult<()> {
+    let tbl = lua.create_table()?;
+    for entry in API_REFERENCE {
+        tbl.set(
+            entry.name,
+            format!("{} — {}", entry.signature, entry.description),
+        )?;
+    }
+    lua.globals().set("api", tbl)?;
+    Ok(())
+}
+
 // ── Helpers ───────────────────────────────────────────────────────────────────
 
 fn lua_value_to_json(val: Value) -> serde_json::Value {
@@ -3,19 +3,36 @@
 /// `ScriptEngine` manages the script registry and fires events.
 /// Each script runs in its own isolated Lua VM.
 /// Output from log.* is captured and stored per-script for the UI.
+///
+/// A run executes the script body, then any `on_*` hook handlers it
+/// registered for the firing event, then its timers.
 use std::{
-    collections::HashMap,
-    sync::{Arc, Mutex},
+    collections::{HashMap, HashSet},
+    sync::{Arc, Mutex, OnceLock},
+    time::{Duration, Instant},
 };
 
 use mlua::Lua;
 use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
 
 use super::{
-    api::{register_all, ScriptLog, ScriptLogEntry, ScriptStore},
-    sandbox::{create_sandboxed_vm, TrustLevel},
-    trigger::ScriptEvent,
+    api::{
+        call_hooks, register_all, run_timers, ScriptLog, ScriptLogEntry, ScriptStore, TimerQueue,
+    },
+    sandbox::{create_sandboxed_vm, restrict_api, ScriptLimits, ScriptPermissions},
+    trigger::{ScriptEvent, ScriptTrigger},
 };
+use crate::analytics::event_logger::{log_event, EventCategory, LogLevel};
+use crate::db::scripts::{self, ScriptRun};
+use crate::state::AppState;
+
+/// Trigger type for scripts that run on every event and react through
+/// their `on_*` hook handlers.
+pub const HOOKS_TRIGGER: &str = "hooks";
+
+/// How long a run may keep firing timers after its body returns.
+const TIMER_WINDOW: Duration = Duration::from_secs(10 * 60);
 
 // ── Script record (mirrors DB row) ────────────────────────────────────────────
 
@@ -27,6 +44,13 @@ pub struct Script {
     pub content: String,
     pub enabled: bool,
     pub trigger_type: String,
+    /// Schedules and further events that also start the script
+    #[serde(default)]
+    pub triggers: Vec<ScriptTrigger>,
+    #[serde(default)]
+    pub permissions: ScriptPermissions,
+    #[serde(default)]
+    pub limits: ScriptLimits,
     pub last_run_at: Option<i64>,
     pub last_error: Option<String>,
 }
@@ -53,6 +77,10 @@ pub struct ScriptEngine {
     stores: Arc<Mutex<HashMap<i64, ScriptStore>>>,
     /// Channel to send events — tokio::sync::broadcast for multi-consumer
     event_tx: tokio::sync::broadcast::Sender<ScriptEvent>,
+    /// The running app, once setup has attached it; station API calls need it.
+    app: Arc<OnceLock<AppHandle>>,
+    /// Scripts with a triggered run in progress
+    running: Arc<Mutex<HashSet<i64>>>,
 }
 
 impl ScriptEngine {
@@ -63,9 +91,16 @@ impl ScriptEngine {
             logs: Arc::new(Mutex::new(HashMap::new())),
             stores: Arc::new(Mutex::new(HashMap::new())),
             event_tx,
+            app: Arc::new(OnceLock::new()),
+            running: Arc::new(Mutex::new(HashSet::new())),
         }
     }
 
+    /// Give scripts access to the station (decks, queue, encoders).
+    pub fn attach(&self, app: AppHandle) {
+        let _ = self.app.set(app);
+    }
+
     // ── Script CRUD ───────────────────────────────────────────────────────
 
     pub fn save_script(&self, mut script: Script) -> i64 {
@@ -89,6 +124,13 @@ impl ScriptEngine {
         id
     }
 
+    /// Add scripts loaded from the database.
+    pub fn load(&self, scripts: Vec<Script>) {
+        for script in scripts {
+            self.save_script(script);
+        }
+    }
+
     pub fn delete_script(&self, id: i64) {
         self.scripts.lock().unwrap().remove(&id);
         self.logs.lock().unwrap().remove(&id);
@@ -112,37 +154,37 @@ impl ScriptEngine {
 
     // ── Event dispatch ────────────────────────────────────────────────────
 
-    /// Fire an event — all enabled scripts whose trigger_type matches will run.
+    /// Fire an event — all enabled scripts whose trigger_type matches will run,
+    /// as will every enabled `hooks` script.
     pub fn fire(&self, event: ScriptEvent) {
         let _ = self.event_tx.send(event);
     }
 
-    /// Spawn a background task that processes events for a script.
-    pub fn start_event_loop(&self, id: i64) {
-        let engine = self.clone();
-        let mut rx = self.event_tx.subscribe();
-        tokio::spawn(async move {
-            loop {
-                match rx.recv().await {
-                    Ok(event) => {
-                        let script = engine.get_script(id);
-                        if let Some(script) = script {
-                            if script.enabled && script.trigger_type == event.trigger_type() {
-                                engine.run_script_with_event(&script, &event).await;
-                            }
-                        }
-                    }
-                    Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
-                    Err(_) => {} // lagged, skip
-                }
-            }
-        });
+    /// Events fired from now on, for the trigger dispatcher.
+    pub fn subscribe(&self) -> tokio::sync::broadcast::Receiver<ScriptEvent> {
+        self.event_tx.subscribe()
     }
 
     // ── Script execution ──────────────────────────────────────────────────
 
     /// Run a script immediately (manual trigger or event dispatch).
     pub async fn run_script(&self, id: i64) -> ScriptRunResult {
+        self.run_script_for_event(id, ScriptEvent::Manual).await
+    }
+
+    /// Run a script for a trigger, unless a triggered run of it is still in
+    /// progress (`None`).
+    pub async fn run_triggered(&self, id: i64, event: ScriptEvent) -> Option<ScriptRunResult> {
+        if !self.running.lock().unwrap().insert(id) {
+            return None;
+        }
+        let result = self.run_script_for_event(id, event).await;
+        self.running.lock().unwrap().remove(&id);
+        Some(result)
+    }
+
+    /// Run a script with `event` as its payload, regardless of its trigger.
+    pub async fn run_script_for_event(&self, id: i64, event: ScriptEvent) -> ScriptRunResult {
         let script = match self.get_script(id) {
             Some(s) => s,
             None => {
@@ -154,14 +196,15 @@ impl ScriptEngine {
                 }
             }
         };
-        let event = ScriptEvent::Manual;
         self.run_script_with_event(&script, &event).await
     }
 
     async fn run_script_with_event(&self, script: &Script, event: &ScriptEvent) -> ScriptRunResult {
         let id = script.id;
         let content = script.content.clone();
+        let (permissions, limits) = (script.permissions, script.limits);
         let event = event.clone();
+        let trigger = event.run_label();
 
         // Build per-run log sink
         let log_sink: ScriptLog = Arc::new(Mutex::new(Vec::new()));
@@ -176,9 +219,21 @@ impl ScriptEngine {
                 .unwrap_or_else(|| Arc::new(Mutex::new(HashMap::new())))
         };
 
+        let app = self.app.get().cloned();
+        let started_at = chrono::Utc::now().timestamp_millis();
+        let started = Instant::now();
+
         // Run in blocking task (Lua is sync)
         let result = tokio::task::spawn_blocking(move || {
-            Self::execute_script(id, &content, &event, log_sink_clone, store)
+            Self::execute_script(
+                id,
+                &content,
+                &event,
+                (&permissions, &limits),
+                log_sink_clone,
+                store,
+                app,
+            )
         })
         .await
         .unwrap_or_else(|e| ScriptRunResult {
@@ -212,18 +267,67 @@ impl ScriptEngine {
             }
         }
 
+        let run = ScriptRun {
+            id: 0,
+            script_id: id,
+            started_at,
+            duration_ms: started.elapsed().as_millis() as i64,
+            trigger,
+            success: result.success,
+            error: result.error.clone(),
+        };
+        self.record_run(script, &run, result.error_line).await;
+
         result
     }
 
+    /// Save the run to the history; failures also go to the event log.
+    async fn record_run(&self, script: &Script, run: &ScriptRun, error_line: Option<u32>) {
+        let Some(app) = self.app.get() else {
+            return;
+        };
+        let state = app.state::<AppState>();
+        let Some(pool) = state.local_db.as_ref() else {
+            return;
+        };
+        if let Err(e) = scripts::record_run(pool, run).await {
+            log::warn!("Script run not recorded (script {}): {e}", script.id);
+        }
+        let Some(error) = &run.error else {
+            return;
+        };
+        let _ = log_event(
+            pool,
+            LogLevel::Error,
+            EventCategory::Scripting,
+            "script_failed",
+            &format!(
+                "Script \"{}\" failed ({}): {error}",
+                script.name, run.trigger
+            ),
+            Some(serde_json::json!({
+                "script_id": script.id,
+                "trigger": run.trigger,
+                "error_line": error_line,
+            })),
+            None,
+            None,
+            None,
+        )
+        .await;
+    }
+
     fn execute_script(
         id: i64,
         content: &str,
         event: &ScriptEvent,
+        (permissions, limits): (&ScriptPermissions, &ScriptLimits),
         log_sink: ScriptLog,
         store: ScriptStore,
+        app: Option<AppHandle>,
     ) -> ScriptRunResult {
         // Create a fresh sandboxed VM for each run
-        let lua = match create_sandboxed_vm(TrustLevel::Basic) {
+        let lua = match create_sandboxed_vm(permissions, limits) {
             Ok(l) => l,
             Err(e) => {
                 return ScriptRunResult {
@@ -236,7 +340,17 @@ impl ScriptEngine {
         };
 
         // Register DesiZone API
-        if let Err(e) = register_all(&lua, id, Arc::clone(&log_sink), Arc::clone(&store)) {
+        let timers = TimerQueue::default();
+        let registered = register_all(
+            &lua,
+            id,
+            Arc::clone(&log_sink),
+            Arc::clone(&store),
+            app,
+            Arc::clone(&timers),
+        )
+        .and_then(|()| restrict_api(&lua, permissions));
+        if let Err(e) = registered {
             return ScriptRunResult {
                 success: false,
                 output: vec![],
@@ -248,8 +362,19 @@ impl ScriptEngine {
         // Inject event payload as `event` global table in the Lua VM
         let _ = inject_event_table(&lua, event);
 
-        // Execute the script
-        match lua.load(content).exec() {
+        // Execute the script, then its hook handlers for this event, then
+        // whatever timers it scheduled
+        let outcome = lua
+            .load(content)
+            .exec()
+            .and_then(|()| {
+                let event_tbl = lua.globals().get("event")?;
+                call_hooks(&lua, event.trigger_type(), event_tbl)
+            })
+            .and_then(|_| run_timers(&lua, &timers, Instant::now() + TIMER_WINDOW));
+        timers.lock().unwrap().clear();
+
+        match outcome {
             Ok(_) => {
                 let output: Vec<String> = log_sink
                     .lock()
@@ -340,8 +465,33 @@ fn inject_event_table(lua: &Lua, event: &ScriptEvent) -> Result<(), mlua::Error>
             tbl.set("incoming_id", *incoming_id)?;
             tbl.set("incoming_title", incoming_title.as_str())?;
         }
+        ScriptEvent::HealthAlert {
+            kind,
+            message,
+            resolved,
+            encoder_id,
+            deck,
+        } => {
+            tbl.set("kind", kind.as_str())?;
+            tbl.set("message", message.as_str())?;
+            tbl.set("resolved", *resolved)?;
+            tbl.set("encoder_id", *encoder_id)?;
+            tbl.set("deck", deck.as_deref())?;
+        }
+        ScriptEvent::Silence { resolved, message } => {
+            tbl.set("resolved", *resolved)?;
+            tbl.set("message", message.as_str())?;
+        }
+        ScriptEvent::ShowStart { show_id, name } => {
+            tbl.set("show_id", *show_id)?;
+            tbl.set("name", name.as_str())?;
+        }
+        ScriptEvent::Schedule { cron } => {
+            tbl.set("cron", cron.as_str())?;
+        }
         ScriptEvent::Manual => {}
     }
+    tbl.set("type", event.trigger_type())?;
     lua.globals().set("event", tbl)?;
     Ok(())
 }
@@ -356,3 +506,39 @@ fn parse_error_line(err: &str) -> Option<u32> {
         None
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hooks_and_timers_run_after_the_body() {
+        let store: ScriptStore = Arc::new(Mutex::new(HashMap::new()));
+        let script = r#"
+            on_track_end(function(e) store.set("ended", e.title) end)
+            on_track_start(function(e) store.set("started", true) end)
+            timer.after(1, function() store.set("timer", store.get("ended")) end)
+            store.set("offline", not pcall(deck.play, "deck_a"))
+        "#;
+        let event = ScriptEvent::TrackEnd {
+            id: 7,
+            title: "Closing Song".to_string(),
+        };
+        let result = ScriptEngine::execute_script(
+            1,
+            script,
+            &event,
+            (&ScriptPermissions::default(), &ScriptLimits::default()),
+            Arc::new(Mutex::new(Vec::new())),
+            Arc::clone(&store),
+            None,
+        );
+        assert!(result.success, "{:?}", result.error);
+
+        let store = store.lock().unwrap();
+        assert_eq!(store["ended"], "Closing Song");
+        assert_eq!(store["timer"], "Closing Song");
+        assert_eq!(store["offline"], true);
+        assert!(!store.contains_key("started"));
+    }
+}
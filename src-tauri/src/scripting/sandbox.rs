@@ -1,43 +1,291 @@
 /// `scripting/sandbox.rs` — Lua sandbox restrictions per script
 ///
-/// Every script VM is created with a restricted set of standard libraries.
-/// Dangerous libraries (os, io, debug, package, require) are omitted by default.
-/// Scripts can be granted additional trust levels through their configuration.
-use mlua::{Lua, Result as LuaResult};
-
-/// Controls which Lua standard libraries are available to a script.
-#[derive(Debug, Clone, PartialEq)]
-pub enum TrustLevel {
-    /// Default: string, table, math, coroutine — no I/O or OS
-    Basic,
-    /// Standard + io (for reading files in a scoped path)
-    FileRead,
-    /// Full standard library — only for trusted scripts
-    Elevated,
-}
-
-impl Default for TrustLevel {
+/// Every script VM is created with a restricted set of standard libraries
+/// and the limits saved with the script:
+///   - time: wall-clock time per invocation, not counting `timer.sleep`
+///   - memory: the VM's total allocation
+///   - instructions: Lua VM instructions per invocation
+///
+/// An invocation is the script body with its hook handlers, or one timer
+/// callback; each starts with the full budget.
+///
+/// Permissions unlock the rest: `filesystem` adds io and the full os
+/// library, `network` the http calls, and `engine_control` the calls that
+/// change what is on air (decks, queue edits, encoders, station).
+/// A call without its permission raises an error naming the permission.
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use mlua::{
+    Error as LuaError, HookTriggers, Lua, LuaOptions, Result as LuaResult, StdLib, Table, VmState,
+};
+use serde::{Deserialize, Serialize};
+
+/// Instructions between budget checks.
+const INSTRUCTION_STEP: u32 = 1_000;
+
+/// `os` functions left to scripts without the filesystem permission.
+const SAFE_OS: &[&str] = &["clock", "date", "difftime", "time"];
+
+/// Calls that change what is on air.
+const ENGINE_CONTROL_CALLS: &[&str] = &[
+    "deck.play",
+    "deck.pause",
+    "deck.stop",
+    "deck.next",
+    "deck.seek",
+    "deck.load",
+    "queue.add",
+    "queue.add_at",
+    "queue.remove",
+    "queue.clear",
+    "queue.add_playlist",
+    "encoder.start",
+    "encoder.stop",
+    "encoder.set_stream_title",
+    "station.set_mode",
+    "station.emergency_stop",
+];
+
+const NETWORK_CALLS: &[&str] = &["http.get", "http.post"];
+
+/// What a script may do beyond the basic API.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScriptPermissions {
+    /// io library, full os library, dofile/loadfile
+    pub filesystem: bool,
+    /// http.get / http.post
+    pub network: bool,
+    /// Deck, queue, encoder and station control
+    pub engine_control: bool,
+}
+
+/// Resource limits for one script.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScriptLimits {
+    /// Wall-clock time per invocation, excluding `timer.sleep`
+    pub max_runtime_ms: u64,
+    /// VM memory cap
+    pub memory_mb: u32,
+    /// Lua instructions per invocation
+    pub instruction_budget: u64,
+}
+
+impl Default for ScriptLimits {
     fn default() -> Self {
-        Self::Basic
+        Self {
+            max_runtime_ms: 5_000,
+            memory_mb: 32,
+            instruction_budget: 50_000_000,
+        }
     }
 }
 
-/// Creates a new Lua VM with sandbox restrictions applied.
-pub fn create_sandboxed_vm(trust: TrustLevel) -> LuaResult<Lua> {
-    let lua = Lua::new();
+impl ScriptLimits {
+    pub fn clamped(self) -> Self {
+        Self {
+            max_runtime_ms: self.max_runtime_ms.clamp(100, 600_000),
+            memory_mb: self.memory_mb.clamp(4, 512),
+            instruction_budget: self
+                .instruction_budget
+                .clamp(INSTRUCTION_STEP as u64, 10_000_000_000),
+        }
+    }
+}
+
+/// Time and instruction budget of the current invocation. Stored as VM app
+/// data so the timer API can pause and re-arm it.
+struct Budget {
+    limits: ScriptLimits,
+    state: Mutex<BudgetState>,
+}
+
+struct BudgetState {
+    deadline: Instant,
+    instructions: u64,
+}
 
-    // Load safe standard libs
-    lua.load_std_libs(
-        mlua::StdLib::TABLE | mlua::StdLib::STRING | mlua::StdLib::MATH | mlua::StdLib::COROUTINE,
-    )?;
+impl Budget {
+    fn rearm(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.deadline = Instant::now() + Duration::from_millis(self.limits.max_runtime_ms);
+        state.instructions = 0;
+    }
+
+    fn charge(&self, instructions: u64) -> LuaResult<VmState> {
+        let mut state = self.state.lock().unwrap();
+        state.instructions += instructions;
+        if state.instructions > self.limits.instruction_budget {
+            return Err(LuaError::runtime(format!(
+                "Script exceeded its budget of {} instructions",
+                self.limits.instruction_budget
+            )));
+        }
+        if Instant::now() > state.deadline {
+            return Err(LuaError::runtime(format!(
+                "Script exceeded its {} ms time limit",
+                self.limits.max_runtime_ms
+            )));
+        }
+        Ok(VmState::Continue)
+    }
+}
 
-    if trust == TrustLevel::FileRead || trust == TrustLevel::Elevated {
-        lua.load_std_libs(mlua::StdLib::IO)?;
+/// Creates a new Lua VM with sandbox restrictions and `limits` applied.
+pub fn create_sandboxed_vm(
+    permissions: &ScriptPermissions,
+    limits: &ScriptLimits,
+) -> LuaResult<Lua> {
+    let mut libs = StdLib::TABLE
+        | StdLib::STRING
+        | StdLib::MATH
+        | StdLib::COROUTINE
+        | StdLib::UTF8
+        | StdLib::OS;
+    if permissions.filesystem {
+        libs |= StdLib::IO;
     }
+    let lua = Lua::new_with(libs, LuaOptions::default())?;
 
-    if trust == TrustLevel::Elevated {
-        lua.load_std_libs(mlua::StdLib::OS | mlua::StdLib::PACKAGE)?;
+    if !permissions.filesystem {
+        let globals = lua.globals();
+        globals.set("dofile", mlua::Nil)?;
+        globals.set("loadfile", mlua::Nil)?;
+        let os: Table = globals.get("os")?;
+        let safe = lua.create_table()?;
+        for name in SAFE_OS {
+            safe.set(*name, os.get::<mlua::Value>(*name)?)?;
+        }
+        globals.set("os", safe)?;
     }
 
+    let limits = limits.clamped();
+    lua.set_memory_limit(limits.memory_mb as usize * 1024 * 1024)?;
+
+    let budget = Arc::new(Budget {
+        limits,
+        state: Mutex::new(BudgetState {
+            deadline: Instant::now(),
+            instructions: 0,
+        }),
+    });
+    budget.rearm();
+    lua.set_app_data(Arc::clone(&budget));
+    lua.set_hook(
+        HookTriggers::new().every_nth_instruction(INSTRUCTION_STEP),
+        move |_, _| budget.charge(INSTRUCTION_STEP as u64),
+    );
+
     Ok(lua)
 }
+
+/// Replace API calls the script lacks permission for with ones that raise.
+/// Run after the API is registered.
+pub fn restrict_api(lua: &Lua, permissions: &ScriptPermissions) -> LuaResult<()> {
+    if !permissions.engine_control {
+        deny(lua, ENGINE_CONTROL_CALLS, "engine_control")?;
+    }
+    if !permissions.network {
+        deny(lua, NETWORK_CALLS, "network")?;
+    }
+    Ok(())
+}
+
+fn deny(lua: &Lua, calls: &[&'static str], permission: &'static str) -> LuaResult<()> {
+    for call in calls {
+        let Some((table, name)) = call.split_once('.') else {
+            continue;
+        };
+        let Ok(tbl) = lua.globals().get::<Table>(table) else {
+            continue;
+        };
+        let call = *call;
+        tbl.set(
+            name,
+            lua.create_function(move |_, _: mlua::MultiValue| -> LuaResult<()> {
+                Err(LuaError::runtime(format!(
+                    "{call} needs the {permission} permission"
+                )))
+            })?,
+        )?;
+    }
+    Ok(())
+}
+
+/// Give the next invocation (a timer callback) a fresh budget.
+pub fn rearm(lua: &Lua) {
+    if let Some(budget) = lua.app_data_ref::<Arc<Budget>>() {
+        budget.rearm();
+    }
+}
+
+/// Keep `duration` spent waiting (`timer.sleep`) off the time limit.
+pub fn pause(lua: &Lua, duration: Duration) {
+    if let Some(budget) = lua.app_data_ref::<Arc<Budget>>() {
+        budget.state.lock().unwrap().deadline += duration;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vm(permissions: ScriptPermissions, limits: ScriptLimits) -> Lua {
+        let lua = create_sandboxed_vm(&permissions, &limits).unwrap();
+        let deck = lua.create_table().unwrap();
+        deck.set("play", lua.create_function(|_, ()| Ok(true)).unwrap())
+            .unwrap();
+        lua.globals().set("deck", deck).unwrap();
+        restrict_api(&lua, &permissions).unwrap();
+        lua
+    }
+
+    #[test]
+    fn limits_stop_runaway_scripts() {
+        let limits = ScriptLimits {
+            instruction_budget: 100_000,
+            ..Default::default()
+        };
+        let err = vm(ScriptPermissions::default(), limits)
+            .load("while true do end")
+            .exec()
+            .unwrap_err();
+        assert!(err.to_string().contains("100000 instructions"), "{err}");
+
+        let limits = ScriptLimits {
+            memory_mb: 4,
+            ..Default::default()
+        };
+        let err = vm(ScriptPermissions::default(), limits)
+            .load("local t = {} for i = 1, 1e8 do t[i] = i end")
+            .exec()
+            .unwrap_err();
+        assert!(matches!(err, LuaError::MemoryError(_)), "{err}");
+    }
+
+    #[test]
+    fn permissions_gate_libraries_and_calls() {
+        let lua = vm(ScriptPermissions::default(), ScriptLimits::default());
+        lua.load(
+            "assert(io == nil and loadfile == nil and os.remove == nil and os.time)
+             local ok, err = pcall(deck.play)
+             assert(not ok and tostring(err):find('engine_control'))",
+        )
+        .exec()
+        .unwrap();
+
+        let all = ScriptPermissions {
+            filesystem: true,
+            network: true,
+            engine_control: true,
+        };
+        vm(all, ScriptLimits::default())
+            .load("assert(io.open and os.remove and deck.play())")
+            .exec()
+            .unwrap();
+    }
+}
@@ -1,9 +1,41 @@
 /// `scripting/trigger.rs` — Script event types that map to Lua callbacks
 ///
-/// The audio engine and other subsystems fire `ScriptEvent`s.
-/// The `ScriptEngine` listens on an async channel and dispatches them
-/// to all enabled scripts whose `trigger_type` matches.
+/// The audio engine and other subsystems fire `ScriptEvent`s into the
+/// `ScriptEngine`'s channel. The trigger dispatcher (`start`) routes each one
+/// to every enabled script that wants it: through its `trigger_type`, the
+/// `hooks` trigger, or an event entry in its `triggers`. The same dispatcher
+/// ticks once a minute for cron schedules and the `on_hour` event.
+///
+/// A triggered run is skipped while the same script is still running.
+use std::time::Duration;
+
+use chrono::{Datelike, Timelike};
 use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+
+use super::engine::{Script, ScriptEngine, HOOKS_TRIGGER};
+use crate::state::AppState;
+
+/// An extra way for a script to start, stored with the script.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ScriptTrigger {
+    /// Five-field cron expression in local time (minute hour day month
+    /// weekday), or `@hourly` / `@daily` / `@weekly` / `@monthly`.
+    Schedule { cron: String },
+    /// An engine event by trigger type, e.g. `on_track_start`.
+    Event { event: String },
+}
+
+impl ScriptTrigger {
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            ScriptTrigger::Schedule { cron } => CronSchedule::parse(cron).map(|_| ()),
+            ScriptTrigger::Event { event } if super::api::HOOKS.contains(&event.as_str()) => Ok(()),
+            ScriptTrigger::Event { event } => Err(format!("Unknown script event: {event}")),
+        }
+    }
+}
 
 /// Events that can trigger script execution.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -41,6 +73,20 @@ pub enum ScriptEvent {
     EncoderConnect { encoder_id: i64 },
     /// Fired when an encoder disconnects.
     EncoderDisconnect { encoder_id: i64, reason: String },
+    /// Fired by the health watchdog when a condition is raised or clears.
+    HealthAlert {
+        kind: String,
+        message: String,
+        resolved: bool,
+        encoder_id: Option<i64>,
+        deck: Option<String>,
+    },
+    /// Fired when dead air is raised (`resolved: false`) or clears.
+    Silence { resolved: bool, message: String },
+    /// Fired when a scheduled show goes on air.
+    ShowStart { show_id: i64, name: String },
+    /// A script's cron schedule came due.
+    Schedule { cron: String },
     /// Manual trigger (user pressed "Run" in UI).
     Manual,
 }
@@ -57,7 +103,243 @@ impl ScriptEvent {
             ScriptEvent::Hour { .. } => "on_hour",
             ScriptEvent::EncoderConnect { .. } => "on_encoder_connect",
             ScriptEvent::EncoderDisconnect { .. } => "on_encoder_disconnect",
+            ScriptEvent::HealthAlert { .. } => "on_health_alert",
+            ScriptEvent::Silence { .. } => "on_silence",
+            ScriptEvent::ShowStart { .. } => "on_show_start",
+            ScriptEvent::Schedule { .. } => "on_schedule",
             ScriptEvent::Manual => "manual",
         }
     }
+
+    /// How a run started, as recorded in the run history.
+    pub fn run_label(&self) -> String {
+        match self {
+            ScriptEvent::Schedule { cron } => format!("schedule:{cron}"),
+            other => other.trigger_type().to_string(),
+        }
+    }
+}
+
+/// Whether `script` should run when `event` fires. Manual runs and schedules
+/// are started directly, never through the event channel.
+pub fn wants_event(script: &Script, event: &ScriptEvent) -> bool {
+    if matches!(event, ScriptEvent::Manual | ScriptEvent::Schedule { .. }) {
+        return false;
+    }
+    let name = event.trigger_type();
+    script.trigger_type == name
+        || script.trigger_type == HOOKS_TRIGGER
+        || script
+            .triggers
+            .iter()
+            .any(|t| matches!(t, ScriptTrigger::Event { event } if event == name))
+}
+
+// ── Cron schedules ────────────────────────────────────────────────────────────
+
+/// A parsed cron expression; each field is a bitmask of allowed values.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CronSchedule {
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    any_day: bool,
+    any_weekday: bool,
+}
+
+impl CronSchedule {
+    pub fn parse(expr: &str) -> Result<Self, String> {
+        let expr = match expr.trim() {
+            "@hourly" => "0 * * * *",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@weekly" => "0 0 * * 0",
+            "@monthly" => "0 0 1 * *",
+            other => other,
+        };
+        let fields: Vec<&str> = expr.split_whitespace().collect();
+        let [minute, hour, day, month, weekday] = fields[..] else {
+            return Err(format!(
+                "Cron needs 5 fields (minute hour day month weekday), got {}",
+                fields.len()
+            ));
+        };
+        let mut weekdays = parse_field(weekday, 0, 7)?;
+        // Both 0 and 7 are Sunday.
+        if weekdays & (1 << 7) != 0 {
+            weekdays |= 1;
+        }
+        Ok(Self {
+            minutes: parse_field(minute, 0, 59)?,
+            hours: parse_field(hour, 0, 23)?,
+            days: parse_field(day, 1, 31)?,
+            months: parse_field(month, 1, 12)?,
+            weekdays,
+            any_day: day == "*",
+            any_weekday: weekday == "*",
+        })
+    }
+
+    pub fn matches<T: Datelike + Timelike>(&self, t: &T) -> bool {
+        let has = |mask: u64, v: u32| mask & (1 << v) != 0;
+        let day = has(self.days, t.day());
+        let weekday = has(self.weekdays, t.weekday().num_days_from_sunday());
+        // As in cron, a restricted day-of-month and day-of-week match on either.
+        let day_ok = if !self.any_day && !self.any_weekday {
+            day || weekday
+        } else {
+            day && weekday
+        };
+        has(self.minutes, t.minute())
+            && has(self.hours, t.hour())
+            && has(self.months, t.month())
+            && day_ok
+    }
+}
+
+/// `*`, `n`, `a-b`, with an optional `/step`, comma separated.
+fn parse_field(field: &str, min: u32, max: u32) -> Result<u64, String> {
+    let mut mask = 0u64;
+    for part in field.split(',') {
+        let bad = || format!("Bad cron field '{part}' (allowed {min}-{max})");
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (
+                range,
+                step.parse::<u32>()
+                    .ok()
+                    .filter(|s| *s > 0)
+                    .ok_or_else(bad)?,
+            ),
+            None => (part, 1),
+        };
+        let num = |v: &str| v.parse::<u32>().map_err(|_| bad());
+        let (lo, hi) = match range.split_once('-') {
+            _ if range == "*" => (min, max),
+            Some((lo, hi)) => (num(lo)?, num(hi)?),
+            // `5/15` runs from 5 to the end of the range.
+            None if step > 1 => (num(range)?, max),
+            None => (num(range)?, num(range)?),
+        };
+        if lo < min || hi > max || lo > hi {
+            return Err(bad());
+        }
+        for v in (lo..=hi).step_by(step as usize) {
+            mask |= 1 << v;
+        }
+    }
+    Ok(mask)
+}
+
+// ── Dispatcher ────────────────────────────────────────────────────────────────
+
+/// Load saved scripts, then route events and schedule ticks to them.
+pub fn start(app: AppHandle) {
+    let engine = app.state::<AppState>().script_engine.clone();
+    let mut events = engine.subscribe();
+
+    let dispatcher = engine.clone();
+    let local = app.state::<AppState>().local_db.clone();
+    tauri::async_runtime::spawn(async move {
+        if let Some(pool) = &local {
+            match crate::db::scripts::load_scripts(pool).await {
+                Ok(scripts) => {
+                    log::info!("Scripts: {} loaded", scripts.len());
+                    dispatcher.load(scripts);
+                }
+                Err(e) => log::warn!("Saved scripts not loaded: {e}"),
+            }
+        }
+        loop {
+            match events.recv().await {
+                Ok(event) => {
+                    for script in dispatcher.get_scripts() {
+                        if script.enabled && wants_event(&script, &event) {
+                            dispatch(&dispatcher, script.id, event.clone());
+                        }
+                    }
+                }
+                Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+                Err(tokio::sync::broadcast::error::RecvError::Lagged(n)) => {
+                    log::warn!("Script dispatcher fell behind; {n} events dropped")
+                }
+            }
+        }
+    });
+
+    tauri::async_runtime::spawn(async move {
+        let mut tick = tokio::time::interval(Duration::from_secs(1));
+        let mut last_minute = None;
+        loop {
+            tick.tick().await;
+            let now = chrono::Local::now();
+            let minute = now.timestamp().div_euclid(60);
+            if last_minute.replace(minute) == Some(minute) {
+                continue;
+            }
+            if now.minute() == 0 {
+                engine.fire(ScriptEvent::Hour {
+                    hour: now.hour() as u8,
+                });
+            }
+            for script in engine.get_scripts().into_iter().filter(|s| s.enabled) {
+                let due = script.triggers.iter().find_map(|t| match t {
+                    ScriptTrigger::Schedule { cron } => CronSchedule::parse(cron)
+                        .ok()
+                        .filter(|c| c.matches(&now))
+                        .map(|_| cron.clone()),
+                    ScriptTrigger::Event { .. } => None,
+                });
+                if let Some(cron) = due {
+                    dispatch(&engine, script.id, ScriptEvent::Schedule { cron });
+                }
+            }
+        }
+    });
+}
+
+fn dispatch(engine: &ScriptEngine, id: i64, event: ScriptEvent) {
+    let engine = engine.clone();
+    tauri::async_runtime::spawn(async move {
+        let trigger = event.run_label();
+        if engine.run_triggered(id, event).await.is_none() {
+            log::info!("Script {id} is still running; {trigger} run skipped");
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn at(y: i32, m: u32, d: u32, h: u32, min: u32) -> chrono::NaiveDateTime {
+        NaiveDate::from_ymd_opt(y, m, d)
+            .unwrap()
+            .and_hms_opt(h, min, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn cron_fields_steps_and_day_rules() {
+        let every_15 = CronSchedule::parse("*/15 6-9 * * 1-5").unwrap();
+        // 2026-10-19 is a Monday.
+        assert!(every_15.matches(&at(2026, 10, 19, 6, 45)));
+        assert!(!every_15.matches(&at(2026, 10, 19, 6, 50)));
+        assert!(!every_15.matches(&at(2026, 10, 18, 6, 45)));
+
+        // Restricted day and weekday match on either; 7 is Sunday.
+        let either = CronSchedule::parse("0 12 1 * 7").unwrap();
+        assert!(either.matches(&at(2026, 10, 18, 12, 0)));
+        assert!(either.matches(&at(2026, 12, 1, 12, 0)));
+        assert!(!either.matches(&at(2026, 10, 20, 12, 0)));
+
+        assert_eq!(
+            CronSchedule::parse("@hourly"),
+            CronSchedule::parse("0 * * * *")
+        );
+        assert!(CronSchedule::parse("60 * * * *").is_err());
+        assert!(CronSchedule::parse("* * *").is_err());
+        assert!(CronSchedule::parse("*/0 * * * *").is_err());
+    }
 }
@@ -0,0 +1,507 @@
+/// Station settings backup and restore.
+///
+/// `export` writes the chosen sections of the local database (plus the
+/// in-memory script library) to a gzip-compressed JSON archive; `import`
+/// replaces those sections on another machine. Rows are stored as plain
+/// column → value objects and restored into whatever columns the running
+/// schema has, so archives from older or newer builds still load: unknown
+/// columns are dropped and new ones take their defaults. Encoder definitions
+/// include server passwords; treat archives as secrets.
+use std::collections::{BTreeMap, HashSet};
+use std::io::{Read, Write};
+use std::path::Path;
+
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sqlx::{sqlite::SqliteRow, Column, Row, SqlitePool, TypeInfo, ValueRef};
+
+use crate::{
+    db::local,
+    error::{AppError, ErrorCode},
+    scripting::engine::Script,
+    state::AppState,
+    stream::broadcaster::EncoderStatus,
+};
+
+/// Bumped when the archive layout itself changes; see `migrate`.
+pub const FORMAT_VERSION: u32 = 1;
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Section {
+    Encoders,
+    Rotation,
+    Clockwheels,
+    Shows,
+    Dsp,
+    Controller,
+    CuePoints,
+    Scripts,
+}
+
+impl Section {
+    pub const ALL: [Section; 8] = [
+        Section::Encoders,
+        Section::Rotation,
+        Section::Clockwheels,
+        Section::Shows,
+        Section::Dsp,
+        Section::Controller,
+        Section::CuePoints,
+        Section::Scripts,
+    ];
+
+    /// Local tables making up the section, parents before children.
+    pub fn tables(self) -> &'static [&'static str] {
+        match self {
+            Section::Encoders => &[
+                "encoder_configs",
+                "metadata_push_targets",
+                "station_id_gate_config",
+            ],
+            Section::Rotation => &["rotation_rules", "rotation_playlists", "playlist_songs"],
+            Section::Clockwheels => &[
+                "autodj_clockwheel_config",
+                "clockwheel_templates",
+                "clockwheel_hour_grid",
+            ],
+            Section::Shows => &["scheduled_shows", "timed_events"],
+            Section::Dsp => &[
+                "channel_dsp_settings",
+                "crossfade_config",
+                "mic_duck_config",
+                "category_gain_trims",
+                "crossfade_profiles",
+                "crossfade_profile_assignments",
+            ],
+            Section::Controller => &[
+                "controller_config",
+                "controller_profiles",
+                "hotkey_config",
+                "osc_config",
+            ],
+            Section::CuePoints => &["cue_points"],
+            // Scripts are restored through the script engine, which saves them.
+            Section::Scripts => &[],
+        }
+    }
+
+    /// Whether restored values only take effect after a restart.
+    fn needs_restart(self) -> bool {
+        matches!(self, Section::Dsp | Section::Controller)
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SectionData {
+    #[serde(default)]
+    pub tables: BTreeMap<String, Vec<Map<String, Value>>>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub scripts: Vec<Script>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SettingsArchive {
+    pub format_version: u32,
+    pub app_version: String,
+    /// Unix ms
+    pub created_at: i64,
+    pub sections: BTreeMap<Section, SectionData>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SectionSummary {
+    pub section: Section,
+    /// Table → row count
+    pub tables: BTreeMap<String, usize>,
+    pub scripts: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchiveSummary {
+    pub format_version: u32,
+    pub app_version: String,
+    pub created_at: i64,
+    pub sections: Vec<SectionSummary>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ImportReport {
+    pub restored: Vec<SectionSummary>,
+    /// Requested sections the archive does not contain
+    pub missing: Vec<Section>,
+    /// Archive columns the current schema no longer has, as `table.column`
+    pub dropped_columns: Vec<String>,
+    pub restart_required: bool,
+}
+
+impl SettingsArchive {
+    pub fn summary(&self) -> ArchiveSummary {
+        ArchiveSummary {
+            format_version: self.format_version,
+            app_version: self.app_version.clone(),
+            created_at: self.created_at,
+            sections: self
+                .sections
+                .iter()
+                .map(|(section, data)| summarize(*section, data))
+                .collect(),
+        }
+    }
+}
+
+fn summarize(section: Section, data: &SectionData) -> SectionSummary {
+    SectionSummary {
+        section,
+        tables: data
+            .tables
+            .iter()
+            .map(|(table, rows)| (table.clone(), rows.len()))
+            .collect(),
+        scripts: data.scripts.len(),
+    }
+}
+
+// ── File format ───────────────────────────────────────────────────────────────
+
+pub fn write_archive(path: &Path, archive: &SettingsArchive) -> Result<(), AppError> {
+    let json = serde_json::to_vec_pretty(archive)?;
+    let file = std::fs::File::create(path).map_err(|e| io_error(e, "create", path))?;
+    let mut encoder = GzEncoder::new(file, Compression::default());
+    let write_err = |e: std::io::Error| io_error(e, "write", path);
+    encoder.write_all(&json).map_err(write_err)?;
+    encoder.finish().map_err(write_err)?;
+    Ok(())
+}
+
+/// Read a gzip-compressed or plain JSON archive and bring it to the current
+/// format version.
+pub fn read_archive(path: &Path) -> Result<SettingsArchive, AppError> {
+    let bytes = std::fs::read(path).map_err(|e| io_error(e, "read", path))?;
+    let json = if bytes.starts_with(&GZIP_MAGIC) {
+        let mut out = Vec::new();
+        GzDecoder::new(bytes.as_slice())
+            .read_to_end(&mut out)
+            .map_err(|e| AppError::invalid_input(format!("Archive is not valid gzip: {e}")))?;
+        out
+    } else {
+        bytes
+    };
+    let value: Value = serde_json::from_slice(&json)
+        .map_err(|e| AppError::invalid_input(format!("Archive is not valid JSON: {e}")))?;
+    let value = migrate(value)?;
+    serde_json::from_value(value)
+        .map_err(|e| AppError::invalid_input(format!("Unrecognised archive layout: {e}")))
+}
+
+/// Keeps the `io::ErrorKind` code and names the file in the message.
+fn io_error(e: std::io::Error, action: &str, path: &Path) -> AppError {
+    let message = format!("Cannot {action} {}: {e}", path.display());
+    AppError::new(AppError::from(e).code, message)
+}
+
+/// Reject archives from newer builds. When `FORMAT_VERSION` is bumped, older
+/// layouts are upgraded here one version at a time; column-level schema
+/// drift is handled on restore instead.
+fn migrate(value: Value) -> Result<Value, AppError> {
+    let version = value
+        .get("format_version")
+        .and_then(Value::as_u64)
+        .ok_or_else(|| AppError::invalid_input("Archive has no format_version"))?
+        as u32;
+    if version > FORMAT_VERSION {
+        return Err(AppError::new(
+            ErrorCode::Unsupported,
+            format!(
+                "Archive format {version} is newer than this build supports ({FORMAT_VERSION})"
+            ),
+        ));
+    }
+    Ok(value)
+}
+
+// ── Table dump / restore ──────────────────────────────────────────────────────
+
+fn row_to_json(row: &SqliteRow) -> Map<String, Value> {
+    let mut out = Map::new();
+    for (i, column) in row.columns().iter().enumerate() {
+        let value = match row.try_get_raw(i) {
+            Ok(raw) if raw.is_null() => Value::Null,
+            Ok(raw) => match raw.type_info().name() {
+                "INTEGER" | "BOOLEAN" => row
+                    .try_get::<i64, _>(i)
+                    .map(Value::from)
+                    .unwrap_or_default(),
+                "REAL" => row
+                    .try_get::<f64, _>(i)
+                    .map(Value::from)
+                    .unwrap_or_default(),
+                "BLOB" => row
+                    .try_get::<Vec<u8>, _>(i)
+                    .map(|b| serde_json::json!({ "$blob": hex(&b) }))
+                    .unwrap_or_default(),
+                _ => row
+                    .try_get::<String, _>(i)
+                    .map(Value::from)
+                    .unwrap_or_default(),
+            },
+            Err(_) => Value::Null,
+        };
+        out.insert(column.name().to_string(), value);
+    }
+    out
+}
+
+/// Lower-case hex of `bytes` (also used for upload signatures).
+pub(crate) fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+pub(crate) fn unhex(s: &str) -> Option<Vec<u8>> {
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(s.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+fn quote_ident(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+async fn dump_table(pool: &SqlitePool, table: &str) -> Result<Vec<Map<String, Value>>, AppError> {
+    let rows = sqlx::query(&format!("SELECT * FROM {}", quote_ident(table)))
+        .fetch_all(pool)
+        .await
+        .map_err(|e| AppError::db(format!("export of {table} failed: {e}")))?;
+    Ok(rows.iter().map(row_to_json).collect())
+}
+
+async fn table_columns(
+    conn: &mut sqlx::SqliteConnection,
+    table: &str,
+) -> Result<HashSet<String>, sqlx::Error> {
+    let rows = sqlx::query(&format!("PRAGMA table_info({})", quote_ident(table)))
+        .fetch_all(&mut *conn)
+        .await?;
+    Ok(rows.iter().map(|r| r.get::<String, _>("name")).collect())
+}
+
+/// Replace `table` with `rows`, keeping only columns the table still has.
+async fn restore_table(
+    conn: &mut sqlx::SqliteConnection,
+    table: &str,
+    rows: &[Map<String, Value>],
+    dropped: &mut Vec<String>,
+) -> Result<(), AppError> {
+    let err = |e: sqlx::Error| AppError::db(format!("restore of {table} failed: {e}"));
+    let columns = table_columns(conn, table).await.map_err(err)?;
+    if columns.is_empty() {
+        return Err(AppError::db(format!("table {table} does not exist")));
+    }
+    sqlx::query(&format!("DELETE FROM {}", quote_ident(table)))
+        .execute(&mut *conn)
+        .await
+        .map_err(err)?;
+
+    for row in rows {
+        let mut names = Vec::new();
+        let mut values = Vec::new();
+        for (name, value) in row {
+            if columns.contains(name) {
+                names.push(quote_ident(name));
+                values.push(value);
+            } else {
+                let qualified = format!("{table}.{name}");
+                if !dropped.contains(&qualified) {
+                    dropped.push(qualified);
+                }
+            }
+        }
+        if names.is_empty() {
+            continue;
+        }
+        let sql = format!(
+            "INSERT INTO {} ({}) VALUES ({})",
+            quote_ident(table),
+            names.join(", "),
+            vec!["?"; names.len()].join(", ")
+        );
+        let mut query = sqlx::query(&sql);
+        for value in values {
+            query = match value {
+                Value::Null => query.bind(None::<i64>),
+                Value::Bool(b) => query.bind(*b as i64),
+                Value::Number(n) => match n.as_i64() {
+                    Some(i) => query.bind(i),
+                    None => query.bind(n.as_f64()),
+                },
+                Value::String(s) => query.bind(s.clone()),
+                Value::Object(o) if o.len() == 1 && o.contains_key("$blob") => {
+                    query.bind(o["$blob"].as_str().and_then(unhex).unwrap_or_default())
+                }
+                other => query.bind(other.to_string()),
+            };
+        }
+        query.execute(&mut *conn).await.map_err(err)?;
+    }
+    Ok(())
+}
+
+// ── Export / import ───────────────────────────────────────────────────────────
+
+pub async fn export(state: &AppState, sections: &[Section]) -> Result<SettingsArchive, AppError> {
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    let mut out = BTreeMap::new();
+    for section in sections {
+        let mut data = SectionData::default();
+        for table in section.tables() {
+            data.tables
+                .insert(table.to_string(), dump_table(pool, table).await?);
+        }
+        if *section == Section::Scripts {
+            let mut scripts = state.script_engine.get_scripts();
+            scripts.sort_by_key(|s| s.id);
+            data.scripts = scripts;
+        }
+        out.insert(*section, data);
+    }
+    Ok(SettingsArchive {
+        format_version: FORMAT_VERSION,
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        created_at: chrono::Utc::now().timestamp_millis(),
+        sections: out,
+    })
+}
+
+/// Restore `sections` from `archive`. Database sections are replaced in one
+/// transaction; encoders and scripts are reloaded into the running app.
+pub async fn import(
+    state: &AppState,
+    archive: &SettingsArchive,
+    sections: &[Section],
+) -> Result<ImportReport, AppError> {
+    let pool = state
+        .local_db
+        .as_ref()
+        .ok_or_else(AppError::db_unavailable)?;
+    let mut report = ImportReport::default();
+
+    if sections.contains(&Section::Encoders) && archive.sections.contains_key(&Section::Encoders) {
+        let live = state
+            .encoder_manager
+            .get_all_runtime()
+            .into_iter()
+            .any(|r| {
+                matches!(
+                    r.status,
+                    EncoderStatus::Connecting
+                        | EncoderStatus::Streaming
+                        | EncoderStatus::Retrying { .. }
+                        | EncoderStatus::Recording
+                )
+            });
+        if live {
+            return Err(AppError::conflict(
+                "Stop all encoders before restoring encoder settings",
+            ));
+        }
+    }
+
+    let mut tx = pool.begin().await?;
+    for section in sections {
+        let Some(data) = archive.sections.get(section) else {
+            report.missing.push(*section);
+            continue;
+        };
+        for table in section.tables() {
+            let rows = data.tables.get(*table).map(Vec::as_slice).unwrap_or(&[]);
+            restore_table(&mut tx, table, rows, &mut report.dropped_columns).await?;
+        }
+        report.restart_required |= section.needs_restart();
+        report.restored.push(summarize(*section, data));
+    }
+    tx.commit().await?;
+
+    if sections.contains(&Section::Encoders) && archive.sections.contains_key(&Section::Encoders) {
+        for cfg in state.encoder_manager.get_encoders() {
+            state.encoder_manager.delete_encoder(cfg.id);
+        }
+        for cfg in local::load_encoder_configs(pool).await? {
+            state.encoder_manager.save_encoder(cfg);
+        }
+    }
+    if let Some(data) = archive
+        .sections
+        .get(&Section::Scripts)
+        .filter(|_| sections.contains(&Section::Scripts))
+    {
+        let engine = &state.script_engine;
+        let existing: HashSet<i64> = engine.get_scripts().iter().map(|s| s.id).collect();
+        for id in &existing {
+            engine.delete_script(*id);
+            crate::db::scripts::delete_script(pool, *id).await?;
+        }
+        for script in &data.scripts {
+            let id = engine.save_script(script.clone());
+            if let Some(saved) = engine.get_script(id) {
+                crate::db::scripts::save_script(pool, &saved).await?;
+            }
+        }
+    }
+
+    log::info!(
+        "Settings restored: {}",
+        report
+            .restored
+            .iter()
+            .map(|s| format!("{:?}", s.section))
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn archive_round_trips_and_rejects_newer_formats() {
+        let mut row = Map::new();
+        row.insert("id".into(), Value::from(3));
+        row.insert("name".into(), Value::from("Power"));
+        let mut tables = BTreeMap::new();
+        tables.insert("rotation_rules".to_string(), vec![row]);
+        let archive = SettingsArchive {
+            format_version: FORMAT_VERSION,
+            app_version: "0.1.0".into(),
+            created_at: 1,
+            sections: BTreeMap::from([(
+                Section::Rotation,
+                SectionData {
+                    tables,
+                    scripts: Vec::new(),
+                },
+            )]),
+        };
+        let json = serde_json::to_value(&archive).unwrap();
+        assert!(json["sections"]["rotation"]["tables"]["rotation_rules"].is_array());
+        let parsed: SettingsArchive = serde_json::from_value(migrate(json).unwrap()).unwrap();
+        assert_eq!(parsed.summary().sections[0].tables["rotation_rules"], 1);
+
+        let future = serde_json::json!({ "format_version": FORMAT_VERSION + 1 });
+        assert!(migrate(future).is_err());
+    }
+
+    #[test]
+    fn blob_hex_round_trips() {
+        let bytes = vec![0u8, 1, 0xab, 0xff];
+        assert_eq!(unhex(&hex(&bytes)), Some(bytes));
+        assert_eq!(unhex("zz"), None);
+    }
+}
@@ -5,6 +5,7 @@ use sqlx::{MySqlPool, SqlitePool};
 use tokio::sync::RwLock;
 
 use crate::{
+    access::AccessControl,
     analytics::health_monitor::HealthMonitor,
     audio::{
         engine::AudioEngine,
@@ -12,6 +13,7 @@ use crate::{
     },
     commands::gateway_commands::AutoPilotStatus,
     controller::service::ControllerService,
+    gateway::audio_ingest::RemoteAudioIngest,
     gateway::client::GatewayClient,
     gateway::remote_dj::{DjPermissions, RemoteSession},
     scripting::engine::ScriptEngine,
@@ -51,10 +53,14 @@ pub struct AppState {
     pub live_talk_active: Mutex<Option<String>>,
     /// Phase 6 — Mix-minus enabled
     pub mix_minus_enabled: Mutex<bool>,
+    /// Remote DJ live audio ingest (one session at a time)
+    pub remote_audio: Mutex<Option<RemoteAudioIngest>>,
     /// Phase 7 — System health monitor
     pub health_monitor: Arc<HealthMonitor>,
     /// Controller runtime and MIDI integration service
     pub controller_service: Arc<ControllerService>,
+    /// Signed-in operator and role checks
+    pub access: AccessControl,
 }
 
 impl AppState {
@@ -85,8 +91,10 @@ impl AppState {
             remote_dj_permissions: Mutex::new(HashMap::new()),
             live_talk_active: Mutex::new(None),
             mix_minus_enabled: Mutex::new(false),
+            remote_audio: Mutex::new(None),
             health_monitor: Arc::new(HealthMonitor::new()),
             controller_service,
+            access: AccessControl::new(),
         }
     }
 
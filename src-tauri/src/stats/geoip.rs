@@ -0,0 +1,226 @@
+/// `stats/geoip.rs` — GeoIP enrichment for listener sessions
+///
+/// Locates listener IPs with a MaxMind database (GeoLite2 / GeoIP2 City or
+/// Country `.mmdb`; the operator downloads it, the licence does not allow
+/// shipping one) and fills `country_code`, `country` and `city` on
+/// `listener_sessions`. New sessions are located as they are recorded and
+/// the geo report backfills older ones, so a database added later still
+/// covers past listeners. Private and unknown addresses stay unlocated.
+use std::{
+    collections::BTreeMap,
+    net::IpAddr,
+    path::PathBuf,
+    sync::{Arc, Mutex, OnceLock},
+};
+
+use maxminddb::{geoip2, Reader};
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+
+const CONFIG_FILE: &str = "geoip.json";
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GeoIpConfig {
+    /// `.mmdb` file; `None` turns lookups off
+    pub database_path: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GeoIpStatus {
+    pub config: GeoIpConfig,
+    pub loaded: bool,
+    /// e.g. "GeoLite2-City"
+    pub database_type: Option<String>,
+    /// Unix seconds the database was built
+    pub built_at: Option<i64>,
+    /// Why the saved database could not be opened at startup
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GeoLocation {
+    /// ISO 3166-1 alpha-2
+    pub country_code: Option<String>,
+    /// English names
+    pub country: Option<String>,
+    pub city: Option<String>,
+}
+
+struct State {
+    config: GeoIpConfig,
+    reader: Option<Arc<Reader<Vec<u8>>>>,
+    error: Option<String>,
+}
+
+fn config_path() -> PathBuf {
+    PathBuf::from(crate::compute_app_data_dir()).join(CONFIG_FILE)
+}
+
+fn open(config: &GeoIpConfig) -> Result<Option<Reader<Vec<u8>>>, String> {
+    let Some(path) = config
+        .database_path
+        .as_deref()
+        .map(str::trim)
+        .filter(|p| !p.is_empty())
+    else {
+        return Ok(None);
+    };
+    Reader::open_readfile(path)
+        .map(Some)
+        .map_err(|e| format!("Cannot open GeoIP database {path}: {e}"))
+}
+
+fn state() -> &'static Mutex<State> {
+    static STATE: OnceLock<Mutex<State>> = OnceLock::new();
+    STATE.get_or_init(|| {
+        let config = std::fs::read(config_path())
+            .ok()
+            .and_then(|bytes| serde_json::from_slice::<GeoIpConfig>(&bytes).ok())
+            .unwrap_or_default();
+        let (reader, error) = match open(&config) {
+            Ok(reader) => (reader.map(Arc::new), None),
+            Err(e) => {
+                log::warn!("{e}");
+                (None, Some(e))
+            }
+        };
+        Mutex::new(State {
+            config,
+            reader,
+            error,
+        })
+    })
+}
+
+fn reader() -> Option<Arc<Reader<Vec<u8>>>> {
+    state().lock().unwrap().reader.clone()
+}
+
+pub fn status() -> GeoIpStatus {
+    let state = state().lock().unwrap();
+    GeoIpStatus {
+        config: state.config.clone(),
+        loaded: state.reader.is_some(),
+        database_type: state
+            .reader
+            .as_ref()
+            .map(|r| r.metadata.database_type.clone()),
+        built_at: state.reader.as_ref().map(|r| r.metadata.build_epoch as i64),
+        error: state.error.clone(),
+    }
+}
+
+/// Open the database (it must load), save and switch lookups to it.
+pub fn set_config(config: GeoIpConfig) -> Result<GeoIpStatus, String> {
+    let reader = open(&config)?;
+    let json = serde_json::to_vec_pretty(&config).map_err(|e| e.to_string())?;
+    std::fs::write(config_path(), json).map_err(|e| format!("Cannot save GeoIP settings: {e}"))?;
+    {
+        let mut state = state().lock().unwrap();
+        state.config = config;
+        state.reader = reader.map(Arc::new);
+        state.error = None;
+    }
+    Ok(status())
+}
+
+pub fn is_enabled() -> bool {
+    reader().is_some()
+}
+
+/// Where `ip` is, when a database is loaded and knows the address.
+pub fn lookup(ip: &str) -> Option<GeoLocation> {
+    let reader = reader()?;
+    locate(&reader, ip)
+}
+
+fn locate(reader: &Reader<Vec<u8>>, ip: &str) -> Option<GeoLocation> {
+    let addr = parse_ip(ip)?;
+    let found: geoip2::City = reader.lookup(addr).ok()?;
+    let english = |names: Option<BTreeMap<&str, &str>>| {
+        names.and_then(|n| n.get("en").map(|name| name.to_string()))
+    };
+    let country = found.country.or(found.registered_country);
+    Some(GeoLocation {
+        country_code: country
+            .as_ref()
+            .and_then(|c| c.iso_code)
+            .map(str::to_string),
+        country: country.and_then(|c| english(c.names)),
+        city: found.city.and_then(|c| english(c.names)),
+    })
+}
+
+/// Client list IPs, including IPv4 reported in IPv6 form (`::ffff:a.b.c.d`).
+fn parse_ip(ip: &str) -> Option<IpAddr> {
+    let addr: IpAddr = ip
+        .trim()
+        .trim_matches(|c| c == '[' || c == ']')
+        .parse()
+        .ok()?;
+    Some(match addr {
+        IpAddr::V6(v6) => v6.to_ipv4_mapped().map_or(addr, IpAddr::V4),
+        v4 => v4,
+    })
+}
+
+/// Locate sessions seen since `since` that have not been looked up yet.
+/// Returns how many addresses were looked up (0 with no database loaded).
+pub async fn backfill(pool: &SqlitePool, since: i64) -> Result<usize, sqlx::Error> {
+    let Some(reader) = reader() else {
+        return Ok(0);
+    };
+    let ips: Vec<String> = sqlx::query_scalar(
+        "SELECT DISTINCT ip FROM listener_sessions WHERE geo_looked_up = 0 AND last_seen_at >= ?",
+    )
+    .bind(since)
+    .fetch_all(pool)
+    .await?;
+    if ips.is_empty() {
+        return Ok(0);
+    }
+    let mut tx = pool.begin().await?;
+    for ip in &ips {
+        let geo = locate(&reader, ip).unwrap_or_default();
+        sqlx::query(
+            r#"
+            UPDATE listener_sessions
+            SET country_code = ?, country = COALESCE(NULLIF(country, ''), ?), city = ?,
+                geo_looked_up = 1
+            WHERE ip = ? AND geo_looked_up = 0
+            "#,
+        )
+        .bind(&geo.country_code)
+        .bind(&geo.country)
+        .bind(&geo.city)
+        .bind(ip)
+        .execute(&mut *tx)
+        .await?;
+    }
+    tx.commit().await?;
+    log::info!("GeoIP: located {} listener address(es)", ips.len());
+    Ok(ips.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_client_list_addresses() {
+        assert_eq!(
+            parse_ip("::ffff:203.0.113.5"),
+            Some("203.0.113.5".parse().unwrap())
+        );
+        assert_eq!(
+            parse_ip("[2001:db8::1]"),
+            Some("2001:db8::1".parse().unwrap())
+        );
+        assert_eq!(
+            parse_ip(" 198.51.100.7 "),
+            Some("198.51.100.7".parse().unwrap())
+        );
+        assert_eq!(parse_ip("listener.example.com"), None);
+    }
+}
@@ -1,9 +1,29 @@
 /// `stats/icecast_stats.rs` — real-time listener stats collector
 ///
-/// Polls Icecast/Shoutcast admin APIs every 30 seconds and stores snapshots
-/// in the local SQLite database. The Tauri frontend reads these via
-/// `get_listener_stats` / `get_current_listeners`.
+/// Polls Icecast/Shoutcast admin APIs and stores per-mount snapshots in the
+/// local SQLite database. Counts come from `/admin/stats.xml` (Icecast) or
+/// `/stats` (SHOUTcast); every few polls the connected clients are fetched
+/// from `/admin/listclients` / `admin.cgi?page=3` and folded into
+/// `listener_sessions` (IP, user agent, country when the server reports one,
+/// otherwise from the GeoIP database in `geoip.rs`), which is where
+/// unique-listener counts and the geo report come from. The Tauri frontend reads
+/// these via `get_listener_stats`, `get_listener_graph`, `get_listener_peak`
+/// and `get_listener_breakdown`.
+use std::collections::HashSet;
+
+use quick_xml::{
+    events::{BytesStart, Event},
+    reader::Reader,
+};
 use serde::{Deserialize, Deserializer, Serialize};
+use sqlx::SqlitePool;
+
+use super::geoip;
+use crate::stream::encoder_manager::{EncoderConfig, OutputType};
+
+/// A client missing from one poll but back on the next within this window is
+/// the same session (covers a skipped or failed poll).
+const SESSION_GAP_SECS: i64 = 90;
 
 // ── Snapshot model ────────────────────────────────────────────────────────────
 
@@ -16,6 +36,19 @@ pub struct ListenerSnapshot {
     pub peak_listeners: u32,
     pub unique_listeners: u32,
     pub stream_bitrate: Option<u32>,
+    #[serde(default)]
+    pub mount: Option<String>,
+}
+
+/// One connected listener as reported by the server's admin client list.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ListenerClient {
+    /// Server-assigned connection id
+    pub id: String,
+    pub ip: String,
+    pub user_agent: Option<String>,
+    pub connected_secs: Option<u64>,
+    pub country: Option<String>,
 }
 
 // ── Icecast JSON response shapes ─────────────────────────────────────────────
@@ -79,19 +112,38 @@ struct ShoutcastStreamStats {
 // ── Polling helpers ───────────────────────────────────────────────────────────
 
 /// Poll an Icecast 2.x server for listener stats on a given mount.
+///
+/// Uses the admin `stats.xml` (exact per-mount figures) and falls back to the
+/// public `status-json.xsl` when the admin credentials are refused.
 pub async fn poll_icecast(
     host: &str,
     port: u16,
+    admin_user: &str,
     password: &str,
     mount: &str,
     encoder_id: i64,
 ) -> Result<ListenerSnapshot, String> {
-    let url = format!("http://{host}:{port}/status-json.xsl");
-
     let client = reqwest::Client::new();
+    match poll_icecast_admin(&client, host, port, admin_user, password, mount).await {
+        Ok((current_listeners, peak_listeners, stream_bitrate)) => {
+            return Ok(ListenerSnapshot {
+                id: None,
+                encoder_id,
+                snapshot_at: now_ts(),
+                current_listeners,
+                peak_listeners,
+                unique_listeners: 0,
+                stream_bitrate,
+                mount: Some(mount.to_string()),
+            });
+        }
+        Err(e) => log::debug!("Icecast admin stats unavailable, using status-json: {e}"),
+    }
+
+    let url = format!("http://{host}:{port}/status-json.xsl");
     let resp = client
         .get(&url)
-        .basic_auth("admin", Some(password))
+        .basic_auth(admin_user, Some(password))
         .timeout(std::time::Duration::from_secs(8))
         .send()
         .await
@@ -114,10 +166,16 @@ pub async fn poll_icecast(
         .find(|s| {
             s.listenurl
                 .as_deref()
-                .map(|u| u.contains(mount))
+                .map(|u| u.ends_with(mount))
                 .unwrap_or(false)
         })
-        .or_else(|| sources.first());
+        .or_else(|| {
+            if sources.len() == 1 {
+                sources.first()
+            } else {
+                None
+            }
+        });
 
     let now = now_ts();
     Ok(ListenerSnapshot {
@@ -126,11 +184,72 @@ pub async fn poll_icecast(
         snapshot_at: now,
         current_listeners: source.and_then(|s| s.listeners).unwrap_or(0),
         peak_listeners: source.and_then(|s| s.listener_peak).unwrap_or(0),
-        unique_listeners: 0, // Icecast does not expose unique count
+        unique_listeners: 0, // filled in from the client list
         stream_bitrate: source.and_then(|s| s.bitrate),
+        mount: Some(mount.to_string()),
     })
 }
 
+/// `(listeners, listener_peak, bitrate)` for one mount from `/admin/stats.xml`.
+async fn poll_icecast_admin(
+    client: &reqwest::Client,
+    host: &str,
+    port: u16,
+    admin_user: &str,
+    password: &str,
+    mount: &str,
+) -> Result<(u32, u32, Option<u32>), String> {
+    let url = format!("http://{host}:{port}/admin/stats.xml");
+    let body = fetch_text(client.get(&url).basic_auth(admin_user, Some(password))).await?;
+    let source = xml_elements(&body, "source")
+        .into_iter()
+        .find(|el| el.attr("mount").as_deref() == Some(mount))
+        .ok_or_else(|| format!("mount {mount} not found in stats.xml"))?;
+    let num = |tag: &str| {
+        source
+            .child_text(tag)
+            .and_then(|v| v.trim().parse::<u32>().ok())
+    };
+    Ok((
+        num("listeners").unwrap_or(0),
+        num("listener_peak").unwrap_or(0),
+        num("bitrate").or_else(|| num("ice-bitrate")),
+    ))
+}
+
+/// Connected clients on an Icecast mount (`/admin/listclients`).
+pub async fn poll_icecast_clients(
+    host: &str,
+    port: u16,
+    admin_user: &str,
+    password: &str,
+    mount: &str,
+) -> Result<Vec<ListenerClient>, String> {
+    let client = reqwest::Client::new();
+    let url = format!(
+        "http://{host}:{port}/admin/listclients?mount={}",
+        urlencoding::encode(mount)
+    );
+    let body = fetch_text(client.get(&url).basic_auth(admin_user, Some(password))).await?;
+    Ok(parse_icecast_clients(&body))
+}
+
+fn parse_icecast_clients(xml: &str) -> Vec<ListenerClient> {
+    xml_elements(xml, "listener")
+        .into_iter()
+        .filter_map(|el| {
+            let ip = el.child_text("IP")?;
+            Some(ListenerClient {
+                id: el.child_text("ID").unwrap_or_else(|| ip.clone()),
+                ip,
+                user_agent: el.child_text("UserAgent").filter(|ua| !ua.is_empty()),
+                connected_secs: el.child_text("Connected").and_then(|c| c.parse().ok()),
+                country: el.child_text("Country").filter(|c| !c.is_empty()),
+            })
+        })
+        .collect()
+}
+
 /// Poll a SHOUTcast server for listener stats.
 pub async fn poll_shoutcast(
     host: &str,
@@ -242,9 +361,270 @@ pub async fn poll_shoutcast(
         peak_listeners,
         unique_listeners,
         stream_bitrate,
+        mount: Some(shoutcast_mount(sid)),
     })
 }
 
+fn shoutcast_mount(sid: u32) -> String {
+    format!("sid={}", sid.max(1))
+}
+
+#[derive(Debug, Deserialize)]
+struct ShoutcastListener {
+    #[serde(default)]
+    hostname: Option<String>,
+    #[serde(default)]
+    useragent: Option<String>,
+    #[serde(default, deserialize_with = "de_opt_u32_any")]
+    connecttime: Option<u32>,
+    #[serde(default, deserialize_with = "de_opt_u32_any")]
+    uid: Option<u32>,
+    #[serde(default)]
+    country: Option<String>,
+}
+
+/// Connected clients on a SHOUTcast stream (`admin.cgi?page=3`): the v2 JSON
+/// view first, then the v1 XML view.
+pub async fn poll_shoutcast_clients(
+    host: &str,
+    port: u16,
+    password: &str,
+    sid: u32,
+) -> Result<Vec<ListenerClient>, String> {
+    let sid = sid.max(1);
+    let client = reqwest::Client::new();
+    let pass = urlencoding::encode(password);
+
+    let url = format!("http://{host}:{port}/admin.cgi?sid={sid}&mode=viewjson&page=3&pass={pass}");
+    let v2_err = match fetch_text(client.get(&url)).await {
+        Ok(body) => match serde_json::from_str::<Vec<ShoutcastListener>>(&body) {
+            Ok(listeners) => {
+                return Ok(listeners
+                    .into_iter()
+                    .filter_map(|l| {
+                        let ip = l.hostname.filter(|h| !h.is_empty())?;
+                        Some(ListenerClient {
+                            id: l.uid.map(|u| u.to_string()).unwrap_or_else(|| ip.clone()),
+                            ip,
+                            user_agent: l.useragent.filter(|ua| !ua.is_empty()),
+                            connected_secs: l.connecttime.map(u64::from),
+                            country: l.country.filter(|c| !c.is_empty()),
+                        })
+                    })
+                    .collect())
+            }
+            Err(e) => format!("JSON parse error: {e}"),
+        },
+        Err(e) => e,
+    };
+
+    let url = format!("http://{host}:{port}/admin.cgi?mode=viewxml&page=3&pass={pass}");
+    let body = fetch_text(client.get(&url))
+        .await
+        .map_err(|e| format!("SHOUTcast client list failed: {v2_err}; {e}"))?;
+    Ok(xml_elements(&body, "LISTENER")
+        .into_iter()
+        .filter_map(|el| {
+            let ip = el.child_text("HOSTNAME")?;
+            Some(ListenerClient {
+                id: el.child_text("UID").unwrap_or_else(|| ip.clone()),
+                ip,
+                user_agent: el.child_text("USERAGENT").filter(|ua| !ua.is_empty()),
+                connected_secs: el.child_text("CONNECTTIME").and_then(|c| c.parse().ok()),
+                country: None,
+            })
+        })
+        .collect())
+}
+
+// ── Collector ─────────────────────────────────────────────────────────────────
+
+/// Poll one encoder's server and persist the result. With `with_clients`,
+/// the client list is fetched as well, sessions are updated and the
+/// snapshot's `unique_listeners` is the number of distinct IPs connected.
+pub async fn collect(
+    pool: Option<&SqlitePool>,
+    cfg: &EncoderConfig,
+    with_clients: bool,
+) -> Result<ListenerSnapshot, String> {
+    let host = cfg.server_host.as_deref().unwrap_or("localhost");
+    let port = cfg.server_port.unwrap_or(8000);
+    let source_password = cfg.server_password.as_deref().unwrap_or("");
+    let admin_user = cfg.admin_username.as_deref().unwrap_or("admin");
+    let admin_password = cfg.admin_password.as_deref().unwrap_or(source_password);
+
+    let mut snap = match cfg.output_type {
+        OutputType::Icecast => {
+            let mount = cfg.mount_point.as_deref().unwrap_or("/stream");
+            poll_icecast(host, port, admin_user, admin_password, mount, cfg.id).await?
+        }
+        OutputType::Shoutcast => {
+            poll_shoutcast(host, port, admin_password, cfg.shoutcast_sid, cfg.id).await?
+        }
+        OutputType::File => return Err("File encoders have no listeners".to_string()),
+    };
+
+    if with_clients {
+        let clients = match cfg.output_type {
+            OutputType::Icecast => {
+                let mount = cfg.mount_point.as_deref().unwrap_or("/stream");
+                poll_icecast_clients(host, port, admin_user, admin_password, mount).await
+            }
+            _ => poll_shoutcast_clients(host, port, admin_password, cfg.shoutcast_sid).await,
+        };
+        match clients {
+            Ok(clients) => {
+                if snap.unique_listeners == 0 {
+                    snap.unique_listeners = unique_ips(&clients);
+                }
+                if let Some(pool) = pool {
+                    if let Err(e) = record_sessions(pool, &snap, &clients).await {
+                        log::warn!("listener sessions for encoder {}: {e}", cfg.id);
+                    }
+                }
+            }
+            // Counts are still worth keeping without the client list.
+            Err(e) => log::debug!("listener client list failed for encoder {}: {e}", cfg.id),
+        }
+    }
+
+    if let Some(pool) = pool {
+        if let Err(e) = insert_snapshot(pool, &snap).await {
+            log::warn!(
+                "listener snapshot insert failed for encoder {}: {e}",
+                cfg.id
+            );
+        }
+    }
+    Ok(snap)
+}
+
+fn unique_ips(clients: &[ListenerClient]) -> u32 {
+    clients
+        .iter()
+        .map(|c| c.ip.as_str())
+        .collect::<HashSet<_>>()
+        .len() as u32
+}
+
+pub(crate) async fn fetch_text(req: reqwest::RequestBuilder) -> Result<String, String> {
+    let resp = req
+        .timeout(std::time::Duration::from_secs(8))
+        .send()
+        .await
+        .map_err(|e| format!("request failed: {e}"))?;
+    let status = resp.status();
+    if !status.is_success() {
+        return Err(format!("HTTP {status}"));
+    }
+    resp.text()
+        .await
+        .map_err(|e| format!("response read error: {e}"))
+}
+
+// ── XML reading ───────────────────────────────────────────────────────────────
+
+/// One matched element: its attributes and the text of every element nested
+/// inside it, in document order.
+pub(crate) struct XmlElement {
+    attrs: Vec<(String, String)>,
+    children: Vec<(String, String)>,
+}
+
+impl XmlElement {
+    fn open(e: &BytesStart<'_>) -> Self {
+        Self {
+            attrs: e
+                .attributes()
+                .flatten()
+                .map(|a| {
+                    (
+                        String::from_utf8_lossy(a.key.local_name().as_ref()).to_string(),
+                        a.unescape_value()
+                            .map(|v| v.into_owned())
+                            .unwrap_or_default(),
+                    )
+                })
+                .collect(),
+            children: Vec::new(),
+        }
+    }
+
+    pub(crate) fn attr(&self, name: &str) -> Option<String> {
+        self.attrs
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.clone())
+    }
+
+    /// Text of the first nested element named `tag` (case-insensitive).
+    pub(crate) fn child_text(&self, tag: &str) -> Option<String> {
+        self.children
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(tag))
+            .map(|(_, text)| text.trim().to_string())
+    }
+}
+
+fn element_name(e: &BytesStart<'_>) -> String {
+    String::from_utf8_lossy(e.local_name().as_ref()).to_string()
+}
+
+/// Every `<tag …>…</tag>` element in `xml`, matched case-insensitively and
+/// without descending into nested elements of the same name. Malformed XML
+/// ends the scan; whatever was complete by then is returned.
+pub(crate) fn xml_elements(xml: &str, tag: &str) -> Vec<XmlElement> {
+    let mut reader = Reader::from_str(xml);
+    reader.trim_text(true);
+    let mut out = Vec::new();
+    let mut current: Option<XmlElement> = None;
+    // Indices into `current.children` of the elements still open, innermost last
+    let mut open = Vec::new();
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(e)) => match current.as_mut() {
+                Some(el) => {
+                    el.children.push((element_name(&e), String::new()));
+                    open.push(el.children.len() - 1);
+                }
+                None if element_name(&e).eq_ignore_ascii_case(tag) => {
+                    current = Some(XmlElement::open(&e));
+                }
+                None => {}
+            },
+            Ok(Event::Empty(e)) => match current.as_mut() {
+                Some(el) => el.children.push((element_name(&e), String::new())),
+                None if element_name(&e).eq_ignore_ascii_case(tag) => {
+                    out.push(XmlElement::open(&e));
+                }
+                None => {}
+            },
+            Ok(Event::Text(t)) => {
+                if let (Some(el), Some(&i), Ok(text)) =
+                    (current.as_mut(), open.last(), t.unescape())
+                {
+                    el.children[i].1.push_str(&text);
+                }
+            }
+            Ok(Event::CData(t)) => {
+                if let (Some(el), Some(&i)) = (current.as_mut(), open.last()) {
+                    el.children[i]
+                        .1
+                        .push_str(&String::from_utf8_lossy(&t.into_inner()));
+                }
+            }
+            Ok(Event::End(_)) => {
+                if open.pop().is_none() {
+                    out.extend(current.take());
+                }
+            }
+            Ok(Event::Eof) | Err(_) => break,
+            Ok(_) => {}
+        }
+    }
+    out
+}
+
 // ── SQLite persistence helpers ────────────────────────────────────────────────
 
 fn de_opt_u32_any<'de, D>(deserializer: D) -> Result<Option<u32>, D::Error>
@@ -276,13 +656,120 @@ pub async fn ensure_table(pool: &sqlx::SqlitePool) -> Result<(), sqlx::Error> {
         );
         CREATE INDEX IF NOT EXISTS idx_ls_encoder_time
             ON listener_snapshots (encoder_id, snapshot_at);
+
+        -- One row per listener connection seen in the admin client list.
+        -- `country` is NULL unless the server reports one.
+        CREATE TABLE IF NOT EXISTS listener_sessions (
+            id            INTEGER PRIMARY KEY,
+            encoder_id    INTEGER NOT NULL,
+            mount         TEXT,
+            client_id     TEXT    NOT NULL,
+            ip            TEXT    NOT NULL,
+            user_agent    TEXT,
+            country       TEXT,
+            connected_at  INTEGER NOT NULL,
+            last_seen_at  INTEGER NOT NULL
+        );
+        CREATE INDEX IF NOT EXISTS idx_lsess_encoder_seen
+            ON listener_sessions (encoder_id, last_seen_at);
         "#,
     )
     .execute(pool)
     .await?;
+    // Added with per-mount history; fails harmlessly once the column exists.
+    let _ = sqlx::query("ALTER TABLE listener_snapshots ADD COLUMN mount TEXT")
+        .execute(pool)
+        .await;
+    // GeoIP location; `geo_looked_up` marks rows the database has been asked
+    // about, found or not, so the report only backfills new ones.
+    for column in [
+        "country_code TEXT",
+        "city TEXT",
+        "geo_looked_up INTEGER NOT NULL DEFAULT 0",
+    ] {
+        let _ = sqlx::query(&format!(
+            "ALTER TABLE listener_sessions ADD COLUMN {column}"
+        ))
+        .execute(pool)
+        .await;
+    }
     Ok(())
 }
 
+/// Fold the current client list into `listener_sessions`: clients seen
+/// recently extend their session, everyone else opens a new one, located
+/// with GeoIP when a database is loaded.
+pub async fn record_sessions(
+    pool: &SqlitePool,
+    snap: &ListenerSnapshot,
+    clients: &[ListenerClient],
+) -> Result<(), String> {
+    let now = snap.snapshot_at;
+    let geo_enabled = geoip::is_enabled();
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| format!("record_sessions: {e}"))?;
+    for client in clients {
+        let updated = sqlx::query(
+            r#"
+            UPDATE listener_sessions
+            SET last_seen_at = ?,
+                user_agent = COALESCE(?, user_agent),
+                country = COALESCE(?, country)
+            WHERE encoder_id = ? AND client_id = ? AND ip = ? AND last_seen_at >= ?
+            "#,
+        )
+        .bind(now)
+        .bind(&client.user_agent)
+        .bind(&client.country)
+        .bind(snap.encoder_id)
+        .bind(&client.id)
+        .bind(&client.ip)
+        .bind(now - SESSION_GAP_SECS)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("record_sessions: {e}"))?
+        .rows_affected();
+        if updated > 0 {
+            continue;
+        }
+        let connected_at = client
+            .connected_secs
+            .map(|secs| now - secs as i64)
+            .unwrap_or(now);
+        let geo = geo_enabled
+            .then(|| geoip::lookup(&client.ip))
+            .flatten()
+            .unwrap_or_default();
+        sqlx::query(
+            r#"
+            INSERT INTO listener_sessions
+                (encoder_id, mount, client_id, ip, user_agent, country, country_code, city,
+                 geo_looked_up, connected_at, last_seen_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(snap.encoder_id)
+        .bind(&snap.mount)
+        .bind(&client.id)
+        .bind(&client.ip)
+        .bind(&client.user_agent)
+        .bind(client.country.as_ref().or(geo.country.as_ref()))
+        .bind(&geo.country_code)
+        .bind(&geo.city)
+        .bind(geo_enabled)
+        .bind(connected_at)
+        .bind(now)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| format!("record_sessions: {e}"))?;
+    }
+    tx.commit()
+        .await
+        .map_err(|e| format!("record_sessions: {e}"))
+}
+
 /// Insert a snapshot into SQLite and return its id.
 pub async fn insert_snapshot(
     pool: &sqlx::SqlitePool,
@@ -291,8 +778,8 @@ pub async fn insert_snapshot(
     let id = sqlx::query_scalar::<_, i64>(
         r#"
         INSERT INTO listener_snapshots
-            (encoder_id, snapshot_at, current_listeners, peak_listeners, unique_listeners, stream_bitrate)
-        VALUES (?, ?, ?, ?, ?, ?)
+            (encoder_id, snapshot_at, current_listeners, peak_listeners, unique_listeners, stream_bitrate, mount)
+        VALUES (?, ?, ?, ?, ?, ?, ?)
         RETURNING id
         "#,
     )
@@ -302,6 +789,7 @@ pub async fn insert_snapshot(
     .bind(snap.peak_listeners as i64)
     .bind(snap.unique_listeners as i64)
     .bind(snap.stream_bitrate.map(|b| b as i64))
+    .bind(&snap.mount)
     .fetch_one(pool)
     .await
     .map_err(|e| format!("insert_snapshot: {e}"))?;
@@ -315,10 +803,10 @@ pub async fn get_snapshots(
     period_secs: i64,
 ) -> Result<Vec<ListenerSnapshot>, String> {
     let cutoff = now_ts() - period_secs;
-    let rows = sqlx::query_as::<_, (i64, i64, i64, i64, i64, i64, Option<i64>)>(
+    let rows = sqlx::query_as::<_, (i64, i64, i64, i64, i64, i64, Option<i64>, Option<String>)>(
         r#"
         SELECT id, encoder_id, snapshot_at,
-               current_listeners, peak_listeners, unique_listeners, stream_bitrate
+               current_listeners, peak_listeners, unique_listeners, stream_bitrate, mount
         FROM listener_snapshots
         WHERE encoder_id = ? AND snapshot_at >= ?
         ORDER BY snapshot_at ASC
@@ -333,7 +821,7 @@ pub async fn get_snapshots(
     Ok(rows
         .into_iter()
         .map(
-            |(id, enc_id, snap_at, cur, peak, uniq, bitrate)| ListenerSnapshot {
+            |(id, enc_id, snap_at, cur, peak, uniq, bitrate, mount)| ListenerSnapshot {
                 id: Some(id),
                 encoder_id: enc_id,
                 snapshot_at: snap_at,
@@ -341,6 +829,7 @@ pub async fn get_snapshots(
                 peak_listeners: peak as u32,
                 unique_listeners: uniq as u32,
                 stream_bitrate: bitrate.map(|b| b as u32),
+                mount,
             },
         )
         .collect())
@@ -354,3 +843,39 @@ fn now_ts() -> i64 {
         .unwrap_or_default()
         .as_secs() as i64
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_icecast_listclients() {
+        let xml = r#"<?xml version="1.0"?>
+<icestats><source mount="/live"><fallback/><Listeners>2</Listeners>
+<listener><IP>203.0.113.7</IP><UserAgent>VLC/3.0 &amp; co</UserAgent><Connected>125</Connected><ID>41</ID></listener>
+<listener><IP>198.51.100.2</IP><UserAgent></UserAgent><Connected>3</Connected><ID>44</ID></listener>
+</source></icestats>"#;
+        let clients = parse_icecast_clients(xml);
+        assert_eq!(clients.len(), 2);
+        assert_eq!(clients[0].id, "41");
+        assert_eq!(clients[0].user_agent.as_deref(), Some("VLC/3.0 & co"));
+        assert_eq!(clients[0].connected_secs, Some(125));
+        assert_eq!(clients[1].user_agent, None);
+    }
+
+    #[test]
+    fn finds_mount_in_stats_xml() {
+        let xml = r#"<icestats><listeners>9</listeners>
+<source mount="/high"><listeners>5</listeners><listener_peak>8</listener_peak></source>
+<source mount="/low"><listeners>4</listeners><bitrate>64</bitrate></source></icestats>"#;
+        let sources = xml_elements(xml, "source");
+        assert_eq!(sources.len(), 2);
+        let low = sources
+            .iter()
+            .find(|s| s.attr("mount").as_deref() == Some("/low"))
+            .unwrap();
+        assert_eq!(low.child_text("listeners").as_deref(), Some("4"));
+        assert_eq!(low.child_text("bitrate").as_deref(), Some("64"));
+        assert_eq!(low.child_text("listener_peak"), None);
+    }
+}
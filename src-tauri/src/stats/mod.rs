@@ -1 +1,2 @@
+pub mod geoip;
 pub mod icecast_stats;
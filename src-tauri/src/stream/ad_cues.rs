@@ -0,0 +1,121 @@
+/// `ad_cues.rs` — ad-break cue markers for downstream ad insertion
+///
+/// When a traffic break's first spot goes to air, every encoder with ad cues
+/// on pushes a cue-out marker as its stream title; when the break's last spot
+/// ends it pushes a cue-in. A server-side splicer watching the ICY metadata
+/// can then replace the break with targeted ads. The same boundaries go to
+/// the UI and the overlay feed as `ad_break` events.
+///
+/// Markers travel as ICY metadata because that is what the stream loops
+/// carry (MP3 over Icecast / SHOUTcast); there is no HLS or Ogg output to put
+/// SCTE-35 tags or Vorbis comments in.
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager};
+
+use super::overlay_server;
+use crate::scheduler::traffic::{AdBreak, PlannedSpot};
+use crate::state::AppState;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CuePhase {
+    /// Break starts: downstream may splice its own ads in
+    Out,
+    /// Break over: back to the station's audio
+    In,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AdBreakCue {
+    pub break_id: Option<i64>,
+    pub name: String,
+    pub spots: usize,
+    /// Sum of the planned spot lengths
+    pub duration_ms: u64,
+}
+
+impl AdBreakCue {
+    pub fn from_plan(ad_break: &AdBreak, spots: &[PlannedSpot]) -> Self {
+        Self {
+            break_id: ad_break.id,
+            name: ad_break.name.clone(),
+            spots: spots.len(),
+            duration_ms: spots
+                .iter()
+                .map(|s| u64::from(s.campaign.duration_secs) * 1000)
+                .sum(),
+        }
+    }
+
+    /// Fill `{break}`, `{break_id}`, `{spots}`, `{duration_ms}` and `{duration_secs}`.
+    pub fn render(&self, template: &str) -> String {
+        template
+            .replace("{break}", &self.name)
+            .replace(
+                "{break_id}",
+                &self.break_id.map(|id| id.to_string()).unwrap_or_default(),
+            )
+            .replace("{spots}", &self.spots.to_string())
+            .replace("{duration_ms}", &self.duration_ms.to_string())
+            .replace(
+                "{duration_secs}",
+                &self.duration_ms.div_ceil(1000).to_string(),
+            )
+    }
+}
+
+#[derive(Serialize)]
+struct AdBreakEvent<'a> {
+    phase: CuePhase,
+    /// Unix ms
+    at_ms: i64,
+    #[serde(flatten)]
+    cue: &'a AdBreakCue,
+}
+
+/// Announce a break boundary and push the marker to the encoders.
+pub fn send(app: &AppHandle, phase: CuePhase, cue: AdBreakCue) {
+    let event = AdBreakEvent {
+        phase,
+        at_ms: chrono::Utc::now().timestamp_millis(),
+        cue: &cue,
+    };
+    let _ = app.emit("ad_break", &event);
+    overlay_server::publish("ad_break", &event);
+    log::info!(
+        "Ad break '{}' cue-{}",
+        cue.name,
+        match phase {
+            CuePhase::Out => "out",
+            CuePhase::In => "in",
+        }
+    );
+
+    let manager = app.state::<AppState>().encoder_manager.clone();
+    tauri::async_runtime::spawn(async move {
+        manager.push_ad_cue(phase, &cue).await;
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_cue_templates() {
+        let cue = AdBreakCue {
+            break_id: Some(7),
+            name: "Top of hour".to_string(),
+            spots: 3,
+            duration_ms: 89_500,
+        };
+        assert_eq!(
+            cue.render("AD_BREAK_START id={break_id} duration_ms={duration_ms} spots={spots}"),
+            "AD_BREAK_START id=7 duration_ms=89500 spots=3"
+        );
+        assert_eq!(
+            cue.render("{break} ({duration_secs}s)"),
+            "Top of hour (90s)"
+        );
+    }
+}
@@ -108,4 +108,7 @@ pub struct EncoderRuntimeState {
     pub current_bitrate_kbps: Option<u32>,
     pub error: Option<String>,
     pub recording_file: Option<String>,
+    /// Reconnect failures since the last stable session
+    #[serde(default)]
+    pub consecutive_failures: u32,
 }
@@ -1,15 +1,15 @@
 /// `encoder_file.rs` — stream-to-file recording with rotation
 ///
-/// Writes the master PCM audio to disk as WAV (or raw PCM for stubs).
-/// Rotation modes: None, Hourly, Daily, BySize.
+/// Writes the master audio to disk as WAV, FLAC, MP3 or Opus (see
+/// `recording_writer`). Rotation modes: None, Hourly, Daily, BySize, ByTrack
+/// (a new file as each track goes on air) and ByDuration (every
+/// `file_split_minutes`).
 /// On rotation: closes current file, opens new file — no audio gap intended
 /// (gap may be a few frames while the file handle switches).
-/// Also writes a companion `.cue` file with track markers (populated via
-/// the `record_cue_entry` helper called by the track-change command).
+/// Each closed file can get a `.cue` sheet or JSON chapter sidecar listing the
+/// tracks heard in it (`recording_chapters`).
 use std::{
-    io::Write,
     path::{Path, PathBuf},
-    sync::{Arc, Mutex},
     time::{Duration, SystemTime, UNIX_EPOCH},
 };
 
@@ -19,6 +19,9 @@ use tokio::sync::oneshot;
 use super::{
     broadcaster::EncoderStatus,
     encoder_manager::{EncoderConfig, EncoderManager, FileRotation},
+    recording_chapters, recording_writer,
+    recording_writer::RecordingWriter,
+    watermark::Watermarker,
 };
 
 /// Async recording loop — runs inside the encoder task.
@@ -34,19 +37,23 @@ pub async fn record_loop_async(
 
     let id = config.id;
     let max_bytes = config.file_max_size_mb * 1024 * 1024;
+    let split_ms = i64::from(config.file_split_minutes.max(1)) * 60_000;
     let rotation = &config.file_rotation;
 
     let mut state = RecordingState::new(config, output_dir)?;
+    manager.set_recording_file(id, Some(state.file_name()));
     manager.set_status(id, EncoderStatus::Recording, None);
 
     // 20 ms frames at 44100 Hz stereo
     const FRAME_SAMPLES: usize = 1764 * 2;
     let mut pcm_buf = vec![0.0f32; FRAME_SAMPLES];
+    let mut watermark = Watermarker::for_encoder(config);
 
     loop {
         // Non-blocking stop check
         if stop_rx.try_recv().is_ok() {
-            state.close();
+            state.close(config);
+            manager.set_recording_file(id, None);
             return Ok(());
         }
 
@@ -67,49 +74,36 @@ pub async fn record_loop_async(
             continue;
         }
 
-        // Write samples as 16-bit LE
-        for &s in &pcm_buf[..filled] {
-            let s16 = (s.clamp(-1.0, 1.0) * i16::MAX as f32) as i16;
-            if let Err(e) = state.writer.write_all(&s16.to_le_bytes()) {
-                return Err(format!("Write error: {e}"));
-            }
+        if let Some(wm) = watermark.as_mut() {
+            wm.process(&mut pcm_buf[..filled]);
         }
-        state.bytes_written += (filled * 2) as u64;
+
+        state.writer.write(&pcm_buf[..filled])?;
 
         // Check rotation triggers
+        let now = now_ms();
         let rotate = match rotation {
             FileRotation::None => false,
-            FileRotation::BySize => state.bytes_written >= max_bytes,
-            FileRotation::Hourly => {
-                let now = SystemTime::now()
-                    .duration_since(UNIX_EPOCH)
-                    .unwrap_or_default()
-                    .as_secs();
-                now / 3600 != state.started_epoch / 3600
-            }
-            FileRotation::Daily => {
-                let now = SystemTime::now()
-                    .duration_since(UNIX_EPOCH)
-                    .unwrap_or_default()
-                    .as_secs();
-                now / 86400 != state.started_epoch / 86400
+            FileRotation::BySize => state.writer.bytes_written() >= max_bytes,
+            FileRotation::Hourly => now / 3_600_000 != state.started_ms / 3_600_000,
+            FileRotation::Daily => now / 86_400_000 != state.started_ms / 86_400_000,
+            FileRotation::ByTrack => {
+                recording_chapters::latest_mark_ms().is_some_and(|at| at > state.started_ms)
             }
+            FileRotation::ByDuration => now - state.started_ms >= split_ms,
         };
 
         if rotate {
             let old_path = state.current_path.clone();
-            state.close();
+            state.close(config);
             state = RecordingState::new(config, output_dir)?;
+            manager.set_recording_file(id, Some(state.file_name()));
 
             log::info!(
                 "Recording rotated: {:?} → {:?}",
                 old_path,
                 state.current_path
             );
-
-            // Update UI
-            let new_file = state.current_path.to_str().unwrap_or_default().to_string();
-            // Note: in a full implementation, emit a `recording_rotation` Tauri event here
         }
 
         // Yield to other tasks
@@ -117,86 +111,96 @@ pub async fn record_loop_async(
     }
 }
 
+fn now_ms() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as i64
+}
+
 // ── Internal recording state ─────────────────────────────────────────────────
 
 struct RecordingState {
-    writer: std::io::BufWriter<std::fs::File>,
+    writer: RecordingWriter,
     current_path: PathBuf,
-    bytes_written: u64,
-    started_epoch: u64,
+    started_ms: i64,
 }
 
 impl RecordingState {
     fn new(config: &EncoderConfig, output_dir: &str) -> Result<Self, String> {
-        let now = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap_or_default()
-            .as_secs();
-
+        let ext = recording_writer::extension(&config.codec)?;
         let filename = expand_template(
             &config.file_name_template,
             config.stream_name.as_deref().unwrap_or("desizone"),
             config.bitrate_kbps.unwrap_or(128),
-            "wav",
+            ext,
         );
-        let path = Path::new(output_dir).join(&filename);
-
-        let file = std::fs::File::create(&path)
-            .map_err(|e| format!("Cannot create recording file {:?}: {e}", path))?;
+        let path = unique_path(Path::new(output_dir).join(&filename).with_extension(ext));
 
-        let mut writer = std::io::BufWriter::new(file);
-        write_wav_header(&mut writer, config.sample_rate, config.channels)
-            .map_err(|e| format!("WAV header error: {e}"))?;
+        let writer = RecordingWriter::create(
+            &path,
+            &config.codec,
+            config.sample_rate,
+            config.channels,
+            config.bitrate_kbps.unwrap_or(128),
+        )?;
 
         log::info!("Recording started: {:?}", path);
 
         Ok(Self {
             writer,
             current_path: path,
-            bytes_written: 0,
-            started_epoch: now,
+            started_ms: now_ms(),
         })
     }
 
-    fn close(&mut self) {
-        let _ = self.writer.flush();
-        // Optionally: patch WAV header with correct data size here
+    fn file_name(&self) -> String {
+        self.current_path.to_str().unwrap_or_default().to_string()
+    }
+
+    fn close(self, config: &EncoderConfig) {
+        if let Err(e) = self.writer.finish() {
+            log::warn!(
+                "Recording {:?} did not close cleanly: {e}",
+                self.current_path
+            );
+        }
+        let station = config.stream_name.as_deref().unwrap_or("desizone");
+        if let Err(e) = recording_chapters::write_sidecar(
+            config.file_sidecar,
+            &self.current_path,
+            station,
+            self.started_ms,
+            now_ms(),
+        ) {
+            log::warn!("{e}");
+        }
         log::info!("Recording closed: {:?}", self.current_path);
     }
 }
 
-// ── WAV header ────────────────────────────────────────────────────────────────
-
-fn write_wav_header(
-    writer: &mut impl Write,
-    sample_rate: u32,
-    channels: u8,
-) -> std::io::Result<()> {
-    let num_channels = channels as u32;
-    let bits_per_sample: u32 = 16;
-    let byte_rate = sample_rate * num_channels * bits_per_sample / 8;
-    let block_align = (num_channels * bits_per_sample / 8) as u16;
-
-    // RIFF header (data size unknown → 0xFFFFFFFF as placeholder)
-    writer.write_all(b"RIFF")?;
-    writer.write_all(&0xFFFFFFFFu32.to_le_bytes())?; // chunk size placeholder
-    writer.write_all(b"WAVE")?;
-
-    // fmt chunk
-    writer.write_all(b"fmt ")?;
-    writer.write_all(&16u32.to_le_bytes())?; // chunk size
-    writer.write_all(&1u16.to_le_bytes())?; // PCM
-    writer.write_all(&(channels as u16).to_le_bytes())?;
-    writer.write_all(&sample_rate.to_le_bytes())?;
-    writer.write_all(&byte_rate.to_le_bytes())?;
-    writer.write_all(&block_align.to_le_bytes())?;
-    writer.write_all(&(bits_per_sample as u16).to_le_bytes())?;
-
-    // data chunk header
-    writer.write_all(b"data")?;
-    writer.write_all(&0xFFFFFFFFu32.to_le_bytes())?; // size placeholder
-
-    Ok(())
+/// Splits can land in the same second as the previous file; never overwrite.
+fn unique_path(path: PathBuf) -> PathBuf {
+    if !path.exists() {
+        return path;
+    }
+    let stem = path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("recording")
+        .to_string();
+    let ext = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or_default();
+    let mut n = 2;
+    loop {
+        let candidate = path.with_file_name(format!("{stem}-{n}.{ext}"));
+        if !candidate.exists() {
+            return candidate;
+        }
+        n += 1;
+    }
 }
 
 // ── File name template expansion ─────────────────────────────────────────────
@@ -280,7 +284,7 @@ fn is_leap(y: u64) -> bool {
     (y % 4 == 0 && y % 100 != 0) || y % 400 == 0
 }
 
-fn slugify(s: &str) -> String {
+pub(crate) fn slugify(s: &str) -> String {
     s.chars()
         .map(|c| {
             if c.is_alphanumeric() {
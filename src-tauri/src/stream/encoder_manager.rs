@@ -13,7 +13,13 @@ use futures_util::FutureExt;
 use serde::{Deserialize, Serialize};
 use tokio::task::JoinHandle;
 
+use super::ad_cues::{AdBreakCue, CuePhase};
 use super::broadcaster::{Broadcaster, EncoderRuntimeState, EncoderStatus, SlotId};
+use super::encoder_probe::{self, EncoderDiagnosis};
+use super::failover::{self, FailoverAction};
+use super::recording_chapters::RecordingSidecar;
+use super::title_format;
+use super::watermark::WatermarkConfig;
 
 // ── Encoder configuration (mirrors DB table) ─────────────────────────────────
 
@@ -47,6 +53,8 @@ pub enum Codec {
     Ogg,
     Wav,
     Flac,
+    /// Opus in Ogg (recordings only)
+    Opus,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -56,6 +64,10 @@ pub enum FileRotation {
     Hourly,
     Daily,
     BySize,
+    /// New file when the next track goes on air
+    ByTrack,
+    /// New file every `file_split_minutes`
+    ByDuration,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -84,6 +96,9 @@ pub struct EncoderConfig {
     pub icecast_version: IcecastVersion,
     pub shoutcast_version: ShoutcastVersion,
     pub shoutcast_sid: u32,
+    /// Admin login for listener stats; defaults to `admin` + `server_password`
+    pub admin_username: Option<String>,
+    pub admin_password: Option<String>,
     pub stream_name: Option<String>,
     pub stream_genre: Option<String>,
     pub stream_url: Option<String>,
@@ -95,16 +110,43 @@ pub struct EncoderConfig {
     pub file_rotation: FileRotation,
     pub file_max_size_mb: u64,
     pub file_name_template: String,
+    pub file_split_minutes: u32,
+    /// Chapter list written next to each closed file
+    pub file_sidecar: RecordingSidecar,
 
     // Metadata
     pub send_metadata: bool,
     pub icy_metadata_interval: u32,
+    /// `{artist}`, `{title}`, `{show}`, `{station}` (or `$artist$`, `$title$`, `$combine$`)
     pub metadata_caption_template: Option<String>,
     pub metadata_url_append: Option<String>,
+    pub metadata_uppercase: bool,
+    /// Characters; longer titles end in "..."; 0 = no limit
+    pub metadata_max_length: u32,
+    /// Romanise non-Latin scripts for players that garble Unicode
+    pub metadata_transliterate: bool,
+
+    // Ad cues
+    /// Push cue-out / cue-in markers as the stream title around ad breaks
+    pub ad_cue_enabled: bool,
+    /// `{break}`, `{break_id}`, `{spots}`, `{duration_ms}`, `{duration_secs}`
+    pub ad_cue_out_template: String,
+    pub ad_cue_in_template: String,
 
     // Reconnect
     pub reconnect_delay_secs: u64,
     pub max_reconnect_attempts: u32, // 0 = infinite
+
+    // Watermark (this feed only)
+    pub watermark: WatermarkConfig,
+
+    // Failover
+    /// Encoders sharing a group back each other up; `None` = standalone
+    pub failover_group: Option<String>,
+    /// Lowest in the group is the primary; the rest are backups in order
+    pub priority: u32,
+    /// Consecutive reconnect failures before the next encoder takes over
+    pub failover_after_attempts: u32,
 }
 
 impl Default for EncoderConfig {
@@ -127,6 +169,8 @@ impl Default for EncoderConfig {
             icecast_version: IcecastVersion::V2,
             shoutcast_version: ShoutcastVersion::V2,
             shoutcast_sid: 1,
+            admin_username: None,
+            admin_password: None,
             stream_name: Some("DesiZone Radio".to_string()),
             stream_genre: Some("Various".to_string()),
             stream_url: None,
@@ -136,16 +180,38 @@ impl Default for EncoderConfig {
             file_rotation: FileRotation::Hourly,
             file_max_size_mb: 500,
             file_name_template: "{date}-{time}-{station}.mp3".to_string(),
+            file_split_minutes: 60,
+            file_sidecar: RecordingSidecar::None,
             send_metadata: true,
             icy_metadata_interval: 8192,
             metadata_caption_template: Some("$combine$".to_string()),
             metadata_url_append: None,
+            metadata_uppercase: false,
+            metadata_max_length: 0,
+            metadata_transliterate: false,
+            ad_cue_enabled: false,
+            ad_cue_out_template:
+                "AD_BREAK_START id={break_id} duration_ms={duration_ms} spots={spots}".to_string(),
+            ad_cue_in_template: "AD_BREAK_END id={break_id}".to_string(),
             reconnect_delay_secs: 5,
             max_reconnect_attempts: 0,
+            watermark: WatermarkConfig::default(),
+            failover_group: None,
+            priority: 0,
+            failover_after_attempts: 3,
         }
     }
 }
 
+impl EncoderConfig {
+    fn group(&self) -> Option<&str> {
+        self.failover_group
+            .as_deref()
+            .map(str::trim)
+            .filter(|g| !g.is_empty())
+    }
+}
+
 // ── In-memory record for a running encoder task ───────────────────────────────
 
 struct RunningEncoder {
@@ -165,6 +231,10 @@ pub struct EncoderManager {
     runtime: Arc<Mutex<HashMap<i64, EncoderRuntimeState>>>,
     tasks: Arc<Mutex<HashMap<i64, RunningEncoder>>>,
     started_at: Arc<Mutex<HashMap<i64, Instant>>>,
+    /// Failover group → backup currently standing in for its primary
+    active_backups: Arc<Mutex<HashMap<String, i64>>>,
+    /// Engine sample rate from the last start, reused when a backup is started
+    source_sample_rate: Arc<Mutex<Option<u32>>>,
 }
 
 impl EncoderManager {
@@ -175,6 +245,8 @@ impl EncoderManager {
             runtime: Arc::new(Mutex::new(HashMap::new())),
             tasks: Arc::new(Mutex::new(HashMap::new())),
             started_at: Arc::new(Mutex::new(HashMap::new())),
+            active_backups: Arc::new(Mutex::new(HashMap::new())),
+            source_sample_rate: Arc::new(Mutex::new(None)),
         }
     }
 
@@ -202,12 +274,17 @@ impl EncoderManager {
             current_bitrate_kbps: None,
             error: None,
             recording_file: None,
+            consecutive_failures: 0,
         });
         id
     }
 
     pub fn delete_encoder(&self, id: i64) {
         self.stop_encoder(id);
+        self.active_backups
+            .lock()
+            .unwrap()
+            .retain(|_, backup| *backup != id);
         self.configs.lock().unwrap().remove(&id);
         self.runtime.lock().unwrap().remove(&id);
         self.broadcaster.remove_slot(id);
@@ -281,6 +358,18 @@ impl EncoderManager {
         }
     }
 
+    pub(crate) fn set_recording_file(&self, id: i64, path: Option<String>) {
+        if let Some(r) = self.runtime.lock().unwrap().get_mut(&id) {
+            r.recording_file = path;
+        }
+    }
+
+    pub(crate) fn set_consecutive_failures(&self, id: i64, failures: u32) {
+        if let Some(r) = self.runtime.lock().unwrap().get_mut(&id) {
+            r.consecutive_failures = failures;
+        }
+    }
+
     pub fn update_listeners(&self, encoder_id: i64, count: u32) {
         let mut rt = self.runtime.lock().unwrap();
         if let Some(r) = rt.get_mut(&encoder_id) {
@@ -308,6 +397,9 @@ impl EncoderManager {
             }
         };
 
+        if let Some(sr) = source_sample_rate.filter(|sr| *sr > 0) {
+            *self.source_sample_rate.lock().unwrap() = Some(sr);
+        }
         if let Some(sr) = source_sample_rate {
             if sr > 0 && config.sample_rate != sr {
                 log::info!(
@@ -368,6 +460,7 @@ impl EncoderManager {
             .lock()
             .unwrap()
             .insert(id, RunningEncoder { handle, stop_tx });
+        self.set_consecutive_failures(id, 0);
         self.set_status(id, EncoderStatus::Connecting, None);
     }
 
@@ -385,14 +478,25 @@ impl EncoderManager {
     }
 
     pub fn start_all_with_sample_rate(&self, source_sample_rate: Option<u32>) {
-        let ids: Vec<i64> = self
-            .configs
-            .lock()
-            .unwrap()
-            .values()
-            .filter(|c| c.enabled)
-            .map(|c| c.id)
-            .collect();
+        let ids: Vec<i64> = {
+            let configs = self.configs.lock().unwrap();
+            configs
+                .values()
+                .filter(|c| c.enabled)
+                .filter(|c| {
+                    // Backups only start on failover.
+                    let Some(group) = c.group() else {
+                        return true;
+                    };
+                    !configs.values().any(|other| {
+                        other.enabled
+                            && other.group() == Some(group)
+                            && (other.priority, other.id) < (c.priority, c.id)
+                    })
+                })
+                .map(|c| c.id)
+                .collect()
+        };
         for id in ids {
             self.start_encoder_with_sample_rate(id, source_sample_rate, None);
         }
@@ -405,78 +509,150 @@ impl EncoderManager {
         }
     }
 
+    // ── Failover groups ───────────────────────────────────────────────────
+
+    /// Evaluate every failover group once and apply the result: start the next
+    /// backup for a failing primary, or stop the backup once the primary is
+    /// stable again. Returns what was done so the caller can notify.
+    pub fn supervise_failover(&self) -> Vec<FailoverAction> {
+        let mut groups: HashMap<String, Vec<EncoderConfig>> = HashMap::new();
+        for cfg in self.get_encoders() {
+            if !cfg.enabled {
+                continue;
+            }
+            if let Some(group) = cfg.group() {
+                groups.entry(group.to_string()).or_default().push(cfg);
+            }
+        }
+
+        let mut actions = Vec::new();
+        for (group, mut configs) in groups {
+            if configs.len() < 2 {
+                continue;
+            }
+            configs.sort_by_key(|c| (c.priority, c.id));
+            let members: Vec<failover::Member> = {
+                let rt = self.runtime.lock().unwrap();
+                configs
+                    .iter()
+                    .map(|c| failover::Member::new(c, rt.get(&c.id)))
+                    .collect()
+            };
+            let active = self.active_backups.lock().unwrap().get(&group).copied();
+            let Some(action) = failover::plan(&group, &members, active) else {
+                continue;
+            };
+            self.apply_failover(&action, members[0].id);
+            actions.push(action);
+        }
+        actions
+    }
+
+    fn apply_failover(&self, action: &FailoverAction, primary_id: i64) {
+        log::warn!("{}", action.describe());
+        match action {
+            FailoverAction::Failover { group, from, to } => {
+                if *from != primary_id {
+                    self.stop_encoder(*from);
+                }
+                let sr = *self.source_sample_rate.lock().unwrap();
+                self.start_encoder_with_sample_rate(*to, sr, None);
+                self.active_backups
+                    .lock()
+                    .unwrap()
+                    .insert(group.clone(), *to);
+            }
+            FailoverAction::Failback { group, backup, .. }
+            | FailoverAction::Release { group, backup } => {
+                self.stop_encoder(*backup);
+                self.active_backups.lock().unwrap().remove(group);
+            }
+        }
+    }
+
     // ── Connection test ───────────────────────────────────────────────────
 
-    pub async fn test_connection(&self, id: i64) -> Result<(), String> {
+    /// Probe the encoder's server; see `encoder_probe` for what is checked.
+    pub async fn test_connection(&self, id: i64) -> Result<EncoderDiagnosis, String> {
         let config = self.get_encoder(id).ok_or("Encoder not found")?;
         let host = config.server_host.as_deref().unwrap_or("localhost");
         let port = config.server_port.unwrap_or(8000);
         let user = config.server_username.as_deref().unwrap_or("<unset>");
         let mount = config.mount_point.as_deref().unwrap_or("/stream");
+        let live = self
+            .get_runtime(id)
+            .is_some_and(|rt| rt.status == EncoderStatus::Streaming);
         match config.output_type {
             OutputType::Icecast => {
                 log::info!(
-                    "Encoder test: type=icecast id={} host={} port={} mount={} user={} version={:?}",
+                    "Encoder test: type=icecast id={} host={} port={} mount={} user={} version={:?} live={}",
                     config.id,
                     host,
                     port,
                     mount,
                     user,
-                    config.icecast_version
+                    config.icecast_version,
+                    live
                 );
-                super::icecast::test_icecast_connection(&config).await
+                Ok(encoder_probe::probe_icecast(&config, live).await)
             }
             OutputType::Shoutcast => {
                 log::info!(
-                    "Encoder test: type=shoutcast id={} host={} port={} sid={} user={} version={:?}",
+                    "Encoder test: type=shoutcast id={} host={} port={} sid={} user={} version={:?} live={}",
                     config.id,
                     host,
                     port,
                     config.shoutcast_sid,
                     user,
-                    config.shoutcast_version
+                    config.shoutcast_version,
+                    live
                 );
-                super::shoutcast::test_shoutcast_connection(&config).await
+                Ok(encoder_probe::probe_shoutcast(&config, live).await)
             }
             OutputType::File => {
                 log::info!("Encoder test: type=file id={} (always passes)", config.id);
-                Ok(())
+                Ok(encoder_probe::file_diagnosis())
             }
         }
     }
 
     // ── Metadata push ─────────────────────────────────────────────────────
 
-    pub async fn push_metadata(&self, artist: &str, title: &str) {
+    /// Push the track to every encoder, titled per its `title_format` settings.
+    pub async fn push_metadata(&self, artist: &str, title: &str, artwork_url: Option<&str>) {
         let configs = self.get_encoders();
+        let show = crate::scheduler::show_scheduler::on_air_show_name();
         for cfg in &configs {
             if !cfg.send_metadata {
                 continue;
             }
-            let combined = format!("{artist} - {title}");
-            let song = cfg
-                .metadata_caption_template
-                .as_deref()
-                .filter(|t| !t.trim().is_empty())
-                .map(|template| {
-                    template
-                        .replace("$combine$", &combined)
-                        .replace("$artist$", artist)
-                        .replace("$title$", title)
-                })
-                .unwrap_or(combined);
+            let song = title_format::format_stream_title(cfg, artist, title, show.as_deref());
+            // Fields in the URL-append template follow the title's script.
+            let (artist, title) = if cfg.metadata_transliterate {
+                (
+                    title_format::to_latin(artist),
+                    title_format::to_latin(title),
+                )
+            } else {
+                (artist.to_string(), title.to_string())
+            };
             match cfg.output_type {
                 OutputType::Icecast => {
-                    if let Err(e) =
-                        super::metadata_pusher::push_icecast_metadata(cfg, artist, title, &song)
-                            .await
+                    if let Err(e) = super::metadata_pusher::push_icecast_metadata(
+                        cfg,
+                        &artist,
+                        &title,
+                        &song,
+                        artwork_url,
+                    )
+                    .await
                     {
                         log::warn!("Metadata push failed for encoder {}: {e}", cfg.id);
                     }
                 }
                 OutputType::Shoutcast => {
                     if let Err(e) =
-                        super::metadata_pusher::push_shoutcast_metadata(cfg, artist, title, &song)
+                        super::metadata_pusher::push_shoutcast_metadata(cfg, &artist, &title, &song)
                             .await
                     {
                         log::warn!("Metadata push failed for encoder {}: {e}", cfg.id);
@@ -486,6 +662,30 @@ impl EncoderManager {
             }
         }
     }
+
+    /// Send an ad-break marker to encoders with ad cues on. Markers skip the
+    /// title formatting so splicers see them verbatim.
+    pub async fn push_ad_cue(&self, phase: CuePhase, cue: &AdBreakCue) {
+        for cfg in self.get_encoders().iter().filter(|c| c.ad_cue_enabled) {
+            let marker = cue.render(match phase {
+                CuePhase::Out => &cfg.ad_cue_out_template,
+                CuePhase::In => &cfg.ad_cue_in_template,
+            });
+            let result = match cfg.output_type {
+                OutputType::Icecast => {
+                    super::metadata_pusher::push_icecast_metadata(cfg, "", &marker, &marker, None)
+                        .await
+                }
+                OutputType::Shoutcast => {
+                    super::metadata_pusher::push_shoutcast_metadata(cfg, "", &marker, &marker).await
+                }
+                OutputType::File => continue,
+            };
+            if let Err(e) = result {
+                log::warn!("Ad cue push failed for encoder {}: {e}", cfg.id);
+            }
+        }
+    }
 }
 
 // ── Per-encoder async task ────────────────────────────────────────────────────
@@ -568,6 +768,7 @@ async fn run_encoder_task(
                     attempt = 0;
                 }
                 attempt += 1;
+                manager.set_consecutive_failures(id, attempt);
                 log::warn!("Encoder {id} error (attempt {attempt}): {e}");
 
                 if max_attempts > 0 && attempt >= max_attempts {
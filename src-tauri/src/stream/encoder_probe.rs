@@ -0,0 +1,670 @@
+/// `encoder_probe.rs` — encoder connection test with server diagnosis
+///
+/// Goes further than a TCP connect: identifies the server and its version,
+/// checks that the mount (Icecast) or stream ID (SHOUTcast) is free, reads
+/// listener capacity, tries the source login and flags setups known to mangle
+/// track titles. Each finding is a `ProbeCheck`, so the encoder editor can say
+/// what to fix rather than just "Failed".
+///
+/// The source login is skipped while the encoder itself is on air — the
+/// server would refuse a second source and the live one is proof enough.
+use std::time::Duration;
+
+use serde::Serialize;
+use serde_json::Value;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+use super::encoder_manager::{EncoderConfig, ShoutcastVersion};
+use super::shoutcast::{connect_legacy_source, source_password_for_v2, source_ports};
+use crate::stats::icecast_stats::{fetch_text, xml_elements};
+
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// SHOUTcast 1.x serves the stream instead of `7.html` to non-browser agents.
+const BROWSER_USER_AGENT: &str = "Mozilla/5.0 (compatible; DesiZone Broadcaster)";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckLevel {
+    Ok,
+    Warning,
+    Error,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProbeCheck {
+    /// Short label, e.g. "Source login"
+    pub name: String,
+    pub level: CheckLevel,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct EncoderDiagnosis {
+    /// No check failed at `Error` level
+    pub ok: bool,
+    /// `icecast`, `shoutcast_v1`, `shoutcast_v2` or `file`
+    pub server_type: String,
+    /// As the server reports it, e.g. "Icecast 2.4.4"
+    pub server_version: Option<String>,
+    /// Mount / stream ID has no other source; `None` when the probe could not tell
+    pub mount_available: Option<bool>,
+    pub auth_ok: Option<bool>,
+    pub max_listeners: Option<u32>,
+    pub current_listeners: Option<u32>,
+    pub checks: Vec<ProbeCheck>,
+}
+
+impl EncoderDiagnosis {
+    fn new(server_type: &str) -> Self {
+        Self {
+            ok: true,
+            server_type: server_type.to_string(),
+            ..Self::default()
+        }
+    }
+
+    fn check(&mut self, name: &str, level: CheckLevel, message: impl Into<String>) {
+        if level == CheckLevel::Error {
+            self.ok = false;
+        }
+        self.checks.push(ProbeCheck {
+            name: name.to_string(),
+            level,
+            message: message.into(),
+        });
+    }
+
+    fn check_capacity(&mut self) {
+        match (self.current_listeners, self.max_listeners) {
+            (Some(current), Some(max)) if max > 0 && current * 10 >= max * 9 => self.check(
+                "Listeners",
+                CheckLevel::Warning,
+                format!("{current} of {max} listener slots in use"),
+            ),
+            (current, max) => self.check(
+                "Listeners",
+                CheckLevel::Ok,
+                format!(
+                    "{} connected, limit {}",
+                    current.map_or("?".to_string(), |c| c.to_string()),
+                    max.map_or("unlimited".to_string(), |m| m.to_string()),
+                ),
+            ),
+        }
+    }
+}
+
+pub fn file_diagnosis() -> EncoderDiagnosis {
+    let mut diag = EncoderDiagnosis::new("file");
+    diag.check("Output", CheckLevel::Ok, "File output needs no server");
+    diag
+}
+
+// ── Icecast ───────────────────────────────────────────────────────────────────
+
+pub async fn probe_icecast(config: &EncoderConfig, live: bool) -> EncoderDiagnosis {
+    let host = config.server_host.as_deref().unwrap_or("localhost");
+    let port = config.server_port.unwrap_or(8000);
+    let mount = config.mount_point.as_deref().unwrap_or("/stream");
+    let user = config
+        .server_username
+        .as_deref()
+        .filter(|s| !s.trim().is_empty())
+        .unwrap_or("source");
+    let password = config.server_password.as_deref().unwrap_or("");
+    let admin_user = config.admin_username.as_deref().unwrap_or("admin");
+    let admin_password = config.admin_password.as_deref().unwrap_or(password);
+
+    let mut diag = EncoderDiagnosis::new("icecast");
+    let client = reqwest::Client::new();
+
+    // Public status page: server identity and the mounts already on air.
+    let resp = match client
+        .get(format!("http://{host}:{port}/status-json.xsl"))
+        .timeout(PROBE_TIMEOUT)
+        .send()
+        .await
+    {
+        Ok(resp) => resp,
+        Err(e) => {
+            diag.check(
+                "Server",
+                CheckLevel::Error,
+                format!("Cannot reach {host}:{port}: {e}"),
+            );
+            return diag;
+        }
+    };
+    let server_header = resp
+        .headers()
+        .get(reqwest::header::SERVER)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string);
+    // Icecast before 2.4 has no status-json.xsl; the header still identifies it.
+    let status: Option<Value> = if resp.status().is_success() {
+        resp.json().await.ok()
+    } else {
+        None
+    };
+    let icestats = status.as_ref().and_then(|s| s.get("icestats"));
+    diag.server_version = icestats
+        .and_then(|s| s.get("server_id"))
+        .and_then(Value::as_str)
+        .map(str::to_string)
+        .or(server_header);
+    if let Some(icestats) = icestats {
+        let sources = match icestats.get("source") {
+            Some(Value::Array(list)) => list.iter().collect(),
+            Some(one @ Value::Object(_)) => vec![one],
+            _ => Vec::new(),
+        };
+        let taken = sources.iter().any(|s| {
+            s.get("listenurl")
+                .and_then(Value::as_str)
+                .is_some_and(|url| url.ends_with(mount))
+        });
+        diag.mount_available = Some(!taken);
+    }
+
+    match diag.server_version.clone() {
+        None => diag.check(
+            "Server",
+            CheckLevel::Warning,
+            "Server answered but did not say what it is",
+        ),
+        Some(v) if !v.to_ascii_lowercase().contains("icecast") => diag.check(
+            "Server",
+            CheckLevel::Warning,
+            format!("Server identifies as \"{v}\", not Icecast"),
+        ),
+        Some(v) if parse_version(&v).is_some_and(|ver| ver < (2, 4, 0)) => diag.check(
+            "Server",
+            CheckLevel::Error,
+            format!("{v} predates HTTP PUT sources (Icecast 2.4); upgrade the server"),
+        ),
+        Some(v) => diag.check("Server", CheckLevel::Ok, v),
+    }
+
+    match diag.mount_available {
+        Some(false) if live => diag.check(
+            "Mount",
+            CheckLevel::Ok,
+            format!("{mount} is on air from this encoder"),
+        ),
+        Some(false) => diag.check(
+            "Mount",
+            CheckLevel::Error,
+            format!("{mount} already has a source connected"),
+        ),
+        Some(true) => diag.check("Mount", CheckLevel::Ok, format!("{mount} is free")),
+        None => {}
+    }
+
+    // Admin stats: listener count and limit for the mount.
+    let stats_url = format!("http://{host}:{port}/admin/stats.xml");
+    match fetch_text(
+        client
+            .get(&stats_url)
+            .basic_auth(admin_user, Some(admin_password)),
+    )
+    .await
+    {
+        Ok(xml) => {
+            let num = |text: Option<String>| text.and_then(|t| t.trim().parse::<u32>().ok());
+            let source = xml_elements(&xml, "source")
+                .into_iter()
+                .find(|el| el.attr("mount").as_deref() == Some(mount));
+            diag.current_listeners = match &source {
+                Some(source) => num(source.child_text("listeners")),
+                None => xml_elements(&xml, "icestats")
+                    .first()
+                    .and_then(|stats| num(stats.child_text("listeners"))),
+            };
+            diag.max_listeners = source.and_then(|s| num(s.child_text("max_listeners")));
+            diag.check_capacity();
+        }
+        Err(e) => diag.check(
+            "Admin stats",
+            CheckLevel::Warning,
+            format!("Admin login {admin_user} not accepted ({e}); listener stats will be limited"),
+        ),
+    }
+
+    if live {
+        diag.auth_ok = Some(true);
+        diag.check(
+            "Source login",
+            CheckLevel::Ok,
+            "Accepted (encoder is streaming)",
+        );
+        return diag;
+    }
+    match icecast_source_login(host, port, mount, user, password).await {
+        Ok((100 | 200, _)) => {
+            diag.auth_ok = Some(true);
+            diag.mount_available.get_or_insert(true);
+            diag.check(
+                "Source login",
+                CheckLevel::Ok,
+                format!("{user} accepted for {mount}"),
+            );
+        }
+        Ok((401, _)) => {
+            diag.auth_ok = Some(false);
+            diag.check(
+                "Source login",
+                CheckLevel::Error,
+                format!("Source user \"{user}\" or password rejected"),
+            );
+        }
+        Ok((403, reason)) => {
+            // Icecast checks credentials before the mount, so a 403 means auth passed.
+            diag.auth_ok = Some(true);
+            if reason.to_ascii_lowercase().contains("in use") {
+                if diag.mount_available == Some(false) {
+                    // Already reported under "Mount".
+                    return diag;
+                }
+                diag.mount_available = Some(false);
+            }
+            diag.check(
+                "Source login",
+                CheckLevel::Error,
+                format!("Server refused the source: {reason}"),
+            );
+        }
+        Ok((code, reason)) => diag.check(
+            "Source login",
+            CheckLevel::Error,
+            format!("Unexpected HTTP {code}: {reason}"),
+        ),
+        Err(e) => diag.check(
+            "Source login",
+            CheckLevel::Warning,
+            format!("Could not confirm the login: {e}"),
+        ),
+    }
+    diag
+}
+
+/// Start a PUT source with `Expect: 100-continue` and hang up before sending
+/// audio. Icecast answers 100 only once the login and mount are accepted.
+async fn icecast_source_login(
+    host: &str,
+    port: u16,
+    mount: &str,
+    user: &str,
+    password: &str,
+) -> Result<(u16, String), String> {
+    // reqwest does the Basic encoding; the request itself is never sent.
+    let auth = reqwest::Client::new()
+        .put(format!("http://{host}:{port}{mount}"))
+        .basic_auth(user, Some(password))
+        .build()
+        .ok()
+        .and_then(|req| {
+            req.headers()
+                .get(reqwest::header::AUTHORIZATION)?
+                .to_str()
+                .ok()
+                .map(str::to_string)
+        })
+        .ok_or("cannot encode credentials")?;
+
+    let mut stream = tokio::time::timeout(PROBE_TIMEOUT, TcpStream::connect((host, port)))
+        .await
+        .map_err(|_| format!("connect to {host}:{port} timed out"))?
+        .map_err(|e| format!("connect to {host}:{port} failed: {e}"))?;
+    let request = format!(
+        "PUT {mount} HTTP/1.1\r\n\
+         Host: {host}:{port}\r\n\
+         Authorization: {auth}\r\n\
+         User-Agent: DesiZone Broadcaster\r\n\
+         Content-Type: audio/mpeg\r\n\
+         Ice-Public: 0\r\n\
+         Expect: 100-continue\r\n\
+         \r\n"
+    );
+    stream
+        .write_all(request.as_bytes())
+        .await
+        .map_err(|e| format!("request write failed: {e}"))?;
+
+    let mut reply = [0u8; 2048];
+    let n = tokio::time::timeout(PROBE_TIMEOUT, stream.read(&mut reply))
+        .await
+        .map_err(|_| "no reply to the source login".to_string())?
+        .map_err(|e| format!("reply read failed: {e}"))?;
+    parse_http_reply(&String::from_utf8_lossy(&reply[..n]))
+        .ok_or_else(|| "server reply was not HTTP".to_string())
+}
+
+/// Status code and a readable reason (the error page text, else the status line).
+fn parse_http_reply(reply: &str) -> Option<(u16, String)> {
+    let status_line = reply.lines().next()?;
+    let mut parts = status_line.splitn(3, ' ');
+    if !parts.next()?.starts_with("HTTP/") {
+        return None;
+    }
+    let code = parts.next()?.parse().ok()?;
+    let status_text = parts.next().unwrap_or_default().trim();
+    let body = reply.split_once("\r\n\r\n").map_or("", |(_, body)| body);
+    let body_text = strip_tags(body)
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ");
+    let reason = if body_text.is_empty() {
+        status_text.to_string()
+    } else {
+        body_text
+    };
+    Some((code, reason))
+}
+
+// ── SHOUTcast ─────────────────────────────────────────────────────────────────
+
+enum ShoutcastServer {
+    /// DNAS 2.x `/statistics?json=1`
+    V2 {
+        version: Option<String>,
+        stats: Value,
+    },
+    /// DNAS 1.x `/7.html`
+    V1(SevenHtml),
+}
+
+/// The comma-separated status line of SHOUTcast 1.x `/7.html`.
+#[derive(Debug, PartialEq)]
+struct SevenHtml {
+    current_listeners: u32,
+    source_connected: bool,
+    max_listeners: u32,
+}
+
+pub async fn probe_shoutcast(config: &EncoderConfig, live: bool) -> EncoderDiagnosis {
+    let host = config.server_host.as_deref().unwrap_or("localhost");
+    let port = config.server_port.unwrap_or(8000);
+    let password = config.server_password.as_deref().unwrap_or("");
+    let admin_password = config.admin_password.as_deref().unwrap_or(password);
+    let user = config
+        .server_username
+        .as_deref()
+        .filter(|s| !s.trim().is_empty())
+        .unwrap_or("source");
+    let sid = config.shoutcast_sid.max(1);
+
+    let mut diag = EncoderDiagnosis::new(match config.shoutcast_version {
+        ShoutcastVersion::V1 => "shoutcast_v1",
+        ShoutcastVersion::V2 => "shoutcast_v2",
+    });
+    let client = reqwest::Client::builder()
+        .user_agent(BROWSER_USER_AGENT)
+        .timeout(PROBE_TIMEOUT)
+        .build()
+        .unwrap_or_default();
+
+    let Some(server) = detect_shoutcast(&client, host, port, admin_password).await else {
+        diag.check(
+            "Server",
+            CheckLevel::Error,
+            format!("No SHOUTcast status page answered on {host}:{port}"),
+        );
+        return diag;
+    };
+
+    let detected_v2 = matches!(server, ShoutcastServer::V2 { .. });
+    match &server {
+        ShoutcastServer::V2 { version, stats } => {
+            diag.server_version = Some(format!(
+                "SHOUTcast DNAS {}",
+                version.as_deref().unwrap_or("2.x")
+            ));
+            let stream = stats
+                .get("streams")
+                .and_then(Value::as_array)
+                .and_then(|streams| {
+                    streams
+                        .iter()
+                        .find(|s| s.get("id").and_then(value_u32) == Some(sid))
+                });
+            let num = |v: Option<&Value>, key: &str| v.and_then(|v| v.get(key)).and_then(value_u32);
+            diag.current_listeners =
+                num(stream, "currentlisteners").or_else(|| num(Some(stats), "currentlisteners"));
+            diag.max_listeners =
+                num(stream, "maxlisteners").or_else(|| num(Some(stats), "maxlisteners"));
+            if matches!(config.shoutcast_version, ShoutcastVersion::V2) {
+                match stream {
+                    Some(stream) => {
+                        diag.mount_available = Some(num(Some(stream), "streamstatus") != Some(1));
+                    }
+                    None => diag.check(
+                        "Stream ID",
+                        CheckLevel::Error,
+                        format!("Stream ID {sid} is not configured on the server"),
+                    ),
+                }
+            }
+        }
+        ShoutcastServer::V1(seven) => {
+            diag.server_version = Some("SHOUTcast DNAS 1.x".to_string());
+            diag.current_listeners = Some(seven.current_listeners);
+            diag.max_listeners = Some(seven.max_listeners);
+            diag.mount_available = Some(!seven.source_connected);
+        }
+    }
+    let server_version = diag.server_version.clone().unwrap_or_default();
+    diag.check("Server", CheckLevel::Ok, server_version);
+
+    match (&config.shoutcast_version, detected_v2) {
+        (ShoutcastVersion::V2, false) => diag.check(
+            "Protocol",
+            CheckLevel::Error,
+            "Server runs SHOUTcast 1.x; set the protocol to v1",
+        ),
+        (ShoutcastVersion::V1, true) => diag.check(
+            "Protocol",
+            CheckLevel::Warning,
+            "Server runs DNAS 2.x; choose v2 to use stream IDs and UTF-8 titles",
+        ),
+        _ => diag.check("Protocol", CheckLevel::Ok, "Matches the server"),
+    }
+
+    let slot = if detected_v2 {
+        format!("Stream ID {sid}")
+    } else {
+        "The stream".to_string()
+    };
+    match diag.mount_available {
+        Some(false) if live => diag.check(
+            "Stream ID",
+            CheckLevel::Ok,
+            format!("{slot} is on air from this encoder"),
+        ),
+        Some(false) => diag.check(
+            "Stream ID",
+            CheckLevel::Error,
+            format!("{slot} already has a source connected"),
+        ),
+        Some(true) => diag.check("Stream ID", CheckLevel::Ok, format!("{slot} is free")),
+        None => {}
+    }
+    diag.check_capacity();
+
+    if live {
+        diag.auth_ok = Some(true);
+        diag.check(
+            "Source login",
+            CheckLevel::Ok,
+            "Accepted (encoder is streaming)",
+        );
+    } else if diag.mount_available != Some(false) {
+        // The same login the stream loop uses for the configured protocol.
+        let (password_line, ports, label) = match config.shoutcast_version {
+            ShoutcastVersion::V1 => (password.to_string(), vec![port], "SHOUTcast v1 probe"),
+            ShoutcastVersion::V2 => (
+                source_password_for_v2(config, sid, user),
+                source_ports(port, true),
+                "SHOUTcast v2 probe",
+            ),
+        };
+        let mut result = Err(String::new());
+        for p in ports {
+            result = connect_legacy_source(host, p, &password_line, label)
+                .await
+                .map(|_| p);
+            if result.is_ok() {
+                break;
+            }
+        }
+        match result {
+            Ok(p) => {
+                diag.auth_ok = Some(true);
+                diag.check(
+                    "Source login",
+                    CheckLevel::Ok,
+                    format!("Accepted on port {p}"),
+                );
+            }
+            Err(e) => {
+                diag.auth_ok = Some(false);
+                diag.check("Source login", CheckLevel::Error, e);
+            }
+        }
+    }
+
+    if config.send_metadata {
+        if detected_v2 {
+            diag.check(
+                "Title charset",
+                CheckLevel::Ok,
+                "UTF-8 titles are shown as sent",
+            );
+        } else {
+            diag.check(
+                "Title charset",
+                CheckLevel::Warning,
+                "SHOUTcast 1.x reads titles as Latin-1: accented, Hindi or Urdu titles \
+                 will show garbled to listeners. DNAS 2.x handles UTF-8.",
+            );
+        }
+    }
+    diag
+}
+
+async fn detect_shoutcast(
+    client: &reqwest::Client,
+    host: &str,
+    port: u16,
+    password: &str,
+) -> Option<ShoutcastServer> {
+    let stats_url = format!(
+        "http://{host}:{port}/statistics?json=1&pass={}",
+        urlencoding::encode(password)
+    );
+    if let Ok(body) = fetch_text(client.get(&stats_url)).await {
+        if let Ok(stats) = serde_json::from_str::<Value>(&body) {
+            if stats.get("streams").is_some() || stats.get("version").is_some() {
+                let version = stats
+                    .get("version")
+                    .and_then(Value::as_str)
+                    .map(str::to_string);
+                return Some(ShoutcastServer::V2 { version, stats });
+            }
+        }
+    }
+    let body = fetch_text(client.get(format!("http://{host}:{port}/7.html")))
+        .await
+        .ok()?;
+    parse_seven_html(&body).map(ShoutcastServer::V1)
+}
+
+/// `<html><body>current,status,peak,max,unique,bitrate,title</body></html>`
+fn parse_seven_html(body: &str) -> Option<SevenHtml> {
+    let text = strip_tags(body);
+    let mut fields = text.trim().splitn(7, ',');
+    let mut next = || fields.next()?.trim().parse::<u32>().ok();
+    let current_listeners = next()?;
+    let source_connected = next()? == 1;
+    let _peak = next()?;
+    let max_listeners = next()?;
+    Some(SevenHtml {
+        current_listeners,
+        source_connected,
+        max_listeners,
+    })
+}
+
+fn value_u32(v: &Value) -> Option<u32> {
+    match v {
+        Value::Number(n) => n.as_u64().and_then(|n| u32::try_from(n).ok()),
+        Value::String(s) => s.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+// ── Helpers ───────────────────────────────────────────────────────────────────
+
+fn strip_tags(html: &str) -> String {
+    let mut out = String::with_capacity(html.len());
+    let mut in_tag = false;
+    for c in html.chars() {
+        match c {
+            '<' => {
+                in_tag = true;
+                out.push(' ');
+            }
+            '>' => in_tag = false,
+            c if !in_tag => out.push(c),
+            _ => {}
+        }
+    }
+    out
+}
+
+/// `(major, minor, patch)` from the first dotted number, e.g. "Icecast 2.4.0-kh15".
+fn parse_version(s: &str) -> Option<(u32, u32, u32)> {
+    let start = s.find(|c: char| c.is_ascii_digit())?;
+    let token = s[start..]
+        .split(|c: char| !c.is_ascii_digit() && c != '.')
+        .next()?;
+    let mut parts = token.split('.').map(|p| p.parse::<u32>().ok());
+    let major = parts.next()??;
+    let minor = parts.next().flatten().unwrap_or(0);
+    let patch = parts.next().flatten().unwrap_or(0);
+    Some((major, minor, patch))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_server_replies() {
+        assert_eq!(parse_version("Icecast 2.4.0-kh15"), Some((2, 4, 0)));
+        assert_eq!(parse_version("Icecast 2.3.3"), Some((2, 3, 3)));
+        assert_eq!(parse_version("Icecast"), None);
+
+        assert_eq!(
+            parse_seven_html("<html><body>12,1,40,100,9,128,Artist - Song, Live</body></html>"),
+            Some(SevenHtml {
+                current_listeners: 12,
+                source_connected: true,
+                max_listeners: 100,
+            })
+        );
+
+        let reply = "HTTP/1.0 403 Forbidden\r\nContent-Type: text/html\r\n\r\n\
+                     <html><head><title>Error 403</title></head>\
+                     <body><b>Mountpoint in use</b></body></html>";
+        assert_eq!(
+            parse_http_reply(reply),
+            Some((403, "Error 403 Mountpoint in use".to_string()))
+        );
+        assert_eq!(
+            parse_http_reply("HTTP/1.1 100 Continue\r\n\r\n"),
+            Some((100, "Continue".to_string()))
+        );
+    }
+}
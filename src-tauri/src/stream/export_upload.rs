@@ -0,0 +1,327 @@
+/// `export_upload.rs` — upload finished show exports
+///
+/// - **SFTP** runs the system OpenSSH `sftp` client in batch mode, so it
+///   needs key-based (non-interactive) authentication. The user and host are
+///   checked so they cannot pass as `sftp` options, and both paths in the
+///   batch command are quoted.
+/// - **S3** (and S3-compatible stores: R2, Spaces, MinIO) is a single signed
+///   `PUT` using AWS Signature V4 with an unsigned, streamed payload and
+///   path-style addressing.
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::settings_archive::hex;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum UploadTarget {
+    Sftp {
+        host: String,
+        port: Option<u16>,
+        username: String,
+        /// Private key; default is the user's ssh config/agent
+        identity_file: Option<String>,
+        remote_dir: String,
+    },
+    S3 {
+        /// Custom endpoint for S3-compatible stores (default AWS)
+        endpoint: Option<String>,
+        region: String,
+        bucket: String,
+        /// Key prefix, e.g. `podcasts/`
+        prefix: String,
+        access_key_id: String,
+        secret_access_key: String,
+    },
+}
+
+/// Upload `path`; returns where it went (`sftp://…` or the object URL).
+pub async fn upload(path: &Path, target: &UploadTarget) -> Result<String, String> {
+    let file_name = path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .ok_or("Export has no file name")?
+        .to_string();
+    match target {
+        UploadTarget::Sftp {
+            host,
+            port,
+            username,
+            identity_file,
+            remote_dir,
+        } => {
+            let local = path.to_path_buf();
+            let (host, username, identity_file) =
+                (host.clone(), username.clone(), identity_file.clone());
+            let remote = format!("{}/{}", remote_dir.trim_end_matches('/'), file_name);
+            let port = port.unwrap_or(22);
+            let url = format!("sftp://{username}@{host}:{port}{remote}");
+            tokio::task::spawn_blocking(move || {
+                sftp_put(
+                    &local,
+                    &host,
+                    port,
+                    &username,
+                    identity_file.as_deref(),
+                    &remote,
+                )
+            })
+            .await
+            .map_err(|e| e.to_string())??;
+            Ok(url)
+        }
+        UploadTarget::S3 {
+            endpoint,
+            region,
+            bucket,
+            prefix,
+            access_key_id,
+            secret_access_key,
+        } => {
+            let key = format!("{prefix}{file_name}");
+            s3_put(
+                path,
+                endpoint.as_deref(),
+                region,
+                bucket,
+                &key,
+                access_key_id,
+                secret_access_key,
+            )
+            .await
+        }
+    }
+}
+
+fn sftp_put(
+    local: &Path,
+    host: &str,
+    port: u16,
+    username: &str,
+    identity_file: Option<&str>,
+    remote: &str,
+) -> Result<(), String> {
+    use std::io::Write;
+
+    let destination = sftp_destination(username, host)?;
+    let batch = format!(
+        "put {} {}\n",
+        sftp_quote(&local.to_string_lossy())?,
+        sftp_quote(remote)?
+    );
+    let mut cmd = Command::new("sftp");
+    cmd.args(["-b", "-", "-P", &port.to_string()])
+        .args(["-o", "BatchMode=yes"]);
+    if let Some(key) = identity_file {
+        cmd.args(["-i", key]);
+    }
+    let mut child = cmd
+        .arg("--")
+        .arg(destination)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Cannot run sftp (is OpenSSH installed?): {e}"))?;
+    child
+        .stdin
+        .take()
+        .ok_or("sftp stdin unavailable")?
+        .write_all(batch.as_bytes())
+        .map_err(|e| format!("sftp: {e}"))?;
+    let output = child.wait_with_output().map_err(|e| format!("sftp: {e}"))?;
+    if output.status.success() {
+        return Ok(());
+    }
+    Err(format!(
+        "sftp failed: {}",
+        String::from_utf8_lossy(&output.stderr).trim()
+    ))
+}
+
+/// `user@host`, refusing values `sftp` would read as options.
+fn sftp_destination(username: &str, host: &str) -> Result<String, String> {
+    for (what, value) in [("user", username), ("host", host)] {
+        if value.is_empty()
+            || value.starts_with('-')
+            || value.chars().any(|c| c.is_whitespace() || c.is_control())
+        {
+            return Err(format!("Invalid SFTP {what}: {value:?}"));
+        }
+    }
+    Ok(format!("{username}@{host}"))
+}
+
+/// A double-quoted argument for an `sftp` batch line.
+fn sftp_quote(path: &str) -> Result<String, String> {
+    if path.contains(['\n', '\r']) {
+        return Err(format!("SFTP paths cannot contain line breaks: {path:?}"));
+    }
+    Ok(format!(
+        "\"{}\"",
+        path.replace('\\', "\\\\").replace('"', "\\\"")
+    ))
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC takes any key length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// URI-encode an object key, keeping `/` between segments.
+fn encode_key(key: &str) -> String {
+    key.split('/')
+        .map(|segment| urlencoding::encode(segment).into_owned())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// SigV4 canonical request. `headers` must be lowercase and sorted by name.
+fn canonical_request(
+    method: &str,
+    path: &str,
+    headers: &[(&str, &str)],
+    payload_hash: &str,
+) -> String {
+    let canonical_headers: String = headers
+        .iter()
+        .map(|(name, value)| format!("{name}:{}\n", value.trim()))
+        .collect();
+    let signed_headers = signed_headers(headers);
+    format!("{method}\n{path}\n\n{canonical_headers}\n{signed_headers}\n{payload_hash}")
+}
+
+fn signed_headers(headers: &[(&str, &str)]) -> String {
+    headers
+        .iter()
+        .map(|(name, _)| *name)
+        .collect::<Vec<_>>()
+        .join(";")
+}
+
+/// SigV4 signature for `canonical_request` (`amz_date` is `YYYYMMDDTHHMMSSZ`).
+fn sign(secret: &str, amz_date: &str, region: &str, canonical_request: &str) -> String {
+    let date = &amz_date[..8];
+    let scope = format!("{date}/{region}/s3/aws4_request");
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{amz_date}\n{scope}\n{}",
+        hex(&Sha256::digest(canonical_request.as_bytes()))
+    );
+    let mut key = format!("AWS4{secret}").into_bytes();
+    for part in [date, region, "s3", "aws4_request"] {
+        key = hmac_sha256(&key, part.as_bytes());
+    }
+    hex(&hmac_sha256(&key, string_to_sign.as_bytes()))
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn s3_put(
+    path: &Path,
+    endpoint: Option<&str>,
+    region: &str,
+    bucket: &str,
+    key: &str,
+    access_key_id: &str,
+    secret_access_key: &str,
+) -> Result<String, String> {
+    let endpoint = endpoint
+        .map(|e| e.trim_end_matches('/').to_string())
+        .unwrap_or_else(|| format!("https://s3.{region}.amazonaws.com"));
+    let host = endpoint
+        .split("://")
+        .nth(1)
+        .unwrap_or(&endpoint)
+        .split('/')
+        .next()
+        .unwrap_or_default()
+        .to_string();
+    let object_path = format!("/{bucket}/{}", encode_key(key));
+    let amz_date = chrono::Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+    let headers = [
+        ("host", host.as_str()),
+        ("x-amz-content-sha256", "UNSIGNED-PAYLOAD"),
+        ("x-amz-date", amz_date.as_str()),
+    ];
+    let canonical = canonical_request("PUT", &object_path, &headers, "UNSIGNED-PAYLOAD");
+    let signature = sign(secret_access_key, &amz_date, region, &canonical);
+    let authorization = format!(
+        "AWS4-HMAC-SHA256 Credential={access_key_id}/{}/{region}/s3/aws4_request, \
+         SignedHeaders={}, Signature={signature}",
+        &amz_date[..8],
+        signed_headers(&headers)
+    );
+
+    let file = tokio::fs::File::open(path)
+        .await
+        .map_err(|e| format!("Cannot open {}: {e}", path.display()))?;
+    let len = file.metadata().await.map_err(|e| e.to_string())?.len();
+    let url = format!("{endpoint}{object_path}");
+    let response = reqwest::Client::new()
+        .put(&url)
+        .header("x-amz-content-sha256", "UNSIGNED-PAYLOAD")
+        .header("x-amz-date", &amz_date)
+        .header("authorization", authorization)
+        .header("content-length", len)
+        .body(reqwest::Body::from(file))
+        .send()
+        .await
+        .map_err(|e| format!("S3 upload failed: {e}"))?;
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        return Err(format!("S3 upload failed ({status}): {}", body.trim()));
+    }
+    Ok(url)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signature_matches_aws_example() {
+        // "GET Object" example from the AWS SigV4 documentation.
+        let empty = hex(&Sha256::digest(b""));
+        let headers = [
+            ("host", "examplebucket.s3.amazonaws.com"),
+            ("range", "bytes=0-9"),
+            ("x-amz-content-sha256", empty.as_str()),
+            ("x-amz-date", "20130524T000000Z"),
+        ];
+        let canonical = canonical_request("GET", "/test.txt", &headers, &empty);
+        let signature = sign(
+            "wJalrXUtnFEMI/K7MDENG/bPxRfiCYEXAMPLEKEY",
+            "20130524T000000Z",
+            "us-east-1",
+            &canonical,
+        );
+        assert_eq!(
+            signature,
+            "f0e8bdb87c964420e857bd35b5d6ed310bd44f0170aba48dd91039c6036bdb41"
+        );
+        assert_eq!(
+            encode_key("shows/Mera Show #1.mp3"),
+            "shows/Mera%20Show%20%231.mp3"
+        );
+    }
+
+    #[test]
+    fn sftp_arguments_cannot_escape() {
+        assert_eq!(
+            sftp_quote(r#"/tmp/a "b"\c.mp3"#).unwrap(),
+            r#""/tmp/a \"b\"\\c.mp3""#
+        );
+        assert!(sftp_quote("a\nrm b").is_err());
+        assert_eq!(
+            sftp_destination("dj", "example.com").unwrap(),
+            "dj@example.com"
+        );
+        assert!(sftp_destination("-oProxyCommand=x", "example.com").is_err());
+        assert!(sftp_destination("dj", "-F/tmp/cfg").is_err());
+    }
+}
@@ -0,0 +1,224 @@
+/// `failover.rs` — encoder failover groups
+///
+/// Encoders sharing a `failover_group` back each other up. The lowest
+/// `priority` is the primary; when it has failed `failover_after_attempts`
+/// reconnects in a row the next encoder in the group is started, and once the
+/// primary has streamed steadily again the backup is stopped.
+use serde::{Deserialize, Serialize};
+
+use super::broadcaster::{EncoderRuntimeState, EncoderStatus};
+use super::encoder_manager::EncoderConfig;
+
+/// A recovered primary must stream this long before the backup is released.
+pub const FAILBACK_STABLE_SECS: u64 = 30;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum FailoverAction {
+    /// `to` takes over from the failing `from`
+    Failover { group: String, from: i64, to: i64 },
+    /// The primary recovered; `backup` is stopped
+    Failback {
+        group: String,
+        primary: i64,
+        backup: i64,
+    },
+    /// The primary was stopped by the operator; `backup` is stopped with it
+    Release { group: String, backup: i64 },
+}
+
+impl FailoverAction {
+    /// The encoder the action centres on, for event log filtering.
+    pub fn encoder_id(&self) -> i64 {
+        match self {
+            FailoverAction::Failover { to, .. } => *to,
+            FailoverAction::Failback { primary, .. } => *primary,
+            FailoverAction::Release { backup, .. } => *backup,
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            FailoverAction::Failover { group, from, to } => {
+                format!("Encoder {from} failing in group '{group}'; backup {to} started")
+            }
+            FailoverAction::Failback {
+                group,
+                primary,
+                backup,
+            } => format!("Encoder {primary} recovered in group '{group}'; backup {backup} stopped"),
+            FailoverAction::Release { group, backup } => {
+                format!("Primary of group '{group}' stopped; backup {backup} stopped")
+            }
+        }
+    }
+}
+
+/// One group member as seen by the planner.
+#[derive(Debug, Clone)]
+pub struct Member {
+    pub id: i64,
+    pub priority: u32,
+    pub failover_after_attempts: u32,
+    pub status: EncoderStatus,
+    pub consecutive_failures: u32,
+    pub uptime_secs: u64,
+    pub bytes_sent: u64,
+}
+
+impl Member {
+    pub fn new(config: &EncoderConfig, runtime: Option<&EncoderRuntimeState>) -> Self {
+        Self {
+            id: config.id,
+            priority: config.priority,
+            failover_after_attempts: config.failover_after_attempts.max(1),
+            status: runtime.map(|r| r.status.clone()).unwrap_or_default(),
+            consecutive_failures: runtime.map_or(0, |r| r.consecutive_failures),
+            uptime_secs: runtime.map_or(0, |r| r.uptime_secs),
+            bytes_sent: runtime.map_or(0, |r| r.bytes_sent),
+        }
+    }
+
+    fn is_stable(&self) -> bool {
+        matches!(self.status, EncoderStatus::Streaming)
+            && self.uptime_secs >= FAILBACK_STABLE_SECS
+            && self.bytes_sent > 0
+    }
+
+    fn is_failing(&self) -> bool {
+        match self.status {
+            EncoderStatus::Failed => true,
+            EncoderStatus::Disabled => false,
+            _ => self.consecutive_failures >= self.failover_after_attempts && !self.is_stable(),
+        }
+    }
+
+    fn is_idle(&self) -> bool {
+        matches!(self.status, EncoderStatus::Disabled)
+    }
+}
+
+/// Decide the next step for one group. `members` must be sorted by priority;
+/// `active_backup` is the member currently standing in for the primary.
+pub fn plan(group: &str, members: &[Member], active_backup: Option<i64>) -> Option<FailoverAction> {
+    let primary = members.first()?;
+    let group = group.to_string();
+
+    let Some(backup_id) = active_backup else {
+        if !primary.is_failing() {
+            return None;
+        }
+        let to = members[1..].iter().find(|m| m.is_idle())?.id;
+        return Some(FailoverAction::Failover {
+            group,
+            from: primary.id,
+            to,
+        });
+    };
+
+    if primary.is_idle() {
+        return Some(FailoverAction::Release {
+            group,
+            backup: backup_id,
+        });
+    }
+    if primary.is_stable() {
+        return Some(FailoverAction::Failback {
+            group,
+            primary: primary.id,
+            backup: backup_id,
+        });
+    }
+
+    // The stand-in failed too: move down the list.
+    let position = members.iter().position(|m| m.id == backup_id)?;
+    let backup = &members[position];
+    if !backup.is_failing() {
+        return None;
+    }
+    let to = members[position + 1..].iter().find(|m| m.is_idle())?.id;
+    Some(FailoverAction::Failover {
+        group,
+        from: backup_id,
+        to,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn member(id: i64, priority: u32, status: EncoderStatus) -> Member {
+        Member {
+            id,
+            priority,
+            failover_after_attempts: 3,
+            status,
+            consecutive_failures: 0,
+            uptime_secs: 0,
+            bytes_sent: 0,
+        }
+    }
+
+    #[test]
+    fn fails_over_after_n_attempts_and_back_when_stable() {
+        let mut primary = member(1, 0, EncoderStatus::Retrying { attempt: 2, max: 0 });
+        primary.consecutive_failures = 2;
+        let backup = member(2, 1, EncoderStatus::Disabled);
+        assert_eq!(plan("main", &[primary.clone(), backup.clone()], None), None);
+
+        primary.consecutive_failures = 3;
+        assert_eq!(
+            plan("main", &[primary.clone(), backup.clone()], None),
+            Some(FailoverAction::Failover {
+                group: "main".into(),
+                from: 1,
+                to: 2
+            })
+        );
+
+        let live_backup = member(2, 1, EncoderStatus::Streaming);
+        primary.status = EncoderStatus::Streaming;
+        primary.uptime_secs = 5;
+        primary.bytes_sent = 4096;
+        assert_eq!(
+            plan("main", &[primary.clone(), live_backup.clone()], Some(2)),
+            None
+        );
+
+        primary.uptime_secs = FAILBACK_STABLE_SECS;
+        assert_eq!(
+            plan("main", &[primary, live_backup], Some(2)),
+            Some(FailoverAction::Failback {
+                group: "main".into(),
+                primary: 1,
+                backup: 2
+            })
+        );
+    }
+
+    #[test]
+    fn cascades_to_next_backup_and_releases_on_operator_stop() {
+        let primary = member(1, 0, EncoderStatus::Failed);
+        let mut first = member(2, 1, EncoderStatus::Failed);
+        first.consecutive_failures = 5;
+        let second = member(3, 2, EncoderStatus::Disabled);
+        assert_eq!(
+            plan("main", &[primary, first.clone(), second.clone()], Some(2)),
+            Some(FailoverAction::Failover {
+                group: "main".into(),
+                from: 2,
+                to: 3
+            })
+        );
+
+        let stopped = member(1, 0, EncoderStatus::Disabled);
+        assert_eq!(
+            plan("main", &[stopped, first, second], Some(3)),
+            Some(FailoverAction::Release {
+                group: "main".into(),
+                backup: 3
+            })
+        );
+    }
+}
@@ -0,0 +1,445 @@
+/// `flac.rs` — streaming 16-bit FLAC encoder for recordings
+///
+/// Fixed 4096-sample blocks, each channel coded independently as a CONSTANT
+/// subframe (silence), the best FIXED predictor (orders 0–4) with
+/// partitioned Rice residuals, or VERBATIM when prediction does not pay.
+/// No LPC, so files are a little larger than `flac -5`, but encoding is
+/// cheap enough to run next to the streaming encoders.
+///
+/// STREAMINFO is written up front and patched on `finish` with the sample
+/// count and frame sizes; the MD5 signature is left zero ("not computed").
+use std::io::{Seek, SeekFrom, Write};
+
+const BLOCK_SIZE: usize = 4096;
+const BITS_PER_SAMPLE: u32 = 16;
+const MAX_FIXED_ORDER: usize = 4;
+const MAX_PARTITION_ORDER: u32 = 8;
+/// Largest 4-bit Rice parameter; 15 is the escape code
+const MAX_RICE_PARAM: u32 = 14;
+
+pub struct FlacEncoder<W: Write + Seek> {
+    writer: W,
+    channels: usize,
+    /// Interleaved samples waiting for a full block
+    pending: Vec<i32>,
+    frame_number: u64,
+    total_samples: u64,
+    min_frame_bytes: u32,
+    max_frame_bytes: u32,
+    bytes_written: u64,
+    sample_rate: u32,
+}
+
+impl<W: Write + Seek> FlacEncoder<W> {
+    pub fn new(mut writer: W, sample_rate: u32, channels: u8) -> std::io::Result<Self> {
+        let channels = channels.clamp(1, 8) as usize;
+        writer.write_all(b"fLaC")?;
+        let mut encoder = Self {
+            writer,
+            channels,
+            pending: Vec::with_capacity(BLOCK_SIZE * channels),
+            frame_number: 0,
+            total_samples: 0,
+            min_frame_bytes: 0,
+            max_frame_bytes: 0,
+            bytes_written: 4,
+            sample_rate,
+        };
+        let info = encoder.stream_info();
+        encoder.writer.write_all(&info)?;
+        encoder.bytes_written += info.len() as u64;
+        Ok(encoder)
+    }
+
+    pub fn bytes_written(&self) -> u64 {
+        self.bytes_written
+    }
+
+    /// Queue interleaved samples, encoding every complete block.
+    pub fn write_interleaved(&mut self, samples: &[f32]) -> std::io::Result<()> {
+        for &s in samples {
+            self.pending
+                .push((s.clamp(-1.0, 1.0) * i16::MAX as f32).round() as i32);
+            if self.pending.len() == BLOCK_SIZE * self.channels {
+                self.encode_pending()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Encode the partial last block and patch STREAMINFO.
+    pub fn finish(mut self) -> std::io::Result<W> {
+        let whole_frames = self.pending.len() / self.channels * self.channels;
+        self.pending.truncate(whole_frames);
+        if !self.pending.is_empty() {
+            self.encode_pending()?;
+        }
+        self.writer.seek(SeekFrom::Start(4))?;
+        let info = self.stream_info();
+        self.writer.write_all(&info)?;
+        self.writer.seek(SeekFrom::End(0))?;
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+
+    fn stream_info(&self) -> [u8; 38] {
+        let mut bits = BitWriter::default();
+        // Last metadata block, type 0 (STREAMINFO), 34 bytes
+        bits.write(1, 1);
+        bits.write(0, 7);
+        bits.write(34, 24);
+        bits.write(BLOCK_SIZE as u64, 16);
+        bits.write(BLOCK_SIZE as u64, 16);
+        bits.write(u64::from(self.min_frame_bytes), 24);
+        bits.write(u64::from(self.max_frame_bytes), 24);
+        bits.write(u64::from(self.sample_rate), 20);
+        bits.write(self.channels as u64 - 1, 3);
+        bits.write(u64::from(BITS_PER_SAMPLE) - 1, 5);
+        bits.write(self.total_samples, 36);
+        for _ in 0..16 {
+            bits.write(0, 8);
+        }
+        let mut out = [0u8; 38];
+        out.copy_from_slice(&bits.into_bytes());
+        out
+    }
+
+    fn encode_pending(&mut self) -> std::io::Result<()> {
+        let block = self.pending.len() / self.channels;
+        let frame = encode_frame(&self.pending, self.channels, self.frame_number);
+        self.writer.write_all(&frame)?;
+
+        let len = frame.len() as u32;
+        self.min_frame_bytes = if self.frame_number == 0 {
+            len
+        } else {
+            self.min_frame_bytes.min(len)
+        };
+        self.max_frame_bytes = self.max_frame_bytes.max(len);
+        self.bytes_written += frame.len() as u64;
+        self.total_samples += block as u64;
+        self.frame_number += 1;
+        self.pending.clear();
+        Ok(())
+    }
+}
+
+fn encode_frame(interleaved: &[i32], channels: usize, frame_number: u64) -> Vec<u8> {
+    let block = interleaved.len() / channels;
+    let mut bits = BitWriter::default();
+
+    // Frame header: sync, fixed blocking, block size and rate from the end of
+    // the header / STREAMINFO, independent channels, 16-bit samples.
+    bits.write(0b11_1111_1111_1110, 14);
+    bits.write(0, 1);
+    bits.write(0, 1);
+    bits.write(0b0111, 4);
+    bits.write(0b0000, 4);
+    bits.write(channels as u64 - 1, 4);
+    bits.write(0b100, 3);
+    bits.write(0, 1);
+    write_utf8_number(&mut bits, frame_number);
+    bits.write(block as u64 - 1, 16);
+    let crc = crc8(bits.bytes());
+    bits.write(u64::from(crc), 8);
+
+    let mut channel = Vec::with_capacity(block);
+    for ch in 0..channels {
+        channel.clear();
+        channel.extend(interleaved.iter().skip(ch).step_by(channels));
+        write_subframe(&mut bits, &channel);
+    }
+
+    bits.align();
+    let crc = crc16(bits.bytes());
+    bits.write(u64::from(crc), 16);
+    bits.into_bytes()
+}
+
+fn write_subframe(bits: &mut BitWriter, samples: &[i32]) {
+    if samples.iter().all(|&s| s == samples[0]) {
+        bits.write(0, 8);
+        write_signed(bits, samples[0], BITS_PER_SAMPLE);
+        return;
+    }
+
+    let order = best_fixed_order(samples);
+    let residual = fixed_residual(samples, order);
+    let (partition_order, params, rice_bits) = best_partitioning(&residual, samples.len(), order);
+    let fixed_bits = 8 + order as u64 * u64::from(BITS_PER_SAMPLE) + rice_bits;
+    let verbatim_bits = 8 + samples.len() as u64 * u64::from(BITS_PER_SAMPLE);
+
+    if fixed_bits >= verbatim_bits {
+        bits.write(0b0000_0010, 8);
+        for &s in samples {
+            write_signed(bits, s, BITS_PER_SAMPLE);
+        }
+        return;
+    }
+
+    bits.write(0b0001_0000 | ((order as u64) << 1), 8);
+    for &s in &samples[..order] {
+        write_signed(bits, s, BITS_PER_SAMPLE);
+    }
+    // Partitioned Rice, 4-bit parameters
+    bits.write(0, 2);
+    bits.write(u64::from(partition_order), 4);
+    let per_partition = samples.len() >> partition_order;
+    let mut start = 0;
+    for (i, &k) in params.iter().enumerate() {
+        let len = if i == 0 {
+            per_partition - order
+        } else {
+            per_partition
+        };
+        bits.write(u64::from(k), 4);
+        for &r in &residual[start..start + len] {
+            let u = zigzag(r);
+            bits.write_unary(u >> k);
+            bits.write(u & ((1 << k) - 1), k);
+        }
+        start += len;
+    }
+}
+
+/// The fixed predictor order with the smallest absolute residual sum.
+fn best_fixed_order(samples: &[i32]) -> usize {
+    let max_order = MAX_FIXED_ORDER.min(samples.len().saturating_sub(1));
+    (0..=max_order)
+        .min_by_key(|&order| {
+            fixed_residual(&samples[max_order - order..], order)
+                .iter()
+                .map(|r| u64::from(r.unsigned_abs()))
+                .sum::<u64>()
+        })
+        .unwrap_or(0)
+}
+
+fn fixed_residual(samples: &[i32], order: usize) -> Vec<i32> {
+    (order..samples.len())
+        .map(|i| {
+            let x = |back: usize| samples[i - back];
+            match order {
+                0 => x(0),
+                1 => x(0) - x(1),
+                2 => x(0) - 2 * x(1) + x(2),
+                3 => x(0) - 3 * x(1) + 3 * x(2) - x(3),
+                _ => x(0) - 4 * x(1) + 6 * x(2) - 4 * x(3) + x(4),
+            }
+        })
+        .collect()
+}
+
+/// `(partition order, Rice parameter per partition, residual bits)` with the
+/// fewest bits.
+fn best_partitioning(residual: &[i32], block: usize, order: usize) -> (u32, Vec<u32>, u64) {
+    let mut best: Option<(u32, Vec<u32>, u64)> = None;
+    for partition_order in 0..=MAX_PARTITION_ORDER {
+        let partitions = 1usize << partition_order;
+        if !block.is_multiple_of(partitions) || block / partitions <= order {
+            break;
+        }
+        let per_partition = block / partitions;
+        let mut params = Vec::with_capacity(partitions);
+        let mut total = 6;
+        let mut start = 0;
+        for i in 0..partitions {
+            let len = if i == 0 {
+                per_partition - order
+            } else {
+                per_partition
+            };
+            let part = &residual[start..start + len];
+            let (k, cost) = best_rice_param(part);
+            params.push(k);
+            total += 4 + cost;
+            start += len;
+        }
+        if best.as_ref().is_none_or(|b| total < b.2) {
+            best = Some((partition_order, params, total));
+        }
+    }
+    best.unwrap_or((0, vec![0], 6))
+}
+
+fn best_rice_param(part: &[i32]) -> (u32, u64) {
+    (0..=MAX_RICE_PARAM)
+        .map(|k| {
+            let bits: u64 = part
+                .iter()
+                .map(|&r| (zigzag(r) >> k) + 1 + u64::from(k))
+                .sum();
+            (k, bits)
+        })
+        .min_by_key(|&(_, bits)| bits)
+        .unwrap_or((0, 0))
+}
+
+fn zigzag(r: i32) -> u64 {
+    ((r << 1) ^ (r >> 31)) as u32 as u64
+}
+
+fn write_signed(bits: &mut BitWriter, value: i32, width: u32) {
+    bits.write(value as u64 & ((1 << width) - 1), width);
+}
+
+/// Frame numbers use the UTF-8 style variable-length code.
+fn write_utf8_number(bits: &mut BitWriter, n: u64) {
+    if n < 0x80 {
+        bits.write(n, 8);
+        return;
+    }
+    let extra = match n {
+        0..=0x7FF => 1,
+        0x800..=0xFFFF => 2,
+        0x1_0000..=0x1F_FFFF => 3,
+        0x20_0000..=0x3FF_FFFF => 4,
+        0x400_0000..=0x7FFF_FFFF => 5,
+        _ => 6,
+    };
+    let lead_marker = (0xFF00_u64 >> (extra + 1)) & 0xFF;
+    bits.write(lead_marker | (n >> (6 * extra)), 8);
+    for i in (0..extra).rev() {
+        bits.write(0x80 | ((n >> (6 * i)) & 0x3F), 8);
+    }
+}
+
+fn crc8(bytes: &[u8]) -> u8 {
+    bytes.iter().fold(0u8, |mut crc, &b| {
+        crc ^= b;
+        for _ in 0..8 {
+            crc = if crc & 0x80 != 0 {
+                (crc << 1) ^ 0x07
+            } else {
+                crc << 1
+            };
+        }
+        crc
+    })
+}
+
+fn crc16(bytes: &[u8]) -> u16 {
+    bytes.iter().fold(0u16, |mut crc, &b| {
+        crc ^= u16::from(b) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 {
+                (crc << 1) ^ 0x8005
+            } else {
+                crc << 1
+            };
+        }
+        crc
+    })
+}
+
+/// MSB-first bit packer.
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    acc: u64,
+    filled: u32,
+}
+
+impl BitWriter {
+    fn write(&mut self, value: u64, width: u32) {
+        for shift in (0..width).rev() {
+            self.acc = (self.acc << 1) | ((value >> shift) & 1);
+            self.filled += 1;
+            if self.filled == 8 {
+                self.bytes.push(self.acc as u8);
+                self.acc = 0;
+                self.filled = 0;
+            }
+        }
+    }
+
+    /// `n` zero bits followed by a one.
+    fn write_unary(&mut self, n: u64) {
+        for _ in 0..n {
+            self.write(0, 1);
+        }
+        self.write(1, 1);
+    }
+
+    fn align(&mut self) {
+        if self.filled > 0 {
+            self.write(0, 8 - self.filled);
+        }
+    }
+
+    /// Whole bytes written so far.
+    fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    fn into_bytes(mut self) -> Vec<u8> {
+        self.align();
+        self.bytes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use symphonia::core::{
+        audio::SampleBuffer, codecs::DecoderOptions, formats::FormatOptions, io::MediaSourceStream,
+        meta::MetadataOptions, probe::Hint,
+    };
+
+    #[test]
+    fn round_trips_through_a_decoder() {
+        // Tone, silence and full-scale noise exercise FIXED, CONSTANT and
+        // VERBATIM subframes; the odd length leaves a short last block.
+        let mut input: Vec<f32> = (0..10_000)
+            .flat_map(|i| {
+                let t = i as f32 / 44_100.0;
+                [
+                    0.5 * (std::f32::consts::TAU * 440.0 * t).sin(),
+                    0.25 * (std::f32::consts::TAU * 1_000.0 * t).sin(),
+                ]
+            })
+            .collect();
+        input.extend(std::iter::repeat(0.0).take(8_192));
+        let mut seed = 1u32;
+        input.extend((0..5_001).map(|_| {
+            seed = seed.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+            (seed >> 16) as f32 / 32_768.0 - 1.0
+        }));
+
+        let mut encoder = FlacEncoder::new(std::io::Cursor::new(Vec::new()), 44_100, 2).unwrap();
+        encoder.write_interleaved(&input).unwrap();
+        let bytes = encoder.finish().unwrap().into_inner();
+
+        let mss = MediaSourceStream::new(Box::new(std::io::Cursor::new(bytes)), Default::default());
+        let mut hint = Hint::new();
+        hint.with_extension("flac");
+        let mut format = symphonia::default::get_probe()
+            .format(
+                &hint,
+                mss,
+                &FormatOptions::default(),
+                &MetadataOptions::default(),
+            )
+            .unwrap()
+            .format;
+        let track = format.default_track().unwrap().clone();
+        assert_eq!(track.codec_params.n_frames, Some(input.len() as u64 / 2));
+        let mut decoder = symphonia::default::get_codecs()
+            .make(&track.codec_params, &DecoderOptions::default())
+            .unwrap();
+
+        let mut decoded: Vec<i16> = Vec::new();
+        while let Ok(packet) = format.next_packet() {
+            let audio = decoder.decode(&packet).unwrap();
+            let mut buf = SampleBuffer::<i16>::new(audio.capacity() as u64, *audio.spec());
+            buf.copy_interleaved_ref(audio);
+            decoded.extend_from_slice(buf.samples());
+        }
+
+        let expected: Vec<i16> = input
+            .iter()
+            .map(|s| (s.clamp(-1.0, 1.0) * i16::MAX as f32).round() as i16)
+            .collect();
+        assert_eq!(decoded, expected);
+    }
+}
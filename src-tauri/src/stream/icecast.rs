@@ -10,6 +10,7 @@ use tokio::sync::oneshot;
 
 use super::encoder_manager::{EncoderConfig, EncoderManager};
 use super::mp3::Mp3Encoder;
+use super::watermark::Watermarker;
 
 /// Icecast / Shoutcast connection parameters
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -303,6 +304,7 @@ pub async fn stream_loop_async(
     });
 
     let mut mp3 = Mp3Encoder::from_config(config)?;
+    let mut watermark = Watermarker::for_encoder(config);
     let mut pcm_buf = vec![0.0f32; mp3.frame_samples()];
     let silence = vec![0.0f32; mp3.frame_samples()];
     let channels = u64::from(config.channels.clamp(1, 2));
@@ -364,6 +366,9 @@ pub async fn stream_loop_async(
         }
         empty_since = None;
 
+        if let Some(wm) = watermark.as_mut() {
+            wm.process(&mut pcm_buf[..filled]);
+        }
         let encoded = mp3.encode_f32_interleaved(&pcm_buf[..filled])?;
         if encoded.is_empty() {
             tokio::task::yield_now().await;
@@ -402,48 +407,3 @@ fn new_mp3_encoder(config: &IcecastConfig) -> Result<Mp3Encoder, String> {
     };
     Mp3Encoder::from_config(&cfg)
 }
-
-/// Quick TCP-level connection test (does not stream audio).
-pub async fn test_icecast_connection(config: &EncoderConfig) -> Result<(), String> {
-    let host = config.server_host.as_deref().unwrap_or("localhost");
-    let port = config.server_port.unwrap_or(8000);
-    let mount = config.mount_point.as_deref().unwrap_or("/stream");
-    let username = config
-        .server_username
-        .as_deref()
-        .filter(|s| !s.trim().is_empty())
-        .unwrap_or("source");
-    let password = config.server_password.as_deref().unwrap_or("");
-
-    let url = format!("http://{host}:{port}{mount}");
-    log::info!(
-        "Icecast test: host={} port={} mount={} user={}",
-        host,
-        port,
-        mount,
-        username
-    );
-
-    let client = reqwest::Client::new();
-    // HEAD request to check connectivity
-    let result = client
-        .head(&url)
-        .basic_auth(username, Some(password))
-        .timeout(Duration::from_secs(5))
-        .send()
-        .await;
-
-    match result {
-        Ok(resp) => {
-            log::info!("Icecast test response status={}", resp.status());
-            // Icecast will return 200 or 404 (mount not found) — either means
-            // the server is reachable and accepts our credentials.
-            if resp.status().as_u16() < 500 {
-                Ok(())
-            } else {
-                Err(format!("Server returned HTTP {}", resp.status()))
-            }
-        }
-        Err(e) => Err(format!("Connection failed: {e}")),
-    }
-}
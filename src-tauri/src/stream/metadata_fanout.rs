@@ -0,0 +1,585 @@
+/// `metadata_fanout.rs` — now-playing push to external services
+///
+/// Encoders get ICY metadata through `metadata_pusher`; this module fans the
+/// same track change out to directory and community services: TuneIn AIR,
+/// Live365, Discord webhooks and generic HTTP templates. Every target gets
+/// its own delivery task with retry/backoff; a newer track supersedes a
+/// delivery still retrying, so a slow service never receives stale titles.
+///
+/// Templates use the encoder caption placeholders: `$artist$`, `$title$`,
+/// `$album$`, `$combine$`, plus `$song_id$`, `$duration$`, `$isrc$` and
+/// `$artwork$` (public cover art link, empty when there is none).
+use serde::{Deserialize, Serialize};
+use sqlx::{Row, SqlitePool};
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+use super::recording_chapters::{self, TrackMark};
+use crate::audio::analyzer::artwork::{self, ArtworkLookup};
+use crate::audio::engine::DeckStateEvent;
+use crate::state::AppState;
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+/// Backoff before retry N (1-based); later retries reuse the last value.
+const RETRY_BACKOFF_SECS: [u64; 4] = [5, 15, 45, 120];
+
+// ── Data model ────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HttpHeader {
+    pub name: String,
+    pub value: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PushDestination {
+    /// TuneIn AIR API (`air.radiotime.com/Playing.ashx`)
+    TuneIn {
+        partner_id: String,
+        partner_key: String,
+        station_id: String,
+    },
+    /// Live365 broadcaster song-update endpoint
+    Live365 {
+        api_url: String,
+        member_name: String,
+        password: String,
+    },
+    Discord {
+        webhook_url: String,
+        message_template: String,
+        username: Option<String>,
+    },
+    Http {
+        /// URL template (values are URL-encoded)
+        url_template: String,
+        /// GET or POST
+        method: String,
+        headers: Vec<HttpHeader>,
+        /// Body template for POST (values are JSON-escaped when the content
+        /// type is JSON, form-encoded otherwise)
+        body_template: Option<String>,
+        content_type: String,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetadataPushTarget {
+    pub id: Option<i64>,
+    pub name: String,
+    pub enabled: bool,
+    pub destination: PushDestination,
+    /// SAM song types pushed to this target (empty = all)
+    #[serde(default)]
+    pub song_types: Vec<String>,
+    #[serde(default = "default_max_retries")]
+    pub max_retries: u32,
+}
+
+fn default_max_retries() -> u32 {
+    3
+}
+
+/// The track being announced.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PushTrack {
+    pub song_id: Option<i64>,
+    pub artist: String,
+    pub title: String,
+    pub album: String,
+    pub duration_secs: u32,
+    pub song_type: String,
+    pub isrc: String,
+    /// Public cover art link (empty when there is none)
+    #[serde(default)]
+    pub artwork_url: String,
+}
+
+impl PushTrack {
+    fn combined(&self) -> String {
+        if self.artist.is_empty() {
+            self.title.clone()
+        } else {
+            format!("{} - {}", self.artist, self.title)
+        }
+    }
+
+    /// Fill a template, passing every value through `escape`.
+    fn render(&self, template: &str, escape: impl Fn(&str) -> String) -> String {
+        template
+            .replace("$artist$", &escape(&self.artist))
+            .replace("$title$", &escape(&self.title))
+            .replace("$album$", &escape(&self.album))
+            .replace("$combine$", &escape(&self.combined()))
+            .replace(
+                "$song_id$",
+                &self.song_id.map(|id| id.to_string()).unwrap_or_default(),
+            )
+            .replace("$duration$", &self.duration_secs.to_string())
+            .replace("$isrc$", &escape(&self.isrc))
+            .replace("$artwork$", &escape(&self.artwork_url))
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PushTargetStatus {
+    pub target_id: i64,
+    pub last_track: Option<String>,
+    pub last_attempt_at: Option<i64>,
+    pub last_success_at: Option<i64>,
+    pub last_error: Option<String>,
+    /// Delivery still queued or retrying
+    pub pending: bool,
+    pub consecutive_failures: u32,
+}
+
+// ── Delivery state ────────────────────────────────────────────────────────────
+
+#[derive(Default)]
+struct FanoutState {
+    /// Bumped per target on every new track; stale deliveries give up.
+    generation: HashMap<i64, u64>,
+    status: HashMap<i64, PushTargetStatus>,
+    /// Last announced track key, to ignore repeated start signals
+    last_key: Option<TrackKey>,
+}
+
+static FANOUT: OnceLock<Mutex<FanoutState>> = OnceLock::new();
+
+fn fanout() -> &'static Mutex<FanoutState> {
+    FANOUT.get_or_init(|| Mutex::new(FanoutState::default()))
+}
+
+fn now_secs() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs() as i64
+}
+
+pub fn get_status() -> Vec<PushTargetStatus> {
+    let mut out: Vec<_> = fanout().lock().unwrap().status.values().cloned().collect();
+    out.sort_by_key(|s| s.target_id);
+    out
+}
+
+/// What makes the on-air track "new": song, file and, for remote streams,
+/// the current ICY title.
+pub type TrackKey = (Option<i64>, Option<String>, Option<String>);
+
+pub fn track_key(deck: &DeckStateEvent) -> TrackKey {
+    (
+        deck.song_id,
+        deck.file_path.clone(),
+        deck.remote_stream.as_ref().and_then(|r| r.title.clone()),
+    )
+}
+
+/// Called from the deck poll loop whenever the on-air deck changes.
+/// Resolves the track from SAM and pushes it to every enabled target.
+pub fn track_started(app: &tauri::AppHandle, deck: &DeckStateEvent) {
+    let key = track_key(deck);
+    {
+        let mut st = fanout().lock().unwrap();
+        if st.last_key.as_ref() == Some(&key) {
+            return;
+        }
+        st.last_key = Some(key);
+    }
+    let app = app.clone();
+    let deck = deck.clone();
+    let at_ms = chrono::Utc::now().timestamp_millis();
+    tauri::async_runtime::spawn(async move {
+        use tauri::Manager;
+        let state = app.state::<AppState>();
+        let track = resolve_track(&state, &deck).await;
+        if track.title.is_empty() {
+            return;
+        }
+        recording_chapters::mark_track(TrackMark {
+            at_ms,
+            ended_at_ms: None,
+            song_id: track.song_id,
+            artist: track.artist.clone(),
+            title: track.title.clone(),
+        });
+        let Some(pool) = state.local_db.clone() else {
+            return;
+        };
+        if let Err(e) = dispatch(&pool, track).await {
+            log::warn!("Metadata fan-out: could not load targets: {e}");
+        }
+    });
+}
+
+async fn resolve_track(state: &AppState, deck: &DeckStateEvent) -> PushTrack {
+    let sam_pool = { state.sam_db.read().await.as_ref().cloned() };
+    let song = match (deck.song_id, sam_pool) {
+        (Some(song_id), Some(pool)) => crate::db::sam::get_song(&pool, song_id)
+            .await
+            .ok()
+            .flatten(),
+        _ => None,
+    };
+    let artwork_url = match (&song, &state.local_db) {
+        (Some(s), Some(local)) => {
+            let mut lookup = ArtworkLookup::for_song(local, s).await;
+            if deck.file_path.is_some() {
+                lookup.file_path = deck.file_path.clone();
+            }
+            artwork::public_url_for(local, &lookup)
+                .await
+                .unwrap_or_default()
+        }
+        _ => String::new(),
+    };
+    match song {
+        Some(s) => PushTrack {
+            artwork_url,
+            song_id: Some(s.id),
+            artist: s.artist,
+            title: s.title,
+            album: s.album,
+            duration_secs: s.duration.max(0) as u32,
+            song_type: s.songtype,
+            isrc: s.isrc,
+        },
+        None => match deck.remote_stream.as_ref().and_then(|r| r.title.as_deref()) {
+            Some(stream_title) => {
+                let (artist, title) = crate::audio::remote_stream::split_stream_title(stream_title);
+                PushTrack {
+                    song_id: deck.song_id,
+                    artist,
+                    title,
+                    ..Default::default()
+                }
+            }
+            None => PushTrack {
+                song_id: deck.song_id,
+                title: deck
+                    .file_path
+                    .as_deref()
+                    .and_then(|p| std::path::Path::new(p).file_stem())
+                    .map(|s| s.to_string_lossy().to_string())
+                    .unwrap_or_default(),
+                duration_secs: (deck.duration_ms / 1000) as u32,
+                ..Default::default()
+            },
+        },
+    }
+}
+
+/// Queue `track` for every enabled target that accepts its song type.
+pub async fn dispatch(pool: &SqlitePool, track: PushTrack) -> Result<(), sqlx::Error> {
+    let targets = get_targets(pool).await?;
+    for target in targets.into_iter().filter(|t| t.enabled) {
+        let accepts = target.song_types.is_empty()
+            || target
+                .song_types
+                .iter()
+                .any(|t| t.eq_ignore_ascii_case(&track.song_type));
+        if accepts {
+            enqueue(target, track.clone());
+        }
+    }
+    Ok(())
+}
+
+fn enqueue(target: MetadataPushTarget, track: PushTrack) {
+    let Some(target_id) = target.id else {
+        return;
+    };
+    let generation = {
+        let mut st = fanout().lock().unwrap();
+        let generation = st.generation.entry(target_id).or_insert(0);
+        *generation += 1;
+        let generation = *generation;
+        let status = st.status.entry(target_id).or_default();
+        status.target_id = target_id;
+        status.last_track = Some(track.combined());
+        status.pending = true;
+        generation
+    };
+
+    tauri::async_runtime::spawn(async move {
+        let mut attempt = 0u32;
+        loop {
+            if fanout().lock().unwrap().generation.get(&target_id) != Some(&generation) {
+                return; // superseded by a newer track
+            }
+            let result = deliver(&target.destination, &track).await;
+            // Record the outcome and decide whether to try again.
+            let retry = {
+                let mut st = fanout().lock().unwrap();
+                let superseded = st.generation.get(&target_id) != Some(&generation);
+                let status = st.status.entry(target_id).or_default();
+                status.last_attempt_at = Some(now_secs());
+                let retry = match result {
+                    Ok(()) => {
+                        status.last_success_at = status.last_attempt_at;
+                        status.last_error = None;
+                        status.consecutive_failures = 0;
+                        false
+                    }
+                    Err(e) => {
+                        log::warn!("Metadata push to '{}' failed: {e}", target.name);
+                        status.last_error = Some(e);
+                        status.consecutive_failures += 1;
+                        !superseded && attempt < target.max_retries
+                    }
+                };
+                if !retry && !superseded {
+                    status.pending = false;
+                }
+                retry
+            };
+            if !retry {
+                return;
+            }
+            let backoff = RETRY_BACKOFF_SECS[(attempt as usize).min(RETRY_BACKOFF_SECS.len() - 1)];
+            attempt += 1;
+            tokio::time::sleep(Duration::from_secs(backoff)).await;
+        }
+    });
+}
+
+// ── Destinations ──────────────────────────────────────────────────────────────
+
+fn url_encode(value: &str) -> String {
+    urlencoding::encode(value).into_owned()
+}
+
+fn json_escape(value: &str) -> String {
+    let quoted = serde_json::to_string(value).unwrap_or_default();
+    quoted[1..quoted.len() - 1].to_string()
+}
+
+async fn check(resp: reqwest::Response, service: &str) -> Result<(), String> {
+    let status = resp.status();
+    if status.is_success() {
+        Ok(())
+    } else {
+        let body = resp.text().await.unwrap_or_default();
+        Err(format!(
+            "{service}: HTTP {status} {}",
+            body.chars().take(200).collect::<String>()
+        ))
+    }
+}
+
+/// Send one track to one destination.
+pub async fn deliver(destination: &PushDestination, track: &PushTrack) -> Result<(), String> {
+    let client = reqwest::Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .map_err(|e| e.to_string())?;
+
+    match destination {
+        PushDestination::TuneIn {
+            partner_id,
+            partner_key,
+            station_id,
+        } => {
+            let mut params = vec![
+                ("partnerId", partner_id.as_str()),
+                ("partnerKey", partner_key.as_str()),
+                ("id", station_id.as_str()),
+            ];
+            // TuneIn wants adverts flagged instead of titled.
+            if track.song_type.eq_ignore_ascii_case("A") {
+                params.push(("commercial", "true"));
+            } else {
+                params.push(("title", track.title.as_str()));
+                params.push(("artist", track.artist.as_str()));
+                if !track.album.is_empty() {
+                    params.push(("album", track.album.as_str()));
+                }
+            }
+            let resp = client
+                .get("https://air.radiotime.com/Playing.ashx")
+                .query(&params)
+                .send()
+                .await
+                .map_err(|e| format!("TuneIn request failed: {e}"))?;
+            check(resp, "TuneIn").await
+        }
+        PushDestination::Live365 {
+            api_url,
+            member_name,
+            password,
+        } => {
+            let seconds = track.duration_secs.to_string();
+            let resp = client
+                .get(api_url)
+                .query(&[
+                    ("member_name", member_name.as_str()),
+                    ("password", password.as_str()),
+                    ("version", "2"),
+                    ("title", track.title.as_str()),
+                    ("artist", track.artist.as_str()),
+                    ("album", track.album.as_str()),
+                    ("seconds", seconds.as_str()),
+                ])
+                .send()
+                .await
+                .map_err(|e| format!("Live365 request failed: {e}"))?;
+            check(resp, "Live365").await
+        }
+        PushDestination::Discord {
+            webhook_url,
+            message_template,
+            username,
+        } => {
+            let template = if message_template.trim().is_empty() {
+                "Now playing: **$title$** by $artist$"
+            } else {
+                message_template.as_str()
+            };
+            let mut body = serde_json::json!({
+                "content": track.render(template, |v| v.to_string()),
+            });
+            if let Some(name) = username.as_deref().filter(|n| !n.trim().is_empty()) {
+                body["username"] = serde_json::Value::String(name.to_string());
+            }
+            if !track.artwork_url.is_empty() {
+                body["embeds"] = serde_json::json!([{
+                    "title": track.title,
+                    "description": track.artist,
+                    "thumbnail": { "url": track.artwork_url },
+                }]);
+            }
+            let resp = client
+                .post(webhook_url)
+                .json(&body)
+                .send()
+                .await
+                .map_err(|e| format!("Discord request failed: {e}"))?;
+            check(resp, "Discord").await
+        }
+        PushDestination::Http {
+            url_template,
+            method,
+            headers,
+            body_template,
+            content_type,
+        } => {
+            let url = track.render(url_template, url_encode);
+            let mut req = if method.eq_ignore_ascii_case("GET") {
+                client.get(&url)
+            } else {
+                let is_json = content_type.contains("json");
+                let body = body_template
+                    .as_deref()
+                    .map(|t| {
+                        if is_json {
+                            track.render(t, json_escape)
+                        } else {
+                            track.render(t, url_encode)
+                        }
+                    })
+                    .unwrap_or_default();
+                client
+                    .post(&url)
+                    .header(reqwest::header::CONTENT_TYPE, content_type.as_str())
+                    .body(body)
+            };
+            for header in headers {
+                req = req.header(header.name.as_str(), header.value.as_str());
+            }
+            let resp = req
+                .send()
+                .await
+                .map_err(|e| format!("HTTP push failed: {e}"))?;
+            check(resp, "HTTP push").await
+        }
+    }
+}
+
+// ── DB helpers ────────────────────────────────────────────────────────────────
+
+pub async fn get_targets(pool: &SqlitePool) -> Result<Vec<MetadataPushTarget>, sqlx::Error> {
+    let rows =
+        sqlx::query("SELECT id, name, enabled, target_json FROM metadata_push_targets ORDER BY id")
+            .fetch_all(pool)
+            .await?;
+    Ok(rows
+        .iter()
+        .filter_map(|r| {
+            let json: String = r.get("target_json");
+            let mut target: MetadataPushTarget = serde_json::from_str(&json)
+                .map_err(|e| log::warn!("Skipping invalid metadata push target: {e}"))
+                .ok()?;
+            target.id = Some(r.get("id"));
+            target.name = r.get("name");
+            target.enabled = r.get::<i64, _>("enabled") != 0;
+            Some(target)
+        })
+        .collect())
+}
+
+pub async fn upsert_target(
+    pool: &SqlitePool,
+    target: &MetadataPushTarget,
+) -> Result<i64, sqlx::Error> {
+    let json = serde_json::to_string(target).unwrap_or_else(|_| "{}".to_string());
+    let id = if let Some(id) = target.id {
+        sqlx::query(
+            "UPDATE metadata_push_targets SET name=?, enabled=?, target_json=?, updated_at=strftime('%s','now') WHERE id=?",
+        )
+        .bind(&target.name)
+        .bind(target.enabled as i64)
+        .bind(&json)
+        .bind(id)
+        .execute(pool)
+        .await?;
+        id
+    } else {
+        sqlx::query(
+            "INSERT INTO metadata_push_targets (name, enabled, target_json) VALUES (?, ?, ?)",
+        )
+        .bind(&target.name)
+        .bind(target.enabled as i64)
+        .bind(&json)
+        .execute(pool)
+        .await?
+        .last_insert_rowid()
+    };
+    Ok(id)
+}
+
+pub async fn delete_target(pool: &SqlitePool, id: i64) -> Result<(), sqlx::Error> {
+    sqlx::query("DELETE FROM metadata_push_targets WHERE id = ?")
+        .bind(id)
+        .execute(pool)
+        .await?;
+    let mut st = fanout().lock().unwrap();
+    st.status.remove(&id);
+    st.generation.remove(&id);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_templates_with_escaping() {
+        let track = PushTrack {
+            song_id: Some(7),
+            artist: "A.R. Rahman".to_string(),
+            title: "Jai \"Ho\"".to_string(),
+            duration_secs: 320,
+            ..Default::default()
+        };
+        assert_eq!(
+            track.render("https://x/np?s=$combine$&id=$song_id$", url_encode),
+            "https://x/np?s=A.R.%20Rahman%20-%20Jai%20%22Ho%22&id=7"
+        );
+        assert_eq!(
+            track.render(r#"{"t":"$title$","d":$duration$}"#, json_escape),
+            r#"{"t":"Jai \"Ho\"","d":320}"#
+        );
+    }
+}
@@ -7,11 +7,15 @@ use super::encoder_manager::ShoutcastVersion;
 
 /// Push ICY metadata to an Icecast 2.x server via the admin API.
 /// Endpoint: GET /admin/metadata?mount=/stream&mode=updinfo&song=Artist+-+Title
+///
+/// ICY has no artwork field, so cover art only reaches the server through a
+/// `$artwork$` placeholder in the encoder's URL-append template.
 pub async fn push_icecast_metadata(
     config: &EncoderConfig,
     artist: &str,
     title: &str,
     song: &str,
+    artwork_url: Option<&str>,
 ) -> Result<(), String> {
     let host = config.server_host.as_deref().unwrap_or("localhost");
     let port = config.server_port.unwrap_or(8000);
@@ -19,7 +23,13 @@ pub async fn push_icecast_metadata(
     let password = config.server_password.as_deref().unwrap_or("");
 
     let encoded_song = urlencoding_encode(song);
-    let extra = render_url_append(config.metadata_url_append.as_deref(), artist, title, song);
+    let extra = render_url_append(
+        config.metadata_url_append.as_deref(),
+        artist,
+        title,
+        song,
+        artwork_url.unwrap_or(""),
+    );
     let url = format!(
         "http://{host}:{port}/admin/metadata?mount={mount}&mode=updinfo&song={encoded_song}{extra}"
     );
@@ -99,7 +109,13 @@ pub async fn push_shoutcast_metadata(
     }
 }
 
-fn render_url_append(template: Option<&str>, artist: &str, title: &str, song: &str) -> String {
+fn render_url_append(
+    template: Option<&str>,
+    artist: &str,
+    title: &str,
+    song: &str,
+    artwork_url: &str,
+) -> String {
     let Some(raw) = template else {
         return String::new();
     };
@@ -115,6 +131,8 @@ fn render_url_append(template: Option<&str>, artist: &str, title: &str, song: &s
         .replace("#combine#", &urlencoding_encode(song))
         .replace("$song$", &urlencoding_encode(song))
         .replace("#song#", &urlencoding_encode(song))
+        .replace("$artwork$", &urlencoding_encode(artwork_url))
+        .replace("#artwork#", &urlencoding_encode(artwork_url))
         .replace("$album$", "")
         .replace("#album#", "")
 }
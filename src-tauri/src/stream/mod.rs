@@ -1,7 +1,20 @@
+pub mod ad_cues;
 pub mod broadcaster;
 pub mod encoder_file;
 pub mod encoder_manager;
+pub mod encoder_probe;
+pub mod export_upload;
+pub mod failover;
+pub mod flac;
 pub mod icecast;
+pub mod metadata_fanout;
 pub mod metadata_pusher;
 pub mod mp3;
+pub mod overlay_server;
+pub mod recording_chapters;
+pub mod recording_writer;
 pub mod shoutcast;
+pub mod show_export;
+pub mod station_id_gate;
+pub mod title_format;
+pub mod watermark;
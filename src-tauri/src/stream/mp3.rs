@@ -15,9 +15,18 @@ unsafe impl Send for Mp3Encoder {}
 
 impl Mp3Encoder {
     pub fn from_config(config: &EncoderConfig) -> Result<Self, String> {
-        let channels = config.channels.clamp(1, 2);
-        let sample_rate = nearest_u32(config.sample_rate, SUPPORTED_SAMPLE_RATES);
-        let bitrate = nearest_u32(config.bitrate_kbps.unwrap_or(128), SUPPORTED_BITRATES);
+        Self::new(
+            config.sample_rate,
+            config.channels,
+            config.bitrate_kbps.unwrap_or(128),
+        )
+    }
+
+    /// Encoder at the supported rate and bitrate nearest the ones asked for.
+    pub fn new(sample_rate: u32, channels: u8, bitrate_kbps: u32) -> Result<Self, String> {
+        let channels = channels.clamp(1, 2);
+        let sample_rate = nearest_u32(sample_rate, SUPPORTED_SAMPLE_RATES);
+        let bitrate = nearest_u32(bitrate_kbps, SUPPORTED_BITRATES);
         let stereo_mode = if channels == 1 {
             StereoMode::Mono
         } else {
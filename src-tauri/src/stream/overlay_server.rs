@@ -0,0 +1,427 @@
+/// WebSocket feed for stream overlays
+///
+/// OBS browser sources and other overlay tools connect to
+/// `ws://host:port/?token=…` and receive JSON text frames shaped
+/// `{ "event": "…", "data": … }`, carrying the payloads the UI already gets
+/// as Tauri events:
+///
+///   now_playing         — `request_api::NowPlaying` (null when nothing is on
+///                         air), on connect and at every track change
+///   deck_state_changed  — deck state and position, one frame per deck
+///   vu_meter            — channel levels, at most every `vu_interval_ms`
+///   ad_break            — `ad_cues` cue-out / cue-in at traffic break boundaries
+///
+/// New clients get now-playing and every deck's state straight away. The
+/// feed is read-only; frames from clients are ignored. Browser sources cannot
+/// set headers, so the token may come as `?token=` as well as
+/// `Authorization: Bearer`.
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use futures_util::{SinkExt, StreamExt};
+use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use tauri::{AppHandle, Manager};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{broadcast, oneshot};
+use tokio_tungstenite::tungstenite::handshake::server::{
+    Callback, ErrorResponse, Request, Response,
+};
+use tokio_tungstenite::tungstenite::{http, Message};
+
+use crate::audio::crossfade::DeckId;
+use crate::scheduler::request_api::{self, token_matches};
+use crate::state::AppState;
+
+const MIN_VU_INTERVAL_MS: u64 = 20;
+/// Frames a slow client may fall behind before it skips ahead.
+const CLIENT_BACKLOG: usize = 256;
+const MAX_CLIENTS: usize = 32;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OverlayServerConfig {
+    pub enabled: bool,
+    /// Loopback by default: overlays normally run on the broadcast machine
+    pub bind_address: String,
+    pub port: u16,
+    /// Shared secret overlays pass as `?token=` or a Bearer header
+    pub token: String,
+    /// Shortest gap between VU frames per channel
+    pub vu_interval_ms: u64,
+}
+
+impl Default for OverlayServerConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            bind_address: "127.0.0.1".to_string(),
+            port: 8096,
+            token: String::new(),
+            vu_interval_ms: 100,
+        }
+    }
+}
+
+impl OverlayServerConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.enabled && self.port == 0 {
+            return Err("Overlay server port must be set".to_string());
+        }
+        if self.enabled && self.token.trim().is_empty() {
+            return Err("Set a token before enabling the overlay server".to_string());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OverlayServerStatus {
+    pub running: bool,
+    pub listening_on: Option<String>,
+    /// Overlays currently connected
+    pub clients: usize,
+    pub connections_rejected: u64,
+    pub last_error: Option<String>,
+}
+
+// ── Server lifecycle ──────────────────────────────────────────────────────────
+
+struct Runtime {
+    shutdown: Option<oneshot::Sender<()>>,
+    status: OverlayServerStatus,
+    /// Serialized frames for every connected client
+    feed: Option<broadcast::Sender<Arc<str>>>,
+    vu_interval: Duration,
+    vu_sent: HashMap<String, Instant>,
+}
+
+static RUNTIME: OnceLock<Mutex<Runtime>> = OnceLock::new();
+
+fn runtime() -> &'static Mutex<Runtime> {
+    RUNTIME.get_or_init(|| {
+        Mutex::new(Runtime {
+            shutdown: None,
+            status: OverlayServerStatus::default(),
+            feed: None,
+            vu_interval: Duration::from_millis(100),
+            vu_sent: HashMap::new(),
+        })
+    })
+}
+
+pub fn status() -> OverlayServerStatus {
+    runtime().lock().unwrap().status.clone()
+}
+
+/// Bind and start serving; a server that is already running is replaced.
+pub async fn start(
+    app: AppHandle,
+    config: OverlayServerConfig,
+) -> Result<OverlayServerStatus, String> {
+    config.validate()?;
+    if stop() {
+        // Give the previous accept loop a moment to release the port.
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+
+    let listener = match TcpListener::bind((config.bind_address.as_str(), config.port)).await {
+        Ok(l) => l,
+        Err(e) => {
+            let msg = format!(
+                "Overlay server: cannot bind {}:{}: {e}",
+                config.bind_address, config.port
+            );
+            runtime().lock().unwrap().status.last_error = Some(msg.clone());
+            return Err(msg);
+        }
+    };
+    let local_addr = listener.local_addr().map_err(|e| e.to_string())?;
+    let (tx, mut rx) = oneshot::channel();
+    let (feed, _) = broadcast::channel(CLIENT_BACKLOG);
+    {
+        let mut rt = runtime().lock().unwrap();
+        rt.shutdown = Some(tx);
+        rt.feed = Some(feed.clone());
+        rt.vu_interval = Duration::from_millis(config.vu_interval_ms.max(MIN_VU_INTERVAL_MS));
+        rt.vu_sent.clear();
+        rt.status = OverlayServerStatus {
+            running: true,
+            listening_on: Some(local_addr.to_string()),
+            ..OverlayServerStatus::default()
+        };
+    }
+    log::info!("Overlay server listening on {local_addr}");
+
+    let token: Arc<str> = config.token.trim().into();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::select! {
+                _ = &mut rx => break,
+                accepted = listener.accept() => match accepted {
+                    Ok((stream, peer)) => {
+                        {
+                            let mut rt = runtime().lock().unwrap();
+                            if rt.status.clients >= MAX_CLIENTS {
+                                rt.status.connections_rejected += 1;
+                                log::debug!("Overlay server: {peer} refused (too many clients)");
+                                continue;
+                            }
+                        }
+                        let app = app.clone();
+                        let token = Arc::clone(&token);
+                        let frames = feed.subscribe();
+                        tauri::async_runtime::spawn(async move {
+                            if let Err(e) = serve_client(&app, &token, stream, peer, frames).await {
+                                log::debug!("Overlay client {peer}: {e}");
+                            }
+                        });
+                    }
+                    Err(e) => {
+                        log::warn!("Overlay server accept failed: {e}");
+                        tokio::time::sleep(Duration::from_millis(200)).await;
+                    }
+                },
+            }
+        }
+        log::info!("Overlay server on {local_addr} stopped");
+    });
+
+    Ok(status())
+}
+
+/// Stop the server and disconnect its clients. Returns whether one was
+/// running.
+pub fn stop() -> bool {
+    let mut rt = runtime().lock().unwrap();
+    rt.status.running = false;
+    rt.status.listening_on = None;
+    // Dropping the feed closes every client's receiver.
+    rt.feed = None;
+    match rt.shutdown.take() {
+        Some(tx) => {
+            let _ = tx.send(());
+            true
+        }
+        None => false,
+    }
+}
+
+// ── Clients ───────────────────────────────────────────────────────────────────
+
+/// Counts a connected client for as long as it lives.
+struct ClientSlot;
+
+impl ClientSlot {
+    fn take() -> Self {
+        runtime().lock().unwrap().status.clients += 1;
+        Self
+    }
+}
+
+impl Drop for ClientSlot {
+    fn drop(&mut self) {
+        let mut rt = runtime().lock().unwrap();
+        rt.status.clients = rt.status.clients.saturating_sub(1);
+    }
+}
+
+async fn serve_client(
+    app: &AppHandle,
+    token: &str,
+    stream: TcpStream,
+    peer: SocketAddr,
+    mut frames: broadcast::Receiver<Arc<str>>,
+) -> Result<(), tokio_tungstenite::tungstenite::Error> {
+    let ws = tokio_tungstenite::accept_hdr_async(stream, TokenCheck { token }).await?;
+    let _slot = ClientSlot::take();
+    log::debug!("Overlay client {peer} connected");
+    let (mut sink, mut incoming) = ws.split();
+
+    for frame in initial_frames(app).await {
+        sink.send(Message::Text(frame)).await?;
+    }
+    loop {
+        tokio::select! {
+            frame = frames.recv() => match frame {
+                Ok(frame) => sink.send(Message::Text(frame.to_string())).await?,
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    log::debug!("Overlay client {peer} fell behind; skipped {skipped} frames");
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            },
+            received = incoming.next() => match received {
+                Some(Ok(Message::Close(_))) | None => return Ok(()),
+                Some(Ok(_)) => {}
+                Some(Err(e)) => return Err(e),
+            },
+        }
+    }
+    sink.send(Message::Close(None)).await
+}
+
+/// Handshake callback that turns away requests without the token.
+struct TokenCheck<'a> {
+    token: &'a str,
+}
+
+impl Callback for TokenCheck<'_> {
+    fn on_request(self, req: &Request, resp: Response) -> Result<Response, ErrorResponse> {
+        if authorized(req, self.token) {
+            return Ok(resp);
+        }
+        runtime().lock().unwrap().status.connections_rejected += 1;
+        let mut reject = ErrorResponse::new(Some("Missing or invalid token".to_string()));
+        *reject.status_mut() = http::StatusCode::UNAUTHORIZED;
+        Err(reject)
+    }
+}
+
+fn authorized(req: &Request, token: &str) -> bool {
+    let from_query = req.uri().query().and_then(|q| {
+        q.split('&')
+            .filter_map(|pair| pair.split_once('='))
+            .find(|(k, _)| *k == "token")
+            .and_then(|(_, v)| urlencoding::decode(v).ok())
+            .map(|v| v.into_owned())
+    });
+    let from_header = req
+        .headers()
+        .get("authorization")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "));
+    token_matches(from_header.or(from_query.as_deref()), token)
+}
+
+/// Now playing and every deck's state, for a client that just connected.
+async fn initial_frames(app: &AppHandle) -> Vec<String> {
+    let state = app.state::<AppState>();
+    let decks: Vec<_> = {
+        let engine = state.engine.lock().unwrap();
+        DeckId::ALL
+            .into_iter()
+            .filter_map(|d| engine.get_deck_state(d))
+            .collect()
+    };
+    let now_playing = request_api::now_playing(&state).await;
+    let mut frames = vec![frame("now_playing", &serde_json::json!(now_playing))];
+    frames.extend(
+        decks
+            .iter()
+            .map(|d| frame("deck_state_changed", &serde_json::json!(d))),
+    );
+    frames
+}
+
+fn frame(event: &str, data: &serde_json::Value) -> String {
+    serde_json::json!({ "event": event, "data": data }).to_string()
+}
+
+// ── Publishing ────────────────────────────────────────────────────────────────
+
+/// The feed, when the server runs and at least one overlay listens.
+fn listening_feed() -> Option<broadcast::Sender<Arc<str>>> {
+    runtime()
+        .lock()
+        .unwrap()
+        .feed
+        .clone()
+        .filter(|f| f.receiver_count() > 0)
+}
+
+/// Send a UI event payload to overlays. The UI loop calls this every poll,
+/// independent of the (focus-dependent) UI emit rate.
+pub fn publish<T: Serialize>(event: &str, payload: &T) {
+    let (feed, payload) = {
+        let mut rt = runtime().lock().unwrap();
+        let Some(feed) = rt.feed.clone().filter(|f| f.receiver_count() > 0) else {
+            return;
+        };
+        let Ok(payload) = serde_json::to_value(payload) else {
+            return;
+        };
+        if event == "vu_meter" {
+            let channel = payload
+                .get("channel")
+                .and_then(|c| c.as_str())
+                .unwrap_or_default();
+            let now = Instant::now();
+            let interval = rt.vu_interval;
+            if rt
+                .vu_sent
+                .get(channel)
+                .is_some_and(|sent| now.duration_since(*sent) < interval)
+            {
+                return;
+            }
+            rt.vu_sent.insert(channel.to_string(), now);
+        }
+        (feed, payload)
+    };
+    let _ = feed.send(frame(event, &payload).into());
+}
+
+/// The on-air track changed: send overlays the new now-playing.
+pub fn track_changed(app: &AppHandle) {
+    if listening_feed().is_none() {
+        return;
+    }
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let now_playing = request_api::now_playing(&app.state::<AppState>()).await;
+        if let Some(feed) = listening_feed() {
+            let _ = feed.send(frame("now_playing", &serde_json::json!(now_playing)).into());
+        }
+    });
+}
+
+// ── DB helpers ────────────────────────────────────────────────────────────────
+
+pub async fn get_config(pool: &SqlitePool) -> Result<OverlayServerConfig, sqlx::Error> {
+    let row: Option<String> =
+        sqlx::query_scalar("SELECT config_json FROM overlay_server_config WHERE id = 1")
+            .fetch_optional(pool)
+            .await?;
+    Ok(row
+        .and_then(|j| serde_json::from_str(&j).ok())
+        .unwrap_or_default())
+}
+
+pub async fn save_config(
+    pool: &SqlitePool,
+    config: &OverlayServerConfig,
+) -> Result<(), sqlx::Error> {
+    let json = serde_json::to_string(config).unwrap_or_else(|_| "{}".to_string());
+    sqlx::query(
+        "INSERT INTO overlay_server_config (id, config_json, updated_at) VALUES (1, ?, strftime('%s','now')) \
+         ON CONFLICT(id) DO UPDATE SET config_json = excluded.config_json, updated_at = excluded.updated_at",
+    )
+    .bind(json)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn token_from_query_or_bearer_header() {
+        let req = |uri: &str, auth: Option<&str>| {
+            let mut builder = http::Request::builder().uri(uri);
+            if let Some(auth) = auth {
+                builder = builder.header("Authorization", auth);
+            }
+            builder.body(()).unwrap()
+        };
+        assert!(authorized(&req("/?token=s3c%2Bret", None), "s3c+ret"));
+        assert!(authorized(&req("/?x=1&token=s3c+ret", None), "s3c+ret"));
+        assert!(authorized(&req("/", Some("Bearer s3c+ret")), "s3c+ret"));
+        assert!(!authorized(&req("/?token=wrong", None), "s3c+ret"));
+        assert!(!authorized(&req("/", Some("Basic s3c+ret")), "s3c+ret"));
+        assert!(!authorized(&req("/?token=", None), ""));
+    }
+}
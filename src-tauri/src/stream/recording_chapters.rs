@@ -0,0 +1,238 @@
+/// `recording_chapters.rs` — track boundaries for recordings
+///
+/// Every on-air track start is logged here with its wall-clock time, and the
+/// engine's completion event for the track later pins where it ended. File
+/// recorders use the log to split archives at track boundaries and, when a
+/// file is closed, to write a `.cue` sheet or JSON chapter list covering the
+/// tracks heard in it (the one already playing when the file opened starts at
+/// 0:00).
+use std::{
+    collections::VecDeque,
+    path::{Path, PathBuf},
+    sync::{Mutex, OnceLock},
+};
+
+use serde::{Deserialize, Serialize};
+
+/// About a day of back-to-back songs; older marks are dropped.
+const MAX_MARKS: usize = 512;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RecordingSidecar {
+    None,
+    /// CD-style `.cue` sheet
+    Cue,
+    /// `.chapters.json`
+    Json,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TrackMark {
+    /// Unix ms when the track went on air
+    pub at_ms: i64,
+    /// Unix ms from the completion event; `None` while playing
+    pub ended_at_ms: Option<i64>,
+    pub song_id: Option<i64>,
+    pub artist: String,
+    pub title: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Chapter {
+    /// Offsets into the file
+    pub start_ms: u64,
+    /// Where the track stopped, the next one started or the file ended
+    pub end_ms: u64,
+    pub song_id: Option<i64>,
+    pub artist: String,
+    pub title: String,
+}
+
+fn log() -> &'static Mutex<VecDeque<TrackMark>> {
+    static LOG: OnceLock<Mutex<VecDeque<TrackMark>>> = OnceLock::new();
+    LOG.get_or_init(Default::default)
+}
+
+pub fn mark_track(mark: TrackMark) {
+    let mut log = log().lock().unwrap();
+    if log.len() == MAX_MARKS {
+        log.pop_front();
+    }
+    log.push_back(mark);
+}
+
+/// Record where a track stopped, from its engine completion event.
+pub fn complete_track(song_id: i64, ended_at_ms: i64) {
+    let mut log = log().lock().unwrap();
+    if let Some(mark) = log
+        .iter_mut()
+        .rev()
+        .find(|m| m.song_id == Some(song_id) && m.ended_at_ms.is_none())
+    {
+        mark.ended_at_ms = Some(ended_at_ms.max(mark.at_ms));
+    }
+}
+
+/// When the newest track went on air.
+pub fn latest_mark_ms() -> Option<i64> {
+    log().lock().unwrap().back().map(|m| m.at_ms)
+}
+
+/// Tracks heard between `from_ms` and `to_ms`, relative to `from_ms`.
+pub fn chapters(from_ms: i64, to_ms: i64) -> Vec<Chapter> {
+    chapters_in(log().lock().unwrap().iter(), from_ms, to_ms)
+}
+
+fn chapters_in<'a>(
+    marks: impl DoubleEndedIterator<Item = &'a TrackMark>,
+    from_ms: i64,
+    to_ms: i64,
+) -> Vec<Chapter> {
+    let mut heard: Vec<&TrackMark> = Vec::new();
+    for mark in marks.rev() {
+        if mark.at_ms >= to_ms {
+            continue;
+        }
+        if mark.ended_at_ms.is_none_or(|end| end > from_ms) {
+            heard.push(mark);
+        }
+        // The track already playing when the file opened is the first chapter.
+        if mark.at_ms <= from_ms {
+            break;
+        }
+    }
+    heard.reverse();
+
+    let offset = |at: i64| (at.clamp(from_ms, to_ms) - from_ms) as u64;
+    heard
+        .iter()
+        .enumerate()
+        .map(|(i, mark)| {
+            let next_start = heard.get(i + 1).map_or(to_ms, |next| next.at_ms);
+            let end = mark
+                .ended_at_ms
+                .map_or(next_start, |end| end.min(next_start));
+            Chapter {
+                start_ms: offset(mark.at_ms),
+                end_ms: offset(end),
+                song_id: mark.song_id,
+                artist: mark.artist.clone(),
+                title: mark.title.clone(),
+            }
+        })
+        .collect()
+}
+
+/// `recording.flac` → `recording.cue` / `recording.chapters.json`
+pub fn sidecar_path(audio: &Path, kind: RecordingSidecar) -> Option<PathBuf> {
+    match kind {
+        RecordingSidecar::None => None,
+        RecordingSidecar::Cue => Some(audio.with_extension("cue")),
+        RecordingSidecar::Json => Some(audio.with_extension("chapters.json")),
+    }
+}
+
+/// Write the sidecar for a closed recording that ran from `from_ms` to `to_ms`.
+pub fn write_sidecar(
+    kind: RecordingSidecar,
+    audio: &Path,
+    station: &str,
+    from_ms: i64,
+    to_ms: i64,
+) -> Result<(), String> {
+    let Some(path) = sidecar_path(audio, kind) else {
+        return Ok(());
+    };
+    let chapters = chapters(from_ms, to_ms);
+    let file_name = audio
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or_default();
+    let contents = match kind {
+        RecordingSidecar::None => return Ok(()),
+        RecordingSidecar::Cue => cue_sheet(file_name, station, &chapters),
+        RecordingSidecar::Json => serde_json::to_string_pretty(&serde_json::json!({
+            "file": file_name,
+            "started_at": from_ms,
+            "duration_ms": (to_ms - from_ms).max(0),
+            "chapters": chapters,
+        }))
+        .map_err(|e| e.to_string())?,
+    };
+    std::fs::write(&path, contents)
+        .map_err(|e| format!("Cannot write chapter file {}: {e}", path.display()))
+}
+
+fn cue_sheet(file_name: &str, station: &str, chapters: &[Chapter]) -> String {
+    let quote = |s: &str| s.replace('"', "'");
+    let file_type = match Path::new(file_name).extension().and_then(|e| e.to_str()) {
+        Some("mp3") => "MP3",
+        _ => "WAVE",
+    };
+    let mut out = String::new();
+    if !station.is_empty() {
+        out.push_str(&format!("PERFORMER \"{}\"\n", quote(station)));
+    }
+    out.push_str(&format!(
+        "TITLE \"{}\"\nFILE \"{}\" {file_type}\n",
+        quote(file_name),
+        quote(file_name),
+    ));
+    for (i, chapter) in chapters.iter().enumerate() {
+        // Cue sheets count 75 frames per second.
+        let frames = chapter.start_ms * 75 / 1000;
+        out.push_str(&format!(
+            "  TRACK {:02} AUDIO\n    TITLE \"{}\"\n    PERFORMER \"{}\"\n    INDEX 01 {:02}:{:02}:{:02}\n",
+            i + 1,
+            quote(&chapter.title),
+            quote(&chapter.artist),
+            frames / 75 / 60,
+            frames / 75 % 60,
+            frames % 75,
+        ));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mark(at_ms: i64, title: &str) -> TrackMark {
+        TrackMark {
+            at_ms,
+            ended_at_ms: None,
+            song_id: None,
+            artist: "Artist".to_string(),
+            title: title.to_string(),
+        }
+    }
+
+    #[test]
+    fn chapters_start_with_the_track_already_playing() {
+        let marks = [
+            mark(0, "Old"),
+            TrackMark {
+                ended_at_ms: Some(69_000),
+                ..mark(10_000, "Playing")
+            },
+            mark(70_500, "Next"),
+            mark(200_000, "Later"),
+        ];
+        let chapters = chapters_in(marks.iter(), 60_000, 180_000);
+        let spans: Vec<_> = chapters
+            .iter()
+            .map(|c| (c.title.as_str(), c.start_ms, c.end_ms))
+            .collect();
+        assert_eq!(
+            spans,
+            vec![("Playing", 0, 9_000), ("Next", 10_500, 120_000)]
+        );
+
+        let cue = cue_sheet("show.flac", "DesiZone", &chapters);
+        assert!(cue.contains("FILE \"show.flac\" WAVE"));
+        assert!(cue.contains("  TRACK 02 AUDIO\n    TITLE \"Next\""));
+        assert!(cue.contains("INDEX 01 00:10:37"));
+    }
+}
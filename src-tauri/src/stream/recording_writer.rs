@@ -0,0 +1,369 @@
+/// `recording_writer.rs` — one recording file in the chosen format
+///
+/// `encoder_file` feeds interleaved stereo master audio; the writer downmixes
+/// to mono when the encoder is set to one channel and owns the container:
+///
+/// - **WAV** — 16-bit PCM (hound); the header sizes are patched on close.
+/// - **FLAC** — 16-bit lossless via `flac::FlacEncoder`.
+/// - **MP3** — shine at the encoder bitrate.
+/// - **Opus** — libopus in an Ogg stream, resampled to 48 kHz if needed.
+use std::{
+    fs::File,
+    io::{BufWriter, Seek, SeekFrom, Write},
+    path::Path,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
+
+use ogg::{PacketWriteEndInfo, PacketWriter};
+use shine_rs::SUPPORTED_SAMPLE_RATES;
+
+use super::{encoder_manager::Codec, flac::FlacEncoder, mp3::Mp3Encoder};
+use crate::gateway::audio_ingest::LinearResampler;
+
+const OPUS_SAMPLE_RATE: u32 = 48_000;
+/// 20 ms packets
+const OPUS_FRAME: usize = 960;
+/// libopus encoder delay at 48 kHz, trimmed by players
+const OPUS_PRE_SKIP: u16 = 312;
+/// Close an Ogg page about once a second
+const OPUS_PACKETS_PER_PAGE: u32 = 50;
+
+/// File extension for a recording codec, or why it cannot be recorded.
+pub fn extension(codec: &Codec) -> Result<&'static str, String> {
+    match codec {
+        Codec::Wav => Ok("wav"),
+        Codec::Flac => Ok("flac"),
+        Codec::Mp3 => Ok("mp3"),
+        Codec::Opus => Ok("opus"),
+        Codec::Aac | Codec::Ogg => Err(format!(
+            "{codec:?} is not available for recordings; use WAV, FLAC, MP3 or Opus"
+        )),
+    }
+}
+
+pub struct RecordingWriter {
+    sink: Sink,
+    channels: u8,
+    bytes: Arc<AtomicU64>,
+    mono: Vec<f32>,
+}
+
+enum Sink {
+    Wav(hound::WavWriter<CountingFile>),
+    Flac(FlacEncoder<CountingFile>),
+    Mp3 {
+        encoder: Mp3Encoder,
+        out: CountingFile,
+    },
+    Opus(Box<OpusSink>),
+}
+
+impl RecordingWriter {
+    pub fn create(
+        path: &Path,
+        codec: &Codec,
+        sample_rate: u32,
+        channels: u8,
+        bitrate_kbps: u32,
+    ) -> Result<Self, String> {
+        let channels = channels.clamp(1, 2);
+        let bytes = Arc::new(AtomicU64::new(0));
+        let file = File::create(path)
+            .map_err(|e| format!("Cannot create recording file {}: {e}", path.display()))?;
+        let out = CountingFile {
+            inner: BufWriter::new(file),
+            bytes: bytes.clone(),
+        };
+        let io_err = |e: std::io::Error| format!("Recording write error: {e}");
+        let sink = match codec {
+            Codec::Wav => {
+                let spec = hound::WavSpec {
+                    channels: u16::from(channels),
+                    sample_rate,
+                    bits_per_sample: 16,
+                    sample_format: hound::SampleFormat::Int,
+                };
+                Sink::Wav(hound::WavWriter::new(out, spec).map_err(|e| e.to_string())?)
+            }
+            Codec::Flac => {
+                Sink::Flac(FlacEncoder::new(out, sample_rate, channels).map_err(io_err)?)
+            }
+            Codec::Mp3 => {
+                if !SUPPORTED_SAMPLE_RATES.contains(&sample_rate) {
+                    return Err(format!("MP3 recording cannot encode at {sample_rate} Hz"));
+                }
+                Sink::Mp3 {
+                    encoder: Mp3Encoder::new(sample_rate, channels, bitrate_kbps)?,
+                    out,
+                }
+            }
+            Codec::Opus => Sink::Opus(Box::new(OpusSink::new(
+                out,
+                sample_rate,
+                channels,
+                bitrate_kbps,
+            )?)),
+            Codec::Aac | Codec::Ogg => return Err(extension(codec).unwrap_err()),
+        };
+        Ok(Self {
+            sink,
+            channels,
+            bytes,
+            mono: Vec::new(),
+        })
+    }
+
+    /// Bytes on disk so far (buffered writes included).
+    pub fn bytes_written(&self) -> u64 {
+        self.bytes.load(Ordering::Relaxed)
+    }
+
+    /// Append interleaved stereo samples.
+    pub fn write(&mut self, stereo: &[f32]) -> Result<(), String> {
+        if let Sink::Opus(opus) = &mut self.sink {
+            // Resample before the downmix; the resampler is stereo-only.
+            return opus.write(stereo);
+        }
+        let samples = if self.channels == 1 {
+            downmix(stereo, &mut self.mono);
+            &self.mono[..]
+        } else {
+            stereo
+        };
+        let io_err = |e: std::io::Error| format!("Recording write error: {e}");
+        match &mut self.sink {
+            Sink::Wav(wav) => {
+                for &s in samples {
+                    wav.write_sample(to_i16(s)).map_err(|e| e.to_string())?;
+                }
+                Ok(())
+            }
+            Sink::Flac(flac) => flac.write_interleaved(samples).map_err(io_err),
+            Sink::Mp3 { encoder, out } => out
+                .write_all(encoder.encode_f32_interleaved(samples)?)
+                .map_err(io_err),
+            Sink::Opus(_) => unreachable!(),
+        }
+    }
+
+    /// Flush encoder tails and finalise headers.
+    pub fn finish(self) -> Result<(), String> {
+        let io_err = |e: std::io::Error| format!("Recording close error: {e}");
+        match self.sink {
+            Sink::Wav(wav) => wav.finalize().map_err(|e| e.to_string()),
+            Sink::Flac(flac) => flac.finish().map(|_| ()).map_err(io_err),
+            Sink::Mp3 {
+                mut encoder,
+                mut out,
+            } => {
+                out.write_all(encoder.flush()?).map_err(io_err)?;
+                out.flush().map_err(io_err)
+            }
+            Sink::Opus(opus) => opus.finish(),
+        }
+    }
+}
+
+fn to_i16(s: f32) -> i16 {
+    (s.clamp(-1.0, 1.0) * i16::MAX as f32).round() as i16
+}
+
+fn downmix(stereo: &[f32], out: &mut Vec<f32>) {
+    out.clear();
+    out.extend(stereo.chunks_exact(2).map(|lr| (lr[0] + lr[1]) * 0.5));
+}
+
+// ── Opus in Ogg ───────────────────────────────────────────────────────────────
+
+struct OpusSink {
+    encoder: opus::Encoder,
+    packets: PacketWriter<'static, CountingFile>,
+    serial: u32,
+    channels: u8,
+    resampler: Option<LinearResampler>,
+    resampled: Vec<f32>,
+    mono: Vec<f32>,
+    /// Interleaved 48 kHz samples waiting for a full packet
+    pending: Vec<f32>,
+    /// Samples per channel encoded so far, excluding pre-skip
+    encoded: u64,
+    packets_in_page: u32,
+    packet: Vec<u8>,
+}
+
+impl OpusSink {
+    fn new(out: CountingFile, sample_rate: u32, channels: u8, kbps: u32) -> Result<Self, String> {
+        let opus_err = |e: opus::Error| format!("Opus encoder error: {e}");
+        let mode = if channels == 1 {
+            opus::Channels::Mono
+        } else {
+            opus::Channels::Stereo
+        };
+        let mut encoder = opus::Encoder::new(OPUS_SAMPLE_RATE, mode, opus::Application::Audio)
+            .map_err(opus_err)?;
+        encoder
+            .set_bitrate(opus::Bitrate::Bits(kbps.clamp(6, 510) as i32 * 1000))
+            .map_err(opus_err)?;
+
+        let mut head = Vec::with_capacity(19);
+        head.extend_from_slice(b"OpusHead");
+        head.push(1);
+        head.push(channels);
+        head.extend_from_slice(&OPUS_PRE_SKIP.to_le_bytes());
+        head.extend_from_slice(&sample_rate.to_le_bytes());
+        head.extend_from_slice(&0i16.to_le_bytes());
+        head.push(0);
+
+        let vendor = concat!("DesiZone Broadcaster ", env!("CARGO_PKG_VERSION"));
+        let mut tags = Vec::new();
+        tags.extend_from_slice(b"OpusTags");
+        tags.extend_from_slice(&(vendor.len() as u32).to_le_bytes());
+        tags.extend_from_slice(vendor.as_bytes());
+        tags.extend_from_slice(&0u32.to_le_bytes());
+
+        let serial = rand::random::<u32>();
+        let mut packets = PacketWriter::new(out);
+        let io_err = |e: std::io::Error| format!("Recording write error: {e}");
+        packets
+            .write_packet(head, serial, PacketWriteEndInfo::EndPage, 0)
+            .map_err(io_err)?;
+        packets
+            .write_packet(tags, serial, PacketWriteEndInfo::EndPage, 0)
+            .map_err(io_err)?;
+
+        Ok(Self {
+            encoder,
+            packets,
+            serial,
+            channels,
+            resampler: (sample_rate != OPUS_SAMPLE_RATE)
+                .then(|| LinearResampler::new(sample_rate, OPUS_SAMPLE_RATE)),
+            resampled: Vec::new(),
+            mono: Vec::new(),
+            pending: Vec::new(),
+            encoded: 0,
+            packets_in_page: 0,
+            packet: vec![0u8; 4000],
+        })
+    }
+
+    fn write(&mut self, stereo: &[f32]) -> Result<(), String> {
+        let stereo = match self.resampler.as_mut() {
+            Some(resampler) => {
+                self.resampled.clear();
+                resampler.process(stereo, &mut self.resampled);
+                &self.resampled[..]
+            }
+            None => stereo,
+        };
+        if self.channels == 1 {
+            downmix(stereo, &mut self.mono);
+            self.pending.extend_from_slice(&self.mono);
+        } else {
+            self.pending.extend_from_slice(stereo);
+        }
+        let packet_len = OPUS_FRAME * self.channels as usize;
+        while self.pending.len() >= packet_len {
+            let frame: Vec<f32> = self.pending.drain(..packet_len).collect();
+            self.encoded += OPUS_FRAME as u64;
+            self.packets_in_page += 1;
+            let end = if self.packets_in_page >= OPUS_PACKETS_PER_PAGE {
+                self.packets_in_page = 0;
+                PacketWriteEndInfo::EndPage
+            } else {
+                PacketWriteEndInfo::NormalPacket
+            };
+            self.encode(&frame, end, self.encoded)?;
+        }
+        Ok(())
+    }
+
+    /// `granule` is the end position of the packet in 48 kHz samples,
+    /// excluding pre-skip.
+    fn encode(
+        &mut self,
+        frame: &[f32],
+        end: PacketWriteEndInfo,
+        granule: u64,
+    ) -> Result<(), String> {
+        let len = self
+            .encoder
+            .encode_float(frame, &mut self.packet)
+            .map_err(|e| format!("Opus encode failed: {e}"))?;
+        self.packets
+            .write_packet(
+                self.packet[..len].to_vec(),
+                self.serial,
+                end,
+                granule + u64::from(OPUS_PRE_SKIP),
+            )
+            .map_err(|e| format!("Recording write error: {e}"))
+    }
+
+    fn finish(mut self) -> Result<(), String> {
+        // Pad the tail to a whole packet; the final granule marks where the
+        // real audio ends so players trim the padding.
+        let packet_len = OPUS_FRAME * self.channels as usize;
+        let real = (self.pending.len() / self.channels as usize) as u64;
+        let mut frame = std::mem::take(&mut self.pending);
+        frame.resize(packet_len, 0.0);
+        let granule = self.encoded + real;
+        self.encode(&frame, PacketWriteEndInfo::EndStream, granule)?;
+        self.packets
+            .into_inner()
+            .flush()
+            .map_err(|e| format!("Recording close error: {e}"))
+    }
+}
+
+// ── Byte-counting file ────────────────────────────────────────────────────────
+
+struct CountingFile {
+    inner: BufWriter<File>,
+    bytes: Arc<AtomicU64>,
+}
+
+impl Write for CountingFile {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.bytes.fetch_add(n as u64, Ordering::Relaxed);
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+impl Seek for CountingFile {
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        self.inner.seek(pos)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn writes_a_readable_mono_wav() {
+        let path =
+            std::env::temp_dir().join(format!("desizone-recording-{}.wav", std::process::id()));
+        let mut writer = RecordingWriter::create(&path, &Codec::Wav, 44_100, 1, 128).unwrap();
+        let stereo: Vec<f32> = (0..2000)
+            .map(|i| if i % 2 == 0 { 0.5 } else { 0.0 })
+            .collect();
+        writer.write(&stereo).unwrap();
+        writer.finish().unwrap();
+
+        let mut reader = hound::WavReader::open(&path).unwrap();
+        assert_eq!(reader.spec().channels, 1);
+        let samples: Vec<i16> = reader.samples::<i16>().map(Result::unwrap).collect();
+        assert_eq!(samples.len(), 1000);
+        assert!(samples.iter().all(|&s| s == to_i16(0.25)));
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -7,25 +7,15 @@
 use std::time::Duration;
 
 use ringbuf::traits::Consumer as _;
-use serde::Deserialize;
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::net::TcpStream;
 use tokio::sync::oneshot;
 
 use super::encoder_manager::{EncoderConfig, EncoderManager, ShoutcastVersion};
 use super::mp3::Mp3Encoder;
+use super::watermark::Watermarker;
 
-#[derive(Debug, Deserialize)]
-struct ShoutcastV2StatsResponse {
-    streams: Option<Vec<ShoutcastV2Stream>>,
-}
-
-#[derive(Debug, Deserialize)]
-struct ShoutcastV2Stream {
-    id: Option<u32>,
-}
-
-fn source_ports(port: u16, with_legacy_fallback: bool) -> Vec<u16> {
+pub(super) fn source_ports(port: u16, with_legacy_fallback: bool) -> Vec<u16> {
     if with_legacy_fallback {
         let next = port.saturating_add(1);
         if next != port {
@@ -35,7 +25,7 @@ fn source_ports(port: u16, with_legacy_fallback: bool) -> Vec<u16> {
     vec![port]
 }
 
-fn source_password_for_v2(config: &EncoderConfig, sid: u32, user: &str) -> String {
+pub(super) fn source_password_for_v2(config: &EncoderConfig, sid: u32, user: &str) -> String {
     let password = config.server_password.as_deref().unwrap_or("");
     if user.trim().is_empty() {
         format!("{password}:#{sid}")
@@ -44,7 +34,7 @@ fn source_password_for_v2(config: &EncoderConfig, sid: u32, user: &str) -> Strin
     }
 }
 
-async fn connect_legacy_source(
+pub(super) async fn connect_legacy_source(
     host: &str,
     port: u16,
     password_line: &str,
@@ -145,6 +135,7 @@ async fn stream_legacy_source_async(
     );
 
     let mut mp3 = Mp3Encoder::from_config(config)?;
+    let mut watermark = Watermarker::for_encoder(config);
     let mut pcm_buf = vec![0.0f32; mp3.frame_samples()];
     let silence = vec![0.0f32; mp3.frame_samples()];
     let channels = u64::from(config.channels.clamp(1, 2));
@@ -196,6 +187,9 @@ async fn stream_legacy_source_async(
         }
         empty_since = None;
 
+        if let Some(wm) = watermark.as_mut() {
+            wm.process(&mut pcm_buf[..filled]);
+        }
         let encoded = mp3.encode_f32_interleaved(&pcm_buf[..filled])?;
         if encoded.is_empty() {
             tokio::task::yield_now().await;
@@ -309,95 +303,3 @@ pub async fn stream_loop_async(
         }
     }
 }
-
-/// Protocol-aware connection test for SHOUTcast.
-pub async fn test_shoutcast_connection(config: &EncoderConfig) -> Result<(), String> {
-    let host = config.server_host.as_deref().unwrap_or("localhost");
-    let port = config.server_port.unwrap_or(8000);
-    let password = config.server_password.as_deref().unwrap_or("");
-    let user = config
-        .server_username
-        .as_deref()
-        .filter(|s| !s.trim().is_empty())
-        .unwrap_or("source");
-
-    match config.shoutcast_version {
-        ShoutcastVersion::V1 => {
-            log::info!("SHOUTcast v1 test: host={} port={}", host, port);
-            tokio::net::TcpStream::connect(format!("{host}:{port}"))
-                .await
-                .map(|_| ())
-                .map_err(|e| format!("SHOUTcast v1 TCP connect failed: {e}"))
-        }
-        ShoutcastVersion::V2 => {
-            let sid = config.shoutcast_sid.max(1);
-            let stats_url =
-                format!("http://{host}:{port}/statistics?json=1&sid={sid}&pass={password}");
-
-            log::info!(
-                "SHOUTcast v2 test: host={} port={} sid={} user={} mode=legacy_compat",
-                host,
-                port,
-                sid,
-                user
-            );
-
-            let stats_resp = reqwest::Client::new()
-                .get(&stats_url)
-                .timeout(Duration::from_secs(5))
-                .send()
-                .await
-                .map_err(|e| format!("SHOUTcast v2 stats request failed: {e}"))?;
-
-            let status = stats_resp.status();
-            let body = stats_resp.text().await.unwrap_or_default();
-            let snippet: String = body.chars().take(240).collect();
-            log::info!(
-                "SHOUTcast v2 test stats status={} body_snippet={}",
-                status,
-                snippet
-            );
-            if !status.is_success() {
-                return Err(format!(
-                    "SHOUTcast v2 stats returned HTTP {} body: {}",
-                    status, snippet
-                ));
-            }
-
-            let parsed: ShoutcastV2StatsResponse = serde_json::from_str(&body).map_err(|e| {
-                format!("SHOUTcast v2 stats parse failed: {} (body: {})", e, snippet)
-            })?;
-            let sid_found = parsed
-                .streams
-                .unwrap_or_default()
-                .iter()
-                .any(|s| s.id == Some(sid));
-            if !sid_found {
-                return Err(format!(
-                    "SHOUTcast v2 SID {} not found in server streams (body: {})",
-                    sid, snippet
-                ));
-            }
-
-            let password_line = source_password_for_v2(config, sid, user);
-            let mut last_err = String::new();
-            for p in source_ports(port, true) {
-                match connect_legacy_source(host, p, &password_line, "SHOUTcast v2 test").await {
-                    Ok(_) => {
-                        log::info!("SHOUTcast v2 test source login accepted on {}:{}", host, p);
-                        return Ok(());
-                    }
-                    Err(e) => {
-                        last_err = e;
-                    }
-                }
-            }
-
-            Err(if last_err.is_empty() {
-                "SHOUTcast v2 source login failed on all candidate ports".to_string()
-            } else {
-                last_err
-            })
-        }
-    }
-}
@@ -0,0 +1,742 @@
+/// `show_export.rs` — turn a finished recording into a podcast-ready file
+///
+/// Reads the 16-bit WAV written by `encoder_file`, trims it to the show
+/// boundaries and makes two passes over the trimmed range: the first measures
+/// gated loudness and sample peak, the second applies the normalising gain and
+/// encodes. Gain is capped so the peak stays below `PEAK_CEILING_DB`.
+///
+/// - **MP3** is encoded natively (shine) behind an ID3v2.3 tag carrying the
+///   show tags plus `CTOC`/`CHAP` chapter frames.
+/// - **AAC** (`.m4a`) is handed to FFmpeg with an FFMETADATA file for tags
+///   and chapters.
+///
+/// The show has to be in one WAV file. FLAC, MP3 and Opus recordings, and
+/// recordings split by track or duration, cannot be exported; the config is
+/// only accepted while a file encoder records in a form this can read.
+use std::{
+    fs::File,
+    io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use serde::{Deserialize, Serialize};
+use shine_rs::SUPPORTED_SAMPLE_RATES;
+
+use super::{
+    encoder_manager::{Codec, EncoderConfig, FileRotation, OutputType},
+    export_upload::UploadTarget,
+    mp3::Mp3Encoder,
+};
+use crate::audio::analyzer::loudness::{gated_loudness_db, BLOCK_MS, BLOCK_STEP_MS};
+
+/// Highest sample peak normalisation may push the show to.
+const PEAK_CEILING_DB: f64 = -1.0;
+/// Frames per read/encode chunk.
+const CHUNK_FRAMES: usize = 8192;
+/// `CTOC` stores the entry count in one byte.
+const MAX_CHAPTERS: usize = 255;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    #[default]
+    Mp3,
+    Aac,
+}
+
+impl ExportFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Mp3 => "mp3",
+            ExportFormat::Aac => "m4a",
+        }
+    }
+}
+
+/// Persisted defaults for `export_show`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ShowExportConfig {
+    /// Where exports go (default: an `exports` folder next to the recording)
+    pub output_dir: Option<String>,
+    pub format: ExportFormat,
+    pub bitrate_kbps: u32,
+    /// Normalisation target, dBFS gated loudness
+    pub target_loudness_db: f64,
+    pub upload: Option<UploadTarget>,
+}
+
+impl Default for ShowExportConfig {
+    fn default() -> Self {
+        Self {
+            output_dir: None,
+            format: ExportFormat::Mp3,
+            bitrate_kbps: 128,
+            target_loudness_db: -16.0,
+            upload: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ShowExportRequest {
+    /// WAV written by a file encoder that does not split by track or duration
+    pub recording_path: String,
+    /// Unix ms the recording began (default: file mtime minus its length)
+    pub recording_started_at: Option<i64>,
+    /// Show boundaries, unix ms (default: the whole recording)
+    pub start_at: Option<i64>,
+    pub end_at: Option<i64>,
+    pub title: String,
+    pub artist: Option<String>,
+    pub album: Option<String>,
+    pub comment: Option<String>,
+    /// Overrides the configured format
+    pub format: Option<ExportFormat>,
+    /// Default true
+    pub normalize: Option<bool>,
+    /// Upload to the configured target afterwards
+    #[serde(default)]
+    pub upload: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Chapter {
+    /// Relative to the start of the export
+    pub start_ms: u64,
+    pub end_ms: u64,
+    pub title: String,
+}
+
+/// A song that aired, from the play log (unix ms).
+#[derive(Debug, Clone)]
+pub struct PlayedSpan {
+    pub started_at: i64,
+    pub ended_at: i64,
+    pub artist: Option<String>,
+    pub title: Option<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ShowTags {
+    pub title: String,
+    pub artist: Option<String>,
+    pub album: Option<String>,
+    pub year: Option<String>,
+    pub comment: Option<String>,
+}
+
+/// Everything `run` needs, resolved by the command layer.
+#[derive(Debug, Clone)]
+pub struct ExportPlan {
+    pub source: PathBuf,
+    pub output: PathBuf,
+    pub format: ExportFormat,
+    pub bitrate_kbps: u32,
+    /// Trim range, ms into the recording
+    pub start_ms: u64,
+    pub end_ms: u64,
+    /// `None` = export at the recorded level
+    pub target_loudness_db: Option<f64>,
+    pub tags: ShowTags,
+    pub chapters: Vec<Chapter>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ShowExportResult {
+    pub output_path: String,
+    pub duration_ms: u64,
+    /// Measured before gain
+    pub loudness_db: f64,
+    pub peak_db: f64,
+    pub gain_db: f64,
+    pub chapters: Vec<Chapter>,
+    pub uploaded_to: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct WavInfo {
+    pub sample_rate: u32,
+    pub channels: u16,
+    pub duration_ms: u64,
+}
+
+/// Whether `encoder` writes recordings this can export: one WAV per file,
+/// not cut at every track or every few minutes.
+pub fn exportable_recorder(encoder: &EncoderConfig) -> bool {
+    matches!(encoder.output_type, OutputType::File)
+        && matches!(encoder.codec, Codec::Wav)
+        && !matches!(
+            encoder.file_rotation,
+            FileRotation::ByTrack | FileRotation::ByDuration
+        )
+}
+
+/// Format and length of a recording (blocking).
+pub fn probe(path: &Path) -> Result<WavInfo, String> {
+    WavSource::open(path).map(|wav| wav.info())
+}
+
+/// Build chapters from the songs that aired between `show_start` and
+/// `show_end` (unix ms). Spans are clipped to the show, and each chapter ends
+/// no later than the next one starts.
+pub fn chapters_from_plays(plays: &[PlayedSpan], show_start: i64, show_end: i64) -> Vec<Chapter> {
+    let mut spans: Vec<(i64, i64, String)> = plays
+        .iter()
+        .filter_map(|play| {
+            let start = play.started_at.max(show_start);
+            let end = play.ended_at.min(show_end);
+            // Sub-second slivers are crossfade overlaps, not songs
+            (end - start >= 1_000).then(|| (start, end, chapter_title(play)))
+        })
+        .collect();
+    spans.sort_by_key(|(start, _, _)| *start);
+
+    let mut chapters: Vec<Chapter> = Vec::with_capacity(spans.len());
+    for (i, (start, end, title)) in spans.iter().enumerate() {
+        let end = spans.get(i + 1).map_or(*end, |next| (*end).min(next.0));
+        if end <= *start {
+            continue;
+        }
+        chapters.push(Chapter {
+            start_ms: (start - show_start) as u64,
+            end_ms: (end - show_start) as u64,
+            title: title.clone(),
+        });
+    }
+    chapters.truncate(MAX_CHAPTERS);
+    chapters
+}
+
+fn chapter_title(play: &PlayedSpan) -> String {
+    match (play.artist.as_deref(), play.title.as_deref()) {
+        (Some(artist), Some(title)) if !artist.is_empty() => format!("{artist} - {title}"),
+        (_, Some(title)) => title.to_string(),
+        (Some(artist), None) => artist.to_string(),
+        (None, None) => "Untitled".to_string(),
+    }
+}
+
+/// `{slug}-{YYYYMMDD}.{ext}` inside `dir`.
+pub fn output_path(dir: &Path, title: &str, started_at_ms: i64, format: ExportFormat) -> PathBuf {
+    use chrono::TimeZone;
+
+    let date = chrono::Local
+        .timestamp_millis_opt(started_at_ms)
+        .single()
+        .map(|t| t.format("%Y%m%d").to_string())
+        .unwrap_or_default();
+    let slug = super::encoder_file::slugify(title);
+    let stem = match (slug.is_empty(), date.is_empty()) {
+        (true, _) => format!("show-{date}"),
+        (false, true) => slug,
+        (false, false) => format!("{slug}-{date}"),
+    };
+    dir.join(format!(
+        "{}.{}",
+        stem.trim_end_matches('-'),
+        format.extension()
+    ))
+}
+
+/// Trim, normalise and encode (blocking).
+pub fn run(plan: &ExportPlan) -> Result<ShowExportResult, String> {
+    let mut wav = WavSource::open(&plan.source)?;
+    let info = wav.info();
+    let end_ms = plan.end_ms.min(info.duration_ms);
+    if end_ms <= plan.start_ms {
+        return Err("Show range is empty or outside the recording".to_string());
+    }
+    let start_frame = ms_to_frames(plan.start_ms, info.sample_rate);
+    let end_frame = ms_to_frames(end_ms, info.sample_rate);
+
+    let (loudness_db, peak_db) = measure_range(&mut wav, start_frame, end_frame)?;
+    let gain_db = plan
+        .target_loudness_db
+        .map(|target| (target - loudness_db).min(PEAK_CEILING_DB - peak_db))
+        .unwrap_or(0.0);
+
+    if let Some(parent) = plan.output.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| format!("Cannot create export dir: {e}"))?;
+    }
+    let duration_ms = end_ms - plan.start_ms;
+    let chapters: Vec<Chapter> = plan
+        .chapters
+        .iter()
+        .filter(|c| c.start_ms < duration_ms)
+        .map(|c| Chapter {
+            end_ms: c.end_ms.min(duration_ms),
+            ..c.clone()
+        })
+        .collect();
+
+    match plan.format {
+        ExportFormat::Mp3 => {
+            let gain = 10f64.powf(gain_db / 20.0) as f32;
+            encode_mp3(plan, &mut wav, start_frame, end_frame, gain, &chapters)?
+        }
+        ExportFormat::Aac => encode_aac(plan, duration_ms, gain_db, &chapters)?,
+    }
+
+    Ok(ShowExportResult {
+        output_path: plan.output.to_string_lossy().to_string(),
+        duration_ms,
+        loudness_db,
+        peak_db,
+        gain_db,
+        chapters,
+        uploaded_to: None,
+    })
+}
+
+fn ms_to_frames(ms: u64, sample_rate: u32) -> u64 {
+    ms * u64::from(sample_rate) / 1000
+}
+
+/// Gated loudness and sample peak (dBFS) of the mono mix of a frame range.
+fn measure_range(wav: &mut WavSource, start: u64, end: u64) -> Result<(f64, f64), String> {
+    let channels = wav.channels as usize;
+    let step = (wav.sample_rate as usize * BLOCK_STEP_MS as usize / 1000).max(1);
+    let steps_per_block = (BLOCK_MS / BLOCK_STEP_MS) as usize;
+
+    // Sum of squares per 100 ms step; 400 ms blocks are four consecutive steps.
+    let mut step_sums: Vec<f64> = Vec::new();
+    let mut acc = 0.0_f64;
+    let mut acc_frames = 0usize;
+    let mut peak = 0.0_f32;
+    let mut buf = Vec::new();
+
+    wav.seek_frame(start)?;
+    let mut remaining = end - start;
+    while remaining > 0 {
+        let frames = wav.read(remaining.min(CHUNK_FRAMES as u64) as usize, &mut buf)?;
+        if frames == 0 {
+            break;
+        }
+        remaining -= frames as u64;
+        for frame in buf.chunks_exact(channels) {
+            let mono = frame.iter().sum::<f32>() / channels as f32;
+            peak = frame.iter().fold(peak, |p, s| p.max(s.abs()));
+            acc += f64::from(mono) * f64::from(mono);
+            acc_frames += 1;
+            if acc_frames == step {
+                step_sums.push(acc);
+                acc = 0.0;
+                acc_frames = 0;
+            }
+        }
+    }
+
+    let block_frames = (step * steps_per_block) as f64;
+    let blocks: Vec<f64> = step_sums
+        .windows(steps_per_block)
+        .map(|w| w.iter().sum::<f64>() / block_frames)
+        .collect();
+    let peak_db = 20.0 * f64::from(peak).max(1e-6).log10();
+    Ok((gated_loudness_db(blocks), peak_db))
+}
+
+fn encode_mp3(
+    plan: &ExportPlan,
+    wav: &mut WavSource,
+    start: u64,
+    end: u64,
+    gain: f32,
+    chapters: &[Chapter],
+) -> Result<(), String> {
+    if !SUPPORTED_SAMPLE_RATES.contains(&wav.sample_rate) {
+        return Err(format!(
+            "MP3 export cannot encode a {} Hz recording",
+            wav.sample_rate
+        ));
+    }
+    if wav.channels > 2 {
+        return Err("MP3 export supports mono or stereo recordings".to_string());
+    }
+    let mut encoder = Mp3Encoder::new(wav.sample_rate, wav.channels as u8, plan.bitrate_kbps)?;
+    let file = File::create(&plan.output)
+        .map_err(|e| format!("Cannot create {}: {e}", plan.output.display()))?;
+    let mut out = BufWriter::new(file);
+    let write_err = |e: std::io::Error| format!("Export write failed: {e}");
+    out.write_all(&id3_tag(&plan.tags, chapters))
+        .map_err(write_err)?;
+
+    let mut buf = Vec::new();
+    wav.seek_frame(start)?;
+    let mut remaining = end - start;
+    while remaining > 0 {
+        let frames = wav.read(remaining.min(CHUNK_FRAMES as u64) as usize, &mut buf)?;
+        if frames == 0 {
+            break;
+        }
+        remaining -= frames as u64;
+        for s in buf.iter_mut() {
+            *s *= gain;
+        }
+        out.write_all(encoder.encode_f32_interleaved(&buf)?)
+            .map_err(write_err)?;
+    }
+    out.write_all(encoder.flush()?).map_err(write_err)?;
+    out.flush().map_err(write_err)
+}
+
+fn encode_aac(
+    plan: &ExportPlan,
+    duration_ms: u64,
+    gain_db: f64,
+    chapters: &[Chapter],
+) -> Result<(), String> {
+    let meta_path = plan.output.with_extension("ffmeta.txt");
+    std::fs::write(&meta_path, ffmetadata(&plan.tags, chapters))
+        .map_err(|e| format!("Cannot write chapter metadata: {e}"))?;
+
+    let output = Command::new("ffmpeg")
+        .args(["-hide_banner", "-loglevel", "error", "-y"])
+        .args(["-ss", &format!("{:.3}", plan.start_ms as f64 / 1000.0)])
+        .args(["-t", &format!("{:.3}", duration_ms as f64 / 1000.0)])
+        .arg("-i")
+        .arg(&plan.source)
+        .arg("-i")
+        .arg(&meta_path)
+        .args(["-map", "0:a", "-map_metadata", "1", "-map_chapters", "1"])
+        .args(["-af", &format!("volume={gain_db:.2}dB")])
+        .args(["-c:a", "aac", "-b:a", &format!("{}k", plan.bitrate_kbps)])
+        .args(["-movflags", "+faststart"])
+        .arg(&plan.output)
+        .output();
+    let _ = std::fs::remove_file(&meta_path);
+
+    let output = output.map_err(|e| format!("Cannot run ffmpeg (is it installed?): {e}"))?;
+    if output.status.success() {
+        return Ok(());
+    }
+    Err(format!(
+        "ffmpeg failed: {}",
+        String::from_utf8_lossy(&output.stderr).trim()
+    ))
+}
+
+// ── Tags ─────────────────────────────────────────────────────────────────────
+
+/// ID3v2.3 tag with text frames and, when there are chapters, a top-level
+/// ordered `CTOC` plus one `CHAP` per chapter.
+fn id3_tag(tags: &ShowTags, chapters: &[Chapter]) -> Vec<u8> {
+    let mut frames = Vec::new();
+    push_frame(&mut frames, b"TIT2", &text_body(&tags.title));
+    for (id, value) in [
+        (b"TPE1", &tags.artist),
+        (b"TALB", &tags.album),
+        (b"TYER", &tags.year),
+    ] {
+        if let Some(value) = value.as_deref().filter(|v| !v.is_empty()) {
+            push_frame(&mut frames, id, &text_body(value));
+        }
+    }
+    if let Some(comment) = tags.comment.as_deref().filter(|c| !c.is_empty()) {
+        let mut body = vec![0x01];
+        body.extend_from_slice(b"eng");
+        body.extend(utf16_with_bom(""));
+        body.extend_from_slice(&[0, 0]);
+        body.extend(utf16_with_bom(comment));
+        push_frame(&mut frames, b"COMM", &body);
+    }
+
+    if !chapters.is_empty() {
+        let mut toc = b"toc\0".to_vec();
+        toc.push(0x03); // top-level | ordered
+        toc.push(chapters.len() as u8);
+        for i in 0..chapters.len() {
+            toc.extend(format!("chp{i}\0").bytes());
+        }
+        push_frame(&mut frames, b"CTOC", &toc);
+
+        for (i, chapter) in chapters.iter().enumerate() {
+            let mut chap = format!("chp{i}\0").into_bytes();
+            chap.extend((chapter.start_ms.min(u32::MAX as u64) as u32).to_be_bytes());
+            chap.extend((chapter.end_ms.min(u32::MAX as u64) as u32).to_be_bytes());
+            // Byte offsets unknown; players use the times
+            chap.extend(u32::MAX.to_be_bytes());
+            chap.extend(u32::MAX.to_be_bytes());
+            push_frame(&mut chap, b"TIT2", &text_body(&chapter.title));
+            push_frame(&mut frames, b"CHAP", &chap);
+        }
+    }
+
+    let mut tag = b"ID3\x03\x00\x00".to_vec();
+    tag.extend(syncsafe(frames.len() as u32));
+    tag.extend(frames);
+    tag
+}
+
+fn push_frame(out: &mut Vec<u8>, id: &[u8; 4], body: &[u8]) {
+    out.extend_from_slice(id);
+    out.extend((body.len() as u32).to_be_bytes());
+    out.extend_from_slice(&[0, 0]);
+    out.extend_from_slice(body);
+}
+
+/// Text frame body: UTF-16 with BOM.
+fn text_body(text: &str) -> Vec<u8> {
+    let mut body = vec![0x01];
+    body.extend(utf16_with_bom(text));
+    body
+}
+
+fn utf16_with_bom(text: &str) -> Vec<u8> {
+    let mut out = vec![0xFF, 0xFE];
+    out.extend(text.encode_utf16().flat_map(u16::to_le_bytes));
+    out
+}
+
+fn syncsafe(size: u32) -> [u8; 4] {
+    [
+        ((size >> 21) & 0x7F) as u8,
+        ((size >> 14) & 0x7F) as u8,
+        ((size >> 7) & 0x7F) as u8,
+        (size & 0x7F) as u8,
+    ]
+}
+
+fn ffmetadata(tags: &ShowTags, chapters: &[Chapter]) -> String {
+    let mut out = String::from(";FFMETADATA1\n");
+    out.push_str(&format!("title={}\n", ffmeta_escape(&tags.title)));
+    for (key, value) in [
+        ("artist", &tags.artist),
+        ("album", &tags.album),
+        ("date", &tags.year),
+        ("comment", &tags.comment),
+    ] {
+        if let Some(value) = value.as_deref().filter(|v| !v.is_empty()) {
+            out.push_str(&format!("{key}={}\n", ffmeta_escape(value)));
+        }
+    }
+    for chapter in chapters {
+        out.push_str(&format!(
+            "[CHAPTER]\nTIMEBASE=1/1000\nSTART={}\nEND={}\ntitle={}\n",
+            chapter.start_ms,
+            chapter.end_ms,
+            ffmeta_escape(&chapter.title)
+        ));
+    }
+    out
+}
+
+fn ffmeta_escape(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for c in value.chars() {
+        if matches!(c, '=' | ';' | '#' | '\\' | '\n') {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+// ── WAV reader ───────────────────────────────────────────────────────────────
+
+/// Streaming reader for 16-bit PCM WAV. A recording that was never closed
+/// cleanly keeps `encoder_file`'s placeholder sizes, so the data chunk is
+/// taken to run to the end of the file whenever its size can't be trusted.
+struct WavSource {
+    reader: BufReader<File>,
+    data_offset: u64,
+    frames: u64,
+    sample_rate: u32,
+    channels: u16,
+    bytes: Vec<u8>,
+}
+
+impl WavSource {
+    fn open(path: &Path) -> Result<Self, String> {
+        let mut file =
+            File::open(path).map_err(|e| format!("Cannot open {}: {e}", path.display()))?;
+        let file_len = file.metadata().map_err(|e| e.to_string())?.len();
+        let mut header = [0u8; 12];
+        file.read_exact(&mut header)
+            .map_err(|_| "Recording is too short to be a WAV file".to_string())?;
+        if &header[0..4] != b"RIFF" || &header[8..12] != b"WAVE" {
+            return Err(
+                "Recording is not a WAV file; FLAC, MP3 and Opus recordings cannot be exported"
+                    .to_string(),
+            );
+        }
+
+        let mut fmt: Option<(u16, u16, u32, u16)> = None;
+        let mut pos = 12u64;
+        loop {
+            let mut chunk = [0u8; 8];
+            if file.read_exact(&mut chunk).is_err() {
+                return Err("WAV has no data chunk".to_string());
+            }
+            let size = u32::from_le_bytes([chunk[4], chunk[5], chunk[6], chunk[7]]);
+            pos += 8;
+            match &chunk[0..4] {
+                b"fmt " => {
+                    let mut body = [0u8; 16];
+                    file.read_exact(&mut body)
+                        .map_err(|_| "WAV fmt chunk is truncated".to_string())?;
+                    fmt = Some((
+                        u16::from_le_bytes([body[0], body[1]]),
+                        u16::from_le_bytes([body[2], body[3]]),
+                        u32::from_le_bytes([body[4], body[5], body[6], body[7]]),
+                        u16::from_le_bytes([body[14], body[15]]),
+                    ));
+                }
+                b"data" => {
+                    let (format, channels, sample_rate, bits) =
+                        fmt.ok_or("WAV data chunk comes before its format")?;
+                    if format != 1 || bits != 16 || channels == 0 || sample_rate == 0 {
+                        return Err("Only 16-bit PCM recordings can be exported".to_string());
+                    }
+                    let available = file_len.saturating_sub(pos);
+                    let len = if size == u32::MAX {
+                        available
+                    } else {
+                        u64::from(size).min(available)
+                    };
+                    return Ok(Self {
+                        reader: BufReader::with_capacity(1 << 16, file),
+                        data_offset: pos,
+                        frames: len / (u64::from(channels) * 2),
+                        sample_rate,
+                        channels,
+                        bytes: Vec::new(),
+                    });
+                }
+                _ => {}
+            }
+            pos += u64::from(size) + u64::from(size & 1);
+            file.seek(SeekFrom::Start(pos))
+                .map_err(|e| format!("WAV seek failed: {e}"))?;
+        }
+    }
+
+    fn info(&self) -> WavInfo {
+        WavInfo {
+            sample_rate: self.sample_rate,
+            channels: self.channels,
+            duration_ms: self.frames * 1000 / u64::from(self.sample_rate),
+        }
+    }
+
+    fn seek_frame(&mut self, frame: u64) -> Result<(), String> {
+        let offset = self.data_offset + frame.min(self.frames) * u64::from(self.channels) * 2;
+        self.reader
+            .seek(SeekFrom::Start(offset))
+            .map(|_| ())
+            .map_err(|e| format!("WAV seek failed: {e}"))
+    }
+
+    /// Read up to `max_frames` interleaved frames into `out`; returns frames read.
+    fn read(&mut self, max_frames: usize, out: &mut Vec<f32>) -> Result<usize, String> {
+        let frame_bytes = self.channels as usize * 2;
+        self.bytes.resize(max_frames * frame_bytes, 0);
+        let mut filled = 0;
+        while filled < self.bytes.len() {
+            match self.reader.read(&mut self.bytes[filled..]) {
+                Ok(0) => break,
+                Ok(n) => filled += n,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(format!("WAV read failed: {e}")),
+            }
+        }
+        let frames = filled / frame_bytes;
+        out.clear();
+        out.extend(
+            self.bytes[..frames * frame_bytes]
+                .chunks_exact(2)
+                .map(|b| f32::from(i16::from_le_bytes([b[0], b[1]])) / i16::MAX as f32),
+        );
+        Ok(frames)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn play(started_at: i64, ended_at: i64, title: &str) -> PlayedSpan {
+        PlayedSpan {
+            started_at,
+            ended_at,
+            artist: Some("Artist".to_string()),
+            title: Some(title.to_string()),
+        }
+    }
+
+    #[test]
+    fn chapters_clip_to_show_and_end_at_next_song() {
+        let plays = [
+            play(5_000, 70_000, "Before"),
+            play(65_000, 200_000, "Overlap"),
+            play(200_000, 200_500, "Sliver"),
+            play(190_000, 400_000, "After"),
+        ];
+        let chapters = chapters_from_plays(&plays, 60_000, 300_000);
+        let spans: Vec<_> = chapters
+            .iter()
+            .map(|c| (c.start_ms, c.end_ms, c.title.as_str()))
+            .collect();
+        assert_eq!(
+            spans,
+            vec![
+                (0, 5_000, "Artist - Before"),
+                (5_000, 130_000, "Artist - Overlap"),
+                (130_000, 240_000, "Artist - After"),
+            ]
+        );
+    }
+
+    #[test]
+    fn id3_tag_sizes_and_chapter_frames() {
+        let tags = ShowTags {
+            title: "Show".to_string(),
+            ..Default::default()
+        };
+        let chapters = [Chapter {
+            start_ms: 0,
+            end_ms: 1_000,
+            title: "A".to_string(),
+        }];
+        let tag = id3_tag(&tags, &chapters);
+        assert_eq!(&tag[..6], b"ID3\x03\x00\x00");
+        let size = tag[6..10]
+            .iter()
+            .fold(0usize, |acc, b| (acc << 7) | *b as usize);
+        assert_eq!(size, tag.len() - 10);
+        let toc = tag.windows(4).position(|w| w == b"CTOC").unwrap();
+        assert_eq!(&tag[toc + 10..toc + 16], b"toc\0\x03\x01");
+        let chap = tag.windows(4).position(|w| w == b"CHAP").unwrap();
+        assert_eq!(&tag[chap + 10..chap + 15], b"chp0\0");
+        assert_eq!(&tag[chap + 19..chap + 23], &1_000u32.to_be_bytes());
+    }
+
+    #[test]
+    fn wav_reader_tolerates_placeholder_sizes() {
+        let path = std::env::temp_dir().join(format!("show-export-{}.wav", std::process::id()));
+        let mut bytes = b"RIFF\xFF\xFF\xFF\xFFWAVEfmt ".to_vec();
+        bytes.extend(16u32.to_le_bytes());
+        bytes.extend(1u16.to_le_bytes()); // PCM
+        bytes.extend(2u16.to_le_bytes());
+        bytes.extend(1000u32.to_le_bytes());
+        bytes.extend(4000u32.to_le_bytes());
+        bytes.extend(4u16.to_le_bytes());
+        bytes.extend(16u16.to_le_bytes());
+        bytes.extend(b"data\xFF\xFF\xFF\xFF");
+        for i in 0..500i16 {
+            bytes.extend(i.to_le_bytes());
+            bytes.extend((-i).to_le_bytes());
+        }
+        std::fs::write(&path, &bytes).unwrap();
+
+        let mut wav = WavSource::open(&path).unwrap();
+        assert_eq!(wav.info().duration_ms, 500);
+        wav.seek_frame(100).unwrap();
+        let mut buf = Vec::new();
+        assert_eq!(wav.read(1_000, &mut buf).unwrap(), 400);
+        assert_eq!(buf[0], 100.0 / i16::MAX as f32);
+        assert_eq!(buf[1], -100.0 / i16::MAX as f32);
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,294 @@
+/// Station ID sign-on gate
+///
+/// Optional compliance check run before a network encoder goes live: a station
+/// ID element must have aired within the last N minutes, or one is played
+/// automatically as the stream starts. Operators may override the gate when
+/// the config allows it; every block/override/auto-play is written to the
+/// event log.
+use serde::{Deserialize, Serialize};
+use sqlx::{MySqlPool, SqlitePool};
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+use crate::scheduler::timed_events::{TimedEvent, TimedEventMode};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StationIdGateConfig {
+    pub enabled: bool,
+    /// A station ID must have aired within this many minutes
+    pub window_minutes: u32,
+    /// SAM `songtype` values that count as a station ID
+    pub song_types: Vec<String>,
+    /// Play `song_id` / `file_path` on sign-on instead of refusing to start
+    pub auto_play: bool,
+    pub song_id: Option<i64>,
+    pub file_path: Option<String>,
+    /// AutoDJ: fade-out time for the song on air when the ID is auto-played
+    pub fade_ms: u32,
+    /// Whether an operator may start encoders with the gate unsatisfied
+    pub allow_override: bool,
+}
+
+impl Default for StationIdGateConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            window_minutes: 60,
+            song_types: vec!["I".to_string()],
+            auto_play: false,
+            song_id: None,
+            file_path: None,
+            fade_ms: 1_500,
+            allow_override: true,
+        }
+    }
+}
+
+impl StationIdGateConfig {
+    /// Whether an element is configured for auto-play on sign-on.
+    pub fn can_auto_play(&self) -> bool {
+        self.auto_play
+            && (self.song_id.is_some()
+                || self
+                    .file_path
+                    .as_deref()
+                    .is_some_and(|p| !p.trim().is_empty()))
+    }
+
+    /// The sign-on element as an exact-time event so the AutoDJ runtime can
+    /// arm it like any other timed element.
+    pub fn sign_on_event(&self, mode: TimedEventMode) -> TimedEvent {
+        TimedEvent {
+            id: None,
+            name: "Station ID (sign-on)".to_string(),
+            minute: 0,
+            hours: Vec::new(),
+            days: Vec::new(),
+            song_id: self.song_id,
+            file_path: self.file_path.clone(),
+            mode,
+            fade_ms: self.fade_ms,
+            grace_minutes: 0,
+            enabled: true,
+            last_fired_at: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StationIdGateStatus {
+    pub enabled: bool,
+    pub satisfied: bool,
+    /// Seconds since a station ID last aired (None = not found / unknown)
+    pub last_played_secs_ago: Option<i64>,
+    /// SAM history was unreachable; the status comes from the last known
+    /// lookup, or the gate fails open when there is none
+    pub sam_offline: bool,
+    pub window_minutes: u32,
+    pub auto_play_available: bool,
+    pub override_allowed: bool,
+}
+
+// ── Pending sign-on ───────────────────────────────────────────────────────────
+
+static PENDING_SIGN_ON: OnceLock<Mutex<Option<StationIdGateConfig>>> = OnceLock::new();
+
+fn pending_cell() -> &'static Mutex<Option<StationIdGateConfig>> {
+    PENDING_SIGN_ON.get_or_init(|| Mutex::new(None))
+}
+
+/// Ask the automation loop to play the configured station ID.
+pub fn request_sign_on(config: StationIdGateConfig) {
+    *pending_cell().lock().unwrap() = Some(config);
+}
+
+pub fn take_sign_on() -> Option<StationIdGateConfig> {
+    pending_cell().lock().unwrap().take()
+}
+
+// ── Evaluation ────────────────────────────────────────────────────────────────
+
+/// Upper bound on the SAM history lookup so an unreachable server can't hold
+/// up encoder start.
+const LOOKUP_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Outcome of the SAM history lookup behind the gate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Lookup {
+    /// SAM answered: seconds since an ID aired (None = never)
+    Fresh(Option<i64>),
+    /// SAM unreachable: last known airing as a unix timestamp (None = never)
+    Cached(Option<i64>),
+    /// SAM unreachable and nothing cached for these song types
+    Unknown,
+}
+
+/// Last successful lookup: the song types it was for and when an ID last
+/// aired (unix seconds).
+type LastKnown = Mutex<Option<(Vec<String>, Option<i64>)>>;
+static LAST_KNOWN: OnceLock<LastKnown> = OnceLock::new();
+
+fn last_known_cell() -> &'static LastKnown {
+    LAST_KNOWN.get_or_init(|| Mutex::new(None))
+}
+
+/// Record a fresh lookup (`Some`) or fall back to the cached one (`None`).
+fn remember_or_recall(song_types: &[String], fresh: Option<Option<i64>>, now: i64) -> Lookup {
+    let mut cached = last_known_cell().lock().unwrap();
+    match fresh {
+        Some(secs_ago) => {
+            *cached = Some((song_types.to_vec(), secs_ago.map(|s| now - s)));
+            Lookup::Fresh(secs_ago)
+        }
+        None => match cached.as_ref() {
+            Some((types, aired_at)) if types == song_types => Lookup::Cached(*aired_at),
+            _ => Lookup::Unknown,
+        },
+    }
+}
+
+async fn lookup(config: &StationIdGateConfig, sam_pool: Option<&MySqlPool>, now: i64) -> Lookup {
+    let fresh = match sam_pool {
+        Some(pool) => {
+            let query = crate::db::sam::seconds_since_song_type_played(pool, &config.song_types);
+            match tokio::time::timeout(LOOKUP_TIMEOUT, query).await {
+                Ok(Ok(secs_ago)) => Some(secs_ago),
+                Ok(Err(e)) => {
+                    log::warn!("Station ID gate: history lookup failed: {e}");
+                    None
+                }
+                Err(_) => {
+                    log::warn!("Station ID gate: history lookup timed out");
+                    None
+                }
+            }
+        }
+        None => None,
+    };
+    let result = remember_or_recall(&config.song_types, fresh, now);
+    if result == Lookup::Unknown {
+        log::warn!("Station ID gate: SAM offline and no cached history; failing open");
+    }
+    result
+}
+
+pub async fn evaluate(
+    config: &StationIdGateConfig,
+    sam_pool: Option<&MySqlPool>,
+) -> StationIdGateStatus {
+    let now = chrono::Utc::now().timestamp();
+    let result = if config.enabled {
+        lookup(config, sam_pool, now).await
+    } else {
+        Lookup::Fresh(None)
+    };
+    status_for(config, result, now)
+}
+
+fn status_for(config: &StationIdGateConfig, lookup: Lookup, now: i64) -> StationIdGateStatus {
+    let window_secs = i64::from(config.window_minutes) * 60;
+    let (last_played_secs_ago, sam_offline) = match lookup {
+        Lookup::Fresh(secs_ago) => (secs_ago, false),
+        Lookup::Cached(aired_at) => (aired_at.map(|t| (now - t).max(0)), true),
+        Lookup::Unknown => (None, true),
+    };
+    let within_window = last_played_secs_ago.is_some_and(|s| s <= window_secs);
+    StationIdGateStatus {
+        enabled: config.enabled,
+        satisfied: !config.enabled || within_window || lookup == Lookup::Unknown,
+        last_played_secs_ago,
+        sam_offline,
+        window_minutes: config.window_minutes,
+        auto_play_available: config.can_auto_play(),
+        override_allowed: config.allow_override,
+    }
+}
+
+// ── DB helpers ────────────────────────────────────────────────────────────────
+
+pub async fn get_config(pool: &SqlitePool) -> Result<StationIdGateConfig, sqlx::Error> {
+    let row: Option<String> =
+        sqlx::query_scalar("SELECT config_json FROM station_id_gate_config WHERE id = 1")
+            .fetch_optional(pool)
+            .await?;
+    Ok(row
+        .and_then(|j| serde_json::from_str(&j).ok())
+        .unwrap_or_default())
+}
+
+pub async fn save_config(
+    pool: &SqlitePool,
+    config: &StationIdGateConfig,
+) -> Result<(), sqlx::Error> {
+    let json = serde_json::to_string(config).unwrap_or_else(|_| "{}".to_string());
+    sqlx::query(
+        "INSERT INTO station_id_gate_config (id, config_json, updated_at) VALUES (1, ?, strftime('%s','now')) \
+         ON CONFLICT(id) DO UPDATE SET config_json = excluded.config_json, updated_at = excluded.updated_at",
+    )
+    .bind(json)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn enabled() -> StationIdGateConfig {
+        StationIdGateConfig {
+            enabled: true,
+            window_minutes: 30,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn fresh_lookup_checks_the_window() {
+        let config = enabled();
+        assert!(status_for(&config, Lookup::Fresh(Some(600)), 0).satisfied);
+        assert!(!status_for(&config, Lookup::Fresh(Some(3_600)), 0).satisfied);
+        assert!(!status_for(&config, Lookup::Fresh(None), 0).satisfied);
+        let disabled = StationIdGateConfig::default();
+        assert!(status_for(&disabled, Lookup::Fresh(None), 0).satisfied);
+    }
+
+    #[test]
+    fn offline_uses_cached_state_or_fails_open() {
+        let config = enabled();
+        let cached = status_for(&config, Lookup::Cached(Some(1_000)), 1_600);
+        assert!(cached.satisfied && cached.sam_offline);
+        assert_eq!(cached.last_played_secs_ago, Some(600));
+        assert!(!status_for(&config, Lookup::Cached(Some(1_000)), 10_000).satisfied);
+        assert!(!status_for(&config, Lookup::Cached(None), 1_000).satisfied);
+        let unknown = status_for(&config, Lookup::Unknown, 1_000);
+        assert!(unknown.satisfied && unknown.sam_offline);
+    }
+
+    #[test]
+    fn cache_is_kept_per_song_types() {
+        let ids = vec!["I".to_string()];
+        let other = vec!["J".to_string()];
+        assert_eq!(
+            remember_or_recall(&ids, Some(Some(60)), 1_000),
+            Lookup::Fresh(Some(60))
+        );
+        assert_eq!(
+            remember_or_recall(&ids, None, 2_000),
+            Lookup::Cached(Some(940))
+        );
+        assert_eq!(remember_or_recall(&other, None, 2_000), Lookup::Unknown);
+    }
+
+    #[test]
+    fn auto_play_needs_an_element() {
+        let mut config = enabled();
+        config.auto_play = true;
+        assert!(!config.can_auto_play());
+        config.file_path = Some("  ".to_string());
+        assert!(!config.can_auto_play());
+        config.song_id = Some(7);
+        assert!(config.can_auto_play());
+    }
+}
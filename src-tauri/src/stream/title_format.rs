@@ -0,0 +1,233 @@
+/// `title_format.rs` — per-encoder stream title formatting
+///
+/// Turns the track on air into the title an encoder pushes: the encoder's
+/// caption template (`{artist}`, `{title}`, `{show}`, `{station}`, plus the
+/// older `$artist$` / `$title$` / `$combine$` tokens), then optional
+/// transliteration to Latin letters, uppercasing and a length cap.
+///
+/// Transliteration exists for players and directories that garble anything
+/// outside Latin-1. Devanagari, Gurmukhi, Gujarati and Bengali are romanised
+/// phonetically ("दिल तो पागल है" → "Dil To Pagal Hai"); other scripts,
+/// Urdu included, fall back to a character-by-character ASCII table, which
+/// for Urdu gives consonants only since the script rarely writes short vowels.
+use super::encoder_manager::EncoderConfig;
+
+/// Characters left dangling at either end when a placeholder is empty.
+const SEPARATORS: &[char] = &[' ', '-', '|', ':', '/', '•', '·', '~'];
+
+/// The title for `config`, given the track and the show on air.
+pub fn format_stream_title(
+    config: &EncoderConfig,
+    artist: &str,
+    title: &str,
+    show: Option<&str>,
+) -> String {
+    let combined = if artist.is_empty() {
+        title.to_string()
+    } else {
+        format!("{artist} - {title}")
+    };
+    let station = config.stream_name.as_deref().unwrap_or("");
+    let show = show.unwrap_or("");
+    let mut song = match config
+        .metadata_caption_template
+        .as_deref()
+        .filter(|t| !t.trim().is_empty())
+    {
+        Some(template) => template
+            .replace("$combine$", &combined)
+            .replace("$artist$", artist)
+            .replace("$title$", title)
+            .replace("{artist}", artist)
+            .replace("{title}", title)
+            .replace("{show}", show)
+            .replace("{station}", station)
+            .trim_matches(SEPARATORS)
+            .to_string(),
+        None => combined,
+    };
+    if config.metadata_transliterate {
+        song = to_latin(&song);
+    }
+    if config.metadata_uppercase {
+        song = song.to_uppercase();
+    }
+    truncate(&song, config.metadata_max_length as usize)
+}
+
+/// Cut to `max` characters (0 = no limit), at a word break when one is near.
+fn truncate(s: &str, max: usize) -> String {
+    if max == 0 || s.chars().count() <= max {
+        return s.to_string();
+    }
+    if max <= 3 {
+        return s.chars().take(max).collect();
+    }
+    let kept: String = s.chars().take(max - 3).collect();
+    let at_break = s.chars().nth(max - 3).is_some_and(char::is_whitespace);
+    let cut = match kept.rfind(' ') {
+        // Back up to the last space unless that loses more than a quarter.
+        Some(space) if !at_break && kept[..space].chars().count() * 4 >= (max - 3) * 3 => {
+            &kept[..space]
+        }
+        _ => kept.as_str(),
+    };
+    format!("{}...", cut.trim_end_matches(SEPARATORS))
+}
+
+// ── Transliteration ───────────────────────────────────────────────────────────
+
+/// Independent vowels, offsets 0x05..=0x14 of each Indic block.
+const VOWELS: [&str; 16] = [
+    "a", "a", "i", "i", "u", "u", "ri", "li", "e", "e", "e", "ai", "o", "o", "o", "au",
+];
+
+/// Consonants, offsets 0x15..=0x39.
+const CONSONANTS: [&str; 37] = [
+    "k", "kh", "g", "gh", "n", "ch", "chh", "j", "jh", "n", "t", "th", "d", "dh", "n", "t", "th",
+    "d", "dh", "n", "n", "p", "ph", "b", "bh", "m", "y", "r", "r", "l", "l", "l", "v", "sh", "sh",
+    "s", "h",
+];
+
+/// Dependent vowel signs, offsets 0x3E..=0x4C.
+const VOWEL_SIGNS: [&str; 15] = [
+    "a", "i", "i", "u", "u", "ri", "ri", "e", "e", "e", "ai", "o", "o", "o", "au",
+];
+
+/// Precomposed nukta consonants, offsets 0x58..=0x5F (क़ ख़ ग़ ज़ ड़ ढ़ फ़ य़).
+const NUKTA_CONSONANTS: [&str; 8] = ["q", "kh", "gh", "z", "r", "rh", "f", "y"];
+
+const NUKTA: u32 = 0x3C;
+const VIRAMA: u32 = 0x4D;
+
+/// Offset within its block for Devanagari, Bengali, Gurmukhi and Gujarati,
+/// which share the same layout.
+fn indic_offset(c: char) -> Option<u32> {
+    matches!(c as u32, 0x0900..=0x0AFF).then_some(c as u32 & 0x7F)
+}
+
+fn is_consonant(offset: u32) -> bool {
+    matches!(offset, 0x15..=0x39 | 0x58..=0x5F)
+}
+
+fn is_vowel_sign(offset: u32) -> bool {
+    matches!(offset, 0x3E..=0x4C | 0x62 | 0x63)
+}
+
+fn consonant(offset: u32, nukta: bool) -> &'static str {
+    if offset >= 0x58 {
+        return NUKTA_CONSONANTS[(offset - 0x58) as usize];
+    }
+    let plain = CONSONANTS[(offset - 0x15) as usize];
+    if !nukta {
+        return plain;
+    }
+    match plain {
+        "k" => "q",
+        "g" => "gh",
+        "j" => "z",
+        "d" => "r",
+        "dh" => "rh",
+        "ph" => "f",
+        other => other,
+    }
+}
+
+/// Sound of a letter that is not a consonant.
+fn indic_sound(offset: u32) -> &'static str {
+    const DIGITS: [&str; 10] = ["0", "1", "2", "3", "4", "5", "6", "7", "8", "9"];
+    match offset {
+        // Candrabindu, anusvara; Gurmukhi tippi
+        0x01 | 0x02 | 0x70 => "n",
+        0x03 => "h",
+        0x05..=0x14 => VOWELS[(offset - 0x05) as usize],
+        0x3E..=0x4C => VOWEL_SIGNS[(offset - 0x3E) as usize],
+        0x50 => "om",
+        0x60 => "ri",
+        0x61..=0x63 => "li",
+        0x64 | 0x65 => ".",
+        0x66..=0x6F => DIGITS[(offset - 0x66) as usize],
+        _ => "",
+    }
+}
+
+/// Romanise Indic scripts and fold everything else to ASCII.
+pub fn to_latin(text: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut out = String::with_capacity(text.len());
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        i += 1;
+        if c.is_ascii() {
+            out.push(c);
+            continue;
+        }
+        let Some(offset) = indic_offset(c) else {
+            out.push_str(deunicode::deunicode_char(c).unwrap_or(""));
+            continue;
+        };
+        if !is_consonant(offset) {
+            push_capitalised(&mut out, indic_sound(offset));
+            continue;
+        }
+        let nukta = chars.get(i).and_then(|&n| indic_offset(n)) == Some(NUKTA);
+        if nukta {
+            i += 1;
+        }
+        push_capitalised(&mut out, consonant(offset, nukta));
+        // The inherent vowel: silenced by a vowel sign or virama, and dropped
+        // at the end of a word as Hindi and Punjabi speakers do.
+        match chars.get(i).and_then(|&n| indic_offset(n)) {
+            Some(VIRAMA) => i += 1,
+            Some(next) if is_vowel_sign(next) => {}
+            Some(next) if is_consonant(next) || matches!(next, 0x01..=0x14) => out.push('a'),
+            _ => {}
+        }
+    }
+    out.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Append `sound`, capitalised when it starts a word.
+fn push_capitalised(out: &mut String, sound: &str) {
+    let word_start = !out.chars().next_back().is_some_and(char::is_alphanumeric);
+    let mut chars = sound.chars();
+    match chars.next() {
+        Some(first) if word_start => {
+            out.extend(first.to_uppercase());
+            out.push_str(chars.as_str());
+        }
+        _ => out.push_str(sound),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn romanises_indic_titles() {
+        assert_eq!(to_latin("दिल तो पागल है"), "Dil To Pagal Hai");
+        assert_eq!(to_latin("ਸਤਿ ਸ੍ਰੀ ਅਕਾਲ"), "Sati Sri Akal");
+        assert_eq!(to_latin("Kishore Kumar - ज़िंदगी"), "Kishore Kumar - Zindagi");
+    }
+
+    #[test]
+    fn applies_template_case_and_length() {
+        let config = EncoderConfig {
+            stream_name: Some("DesiZone".to_string()),
+            metadata_caption_template: Some("{show}: {artist} - {title}".to_string()),
+            metadata_uppercase: true,
+            metadata_max_length: 24,
+            ..EncoderConfig::default()
+        };
+        assert_eq!(
+            format_stream_title(&config, "Lata Mangeshkar", "Lag Jaa Gale", None),
+            "LATA MANGESHKAR - LAG..."
+        );
+        assert_eq!(
+            format_stream_title(&config, "Atif", "Tum Hi Ho", Some("Drive")),
+            "DRIVE: ATIF - TUM HI HO"
+        );
+    }
+}
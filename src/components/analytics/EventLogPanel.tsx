@@ -5,26 +5,36 @@ export const EventLogPanel: React.FC = () => {
   const [events, setEvents] = useState<EventLogEntry[]>([]);
   const [total, setTotal] = useState(0);
   const [page, setPage] = useState(0);
+  // `beforeId` cursor for each page visited; page 0 starts at the newest entry.
+  const [cursors, setCursors] = useState<(number | undefined)[]>([undefined]);
+  const [nextCursor, setNextCursor] = useState<number | null>(null);
   const [levelFilter, setLevelFilter] = useState<string>('');
   const [categoryFilter, setCategoryFilter] = useState<string>('');
   const [search, setSearch] = useState('');
   const pageSize = 50;
 
+  useEffect(() => {
+    setPage(0);
+    setCursors([undefined]);
+  }, [levelFilter, categoryFilter, search]);
+
   useEffect(() => {
     fetchEvents();
-  }, [page, levelFilter, categoryFilter, search]);
+  }, [page, cursors, levelFilter, categoryFilter, search]);
 
   const fetchEvents = async () => {
     try {
       const result = await getEventLog({
         limit: pageSize,
-        offset: page * pageSize,
+        offset: 0,
+        beforeId: cursors[page],
         level: levelFilter || undefined,
         category: categoryFilter || undefined,
         search: search || undefined,
       });
       setEvents(result.events);
       setTotal(result.total);
+      setNextCursor(result.next_before_id);
     } catch (err) {
       console.error('Failed to fetch events:', err);
     }
@@ -165,8 +175,12 @@ export const EventLogPanel: React.FC = () => {
             Previous
           </button>
           <button
-            onClick={() => setPage(page + 1)}
-            disabled={(page + 1) * pageSize >= total}
+            onClick={() => {
+              if (nextCursor === null) return;
+              setCursors([...cursors.slice(0, page + 1), nextCursor]);
+              setPage(page + 1);
+            }}
+            disabled={nextCursor === null}
             className="px-3 py-1 bg-gray-700 text-white rounded hover:bg-gray-600 disabled:opacity-50 disabled:cursor-not-allowed"
           >
             Next
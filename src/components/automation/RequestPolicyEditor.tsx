@@ -18,6 +18,7 @@ const defaultPolicy = (): RequestPolicy => ({
     blacklisted_categories: [],
     active_hours: null,
     auto_accept: false,
+    auto_accept_hours: null,
 });
 
 function NumberPolicyField({
@@ -243,6 +244,47 @@ export default function RequestPolicyEditor() {
                             onChange={(e) => setPolicy((p) => ({ ...p, auto_accept: e.target.checked }))}
                         />
                     </label>
+                    {policy.auto_accept && (
+                        <label className="rp-field">
+                            <span>Only auto-accept during hours</span>
+                            <input
+                                type="checkbox"
+                                checked={policy.auto_accept_hours !== null}
+                                onChange={(e) =>
+                                    setPolicy((p) => ({
+                                        ...p,
+                                        auto_accept_hours: e.target.checked ? [0, 8] : null,
+                                    }))
+                                }
+                            />
+                        </label>
+                    )}
+                    {policy.auto_accept && policy.auto_accept_hours && (
+                        <div className="rr-add-form">
+                            <NumberPolicyField
+                                label="From (hour)"
+                                value={policy.auto_accept_hours[0]}
+                                min={0} max={23}
+                                onChange={(v) =>
+                                    setPolicy((p) => ({
+                                        ...p,
+                                        auto_accept_hours: [v, (p.auto_accept_hours ?? [0, 8])[1]],
+                                    }))
+                                }
+                            />
+                            <NumberPolicyField
+                                label="To (hour)"
+                                value={policy.auto_accept_hours[1]}
+                                min={0} max={24}
+                                onChange={(v) =>
+                                    setPolicy((p) => ({
+                                        ...p,
+                                        auto_accept_hours: [(p.auto_accept_hours ?? [0, 8])[0], v],
+                                    }))
+                                }
+                            />
+                        </div>
+                    )}
                 </div>
             </div>
 
@@ -4,6 +4,8 @@ import {
     EncoderCodec,
     OutputType,
     FileRotation,
+    RecordingSidecar,
+    EncoderDiagnosis,
     saveEncoder,
     testEncoderConnection,
 } from "../../lib/bridge";
@@ -45,11 +47,19 @@ function makeDefault(): EncoderConfig {
         file_rotation: "hourly",
         file_max_size_mb: 200,
         file_name_template: "desizone_{datetime}.wav",
+        file_split_minutes: 60,
+        file_sidecar: "none",
 
         send_metadata: true,
         icy_metadata_interval: 16000,
         metadata_caption_template: "$combine$",
         metadata_url_append: null,
+        metadata_uppercase: false,
+        metadata_max_length: 0,
+        metadata_transliterate: false,
+        ad_cue_enabled: false,
+        ad_cue_out_template: "AD_BREAK_START id={break_id} duration_ms={duration_ms} spots={spots}",
+        ad_cue_in_template: "AD_BREAK_END id={break_id}",
 
         reconnect_delay_secs: 10,
         max_reconnect_attempts: 0,
@@ -123,12 +133,14 @@ function TabConnection({
     onTest,
     testState,
     testError,
+    diagnosis,
 }: {
     enc: EncoderConfig;
     set: <K extends keyof EncoderConfig>(k: K, v: EncoderConfig[K]) => void;
     onTest: () => void;
     testState: "idle" | "testing" | "ok" | "fail";
     testError: string | null;
+    diagnosis: EncoderDiagnosis | null;
 }) {
     if (enc.output_type === "file") {
         return (
@@ -162,6 +174,8 @@ function TabConnection({
                         <option value="hourly">Hourly</option>
                         <option value="daily">Daily</option>
                         <option value="by_size">By Size</option>
+                        <option value="by_track">By Track</option>
+                        <option value="by_duration">By Duration</option>
                     </select>
                 </FormField>
 
@@ -175,6 +189,30 @@ function TabConnection({
                         />
                     </FormField>
                 )}
+
+                {enc.file_rotation === "by_duration" && (
+                    <FormField label="Split Every (minutes)" half>
+                        <input
+                            className="input"
+                            type="number"
+                            min={1}
+                            value={enc.file_split_minutes ?? 60}
+                            onChange={(e) => set("file_split_minutes", Math.max(1, Number(e.target.value)))}
+                        />
+                    </FormField>
+                )}
+
+                <FormField label="Chapter Sidecar" half>
+                    <select
+                        className="input"
+                        value={enc.file_sidecar ?? "none"}
+                        onChange={(e) => set("file_sidecar", e.target.value as RecordingSidecar)}
+                    >
+                        <option value="none">None</option>
+                        <option value="cue">Cue sheet (.cue)</option>
+                        <option value="json">JSON chapters</option>
+                    </select>
+                </FormField>
             </div>
         );
     }
@@ -312,13 +350,31 @@ function TabConnection({
                     {testError}
                 </div>
             )}
+            {diagnosis && (
+                <div style={{ marginTop: 6, fontSize: 11, display: "flex", flexDirection: "column", gap: 3, maxWidth: "100%" }}>
+                    {diagnosis.checks.map((c, i) => (
+                        <div key={i} style={{ display: "flex", gap: 6 }}>
+                            <span
+                                style={{
+                                    minWidth: 90,
+                                    fontWeight: 600,
+                                    color: c.level === "error" ? "var(--red)" : c.level === "warning" ? "var(--amber)" : "var(--green)",
+                                }}
+                            >
+                                {c.level === "error" ? "✕" : c.level === "warning" ? "!" : "✓"} {c.name}
+                            </span>
+                            <span style={{ color: "var(--text-muted)" }}>{c.message}</span>
+                        </div>
+                    ))}
+                </div>
+            )}
         </div>
     );
 }
 
 function TabCodec({ enc, set }: { enc: EncoderConfig; set: <K extends keyof EncoderConfig>(k: K, v: EncoderConfig[K]) => void }) {
     const codecs: EncoderCodec[] = enc.output_type === "file"
-        ? ["wav", "flac", "mp3", "aac"]
+        ? ["wav", "flac", "mp3", "opus"]
         : ["mp3", "aac", "ogg"];
 
     return (
@@ -337,7 +393,7 @@ function TabCodec({ enc, set }: { enc: EncoderConfig; set: <K extends keyof Enco
                 </div>
             </FormField>
 
-            {enc.output_type !== "file" || ["mp3", "aac", "ogg"].includes(enc.codec) ? (
+            {enc.output_type !== "file" || ["mp3", "opus"].includes(enc.codec) ? (
                 <FormField label="Bitrate (kbps)" half>
                     <select
                         className="input"
@@ -420,10 +476,32 @@ function TabAdvanced({ enc, set }: { enc: EncoderConfig; set: <K extends keyof E
                         className="input"
                         value={enc.metadata_caption_template ?? ""}
                         onChange={(e) => set("metadata_caption_template", e.target.value || null)}
-                        placeholder="$combine$"
+                        placeholder="{show} | {artist} - {title}"
                     />
                 </FormField>
             )}
+            {enc.send_metadata && (
+                <FormField label="Max Title Length" half>
+                    <input
+                        className="input"
+                        type="number"
+                        value={enc.metadata_max_length ?? 0}
+                        onChange={(e) => set("metadata_max_length", Math.max(0, Number(e.target.value)))}
+                        min={0}
+                        title="0 = no limit"
+                    />
+                </FormField>
+            )}
+            {enc.send_metadata && (
+                <Toggle value={enc.metadata_uppercase ?? false} onChange={(v) => set("metadata_uppercase", v)} label="Uppercase titles" />
+            )}
+            {enc.send_metadata && (
+                <Toggle
+                    value={enc.metadata_transliterate ?? false}
+                    onChange={(v) => set("metadata_transliterate", v)}
+                    label="Transliterate non-Latin titles (Hindi, Punjabi, Urdu…) to Latin letters"
+                />
+            )}
             {enc.output_type === "icecast" && enc.send_metadata && (
                 <FormField label="URL Append Template">
                     <input
@@ -434,6 +512,33 @@ function TabAdvanced({ enc, set }: { enc: EncoderConfig; set: <K extends keyof E
                     />
                 </FormField>
             )}
+            {enc.output_type !== "file" && (
+                <Toggle
+                    value={enc.ad_cue_enabled ?? false}
+                    onChange={(v) => set("ad_cue_enabled", v)}
+                    label="Send ad-break cue markers (for server-side ad insertion)"
+                />
+            )}
+            {enc.output_type !== "file" && enc.ad_cue_enabled && (
+                <FormField label="Cue-Out Marker">
+                    <input
+                        className="input"
+                        value={enc.ad_cue_out_template ?? ""}
+                        onChange={(e) => set("ad_cue_out_template", e.target.value)}
+                        placeholder="AD_BREAK_START id={break_id} duration_ms={duration_ms} spots={spots}"
+                    />
+                </FormField>
+            )}
+            {enc.output_type !== "file" && enc.ad_cue_enabled && (
+                <FormField label="Cue-In Marker">
+                    <input
+                        className="input"
+                        value={enc.ad_cue_in_template ?? ""}
+                        onChange={(e) => set("ad_cue_in_template", e.target.value)}
+                        placeholder="AD_BREAK_END id={break_id}"
+                    />
+                </FormField>
+            )}
             <FormField label="Stream Description">
                 <textarea
                     className="input"
@@ -463,6 +568,7 @@ export function EncoderEditor({ initial, onClose, onSaved }: Props) {
     const [testState, setTestState] = useState<"idle" | "testing" | "ok" | "fail">("idle");
     const [error, setError] = useState<string | null>(null);
     const [testError, setTestError] = useState<string | null>(null);
+    const [diagnosis, setDiagnosis] = useState<EncoderDiagnosis | null>(null);
 
     const set = <K extends keyof EncoderConfig>(key: K, value: EncoderConfig[K]) =>
         setEncState((prev) => ({ ...prev, [key]: value }));
@@ -482,13 +588,12 @@ export function EncoderEditor({ initial, onClose, onSaved }: Props) {
 
     const handleTest = async () => {
         setTestError(null);
+        setDiagnosis(null);
         setTestState("testing");
         try {
-            const ok = await testEncoderConnection(enc.id);
-            setTestState(ok ? "ok" : "fail");
-            if (!ok) {
-                setTestError("Connection test returned failure.");
-            }
+            const result = await testEncoderConnection(enc.id);
+            setDiagnosis(result);
+            setTestState(result.ok ? "ok" : "fail");
         } catch (e: any) {
             setTestState("fail");
             setTestError(String(e));
@@ -542,6 +647,7 @@ export function EncoderEditor({ initial, onClose, onSaved }: Props) {
                             onTest={handleTest}
                             testState={testState}
                             testError={testError}
+                            diagnosis={diagnosis}
                         />
                     )}
                     {tab === "codec" && <TabCodec enc={enc} set={set} />}
@@ -158,6 +158,11 @@ export const RemoteDjManager: React.FC = () => {
                 checked={permissions.can_set_autopilot}
                 onChange={() => togglePermission('can_set_autopilot')}
               />
+              <PermissionToggle
+                label="Go Live (mic to Aux 2)"
+                checked={permissions.can_go_live}
+                onChange={() => togglePermission('can_go_live')}
+              />
               <button
                 onClick={handleUpdatePermissions}
                 className="w-full mt-4 px-4 py-2 bg-green-600 text-white rounded hover:bg-green-700"
@@ -3,6 +3,9 @@ import {
     Script,
     ScriptRunResult,
     ScriptLogEntry,
+    ScriptLimits,
+    ScriptPermissions,
+    ScriptTrigger,
     TriggerType,
     saveScript,
     runScript,
@@ -12,7 +15,8 @@ import {
 const TRIGGER_TYPES: TriggerType[] = [
     "on_track_start", "on_track_end", "on_crossfade_start",
     "on_queue_empty", "on_request_received", "on_hour",
-    "on_encoder_connect", "on_encoder_disconnect", "manual",
+    "on_encoder_connect", "on_encoder_disconnect", "on_silence",
+    "on_show_start", "hooks", "manual",
 ];
 
 const TRIGGER_LABELS: Record<TriggerType, string> = {
@@ -24,11 +28,39 @@ const TRIGGER_LABELS: Record<TriggerType, string> = {
     on_hour: "on_hour — fires at start of each hour",
     on_encoder_connect: "on_encoder_connect — fires on encoder connect",
     on_encoder_disconnect: "on_encoder_disconnect — fires on encoder disconnect",
+    on_silence: "on_silence — fires when dead air starts or clears",
+    on_show_start: "on_show_start — fires when a scheduled show goes on air",
+    hooks: "hooks — runs on every event; register on_* handlers",
     manual: "manual — only via Run button",
 };
 
+const DEFAULT_PERMISSIONS: ScriptPermissions = {
+    filesystem: false,
+    network: false,
+    engine_control: false,
+};
+
+const DEFAULT_LIMITS: ScriptLimits = {
+    max_runtime_ms: 5000,
+    memory_mb: 32,
+    instruction_budget: 50_000_000,
+};
+
+const PERMISSION_LABELS: Record<keyof ScriptPermissions, string> = {
+    filesystem: "Files",
+    network: "Network",
+    engine_control: "Engine control",
+};
+
+const LIMIT_FIELDS: { key: keyof ScriptLimits; label: string }[] = [
+    { key: "max_runtime_ms", label: "TIME (MS)" },
+    { key: "memory_mb", label: "MEMORY (MB)" },
+    { key: "instruction_budget", label: "INSTRUCTIONS" },
+];
+
 const DEFAULT_SCRIPT = `-- DesiZone Broadcaster — Lua Script
--- Available globals: log, store, deck, queue, media, encoder, schedule, station, http, event
+-- Available globals: log, store, deck, queue, media, encoder, timer, schedule, station, http,
+-- event, api (reference: api["deck.play"]) and the on_* hooks
 
 log.info("Script loaded!")
 
@@ -38,6 +70,15 @@ if event and event.title then
 end
 `;
 
+function buildTriggers(schedules: string, existing: ScriptTrigger[] = []): ScriptTrigger[] {
+    const crons: ScriptTrigger[] = schedules
+        .split(";")
+        .map((c) => c.trim())
+        .filter(Boolean)
+        .map((cron) => ({ type: "schedule", cron }));
+    return [...crons, ...existing.filter((t) => t.type === "event")];
+}
+
 interface Props {
     script: Script | null;  // null = create new
     onSaved: () => void;
@@ -51,6 +92,16 @@ export function ScriptEditor({ script, onSaved, onClose }: Props) {
     const [description] = useState(script?.description ?? "");
     const [triggerType, setTriggerType] = useState<TriggerType>(script?.trigger_type ?? "manual");
     const [enabled, setEnabled] = useState(script?.enabled ?? true);
+    // Cron schedules, separated by ";" (cron fields use commas)
+    const [schedules, setSchedules] = useState(
+        (script?.triggers ?? [])
+            .flatMap((t) => (t.type === "schedule" ? [t.cron] : []))
+            .join("; "),
+    );
+    const [permissions, setPermissions] = useState<ScriptPermissions>(
+        script?.permissions ?? DEFAULT_PERMISSIONS
+    );
+    const [limits, setLimits] = useState<ScriptLimits>(script?.limits ?? DEFAULT_LIMITS);
     const [content, setContent] = useState(script?.content ?? DEFAULT_SCRIPT);
 
     const [running, setRunning] = useState(false);
@@ -84,6 +135,9 @@ export function ScriptEditor({ script, onSaved, onClose }: Props) {
                 content,
                 enabled,
                 trigger_type: triggerType,
+                triggers: buildTriggers(schedules, script?.triggers),
+                permissions,
+                limits,
                 last_run_at: script?.last_run_at,
                 last_error: script?.last_error,
             };
@@ -108,6 +162,9 @@ export function ScriptEditor({ script, onSaved, onClose }: Props) {
                 content,
                 enabled: true,
                 trigger_type: triggerType,
+                triggers: buildTriggers(schedules, script?.triggers),
+                permissions,
+                limits,
                 last_run_at: script?.last_run_at,
                 last_error: script?.last_error,
             };
@@ -190,6 +247,16 @@ export function ScriptEditor({ script, onSaved, onClose }: Props) {
                             ))}
                         </select>
                     </div>
+                    <div style={{ flex: 1, minWidth: 140 }}>
+                        <label style={{ fontSize: 10, color: "var(--text-muted)", display: "block", marginBottom: 4 }}>SCHEDULE (CRON)</label>
+                        <input
+                            className="input"
+                            value={schedules}
+                            placeholder="e.g. 0 * * * *; @daily"
+                            onChange={(e) => setSchedules(e.target.value)}
+                            style={{ width: "100%", fontSize: 12 }}
+                        />
+                    </div>
                     <div style={{ display: "flex", alignItems: "flex-end", gap: 8, flexShrink: 0 }}>
                         <label style={{ display: "flex", alignItems: "center", gap: 6, cursor: "pointer", fontSize: 12 }}>
                             <input type="checkbox" checked={enabled} onChange={(e) => setEnabled(e.target.checked)} />
@@ -198,6 +265,43 @@ export function ScriptEditor({ script, onSaved, onClose }: Props) {
                     </div>
                 </div>
 
+                {/* Sandbox row */}
+                <div style={{
+                    display: "flex",
+                    gap: 12,
+                    padding: "8px 20px",
+                    borderBottom: "1px solid var(--border)",
+                    flexShrink: 0,
+                    flexWrap: "wrap",
+                    alignItems: "flex-end",
+                }}>
+                    {LIMIT_FIELDS.map(({ key, label }) => (
+                        <div key={key} style={{ width: 110 }}>
+                            <label style={{ fontSize: 10, color: "var(--text-muted)", display: "block", marginBottom: 4 }}>{label}</label>
+                            <input
+                                className="input"
+                                type="number"
+                                min={0}
+                                value={limits[key]}
+                                onChange={(e) => setLimits({ ...limits, [key]: Number(e.target.value) })}
+                                style={{ width: "100%", fontSize: 12 }}
+                            />
+                        </div>
+                    ))}
+                    <div style={{ display: "flex", gap: 10, flexWrap: "wrap" }}>
+                        {(Object.keys(PERMISSION_LABELS) as (keyof ScriptPermissions)[]).map((key) => (
+                            <label key={key} style={{ display: "flex", alignItems: "center", gap: 6, cursor: "pointer", fontSize: 12 }}>
+                                <input
+                                    type="checkbox"
+                                    checked={permissions[key]}
+                                    onChange={(e) => setPermissions({ ...permissions, [key]: e.target.checked })}
+                                />
+                                {PERMISSION_LABELS[key]}
+                            </label>
+                        ))}
+                    </div>
+                </div>
+
                 {/* Tabs */}
                 <div style={{
                     display: "flex",
@@ -19,6 +19,9 @@ const TRIGGER_LABELS: Record<TriggerType, string> = {
     on_hour: "Hourly",
     on_encoder_connect: "Enc. Connect",
     on_encoder_disconnect: "Enc. Disconnect",
+    on_silence: "Silence",
+    on_show_start: "Show Start",
+    hooks: "Hooks",
     manual: "Manual",
 };
 
@@ -31,6 +34,9 @@ const TRIGGER_COLORS: Record<TriggerType, string> = {
     on_hour: "var(--green)",
     on_encoder_connect: "var(--cyan)",
     on_encoder_disconnect: "var(--red)",
+    on_silence: "var(--red)",
+    on_show_start: "var(--green)",
+    hooks: "var(--purple)",
     manual: "var(--text-secondary)",
 };
 
@@ -1,9 +1,20 @@
-import { invoke } from "@tauri-apps/api/core";
+import { invoke } from "./invoke";
 import { listen, UnlistenFn } from "@tauri-apps/api/event";
 
 // ── Types ─────────────────────────────────────────────────────────────────────
 
 export type DeckId = "deck_a" | "deck_b" | "sound_fx" | "aux_1" | "aux_2" | "voice_fx";
+/** Decks with the full deck feature set; Aux 1/2 double as decks C/D. */
+export type PlaybackDeckId = "deck_a" | "deck_b" | "aux_1" | "aux_2";
+
+export type CrossfaderSide = "a" | "b" | "thru";
+
+export interface CrossfaderAssign {
+  deck_a: CrossfaderSide;
+  deck_b: CrossfaderSide;
+  deck_c: CrossfaderSide;
+  deck_d: CrossfaderSide;
+}
 
 export type FadeCurve =
   | "linear"
@@ -35,6 +46,7 @@ export interface CrossfadeConfig {
   auto_detect_min_ms: number;
   auto_detect_max_ms: number;
   fixed_crossfade_point_ms: number | null;
+  crossfader_assign?: CrossfaderAssign;
 }
 
 export interface CurvePoint {
@@ -53,6 +65,14 @@ export interface DeckStateEvent {
   playback_rate?: number;
   pitch_pct?: number;
   tempo_pct?: number;
+  keylock?: boolean;
+  /** Source lookahead keylock adds; position_ms already accounts for it */
+  keylock_latency_ms?: number;
+  reverse?: boolean;
+  censoring?: boolean;
+  slip?: boolean;
+  /** Where the track would have been, while slip is on */
+  slip_position_ms?: number | null;
   channel_gain?: number;
   bass_db?: number;
   filter_amount?: number;
@@ -63,6 +83,20 @@ export interface DeckStateEvent {
   loop_enabled?: boolean;
   loop_start_ms?: number | null;
   loop_end_ms?: number | null;
+  crossfader_side?: CrossfaderSide;
+  /** Set while the deck plays an HTTP(S) stream */
+  remote_stream?: RemoteStreamInfo | null;
+  /** Stem levels, while the deck plays separated stems */
+  stems?: StemMix | null;
+}
+
+export interface RemoteStreamInfo {
+  /** Latest ICY StreamTitle */
+  title: string | null;
+  station: string | null;
+  /** False while reconnecting */
+  connected: boolean;
+  reconnects: number;
 }
 
 export interface VuEvent {
@@ -71,6 +105,77 @@ export interface VuEvent {
   right_db: number;
 }
 
+export interface SpectrumConfig {
+  enabled: boolean;
+  /** Analyze every channel, not just the master bus */
+  per_deck: boolean;
+  bands: number;
+  fft_size: number;
+  interval_ms: number;
+}
+
+export interface SpectrumFrame {
+  channel: DeckId | "master";
+  /** Peak level per band, dBFS */
+  bands_db: number[];
+}
+
+export interface SpectrumData {
+  enabled: boolean;
+  band_centres_hz: number[];
+  channels: SpectrumFrame[];
+}
+
+export interface LoudnessConfig {
+  target_lufs: number;
+  /** True-peak level above which a sample counts as an over */
+  true_peak_ceiling_dbtp: number;
+}
+
+export interface LoudnessReading {
+  momentary_lufs: number;
+  short_term_lufs: number;
+  /** null until a block has passed the gates */
+  integrated_lufs: number | null;
+  /** Over the momentary (400 ms) window */
+  true_peak_dbtp: number;
+  /** Since the last reset */
+  max_true_peak_dbtp: number;
+  overs: number;
+  measured_secs: number;
+}
+
+export type RecordBus = "deck_a" | "deck_b" | "voice_fx" | "master";
+
+export interface MultitrackConfig {
+  /** Default: recordings/multitrack in the app data dir */
+  output_dir: string | null;
+  /** "wav" | "flac" | "mp3" | "opus" */
+  codec: EncoderCodec;
+  /** For MP3 and Opus */
+  bitrate_kbps: number;
+  buses: RecordBus[];
+  /** Chapter list written next to the master track */
+  sidecar: RecordingSidecar;
+}
+
+export interface MultitrackTrack {
+  bus: RecordBus;
+  path: string;
+  bytes_written: number;
+  /** Frames lost because the writer fell behind */
+  dropped_frames: number;
+  error: string | null;
+}
+
+export interface MultitrackStatus {
+  recording: boolean;
+  started_at: number | null;
+  folder: string | null;
+  sample_rate: number | null;
+  tracks: MultitrackTrack[];
+}
+
 export interface CrossfadeProgressEvent {
   progress: number;
   outgoing_deck: DeckId;
@@ -90,7 +195,7 @@ export interface CuePoint {
   song_id: number;
   name: string;
   position_ms: number;
-  cue_kind?: "hotcue" | "memory" | "transition";
+  cue_kind?: "hotcue" | "memory" | "transition" | "loop";
   slot?: number | null;
   label?: string;
   color_hex?: string;
@@ -99,6 +204,13 @@ export interface CuePoint {
 
 export type CueQuantize = "off" | "beat_1" | "beat_half" | "beat_quarter";
 
+/** Hot-cue/loop jumps on a playing deck wait for the next grid boundary. */
+export interface QuantizeConfig {
+  mode: CueQuantize;
+  /** Per-deck overrides of `mode` */
+  decks: Partial<Record<DeckId, CueQuantize>>;
+}
+
 export interface HotCue {
   song_id: number;
   slot: number;
@@ -108,6 +220,20 @@ export interface HotCue {
   quantized: boolean;
 }
 
+export interface SavedLoop {
+  song_id: number;
+  slot: number;
+  start_ms: number;
+  end_ms: number;
+  label: string;
+  color_hex: string;
+}
+
+export interface LoopRange {
+  start_ms: number;
+  end_ms: number;
+}
+
 export interface BeatGridAnalysis {
   song_id: number;
   file_path: string;
@@ -125,11 +251,25 @@ export interface StemAnalysis {
   source_mtime_ms: number;
   vocals_file_path: string;
   instrumental_file_path: string;
+  /** 4-stem outputs; null for older two-stem analyses */
+  drums_file_path?: string | null;
+  bass_file_path?: string | null;
+  other_file_path?: string | null;
   model_name: string;
   updated_at?: number | null;
 }
 
-export type StemPlaybackSource = "original" | "vocals" | "instrumental";
+export type StemPlaybackSource = "original" | "vocals" | "instrumental" | "stems";
+
+export type Stem = "vocals" | "drums" | "bass" | "other";
+
+export interface StemLevel {
+  /** Linear gain, 0–2 */
+  gain: number;
+  muted: boolean;
+}
+
+export type StemMix = Record<Stem, StemLevel>;
 
 export interface DeckStemSourceResult {
   source: StemPlaybackSource;
@@ -346,6 +486,7 @@ export type StemFilterMode = "off" | "vocal" | "instrumental";
 
 // ── Deck control ─────────────────────────────────────────────────────────────
 
+/** `filePath` may also be an http(s):// stream URL. */
 export const loadTrack = (deck: DeckId, filePath: string, songId?: number) =>
   invoke<void>("load_track", { deck, filePath, songId: songId ?? null });
 
@@ -378,6 +519,21 @@ export const setDeckPitch = (deck: DeckId, pitchPct: number) =>
 export const setDeckTempo = (deck: DeckId, tempoPct: number) =>
   invoke<void>("set_deck_tempo", { deck, tempoPct });
 
+/** Hold the deck's key while its tempo moves. */
+export const setDeckKeylock = (deck: DeckId, enabled: boolean) =>
+  invoke<void>("set_deck_keylock", { deck, enabled });
+
+export const setDeckReverse = (deck: PlaybackDeckId, enabled: boolean) =>
+  invoke<void>("set_deck_reverse", { deck, enabled });
+
+/** Slip-reverse while `active`; call again with `false` on release. */
+export const censorDeck = (deck: PlaybackDeckId, active: boolean) =>
+  invoke<void>("censor_deck", { deck, active });
+
+/** Turning slip off returns playback to where the track would have been. */
+export const setDeckSlip = (deck: DeckId, enabled: boolean) =>
+  invoke<void>("set_deck_slip", { deck, enabled });
+
 export const setMasterLevel = (level: number) =>
   invoke<void>("set_master_level", { level });
 
@@ -420,11 +576,52 @@ export const setDeckLoop = (deck: DeckId, startMs: number, endMs: number) =>
 export const clearDeckLoop = (deck: DeckId) =>
   invoke<void>("clear_deck_loop", { deck });
 
+export const halveDeckLoop = (deck: DeckId) =>
+  invoke<LoopRange>("halve_deck_loop", { deck });
+
+export const doubleDeckLoop = (deck: DeckId) =>
+  invoke<LoopRange>("double_deck_loop", { deck });
+
+/** Loop 1, 2, 4, 8 or 16 beats from the next beat of the track's beat grid. */
+export const setDeckAutoLoop = (deck: DeckId, beats: 1 | 2 | 4 | 8 | 16) =>
+  invoke<LoopRange>("set_deck_auto_loop", { deck, beats });
+
 export const getDeckState = (deck: DeckId) =>
   invoke<DeckStateEvent | null>("get_deck_state", { deck });
 
 export const getVuReadings = () => invoke<VuEvent[]>("get_vu_readings");
 
+export const getSpectrumConfig = () => invoke<SpectrumConfig>("get_spectrum_config");
+
+export const setSpectrumConfig = (config: SpectrumConfig) =>
+  invoke<void>("set_spectrum_config", { config });
+
+export const getSpectrumData = () => invoke<SpectrumData>("get_spectrum_data");
+
+export const getLoudnessConfig = () => invoke<LoudnessConfig>("get_loudness_config");
+
+export const setLoudnessConfig = (config: LoudnessConfig) =>
+  invoke<void>("set_loudness_config", { config });
+
+export const getLoudnessReading = () =>
+  invoke<LoudnessReading | null>("get_loudness_reading");
+
+export const resetLoudnessMeter = () => invoke<void>("reset_loudness_meter");
+
+export const getMultitrackConfig = () => invoke<MultitrackConfig>("get_multitrack_config");
+
+export const setMultitrackConfig = (config: MultitrackConfig) =>
+  invoke<void>("set_multitrack_config", { config });
+
+export const startMultitrackRecording = () =>
+  invoke<MultitrackStatus>("start_multitrack_recording");
+
+/** Resolves once every file of the take is closed. */
+export const stopMultitrackRecording = () =>
+  invoke<MultitrackStatus>("stop_multitrack_recording");
+
+export const getMultitrackStatus = () => invoke<MultitrackStatus>("get_multitrack_status");
+
 // ── Crossfade ────────────────────────────────────────────────────────────────
 
 export const getCrossfadeConfig = () =>
@@ -433,9 +630,46 @@ export const getCrossfadeConfig = () =>
 export const setCrossfadeConfig = (config: CrossfadeConfig) =>
   invoke<void>("set_crossfade_config", { config });
 
+export interface CrossfadeProfile {
+  id: number | null;
+  name: string;
+  config: CrossfadeConfig;
+}
+
+/** SAM category (by name) or song type (`S`, `J`, …) */
+export type CategoryKind = "category" | "song_type";
+
+export interface CrossfadeProfileAssignment {
+  kind: CategoryKind;
+  name: string;
+  profile_id: number;
+}
+
+export const getCrossfadeProfiles = () =>
+  invoke<CrossfadeProfile[]>("get_crossfade_profiles");
+
+export const saveCrossfadeProfile = (profile: CrossfadeProfile) =>
+  invoke<number>("save_crossfade_profile", { profile });
+
+export const deleteCrossfadeProfile = (id: number) =>
+  invoke<void>("delete_crossfade_profile", { id });
+
+export const getCrossfadeProfileAssignments = () =>
+  invoke<CrossfadeProfileAssignment[]>("get_crossfade_profile_assignments");
+
+/** A category assignment wins over a song type one. */
+export const setCrossfadeProfileAssignment = (assignment: CrossfadeProfileAssignment) =>
+  invoke<void>("set_crossfade_profile_assignment", { assignment });
+
+export const deleteCrossfadeProfileAssignment = (kind: CategoryKind, name: string) =>
+  invoke<void>("delete_crossfade_profile_assignment", { kind, name });
+
 export const startCrossfade = (outgoing: DeckId, incoming: DeckId) =>
   invoke<void>("start_crossfade", { outgoing, incoming });
 
+export const setCrossfaderAssignment = (deck: PlaybackDeckId, side: CrossfaderSide) =>
+  invoke<void>("set_crossfader_assignment", { deck, side });
+
 export const setManualCrossfade = (position: number) =>
   invoke<void>("set_manual_crossfade", { position });
 
@@ -445,6 +679,27 @@ export const triggerManualFade = (direction: "a_to_b" | "b_to_a", durationMs: nu
 export const getFadeCurvePreview = (curve: FadeCurve, steps = 50) =>
   invoke<CurvePoint[]>("get_fade_curve_preview", { curve, steps });
 
+// ── Sound FX voices ──────────────────────────────────────────────────────────
+
+export interface SfxVoiceState {
+  voice_id: number;
+  choke_group: number | null;
+  gain: number;
+  position_ms: number;
+  duration_ms: number;
+  fading: boolean;
+}
+
+/** Fire a one-shot stinger over the SFX channel; resolves to its voice id. */
+export const playSfx = (filePath: string, gainDb?: number, chokeGroup?: number) =>
+  invoke<number>("play_sfx", { filePath, gainDb, chokeGroup });
+
+/** Stop one voice, a choke group, or (with neither) every SFX voice. */
+export const stopSfx = (opts: { voiceId?: number; chokeGroup?: number; fadeMs?: number } = {}) =>
+  invoke<void>("stop_sfx", opts);
+
+export const getSfxVoices = () => invoke<SfxVoiceState[]>("get_sfx_voices");
+
 // ── DSP ──────────────────────────────────────────────────────────────────────
 
 export const getChannelDsp = (channel: DeckId | "master") =>
@@ -502,6 +757,26 @@ export const deleteCuePoint = (songId: number, name: string) =>
 export const jumpToCue = (deck: DeckId, songId: number, cueName: string) =>
   invoke<void>("jump_to_cue", { deck, songId, cueName });
 
+export interface SamCueImportProgress {
+  total: number;
+  processed: number;
+  songs_updated: number;
+  cues_written: number;
+  /** Left alone because the song already had a cue by that name */
+  cues_kept: number;
+  unreadable: number;
+  done: boolean;
+}
+
+/** Intro / outro / xfade cues from SAM's song list, for every song. */
+export const importSamCues = (overwrite = false) =>
+  invoke<SamCueImportProgress>("import_sam_cues", { options: { overwrite } });
+
+export const onSamCueImportProgress = (
+  cb: (event: SamCueImportProgress) => void
+): Promise<UnlistenFn> =>
+  listen<SamCueImportProgress>("sam_cue_import_progress", (e) => cb(e.payload));
+
 export const getHotCues = (songId: number) =>
   invoke<HotCue[]>("get_hot_cues", { songId });
 
@@ -544,6 +819,39 @@ export const renameHotCue = (songId: number, slot: number, label: string) =>
 export const recolorHotCue = (songId: number, slot: number, colorHex: string) =>
   invoke<void>("recolor_hot_cue", { songId, slot, colorHex });
 
+export const getQuantizeConfig = () => invoke<QuantizeConfig>("get_quantize_config");
+
+export const setQuantizeConfig = (config: QuantizeConfig) =>
+  invoke<void>("set_quantize_config", { config });
+
+export const getSavedLoops = (songId: number) =>
+  invoke<SavedLoop[]>("get_saved_loops", { songId });
+
+export const setSavedLoop = (
+  songId: number,
+  slot: number,
+  startMs: number,
+  endMs: number,
+  label?: string,
+  colorHex?: string,
+  quantizeMode?: CueQuantize
+) =>
+  invoke<SavedLoop>("set_saved_loop", {
+    songId,
+    slot,
+    startMs,
+    endMs,
+    label: label ?? null,
+    colorHex: colorHex ?? null,
+    quantizeMode: quantizeMode ?? null,
+  });
+
+export const clearSavedLoop = (songId: number, slot: number) =>
+  invoke<void>("clear_saved_loop", { songId, slot });
+
+export const triggerSavedLoop = (deck: DeckId, songId: number, slot: number) =>
+  invoke<SavedLoop>("trigger_saved_loop", { deck, songId, slot });
+
 export const analyzeBeatgrid = (
   songId: number,
   filePath: string,
@@ -554,6 +862,10 @@ export const analyzeBeatgrid = (
 export const getBeatgrid = (songId: number, filePath: string) =>
   invoke<BeatGridAnalysis | null>("get_beatgrid", { songId, filePath });
 
+/** Re-detect intro/outro transition cues; hand-placed cues are kept. */
+export const detectTransitionCues = (songId: number, filePath: string) =>
+  invoke<CuePoint[]>("detect_transition_cues", { songId, filePath });
+
 export const analyzeStems = (
   songId: number,
   filePath: string,
@@ -586,6 +898,10 @@ export const setDeckStemSource = (
     originalFilePath: originalFilePath ?? null,
   });
 
+/** Levels for a deck playing the "stems" source; reset on each load. */
+export const setDeckStemMix = (deck: DeckId, mix: StemMix) =>
+  invoke<void>("set_deck_stem_mix", { deck, mix });
+
 export const getMonitorRoutingConfig = () =>
   invoke<MonitorRoutingConfig>("get_monitor_routing_config");
 
@@ -735,10 +1051,236 @@ export const saveSamDbConfig = (config: SamDbConfig, password: string) =>
 export const getSamDbStatus = () =>
   invoke<SamDbStatus>("get_sam_db_status");
 
+/** Payload of the `sam_db_status_changed` event. */
+export interface SamDbHealth {
+  /** `degraded` = configured but unreachable; AutoDJ plays cached candidates */
+  state: "connected" | "degraded" | "disconnected";
+  /** Unix ms of the last state change */
+  since: number;
+  last_ok_at: number | null;
+  latency_ms: number | null;
+  last_error: string | null;
+  reconnect_attempts: number;
+  cached_candidates: number;
+}
+
+export const getSamDbHealth = () =>
+  invoke<SamDbHealth>("get_sam_db_health");
+
+export interface SamCacheStats {
+  hits: number;
+  misses: number;
+  /** Writes that invalidated cached query results since startup */
+  invalidations: number;
+}
+
+export const getSamCacheStats = () =>
+  invoke<SamCacheStats>("get_sam_cache_stats");
+
 /** Return SAM categories (supports both `category` and legacy `catlist`). */
 export const getSamCategories = () =>
   invoke<SamCategory[]>("get_sam_categories");
 
+// ── SAM write-behind outbox ───────────────────────────────────────────────────
+
+export type SamWrite =
+  | { op: "history"; song_id: number; listeners: number; played_at: number }
+  | { op: "remove_from_queue"; queue_id: number }
+  | { op: "queue_request"; song_id: number; request_id: number; position: number }
+  | { op: "play_stats"; song_id: number; listeners: number; request_origin: boolean }
+  | { op: "weight_delta"; song_id: number; delta: number };
+
+export interface SamOutboxStatus {
+  pending: number;
+  failed: number;
+  /** Unix ms of the oldest pending write */
+  oldest_pending_at: number | null;
+  head_attempts: number;
+  last_error: string | null;
+}
+
+export interface FailedSamWrite {
+  id: number;
+  /** Null when the stored entry can't be parsed */
+  write: SamWrite | null;
+  created_at: number;
+  attempts: number;
+  last_error: string | null;
+}
+
+export const getSamOutboxStatus = () =>
+  invoke<SamOutboxStatus>("get_sam_outbox_status");
+
+export const getFailedSamWrites = () =>
+  invoke<FailedSamWrite[]>("get_failed_sam_writes");
+
+/** Re-queue parked writes; omit `ids` for all. Returns how many. */
+export const retryFailedSamWrites = (ids?: number[]) =>
+  invoke<number>("retry_failed_sam_writes", { ids: ids ?? null });
+
+/** Delete parked writes; omit `ids` for all. Returns how many. */
+export const discardFailedSamWrites = (ids?: number[]) =>
+  invoke<number>("discard_failed_sam_writes", { ids: ids ?? null });
+
+// ── Two-way song sync ─────────────────────────────────────────────────────────
+
+export interface SyncedSong {
+  song_id: number;
+  title: string;
+  artist: string;
+  album: string;
+  weight: number;
+  /** First category the song is in; empty when none */
+  category: string;
+  /** Unix ms of the last change seen, 0 if unchanged since first seen */
+  modified_at: number;
+}
+
+export interface SongEdit {
+  /** `modified_at` of the copy the editor started from */
+  base_modified_at: number;
+  title?: string;
+  artist?: string;
+  album?: string;
+  weight?: number;
+  category?: string;
+}
+
+export interface SamSongChanges {
+  /** "sam" for edits made in SAM, "local" for edits saved here */
+  source: "sam" | "local";
+  changes: { song: SyncedSong; fields: string[] }[];
+  removed: number[];
+}
+
+export const getSyncedSong = (songId: number) =>
+  invoke<SyncedSong>("get_synced_song", { songId });
+
+/** Rejected with a conflict error if the song changed since it was loaded. */
+export const saveSyncedSong = (songId: number, edit: SongEdit) =>
+  invoke<SyncedSong>("save_synced_song", { songId, edit });
+
+export const getSamSongChanges = (since: number, limit?: number) =>
+  invoke<SyncedSong[]>("get_sam_song_changes", { since, limit: limit ?? null });
+
+export const onSamSongChanges = (
+  cb: (event: SamSongChanges) => void
+): Promise<UnlistenFn> =>
+  listen<SamSongChanges>("sam_song_changes", (e) => cb(e.payload));
+
+// ── Tag enrichment ────────────────────────────────────────────────────────────
+
+export interface TagEnrichmentConfig {
+  enabled: boolean;
+  musicbrainz: boolean;
+  /** Discogs personal access token; empty = Discogs is not asked */
+  discogs_token: string;
+  /** Lowest MusicBrainz search score (0–100) accepted as a match */
+  min_score: number;
+}
+
+export interface TagEnrichmentProgress {
+  total: number;
+  processed: number;
+  proposed: number;
+  not_found: number;
+  failed: number;
+  cancelled: boolean;
+  done: boolean;
+}
+
+export type TagProposalStatus = "pending" | "applied" | "rejected" | "stale";
+
+export interface TagProposal {
+  id: number;
+  song_id: number;
+  artist: string;
+  title: string;
+  field: "album" | "albumyear" | "isrc";
+  current: string;
+  proposed: string;
+  source: "musicbrainz" | "discogs";
+  score: number;
+  status: TagProposalStatus;
+  created_at: number;
+}
+
+export interface TagApplySummary {
+  applied: number;
+  /** Proposals whose field had changed in the meantime */
+  stale: number;
+  songs_updated: number;
+}
+
+export const getTagEnrichmentConfig = () =>
+  invoke<TagEnrichmentConfig>("get_tag_enrichment_config");
+
+export const setTagEnrichmentConfig = (config: TagEnrichmentConfig) =>
+  invoke<void>("set_tag_enrichment_config", { config });
+
+/** Look up poorly tagged songs; omit `songIds` to pick them automatically. */
+export const startTagEnrichment = (songIds?: number[], limit = 200) =>
+  invoke<TagEnrichmentProgress>("start_tag_enrichment", {
+    options: { song_ids: songIds ?? null, limit },
+  });
+
+export const cancelTagEnrichment = () => invoke<void>("cancel_tag_enrichment");
+
+export const getTagProposals = (status?: TagProposalStatus, limit?: number) =>
+  invoke<TagProposal[]>("get_tag_proposals", {
+    status: status ?? null,
+    limit: limit ?? null,
+  });
+
+export const applyTagProposals = (ids: number[]) =>
+  invoke<TagApplySummary>("apply_tag_proposals", { ids });
+
+export const rejectTagProposals = (ids: number[]) =>
+  invoke<number>("reject_tag_proposals", { ids });
+
+export const onTagEnrichmentProgress = (
+  cb: (event: TagEnrichmentProgress) => void
+): Promise<UnlistenFn> =>
+  listen<TagEnrichmentProgress>("tag_enrichment_progress", (e) => cb(e.payload));
+
+// ── Path translation rules ────────────────────────────────────────────────────
+
+export interface PathRule {
+  id: number | null;
+  position: number;
+  /** `prefix` = case-insensitive prefix swap; `regex` = pattern with `$1` replacements */
+  kind: "prefix" | "regex";
+  pattern: string;
+  replacement: string;
+  enabled: boolean;
+  label: string | null;
+}
+
+export interface PathTranslation {
+  input: string;
+  output: string;
+  rule_id: number | null;
+  /** Matched the single prefix pair in the SAM DB config */
+  legacy: boolean;
+  exists: boolean;
+}
+
+export const getPathTranslationRules = () =>
+  invoke<PathRule[]>("get_path_translation_rules");
+
+export const savePathTranslationRule = (rule: PathRule) =>
+  invoke<number>("save_path_translation_rule", { rule });
+
+export const deletePathTranslationRule = (id: number) =>
+  invoke<void>("delete_path_translation_rule", { id });
+
+/** `ids` in evaluation order, first match wins. */
+export const reorderPathTranslationRules = (ids: number[]) =>
+  invoke<void>("reorder_path_translation_rules", { ids });
+
+export const testPathTranslation = (paths: string[]) =>
+  invoke<PathTranslation[]>("test_path_translation", { paths });
+
 /** Return songs belonging to a SAM category via the categorylist join table. */
 export const getSongsInCategory = (categoryId: number, limit = 500, offset = 0) =>
   invoke<SamSong[]>("get_songs_in_category", { categoryId, limit, offset });
@@ -775,14 +1317,24 @@ export const getStreamStatus = () => invoke<boolean>("get_stream_status");
 
 // ── Event listeners ──────────────────────────────────────────────────────────
 
+/**
+ * High-rate engine updates arrive batched, one `engine_tick` event per poll:
+ * event name → payloads that changed since they were last sent.
+ */
+type EngineTick = Partial<Record<string, unknown[]>>;
+
+const onEngineTick = <T>(event: string, cb: (payload: T) => void): Promise<UnlistenFn> =>
+  listen<EngineTick>("engine_tick", (e) => {
+    for (const payload of e.payload[event] ?? []) cb(payload as T);
+  });
+
 export const onDeckStateChanged = (
   cb: (event: DeckStateEvent) => void
-): Promise<UnlistenFn> => listen<DeckStateEvent>("deck_state_changed", (e) => cb(e.payload));
+): Promise<UnlistenFn> => onEngineTick("deck_state_changed", cb);
 
 export const onCrossfadeProgress = (
   cb: (event: CrossfadeProgressEvent) => void
-): Promise<UnlistenFn> =>
-  listen<CrossfadeProgressEvent>("crossfade_progress", (e) => cb(e.payload));
+): Promise<UnlistenFn> => onEngineTick("crossfade_progress", cb);
 
 export const onManualCrossfadeChanged = (
   cb: (event: ManualCrossfadeChangedEvent) => void
@@ -796,7 +1348,15 @@ export const onMasterVolumeChanged = (
 
 export const onVuMeter = (
   cb: (event: VuEvent) => void
-): Promise<UnlistenFn> => listen<VuEvent>("vu_meter", (e) => cb(e.payload));
+): Promise<UnlistenFn> => onEngineTick("vu_meter", cb);
+
+export const onSpectrum = (
+  cb: (frame: SpectrumFrame) => void
+): Promise<UnlistenFn> => onEngineTick("spectrum", cb);
+
+export const onLoudness = (
+  cb: (reading: LoudnessReading) => void
+): Promise<UnlistenFn> => onEngineTick("loudness", cb);
 
 export const onStreamConnected = (
   cb: (mount: string) => void
@@ -814,6 +1374,157 @@ export const getWaveformData = (filePath: string, resolution = 1000) =>
     (arr) => new Float32Array(arr)
   );
 
+export interface WaveformChunk {
+  resolution: number;
+  offset: number;
+  total: number;
+  duration_ms: number;
+  peaks: number[];
+}
+
+/** `count` peaks from `offset` of a `resolution`-wide rendering of the track. */
+export const getWaveformChunk = (
+  filePath: string,
+  resolution: number,
+  offset: number,
+  count: number
+) => invoke<WaveformChunk>("get_waveform_chunk", { filePath, resolution, offset, count });
+
+// ── Background analysis queue ────────────────────────────────────────────────
+
+export type AnalysisKind =
+  | "beatgrid"
+  | "waveform"
+  | "loudness"
+  | "stems"
+  | "fingerprint";
+
+export interface AnalysisJob {
+  id: number;
+  song_id: number | null;
+  file_path: string;
+  kind: AnalysisKind;
+  priority: number;
+  status: "pending" | "running" | "done" | "failed" | "cancelled";
+  error: string | null;
+  created_at: number;
+  started_at: number | null;
+  finished_at: number | null;
+  /** 0–100 while running, for kinds that report it (stems) */
+  progress_pct: number | null;
+}
+
+export type StemModel = "htdemucs" | "htdemucs_ft" | "hdemucs_mmi" | "mdx_extra" | "mdx_extra_q";
+
+export type StemDevice = "auto" | "cpu" | "cuda" | "mps";
+
+export interface StemJobConfig {
+  model: StemModel;
+  device: StemDevice;
+  /** CPU threads the model may use (0 = all cores) */
+  cpu_threads: number;
+  /** Stem jobs running at once, within max_workers */
+  max_concurrent: number;
+}
+
+export interface AnalysisQueueConfig {
+  enabled: boolean;
+  max_workers: number;
+  cooldown_ms: number;
+  lookahead: number;
+  auto_kinds: AnalysisKind[];
+  stems: StemJobConfig;
+}
+
+export interface AnalysisQueueStatus {
+  config: AnalysisQueueConfig;
+  pending: number;
+  running: number;
+  jobs: AnalysisJob[];
+}
+
+export interface AnalysisProgressEvent {
+  job: AnalysisJob;
+  pending: number;
+  running: number;
+}
+
+export interface AnalysisJobProgress {
+  job_id: number;
+  kind: AnalysisKind;
+  percent: number;
+}
+
+export interface TrackLoudness {
+  song_id: number;
+  file_path: string;
+  mtime_ms: number;
+  loudness_db: number;
+  peak_db: number;
+}
+
+export const getAnalysisQueue = () => invoke<AnalysisQueueStatus>("get_analysis_queue");
+
+export const enqueueAnalysis = (
+  songId: number | null,
+  filePath: string,
+  kinds: AnalysisKind[]
+) => invoke<number[]>("enqueue_analysis", { songId, filePath, kinds });
+
+export const cancelAnalysisJob = (id: number) => invoke<void>("cancel_analysis_job", { id });
+
+/** Queue stem separation for SAM songs behind other analysis; returns job ids. */
+export const queueStemAnalysis = (songIds: number[]) =>
+  invoke<number[]>("queue_stem_analysis", { songIds });
+
+/** Cancel waiting stem jobs and stop running ones; returns how many. */
+export const cancelStemJobs = () => invoke<number>("cancel_stem_jobs");
+
+export const getAnalysisQueueConfig = () =>
+  invoke<AnalysisQueueConfig>("get_analysis_queue_config");
+
+export const setAnalysisQueueConfig = (config: AnalysisQueueConfig) =>
+  invoke<void>("set_analysis_queue_config", { config });
+
+export const getTrackLoudness = (filePath: string) =>
+  invoke<TrackLoudness | null>("get_track_loudness", { filePath });
+
+export interface DuplicateSearchOptions {
+  /** Share of fingerprint bits that must agree, 0.5 (random) – 1.0 */
+  min_similarity: number;
+  max_duration_diff_ms: number;
+}
+
+export interface DuplicateSong {
+  song_id: number;
+  file_path: string;
+  duration_ms: number;
+  artist: string;
+  title: string;
+}
+
+export interface DuplicateGroup {
+  songs: DuplicateSong[];
+  /** Lowest similarity among the matched pairs */
+  similarity: number;
+}
+
+/** Queue fingerprinting for the whole library; returns songs queued. */
+export const fingerprintLibrary = () => invoke<number>("fingerprint_library");
+
+export const findDuplicateSongs = (options?: Partial<DuplicateSearchOptions>) =>
+  invoke<DuplicateGroup[]>("find_duplicate_songs", { options: options ?? null });
+
+export const onAnalysisProgress = (
+  cb: (event: AnalysisProgressEvent) => void
+): Promise<UnlistenFn> =>
+  listen<AnalysisProgressEvent>("analysis_progress", (e) => cb(e.payload));
+
+export const onAnalysisJobProgress = (
+  cb: (event: AnalysisJobProgress) => void
+): Promise<UnlistenFn> =>
+  listen<AnalysisJobProgress>("analysis_job_progress", (e) => cb(e.payload));
+
 // ── Phase 2 — Song details ───────────────────────────────────────────────────
 
 /** Extended song detail — adds local-only metadata on top of SAM fields. */
@@ -902,8 +1613,15 @@ export const onRequestReceived = (
 export type OutputType = "icecast" | "shoutcast" | "file";
 export type IcecastVersion = "v1" | "v2";
 export type ShoutcastVersion = "v1" | "v2";
-export type EncoderCodec = "mp3" | "aac" | "ogg" | "wav" | "flac";
-export type FileRotation = "none" | "hourly" | "daily" | "by_size";
+export type EncoderCodec = "mp3" | "aac" | "ogg" | "wav" | "flac" | "opus";
+export type FileRotation =
+  | "none"
+  | "hourly"
+  | "daily"
+  | "by_size"
+  | "by_track"
+  | "by_duration";
+export type RecordingSidecar = "none" | "cue" | "json";
 
 export type EncoderStatusKind =
   | "disabled"
@@ -944,6 +1662,8 @@ export interface EncoderConfig {
   icecast_version: IcecastVersion;
   shoutcast_version: ShoutcastVersion;
   shoutcast_sid: number;
+  admin_username?: string | null;
+  admin_password?: string | null;
   stream_name: string | null;
   stream_genre: string | null;
   stream_url: string | null;
@@ -955,16 +1675,37 @@ export interface EncoderConfig {
   file_rotation: FileRotation;
   file_max_size_mb: number;
   file_name_template: string;
+  file_split_minutes?: number;
+  /** Chapter list written next to each closed file */
+  file_sidecar?: RecordingSidecar;
 
   // Metadata
   send_metadata: boolean;
   icy_metadata_interval: number;
+  /** `{artist}`, `{title}`, `{show}`, `{station}` (or `$artist$`, `$title$`, `$combine$`) */
   metadata_caption_template: string | null;
   metadata_url_append: string | null;
+  metadata_uppercase?: boolean;
+  /** Characters; 0 = no limit */
+  metadata_max_length?: number;
+  /** Romanise Devanagari, Gurmukhi, Urdu etc. for players that garble Unicode */
+  metadata_transliterate?: boolean;
+
+  // Ad cues
+  /** Push cue-out / cue-in markers as the stream title around ad breaks */
+  ad_cue_enabled?: boolean;
+  /** `{break}`, `{break_id}`, `{spots}`, `{duration_ms}`, `{duration_secs}` */
+  ad_cue_out_template?: string;
+  ad_cue_in_template?: string;
 
   // Reconnect
   reconnect_delay_secs: number;
   max_reconnect_attempts: number;
+
+  // Failover
+  failover_group: string | null;
+  priority: number;
+  failover_after_attempts: number;
 }
 
 export interface EncoderRuntimeState {
@@ -976,6 +1717,37 @@ export interface EncoderRuntimeState {
   current_bitrate_kbps: number | null;
   error: string | null;
   recording_file: string | null;
+  consecutive_failures: number;
+}
+
+/** Payload of the `ad_break` event (also on the overlay feed). */
+export interface AdBreakCueEvent {
+  phase: "out" | "in";
+  at_ms: number;
+  break_id: number | null;
+  name: string;
+  spots: number;
+  duration_ms: number;
+}
+
+export type ProbeCheckLevel = "ok" | "warning" | "error";
+
+export interface ProbeCheck {
+  name: string;
+  level: ProbeCheckLevel;
+  message: string;
+}
+
+/** Result of `test_encoder_connection`; `ok` is false when any check is an error. */
+export interface EncoderDiagnosis {
+  ok: boolean;
+  server_type: "icecast" | "shoutcast_v1" | "shoutcast_v2" | "file";
+  server_version: string | null;
+  mount_available: boolean | null;
+  auth_ok: boolean | null;
+  max_listeners: number | null;
+  current_listeners: number | null;
+  checks: ProbeCheck[];
 }
 
 export interface ListenerSnapshot {
@@ -986,6 +1758,7 @@ export interface ListenerSnapshot {
   peak_listeners: number;
   unique_listeners: number;
   stream_bitrate: number | null;
+  mount: string | null;
 }
 
 // ── Phase 4 — Encoder commands ──────────────────────────────────────────────
@@ -1012,7 +1785,7 @@ export const stopAllEncoders = () =>
   invoke<void>("stop_all_encoders");
 
 export const testEncoderConnection = (id: number) =>
-  invoke<boolean>("test_encoder_connection", { id });
+  invoke<EncoderDiagnosis>("test_encoder_connection", { id });
 
 export const getEncoderRuntime = () =>
   invoke<EncoderRuntimeState[]>("get_encoder_runtime");
@@ -1025,6 +1798,83 @@ export const startRecording = (encoderId: number) =>
 export const stopRecording = (encoderId: number) =>
   invoke<void>("stop_recording", { encoderId });
 
+// ── Show export ─────────────────────────────────────────────────────────────
+
+export type ExportFormat = "mp3" | "aac";
+
+export type UploadTarget =
+  | {
+      type: "sftp";
+      host: string;
+      port: number | null;
+      username: string;
+      /** Private key; default is the user's ssh config/agent */
+      identity_file: string | null;
+      remote_dir: string;
+    }
+  | {
+      type: "s3";
+      /** Custom endpoint for S3-compatible stores (default AWS) */
+      endpoint: string | null;
+      region: string;
+      bucket: string;
+      prefix: string;
+      access_key_id: string;
+      secret_access_key: string;
+    };
+
+export interface ShowExportConfig {
+  /** Default: an `exports` folder next to the recording */
+  output_dir: string | null;
+  format: ExportFormat;
+  bitrate_kbps: number;
+  target_loudness_db: number;
+  upload: UploadTarget | null;
+}
+
+export interface ShowExportRequest {
+  /** WAV written by a file encoder */
+  recording_path: string;
+  /** Unix ms; default is the file mtime minus its length */
+  recording_started_at?: number | null;
+  /** Show boundaries, unix ms; default is the whole recording */
+  start_at?: number | null;
+  end_at?: number | null;
+  title: string;
+  artist?: string | null;
+  album?: string | null;
+  comment?: string | null;
+  format?: ExportFormat | null;
+  normalize?: boolean | null;
+  upload?: boolean;
+}
+
+export interface ExportChapter {
+  start_ms: number;
+  end_ms: number;
+  title: string;
+}
+
+export interface ShowExportResult {
+  output_path: string;
+  duration_ms: number;
+  loudness_db: number;
+  peak_db: number;
+  gain_db: number;
+  chapters: ExportChapter[];
+  uploaded_to: string | null;
+}
+
+/** Trim, normalise, encode with play-log chapters and optionally upload a recording. */
+export const exportShow = (request: ShowExportRequest) =>
+  invoke<ShowExportResult>("export_show", { request });
+
+export const getShowExportConfig = () =>
+  invoke<ShowExportConfig>("get_show_export_config");
+
+export const setShowExportConfig = (config: ShowExportConfig) =>
+  invoke<void>("set_show_export_config", { config });
+
 // ── Phase 4 — Stats commands ────────────────────────────────────────────────
 
 export type StatsPeriod = "1h" | "6h" | "24h" | "7d";
@@ -1049,6 +1899,11 @@ export interface EncoderStatusChangedEvent {
   error?: string;
 }
 
+export type EncoderFailoverEvent =
+  | { kind: "failover"; group: string; from: number; to: number }
+  | { kind: "failback"; group: string; primary: number; backup: number }
+  | { kind: "release"; group: string; backup: number };
+
 export interface ListenerCountUpdatedEvent {
   encoderId: number;
   count: number;
@@ -1065,6 +1920,11 @@ export const onEncoderStatusChanged = (
 ): Promise<UnlistenFn> =>
   listen<EncoderStatusChangedEvent>("encoder_status_changed", (e) => cb(e.payload));
 
+export const onEncoderFailover = (
+  cb: (e: EncoderFailoverEvent) => void
+): Promise<UnlistenFn> =>
+  listen<EncoderFailoverEvent>("encoder_failover", (e) => cb(e.payload));
+
 export const onListenerCountUpdated = (
   cb: (e: ListenerCountUpdatedEvent) => void
 ): Promise<UnlistenFn> =>
@@ -1110,6 +1970,8 @@ export interface MixxxPlannerConfig {
 export interface AutoTransitionConfig {
   engine: AutodjTransitionEngine;
   mixxx_planner_config: MixxxPlannerConfig;
+  /** Decks AutoDJ cycles through in order; defaults to A → B. */
+  deck_rotation?: PlaybackDeckId[];
 }
 
 export interface TransitionDecisionDebug {
@@ -1233,6 +2095,142 @@ export interface SongCandidate {
 export const getNextAutoDjTrack = (): Promise<SongCandidate | null> =>
   invoke<SongCandidate | null>("get_next_autodj_track");
 
+export interface RotationFilterStep {
+  rule: string;
+  removed: number;
+  examples: string[];
+}
+
+export interface RotationSlotTrace {
+  slot_id: string;
+  kind: ClockwheelSlotKind;
+  target: string;
+  selection_method: ClockwheelSelectionMethod;
+  candidates: number;
+  excluded: number;
+  eligible: number;
+  filters: RotationFilterStep[];
+  outcome: string;
+}
+
+export interface RotationPickTrace {
+  song_id: number;
+  title: string;
+  artist: string;
+  weight: number;
+  reason: string;
+}
+
+export interface RotationDecision {
+  at: number;
+  template_id: number | null;
+  active_category: string | null;
+  excluded: number;
+  slots: RotationSlotTrace[];
+  picked: RotationPickTrace | null;
+  fallback: boolean;
+}
+
+export interface GhostQueueEntry extends SongCandidate {
+  planned_at: number;
+  pinned: boolean;
+}
+
+/** Empty unless the clockwheel's ghost queue is enabled. */
+export const getGhostQueue = (): Promise<GhostQueueEntry[]> =>
+  invoke<GhostQueueEntry[]>("get_ghost_queue");
+
+export const vetoGhostQueueEntry = (songId: number): Promise<void> =>
+  invoke<void>("veto_ghost_queue_entry", { songId });
+
+export type PlanItemRef =
+  | { source: "queue"; queue_id: number }
+  | { source: "ghost"; song_id: number };
+
+export type PlanItem = PlanItemRef & {
+  song_id: number;
+  title: string;
+  artist: string;
+  duration: number;
+  pinned: boolean;
+  /** Loaded on a deck; read-only */
+  claimed: boolean;
+};
+
+/** SAM queue followed by the ghost queue, in play order. */
+export const getAutodjPlan = (): Promise<PlanItem[]> =>
+  invoke<PlanItem[]>("get_autodj_plan");
+
+export const setAutodjPlanPin = (item: PlanItemRef, pinned: boolean): Promise<void> =>
+  invoke<void>("set_autodj_plan_pin", { item, pinned });
+
+/** `order` lists every unclaimed item; pinned items must keep their position. */
+export const reorderAutodjPlan = (order: PlanItemRef[]): Promise<void> =>
+  invoke<void>("reorder_autodj_plan", { order });
+
+export const replaceAutodjPlanItem = (item: PlanItemRef, songId: number): Promise<void> =>
+  invoke<void>("replace_autodj_plan_item", { item, songId });
+
+/** Only recorded while the clockwheel's verbose logging is enabled. */
+export const getLastRotationDecision = (): Promise<RotationDecision | null> =>
+  invoke<RotationDecision | null>("get_last_rotation_decision");
+
+export interface SimulatedPick {
+  at: number;
+  song_id: number;
+  title: string;
+  artist: string;
+  album: string;
+  category: string | null;
+  duration: number;
+  template_id: number | null;
+  slot_id: string;
+  slot_kind: ClockwheelSlotKind;
+  slot_target: string;
+  selection_method: ClockwheelSelectionMethod;
+  weight: number;
+  candidates: number;
+  eligible: number;
+  filters: RotationFilterStep[];
+  skipped_slots: string[];
+  fallback: boolean;
+}
+
+export interface RotationSimulation {
+  seed: number;
+  start: number;
+  end: number;
+  picks: SimulatedPick[];
+  stalled_at: number | null;
+  truncated: boolean;
+}
+
+export const simulateRotation = (
+  hours: number,
+  startAt?: number,
+  seed?: number,
+): Promise<RotationSimulation> =>
+  invoke<RotationSimulation>("simulate_rotation", {
+    hours,
+    startAt: startAt ?? null,
+    seed: seed ?? null,
+  });
+
+export interface BadTrack {
+  song_id: number;
+  file_path: string;
+  reason: string;
+  failures: number;
+  first_failed_at: number;
+  last_failed_at: number;
+  quarantined_until: number;
+}
+
+export const getBadTracks = (): Promise<BadTrack[]> => invoke<BadTrack[]>("get_bad_tracks");
+
+export const releaseBadTrack = (songId: number): Promise<void> =>
+  invoke<void>("release_bad_track", { songId });
+
 export interface EnqueuedClockwheelTrack {
   queue_id: number;
   song: SongCandidate;
@@ -1262,6 +2260,38 @@ export const savePlaylist = (playlist: Playlist): Promise<number> =>
 export const setActivePlaylist = (playlistId: number): Promise<void> =>
   invoke<void>("set_active_playlist", { playlistId });
 
+export type PlaylistFileFormat = "m3u" | "pls" | "csv";
+
+export interface PlaylistImportReport {
+  format: PlaylistFileFormat;
+  entries: number;
+  matched: number;
+  unmatched: string[];
+  playlist_id: number | null;
+  queue_ids: number[];
+}
+
+/** Import an M3U/M3U8, PLS or CSV file; replaces `playlistId` or creates a playlist. */
+export const importPlaylistFile = (
+  path: string,
+  playlistId?: number,
+  name?: string
+): Promise<PlaylistImportReport> =>
+  invoke<PlaylistImportReport>("import_playlist_file", {
+    path,
+    playlistId: playlistId ?? null,
+    name: name ?? null,
+  });
+
+export const queuePlaylistFile = (path: string): Promise<PlaylistImportReport> =>
+  invoke<PlaylistImportReport>("queue_playlist_file", { path });
+
+export const exportPlaylistM3u = (playlistId: number, path: string): Promise<number> =>
+  invoke<number>("export_playlist_m3u", { playlistId, path });
+
+export const exportQueueM3u = (path: string): Promise<number> =>
+  invoke<number>("export_queue_m3u", { path });
+
 // ── Show Scheduler ────────────────────────────────────────────────────────────
 
 export type DayOfWeek =
@@ -1285,6 +2315,22 @@ export interface Show {
   duration_minutes: number;
   actions: ShowActionType[];
   enabled: boolean;
+  overrides?: ShowOverrides;
+}
+
+/** Station settings swapped in while the show is on air; null keeps the station's. */
+export interface ShowOverrides {
+  dj_mode: "auto_dj" | "assisted" | "manual" | null;
+  crossfade: CrossfadeConfig | null;
+  /** Used when `crossfade` is null */
+  crossfade_profile_id: number | null;
+  clockwheel_template_id: number | null;
+}
+
+export interface ActiveShowOverrides {
+  show_id: number;
+  show_name: string;
+  overrides: ShowOverrides;
 }
 
 export interface ScheduledEvent {
@@ -1306,6 +2352,58 @@ export const deleteShow = (id: number): Promise<void> =>
 export const getUpcomingEvents = (hours = 24): Promise<ScheduledEvent[]> =>
   invoke<ScheduledEvent[]>("get_upcoming_events", { hours });
 
+export const getActiveShowOverrides = (): Promise<ActiveShowOverrides | null> =>
+  invoke<ActiveShowOverrides | null>("get_active_show_overrides");
+
+export const onShowOverridesChanged = (
+  cb: (event: ActiveShowOverrides | null) => void
+): Promise<UnlistenFn> =>
+  listen<ActiveShowOverrides | null>("show_overrides_changed", (e) => cb(e.payload));
+
+// ── Relays ────────────────────────────────────────────────────────────────────
+
+export interface RelayEvent {
+  id: number | null;
+  name: string;
+  /** http(s):// stream to rebroadcast */
+  url: string;
+  /** Minute of the hour the relay starts */
+  minute: number;
+  /** Local hours (empty = every hour) */
+  hours: number[];
+  /** 0=Mon..6=Sun (empty = every day) */
+  days: number[];
+  duration_minutes: number;
+  fade_ms: number;
+  enabled: boolean;
+  last_fired_at: number | null;
+}
+
+export interface RelayStatus {
+  relay_id: number | null;
+  name: string;
+  url: string;
+  /** Deck the relay plays on; null while AutoDJ has the air */
+  deck: DeckId | null;
+  ends_at: number;
+  attempts: number;
+  last_error: string | null;
+}
+
+export const getRelayEvents = (): Promise<RelayEvent[]> =>
+  invoke<RelayEvent[]>("get_relay_events");
+
+export const saveRelayEvent = (event: RelayEvent): Promise<number> =>
+  invoke<number>("save_relay_event", { event });
+
+export const deleteRelayEvent = (id: number): Promise<void> =>
+  invoke<void>("delete_relay_event", { id });
+
+export const getRelayStatus = (): Promise<RelayStatus | null> =>
+  invoke<RelayStatus | null>("get_relay_status");
+
+export const stopRelay = (): Promise<void> => invoke<void>("stop_relay");
+
 // ── GAP Killer ────────────────────────────────────────────────────────────────
 
 export interface GapKillerConfig {
@@ -1340,6 +2438,8 @@ export interface RequestPolicy {
   blacklisted_categories: string[];
   active_hours: [number, number] | null;
   auto_accept: boolean;
+  /** Hours when passing requests are auto-accepted; null = always */
+  auto_accept_hours: [number, number] | null;
 }
 
 export const getRequestPolicy = (): Promise<RequestPolicy> =>
@@ -1390,3 +2490,44 @@ export const onShowTriggered = (
   cb: (event: ShowTriggeredEvent) => void
 ): Promise<UnlistenFn> =>
   listen<ShowTriggeredEvent>("show_triggered", (e) => cb(e.payload));
+
+// ── Crash recovery ────────────────────────────────────────────────────────────
+
+export interface DeckSnapshot {
+  deck: DeckId;
+  file_path: string;
+  song_id: number | null;
+  position_ms: number;
+  duration_ms: number;
+  playing: boolean;
+  channel_gain: number;
+}
+
+export interface SessionSnapshot {
+  saved_at: number;
+  dj_mode: string;
+  crossfader: number;
+  decks: DeckSnapshot[];
+  live_encoder_ids: number[];
+}
+
+export interface ResumeReport {
+  decks_restored: DeckId[];
+  decks_missing: DeckId[];
+  encoders_started: number[];
+  dj_mode: string | null;
+}
+
+export const getPreviousSession = (): Promise<SessionSnapshot | null> =>
+  invoke<SessionSnapshot | null>("get_previous_session");
+
+export const resumePreviousSession = (): Promise<ResumeReport> =>
+  invoke<ResumeReport>("resume_previous_session");
+
+export const discardPreviousSession = (): Promise<boolean> =>
+  invoke<boolean>("discard_previous_session");
+
+export const onPreviousSessionAvailable = (
+  cb: (snapshot: SessionSnapshot) => void
+): Promise<UnlistenFn> =>
+  listen<SessionSnapshot>("previous_session_available", (e) => cb(e.payload));
@@ -1,6 +1,6 @@
 /// Phase 5 TypeScript bridge types and invoke wrappers
 
-import { invoke } from "@tauri-apps/api/core";
+import { invoke } from "./invoke";
 import { listen } from "@tauri-apps/api/event";
 
 // ── Script types ──────────────────────────────────────────────────────────────
@@ -14,8 +14,35 @@ export type TriggerType =
     | "on_hour"
     | "on_encoder_connect"
     | "on_encoder_disconnect"
+    | "on_silence"
+    | "on_show_start"
+    | "hooks"
     | "manual";
 
+/** Extra ways a script starts: a cron schedule (local time) or an engine event */
+export type ScriptTrigger =
+    | { type: "schedule"; cron: string }
+    | { type: "event"; event: string };
+
+/** What a script may do beyond the basic API */
+export interface ScriptPermissions {
+    /** io library, full os library, dofile/loadfile */
+    filesystem: boolean;
+    /** http.get / http.post */
+    network: boolean;
+    /** Deck, queue, encoder and station control */
+    engine_control: boolean;
+}
+
+/** Per-invocation limits; the body with its hooks and each timer callback is one invocation */
+export interface ScriptLimits {
+    /** Wall-clock time, excluding timer.sleep (100–600000) */
+    max_runtime_ms: number;
+    /** VM memory cap (4–512) */
+    memory_mb: number;
+    instruction_budget: number;
+}
+
 export interface Script {
     id: number;
     name: string;
@@ -23,6 +50,9 @@ export interface Script {
     content: string;
     enabled: boolean;
     trigger_type: TriggerType;
+    triggers?: ScriptTrigger[];
+    permissions?: ScriptPermissions;
+    limits?: ScriptLimits;
     last_run_at?: number;
     last_error?: string;
 }
@@ -34,6 +64,26 @@ export interface ScriptRunResult {
     error_line?: number;
 }
 
+/** One recorded run of a script */
+export interface ScriptRun {
+    id: number;
+    script_id: number;
+    /** Unix ms */
+    started_at: number;
+    duration_ms: number;
+    /** "manual", an event trigger type, or "schedule:<cron>" */
+    trigger: string;
+    success: boolean;
+    error?: string;
+}
+
+/** One documented Lua API call */
+export interface ScriptApiEntry {
+    name: string;
+    signature: string;
+    description: string;
+}
+
 export interface ScriptLogEntry {
     level: "info" | "warn" | "error";
     message: string;
@@ -77,6 +127,9 @@ export const deleteScript = (id: number) => invoke<void>("delete_script", { id }
 export const runScript = (id: number) => invoke<ScriptRunResult>("run_script", { id });
 export const getScriptLog = (id: number, limit = 50) =>
     invoke<ScriptLogEntry[]>("get_script_log", { id, limit });
+export const getScriptApi = () => invoke<ScriptApiEntry[]>("get_script_api");
+export const getScriptRuns = (id: number, limit = 50) =>
+    invoke<ScriptRun[]>("get_script_runs", { id, limit });
 
 // ── Mic commands ──────────────────────────────────────────────────────────────
 
@@ -1,11 +1,19 @@
 // Phase 6: Gateway integration bridge
-import { invoke } from '@tauri-apps/api/core';
+import { invoke } from './invoke';
 
 export interface GatewayStatus {
   connected: boolean;
   url: string;
   reconnecting: boolean;
   last_error?: string;
+  reconnect_attempt: number;
+  next_retry_at: number | null;
+  reconnect_count: number;
+  connected_since: number | null;
+  session_id: string;
+  session_resumed: boolean;
+  queued_messages: number;
+  dropped_messages: number;
 }
 
 export interface AutoPilotStatus {
@@ -31,6 +39,20 @@ export interface DjPermissions {
   can_queue_remove: boolean;
   can_trigger_crossfade: boolean;
   can_set_autopilot: boolean;
+  can_go_live: boolean;
+}
+
+export interface RemoteAudioStatus {
+  session_id: string;
+  active: boolean;
+  connected: boolean;
+  packets_received: number;
+  packets_lost: number;
+  packets_late: number;
+  underruns: number;
+  jitter_ms: number;
+  mix_minus: boolean;
+  last_error: string | null;
 }
 
 // Gateway connection
@@ -88,3 +110,16 @@ export async function setMixMinus(enabled: boolean): Promise<void> {
   return invoke('set_mix_minus', { enabled });
 }
 
+// Remote DJ live audio (Aux 2)
+export async function startRemoteAudio(sessionId: string): Promise<RemoteAudioStatus> {
+  return invoke('start_remote_audio', { sessionId });
+}
+
+export async function stopRemoteAudio(): Promise<void> {
+  return invoke('stop_remote_audio');
+}
+
+export async function getRemoteAudioStatus(): Promise<RemoteAudioStatus | null> {
+  return invoke('get_remote_audio_status');
+}
+
@@ -1,5 +1,7 @@
 // Phase 7: Analytics & Operations bridge
-import { invoke } from '@tauri-apps/api/core';
+import { listen, type UnlistenFn } from '@tauri-apps/api/event';
+import { invoke } from './invoke';
+import type { ClockwheelSlot, CrossfadeConfig, GapKillerConfig } from './bridge';
 
 // ── Types ────────────────────────────────────────────────────────────────────
 
@@ -40,6 +42,34 @@ export interface ListenerPeak {
   timestamp: number;
 }
 
+export interface GeoEntry {
+  country_code: string | null;
+  country: string;
+  city: string | null;
+  listeners: number;
+  sessions: number;
+  listening_hours: number;
+}
+
+export interface ListenerGeoBreakdown {
+  unique_listeners: number;
+  located_listeners: number;
+  countries: GeoEntry[];
+  cities: GeoEntry[];
+}
+
+export interface GeoIpConfig {
+  database_path: string | null;
+}
+
+export interface GeoIpStatus {
+  config: GeoIpConfig;
+  loaded: boolean;
+  database_type: string | null;
+  built_at: number | null;
+  error: string | null;
+}
+
 export interface EventLogEntry {
   id: number;
   timestamp: number;
@@ -56,6 +86,60 @@ export interface EventLogEntry {
 export interface EventLogResponse {
   events: EventLogEntry[];
   total: number;
+  /** `beforeId` for the next page; null on the last one */
+  next_before_id: number | null;
+}
+
+export interface EventLogRetention {
+  enabled: boolean;
+  /** 0 = no age limit */
+  max_age_days: number;
+  /** 0 = no row limit */
+  max_rows: number;
+  /** 0 = no size limit */
+  max_size_mb: number;
+  /** Write pruned entries to .ndjson.gz first */
+  archive: boolean;
+  archive_dir: string | null;
+}
+
+export interface PruneReport {
+  deleted: number;
+  archive_path: string | null;
+  remaining: number;
+}
+
+export interface PlayLogEntry {
+  id: number;
+  ended_at: number;
+  song_id: number;
+  queue_id?: number;
+  deck: string;
+  artist?: string;
+  title?: string;
+  played_ms: number;
+  duration_ms: number;
+  outcome: 'full' | 'partial' | 'skip';
+  reason: 'completed' | 'manual_skip' | 'crossfade' | 'crossfade_early' | 'error';
+  from_rotation: boolean;
+}
+
+export interface PlayLogResponse {
+  entries: PlayLogEntry[];
+  total: number;
+}
+
+export interface AppLogEntry {
+  timestamp: string;
+  level: string;
+  category: string;
+  target: string;
+  message: string;
+}
+
+export interface AppLogResponse {
+  log_dir?: string;
+  entries: AppLogEntry[];
 }
 
 export interface SystemHealthSnapshot {
@@ -68,6 +152,58 @@ export interface SystemHealthSnapshot {
   stream_connected: boolean;
   mysql_connected: boolean;
   active_encoders: number;
+  /** Master bus loudness; null before any audio */
+  short_term_lufs: number | null;
+  integrated_lufs: number | null;
+  /** Highest master true peak since the previous snapshot */
+  true_peak_dbtp: number | null;
+  /** True-peak overs since the previous snapshot */
+  true_peak_overs: number;
+}
+
+export type AlertKind = 'encoder_disconnect' | 'buffer_underrun' | 'sam_db_lost' | 'dead_air';
+
+export interface HealthAlert {
+  kind: AlertKind;
+  resolved: boolean;
+  message: string;
+  encoder_id: number | null;
+  deck: string | null;
+  since: number;
+  timestamp: number;
+}
+
+export type AlertAction =
+  | {
+      type: 'email';
+      smtp_host: string;
+      smtp_port: number;
+      security?: 'tls' | 'start_tls' | 'none';
+      username?: string;
+      password?: string;
+      from: string;
+      to: string[];
+    }
+  | { type: 'webhook'; url: string; format?: 'discord' | 'slack' | 'json' }
+  | { type: 'script'; script_id: number };
+
+export type AlertTarget = AlertAction & {
+  name: string;
+  enabled: boolean;
+  /** Empty = all kinds */
+  kinds: AlertKind[];
+};
+
+export interface AlertConfig {
+  enabled: boolean;
+  encoder_down_secs: number;
+  underrun_secs: number;
+  sam_down_secs: number;
+  dead_air_secs: number;
+  dead_air_threshold_db: number;
+  repeat_after_mins: number;
+  notify_recovery: boolean;
+  targets: AlertTarget[];
 }
 
 export interface ReportData {
@@ -97,6 +233,146 @@ export type ReportType =
   | { type: 'RequestLog'; start_date: string; end_date: string }
   | { type: 'StreamUptime'; period_days: number };
 
+export type Role = 'admin' | 'dj' | 'producer' | 'viewer';
+
+export interface Actor {
+  user_id: number | null;
+  username: string;
+  role: Role;
+}
+
+export interface AccessStatus {
+  enforced: boolean;
+  current: Actor | null;
+}
+
+export interface User {
+  id: number;
+  username: string;
+  display_name: string | null;
+  role: Role;
+  enabled: boolean;
+  created_at: number;
+  last_login_at: number | null;
+}
+
+export interface AuditEntry {
+  id: number;
+  timestamp: number;
+  user_id: number | null;
+  username: string;
+  role: string;
+  action: string;
+  target: string | null;
+  details: Record<string, unknown> | null;
+}
+
+export interface AuditFilter {
+  username?: string;
+  action?: string;
+  since_ms?: number;
+  until_ms?: number;
+  limit?: number;
+  offset?: number;
+}
+
+export type SettingsSection =
+  | 'encoders'
+  | 'rotation'
+  | 'clockwheels'
+  | 'shows'
+  | 'dsp'
+  | 'controller'
+  | 'cue_points'
+  | 'scripts';
+
+export interface SettingsSectionSummary {
+  section: SettingsSection;
+  tables: Record<string, number>;
+  scripts: number;
+}
+
+export interface SettingsArchiveSummary {
+  format_version: number;
+  app_version: string;
+  created_at: number;
+  sections: SettingsSectionSummary[];
+}
+
+export interface SettingsImportReport {
+  restored: SettingsSectionSummary[];
+  missing: SettingsSection[];
+  dropped_columns: string[];
+  restart_required: boolean;
+}
+
+export interface SamMappedSetting {
+  setting: string;
+  source_file: string;
+  source_key: string;
+  value: string;
+}
+
+export interface SamClockwheelTemplate {
+  id: number | null;
+  name: string;
+  slots: ClockwheelSlot[];
+}
+
+export interface SamImportPlan {
+  crossfade: CrossfadeConfig | null;
+  gap_killer: GapKillerConfig | null;
+  clockwheels: SamClockwheelTemplate[];
+  mapped: SamMappedSetting[];
+  unmapped_keys: string[];
+  unmapped_count: number;
+  warnings: string[];
+}
+
+export interface SamImportSelection {
+  crossfade: boolean;
+  gap_killer: boolean;
+  clockwheels: boolean;
+}
+
+export interface SamImportResult {
+  crossfade: boolean;
+  gap_killer: boolean;
+  clockwheels_created: number;
+  clockwheels_updated: number;
+  warnings: string[];
+}
+
+export interface MissingFileConfig {
+  music_roots: string[];
+  duration_tolerance_secs: number;
+  auto_relink: boolean;
+}
+
+export interface RelinkCandidate {
+  path: string;
+  duration_secs: number | null;
+  duration_match: boolean | null;
+}
+
+export interface MissingFile {
+  song_id: number;
+  artist: string;
+  title: string;
+  path: string;
+  duration_secs: number;
+  detected_at: number;
+  candidates: RelinkCandidate[];
+}
+
+export interface IntegrityScanReport {
+  scanned: number;
+  missing: number;
+  relinked: { song_id: number; old_path: string; new_path: string }[];
+  started_at: number;
+  finished_at: number;
+}
+
 // ── Play Stats ───────────────────────────────────────────────────────────────
 
 export async function getTopSongs(period: string, limit: number): Promise<TopSong[]> {
@@ -133,6 +409,36 @@ export async function getListenerPeak(
   return invoke('get_listener_peak', { encoderId, period });
 }
 
+/** `encoderId` null = all encoders */
+export async function getListenerGeoBreakdown(
+  encoderId: number | null,
+  period: string,
+  limit?: number
+): Promise<ListenerGeoBreakdown> {
+  return invoke('get_listener_geo_breakdown', { encoderId, period, limit });
+}
+
+export async function getGeoIpStatus(): Promise<GeoIpStatus> {
+  return invoke('get_geoip_status');
+}
+
+export async function setGeoIpConfig(config: GeoIpConfig): Promise<GeoIpStatus> {
+  return invoke('set_geoip_config', { config });
+}
+
+export async function getPlayLog(params: {
+  limit: number;
+  offset: number;
+  startTime?: number;
+  endTime?: number;
+  songId?: number;
+  deck?: string;
+  outcome?: PlayLogEntry['outcome'];
+  reason?: PlayLogEntry['reason'];
+}): Promise<PlayLogResponse> {
+  return invoke('get_play_log', params);
+}
+
 // ── Event Log ────────────────────────────────────────────────────────────────
 
 export async function getEventLog(params: {
@@ -142,12 +448,37 @@ export async function getEventLog(params: {
   category?: string;
   startTime?: number;
   endTime?: number;
+  /** Word-prefix full-text match on event, message and metadata */
   search?: string;
   deck?: string;
+  /** Keyset cursor from `next_before_id`; use instead of a deep offset */
+  beforeId?: number;
 }): Promise<EventLogResponse> {
   return invoke('get_event_log', params);
 }
 
+export async function getEventLogRetention(): Promise<EventLogRetention> {
+  return invoke('get_event_log_retention');
+}
+
+export async function setEventLogRetention(config: EventLogRetention): Promise<void> {
+  return invoke('set_event_log_retention', { config });
+}
+
+/** Applies the saved retention policy now. */
+export async function pruneEventLog(): Promise<PruneReport> {
+  return invoke('prune_event_log');
+}
+
+export async function getAppLogs(params: {
+  limit?: number;
+  level?: 'error' | 'warn' | 'info' | 'debug' | 'trace';
+  category?: string;
+  search?: string;
+}): Promise<AppLogResponse> {
+  return invoke('get_app_logs', params);
+}
+
 export async function clearEventLog(olderThanDays: number): Promise<number> {
   return invoke('clear_event_log', { olderThanDays });
 }
@@ -180,6 +511,154 @@ export async function getHealthHistory(periodMinutes: number): Promise<SystemHea
   return invoke('get_health_history', { periodMinutes });
 }
 
+export async function getAlertConfig(): Promise<AlertConfig> {
+  return invoke('get_alert_config');
+}
+
+export async function setAlertConfig(config: AlertConfig): Promise<void> {
+  return invoke('set_alert_config', { config });
+}
+
+export async function testAlertTarget(target: AlertTarget): Promise<void> {
+  return invoke('test_alert_target', { target });
+}
+
+export interface SmtpSettings {
+  smtp_host: string;
+  smtp_port: number;
+  security: 'tls' | 'start_tls' | 'none';
+  username: string;
+  password: string;
+  from: string;
+}
+
+export interface DigestConfig {
+  enabled: boolean;
+  frequency: 'daily' | 'weekly';
+  /** Local hour, 0–23 */
+  send_hour: number;
+  /** 0 = Monday … 6 = Sunday */
+  weekday: number;
+  top_songs: number;
+  smtp: SmtpSettings;
+  to: string[];
+  /** Send day of the last scheduled digest; read-only */
+  last_sent: string | null;
+}
+
+/** The SMTP password comes back blank; saving a blank one keeps it. */
+export async function getReportDigestConfig(): Promise<DigestConfig> {
+  return invoke('get_report_digest_config');
+}
+
+export async function setReportDigestConfig(config: DigestConfig): Promise<void> {
+  return invoke('set_report_digest_config', { config });
+}
+
+/** Mails the latest digest now with `config`, saved or not. */
+export async function testReportDigest(config: DigestConfig): Promise<void> {
+  return invoke('test_report_digest', { config });
+}
+
+export function onHealthAlert(cb: (alert: HealthAlert) => void): Promise<UnlistenFn> {
+  return listen<HealthAlert>('health_alert', (e) => cb(e.payload));
+}
+
+// ── Accounts & Audit ─────────────────────────────────────────────────────────
+
+export async function getAccessStatus(): Promise<AccessStatus> {
+  return invoke('get_access_status');
+}
+
+export async function signIn(username: string, password: string): Promise<Actor> {
+  return invoke('sign_in', { username, password });
+}
+
+export async function signOut(): Promise<void> {
+  return invoke('sign_out');
+}
+
+export async function listUsers(): Promise<User[]> {
+  return invoke('list_users');
+}
+
+export async function createUser(user: {
+  username: string;
+  display_name?: string | null;
+  role: Role;
+  password: string;
+}): Promise<User> {
+  return invoke('create_user', { user });
+}
+
+export async function updateUser(
+  id: number,
+  changes: { display_name?: string; role?: Role; enabled?: boolean; password?: string }
+): Promise<User> {
+  return invoke('update_user', { id, changes });
+}
+
+export async function deleteUser(id: number): Promise<void> {
+  return invoke('delete_user', { id });
+}
+
+export async function getAuditLog(filter?: AuditFilter): Promise<AuditEntry[]> {
+  return invoke('get_audit_log', { filter: filter ?? null });
+}
+
+// ── Settings Backup ──────────────────────────────────────────────────────────
+
+export async function exportSettings(
+  path: string,
+  sections?: SettingsSection[]
+): Promise<SettingsArchiveSummary> {
+  return invoke('export_settings', { path, sections: sections ?? null });
+}
+
+export async function inspectSettingsArchive(path: string): Promise<SettingsArchiveSummary> {
+  return invoke('inspect_settings_archive', { path });
+}
+
+export async function importSettings(
+  path: string,
+  sections?: SettingsSection[]
+): Promise<SettingsImportReport> {
+  return invoke('import_settings', { path, sections: sections ?? null });
+}
+
+export async function previewSamImport(paths: string[]): Promise<SamImportPlan> {
+  return invoke('preview_sam_import', { paths });
+}
+
+export async function applySamImport(
+  paths: string[],
+  selection: SamImportSelection
+): Promise<SamImportResult> {
+  return invoke('apply_sam_import', { paths, selection });
+}
+
+// ── Missing Files ────────────────────────────────────────────────────────────
+
+export async function scanMissingFiles(): Promise<IntegrityScanReport> {
+  return invoke('scan_missing_files');
+}
+
+export async function getMissingFiles(): Promise<MissingFile[]> {
+  return invoke('get_missing_files');
+}
+
+export async function relinkSong(songId: number, path: string): Promise<void> {
+  return invoke('relink_song', { songId, path });
+}
+
+export async function getMissingFileConfig(): Promise<MissingFileConfig> {
+  return invoke('get_missing_file_config');
+}
+
+export async function setMissingFileConfig(config: MissingFileConfig): Promise<void> {
+  return invoke('set_missing_file_config', { config });
+}
+
 // ── Reports ──────────────────────────────────────────────────────────────────
 
 export async function generateReport(reportType: ReportType): Promise<ReportData> {
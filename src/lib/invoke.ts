@@ -0,0 +1,60 @@
+import { invoke as tauriInvoke, type InvokeArgs } from "@tauri-apps/api/core";
+
+/** Machine-readable failure class returned by every backend command. */
+export type ErrorCode =
+  | "DB_UNAVAILABLE"
+  | "SAM_DB_UNAVAILABLE"
+  | "DB_ERROR"
+  | "FILE_NOT_FOUND"
+  | "INVALID_INPUT"
+  | "NOT_FOUND"
+  | "ENGINE_QUEUE_FULL"
+  | "ENGINE_ERROR"
+  | "DECODE_ERROR"
+  | "DEVICE_ERROR"
+  | "NETWORK_ERROR"
+  | "PERMISSION_DENIED"
+  | "CONFLICT"
+  | "UNSUPPORTED"
+  | "INTERNAL";
+
+/**
+ * Rejection value for failed commands. `String(err)` and `err.message` still
+ * give the human-readable text; branch on `err.code`.
+ */
+export class CommandError extends Error {
+  readonly code: ErrorCode;
+  readonly command: string;
+
+  constructor(command: string, code: ErrorCode, message: string) {
+    super(message);
+    this.name = "CommandError";
+    this.command = command;
+    this.code = code;
+  }
+
+  override toString(): string {
+    return this.message;
+  }
+}
+
+export function isCommandError(err: unknown, code?: ErrorCode): err is CommandError {
+  return err instanceof CommandError && (code === undefined || err.code === code);
+}
+
+function toCommandError(command: string, raw: unknown): CommandError {
+  if (raw && typeof raw === "object" && "code" in raw && "message" in raw) {
+    const { code, message } = raw as { code: ErrorCode; message: string };
+    return new CommandError(command, code, message);
+  }
+  return new CommandError(command, "INTERNAL", String(raw));
+}
+
+/** Drop-in replacement for Tauri's `invoke` that rejects with `CommandError`. */
+export async function invoke<T>(command: string, args?: InvokeArgs): Promise<T> {
+  try {
+    return await tauriInvoke<T>(command, args);
+  } catch (raw) {
+    throw toCommandError(command, raw);
+  }
+}